@@ -0,0 +1,102 @@
+#![allow(deprecated)]
+
+use beacon_chain::attestation_verification::obtain_indexed_attestation_and_committees_per_slot;
+use beacon_chain::test_utils::{
+    test_spec, AttestationStrategy, BeaconChainHarness, BlockStrategy, EphemeralHarnessType,
+};
+use criterion::{black_box, criterion_group, criterion_main, Benchmark, Criterion};
+use types::{Attestation, MainnetEthSpec, SubnetId};
+
+type E = MainnetEthSpec;
+
+const VALIDATOR_COUNT: usize = 512;
+const ATTESTATION_COUNT: usize = 10_000;
+
+/// Returns a harness in a slot without blocks and `ATTESTATION_COUNT` attestations for that slot.
+fn harness_and_attestations() -> (
+    BeaconChainHarness<EphemeralHarnessType<E>>,
+    Vec<Attestation<E>>,
+) {
+    let harness = BeaconChainHarness::builder(MainnetEthSpec)
+        .spec(test_spec::<E>())
+        .deterministic_keypairs(VALIDATOR_COUNT)
+        .fresh_ephemeral_store()
+        .build();
+
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    runtime.block_on(harness.extend_chain(
+        2,
+        BlockStrategy::OnCanonicalHead,
+        AttestationStrategy::AllValidators,
+    ));
+    harness.advance_slot();
+
+    let head = harness.chain.head_snapshot();
+    let slot = harness.chain.slot().unwrap();
+    let attestations: Vec<(Attestation<E>, SubnetId)> = harness
+        .make_unaggregated_attestations(
+            &(0..VALIDATOR_COUNT).collect::<Vec<_>>(),
+            &head.beacon_state,
+            head.beacon_state_root(),
+            head.beacon_block_root.into(),
+            slot,
+        )
+        .into_iter()
+        .flatten()
+        .collect();
+    let attestations = attestations
+        .into_iter()
+        .map(|(attestation, _)| attestation)
+        .cycle()
+        .take(ATTESTATION_COUNT)
+        .collect();
+
+    (harness, attestations)
+}
+
+fn committee_lookups(c: &mut Criterion) {
+    let (harness, attestations) = harness_and_attestations();
+    let chain = harness.chain.clone();
+
+    // Committees are served by the committee memo, except for the first of each committee.
+    c.bench(
+        "committee lookups for one slot",
+        Benchmark::new(format!("{} attestations", ATTESTATION_COUNT), move |b| {
+            b.iter(|| {
+                for attestation in &attestations {
+                    black_box(
+                        obtain_indexed_attestation_and_committees_per_slot(&chain, attestation)
+                            .unwrap(),
+                    );
+                }
+            })
+        })
+        .sample_size(10),
+    );
+
+    // Clearing the committee memo before each lookup forces the committee to be read from the
+    // shuffling cache.
+    let (harness, attestations) = harness_and_attestations();
+    let chain = harness.chain.clone();
+    c.bench(
+        "committee lookups for one slot",
+        Benchmark::new(
+            format!("{} attestations without committee memo", ATTESTATION_COUNT),
+            move |b| {
+                b.iter(|| {
+                    for attestation in &attestations {
+                        chain.committee_memo.clear();
+                        black_box(
+                            obtain_indexed_attestation_and_committees_per_slot(&chain, attestation)
+                                .unwrap(),
+                        );
+                    }
+                })
+            },
+        )
+        .sample_size(10),
+    );
+}
+
+criterion_group!(benches, committee_lookups);
+criterion_main!(benches);
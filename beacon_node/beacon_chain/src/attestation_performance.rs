@@ -0,0 +1,168 @@
+//! Reports the attestation performance of validators in an epoch.
+//!
+//! From Altair, the participation of each validator in an epoch is recorded in the state as a set
+//! of flags, and the validator is rewarded for those flags at the end of the following epoch. The
+//! report for epoch `N` is therefore computed from the state at the last slot of epoch `N + 1`,
+//! which is the only state loaded.
+use crate::{BeaconChain, BeaconChainError, BeaconChainTypes};
+use safe_arith::SafeArith;
+use state_processing::common::altair::{get_base_reward, BaseRewardPerIncrement};
+use state_processing::per_epoch_processing::altair::{
+    process_inactivity_updates, process_justification_and_finalization,
+    rewards_and_penalties::{get_flag_index_deltas, get_inactivity_penalty_deltas},
+    ParticipationCache,
+};
+use state_processing::per_epoch_processing::Delta;
+use state_processing::EpochProcessingError;
+use types::consts::altair::{PARTICIPATION_FLAG_WEIGHTS, WEIGHT_DENOMINATOR};
+use types::{BeaconState, ChainSpec, Epoch, EthSpec, RelativeEpoch};
+
+/// The participation of a validator in an epoch and the rewards it received for it.
+///
+/// All rewards in Gwei.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidatorAttestationPerformance {
+    pub validator_index: u64,
+    /// Whether the validator was active and unslashed in the epoch.
+    pub active: bool,
+    pub source: bool,
+    pub target: bool,
+    pub head: bool,
+    /// The reward the validator would have received for timely source, target and head votes.
+    pub ideal_reward: u64,
+    /// The rewards less the penalties applied to the validator for the epoch, including any
+    /// inactivity penalty.
+    pub reward: i64,
+}
+
+impl<T: BeaconChainTypes> BeaconChain<T> {
+    /// Report the attestation performance of the validators in `validator_filter`, or of every
+    /// validator if `None`, during `epoch` of the canonical chain.
+    ///
+    /// The report is only available once the canonical chain includes the last slot of the epoch
+    /// following `epoch`, and only for epochs from Altair onwards.
+    pub fn attestation_performance(
+        &self,
+        epoch: Epoch,
+        validator_filter: Option<&[u64]>,
+    ) -> Result<Vec<ValidatorAttestationPerformance>, BeaconChainError> {
+        let slot = epoch.safe_add(1)?.end_slot(T::EthSpec::slots_per_epoch());
+        if slot > self.canonical_head.cached_head().head_slot() {
+            return Err(BeaconChainError::AttestationPerformanceEpochTooRecent(
+                epoch,
+            ));
+        }
+
+        let (state_root, _) = self
+            .forwards_iter_state_roots_until(slot, slot)?
+            .next()
+            .ok_or(BeaconChainError::NoStateForSlot(slot))??;
+        let mut state = self
+            .get_state(&state_root, Some(slot))?
+            .ok_or(BeaconChainError::MissingBeaconState(state_root))?;
+        if let BeaconState::Base(_) = state {
+            return Err(BeaconChainError::AttestationPerformancePreAltair(epoch));
+        }
+
+        let validator_indices = match validator_filter {
+            Some(indices) => indices.to_vec(),
+            None => (0..state.validators().len() as u64).collect(),
+        };
+
+        Ok(compute_attestation_performance(
+            &mut state,
+            &validator_indices,
+            &self.spec,
+        )?)
+    }
+}
+
+/// Compute the attestation performance of `validator_indices` in the previous epoch of `state`,
+/// which must be at the last slot of its current epoch.
+///
+/// The parts of epoch processing which precede the rewards are applied to `state`.
+fn compute_attestation_performance<E: EthSpec>(
+    state: &mut BeaconState<E>,
+    validator_indices: &[u64],
+    spec: &ChainSpec,
+) -> Result<Vec<ValidatorAttestationPerformance>, EpochProcessingError> {
+    state.build_committee_cache(RelativeEpoch::Previous, spec)?;
+    state.build_committee_cache(RelativeEpoch::Current, spec)?;
+    let participation_cache = ParticipationCache::new(state, spec)?;
+
+    // Justification and the inactivity scores determine the penalties, so update them as epoch
+    // processing would before computing the rewards.
+    process_justification_and_finalization(state, &participation_cache)?;
+    process_inactivity_updates(state, &participation_cache, spec)?;
+
+    let total_active_balance = participation_cache.current_epoch_total_active_balance();
+    let mut deltas = vec![Delta::default(); state.validators().len()];
+    for flag_index in 0..PARTICIPATION_FLAG_WEIGHTS.len() {
+        get_flag_index_deltas(
+            &mut deltas,
+            state,
+            flag_index,
+            total_active_balance,
+            &participation_cache,
+            spec,
+        )?;
+    }
+    get_inactivity_penalty_deltas(&mut deltas, state, &participation_cache, spec)?;
+
+    // The reward for each flag given the participation of the whole validator set, as a fraction
+    // of the base reward.
+    let previous_epoch = state.previous_epoch();
+    let in_inactivity_leak = state.is_in_inactivity_leak(previous_epoch, spec);
+    let active_increments = total_active_balance.safe_div(spec.effective_balance_increment)?;
+    let mut flag_reward_fractions = vec![];
+    for (flag_index, weight) in PARTICIPATION_FLAG_WEIGHTS.iter().enumerate() {
+        let participating_increments = participation_cache
+            .get_unslashed_participating_indices(flag_index, previous_epoch)?
+            .total_balance()?
+            .safe_div(spec.effective_balance_increment)?;
+        flag_reward_fractions.push((
+            weight.safe_mul(participating_increments)?,
+            active_increments.safe_mul(WEIGHT_DENOMINATOR)?,
+        ));
+    }
+    let base_reward_per_increment = BaseRewardPerIncrement::new(total_active_balance, spec)?;
+
+    validator_indices
+        .iter()
+        .map(|&validator_index| {
+            let index = validator_index as usize;
+            let delta = deltas
+                .get(index)
+                .ok_or(EpochProcessingError::DeltaOutOfBounds(index))?;
+
+            let is_eligible = participation_cache
+                .eligible_validator_indices()
+                .binary_search(&index)
+                .is_ok();
+            let ideal_reward = if is_eligible && !in_inactivity_leak {
+                let base_reward = get_base_reward(state, index, base_reward_per_increment, spec)?;
+                let mut ideal_reward: u64 = 0;
+                for (numerator, denominator) in &flag_reward_fractions {
+                    ideal_reward.safe_add_assign(
+                        base_reward.safe_mul(*numerator)?.safe_div(*denominator)?,
+                    )?;
+                }
+                ideal_reward
+            } else {
+                0
+            };
+
+            Ok(ValidatorAttestationPerformance {
+                validator_index,
+                active: participation_cache
+                    .is_active_unslashed_in_previous_epoch(index)
+                    .unwrap_or(false),
+                source: participation_cache.is_previous_epoch_timely_source_attester(index)?,
+                target: participation_cache.is_previous_epoch_timely_target_attester(index)?,
+                head: participation_cache.is_previous_epoch_timely_head_attester(index)?,
+                ideal_reward,
+                reward: delta.rewards as i64 - delta.penalties as i64,
+            })
+        })
+        .collect()
+}
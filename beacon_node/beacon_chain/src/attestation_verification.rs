@@ -30,11 +30,9 @@
 mod batch;
 
 use crate::{
-    beacon_chain::{MAXIMUM_GOSSIP_CLOCK_DISPARITY, VALIDATOR_PUBKEY_CACHE_LOCK_TIMEOUT},
-    metrics,
-    observed_aggregates::ObserveOutcome,
-    observed_attesters::Error as ObservedAttestersError,
-    BeaconChain, BeaconChainError, BeaconChainTypes,
+    beacon_chain::VALIDATOR_PUBKEY_CACHE_LOCK_TIMEOUT, committee_memo::MemoizedCommittee, metrics,
+    observed_aggregates::ObserveOutcome, observed_attesters::Error as ObservedAttestersError,
+    shuffling_cache::BlockShufflingIds, BeaconChain, BeaconChainError, BeaconChainTypes,
 };
 use bls::verify_signature_sets;
 use proto_array::Block as ProtoBlock;
@@ -49,6 +47,7 @@ use state_processing::{
     },
 };
 use std::borrow::Cow;
+use std::time::Duration;
 use strum::AsRefStr;
 use tree_hash::TreeHash;
 use types::{
@@ -56,7 +55,14 @@ use types::{
     SelectionProof, SignedAggregateAndProof, Slot, SubnetId,
 };
 
-pub use batch::{batch_verify_aggregated_attestations, batch_verify_unaggregated_attestations};
+pub use batch::{
+    batch_verify_aggregated_attestations, batch_verify_unaggregated_attestations,
+    BatchVerificationSummary,
+};
+
+/// An attestation to the block in the early attester cache is only deferred if the block was added
+/// to the cache at most this long ago, after which its import is assumed to have failed.
+pub const EARLY_ATTESTER_CACHE_DEFERRAL_WINDOW: Duration = Duration::from_secs(1);
 
 /// Returned when an attestation was not successfully verified. It might not have been verified for
 /// two reasons:
@@ -141,6 +147,14 @@ pub enum Error {
     /// The attestation points to a block we have not yet imported. It's unclear if the attestation
     /// is valid or not.
     UnknownHeadBlock { beacon_block_root: Hash256 },
+    /// The `attestation.data.beacon_block_root` block is in the early attester cache, but it (or
+    /// the target block) is not yet in fork choice.
+    ///
+    /// ## Peer scoring
+    ///
+    /// The block is being imported, so the attestation should be re-processed shortly. It's
+    /// unclear if the attestation is valid or not.
+    DeferredUnknownHead { beacon_block_root: Hash256 },
     /// The `attestation.data.beacon_block_root` block is from before the finalized checkpoint.
     ///
     /// ## Peer scoring
@@ -256,6 +270,13 @@ enum CheckAttestationSignature {
     No,
 }
 
+/// Used to accept aggregates from unselected aggregators for inclusion in blocks.
+#[derive(Copy, Clone)]
+enum CheckSelectionProof {
+    Yes,
+    No,
+}
+
 /// Wraps a `SignedAggregateAndProof` that has been verified up until the point that an
 /// `IndexedAttestation` can be derived.
 ///
@@ -274,6 +295,7 @@ struct IndexedUnaggregatedAttestation<'a, T: BeaconChainTypes> {
     attestation: &'a Attestation<T::EthSpec>,
     indexed_attestation: IndexedAttestation<T::EthSpec>,
     subnet_id: SubnetId,
+    committee_len: usize,
     validator_index: u64,
 }
 
@@ -290,11 +312,28 @@ impl<'a, T: BeaconChainTypes> VerifiedAggregatedAttestation<'a, T> {
     }
 }
 
+/// Wraps a `SignedAggregateAndProof` that has been fully verified for inclusion in blocks, except
+/// that its aggregator may not have been selected.
+///
+/// An unselected aggregator violates the gossip rules, so these must never be (re)published.
+/// This is enforced by never exposing the `SignedAggregateAndProof`, only the attestation.
+pub struct InclusionOnlyAggregatedAttestation<'a, T: BeaconChainTypes> {
+    signed_aggregate: &'a SignedAggregateAndProof<T::EthSpec>,
+    indexed_attestation: IndexedAttestation<T::EthSpec>,
+}
+
+impl<'a, T: BeaconChainTypes> InclusionOnlyAggregatedAttestation<'a, T> {
+    pub fn into_indexed_attestation(self) -> IndexedAttestation<T::EthSpec> {
+        self.indexed_attestation
+    }
+}
+
 /// Wraps an `Attestation` that has been fully verified for propagation on the gossip network.
 pub struct VerifiedUnaggregatedAttestation<'a, T: BeaconChainTypes> {
     attestation: &'a Attestation<T::EthSpec>,
     indexed_attestation: IndexedAttestation<T::EthSpec>,
     subnet_id: SubnetId,
+    committee_len: usize,
 }
 
 impl<'a, T: BeaconChainTypes> VerifiedUnaggregatedAttestation<'a, T> {
@@ -311,6 +350,7 @@ impl<'a, T: BeaconChainTypes> Clone for IndexedUnaggregatedAttestation<'a, T> {
             attestation: self.attestation,
             indexed_attestation: self.indexed_attestation.clone(),
             subnet_id: self.subnet_id,
+            committee_len: self.committee_len,
             validator_index: self.validator_index,
         }
     }
@@ -334,6 +374,16 @@ impl<'a, T: BeaconChainTypes> VerifiedAttestation<T> for VerifiedAggregatedAttes
     }
 }
 
+impl<'a, T: BeaconChainTypes> VerifiedAttestation<T> for InclusionOnlyAggregatedAttestation<'a, T> {
+    fn attestation(&self) -> &Attestation<T::EthSpec> {
+        self.attestation()
+    }
+
+    fn indexed_attestation(&self) -> &IndexedAttestation<T::EthSpec> {
+        &self.indexed_attestation
+    }
+}
+
 impl<'a, T: BeaconChainTypes> VerifiedAttestation<T> for VerifiedUnaggregatedAttestation<'a, T> {
     fn attestation(&self) -> &Attestation<T::EthSpec> {
         self.attestation
@@ -429,6 +479,7 @@ impl<'a, T: BeaconChainTypes> IndexedAggregatedAttestation<'a, T> {
                 verified_aggregate
             })
             .map_err(|slash_info| process_slash_info(slash_info, chain))
+            .map_err(|e| defer_if_block_is_importing(chain, &signed_aggregate.message.aggregate, e))
     }
 
     /// Run the checks that happen before an indexed attestation is constructed.
@@ -439,10 +490,14 @@ impl<'a, T: BeaconChainTypes> IndexedAggregatedAttestation<'a, T> {
         let attestation = &signed_aggregate.message.aggregate;
 
         // Ensure attestation is within the last ATTESTATION_PROPAGATION_SLOT_RANGE slots (within a
-        // MAXIMUM_GOSSIP_CLOCK_DISPARITY allowance).
+        // gossip clock disparity allowance).
         //
         // We do not queue future attestations for later processing.
-        verify_propagation_slot_range(&chain.slot_clock, attestation)?;
+        verify_propagation_slot_range(
+            &chain.slot_clock,
+            attestation,
+            chain.gossip_clock_disparity(),
+        )?;
 
         // Check the attestation's epoch matches its target.
         if attestation.data.slot.epoch(T::EthSpec::slots_per_epoch())
@@ -516,6 +571,21 @@ impl<'a, T: BeaconChainTypes> IndexedAggregatedAttestation<'a, T> {
     pub fn verify_slashable(
         signed_aggregate: &'a SignedAggregateAndProof<T::EthSpec>,
         chain: &BeaconChain<T>,
+    ) -> Result<Self, AttestationSlashInfo<'a, T, Error>> {
+        Self::verify_slashable_with_selection_proof(
+            signed_aggregate,
+            chain,
+            CheckSelectionProof::Yes,
+        )
+    }
+
+    /// As per `Self::verify_slashable`, but the check that the aggregator was selected is only
+    /// applied if `check_selection_proof` is `Yes`. The signature of the selection proof is
+    /// checked regardless, along with the other signatures.
+    fn verify_slashable_with_selection_proof(
+        signed_aggregate: &'a SignedAggregateAndProof<T::EthSpec>,
+        chain: &BeaconChain<T>,
+        check_selection_proof: CheckSelectionProof,
     ) -> Result<Self, AttestationSlashInfo<'a, T, Error>> {
         use AttestationSlashInfo::*;
 
@@ -534,9 +604,10 @@ impl<'a, T: BeaconChainTypes> IndexedAggregatedAttestation<'a, T> {
                 let selection_proof =
                     SelectionProof::from(signed_aggregate.message.selection_proof.clone());
 
-                if !selection_proof
-                    .is_aggregator(committee.committee.len(), &chain.spec)
-                    .map_err(|e| Error::BeaconChainError(e.into()))?
+                if matches!(check_selection_proof, CheckSelectionProof::Yes)
+                    && !selection_proof
+                        .is_aggregator(committee.committee.len(), &chain.spec)
+                        .map_err(|e| Error::BeaconChainError(e.into()))?
                 {
                     return Err(Error::InvalidSelectionProof { aggregator_index });
                 }
@@ -620,6 +691,7 @@ impl<'a, T: BeaconChainTypes> VerifiedAggregatedAttestation<'a, T> {
     ) -> Result<Self, Error> {
         Self::verify_slashable(signed_aggregate, chain, check_signature)
             .map(|verified_aggregate| verified_aggregate.apply_to_slasher(chain))
+            .map(|verified_aggregate| verified_aggregate.apply_to_indexed_attestation_cache(chain))
             .map_err(|slash_info| process_slash_info(slash_info, chain))
     }
 
@@ -630,6 +702,14 @@ impl<'a, T: BeaconChainTypes> VerifiedAggregatedAttestation<'a, T> {
         self
     }
 
+    /// Cache the indexed attestation, in case the aggregate is later imported in a block.
+    fn apply_to_indexed_attestation_cache(self, chain: &BeaconChain<T>) -> Self {
+        chain
+            .indexed_attestation_cache
+            .insert(self.attestation(), &self.indexed_attestation);
+        self
+    }
+
     /// Verify the attestation, producing extra information about whether it might be slashable.
     fn verify_slashable(
         signed_aggregate: IndexedAggregatedAttestation<'a, T>,
@@ -686,6 +766,48 @@ impl<'a, T: BeaconChainTypes> VerifiedAggregatedAttestation<'a, T> {
     }
 }
 
+impl<'a, T: BeaconChainTypes> InclusionOnlyAggregatedAttestation<'a, T> {
+    /// Verify the `signed_aggregate` for inclusion in blocks, without requiring that the
+    /// aggregator was selected.
+    ///
+    /// The aggregate and aggregator aren't observed, so that an aggregate which is later received
+    /// from a selected aggregator on gossip is still published.
+    pub fn verify(
+        signed_aggregate: &'a SignedAggregateAndProof<T::EthSpec>,
+        chain: &BeaconChain<T>,
+    ) -> Result<Self, Error> {
+        let IndexedAggregatedAttestation {
+            signed_aggregate,
+            indexed_attestation,
+            ..
+        } = IndexedAggregatedAttestation::verify_slashable_with_selection_proof(
+            signed_aggregate,
+            chain,
+            CheckSelectionProof::No,
+        )
+        .map_err(|slash_info| process_slash_info(slash_info, chain))
+        .map_err(|e| defer_if_block_is_importing(chain, &signed_aggregate.message.aggregate, e))?;
+
+        if !verify_signed_aggregate_signatures(chain, signed_aggregate, &indexed_attestation)? {
+            return Err(Error::InvalidSignature);
+        }
+
+        if let Some(slasher) = chain.slasher.as_ref() {
+            slasher.accept_attestation(indexed_attestation.clone());
+        }
+
+        Ok(InclusionOnlyAggregatedAttestation {
+            signed_aggregate,
+            indexed_attestation,
+        })
+    }
+
+    /// Returns the underlying `attestation` for the `signed_aggregate`.
+    pub fn attestation(&self) -> &Attestation<T::EthSpec> {
+        &self.signed_aggregate.message.aggregate
+    }
+}
+
 impl<'a, T: BeaconChainTypes> IndexedUnaggregatedAttestation<'a, T> {
     /// Run the checks that happen before an indexed attestation is constructed.
     pub fn verify_early_checks(
@@ -703,10 +825,14 @@ impl<'a, T: BeaconChainTypes> IndexedUnaggregatedAttestation<'a, T> {
         }
 
         // Ensure attestation is within the last ATTESTATION_PROPAGATION_SLOT_RANGE slots (within a
-        // MAXIMUM_GOSSIP_CLOCK_DISPARITY allowance).
+        // gossip clock disparity allowance).
         //
         // We do not queue future attestations for later processing.
-        verify_propagation_slot_range(&chain.slot_clock, attestation)?;
+        verify_propagation_slot_range(
+            &chain.slot_clock,
+            attestation,
+            chain.gossip_clock_disparity(),
+        )?;
 
         // Check to ensure that the attestation is "unaggregated". I.e., it has exactly one
         // aggregation bit set.
@@ -795,6 +921,7 @@ impl<'a, T: BeaconChainTypes> IndexedUnaggregatedAttestation<'a, T> {
                 verified_unaggregated
             })
             .map_err(|slash_info| process_slash_info(slash_info, chain))
+            .map_err(|e| defer_if_block_is_importing(chain, attestation, e))
     }
 
     /// Verify the attestation, producing extra information about whether it might be slashable.
@@ -832,6 +959,9 @@ impl<'a, T: BeaconChainTypes> IndexedUnaggregatedAttestation<'a, T> {
             attestation,
             indexed_attestation,
             subnet_id: expected_subnet_id,
+            // The length of the aggregation bits was checked against the committee whilst
+            // obtaining the indexed attestation.
+            committee_len: attestation.aggregation_bits.len(),
             validator_index,
         })
     }
@@ -913,6 +1043,7 @@ impl<'a, T: BeaconChainTypes> VerifiedUnaggregatedAttestation<'a, T> {
             attestation,
             indexed_attestation,
             subnet_id,
+            committee_len,
             validator_index,
         } = attestation;
 
@@ -933,14 +1064,23 @@ impl<'a, T: BeaconChainTypes> VerifiedUnaggregatedAttestation<'a, T> {
             attestation,
             indexed_attestation,
             subnet_id,
+            committee_len,
         })
     }
 
     /// Returns the correct subnet for the attestation.
+    ///
+    /// This is computed from the committee of the attestation, so it is available even if the
+    /// attestation was not received on a subnet.
     pub fn subnet_id(&self) -> SubnetId {
         self.subnet_id
     }
 
+    /// Returns the length of the committee of the attestation.
+    pub fn committee_len(&self) -> usize {
+        self.committee_len
+    }
+
     /// Returns the wrapped `attestation`.
     pub fn attestation(&self) -> &Attestation<T::EthSpec> {
         self.attestation
@@ -1014,18 +1154,52 @@ fn verify_head_block_is_known<T: BeaconChainTypes>(
     }
 }
 
+/// Returns `Error::DeferredUnknownHead` if `error` indicates that the head or target block of
+/// `attestation` is unknown, but the head block was recently added to the early attester cache.
+///
+/// There is a short window during block import in which the block is in the early attester cache,
+/// but not in fork choice. Attestations to the block which arrive during this window are deferred
+/// rather than rejected, so that they can be re-processed once the block has been imported.
+/// Otherwise, `error` is returned unchanged.
+fn defer_if_block_is_importing<T: BeaconChainTypes>(
+    chain: &BeaconChain<T>,
+    attestation: &Attestation<T::EthSpec>,
+    error: Error,
+) -> Error {
+    let block_unknown = matches!(
+        error,
+        Error::UnknownHeadBlock { .. }
+            | Error::UnknownTargetRoot(_)
+            | Error::BeaconChainError(BeaconChainError::MissingBeaconBlock(_))
+    );
+    let beacon_block_root = attestation.data.beacon_block_root;
+
+    if block_unknown
+        && chain.config.defer_attestations_to_importing_blocks
+        && chain
+            .early_attester_cache
+            .contains_recent_block(beacon_block_root, EARLY_ATTESTER_CACHE_DEFERRAL_WINDOW)
+    {
+        metrics::inc_counter(&metrics::BEACON_EARLY_ATTESTER_CACHE_DEFERRED_ATTESTATIONS);
+        Error::DeferredUnknownHead { beacon_block_root }
+    } else {
+        error
+    }
+}
+
 /// Verify that the `attestation` is within the acceptable gossip propagation range, with reference
 /// to the current slot of the `chain`.
 ///
-/// Accounts for `MAXIMUM_GOSSIP_CLOCK_DISPARITY`.
+/// Accounts for a `clock_disparity` allowance, usually `BeaconChain::gossip_clock_disparity`.
 pub fn verify_propagation_slot_range<S: SlotClock, E: EthSpec>(
     slot_clock: &S,
     attestation: &Attestation<E>,
+    clock_disparity: Duration,
 ) -> Result<(), Error> {
     let attestation_slot = attestation.data.slot;
 
     let latest_permissible_slot = slot_clock
-        .now_with_future_tolerance(MAXIMUM_GOSSIP_CLOCK_DISPARITY)
+        .now_with_future_tolerance(clock_disparity)
         .ok_or(BeaconChainError::UnableToReadSlot)?;
     if attestation_slot > latest_permissible_slot {
         return Err(Error::FutureSlot {
@@ -1036,7 +1210,7 @@ pub fn verify_propagation_slot_range<S: SlotClock, E: EthSpec>(
 
     // Taking advantage of saturating subtraction on `Slot`.
     let earliest_permissible_slot = slot_clock
-        .now_with_past_tolerance(MAXIMUM_GOSSIP_CLOCK_DISPARITY)
+        .now_with_past_tolerance(clock_disparity)
         .ok_or(BeaconChainError::UnableToReadSlot)?
         - E::slots_per_epoch();
     if attestation_slot < earliest_permissible_slot {
@@ -1224,8 +1398,9 @@ pub fn obtain_indexed_attestation_and_committees_per_slot<T: BeaconChainTypes>(
 /// `beacon_chain.shuffling_cache` or it might involve reading it from a state from the DB. Due to
 /// the complexities of `RwLock`s on the shuffling cache, a simple `Cow` isn't suitable here.
 ///
-/// If the committee for `attestation` isn't found in the `shuffling_cache`, we will read a state
-/// from disk and then update the `shuffling_cache`.
+/// The `committee_memo` is consulted before the `shuffling_cache`. If the committee for
+/// `attestation` isn't found in the `shuffling_cache`, we will read a state from disk and then
+/// update the `shuffling_cache`.
 fn map_attestation_committee<T, F, R>(
     chain: &BeaconChain<T>,
     attestation: &Attestation<T::EthSpec>,
@@ -1245,28 +1420,59 @@ where
     // processing an attestation that does not include our latest finalized block in its chain.
     //
     // We do not delay consideration for later, we simply drop the attestation.
-    if !chain
+    let target_block = chain
         .canonical_head
         .fork_choice_read_lock()
-        .contains_block(&target.root)
-        && !chain.early_attester_cache.contains_block(target.root)
-    {
+        .get_block(&target.root);
+    if target_block.is_none() && !chain.early_attester_cache.contains_block(target.root) {
         return Err(Error::UnknownTargetRoot(target.root));
     }
 
+    // The committee is determined by the attestation slot and the shuffling decision root of the
+    // target, so a committee memoized for another attestation with the same decision root is
+    // identical to the one in the `shuffling_cache`.
+    let shuffling_decision_root = target_block.and_then(|block| {
+        BlockShufflingIds {
+            current: block.current_epoch_shuffling_id,
+            next: block.next_epoch_shuffling_id,
+            block_root: block.root,
+        }
+        .id_for_epoch(attestation_epoch)
+        .map(|shuffling_id| shuffling_id.shuffling_decision_block)
+    });
+    if let Some(memoized) = shuffling_decision_root.and_then(|root| {
+        chain
+            .committee_memo
+            .get(attestation.data.slot, attestation.data.index, root)
+    }) {
+        metrics::inc_counter(&metrics::COMMITTEE_MEMO_HITS);
+        return map_fn((memoized.beacon_committee(), memoized.committees_per_slot));
+    }
+    metrics::inc_counter(&metrics::COMMITTEE_MEMO_MISSES);
+
     chain
-        .with_committee_cache(target.root, attestation_epoch, |committee_cache, _| {
-            let committees_per_slot = committee_cache.committees_per_slot();
-
-            Ok(committee_cache
-                .get_beacon_committee(attestation.data.slot, attestation.data.index)
-                .map(|committee| map_fn((committee, committees_per_slot)))
-                .unwrap_or_else(|| {
-                    Err(Error::NoCommitteeForSlotAndIndex {
-                        slot: attestation.data.slot,
-                        index: attestation.data.index,
+        .with_committee_cache(
+            target.root,
+            attestation_epoch,
+            |committee_cache, shuffling_decision_root| {
+                let committees_per_slot = committee_cache.committees_per_slot();
+
+                Ok(committee_cache
+                    .get_beacon_committee(attestation.data.slot, attestation.data.index)
+                    .map(|committee| {
+                        chain.committee_memo.insert(
+                            shuffling_decision_root,
+                            MemoizedCommittee::new(&committee, committees_per_slot),
+                        );
+                        map_fn((committee, committees_per_slot))
                     })
-                }))
-        })
+                    .unwrap_or_else(|| {
+                        Err(Error::NoCommitteeForSlotAndIndex {
+                            slot: attestation.data.slot,
+                            index: attestation.data.index,
+                        })
+                    }))
+            },
+        )
         .map_err(BeaconChainError::from)?
 }
@@ -8,7 +8,12 @@
 //!
 //! The outcome of each function is a `Vec<Result>` with a one-to-one mapping to the attestations
 //! supplied as input. Each result provides the exact success or failure result of the corresponding
-//! attestation, with no loss of fidelity when compared to individual verification.
+//! attestation, with no loss of fidelity when compared to individual verification. It is returned
+//! alongside a `BatchVerificationSummary`, which records whether the batch had to fall back to
+//! individual verification.
+//!
+//! Each batch is counted as in flight for the duration of its verification, which contributes to
+//! `BeaconChain::verification_pressure`.
 use super::{
     CheckAttestationSignature, Error, IndexedAggregatedAttestation, IndexedUnaggregatedAttestation,
     VerifiedAggregatedAttestation, VerifiedUnaggregatedAttestation,
@@ -25,17 +30,40 @@ use state_processing::signature_sets::{
 use std::borrow::Cow;
 use types::*;
 
+/// A summary of the verification of a batch of attestations.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct BatchVerificationSummary {
+    /// The number of attestations in the batch.
+    pub batch_size: usize,
+    /// The number of signature sets which were verified as a batch.
+    pub signature_sets: usize,
+    /// Whether the batch signature verification failed, so that the signature of each attestation
+    /// was verified individually.
+    pub fallback: bool,
+    /// The number of attestations which were verified individually after falling back.
+    pub salvaged: usize,
+}
+
 /// Verify aggregated attestations using batch BLS signature verification.
 ///
 /// See module-level docs for more info.
+#[allow(clippy::type_complexity)]
 pub fn batch_verify_aggregated_attestations<'a, T, I>(
     aggregates: I,
     chain: &BeaconChain<T>,
-) -> Result<Vec<Result<VerifiedAggregatedAttestation<'a, T>, Error>>, Error>
+) -> Result<
+    (
+        Vec<Result<VerifiedAggregatedAttestation<'a, T>, Error>>,
+        BatchVerificationSummary,
+    ),
+    Error,
+>
 where
     T: BeaconChainTypes,
     I: Iterator<Item = &'a SignedAggregateAndProof<T::EthSpec>> + ExactSizeIterator,
 {
+    let _pressure_guard = chain.verification_pressure.start_batch();
+
     let mut num_indexed = 0;
     let mut num_failed = 0;
 
@@ -52,6 +80,15 @@ where
         })
         .collect::<Vec<_>>();
 
+    let mut summary = BatchVerificationSummary {
+        batch_size: indexing_results.len(),
+        ..<_>::default()
+    };
+    metrics::observe(
+        &metrics::ATTESTATION_PROCESSING_BATCH_AGG_SIZE,
+        summary.batch_size as f64,
+    );
+
     // May be set to `No` if batch verification succeeds.
     let mut check_signatures = CheckAttestationSignature::Yes;
 
@@ -112,10 +149,14 @@ where
         let _signature_verification_timer =
             metrics::start_timer(&metrics::ATTESTATION_PROCESSING_BATCH_AGG_SIGNATURE_TIMES);
 
+        summary.signature_sets = signature_sets.len();
         if verify_signature_sets(signature_sets.iter()) {
             // Since all the signatures verified in a batch, there's no reason for them to be
             // checked again later.
             check_signatures = CheckAttestationSignature::No
+        } else {
+            summary.fallback = true;
+            metrics::inc_counter(&metrics::ATTESTATION_PROCESSING_BATCH_AGG_FALLBACKS);
         }
     }
 
@@ -128,22 +169,39 @@ where
             }
             Err(e) => Err(e),
         })
-        .collect();
+        .collect::<Vec<_>>();
 
-    Ok(final_results)
+    if summary.fallback {
+        summary.salvaged = final_results.iter().filter(|result| result.is_ok()).count();
+        metrics::inc_counter_by(
+            &metrics::ATTESTATION_PROCESSING_BATCH_AGG_SALVAGED,
+            summary.salvaged as u64,
+        );
+    }
+
+    Ok((final_results, summary))
 }
 
 /// Verify unaggregated attestations using batch BLS signature verification.
 ///
 /// See module-level docs for more info.
+#[allow(clippy::type_complexity)]
 pub fn batch_verify_unaggregated_attestations<'a, T, I>(
     attestations: I,
     chain: &BeaconChain<T>,
-) -> Result<Vec<Result<VerifiedUnaggregatedAttestation<'a, T>, Error>>, Error>
+) -> Result<
+    (
+        Vec<Result<VerifiedUnaggregatedAttestation<'a, T>, Error>>,
+        BatchVerificationSummary,
+    ),
+    Error,
+>
 where
     T: BeaconChainTypes,
     I: Iterator<Item = (&'a Attestation<T::EthSpec>, Option<SubnetId>)> + ExactSizeIterator,
 {
+    let _pressure_guard = chain.verification_pressure.start_batch();
+
     let mut num_partially_verified = 0;
     let mut num_failed = 0;
 
@@ -160,6 +218,15 @@ where
         })
         .collect::<Vec<_>>();
 
+    let mut summary = BatchVerificationSummary {
+        batch_size: partial_results.len(),
+        ..<_>::default()
+    };
+    metrics::observe(
+        &metrics::ATTESTATION_PROCESSING_BATCH_UNAGG_SIZE,
+        summary.batch_size as f64,
+    );
+
     // May be set to `No` if batch verification succeeds.
     let mut check_signatures = CheckAttestationSignature::Yes;
 
@@ -200,10 +267,14 @@ where
         let _signature_verification_timer =
             metrics::start_timer(&metrics::ATTESTATION_PROCESSING_BATCH_UNAGG_SIGNATURE_TIMES);
 
+        summary.signature_sets = signature_sets.len();
         if verify_signature_sets(signature_sets.iter()) {
             // Since all the signatures verified in a batch, there's no reason for them to be
             // checked again later.
             check_signatures = CheckAttestationSignature::No
+        } else {
+            summary.fallback = true;
+            metrics::inc_counter(&metrics::ATTESTATION_PROCESSING_BATCH_UNAGG_FALLBACKS);
         }
     }
 
@@ -216,7 +287,15 @@ where
             }
             Err(e) => Err(e),
         })
-        .collect();
+        .collect::<Vec<_>>();
+
+    if summary.fallback {
+        summary.salvaged = final_results.iter().filter(|result| result.is_ok()).count();
+        metrics::inc_counter_by(
+            &metrics::ATTESTATION_PROCESSING_BATCH_UNAGG_SALVAGED,
+            summary.salvaged as u64,
+        );
+    }
 
-    Ok(final_results)
+    Ok((final_results, summary))
 }
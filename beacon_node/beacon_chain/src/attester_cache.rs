@@ -36,6 +36,10 @@ type CacheHashMap = HashMap<AttesterCacheKey, AttesterCacheValue>;
 /// however we make it large since the values are so small.
 const MAX_CACHE_LEN: usize = 1_024;
 
+/// The memory used by each entry of the cache, in bytes.
+const ENTRY_MEMORY_USAGE: usize =
+    std::mem::size_of::<AttesterCacheKey>() + std::mem::size_of::<AttesterCacheValue>();
+
 #[derive(Debug)]
 pub enum Error {
     BeaconState(BeaconStateError),
@@ -381,4 +385,25 @@ impl AttesterCache {
     pub fn prune_below(&self, epoch: Epoch) {
         self.cache.write().retain(|target, _| target.epoch >= epoch);
     }
+
+    /// Returns an estimate of the memory used by the cached values, in bytes.
+    pub fn memory_usage(&self) -> usize {
+        self.cache.read().len() * ENTRY_MEMORY_USAGE
+    }
+
+    /// Remove the entries with the lowest epochs until at most `max_bytes` are used.
+    pub fn shrink_to(&self, max_bytes: usize) {
+        let mut cache = self.cache.write();
+        while cache.len() * ENTRY_MEMORY_USAGE > max_bytes {
+            if let Some(oldest) = cache
+                .iter()
+                .map(|(key, _)| *key)
+                .min_by_key(|key| key.epoch)
+            {
+                cache.remove(&oldest);
+            } else {
+                break;
+            }
+        }
+    }
 }
@@ -0,0 +1,191 @@
+//! Paces the import of historical blocks by backfill sync.
+//!
+//! After checkpoint sync, backfill competes with forward sync and validator duties for CPU and
+//! disk. The limiter allows a configured number of batches per slot, and can be paused and resumed
+//! at runtime.
+use crate::chain_config::BackfillRateLimit;
+use crate::{metrics, BeaconChain, BeaconChainTypes};
+use futures::future::select;
+use futures::pin_mut;
+use parking_lot::Mutex;
+use slot_clock::SlotClock;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tokio::sync::Notify;
+use tokio::time::sleep;
+use types::Slot;
+
+/// How long to wait before retrying a batch if the current slot is unknown.
+const UNKNOWN_SLOT_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+pub struct BackfillRateLimiter {
+    limit: Option<BackfillRateLimit>,
+    paused: AtomicBool,
+    /// Wakes any batch waiting on a pause when backfill is resumed.
+    resumed: Notify,
+    state: Mutex<LimiterState>,
+}
+
+#[derive(Default)]
+struct LimiterState {
+    /// The slot in which `batches_this_slot` were imported.
+    slot: Slot,
+    batches_this_slot: u64,
+    /// The slot in which the last batch was imported.
+    last_batch_slot: Option<Slot>,
+}
+
+impl BackfillRateLimiter {
+    pub fn new(limit: Option<BackfillRateLimit>) -> Self {
+        Self {
+            limit,
+            paused: AtomicBool::new(false),
+            resumed: Notify::new(),
+            state: Mutex::new(LimiterState::default()),
+        }
+    }
+
+    pub fn pause(&self) {
+        self.paused.store(true, Ordering::Relaxed);
+    }
+
+    pub fn resume(&self) {
+        self.paused.store(false, Ordering::Relaxed);
+        self.resumed.notify_waiters();
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    /// Returns `true` and records a batch if a batch may be imported at `current_slot`.
+    pub fn try_acquire(&self, current_slot: Slot) -> bool {
+        if self.is_paused() {
+            return false;
+        }
+
+        let mut state = self.state.lock();
+        if state.slot != current_slot {
+            state.slot = current_slot;
+            state.batches_this_slot = 0;
+        }
+
+        let allowed = match self.limit {
+            None => true,
+            Some(limit) => {
+                state.batches_this_slot < limit.batches_per_slot
+                    || state.last_batch_slot.map_or(true, |last_batch_slot| {
+                        current_slot >= last_batch_slot + limit.max_slots_between_batches
+                    })
+            }
+        };
+
+        if allowed {
+            state.batches_this_slot += 1;
+            state.last_batch_slot = Some(current_slot);
+        }
+        allowed
+    }
+}
+
+impl<T: BeaconChainTypes> BeaconChain<T> {
+    /// Returns `true` and records a batch if the backfill rate limit allows another batch of
+    /// historical blocks to be imported now.
+    ///
+    /// Always returns `false` whilst backfill is paused.
+    pub fn try_acquire_backfill_batch(&self) -> bool {
+        let allowed = self.slot_clock.now().map_or(false, |current_slot| {
+            self.backfill_rate_limiter.try_acquire(current_slot)
+        });
+        if !allowed {
+            metrics::inc_counter(&metrics::BACKFILL_RATE_LIMITED_TOTAL);
+        }
+        allowed
+    }
+
+    /// Wait until a batch refused by `Self::try_acquire_backfill_batch` is worth retrying, i.e.
+    /// until the start of the next slot or until backfill is resumed.
+    pub async fn wait_for_backfill_capacity(&self) {
+        let next_slot = self
+            .slot_clock
+            .duration_to_next_slot()
+            .unwrap_or(UNKNOWN_SLOT_RETRY_DELAY);
+        let next_slot = sleep(next_slot);
+        let resumed = self.backfill_rate_limiter.resumed.notified();
+        pin_mut!(next_slot, resumed);
+        select(next_slot, resumed).await;
+    }
+
+    /// Stop importing historical blocks until `Self::resume_backfill` is called.
+    pub fn pause_backfill(&self) {
+        self.backfill_rate_limiter.pause();
+        metrics::set_gauge(&metrics::BACKFILL_PAUSED, 1);
+    }
+
+    /// Resume importing historical blocks after `Self::pause_backfill`.
+    pub fn resume_backfill(&self) {
+        self.backfill_rate_limiter.resume();
+        metrics::set_gauge(&metrics::BACKFILL_PAUSED, 0);
+    }
+
+    pub fn is_backfill_paused(&self) -> bool {
+        self.backfill_rate_limiter.is_paused()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the number of batches allowed at each of `num_slots` slots, attempting `attempts`
+    /// batches per slot.
+    fn batches_per_slot(
+        limiter: &BackfillRateLimiter,
+        num_slots: u64,
+        attempts: usize,
+    ) -> Vec<usize> {
+        (0..num_slots)
+            .map(|slot| {
+                (0..attempts)
+                    .filter(|_| limiter.try_acquire(Slot::new(slot)))
+                    .count()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn unlimited() {
+        let limiter = BackfillRateLimiter::new(None);
+        assert_eq!(batches_per_slot(&limiter, 2, 5), vec![5, 5]);
+    }
+
+    #[test]
+    fn batches_per_slot_limit() {
+        let limiter = BackfillRateLimiter::new(Some(BackfillRateLimit {
+            batches_per_slot: 2,
+            max_slots_between_batches: 4,
+        }));
+        assert_eq!(batches_per_slot(&limiter, 3, 5), vec![2, 2, 2]);
+    }
+
+    #[test]
+    fn never_starved() {
+        let limiter = BackfillRateLimiter::new(Some(BackfillRateLimit {
+            batches_per_slot: 0,
+            max_slots_between_batches: 3,
+        }));
+        assert_eq!(
+            batches_per_slot(&limiter, 8, 2),
+            vec![1, 0, 0, 1, 0, 0, 1, 0]
+        );
+    }
+
+    #[test]
+    fn pause_and_resume() {
+        let limiter = BackfillRateLimiter::new(None);
+        limiter.pause();
+        assert!(!limiter.try_acquire(Slot::new(0)));
+        limiter.resume();
+        assert!(limiter.try_acquire(Slot::new(0)));
+    }
+}
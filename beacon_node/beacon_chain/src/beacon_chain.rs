@@ -1,30 +1,44 @@
 use crate::attestation_verification::{
     batch_verify_aggregated_attestations, batch_verify_unaggregated_attestations,
-    Error as AttestationError, VerifiedAggregatedAttestation, VerifiedAttestation,
-    VerifiedUnaggregatedAttestation,
+    BatchVerificationSummary, Error as AttestationError, InclusionOnlyAggregatedAttestation,
+    VerifiedAggregatedAttestation, VerifiedAttestation, VerifiedUnaggregatedAttestation,
 };
 use crate::attester_cache::{AttesterCache, AttesterCacheKey};
+use crate::backfill_rate_limiter::BackfillRateLimiter;
 use crate::beacon_proposer_cache::compute_proposer_duties_from_head;
 use crate::beacon_proposer_cache::BeaconProposerCache;
-use crate::block_times_cache::BlockTimesCache;
+use crate::block_size_budget::BlockSizeBudget;
+use crate::block_times_cache::{BlockTimesCache, BlockTimesCacheValue};
 use crate::block_verification::{
     check_block_is_finalized_descendant, check_block_relevancy, get_block_root,
     signature_verify_chain_segment, BlockError, ExecutionPendingBlock, GossipVerifiedBlock,
     IntoExecutionPendingBlock, PayloadVerificationOutcome, POS_PANDA_BANNER,
 };
-use crate::chain_config::ChainConfig;
+use crate::builder_bid_history::BuilderBidHistory;
+use crate::chain_config::{ChainConfig, ShutdownTarget, HEAD_SYNCED_TOLERANCE_SLOTS};
+use crate::checkpoint_state_cache::CheckpointStateCache;
+use crate::clock_monitor::ClockMonitor;
+use crate::committee_memo::CommitteeMemo;
 use crate::early_attester_cache::EarlyAttesterCache;
 use crate::errors::{BeaconChainError as Error, BlockProductionError};
-use crate::eth1_chain::{Eth1Chain, Eth1ChainBackend};
+use crate::eth1_chain::{get_fallback_vote, Eth1Chain, Eth1ChainBackend};
 use crate::events::ServerSentEventHandler;
 use crate::execution_payload::{get_execution_payload, PreparePayloadHandle};
-use crate::fork_choice_signal::{ForkChoiceSignalRx, ForkChoiceSignalTx, ForkChoiceWaitResult};
-use crate::head_tracker::HeadTracker;
-use crate::historical_blocks::HistoricalBlockError;
+use crate::exit_queue::ExitQueueSummary;
+use crate::failure_log_limiter::{FailureKind, FailureLogLimiter};
+use crate::finality_watchdog::FinalityWatchdog;
+use crate::fork_choice_runs::ForkChoiceRunHistory;
+use crate::fork_choice_signal::{ForkChoiceSignalRx, ForkChoiceSignalTx, SlotWaitResult};
+use crate::graffiti::GraffitiMode;
+use crate::head_tracker::{Error as HeadTrackerError, HeadTracker, SszHeadTracker};
+use crate::historical_blocks::{AnchorBlockRoots, HistoricalBlockError};
+use crate::inclusion_delays::{InclusionDelaySummaries, InclusionDelaySummary};
+use crate::indexed_attestation_cache::IndexedAttestationCache;
+use crate::invalid_block_cache::InvalidBlockCache;
 use crate::migrate::BackgroundMigrator;
 use crate::naive_aggregation_pool::{
-    AggregatedAttestationMap, Error as NaiveAggregationError, NaiveAggregationPool,
-    SyncContributionAggregateMap,
+    AggregatedAttestationMap, CommitteeAggregationSummary, Error as NaiveAggregationError,
+    NaiveAggregationPool, SyncContributionAggregateMap,
 };
 use crate::observed_aggregates::{
     Error as AttestationObservationError, ObservedAggregateAttestations, ObservedSyncContributions,
@@ -34,12 +48,19 @@ use crate::observed_attesters::{
 };
 use crate::observed_block_producers::ObservedBlockProducers;
 use crate::observed_operations::{ObservationOutcome, ObservedOperations};
+use crate::op_inclusion_latency::{OperationKind, PendingOperations};
+use crate::orphan_blocks::OrphanBlockTracker;
+use crate::partial_state_skip::PartialStateSkip;
+use crate::payload_reconstruction::PayloadReconstructionFailures;
 use crate::persisted_beacon_chain::{PersistedBeaconChain, DUMMY_CANONICAL_HEAD_BLOCK_ROOT};
 use crate::persisted_fork_choice::PersistedForkChoice;
 use crate::pre_finalization_cache::PreFinalizationBlockCache;
+use crate::proposal_history::ProposalHistory;
 use crate::proposer_prep_service::PAYLOAD_PREPARATION_LOOKAHEAD_FACTOR;
 use crate::shuffling_cache::{BlockShufflingIds, ShufflingCache};
+use crate::shutdown_report::{ShutdownCause, ShutdownReport};
 use crate::snapshot_cache::SnapshotCache;
+use crate::state_advance_locks::StateAdvanceLocks;
 use crate::sync_committee_verification::{
     Error as SyncCommitteeError, VerifiedSyncCommitteeMessage, VerifiedSyncContribution,
 };
@@ -49,11 +70,15 @@ use crate::validator_monitor::{
     HISTORIC_EPOCHS as VALIDATOR_MONITOR_HISTORIC_EPOCHS,
 };
 use crate::validator_pubkey_cache::ValidatorPubkeyCache;
+use crate::validator_registrations::ValidatorRegistrations;
+use crate::validator_registry_diffs::ValidatorRegistryDiffs;
+use crate::verification_pressure::VerificationPressureTracker;
 use crate::BeaconForkChoiceStore;
 use crate::BeaconSnapshot;
 use crate::{metrics, BeaconChainError};
-use eth2::types::{EventKind, SseBlock, SyncDuty};
-use execution_layer::{ExecutionLayer, PayloadAttributes, PayloadStatus};
+use eth2::lighthouse::Eth1CacheStatusData;
+use eth2::types::{EventKind, SelfTestReport, SseBlock, SseInvalidPublishedBlock, SyncDuty};
+use execution_layer::{ExecutionLayer, PayloadAttributes, PayloadSource, PayloadStatus};
 use fork_choice::{
     AttestationFromBlock, ExecutionStatus, ForkChoice, ForkchoiceUpdateParameters,
     InvalidationOperation, PayloadVerificationStatus,
@@ -61,7 +86,7 @@ use fork_choice::{
 use futures::channel::mpsc::Sender;
 use itertools::process_results;
 use itertools::Itertools;
-use operation_pool::{OperationPool, PersistedOperationPool};
+use operation_pool::{OperationPool, PackingBudget, PersistedOperationPool};
 use parking_lot::{Mutex, RwLock};
 use safe_arith::SafeArith;
 use slasher::Slasher;
@@ -69,30 +94,37 @@ use slog::{crit, debug, error, info, trace, warn, Logger};
 use slot_clock::SlotClock;
 use ssz::Encode;
 use state_processing::{
-    common::get_indexed_attestation,
     per_block_processing,
     per_block_processing::errors::AttestationValidationError,
     per_slot_processing,
     state_advance::{complete_state_advance, partial_state_advance},
+    upgrade::upgrade_to_altair,
     BlockSignatureStrategy, SigVerifiedOp, VerifyBlockRoot,
 };
 use std::cmp::Ordering;
+use std::collections::BTreeMap;
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::io::prelude::*;
 use std::marker::PhantomData;
+use std::ops::Range;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering as AtomicOrdering};
 use std::sync::Arc;
-use std::time::{Duration, Instant};
-use store::iter::{BlockRootsIterator, ParentRootBlockIterator, StateRootsIterator};
+use std::time::Duration;
+use store::iter::{
+    AvailableBlockRootsIterator, BlockRootsIterator, ParentRootBlockIterator, StateRootsIterator,
+};
 use store::{
     DatabaseBlock, Error as DBError, HotColdDB, KeyValueStore, KeyValueStoreOp, StoreItem, StoreOp,
 };
 use task_executor::{ShutdownReason, TaskExecutor};
+use tokio::sync::broadcast;
 use tree_hash::TreeHash;
 use types::beacon_state::CloneConfig;
 use types::*;
 
-pub use crate::canonical_head::{CanonicalHead, CanonicalHeadRwLock};
+pub use crate::canonical_head::{CanonicalHead, CanonicalHeadRwLock, HeadChange};
 
 pub type ForkChoiceError = fork_choice::Error<crate::ForkChoiceStoreError>;
 
@@ -158,6 +190,26 @@ pub enum ChainSegmentResult<T: EthSpec> {
     },
 }
 
+/// How the head tracker was persisted by `BeaconChain::persist_head_and_fork_choice`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeadTrackerPersistence {
+    /// All heads were persisted.
+    Full,
+    /// The head tracker could not be encoded, so only the most recent heads were persisted.
+    Truncated { dropped: usize },
+    /// The head tracker was not persisted.
+    Failed,
+    /// Nothing was persisted, since the chain is in read-only mode.
+    ReadOnly,
+}
+
+/// The components persisted by `BeaconChain::persist_head_and_fork_choice`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PersistHeadAndForkChoiceResult {
+    pub fork_choice_persisted: bool,
+    pub head_tracker: HeadTrackerPersistence,
+}
+
 /// Configure the signature verification of produced blocks.
 pub enum ProduceBlockVerification {
     VerifyRandao,
@@ -217,6 +269,49 @@ pub enum StateSkipConfig {
     WithoutStateRoots,
 }
 
+/// A head of the chain, annotated with its relationship to the canonical head and fork choice.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DetailedHead {
+    pub root: Hash256,
+    pub slot: Slot,
+    /// Whether this is the canonical head.
+    pub canonical: bool,
+    /// The number of slots between this head and the canonical head.
+    pub distance: u64,
+    /// Whether this head descends from the justified checkpoint of fork choice.
+    pub descends_from_justified: bool,
+    pub execution_status: ExecutionStatus,
+    /// Whether fork choice could select this head, i.e. it descends from the justified checkpoint
+    /// and its execution payload is not invalid.
+    pub viable: bool,
+}
+
+/// The quality of the attestations packed into a produced block.
+///
+/// Rewards are the proposer rewards for including attestations, in Gwei. Attestations later left
+/// out of the block to keep it within the maximum network size are still counted.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct AttestationPackingInfo {
+    /// The number of distinct `AttestationData` among the packed attestations.
+    pub distinct_attestation_data: usize,
+    /// The number of validators whose votes are included for the first time by the block.
+    pub new_attesting_indices: usize,
+    /// The number of op pool attestations skipped because their shuffling is incompatible with
+    /// the state the block is produced on.
+    pub incompatible_shuffling: usize,
+    /// The reward for the packed attestations.
+    pub packed_reward: u64,
+    /// An estimate of the reward for valid op pool attestations which could not be packed.
+    pub reward_left_on_table: u64,
+}
+
+/// Information about the production of a block.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BlockProductionInfo {
+    pub slot: Slot,
+    pub attestation_packing: AttestationPackingInfo,
+}
+
 pub trait BeaconChainTypes: Send + Sync + 'static {
     type HotStore: store::ItemStore<Self::EthSpec>;
     type ColdStore: store::ItemStore<Self::EthSpec>;
@@ -237,6 +332,7 @@ struct PartialBeaconBlock<E: EthSpec, Payload> {
     proposer_slashings: Vec<ProposerSlashing>,
     attester_slashings: Vec<AttesterSlashing<E>>,
     attestations: Vec<Attestation<E>>,
+    attestation_packing: AttestationPackingInfo,
     deposits: Vec<Deposit>,
     voluntary_exits: Vec<SignedVoluntaryExit>,
     sync_aggregate: Option<SyncAggregate<E>>,
@@ -324,6 +420,8 @@ pub struct BeaconChain<T: BeaconChainTypes> {
     /// Stores information about the canonical head and finalized/justified checkpoints of the
     /// chain. Also contains the fork choice struct, for computing the canonical head.
     pub canonical_head: CanonicalHead<T>,
+    /// Publishes a `HeadChange` each time the canonical head block changes.
+    pub(crate) head_change_tx: broadcast::Sender<HeadChange>,
     /// The root of the genesis block.
     pub genesis_block_root: Hash256,
     /// The root of the genesis state.
@@ -345,6 +443,13 @@ pub struct BeaconChain<T: BeaconChainTypes> {
     pub(crate) snapshot_cache: TimeoutRwLock<SnapshotCache<T::EthSpec>>,
     /// Caches the attester shuffling for a given epoch and shuffling key root.
     pub shuffling_cache: TimeoutRwLock<ShufflingCache>,
+    /// Memoizes the committees of recent slots, consulted before the `shuffling_cache` when
+    /// verifying attestations.
+    pub committee_memo: CommitteeMemo,
+    /// Caches the indexed attestations of gossip aggregates, for reuse during block import.
+    pub indexed_attestation_cache: IndexedAttestationCache<T::EthSpec>,
+    /// Caches the states of the finalized and justified checkpoints.
+    pub(crate) checkpoint_state_cache: CheckpointStateCache<T::EthSpec>,
     /// Caches the beacon block proposer shuffling for a given epoch and shuffling key root.
     pub beacon_proposer_cache: Mutex<BeaconProposerCache>,
     /// Caches a map of `validator_index -> validator_pubkey`.
@@ -357,17 +462,68 @@ pub struct BeaconChain<T: BeaconChainTypes> {
     pub block_times_cache: Arc<RwLock<BlockTimesCache>>,
     /// A cache used to track pre-finalization block roots for quick rejection.
     pub pre_finalization_block_cache: PreFinalizationBlockCache,
+    /// Caches the roots of blocks with invalid payloads, for quick rejection of their children.
+    pub invalid_block_cache: InvalidBlockCache,
+    /// Tracks the pressure on the batch verification of gossip attestations.
+    pub verification_pressure: VerificationPressureTracker,
+    /// The head block and epoch for which the committee caches were most recently pre-warmed.
+    pub(crate) last_cache_prewarm: Mutex<Option<(Hash256, Epoch)>>,
+    /// Summaries of the most recent fork choice runs at the start of a slot.
+    pub(crate) fork_choice_run_history: ForkChoiceRunHistory,
+    /// Summarizes the inclusion delays of the attestations in recently imported blocks.
+    pub(crate) inclusion_delay_summaries: InclusionDelaySummaries,
+    /// Tracks verified operations until they're included in a block, to measure their latency.
+    pub(crate) pending_operations: PendingOperations,
+    /// Tracks blocks which were rejected because their parent is unknown.
+    pub(crate) orphan_block_tracker: OrphanBlockTracker,
+    /// Records payloads from the execution layer which did not match the headers of their blocks.
+    pub(crate) payload_reconstruction_failures: PayloadReconstructionFailures,
+    /// Serializes advancing the state of the head block for block production.
+    pub state_advance_locks: StateAdvanceLocks,
+    /// Paces the import of historical blocks by backfill sync.
+    pub backfill_rate_limiter: BackfillRateLimiter,
+    /// Detects jumps in the wall-clock slot.
+    pub(crate) clock_monitor: ClockMonitor,
+    /// Tracks the number of epochs since finality.
+    pub(crate) finality_watchdog: FinalityWatchdog,
+    /// Rate-limits the logs of failures which repeat each slot or head change.
+    pub(crate) failure_log_limiter: FailureLogLimiter,
+    /// The report of the most recent self-test, if any.
+    pub(crate) self_test_report: Mutex<Option<SelfTestReport>>,
+    /// The progress of the most recent forward state skip which failed part way.
+    pub(crate) partial_state_skip: Mutex<Option<PartialStateSkip<T::EthSpec>>>,
+    /// The exit queue summary of the head state, computed at most once per epoch.
+    pub(crate) exit_queue_summary_cache: Mutex<Option<ExitQueueSummary>>,
+    /// The `block_roots` of the head state at startup, used to check backfilled blocks. `None` if
+    /// backfill was already complete, or once it has passed the slots they cover.
+    pub anchor_block_roots: RwLock<Option<AnchorBlockRoots>>,
     /// Sender given to tasks, so that if they encounter a state in which execution cannot
     /// continue they can request that everything shuts down.
     pub shutdown_sender: Sender<ShutdownReason>,
+    /// Set once `ChainConfig::shutdown_after` has been reached and a shut down requested.
+    pub(crate) shutdown_target_reached: AtomicBool,
+    /// The path at which to write a report when the chain shuts down the node, if any.
+    pub(crate) shutdown_report_path: Option<PathBuf>,
+    /// Set once a shutdown report has been written during this run.
+    pub(crate) shutdown_report_written: AtomicBool,
     /// Logging to CLI, etc.
     pub(crate) log: Logger,
-    /// Arbitrary bytes included in the blocks.
-    pub(crate) graffiti: Graffiti,
+    /// Determines the bytes included in blocks which lack graffiti from the validator.
+    pub(crate) graffiti: GraffitiMode,
     /// Optional slasher.
     pub slasher: Option<Arc<Slasher<T::EthSpec>>>,
     /// Provides monitoring of a set of explicitly defined validators.
     pub validator_monitor: RwLock<ValidatorMonitor<T::EthSpec>>,
+    /// Stores the preferences from validator registrations received via the builder API.
+    pub validator_registrations: RwLock<ValidatorRegistrations>,
+    /// The changes to the validator registry during recent epochs.
+    pub(crate) validator_registry_diffs: Mutex<ValidatorRegistryDiffs>,
+    /// A record of the blocks recently produced by this node.
+    pub(crate) proposal_history: RwLock<ProposalHistory>,
+    /// The signed bids of the builders whose payloads were used for recently produced blocks.
+    pub(crate) builder_bid_history: RwLock<BuilderBidHistory>,
+    /// Information about the most recent block produced by this node.
+    pub(crate) last_block_production_info: RwLock<Option<BlockProductionInfo>>,
 }
 
 type BeaconBlockAndState<T, Payload> = (BeaconBlock<T, Payload>, BeaconState<T>);
@@ -377,18 +533,120 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
     ///
     /// We do it atomically even though no guarantees need to be made about blocks from
     /// the head tracker also being present in fork choice.
-    pub fn persist_head_and_fork_choice(&self) -> Result<(), Error> {
-        let mut batch = vec![];
+    ///
+    /// If the head tracker can't be encoded (i.e., it has more than
+    /// `ChainConfig::max_persisted_heads` heads), fork choice is persisted on its own, followed by
+    /// the most recent heads. The in-memory head tracker is not modified. An error is only
+    /// returned if no component was persisted.
+    pub fn persist_head_and_fork_choice(&self) -> Result<PersistHeadAndForkChoiceResult, Error> {
+        if self.config.read_only {
+            return Ok(PersistHeadAndForkChoiceResult {
+                fork_choice_persisted: false,
+                head_tracker: HeadTrackerPersistence::ReadOnly,
+            });
+        }
 
-        let _head_timer = metrics::start_timer(&metrics::PERSIST_HEAD);
-        batch.push(self.persist_head_in_batch());
+        let head_timer = metrics::start_timer(&metrics::PERSIST_HEAD);
+        let head_tracker_result = self
+            .head_tracker
+            .try_to_ssz_container(self.config.max_persisted_heads);
+        drop(head_timer);
 
-        let _fork_choice_timer = metrics::start_timer(&metrics::PERSIST_FORK_CHOICE);
-        batch.push(self.persist_fork_choice_in_batch());
+        let ssz_head_tracker = match head_tracker_result {
+            Ok(ssz_head_tracker) => ssz_head_tracker,
+            Err(e) => return self.persist_fork_choice_and_truncated_head(e),
+        };
 
+        let _fork_choice_timer = metrics::start_timer(&metrics::PERSIST_FORK_CHOICE);
+        let batch = vec![
+            self.make_persisted_head_from_container(ssz_head_tracker)
+                .as_kv_store_op(BEACON_CHAIN_DB_KEY),
+            self.persist_fork_choice_in_batch(),
+        ];
         self.store.hot_db.do_atomically(batch)?;
 
-        Ok(())
+        Ok(PersistHeadAndForkChoiceResult {
+            fork_choice_persisted: true,
+            head_tracker: HeadTrackerPersistence::Full,
+        })
+    }
+
+    /// Persist fork choice, then the most recent heads of the head tracker, in separate writes.
+    ///
+    /// Used when the full head tracker failed to encode with `head_tracker_error`.
+    fn persist_fork_choice_and_truncated_head(
+        &self,
+        head_tracker_error: HeadTrackerError,
+    ) -> Result<PersistHeadAndForkChoiceResult, Error> {
+        let fork_choice_result = {
+            let _timer = metrics::start_timer(&metrics::PERSIST_FORK_CHOICE);
+            self.store
+                .hot_db
+                .do_atomically(vec![self.persist_fork_choice_in_batch()])
+        };
+        if let Err(e) = &fork_choice_result {
+            error!(
+                self.log,
+                "Failed to persist fork choice";
+                "error" => ?e,
+            );
+        }
+
+        let _timer = metrics::start_timer(&metrics::PERSIST_HEAD);
+        let (ssz_head_tracker, dropped) = self
+            .head_tracker
+            .to_truncated_ssz_container(self.config.max_persisted_heads);
+        warn!(
+            self.log,
+            "Truncated persisted head tracker";
+            "error" => ?head_tracker_error,
+            "dropped_heads" => dropped.len(),
+            "most_recent_dropped_slot" => ?dropped.first().map(|(_, slot)| *slot),
+            "info" => "dropped heads will not be pruned after a restart",
+        );
+        for (root, slot) in &dropped {
+            debug!(
+                self.log,
+                "Dropped head from persisted head tracker";
+                "root" => ?root,
+                "slot" => slot,
+            );
+        }
+        let head_tracker = match self.store.hot_db.do_atomically(vec![self
+            .make_persisted_head_from_container(ssz_head_tracker)
+            .as_kv_store_op(BEACON_CHAIN_DB_KEY)])
+        {
+            Ok(()) => HeadTrackerPersistence::Truncated {
+                dropped: dropped.len(),
+            },
+            Err(e) => {
+                error!(
+                    self.log,
+                    "Failed to persist head tracker";
+                    "error" => ?e,
+                );
+                HeadTrackerPersistence::Failed
+            }
+        };
+
+        match fork_choice_result {
+            Err(e) if head_tracker == HeadTrackerPersistence::Failed => Err(e.into()),
+            fork_choice_result => Ok(PersistHeadAndForkChoiceResult {
+                fork_choice_persisted: fork_choice_result.is_ok(),
+                head_tracker,
+            }),
+        }
+    }
+
+    fn make_persisted_head_from_container(
+        &self,
+        ssz_head_tracker: SszHeadTracker,
+    ) -> PersistedBeaconChain {
+        PersistedBeaconChain {
+            _canonical_head_block_root: DUMMY_CANONICAL_HEAD_BLOCK_ROOT,
+            genesis_block_root: self.genesis_block_root,
+            ssz_head_tracker,
+        }
     }
 
     /// Return a `PersistedBeaconChain` without reference to a `BeaconChain`.
@@ -444,6 +702,10 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
     /// This operation is typically slow and causes a lot of allocations. It should be used
     /// sparingly.
     pub fn persist_op_pool(&self) -> Result<(), Error> {
+        if self.config.read_only {
+            return Ok(());
+        }
+
         let _timer = metrics::start_timer(&metrics::PERSIST_OP_POOL);
 
         self.store.put_item(
@@ -456,6 +718,10 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
 
     /// Persists `self.eth1_chain` and its caches to disk.
     pub fn persist_eth1_cache(&self) -> Result<(), Error> {
+        if self.config.read_only {
+            return Ok(());
+        }
+
         let _timer = metrics::start_timer(&metrics::PERSIST_OP_POOL);
 
         if let Some(eth1_chain) = self.eth1_chain.as_ref() {
@@ -466,6 +732,13 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         Ok(())
     }
 
+    /// Returns the contents of the eth1 caches, or `None` if there is no eth1 backend.
+    pub fn eth1_cache_status(&self) -> Option<Eth1CacheStatusData> {
+        self.eth1_chain
+            .as_ref()
+            .map(|eth1_chain| eth1_chain.cache_status())
+    }
+
     /// Returns the slot _right now_ according to `self.slot_clock`. Returns `Err` if the slot is
     /// unavailable.
     ///
@@ -475,6 +748,15 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         self.slot_clock.now().ok_or(Error::UnableToReadSlot)
     }
 
+    /// Returns `Err(ReadOnlyMode)` if `ChainConfig::read_only` prohibits modifying the chain.
+    pub fn ensure_writable(&self) -> Result<(), Error> {
+        if self.config.read_only {
+            Err(Error::ReadOnlyMode)
+        } else {
+            Ok(())
+        }
+    }
+
     /// Returns the epoch _right now_ according to `self.slot_clock`. Returns `Err` if the epoch is
     /// unavailable.
     ///
@@ -569,10 +851,24 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
     ///     non-skipped slot (identical to the way they are stored in `state.block_roots`) .
     /// - Iterator returns `(Hash256, Slot)`.
     /// - The provided `block_root` is included as the first item in the iterator.
+    /// - On a checkpoint-synced node the iterator ends at the oldest block stored (see
+    ///     `get_oldest_block_slot`), rather than returning roots of blocks yet to be backfilled.
     pub fn rev_iter_block_roots_from(
         &self,
         block_root: Hash256,
     ) -> Result<impl Iterator<Item = Result<(Hash256, Slot), Error>> + '_, Error> {
+        self.rev_iter_available_block_roots_from(block_root)
+    }
+
+    /// As for `rev_iter_block_roots_from`, but the iterator returned reports whether the iteration
+    /// ended before genesis because older blocks or states aren't stored.
+    pub fn rev_iter_available_block_roots_from(
+        &self,
+        block_root: Hash256,
+    ) -> Result<
+        AvailableBlockRootsIterator<impl Iterator<Item = Result<(Hash256, Slot), Error>> + '_>,
+        Error,
+    > {
         let block = self
             .get_blinded_block(&block_root)?
             .ok_or(Error::MissingBeaconBlock(block_root))?;
@@ -580,9 +876,13 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
             .get_state(&block.state_root(), Some(block.slot()))?
             .ok_or_else(|| Error::MissingBeaconState(block.state_root()))?;
         let iter = BlockRootsIterator::owned(&self.store, state);
-        Ok(std::iter::once(Ok((block_root, block.slot())))
+        let iter = std::iter::once(Ok((block_root, block.slot())))
             .chain(iter)
-            .map(|result| result.map_err(|e| e.into())))
+            .map(|result| result.map_err(|e| e.into()));
+        Ok(AvailableBlockRootsIterator::new(
+            iter,
+            self.store.get_oldest_block_slot(),
+        ))
     }
 
     /// Iterates backwards across all `(state_root, slot)` pairs starting from
@@ -657,6 +957,68 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         })
     }
 
+    /// Returns the state root at the start of each epoch in `epochs`, in a single pass over the
+    /// state roots of the canonical chain rather than a lookup for each epoch.
+    ///
+    /// Epochs which start after the head block, or whose states have been pruned, are omitted.
+    ///
+    /// ## Errors
+    ///
+    /// May return a database error.
+    pub fn epoch_boundary_state_roots(
+        &self,
+        epochs: Range<Epoch>,
+    ) -> Result<Vec<(Epoch, Hash256)>, Error> {
+        if epochs.is_empty() {
+            return Ok(vec![]);
+        }
+        let slots_per_epoch = T::EthSpec::slots_per_epoch();
+        let head_slot = self.canonical_head.cached_head().head_slot();
+
+        // Skip any epochs which start within the pruned range of historic states.
+        let (historic_lower_limit, historic_upper_limit) = self.store.get_historic_state_limits();
+        let mut start_slot = epochs.start.start_slot(slots_per_epoch);
+        if start_slot > historic_lower_limit && start_slot < historic_upper_limit {
+            let first_epoch =
+                (historic_upper_limit.as_u64() + slots_per_epoch - 1) / slots_per_epoch;
+            start_slot = Epoch::new(first_epoch).start_slot(slots_per_epoch);
+        }
+        let end_slot = std::cmp::min(epochs.end.start_slot(slots_per_epoch) - 1, head_slot);
+        if start_slot > end_slot {
+            return Ok(vec![]);
+        }
+        let epoch_start_slots = (start_slot.as_u64()..=end_slot.as_u64())
+            .step_by(slots_per_epoch as usize)
+            .map(Slot::new);
+
+        // Try an optimized path of reading the roots directly from the head state.
+        let fast_lookup = self.with_head(|head| {
+            let roots = epoch_start_slots
+                .map(|slot| {
+                    let root = if slot == head.beacon_state.slot() {
+                        Ok(head.beacon_state_root())
+                    } else {
+                        head.beacon_state.get_state_root(slot).copied()
+                    };
+                    root.map(|root| (slot.epoch(slots_per_epoch), root))
+                })
+                .collect::<Result<Vec<_>, _>>();
+            Ok::<_, Error>(roots.ok())
+        })?;
+        if let Some(roots) = fast_lookup {
+            return Ok(roots);
+        }
+
+        process_results(
+            self.forwards_iter_state_roots_until(start_slot, end_slot)?,
+            |iter| {
+                iter.filter(|(_, slot)| *slot % slots_per_epoch == 0)
+                    .map(|(root, slot)| (slot.epoch(slots_per_epoch), root))
+                    .collect()
+            },
+        )
+    }
+
     /// Returns the block at the given slot, if any. Only returns blocks in the canonical chain.
     ///
     /// Use the `skips` parameter to define the behaviour when `request_slot` is a skipped slot.
@@ -753,6 +1115,28 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         })
     }
 
+    /// Returns the block root at the given slot, if any, along with whether the slot was skipped.
+    /// Only returns roots in the canonical chain.
+    ///
+    /// If the slot was skipped, the root of the previous non-skipped slot is returned. Both facts
+    /// are determined from a single read of the head state or a single pass of the forwards
+    /// iterator.
+    ///
+    /// ## Notes
+    ///
+    /// - Returns `Ok(None)` for any slot higher than the current wall-clock slot, or less than
+    ///   the oldest known block slot.
+    pub fn block_root_at_slot_with_skip_info(
+        &self,
+        request_slot: Slot,
+    ) -> Result<Option<(Hash256, bool)>, Error> {
+        self.block_root_at_slot_with_skip_info_internal(request_slot)
+            .or_else(|e| match e {
+                Error::HistoricalBlockError(_) => Ok(None),
+                e => Err(e),
+            })
+    }
+
     /// Returns the block root at the given slot, if any. Only returns roots in the canonical chain.
     ///
     /// ## Notes
@@ -764,39 +1148,94 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
     ///
     /// May return a database error.
     fn block_root_at_slot_skips_none(&self, request_slot: Slot) -> Result<Option<Hash256>, Error> {
+        Ok(self
+            .block_root_at_slot_with_skip_info_internal(request_slot)?
+            .and_then(|(root, was_skipped)| (!was_skipped).then(|| root)))
+    }
+
+    /// Returns the block root at the given slot, if any. Only returns roots in the canonical chain.
+    ///
+    /// ## Notes
+    ///
+    /// - Returns the root at the previous non-skipped slot if the given `Slot` was skipped.
+    /// - Returns `Ok(None)` for any slot higher than the current wall-clock slot.
+    ///
+    /// ## Errors
+    ///
+    /// May return a database error.
+    fn block_root_at_slot_skips_prev(&self, request_slot: Slot) -> Result<Option<Hash256>, Error> {
+        Ok(self
+            .block_root_at_slot_with_skip_info_internal(request_slot)?
+            .map(|(root, _)| root))
+    }
+
+    /// Returns the block root at the given slot, if any, along with whether the slot was skipped.
+    ///
+    /// ## Notes
+    ///
+    /// - Returns the root at the previous non-skipped slot if the given `Slot` was skipped.
+    /// - Returns `Ok(None)` for any slot higher than the current wall-clock slot.
+    ///
+    /// ## Errors
+    ///
+    /// May return a database error, or a `HistoricalBlockError` for a slot prior to the oldest
+    /// known block.
+    fn block_root_at_slot_with_skip_info_internal(
+        &self,
+        request_slot: Slot,
+    ) -> Result<Option<(Hash256, bool)>, Error> {
         if request_slot > self.slot()? {
             return Ok(None);
         } else if request_slot == self.spec.genesis_slot {
-            return Ok(Some(self.genesis_block_root));
+            return Ok(Some((self.genesis_block_root, false)));
         }
 
         let prev_slot = request_slot.saturating_sub(1_u64);
 
-        // Try an optimized path of reading the root directly from the head state.
-        let fast_lookup: Option<Option<Hash256>> = self.with_head(|head| {
+        // Try an optimized path of reading the roots directly from the head state.
+        let fast_lookup: Option<(Hash256, bool)> = self.with_head(|head| {
             let state = &head.beacon_state;
 
-            // Try find the root for the `request_slot`.
-            let request_root_opt = match state.slot().cmp(&request_slot) {
-                // It's always a skip slot if the head is less than the request slot, return early.
-                Ordering::Less => return Ok(Some(None)),
-                // The request slot is the head slot.
-                Ordering::Equal => Some(head.beacon_block_root),
-                // Try find the request slot in the state.
-                Ordering::Greater => state.get_block_root(request_slot).ok().copied(),
-            };
+            match head.beacon_block.slot().cmp(&request_slot) {
+                // All slots between the head block and the request slot are skipped.
+                Ordering::Less => return Ok(Some((head.beacon_block_root, true))),
+                // The request slot is the head block slot.
+                Ordering::Equal => return Ok(Some((head.beacon_block_root, false))),
+                Ordering::Greater => (),
+            }
 
-            if let Some(request_root) = request_root_opt {
-                if let Ok(prev_root) = state.get_block_root(prev_slot) {
-                    return Ok(Some((*prev_root != request_root).then(|| request_root)));
-                }
+            if let (Ok(request_root), Ok(prev_root)) = (
+                state.get_block_root(request_slot),
+                state.get_block_root(prev_slot),
+            ) {
+                return Ok(Some((*request_root, request_root == prev_root)));
             }
 
             // Fast lookup is not possible.
             Ok::<_, Error>(None)
         })?;
-        if let Some(root_opt) = fast_lookup {
-            return Ok(root_opt);
+        if fast_lookup.is_some() {
+            return Ok(fast_lookup);
+        }
+
+        // The slot prior to the oldest known block can't be read, so load the block to determine
+        // whether the slot was skipped.
+        if prev_slot < self.store.get_oldest_block_slot() {
+            return process_results(
+                self.forwards_iter_block_roots_until(request_slot, request_slot)?,
+                |mut iter| iter.next(),
+            )?
+            .map(|(root, slot)| {
+                // Sanity check.
+                if slot != request_slot {
+                    return Err(Error::InconsistentForwardsIter { request_slot, slot });
+                }
+                let block = self
+                    .get_blinded_block(&root)?
+                    .ok_or(Error::MissingBeaconBlock(root))?;
+                Ok((root, block.slot() != request_slot))
+            })
+            .transpose();
         }
 
         if let Some(((prev_root, _), (curr_root, curr_slot))) = process_results(
@@ -810,63 +1249,12 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
                     slot: curr_slot,
                 });
             }
-            Ok((curr_root != prev_root).then(|| curr_root))
+            Ok(Some((curr_root, curr_root == prev_root)))
         } else {
             Ok(None)
         }
     }
 
-    /// Returns the block root at the given slot, if any. Only returns roots in the canonical chain.
-    ///
-    /// ## Notes
-    ///
-    /// - Returns the root at the previous non-skipped slot if the given `Slot` was skipped.
-    /// - Returns `Ok(None)` for any slot higher than the current wall-clock slot.
-    ///
-    /// ## Errors
-    ///
-    /// May return a database error.
-    fn block_root_at_slot_skips_prev(&self, request_slot: Slot) -> Result<Option<Hash256>, Error> {
-        if request_slot > self.slot()? {
-            return Ok(None);
-        } else if request_slot == self.spec.genesis_slot {
-            return Ok(Some(self.genesis_block_root));
-        }
-
-        // Try an optimized path of reading the root directly from the head state.
-        let fast_lookup: Option<Hash256> = self.with_head(|head| {
-            if head.beacon_block.slot() <= request_slot {
-                // Return the head root if all slots between the request and the head are skipped.
-                Ok(Some(head.beacon_block_root))
-            } else if let Ok(root) = head.beacon_state.get_block_root(request_slot) {
-                // Return the root if it's easily accessible from the head state.
-                Ok(Some(*root))
-            } else {
-                // Fast lookup is not possible.
-                Ok::<_, Error>(None)
-            }
-        })?;
-        if let Some(root) = fast_lookup {
-            return Ok(Some(root));
-        }
-
-        process_results(
-            self.forwards_iter_block_roots_until(request_slot, request_slot)?,
-            |mut iter| {
-                if let Some((root, slot)) = iter.next() {
-                    if slot == request_slot {
-                        Ok(Some(root))
-                    } else {
-                        // Sanity check.
-                        Err(Error::InconsistentForwardsIter { request_slot, slot })
-                    }
-                } else {
-                    Ok(None)
-                }
-            },
-        )?
-    }
-
     /// Returns the block at the given root, if any.
     ///
     /// Will also check the early attester cache for the block. Because of this, there's no
@@ -905,49 +1293,89 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         };
 
         // If we only have a blinded block, load the execution payload from the EL.
-        let block_message = blinded_block.message();
-        let execution_payload_header = &block_message
-            .execution_payload()
-            .map_err(|_| Error::BlockVariantLacksExecutionPayload(*block_root))?
-            .execution_payload_header;
+        let execution_payload = self
+            .reconstruct_payload(*block_root, &blinded_block)
+            .await?;
 
-        let exec_block_hash = execution_payload_header.block_hash;
+        // Add the payload to the block to form a full block.
+        blinded_block
+            .try_into_full_block(Some(execution_payload))
+            .ok_or(Error::AddPayloadLogicError)
+            .map(Some)
+    }
 
-        let execution_payload = self
+    /// Obtain the payload for a signed blinded block from the builder and return the full block.
+    ///
+    /// If the builder fails to reveal a payload matching the header in `blinded_block`, the
+    /// builder is considered to have withheld the payload. The failure is recorded in the proposal
+    /// history and the builder's circuit breaker is tripped, so that local payloads are used for
+    /// the next few proposals.
+    ///
+    /// There is no gossip topic for blinded blocks, so the block cannot be published without its
+    /// payload. It is still possible for the builder to publish the full block itself.
+    pub async fn unblind_block(
+        &self,
+        blinded_block: SignedBlindedBeaconBlock<T::EthSpec>,
+    ) -> Result<SignedBeaconBlock<T::EthSpec>, Error> {
+        let execution_layer = self
             .execution_layer
             .as_ref()
-            .ok_or(Error::ExecutionLayerMissing)?
-            .get_payload_by_block_hash(exec_block_hash)
+            .ok_or(Error::ExecutionLayerMissing)?;
+        let block_root = blinded_block.canonical_root();
+        let slot = blinded_block.slot();
+        let execution_payload_header = blinded_block
+            .message()
+            .execution_payload()
+            .map_err(|_| Error::BlockVariantLacksExecutionPayload(block_root))?
+            .execution_payload_header
+            .clone();
+
+        let result = match execution_layer
+            .propose_blinded_beacon_block(&blinded_block)
             .await
-            .map_err(|e| Error::ExecutionLayerErrorPayloadReconstruction(exec_block_hash, e))?
-            .ok_or(Error::BlockHashMissingFromExecutionLayer(exec_block_hash))?;
+        {
+            Ok(execution_payload) => {
+                let header_from_payload = ExecutionPayloadHeader::from(&execution_payload);
+                if header_from_payload == execution_payload_header {
+                    Ok(execution_payload)
+                } else {
+                    Err(Error::BuilderRevealedInconsistentPayload {
+                        slot,
+                        expected_payload_root: execution_payload_header.tree_hash_root(),
+                        revealed_payload_root: header_from_payload.tree_hash_root(),
+                    })
+                }
+            }
+            Err(error) => Err(Error::BuilderFailedToRevealPayload { slot, error }),
+        };
 
-        // Verify payload integrity.
-        let header_from_payload = ExecutionPayloadHeader::from(&execution_payload);
-        if header_from_payload != *execution_payload_header {
-            for txn in &execution_payload.transactions {
-                debug!(
+        match result {
+            Ok(execution_payload) => blinded_block
+                .try_into_full_block(Some(execution_payload))
+                .ok_or(Error::AddPayloadLogicError),
+            Err(e) => {
+                // The builder's signed bid, if this node produced the block.
+                let builder_bid = self
+                    .builder_bid_history
+                    .read()
+                    .get_by_block_root(slot, block_root)
+                    .cloned();
+                crit!(
                     self.log,
-                    "Reconstructed txn";
-                    "bytes" => format!("0x{}", hex::encode(&**txn)),
+                    "Builder withheld payload";
+                    "msg" => "the block will likely be missed, the builder will not be used \
+                              for the next few proposals",
+                    "error" => ?e,
+                    "block_root" => ?block_root,
+                    "slot" => slot,
+                    "builder_bid" => ?builder_bid,
                 );
+                metrics::inc_counter(&metrics::BUILDER_PAYLOAD_WITHHELD_TOTAL);
+                self.proposal_history.write().mark_withheld(block_root);
+                execution_layer.trip_builder_circuit_breaker(slot).await;
+                Err(e)
             }
-
-            return Err(Error::InconsistentPayloadReconstructed {
-                slot: blinded_block.slot(),
-                exec_block_hash,
-                canonical_payload_root: execution_payload_header.tree_hash_root(),
-                reconstructed_payload_root: header_from_payload.tree_hash_root(),
-                canonical_transactions_root: execution_payload_header.transactions_root,
-                reconstructed_transactions_root: header_from_payload.transactions_root,
-            });
         }
-
-        // Add the payload to the block to form a full block.
-        blinded_block
-            .try_into_full_block(Some(execution_payload))
-            .ok_or(Error::AddPayloadLogicError)
-            .map(Some)
     }
 
     pub fn get_blinded_block(
@@ -1022,7 +1450,8 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
     /// is lagging the current period and we need duties for the next period (because we only
     /// have to transition the head to start of the current period).
     ///
-    /// We also need to ensure that the load slot is after the Altair fork.
+    /// We also need to ensure that the load slot is after the Altair fork, and that the state
+    /// loaded has been upgraded to Altair.
     ///
     /// **WARNING**: the state returned will have dummy state roots. It should only be used
     /// for its sync committees (determining duties, etc).
@@ -1034,6 +1463,7 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
             .spec
             .altair_fork_epoch
             .ok_or(Error::AltairForkDisabled)?;
+        let altair_fork_slot = altair_fork_epoch.start_slot(T::EthSpec::slots_per_epoch());
 
         let load_slot = std::cmp::max(
             self.spec.epochs_per_sync_committee_period * sync_committee_period.saturating_sub(1),
@@ -1041,7 +1471,35 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         )
         .start_slot(T::EthSpec::slots_per_epoch());
 
-        self.state_at_slot(load_slot, StateSkipConfig::WithoutStateRoots)
+        let mut state = self.state_at_slot(load_slot, StateSkipConfig::WithoutStateRoots)?;
+
+        // If the first slots of the fork epoch were skipped, the state loaded for the fork slot may
+        // be a pre-fork state which has been advanced to the fork slot without being upgraded.
+        // Advance it across the fork boundary so that it has sync committees.
+        while state.slot() < altair_fork_slot {
+            per_slot_processing(&mut state, Some(Hash256::zero()), &self.spec)?;
+        }
+        if let BeaconState::Base(_) = state {
+            if state.slot() != altair_fork_slot {
+                return Err(Error::NoStateForSlot(load_slot));
+            }
+            upgrade_to_altair(&mut state, &self.spec)?;
+        }
+
+        Ok(state)
+    }
+
+    /// Returns the times at which the block with `block_root` was observed, imported and set as
+    /// head, along with the sources which delivered it.
+    ///
+    /// Only blocks from the last two epochs are available.
+    pub fn block_timings(&self, block_root: Hash256) -> Option<BlockTimesCacheValue> {
+        self.block_times_cache.read().get(block_root).cloned()
+    }
+
+    /// Returns information about the most recent block produced by this node.
+    pub fn last_block_production_info(&self) -> Option<BlockProductionInfo> {
+        *self.last_block_production_info.read()
     }
 
     /// Returns the current heads of the `BeaconChain`. For the canonical head, see `Self::head`.
@@ -1051,20 +1509,64 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         self.head_tracker.heads()
     }
 
+    /// Returns the heads of the `BeaconChain` no more than
+    /// `ChainConfig::detailed_heads_max_age_slots` older than the current slot, annotated with
+    /// their relationship to the canonical head and fork choice.
+    ///
+    /// Heads which have been pruned from fork choice are omitted.
+    pub fn detailed_heads(&self) -> Result<Vec<DetailedHead>, Error> {
+        let min_slot = self
+            .slot()?
+            .saturating_sub(self.config.detailed_heads_max_age_slots);
+        let cached_head = self.canonical_head.cached_head();
+        let canonical_root = cached_head.head_block_root();
+        let canonical_slot = cached_head.head_slot();
+
+        let fork_choice = self.canonical_head.fork_choice_read_lock();
+        let justified_root = fork_choice.justified_checkpoint().root;
+        let proto_array = fork_choice.proto_array();
+
+        let detailed_heads = self
+            .heads()
+            .into_iter()
+            .filter(|(_, slot)| *slot >= min_slot)
+            .filter_map(|(root, slot)| {
+                let execution_status = proto_array.get_block_execution_status(&root)?;
+                let descends_from_justified = proto_array.is_descendant(justified_root, root);
+                Some(DetailedHead {
+                    root,
+                    slot,
+                    canonical: root == canonical_root,
+                    distance: std::cmp::max(slot, canonical_slot).as_u64()
+                        - std::cmp::min(slot, canonical_slot).as_u64(),
+                    descends_from_justified,
+                    execution_status,
+                    viable: descends_from_justified && !execution_status.is_invalid(),
+                })
+            })
+            .collect();
+
+        Ok(detailed_heads)
+    }
+
     pub fn knows_head(&self, block_hash: &SignedBeaconBlockHash) -> bool {
         self.head_tracker.contains_head((*block_hash).into())
     }
 
     /// Returns the `BeaconState` at the given slot.
     ///
-    /// Returns `None` when the state is not found in the database or there is an error skipping
-    /// to a future state.
+    /// Returns `NoStateForSlot` when the state is not found in the database, or
+    /// `StateAdvanceFailed` when there is an error skipping to a future state. The progress of a
+    /// failed skip is retained, so that a retry resumes from the slot at which it failed.
     pub fn state_at_slot(
         &self,
         slot: Slot,
         config: StateSkipConfig,
     ) -> Result<BeaconState<T::EthSpec>, Error> {
-        let head_state = self.head_beacon_state_cloned();
+        let head = self.head_snapshot();
+        let head_state = head
+            .beacon_state
+            .clone_with(CloneConfig::committee_caches_only());
 
         match slot.cmp(&head_state.slot()) {
             Ordering::Equal => Ok(head_state),
@@ -1078,48 +1580,7 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
                     )
                 }
 
-                let start_slot = head_state.slot();
-                let task_start = Instant::now();
-                let max_task_runtime = Duration::from_secs(self.spec.seconds_per_slot);
-
-                let head_state_slot = head_state.slot();
-                let mut state = head_state;
-
-                let skip_state_root = match config {
-                    StateSkipConfig::WithStateRoots => None,
-                    StateSkipConfig::WithoutStateRoots => Some(Hash256::zero()),
-                };
-
-                while state.slot() < slot {
-                    // Do not allow and forward state skip that takes longer than the maximum task duration.
-                    //
-                    // This is a protection against nodes doing too much work when they're not synced
-                    // to a chain.
-                    if task_start + max_task_runtime < Instant::now() {
-                        return Err(Error::StateSkipTooLarge {
-                            start_slot,
-                            requested_slot: slot,
-                            max_task_runtime,
-                        });
-                    }
-
-                    // Note: supplying some `state_root` when it is known would be a cheap and easy
-                    // optimization.
-                    match per_slot_processing(&mut state, skip_state_root, &self.spec) {
-                        Ok(_) => (),
-                        Err(e) => {
-                            warn!(
-                                self.log,
-                                "Unable to load state at slot";
-                                "error" => ?e,
-                                "head_slot" => head_state_slot,
-                                "requested_slot" => slot
-                            );
-                            return Err(Error::NoStateForSlot(slot));
-                        }
-                    };
-                }
-                Ok(state)
+                self.skip_state_forward(head_state, head.beacon_state_root(), slot, config)
             }
             Ordering::Less => {
                 let state_root =
@@ -1252,7 +1713,10 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
 
     /// Returns the block canonical root of the current canonical chain at a given slot, starting from the given state.
     ///
-    /// Returns `None` if the given slot doesn't exist in the chain.
+    /// Returns `None` if the given slot doesn't exist in the chain, or is prior to the states stored.
+    ///
+    /// The roots are read from states rather than blocks, so the root of a block which is yet to be
+    /// backfilled may be returned.
     pub fn root_at_slot_from_state(
         &self,
         target_slot: Slot,
@@ -1323,6 +1787,15 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         }
     }
 
+    /// Summarizes the aggregated attestations in `self.naive_aggregation_pool` at `slot`, by
+    /// committee index.
+    ///
+    /// This allows aggregators to determine whether aggregating is worthwhile without
+    /// cloning any attestations.
+    pub fn naive_pool_summary(&self, slot: Slot) -> BTreeMap<u64, CommitteeAggregationSummary> {
+        self.naive_aggregation_pool.read().summarize_slot(slot)
+    }
+
     /// Returns an aggregated `Attestation`, if any, that has a matching
     /// `attestation.data.tree_hash_root()`.
     ///
@@ -1469,6 +1942,11 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
             let lowest_permissible_slot =
                 head_state.slot().saturating_sub(slots_per_historical_root);
             if request_slot < lowest_permissible_slot {
+                if self.config.allow_ancient_attestation_production {
+                    drop(head);
+                    return self
+                        .produce_ancient_unaggregated_attestation(request_slot, request_index);
+                }
                 return Err(Error::AttestingToAncientSlot {
                     lowest_permissible_slot,
                     request_slot,
@@ -1598,20 +2076,101 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         })
     }
 
+    /// Produce an unaggregated `Attestation` for a `request_slot` which is beyond the
+    /// `slots_per_historical_root` window of the head state, using the state at `request_slot`.
+    ///
+    /// The state may have to be loaded from the freezer database and replayed, so this is much
+    /// slower than attesting from the head. It's only used if
+    /// `ChainConfig::allow_ancient_attestation_production` is set.
+    fn produce_ancient_unaggregated_attestation(
+        &self,
+        request_slot: Slot,
+        request_index: CommitteeIndex,
+    ) -> Result<Attestation<T::EthSpec>, Error> {
+        let _timer = metrics::start_timer(&metrics::ATTESTATION_PRODUCTION_ANCIENT_SECONDS);
+        let request_epoch = request_slot.epoch(T::EthSpec::slots_per_epoch());
+
+        let beacon_block_root = self
+            .block_root_at_slot(request_slot, WhenSlotSkipped::Prev)?
+            .ok_or(Error::NoBlockForSlot(request_slot))?;
+        let target_slot = request_epoch.start_slot(T::EthSpec::slots_per_epoch());
+        let target_root = self
+            .block_root_at_slot(target_slot, WhenSlotSkipped::Prev)?
+            .ok_or(Error::NoBlockForSlot(target_slot))?;
+
+        // Only attest to a block if it is fully verified (i.e. not optimistic or invalid).
+        match self
+            .canonical_head
+            .fork_choice_read_lock()
+            .get_block_execution_status(&beacon_block_root)
+        {
+            Some(execution_status) if execution_status.is_valid_or_irrelevant() => (),
+            Some(execution_status) => {
+                return Err(Error::HeadBlockNotFullyVerified {
+                    beacon_block_root,
+                    execution_status,
+                })
+            }
+            None => return Err(Error::HeadMissingFromForkChoice(beacon_block_root)),
+        };
+
+        debug!(
+            self.log,
+            "Producing attestation from historical state";
+            "beacon_block_root" => ?beacon_block_root,
+            "request_slot" => %request_slot,
+        );
+        let mut state = self.state_at_slot(request_slot, StateSkipConfig::WithStateRoots)?;
+        state.build_committee_cache(RelativeEpoch::Current, &self.spec)?;
+        let committee_len = state
+            .get_beacon_committee(request_slot, request_index)?
+            .committee
+            .len();
+
+        Ok(Attestation {
+            aggregation_bits: BitList::with_capacity(committee_len)?,
+            data: AttestationData {
+                slot: request_slot,
+                index: request_index,
+                beacon_block_root,
+                source: state.current_justified_checkpoint(),
+                target: Checkpoint {
+                    epoch: request_epoch,
+                    root: target_root,
+                },
+            },
+            signature: AggregateSignature::empty(),
+        })
+    }
+
     /// Performs the same validation as `Self::verify_unaggregated_attestation_for_gossip`, but for
     /// multiple attestations using batch BLS verification. Batch verification can provide
     /// significant CPU-time savings compared to individual verification.
+    ///
+    /// The results are returned with a summary of the batch, which records whether the batch had
+    /// to fall back to individual signature verification.
+    #[allow(clippy::type_complexity)]
     pub fn batch_verify_unaggregated_attestations_for_gossip<'a, I>(
         &self,
         attestations: I,
     ) -> Result<
-        Vec<Result<VerifiedUnaggregatedAttestation<'a, T>, AttestationError>>,
+        (
+            Vec<Result<VerifiedUnaggregatedAttestation<'a, T>, AttestationError>>,
+            BatchVerificationSummary,
+        ),
         AttestationError,
     >
     where
         I: Iterator<Item = (&'a Attestation<T::EthSpec>, Option<SubnetId>)> + ExactSizeIterator,
     {
-        batch_verify_unaggregated_attestations(attestations, self)
+        let (results, summary) = batch_verify_unaggregated_attestations(attestations, self)?;
+        for verified in results.iter().flatten() {
+            self.observe_verified_operation(
+                OperationKind::Attestation,
+                verified.attestation().data.tree_hash_root(),
+            );
+        }
+        Ok((results, summary))
     }
 
     /// Accepts some `Attestation` from the network and attempts to verify it, returning `Ok(_)` if
@@ -1637,6 +2196,10 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
                             .register(EventKind::Attestation(Box::new(v.attestation().clone())));
                     }
                 }
+                self.observe_verified_operation(
+                    OperationKind::Attestation,
+                    v.attestation().data.tree_hash_root(),
+                );
                 metrics::inc_counter(&metrics::UNAGGREGATED_ATTESTATION_PROCESSING_SUCCESSES);
                 v
             },
@@ -1646,14 +2209,31 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
     /// Performs the same validation as `Self::verify_aggregated_attestation_for_gossip`, but for
     /// multiple attestations using batch BLS verification. Batch verification can provide
     /// significant CPU-time savings compared to individual verification.
+    ///
+    /// The results are returned with a summary of the batch, see
+    /// `Self::batch_verify_unaggregated_attestations_for_gossip`.
+    #[allow(clippy::type_complexity)]
     pub fn batch_verify_aggregated_attestations_for_gossip<'a, I>(
         &self,
         aggregates: I,
-    ) -> Result<Vec<Result<VerifiedAggregatedAttestation<'a, T>, AttestationError>>, AttestationError>
+    ) -> Result<
+        (
+            Vec<Result<VerifiedAggregatedAttestation<'a, T>, AttestationError>>,
+            BatchVerificationSummary,
+        ),
+        AttestationError,
+    >
     where
         I: Iterator<Item = &'a SignedAggregateAndProof<T::EthSpec>> + ExactSizeIterator,
     {
-        batch_verify_aggregated_attestations(aggregates, self)
+        let (results, summary) = batch_verify_aggregated_attestations(aggregates, self)?;
+        for verified in results.iter().flatten() {
+            self.observe_verified_operation(
+                OperationKind::Attestation,
+                verified.attestation().data.tree_hash_root(),
+            );
+        }
+        Ok((results, summary))
     }
 
     /// Accepts some `SignedAggregateAndProof` from the network and attempts to verify it,
@@ -1674,11 +2254,33 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
                         .register(EventKind::Attestation(Box::new(v.attestation().clone())));
                 }
             }
+            self.observe_verified_operation(
+                OperationKind::Attestation,
+                v.attestation().data.tree_hash_root(),
+            );
             metrics::inc_counter(&metrics::AGGREGATED_ATTESTATION_PROCESSING_SUCCESSES);
             v
         })
     }
 
+    /// Accepts some `SignedAggregateAndProof` from a private aggregation service and, if it's
+    /// valid, adds it to the op pool for inclusion in blocks.
+    ///
+    /// As per `Self::verify_aggregated_attestation_for_gossip`, except that the aggregator needn't
+    /// have been selected. Since that makes it invalid on gossip, the result can't be published.
+    pub fn verify_aggregated_attestation_for_inclusion<'a>(
+        &self,
+        signed_aggregate: &'a SignedAggregateAndProof<T::EthSpec>,
+    ) -> Result<InclusionOnlyAggregatedAttestation<'a, T>, AttestationError> {
+        let verified = InclusionOnlyAggregatedAttestation::verify(signed_aggregate, self)?;
+        self.add_to_block_inclusion_pool(&verified)?;
+        self.observe_verified_operation(
+            OperationKind::Attestation,
+            verified.attestation().data.tree_hash_root(),
+        );
+        Ok(verified)
+    }
+
     /// Accepts some `SyncCommitteeMessage` from the network and attempts to verify it, returning `Ok(_)` if
     /// it is valid to be (re)broadcast on the gossip network.
     pub fn verify_sync_committee_message_for_gossip(
@@ -1711,6 +2313,10 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
                     )));
                 }
             }
+            self.observe_verified_operation(
+                OperationKind::SyncContribution,
+                v.aggregate().message.contribution.beacon_block_root,
+            );
             metrics::inc_counter(&metrics::SYNC_CONTRIBUTION_PROCESSING_SUCCESSES);
             v
         })
@@ -1727,6 +2333,8 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         &self,
         verified: &impl VerifiedAttestation<T>,
     ) -> Result<(), Error> {
+        self.ensure_writable()?;
+
         let _timer = metrics::start_timer(&metrics::FORK_CHOICE_PROCESS_ATTESTATION_TIMES);
 
         self.canonical_head
@@ -1751,6 +2359,8 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         &self,
         unaggregated_attestation: &impl VerifiedAttestation<T>,
     ) -> Result<(), AttestationError> {
+        self.ensure_writable()?;
+
         let _timer = metrics::start_timer(&metrics::ATTESTATION_PROCESSING_APPLY_TO_AGG_POOL);
 
         let attestation = unaggregated_attestation.attestation();
@@ -1860,6 +2470,8 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         &self,
         verified_attestation: &impl VerifiedAttestation<T>,
     ) -> Result<(), AttestationError> {
+        self.ensure_writable()?;
+
         let _timer = metrics::start_timer(&metrics::ATTESTATION_PROCESSING_APPLY_TO_OP_POOL);
 
         // If there's no eth1 chain then it's impossible to produce blocks and therefore
@@ -2000,21 +2612,55 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
     ) -> Result<ObservationOutcome<SignedVoluntaryExit>, Error> {
         // NOTE: this could be more efficient if it avoided cloning the head state
         let wall_clock_state = self.wall_clock_state()?;
-        Ok(self
+        Ok(self
+            .observed_voluntary_exits
+            .lock()
+            .verify_and_observe(exit, &wall_clock_state, &self.spec)
+            .map(|exit| {
+                // this method is called for both API and gossip exits, so this covers all exit events
+                if let Some(event_handler) = self.event_handler.as_ref() {
+                    if event_handler.has_exit_subscribers() {
+                        if let ObservationOutcome::New(exit) = exit.clone() {
+                            event_handler.register(EventKind::VoluntaryExit(exit.into_inner()));
+                        }
+                    }
+                }
+                self.observe_verified_new_operation(OperationKind::VoluntaryExit, &exit);
+                exit
+            })?)
+    }
+
+    /// Verify a batch of voluntary exits before allowing them to propagate on the gossip network.
+    ///
+    /// The wall-clock state is computed once and the signatures of the batch are verified
+    /// together. The outcome for each exit is identical to that of
+    /// `verify_voluntary_exit_for_gossip` when called for each exit in order.
+    pub fn batch_verify_voluntary_exits_for_gossip(
+        &self,
+        exits: Vec<SignedVoluntaryExit>,
+    ) -> Result<Vec<Result<ObservationOutcome<SignedVoluntaryExit>, Error>>, Error> {
+        let wall_clock_state = self.wall_clock_state()?;
+        let outcomes = self
             .observed_voluntary_exits
             .lock()
-            .verify_and_observe(exit, &wall_clock_state, &self.spec)
-            .map(|exit| {
-                // this method is called for both API and gossip exits, so this covers all exit events
+            .batch_verify_and_observe(exits, &wall_clock_state, &self.spec);
+        Ok(outcomes
+            .into_iter()
+            .map(|outcome| {
                 if let Some(event_handler) = self.event_handler.as_ref() {
                     if event_handler.has_exit_subscribers() {
-                        if let ObservationOutcome::New(exit) = exit.clone() {
-                            event_handler.register(EventKind::VoluntaryExit(exit.into_inner()));
+                        if let Ok(ObservationOutcome::New(exit)) = &outcome {
+                            event_handler
+                                .register(EventKind::VoluntaryExit(exit.as_inner().clone()));
                         }
                     }
                 }
-                exit
-            })?)
+                if let Ok(outcome) = &outcome {
+                    self.observe_verified_new_operation(OperationKind::VoluntaryExit, outcome);
+                }
+                outcome.map_err(Into::into)
+            })
+            .collect())
     }
 
     /// Accept a pre-verified exit and queue it for inclusion in an appropriate block.
@@ -2030,11 +2676,36 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         proposer_slashing: ProposerSlashing,
     ) -> Result<ObservationOutcome<ProposerSlashing>, Error> {
         let wall_clock_state = self.wall_clock_state()?;
-        Ok(self.observed_proposer_slashings.lock().verify_and_observe(
+        let outcome = self.observed_proposer_slashings.lock().verify_and_observe(
             proposer_slashing,
             &wall_clock_state,
             &self.spec,
-        )?)
+        )?;
+        self.observe_verified_new_operation(OperationKind::ProposerSlashing, &outcome);
+        Ok(outcome)
+    }
+
+    /// Verify a batch of proposer slashings before allowing them to propagate on the gossip
+    /// network.
+    ///
+    /// See `batch_verify_voluntary_exits_for_gossip`.
+    pub fn batch_verify_proposer_slashings_for_gossip(
+        &self,
+        proposer_slashings: Vec<ProposerSlashing>,
+    ) -> Result<Vec<Result<ObservationOutcome<ProposerSlashing>, Error>>, Error> {
+        let wall_clock_state = self.wall_clock_state()?;
+        Ok(self
+            .observed_proposer_slashings
+            .lock()
+            .batch_verify_and_observe(proposer_slashings, &wall_clock_state, &self.spec)
+            .into_iter()
+            .map(|outcome| {
+                if let Ok(outcome) = &outcome {
+                    self.observe_verified_new_operation(OperationKind::ProposerSlashing, outcome);
+                }
+                outcome.map_err(Into::into)
+            })
+            .collect())
     }
 
     /// Accept some proposer slashing and queue it for inclusion in an appropriate block.
@@ -2050,11 +2721,36 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         attester_slashing: AttesterSlashing<T::EthSpec>,
     ) -> Result<ObservationOutcome<AttesterSlashing<T::EthSpec>>, Error> {
         let wall_clock_state = self.wall_clock_state()?;
-        Ok(self.observed_attester_slashings.lock().verify_and_observe(
+        let outcome = self.observed_attester_slashings.lock().verify_and_observe(
             attester_slashing,
             &wall_clock_state,
             &self.spec,
-        )?)
+        )?;
+        self.observe_verified_new_operation(OperationKind::AttesterSlashing, &outcome);
+        Ok(outcome)
+    }
+
+    /// Verify a batch of attester slashings before allowing them to propagate on the gossip
+    /// network.
+    ///
+    /// See `batch_verify_voluntary_exits_for_gossip`.
+    pub fn batch_verify_attester_slashings_for_gossip(
+        &self,
+        attester_slashings: Vec<AttesterSlashing<T::EthSpec>>,
+    ) -> Result<Vec<Result<ObservationOutcome<AttesterSlashing<T::EthSpec>>, Error>>, Error> {
+        let wall_clock_state = self.wall_clock_state()?;
+        Ok(self
+            .observed_attester_slashings
+            .lock()
+            .batch_verify_and_observe(attester_slashings, &wall_clock_state, &self.spec)
+            .into_iter()
+            .map(|outcome| {
+                if let Ok(outcome) = &outcome {
+                    self.observe_verified_new_operation(OperationKind::AttesterSlashing, outcome);
+                }
+                outcome.map_err(Into::into)
+            })
+            .collect())
     }
 
     /// Accept some attester slashing and queue it for inclusion in an appropriate block.
@@ -2216,6 +2912,13 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         self: &Arc<Self>,
         chain_segment: Vec<Arc<SignedBeaconBlock<T::EthSpec>>>,
     ) -> ChainSegmentResult<T::EthSpec> {
+        if let Err(e) = self.ensure_writable() {
+            return ChainSegmentResult::Failed {
+                imported_blocks: 0,
+                error: BlockError::BeaconChainError(e),
+            };
+        }
+
         let mut imported_blocks = 0;
 
         // Filter uninteresting blocks from the chain segment in a blocking task.
@@ -2337,6 +3040,14 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
                                 "slot" => slot,
                             );
 
+                            if let BlockError::ParentUnknown(block) = &e {
+                                chain.register_orphan_block(
+                                    block.canonical_root(),
+                                    block.parent_root(),
+                                    slot,
+                                );
+                            }
+
                             Err(e)
                         }
                     }
@@ -2364,6 +3075,9 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         self: &Arc<Self>,
         unverified_block: B,
     ) -> Result<Hash256, BlockError<T::EthSpec>> {
+        self.ensure_writable()
+            .map_err(BlockError::BeaconChainError)?;
+
         // Start the Prometheus timer.
         let _full_timer = metrics::start_timer(&metrics::BLOCK_PROCESSING_TIMES);
 
@@ -2423,11 +3137,73 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
                     "Beacon block rejected";
                     "reason" => other.to_string(),
                 );
+                if let BlockError::ParentUnknown(block) = &other {
+                    self.register_orphan_block(
+                        block.canonical_root(),
+                        block.parent_root(),
+                        block.slot(),
+                    );
+                }
                 Err(other)
             }
         }
     }
 
+    /// Verifies a locally produced `block` for gossip, passes it to `publish` and then imports it,
+    /// returning the block root.
+    ///
+    /// This allows the block to be broadcast as soon as it is known to be valid for gossip, rather
+    /// than after the full verification and database write of `Self::process_block`. The block is
+    /// not passed to `publish` if gossip verification fails.
+    ///
+    /// The early attester cache is populated by the import prior to the database write, so
+    /// attestations to the block may be produced whilst it is being written.
+    ///
+    /// ## Errors
+    ///
+    /// If the block is found to be invalid after it was published, a critical log and an
+    /// `invalid_published_block` event are emitted, since the proposal is likely to be orphaned.
+    pub async fn publish_then_import<F>(
+        self: &Arc<Self>,
+        block: Arc<SignedBeaconBlock<T::EthSpec>>,
+        publish: F,
+    ) -> Result<Hash256, BlockError<T::EthSpec>>
+    where
+        F: FnOnce(Arc<SignedBeaconBlock<T::EthSpec>>),
+    {
+        let gossip_verified = self.verify_block_for_gossip(block.clone()).await?;
+        let block_root = gossip_verified.block_root();
+
+        publish(block.clone());
+
+        self.process_block(gossip_verified).await.map_err(|e| {
+            if e.is_invalid_block() {
+                crit!(
+                    self.log,
+                    "Published block failed import";
+                    "msg" => "the block is invalid and will likely be orphaned",
+                    "error" => ?e,
+                    "block_root" => ?block_root,
+                    "slot" => block.slot(),
+                );
+
+                if let Some(event_handler) = self.event_handler.as_ref() {
+                    if event_handler.has_invalid_published_block_subscribers() {
+                        event_handler.register(EventKind::InvalidPublishedBlock(
+                            SseInvalidPublishedBlock {
+                                slot: block.slot(),
+                                block: block_root,
+                                proposer_index: block.message().proposer_index(),
+                                error: format!("{:?}", e),
+                            },
+                        ));
+                    }
+                }
+            }
+            e
+        })
+    }
+
     /// Accepts a fully-verified block and imports it into the chain without performing any
     /// additional verification.
     ///
@@ -2537,14 +3313,20 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
 
         metrics::stop_timer(attestation_observation_timer);
 
+        // Index the attestations in the block once, for both the slasher and fork choice.
+        let indexed_attestations = signed_block
+            .message()
+            .body()
+            .attestations()
+            .iter()
+            .map(|attestation| self.indexed_attestation_for_block(&state, attestation))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(BlockError::BeaconChainError)?;
+
         // If a slasher is configured, provide the attestations from the block.
         if let Some(slasher) = self.slasher.as_ref() {
-            for attestation in signed_block.message().body().attestations() {
-                let committee =
-                    state.get_beacon_committee(attestation.data.slot, attestation.data.index)?;
-                let indexed_attestation = get_indexed_attestation(committee.committee, attestation)
-                    .map_err(|e| BlockError::BeaconChainError(e.into()))?;
-                slasher.accept_attestation(indexed_attestation);
+            for indexed_attestation in &indexed_attestations {
+                slasher.accept_attestation(indexed_attestation.clone());
             }
         }
 
@@ -2613,7 +3395,6 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
                 if let Err(e) =
                     self.verify_weak_subjectivity_checkpoint(wss_checkpoint, block_root, &state)
                 {
-                    let mut shutdown_sender = self.shutdown_sender();
                     crit!(
                         self.log,
                         "Weak subjectivity checkpoint verification failed while importing block!";
@@ -2625,11 +3406,19 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
                         "error" => ?e,
                     );
                     crit!(self.log, "You must use the `--purge-db` flag to clear the database and restart sync. You may be on a hostile network.");
-                    shutdown_sender
-                        .try_send(ShutdownReason::Failure(
-                            "Weak subjectivity checkpoint verification failed. Provided block root is not a checkpoint."
-                        ))
-                        .map_err(|err| BlockError::BeaconChainError(BeaconChainError::WeakSubjectivtyShutdownError(err)))?;
+                    let cached_head = self.canonical_head.cached_head();
+                    self.shut_down_with_report(ShutdownReport::new(
+                        ShutdownCause::WeakSubjectivityConflict,
+                        block_root,
+                        None,
+                        cached_head.head_block_root(),
+                        cached_head.head_slot(),
+                    ))
+                    .map_err(|err| {
+                        BlockError::BeaconChainError(
+                            BeaconChainError::WeakSubjectivtyShutdownError(err),
+                        )
+                    })?;
                     return Err(BlockError::WeakSubjectivityConflict);
                 }
             }
@@ -2671,19 +3460,16 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         let validator_monitor = self.validator_monitor.read();
 
         // Register each attestation in the block with the fork choice service.
-        for attestation in block.body().attestations() {
+        let mut inclusion_delays = InclusionDelaySummary::default();
+        for indexed_attestation in &indexed_attestations {
             let _fork_choice_attestation_timer =
                 metrics::start_timer(&metrics::FORK_CHOICE_PROCESS_ATTESTATION_TIMES);
-            let attestation_target_epoch = attestation.data.target.epoch;
-
-            let committee =
-                state.get_beacon_committee(attestation.data.slot, attestation.data.index)?;
-            let indexed_attestation = get_indexed_attestation(committee.committee, attestation)
-                .map_err(|e| BlockError::BeaconChainError(e.into()))?;
+            let attestation_target_epoch = indexed_attestation.data.target.epoch;
+            inclusion_delays.record(block.slot(), indexed_attestation.data.slot);
 
             match fork_choice.on_attestation(
                 current_slot,
-                &indexed_attestation,
+                indexed_attestation,
                 AttestationFromBlock::True,
             ) {
                 Ok(()) => Ok(()),
@@ -2720,7 +3506,7 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
             {
                 match fork_choice.get_block(&block.parent_root()) {
                     Some(parent_block) => validator_monitor.register_attestation_in_block(
-                        &indexed_attestation,
+                        indexed_attestation,
                         parent_block.slot,
                         &self.spec,
                     ),
@@ -2728,6 +3514,8 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
                 }
             }
         }
+        self.record_inclusion_delays(block.slot(), &inclusion_delays);
+        self.observe_included_operations(block);
 
         // If the block is recent enough and it was not optimistically imported, check to see if it
         // becomes the head block. If so, apply it to the early attester cache. This will allow
@@ -2777,17 +3565,12 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
             // `SyncCommittee` for the sync_aggregate should correspond to the duty slot
             let duty_epoch = block.slot().epoch(T::EthSpec::slots_per_epoch());
             let sync_committee = self.sync_committee_at_epoch(duty_epoch)?;
-            let participant_pubkeys = sync_committee
-                .pubkeys
-                .iter()
-                .zip(sync_aggregate.sync_committee_bits.iter())
-                .filter_map(|(pubkey, bit)| bit.then(|| pubkey))
-                .collect::<Vec<_>>();
 
             validator_monitor.register_sync_aggregate_in_block(
                 block.slot(),
                 block.parent_root(),
-                participant_pubkeys,
+                &sync_committee.pubkeys,
+                &sync_aggregate.sync_committee_bits,
             );
         }
 
@@ -2848,10 +3631,12 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
             // Since the write failed, try to revert the canonical head back to what was stored
             // in the database. This attempts to prevent inconsistency between the database and
             // fork choice.
-            if let Err(e) =
-                self.canonical_head
-                    .restore_from_store(fork_choice, &self.store, &self.spec)
-            {
+            if let Err(e) = self.canonical_head.restore_from_store(
+                fork_choice,
+                &self.store,
+                &self.spec,
+                &self.log,
+            ) {
                 crit!(
                     self.log,
                     "No stored fork choice found to restore from";
@@ -2865,6 +3650,11 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         }
         drop(txn_lock);
 
+        // Register the block with the head tracker whilst holding the fork choice lock, so that
+        // the two never disagree upon the heads of the chain (see `check_head_consistency`).
+        self.head_tracker
+            .register_block(block_root, block.parent_root(), block.slot());
+
         // The fork choice write-lock is dropped *after* the on-disk database has been updated.
         // This prevents inconsistency between the two at the expense of concurrency.
         drop(fork_choice);
@@ -2873,7 +3663,6 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         // about it.
         let block_time_imported = timestamp_now();
 
-        let parent_root = block.parent_root();
         let slot = block.slot();
 
         self.snapshot_cache
@@ -2899,9 +3688,6 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
                 );
             });
 
-        self.head_tracker
-            .register_block(block_root, parent_root, slot);
-
         // Send an event to the `events` endpoint after fully processing the block.
         if let Some(event_handler) = self.event_handler.as_ref() {
             if event_handler.has_block_subscribers() {
@@ -2952,15 +3738,22 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         self.pre_finalization_block_cache
             .block_processed(block_root);
 
+        // The block and its children are no longer awaiting an unknown parent.
+        self.orphan_block_tracker.block_imported(block_root);
+
+        // Record that this block was published, if it was produced by this node.
+        self.proposal_history.write().mark_published(block_root);
+
         Ok(block_root)
     }
 
-    /// If configured, wait for the fork choice run at the start of the slot to complete.
-    fn wait_for_fork_choice_before_block_production(
+    /// If configured, wait for the start of the slot and the fork choice run at the start of it to
+    /// complete.
+    async fn wait_for_fork_choice_before_block_production(
         self: &Arc<Self>,
         slot: Slot,
     ) -> Result<(), BlockProductionError> {
-        if let Some(rx) = &self.fork_choice_signal_rx {
+        if self.fork_choice_signal_rx.is_some() {
             let current_slot = self
                 .slot()
                 .map_err(|_| BlockProductionError::UnableToReadSlot)?;
@@ -2968,16 +3761,20 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
             let timeout = Duration::from_millis(self.config.fork_choice_before_proposal_timeout_ms);
 
             if slot == current_slot || slot == current_slot + 1 {
-                match rx.wait_for_fork_choice(slot, timeout) {
-                    ForkChoiceWaitResult::Success(fc_slot) => {
+                match self.wait_for_slot(slot, timeout).await {
+                    SlotWaitResult::Success(fc_slot, summary) => {
                         debug!(
                             self.log,
                             "Fork choice successfully updated before block production";
                             "slot" => slot,
                             "fork_choice_slot" => fc_slot,
+                            "applied_attestations" => ?summary.map(|s| s.applied),
+                            "fork_choice_ms" => ?summary.map(|s| s.duration.as_millis()),
                         );
                     }
-                    ForkChoiceWaitResult::Behind(fc_slot) => {
+                    // Unreachable, since the fork choice signal is configured.
+                    SlotWaitResult::SlotStarted => (),
+                    SlotWaitResult::Behind(fc_slot) => {
                         warn!(
                             self.log,
                             "Fork choice notifier out of sync with block production";
@@ -2986,7 +3783,7 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
                             "message" => "this block may be orphaned",
                         );
                     }
-                    ForkChoiceWaitResult::TimeOut => {
+                    SlotWaitResult::TimeOut => {
                         warn!(
                             self.log,
                             "Timed out waiting for fork choice before proposal";
@@ -3034,14 +3831,34 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         validator_graffiti: Option<Graffiti>,
         verification: ProduceBlockVerification,
     ) -> Result<BeaconBlockAndState<T::EthSpec, Payload>, BlockProductionError> {
+        let fork_choice_timer = metrics::start_timer(&metrics::BLOCK_PRODUCTION_FORK_CHOICE_TIMES);
+        self.wait_for_fork_choice_before_block_production(slot)
+            .await?;
+        drop(fork_choice_timer);
+
         // Part 1/2 (blocking)
         //
-        // Load the parent state from disk.
+        // Load the parent state from disk, and check the RANDAO reveal before doing any work
+        // upon it.
         let chain = self.clone();
+        let verify_randao = matches!(verification, ProduceBlockVerification::VerifyRandao);
+        let reveal = randao_reveal.clone();
         let (state, state_root_opt) = self
             .task_executor
             .spawn_blocking_handle(
-                move || chain.load_state_for_block_production::<Payload>(slot),
+                move || {
+                    let (state, state_root_opt) =
+                        chain.load_state_for_block_production::<Payload>(slot)?;
+                    if verify_randao {
+                        chain.verify_randao_reveal_for_block_production(
+                            &state,
+                            state_root_opt,
+                            slot,
+                            &reveal,
+                        )?;
+                    }
+                    Ok((state, state_root_opt))
+                },
                 "produce_partial_beacon_block",
             )
             .ok_or(BlockProductionError::ShuttingDown)?
@@ -3062,6 +3879,59 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         .await
     }
 
+    /// Check that `randao_reveal` is a signature by the proposer at `slot` over the epoch of `slot`,
+    /// so that a block with an invalid reveal fails before it is packed rather than when it is
+    /// processed.
+    ///
+    /// The `state` is the parent state loaded for block production. If it is from an earlier epoch
+    /// than `slot`, a copy of it is advanced to find the proposer.
+    fn verify_randao_reveal_for_block_production(
+        &self,
+        state: &BeaconState<T::EthSpec>,
+        state_root_opt: Option<Hash256>,
+        slot: Slot,
+        randao_reveal: &Signature,
+    ) -> Result<(), BlockProductionError> {
+        let expected_epoch = slot.epoch(T::EthSpec::slots_per_epoch());
+
+        let proposer_index = if state.current_epoch() == expected_epoch {
+            state.get_beacon_proposer_index(slot, &self.spec)?
+        } else {
+            let mut state = state.clone_with(CloneConfig::committee_caches_only());
+            // The state root is only required if a block has been applied to the state without it
+            // yet being advanced another slot.
+            let state_root_opt = state_root_opt.or_else(|| {
+                (state.slot() == state.latest_block_header().slot).then(|| state.canonical_root())
+            });
+            partial_state_advance(
+                &mut state,
+                state_root_opt,
+                expected_epoch.start_slot(T::EthSpec::slots_per_epoch()),
+                &self.spec,
+            )?;
+            state.get_beacon_proposer_index(slot, &self.spec)?
+        };
+
+        let pubkey = self
+            .validator_pubkey(proposer_index)
+            .map_err(BlockProductionError::BeaconChain)?
+            .ok_or(BlockProductionError::BeaconChain(
+                Error::ValidatorPubkeyCacheIncomplete(proposer_index),
+            ))?;
+        let domain = self.spec.get_domain(
+            expected_epoch,
+            Domain::Randao,
+            &self.spec.fork_at_epoch(expected_epoch),
+            state.genesis_validators_root(),
+        );
+
+        if randao_reveal.verify(&pubkey, expected_epoch.signing_root(domain)) {
+            Ok(())
+        } else {
+            Err(BlockProductionError::InvalidRandaoReveal { expected_epoch })
+        }
+    }
+
     /// Load a beacon state from the database for block production. This is a long-running process
     /// that should not be performed in an `async` context.
     fn load_state_for_block_production<Payload: ExecPayload<T::EthSpec>>(
@@ -3071,10 +3941,6 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         metrics::inc_counter(&metrics::BLOCK_PRODUCTION_REQUESTS);
         let _complete_timer = metrics::start_timer(&metrics::BLOCK_PRODUCTION_TIMES);
 
-        let fork_choice_timer = metrics::start_timer(&metrics::BLOCK_PRODUCTION_FORK_CHOICE_TIMES);
-        self.wait_for_fork_choice_before_block_production(slot)?;
-        drop(fork_choice_timer);
-
         // Producing a block requires the tree hash cache, so clone a full state corresponding to
         // the head from the snapshot cache. Unfortunately we can't move the snapshot out of the
         // cache (which would be fast), because we need to re-process the block after it has been
@@ -3089,6 +3955,11 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         };
         let (state, state_root_opt) = if head_slot < slot {
             // Normal case: proposing a block atop the current head. Use the snapshot cache.
+            //
+            // Hold the lock for advancing the head state, so that if it needs advancing then
+            // concurrent producers wait for and reuse the result rather than each advancing it.
+            let advance_lock = self.state_advance_locks.lock_for(head_block_root);
+            let mut advances = advance_lock.lock();
             if let Some(pre_state) = self
                 .snapshot_cache
                 .try_read_for(BLOCK_PROCESSING_CACHE_LOCK_TIMEOUT)
@@ -3096,19 +3967,43 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
                     snapshot_cache.get_state_for_block_production(head_block_root)
                 })
             {
-                (pre_state.pre_state, pre_state.state_root)
+                let mut state = pre_state.pre_state;
+                if let Some(state_root) = pre_state.state_root {
+                    // The state advance timer hasn't advanced the head state, so advance it a
+                    // single slot and return it to the snapshot cache.
+                    let _timer =
+                        metrics::start_timer(&metrics::BLOCK_PRODUCTION_STATE_ADVANCE_TIMES);
+                    complete_state_advance(
+                        &mut state,
+                        Some(state_root),
+                        head_slot + 1,
+                        &self.spec,
+                    )?;
+                    *advances += 1;
+
+                    if let Some(mut snapshot_cache) = self
+                        .snapshot_cache
+                        .try_write_for(BLOCK_PROCESSING_CACHE_LOCK_TIMEOUT)
+                    {
+                        snapshot_cache.update_pre_state(
+                            head_block_root,
+                            state.clone_with(CloneConfig::all()),
+                        );
+                    }
+                }
+                (state, None)
             } else {
+                // The head state is missing from the snapshot cache, so the state advance timer
+                // hasn't prepared it. Load it and advance it inline instead.
                 warn!(
                     self.log,
                     "Block production cache miss";
-                    "message" => "this block is more likely to be orphaned",
+                    "message" => "state advance timer lagged, advancing the head state inline",
                     "slot" => slot,
+                    "head_block_root" => ?head_block_root,
                 );
-                let state = self
-                    .state_at_slot(slot - 1, StateSkipConfig::WithStateRoots)
-                    .map_err(|_| BlockProductionError::UnableToProduceAtSlot(slot))?;
-
-                (state, None)
+                metrics::inc_counter(&metrics::BLOCK_PRODUCTION_STATE_ADVANCE_FALLBACKS);
+                self.load_head_state_for_block_production(head_block_root)?
             }
         } else {
             warn!(
@@ -3129,6 +4024,36 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         Ok((state, state_root_opt))
     }
 
+    /// Load the state of the head block with `head_block_root` for block production, when it's
+    /// missing from the snapshot cache.
+    ///
+    /// The state is returned at the slot of the head block, along with its root. It is advanced to
+    /// the production slot by `produce_partial_beacon_block`.
+    fn load_head_state_for_block_production(
+        &self,
+        head_block_root: Hash256,
+    ) -> Result<(BeaconState<T::EthSpec>, Option<Hash256>), BlockProductionError> {
+        let head = self.head_snapshot();
+        if head.beacon_block_root == head_block_root {
+            let state = head.beacon_state.clone_with(CloneConfig::all());
+            return Ok((state, Some(head.beacon_state_root())));
+        }
+        drop(head);
+
+        // The head has changed since its root was read, so load its state from the database.
+        let block = self
+            .get_blinded_block(&head_block_root)
+            .map_err(BlockProductionError::BeaconChain)?
+            .ok_or(BlockProductionError::MissingHeadState { head_block_root })?;
+        let state_root = block.state_root();
+        let state = self
+            .get_state(&state_root, Some(block.slot()))
+            .map_err(BlockProductionError::BeaconChain)?
+            .ok_or(BlockProductionError::MissingHeadState { head_block_root })?;
+
+        Ok((state, Some(state_root)))
+    }
+
     /// Produce a block for some `slot` upon the given `state`.
     ///
     /// Typically the `self.produce_block()` function should be used, instead of calling this
@@ -3176,15 +4101,16 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         //
         // Wait for the execution layer to return an execution payload (if one is required).
         let prepare_payload_handle = partial_beacon_block.prepare_payload_handle.take();
-        let execution_payload = if let Some(prepare_payload_handle) = prepare_payload_handle {
-            let execution_payload = prepare_payload_handle
-                .await
-                .map_err(BlockProductionError::TokioJoin)?
-                .ok_or(BlockProductionError::ShuttingDown)??;
-            Some(execution_payload)
-        } else {
-            None
-        };
+        let (execution_payload, payload_source) =
+            if let Some(prepare_payload_handle) = prepare_payload_handle {
+                let (execution_payload, payload_source) = prepare_payload_handle
+                    .await
+                    .map_err(BlockProductionError::TokioJoin)?
+                    .ok_or(BlockProductionError::ShuttingDown)??;
+                (Some(execution_payload), payload_source)
+            } else {
+                (None, None)
+            };
 
         // Part 3/3 (blocking)
         //
@@ -3196,6 +4122,7 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
                     chain.complete_partial_beacon_block(
                         partial_beacon_block,
                         execution_payload,
+                        payload_source,
                         verification,
                     )
                 },
@@ -3265,7 +4192,21 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         let (proposer_slashings, attester_slashings, voluntary_exits) =
             self.op_pool.get_slashings_and_exits(&state, &self.spec);
 
-        let eth1_data = eth1_chain.eth1_data_for_block_production(&state, &self.spec)?;
+        // A proposal shouldn't be missed because the eth1 node is unavailable, so fall back to
+        // voting from the state if the eth1 cache is unable to provide a vote.
+        let eth1_data = eth1_chain
+            .eth1_data_for_block_production(&state, &self.spec)
+            .unwrap_or_else(|e| {
+                let vote = get_fallback_vote(&state);
+                warn!(
+                    self.log,
+                    "Unable to produce eth1 data vote";
+                    "error" => ?e,
+                    "outcome" => "casting fallback eth1 vote",
+                    "is_state_eth1_data" => vote == *state.eth1_data(),
+                );
+                vote
+            });
         let deposits = eth1_chain.deposits_for_block_inclusion(&state, &eth1_data, &self.spec)?;
 
         // Iterate through the naive aggregation pool and ensure all the attestations from there
@@ -3292,32 +4233,83 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         // Override the beacon node's graffiti with graffiti from the validator, if present.
         let graffiti = match validator_graffiti {
             Some(graffiti) => graffiti,
-            None => self.graffiti,
+            None => self.graffiti.resolve(),
         };
 
         let attestation_packing_timer =
             metrics::start_timer(&metrics::BLOCK_PRODUCTION_ATTESTATION_TIMES);
 
         let mut prev_filter_cache = HashMap::new();
+        let mut prev_incompatible = 0;
         let prev_attestation_filter = |att: &&Attestation<T::EthSpec>| {
-            self.filter_op_pool_attestation(&mut prev_filter_cache, *att, &state)
+            let compatible = self.filter_op_pool_attestation(&mut prev_filter_cache, *att, &state);
+            prev_incompatible += usize::from(!compatible);
+            compatible
         };
         let mut curr_filter_cache = HashMap::new();
+        let mut curr_incompatible = 0;
         let curr_attestation_filter = |att: &&Attestation<T::EthSpec>| {
-            self.filter_op_pool_attestation(&mut curr_filter_cache, *att, &state)
+            let compatible = self.filter_op_pool_attestation(&mut curr_filter_cache, *att, &state);
+            curr_incompatible += usize::from(!compatible);
+            compatible
         };
 
-        let attestations = self
+        let (attestations, packing_stats) = self
             .op_pool
-            .get_attestations(
+            .get_attestations_with_stats(
                 &state,
                 prev_attestation_filter,
                 curr_attestation_filter,
+                PackingBudget {
+                    max_iterations: self.config.attestation_packing_max_iterations,
+                    max_time: Duration::from_millis(self.config.attestation_packing_timeout_ms),
+                },
                 &self.spec,
             )
             .map_err(BlockProductionError::OpPoolError)?;
         drop(attestation_packing_timer);
 
+        let attestation_packing = AttestationPackingInfo {
+            distinct_attestation_data: attestations
+                .iter()
+                .map(|att| &att.data)
+                .collect::<HashSet<_>>()
+                .len(),
+            new_attesting_indices: packing_stats.new_attesting_indices,
+            incompatible_shuffling: prev_incompatible + curr_incompatible,
+            packed_reward: packing_stats.packed_reward,
+            reward_left_on_table: packing_stats
+                .available_reward
+                .saturating_sub(packing_stats.packed_reward),
+        };
+        debug!(
+            self.log,
+            "Packed attestations for block";
+            "slot" => state.slot(),
+            "attestations" => attestations.len(),
+            "distinct_attestation_data" => attestation_packing.distinct_attestation_data,
+            "new_attesting_indices" => attestation_packing.new_attesting_indices,
+            "incompatible_shuffling" => attestation_packing.incompatible_shuffling,
+            "packed_reward" => attestation_packing.packed_reward,
+            "reward_left_on_table" => attestation_packing.reward_left_on_table,
+        );
+        metrics::set_gauge(
+            &metrics::BLOCK_PRODUCTION_ATTESTATION_DISTINCT_DATA,
+            attestation_packing.distinct_attestation_data as i64,
+        );
+        metrics::set_gauge(
+            &metrics::BLOCK_PRODUCTION_ATTESTATION_NEW_ATTESTERS,
+            attestation_packing.new_attesting_indices as i64,
+        );
+        metrics::set_gauge(
+            &metrics::BLOCK_PRODUCTION_ATTESTATION_INCOMPATIBLE_SHUFFLING,
+            attestation_packing.incompatible_shuffling as i64,
+        );
+        metrics::set_gauge(
+            &metrics::BLOCK_PRODUCTION_ATTESTATION_REWARD_LEFT_ON_TABLE,
+            attestation_packing.reward_left_on_table as i64,
+        );
+
         let slot = state.slot();
         let proposer_index = state.get_beacon_proposer_index(state.slot(), &self.spec)? as u64;
 
@@ -3350,6 +4342,7 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
             proposer_slashings,
             attester_slashings,
             attestations,
+            attestation_packing,
             deposits,
             voluntary_exits,
             sync_aggregate,
@@ -3361,6 +4354,7 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         &self,
         partial_beacon_block: PartialBeaconBlock<T::EthSpec, Payload>,
         execution_payload: Option<Payload>,
+        payload_source: Option<PayloadSource>,
         verification: ProduceBlockVerification,
     ) -> Result<BeaconBlockAndState<T::EthSpec, Payload>, BlockProductionError> {
         let PartialBeaconBlock {
@@ -3374,6 +4368,7 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
             proposer_slashings,
             attester_slashings,
             attestations,
+            attestation_packing,
             deposits,
             voluntary_exits,
             sync_aggregate,
@@ -3393,11 +4388,11 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
                     randao_reveal,
                     eth1_data,
                     graffiti,
-                    proposer_slashings: proposer_slashings.into(),
-                    attester_slashings: attester_slashings.into(),
-                    attestations: attestations.into(),
+                    proposer_slashings: VariableList::empty(),
+                    attester_slashings: VariableList::empty(),
+                    attestations: VariableList::empty(),
                     deposits: deposits.into(),
-                    voluntary_exits: voluntary_exits.into(),
+                    voluntary_exits: VariableList::empty(),
                     _phantom: PhantomData,
                 },
             }),
@@ -3410,11 +4405,11 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
                     randao_reveal,
                     eth1_data,
                     graffiti,
-                    proposer_slashings: proposer_slashings.into(),
-                    attester_slashings: attester_slashings.into(),
-                    attestations: attestations.into(),
+                    proposer_slashings: VariableList::empty(),
+                    attester_slashings: VariableList::empty(),
+                    attestations: VariableList::empty(),
                     deposits: deposits.into(),
-                    voluntary_exits: voluntary_exits.into(),
+                    voluntary_exits: VariableList::empty(),
                     sync_aggregate: sync_aggregate
                         .ok_or(BlockProductionError::MissingSyncAggregate)?,
                     _phantom: PhantomData,
@@ -3429,11 +4424,11 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
                     randao_reveal,
                     eth1_data,
                     graffiti,
-                    proposer_slashings: proposer_slashings.into(),
-                    attester_slashings: attester_slashings.into(),
-                    attestations: attestations.into(),
+                    proposer_slashings: VariableList::empty(),
+                    attester_slashings: VariableList::empty(),
+                    attestations: VariableList::empty(),
                     deposits: deposits.into(),
-                    voluntary_exits: voluntary_exits.into(),
+                    voluntary_exits: VariableList::empty(),
                     sync_aggregate: sync_aggregate
                         .ok_or(BlockProductionError::MissingSyncAggregate)?,
                     execution_payload: execution_payload
@@ -3448,6 +4443,32 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
             Signature::empty(),
         );
 
+        // Add the operations which aren't required in order of value, for as long as they fit
+        // within the maximum network size.
+        let mandatory_size = block.ssz_bytes_len();
+        let mut budget = BlockSizeBudget::new(self.config.max_network_size, mandatory_size)
+            .ok_or(BlockProductionError::BlockTooLarge(mandatory_size))?;
+        let (mut inner_block, signature) = block.deconstruct();
+        let mut body = inner_block.body_mut();
+        *body.proposer_slashings_mut() = budget.pack(proposer_slashings).into();
+        *body.attester_slashings_mut() = budget.pack(attester_slashings).into();
+        *body.attestations_mut() = budget.pack(attestations).into();
+        *body.voluntary_exits_mut() = budget.pack(voluntary_exits).into();
+        if budget.excluded() > 0 {
+            warn!(
+                self.log,
+                "Operations excluded from block by size limit";
+                "excluded" => budget.excluded(),
+                "max_network_size" => self.config.max_network_size,
+                "slot" => slot,
+            );
+            metrics::inc_counter_by(
+                &metrics::BLOCK_PRODUCTION_OPERATIONS_EXCLUDED_BY_SIZE,
+                budget.excluded() as u64,
+            );
+        }
+        let block = SignedBeaconBlock::from_block(inner_block, signature);
+
         let block_size = block.ssz_bytes_len();
         debug!(
             self.log,
@@ -3457,6 +4478,7 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
 
         metrics::observe(&metrics::BLOCK_SIZE, block_size as f64);
 
+        // The operations were packed to fit, so this is only a backstop.
         if block_size > self.config.max_network_size {
             return Err(BlockProductionError::BlockTooLarge(block_size));
         }
@@ -3485,6 +4507,23 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
 
         metrics::inc_counter(&metrics::BLOCK_PRODUCTION_SUCCESSES);
 
+        *self.last_block_production_info.write() = Some(BlockProductionInfo {
+            slot,
+            attestation_packing,
+        });
+
+        // Record the source of the payload. This happens prior to the block being signed or
+        // published so that the record exists even if the proposal never makes it on-chain.
+        if let Some(payload_source) = payload_source {
+            self.record_proposal(
+                block.slot(),
+                block.canonical_root(),
+                block.proposer_index(),
+                matches!(Payload::block_type(), BlockType::Blinded),
+                payload_source,
+            );
+        }
+
         trace!(
             self.log,
             "Produced beacon block";
@@ -3523,10 +4562,12 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         let fork_choice_result = self
             .spawn_blocking_handle(
                 move || {
-                    chain
-                        .canonical_head
-                        .fork_choice_write_lock()
-                        .on_invalid_execution_payload(&inner_op)
+                    let mut fork_choice = chain.canonical_head.fork_choice_write_lock();
+                    let result = fork_choice.on_invalid_execution_payload(&inner_op);
+                    // Cache the invalidated blocks, including any descendants of `inner_op`'s
+                    // block, so that their children can be rejected early on gossip.
+                    chain.cache_invalid_blocks(fork_choice.proto_array());
+                    result
                 },
                 "invalid_payload_fork_choice_update",
             )
@@ -3581,9 +4622,13 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
                 recoverable, please reach out to the lighthouse developers for assistance."
             );
 
-            let mut shutdown_sender = self.shutdown_sender();
-            if let Err(e) = shutdown_sender.try_send(ShutdownReason::Failure(
-                INVALID_JUSTIFIED_PAYLOAD_SHUTDOWN_REASON,
+            let cached_head = self.canonical_head.cached_head();
+            if let Err(e) = self.shut_down_with_report(ShutdownReport::new(
+                ShutdownCause::InvalidJustifiedPayload,
+                justified_block.root,
+                justified_block.execution_status.block_hash(),
+                cached_head.head_block_root(),
+                cached_head.head_slot(),
             )) {
                 crit!(
                     self.log,
@@ -3638,9 +4683,12 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
             .clone()
             .ok_or(Error::ExecutionLayerMissing)?;
 
-        // Nothing to do if there are no proposers registered with the EL, exit early to avoid
-        // wasting cycles.
-        if !execution_layer.has_any_proposer_preparation_data().await {
+        // Nothing to do if there are no proposers registered with the EL, via the builder API or
+        // in the suggested fee recipient file, exit early to avoid wasting cycles.
+        if !execution_layer.has_any_proposer_preparation_data().await
+            && self.validator_registrations.read().is_empty()
+            && !execution_layer.has_any_fee_recipient_file_entries()
+        {
             return Ok(());
         }
 
@@ -3654,16 +4702,11 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
             self.spawn_blocking_handle(
                 move || {
                     let cached_head = chain.canonical_head.cached_head();
-                    let head_block_root = cached_head.head_block_root();
-                    let decision_root = cached_head
-                        .snapshot
-                        .beacon_state
-                        .proposer_shuffling_decision_root(head_block_root)?;
                     Ok::<_, Error>((
                         cached_head.head_slot(),
-                        head_block_root,
-                        decision_root,
-                        cached_head.head_random()?,
+                        cached_head.head_block_root(),
+                        cached_head.head_proposer_shuffling_decision_root(),
+                        cached_head.head_random(),
                         cached_head.forkchoice_update_parameters(),
                     ))
                 },
@@ -3693,7 +4736,25 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         let shuffling_decision_root = if head_epoch == prepare_epoch {
             head_decision_root
         } else {
-            head_root
+            Some(head_root)
+        };
+
+        // The head state may lack the history for these values (e.g., an anchor state). A warning
+        // was logged when the head was cached, so there's no need to log another each slot.
+        let (shuffling_decision_root, head_random) = match (shuffling_decision_root, head_random) {
+            (Some(decision_root), Some(head_random)) => (decision_root, head_random),
+            (decision_root, head_random) => {
+                debug!(
+                    self.log,
+                    "Skipping proposer preparation";
+                    "msg" => "the head state lacks the decision root or randao mix",
+                    "has_decision_root" => decision_root.is_some(),
+                    "has_randao_mix" => head_random.is_some(),
+                    "head_root" => ?head_root,
+                    "prepare_slot" => prepare_slot,
+                );
+                return Ok(());
+            }
         };
 
         // Read the proposer from the proposer cache.
@@ -3754,11 +4815,31 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
             proposer
         };
 
-        // If the execution layer doesn't have any proposer data for this validator then we assume
-        // it's not connected to this BN and no action is required.
+        // Validator registrations don't expire, unlike the proposer data stored in the execution
+        // layer. Refresh the execution layer's data for a registered proposer so its fee recipient
+        // is used even if it hasn't recently sent a `prepare_beacon_proposer` request.
+        if let Some(fee_recipient) = self.registered_fee_recipient(proposer as u64)? {
+            execution_layer
+                .update_proposer_preparation(
+                    prepare_epoch,
+                    &[ProposerPreparationData {
+                        validator_index: proposer as u64,
+                        fee_recipient,
+                    }],
+                )
+                .await;
+        }
+
+        // If the execution layer doesn't have any proposer data for this validator and it isn't
+        // listed in the suggested fee recipient file then we assume it's not connected to this BN
+        // and no action is required.
+        let proposer_pubkey = self.validator_pubkey_bytes(proposer)?;
         if !execution_layer
             .has_proposer_preparation_data(proposer as u64)
             .await
+            && !proposer_pubkey.map_or(false, |pubkey| {
+                execution_layer.has_fee_recipient_file_entry(&pubkey)
+            })
         {
             return Ok(());
         }
@@ -3771,7 +4852,7 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
                 .as_secs(),
             prev_randao: head_random,
             suggested_fee_recipient: execution_layer
-                .get_suggested_fee_recipient(proposer as u64)
+                .get_suggested_fee_recipient(proposer as u64, proposer_pubkey)
                 .await,
         };
 
@@ -3965,6 +5046,7 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
                     let fork_choice_update_result = self
                         .spawn_blocking_handle(
                             move || {
+                                chain.uncache_valid_block(&head_block_root);
                                 chain
                                     .canonical_head
                                     .fork_choice_write_lock()
@@ -4171,8 +5253,11 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
                     }
                 }
                 None => {
+                    // This includes a checkpoint which is prior to the states stored, which can't
+                    // be verified.
                     crit!(self.log, "The root at the start slot of the given epoch could not be found";
-                    "wss_checkpoint_slot" => ?slot);
+                    "wss_checkpoint_slot" => ?slot,
+                    "historic_state_limits" => ?self.store.get_historic_state_limits());
                     return Err(BeaconChainError::WeakSubjectivtyVerificationFailure);
                 }
             }
@@ -4188,25 +5273,82 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
     pub async fn per_slot_task(self: &Arc<Self>) {
         trace!(self.log, "Running beacon chain per slot tasks");
         if let Some(slot) = self.slot_clock.now() {
+            // Check for a jump in the system clock before pruning relative to the current slot.
+            self.observe_clock_slot(slot);
+
+            // A read-only chain never changes, so there is nothing to prune and no need to run
+            // fork choice.
+            if self.config.read_only {
+                return;
+            }
+
             // Always run the light-weight pruning tasks (these structures should be empty during
             // sync anyway).
-            self.naive_aggregation_pool.write().prune(slot);
+            let naive_pool_items = {
+                let mut naive_aggregation_pool = self.naive_aggregation_pool.write();
+                naive_aggregation_pool.prune(slot);
+                naive_aggregation_pool.num_items()
+            };
+            metrics::set_gauge(
+                &metrics::NAIVE_AGGREGATION_POOL_ITEMS,
+                naive_pool_items as i64,
+            );
             self.block_times_cache.write().prune(slot);
+            self.prune_pre_finalization_block_cache();
+            self.prune_invalid_block_cache();
+            self.prune_orphan_blocks();
+            self.update_proposal_outcomes(slot);
+            self.prune_builder_bids(slot);
+            self.prune_indexed_attestation_cache(slot);
+            self.prune_pending_operations(slot);
+            if let Err(e) = self.enforce_cache_memory_budget() {
+                debug!(
+                    self.log,
+                    "Unable to enforce cache memory budget";
+                    "error" => ?e,
+                );
+            }
+
+            // The wall-clock slot may have advanced to within the tolerance of the head, so check
+            // the shutdown target even if the head hasn't changed.
+            self.check_shutdown_target();
+
+            // Whilst slots are being skipped, prepare the caches for the first attestations and
+            // block after the gap.
+            self.spawn_cache_prewarm_if_lagging(slot);
 
             // Don't run heavy-weight tasks during sync.
-            if self.best_slot() + MAX_PER_SLOT_FORK_CHOICE_DISTANCE < slot {
+            let head_is_syncing = self.best_slot() + MAX_PER_SLOT_FORK_CHOICE_DISTANCE < slot;
+            self.check_finality_delay(slot, head_is_syncing);
+            if head_is_syncing {
                 return;
             }
 
             // Run fork choice and signal to any waiting task that it has completed.
-            if let Err(e) = self.recompute_head_at_current_slot().await {
-                error!(
-                    self.log,
-                    "Fork choice error at slot start";
-                    "error" => ?e,
-                    "slot" => slot,
-                );
-            }
+            let summary = match self.fork_choice_at_slot(slot).await {
+                Ok(summary) => {
+                    self.record_failure_cleared(FailureKind::ForkChoice);
+                    Some(summary)
+                }
+                Err(e) => {
+                    if self.should_log_failure(
+                        FailureKind::ForkChoice,
+                        "Fork choice error at slot start",
+                        &e,
+                    ) {
+                        error!(
+                            self.log,
+                            "Fork choice error at slot start";
+                            "error" => ?e,
+                            "slot" => slot,
+                        );
+                    }
+                    None
+                }
+            };
+
+            // Check the views of the head now that fork choice has been run.
+            self.check_and_heal_head_consistency().await;
 
             // Send the notification regardless of fork choice success, this is a "best effort"
             // notification and we don't want block production to hit the timeout in case of error.
@@ -4217,7 +5359,7 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
                 move || {
                     // Signal block proposal for the next slot (if it happens to be waiting).
                     if let Some(tx) = &chain.fork_choice_signal_tx {
-                        if let Err(e) = tx.notify_fork_choice_complete(slot) {
+                        if let Err(e) = tx.notify_fork_choice_complete(slot, summary) {
                             warn!(
                                 chain.log,
                                 "Error signalling fork choice waiter";
@@ -4584,6 +5726,81 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         self.shutdown_sender.clone()
     }
 
+    /// Returns `true` if the head has reached `target`.
+    fn is_shutdown_target_reached(&self, target: ShutdownTarget) -> bool {
+        let cached_head = self.canonical_head.cached_head();
+        match target {
+            ShutdownTarget::HeadSynced => self.slot().map_or(false, |current_slot| {
+                cached_head.head_slot() + HEAD_SYNCED_TOLERANCE_SLOTS >= current_slot
+            }),
+            ShutdownTarget::Slot(slot) => cached_head.head_slot() >= slot,
+            ShutdownTarget::FinalizedEpoch(epoch) => {
+                cached_head.finalized_checkpoint().epoch >= epoch
+            }
+        }
+    }
+
+    /// Persist the chain to disk and request a shut down if `ChainConfig::shutdown_after` has been
+    /// reached.
+    ///
+    /// The shut down is requested at most once.
+    pub fn check_shutdown_target(&self) {
+        let target = match self.config.shutdown_after {
+            Some(target) => target,
+            None => return,
+        };
+
+        if self.shutdown_target_reached.load(AtomicOrdering::Relaxed)
+            || !self.is_shutdown_target_reached(target)
+            || self
+                .shutdown_target_reached
+                .swap(true, AtomicOrdering::Relaxed)
+        {
+            return;
+        }
+
+        let persist = || -> Result<(), Error> {
+            self.persist_head_and_fork_choice()?;
+            self.persist_op_pool()?;
+            self.persist_eth1_cache()?;
+            self.persist_proposal_history()?;
+            self.persist_builder_bid_history()?;
+            self.persist_validator_registrations()
+        };
+        if let Err(e) = persist() {
+            error!(
+                self.log,
+                "Failed to persist beacon chain before shut down";
+                "error" => ?e,
+            );
+        }
+
+        let cached_head = self.canonical_head.cached_head();
+        let finalized_checkpoint = cached_head.finalized_checkpoint();
+        info!(
+            self.log,
+            "Shutdown target reached";
+            "target" => ?target,
+            "head_slot" => cached_head.head_slot(),
+            "head_block_root" => ?cached_head.head_block_root(),
+            "head_state_root" => ?cached_head.head_state_root(),
+            "finalized_epoch" => finalized_checkpoint.epoch,
+            "finalized_root" => ?finalized_checkpoint.root,
+        );
+
+        if let Err(e) = self
+            .shutdown_sender()
+            .try_send(ShutdownReason::Success("Shutdown target reached"))
+        {
+            crit!(
+                self.log,
+                "Unable to trigger client shut down";
+                "msg" => "shut down may already be under way",
+                "error" => ?e,
+            );
+        }
+    }
+
     // Used for debugging
     #[allow(dead_code)]
     pub fn dump_dot_file(&self, file_name: &str) {
@@ -4620,10 +5837,21 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
 
 impl<T: BeaconChainTypes> Drop for BeaconChain<T> {
     fn drop(&mut self) {
+        if self.config.read_only {
+            debug!(
+                self.log,
+                "Not saving read-only beacon chain to disk";
+            );
+            return;
+        }
+
         let drop = || -> Result<(), Error> {
             self.persist_head_and_fork_choice()?;
             self.persist_op_pool()?;
-            self.persist_eth1_cache()
+            self.persist_eth1_cache()?;
+            self.persist_proposal_history()?;
+            self.persist_builder_bid_history()?;
+            self.persist_validator_registrations()
         };
 
         if let Err(e) = drop() {
@@ -0,0 +1,104 @@
+//! Re-executes the state transition of a stored block, to diagnose consensus failures.
+//!
+//! The block and its parent state are loaded from the database and the state transition is run on
+//! a copy of the parent state, so the database is never modified.
+use crate::{BeaconChain, BeaconChainError, BeaconChainTypes};
+use eth2::lighthouse::{ReplayReport, ReplayTimings};
+use state_processing::{
+    per_block_processing, per_slot_processing, BlockSignatureStrategy, VerifyBlockRoot,
+};
+use std::time::Instant;
+use types::{BeaconState, Hash256, SignedBlindedBeaconBlock};
+
+impl<T: BeaconChainTypes> BeaconChain<T> {
+    /// Re-execute the state transition of the stored block with `block_root` on its parent state,
+    /// verifying signatures according to `strategy`.
+    ///
+    /// An error from the state transition is included in the report. An `Err` is only returned if
+    /// the block or its parent state can't be loaded.
+    pub fn replay_block(
+        &self,
+        block_root: Hash256,
+        strategy: BlockSignatureStrategy,
+    ) -> Result<ReplayReport, BeaconChainError> {
+        let mut timings = ReplayTimings::default();
+
+        let timer = Instant::now();
+        let block = self
+            .get_blinded_block(&block_root)?
+            .ok_or(BeaconChainError::MissingBeaconBlock(block_root))?;
+        let parent_root = block.parent_root();
+        let parent_block = self
+            .get_blinded_block(&parent_root)?
+            .ok_or(BeaconChainError::MissingBeaconBlock(parent_root))?;
+        let parent_state_root = parent_block.state_root();
+        let state = self
+            .get_state(&parent_state_root, Some(parent_block.slot()))?
+            .ok_or(BeaconChainError::MissingBeaconState(parent_state_root))?;
+        timings.load = timer.elapsed();
+
+        let result = self.replay_block_on_state(
+            &block,
+            block_root,
+            state,
+            parent_state_root,
+            strategy,
+            &mut timings,
+        );
+        let stored_state_root = block.state_root();
+        let computed_state_root = result.as_ref().ok().copied();
+
+        Ok(ReplayReport {
+            block_root,
+            slot: block.slot(),
+            parent_root,
+            computed_state_root,
+            stored_state_root,
+            state_roots_match: computed_state_root == Some(stored_state_root),
+            timings,
+            error: result.err(),
+        })
+    }
+
+    /// Apply `block` to its parent `state`, returning the root of the post-state.
+    fn replay_block_on_state(
+        &self,
+        block: &SignedBlindedBeaconBlock<T::EthSpec>,
+        block_root: Hash256,
+        mut state: BeaconState<T::EthSpec>,
+        parent_state_root: Hash256,
+        strategy: BlockSignatureStrategy,
+        timings: &mut ReplayTimings,
+    ) -> Result<Hash256, String> {
+        let timer = Instant::now();
+        let mut state_root = Some(parent_state_root);
+        while state.slot() < block.slot() {
+            per_slot_processing(&mut state, state_root.take(), &self.spec)
+                .map_err(|e| format!("slot processing failed: {:?}", e))?;
+        }
+        timings.slot_processing = timer.elapsed();
+
+        let timer = Instant::now();
+        state
+            .build_all_committee_caches(&self.spec)
+            .map_err(|e| format!("unable to build committee caches: {:?}", e))?;
+        per_block_processing(
+            &mut state,
+            block,
+            Some(block_root),
+            strategy,
+            VerifyBlockRoot::True,
+            &self.spec,
+        )
+        .map_err(|e| format!("block processing failed: {:?}", e))?;
+        timings.block_processing = timer.elapsed();
+
+        let timer = Instant::now();
+        let state_root = state
+            .update_tree_hash_cache()
+            .map_err(|e| format!("unable to compute state root: {:?}", e))?;
+        timings.state_root = timer.elapsed();
+
+        Ok(state_root)
+    }
+}
@@ -1,10 +1,31 @@
 use crate::{BeaconChain, BeaconChainError, BeaconChainTypes};
 use eth2::lighthouse::{AttestationRewards, BlockReward, BlockRewardMeta};
 use operation_pool::{AttMaxCover, MaxCover};
-use state_processing::per_block_processing::altair::sync_committee::compute_sync_aggregate_rewards;
-use types::{BeaconBlockRef, BeaconState, EthSpec, ExecPayload, Hash256};
+use safe_arith::SafeArith;
+use state_processing::common::{
+    altair::{get_base_reward, BaseRewardPerIncrement},
+    get_attestation_participation_flag_indices, get_attesting_indices,
+};
+use state_processing::per_block_processing::{
+    altair::sync_committee::compute_sync_aggregate_rewards, get_slashable_indices_modular,
+};
+use state_processing::state_advance::complete_state_advance;
+use std::collections::{HashMap, HashSet};
+use types::consts::altair::{PARTICIPATION_FLAG_WEIGHTS, PROPOSER_WEIGHT, WEIGHT_DENOMINATOR};
+use types::{
+    BeaconBlockRef, BeaconState, BeaconStateError, Epoch, EthSpec, ExecPayload, Hash256,
+    ParticipationFlags,
+};
+
+/// The reward for each validator included by each attestation of a block, along with the total
+/// reward for each attestation.
+type PerAttestationRewards = (Vec<HashMap<u64, u64>>, Vec<u64>);
 
 impl<T: BeaconChainTypes> BeaconChain<T> {
+    /// Compute the rewards paid to the proposer of `block`.
+    ///
+    /// The `state` must be the pre-state of `block`, advanced to the slot of `block`. The rewards
+    /// are counted as per-block processing would apply them, without modifying the `state`.
     pub fn compute_block_reward<Payload: ExecPayload<T::EthSpec>>(
         &self,
         block: BeaconBlockRef<'_, T::EthSpec, Payload>,
@@ -16,50 +37,31 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
             return Err(BeaconChainError::BlockRewardSlotError);
         }
 
-        let total_active_balance = state.get_total_active_balance()?;
-        let mut per_attestation_rewards = block
-            .body()
-            .attestations()
-            .iter()
-            .map(|att| {
-                AttMaxCover::new(att, state, total_active_balance, &self.spec)
-                    .ok_or(BeaconChainError::BlockRewardAttestationError)
-            })
-            .collect::<Result<Vec<_>, _>>()?;
-
-        // Update the attestation rewards for each previous attestation included.
-        // This is O(n^2) in the number of attestations n.
-        for i in 0..per_attestation_rewards.len() {
-            let (updated, to_update) = per_attestation_rewards.split_at_mut(i + 1);
-            let latest_att = &updated[i];
-
-            for att in to_update {
-                att.update_covering_set(latest_att.object(), latest_att.covering_set());
+        let (per_attestation_rewards, per_attestation_totals) = match state {
+            BeaconState::Base(_) => self.compute_attestation_rewards_base(block, state)?,
+            BeaconState::Altair(_) | BeaconState::Merge(_) => {
+                self.compute_attestation_rewards_altair(block, state)?
             }
-        }
+        };
 
         let mut prev_epoch_total = 0;
         let mut curr_epoch_total = 0;
 
-        for cover in &per_attestation_rewards {
-            for &reward in cover.fresh_validators_rewards.values() {
-                if cover.att.data.slot.epoch(T::EthSpec::slots_per_epoch()) == state.current_epoch()
-                {
-                    curr_epoch_total += reward;
-                } else {
-                    prev_epoch_total += reward;
-                }
+        for (att, reward) in block
+            .body()
+            .attestations()
+            .iter()
+            .zip(per_attestation_totals)
+        {
+            if att.data.target.epoch == state.current_epoch() {
+                curr_epoch_total += reward;
+            } else {
+                prev_epoch_total += reward;
             }
         }
 
         let attestation_total = prev_epoch_total + curr_epoch_total;
 
-        // Drop the covers.
-        let per_attestation_rewards = per_attestation_rewards
-            .into_iter()
-            .map(|cover| cover.fresh_validators_rewards)
-            .collect();
-
         // Add the attestation data if desired.
         let attestations = if include_attestations {
             block
@@ -89,8 +91,45 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
             0
         };
 
+        // Slashing rewards. The proposer receives the whole whistleblower reward, and each
+        // validator is only slashed once.
+        let mut slashed = HashSet::new();
+        let mut whistleblower_reward = |index: u64| -> Result<u64, BeaconChainError> {
+            if !slashed.insert(index) {
+                return Ok(0);
+            }
+            let validator = state
+                .validators()
+                .get(index as usize)
+                .ok_or(BeaconStateError::UnknownValidator(index as usize))?;
+            Ok(validator
+                .effective_balance
+                .safe_div(self.spec.whistleblower_reward_quotient)?)
+        };
+
+        let mut proposer_slashing_reward = 0;
+        for proposer_slashing in block.body().proposer_slashings() {
+            proposer_slashing_reward +=
+                whistleblower_reward(proposer_slashing.signed_header_1.message.proposer_index)?;
+        }
+
+        let mut attester_slashing_reward = 0;
+        for attester_slashing in block.body().attester_slashings() {
+            let slashable_indices =
+                get_slashable_indices_modular(state, attester_slashing, |_, validator| {
+                    validator.is_slashable_at(state.current_epoch())
+                })
+                .map_err(|_| BeaconChainError::BlockRewardSlashingError)?;
+            for index in slashable_indices {
+                attester_slashing_reward += whistleblower_reward(index)?;
+            }
+        }
+
         // Total, metadata
-        let total = attestation_total + sync_committee_rewards;
+        let total = attestation_total
+            + sync_committee_rewards
+            + proposer_slashing_reward
+            + attester_slashing_reward;
 
         let meta = BlockRewardMeta {
             slot: block.slot(),
@@ -105,6 +144,157 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
             meta,
             attestation_rewards,
             sync_committee_rewards,
+            proposer_slashing_reward,
+            attester_slashing_reward,
         })
     }
+
+    /// Compute the rewards paid to the proposer of the imported block with `block_root`, loading
+    /// the state of its parent.
+    pub fn compute_block_reward_for_root(
+        &self,
+        block_root: Hash256,
+        include_attestations: bool,
+    ) -> Result<BlockReward, BeaconChainError> {
+        let block = self
+            .get_blinded_block(&block_root)?
+            .ok_or(BeaconChainError::MissingBeaconBlock(block_root))?;
+        let parent_root = block.parent_root();
+        let parent_block = self
+            .get_blinded_block(&parent_root)?
+            .ok_or(BeaconChainError::MissingBeaconBlock(parent_root))?;
+        let parent_state_root = parent_block.state_root();
+        let mut state = self
+            .get_state(&parent_state_root, Some(parent_block.slot()))?
+            .ok_or(BeaconChainError::MissingBeaconState(parent_state_root))?;
+
+        complete_state_advance(
+            &mut state,
+            Some(parent_state_root),
+            block.slot(),
+            &self.spec,
+        )?;
+        state.build_all_committee_caches(&self.spec)?;
+
+        self.compute_block_reward(block.message(), block_root, &state, include_attestations)
+    }
+
+    /// Prior to Altair the proposer is rewarded for attestations during epoch processing. Count
+    /// the reward for each validator included for the first time by the block.
+    fn compute_attestation_rewards_base<Payload: ExecPayload<T::EthSpec>>(
+        &self,
+        block: BeaconBlockRef<'_, T::EthSpec, Payload>,
+        state: &BeaconState<T::EthSpec>,
+    ) -> Result<PerAttestationRewards, BeaconChainError> {
+        let total_active_balance = state.get_total_active_balance()?;
+        let mut per_attestation_rewards = block
+            .body()
+            .attestations()
+            .iter()
+            .map(|att| {
+                AttMaxCover::new(att, state, total_active_balance, &self.spec)
+                    .ok_or(BeaconChainError::BlockRewardAttestationError)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Update the attestation rewards for each previous attestation included.
+        // This is O(n^2) in the number of attestations n.
+        for i in 0..per_attestation_rewards.len() {
+            let (updated, to_update) = per_attestation_rewards.split_at_mut(i + 1);
+            let latest_att = &updated[i];
+
+            for att in to_update {
+                att.update_covering_set(latest_att.object(), latest_att.covering_set());
+            }
+        }
+
+        // Drop the covers.
+        let per_attestation_rewards = per_attestation_rewards
+            .into_iter()
+            .map(|cover| cover.fresh_validators_rewards)
+            .collect::<Vec<_>>();
+        let per_attestation_totals = per_attestation_rewards
+            .iter()
+            .map(|rewards| rewards.values().sum())
+            .collect();
+
+        Ok((per_attestation_rewards, per_attestation_totals))
+    }
+
+    /// From Altair the proposer is rewarded for each participation flag newly set by an
+    /// attestation, as in `process_attestation`. The reward of each attestation is rounded down
+    /// once, so flags set by earlier attestations in the block are tracked.
+    fn compute_attestation_rewards_altair<Payload: ExecPayload<T::EthSpec>>(
+        &self,
+        block: BeaconBlockRef<'_, T::EthSpec, Payload>,
+        state: &BeaconState<T::EthSpec>,
+    ) -> Result<PerAttestationRewards, BeaconChainError> {
+        let total_active_balance = state.get_total_active_balance()?;
+        let base_reward_per_increment =
+            BaseRewardPerIncrement::new(total_active_balance, &self.spec)?;
+        let proposer_reward_denominator = WEIGHT_DENOMINATOR
+            .safe_sub(PROPOSER_WEIGHT)?
+            .safe_mul(WEIGHT_DENOMINATOR)?
+            .safe_div(PROPOSER_WEIGHT)?;
+
+        let mut participation = HashMap::<(Epoch, usize), ParticipationFlags>::new();
+        let mut per_attestation_rewards = vec![];
+        let mut per_attestation_totals = vec![];
+
+        for att in block.body().attestations() {
+            let committee = state.get_beacon_committee(att.data.slot, att.data.index)?;
+            let attesting_indices =
+                get_attesting_indices::<T::EthSpec>(committee.committee, &att.aggregation_bits)?;
+            let inclusion_delay = state.slot().safe_sub(att.data.slot)?.as_u64();
+            let participation_flag_indices = get_attestation_participation_flag_indices(
+                state,
+                &att.data,
+                inclusion_delay,
+                &self.spec,
+            )?;
+            let epoch_participation = if att.data.target.epoch == state.current_epoch() {
+                state.current_epoch_participation()?
+            } else {
+                state.previous_epoch_participation()?
+            };
+
+            let mut rewards = HashMap::new();
+            let mut proposer_reward_numerator = 0;
+            for index in attesting_indices {
+                let state_flags = *epoch_participation
+                    .get(index)
+                    .ok_or(BeaconStateError::ParticipationOutOfBounds(index))?;
+                let flags = participation
+                    .entry((att.data.target.epoch, index))
+                    .or_insert(state_flags);
+                let base_reward =
+                    get_base_reward(state, index, base_reward_per_increment, &self.spec)?;
+
+                let mut validator_reward_numerator = 0;
+                for (flag_index, weight) in PARTICIPATION_FLAG_WEIGHTS.iter().enumerate() {
+                    if participation_flag_indices.contains(&flag_index)
+                        && !flags.has_flag(flag_index)?
+                    {
+                        flags.add_flag(flag_index)?;
+                        validator_reward_numerator
+                            .safe_add_assign(base_reward.safe_mul(*weight)?)?;
+                    }
+                }
+
+                if validator_reward_numerator != 0 {
+                    rewards.insert(
+                        index as u64,
+                        validator_reward_numerator.safe_div(proposer_reward_denominator)?,
+                    );
+                }
+                proposer_reward_numerator.safe_add_assign(validator_reward_numerator)?;
+            }
+
+            per_attestation_rewards.push(rewards);
+            per_attestation_totals
+                .push(proposer_reward_numerator.safe_div(proposer_reward_denominator)?);
+        }
+
+        Ok((per_attestation_rewards, per_attestation_totals))
+    }
 }
@@ -0,0 +1,104 @@
+//! Limits the size of the blocks produced by this node to the maximum network size.
+//!
+//! The parts of a block which must be included (the header, deposits, sync aggregate and execution
+//! payload) are counted first. The remaining operations are then packed in order of value, each
+//! only if its SSZ encoding fits in the bytes that are left. An oversized op pool therefore results
+//! in a smaller block rather than a missed proposal.
+use ssz::{Encode, BYTES_PER_LENGTH_OFFSET};
+
+/// The number of bytes that `item` adds to the SSZ encoding of a list containing it.
+///
+/// The items of a list of variable-length items are each preceded by an offset.
+pub fn ssz_list_item_len<T: Encode>(item: &T) -> usize {
+    if <T as Encode>::is_ssz_fixed_len() {
+        item.ssz_bytes_len()
+    } else {
+        item.ssz_bytes_len() + BYTES_PER_LENGTH_OFFSET
+    }
+}
+
+/// The bytes remaining for operations in a block.
+pub struct BlockSizeBudget {
+    remaining: usize,
+    excluded: usize,
+}
+
+impl BlockSizeBudget {
+    /// Create a budget for a block of at most `max_size` bytes, of which `mandatory_size` bytes are
+    /// already used.
+    ///
+    /// Returns `None` if the mandatory parts of the block alone exceed `max_size`.
+    pub fn new(max_size: usize, mandatory_size: usize) -> Option<Self> {
+        Some(Self {
+            remaining: max_size.checked_sub(mandatory_size)?,
+            excluded: 0,
+        })
+    }
+
+    /// Returns the `items` which fit in the budget, taking them in order and skipping any which
+    /// don't fit. `items` should be ordered from the most valuable.
+    pub fn pack<T: Encode>(&mut self, items: Vec<T>) -> Vec<T> {
+        let num_items = items.len();
+        let packed = items
+            .into_iter()
+            .filter(|item| {
+                let len = ssz_list_item_len(item);
+                if len <= self.remaining {
+                    self.remaining -= len;
+                    true
+                } else {
+                    false
+                }
+            })
+            .collect::<Vec<_>>();
+        self.excluded += num_items - packed.len();
+        packed
+    }
+
+    /// The number of bytes which remain.
+    pub fn remaining(&self) -> usize {
+        self.remaining
+    }
+
+    /// The number of items which have been excluded from the block for lack of space.
+    pub fn excluded(&self) -> usize {
+        self.excluded
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn list_item_len() {
+        // Fixed-length items aren't preceded by an offset.
+        assert_eq!(ssz_list_item_len(&7_u64), 8);
+        // Variable-length items are.
+        assert_eq!(
+            ssz_list_item_len(&vec![1_u8, 2, 3]),
+            3 + BYTES_PER_LENGTH_OFFSET
+        );
+    }
+
+    #[test]
+    fn mandatory_parts_too_large() {
+        assert!(BlockSizeBudget::new(100, 101).is_none());
+        assert_eq!(BlockSizeBudget::new(100, 100).unwrap().remaining(), 0);
+    }
+
+    #[test]
+    fn packs_items_which_fit_in_order() {
+        let mut budget = BlockSizeBudget::new(100, 70).unwrap();
+
+        // The second item doesn't fit, but the smaller third item does.
+        let items = vec![vec![0_u8; 12], vec![1; 20], vec![2; 4], vec![3; 4]];
+        assert_eq!(budget.pack(items), vec![vec![0; 12], vec![2; 4]]);
+        assert_eq!(budget.remaining(), 6);
+        assert_eq!(budget.excluded(), 2);
+
+        assert_eq!(budget.pack(vec![9_u32, 10]), vec![9]);
+        assert_eq!(budget.remaining(), 2);
+        assert_eq!(budget.excluded(), 3);
+    }
+}
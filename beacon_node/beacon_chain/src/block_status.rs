@@ -0,0 +1,73 @@
+//! Reports whether an arbitrary block is canonical and whether it is finalized.
+//!
+//! Blocks which are known to fork choice are checked against the head and finalized checkpoint
+//! read under the same fork choice lock, so the answer is consistent even whilst the head changes.
+//! Blocks which fork choice has pruned are from before finalization, and are checked against the
+//! canonical block roots in the database. The pre-finalization cache also holds blocks which were
+//! rejected for conflicting with finalization, which are reported separately since they were never
+//! imported.
+use crate::{BeaconChain, BeaconChainError, BeaconChainTypes, ExecutionStatus, WhenSlotSkipped};
+use types::{Hash256, Slot};
+
+/// The status of a block with respect to the canonical chain.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BlockStatusReport {
+    Known {
+        slot: Slot,
+        /// Whether the block is an ancestor of (or is) the head.
+        canonical: bool,
+        /// Whether the block is an ancestor of (or is) the finalized block.
+        finalized: bool,
+        /// The status of the block's execution payload, or `None` if the block is from before
+        /// finalization and has been pruned from fork choice.
+        execution_status: Option<ExecutionStatus>,
+    },
+    /// The block was rejected for conflicting with finalization, and was never imported.
+    ConflictsWithFinalization { slot: Slot },
+    /// The block has never been imported, or was pruned after it conflicted with finalization.
+    Unknown,
+}
+
+impl<T: BeaconChainTypes> BeaconChain<T> {
+    /// Report whether the block with `block_root` is canonical and whether it is finalized.
+    pub fn block_status(&self, block_root: Hash256) -> Result<BlockStatusReport, BeaconChainError> {
+        let fork_choice = self.canonical_head.fork_choice_read_lock();
+        let view = fork_choice.cached_fork_choice_view();
+        let proto_array = fork_choice.proto_array();
+        if let Some(node) = proto_array.iter_nodes(&block_root).next() {
+            return Ok(BlockStatusReport::Known {
+                slot: node.slot,
+                canonical: proto_array.is_descendant(block_root, view.head_block_root),
+                finalized: proto_array.is_descendant(block_root, view.finalized_checkpoint.root),
+                execution_status: Some(node.execution_status),
+            });
+        }
+        drop(fork_choice);
+
+        // The block isn't known to fork choice, so if we've seen it then it is from before
+        // finalization. The pre-finalization cache saves loading it to learn its slot.
+        let slot = match self.pre_finalization_block_cache.block_slot(block_root) {
+            Some(slot) => slot,
+            None => match self.store.get_blinded_block(&block_root)? {
+                Some(block) => block.slot(),
+                None => return Ok(BlockStatusReport::Unknown),
+            },
+        };
+
+        // Every canonical block from before finalization is finalized.
+        let canonical = self.block_root_at_slot(slot, WhenSlotSkipped::None)? == Some(block_root);
+
+        // A block which is only known to the pre-finalization cache may have been rejected
+        // without ever being stored.
+        if !canonical && !self.store.block_exists(&block_root)? {
+            return Ok(BlockStatusReport::ConflictsWithFinalization { slot });
+        }
+
+        Ok(BlockStatusReport::Known {
+            slot,
+            canonical,
+            finalized: canonical,
+            execution_status: None,
+        })
+    }
+}
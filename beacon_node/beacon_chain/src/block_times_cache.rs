@@ -9,13 +9,13 @@
 //! - We were too slow to import it.
 //! - We were too slow to set it as head.
 
-use eth2::types::{Hash256, Slot};
-use std::collections::HashMap;
+use eth2::types::{BlockDeliveryTransport, Hash256, Slot};
+use std::collections::{HashMap, HashSet};
 use std::time::Duration;
 
 type BlockRoot = Hash256;
 
-#[derive(Clone, Default)]
+#[derive(Debug, Clone, Default)]
 pub struct Timestamps {
     pub observed: Option<Duration>,
     pub imported: Option<Duration>,
@@ -49,14 +49,26 @@ impl BlockDelays {
     }
 }
 
-// If the block was received via gossip, we can record the client type of the peer which sent us
-// the block.
-#[derive(Clone, Default)]
+// The source from which we first received the block. If the block was received from a peer we can
+// record its id, and if it was received via gossip the client type of the peer as well.
+#[derive(Debug, Clone, Default)]
 pub struct BlockPeerInfo {
     pub id: Option<String>,
     pub client: Option<String>,
+    pub transport: Option<BlockDeliveryTransport>,
+    /// The number of times the block was delivered after the first.
+    pub duplicate_deliveries: u64,
+    /// The ids of all the peers which delivered the block.
+    pub peers: HashSet<String>,
 }
 
+impl BlockPeerInfo {
+    pub fn distinct_peers(&self) -> usize {
+        self.peers.len()
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct BlockTimesCacheValue {
     pub slot: Slot,
     pub timestamps: Timestamps,
@@ -80,11 +92,18 @@ pub struct BlockTimesCache {
 
 /// Helper methods to read from and write to the cache.
 impl BlockTimesCache {
+    /// Record a delivery of the block over `transport`.
+    ///
+    /// The first delivery sets the observed time and the source of the block, later deliveries are
+    /// only counted as duplicates. Repeated deliveries from the same peer are ignored, since they
+    /// are the same delivery being retried (e.g., an RPC block requeued whilst the block is
+    /// imported from gossip).
     pub fn set_time_observed(
         &mut self,
         block_root: BlockRoot,
         slot: Slot,
         timestamp: Duration,
+        transport: BlockDeliveryTransport,
         peer_id: Option<String>,
         peer_client: Option<String>,
     ) {
@@ -92,11 +111,21 @@ impl BlockTimesCache {
             .cache
             .entry(block_root)
             .or_insert_with(|| BlockTimesCacheValue::new(slot));
+        let peer_info = &mut block_times.peer_info;
+        if let Some(peer_id) = &peer_id {
+            if !peer_info.peers.insert(peer_id.clone()) {
+                return;
+            }
+        }
+
+        if peer_info.transport.is_some() {
+            peer_info.duplicate_deliveries = peer_info.duplicate_deliveries.saturating_add(1);
+            return;
+        }
         block_times.timestamps.observed = Some(timestamp);
-        block_times.peer_info = BlockPeerInfo {
-            id: peer_id,
-            client: peer_client,
-        };
+        peer_info.id = peer_id;
+        peer_info.client = peer_client;
+        peer_info.transport = Some(transport);
     }
 
     pub fn set_time_imported(&mut self, block_root: BlockRoot, slot: Slot, timestamp: Duration) {
@@ -135,6 +164,10 @@ impl BlockTimesCache {
         }
     }
 
+    pub fn get(&self, block_root: BlockRoot) -> Option<&BlockTimesCacheValue> {
+        self.cache.get(&block_root)
+    }
+
     // Prune the cache to only store the most recent 2 epochs.
     pub fn prune(&mut self, current_slot: Slot) {
         self.cache
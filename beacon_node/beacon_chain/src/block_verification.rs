@@ -51,8 +51,7 @@ use crate::validator_monitor::HISTORIC_EPOCHS as VALIDATOR_MONITOR_HISTORIC_EPOC
 use crate::validator_pubkey_cache::ValidatorPubkeyCache;
 use crate::{
     beacon_chain::{
-        BeaconForkChoice, BLOCK_PROCESSING_CACHE_LOCK_TIMEOUT, MAXIMUM_GOSSIP_CLOCK_DISPARITY,
-        VALIDATOR_PUBKEY_CACHE_LOCK_TIMEOUT,
+        BeaconForkChoice, BLOCK_PROCESSING_CACHE_LOCK_TIMEOUT, VALIDATOR_PUBKEY_CACHE_LOCK_TIMEOUT,
     },
     metrics, BeaconChain, BeaconChainError, BeaconChainTypes,
 };
@@ -277,6 +276,49 @@ pub enum BlockError<T: EthSpec> {
     /// The peer sent us an invalid block, but I'm not really sure how to score this in an
     /// "optimistic" sync world.
     ParentExecutionPayloadInvalid { parent_root: Hash256 },
+    /// The block's parent is known to have an invalid execution payload, or to descend from a
+    /// block with one. Unlike `ParentExecutionPayloadInvalid`, this is detected before the block
+    /// is verified for gossip.
+    ///
+    /// ## Peer scoring
+    ///
+    /// The block is invalid and the peer is faulty.
+    ParentExecutionInvalid { parent_root: Hash256 },
+}
+
+impl<T: EthSpec> BlockError<T> {
+    /// Returns `true` if the error shows the block to be invalid, rather than the block being
+    /// unable to be verified at this time or some fault of our own.
+    pub fn is_invalid_block(&self) -> bool {
+        // This match statement should never have a default case so that we are always forced to
+        // consider here whether or not a new error condition shows the block to be invalid.
+        match self {
+            BlockError::ParentUnknown(_) => false,
+            BlockError::TooManySkippedSlots { .. } => false,
+            BlockError::FutureSlot { .. } => false,
+            BlockError::StateRootMismatch { .. } => true,
+            BlockError::GenesisBlock => true,
+            BlockError::WouldRevertFinalizedSlot { .. } => true,
+            BlockError::NotFinalizedDescendant { .. } => true,
+            BlockError::BlockIsAlreadyKnown => false,
+            BlockError::RepeatProposal { .. } => true,
+            BlockError::BlockSlotLimitReached => true,
+            BlockError::IncorrectBlockProposer { .. } => true,
+            BlockError::ProposalSignatureInvalid => true,
+            BlockError::UnknownValidator(_) => true,
+            BlockError::InvalidSignature => true,
+            BlockError::BlockIsNotLaterThanParent { .. } => true,
+            BlockError::NonLinearParentRoots => true,
+            BlockError::NonLinearSlots => true,
+            BlockError::PerBlockProcessingError(_) => true,
+            BlockError::BeaconChainError(_) => false,
+            BlockError::WeakSubjectivityConflict => true,
+            BlockError::InconsistentFork(_) => true,
+            BlockError::ExecutionPayloadError(e) => e.penalize_peer(),
+            BlockError::ParentExecutionPayloadInvalid { .. } => true,
+            BlockError::ParentExecutionInvalid { .. } => true,
+        }
+    }
 }
 
 /// Returned when block validation failed due to some issue verifying
@@ -662,7 +704,7 @@ impl<T: BeaconChainTypes> GossipVerifiedBlock<T> {
         // Do not gossip or process blocks from future slots.
         let present_slot_with_tolerance = chain
             .slot_clock
-            .now_with_future_tolerance(MAXIMUM_GOSSIP_CLOCK_DISPARITY)
+            .now_with_future_tolerance(chain.gossip_clock_disparity())
             .ok_or(BeaconChainError::UnableToReadSlot)?;
         if block.slot() > present_slot_with_tolerance {
             return Err(BlockError::FutureSlot {
@@ -673,6 +715,14 @@ impl<T: BeaconChainTypes> GossipVerifiedBlock<T> {
 
         let block_root = get_block_root(&block);
 
+        // Reject blocks which build upon an invalidated branch before doing any expensive work.
+        // It's impossible for a valid block to descend from an invalid one.
+        if chain.is_known_invalid_block(&block.parent_root()) {
+            return Err(BlockError::ParentExecutionInvalid {
+                parent_root: block.parent_root(),
+            });
+        }
+
         // Disallow blocks that conflict with the anchor (weak subjectivity checkpoint), if any.
         check_block_against_anchor_slot(block.message(), chain)?;
 
@@ -1424,7 +1474,7 @@ fn check_block_against_finalized_slot<T: BeaconChainTypes>(
         .start_slot(T::EthSpec::slots_per_epoch());
 
     if block.slot() <= finalized_slot {
-        chain.pre_finalization_block_rejected(block_root);
+        chain.pre_finalization_block_rejected(block_root, block.slot());
         Err(BlockError::WouldRevertFinalizedSlot {
             block_slot: block.slot(),
             finalized_slot,
@@ -1,15 +1,29 @@
+use crate::backfill_rate_limiter::BackfillRateLimiter;
 use crate::beacon_chain::{CanonicalHead, BEACON_CHAIN_DB_KEY, ETH1_CACHE_DB_KEY, OP_POOL_DB_KEY};
+use crate::builder_bid_history::{
+    BuilderBidHistory, PersistedBuilderBidHistory, BUILDER_BID_HISTORY_DB_KEY,
+};
+use crate::canonical_head::HEAD_CHANGE_CHANNEL_CAPACITY;
 use crate::eth1_chain::{CachingEth1Backend, SszEth1};
 use crate::fork_choice_signal::ForkChoiceSignalTx;
 use crate::fork_revert::{reset_fork_choice_to_finalization, revert_to_fork_boundary};
+use crate::graffiti::GraffitiMode;
 use crate::head_tracker::HeadTracker;
+use crate::historical_blocks::AnchorBlockRoots;
 use crate::migrate::{BackgroundMigrator, MigratorConfig};
 use crate::persisted_beacon_chain::PersistedBeaconChain;
+use crate::pre_finalization_cache::PreFinalizationBlockCache;
+use crate::proposal_history::{PersistedProposalHistory, ProposalHistory, PROPOSAL_HISTORY_DB_KEY};
 use crate::shuffling_cache::ShufflingCache;
+use crate::shutdown_report::{ShutdownReport, SHUTDOWN_REPORT_FILENAME};
 use crate::snapshot_cache::{SnapshotCache, DEFAULT_SNAPSHOT_CACHE_SIZE};
+use crate::startup_repair::rebuild_fork_choice;
 use crate::timeout_rw_lock::TimeoutRwLock;
 use crate::validator_monitor::ValidatorMonitor;
 use crate::validator_pubkey_cache::ValidatorPubkeyCache;
+use crate::validator_registrations::{
+    PersistedValidatorRegistrations, ValidatorRegistrations, VALIDATOR_REGISTRATIONS_DB_KEY,
+};
 use crate::ChainConfig;
 use crate::{
     BeaconChain, BeaconChainTypes, BeaconForkChoiceStore, BeaconSnapshot, Eth1Chain,
@@ -22,16 +36,19 @@ use futures::channel::mpsc::Sender;
 use operation_pool::{OperationPool, PersistedOperationPool};
 use parking_lot::RwLock;
 use slasher::Slasher;
-use slog::{crit, error, info, Logger};
+use slog::{crit, error, info, warn, Logger};
 use slot_clock::{SlotClock, TestingSlotClock};
 use std::marker::PhantomData;
+use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
 use std::time::Duration;
-use store::{Error as StoreError, HotColdDB, ItemStore, KeyValueStoreOp};
+use store::{Error as StoreError, HotColdDB, ItemStore, KeyValueStore, KeyValueStoreOp};
 use task_executor::{ShutdownReason, TaskExecutor};
+use tokio::sync::broadcast;
 use types::{
-    BeaconBlock, BeaconState, ChainSpec, Checkpoint, EthSpec, Graffiti, Hash256, PublicKeyBytes,
-    Signature, SignedBeaconBlock, Slot,
+    BeaconBlock, BeaconState, ChainSpec, Checkpoint, EthSpec, Hash256, PublicKeyBytes, Signature,
+    SignedBeaconBlock, Slot,
 };
 
 /// An empty struct used to "witness" all the `BeaconChainTypes` traits. It has no user-facing
@@ -81,12 +98,13 @@ pub struct BeaconChainBuilder<T: BeaconChainTypes> {
     event_handler: Option<ServerSentEventHandler<T::EthSpec>>,
     slot_clock: Option<T::SlotClock>,
     shutdown_sender: Option<Sender<ShutdownReason>>,
+    data_dir: Option<PathBuf>,
     head_tracker: Option<HeadTracker>,
     validator_pubkey_cache: Option<ValidatorPubkeyCache<T>>,
     spec: ChainSpec,
     chain_config: ChainConfig,
     log: Option<Logger>,
-    graffiti: Graffiti,
+    graffiti: GraffitiMode,
     slasher: Option<Arc<Slasher<T::EthSpec>>>,
     validator_monitor: Option<ValidatorMonitor<T::EthSpec>>,
     // Pending I/O batch that is constructed during building and should be executed atomically
@@ -122,12 +140,13 @@ where
             event_handler: None,
             slot_clock: None,
             shutdown_sender: None,
+            data_dir: None,
             head_tracker: None,
             validator_pubkey_cache: None,
             spec: TEthSpec::default_spec(),
             chain_config: ChainConfig::default(),
             log: None,
-            graffiti: Graffiti::default(),
+            graffiti: GraffitiMode::default(),
             slasher: None,
             validator_monitor: None,
             pending_io_batch: vec![],
@@ -204,6 +223,20 @@ where
             .map_err(|e| format!("DB error whilst reading eth1 cache: {:?}", e))
     }
 
+    /// Delete any eth1 cache persisted in the builder's `Store`, so that the eth1 backend starts
+    /// with empty caches.
+    pub fn purge_persisted_eth1_backend(&self) -> Result<(), String> {
+        let store = self
+            .store
+            .clone()
+            .ok_or("purge_persisted_eth1_backend requires a store.")?;
+
+        store
+            .hot_db
+            .delete::<SszEth1>(&ETH1_CACHE_DB_KEY)
+            .map_err(|e| format!("DB error whilst deleting eth1 cache: {:?}", e))
+    }
+
     /// Returns true if `self.store` contains a persisted beacon chain.
     pub fn store_contains_beacon_chain(&self) -> Result<bool, String> {
         let store = self
@@ -242,13 +275,39 @@ where
                     .to_string()
             })?;
 
-        let fork_choice =
+        let repair = self.chain_config.repair_on_startup;
+        if repair && self.chain_config.read_only {
+            return Err("The database cannot be repaired in read-only mode".into());
+        }
+        if repair {
+            warn!(
+                log,
+                "Repairing database on startup";
+                "info" => "fork choice and the validator pubkey cache will be rebuilt",
+            );
+        }
+
+        let mut head_tracker = HeadTracker::from_ssz_container(&chain.ssz_head_tracker)
+            .map_err(|e| format!("Failed to decode head tracker for database: {:?}", e))?;
+
+        let fork_choice = if repair {
+            let (fork_choice, repaired_head_tracker) = rebuild_fork_choice(
+                store.clone(),
+                chain.genesis_block_root,
+                &head_tracker,
+                &self.spec,
+                log,
+            )?;
+            head_tracker = repaired_head_tracker;
+            fork_choice
+        } else {
             BeaconChain::<Witness<TSlotClock, TEth1Backend, _, _, _>>::load_fork_choice(
                 store.clone(),
                 &self.spec,
             )
             .map_err(|e| format!("Unable to load fork choice from disk: {:?}", e))?
-            .ok_or("Fork choice not found in store")?;
+            .ok_or("Fork choice not found in store")?
+        };
 
         let genesis_block = store
             .get_blinded_block(&chain.genesis_block_root)
@@ -261,31 +320,47 @@ where
 
         self.genesis_time = Some(genesis_state.genesis_time());
 
-        self.op_pool = Some(
-            store
-                .get_item::<PersistedOperationPool<TEthSpec>>(&OP_POOL_DB_KEY)
-                .map_err(|e| format!("DB error whilst reading persisted op pool: {:?}", e))?
-                .map(PersistedOperationPool::into_operation_pool)
-                .transpose()
-                .map_err(|e| {
-                    format!(
-                        "Error while creating the op pool from the persisted op pool: {:?}",
-                        e
-                    )
-                })?
-                .unwrap_or_else(OperationPool::new),
-        );
-
-        let pubkey_cache = ValidatorPubkeyCache::load_from_store(store)
-            .map_err(|e| format!("Unable to open persisted pubkey cache: {:?}", e))?;
+        let op_pool = store
+            .get_item::<PersistedOperationPool<TEthSpec>>(&OP_POOL_DB_KEY)
+            .map_err(|e| format!("DB error whilst reading persisted op pool: {:?}", e))
+            .and_then(|persisted_op_pool| {
+                persisted_op_pool
+                    .map(PersistedOperationPool::into_operation_pool)
+                    .transpose()
+                    .map_err(|e| {
+                        format!(
+                            "Error while creating the op pool from the persisted op pool: {:?}",
+                            e
+                        )
+                    })
+            });
+        self.op_pool = Some(match op_pool {
+            Ok(op_pool) => op_pool.unwrap_or_else(OperationPool::new),
+            Err(e) if repair => {
+                warn!(
+                    log,
+                    "Clearing persisted op pool";
+                    "error" => e,
+                );
+                store
+                    .hot_db
+                    .delete::<PersistedOperationPool<TEthSpec>>(&OP_POOL_DB_KEY)
+                    .map_err(|e| format!("Unable to clear persisted op pool: {:?}", e))?;
+                OperationPool::new()
+            }
+            Err(e) => return Err(e),
+        });
+
+        // When repairing, the pubkey cache is rebuilt from the head state in `build`.
+        if !repair {
+            let pubkey_cache = ValidatorPubkeyCache::load_from_store(store)
+                .map_err(|e| format!("Unable to open persisted pubkey cache: {:?}", e))?;
+            self.validator_pubkey_cache = Some(pubkey_cache);
+        }
 
         self.genesis_block_root = Some(chain.genesis_block_root);
         self.genesis_state_root = Some(genesis_block.state_root());
-        self.head_tracker = Some(
-            HeadTracker::from_ssz_container(&chain.ssz_head_tracker)
-                .map_err(|e| format!("Failed to decode head tracker for database: {:?}", e))?,
-        );
-        self.validator_pubkey_cache = Some(pubkey_cache);
+        self.head_tracker = Some(head_tracker);
         self.fork_choice = Some(fork_choice);
 
         Ok(self)
@@ -305,6 +380,10 @@ where
             .clone()
             .ok_or("set_genesis_state requires a store")?;
 
+        if self.chain_config.read_only {
+            return Err("A new database cannot be initialized in read-only mode".into());
+        }
+
         let beacon_block = genesis_block(&mut beacon_state, &self.spec)?;
 
         beacon_state
@@ -379,6 +458,10 @@ where
     ) -> Result<Self, String> {
         let store = self.store.clone().ok_or("genesis_state requires a store")?;
 
+        if self.chain_config.read_only {
+            return Err("A new database cannot be initialized in read-only mode".into());
+        }
+
         let weak_subj_slot = weak_subj_state.slot();
         let weak_subj_block_root = weak_subj_block.canonical_root();
         let weak_subj_state_root = weak_subj_block.state_root();
@@ -522,6 +605,13 @@ where
         self
     }
 
+    /// Sets the data directory, in which a report is written if the beacon chain shuts down the
+    /// node.
+    pub fn data_dir(mut self, data_dir: PathBuf) -> Self {
+        self.data_dir = Some(data_dir);
+        self
+    }
+
     /// Creates a new, empty operation pool.
     fn empty_op_pool(mut self) -> Self {
         self.op_pool = Some(OperationPool::new());
@@ -529,7 +619,7 @@ where
     }
 
     /// Sets the `graffiti` field.
-    pub fn graffiti(mut self, graffiti: Graffiti) -> Self {
+    pub fn graffiti(mut self, graffiti: GraffitiMode) -> Self {
         self.graffiti = graffiti;
         self
     }
@@ -589,6 +679,31 @@ where
             .ok_or("Cannot build without a validator monitor")?;
         let head_tracker = Arc::new(self.head_tracker.unwrap_or_default());
 
+        let shutdown_report_path = self
+            .data_dir
+            .as_ref()
+            .map(|data_dir| data_dir.join(SHUTDOWN_REPORT_FILENAME));
+        if let Some(path) = &shutdown_report_path {
+            match ShutdownReport::load(path) {
+                Ok(Some(report)) => {
+                    report.log_previous(&log);
+                    if let Err(e) = ShutdownReport::archive(path) {
+                        warn!(
+                            log,
+                            "Unable to archive previous shutdown report";
+                            "error" => e,
+                        );
+                    }
+                }
+                Ok(None) => (),
+                Err(e) => warn!(
+                    log,
+                    "Unable to read previous shutdown report";
+                    "error" => e,
+                ),
+            }
+        }
+
         let current_slot = if slot_clock
             .is_prior_to_genesis()
             .ok_or("Unable to read slot clock")?
@@ -675,10 +790,24 @@ where
             ));
         }
 
-        let validator_pubkey_cache = self.validator_pubkey_cache.map(Ok).unwrap_or_else(|| {
-            ValidatorPubkeyCache::new(&head_snapshot.beacon_state, store.clone())
-                .map_err(|e| format!("Unable to init validator pubkey cache: {:?}", e))
-        })?;
+        let validator_pubkey_cache = match self.validator_pubkey_cache {
+            Some(validator_pubkey_cache) => validator_pubkey_cache,
+            None if self.chain_config.repair_on_startup => {
+                let validator_pubkey_cache =
+                    ValidatorPubkeyCache::rebuild(&head_snapshot.beacon_state, store.clone())
+                        .map_err(|e| {
+                            format!("Unable to rebuild validator pubkey cache: {:?}", e)
+                        })?;
+                info!(
+                    log,
+                    "Rebuilt validator pubkey cache";
+                    "validators" => validator_pubkey_cache.len(),
+                );
+                validator_pubkey_cache
+            }
+            None => ValidatorPubkeyCache::new(&head_snapshot.beacon_state, store.clone())
+                .map_err(|e| format!("Unable to init validator pubkey cache: {:?}", e))?,
+        };
 
         let migrator_config = self.store_migrator_config.unwrap_or_default();
         let store_migrator = BackgroundMigrator::new(
@@ -688,6 +817,53 @@ where
             log.clone(),
         );
 
+        // The proposal history is purely informational, so start afresh if it can't be loaded.
+        let proposal_history = store
+            .get_item::<PersistedProposalHistory>(&PROPOSAL_HISTORY_DB_KEY)
+            .map_err(|e| format!("{:?}", e))
+            .and_then(|persisted| persisted.map(ProposalHistory::try_from).transpose())
+            .unwrap_or_else(|e| {
+                warn!(
+                    log,
+                    "Unable to load proposal history";
+                    "error" => e,
+                );
+                None
+            })
+            .unwrap_or_default();
+        let builder_bid_history = store
+            .get_item::<PersistedBuilderBidHistory>(&BUILDER_BID_HISTORY_DB_KEY)
+            .map_err(|e| format!("{:?}", e))
+            .and_then(|persisted| persisted.map(BuilderBidHistory::try_from).transpose())
+            .unwrap_or_else(|e| {
+                warn!(
+                    log,
+                    "Unable to load builder bid history";
+                    "error" => e,
+                );
+                None
+            })
+            .unwrap_or_default();
+        let validator_registrations = store
+            .get_item::<PersistedValidatorRegistrations>(&VALIDATOR_REGISTRATIONS_DB_KEY)
+            .unwrap_or_else(|e| {
+                warn!(
+                    log,
+                    "Unable to load validator registrations";
+                    "error" => ?e,
+                );
+                None
+            })
+            .map(|persisted| {
+                ValidatorRegistrations::from_persisted(
+                    persisted,
+                    current_slot.epoch(TEthSpec::slots_per_epoch()),
+                    |pubkey| validator_pubkey_cache.get_index(pubkey),
+                )
+            })
+            .unwrap_or_default();
+        let proposer_preparation_data = validator_registrations.proposer_preparation_data();
+
         if let Some(slot) = slot_clock.now() {
             validator_monitor.process_valid_state(
                 slot.epoch(TEthSpec::slots_per_epoch()),
@@ -710,25 +886,39 @@ where
         //
         // This *must* be stored before constructing the `BeaconChain`, so that its `Drop` instance
         // doesn't write a `PersistedBeaconChain` without the rest of the batch.
-        self.pending_io_batch.push(BeaconChain::<
-            Witness<TSlotClock, TEth1Backend, TEthSpec, THotStore, TColdStore>,
-        >::persist_head_in_batch_standalone(
-            genesis_block_root, &head_tracker
-        ));
-        self.pending_io_batch.push(BeaconChain::<
-            Witness<TSlotClock, TEth1Backend, TEthSpec, THotStore, TColdStore>,
-        >::persist_fork_choice_in_batch_standalone(
-            &fork_choice
-        ));
-        store
-            .hot_db
-            .do_atomically(self.pending_io_batch)
-            .map_err(|e| format!("Error writing chain & metadata to disk: {:?}", e))?;
+        //
+        // A read-only chain is always resumed from an existing database, which already contains
+        // them.
+        if !self.chain_config.read_only {
+            self.pending_io_batch.push(BeaconChain::<
+                Witness<TSlotClock, TEth1Backend, TEthSpec, THotStore, TColdStore>,
+            >::persist_head_in_batch_standalone(
+                genesis_block_root, &head_tracker
+            ));
+            self.pending_io_batch.push(BeaconChain::<
+                Witness<TSlotClock, TEth1Backend, TEthSpec, THotStore, TColdStore>,
+            >::persist_fork_choice_in_batch_standalone(
+                &fork_choice
+            ));
+            store
+                .hot_db
+                .do_atomically(self.pending_io_batch)
+                .map_err(|e| format!("Error writing chain & metadata to disk: {:?}", e))?;
+        }
 
         let genesis_validators_root = head_snapshot.beacon_state.genesis_validators_root();
         let genesis_time = head_snapshot.beacon_state.genesis_time();
         let head_for_snapshot_cache = head_snapshot.clone();
-        let canonical_head = CanonicalHead::new(fork_choice, Arc::new(head_snapshot));
+        // When starting from a checkpoint the head state is the anchor state. After a restart it is
+        // a descendant of the anchor, which is just as useful for the slots it covers.
+        let anchor_block_roots = store
+            .get_anchor_info()
+            .filter(|anchor| !anchor.block_backfill_complete())
+            .map(|_| AnchorBlockRoots::from_state(&head_snapshot.beacon_state));
+        let canonical_head = CanonicalHead::new(fork_choice, Arc::new(head_snapshot), &log);
+        let pre_finalization_block_cache =
+            PreFinalizationBlockCache::new(self.chain_config.pre_finalization_block_cache_size);
+        let backfill_rate_limit = self.chain_config.backfill_rate_limit;
 
         let beacon_chain = BeaconChain {
             spec: self.spec,
@@ -769,6 +959,7 @@ where
             genesis_validators_root,
             genesis_time,
             canonical_head,
+            head_change_tx: broadcast::channel(HEAD_CHANGE_CHANNEL_CAPACITY).0,
             genesis_block_root,
             genesis_state_root,
             fork_choice_signal_tx,
@@ -780,19 +971,47 @@ where
                 head_for_snapshot_cache,
             )),
             shuffling_cache: TimeoutRwLock::new(ShufflingCache::new()),
+            committee_memo: <_>::default(),
+            indexed_attestation_cache: <_>::default(),
+            checkpoint_state_cache: <_>::default(),
             beacon_proposer_cache: <_>::default(),
             block_times_cache: <_>::default(),
-            pre_finalization_block_cache: <_>::default(),
+            pre_finalization_block_cache,
+            invalid_block_cache: <_>::default(),
+            verification_pressure: <_>::default(),
+            last_cache_prewarm: <_>::default(),
+            fork_choice_run_history: <_>::default(),
+            inclusion_delay_summaries: <_>::default(),
+            pending_operations: <_>::default(),
+            orphan_block_tracker: <_>::default(),
+            payload_reconstruction_failures: <_>::default(),
+            state_advance_locks: <_>::default(),
+            backfill_rate_limiter: BackfillRateLimiter::new(backfill_rate_limit),
+            clock_monitor: <_>::default(),
+            finality_watchdog: <_>::default(),
+            failure_log_limiter: <_>::default(),
+            self_test_report: <_>::default(),
+            partial_state_skip: <_>::default(),
+            exit_queue_summary_cache: <_>::default(),
+            anchor_block_roots: RwLock::new(anchor_block_roots),
             validator_pubkey_cache: TimeoutRwLock::new(validator_pubkey_cache),
             attester_cache: <_>::default(),
             early_attester_cache: <_>::default(),
             shutdown_sender: self
                 .shutdown_sender
                 .ok_or("Cannot build without a shutdown sender.")?,
+            shutdown_target_reached: AtomicBool::new(false),
+            shutdown_report_path,
+            shutdown_report_written: AtomicBool::new(false),
             log: log.clone(),
             graffiti: self.graffiti,
             slasher: self.slasher.clone(),
             validator_monitor: RwLock::new(validator_monitor),
+            validator_registrations: RwLock::new(validator_registrations),
+            validator_registry_diffs: <_>::default(),
+            proposal_history: RwLock::new(proposal_history),
+            builder_bid_history: RwLock::new(builder_bid_history),
+            last_block_production_info: <_>::default(),
         };
 
         let head = beacon_chain.head_snapshot();
@@ -836,6 +1055,48 @@ where
             "head_slot" => format!("{}", head.beacon_block.slot()),
         );
 
+        if let Err(e) = beacon_chain.register_initial_events() {
+            warn!(
+                log,
+                "Unable to register initial server-sent events";
+                "error" => ?e,
+            );
+        }
+
+        // Seed the execution layer with the fee recipients of the registered validators, so that
+        // they're used for proposals prior to the validator clients re-sending their preparations.
+        if let Some(execution_layer) = beacon_chain.execution_layer.clone() {
+            if !proposer_preparation_data.is_empty() {
+                let current_epoch = current_slot.epoch(TEthSpec::slots_per_epoch());
+                beacon_chain.task_executor.spawn(
+                    async move {
+                        execution_layer
+                            .update_proposer_preparation(current_epoch, &proposer_preparation_data)
+                            .await
+                    },
+                    "seed_proposer_preparation",
+                );
+            }
+        }
+
+        if beacon_chain.config.allow_unchecked_gossip_blocks {
+            crit!(
+                log,
+                "Blocks may be imported without gossip checks";
+                "info" => "this is intended only for testing tools, never enable it on a node \
+                           following a live network",
+            );
+        }
+
+        // A crash may have left the views of the head inconsistent. Fork choice and the snapshot
+        // were loaded together above, so only the head tracker can be healed here.
+        if !beacon_chain.check_head_consistency().is_consistent()
+            && beacon_chain.config.heal_head_divergence
+            && beacon_chain.heal_head_tracker()
+        {
+            beacon_chain.check_head_consistency();
+        }
+
         // Check for states to reconstruct (in the background).
         if beacon_chain.config.reconstruct_historic_states {
             beacon_chain.store_migrator.process_reconstruction();
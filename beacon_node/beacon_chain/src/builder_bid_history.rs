@@ -0,0 +1,262 @@
+//! Retains the signed bids of the builders whose payload headers were used for block production.
+//!
+//! Once a blinded block has been signed, only the builder is able to reveal its payload. If the
+//! builder withholds the payload, its signed bid is the evidence of the payload that it committed
+//! to. Bids are retained for `ChainConfig::builder_bid_retention_epochs`, and are persisted to disk
+//! alongside the proposal history.
+use crate::{BeaconChain, BeaconChainError, BeaconChainTypes};
+use serde_derive::{Deserialize, Serialize};
+use ssz::{Decode, Encode};
+use ssz_derive::{Decode, Encode};
+use std::collections::BTreeMap;
+use store::{DBColumn, Error as StoreError, StoreItem};
+use types::builder_bid::BuilderBidEvidence;
+use types::{EthSpec, Hash256, Slot};
+
+/// 32-byte key for accessing the `BuilderBidHistory`. All zero because it has its own column.
+pub const BUILDER_BID_HISTORY_DB_KEY: Hash256 = Hash256::zero();
+
+/// The bid of the builder whose payload header was used for a block produced by this node.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BuilderBidRecord {
+    pub slot: Slot,
+    /// The root of the blinded block which was produced with the builder's payload header.
+    pub block_root: Hash256,
+    /// The (redacted) URL of the builder.
+    pub relay: String,
+    pub bid: BuilderBidEvidence,
+}
+
+/// The `BuilderBidRecord`s of recently produced blocks, by slot.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct BuilderBidHistory {
+    bids: BTreeMap<Slot, Vec<BuilderBidRecord>>,
+}
+
+impl BuilderBidHistory {
+    /// Add `record` to the history, unless there is already a record for its block.
+    pub fn insert(&mut self, record: BuilderBidRecord) {
+        let records = self.bids.entry(record.slot).or_default();
+        if records
+            .iter()
+            .all(|existing| existing.block_root != record.block_root)
+        {
+            records.push(record);
+        }
+    }
+
+    /// Returns the records of the blocks produced at `slot`.
+    pub fn get(&self, slot: Slot) -> &[BuilderBidRecord] {
+        self.bids.get(&slot).map_or(&[], Vec::as_slice)
+    }
+
+    /// Returns the record of the block with `block_root`, produced at `slot`.
+    pub fn get_by_block_root(&self, slot: Slot, block_root: Hash256) -> Option<&BuilderBidRecord> {
+        self.get(slot)
+            .iter()
+            .find(|record| record.block_root == block_root)
+    }
+
+    /// Drop the records of all blocks produced prior to `oldest_slot`.
+    pub fn prune(&mut self, oldest_slot: Slot) {
+        self.bids = self.bids.split_off(&oldest_slot);
+    }
+
+    /// Returns all records, ordered by slot.
+    pub fn iter(&self) -> impl Iterator<Item = &BuilderBidRecord> {
+        self.bids.values().flatten()
+    }
+
+    pub fn len(&self) -> usize {
+        self.bids.values().map(Vec::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bids.is_empty()
+    }
+}
+
+impl<T: BeaconChainTypes> BeaconChain<T> {
+    /// Returns the bids of the builders whose payload headers were used for the blocks produced at
+    /// `slot`.
+    pub fn builder_bids_at_slot(&self, slot: Slot) -> Vec<BuilderBidRecord> {
+        self.builder_bid_history.read().get(slot).to_vec()
+    }
+
+    /// Returns all retained builder bids, ordered by slot.
+    pub fn builder_bids(&self) -> Vec<BuilderBidRecord> {
+        self.builder_bid_history.read().iter().cloned().collect()
+    }
+
+    /// Record the bid of the builder whose payload header was used to produce a block.
+    pub(crate) fn record_builder_bid(&self, record: BuilderBidRecord) {
+        let oldest_slot = self.oldest_retained_builder_bid_slot(record.slot);
+        let mut history = self.builder_bid_history.write();
+        history.insert(record);
+        history.prune(oldest_slot);
+    }
+
+    /// Drop the bids which are older than `ChainConfig::builder_bid_retention_epochs` as of
+    /// `current_slot`.
+    pub(crate) fn prune_builder_bids(&self, current_slot: Slot) {
+        let oldest_slot = self.oldest_retained_builder_bid_slot(current_slot);
+        self.builder_bid_history.write().prune(oldest_slot);
+    }
+
+    fn oldest_retained_builder_bid_slot(&self, current_slot: Slot) -> Slot {
+        current_slot.saturating_sub(
+            self.config
+                .builder_bid_retention_epochs
+                .saturating_mul(T::EthSpec::slots_per_epoch()),
+        )
+    }
+
+    /// Persists the builder bid history to disk.
+    pub fn persist_builder_bid_history(&self) -> Result<(), BeaconChainError> {
+        if self.config.read_only {
+            return Ok(());
+        }
+
+        let persisted = PersistedBuilderBidHistory::from(&*self.builder_bid_history.read());
+        self.store
+            .put_item(&BUILDER_BID_HISTORY_DB_KEY, &persisted)
+            .map_err(Into::into)
+    }
+}
+
+/// SSZ-encodable form of a `BuilderBidRecord`.
+#[derive(Encode, Decode)]
+struct SszBuilderBidRecord {
+    slot: Slot,
+    block_root: Hash256,
+    relay: Vec<u8>,
+    bid: BuilderBidEvidence,
+}
+
+impl From<&BuilderBidRecord> for SszBuilderBidRecord {
+    fn from(record: &BuilderBidRecord) -> Self {
+        Self {
+            slot: record.slot,
+            block_root: record.block_root,
+            relay: record.relay.as_bytes().to_vec(),
+            bid: record.bid.clone(),
+        }
+    }
+}
+
+impl TryFrom<SszBuilderBidRecord> for BuilderBidRecord {
+    type Error = String;
+
+    fn try_from(record: SszBuilderBidRecord) -> Result<Self, String> {
+        Ok(Self {
+            slot: record.slot,
+            block_root: record.block_root,
+            relay: String::from_utf8(record.relay).map_err(|e| format!("invalid relay: {}", e))?,
+            bid: record.bid,
+        })
+    }
+}
+
+/// Wrapper around the `BuilderBidHistory` for persistence to disk.
+#[derive(Encode, Decode)]
+pub struct PersistedBuilderBidHistory {
+    records: Vec<SszBuilderBidRecord>,
+}
+
+impl From<&BuilderBidHistory> for PersistedBuilderBidHistory {
+    fn from(history: &BuilderBidHistory) -> Self {
+        Self {
+            records: history.iter().map(SszBuilderBidRecord::from).collect(),
+        }
+    }
+}
+
+impl TryFrom<PersistedBuilderBidHistory> for BuilderBidHistory {
+    type Error = String;
+
+    fn try_from(persisted: PersistedBuilderBidHistory) -> Result<Self, String> {
+        let mut history = BuilderBidHistory::default();
+        for record in persisted.records {
+            history.insert(record.try_into()?);
+        }
+        Ok(history)
+    }
+}
+
+impl StoreItem for PersistedBuilderBidHistory {
+    fn db_column() -> DBColumn {
+        DBColumn::BuilderBidHistory
+    }
+
+    fn as_store_bytes(&self) -> Vec<u8> {
+        self.as_ssz_bytes()
+    }
+
+    fn from_store_bytes(bytes: &[u8]) -> Result<Self, StoreError> {
+        Self::from_ssz_bytes(bytes).map_err(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::{ExecutionBlockHash, PublicKeyBytes, Signature, Uint256};
+
+    fn record(slot: u64, root: u64) -> BuilderBidRecord {
+        BuilderBidRecord {
+            slot: Slot::new(slot),
+            block_root: Hash256::from_low_u64_be(root),
+            relay: "http://relay.example.com/".into(),
+            bid: BuilderBidEvidence {
+                header_root: Hash256::from_low_u64_be(root),
+                block_hash: ExecutionBlockHash::zero(),
+                value: Uint256::from(1_000_000_000_u64),
+                pubkey: PublicKeyBytes::empty(),
+                signature: Signature::empty(),
+                time_to_first_byte_ms: 0,
+                deserialization_ms: 0,
+            },
+        }
+    }
+
+    #[test]
+    fn insert_and_prune() {
+        let mut history = BuilderBidHistory::default();
+        history.insert(record(1, 1));
+        history.insert(record(2, 2));
+        history.insert(record(2, 3));
+        // Duplicates are ignored.
+        history.insert(record(2, 3));
+        assert_eq!(history.len(), 3);
+        assert_eq!(history.get(Slot::new(2)), &[record(2, 2), record(2, 3)]);
+        assert_eq!(
+            history.get_by_block_root(Slot::new(2), Hash256::from_low_u64_be(3)),
+            Some(&record(2, 3))
+        );
+        assert_eq!(
+            history.get_by_block_root(Slot::new(1), Hash256::from_low_u64_be(3)),
+            None
+        );
+
+        history.prune(Slot::new(2));
+        assert!(history.get(Slot::new(1)).is_empty());
+        assert_eq!(history.len(), 2);
+
+        history.prune(Slot::new(3));
+        assert!(history.is_empty());
+    }
+
+    #[test]
+    fn ssz_round_trip() {
+        let mut history = BuilderBidHistory::default();
+        history.insert(record(1, 1));
+        history.insert(record(2, 2));
+
+        let bytes = PersistedBuilderBidHistory::from(&history).as_store_bytes();
+        let decoded = BuilderBidHistory::try_from(
+            PersistedBuilderBidHistory::from_store_bytes(&bytes).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(decoded, history);
+    }
+}
@@ -0,0 +1,70 @@
+//! Allows the builders (or relays) used for block production to be changed without a restart.
+//!
+//! A builder added at runtime is immediately sent the latest registration of each validator, so
+//! that it can bid for their proposals without waiting for the validator clients to re-register.
+use crate::{BeaconChain, BeaconChainError as Error, BeaconChainTypes};
+use execution_layer::{BuilderConfig, BuilderHttpClient, BuilderInfo};
+use sensitive_url::SensitiveUrl;
+use slog::{debug, warn};
+
+impl<T: BeaconChainTypes> BeaconChain<T> {
+    /// Connect to the builder described by `config`, which is queried from the next proposal.
+    ///
+    /// Failing to forward the stored validator registrations to the builder is logged but is not
+    /// an error, since validator clients periodically re-send their registrations.
+    pub async fn add_builder(&self, config: BuilderConfig) -> Result<(), Error> {
+        let execution_layer = self
+            .execution_layer
+            .as_ref()
+            .ok_or(Error::ExecutionLayerMissing)?;
+        config.validate().map_err(Error::InvalidBuilderConfig)?;
+        let builder = BuilderHttpClient::from_config(&config)
+            .map_err(|e| Error::InvalidBuilderConfig(format!("{:?}", e)))?;
+        execution_layer
+            .add_builder(builder.clone())
+            .map_err(Error::AddBuilderFailed)?;
+
+        let registrations = self.validator_registrations.read().signed_registrations();
+        if registrations.is_empty() {
+            return Ok(());
+        }
+        match execution_layer
+            .register_validators_with_builder(&builder, &registrations)
+            .await
+        {
+            Ok(()) => debug!(
+                self.log,
+                "Sent registrations to new builder";
+                "count" => registrations.len(),
+                "url" => %builder.server(),
+            ),
+            Err(e) => warn!(
+                self.log,
+                "Failed to send registrations to new builder";
+                "error" => ?e,
+                "count" => registrations.len(),
+                "url" => %builder.server(),
+            ),
+        }
+        Ok(())
+    }
+
+    /// Disconnect from the builder at `url`, returning `false` if it was not connected.
+    ///
+    /// A block using a payload offered by the builder can still be unblinded.
+    pub fn remove_builder(&self, url: &SensitiveUrl) -> Result<bool, Error> {
+        let execution_layer = self
+            .execution_layer
+            .as_ref()
+            .ok_or(Error::ExecutionLayerMissing)?;
+        Ok(execution_layer.remove_builder(url))
+    }
+
+    /// Returns the connected builders.
+    pub fn list_builders(&self) -> Vec<BuilderInfo> {
+        self.execution_layer
+            .as_ref()
+            .map(|execution_layer| execution_layer.list_builders())
+            .unwrap_or_default()
+    }
+}
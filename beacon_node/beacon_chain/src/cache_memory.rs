@@ -0,0 +1,166 @@
+//! Estimates the memory used by the chain's caches and keeps it within
+//! `ChainConfig::cache_memory_budget_mb`.
+//!
+//! Each cache is sized independently, so without a global view their total may grow well beyond
+//! what the operator expects. The coordinator runs each slot: it estimates the memory of each
+//! cache, exports the estimates as metrics and, if the total exceeds the budget, evicts from each
+//! cache in proportion to its share of the total.
+//!
+//! The snapshot of the canonical head is exempt from the budget. The snapshot of the head in the
+//! snapshot cache and the item of the early attester cache count towards the budget but are never
+//! evicted, so the budget can't be met if they alone exceed it.
+//!
+//! The memory of a state is estimated from its SSZ size and committee caches, ignoring its other
+//! caches, so the estimates are a lower bound.
+use crate::beacon_chain::{ATTESTATION_CACHE_LOCK_TIMEOUT, BLOCK_PROCESSING_CACHE_LOCK_TIMEOUT};
+use crate::{metrics, BeaconChain, BeaconChainError as Error, BeaconChainTypes};
+use serde_derive::{Deserialize, Serialize};
+use slog::debug;
+use ssz::Encode;
+use types::{BeaconState, EthSpec};
+
+/// Returns an estimate of the memory used by `state`, in bytes.
+pub fn state_memory_usage<E: EthSpec>(state: &BeaconState<E>) -> usize {
+    state.ssz_bytes_len()
+        + state
+            .committee_caches()
+            .iter()
+            .map(|committee_cache| committee_cache.memory_usage())
+            .sum::<usize>()
+}
+
+/// The estimated memory used by each of the chain's caches, in bytes.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CacheMemoryUsage {
+    pub shuffling_cache: usize,
+    pub attester_cache: usize,
+    pub early_attester_cache: usize,
+    pub snapshot_cache: usize,
+    /// The part of `snapshot_cache` used by the snapshot of the head, which is never evicted.
+    pub snapshot_cache_head: usize,
+    pub checkpoint_state_cache: usize,
+    /// The snapshot of the canonical head, which is exempt from the budget.
+    pub head: usize,
+}
+
+impl CacheMemoryUsage {
+    /// The total memory which counts towards the budget.
+    pub fn total(&self) -> usize {
+        self.shuffling_cache
+            + self.attester_cache
+            + self.early_attester_cache
+            + self.snapshot_cache
+            + self.checkpoint_state_cache
+    }
+
+    /// The memory which counts towards the budget but is never evicted.
+    pub fn pinned(&self) -> usize {
+        self.early_attester_cache + self.snapshot_cache_head
+    }
+
+    fn set_metrics(&self) {
+        for (cache, bytes) in [
+            ("shuffling_cache", self.shuffling_cache),
+            ("attester_cache", self.attester_cache),
+            ("early_attester_cache", self.early_attester_cache),
+            ("snapshot_cache", self.snapshot_cache),
+            ("checkpoint_state_cache", self.checkpoint_state_cache),
+            ("head", self.head),
+        ] {
+            metrics::set_gauge_vec(&metrics::CACHE_MEMORY_USAGE, &[cache], bytes as i64);
+        }
+        metrics::set_gauge(&metrics::CACHE_MEMORY_TOTAL, self.total() as i64);
+    }
+}
+
+impl<T: BeaconChainTypes> BeaconChain<T> {
+    /// Returns an estimate of the memory used by each of the chain's caches.
+    pub fn cache_memory_usage(&self) -> Result<CacheMemoryUsage, Error> {
+        let shuffling_cache = self
+            .shuffling_cache
+            .try_read_for(ATTESTATION_CACHE_LOCK_TIMEOUT)
+            .ok_or(Error::AttestationCacheLockTimeout)?
+            .memory_usage();
+        let (snapshot_cache, snapshot_cache_head) = {
+            let snapshot_cache = self
+                .snapshot_cache
+                .try_read_for(BLOCK_PROCESSING_CACHE_LOCK_TIMEOUT)
+                .ok_or(Error::SnapshotCacheLockTimeout)?;
+            (
+                snapshot_cache.memory_usage(),
+                snapshot_cache.head_memory_usage(),
+            )
+        };
+        let head = {
+            let head = self.head_snapshot();
+            head.beacon_block.ssz_bytes_len() + state_memory_usage(&head.beacon_state)
+        };
+
+        Ok(CacheMemoryUsage {
+            shuffling_cache,
+            attester_cache: self.attester_cache.memory_usage(),
+            early_attester_cache: self.early_attester_cache.memory_usage(),
+            snapshot_cache,
+            snapshot_cache_head,
+            checkpoint_state_cache: self.checkpoint_state_cache.memory_usage(),
+            head,
+        })
+    }
+
+    /// Estimate the memory used by the caches and, if it exceeds the budget, evict from each cache
+    /// in proportion to its usage. Returns the usage after any eviction.
+    pub fn enforce_cache_memory_budget(&self) -> Result<CacheMemoryUsage, Error> {
+        let mut usage = self.cache_memory_usage()?;
+
+        if let Some(budget) = self
+            .config
+            .cache_memory_budget_mb
+            .map(|mb| mb as usize * 1_048_576)
+        {
+            if usage.total() > budget {
+                metrics::inc_counter(&metrics::CACHE_MEMORY_BUDGET_EXCEEDED);
+                self.shrink_caches(&usage, budget)?;
+
+                let total_before = usage.total();
+                usage = self.cache_memory_usage()?;
+                debug!(
+                    self.log,
+                    "Evicted from caches to meet memory budget";
+                    "budget" => budget,
+                    "before" => total_before,
+                    "after" => usage.total(),
+                );
+            }
+        }
+
+        usage.set_metrics();
+        Ok(usage)
+    }
+
+    /// Shrink each cache such that its evictable memory is at most the same fraction of `usage`,
+    /// bringing the total within `budget` unless the pinned memory alone exceeds it.
+    fn shrink_caches(&self, usage: &CacheMemoryUsage, budget: usize) -> Result<(), Error> {
+        let evictable = usage.total().saturating_sub(usage.pinned());
+        if evictable == 0 {
+            return Ok(());
+        }
+        let allowance = budget.saturating_sub(usage.pinned());
+        let share = |bytes: usize| (bytes as u128 * allowance as u128 / evictable as u128) as usize;
+
+        self.shuffling_cache
+            .try_write_for(ATTESTATION_CACHE_LOCK_TIMEOUT)
+            .ok_or(Error::AttestationCacheLockTimeout)?
+            .shrink_to(share(usage.shuffling_cache));
+        self.attester_cache.shrink_to(share(usage.attester_cache));
+        self.snapshot_cache
+            .try_write_for(BLOCK_PROCESSING_CACHE_LOCK_TIMEOUT)
+            .ok_or(Error::SnapshotCacheLockTimeout)?
+            .shrink_to(
+                usage.snapshot_cache_head + share(usage.snapshot_cache - usage.snapshot_cache_head),
+            );
+        self.checkpoint_state_cache
+            .shrink_to(share(usage.checkpoint_state_cache));
+
+        Ok(())
+    }
+}
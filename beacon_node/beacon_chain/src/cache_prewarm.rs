@@ -0,0 +1,117 @@
+//! Pre-warms the committee caches whilst slots are being skipped.
+//!
+//! The state advance timer only advances the head state a single slot, so after a long run of
+//! skipped slots the first attestations and block of the new epoch each miss the caches, loading
+//! and advancing a state from the database just when latency matters most. Instead, whilst the head
+//! lags the wall-clock slot by more than an epoch, a copy of the head state is advanced to the
+//! current epoch and used to populate the shuffling, attester and proposer caches ahead of demand.
+//!
+//! Each entry is keyed by the shuffling decision roots of the advanced state, which are those that
+//! attestations and blocks building upon the head will use. The advance is skipped if it would
+//! cross more than `ChainConfig::prewarm_max_epochs` epochs, bounding the CPU spent on a head which
+//! may be lagging because the node is syncing.
+use crate::beacon_chain::ATTESTATION_CACHE_LOCK_TIMEOUT;
+use crate::{metrics, BeaconChain, BeaconChainError as Error, BeaconChainTypes};
+use slog::debug;
+use state_processing::state_advance::partial_state_advance;
+use std::sync::Arc;
+use types::{beacon_state::CloneConfig, AttestationShufflingId, EthSpec, RelativeEpoch, Slot};
+
+impl<T: BeaconChainTypes> BeaconChain<T> {
+    /// Spawn a task to pre-warm the caches if the head lags `current_slot` by more than an epoch.
+    pub(crate) fn spawn_cache_prewarm_if_lagging(self: &Arc<Self>, current_slot: Slot) {
+        if self.best_slot() + T::EthSpec::slots_per_epoch() >= current_slot {
+            return;
+        }
+
+        let chain = self.clone();
+        self.task_executor.spawn_blocking(
+            move || {
+                if let Err(e) = chain.prewarm_caches(current_slot) {
+                    debug!(
+                        chain.log,
+                        "Failed to pre-warm caches";
+                        "error" => ?e,
+                        "current_slot" => current_slot,
+                    );
+                }
+            },
+            "cache_prewarm",
+        );
+    }
+
+    /// Advance a copy of the head state to the epoch of `current_slot` and use it to populate the
+    /// shuffling, attester and proposer caches.
+    ///
+    /// Returns `false` without doing any work if the head is already in that epoch, the caches
+    /// have already been pre-warmed for the head and epoch, another pre-warm is in progress, or the
+    /// advance would exceed `ChainConfig::prewarm_max_epochs`.
+    pub fn prewarm_caches(&self, current_slot: Slot) -> Result<bool, Error> {
+        let mut last_prewarm = match self.last_cache_prewarm.try_lock() {
+            Some(last_prewarm) => last_prewarm,
+            None => return Ok(false),
+        };
+
+        let slots_per_epoch = T::EthSpec::slots_per_epoch();
+        let current_epoch = current_slot.epoch(slots_per_epoch);
+        let head = self.head_snapshot();
+        let head_root = head.beacon_block_root;
+        let head_epoch = head.beacon_state.current_epoch();
+        if head_epoch >= current_epoch
+            || head_epoch + self.config.prewarm_max_epochs < current_epoch
+            || *last_prewarm == Some((head_root, current_epoch))
+        {
+            return Ok(false);
+        }
+
+        let _timer = metrics::start_timer(&metrics::CACHE_PREWARM_SECONDS);
+
+        let mut state = head
+            .beacon_state
+            .clone_with(CloneConfig::committee_caches_only());
+        let state_root = head.beacon_state_root();
+        drop(head);
+
+        partial_state_advance(
+            &mut state,
+            Some(state_root),
+            current_epoch.start_slot(slots_per_epoch),
+            &self.spec,
+        )?;
+        state.build_committee_cache(RelativeEpoch::Current, &self.spec)?;
+        state.build_committee_cache(RelativeEpoch::Next, &self.spec)?;
+
+        self.beacon_proposer_cache.lock().insert(
+            current_epoch,
+            state.proposer_shuffling_decision_root(head_root)?,
+            state.get_beacon_proposer_indices(&self.spec)?,
+            state.fork(),
+        )?;
+
+        {
+            let mut shuffling_cache = self
+                .shuffling_cache
+                .try_write_for(ATTESTATION_CACHE_LOCK_TIMEOUT)
+                .ok_or(Error::AttestationCacheLockTimeout)?;
+            for relative_epoch in [RelativeEpoch::Current, RelativeEpoch::Next] {
+                let shuffling_id = AttestationShufflingId::new(head_root, &state, relative_epoch)?;
+                shuffling_cache.insert(shuffling_id, state.committee_cache(relative_epoch)?);
+            }
+        }
+
+        self.attester_cache
+            .maybe_cache_state(&state, head_root, &self.spec)?;
+
+        *last_prewarm = Some((head_root, current_epoch));
+
+        debug!(
+            self.log,
+            "Pre-warmed caches for skipped slots";
+            "head_root" => ?head_root,
+            "head_epoch" => head_epoch,
+            "current_epoch" => current_epoch,
+        );
+
+        Ok(true)
+    }
+}
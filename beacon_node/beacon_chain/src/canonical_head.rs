@@ -8,13 +8,15 @@
 //!
 //! ## Deadlock safety
 //!
-//! This module contains three locks:
+//! This module contains four locks:
 //!
 //! 1. `RwLock<BeaconForkChoice>`: Contains `proto_array` fork choice.
 //! 2. `RwLock<CachedHead>`: Contains a cached block/state from the last run of `proto_array`.
 //! 3. `Mutex<()>`: Is used to prevent concurrent execution of `BeaconChain::recompute_head`.
+//! 4. `Mutex<CanonicalUpdates>`: Contains the recent changes to the canonical chain. It is only
+//!    held briefly and no other lock is ever acquired whilst holding it.
 //!
-//! This module has to take great efforts to avoid causing a deadlock with these three methods. Any
+//! This module has to take great efforts to avoid causing a deadlock with these four methods. Any
 //! developers working in this module should tread carefully and seek a detailed review.
 //!
 //! To encourage safe use of this module, it should **only ever return a read or write lock for the
@@ -31,7 +33,10 @@
 //! the head block root. This is unacceptable for fast-responding functions like the networking
 //! stack.
 
+use crate::canonical_updates::{CanonicalChainDiff, CanonicalUpdates};
+use crate::failure_log_limiter::FailureKind;
 use crate::persisted_fork_choice::PersistedForkChoice;
+use crate::shutdown_report::{ShutdownCause, ShutdownReport};
 use crate::{
     beacon_chain::{
         BeaconForkChoice, BeaconStore, BLOCK_PROCESSING_CACHE_LOCK_TIMEOUT, FORK_CHOICE_DB_KEY,
@@ -40,20 +45,50 @@ use crate::{
     events::ServerSentEventHandler,
     metrics,
     validator_monitor::{get_slot_delay_ms, timestamp_now},
+    validator_registry_diffs::VALIDATOR_REGISTRY_DIFFS_CAPACITY,
     BeaconChain, BeaconChainError as Error, BeaconChainTypes, BeaconSnapshot,
 };
-use eth2::types::{EventKind, SseChainReorg, SseFinalizedCheckpoint, SseHead, SseLateHead};
+use eth2::types::{
+    EventKind, SseCanonicalChainUpdate, SseChainReorg, SseFinalizedCheckpoint, SseHead, SseLateHead,
+};
 use fork_choice::{ExecutionStatus, ForkChoiceView, ForkchoiceUpdateParameters, ProtoBlock};
 use itertools::process_results;
 use parking_lot::{Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard};
-use slog::{crit, debug, error, warn, Logger};
+use proto_array::HeadWeights;
+use slog::{crit, debug, error, info, warn, Logger};
 use slot_clock::SlotClock;
+use std::collections::HashSet;
 use std::sync::Arc;
 use std::time::Duration;
 use store::{iter::StateRootsIterator, KeyValueStoreOp, StoreItem};
-use task_executor::{JoinHandle, ShutdownReason};
+use task_executor::JoinHandle;
+use tokio::sync::broadcast;
 use types::*;
 
+/// The number of `HeadChange` notifications which are buffered for each subscriber. A subscriber
+/// which falls further behind misses the oldest notifications.
+pub const HEAD_CHANGE_CHANNEL_CAPACITY: usize = 16;
+
+/// A change of head is logged if the weight of the new head exceeds that of its competitor by no
+/// more than this percentage of the new head's weight.
+pub const NARROW_HEAD_MARGIN_PERCENT: u64 = 10;
+
+/// A narrow head margin is logged at most once per this many slots.
+pub const NARROW_HEAD_MARGIN_LOG_INTERVAL_SLOTS: u64 = 8;
+
+/// A change to the head block of the canonical chain, published after the new head has been
+/// enshrined in the `CanonicalHead`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HeadChange {
+    pub old_root: Hash256,
+    pub new_root: Hash256,
+    /// The slot of the new head block.
+    pub slot: Slot,
+    /// `true` if the old head is not an ancestor of the new head.
+    pub is_reorg: bool,
+    pub finalized_checkpoint: Checkpoint,
+}
+
 /// Simple wrapper around `RwLock` that uses private visibility to prevent any other modules from
 /// accessing the contained lock without it being explicitly noted in this module.
 pub struct CanonicalHeadRwLock<T>(RwLock<T>);
@@ -76,6 +111,10 @@ impl<T> CanonicalHeadRwLock<T> {
     fn write(&self) -> RwLockWriteGuard<T> {
         self.0.write()
     }
+
+    fn try_read_for(&self, timeout: Duration) -> Option<RwLockReadGuard<T>> {
+        self.0.try_read_for(timeout)
+    }
 }
 
 /// Provides a series of cached values from the last time `BeaconChain::recompute_head` was run.
@@ -103,9 +142,65 @@ pub struct CachedHead<E: EthSpec> {
     justified_hash: Option<ExecutionBlockHash>,
     /// The `execution_payload.block_hash` of the finalized block. Set to `None` before Bellatrix.
     finalized_hash: Option<ExecutionBlockHash>,
+    /// The proposer shuffling decision root of the current epoch of the head state. Set to `None`
+    /// if the head state lacks the block roots to compute it (e.g., some anchor states).
+    head_proposer_shuffling_decision_root: Option<Hash256>,
+    /// The randao mix of the current epoch of the head state. Set to `None` if the head state
+    /// lacks the mix.
+    head_random: Option<Hash256>,
 }
 
 impl<E: EthSpec> CachedHead<E> {
+    /// Instantiate `Self` for a head which has not been cached before.
+    ///
+    /// A warning is logged if the head state is unable to provide the proposer shuffling decision
+    /// root or the randao mix. Most consumers need neither, so the failure is not fatal, and it's
+    /// only logged once for each head.
+    fn new(
+        snapshot: Arc<BeaconSnapshot<E>>,
+        fork_choice_view: &ForkChoiceView,
+        forkchoice_update_params: &ForkchoiceUpdateParameters,
+        log: &Logger,
+    ) -> Self {
+        let state = &snapshot.beacon_state;
+        let head_proposer_shuffling_decision_root = state
+            .proposer_shuffling_decision_root(snapshot.beacon_block_root)
+            .map_err(|e| {
+                warn!(
+                    log,
+                    "Unable to compute head decision root";
+                    "error" => ?e,
+                    "head_block" => ?snapshot.beacon_block_root,
+                    "head_slot" => state.slot(),
+                );
+            })
+            .ok();
+        let head_random = state
+            .get_randao_mix(state.current_epoch())
+            .map_err(|e| {
+                warn!(
+                    log,
+                    "Unable to read head randao mix";
+                    "error" => ?e,
+                    "head_block" => ?snapshot.beacon_block_root,
+                    "head_slot" => state.slot(),
+                );
+            })
+            .ok()
+            .copied();
+
+        Self {
+            snapshot,
+            justified_checkpoint: fork_choice_view.justified_checkpoint,
+            finalized_checkpoint: fork_choice_view.finalized_checkpoint,
+            head_hash: forkchoice_update_params.head_hash,
+            justified_hash: forkchoice_update_params.justified_hash,
+            finalized_hash: forkchoice_update_params.finalized_hash,
+            head_proposer_shuffling_decision_root,
+            head_random,
+        }
+    }
+
     /// Returns root of the block at the head of the beacon chain.
     pub fn head_block_root(&self) -> Hash256 {
         self.snapshot.beacon_block_root
@@ -137,10 +232,17 @@ impl<E: EthSpec> CachedHead<E> {
     }
 
     /// Returns the randao mix for the block at the head of the chain.
-    pub fn head_random(&self) -> Result<Hash256, BeaconStateError> {
-        let state = &self.snapshot.beacon_state;
-        let root = *state.get_randao_mix(state.current_epoch())?;
-        Ok(root)
+    ///
+    /// Returns `None` if the head state lacks the mix.
+    pub fn head_random(&self) -> Option<Hash256> {
+        self.head_random
+    }
+
+    /// Returns the proposer shuffling decision root for the current epoch of the head state.
+    ///
+    /// Returns `None` if the head state lacks the block roots to compute it.
+    pub fn head_proposer_shuffling_decision_root(&self) -> Option<Hash256> {
+        self.head_proposer_shuffling_decision_root
     }
 
     /// Returns the active validator count for the current epoch of the head state.
@@ -212,6 +314,10 @@ pub struct CanonicalHead<T: BeaconChainTypes> {
     ///
     /// This lock **should not be made public**, it should only be used inside this module.
     recompute_head_lock: Mutex<()>,
+    /// The most recent changes to the canonical chain, see `BeaconChain::canonical_updates_since`.
+    canonical_updates: Mutex<CanonicalUpdates>,
+    /// The slot of the last head whose narrow margin was logged, used to rate-limit the log.
+    narrow_margin_logged_slot: Mutex<Option<Slot>>,
 }
 
 impl<T: BeaconChainTypes> CanonicalHead<T> {
@@ -219,22 +325,19 @@ impl<T: BeaconChainTypes> CanonicalHead<T> {
     pub fn new(
         fork_choice: BeaconForkChoice<T>,
         snapshot: Arc<BeaconSnapshot<T::EthSpec>>,
+        log: &Logger,
     ) -> Self {
         let fork_choice_view = fork_choice.cached_fork_choice_view();
         let forkchoice_update_params = fork_choice.get_forkchoice_update_parameters();
-        let cached_head = CachedHead {
-            snapshot,
-            justified_checkpoint: fork_choice_view.justified_checkpoint,
-            finalized_checkpoint: fork_choice_view.finalized_checkpoint,
-            head_hash: forkchoice_update_params.head_hash,
-            justified_hash: forkchoice_update_params.justified_hash,
-            finalized_hash: forkchoice_update_params.finalized_hash,
-        };
+        let cached_head =
+            CachedHead::new(snapshot, &fork_choice_view, &forkchoice_update_params, log);
 
         Self {
             fork_choice: CanonicalHeadRwLock::new(fork_choice),
             cached_head: CanonicalHeadRwLock::new(cached_head),
             recompute_head_lock: Mutex::new(()),
+            canonical_updates: Mutex::new(CanonicalUpdates::default()),
+            narrow_margin_logged_slot: Mutex::new(None),
         }
     }
 
@@ -251,6 +354,7 @@ impl<T: BeaconChainTypes> CanonicalHead<T> {
         mut fork_choice_write_lock: RwLockWriteGuard<BeaconForkChoice<T>>,
         store: &BeaconStore<T>,
         spec: &ChainSpec,
+        log: &Logger,
     ) -> Result<(), Error> {
         let fork_choice = <BeaconChain<T>>::load_fork_choice(store.clone(), spec)?
             .ok_or(Error::MissingPersistedForkChoice)?;
@@ -271,14 +375,12 @@ impl<T: BeaconChainTypes> CanonicalHead<T> {
         };
 
         let forkchoice_update_params = fork_choice.get_forkchoice_update_parameters();
-        let cached_head = CachedHead {
-            snapshot: Arc::new(snapshot),
-            justified_checkpoint: fork_choice_view.justified_checkpoint,
-            finalized_checkpoint: fork_choice_view.finalized_checkpoint,
-            head_hash: forkchoice_update_params.head_hash,
-            justified_hash: forkchoice_update_params.justified_hash,
-            finalized_hash: forkchoice_update_params.finalized_hash,
-        };
+        let cached_head = CachedHead::new(
+            Arc::new(snapshot),
+            &fork_choice_view,
+            &forkchoice_update_params,
+            log,
+        );
 
         *fork_choice_write_lock = fork_choice;
         // Avoid interleaving the fork choice and cached head locks.
@@ -311,6 +413,16 @@ impl<T: BeaconChainTypes> CanonicalHead<T> {
         self.cached_head_read_lock().clone()
     }
 
+    /// Returns a clone of `self.cached_head`, or `None` if the lock can't be obtained within
+    /// `BLOCK_PROCESSING_CACHE_LOCK_TIMEOUT`.
+    ///
+    /// This is useful for reporting, where stale or missing values are preferable to waiting.
+    pub fn try_cached_head(&self) -> Option<CachedHead<T::EthSpec>> {
+        self.cached_head
+            .try_read_for(BLOCK_PROCESSING_CACHE_LOCK_TIMEOUT)
+            .map(|cached_head| cached_head.clone())
+    }
+
     /// Access a read-lock for the cached head.
     ///
     /// This function is **not safe** to be public. See the module-level documentation for more
@@ -327,6 +439,18 @@ impl<T: BeaconChainTypes> CanonicalHead<T> {
         self.cached_head.write()
     }
 
+    /// Returns the head block root of the cached head, along with a read-lock for fork choice.
+    ///
+    /// Blocks whilst the head is being recomputed, so that the cached head is never observed
+    /// lagging fork choice part way through `BeaconChain::recompute_head_at_slot`. Whilst the lock
+    /// is held, fork choice's view of the head is consistent with the returned root.
+    pub(crate) fn settled_head(&self) -> (Hash256, RwLockReadGuard<BeaconForkChoice<T>>) {
+        let _recompute_head_lock = self.recompute_head_lock.lock();
+        let fork_choice = self.fork_choice_read_lock();
+        let head_block_root = self.cached_head_read_lock().head_block_root();
+        (head_block_root, fork_choice)
+    }
+
     /// Access a read-lock for fork choice.
     pub fn fork_choice_read_lock(&self) -> RwLockReadGuard<BeaconForkChoice<T>> {
         self.fork_choice.read()
@@ -336,6 +460,20 @@ impl<T: BeaconChainTypes> CanonicalHead<T> {
     pub fn fork_choice_write_lock(&self) -> RwLockWriteGuard<BeaconForkChoice<T>> {
         self.fork_choice.write()
     }
+
+    /// Returns `true` if the narrow margin of a new head at `head_slot` should be logged, i.e., no
+    /// narrow margin has been logged in the last `NARROW_HEAD_MARGIN_LOG_INTERVAL_SLOTS`.
+    fn should_log_narrow_margin(&self, head_slot: Slot) -> bool {
+        let mut logged_slot = self.narrow_margin_logged_slot.lock();
+        let should_log = logged_slot.map_or(true, |logged_slot| {
+            head_slot >= logged_slot + NARROW_HEAD_MARGIN_LOG_INTERVAL_SLOTS
+                || head_slot < logged_slot
+        });
+        if should_log {
+            *logged_slot = Some(head_slot);
+        }
+        should_log
+    }
 }
 
 impl<T: BeaconChainTypes> BeaconChain<T> {
@@ -390,6 +528,13 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         self.canonical_head.cached_head_read_lock().snapshot.clone()
     }
 
+    /// Returns a receiver of a `HeadChange` for each subsequent change to the canonical head block.
+    ///
+    /// Notifications are published once the new head is visible via `Self::head_snapshot`.
+    pub fn subscribe_to_head_changes(&self) -> broadcast::Receiver<HeadChange> {
+        self.head_change_tx.subscribe()
+    }
+
     /// Returns the beacon block at the head of the canonical chain.
     ///
     /// See `Self::head` for more information.
@@ -414,6 +559,18 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
             .clone_with(CloneConfig::committee_caches_only())
     }
 
+    /// Returns the recorded changes to the canonical chain with a sequence number of at least
+    /// `sequence_number`, ordered from the oldest.
+    ///
+    /// Only a bounded number of changes are retained. If the first change returned has a higher
+    /// sequence number than `sequence_number`, the changes in between have been missed.
+    pub fn canonical_updates_since(&self, sequence_number: u64) -> Vec<SseCanonicalChainUpdate> {
+        self.canonical_head
+            .canonical_updates
+            .lock()
+            .since(sequence_number)
+    }
+
     /// Execute the fork choice algorithm and enthrone the result as the canonical head.
     ///
     /// This method replaces the old `BeaconChain::fork_choice` method.
@@ -429,6 +586,24 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
     /// *just* before the start of the slot. This ensures that block production can use the correct
     /// head value without being delayed.
     pub async fn recompute_head_at_slot(self: &Arc<Self>, current_slot: Slot) -> Result<(), Error> {
+        // Running fork choice may finalize a new checkpoint and migrate the database, so the head
+        // of a read-only chain never changes.
+        if self.config.read_only {
+            return Ok(());
+        }
+
+        let result = self
+            .recompute_head_at_slot_with_el_update(current_slot)
+            .await;
+        self.check_shutdown_target();
+        result
+    }
+
+    /// Recompute the head and await any resulting updates to the execution layer.
+    async fn recompute_head_at_slot_with_el_update(
+        self: &Arc<Self>,
+        current_slot: Slot,
+    ) -> Result<(), Error> {
         metrics::inc_counter(&metrics::FORK_CHOICE_REQUESTS);
         let _timer = metrics::start_timer(&metrics::FORK_CHOICE_TIMES);
 
@@ -514,7 +689,7 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         // Check to ensure that the finalized block hasn't been marked as invalid. If it has,
         // shut down Lighthouse.
         let finalized_proto_block = fork_choice_read_lock.get_finalized_block()?;
-        check_finalized_payload_validity(self, &finalized_proto_block)?;
+        check_finalized_payload_validity(self, &finalized_proto_block, &old_cached_head)?;
 
         // Sanity check the finalized checkpoint.
         //
@@ -565,6 +740,18 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
 
         perform_debug_logging::<T>(&old_view, &new_view, &fork_choice_read_lock, &self.log);
 
+        // Determine the blocks which were added to and removed from the canonical chain whilst
+        // both the old and new heads can be found in fork choice.
+        let canonical_chain_diff = if new_view.head_block_root != old_view.head_block_root {
+            CanonicalChainDiff::from_proto_array(
+                fork_choice_read_lock.proto_array(),
+                old_view.head_block_root,
+                new_view.head_block_root,
+            )
+        } else {
+            None
+        };
+
         // Drop the read lock, it's no longer required and holding it any longer than necessary
         // will just cause lock contention.
         drop(fork_choice_read_lock);
@@ -612,14 +799,12 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
                         .map(|()| snapshot)
                 })?;
 
-            let new_cached_head = CachedHead {
-                snapshot: Arc::new(new_snapshot),
-                justified_checkpoint: new_view.justified_checkpoint,
-                finalized_checkpoint: new_view.finalized_checkpoint,
-                head_hash: new_forkchoice_update_parameters.head_hash,
-                justified_hash: new_forkchoice_update_parameters.justified_hash,
-                finalized_hash: new_forkchoice_update_parameters.finalized_hash,
-            };
+            let new_cached_head = CachedHead::new(
+                Arc::new(new_snapshot),
+                &new_view,
+                &new_forkchoice_update_parameters,
+                &self.log,
+            );
 
             let new_head = {
                 // Now the new snapshot has been obtained, take a write-lock on the cached head so
@@ -646,6 +831,9 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
                 head_hash: new_forkchoice_update_parameters.head_hash,
                 justified_hash: new_forkchoice_update_parameters.justified_hash,
                 finalized_hash: new_forkchoice_update_parameters.finalized_hash,
+                head_proposer_shuffling_decision_root: old_cached_head
+                    .head_proposer_shuffling_decision_root,
+                head_random: old_cached_head.head_random,
             };
 
             let mut cached_head_write_lock = self.canonical_head.cached_head_write_lock();
@@ -664,17 +852,65 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         let old_snapshot = &old_cached_head.snapshot;
 
         // If the head changed, perform some updates.
-        if new_snapshot.beacon_block_root != old_snapshot.beacon_block_root {
-            if let Err(e) =
-                self.after_new_head(&old_cached_head, &new_cached_head, new_head_proto_block)
-            {
+        let head_change = if new_snapshot.beacon_block_root != old_snapshot.beacon_block_root {
+            // Finding the competitor of the new head requires a scan of the proto array, so it's
+            // done with a fresh read-lock rather than whilst holding the lock with which the head
+            // was computed. Fork choice only runs under the `recompute_head_lock`, so the weights
+            // are still those which selected the new head.
+            let head_weights = self
+                .canonical_head
+                .fork_choice_read_lock()
+                .proto_array()
+                .head_weights(&new_snapshot.beacon_block_root)
+                .unwrap_or_default();
+            metrics::set_gauge(
+                &metrics::FORK_CHOICE_HEAD_WEIGHT,
+                head_weights.head_weight as i64,
+            );
+            metrics::set_gauge(
+                &metrics::FORK_CHOICE_COMPETITOR_WEIGHT,
+                head_weights.competitor_weight.unwrap_or(0) as i64,
+            );
+            metrics::set_gauge(
+                &metrics::FORK_CHOICE_HEAD_PROPOSER_BOOST,
+                head_weights.proposer_boost as i64,
+            );
+
+            // Detect and potentially report any re-orgs.
+            let reorg_distance = detect_reorg(
+                &old_snapshot.beacon_state,
+                old_snapshot.beacon_block_root,
+                &new_snapshot.beacon_state,
+                new_snapshot.beacon_block_root,
+                &self.spec,
+                &self.log,
+            );
+
+            if let Err(e) = self.after_new_head(
+                &old_cached_head,
+                &new_cached_head,
+                new_head_proto_block,
+                head_weights,
+                canonical_chain_diff,
+                reorg_distance,
+            ) {
                 crit!(
                     self.log,
                     "Error updating canonical head";
                     "error" => ?e
                 );
             }
-        }
+
+            Some(HeadChange {
+                old_root: old_snapshot.beacon_block_root,
+                new_root: new_snapshot.beacon_block_root,
+                slot: new_snapshot.beacon_block.slot(),
+                is_reorg: reorg_distance.is_some(),
+                finalized_checkpoint: new_cached_head.finalized_checkpoint,
+            })
+        } else {
+            None
+        };
 
         // Drop the old cache head nice and early to try and free the memory as soon as possible.
         drop(old_cached_head);
@@ -692,6 +928,12 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
             }
         }
 
+        // Notify internal subscribers of the new head. An error only indicates that there are
+        // currently no subscribers.
+        if let Some(head_change) = head_change {
+            let _ = self.head_change_tx.send(head_change);
+        }
+
         // The execution layer updates might attempt to take a write-lock on fork choice, so it's
         // important to ensure the fork-choice lock isn't being held.
         let el_update_handle =
@@ -705,25 +947,22 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
     }
 
     /// Perform updates to caches and other components after the canonical head has been changed.
+    ///
+    /// The `canonical_chain_diff` is `None` if the old head could no longer be found in fork
+    /// choice, in which case only the common ancestor of the heads is reported.
+    #[allow(clippy::too_many_arguments)]
     fn after_new_head(
         self: &Arc<Self>,
         old_cached_head: &CachedHead<T::EthSpec>,
         new_cached_head: &CachedHead<T::EthSpec>,
         new_head_proto_block: ProtoBlock,
+        head_weights: HeadWeights,
+        canonical_chain_diff: Option<CanonicalChainDiff>,
+        reorg_distance: Option<Slot>,
     ) -> Result<(), Error> {
         let old_snapshot = &old_cached_head.snapshot;
         let new_snapshot = &new_cached_head.snapshot;
 
-        // Detect and potentially report any re-orgs.
-        let reorg_distance = detect_reorg(
-            &old_snapshot.beacon_state,
-            old_snapshot.beacon_block_root,
-            &new_snapshot.beacon_state,
-            new_snapshot.beacon_block_root,
-            &self.spec,
-            &self.log,
-        );
-
         // Determine if the new head is in a later epoch to the previous head.
         let is_epoch_transition = old_snapshot
             .beacon_block
@@ -763,6 +1002,12 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
                 );
             });
 
+        // Only the state of the head is advanced or skipped forward, so the locks of other blocks
+        // and the progress of failed skips from other states are no longer needed.
+        self.state_advance_locks
+            .prune(new_snapshot.beacon_block_root);
+        self.prune_partial_state_skip(state_root);
+
         observe_head_block_delays(
             &mut self.block_times_cache.write(),
             &new_head_proto_block,
@@ -778,17 +1023,34 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
             &self.log,
         );
 
+        if let Some(competitor_weight) = head_weights.competitor_weight {
+            let margin = head_weights.head_weight.saturating_sub(competitor_weight);
+            if margin.saturating_mul(100)
+                <= head_weights
+                    .head_weight
+                    .saturating_mul(NARROW_HEAD_MARGIN_PERCENT)
+                && self.canonical_head.should_log_narrow_margin(head_slot)
+            {
+                info!(
+                    self.log,
+                    "New head has a narrow margin";
+                    "head_weight" => head_weights.head_weight,
+                    "competitor_weight" => competitor_weight,
+                    "proposer_boost" => head_weights.proposer_boost,
+                    "head_block" => ?new_snapshot.beacon_block_root,
+                    "slot" => head_slot,
+                );
+            }
+        }
+
         if is_epoch_transition || reorg_distance.is_some() {
             self.persist_head_and_fork_choice()?;
             self.op_pool.prune_attestations(self.epoch()?);
         }
 
-        // Register server-sent-events for a new head.
-        if let Some(event_handler) = self
-            .event_handler
-            .as_ref()
-            .filter(|handler| handler.has_head_subscribers())
-        {
+        // Register server-sent-events for a new head. The event is registered even without
+        // subscribers, so that it may be replayed to later subscribers.
+        if let Some(event_handler) = self.event_handler.as_ref() {
             match (dependent_root, prev_dependent_root) {
                 (Ok(current_duty_dependent_root), Ok(previous_duty_dependent_root)) => {
                     event_handler.register(EventKind::Head(SseHead {
@@ -798,6 +1060,9 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
                         current_duty_dependent_root,
                         previous_duty_dependent_root,
                         epoch_transition: is_epoch_transition,
+                        head_weight: Some(head_weights.head_weight),
+                        competitor_weight: head_weights.competitor_weight,
+                        proposer_boost: Some(head_weights.proposer_boost),
                     }));
                 }
                 (Err(e), _) | (_, Err(e)) => {
@@ -829,6 +1094,125 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
             }
         }
 
+        // Record the change to the canonical chain and register a server-sent-event for it.
+        let canonical_chain_diff = canonical_chain_diff.unwrap_or_else(|| {
+            let common_ancestor = find_common_ancestor_in_states(
+                &old_snapshot.beacon_state,
+                old_snapshot.beacon_block_root,
+                &new_snapshot.beacon_state,
+                new_snapshot.beacon_block_root,
+                &self.spec,
+            )
+            .unwrap_or(new_cached_head.finalized_checkpoint.root);
+            debug!(
+                self.log,
+                "Old head unknown to fork choice";
+                "info" => "only the common ancestor of the heads is reported",
+                "old_head" => ?old_snapshot.beacon_block_root,
+                "common_ancestor" => ?common_ancestor,
+            );
+            CanonicalChainDiff::ancestor_only(common_ancestor)
+        });
+        let canonical_chain_update = self.canonical_head.canonical_updates.lock().push(
+            head_slot,
+            old_snapshot.beacon_block_root,
+            new_snapshot.beacon_block_root,
+            canonical_chain_diff,
+        );
+        if let Some(event_handler) = self
+            .event_handler
+            .as_ref()
+            .filter(|handler| handler.has_canonical_chain_update_subscribers())
+        {
+            event_handler.register(EventKind::CanonicalChainUpdate(canonical_chain_update));
+        }
+
+        // Record the changes to the validator registry during the previous epoch, unless the head
+        // is too far behind the current epoch for them to be of interest (e.g. whilst syncing).
+        let head_epoch = head_slot.epoch(T::EthSpec::slots_per_epoch());
+        let is_recent = self.epoch().map_or(false, |current_epoch| {
+            head_epoch + VALIDATOR_REGISTRY_DIFFS_CAPACITY as u64 >= current_epoch
+        });
+        if is_epoch_transition && is_recent {
+            let previous_epoch = head_epoch.saturating_sub(1_u64);
+            let inclusion_delays = self
+                .inclusion_delay_summary(previous_epoch)
+                .unwrap_or_default();
+            info!(
+                self.log,
+                "Epoch summary";
+                "epoch" => previous_epoch,
+                "included_attestations" => inclusion_delays.attestations(),
+                "mean_inclusion_delay" => inclusion_delays
+                    .mean()
+                    .map(|mean| format!("{:.2}", mean)),
+            );
+
+            match self.update_validator_registry_diff(&new_snapshot.beacon_state, state_root) {
+                Ok(Some(update)) => {
+                    if let Some(event_handler) = self
+                        .event_handler
+                        .as_ref()
+                        .filter(|handler| handler.has_validator_registry_update_subscribers())
+                    {
+                        event_handler.register(EventKind::ValidatorRegistryUpdate(update));
+                    }
+                }
+                Ok(None) => (),
+                Err(e) => warn!(
+                    self.log,
+                    "Unable to compute validator registry diff";
+                    "epoch" => head_epoch,
+                    "error" => ?e
+                ),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Register server-sent events for the head and finalized checkpoint at startup, so that they
+    /// may be replayed to subscribers before either changes.
+    ///
+    /// This is particularly useful after checkpoint sync, when finality might not advance for some
+    /// time.
+    pub(crate) fn register_initial_events(&self) -> Result<(), Error> {
+        let event_handler = match self.event_handler.as_ref() {
+            Some(event_handler) => event_handler,
+            None => return Ok(()),
+        };
+
+        let (finalized_checkpoint, finalized_proto_block) = {
+            let fork_choice = self.canonical_head.fork_choice_read_lock();
+            (
+                fork_choice.finalized_checkpoint(),
+                fork_choice.get_finalized_block()?,
+            )
+        };
+        event_handler.register(EventKind::FinalizedCheckpoint(SseFinalizedCheckpoint {
+            epoch: finalized_checkpoint.epoch,
+            block: finalized_checkpoint.root,
+            state: finalized_proto_block.state_root,
+        }));
+
+        let head = self.head_snapshot();
+        let state = &head.beacon_state;
+        event_handler.register(EventKind::Head(SseHead {
+            slot: head.beacon_block.slot(),
+            block: head.beacon_block_root,
+            state: head.beacon_state_root(),
+            current_duty_dependent_root: state
+                .proposer_shuffling_decision_root(head.beacon_block_root)
+                .map_err(Error::BeaconStateError)?,
+            previous_duty_dependent_root: state
+                .attester_shuffling_decision_root(head.beacon_block_root, RelativeEpoch::Current)
+                .map_err(Error::BeaconStateError)?,
+            epoch_transition: false,
+            head_weight: None,
+            competitor_weight: None,
+            proposer_boost: None,
+        }));
+
         Ok(())
     }
 
@@ -875,17 +1259,17 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         self.attester_cache
             .prune_below(new_view.finalized_checkpoint.epoch);
 
+        // The event is registered even without subscribers, so that it may be replayed to later
+        // subscribers.
         if let Some(event_handler) = self.event_handler.as_ref() {
-            if event_handler.has_finalized_subscribers() {
-                event_handler.register(EventKind::FinalizedCheckpoint(SseFinalizedCheckpoint {
-                    epoch: new_view.finalized_checkpoint.epoch,
-                    block: new_view.finalized_checkpoint.root,
-                    // Provide the state root of the latest finalized block, rather than the
-                    // specific state root at the first slot of the finalized epoch (which
-                    // might be a skip slot).
-                    state: finalized_proto_block.state_root,
-                }));
-            }
+            event_handler.register(EventKind::FinalizedCheckpoint(SseFinalizedCheckpoint {
+                epoch: new_view.finalized_checkpoint.epoch,
+                block: new_view.finalized_checkpoint.root,
+                // Provide the state root of the latest finalized block, rather than the
+                // specific state root at the first slot of the finalized epoch (which
+                // might be a skip slot).
+                state: finalized_proto_block.state_root,
+            }));
         }
 
         // The store migration task requires the *state at the slot of the finalized epoch*,
@@ -912,10 +1296,37 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         )?
         .ok_or(Error::MissingFinalizedStateRoot(new_finalized_slot))?;
 
+        self.refresh_checkpoint_state_cache(
+            new_view.finalized_checkpoint,
+            new_finalized_state_root,
+            new_view.justified_checkpoint,
+        );
+
+        // Fork choice still references the finalized block and its descendants, so the migrator
+        // must not prune them even if they appear on an abandoned chain.
+        let protected_block_roots = {
+            let fork_choice = self.canonical_head.fork_choice_read_lock();
+            let proto_array = fork_choice.proto_array().core_proto_array();
+            let mut protected_block_roots = HashSet::new();
+            // Parents precede their children in the array.
+            for node in &proto_array.nodes {
+                let parent_protected = node
+                    .parent
+                    .and_then(|parent_index| proto_array.nodes.get(parent_index))
+                    .map_or(false, |parent| protected_block_roots.contains(&parent.root));
+                if parent_protected || node.root == new_view.finalized_checkpoint.root {
+                    protected_block_roots.insert(node.root);
+                }
+            }
+            protected_block_roots
+        };
+
         self.store_migrator.process_finalization(
             new_finalized_state_root.into(),
             new_view.finalized_checkpoint,
             self.head_tracker.clone(),
+            protected_block_roots,
+            self.config.prune_payloads,
         )?;
 
         Ok(())
@@ -948,6 +1359,7 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
 fn check_finalized_payload_validity<T: BeaconChainTypes>(
     chain: &BeaconChain<T>,
     finalized_proto_block: &ProtoBlock,
+    cached_head: &CachedHead<T::EthSpec>,
 ) -> Result<(), Error> {
     if let ExecutionStatus::Invalid(block_hash) = finalized_proto_block.execution_status {
         crit!(
@@ -957,10 +1369,13 @@ fn check_finalized_payload_validity<T: BeaconChainTypes>(
             You may be on a hostile network.",
             "block_hash" => ?block_hash
         );
-        let mut shutdown_sender = chain.shutdown_sender();
-        shutdown_sender
-            .try_send(ShutdownReason::Failure(
-                "Finalized block has an invalid execution payload.",
+        chain
+            .shut_down_with_report(ShutdownReport::new(
+                ShutdownCause::InvalidFinalizedPayload,
+                finalized_proto_block.root,
+                Some(block_hash),
+                cached_head.head_block_root(),
+                cached_head.head_slot(),
             ))
             .map_err(Error::InvalidFinalizedPayloadShutdownError)?;
 
@@ -1054,15 +1469,24 @@ fn spawn_execution_layer_updates<T: BeaconChainTypes>(
                     return;
                 }
 
-                if let Err(e) = chain
+                match chain
                     .update_execution_engine_forkchoice(current_slot, forkchoice_update_params)
                     .await
                 {
-                    crit!(
-                        chain.log,
-                        "Failed to update execution head";
-                        "error" => ?e
-                    );
+                    Ok(()) => chain.record_failure_cleared(FailureKind::ExecutionForkchoiceUpdate),
+                    Err(e) => {
+                        if chain.should_log_failure(
+                            FailureKind::ExecutionForkchoiceUpdate,
+                            "Failed to update execution head",
+                            &e,
+                        ) {
+                            crit!(
+                                chain.log,
+                                "Failed to update execution head";
+                                "error" => ?e
+                            );
+                        }
+                    }
                 }
 
                 // Update the mechanism for preparing for block production on the execution layer.
@@ -1073,12 +1497,21 @@ fn spawn_execution_layer_updates<T: BeaconChainTypes>(
                 //
                 // This seems OK. It's not a significant waste of EL<>CL bandwidth or resources, as far as I
                 // know.
-                if let Err(e) = chain.prepare_beacon_proposer(current_slot).await {
-                    crit!(
-                        chain.log,
-                        "Failed to prepare proposers after fork choice";
-                        "error" => ?e
-                    );
+                match chain.prepare_beacon_proposer(current_slot).await {
+                    Ok(()) => chain.record_failure_cleared(FailureKind::PrepareProposer),
+                    Err(e) => {
+                        if chain.should_log_failure(
+                            FailureKind::PrepareProposer,
+                            "Failed to prepare proposers after fork choice",
+                            &e,
+                        ) {
+                            crit!(
+                                chain.log,
+                                "Failed to prepare proposers after fork choice";
+                                "error" => ?e
+                            );
+                        }
+                    }
                 }
             },
             "update_el_forkchoice",
@@ -1204,6 +1637,23 @@ pub fn find_reorg_slot<E: EthSpec>(
         .start_slot(E::slots_per_epoch()))
 }
 
+/// Find the common ancestor of two heads using the block roots in their states, for when the heads
+/// can't be compared in fork choice. See `find_reorg_slot` for the limitations of this search.
+fn find_common_ancestor_in_states<E: EthSpec>(
+    old_state: &BeaconState<E>,
+    old_block_root: Hash256,
+    new_state: &BeaconState<E>,
+    new_block_root: Hash256,
+    spec: &ChainSpec,
+) -> Option<Hash256> {
+    let slot = find_reorg_slot(old_state, old_block_root, new_state, new_block_root, spec).ok()?;
+    if slot == new_state.slot() {
+        Some(new_block_root)
+    } else {
+        new_state.get_block_root(slot).ok().copied()
+    }
+}
+
 fn observe_head_block_delays<E: EthSpec, S: SlotClock>(
     block_times_cache: &mut BlockTimesCache,
     head_block: &ProtoBlock,
@@ -1302,6 +1752,9 @@ fn observe_head_block_delays<E: EthSpec, S: SlotClock>(
                 block: head_block_root,
                 peer_id: peer_info.id,
                 peer_client: peer_info.client,
+                transport: peer_info.transport,
+                duplicate_deliveries: peer_info.duplicate_deliveries,
+                distinct_peers: peer_info.peers.len() as u64,
                 proposer_index: head_block_proposer_index,
                 proposer_graffiti: head_block_graffiti,
                 block_delay: block_delay_total,
@@ -0,0 +1,165 @@
+//! Provides a bounded, in-memory history of the changes to the canonical chain.
+//!
+//! Each change of head is recorded as a `SseCanonicalChainUpdate` which lists the blocks that
+//! became canonical and the blocks that were removed from the canonical chain. Consumers such as
+//! indexers can use the sequence numbers of the updates to follow re-orgs without missing any.
+use eth2::types::SseCanonicalChainUpdate;
+use proto_array::ProtoArrayForkChoice;
+use std::collections::VecDeque;
+use types::{Hash256, Slot};
+
+/// The number of updates retained by `CanonicalUpdates`.
+pub const CANONICAL_UPDATES_CAPACITY: usize = 256;
+
+/// The blocks added to and removed from the canonical chain by a change of head.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CanonicalChainDiff {
+    pub common_ancestor: Hash256,
+    /// Ordered from the oldest block up to and including the new head.
+    pub canonical_blocks: Vec<Hash256>,
+    /// Ordered from the oldest block up to and including the old head.
+    pub non_canonical_blocks: Vec<Hash256>,
+}
+
+impl CanonicalChainDiff {
+    /// Compute the diff between the chains ending at `old_head` and `new_head` in `proto_array`.
+    ///
+    /// Returns `None` if the heads don't have a common ancestor in `proto_array`, e.g. because the
+    /// old head has been pruned.
+    pub fn from_proto_array(
+        proto_array: &ProtoArrayForkChoice,
+        old_head: Hash256,
+        new_head: Hash256,
+    ) -> Option<Self> {
+        let common_ancestor = proto_array.find_common_ancestor(old_head, new_head)?;
+        let blocks_since_ancestor = |head: &Hash256| {
+            let mut roots = proto_array
+                .iter_nodes(head)
+                .map(|node| node.root)
+                .take_while(|root| *root != common_ancestor)
+                .collect::<Vec<_>>();
+            roots.reverse();
+            roots
+        };
+
+        Some(Self {
+            common_ancestor,
+            canonical_blocks: blocks_since_ancestor(&new_head),
+            non_canonical_blocks: blocks_since_ancestor(&old_head),
+        })
+    }
+
+    /// A diff which only reports the `common_ancestor`, for when the blocks after it are unknown.
+    pub fn ancestor_only(common_ancestor: Hash256) -> Self {
+        Self {
+            common_ancestor,
+            canonical_blocks: vec![],
+            non_canonical_blocks: vec![],
+        }
+    }
+}
+
+/// A ring of the most recent `SseCanonicalChainUpdate`s.
+pub struct CanonicalUpdates {
+    updates: VecDeque<SseCanonicalChainUpdate>,
+    next_sequence_number: u64,
+    capacity: usize,
+}
+
+impl Default for CanonicalUpdates {
+    fn default() -> Self {
+        Self::new(CANONICAL_UPDATES_CAPACITY)
+    }
+}
+
+impl CanonicalUpdates {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            updates: VecDeque::with_capacity(capacity),
+            next_sequence_number: 0,
+            capacity,
+        }
+    }
+
+    /// Record an update for the change of head described by `diff`, evicting the oldest update if
+    /// the ring is full. Returns the update with its assigned sequence number.
+    pub fn push(
+        &mut self,
+        slot: Slot,
+        old_head_block: Hash256,
+        new_head_block: Hash256,
+        diff: CanonicalChainDiff,
+    ) -> SseCanonicalChainUpdate {
+        let update = SseCanonicalChainUpdate {
+            sequence_number: self.next_sequence_number,
+            slot,
+            old_head_block,
+            new_head_block,
+            common_ancestor: diff.common_ancestor,
+            canonical_blocks: diff.canonical_blocks,
+            non_canonical_blocks: diff.non_canonical_blocks,
+        };
+        self.next_sequence_number += 1;
+
+        if self.updates.len() >= self.capacity {
+            self.updates.pop_front();
+        }
+        if self.capacity > 0 {
+            self.updates.push_back(update.clone());
+        }
+
+        update
+    }
+
+    /// Returns the retained updates with a sequence number of at least `sequence_number`, ordered
+    /// from the oldest.
+    ///
+    /// If the first update returned has a higher sequence number than `sequence_number`, the
+    /// updates in between have already been evicted.
+    pub fn since(&self, sequence_number: u64) -> Vec<SseCanonicalChainUpdate> {
+        self.updates
+            .iter()
+            .skip_while(|update| update.sequence_number < sequence_number)
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diff(i: u64) -> CanonicalChainDiff {
+        CanonicalChainDiff::ancestor_only(Hash256::from_low_u64_be(i))
+    }
+
+    #[test]
+    fn sequence_numbers() {
+        let mut updates = CanonicalUpdates::new(4);
+        for i in 0..3 {
+            let update = updates.push(Slot::new(i), Hash256::zero(), Hash256::zero(), diff(i));
+            assert_eq!(update.sequence_number, i);
+        }
+
+        let all = updates.since(0);
+        assert_eq!(all.len(), 3);
+        assert_eq!(all[2].common_ancestor, Hash256::from_low_u64_be(2));
+        assert_eq!(updates.since(2).len(), 1);
+        assert!(updates.since(3).is_empty());
+    }
+
+    #[test]
+    fn oldest_updates_are_evicted() {
+        let mut updates = CanonicalUpdates::new(2);
+        for i in 0..5 {
+            updates.push(Slot::new(i), Hash256::zero(), Hash256::zero(), diff(i));
+        }
+
+        let retained = updates
+            .since(0)
+            .into_iter()
+            .map(|update| update.sequence_number)
+            .collect::<Vec<_>>();
+        assert_eq!(retained, vec![3, 4]);
+    }
+}
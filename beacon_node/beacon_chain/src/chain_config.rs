@@ -1,8 +1,73 @@
+use crate::head_tracker::MAX_SSZ_HEADS;
 use serde_derive::{Deserialize, Serialize};
-use types::Checkpoint;
+use types::{Checkpoint, Epoch, Slot};
 
 pub const DEFAULT_FORK_CHOICE_BEFORE_PROPOSAL_TIMEOUT: u64 = 250;
 
+/// Default value for `ChainConfig::builder_fallback_skips`.
+pub const DEFAULT_BUILDER_FALLBACK_SKIPS: usize = 3;
+
+/// Default value for `ChainConfig::builder_fallback_epochs_since_finality`.
+pub const DEFAULT_BUILDER_FALLBACK_EPOCHS_SINCE_FINALITY: u64 = 3;
+
+/// Default value for `ChainConfig::builder_bid_retention_epochs`.
+pub const DEFAULT_BUILDER_BID_RETENTION_EPOCHS: u64 = 256;
+
+/// Default value for `ChainConfig::pre_finalization_block_cache_size`.
+pub const DEFAULT_PRE_FINALIZATION_BLOCK_CACHE_SIZE: usize = 512;
+
+/// Default value for `ChainConfig::detailed_heads_max_age_slots`.
+pub const DEFAULT_DETAILED_HEADS_MAX_AGE_SLOTS: u64 = 8192;
+
+/// Default value for `ChainConfig::attestation_packing_max_iterations`.
+pub const DEFAULT_ATTESTATION_PACKING_MAX_ITERATIONS: usize = 128;
+
+/// Default value for `ChainConfig::attestation_packing_timeout_ms`.
+pub const DEFAULT_ATTESTATION_PACKING_TIMEOUT_MS: u64 = 25;
+
+/// Default value for `ChainConfig::finality_delay_warn_epochs`.
+pub const DEFAULT_FINALITY_DELAY_WARN_EPOCHS: u64 = 5;
+
+/// Default value for `ChainConfig::finality_delay_crit_epochs`.
+pub const DEFAULT_FINALITY_DELAY_CRIT_EPOCHS: u64 = 20;
+
+/// Default value for `ChainConfig::failure_log_window_secs`.
+pub const DEFAULT_FAILURE_LOG_WINDOW_SECS: u64 = 600;
+
+/// Default value for `ChainConfig::verification_pressure_latency_ms`.
+pub const DEFAULT_VERIFICATION_PRESSURE_LATENCY_MS: u64 = 250;
+
+/// Default value for `ChainConfig::verification_pressure_max_batches`.
+pub const DEFAULT_VERIFICATION_PRESSURE_MAX_BATCHES: usize = 8;
+
+/// Default value for `ChainConfig::prewarm_max_epochs`.
+pub const DEFAULT_PREWARM_MAX_EPOCHS: u64 = 4;
+
+/// The head is considered synced for `ShutdownTarget::HeadSynced` if it is at most this many slots
+/// behind the wall-clock slot.
+pub const HEAD_SYNCED_TOLERANCE_SLOTS: u64 = 2;
+
+/// A condition which, once reached, causes the node to persist its state and shut down.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Deserialize, Serialize)]
+pub enum ShutdownTarget {
+    /// The head is within `HEAD_SYNCED_TOLERANCE_SLOTS` of the wall-clock slot.
+    HeadSynced,
+    /// The head is at or beyond this slot.
+    Slot(Slot),
+    /// The finalized checkpoint is at or beyond this epoch.
+    FinalizedEpoch(Epoch),
+}
+
+/// Limits the rate at which historical blocks are imported by backfill sync.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Deserialize, Serialize)]
+pub struct BackfillRateLimit {
+    /// The maximum number of batches to import per slot.
+    pub batches_per_slot: u64,
+    /// A batch is always allowed once this many slots have passed without one, so that backfill
+    /// is never starved completely.
+    pub max_slots_between_batches: u64,
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Deserialize, Serialize)]
 pub struct ChainConfig {
     /// Maximum number of slots to skip when importing a consensus message (e.g., block,
@@ -24,6 +89,97 @@ pub struct ChainConfig {
     ///
     /// If set to 0 then block proposal will not wait for fork choice at all.
     pub fork_choice_before_proposal_timeout_ms: u64,
+    /// If this node has recently missed more than this many proposals which used a builder payload,
+    /// local payloads will be used instead of the builder.
+    pub builder_fallback_skips: usize,
+    /// If the chain has not finalized for more than this many epochs, local payloads will be used
+    /// instead of the builder.
+    pub builder_fallback_epochs_since_finality: u64,
+    /// The signed bids of builders whose payloads were used for block production are retained for
+    /// this many epochs.
+    pub builder_bid_retention_epochs: u64,
+    /// Whether to publish a signed blinded block on the blinded block gossip topic when the
+    /// builder withholds its payload, so that peers which subscribe to the topic may still obtain
+    /// the payload from the relay.
+    pub broadcast_withheld_blinded_blocks: bool,
+    /// Shut down once this target has been reached.
+    pub shutdown_after: Option<ShutdownTarget>,
+    /// Re-derive fork choice and rebuild caches from the database when resuming, rather than
+    /// trusting the persisted copies.
+    pub repair_on_startup: bool,
+    /// The maximum number of pre-finalization block roots to cache for the quick rejection of
+    /// attestations to them.
+    pub pre_finalization_block_cache_size: usize,
+    /// Limits the rate of backfill sync, leaving resources for forward sync and validator duties.
+    ///
+    /// If `None`, backfill is not rate limited.
+    pub backfill_rate_limit: Option<BackfillRateLimit>,
+    /// Heads more than this many slots older than the wall-clock slot are omitted from
+    /// `BeaconChain::detailed_heads`.
+    pub detailed_heads_max_age_slots: u64,
+    /// The maximum number of swaps made when improving upon the greedy packing of attestations
+    /// into a block.
+    ///
+    /// If set to 0 then the greedy packing is used.
+    pub attestation_packing_max_iterations: usize,
+    /// Number of milliseconds to spend improving upon the greedy packing of attestations into a
+    /// block.
+    ///
+    /// If set to 0 then the greedy packing is used.
+    pub attestation_packing_timeout_ms: u64,
+    /// Whether to delete the execution payloads of finalized blocks as the database is migrated.
+    ///
+    /// The payloads are reconstructed from the execution layer when the blocks are requested.
+    pub prune_payloads: bool,
+    /// A warning is logged each epoch whilst the wall-clock epoch is at least this many epochs
+    /// after the finalized epoch.
+    pub finality_delay_warn_epochs: u64,
+    /// A critical log is emitted each epoch whilst the wall-clock epoch is at least this many
+    /// epochs after the finalized epoch.
+    pub finality_delay_crit_epochs: u64,
+    /// Whether to pause backfill sync whilst the finality delay is at least
+    /// `finality_delay_crit_epochs`, conserving resources for the head of the chain.
+    pub finality_delay_pause_backfill: bool,
+    /// Failures which repeat each slot or head change, such as those of fork choice or the
+    /// execution layer, are logged at full severity at most once within this many seconds.
+    pub failure_log_window_secs: u64,
+    /// Whether to keep the states of the finalized and justified checkpoints in memory, so that
+    /// they can be served without reading the database.
+    pub cache_checkpoint_states: bool,
+    /// The maximum number of heads to persist. If the head tracker exceeds this, only the most
+    /// recent heads are persisted.
+    pub max_persisted_heads: usize,
+    /// Whether to defer, rather than reject, attestations to a block which is in the early
+    /// attester cache but not yet in fork choice.
+    pub defer_attestations_to_importing_blocks: bool,
+    /// Serve the existing database without importing blocks or attestations, running fork choice
+    /// or writing to the database.
+    pub read_only: bool,
+    /// Whether to log each transaction of a payload reconstructed by the execution layer which
+    /// does not match the header of its block. The transactions may be gigabytes in total.
+    pub log_inconsistent_payload_transactions: bool,
+    /// Attestation verification is under pressure whilst the moving average of the time taken to
+    /// verify a batch exceeds this many milliseconds.
+    pub verification_pressure_latency_ms: u64,
+    /// Attestation verification is under pressure whilst more than this many batches are being
+    /// verified concurrently.
+    pub verification_pressure_max_batches: usize,
+    /// The maximum memory of the chain's caches in megabytes, beyond which entries are evicted.
+    /// The snapshot of the canonical head is exempt. No limit is applied if `None`.
+    pub cache_memory_budget_mb: Option<u64>,
+    /// Whilst slots are being skipped, the committee caches are pre-warmed for the current epoch
+    /// only if the head state must be advanced at most this many epochs. Zero disables this.
+    pub prewarm_max_epochs: u64,
+    /// Whether to heal divergence between the canonical head snapshot, fork choice and the head
+    /// tracker by re-running fork choice and registering the head, rather than only reporting it.
+    pub heal_head_divergence: bool,
+    /// Whether to permit `BeaconChain::process_block_unchecked_gossip`, which imports blocks
+    /// without the gossip checks. This is intended only for fuzzers and fork-testing tools.
+    pub allow_unchecked_gossip_blocks: bool,
+    /// Whether to produce attestations to slots beyond the `slots_per_historical_root` window of
+    /// the head state from a historical state, rather than refusing them. Loading the state may
+    /// be slow, so this is intended for lagging validator clients and testing.
+    pub allow_ancient_attestation_production: bool,
 }
 
 impl Default for ChainConfig {
@@ -35,6 +191,34 @@ impl Default for ChainConfig {
             enable_lock_timeouts: true,
             max_network_size: 10 * 1_048_576, // 10M
             fork_choice_before_proposal_timeout_ms: DEFAULT_FORK_CHOICE_BEFORE_PROPOSAL_TIMEOUT,
+            builder_fallback_skips: DEFAULT_BUILDER_FALLBACK_SKIPS,
+            builder_fallback_epochs_since_finality: DEFAULT_BUILDER_FALLBACK_EPOCHS_SINCE_FINALITY,
+            builder_bid_retention_epochs: DEFAULT_BUILDER_BID_RETENTION_EPOCHS,
+            broadcast_withheld_blinded_blocks: false,
+            shutdown_after: None,
+            repair_on_startup: false,
+            pre_finalization_block_cache_size: DEFAULT_PRE_FINALIZATION_BLOCK_CACHE_SIZE,
+            backfill_rate_limit: None,
+            detailed_heads_max_age_slots: DEFAULT_DETAILED_HEADS_MAX_AGE_SLOTS,
+            attestation_packing_max_iterations: DEFAULT_ATTESTATION_PACKING_MAX_ITERATIONS,
+            attestation_packing_timeout_ms: DEFAULT_ATTESTATION_PACKING_TIMEOUT_MS,
+            prune_payloads: true,
+            finality_delay_warn_epochs: DEFAULT_FINALITY_DELAY_WARN_EPOCHS,
+            finality_delay_crit_epochs: DEFAULT_FINALITY_DELAY_CRIT_EPOCHS,
+            finality_delay_pause_backfill: false,
+            failure_log_window_secs: DEFAULT_FAILURE_LOG_WINDOW_SECS,
+            cache_checkpoint_states: true,
+            max_persisted_heads: MAX_SSZ_HEADS,
+            defer_attestations_to_importing_blocks: true,
+            read_only: false,
+            log_inconsistent_payload_transactions: false,
+            verification_pressure_latency_ms: DEFAULT_VERIFICATION_PRESSURE_LATENCY_MS,
+            verification_pressure_max_batches: DEFAULT_VERIFICATION_PRESSURE_MAX_BATCHES,
+            cache_memory_budget_mb: None,
+            prewarm_max_epochs: DEFAULT_PREWARM_MAX_EPOCHS,
+            heal_head_divergence: false,
+            allow_unchecked_gossip_blocks: false,
+            allow_ancient_attestation_production: false,
         }
     }
 }
@@ -0,0 +1,236 @@
+//! Caches the states of the finalized and justified checkpoints.
+//!
+//! These states are requested repeatedly, e.g. by HTTP API queries of the validator set at
+//! `finalized`, and each request would otherwise read the state from the database. The states are
+//! refreshed when the chain finalizes, and are otherwise loaded by the first request after their
+//! checkpoint changes. The cost is the memory of two states, so the cache may be disabled with
+//! `ChainConfig::cache_checkpoint_states`.
+//!
+//! The state of a checkpoint is the state at the first slot of its epoch on the canonical chain,
+//! as per the `finalized` and `justified` state identifiers of the HTTP API.
+use crate::{
+    cache_memory::state_memory_usage, BeaconChain, BeaconChainError as Error, BeaconChainTypes,
+};
+use parking_lot::{Mutex, RwLock};
+use slog::{debug, warn};
+use std::sync::Arc;
+use types::{BeaconState, Checkpoint, EthSpec, Hash256};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckpointKind {
+    Finalized,
+    Justified,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct CheckpointStateCacheStats {
+    /// Number of requests served from the cache.
+    pub hits: u64,
+    /// Number of states read from the database.
+    pub store_loads: u64,
+}
+
+struct CachedState<E: EthSpec> {
+    checkpoint: Checkpoint,
+    state: Arc<BeaconState<E>>,
+}
+
+pub struct CheckpointStateCache<E: EthSpec> {
+    finalized: RwLock<Option<CachedState<E>>>,
+    justified: RwLock<Option<CachedState<E>>>,
+    stats: Mutex<CheckpointStateCacheStats>,
+}
+
+impl<E: EthSpec> Default for CheckpointStateCache<E> {
+    fn default() -> Self {
+        Self {
+            finalized: RwLock::new(None),
+            justified: RwLock::new(None),
+            stats: Mutex::new(CheckpointStateCacheStats::default()),
+        }
+    }
+}
+
+impl<E: EthSpec> CheckpointStateCache<E> {
+    fn cached_state(&self, kind: CheckpointKind) -> &RwLock<Option<CachedState<E>>> {
+        match kind {
+            CheckpointKind::Finalized => &self.finalized,
+            CheckpointKind::Justified => &self.justified,
+        }
+    }
+
+    /// Returns the state of `checkpoint`, if it is the cached checkpoint of `kind`.
+    pub fn get(&self, kind: CheckpointKind, checkpoint: Checkpoint) -> Option<Arc<BeaconState<E>>> {
+        let state = self
+            .cached_state(kind)
+            .read()
+            .as_ref()
+            .filter(|cached| cached.checkpoint == checkpoint)
+            .map(|cached| cached.state.clone());
+        if state.is_some() {
+            self.stats.lock().hits += 1;
+        }
+        state
+    }
+
+    /// Returns `true` if the state of `checkpoint` is cached as the checkpoint of `kind`.
+    pub fn contains(&self, kind: CheckpointKind, checkpoint: Checkpoint) -> bool {
+        self.cached_state(kind)
+            .read()
+            .as_ref()
+            .map_or(false, |cached| cached.checkpoint == checkpoint)
+    }
+
+    /// Cache `state` as the state of `checkpoint`, replacing the cached checkpoint of `kind`.
+    pub fn insert(&self, kind: CheckpointKind, checkpoint: Checkpoint, state: Arc<BeaconState<E>>) {
+        *self.cached_state(kind).write() = Some(CachedState { checkpoint, state });
+    }
+
+    pub fn stats(&self) -> CheckpointStateCacheStats {
+        *self.stats.lock()
+    }
+
+    fn kind_memory_usage(&self, kind: CheckpointKind) -> usize {
+        self.cached_state(kind)
+            .read()
+            .as_ref()
+            .map_or(0, |cached| state_memory_usage(&cached.state))
+    }
+
+    /// Returns an estimate of the memory used by the cached states, in bytes.
+    pub fn memory_usage(&self) -> usize {
+        self.kind_memory_usage(CheckpointKind::Finalized)
+            + self.kind_memory_usage(CheckpointKind::Justified)
+    }
+
+    /// Drop cached states until at most `max_bytes` are used. The justified state is dropped
+    /// first, since the finalized state changes less often.
+    pub fn shrink_to(&self, max_bytes: usize) {
+        for kind in [CheckpointKind::Justified, CheckpointKind::Finalized] {
+            if self.memory_usage() <= max_bytes {
+                break;
+            }
+            *self.cached_state(kind).write() = None;
+        }
+    }
+}
+
+impl<T: BeaconChainTypes> BeaconChain<T> {
+    /// Returns the state of the finalized checkpoint.
+    ///
+    /// The state is served from the checkpoint state cache if possible, otherwise it is read from
+    /// the database.
+    pub fn finalized_state(&self) -> Result<Arc<BeaconState<T::EthSpec>>, Error> {
+        self.checkpoint_state(CheckpointKind::Finalized)
+    }
+
+    /// Returns the state of the justified checkpoint.
+    ///
+    /// The state is served from the checkpoint state cache if possible, otherwise it is read from
+    /// the database.
+    pub fn justified_state(&self) -> Result<Arc<BeaconState<T::EthSpec>>, Error> {
+        self.checkpoint_state(CheckpointKind::Justified)
+    }
+
+    pub fn checkpoint_state_cache_stats(&self) -> CheckpointStateCacheStats {
+        self.checkpoint_state_cache.stats()
+    }
+
+    /// Returns the state of the checkpoint of `kind`, from the cache if possible.
+    pub fn checkpoint_state(
+        &self,
+        kind: CheckpointKind,
+    ) -> Result<Arc<BeaconState<T::EthSpec>>, Error> {
+        let checkpoint = {
+            let head = self.canonical_head.cached_head();
+            match kind {
+                CheckpointKind::Finalized => head.finalized_checkpoint(),
+                CheckpointKind::Justified => head.justified_checkpoint(),
+            }
+        };
+
+        if self.config.cache_checkpoint_states {
+            if let Some(state) = self.checkpoint_state_cache.get(kind, checkpoint) {
+                return Ok(state);
+            }
+        }
+
+        let slot = checkpoint.epoch.start_slot(T::EthSpec::slots_per_epoch());
+        let state_root = self
+            .state_root_at_slot(slot)?
+            .ok_or(Error::MissingCheckpointStateRoot(checkpoint))?;
+        self.load_checkpoint_state(kind, checkpoint, state_root)
+    }
+
+    /// Read the state of `checkpoint` from the database, caching it if enabled.
+    fn load_checkpoint_state(
+        &self,
+        kind: CheckpointKind,
+        checkpoint: Checkpoint,
+        state_root: Hash256,
+    ) -> Result<Arc<BeaconState<T::EthSpec>>, Error> {
+        let slot = checkpoint.epoch.start_slot(T::EthSpec::slots_per_epoch());
+        let state = self
+            .get_state(&state_root, Some(slot))?
+            .ok_or(Error::MissingBeaconState(state_root))?;
+        let state = Arc::new(state);
+        self.checkpoint_state_cache.stats.lock().store_loads += 1;
+
+        if self.config.cache_checkpoint_states {
+            self.checkpoint_state_cache
+                .insert(kind, checkpoint, state.clone());
+        }
+        Ok(state)
+    }
+
+    /// Refresh the checkpoint state cache after the finalized checkpoint has changed.
+    ///
+    /// The `finalized_state_root` is the root of the state at the first slot of the finalized
+    /// epoch. The states are loaded before the database migration moves the finalized state to the
+    /// freezer database.
+    pub(crate) fn refresh_checkpoint_state_cache(
+        &self,
+        finalized_checkpoint: Checkpoint,
+        finalized_state_root: Hash256,
+        justified_checkpoint: Checkpoint,
+    ) {
+        if !self.config.cache_checkpoint_states {
+            return;
+        }
+
+        if let Err(e) = self.load_checkpoint_state(
+            CheckpointKind::Finalized,
+            finalized_checkpoint,
+            finalized_state_root,
+        ) {
+            warn!(
+                self.log,
+                "Failed to cache finalized state";
+                "error" => ?e,
+                "epoch" => finalized_checkpoint.epoch,
+            );
+        }
+
+        // The justified state is only loaded if it changed, which it usually has.
+        if !self
+            .checkpoint_state_cache
+            .contains(CheckpointKind::Justified, justified_checkpoint)
+        {
+            if let Err(e) = self.checkpoint_state(CheckpointKind::Justified) {
+                warn!(
+                    self.log,
+                    "Failed to cache justified state";
+                    "error" => ?e,
+                    "epoch" => justified_checkpoint.epoch,
+                );
+            }
+        }
+
+        debug!(
+            self.log,
+            "Checkpoint state cache refreshed";
+            "finalized_epoch" => finalized_checkpoint.epoch,
+            "justified_epoch" => justified_checkpoint.epoch,
+        );
+    }
+}
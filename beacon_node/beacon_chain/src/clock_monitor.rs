@@ -0,0 +1,247 @@
+//! Detects jumps in the wall-clock slot, e.g. when NTP steps the system clock.
+//!
+//! The per-slot task observes the current slot once per slot. A slot earlier than the last one
+//! observed, or more than `CLOCK_JUMP_TOLERANCE_SLOTS` after it, is reported as a jump. For
+//! `CLOCK_JUMP_RECOVERY_SLOTS` after a jump the gossip clock disparity tolerance is widened, since
+//! our clock may still disagree with those of our peers.
+use crate::beacon_chain::MAXIMUM_GOSSIP_CLOCK_DISPARITY;
+use crate::{metrics, BeaconChain, BeaconChainTypes};
+use parking_lot::Mutex;
+use slog::{crit, info};
+use std::time::Duration;
+use types::{EthSpec, Slot};
+
+/// The slot may advance by up to this many slots between observations without it being reported
+/// as a jump, allowing for a delayed per-slot task.
+pub const CLOCK_JUMP_TOLERANCE_SLOTS: u64 = 2;
+
+/// The number of slots after a jump for which `CLOCK_JUMP_GOSSIP_CLOCK_DISPARITY` is used.
+pub const CLOCK_JUMP_RECOVERY_SLOTS: u64 = 32;
+
+/// The gossip clock disparity tolerance used whilst recovering from a jump.
+pub const CLOCK_JUMP_GOSSIP_CLOCK_DISPARITY: Duration = Duration::from_millis(2_000);
+
+/// A discontinuity in the wall-clock slot.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ClockJump {
+    Backwards { from: Slot, to: Slot },
+    Forwards { from: Slot, to: Slot },
+}
+
+/// The health of the wall clock, as observed by the per-slot task.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ClockStatus {
+    /// The slot most recently observed.
+    pub last_observed_slot: Option<Slot>,
+    /// The most recent jump, if any.
+    pub last_jump: Option<ClockJump>,
+    /// Whether the gossip clock disparity tolerance is currently widened following a jump.
+    pub recovering: bool,
+}
+
+#[derive(Default)]
+struct MonitorState {
+    last_slot: Option<Slot>,
+    last_jump: Option<ClockJump>,
+    /// The slot at which recovery from the last jump ends.
+    recovery_end: Option<Slot>,
+}
+
+#[derive(Default)]
+pub struct ClockMonitor {
+    state: Mutex<MonitorState>,
+}
+
+impl ClockMonitor {
+    /// Record that the wall-clock slot is `slot`, returning the jump since the previously observed
+    /// slot, if any.
+    pub fn observe(&self, slot: Slot) -> Option<ClockJump> {
+        let mut state = self.state.lock();
+
+        let jump = state.last_slot.and_then(|last_slot| {
+            if slot < last_slot {
+                Some(ClockJump::Backwards {
+                    from: last_slot,
+                    to: slot,
+                })
+            } else if slot > last_slot + CLOCK_JUMP_TOLERANCE_SLOTS {
+                Some(ClockJump::Forwards {
+                    from: last_slot,
+                    to: slot,
+                })
+            } else {
+                None
+            }
+        });
+        state.last_slot = Some(slot);
+
+        if jump.is_some() {
+            state.last_jump = jump;
+            state.recovery_end = Some(slot + CLOCK_JUMP_RECOVERY_SLOTS);
+        } else if state.recovery_end.map_or(false, |end| slot >= end) {
+            state.recovery_end = None;
+        }
+
+        jump
+    }
+
+    pub fn status(&self) -> ClockStatus {
+        let state = self.state.lock();
+        ClockStatus {
+            last_observed_slot: state.last_slot,
+            last_jump: state.last_jump,
+            recovering: state.recovery_end.is_some(),
+        }
+    }
+
+    /// The tolerance for the clocks of gossip peers being ahead of or behind our own.
+    pub fn gossip_clock_disparity(&self) -> Duration {
+        if self.state.lock().recovery_end.is_some() {
+            CLOCK_JUMP_GOSSIP_CLOCK_DISPARITY
+        } else {
+            MAXIMUM_GOSSIP_CLOCK_DISPARITY
+        }
+    }
+}
+
+impl<T: BeaconChainTypes> BeaconChain<T> {
+    /// Returns the health of the wall clock, as observed by the per-slot task.
+    pub fn clock_status(&self) -> ClockStatus {
+        self.clock_monitor.status()
+    }
+
+    /// The tolerance for the clocks of gossip peers being ahead of or behind our own, which is
+    /// widened for a while after our clock jumps.
+    pub fn gossip_clock_disparity(&self) -> Duration {
+        self.clock_monitor.gossip_clock_disparity()
+    }
+
+    /// Observe the wall-clock `slot` from the per-slot task, and if it has jumped re-prune the
+    /// caches which track the current slot.
+    pub(crate) fn observe_clock_slot(&self, slot: Slot) {
+        let jump = match self.clock_monitor.observe(slot) {
+            Some(jump) => jump,
+            None => return,
+        };
+        metrics::inc_counter(&metrics::CLOCK_JUMPS_TOTAL);
+
+        match jump {
+            ClockJump::Backwards { from, to } => crit!(
+                self.log,
+                "System clock jumped backwards";
+                "msg" => "check the time synchronisation of this host (e.g. NTP)",
+                "from_slot" => from,
+                "to_slot" => to,
+            ),
+            ClockJump::Forwards { from, to } => crit!(
+                self.log,
+                "System clock jumped forwards";
+                "msg" => "check the time synchronisation of this host (e.g. NTP)",
+                "from_slot" => from,
+                "to_slot" => to,
+            ),
+        }
+
+        self.prune_caches_after_clock_jump(slot);
+        info!(
+            self.log,
+            "Widened gossip clock disparity after clock jump";
+            "tolerance_ms" => CLOCK_JUMP_GOSSIP_CLOCK_DISPARITY.as_millis(),
+            "slots" => CLOCK_JUMP_RECOVERY_SLOTS,
+        );
+    }
+
+    /// Prune the caches of gossip messages relative to the current `slot`.
+    ///
+    /// The caches otherwise only move forwards with the slots of the messages given to them. After
+    /// a backwards jump they would reject messages from the current slot, and they would hold
+    /// messages from slots which are now in the future.
+    fn prune_caches_after_clock_jump(&self, slot: Slot) {
+        let epoch = slot.epoch(T::EthSpec::slots_per_epoch());
+
+        let mut naive_aggregation_pool = self.naive_aggregation_pool.write();
+        naive_aggregation_pool.prune_future(slot);
+        naive_aggregation_pool.prune(slot);
+        drop(naive_aggregation_pool);
+        let mut naive_sync_aggregation_pool = self.naive_sync_aggregation_pool.write();
+        naive_sync_aggregation_pool.prune_future(slot);
+        naive_sync_aggregation_pool.prune(slot);
+        drop(naive_sync_aggregation_pool);
+
+        self.observed_attestations.write().prune(slot);
+        self.observed_sync_contributions.write().prune(slot);
+        self.observed_gossip_attesters.write().prune(epoch);
+        self.observed_block_attesters.write().prune(epoch);
+        self.observed_aggregators.write().prune(epoch);
+        self.observed_sync_contributors.write().prune(slot);
+        self.observed_sync_aggregators.write().prune(slot);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn steady_progression() {
+        let monitor = ClockMonitor::default();
+        for slot in [0, 1, 1, 2, 4] {
+            assert_eq!(monitor.observe(Slot::new(slot)), None);
+        }
+        assert_eq!(
+            monitor.status(),
+            ClockStatus {
+                last_observed_slot: Some(Slot::new(4)),
+                last_jump: None,
+                recovering: false,
+            }
+        );
+        assert_eq!(
+            monitor.gossip_clock_disparity(),
+            MAXIMUM_GOSSIP_CLOCK_DISPARITY
+        );
+    }
+
+    #[test]
+    fn jumps_and_recovery() {
+        let monitor = ClockMonitor::default();
+        monitor.observe(Slot::new(10));
+
+        let backwards = ClockJump::Backwards {
+            from: Slot::new(10),
+            to: Slot::new(9),
+        };
+        assert_eq!(monitor.observe(Slot::new(9)), Some(backwards));
+        assert!(monitor.status().recovering);
+        assert_eq!(
+            monitor.gossip_clock_disparity(),
+            CLOCK_JUMP_GOSSIP_CLOCK_DISPARITY
+        );
+
+        let forwards = ClockJump::Forwards {
+            from: Slot::new(9),
+            to: Slot::new(13),
+        };
+        assert_eq!(monitor.observe(Slot::new(13)), Some(forwards));
+
+        // Recovery lasts from the most recent jump.
+        let recovery_end = 13 + CLOCK_JUMP_RECOVERY_SLOTS;
+        for slot in 14..recovery_end {
+            assert_eq!(monitor.observe(Slot::new(slot)), None);
+            assert!(monitor.status().recovering);
+        }
+        assert_eq!(monitor.observe(Slot::new(recovery_end)), None);
+        assert_eq!(
+            monitor.status(),
+            ClockStatus {
+                last_observed_slot: Some(Slot::new(recovery_end)),
+                last_jump: Some(forwards),
+                recovering: false,
+            }
+        );
+        assert_eq!(
+            monitor.gossip_clock_disparity(),
+            MAXIMUM_GOSSIP_CLOCK_DISPARITY
+        );
+    }
+}
@@ -0,0 +1,179 @@
+//! Provides a small memo of the committees of recent slots, consulted by attestation verification
+//! before the `ShufflingCache`.
+//!
+//! Within a slot, the same few hundred `(slot, index)` pairs are looked up for every attestation
+//! received on gossip. Reading the `ShufflingCache` requires its write lock (since the LRU is
+//! updated on each read) and a search of the LRU, so this memo retains only the committees of the
+//! most recent slots behind a read-mostly lock.
+//!
+//! The committees of each slot are tagged with their shuffling decision root. A committee is only
+//! served when the decision root of the attestation's target matches, so an attestation on another
+//! fork (e.g. after the head changes mid-slot) falls back to the `ShufflingCache` and replaces the
+//! memoized committees of that slot.
+//!
+//! The full committee is retained rather than just its length, since the validator indices are
+//! required to produce the indexed attestation.
+use parking_lot::RwLock;
+use std::collections::HashMap;
+use std::sync::Arc;
+use types::{BeaconCommittee, CommitteeIndex, Hash256, Slot};
+
+/// The number of slots, up to and including the latest memoized slot, whose committees are
+/// retained.
+pub const COMMITTEE_MEMO_SLOTS: u64 = 2;
+
+/// A committee, as read from the `ShufflingCache`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemoizedCommittee {
+    pub slot: Slot,
+    pub index: CommitteeIndex,
+    /// The indices of the validators in the committee.
+    pub committee: Arc<[usize]>,
+    pub committees_per_slot: u64,
+}
+
+impl MemoizedCommittee {
+    pub fn new(committee: &BeaconCommittee, committees_per_slot: u64) -> Self {
+        Self {
+            slot: committee.slot,
+            index: committee.index,
+            committee: committee.committee.into(),
+            committees_per_slot,
+        }
+    }
+
+    pub fn beacon_committee(&self) -> BeaconCommittee {
+        BeaconCommittee {
+            slot: self.slot,
+            index: self.index,
+            committee: &self.committee,
+        }
+    }
+}
+
+struct SlotCommittees {
+    shuffling_decision_root: Hash256,
+    committees: HashMap<CommitteeIndex, MemoizedCommittee>,
+}
+
+#[derive(Default)]
+pub struct CommitteeMemo {
+    slots: RwLock<HashMap<Slot, SlotCommittees>>,
+}
+
+impl CommitteeMemo {
+    /// Returns the committee for `(slot, index)`, if it is memoized for the shuffling decided by
+    /// `shuffling_decision_root`.
+    pub fn get(
+        &self,
+        slot: Slot,
+        index: CommitteeIndex,
+        shuffling_decision_root: Hash256,
+    ) -> Option<MemoizedCommittee> {
+        let slots = self.slots.read();
+        let slot_committees = slots.get(&slot)?;
+        if slot_committees.shuffling_decision_root != shuffling_decision_root {
+            return None;
+        }
+        slot_committees.committees.get(&index).cloned()
+    }
+
+    /// Memoize `committee`, which was taken from the shuffling decided by
+    /// `shuffling_decision_root`.
+    ///
+    /// The committees of its slot from any other shuffling are discarded, as are the committees of
+    /// slots prior to the most recent `COMMITTEE_MEMO_SLOTS`.
+    pub fn insert(&self, shuffling_decision_root: Hash256, committee: MemoizedCommittee) {
+        let mut slots = self.slots.write();
+
+        let latest_slot = slots
+            .keys()
+            .copied()
+            .chain(std::iter::once(committee.slot))
+            .max()
+            .unwrap_or(committee.slot);
+        if committee.slot + COMMITTEE_MEMO_SLOTS <= latest_slot {
+            return;
+        }
+        slots.retain(|slot, _| *slot + COMMITTEE_MEMO_SLOTS > latest_slot);
+
+        let slot_committees = slots
+            .entry(committee.slot)
+            .or_insert_with(|| SlotCommittees {
+                shuffling_decision_root,
+                committees: HashMap::new(),
+            });
+        if slot_committees.shuffling_decision_root != shuffling_decision_root {
+            slot_committees.shuffling_decision_root = shuffling_decision_root;
+            slot_committees.committees.clear();
+        }
+        slot_committees
+            .committees
+            .insert(committee.index, committee);
+    }
+
+    /// Discard all memoized committees.
+    pub fn clear(&self) {
+        self.slots.write().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn committee(slot: u64, index: u64, members: &[usize]) -> MemoizedCommittee {
+        MemoizedCommittee {
+            slot: Slot::new(slot),
+            index,
+            committee: members.into(),
+            committees_per_slot: 1,
+        }
+    }
+
+    #[test]
+    fn committees_are_tagged_with_decision_root() {
+        let memo = CommitteeMemo::default();
+        let root_a = Hash256::from_low_u64_be(1);
+        let root_b = Hash256::from_low_u64_be(2);
+        let slot = Slot::new(10);
+
+        memo.insert(root_a, committee(10, 0, &[1, 2, 3]));
+        memo.insert(root_a, committee(10, 1, &[4, 5]));
+        assert_eq!(
+            memo.get(slot, 0, root_a),
+            Some(committee(10, 0, &[1, 2, 3]))
+        );
+        assert_eq!(memo.get(slot, 1, root_a), Some(committee(10, 1, &[4, 5])));
+        assert_eq!(memo.get(slot, 2, root_a), None);
+        assert_eq!(memo.get(slot, 0, root_b), None);
+
+        // A committee from another shuffling replaces all committees of the slot.
+        memo.insert(root_b, committee(10, 0, &[3, 2]));
+        assert_eq!(memo.get(slot, 0, root_b), Some(committee(10, 0, &[3, 2])));
+        assert_eq!(memo.get(slot, 0, root_a), None);
+        assert_eq!(memo.get(slot, 1, root_a), None);
+    }
+
+    #[test]
+    fn old_slots_are_pruned() {
+        let memo = CommitteeMemo::default();
+        let root = Hash256::from_low_u64_be(1);
+
+        memo.insert(root, committee(10, 0, &[1]));
+        memo.insert(root, committee(11, 0, &[2]));
+        assert!(memo.get(Slot::new(10), 0, root).is_some());
+
+        memo.insert(root, committee(12, 0, &[3]));
+        assert_eq!(memo.get(Slot::new(10), 0, root), None);
+        assert!(memo.get(Slot::new(11), 0, root).is_some());
+        assert!(memo.get(Slot::new(12), 0, root).is_some());
+
+        // Committees of slots which are already too old aren't memoized.
+        memo.insert(root, committee(10, 0, &[1]));
+        assert_eq!(memo.get(Slot::new(10), 0, root), None);
+
+        memo.clear();
+        assert_eq!(memo.get(Slot::new(12), 0, root), None);
+    }
+}
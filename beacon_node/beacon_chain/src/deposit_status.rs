@@ -0,0 +1,258 @@
+//! Reports how far a validator's deposit has progressed, from the eth1 deposit cache through
+//! inclusion in a block to activation, along with a summary of the deposits and validators which
+//! are queued for activation.
+//!
+//! Everything is derived from a single read of the head state and the eth1 deposit cache. The
+//! estimated activation epochs assume that the churn limit of the head state holds and that the
+//! chain finalizes promptly, so that queued validators are dequeued as soon as the spec permits.
+use crate::{BeaconChain, BeaconChainError as Error, BeaconChainTypes};
+use eth2::types::ValidatorId;
+use std::cmp::max;
+use types::{BeaconState, BeaconStateError, ChainSpec, Epoch, EthSpec};
+
+/// The progress of a validator's deposit.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DepositStatus {
+    /// Neither the eth1 deposit cache nor the head state know of the validator.
+    Unknown,
+    /// The deposit is in the eth1 deposit cache but has yet to be included in a block.
+    InEth1Cache { deposit_index: u64 },
+    /// The deposit has been included in a block, but the validator is yet to become eligible for
+    /// the activation queue (e.g., its balance is below the maximum effective balance).
+    Included {
+        validator_index: u64,
+        /// The index of the deposit, if it's in the eth1 deposit cache.
+        deposit_index: Option<u64>,
+    },
+    /// The validator is in the activation queue, or has left it and awaits its activation epoch.
+    PendingActivation {
+        validator_index: u64,
+        /// The index of the deposit, if it's in the eth1 deposit cache.
+        deposit_index: Option<u64>,
+        /// The number of validators ahead in the activation queue. Zero once the validator has
+        /// left the queue.
+        queue_position: u64,
+        /// The estimated activation epoch, which is exact once the validator has left the queue.
+        estimated_activation_epoch: Epoch,
+    },
+    /// The validator has been activated. It may since have exited.
+    Active {
+        validator_index: u64,
+        /// The index of the deposit, if it's in the eth1 deposit cache.
+        deposit_index: Option<u64>,
+        activation_epoch: Epoch,
+    },
+}
+
+/// A summary of the deposits and validators which are yet to be activated.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PendingDepositQueueSummary {
+    /// The number of deposits in the eth1 deposit cache which have yet to be included in a block.
+    pub eth1_cached_deposits: u64,
+    /// The number of deposits which the head state's eth1 data includes but which have yet to be
+    /// included in a block.
+    pub awaiting_inclusion: u64,
+    /// The number of validators which are yet to become eligible for the activation queue.
+    pub awaiting_eligibility: u64,
+    /// The number of validators in the activation queue.
+    pub activation_queue_length: u64,
+    /// The number of validators which have left the queue and await their activation epoch.
+    pub awaiting_activation_epoch: u64,
+    /// The maximum number of validators which leave the activation queue each epoch.
+    pub churn_limit: u64,
+    /// The estimated activation epoch of the last validator in the activation queue.
+    pub estimated_queue_clear_epoch: Option<Epoch>,
+}
+
+/// The activation queue of a state, in the order in which validators leave it.
+struct ActivationQueue {
+    /// The indices of the validators in the queue.
+    indices: Vec<usize>,
+    churn_limit: u64,
+}
+
+impl ActivationQueue {
+    fn new<E: EthSpec>(state: &BeaconState<E>, spec: &ChainSpec) -> Self {
+        let current_epoch = state.current_epoch();
+        let mut queue = vec![];
+        let mut active_validators = 0;
+        for (index, validator) in state.validators().iter().enumerate() {
+            if validator.is_active_at(current_epoch) {
+                active_validators += 1;
+            }
+            if validator.activation_eligibility_epoch != spec.far_future_epoch
+                && validator.activation_epoch == spec.far_future_epoch
+            {
+                queue.push((validator.activation_eligibility_epoch, index));
+            }
+        }
+        queue.sort_unstable();
+
+        Self {
+            indices: queue.into_iter().map(|(_, index)| index).collect(),
+            churn_limit: max(
+                spec.min_per_epoch_churn_limit,
+                active_validators / spec.churn_limit_quotient,
+            ),
+        }
+    }
+
+    /// Estimate the activation epoch of the validator at `position` in the queue.
+    ///
+    /// A validator leaves the queue during the processing of the first epoch in which it's within
+    /// the churn limit of the front of the queue and the epoch in which it became eligible has
+    /// been finalized. The eligibility epoch is finalized one epoch after it ends at the earliest.
+    fn estimated_activation_epoch<E: EthSpec>(
+        &self,
+        position: usize,
+        state: &BeaconState<E>,
+        spec: &ChainSpec,
+    ) -> Result<Epoch, Error> {
+        let current_epoch = state.current_epoch();
+        let eligibility_epoch = state
+            .get_validator(self.indices[position])
+            .map_err(Error::BeaconStateError)?
+            .activation_eligibility_epoch;
+        let earliest_dequeue_epoch = if eligibility_epoch <= state.finalized_checkpoint().epoch {
+            current_epoch
+        } else {
+            max(current_epoch, eligibility_epoch + 1)
+        };
+        let dequeue_epoch = max(
+            current_epoch + position as u64 / self.churn_limit,
+            earliest_dequeue_epoch,
+        );
+        state
+            .compute_activation_exit_epoch(dequeue_epoch, spec)
+            .map_err(Error::BeaconStateError)
+    }
+}
+
+impl<T: BeaconChainTypes> BeaconChain<T> {
+    /// Returns the progress of the deposit of the validator with `validator_id`.
+    ///
+    /// A public key is looked up in both the head state and the eth1 deposit cache, whilst an index
+    /// is only looked up in the head state.
+    pub fn deposit_status(&self, validator_id: &ValidatorId) -> Result<DepositStatus, Error> {
+        let head = self.head_snapshot();
+        let state = &head.beacon_state;
+
+        let (pubkey, validator_index) = match validator_id {
+            ValidatorId::PublicKey(pubkey) => {
+                // The pubkey cache may contain validators from other forks.
+                let validator_index = self.validator_index(pubkey)?.filter(|&index| {
+                    state
+                        .validators()
+                        .get(index)
+                        .map_or(false, |validator| validator.pubkey == *pubkey)
+                });
+                (*pubkey, validator_index)
+            }
+            ValidatorId::Index(index) => match state.validators().get(*index as usize) {
+                Some(validator) => (validator.pubkey, Some(*index as usize)),
+                None => return Ok(DepositStatus::Unknown),
+            },
+        };
+
+        let deposit_index = self
+            .eth1_chain
+            .as_ref()
+            .and_then(|eth1_chain| eth1_chain.deposit_index(&pubkey));
+
+        let validator_index = match (validator_index, deposit_index) {
+            (Some(validator_index), _) => validator_index,
+            (None, Some(deposit_index)) => return Ok(DepositStatus::InEth1Cache { deposit_index }),
+            (None, None) => return Ok(DepositStatus::Unknown),
+        };
+        let validator = state
+            .get_validator(validator_index)
+            .map_err(Error::BeaconStateError)?;
+
+        let status = if validator.activation_epoch <= state.current_epoch() {
+            DepositStatus::Active {
+                validator_index: validator_index as u64,
+                deposit_index,
+                activation_epoch: validator.activation_epoch,
+            }
+        } else if validator.activation_epoch != self.spec.far_future_epoch {
+            DepositStatus::PendingActivation {
+                validator_index: validator_index as u64,
+                deposit_index,
+                queue_position: 0,
+                estimated_activation_epoch: validator.activation_epoch,
+            }
+        } else if validator.activation_eligibility_epoch != self.spec.far_future_epoch {
+            let queue = ActivationQueue::new(state, &self.spec);
+            let position = queue
+                .indices
+                .iter()
+                .position(|&index| index == validator_index)
+                .ok_or(Error::BeaconStateError(BeaconStateError::UnknownValidator(
+                    validator_index,
+                )))?;
+            DepositStatus::PendingActivation {
+                validator_index: validator_index as u64,
+                deposit_index,
+                queue_position: position as u64,
+                estimated_activation_epoch: queue
+                    .estimated_activation_epoch(position, state, &self.spec)?,
+            }
+        } else {
+            DepositStatus::Included {
+                validator_index: validator_index as u64,
+                deposit_index,
+            }
+        };
+
+        Ok(status)
+    }
+
+    /// Returns a summary of the deposits and validators which are yet to be activated.
+    pub fn pending_deposit_queue_summary(&self) -> Result<PendingDepositQueueSummary, Error> {
+        let head = self.head_snapshot();
+        let state = &head.beacon_state;
+        let current_epoch = state.current_epoch();
+        let far_future_epoch = self.spec.far_future_epoch;
+
+        let eth1_deposit_index = state.eth1_deposit_index();
+        let eth1_cached_deposits = self
+            .eth1_chain
+            .as_ref()
+            .map_or(0, |eth1_chain| eth1_chain.deposit_count())
+            .saturating_sub(eth1_deposit_index);
+
+        let validators = state.validators();
+        let awaiting_eligibility = validators
+            .iter()
+            .filter(|validator| validator.activation_eligibility_epoch == far_future_epoch)
+            .count();
+        let awaiting_activation_epoch = validators
+            .iter()
+            .filter(|validator| {
+                validator.activation_epoch != far_future_epoch
+                    && validator.activation_epoch > current_epoch
+            })
+            .count();
+
+        let queue = ActivationQueue::new(state, &self.spec);
+        let estimated_queue_clear_epoch = queue
+            .indices
+            .len()
+            .checked_sub(1)
+            .map(|last| queue.estimated_activation_epoch(last, state, &self.spec))
+            .transpose()?;
+
+        Ok(PendingDepositQueueSummary {
+            eth1_cached_deposits,
+            awaiting_inclusion: state
+                .eth1_data()
+                .deposit_count
+                .saturating_sub(eth1_deposit_index),
+            awaiting_eligibility: awaiting_eligibility as u64,
+            activation_queue_length: queue.indices.len() as u64,
+            awaiting_activation_epoch: awaiting_activation_epoch as u64,
+            churn_limit: queue.churn_limit,
+            estimated_queue_clear_epoch,
+        })
+    }
+}
@@ -4,7 +4,9 @@ use crate::{
 };
 use parking_lot::RwLock;
 use proto_array::Block as ProtoBlock;
+use ssz::Encode;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use types::*;
 
 pub struct CacheItem<E: EthSpec> {
@@ -21,6 +23,8 @@ pub struct CacheItem<E: EthSpec> {
      */
     block: Arc<SignedBeaconBlock<E>>,
     proto_block: ProtoBlock,
+    /// When the item was added to the cache.
+    added: Instant,
 }
 
 /// Provides a single-item cache which allows for attesting to blocks before those blocks have
@@ -75,6 +79,7 @@ impl<E: EthSpec> EarlyAttesterCache<E> {
             target,
             block,
             proto_block,
+            added: Instant::now(),
         };
 
         *self.item.write() = Some(item);
@@ -146,6 +151,14 @@ impl<E: EthSpec> EarlyAttesterCache<E> {
             .map_or(false, |item| item.beacon_block_root == block_root)
     }
 
+    /// Returns `true` if `block_root` matches the cached item and the item was added to the cache
+    /// no more than `max_age` ago.
+    pub fn contains_recent_block(&self, block_root: Hash256, max_age: Duration) -> bool {
+        self.item.read().as_ref().map_or(false, |item| {
+            item.beacon_block_root == block_root && item.added.elapsed() <= max_age
+        })
+    }
+
     /// Returns the block, if `block_root` matches the cached item.
     pub fn get_block(&self, block_root: Hash256) -> Option<Arc<SignedBeaconBlock<E>>> {
         self.item
@@ -163,4 +176,11 @@ impl<E: EthSpec> EarlyAttesterCache<E> {
             .filter(|item| item.beacon_block_root == block_root)
             .map(|item| item.proto_block.clone())
     }
+
+    /// Returns an estimate of the memory used by the cached item, in bytes.
+    pub fn memory_usage(&self) -> usize {
+        self.item.read().as_ref().map_or(0, |item| {
+            std::mem::size_of::<CacheItem<E>>() + item.block.ssz_bytes_len()
+        })
+    }
 }
@@ -8,6 +8,7 @@ use crate::naive_aggregation_pool::Error as NaiveAggregationError;
 use crate::observed_aggregates::Error as ObservedAttestationsError;
 use crate::observed_attesters::Error as ObservedAttestersError;
 use crate::observed_block_producers::Error as ObservedBlockProducersError;
+use crate::pubkey_cache_transfer::PubkeyCacheTransferError;
 use execution_layer::PayloadStatus;
 use fork_choice::ExecutionStatus;
 use futures::channel::mpsc::TrySendError;
@@ -22,7 +23,7 @@ use state_processing::{
     },
     signature_sets::Error as SignatureSetError,
     state_advance::Error as StateAdvanceError,
-    BlockProcessingError, BlockReplayError, SlotProcessingError,
+    BlockProcessingError, BlockReplayError, EpochProcessingError, SlotProcessingError,
 };
 use std::time::Duration;
 use task_executor::ShutdownReason;
@@ -49,7 +50,18 @@ pub enum BeaconChainError {
         new: Checkpoint,
     },
     SlotClockDidNotStart,
+    /// The state at the slot is not available, e.g., it's not in the database.
     NoStateForSlot(Slot),
+    /// The canonical block at or prior to the slot is not known, e.g., it has yet to be
+    /// backfilled.
+    NoBlockForSlot(Slot),
+    /// Processing failed while skipping a state forward to `requested_slot`, during the transition
+    /// to `failed_slot`.
+    StateAdvanceFailed {
+        requested_slot: Slot,
+        failed_slot: Slot,
+        error: SlotProcessingError,
+    },
     UnableToFindTargetRoot(Slot),
     BeaconStateError(BeaconStateError),
     DBInconsistent(String),
@@ -76,6 +88,7 @@ pub enum BeaconChainError {
         max_task_runtime: Duration,
     },
     MissingFinalizedStateRoot(Slot),
+    MissingCheckpointStateRoot(Checkpoint),
     /// Returned when an internal check fails, indicating corrupt data.
     InvariantViolated(String),
     SszTypesError(SszTypesError),
@@ -153,12 +166,28 @@ pub enum BeaconChainError {
     AddPayloadLogicError,
     ExecutionForkChoiceUpdateFailed(execution_layer::Error),
     PrepareProposerBlockingFailed(execution_layer::Error),
+    ValidatorRegistrationForwardingFailed(execution_layer::Error),
+    InvalidBuilderConfig(String),
+    AddBuilderFailed(execution_layer::Error),
+    BuilderFailedToRevealPayload {
+        slot: Slot,
+        error: execution_layer::Error,
+    },
+    BuilderRevealedInconsistentPayload {
+        slot: Slot,
+        expected_payload_root: Hash256,
+        revealed_payload_root: Hash256,
+    },
     ExecutionForkChoiceUpdateInvalid {
         status: PayloadStatus,
     },
     BlockRewardSlotError,
     BlockRewardAttestationError,
     BlockRewardSyncError,
+    BlockRewardSlashingError,
+    AttestationPerformanceEpochTooRecent(Epoch),
+    AttestationPerformancePreAltair(Epoch),
+    EpochProcessingError(EpochProcessingError),
     HeadMissingFromForkChoice(Hash256),
     FinalizedBlockMissingFromForkChoice(Hash256),
     HeadBlockMissingFromForkChoice(Hash256),
@@ -198,6 +227,12 @@ pub enum BeaconChainError {
     },
     AttestationHeadNotInForkChoice(Hash256),
     MissingPersistedForkChoice,
+    /// The operation would modify the chain, which is not permitted by `ChainConfig::read_only`.
+    ReadOnlyMode,
+    /// Blocks may only be imported without the gossip checks if
+    /// `ChainConfig::allow_unchecked_gossip_blocks` is set.
+    UncheckedGossipBlocksDisabled,
+    PubkeyCacheTransferError(PubkeyCacheTransferError),
 }
 
 easy_from_to!(SlotProcessingError, BeaconChainError);
@@ -220,12 +255,18 @@ easy_from_to!(ForkChoiceStoreError, BeaconChainError);
 easy_from_to!(HistoricalBlockError, BeaconChainError);
 easy_from_to!(StateAdvanceError, BeaconChainError);
 easy_from_to!(BlockReplayError, BeaconChainError);
+easy_from_to!(EpochProcessingError, BeaconChainError);
+easy_from_to!(PubkeyCacheTransferError, BeaconChainError);
 
 #[derive(Debug)]
 pub enum BlockProductionError {
     UnableToGetBlockRootFromState,
     UnableToReadSlot,
     UnableToProduceAtSlot(Slot),
+    /// The state of the head block could not be loaded to produce a block upon it.
+    MissingHeadState {
+        head_block_root: Hash256,
+    },
     SlotProcessingError(SlotProcessingError),
     BlockProcessingError(BlockProcessingError),
     Eth1ChainError(Eth1ChainError),
@@ -246,6 +287,10 @@ pub enum BlockProductionError {
     FailedToReadFinalizedBlock(store::Error),
     MissingFinalizedBlock(Hash256),
     BlockTooLarge(usize),
+    /// The RANDAO reveal is not the proposer's signature over `expected_epoch`.
+    InvalidRandaoReveal {
+        expected_epoch: Epoch,
+    },
     ForkChoiceError(BeaconChainError),
     ShuttingDown,
     MissingSyncAggregate,
@@ -1,9 +1,9 @@
 use crate::metrics;
 use eth1::{Config as Eth1Config, Eth1Block, Service as HttpService};
-use eth2::lighthouse::Eth1SyncStatusData;
+use eth2::lighthouse::{Eth1CacheStatusData, Eth1SyncStatusData};
 use eth2_hashing::hash;
 use int_to_bytes::int_to_bytes32;
-use slog::{debug, error, trace, Logger};
+use slog::{debug, trace, warn, Logger};
 use ssz::{Decode, Encode};
 use ssz_derive::{Decode, Encode};
 use state_processing::per_block_processing::get_new_eth1_data;
@@ -15,8 +15,8 @@ use std::time::{SystemTime, UNIX_EPOCH};
 use store::{DBColumn, Error as StoreError, StoreItem};
 use task_executor::TaskExecutor;
 use types::{
-    BeaconState, BeaconStateError, ChainSpec, Deposit, Eth1Data, EthSpec, Hash256, Slot, Unsigned,
-    DEPOSIT_TREE_DEPTH,
+    BeaconState, BeaconStateError, ChainSpec, Deposit, DepositTreeSnapshot, Eth1Data, EthSpec,
+    Hash256, PublicKeyBytes, Slot, Unsigned, DEPOSIT_TREE_DEPTH,
 };
 
 type BlockNumber = u64;
@@ -168,10 +168,32 @@ fn get_sync_status<T: EthSpec>(
     })
 }
 
+/// Returns the contents of the eth1 caches, relative to the block they follow.
+fn get_cache_status(
+    latest_cached_block: Option<&Eth1Block>,
+    head_block: Option<&Eth1Block>,
+    deposit_count: u64,
+    cache_follow_distance: u64,
+) -> Eth1CacheStatusData {
+    let latest_cached_block_number = latest_cached_block.map(|b| b.number);
+    let follow_head_block_number =
+        head_block.map(|b| b.number.saturating_sub(cache_follow_distance));
+    let blocks_behind_follow_head = follow_head_block_number
+        .map(|follow_head| follow_head.saturating_sub(latest_cached_block_number.unwrap_or(0)));
+
+    Eth1CacheStatusData {
+        latest_cached_block_number,
+        latest_cached_block_timestamp: latest_cached_block.map(|b| b.timestamp),
+        deposit_count,
+        follow_head_block_number,
+        blocks_behind_follow_head,
+    }
+}
+
 #[derive(Encode, Decode, Clone)]
 pub struct SszEth1 {
-    use_dummy_backend: bool,
-    backend_bytes: Vec<u8>,
+    pub use_dummy_backend: bool,
+    pub backend_bytes: Vec<u8>,
 }
 
 impl StoreItem for SszEth1 {
@@ -279,6 +301,35 @@ where
         )
     }
 
+    /// Returns the index of the first deposit for `pubkey` with a valid signature, or `None` if
+    /// there's no such deposit in the cache or the dummy backend is in use.
+    pub fn deposit_index(&self, pubkey: &PublicKeyBytes) -> Option<u64> {
+        if self.use_dummy_backend {
+            None
+        } else {
+            self.backend.deposit_index(pubkey)
+        }
+    }
+
+    /// Returns the number of deposits in the cache, or zero if the dummy backend is in use.
+    pub fn deposit_count(&self) -> u64 {
+        if self.use_dummy_backend {
+            0
+        } else {
+            self.backend.deposit_count()
+        }
+    }
+
+    /// Returns the contents of the caches of the backend.
+    pub fn cache_status(&self) -> Eth1CacheStatusData {
+        get_cache_status(
+            self.backend.latest_cached_block().as_ref(),
+            self.backend.head_block().as_ref(),
+            self.backend.deposit_count(),
+            self.backend.cache_follow_distance(),
+        )
+    }
+
     /// Instantiate `Eth1Chain` from a persisted `SszEth1`.
     ///
     /// The `Eth1Chain` will have the same caches as the persisted `SszEth1`.
@@ -339,6 +390,16 @@ pub trait Eth1ChainBackend<T: EthSpec>: Sized + Send + Sync {
     /// an idea of how up-to-date the remote eth1 node is.
     fn head_block(&self) -> Option<Eth1Block>;
 
+    /// Returns the number of deposits stored in the cache.
+    fn deposit_count(&self) -> u64;
+
+    /// Returns the index of the first deposit for `pubkey` with a valid signature, if it's stored
+    /// in the cache.
+    fn deposit_index(&self, pubkey: &PublicKeyBytes) -> Option<u64>;
+
+    /// Returns the number of blocks behind the head of the eth1 chain that the cache follows.
+    fn cache_follow_distance(&self) -> u64;
+
     /// Encode the `Eth1ChainBackend` instance to bytes.
     fn as_bytes(&self) -> Vec<u8>;
 
@@ -393,6 +454,18 @@ impl<T: EthSpec> Eth1ChainBackend<T> for DummyEth1ChainBackend<T> {
         None
     }
 
+    fn deposit_count(&self) -> u64 {
+        0
+    }
+
+    fn deposit_index(&self, _: &PublicKeyBytes) -> Option<u64> {
+        None
+    }
+
+    fn cache_follow_distance(&self) -> u64 {
+        0
+    }
+
     /// Return empty Vec<u8> for dummy backend.
     fn as_bytes(&self) -> Vec<u8> {
         Vec::new()
@@ -439,6 +512,23 @@ impl<T: EthSpec> CachingEth1Backend<T> {
         }
     }
 
+    /// Instantiates `self` with the deposit cache initialised from `snapshot`, so that only the
+    /// deposits made after the snapshot are downloaded from the eth1 node.
+    ///
+    /// Does not connect to the eth1 node or start any tasks to keep the cache updated.
+    pub fn from_deposit_snapshot(
+        config: Eth1Config,
+        log: Logger,
+        spec: ChainSpec,
+        snapshot: DepositTreeSnapshot,
+    ) -> Result<Self, String> {
+        Ok(Self {
+            core: HttpService::from_deposit_snapshot(config, log.clone(), spec, snapshot)?,
+            log,
+            _phantom: PhantomData,
+        })
+    }
+
     /// Starts the routine which connects to the external eth1 node and updates the caches.
     pub fn start(&self, handle: TaskExecutor) {
         HttpService::auto_update(self.core.clone(), handle);
@@ -482,7 +572,7 @@ impl<T: EthSpec> Eth1ChainBackend<T> for CachingEth1Backend<T> {
             // In this case, there are no valid votes available.
             //
             // Here we choose the eth1_data corresponding to the latest block in our voting window.
-            // If no votes exist, choose `state.eth1_data` as default vote.
+            // If no votes exist the cache is stale, so fall back to the votes in the state.
             votes_to_consider
                 .iter()
                 .max_by_key(|(_, block_number)| *block_number)
@@ -497,16 +587,16 @@ impl<T: EthSpec> Eth1ChainBackend<T> for CachingEth1Backend<T> {
                     vote
                 })
                 .unwrap_or_else(|| {
-                    let vote = state.eth1_data().clone();
-                    error!(
+                    let vote = get_fallback_vote(state);
+                    warn!(
                         self.log,
                         "No valid eth1_data votes, `votes_to_consider` empty";
                         "lowest_block_number" => self.core.lowest_block_number(),
                         "earliest_block_timestamp" => self.core.earliest_block_timestamp(),
                         "genesis_time" => state.genesis_time(),
-                        "outcome" => "casting `state.eth1_data` as eth1 vote"
+                        "outcome" => "casting fallback eth1 vote",
+                        "is_state_eth1_data" => vote == *state.eth1_data(),
                     );
-                    metrics::inc_counter(&metrics::DEFAULT_ETH1_VOTES);
                     vote
                 })
         };
@@ -561,6 +651,24 @@ impl<T: EthSpec> Eth1ChainBackend<T> for CachingEth1Backend<T> {
         self.core.head_block()
     }
 
+    fn deposit_count(&self) -> u64 {
+        self.core.deposit_cache_len() as u64
+    }
+
+    fn deposit_index(&self, pubkey: &PublicKeyBytes) -> Option<u64> {
+        self.core
+            .deposits()
+            .read()
+            .cache
+            .iter()
+            .find(|log| log.signature_is_valid && log.deposit_data.pubkey == *pubkey)
+            .map(|log| log.index)
+    }
+
+    fn cache_follow_distance(&self) -> u64 {
+        self.core.cache_follow_distance()
+    }
+
     /// Return encoded byte representation of the block and deposit caches.
     fn as_bytes(&self) -> Vec<u8> {
         self.core.as_bytes()
@@ -631,6 +739,38 @@ fn collect_valid_votes<T: EthSpec>(
     valid_votes
 }
 
+/// Returns the vote to cast when the eth1 cache is unable to provide a fresh `Eth1Data`.
+///
+/// This is the vote which has the most support in the current voting period, or `state.eth1_data`
+/// if there is none. A vote which would require more deposits to be included in the block is never
+/// chosen, since the deposits can't be read from a stale cache.
+pub fn get_fallback_vote<T: EthSpec>(state: &BeaconState<T>) -> Eth1Data {
+    let state_eth1_data = state.eth1_data();
+
+    let mut vote_counts = HashMap::<&Eth1Data, u64>::new();
+    for vote in state.eth1_data_votes().iter() {
+        *vote_counts.entry(vote).or_default() += 1;
+    }
+
+    let vote = vote_counts
+        .into_iter()
+        .filter(|(vote, _)| vote.deposit_count >= state_eth1_data.deposit_count)
+        // Break ties on the deposit count and block hash, so that the vote is deterministic.
+        .max_by_key(|(vote, count)| (*count, vote.deposit_count, vote.block_hash))
+        .map(|(vote, _)| vote.clone())
+        .filter(|vote| match get_new_eth1_data(state, vote) {
+            Ok(Some(new_eth1_data)) => new_eth1_data.deposit_count <= state_eth1_data.deposit_count,
+            Ok(None) => true,
+            Err(_) => false,
+        });
+
+    metrics::inc_counter(&metrics::FALLBACK_ETH1_VOTES);
+    vote.unwrap_or_else(|| {
+        metrics::inc_counter(&metrics::DEFAULT_ETH1_VOTES);
+        state_eth1_data.clone()
+    })
+}
+
 /// Selects the winning vote from `valid_votes`.
 fn find_winning_vote(valid_votes: Eth1DataVoteCount) -> Option<Eth1Data> {
     valid_votes
@@ -719,6 +859,30 @@ mod test {
         }
     }
 
+    #[test]
+    fn cache_status() {
+        let status = get_cache_status(None, None, 0, 8);
+        assert_eq!(status.latest_cached_block_number, None);
+        assert_eq!(status.follow_head_block_number, None);
+        assert_eq!(status.blocks_behind_follow_head, None);
+
+        let head = get_eth1_block(1_000, 100);
+        let status = get_cache_status(None, Some(&head), 0, 8);
+        assert_eq!(status.follow_head_block_number, Some(92));
+        assert_eq!(status.blocks_behind_follow_head, Some(92));
+
+        let latest_cached = get_eth1_block(900, 90);
+        let status = get_cache_status(Some(&latest_cached), Some(&head), 3, 8);
+        assert_eq!(status.latest_cached_block_number, Some(90));
+        assert_eq!(status.latest_cached_block_timestamp, Some(900));
+        assert_eq!(status.deposit_count, 3);
+        assert_eq!(status.blocks_behind_follow_head, Some(2));
+
+        // The cache may briefly be ahead of the follow head whilst the head is re-fetched.
+        let status = get_cache_status(Some(&head), Some(&latest_cached), 3, 8);
+        assert_eq!(status.blocks_behind_follow_head, Some(0));
+    }
+
     mod eth1_chain_json_backend {
         use super::*;
         use eth1::DepositLog;
@@ -1188,4 +1352,49 @@ mod test {
             );
         }
     }
+
+    mod fallback_vote {
+        use super::*;
+
+        fn state_with_votes(votes: Vec<Eth1Data>) -> BeaconState<E> {
+            let spec = &E::default_spec();
+            let mut state: BeaconState<E> = BeaconState::new(0, get_eth1_data(5), spec);
+            *state.eth1_data_votes_mut() = votes.into();
+            state
+        }
+
+        #[test]
+        fn no_votes() {
+            let state = state_with_votes(vec![]);
+            assert_eq!(get_fallback_vote(&state), get_eth1_data(5));
+        }
+
+        #[test]
+        fn most_supported_vote() {
+            let mut votes = vec![get_eth1_data(6); 3];
+            votes.push(get_eth1_data(7));
+            // Votes which reduce the deposit count are invalid.
+            votes.extend(vec![get_eth1_data(4); 5]);
+
+            let state = state_with_votes(votes);
+            assert_eq!(get_fallback_vote(&state), get_eth1_data(6));
+        }
+
+        #[test]
+        fn vote_requiring_deposits() {
+            let threshold = <E as EthSpec>::SlotsPerEth1VotingPeriod::to_usize() / 2;
+
+            // The vote would be adopted, so its deposits would need to be included.
+            let state = state_with_votes(vec![get_eth1_data(6); threshold]);
+            assert_eq!(get_fallback_vote(&state), get_eth1_data(5));
+
+            // The vote would be adopted, but it doesn't contain any new deposits.
+            let vote = Eth1Data {
+                block_hash: Hash256::repeat_byte(42),
+                ..get_eth1_data(5)
+            };
+            let state = state_with_votes(vec![vote.clone(); threshold]);
+            assert_eq!(get_fallback_vote(&state), vote);
+        }
+    }
 }
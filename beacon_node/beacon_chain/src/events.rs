@@ -1,4 +1,5 @@
 pub use eth2::types::{EventKind, SseBlock, SseFinalizedCheckpoint, SseHead};
+use parking_lot::Mutex;
 use slog::{trace, Logger};
 use tokio::sync::broadcast;
 use tokio::sync::broadcast::{error::SendError, Receiver, Sender};
@@ -13,9 +14,19 @@ pub struct ServerSentEventHandler<T: EthSpec> {
     head_tx: Sender<EventKind<T>>,
     exit_tx: Sender<EventKind<T>>,
     chain_reorg_tx: Sender<EventKind<T>>,
+    canonical_chain_update_tx: Sender<EventKind<T>>,
     contribution_tx: Sender<EventKind<T>>,
     late_head: Sender<EventKind<T>>,
+    validator_registry_update_tx: Sender<EventKind<T>>,
+    finality_delay_tx: Sender<EventKind<T>>,
+    invalid_published_block_tx: Sender<EventKind<T>>,
     block_reward_tx: Sender<EventKind<T>>,
+    /// The most recent head event, replayed to new subscribers so that they needn't wait for the
+    /// head to change to learn of it.
+    latest_head: Mutex<Option<SseHead>>,
+    /// The most recent finalized checkpoint event, replayed to new subscribers so that they
+    /// needn't wait for the next finalization to learn of it.
+    latest_finalized: Mutex<Option<SseFinalizedCheckpoint>>,
     log: Logger,
 }
 
@@ -31,8 +42,12 @@ impl<T: EthSpec> ServerSentEventHandler<T> {
         let (head_tx, _) = broadcast::channel(capacity);
         let (exit_tx, _) = broadcast::channel(capacity);
         let (chain_reorg_tx, _) = broadcast::channel(capacity);
+        let (canonical_chain_update_tx, _) = broadcast::channel(capacity);
         let (contribution_tx, _) = broadcast::channel(capacity);
         let (late_head, _) = broadcast::channel(capacity);
+        let (validator_registry_update_tx, _) = broadcast::channel(capacity);
+        let (finality_delay_tx, _) = broadcast::channel(capacity);
+        let (invalid_published_block_tx, _) = broadcast::channel(capacity);
         let (block_reward_tx, _) = broadcast::channel(capacity);
 
         Self {
@@ -42,9 +57,15 @@ impl<T: EthSpec> ServerSentEventHandler<T> {
             head_tx,
             exit_tx,
             chain_reorg_tx,
+            canonical_chain_update_tx,
             contribution_tx,
             late_head,
+            validator_registry_update_tx,
+            finality_delay_tx,
+            invalid_published_block_tx,
             block_reward_tx,
+            latest_head: <_>::default(),
+            latest_finalized: <_>::default(),
             log,
         }
     }
@@ -57,19 +78,37 @@ impl<T: EthSpec> ServerSentEventHandler<T> {
                 .map(|count| trace!(self.log, "Registering server-sent attestation event"; "receiver_count" => count)),
             EventKind::Block(block) => self.block_tx.send(EventKind::Block(block))
                 .map(|count| trace!(self.log, "Registering server-sent block event"; "receiver_count" => count)),
-            EventKind::FinalizedCheckpoint(checkpoint) => self.finalized_tx
-                .send(EventKind::FinalizedCheckpoint(checkpoint))
-                .map(|count| trace!(self.log, "Registering server-sent finalized checkpoint event"; "receiver_count" => count)),
-            EventKind::Head(head) => self.head_tx.send(EventKind::Head(head))
-                .map(|count| trace!(self.log, "Registering server-sent head event"; "receiver_count" => count)),
+            EventKind::FinalizedCheckpoint(checkpoint) => {
+                // Send whilst holding the lock, so that a concurrent subscriber receives either
+                // the replay or the event, but never misses both.
+                let mut latest_finalized = self.latest_finalized.lock();
+                *latest_finalized = Some(checkpoint.clone());
+                self.finalized_tx
+                    .send(EventKind::FinalizedCheckpoint(checkpoint))
+                    .map(|count| trace!(self.log, "Registering server-sent finalized checkpoint event"; "receiver_count" => count))
+            }
+            EventKind::Head(head) => {
+                let mut latest_head = self.latest_head.lock();
+                *latest_head = Some(head.clone());
+                self.head_tx.send(EventKind::Head(head))
+                    .map(|count| trace!(self.log, "Registering server-sent head event"; "receiver_count" => count))
+            }
             EventKind::VoluntaryExit(exit) => self.exit_tx.send(EventKind::VoluntaryExit(exit))
                 .map(|count| trace!(self.log, "Registering server-sent voluntary exit event"; "receiver_count" => count)),
             EventKind::ChainReorg(reorg) => self.chain_reorg_tx.send(EventKind::ChainReorg(reorg))
                 .map(|count| trace!(self.log, "Registering server-sent chain reorg event"; "receiver_count" => count)),
+            EventKind::CanonicalChainUpdate(update) => self.canonical_chain_update_tx.send(EventKind::CanonicalChainUpdate(update))
+                .map(|count| trace!(self.log, "Registering server-sent canonical chain update event"; "receiver_count" => count)),
             EventKind::ContributionAndProof(contribution_and_proof) => self.contribution_tx.send(EventKind::ContributionAndProof(contribution_and_proof))
                 .map(|count| trace!(self.log, "Registering server-sent contribution and proof event"; "receiver_count" => count)),
             EventKind::LateHead(late_head) => self.late_head.send(EventKind::LateHead(late_head))
                 .map(|count| trace!(self.log, "Registering server-sent late head event"; "receiver_count" => count)),
+            EventKind::ValidatorRegistryUpdate(update) => self.validator_registry_update_tx.send(EventKind::ValidatorRegistryUpdate(update))
+                .map(|count| trace!(self.log, "Registering server-sent validator registry update event"; "receiver_count" => count)),
+            EventKind::FinalityDelay(delay) => self.finality_delay_tx.send(EventKind::FinalityDelay(delay))
+                .map(|count| trace!(self.log, "Registering server-sent finality delay event"; "receiver_count" => count)),
+            EventKind::InvalidPublishedBlock(block) => self.invalid_published_block_tx.send(EventKind::InvalidPublishedBlock(block))
+                .map(|count| trace!(self.log, "Registering server-sent invalid published block event"; "receiver_count" => count)),
             EventKind::BlockReward(block_reward) => self.block_reward_tx.send(EventKind::BlockReward(block_reward))
                 .map(|count| trace!(self.log, "Registering server-sent contribution and proof event"; "receiver_count" => count)),
         };
@@ -94,6 +133,28 @@ impl<T: EthSpec> ServerSentEventHandler<T> {
         self.head_tx.subscribe()
     }
 
+    /// Subscribe to finalized checkpoint events, also returning the most recent event, if any, to
+    /// replay before those which are received.
+    pub fn subscribe_finalized_with_replay(
+        &self,
+    ) -> (Option<EventKind<T>>, Receiver<EventKind<T>>) {
+        let latest_finalized = self.latest_finalized.lock();
+        (
+            latest_finalized.clone().map(EventKind::FinalizedCheckpoint),
+            self.finalized_tx.subscribe(),
+        )
+    }
+
+    /// Subscribe to head events, also returning the most recent event, if any, to replay before
+    /// those which are received.
+    pub fn subscribe_head_with_replay(&self) -> (Option<EventKind<T>>, Receiver<EventKind<T>>) {
+        let latest_head = self.latest_head.lock();
+        (
+            latest_head.clone().map(EventKind::Head),
+            self.head_tx.subscribe(),
+        )
+    }
+
     pub fn subscribe_exit(&self) -> Receiver<EventKind<T>> {
         self.exit_tx.subscribe()
     }
@@ -102,6 +163,10 @@ impl<T: EthSpec> ServerSentEventHandler<T> {
         self.chain_reorg_tx.subscribe()
     }
 
+    pub fn subscribe_canonical_chain_updates(&self) -> Receiver<EventKind<T>> {
+        self.canonical_chain_update_tx.subscribe()
+    }
+
     pub fn subscribe_contributions(&self) -> Receiver<EventKind<T>> {
         self.contribution_tx.subscribe()
     }
@@ -110,6 +175,18 @@ impl<T: EthSpec> ServerSentEventHandler<T> {
         self.late_head.subscribe()
     }
 
+    pub fn subscribe_validator_registry_updates(&self) -> Receiver<EventKind<T>> {
+        self.validator_registry_update_tx.subscribe()
+    }
+
+    pub fn subscribe_finality_delay(&self) -> Receiver<EventKind<T>> {
+        self.finality_delay_tx.subscribe()
+    }
+
+    pub fn subscribe_invalid_published_block(&self) -> Receiver<EventKind<T>> {
+        self.invalid_published_block_tx.subscribe()
+    }
+
     pub fn subscribe_block_reward(&self) -> Receiver<EventKind<T>> {
         self.block_reward_tx.subscribe()
     }
@@ -138,6 +215,10 @@ impl<T: EthSpec> ServerSentEventHandler<T> {
         self.chain_reorg_tx.receiver_count() > 0
     }
 
+    pub fn has_canonical_chain_update_subscribers(&self) -> bool {
+        self.canonical_chain_update_tx.receiver_count() > 0
+    }
+
     pub fn has_contribution_subscribers(&self) -> bool {
         self.contribution_tx.receiver_count() > 0
     }
@@ -146,6 +227,18 @@ impl<T: EthSpec> ServerSentEventHandler<T> {
         self.late_head.receiver_count() > 0
     }
 
+    pub fn has_validator_registry_update_subscribers(&self) -> bool {
+        self.validator_registry_update_tx.receiver_count() > 0
+    }
+
+    pub fn has_finality_delay_subscribers(&self) -> bool {
+        self.finality_delay_tx.receiver_count() > 0
+    }
+
+    pub fn has_invalid_published_block_subscribers(&self) -> bool {
+        self.invalid_published_block_tx.receiver_count() > 0
+    }
+
     pub fn has_block_reward_subscribers(&self) -> bool {
         self.block_reward_tx.receiver_count() > 0
     }
@@ -11,7 +11,7 @@ use crate::{
     BeaconChain, BeaconChainError, BeaconChainTypes, BlockError, BlockProductionError,
     ExecutionPayloadError,
 };
-use execution_layer::PayloadStatus;
+use execution_layer::{BuilderParams, PayloadSource, PayloadStatus};
 use fork_choice::{InvalidationOperation, PayloadVerificationStatus};
 use proto_array::{Block as ProtoBlock, ExecutionStatus};
 use slog::debug;
@@ -24,7 +24,9 @@ use std::sync::Arc;
 use tokio::task::JoinHandle;
 use types::*;
 
-pub type PreparePayloadResult<Payload> = Result<Payload, BlockProductionError>;
+/// The prepared payload, along with its source if it was obtained from the execution layer.
+pub type PreparePayloadResult<Payload> =
+    Result<(Payload, Option<PayloadSource>), BlockProductionError>;
 pub type PreparePayloadHandle<Payload> = JoinHandle<Option<PreparePayloadResult<Payload>>>;
 
 /// Used to await the result of executing payload with a remote EE.
@@ -344,7 +346,8 @@ pub fn get_execution_payload<
 
 /// Prepares an execution payload for inclusion in a block.
 ///
-/// Will return `Ok(None)` if the merge fork has occurred, but a terminal block has not been found.
+/// Will return a default payload without a `PayloadSource` if the merge fork has occurred, but a
+/// terminal block has not been found.
 ///
 /// ## Errors
 ///
@@ -366,7 +369,7 @@ pub async fn prepare_execution_payload<T, Payload>(
     proposer_index: u64,
     pubkey: Option<PublicKeyBytes>,
     latest_execution_payload_header_block_hash: ExecutionBlockHash,
-) -> Result<Payload, BlockProductionError>
+) -> PreparePayloadResult<Payload>
 where
     T: BeaconChainTypes,
     Payload: ExecPayload<T::EthSpec> + Default,
@@ -386,7 +389,7 @@ where
         if is_terminal_block_hash_set && !is_activation_epoch_reached {
             // Use the "empty" payload if there's a terminal block hash, but we haven't reached the
             // terminal block epoch yet.
-            return Ok(<_>::default());
+            return Ok((<_>::default(), None));
         }
 
         let terminal_pow_block_hash = execution_layer
@@ -399,45 +402,52 @@ where
         } else {
             // If the merge transition hasn't occurred yet and the EL hasn't found the terminal
             // block, return an "empty" payload.
-            return Ok(<_>::default());
+            return Ok((<_>::default(), None));
         }
     } else {
         latest_execution_payload_header_block_hash
     };
 
-    // Try to obtain the fork choice update parameters from the cached head.
+    // Try to obtain the fork choice update parameters and the health of the chain from the cached
+    // head.
     //
     // Use a blocking task to interact with the `canonical_head` lock otherwise we risk blocking the
     // core `tokio` executor.
     let inner_chain = chain.clone();
-    let forkchoice_update_params = chain
+    let (forkchoice_update_params, chain_health) = chain
         .spawn_blocking_handle(
             move || {
-                inner_chain
-                    .canonical_head
-                    .cached_head()
-                    .forkchoice_update_parameters()
+                let cached_head = inner_chain.canonical_head.cached_head();
+                let chain_health = inner_chain
+                    .builder_chain_health(slot, cached_head.finalized_checkpoint().epoch);
+                (cached_head.forkchoice_update_parameters(), chain_health)
             },
             "prepare_execution_payload_forkchoice_update_params",
         )
         .await
         .map_err(BlockProductionError::BeaconChain)?;
 
+    let builder_params = BuilderParams {
+        pubkey,
+        slot,
+        chain_health,
+        builder_domain: spec.get_builder_domain(),
+    };
+
     // Note: the suggested_fee_recipient is stored in the `execution_layer`, it will add this parameter.
     //
     // This future is not executed here, it's up to the caller to await it.
-    let execution_payload = execution_layer
+    let produced_payload = execution_layer
         .get_payload::<Payload>(
             parent_hash,
             timestamp,
             random,
             proposer_index,
-            pubkey,
-            slot,
+            builder_params,
             forkchoice_update_params,
         )
         .await
         .map_err(BlockProductionError::GetPayloadFailed)?;
 
-    Ok(execution_payload)
+    Ok((produced_payload.payload, Some(produced_payload.source)))
 }
@@ -0,0 +1,107 @@
+//! Summarizes the execution payloads of blocks from the payload headers of their blinded blocks.
+//!
+//! Unlike `BeaconChain::get_block`, this never requests payloads from the execution layer, so the
+//! payloads of finalized blocks can be summarized after they have been pruned.
+use crate::{BeaconChain, BeaconChainError as Error, BeaconChainTypes};
+use serde_derive::{Deserialize, Serialize};
+use types::{
+    ExecutionBlockHash, ExecutionPayload, ExecutionPayloadHeader, Hash256,
+    SignedBlindedBeaconBlock, Slot, Uint256,
+};
+
+/// The metadata of the execution payload of a block.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExecutionPayloadSummary {
+    pub slot: Slot,
+    pub block_root: Hash256,
+    pub block_hash: ExecutionBlockHash,
+    pub block_number: u64,
+    pub gas_used: u64,
+    pub gas_limit: u64,
+    pub base_fee_per_gas: Uint256,
+    pub timestamp: u64,
+    /// The number of transactions in the payload, or `None` if the payload has been pruned from
+    /// the database, as only the root of the transactions is in the payload header.
+    pub transaction_count: Option<usize>,
+}
+
+impl<T: BeaconChainTypes> BeaconChain<T> {
+    /// Returns the summary of the execution payload of the block with `block_root`.
+    ///
+    /// Returns `None` if the block is unknown, or if it is prior to the merge and so has no
+    /// execution payload.
+    pub fn execution_payload_summary(
+        &self,
+        block_root: Hash256,
+    ) -> Result<Option<ExecutionPayloadSummary>, Error> {
+        match self.get_blinded_block(&block_root)? {
+            Some(block) => self.summarize_execution_payload(block_root, &block),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the summaries of the execution payloads of the canonical blocks from `start_slot`
+    /// to `end_slot` (inclusive), ordered by slot.
+    ///
+    /// Skipped slots and blocks prior to the merge are omitted.
+    pub fn execution_payload_summaries(
+        &self,
+        start_slot: Slot,
+        end_slot: Slot,
+    ) -> Result<Vec<ExecutionPayloadSummary>, Error> {
+        let mut summaries = vec![];
+        let mut prev_block_root = None;
+        for result in self.forwards_iter_block_roots_until(start_slot, end_slot)? {
+            let (block_root, slot) = result?;
+            if prev_block_root == Some(block_root) {
+                continue;
+            }
+            prev_block_root = Some(block_root);
+
+            let block = self
+                .get_blinded_block(&block_root)?
+                .ok_or(Error::MissingBeaconBlock(block_root))?;
+            // If `start_slot` is skipped, its root is that of a block prior to the range.
+            if block.slot() != slot {
+                continue;
+            }
+
+            if let Some(summary) = self.summarize_execution_payload(block_root, &block)? {
+                summaries.push(summary);
+            }
+        }
+        Ok(summaries)
+    }
+
+    fn summarize_execution_payload(
+        &self,
+        block_root: Hash256,
+        block: &SignedBlindedBeaconBlock<T::EthSpec>,
+    ) -> Result<Option<ExecutionPayloadSummary>, Error> {
+        let header = match block.message().execution_payload() {
+            Ok(payload) => &payload.execution_payload_header,
+            Err(_) => return Ok(None),
+        };
+        // Blocks after the Bellatrix fork but prior to the merge have a default payload.
+        if *header == ExecutionPayloadHeader::default() {
+            return Ok(None);
+        }
+
+        let transaction_count = self
+            .store
+            .get_item::<ExecutionPayload<T::EthSpec>>(&block_root)?
+            .map(|payload| payload.transactions.len());
+
+        Ok(Some(ExecutionPayloadSummary {
+            slot: block.slot(),
+            block_root,
+            block_hash: header.block_hash,
+            block_number: header.block_number,
+            gas_used: header.gas_used,
+            gas_limit: header.gas_limit,
+            base_fee_per_gas: header.base_fee_per_gas,
+            timestamp: header.timestamp,
+            transaction_count,
+        }))
+    }
+}
@@ -0,0 +1,132 @@
+//! Summarizes the exit queue and the withdrawal credentials of the validator registry in the head
+//! state, so that operators can see how many validators are leaving and how quickly.
+//!
+//! The registry may hold hundreds of thousands of validators, so the summary is computed in a
+//! single pass over the registry of the head snapshot, without cloning it, and is cached until the
+//! epoch or the shuffling decision root of the head state changes, so that a re-org across an epoch
+//! boundary refreshes it. Exits initiated within an epoch are therefore only reflected from the
+//! next epoch.
+use crate::{metrics, BeaconChain, BeaconChainError as Error, BeaconChainTypes};
+pub use eth2::lighthouse::ExitQueueSummary;
+use std::cmp::max;
+use std::collections::BTreeMap;
+use types::{BeaconState, ChainSpec, Epoch, EthSpec, Hash256};
+
+/// Summarize the exit queue and withdrawal credentials of `state`.
+fn compute_exit_queue_summary<E: EthSpec>(
+    state: &BeaconState<E>,
+    head_block_root: Hash256,
+    decision_root: Hash256,
+    spec: &ChainSpec,
+) -> Result<ExitQueueSummary, Error> {
+    let current_epoch = state.current_epoch();
+    let mut active_validators = 0;
+    let mut exiting_validators = 0;
+    // The furthest exit epoch of any validator, and the number of validators exiting in it.
+    let mut furthest_exit: Option<(Epoch, u64)> = None;
+    let mut prefixes = BTreeMap::<u8, u64>::new();
+
+    for validator in state.validators().iter() {
+        if validator.is_active_at(current_epoch) {
+            active_validators += 1;
+        }
+        if validator.exit_epoch != spec.far_future_epoch {
+            if validator.exit_epoch > current_epoch {
+                exiting_validators += 1;
+            }
+            furthest_exit = match furthest_exit {
+                Some((epoch, count)) if epoch == validator.exit_epoch => Some((epoch, count + 1)),
+                Some((epoch, count)) if epoch > validator.exit_epoch => Some((epoch, count)),
+                _ => Some((validator.exit_epoch, 1)),
+            };
+        }
+        *prefixes
+            .entry(validator.withdrawal_credentials.as_bytes()[0])
+            .or_default() += 1;
+    }
+
+    // As in `initiate_validator_exit`, exits are never scheduled sooner than the activation
+    // exit epoch.
+    let activation_exit_epoch = state
+        .compute_activation_exit_epoch(current_epoch, spec)
+        .map_err(Error::BeaconStateError)?;
+    let (exit_queue_epoch, exit_queue_churn) = match furthest_exit {
+        Some((epoch, count)) if epoch >= activation_exit_epoch => (epoch, count),
+        _ => (activation_exit_epoch, 0),
+    };
+
+    Ok(ExitQueueSummary {
+        epoch: current_epoch,
+        head_block_root,
+        decision_root,
+        exiting_validators,
+        max_exit_epoch: furthest_exit
+            .map(|(epoch, _)| epoch)
+            .filter(|&epoch| epoch > current_epoch),
+        exit_queue_epoch,
+        exit_queue_churn,
+        churn_limit: max(
+            spec.min_per_epoch_churn_limit,
+            active_validators / spec.churn_limit_quotient,
+        ),
+        withdrawal_credential_prefixes: prefixes
+            .into_iter()
+            .map(|(prefix, count)| (format!("0x{:02x}", prefix), count))
+            .collect(),
+    })
+}
+
+impl<T: BeaconChainTypes> BeaconChain<T> {
+    /// Returns the summary of the exit queue of the head state, computing it if the epoch or the
+    /// shuffling decision root of the head state has changed since it was last computed.
+    pub fn exit_queue_summary(&self) -> Result<ExitQueueSummary, Error> {
+        // Hold the lock whilst computing, so that concurrent callers don't repeat the work.
+        let mut cached_summary = self.exit_queue_summary_cache.lock();
+        self.with_head(|head| {
+            let epoch = head.beacon_state.current_epoch();
+            let decision_root = head
+                .beacon_state
+                .proposer_shuffling_decision_root(head.beacon_block_root)
+                .map_err(Error::BeaconStateError)?;
+            if let Some(summary) = cached_summary
+                .as_ref()
+                .filter(|summary| summary.epoch == epoch && summary.decision_root == decision_root)
+            {
+                return Ok(summary.clone());
+            }
+
+            let summary = compute_exit_queue_summary(
+                &head.beacon_state,
+                head.beacon_block_root,
+                decision_root,
+                &self.spec,
+            )?;
+            metrics::set_gauge(
+                &metrics::EXIT_QUEUE_EXITING_VALIDATORS,
+                summary.exiting_validators as i64,
+            );
+            metrics::set_gauge(
+                &metrics::EXIT_QUEUE_MAX_EXIT_EPOCH,
+                summary
+                    .max_exit_epoch
+                    .map_or(0, |epoch| epoch.as_u64() as i64),
+            );
+            metrics::set_gauge(&metrics::EXIT_QUEUE_CHURN, summary.exit_queue_churn as i64);
+            metrics::set_gauge(&metrics::EXIT_QUEUE_CHURN_LIMIT, summary.churn_limit as i64);
+            // Reset the prefixes, so that a prefix which no validator holds any longer is removed.
+            let _ = metrics::WITHDRAWAL_CREDENTIAL_PREFIXES
+                .as_ref()
+                .map(|gauge| gauge.reset());
+            for (prefix, count) in &summary.withdrawal_credential_prefixes {
+                metrics::set_int_gauge(
+                    &metrics::WITHDRAWAL_CREDENTIAL_PREFIXES,
+                    &[prefix],
+                    *count as i64,
+                );
+            }
+
+            *cached_summary = Some(summary.clone());
+            Ok(summary)
+        })
+    }
+}
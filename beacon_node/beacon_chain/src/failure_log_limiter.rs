@@ -0,0 +1,193 @@
+//! Rate-limits the logs of failures that repeat every slot or head change.
+//!
+//! When the execution layer is offline or fork choice keeps failing, the same error would otherwise
+//! be logged at high severity each time the failing routine runs, paging operators repeatedly for a
+//! single fault. Only the first occurrence of a failure within
+//! `ChainConfig::failure_log_window_secs` is logged at full severity. Repeats are logged at debug
+//! level with a count, and a summary is logged once the routine succeeds again. Every occurrence is
+//! still counted by a metric.
+use crate::{metrics, BeaconChain, BeaconChainTypes};
+use parking_lot::Mutex;
+use slog::{debug, info};
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::time::{Duration, Instant};
+
+/// The routines whose failures are rate-limited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FailureKind {
+    /// Running fork choice to recompute the head.
+    ForkChoice,
+    /// Sending `forkchoiceUpdated` to the execution layer.
+    ExecutionForkchoiceUpdate,
+    /// Preparing the execution layer for upcoming proposals.
+    PrepareProposer,
+}
+
+impl FailureKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FailureKind::ForkChoice => "fork_choice",
+            FailureKind::ExecutionForkchoiceUpdate => "execution_forkchoice_update",
+            FailureKind::PrepareProposer => "prepare_proposer",
+        }
+    }
+}
+
+/// How a failure should be logged.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FailureLog {
+    /// Log the failure at full severity.
+    Log,
+    /// The failure has been logged at full severity within the window. This is the `repeats`-th
+    /// repeat since then.
+    Suppress { repeats: u64 },
+}
+
+struct FailureState {
+    /// When the failure was last logged at full severity.
+    logged_at: Instant,
+    /// The number of repeats since the failure was last logged at full severity.
+    repeats: u64,
+    /// The number of consecutive failures.
+    occurrences: u64,
+}
+
+#[derive(Default)]
+pub struct FailureLogLimiter {
+    failures: Mutex<HashMap<FailureKind, FailureState>>,
+}
+
+impl FailureLogLimiter {
+    /// Record a failure of `kind` at `now`, returning how it should be logged.
+    pub fn failure(&self, kind: FailureKind, now: Instant, window: Duration) -> FailureLog {
+        let mut failures = self.failures.lock();
+        match failures.get_mut(&kind) {
+            Some(state) if now.saturating_duration_since(state.logged_at) < window => {
+                state.repeats += 1;
+                state.occurrences += 1;
+                FailureLog::Suppress {
+                    repeats: state.repeats,
+                }
+            }
+            Some(state) => {
+                state.logged_at = now;
+                state.repeats = 0;
+                state.occurrences += 1;
+                FailureLog::Log
+            }
+            None => {
+                failures.insert(
+                    kind,
+                    FailureState {
+                        logged_at: now,
+                        repeats: 0,
+                        occurrences: 1,
+                    },
+                );
+                FailureLog::Log
+            }
+        }
+    }
+
+    /// Record a success of `kind`, returning the number of consecutive failures it ended, if any.
+    pub fn success(&self, kind: FailureKind) -> Option<u64> {
+        self.failures
+            .lock()
+            .remove(&kind)
+            .map(|state| state.occurrences)
+    }
+}
+
+impl<T: BeaconChainTypes> BeaconChain<T> {
+    /// Record a failure of `kind`, returning `true` if it should be logged at full severity.
+    ///
+    /// If not, `msg` and `error` are logged at debug level.
+    pub fn should_log_failure<E: Debug>(&self, kind: FailureKind, msg: &str, error: &E) -> bool {
+        metrics::inc_counter_vec(&metrics::REPEATED_FAILURES_TOTAL, &[kind.as_str()]);
+        match self.failure_log_limiter.failure(
+            kind,
+            Instant::now(),
+            Duration::from_secs(self.config.failure_log_window_secs),
+        ) {
+            FailureLog::Log => true,
+            FailureLog::Suppress { repeats } => {
+                debug!(
+                    self.log,
+                    "Repeated failure";
+                    "msg" => msg,
+                    "error" => ?error,
+                    "repeats" => repeats,
+                );
+                false
+            }
+        }
+    }
+
+    /// Record a success of `kind`, logging a summary if it ends a series of failures.
+    pub fn record_failure_cleared(&self, kind: FailureKind) {
+        if let Some(occurrences) = self.failure_log_limiter.success(kind) {
+            info!(
+                self.log,
+                "Failure cleared";
+                "failure" => kind.as_str(),
+                "occurrences" => occurrences,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repeated_failures_are_suppressed() {
+        let limiter = FailureLogLimiter::default();
+        let window = Duration::from_secs(60);
+        let start = Instant::now();
+        let kind = FailureKind::ExecutionForkchoiceUpdate;
+
+        let logs = (0..10)
+            .map(|i| limiter.failure(kind, start + Duration::from_secs(i), window))
+            .collect::<Vec<_>>();
+        assert_eq!(logs[0], FailureLog::Log);
+        for (i, log) in logs.iter().enumerate().skip(1) {
+            assert_eq!(*log, FailureLog::Suppress { repeats: i as u64 });
+        }
+
+        // Other kinds of failure are limited independently.
+        assert_eq!(
+            limiter.failure(FailureKind::ForkChoice, start, window),
+            FailureLog::Log
+        );
+
+        assert_eq!(limiter.success(kind), Some(10));
+        assert_eq!(limiter.success(kind), None);
+        // A failure after the condition has cleared is logged again.
+        assert_eq!(limiter.failure(kind, start, window), FailureLog::Log);
+    }
+
+    #[test]
+    fn failures_are_logged_once_per_window() {
+        let limiter = FailureLogLimiter::default();
+        let window = Duration::from_secs(60);
+        let start = Instant::now();
+        let kind = FailureKind::PrepareProposer;
+
+        assert_eq!(limiter.failure(kind, start, window), FailureLog::Log);
+        assert_eq!(
+            limiter.failure(kind, start + Duration::from_secs(59), window),
+            FailureLog::Suppress { repeats: 1 }
+        );
+        assert_eq!(
+            limiter.failure(kind, start + Duration::from_secs(60), window),
+            FailureLog::Log
+        );
+        assert_eq!(
+            limiter.failure(kind, start + Duration::from_secs(61), window),
+            FailureLog::Suppress { repeats: 1 }
+        );
+        assert_eq!(limiter.success(kind), Some(4));
+    }
+}
@@ -0,0 +1,186 @@
+//! Tracks how many epochs have passed since finality, escalating as the delay grows.
+//!
+//! The per-slot task compares the wall-clock epoch to the finalized epoch of the head. Each time
+//! either changes the delay is reported: a warning or critical log is emitted once the delay
+//! reaches `ChainConfig::finality_delay_warn_epochs` or `ChainConfig::finality_delay_crit_epochs`,
+//! and a `finality_delay` server-sent event is published. If configured, backfill sync is paused
+//! whilst the delay is critical.
+use crate::chain_config::ChainConfig;
+use crate::{metrics, BeaconChain, BeaconChainTypes};
+use eth2::types::{EventKind, FinalityDelayLevel, SseFinalityDelay};
+use parking_lot::Mutex;
+use slog::{crit, info, warn};
+use types::{Epoch, EthSpec, Slot};
+
+#[derive(Default)]
+struct WatchdogState {
+    /// The most recent report.
+    last_report: Option<SseFinalityDelay>,
+    /// Whether backfill sync was paused by the watchdog, rather than by the user.
+    paused_backfill: bool,
+}
+
+#[derive(Default)]
+pub struct FinalityWatchdog {
+    state: Mutex<WatchdogState>,
+}
+
+impl FinalityWatchdog {
+    /// Record the wall-clock `epoch` and the `finalized_epoch` of the head, returning a new report
+    /// if either has changed since the last observation.
+    pub fn observe(
+        &self,
+        epoch: Epoch,
+        finalized_epoch: Epoch,
+        config: &ChainConfig,
+    ) -> Option<SseFinalityDelay> {
+        let mut state = self.state.lock();
+        if state.last_report.as_ref().map_or(false, |report| {
+            report.epoch == epoch && report.finalized_epoch == finalized_epoch
+        }) {
+            return None;
+        }
+
+        let epochs_since_finality = epoch.saturating_sub(finalized_epoch).as_u64();
+        let level = if epochs_since_finality >= config.finality_delay_crit_epochs {
+            FinalityDelayLevel::Critical
+        } else if epochs_since_finality >= config.finality_delay_warn_epochs {
+            FinalityDelayLevel::Warning
+        } else {
+            FinalityDelayLevel::Healthy
+        };
+        let report = SseFinalityDelay {
+            epoch,
+            finalized_epoch,
+            epochs_since_finality,
+            level,
+        };
+        state.last_report = Some(report.clone());
+        Some(report)
+    }
+
+    pub fn last_report(&self) -> Option<SseFinalityDelay> {
+        self.state.lock().last_report.clone()
+    }
+}
+
+impl<T: BeaconChainTypes> BeaconChain<T> {
+    /// Returns the most recent finality delay observed by the per-slot task.
+    pub fn finality_delay(&self) -> Option<SseFinalityDelay> {
+        self.finality_watchdog.last_report()
+    }
+
+    /// Observe the finality delay at the wall-clock `slot`, alerting if it has grown too large.
+    ///
+    /// Whilst the head is syncing its finalized checkpoint is expected to be far behind, so only
+    /// the metric is updated.
+    pub fn check_finality_delay(&self, slot: Slot, head_is_syncing: bool) {
+        let epoch = slot.epoch(T::EthSpec::slots_per_epoch());
+        let finalized_epoch = self
+            .canonical_head
+            .cached_head()
+            .finalized_checkpoint()
+            .epoch;
+        metrics::set_gauge(
+            &metrics::FINALITY_DELAY_EPOCHS,
+            epoch.saturating_sub(finalized_epoch).as_u64() as i64,
+        );
+        if head_is_syncing {
+            return;
+        }
+
+        let previous_level = self.finality_delay().map(|report| report.level);
+        let report = match self
+            .finality_watchdog
+            .observe(epoch, finalized_epoch, &self.config)
+        {
+            Some(report) => report,
+            None => return,
+        };
+
+        match report.level {
+            FinalityDelayLevel::Critical => crit!(
+                self.log,
+                "Chain is not finalizing";
+                "msg" => "the network may be partitioned or many validators may be offline",
+                "epochs_since_finality" => report.epochs_since_finality,
+                "finalized_epoch" => report.finalized_epoch,
+            ),
+            FinalityDelayLevel::Warning => warn!(
+                self.log,
+                "Finality is delayed";
+                "epochs_since_finality" => report.epochs_since_finality,
+                "finalized_epoch" => report.finalized_epoch,
+            ),
+            FinalityDelayLevel::Healthy => {
+                if previous_level.map_or(false, |level| level > FinalityDelayLevel::Healthy) {
+                    info!(
+                        self.log,
+                        "Finality restored";
+                        "epochs_since_finality" => report.epochs_since_finality,
+                        "finalized_epoch" => report.finalized_epoch,
+                    );
+                }
+            }
+        }
+
+        self.pause_backfill_for_finality_delay(report.level);
+
+        if let Some(event_handler) = self
+            .event_handler
+            .as_ref()
+            .filter(|handler| handler.has_finality_delay_subscribers())
+        {
+            event_handler.register(EventKind::FinalityDelay(report));
+        }
+    }
+
+    /// Pause backfill sync whilst the finality delay is critical, if configured, and resume it
+    /// once the delay is no longer critical.
+    ///
+    /// Backfill is only resumed if it was paused here.
+    fn pause_backfill_for_finality_delay(&self, level: FinalityDelayLevel) {
+        let mut state = self.finality_watchdog.state.lock();
+        let critical = level == FinalityDelayLevel::Critical;
+        if critical && self.config.finality_delay_pause_backfill && !state.paused_backfill {
+            if !self.backfill_rate_limiter.is_paused() {
+                self.backfill_rate_limiter.pause();
+                state.paused_backfill = true;
+                warn!(self.log, "Paused backfill sync whilst finality is delayed");
+            }
+        } else if !critical && state.paused_backfill {
+            self.backfill_rate_limiter.resume();
+            state.paused_backfill = false;
+            info!(self.log, "Resumed backfill sync");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn observe_escalates() {
+        let config = ChainConfig {
+            finality_delay_warn_epochs: 3,
+            finality_delay_crit_epochs: 5,
+            ..ChainConfig::default()
+        };
+        let watchdog = FinalityWatchdog::default();
+        let level = |epoch, finalized_epoch| {
+            watchdog
+                .observe(Epoch::new(epoch), Epoch::new(finalized_epoch), &config)
+                .map(|report| report.level)
+        };
+
+        assert_eq!(level(2, 0), Some(FinalityDelayLevel::Healthy));
+        // Repeated observations within the same epoch aren't reported.
+        assert_eq!(level(2, 0), None);
+        assert_eq!(level(3, 0), Some(FinalityDelayLevel::Warning));
+        assert_eq!(level(4, 0), Some(FinalityDelayLevel::Warning));
+        assert_eq!(level(5, 0), Some(FinalityDelayLevel::Critical));
+        // Finalization within an epoch is reported.
+        assert_eq!(level(5, 3), Some(FinalityDelayLevel::Healthy));
+    }
+}
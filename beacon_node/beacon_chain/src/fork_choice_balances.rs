@@ -0,0 +1,87 @@
+//! Provides a summary of the balances used by fork choice to weigh votes.
+//!
+//! When fork choice selects a surprising head, the first thing to check is which effective
+//! balances it used. The balances are already held by the fork choice store, so no states need to
+//! be loaded to produce the summary. The checksum allows the balances of two nodes to be compared
+//! without transferring them, whilst the full list can be dumped to a file for closer inspection.
+use crate::{BeaconChain, BeaconChainTypes};
+use fork_choice::ForkChoiceStore;
+use ssz::Encode;
+use std::fs;
+use std::path::Path;
+use types::{Checkpoint, Hash256};
+
+/// A summary of the balances of the justified checkpoint used by fork choice.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ForkChoiceBalancesSummary {
+    /// The justified checkpoint whose balances are used.
+    pub justified_checkpoint: Checkpoint,
+    /// The sum of the balances, in gwei.
+    ///
+    /// Validators which were not active at the justified checkpoint have a balance of zero.
+    pub total_active_balance: u64,
+    /// The number of validators with a non-zero balance.
+    pub non_zero_validator_count: usize,
+    /// The total number of validators, including those with a zero balance.
+    pub validator_count: usize,
+    /// The hash of the SSZ encoding of the balances.
+    pub checksum: Hash256,
+}
+
+impl ForkChoiceBalancesSummary {
+    /// Summarise `balances`, the balances of `justified_checkpoint`.
+    pub fn new(justified_checkpoint: Checkpoint, balances: &[u64]) -> Self {
+        Self {
+            justified_checkpoint,
+            total_active_balance: balances.iter().sum(),
+            non_zero_validator_count: balances.iter().filter(|balance| **balance > 0).count(),
+            validator_count: balances.len(),
+            checksum: balances_checksum(balances),
+        }
+    }
+}
+
+/// The hash of the SSZ encoding of `balances`.
+pub fn balances_checksum(balances: &[u64]) -> Hash256 {
+    Hash256::from_slice(&eth2_hashing::hash(&balances.to_vec().as_ssz_bytes()))
+}
+
+impl<T: BeaconChainTypes> BeaconChain<T> {
+    /// Summarise the justified balances currently used by fork choice.
+    pub fn fork_choice_balances_summary(&self) -> ForkChoiceBalancesSummary {
+        let fork_choice = self.canonical_head.fork_choice_read_lock();
+        let fc_store = fork_choice.fc_store();
+        ForkChoiceBalancesSummary::new(
+            *fc_store.justified_checkpoint(),
+            fc_store.justified_balances(),
+        )
+    }
+
+    /// Write the justified balances currently used by fork choice to `path` as a JSON list,
+    /// returning the summary of the balances written.
+    ///
+    /// This is intended for debugging only.
+    pub fn dump_fork_choice_balances(
+        &self,
+        path: &Path,
+    ) -> Result<ForkChoiceBalancesSummary, String> {
+        // Copy the balances so the fork choice lock isn't held whilst writing the file.
+        let (justified_checkpoint, balances) = {
+            let fork_choice = self.canonical_head.fork_choice_read_lock();
+            let fc_store = fork_choice.fc_store();
+            (
+                *fc_store.justified_checkpoint(),
+                fc_store.justified_balances().to_vec(),
+            )
+        };
+
+        let json = serde_json::to_string(&balances)
+            .map_err(|e| format!("Unable to encode fork choice balances: {:?}", e))?;
+        fs::write(path, json).map_err(|e| format!("Unable to write {:?}: {:?}", path, e))?;
+
+        Ok(ForkChoiceBalancesSummary::new(
+            justified_checkpoint,
+            &balances,
+        ))
+    }
+}
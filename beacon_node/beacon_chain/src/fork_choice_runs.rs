@@ -0,0 +1,144 @@
+//! Summarizes the fork choice runs at the start of each slot.
+//!
+//! Attestations from the current slot are queued by fork choice and only applied once their slot
+//! has passed, whenever fork choice is next brought up to date with the slot clock. This may happen
+//! before the run at the start of the slot, for instance when an attestation or block is imported,
+//! so fork choice accumulates counts of the dequeued attestations which each run then takes. A
+//! summary of how many were dequeued, applied and dropped, along with the duration of the runs,
+//! helps to diagnose votes which lag behind the head.
+use crate::{metrics, BeaconChain, BeaconChainError as Error, BeaconChainTypes};
+use parking_lot::Mutex;
+use serde_derive::{Deserialize, Serialize};
+use slog::debug;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use types::Slot;
+
+/// The number of recent summaries to retain.
+pub const FORK_CHOICE_RUN_HISTORY_LEN: usize = 8;
+
+/// A summary of the fork choice run at the start of `slot`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ForkChoiceRunSummary {
+    pub slot: Slot,
+    /// The number of queued attestations which became eligible since the previous run.
+    pub queued_attestations: usize,
+    /// The number of queued attestations which were applied.
+    pub applied: usize,
+    /// The number of queued attestations which could not be applied and were dropped.
+    pub dropped_invalid: usize,
+    /// The time taken to process the queue and recompute the head, across all runs for `slot`.
+    pub duration: Duration,
+}
+
+/// The summaries of the most recent fork choice runs, oldest first.
+#[derive(Default)]
+pub struct ForkChoiceRunHistory {
+    summaries: Mutex<VecDeque<ForkChoiceRunSummary>>,
+}
+
+impl ForkChoiceRunHistory {
+    /// Record `summary`, returning the summary which is retained for its slot.
+    ///
+    /// Fork choice may be run more than once for a slot (e.g. by the state advance timer prior to
+    /// the slot and again at its start), in which case the runs are merged into a single summary.
+    /// Otherwise, the oldest summary is evicted if the history is full.
+    pub fn insert(&self, summary: ForkChoiceRunSummary) -> ForkChoiceRunSummary {
+        let mut summaries = self.summaries.lock();
+        if let Some(existing) = summaries
+            .back_mut()
+            .filter(|existing| existing.slot == summary.slot)
+        {
+            existing.queued_attestations += summary.queued_attestations;
+            existing.applied += summary.applied;
+            existing.dropped_invalid += summary.dropped_invalid;
+            existing.duration += summary.duration;
+            return *existing;
+        }
+
+        if summaries.len() >= FORK_CHOICE_RUN_HISTORY_LEN {
+            summaries.pop_front();
+        }
+        summaries.push_back(summary);
+        summary
+    }
+
+    /// Returns the recorded summaries, oldest first.
+    pub fn summaries(&self) -> Vec<ForkChoiceRunSummary> {
+        self.summaries.lock().iter().copied().collect()
+    }
+}
+
+impl<T: BeaconChainTypes> BeaconChain<T> {
+    /// Bring fork choice up to date with `slot`, applying any queued attestations which are now
+    /// eligible, then recompute the head.
+    ///
+    /// This is intended for the run at (or just prior to) the start of each slot. The summary of
+    /// the run is logged, exported as metrics and retained for the API.
+    pub async fn fork_choice_at_slot(
+        self: &Arc<Self>,
+        slot: Slot,
+    ) -> Result<ForkChoiceRunSummary, Error> {
+        self.ensure_writable()?;
+
+        let _timer = metrics::start_timer(&metrics::FORK_CHOICE_RUN_SECONDS);
+        let start = Instant::now();
+
+        let chain = self.clone();
+        let stats = self
+            .spawn_blocking_handle(
+                move || {
+                    let mut fork_choice = chain.canonical_head.fork_choice_write_lock();
+                    fork_choice
+                        .update_time(slot)
+                        .map_err(Error::ForkChoiceError)?;
+                    Ok::<_, Error>(fork_choice.take_attestation_queue_stats())
+                },
+                "fork_choice_attestation_queue",
+            )
+            .await??;
+
+        self.recompute_head_at_slot(slot).await?;
+
+        // Counters are incremented by this run alone, whilst the gauge and the log reflect all runs
+        // for the slot.
+        metrics::inc_counter_by(
+            &metrics::FORK_CHOICE_RUN_APPLIED_ATTESTATIONS,
+            stats.applied as u64,
+        );
+        metrics::inc_counter_by(
+            &metrics::FORK_CHOICE_RUN_DROPPED_ATTESTATIONS,
+            stats.dropped_invalid as u64,
+        );
+
+        let summary = self.fork_choice_run_history.insert(ForkChoiceRunSummary {
+            slot,
+            queued_attestations: stats.queued,
+            applied: stats.applied,
+            dropped_invalid: stats.dropped_invalid,
+            duration: start.elapsed(),
+        });
+
+        metrics::set_gauge(
+            &metrics::FORK_CHOICE_RUN_QUEUED_ATTESTATIONS,
+            summary.queued_attestations as i64,
+        );
+        debug!(
+            self.log,
+            "Fork choice run at slot start";
+            "slot" => slot,
+            "queued_attestations" => summary.queued_attestations,
+            "applied" => summary.applied,
+            "dropped_invalid" => summary.dropped_invalid,
+            "duration_ms" => %summary.duration.as_millis(),
+        );
+
+        Ok(summary)
+    }
+
+    /// Returns the summaries of the most recent fork choice runs, oldest first.
+    pub fn fork_choice_run_summaries(&self) -> Vec<ForkChoiceRunSummary> {
+        self.fork_choice_run_history.summaries()
+    }
+}
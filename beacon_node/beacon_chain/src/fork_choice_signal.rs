@@ -2,63 +2,106 @@
 //!
 //! The transmitter provides a way for a thread runnning fork choice on a schedule to signal
 //! to the receiver that fork choice has been updated for a given slot.
-use crate::BeaconChainError;
+//!
+//! The summary of the fork choice run, if any, is carried along with the slot.
+//!
+//! The receiver may be waited upon by blocking the current thread, or asynchronously.
+//! `BeaconChain::wait_for_slot` also waits for the start of the slot, since the fork choice run
+//! for a slot may complete before the slot starts.
+use crate::fork_choice_runs::ForkChoiceRunSummary;
+use crate::{BeaconChain, BeaconChainError, BeaconChainTypes};
 use parking_lot::{Condvar, Mutex};
+use slot_clock::SlotClock;
 use std::sync::Arc;
 use std::time::Duration;
+use tokio::sync::watch;
+use tokio::time::Instant;
 use types::Slot;
 
+/// The most recent fork choice run signalled by the sender.
+#[derive(Debug, Default, Clone, Copy)]
+struct CompletedRun {
+    slot: Slot,
+    summary: Option<ForkChoiceRunSummary>,
+}
+
 /// Sender, for use by the per-slot task timer.
 pub struct ForkChoiceSignalTx {
-    pair: Arc<(Mutex<Slot>, Condvar)>,
+    pair: Arc<(Mutex<CompletedRun>, Condvar)>,
+    watch_tx: watch::Sender<CompletedRun>,
 }
 
 /// Receiver, for use by the beacon chain waiting on fork choice to complete.
 pub struct ForkChoiceSignalRx {
-    pair: Arc<(Mutex<Slot>, Condvar)>,
+    pair: Arc<(Mutex<CompletedRun>, Condvar)>,
+    watch_rx: watch::Receiver<CompletedRun>,
 }
 
 pub enum ForkChoiceWaitResult {
-    /// Successfully reached a slot greater than or equal to the awaited slot.
-    Success(Slot),
+    /// Successfully reached a slot greater than or equal to the awaited slot. The summary of the
+    /// run is included, unless the run failed.
+    Success(Slot, Option<ForkChoiceRunSummary>),
     /// Fork choice was updated to a lower slot, indicative of lag or processing delays.
     Behind(Slot),
     /// Timed out waiting for the fork choice update from the sender.
     TimeOut,
 }
 
+/// The result of `BeaconChain::wait_for_slot`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SlotWaitResult {
+    /// The slot has started and fork choice has been updated to a slot greater than or equal to it.
+    /// The summary of the run is included, unless the run failed.
+    Success(Slot, Option<ForkChoiceRunSummary>),
+    /// The slot has started. The fork choice signal isn't configured, so fork choice wasn't awaited.
+    SlotStarted,
+    /// The slot has started, but fork choice was updated to a lower slot.
+    Behind(Slot),
+    /// Timed out waiting for the slot to start or for the fork choice update.
+    TimeOut,
+}
+
 impl ForkChoiceSignalTx {
     pub fn new() -> Self {
-        let pair = Arc::new((Mutex::new(Slot::new(0)), Condvar::new()));
-        Self { pair }
+        let pair = Arc::new((Mutex::new(CompletedRun::default()), Condvar::new()));
+        let (watch_tx, _) = watch::channel(CompletedRun::default());
+        Self { pair, watch_tx }
     }
 
     pub fn get_receiver(&self) -> ForkChoiceSignalRx {
         ForkChoiceSignalRx {
             pair: self.pair.clone(),
+            watch_rx: self.watch_tx.subscribe(),
         }
     }
 
-    /// Signal to the receiver that fork choice has been updated to `slot`.
+    /// Signal to the receiver that fork choice has been updated to `slot`, with the `summary` of
+    /// the run if it succeeded.
     ///
     /// Return an error if the provided `slot` is strictly less than any previously provided slot.
-    pub fn notify_fork_choice_complete(&self, slot: Slot) -> Result<(), BeaconChainError> {
+    pub fn notify_fork_choice_complete(
+        &self,
+        slot: Slot,
+        summary: Option<ForkChoiceRunSummary>,
+    ) -> Result<(), BeaconChainError> {
         let &(ref lock, ref condvar) = &*self.pair;
 
-        let mut current_slot = lock.lock();
+        let mut current_run = lock.lock();
 
-        if slot < *current_slot {
+        if slot < current_run.slot {
             return Err(BeaconChainError::ForkChoiceSignalOutOfOrder {
-                current: *current_slot,
+                current: current_run.slot,
                 latest: slot,
             });
         } else {
-            *current_slot = slot;
+            *current_run = CompletedRun { slot, summary };
         }
 
         // We use `notify_all` because there may be multiple block proposals waiting simultaneously.
         // Usually there'll be 0-1.
         condvar.notify_all();
+        // Use `send_replace` so that the value is updated even if there are no async receivers.
+        self.watch_tx.send_replace(*current_run);
 
         Ok(())
     }
@@ -74,24 +117,92 @@ impl ForkChoiceSignalRx {
     pub fn wait_for_fork_choice(&self, slot: Slot, timeout: Duration) -> ForkChoiceWaitResult {
         let &(ref lock, ref condvar) = &*self.pair;
 
-        let mut current_slot = lock.lock();
+        let mut current_run = lock.lock();
 
-        // Wait for `current_slot >= slot`.
+        // Wait for `current_run.slot >= slot`.
         //
         // Do not loop and wait, if we receive an update for the wrong slot then something is
         // quite out of whack and we shouldn't waste more time waiting.
-        if *current_slot < slot {
-            let timeout_result = condvar.wait_for(&mut current_slot, timeout);
+        if current_run.slot < slot {
+            let timeout_result = condvar.wait_for(&mut current_run, timeout);
 
             if timeout_result.timed_out() {
                 return ForkChoiceWaitResult::TimeOut;
             }
         }
 
-        if *current_slot >= slot {
-            ForkChoiceWaitResult::Success(*current_slot)
+        current_run.wait_result(slot)
+    }
+
+    /// As per `wait_for_fork_choice`, but waits asynchronously rather than blocking the thread.
+    pub async fn wait_for_fork_choice_async(
+        &self,
+        slot: Slot,
+        timeout: Duration,
+    ) -> ForkChoiceWaitResult {
+        let mut watch_rx = self.watch_rx.clone();
+
+        // Mark the current value as seen, so that only a subsequent update is awaited.
+        let current_slot = watch_rx.borrow_and_update().slot;
+
+        // As above, wait for a single update rather than looping.
+        if current_slot < slot {
+            match tokio::time::timeout(timeout, watch_rx.changed()).await {
+                Ok(Ok(())) => (),
+                // The sender has been dropped, so there will be no update.
+                Ok(Err(_)) | Err(_) => return ForkChoiceWaitResult::TimeOut,
+            }
+        }
+
+        let current_run = *watch_rx.borrow();
+        current_run.wait_result(slot)
+    }
+}
+
+impl CompletedRun {
+    fn wait_result(&self, slot: Slot) -> ForkChoiceWaitResult {
+        if self.slot >= slot {
+            ForkChoiceWaitResult::Success(self.slot, self.summary)
         } else {
-            ForkChoiceWaitResult::Behind(*current_slot)
+            ForkChoiceWaitResult::Behind(self.slot)
+        }
+    }
+}
+
+impl<T: BeaconChainTypes> BeaconChain<T> {
+    /// Wait until the wall-clock has reached `slot` and, if the fork choice signal is configured,
+    /// until the fork choice run for `slot` has completed.
+    ///
+    /// Resolves immediately if both have already happened. Returns `SlotWaitResult::TimeOut` if
+    /// they haven't happened within `timeout`.
+    pub async fn wait_for_slot(&self, slot: Slot, timeout: Duration) -> SlotWaitResult {
+        let deadline = Instant::now() + timeout;
+
+        // The duration is `None` if the slot has already started.
+        if let Some(duration) = self.slot_clock.duration_to_slot(slot) {
+            if tokio::time::timeout_at(deadline, tokio::time::sleep(duration))
+                .await
+                .is_err()
+            {
+                return SlotWaitResult::TimeOut;
+            }
+        }
+
+        let rx = if let Some(rx) = &self.fork_choice_signal_rx {
+            rx
+        } else {
+            return SlotWaitResult::SlotStarted;
+        };
+
+        match rx
+            .wait_for_fork_choice_async(slot, deadline.saturating_duration_since(Instant::now()))
+            .await
+        {
+            ForkChoiceWaitResult::Success(fc_slot, summary) => {
+                SlotWaitResult::Success(fc_slot, summary)
+            }
+            ForkChoiceWaitResult::Behind(fc_slot) => SlotWaitResult::Behind(fc_slot),
+            ForkChoiceWaitResult::TimeOut => SlotWaitResult::TimeOut,
         }
     }
 }
@@ -9,7 +9,10 @@ use state_processing::{
 use std::sync::Arc;
 use std::time::Duration;
 use store::{iter::ParentRootBlockIterator, HotColdDB, ItemStore};
-use types::{BeaconState, ChainSpec, EthSpec, ForkName, Hash256, SignedBeaconBlock, Slot};
+use types::{
+    BeaconState, BlindedPayload, ChainSpec, Checkpoint, EthSpec, ForkName, Hash256,
+    SignedBeaconBlock, Slot,
+};
 
 const CORRUPT_DB_MESSAGE: &str = "The database could be corrupt. Check its file permissions or \
                                   consider deleting it by running with the --purge-db flag.";
@@ -100,8 +103,37 @@ pub fn reset_fork_choice_to_finalization<E: EthSpec, Hot: ItemStore<E>, Cold: It
     current_slot: Option<Slot>,
     spec: &ChainSpec,
 ) -> Result<ForkChoice<BeaconForkChoiceStore<E, Hot, Cold>, E>, String> {
-    // Fetch finalized block.
     let finalized_checkpoint = head_state.finalized_checkpoint();
+    let (mut fork_choice, finalized_state) =
+        fork_choice_from_checkpoint(finalized_checkpoint, store.clone(), current_slot, spec)?;
+
+    // Replay blocks from finalized checkpoint back to head.
+    // We do not replay attestations presently, relying on the absence of other blocks
+    // to guarantee `head_block_root` as the head.
+    let finalized_slot = finalized_checkpoint.epoch.start_slot(E::slots_per_epoch());
+    let blocks = store
+        .load_blocks_to_replay(finalized_slot + 1, head_state.slot(), head_block_root)
+        .map_err(|e| format!("Error loading blocks to replay for fork choice: {:?}", e))?;
+    replay_blocks_into_fork_choice(&mut fork_choice, finalized_state, blocks, spec)?;
+
+    Ok(fork_choice)
+}
+
+/// Initialize fork choice from the finalized `checkpoint`, returning it along with the finalized
+/// state advanced to the start of the checkpoint's epoch.
+pub(crate) fn fork_choice_from_checkpoint<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>>(
+    finalized_checkpoint: Checkpoint,
+    store: Arc<HotColdDB<E, Hot, Cold>>,
+    current_slot: Option<Slot>,
+    spec: &ChainSpec,
+) -> Result<
+    (
+        ForkChoice<BeaconForkChoiceStore<E, Hot, Cold>, E>,
+        BeaconState<E>,
+    ),
+    String,
+> {
+    // Fetch finalized block.
     let finalized_block_root = finalized_checkpoint.root;
     let finalized_block = store
         .get_full_block(&finalized_block_root)
@@ -145,7 +177,7 @@ pub fn reset_fork_choice_to_finalization<E: EthSpec, Hot: ItemStore<E>, Cold: It
 
     let fc_store = BeaconForkChoiceStore::get_forkchoice_store(store.clone(), &finalized_snapshot);
 
-    let mut fork_choice = ForkChoice::from_anchor(
+    let fork_choice = ForkChoice::from_anchor(
         fc_store,
         finalized_block_root,
         &finalized_snapshot.beacon_block,
@@ -155,14 +187,18 @@ pub fn reset_fork_choice_to_finalization<E: EthSpec, Hot: ItemStore<E>, Cold: It
     )
     .map_err(|e| format!("Unable to reset fork choice for revert: {:?}", e))?;
 
-    // Replay blocks from finalized checkpoint back to head.
-    // We do not replay attestations presently, relying on the absence of other blocks
-    // to guarantee `head_block_root` as the head.
-    let blocks = store
-        .load_blocks_to_replay(finalized_slot + 1, head_state.slot(), head_block_root)
-        .map_err(|e| format!("Error loading blocks to replay for fork choice: {:?}", e))?;
+    Ok((fork_choice, finalized_snapshot.beacon_state))
+}
 
-    let mut state = finalized_snapshot.beacon_state;
+/// Apply `blocks`, which must descend from the latest block applied to `state`, to `fork_choice`.
+///
+/// Blocks which are already known to fork choice are only applied to `state`.
+pub(crate) fn replay_blocks_into_fork_choice<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>>(
+    fork_choice: &mut ForkChoice<BeaconForkChoiceStore<E, Hot, Cold>, E>,
+    mut state: BeaconState<E>,
+    blocks: Vec<SignedBeaconBlock<E, BlindedPayload<E>>>,
+    spec: &ChainSpec,
+) -> Result<(), String> {
     for block in blocks {
         complete_state_advance(&mut state, None, block.slot(), spec)
             .map_err(|e| format!("State advance failed: {:?}", e))?;
@@ -177,6 +213,11 @@ pub fn reset_fork_choice_to_finalization<E: EthSpec, Hot: ItemStore<E>, Cold: It
         )
         .map_err(|e| format!("Error replaying block: {:?}", e))?;
 
+        let block_root = block.canonical_root();
+        if fork_choice.contains_block(&block_root) {
+            continue;
+        }
+
         // Setting this to unverified is the safest solution, since we don't have a way to
         // retro-actively determine if they were valid or not.
         //
@@ -187,7 +228,7 @@ pub fn reset_fork_choice_to_finalization<E: EthSpec, Hot: ItemStore<E>, Cold: It
             .on_block(
                 block.slot(),
                 block.message(),
-                block.canonical_root(),
+                block_root,
                 // Reward proposer boost. We are reinforcing the canonical chain.
                 Duration::from_secs(0),
                 &state,
@@ -197,5 +238,5 @@ pub fn reset_fork_choice_to_finalization<E: EthSpec, Hot: ItemStore<E>, Cold: It
             .map_err(|e| format!("Error applying replayed block to fork choice: {:?}", e))?;
     }
 
-    Ok(fork_choice)
+    Ok(())
 }
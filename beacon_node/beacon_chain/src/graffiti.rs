@@ -0,0 +1,117 @@
+//! The graffiti included in blocks produced by this node, when the validator doesn't supply its
+//! own.
+//!
+//! The mode is resolved to bytes each time a block is produced, so `Random` yields different
+//! graffiti in every block.
+use rand::Rng;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+use types::{Graffiti, GRAFFITI_BYTES_LEN};
+
+/// The lowest printable ASCII byte, a space.
+const PRINTABLE_MIN: u8 = 0x20;
+/// The highest printable ASCII byte, a tilde.
+const PRINTABLE_MAX: u8 = 0x7e;
+
+/// Determines the graffiti of blocks which lack graffiti from the validator.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GraffitiMode {
+    /// Fixed graffiti chosen by the user.
+    Custom(Graffiti),
+    /// The version of this build, truncated to fit in 32 bytes.
+    ClientVersion,
+    /// Zeroed graffiti, which reveals nothing about the node.
+    Empty,
+    /// Random printable ASCII bytes, chosen afresh for each block.
+    Random,
+}
+
+impl GraffitiMode {
+    /// Returns the graffiti to include in a block.
+    pub fn resolve(&self) -> Graffiti {
+        match self {
+            GraffitiMode::Custom(graffiti) => *graffiti,
+            GraffitiMode::ClientVersion => {
+                let version = lighthouse_version::VERSION.as_bytes();
+                let len = std::cmp::min(version.len(), GRAFFITI_BYTES_LEN);
+                let mut graffiti = Graffiti::default();
+                graffiti.0[..len].copy_from_slice(&version[..len]);
+                graffiti
+            }
+            GraffitiMode::Empty => Graffiti::default(),
+            GraffitiMode::Random => {
+                let mut rng = rand::thread_rng();
+                let mut graffiti = Graffiti::default();
+                for byte in graffiti.0.iter_mut() {
+                    *byte = rng.gen_range(PRINTABLE_MIN..=PRINTABLE_MAX);
+                }
+                graffiti
+            }
+        }
+    }
+}
+
+impl Default for GraffitiMode {
+    fn default() -> Self {
+        GraffitiMode::Empty
+    }
+}
+
+impl From<Graffiti> for GraffitiMode {
+    fn from(graffiti: Graffiti) -> Self {
+        GraffitiMode::Custom(graffiti)
+    }
+}
+
+/// Parses the name of a mode other than `Custom`.
+impl FromStr for GraffitiMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "client-version" => Ok(GraffitiMode::ClientVersion),
+            "empty" => Ok(GraffitiMode::Empty),
+            "random" => Ok(GraffitiMode::Random),
+            other => Err(format!("Unknown graffiti mode: {}", other)),
+        }
+    }
+}
+
+/// `Custom` graffiti is displayed as hex, other modes by name.
+impl fmt::Display for GraffitiMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GraffitiMode::Custom(graffiti) => write!(f, "{}", graffiti),
+            GraffitiMode::ClientVersion => write!(f, "client-version"),
+            GraffitiMode::Empty => write!(f, "empty"),
+            GraffitiMode::Random => write!(f, "random"),
+        }
+    }
+}
+
+impl Serialize for GraffitiMode {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            GraffitiMode::Custom(graffiti) => graffiti.serialize(serializer),
+            _ => serializer.collect_str(self),
+        }
+    }
+}
+
+/// Accepts either hex graffiti, as written by earlier versions, or the name of a mode.
+impl<'de> Deserialize<'de> for GraffitiMode {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(serde_derive::Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Custom(Graffiti),
+            Named(String),
+        }
+
+        match Repr::deserialize(deserializer)? {
+            Repr::Custom(graffiti) => Ok(GraffitiMode::Custom(graffiti)),
+            Repr::Named(name) => name.parse().map_err(serde::de::Error::custom),
+        }
+    }
+}
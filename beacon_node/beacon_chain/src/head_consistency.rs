@@ -0,0 +1,200 @@
+//! Detects divergence between the views of the head held by the canonical head snapshot, fork
+//! choice and the head tracker.
+//!
+//! The three views are updated at different times and persisted separately, so a crash may leave
+//! them disagreeing after a restart. Nothing depends on them agreeing directly, so divergence would
+//! otherwise go unnoticed until something downstream behaves oddly, such as a head being omitted
+//! from pruning or the snapshot lagging fork choice.
+//!
+//! The snapshot must agree with fork choice's head, and the head tracker must agree with the leaves
+//! of fork choice's block tree:
+//!
+//! - Every leaf which is viable for the head must be tracked. Leaves which aren't viable (e.g.,
+//!   blocks with invalid payloads) are ignored, since the head tracker has no notion of viability.
+//! - No block which fork choice knows to have children may be tracked. Tracked blocks which are
+//!   unknown to fork choice are ignored, since abandoned forks are pruned from fork choice before
+//!   they're pruned from the head tracker.
+//!
+//! The check only compares block roots, so it's cheap enough to run every slot. If
+//! `ChainConfig::heal_head_divergence` is set, a divergence is healed by re-running fork choice,
+//! refreshing the snapshot and bringing the head tracker into line with fork choice.
+use crate::beacon_chain::BeaconForkChoice;
+use crate::{metrics, BeaconChain, BeaconChainError as Error, BeaconChainTypes};
+use slog::{info, warn};
+use std::collections::HashSet;
+use std::sync::Arc;
+use types::Hash256;
+
+/// The head block root held by each view of the head.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeadConsistency {
+    /// The block root of the canonical head snapshot.
+    pub snapshot_head_root: Hash256,
+    /// The head block root as of the last run of fork choice.
+    pub fork_choice_head_root: Hash256,
+    /// The leaves of fork choice which are viable for the head, but missing from the head
+    /// tracker.
+    pub untracked_heads: Vec<Hash256>,
+    /// The blocks in the head tracker which have children in fork choice.
+    pub stale_heads: Vec<Hash256>,
+}
+
+impl HeadConsistency {
+    /// Returns `true` if all of the views agree upon the head.
+    pub fn is_consistent(&self) -> bool {
+        self.snapshot_head_root == self.fork_choice_head_root
+            && self.untracked_heads.is_empty()
+            && self.stale_heads.is_empty()
+    }
+}
+
+impl<T: BeaconChainTypes> BeaconChain<T> {
+    /// Compare the views of the head, logging any divergence and updating the metric.
+    ///
+    /// Blocks whilst the head is being recomputed, so avoid calling this from an async task.
+    pub fn check_head_consistency(&self) -> HeadConsistency {
+        // Hold the fork choice lock whilst reading the head tracker, since blocks are registered
+        // with both under the fork choice write-lock.
+        let (snapshot_head_root, fork_choice) = self.canonical_head.settled_head();
+        let (untracked_heads, stale_heads) = self.divergent_heads(&fork_choice);
+        let consistency = HeadConsistency {
+            snapshot_head_root,
+            fork_choice_head_root: fork_choice.cached_fork_choice_view().head_block_root,
+            untracked_heads,
+            stale_heads,
+        };
+        drop(fork_choice);
+
+        let consistent = consistency.is_consistent();
+        metrics::set_gauge(&metrics::HEAD_VIEWS_CONSISTENT, consistent as i64);
+        if !consistent {
+            warn!(
+                self.log,
+                "Views of the head have diverged";
+                "snapshot_head_root" => ?consistency.snapshot_head_root,
+                "fork_choice_head_root" => ?consistency.fork_choice_head_root,
+                "untracked_heads" => ?consistency.untracked_heads,
+                "stale_heads" => ?consistency.stale_heads,
+                "heal" => self.config.heal_head_divergence,
+            );
+        }
+
+        consistency
+    }
+
+    /// Returns the leaves of fork choice which are viable for the head but missing from the head
+    /// tracker, and the blocks in the head tracker which have children in fork choice.
+    fn divergent_heads(&self, fork_choice: &BeaconForkChoice<T>) -> (Vec<Hash256>, Vec<Hash256>) {
+        let proto_array = fork_choice.proto_array().core_proto_array();
+        let leaves = proto_array.leaves();
+
+        let untracked_heads = leaves
+            .iter()
+            .filter(|node| proto_array.is_viable_for_head(node))
+            .map(|node| node.root)
+            .filter(|root| !self.head_tracker.contains_head(*root))
+            .collect();
+
+        let leaf_roots = leaves.iter().map(|node| node.root).collect::<HashSet<_>>();
+        let stale_heads = self
+            .head_tracker
+            .heads()
+            .into_iter()
+            .map(|(root, _)| root)
+            .filter(|root| proto_array.indices.contains_key(root) && !leaf_roots.contains(root))
+            .collect();
+
+        (untracked_heads, stale_heads)
+    }
+
+    /// Bring the head tracker into line with the leaves of fork choice, registering any untracked
+    /// heads and removing any stale heads.
+    ///
+    /// Returns `true` if the head tracker was modified.
+    pub(crate) fn heal_head_tracker(&self) -> bool {
+        let fork_choice = self.canonical_head.fork_choice_read_lock();
+        let (untracked_heads, stale_heads) = self.divergent_heads(&fork_choice);
+        let proto_array = fork_choice.proto_array().core_proto_array();
+
+        for root in &untracked_heads {
+            if let Some(node) = proto_array
+                .indices
+                .get(root)
+                .and_then(|index| proto_array.nodes.get(*index))
+            {
+                let parent_root = node
+                    .parent
+                    .and_then(|index| proto_array.nodes.get(index))
+                    .map_or_else(Hash256::zero, |parent| parent.root);
+                self.head_tracker
+                    .register_block(node.root, parent_root, node.slot);
+            }
+        }
+        let mut head_tracker = self.head_tracker.0.write();
+        for root in &stale_heads {
+            head_tracker.remove(root);
+        }
+
+        !untracked_heads.is_empty() || !stale_heads.is_empty()
+    }
+
+    /// Re-run fork choice, refreshing the snapshot, and bring the head tracker into line with fork
+    /// choice.
+    ///
+    /// Returns the consistency of the views afterwards.
+    pub async fn heal_head_divergence(self: &Arc<Self>) -> Result<HeadConsistency, Error> {
+        self.recompute_head_at_current_slot().await?;
+
+        let chain = self.clone();
+        let consistency = self
+            .spawn_blocking_handle(
+                move || {
+                    chain.heal_head_tracker();
+                    chain.check_head_consistency()
+                },
+                "heal_head_divergence",
+            )
+            .await?;
+        if consistency.is_consistent() {
+            info!(
+                self.log,
+                "Healed divergent views of the head";
+                "head_root" => ?consistency.snapshot_head_root,
+            );
+        }
+        Ok(consistency)
+    }
+
+    /// Check the views of the head and, if configured, heal any divergence.
+    pub(crate) async fn check_and_heal_head_consistency(self: &Arc<Self>) {
+        let chain = self.clone();
+        let consistent = match self
+            .spawn_blocking_handle(
+                move || chain.check_head_consistency().is_consistent(),
+                "check_head_consistency",
+            )
+            .await
+        {
+            Ok(consistent) => consistent,
+            Err(e) => {
+                warn!(
+                    self.log,
+                    "Unable to check the views of the head";
+                    "error" => ?e,
+                );
+                return;
+            }
+        };
+        if consistent || !self.config.heal_head_divergence {
+            return;
+        }
+
+        if let Err(e) = self.heal_head_divergence().await {
+            warn!(
+                self.log,
+                "Unable to heal divergent views of the head";
+                "error" => ?e,
+            );
+        }
+    }
+}
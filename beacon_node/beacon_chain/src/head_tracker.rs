@@ -1,11 +1,20 @@
 use parking_lot::RwLock;
+use ssz::{BYTES_PER_LENGTH_OFFSET, MAX_LENGTH_VALUE};
 use ssz_derive::{Decode, Encode};
 use std::collections::HashMap;
 use types::{Hash256, Slot};
 
+/// The maximum number of heads which can be encoded in an `SszHeadTracker`.
+///
+/// The container is two variable-length lists, so the offset of the end of the slots list must
+/// fit in a length offset. Beyond this, SSZ encoding would silently produce a corrupt container.
+pub const MAX_SSZ_HEADS: usize = (MAX_LENGTH_VALUE - 2 * BYTES_PER_LENGTH_OFFSET)
+    / (std::mem::size_of::<Hash256>() + std::mem::size_of::<Slot>());
+
 #[derive(Debug, PartialEq)]
 pub enum Error {
     MismatchingLengths { roots_len: usize, slots_len: usize },
+    TooManyHeads { heads: usize, max_heads: usize },
 }
 
 /// Maintains a list of `BeaconChain` head block roots and slots.
@@ -48,6 +57,39 @@ impl HeadTracker {
         SszHeadTracker::from_map(&*self.0.read())
     }
 
+    /// As per `Self::to_ssz_container`, but returns an error rather than a container of more than
+    /// `max_heads` heads.
+    pub fn try_to_ssz_container(&self, max_heads: usize) -> Result<SszHeadTracker, Error> {
+        let map = self.0.read();
+        let max_heads = std::cmp::min(max_heads, MAX_SSZ_HEADS);
+        if map.len() > max_heads {
+            return Err(Error::TooManyHeads {
+                heads: map.len(),
+                max_heads,
+            });
+        }
+        Ok(SszHeadTracker::from_map(&*map))
+    }
+
+    /// Returns a `SszHeadTracker` of the `max_heads` most recent heads, along with the heads which
+    /// were dropped from it.
+    ///
+    /// `Self` is not modified.
+    pub fn to_truncated_ssz_container(
+        &self,
+        max_heads: usize,
+    ) -> (SszHeadTracker, Vec<(Hash256, Slot)>) {
+        let max_heads = std::cmp::min(max_heads, MAX_SSZ_HEADS);
+        let mut heads = self.heads();
+        // Most recent first, breaking ties by root so the result is deterministic.
+        heads.sort_unstable_by(|(root_a, slot_a), (root_b, slot_b)| {
+            slot_b.cmp(slot_a).then_with(|| root_a.cmp(root_b))
+        });
+        let dropped = heads.split_off(std::cmp::min(max_heads, heads.len()));
+        let (roots, slots) = heads.into_iter().unzip();
+        (SszHeadTracker { roots, slots }, dropped)
+    }
+
     /// Creates a new `Self` from the given `SszHeadTracker`, restoring `Self` to the same state of
     /// the `Self` that created the `SszHeadTracker`.
     pub fn from_ssz_container(ssz_container: &SszHeadTracker) -> Result<Self, Error> {
@@ -185,6 +227,44 @@ mod test {
         );
     }
 
+    #[test]
+    fn too_many_heads() {
+        let head_tracker = HeadTracker::default();
+        for i in 0..16 {
+            head_tracker
+                .0
+                .write()
+                .insert(Hash256::from_low_u64_be(i), Slot::new(i));
+        }
+
+        assert!(head_tracker.try_to_ssz_container(16).is_ok());
+        assert_eq!(
+            head_tracker.try_to_ssz_container(4).err(),
+            Some(Error::TooManyHeads {
+                heads: 16,
+                max_heads: 4
+            })
+        );
+
+        let (container, dropped) = head_tracker.to_truncated_ssz_container(4);
+        assert_eq!(dropped.len(), 12);
+        assert!(dropped.iter().all(|(_, slot)| *slot < 12));
+
+        let bytes = container.as_ssz_bytes();
+        let truncated = HeadTracker::from_ssz_container(
+            &SszHeadTracker::from_ssz_bytes(&bytes).expect("should decode"),
+        )
+        .expect("should restore");
+        let mut heads = truncated.heads();
+        heads.sort();
+        assert_eq!(
+            heads,
+            (12..16)
+                .map(|i| (Hash256::from_low_u64_be(i), Slot::new(i)))
+                .collect::<Vec<_>>()
+        );
+    }
+
     #[test]
     fn bad_length() {
         let container = SszHeadTracker {
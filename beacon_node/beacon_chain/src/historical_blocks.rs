@@ -10,7 +10,7 @@ use std::iter;
 use std::sync::Arc;
 use std::time::Duration;
 use store::{chunked_vector::BlockRoots, AnchorInfo, ChunkWriter, KeyValueStore};
-use types::{Hash256, SignedBlindedBeaconBlock, Slot};
+use types::{BeaconState, EthSpec, Hash256, SignedBlindedBeaconBlock, Slot};
 
 /// Use a longer timeout on the pubkey cache.
 ///
@@ -26,6 +26,13 @@ pub enum HistoricalBlockError {
         block_root: Hash256,
         expected_block_root: Hash256,
     },
+    /// The block at `slot` doesn't match the `block_roots` of the anchor state, caller should
+    /// retry with different blocks.
+    AnchorBlockRootMismatch {
+        slot: Slot,
+        block_root: Hash256,
+        expected_block_root: Hash256,
+    },
     /// Bad signature, caller should retry with different blocks.
     SignatureSet(SignatureSetError),
     /// Bad signature, caller should retry with different blocks.
@@ -38,6 +45,46 @@ pub enum HistoricalBlockError {
     IndexOutOfBounds,
 }
 
+/// The `block_roots` of the state the node started from, used to check backfilled blocks.
+///
+/// A state's `block_roots` only covers the `SLOTS_PER_HISTORICAL_ROOT` slots prior to it, which
+/// generally spans two `historical_roots` periods. Older periods are summarised by
+/// `historical_roots`, however each entry is the root of a `HistoricalBatch` which also commits to
+/// the `state_roots` of its period. Backfill can't recover those state roots, as the roots of the
+/// states at skipped slots aren't included in any block, so blocks prior to the window are only
+/// checked against their successors.
+pub struct AnchorBlockRoots {
+    state_slot: Slot,
+    block_roots: Vec<Hash256>,
+}
+
+impl AnchorBlockRoots {
+    pub fn from_state<E: EthSpec>(state: &BeaconState<E>) -> Self {
+        Self {
+            state_slot: state.slot(),
+            block_roots: state.block_roots().to_vec(),
+        }
+    }
+
+    /// Returns the root of the block at `slot`, or the most recent block prior to `slot` if it
+    /// was skipped. Returns `None` if `slot` is outside the window of the anchor state.
+    pub fn block_root_at_slot(&self, slot: Slot) -> Option<Hash256> {
+        let len = self.block_roots.len() as u64;
+        if slot >= self.state_slot || slot + len < self.state_slot {
+            return None;
+        }
+        self.block_roots
+            .get((slot.as_u64() % len) as usize)
+            .copied()
+    }
+
+    /// Returns `true` if every block older than `oldest_block_slot` is prior to the window of the
+    /// anchor state, such that the `block_roots` are of no further use.
+    pub fn is_exhausted(&self, oldest_block_slot: Slot) -> bool {
+        oldest_block_slot + self.block_roots.len() as u64 <= self.state_slot
+    }
+}
+
 impl<T: BeaconChainTypes> BeaconChain<T> {
     /// Store a batch of historical blocks in the database.
     ///
@@ -46,7 +93,9 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
     ///
     /// The block roots and proposer signatures are verified. If any block doesn't match the parent
     /// root listed in its successor, then the whole batch will be discarded and
-    /// `MismatchedBlockRoot` will be returned. If any proposer signature is invalid then
+    /// `MismatchedBlockRoot` will be returned. Blocks within the `block_roots` window of the state
+    /// the node started from are also checked against it, returning `AnchorBlockRootMismatch` with
+    /// the slot of the first block that differs. If any proposer signature is invalid then
     /// `SignatureSetError` or `InvalidSignature` will be returned.
     ///
     /// To align with sync we allow some excess blocks with slots greater than or equal to
@@ -61,6 +110,8 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         &self,
         blocks: Vec<Arc<SignedBlindedBeaconBlock<T::EthSpec>>>,
     ) -> Result<usize, Error> {
+        self.ensure_writable()?;
+
         let anchor_info = self
             .store
             .get_anchor_info()
@@ -95,10 +146,25 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         let mut cold_batch = Vec::with_capacity(blocks.len());
         let mut hot_batch = Vec::with_capacity(blocks.len());
 
+        let anchor_block_roots = self.anchor_block_roots.read();
         for block in blocks_to_import.iter().rev() {
             // Check chain integrity.
             let block_root = block.canonical_root();
 
+            if let Some(expected_block_root) = anchor_block_roots
+                .as_ref()
+                .and_then(|anchor_block_roots| anchor_block_roots.block_root_at_slot(block.slot()))
+            {
+                if block_root != expected_block_root {
+                    return Err(HistoricalBlockError::AnchorBlockRootMismatch {
+                        slot: block.slot(),
+                        block_root,
+                        expected_block_root,
+                    }
+                    .into());
+                }
+            }
+
             if block_root != expected_block_root {
                 return Err(HistoricalBlockError::MismatchedBlockRoot {
                     block_root,
@@ -133,6 +199,7 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
                 break;
             }
         }
+        drop(anchor_block_roots);
         chunk_writer.write(&mut cold_batch)?;
 
         // Verify signatures in one batch, holding the pubkey cache lock for the shortest duration
@@ -192,6 +259,25 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         let backfill_complete = new_anchor.block_backfill_complete();
         self.store
             .compare_and_set_anchor_info_with_write(Some(anchor_info), Some(new_anchor))?;
+        metrics::set_gauge(
+            &metrics::BACKFILL_OLDEST_BLOCK_SLOT,
+            prev_block_slot.as_u64() as i64,
+        );
+
+        // Free the anchor's `block_roots` once backfill has passed the slots they cover.
+        let mut anchor_block_roots = self.anchor_block_roots.write();
+        if anchor_block_roots
+            .as_ref()
+            .map_or(false, |roots| roots.is_exhausted(prev_block_slot))
+        {
+            *anchor_block_roots = None;
+            debug!(
+                self.log,
+                "Backfill passed the anchor block roots";
+                "oldest_block_slot" => prev_block_slot,
+            );
+        }
+        drop(anchor_block_roots);
 
         // If backfill has completed and the chain is configured to reconstruct historic states,
         // send a message to the background migrator instructing it to begin reconstruction.
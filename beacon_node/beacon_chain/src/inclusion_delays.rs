@@ -0,0 +1,149 @@
+//! Summarizes the inclusion delays of all attestations in imported blocks, by the epoch of the
+//! including block.
+//!
+//! The inclusion delay of an attestation is the slot of the block which includes it, minus the slot
+//! of the attestation. Unlike the validator monitor, which only considers monitored validators,
+//! the summaries cover every attestation and so reflect the health of the network as a whole.
+use crate::{metrics, BeaconChain, BeaconChainTypes};
+use parking_lot::RwLock;
+use serde_derive::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use types::{Epoch, EthSpec, Slot};
+
+/// The number of epochs, up to and including the latest epoch with an imported block, for which
+/// summaries are retained.
+pub const INCLUSION_DELAY_SUMMARY_EPOCHS: u64 = 8;
+
+/// The number of attestations included with each inclusion delay.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InclusionDelaySummary {
+    /// Inclusion delay (in slots) to the number of attestations included with that delay.
+    pub delays: BTreeMap<u64, u64>,
+}
+
+impl InclusionDelaySummary {
+    /// Record the inclusion of an attestation from `attestation_slot` in a block at `block_slot`.
+    pub fn record(&mut self, block_slot: Slot, attestation_slot: Slot) {
+        let delay = block_slot.saturating_sub(attestation_slot).as_u64();
+        metrics::observe(&metrics::BLOCK_ATTESTATION_INCLUSION_DELAY, delay as f64);
+        *self.delays.entry(delay).or_default() += 1;
+    }
+
+    /// Add the counts of `other` to `self`.
+    pub fn merge(&mut self, other: &Self) {
+        for (delay, count) in &other.delays {
+            *self.delays.entry(*delay).or_default() += count;
+        }
+    }
+
+    /// The total number of attestations.
+    pub fn attestations(&self) -> u64 {
+        self.delays.values().sum()
+    }
+
+    /// The mean inclusion delay, or `None` if there are no attestations.
+    pub fn mean(&self) -> Option<f64> {
+        let attestations = self.attestations();
+        if attestations == 0 {
+            return None;
+        }
+        let total_delay: u64 = self.delays.iter().map(|(delay, count)| delay * count).sum();
+        Some(total_delay as f64 / attestations as f64)
+    }
+}
+
+#[derive(Default)]
+pub struct InclusionDelaySummaries {
+    epochs: RwLock<BTreeMap<Epoch, InclusionDelaySummary>>,
+}
+
+impl InclusionDelaySummaries {
+    /// Add the inclusion delays of the attestations in a block from `epoch`.
+    ///
+    /// Blocks from prior to the most recent `INCLUSION_DELAY_SUMMARY_EPOCHS` are ignored.
+    pub fn insert(&self, epoch: Epoch, block_summary: &InclusionDelaySummary) {
+        let mut epochs = self.epochs.write();
+
+        let latest_epoch = epochs
+            .keys()
+            .next_back()
+            .copied()
+            .map_or(epoch, |latest| std::cmp::max(latest, epoch));
+        if epoch + INCLUSION_DELAY_SUMMARY_EPOCHS <= latest_epoch {
+            return;
+        }
+
+        epochs.entry(epoch).or_default().merge(block_summary);
+        epochs.retain(|epoch, _| *epoch + INCLUSION_DELAY_SUMMARY_EPOCHS > latest_epoch);
+    }
+
+    pub fn get(&self, epoch: Epoch) -> Option<InclusionDelaySummary> {
+        self.epochs.read().get(&epoch).cloned()
+    }
+}
+
+impl<T: BeaconChainTypes> BeaconChain<T> {
+    /// Returns the inclusion delays of the attestations in the blocks from `epoch` which have been
+    /// imported, if `epoch` is recent enough to have been retained.
+    pub fn inclusion_delay_summary(&self, epoch: Epoch) -> Option<InclusionDelaySummary> {
+        self.inclusion_delay_summaries.get(epoch)
+    }
+
+    /// Record the inclusion delays of the attestations in an imported block at `block_slot`.
+    pub(crate) fn record_inclusion_delays(
+        &self,
+        block_slot: Slot,
+        block_summary: &InclusionDelaySummary,
+    ) {
+        self.inclusion_delay_summaries.insert(
+            block_slot.epoch(T::EthSpec::slots_per_epoch()),
+            block_summary,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(delays: &[(u64, u64)]) -> InclusionDelaySummary {
+        InclusionDelaySummary {
+            delays: delays.iter().copied().collect(),
+        }
+    }
+
+    #[test]
+    fn mean_inclusion_delay() {
+        let mut summary = InclusionDelaySummary::default();
+        assert_eq!(summary.mean(), None);
+
+        summary.record(Slot::new(10), Slot::new(9));
+        summary.record(Slot::new(10), Slot::new(9));
+        summary.record(Slot::new(10), Slot::new(6));
+        assert_eq!(summary.delays, BTreeMap::from([(1, 2), (4, 1)]));
+        assert_eq!(summary.attestations(), 3);
+        assert_eq!(summary.mean(), Some(2.0));
+    }
+
+    #[test]
+    fn summaries_are_merged_and_pruned() {
+        let summaries = InclusionDelaySummaries::default();
+        summaries.insert(Epoch::new(1), &summary(&[(1, 2)]));
+        summaries.insert(Epoch::new(1), &summary(&[(1, 1), (2, 1)]));
+        assert_eq!(
+            summaries.get(Epoch::new(1)),
+            Some(summary(&[(1, 3), (2, 1)]))
+        );
+
+        // Older epochs are dropped once a later epoch is inserted.
+        summaries.insert(
+            Epoch::new(1 + INCLUSION_DELAY_SUMMARY_EPOCHS),
+            &summary(&[(1, 1)]),
+        );
+        assert_eq!(summaries.get(Epoch::new(1)), None);
+
+        // Epochs prior to the retained window are ignored.
+        summaries.insert(Epoch::new(1), &summary(&[(1, 1)]));
+        assert_eq!(summaries.get(Epoch::new(1)), None);
+    }
+}
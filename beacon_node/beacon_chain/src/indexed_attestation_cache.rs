@@ -0,0 +1,215 @@
+//! Caches the `IndexedAttestation`s of aggregates verified on gossip, for reuse when the same
+//! aggregates are imported in a block.
+//!
+//! Block import needs the indexed form of each attestation in the block for fork choice and the
+//! slasher, which requires a committee lookup and a mapping of the aggregation bits to validator
+//! indices. Most of the attestations in a block are aggregates which were verified on gossip a few
+//! slots earlier, at which point their indexed form was already computed.
+//!
+//! Only aggregates are cached, since blocks rarely include an unaggregated attestation as-is.
+//! Entries are only inserted once the aggregate's signature has been verified, and an entry is
+//! only served if its signature matches the attestation in the block.
+use crate::{metrics, BeaconChain, BeaconChainError as Error, BeaconChainTypes};
+use parking_lot::{Mutex, RwLock};
+use state_processing::common::get_indexed_attestation;
+use std::collections::HashMap;
+use tree_hash::TreeHash;
+use types::{Attestation, BeaconState, EthSpec, Hash256, IndexedAttestation, Slot};
+
+/// The maximum number of indexed attestations to cache. Further aggregates are not cached until
+/// older entries are pruned.
+pub const INDEXED_ATTESTATION_CACHE_CAPACITY: usize = 16_384;
+
+/// Identifies the attestations which share an `IndexedAttestation`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct IndexedAttestationKey {
+    pub attestation_data_root: Hash256,
+    pub aggregation_bits_root: Hash256,
+}
+
+impl IndexedAttestationKey {
+    pub fn new<E: EthSpec>(attestation: &Attestation<E>) -> Self {
+        Self {
+            attestation_data_root: attestation.data.tree_hash_root(),
+            aggregation_bits_root: attestation.aggregation_bits.tree_hash_root(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct IndexedAttestationCacheStats {
+    /// Number of lookups served from the cache.
+    pub hits: u64,
+    /// Number of lookups which had to compute the indexed attestation.
+    pub misses: u64,
+}
+
+pub struct IndexedAttestationCache<E: EthSpec> {
+    attestations: RwLock<HashMap<IndexedAttestationKey, IndexedAttestation<E>>>,
+    stats: Mutex<IndexedAttestationCacheStats>,
+}
+
+impl<E: EthSpec> Default for IndexedAttestationCache<E> {
+    fn default() -> Self {
+        Self {
+            attestations: RwLock::new(HashMap::new()),
+            stats: Mutex::new(IndexedAttestationCacheStats::default()),
+        }
+    }
+}
+
+impl<E: EthSpec> IndexedAttestationCache<E> {
+    /// Cache the `indexed_attestation` of `attestation`, unless the cache is full.
+    pub fn insert(
+        &self,
+        attestation: &Attestation<E>,
+        indexed_attestation: &IndexedAttestation<E>,
+    ) {
+        let key = IndexedAttestationKey::new(attestation);
+        let mut attestations = self.attestations.write();
+        if attestations.len() < INDEXED_ATTESTATION_CACHE_CAPACITY {
+            attestations
+                .entry(key)
+                .or_insert_with(|| indexed_attestation.clone());
+        }
+    }
+
+    /// Returns the cached indexed form of `attestation`, if any.
+    pub fn get(&self, attestation: &Attestation<E>) -> Option<IndexedAttestation<E>> {
+        let key = IndexedAttestationKey::new(attestation);
+        let indexed_attestation = self
+            .attestations
+            .read()
+            .get(&key)
+            .filter(|indexed| indexed.signature == attestation.signature)
+            .cloned();
+
+        let mut stats = self.stats.lock();
+        if indexed_attestation.is_some() {
+            stats.hits += 1;
+            metrics::inc_counter(&metrics::INDEXED_ATTESTATION_CACHE_HITS);
+        } else {
+            stats.misses += 1;
+            metrics::inc_counter(&metrics::INDEXED_ATTESTATION_CACHE_MISSES);
+        }
+        indexed_attestation
+    }
+
+    /// Drop the indexed attestations of slots prior to `oldest_slot`.
+    pub fn prune(&self, oldest_slot: Slot) {
+        self.attestations
+            .write()
+            .retain(|_, indexed| indexed.data.slot >= oldest_slot);
+    }
+
+    pub fn len(&self) -> usize {
+        self.attestations.read().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn stats(&self) -> IndexedAttestationCacheStats {
+        *self.stats.lock()
+    }
+}
+
+impl<T: BeaconChainTypes> BeaconChain<T> {
+    /// Returns the indexed form of `attestation`, which is included in a block with the given
+    /// post-`state`.
+    ///
+    /// The indexed attestation is served from the cache if possible, otherwise it is computed from
+    /// the committees of `state`.
+    pub fn indexed_attestation_for_block(
+        &self,
+        state: &BeaconState<T::EthSpec>,
+        attestation: &Attestation<T::EthSpec>,
+    ) -> Result<IndexedAttestation<T::EthSpec>, Error> {
+        if let Some(indexed_attestation) = self.indexed_attestation_cache.get(attestation) {
+            return Ok(indexed_attestation);
+        }
+
+        let committee =
+            state.get_beacon_committee(attestation.data.slot, attestation.data.index)?;
+        get_indexed_attestation(committee.committee, attestation).map_err(Into::into)
+    }
+
+    pub fn indexed_attestation_cache_stats(&self) -> IndexedAttestationCacheStats {
+        self.indexed_attestation_cache.stats()
+    }
+
+    /// Drop the indexed attestations which are too old to be included in a block produced at
+    /// `current_slot`.
+    pub(crate) fn prune_indexed_attestation_cache(&self, current_slot: Slot) {
+        self.indexed_attestation_cache
+            .prune(current_slot.saturating_sub(T::EthSpec::slots_per_epoch()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::{AggregateSignature, AttestationData, BitList, MainnetEthSpec, VariableList};
+
+    type E = MainnetEthSpec;
+
+    fn attestation(slot: u64, bits: &[usize]) -> (Attestation<E>, IndexedAttestation<E>) {
+        let data = AttestationData {
+            slot: Slot::new(slot),
+            ..AttestationData::default()
+        };
+        let mut aggregation_bits = BitList::with_capacity(8).unwrap();
+        for &bit in bits {
+            aggregation_bits.set(bit, true).unwrap();
+        }
+        let attestation = Attestation {
+            aggregation_bits,
+            data: data.clone(),
+            signature: AggregateSignature::empty(),
+        };
+        let indexed_attestation = IndexedAttestation {
+            attesting_indices: VariableList::new(bits.iter().map(|bit| *bit as u64).collect())
+                .unwrap(),
+            data,
+            signature: AggregateSignature::empty(),
+        };
+        (attestation, indexed_attestation)
+    }
+
+    #[test]
+    fn lookups_match_data_and_bits() {
+        let cache = IndexedAttestationCache::<E>::default();
+        let (a, indexed_a) = attestation(1, &[0, 1]);
+        let (b, _) = attestation(1, &[0, 2]);
+        let (c, _) = attestation(2, &[0, 1]);
+
+        cache.insert(&a, &indexed_a);
+        assert_eq!(cache.get(&a), Some(indexed_a));
+        assert_eq!(cache.get(&b), None);
+        assert_eq!(cache.get(&c), None);
+        assert_eq!(
+            cache.stats(),
+            IndexedAttestationCacheStats { hits: 1, misses: 2 }
+        );
+
+        // A different signature is not served.
+        let mut a_infinity = a;
+        a_infinity.signature = AggregateSignature::infinity();
+        assert_eq!(cache.get(&a_infinity), None);
+    }
+
+    #[test]
+    fn pruned_by_slot() {
+        let cache = IndexedAttestationCache::<E>::default();
+        for slot in 1..4 {
+            let (attestation, indexed) = attestation(slot, &[0]);
+            cache.insert(&attestation, &indexed);
+        }
+        assert_eq!(cache.len(), 3);
+
+        cache.prune(Slot::new(3));
+        assert_eq!(cache.len(), 1);
+        assert!(cache.get(&attestation(3, &[0]).0).is_some());
+    }
+}
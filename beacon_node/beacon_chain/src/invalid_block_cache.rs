@@ -0,0 +1,153 @@
+//! Caches the roots of blocks which are known to have invalid execution payloads, including the
+//! descendants of a block whose payload was found to be invalid.
+//!
+//! Fork choice rejects the children of such blocks, but only once they have been verified for
+//! gossip, which includes verifying the proposer signature. Consulting this cache first allows
+//! gossip blocks which build upon an invalidated branch to be rejected before any expensive work.
+use crate::{metrics, BeaconChain, BeaconChainTypes};
+use lru::LruCache;
+use parking_lot::Mutex;
+use proto_array::ProtoArrayForkChoice;
+use slog::warn;
+use types::{EthSpec, Hash256, Slot};
+
+/// The maximum number of invalid block roots to cache, evicting the least recently inserted.
+pub const INVALID_BLOCK_CACHE_CAPACITY: usize = 1_024;
+
+pub struct InvalidBlockCache {
+    /// The roots of blocks with invalid payloads, with the slots of those blocks.
+    block_roots: Mutex<LruCache<Hash256, Slot>>,
+}
+
+impl Default for InvalidBlockCache {
+    fn default() -> Self {
+        Self::new(INVALID_BLOCK_CACHE_CAPACITY)
+    }
+}
+
+impl InvalidBlockCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            block_roots: Mutex::new(LruCache::new(capacity)),
+        }
+    }
+
+    pub fn insert(&self, block_root: Hash256, block_slot: Slot) {
+        self.block_roots.lock().put(block_root, block_slot);
+    }
+
+    pub fn contains(&self, block_root: &Hash256) -> bool {
+        self.block_roots.lock().contains(block_root)
+    }
+
+    /// Clear the cache if `block_root` is cached, returning `true` if it was.
+    ///
+    /// A block which was found to be invalid should never later be found to be valid. If it is,
+    /// the blocks cached as its descendants can't be trusted either, so all entries are dropped.
+    pub fn on_valid_block(&self, block_root: &Hash256) -> bool {
+        let mut block_roots = self.block_roots.lock();
+        if block_roots.contains(block_root) {
+            block_roots.clear();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Drop the roots of blocks prior to `finalized_slot`, whose children can't be imported
+    /// regardless.
+    pub fn prune(&self, finalized_slot: Slot) {
+        let mut block_roots = self.block_roots.lock();
+        let stale_block_roots = block_roots
+            .iter()
+            .filter(|(_, block_slot)| **block_slot < finalized_slot)
+            .map(|(block_root, _)| *block_root)
+            .collect::<Vec<_>>();
+        for block_root in &stale_block_roots {
+            block_roots.pop(block_root);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.block_roots.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<T: BeaconChainTypes> BeaconChain<T> {
+    /// Returns `true` if the block with `block_root` is known to have an invalid execution
+    /// payload, or to descend from a block with one.
+    pub fn is_known_invalid_block(&self, block_root: &Hash256) -> bool {
+        let is_invalid = self.invalid_block_cache.contains(block_root);
+        if is_invalid {
+            metrics::inc_counter(&metrics::INVALID_BLOCK_CACHE_HITS);
+        }
+        is_invalid
+    }
+
+    /// Cache the roots of all blocks which `proto_array` considers to have invalid payloads.
+    pub(crate) fn cache_invalid_blocks(&self, proto_array: &ProtoArrayForkChoice) {
+        proto_array
+            .core_proto_array()
+            .nodes
+            .iter()
+            .filter(|node| node.execution_status.is_invalid())
+            .for_each(|node| self.invalid_block_cache.insert(node.root, node.slot));
+    }
+
+    /// Guard against a block which was cached as invalid later being found to be valid.
+    pub(crate) fn uncache_valid_block(&self, block_root: &Hash256) {
+        if self.invalid_block_cache.on_valid_block(block_root) {
+            warn!(
+                self.log,
+                "Invalid block found to be valid";
+                "info" => "the cache of invalid blocks has been cleared",
+                "block_root" => ?block_root,
+            );
+        }
+    }
+
+    /// Drop the invalid blocks which are prior to the finalized checkpoint.
+    pub fn prune_invalid_block_cache(&self) {
+        let finalized_slot = self
+            .canonical_head
+            .cached_head()
+            .finalized_checkpoint()
+            .epoch
+            .start_slot(T::EthSpec::slots_per_epoch());
+        self.invalid_block_cache.prune(finalized_slot);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn root(i: u64) -> Hash256 {
+        Hash256::from_low_u64_be(i)
+    }
+
+    #[test]
+    fn prune_and_clear() {
+        let cache = InvalidBlockCache::new(4);
+        for i in 1..4 {
+            cache.insert(root(i), Slot::new(i));
+        }
+
+        cache.prune(Slot::new(2));
+        assert!(!cache.contains(&root(1)));
+        assert!(cache.contains(&root(2)));
+        assert_eq!(cache.len(), 2);
+
+        // Finding an uncached block to be valid has no effect.
+        assert!(!cache.on_valid_block(&root(1)));
+        assert_eq!(cache.len(), 2);
+
+        // Finding a cached block to be valid clears the cache.
+        assert!(cache.on_valid_block(&root(3)));
+        assert!(cache.is_empty());
+    }
+}
@@ -1,25 +1,50 @@
 #![recursion_limit = "128"] // For lazy-static
+pub mod attestation_performance;
 pub mod attestation_verification;
 mod attester_cache;
+mod backfill_rate_limiter;
 mod beacon_chain;
 mod beacon_fork_choice_store;
 pub mod beacon_proposer_cache;
 mod beacon_snapshot;
+pub mod block_replay;
 pub mod block_reward;
-mod block_times_cache;
+mod block_size_budget;
+pub mod block_status;
+pub mod block_times_cache;
 mod block_verification;
 pub mod builder;
+pub mod builder_bid_history;
+pub mod builder_relays;
+pub mod cache_memory;
+pub mod cache_prewarm;
 pub mod canonical_head;
+pub mod canonical_updates;
 pub mod chain_config;
+pub mod checkpoint_state_cache;
+pub mod clock_monitor;
+pub mod committee_memo;
+pub mod deposit_status;
 mod early_attester_cache;
 mod errors;
 pub mod eth1_chain;
 pub mod events;
 mod execution_payload;
+pub mod execution_payload_summary;
+pub mod exit_queue;
+pub mod failure_log_limiter;
+pub mod finality_watchdog;
+pub mod fork_choice_balances;
+pub mod fork_choice_runs;
 pub mod fork_choice_signal;
 pub mod fork_revert;
+pub mod graffiti;
+pub mod head_consistency;
 mod head_tracker;
 pub mod historical_blocks;
+pub mod inclusion_delays;
+pub mod indexed_attestation_cache;
+pub mod invalid_block_cache;
 pub mod merge_readiness;
 mod metrics;
 pub mod migrate;
@@ -28,23 +53,45 @@ mod observed_aggregates;
 mod observed_attesters;
 mod observed_block_producers;
 pub mod observed_operations;
+pub mod op_inclusion_latency;
+pub mod orphan_blocks;
+mod partial_state_skip;
+pub mod payload_pruning;
+pub mod payload_reconstruction;
 mod persisted_beacon_chain;
 mod persisted_fork_choice;
 mod pre_finalization_cache;
+pub mod proposal_history;
 pub mod proposer_prep_service;
+pub mod pubkey_cache_transfer;
+pub mod randao_snapshot;
 pub mod schema_change;
+pub mod self_test;
 mod shuffling_cache;
+pub mod shutdown_report;
 mod snapshot_cache;
+mod startup_repair;
+pub mod state_advance_locks;
 pub mod state_advance_timer;
 pub mod sync_committee_verification;
+pub mod sync_status;
 pub mod test_utils;
 mod timeout_rw_lock;
+#[cfg(any(test, feature = "unchecked_gossip_blocks"))]
+pub mod unchecked_gossip;
 pub mod validator_monitor;
+pub mod validator_monitor_file;
 mod validator_pubkey_cache;
+pub mod validator_registrations;
+pub mod validator_registry_diffs;
+pub mod verification_pressure;
 
+pub use self::backfill_rate_limiter::BackfillRateLimiter;
 pub use self::beacon_chain::{
-    AttestationProcessingOutcome, BeaconChain, BeaconChainTypes, BeaconStore, ChainSegmentResult,
-    ForkChoiceError, ProduceBlockVerification, StateSkipConfig, WhenSlotSkipped,
+    AttestationPackingInfo, AttestationProcessingOutcome, BeaconChain, BeaconChainTypes,
+    BeaconStore, BlockProductionInfo, ChainSegmentResult, DetailedHead, ForkChoiceError,
+    HeadTrackerPersistence, PersistHeadAndForkChoiceResult, ProduceBlockVerification,
+    StateSkipConfig, WhenSlotSkipped, BLOCK_PROCESSING_CACHE_LOCK_TIMEOUT,
     INVALID_JUSTIFIED_PAYLOAD_SHUTDOWN_REASON, MAXIMUM_GOSSIP_CLOCK_DISPARITY,
 };
 pub use self::beacon_snapshot::BeaconSnapshot;
@@ -54,12 +101,15 @@ pub use self::historical_blocks::HistoricalBlockError;
 pub use attestation_verification::Error as AttestationError;
 pub use beacon_fork_choice_store::{BeaconForkChoiceStore, Error as ForkChoiceStoreError};
 pub use block_verification::{BlockError, ExecutionPayloadError, GossipVerifiedBlock};
-pub use canonical_head::{CachedHead, CanonicalHead, CanonicalHeadRwLock};
+pub use canonical_head::{CachedHead, CanonicalHead, CanonicalHeadRwLock, HeadChange};
 pub use eth1_chain::{Eth1Chain, Eth1ChainBackend};
+pub use eth2::types::BlockDeliveryTransport;
 pub use events::ServerSentEventHandler;
 pub use fork_choice::{ExecutionStatus, ForkchoiceUpdateParameters};
+pub use graffiti::GraffitiMode;
 pub use metrics::scrape_for_metrics;
 pub use parking_lot;
+pub use pre_finalization_cache::{PreFinalizationBlockCache, PreFinalizationCacheStats};
 pub use slot_clock;
 pub use state_processing::per_block_processing::errors::{
     AttestationValidationError, AttesterSlashingValidationError, DepositValidationError,
@@ -72,10 +72,25 @@ lazy_static! {
         "beacon_block_processing_attestation_observation_seconds",
         "Time spent hashing and remembering all the attestations in the block"
     );
+    pub static ref BLOCK_ATTESTATION_INCLUSION_DELAY: Result<Histogram> = try_create_histogram_with_buckets(
+        "beacon_block_attestation_inclusion_delay_slots",
+        "Number of slots between each attestation in an imported block and the block",
+        vec![1.0, 2.0, 3.0, 4.0, 6.0, 8.0, 12.0, 16.0, 24.0, 32.0]
+    );
     pub static ref BLOCK_SYNC_AGGREGATE_SET_BITS: Result<IntGauge> = try_create_int_gauge(
         "block_sync_aggregate_set_bits",
         "The number of true bits in the last sync aggregate in a block"
     );
+    pub static ref OP_INCLUSION_LATENCY: Result<HistogramVec> = try_create_histogram_vec_with_buckets(
+        "beacon_op_pool_inclusion_latency_seconds",
+        "Time from the verification of an operation to its inclusion in an imported block",
+        &["type"],
+        vec![1.0, 2.0, 4.0, 6.0, 12.0, 24.0, 36.0, 48.0, 72.0, 96.0, 192.0, 384.0, 768.0]
+    );
+    pub static ref OP_INCLUSION_LATENCY_PENDING: Result<IntGauge> = try_create_int_gauge(
+        "beacon_op_pool_inclusion_latency_pending_operations",
+        "The number of verified operations awaiting inclusion in an imported block"
+    );
 
     /*
      * Block Production
@@ -98,6 +113,14 @@ lazy_static! {
         "beacon_block_production_state_load_seconds",
         "Time taken to load the base state for block production"
     );
+    pub static ref BLOCK_PRODUCTION_STATE_ADVANCE_TIMES: Result<Histogram> = try_create_histogram(
+        "beacon_block_production_state_advance_seconds",
+        "Time taken to advance the head state for block production when it wasn't already advanced"
+    );
+    pub static ref BLOCK_PRODUCTION_STATE_ADVANCE_FALLBACKS: Result<IntCounter> = try_create_int_counter(
+        "beacon_block_production_state_advance_fallbacks_total",
+        "Count of block productions which loaded and advanced the head state inline because it was missing from the snapshot cache"
+    );
     pub static ref BLOCK_PRODUCTION_SLOT_PROCESS_TIMES: Result<Histogram> = try_create_histogram(
         "beacon_block_production_slot_process_seconds",
         "Time taken to advance the state to the block production slot"
@@ -118,6 +141,29 @@ lazy_static! {
         "beacon_block_production_state_root_seconds",
         "Time taken to calculate the block's state root"
     );
+    pub static ref BLOCK_PRODUCTION_ATTESTATION_DISTINCT_DATA: Result<IntGauge> = try_create_int_gauge(
+        "beacon_block_production_attestation_distinct_data",
+        "Number of distinct attestation data packed into the last block produced"
+    );
+    pub static ref BLOCK_PRODUCTION_ATTESTATION_NEW_ATTESTERS: Result<IntGauge> = try_create_int_gauge(
+        "beacon_block_production_attestation_new_attesters",
+        "Number of validators included for the first time by the attestations of the last block produced"
+    );
+    pub static ref BLOCK_PRODUCTION_ATTESTATION_INCOMPATIBLE_SHUFFLING: Result<IntGauge> =
+        try_create_int_gauge(
+            "beacon_block_production_attestation_incompatible_shuffling",
+            "Number of op pool attestations skipped due to an incompatible shuffling when producing the last block"
+        );
+    pub static ref BLOCK_PRODUCTION_ATTESTATION_REWARD_LEFT_ON_TABLE: Result<IntGauge> =
+        try_create_int_gauge(
+            "beacon_block_production_attestation_reward_left_on_table_gwei",
+            "Estimated proposer reward of valid op pool attestations not packed into the last block produced"
+        );
+    pub static ref BLOCK_PRODUCTION_OPERATIONS_EXCLUDED_BY_SIZE: Result<IntCounter> =
+        try_create_int_counter(
+            "beacon_block_production_operations_excluded_by_size_total",
+            "Count of operations left out of produced blocks to stay within the maximum network size"
+        );
 
     /*
      * Block Statistics
@@ -191,6 +237,10 @@ lazy_static! {
         "beacon_attestation_processing_agg_pool_create_map",
         "Time spent for creating a map for a new slot"
     );
+    pub static ref NAIVE_AGGREGATION_POOL_ITEMS: Result<IntGauge> = try_create_int_gauge(
+        "beacon_naive_aggregation_pool_items",
+        "Number of distinct attestation data in the naive aggregation pool, across all slots"
+    );
     pub static ref ATTESTATION_PROCESSING_APPLY_TO_OP_POOL: Result<Histogram> = try_create_histogram(
         "beacon_attestation_processing_apply_to_op_pool",
         "Time spent applying an attestation to the block inclusion pool"
@@ -243,6 +293,34 @@ lazy_static! {
         "beacon_attestation_processing_batch_unagg_signature_times",
         "Time spent on the signature verification of batch unaggregate attestation processing"
     );
+    pub static ref ATTESTATION_PROCESSING_BATCH_AGG_SIZE: Result<Histogram> = try_create_histogram_with_buckets(
+        "beacon_attestation_processing_batch_agg_size",
+        "Number of aggregate attestations in each batch",
+        vec![1.0, 2.0, 4.0, 8.0, 16.0, 32.0, 64.0, 128.0]
+    );
+    pub static ref ATTESTATION_PROCESSING_BATCH_UNAGG_SIZE: Result<Histogram> = try_create_histogram_with_buckets(
+        "beacon_attestation_processing_batch_unagg_size",
+        "Number of unaggregated attestations in each batch",
+        vec![1.0, 2.0, 4.0, 8.0, 16.0, 32.0, 64.0, 128.0]
+    );
+    pub static ref ATTESTATION_PROCESSING_BATCH_AGG_FALLBACKS: Result<IntCounter> = try_create_int_counter(
+        "beacon_attestation_processing_batch_agg_fallbacks_total",
+        "Number of batches of aggregate attestations which failed batch signature verification, \
+         so that each signature was verified individually"
+    );
+    pub static ref ATTESTATION_PROCESSING_BATCH_UNAGG_FALLBACKS: Result<IntCounter> = try_create_int_counter(
+        "beacon_attestation_processing_batch_unagg_fallbacks_total",
+        "Number of batches of unaggregated attestations which failed batch signature verification, \
+         so that each signature was verified individually"
+    );
+    pub static ref ATTESTATION_PROCESSING_BATCH_AGG_SALVAGED: Result<IntCounter> = try_create_int_counter(
+        "beacon_attestation_processing_batch_agg_salvaged_total",
+        "Number of aggregate attestations verified individually after their batch failed"
+    );
+    pub static ref ATTESTATION_PROCESSING_BATCH_UNAGG_SALVAGED: Result<IntCounter> = try_create_int_counter(
+        "beacon_attestation_processing_batch_unagg_salvaged_total",
+        "Number of unaggregated attestations verified individually after their batch failed"
+    );
 
     /*
      * Shuffling cache
@@ -251,6 +329,14 @@ lazy_static! {
         try_create_int_counter("beacon_shuffling_cache_hits_total", "Count of times shuffling cache fulfils request");
     pub static ref SHUFFLING_CACHE_MISSES: Result<IntCounter> =
         try_create_int_counter("beacon_shuffling_cache_misses_total", "Count of times shuffling cache fulfils request");
+    pub static ref COMMITTEE_MEMO_HITS: Result<IntCounter> =
+        try_create_int_counter("beacon_committee_memo_hits_total", "Count of times the committee memo fulfils an attestation's committee lookup");
+    pub static ref COMMITTEE_MEMO_MISSES: Result<IntCounter> =
+        try_create_int_counter("beacon_committee_memo_misses_total", "Count of times an attestation's committee is read from the shuffling cache instead of the committee memo");
+    pub static ref INDEXED_ATTESTATION_CACHE_HITS: Result<IntCounter> =
+        try_create_int_counter("beacon_indexed_attestation_cache_hits_total", "Count of times the indexed attestation cache fulfils a block attestation");
+    pub static ref INDEXED_ATTESTATION_CACHE_MISSES: Result<IntCounter> =
+        try_create_int_counter("beacon_indexed_attestation_cache_misses_total", "Count of times a block attestation is indexed using the committees of the block's state");
 
     /*
      * Early attester cache
@@ -259,6 +345,10 @@ lazy_static! {
         "beacon_early_attester_cache_hits",
         "Count of times the early attester cache returns an attestation"
     );
+    pub static ref BEACON_EARLY_ATTESTER_CACHE_DEFERRED_ATTESTATIONS: Result<IntCounter> = try_create_int_counter(
+        "beacon_early_attester_cache_deferred_attestations_total",
+        "Count of attestations deferred because their block is in the early attester cache but not yet in fork choice"
+    );
 
     /*
      * Attestation Production
@@ -279,6 +369,10 @@ lazy_static! {
         "attestation_production_cache_prime_seconds",
         "Time spent loading a new state from the disk due to a cache miss"
     );
+    pub static ref ATTESTATION_PRODUCTION_ANCIENT_SECONDS: Result<Histogram> = try_create_histogram(
+        "attestation_production_ancient_seconds",
+        "Time spent producing an attestation to a slot beyond the historical roots of the head"
+    );
 }
 
 // Second lazy-static block is used to account for macro recursion limit.
@@ -318,10 +412,38 @@ lazy_static! {
         "beacon_fork_choice_process_attestation_seconds",
         "Time taken to add an attestation to fork choice"
     );
+    pub static ref FORK_CHOICE_RUN_SECONDS: Result<Histogram> = try_create_histogram(
+        "beacon_fork_choice_run_seconds",
+        "Time taken to process queued attestations and find the head at the start of a slot"
+    );
+    pub static ref FORK_CHOICE_RUN_QUEUED_ATTESTATIONS: Result<IntGauge> = try_create_int_gauge(
+        "beacon_fork_choice_run_queued_attestations",
+        "Number of queued attestations which became eligible for fork choice by the run for the most recent slot"
+    );
+    pub static ref FORK_CHOICE_RUN_APPLIED_ATTESTATIONS: Result<IntCounter> = try_create_int_counter(
+        "beacon_fork_choice_run_applied_attestations_total",
+        "Count of queued attestations applied to fork choice at the start of a slot"
+    );
+    pub static ref FORK_CHOICE_RUN_DROPPED_ATTESTATIONS: Result<IntCounter> = try_create_int_counter(
+        "beacon_fork_choice_run_dropped_attestations_total",
+        "Count of queued attestations dropped by fork choice as they could not be applied"
+    );
     pub static ref FORK_CHOICE_SET_HEAD_LAG_TIMES: Result<Histogram> = try_create_histogram(
         "beacon_fork_choice_set_head_lag_times",
         "Time taken between finding the head and setting the canonical head value"
     );
+    pub static ref FORK_CHOICE_HEAD_WEIGHT: Result<IntGauge> = try_create_int_gauge(
+        "beacon_fork_choice_head_weight",
+        "Weight (in Gwei) of the head block in fork choice, including any proposer boost"
+    );
+    pub static ref FORK_CHOICE_COMPETITOR_WEIGHT: Result<IntGauge> = try_create_int_gauge(
+        "beacon_fork_choice_competitor_weight",
+        "Greatest weight (in Gwei) of the other children of the parent of the head block"
+    );
+    pub static ref FORK_CHOICE_HEAD_PROPOSER_BOOST: Result<IntGauge> = try_create_int_gauge(
+        "beacon_fork_choice_head_proposer_boost",
+        "Proposer boost (in Gwei) included in the weight of the head block"
+    );
     pub static ref BALANCES_CACHE_HITS: Result<IntCounter> =
         try_create_int_counter("beacon_balances_cache_hits_total", "Count of times balances cache fulfils request");
     pub static ref BALANCES_CACHE_MISSES: Result<IntCounter> =
@@ -344,6 +466,8 @@ lazy_static! {
      */
     pub static ref DEFAULT_ETH1_VOTES: Result<IntCounter> =
         try_create_int_counter("beacon_eth1_default_votes", "Count of times we have voted default value for eth1 data");
+    pub static ref FALLBACK_ETH1_VOTES: Result<IntCounter> =
+        try_create_int_counter("beacon_eth1_fallback_votes", "Count of times the eth1 cache was unable to provide an eth1 data vote");
 
     /*
      * Chain Head
@@ -650,6 +774,18 @@ lazy_static! {
             "The count of times a sync signature was seen inside a block.",
             &["validator"]
         );
+    pub static ref VALIDATOR_MONITOR_PREV_EPOCH_SYNC_SIGNATURE_BLOCK_MISSES: Result<IntGaugeVec> =
+        try_create_int_gauge_vec(
+            "validator_monitor_prev_epoch_sync_signature_block_misses",
+            "The count of times a sync signature was expected but not seen inside a block.",
+            &["validator"]
+        );
+    pub static ref VALIDATOR_MONITOR_PREV_EPOCH_SYNC_SIGNATURE_BLOCK_HIT_RATE: Result<GaugeVec> =
+        try_create_float_gauge_vec(
+            "validator_monitor_prev_epoch_sync_signature_block_hit_rate",
+            "The ratio of sync signatures seen inside blocks to those expected.",
+            &["validator"]
+        );
     pub static ref VALIDATOR_MONITOR_PREV_EPOCH_SYNC_CONTRIBUTIONS_TOTAL: Result<IntGaugeVec> =
         try_create_int_gauge_vec(
             "validator_monitor_prev_epoch_sync_contributions_total",
@@ -741,6 +877,11 @@ lazy_static! {
         "Number of times a validator's sync committee message has been seen in a sync aggregate",
         &["src", "validator"]
     );
+    pub static ref VALIDATOR_MONITOR_SYNC_COMMITTEE_MESSAGE_NOT_IN_BLOCK_TOTAL: Result<IntCounterVec> = try_create_int_counter_vec(
+        "validator_monitor_sync_committee_message_not_in_block_total",
+        "Number of times a validator's sync committee message was absent from a sync aggregate",
+        &["validator"]
+    );
     pub static ref VALIDATOR_MONITOR_ATTESTATION_IN_BLOCK_DELAY_SLOTS: Result<IntGaugeVec> = try_create_int_gauge_vec(
         "validator_monitor_attestation_in_block_delay_slots",
         "The excess slots (beyond the minimum delay) between the attestation slot and the block slot.",
@@ -908,6 +1049,43 @@ lazy_static! {
         "beacon_backfill_signature_total_seconds",
         "Time spent verifying the signature set during backfill sync, including setup"
     );
+    pub static ref BACKFILL_OLDEST_BLOCK_SLOT: Result<IntGauge> = try_create_int_gauge(
+        "beacon_backfill_oldest_block_slot",
+        "Slot of the oldest block in the database, backfill is complete once this reaches genesis"
+    );
+    pub static ref BACKFILL_RATE_LIMITED_TOTAL: Result<IntCounter> = try_create_int_counter(
+        "beacon_backfill_rate_limited_total",
+        "Count of times a backfill batch was delayed by the rate limit or a pause"
+    );
+    pub static ref BACKFILL_PAUSED: Result<IntGauge> = try_create_int_gauge(
+        "beacon_backfill_paused",
+        "Set to 1 whilst backfill sync is paused"
+    );
+
+    /*
+     * Clock monitoring
+     */
+    pub static ref CLOCK_JUMPS_TOTAL: Result<IntCounter> = try_create_int_counter(
+        "beacon_clock_jumps_total",
+        "Count of jumps in the wall-clock slot detected by the per-slot task"
+    );
+
+    /*
+     * Finality watchdog
+     */
+    pub static ref FINALITY_DELAY_EPOCHS: Result<IntGauge> = try_create_int_gauge(
+        "beacon_finality_delay_epochs",
+        "Number of epochs between the wall-clock epoch and the finalized epoch of the head"
+    );
+
+    /*
+     * Repeated failures
+     */
+    pub static ref REPEATED_FAILURES_TOTAL: Result<IntCounterVec> = try_create_int_counter_vec(
+        "beacon_repeated_failures_total",
+        "Count of failures of routines which run each slot or head change, by routine",
+        &["failure"]
+    );
 
     /*
      * Pre-finalization block cache.
@@ -922,6 +1100,151 @@ lazy_static! {
             "beacon_pre_finalization_block_lookup_count",
             "Number of block roots subject to single block lookups"
         );
+    pub static ref INVALID_BLOCK_CACHE_HITS: Result<IntCounter> = try_create_int_counter(
+        "beacon_invalid_block_cache_hits_total",
+        "Number of blocks rejected early because their parent is known to be invalid"
+    );
+    pub static ref HEAD_VIEWS_CONSISTENT: Result<IntGauge> = try_create_int_gauge(
+        "beacon_head_views_consistent",
+        "Set to 1 if the canonical head snapshot, fork choice and the head tracker agree upon the head, 0 otherwise"
+    );
+    pub static ref CACHE_PREWARM_SECONDS: Result<Histogram> = try_create_histogram(
+        "beacon_cache_prewarm_seconds",
+        "Time taken to pre-warm the committee caches whilst slots are skipped"
+    );
+    pub static ref CACHE_MEMORY_USAGE: Result<IntGaugeVec> = try_create_int_gauge_vec(
+        "beacon_cache_memory_usage_bytes",
+        "Estimated memory used by each of the chain's caches",
+        &["cache"]
+    );
+    pub static ref CACHE_MEMORY_TOTAL: Result<IntGauge> = try_create_int_gauge(
+        "beacon_cache_memory_total_bytes",
+        "Estimated memory used by the chain's caches which counts towards the budget"
+    );
+    pub static ref CACHE_MEMORY_BUDGET_EXCEEDED: Result<IntCounter> = try_create_int_counter(
+        "beacon_cache_memory_budget_exceeded_total",
+        "Number of times the caches were shrunk to meet the memory budget"
+    );
+    pub static ref ATTESTATION_VERIFICATION_IN_FLIGHT_BATCHES: Result<IntGauge> = try_create_int_gauge(
+        "beacon_attestation_verification_in_flight_batches",
+        "Number of batches of gossip attestations currently being verified"
+    );
+    pub static ref ATTESTATION_VERIFICATION_LATENCY_EWMA: Result<Gauge> = try_create_float_gauge(
+        "beacon_attestation_verification_latency_ewma_seconds",
+        "Moving average of the time taken to verify a batch of gossip attestations"
+    );
+    pub static ref PRE_FINALIZATION_BLOCK_CACHE_HITS: Result<IntCounter> =
+        try_create_int_counter(
+            "beacon_pre_finalization_block_cache_hits_total",
+            "Number of block roots answered by the pre-finalization block cache"
+        );
+    pub static ref PRE_FINALIZATION_BLOCK_CACHE_MISSES: Result<IntCounter> =
+        try_create_int_counter(
+            "beacon_pre_finalization_block_cache_misses_total",
+            "Number of block roots which missed the pre-finalization block cache"
+        );
+    pub static ref PRE_FINALIZATION_BLOCK_CACHE_INSERTS: Result<IntCounter> =
+        try_create_int_counter(
+            "beacon_pre_finalization_block_cache_inserts_total",
+            "Number of block roots inserted into the pre-finalization block cache"
+        );
+    pub static ref PRE_FINALIZATION_BLOCK_CACHE_PRUNED: Result<IntCounter> =
+        try_create_int_counter(
+            "beacon_pre_finalization_block_cache_pruned_total",
+            "Number of entries pruned from the pre-finalization block cache after finalization"
+        );
+
+    /*
+     * Orphan blocks
+     */
+    pub static ref ORPHAN_BLOCKS: Result<IntGauge> =
+        try_create_int_gauge(
+            "beacon_orphan_blocks",
+            "Number of recently received blocks whose parent is unknown"
+        );
+
+    /*
+     * Pruning of abandoned forks
+     */
+    pub static ref PRUNED_ABANDONED_BLOCKS: Result<IntCounter> = try_create_int_counter(
+        "beacon_pruned_abandoned_blocks_total",
+        "Number of blocks from abandoned forks deleted from the hot database after finalization"
+    );
+    pub static ref PRUNED_ABANDONED_STATES: Result<IntCounter> = try_create_int_counter(
+        "beacon_pruned_abandoned_states_total",
+        "Number of states from abandoned forks deleted from the hot database after finalization"
+    );
+    pub static ref PRUNED_ABANDONED_BYTES: Result<IntCounter> = try_create_int_counter(
+        "beacon_pruned_abandoned_bytes_total",
+        "Number of bytes of blocks and states from abandoned forks deleted from the hot database"
+    );
+    pub static ref PRUNING_PROTECTED_HEADS_SKIPPED: Result<IntCounter> = try_create_int_counter(
+        "beacon_pruning_protected_heads_skipped_total",
+        "Number of abandoned heads left unpruned because fork choice still references their blocks"
+    );
+    pub static ref PRUNED_EXECUTION_PAYLOADS: Result<IntCounter> = try_create_int_counter(
+        "beacon_pruned_execution_payloads_total",
+        "Number of execution payloads of finalized blocks deleted from the database"
+    );
+    pub static ref PAYLOAD_RECONSTRUCTION_FAILURES: Result<IntCounter> = try_create_int_counter(
+        "beacon_payload_reconstruction_failures_total",
+        "Number of payloads from the execution layer which did not match the header of the stored block"
+    );
+
+    /*
+     * Validator registrations (builder API)
+     */
+    pub static ref VALIDATOR_REGISTRATIONS: Result<IntCounterVec> = try_create_int_counter_vec(
+        "beacon_validator_registrations_total",
+        "Count of validator registrations processed, by outcome",
+        &["outcome"]
+    );
+    pub static ref VALIDATOR_REGISTRATIONS_STORED: Result<IntGauge> = try_create_int_gauge(
+        "beacon_validator_registrations_stored",
+        "Number of validators with a stored builder API registration"
+    );
+
+    /*
+     * Proposal history
+     */
+    pub static ref PROPOSAL_PAYLOAD_SOURCE_TOTAL: Result<IntCounterVec> = try_create_int_counter_vec(
+        "beacon_proposal_payload_source_total",
+        "Count of blocks produced by this node, by the source of their execution payload",
+        &["source"]
+    );
+    pub static ref PROPOSALS_MISSED_TOTAL: Result<IntCounter> = try_create_int_counter(
+        "beacon_proposals_missed_total",
+        "Count of slots for which this node produced a block which was never published"
+    );
+    pub static ref BUILDER_PAYLOAD_WITHHELD_TOTAL: Result<IntCounter> = try_create_int_counter(
+        "beacon_builder_payload_withheld_total",
+        "Count of signed blinded blocks for which the builder failed to reveal a valid payload"
+    );
+
+    /*
+     * Exit queue
+     */
+    pub static ref EXIT_QUEUE_EXITING_VALIDATORS: Result<IntGauge> = try_create_int_gauge(
+        "beacon_exit_queue_exiting_validators",
+        "Number of validators in the head state which have initiated an exit but not yet exited"
+    );
+    pub static ref EXIT_QUEUE_MAX_EXIT_EPOCH: Result<IntGauge> = try_create_int_gauge(
+        "beacon_exit_queue_max_exit_epoch",
+        "The furthest exit epoch of the exiting validators in the head state, or zero if none"
+    );
+    pub static ref EXIT_QUEUE_CHURN: Result<IntGauge> = try_create_int_gauge(
+        "beacon_exit_queue_churn",
+        "Number of validators scheduled to exit in the epoch at the end of the exit queue"
+    );
+    pub static ref EXIT_QUEUE_CHURN_LIMIT: Result<IntGauge> = try_create_int_gauge(
+        "beacon_exit_queue_churn_limit",
+        "The maximum number of validators which may exit in an epoch"
+    );
+    pub static ref WITHDRAWAL_CREDENTIAL_PREFIXES: Result<IntGaugeVec> = try_create_int_gauge_vec(
+        "beacon_withdrawal_credential_prefixes",
+        "Number of validators in the head state with each withdrawal credential prefix",
+        &["prefix"]
+    );
 }
 
 /// Scrape the `beacon_chain` for metrics that are not constantly updated (e.g., the present slot,
@@ -931,6 +1254,8 @@ pub fn scrape_for_metrics<T: BeaconChainTypes>(beacon_chain: &BeaconChain<T>) {
         scrape_head_state(&head.beacon_state, head.beacon_state_root());
         Ok::<_, BeaconChainError>(())
     });
+    // Updates the exit queue gauges whenever the epoch of the head state changes.
+    let _ = beacon_chain.exit_queue_summary();
 
     if let Some(slot) = beacon_chain.slot_clock.now() {
         scrape_attestation_observation(slot, beacon_chain);
@@ -949,11 +1274,27 @@ pub fn scrape_for_metrics<T: BeaconChainTypes>(beacon_chain: &BeaconChain<T>) {
         )
     }
 
+    if let Some(anchor) = beacon_chain.store.get_anchor_info() {
+        set_gauge(
+            &BACKFILL_OLDEST_BLOCK_SLOT,
+            anchor.oldest_block_slot.as_u64() as i64,
+        );
+    }
+
     if let Some((size, num_lookups)) = beacon_chain.pre_finalization_block_cache.metrics() {
         set_gauge_by_usize(&PRE_FINALIZATION_BLOCK_CACHE_SIZE, size);
         set_gauge_by_usize(&PRE_FINALIZATION_BLOCK_LOOKUP_COUNT, num_lookups);
     }
 
+    if let Some(orphans) = beacon_chain.orphan_block_tracker.metrics() {
+        set_gauge_by_usize(&ORPHAN_BLOCKS, orphans);
+    }
+
+    set_gauge_by_usize(
+        &VALIDATOR_REGISTRATIONS_STORED,
+        beacon_chain.validator_registrations.read().len(),
+    );
+
     set_gauge_by_usize(
         &OP_POOL_NUM_ATTESTATIONS,
         attestation_stats.num_attestations,
@@ -1,6 +1,8 @@
 use crate::beacon_chain::BEACON_CHAIN_DB_KEY;
 use crate::errors::BeaconChainError;
 use crate::head_tracker::{HeadTracker, SszHeadTracker};
+use crate::metrics;
+use crate::payload_pruning::prune_execution_payloads;
 use crate::persisted_beacon_chain::{PersistedBeaconChain, DUMMY_CANONICAL_HEAD_BLOCK_ROOT};
 use parking_lot::Mutex;
 use slog::{debug, error, info, warn, Logger};
@@ -10,8 +12,8 @@ use std::sync::{mpsc, Arc};
 use std::thread;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use store::hot_cold_store::{migrate_database, HotColdDBError};
-use store::iter::RootsIterator;
-use store::{Error, ItemStore, StoreItem, StoreOp};
+use store::iter::{BlockRootsIterator, RootsIterator};
+use store::{DBColumn, Error, ItemStore, StoreItem, StoreOp};
 pub use store::{HotColdDB, MemoryStore};
 use types::{
     BeaconState, BeaconStateError, BeaconStateHash, Checkpoint, Epoch, EthSpec, Hash256,
@@ -92,6 +94,10 @@ pub struct FinalizationNotification {
     finalized_state_root: BeaconStateHash,
     finalized_checkpoint: Checkpoint,
     head_tracker: Arc<HeadTracker>,
+    /// Blocks which fork choice still references, and which must not be pruned.
+    protected_block_roots: HashSet<Hash256>,
+    /// Whether to delete the execution payloads of newly finalized blocks.
+    prune_payloads: bool,
     genesis_block_root: Hash256,
 }
 
@@ -103,7 +109,16 @@ impl<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>> BackgroundMigrator<E, Ho
         genesis_block_root: Hash256,
         log: Logger,
     ) -> Self {
+        if db.get_config().freezer_readonly {
+            info!(
+                log,
+                "Finalized states will not be migrated";
+                "reason" => "the freezer database is read-only",
+            );
+        }
         let tx_thread = if config.blocking {
+            // Run any pending restore point migration to completion in the foreground.
+            while Self::run_restore_point_migration_step(&db, &log) {}
             None
         } else {
             Some(Mutex::new(Self::spawn_thread(db.clone(), log.clone())))
@@ -120,17 +135,24 @@ impl<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>> BackgroundMigrator<E, Ho
     ///
     /// If successful, all forks descending from before the `finalized_checkpoint` will be
     /// pruned, and the split point of the database will be advanced to the slot of the finalized
-    /// checkpoint.
+    /// checkpoint. Forks containing any of the `protected_block_roots` are never pruned.
+    ///
+    /// If `prune_payloads` is set, the execution payloads of the blocks which move prior to the
+    /// split point are deleted.
     pub fn process_finalization(
         &self,
         finalized_state_root: BeaconStateHash,
         finalized_checkpoint: Checkpoint,
         head_tracker: Arc<HeadTracker>,
+        protected_block_roots: HashSet<Hash256>,
+        prune_payloads: bool,
     ) -> Result<(), BeaconChainError> {
         let notif = FinalizationNotification {
             finalized_state_root,
             finalized_checkpoint,
             head_tracker,
+            protected_block_roots,
+            prune_payloads,
             genesis_block_root: self.genesis_block_root,
         };
 
@@ -153,6 +175,22 @@ impl<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>> BackgroundMigrator<E, Ho
     }
 
     pub fn run_reconstruction(db: Arc<HotColdDB<E, Hot, Cold>>, log: &Logger) {
+        if db.get_config().freezer_readonly {
+            warn!(
+                log,
+                "State reconstruction skipped";
+                "reason" => "the freezer database is read-only",
+            );
+            return;
+        }
+        if db.restore_point_migration_pending() {
+            warn!(
+                log,
+                "State reconstruction skipped";
+                "reason" => "the restore point migration is incomplete",
+            );
+            return;
+        }
         if let Err(e) = db.reconstruct_historic_states() {
             error!(
                 log,
@@ -162,6 +200,28 @@ impl<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>> BackgroundMigrator<E, Ho
         }
     }
 
+    /// Run the next step of any pending restore point migration.
+    ///
+    /// Return `true` if there are more steps to run, or `false` if the migration is complete or
+    /// failed.
+    fn run_restore_point_migration_step(db: &HotColdDB<E, Hot, Cold>, log: &Logger) -> bool {
+        if !db.restore_point_migration_pending() {
+            return false;
+        }
+        match db.restore_point_migration_step() {
+            Ok(complete) => !complete,
+            Err(e) => {
+                error!(
+                    log,
+                    "Restore point migration failed";
+                    "error" => ?e,
+                    "msg" => "the migration will be resumed on restart",
+                );
+                false
+            }
+        }
+    }
+
     /// If configured to run in the background, send `notif` to the background thread.
     ///
     /// Return `None` if the message was sent to the background thread, `Some(notif)` otherwise.
@@ -224,6 +284,7 @@ impl<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>> BackgroundMigrator<E, Ho
         let old_finalized_checkpoint = match Self::prune_abandoned_forks(
             db.clone(),
             notif.head_tracker,
+            &notif.protected_block_roots,
             finalized_state_root,
             &finalized_state,
             notif.finalized_checkpoint,
@@ -260,24 +321,48 @@ impl<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>> BackgroundMigrator<E, Ho
             }
         };
 
-        match migrate_database(db.clone(), finalized_state_root.into(), &finalized_state) {
-            Ok(()) => {}
-            Err(Error::HotColdDBError(HotColdDBError::FreezeSlotUnaligned(slot))) => {
-                debug!(
-                    log,
-                    "Database migration postponed, unaligned finalized block";
-                    "slot" => slot.as_u64()
-                );
-            }
-            Err(e) => {
-                warn!(
-                    log,
-                    "Database migration failed";
-                    "error" => format!("{:?}", e)
-                );
-                return;
-            }
-        };
+        // The hot database is still pruned, but finalized states can't be moved to the freezer.
+        if db.get_config().freezer_readonly {
+            debug!(
+                log,
+                "Database migration skipped";
+                "reason" => "the freezer database is read-only",
+                "finalized_epoch" => notif.finalized_checkpoint.epoch,
+            );
+        } else {
+            let old_split_slot = db.get_split_slot();
+            match migrate_database(db.clone(), finalized_state_root.into(), &finalized_state) {
+                Ok(()) if notif.prune_payloads => {
+                    // The blocks between the old and new split points are now returned blinded,
+                    // so their payloads are no longer needed.
+                    let block_roots =
+                        BlockRootsIterator::new(&db, &finalized_state).take_while(|result| {
+                            result
+                                .as_ref()
+                                .map_or(true, |(_, slot)| *slot >= old_split_slot)
+                        });
+                    if let Err(e) = prune_execution_payloads(&db, block_roots, log) {
+                        warn!(log, "Execution payload pruning failed"; "error" => ?e);
+                    }
+                }
+                Ok(()) => {}
+                Err(Error::HotColdDBError(HotColdDBError::FreezeSlotUnaligned(slot))) => {
+                    debug!(
+                        log,
+                        "Database migration postponed, unaligned finalized block";
+                        "slot" => slot.as_u64()
+                    );
+                }
+                Err(e) => {
+                    warn!(
+                        log,
+                        "Database migration failed";
+                        "error" => format!("{:?}", e)
+                    );
+                    return;
+                }
+            };
+        }
 
         // Finally, compact the database so that new free space is properly reclaimed.
         if let Err(e) = Self::run_compaction(
@@ -301,7 +386,32 @@ impl<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>> BackgroundMigrator<E, Ho
     ) -> (mpsc::Sender<Notification>, thread::JoinHandle<()>) {
         let (tx, rx) = mpsc::channel();
         let thread = thread::spawn(move || {
-            while let Ok(notif) = rx.recv() {
+            // Run the steps of any pending restore point migration whenever there are no
+            // notifications to process. Reconstruction is deferred until the migration completes.
+            let mut restore_point_migration = db.restore_point_migration_pending();
+            let mut reconstruction_deferred = false;
+            loop {
+                let notif = if restore_point_migration {
+                    match rx.try_recv() {
+                        Ok(notif) => notif,
+                        Err(mpsc::TryRecvError::Empty) => {
+                            restore_point_migration =
+                                Self::run_restore_point_migration_step(&db, &log);
+                            if !restore_point_migration && reconstruction_deferred {
+                                reconstruction_deferred = false;
+                                Self::run_reconstruction(db.clone(), &log);
+                            }
+                            continue;
+                        }
+                        Err(mpsc::TryRecvError::Disconnected) => break,
+                    }
+                } else {
+                    match rx.recv() {
+                        Ok(notif) => notif,
+                        Err(_) => break,
+                    }
+                };
+
                 // Read the rest of the messages in the channel, preferring any reconstruction
                 // notification, or the finalization notification with the greatest finalized epoch.
                 let notif =
@@ -323,6 +433,9 @@ impl<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>> BackgroundMigrator<E, Ho
                         });
 
                 match notif {
+                    Notification::Reconstruction if restore_point_migration => {
+                        reconstruction_deferred = true
+                    }
                     Notification::Reconstruction => Self::run_reconstruction(db.clone(), &log),
                     Notification::Finalization(fin) => Self::run_migration(db.clone(), fin, &log),
                 }
@@ -334,10 +447,13 @@ impl<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>> BackgroundMigrator<E, Ho
     /// Traverses live heads and prunes blocks and states of chains that we know can't be built
     /// upon because finalization would prohibit it. This is an optimisation intended to save disk
     /// space.
+    ///
+    /// Chains containing any of the `protected_block_roots` are left untouched.
     #[allow(clippy::too_many_arguments)]
     fn prune_abandoned_forks(
         store: Arc<HotColdDB<E, Hot, Cold>>,
         head_tracker: Arc<HeadTracker>,
+        protected_block_roots: &HashSet<Hash256>,
         new_finalized_state_hash: BeaconStateHash,
         new_finalized_state: &BeaconState<E>,
         new_finalized_checkpoint: Checkpoint,
@@ -527,6 +643,27 @@ impl<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>> BackgroundMigrator<E, Ho
                 }
             }
 
+            // Fork choice should only reference blocks descending from the finalized block, which
+            // aren't abandoned. Leave the chain in place rather than pull a block out from under
+            // fork choice if that isn't so.
+            let protected_block_root = potentially_abandoned_blocks
+                .iter()
+                .filter_map(|(_, maybe_block_hash, _)| maybe_block_hash.map(Hash256::from))
+                .find(|block_root| protected_block_roots.contains(block_root));
+            if let (Some(_), Some(protected_block_root)) =
+                (potentially_abandoned_head, protected_block_root)
+            {
+                warn!(
+                    log,
+                    "Not pruning chain referenced by fork choice";
+                    "head_block_root" => ?head_hash,
+                    "head_slot" => head_slot,
+                    "block_root" => ?protected_block_root,
+                );
+                metrics::inc_counter(&metrics::PRUNING_PROTECTED_HEADS_SKIPPED);
+                potentially_abandoned_head.take();
+            }
+
             if let Some(abandoned_head) = potentially_abandoned_head {
                 debug!(
                     log,
@@ -564,6 +701,28 @@ impl<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>> BackgroundMigrator<E, Ho
         for head_hash in abandoned_heads {
             head_tracker_lock.remove(&head_hash);
         }
+        // Block imports register with the head tracker whilst holding the fork choice lock, so
+        // release the lock before reading from the database.
+        let ssz_head_tracker = SszHeadTracker::from_map(&*head_tracker_lock);
+        drop(head_tracker_lock);
+
+        // Measure the space taken by the abandoned blocks and states before they're deleted.
+        let num_abandoned_blocks = abandoned_blocks.len();
+        let num_abandoned_states = abandoned_states.len();
+        let mut bytes_reclaimed = 0;
+        for block_hash in &abandoned_blocks {
+            let block_root = (*block_hash).into();
+            bytes_reclaimed += hot_db_value_len(&store, DBColumn::BeaconBlock, block_root)?;
+            bytes_reclaimed += hot_db_value_len(&store, DBColumn::ExecPayload, block_root)?;
+        }
+        for (slot, state_hash) in &abandoned_states {
+            let state_root = (*state_hash).into();
+            bytes_reclaimed += hot_db_value_len(&store, DBColumn::BeaconStateSummary, state_root)?;
+            if *slot % E::slots_per_epoch() == 0 {
+                bytes_reclaimed += hot_db_value_len(&store, DBColumn::BeaconState, state_root)?;
+                bytes_reclaimed += hot_db_value_len(&store, DBColumn::BeaconStateDiff, state_root)?;
+            }
+        }
 
         let batch: Vec<StoreOp<E>> = abandoned_blocks
             .into_iter()
@@ -588,16 +747,31 @@ impl<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>> BackgroundMigrator<E, Ho
         let persisted_head = PersistedBeaconChain {
             _canonical_head_block_root: DUMMY_CANONICAL_HEAD_BLOCK_ROOT,
             genesis_block_root,
-            ssz_head_tracker: SszHeadTracker::from_map(&*head_tracker_lock),
+            ssz_head_tracker,
         };
-        drop(head_tracker_lock);
         kv_batch.push(persisted_head.as_kv_store_op(BEACON_CHAIN_DB_KEY));
 
         // Persist the new finalized checkpoint as the pruning checkpoint.
         kv_batch.push(store.pruning_checkpoint_store_op(new_finalized_checkpoint));
 
         store.hot_db.do_atomically(kv_batch)?;
-        debug!(log, "Database pruning complete");
+
+        metrics::inc_counter_by(
+            &metrics::PRUNED_ABANDONED_BLOCKS,
+            num_abandoned_blocks as u64,
+        );
+        metrics::inc_counter_by(
+            &metrics::PRUNED_ABANDONED_STATES,
+            num_abandoned_states as u64,
+        );
+        metrics::inc_counter_by(&metrics::PRUNED_ABANDONED_BYTES, bytes_reclaimed);
+        debug!(
+            log,
+            "Database pruning complete";
+            "pruned_blocks" => num_abandoned_blocks,
+            "pruned_states" => num_abandoned_states,
+            "bytes_reclaimed" => bytes_reclaimed,
+        );
 
         Ok(PruningOutcome::Successful {
             old_finalized_checkpoint,
@@ -649,3 +823,15 @@ impl<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>> BackgroundMigrator<E, Ho
         Ok(())
     }
 }
+
+/// The length of the value stored under `key` in `column` of the hot database, or 0 if absent.
+fn hot_db_value_len<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>>(
+    store: &HotColdDB<E, Hot, Cold>,
+    column: DBColumn,
+    key: Hash256,
+) -> Result<u64, Error> {
+    Ok(store
+        .hot_db
+        .get_bytes(column.into(), key.as_bytes())?
+        .map_or(0, |bytes| bytes.len() as u64))
+}
@@ -1,5 +1,5 @@
 use crate::metrics;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use tree_hash::TreeHash;
 use types::consts::altair::SYNC_COMMITTEE_SUBNET_COUNT;
 use types::slot_data::SlotData;
@@ -34,6 +34,15 @@ pub enum InsertOutcome {
     SignatureAggregated { committee_index: usize },
 }
 
+/// Summarizes the aggregated attestations of a single committee in the `NaiveAggregationPool`.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct CommitteeAggregationSummary {
+    /// The number of distinct `AttestationData` for the committee.
+    pub data_roots: usize,
+    /// The greatest number of attesters in any one of the committee's aggregates.
+    pub max_participation: usize,
+}
+
 #[derive(Debug, PartialEq)]
 pub enum Error {
     /// The given `data.slot` was too low to be stored. No changes were made.
@@ -424,6 +433,12 @@ impl<T: AggregateMap> NaiveAggregationPool<T> {
         self.maps.values().flat_map(|map| map.get_map().values())
     }
 
+    /// Removes any items with a slot after the slot following `current_slot`, e.g. after the
+    /// system clock has moved backwards.
+    pub fn prune_future(&mut self, current_slot: Slot) {
+        self.maps.retain(|slot, _map| *slot <= current_slot + 1);
+    }
+
     /// Removes any items with a slot lower than `current_slot` and bars any future
     /// items with a slot lower than `current_slot - SLOTS_RETAINED`.
     pub fn prune(&mut self, current_slot: Slot) {
@@ -466,6 +481,24 @@ impl<T: AggregateMap> NaiveAggregationPool<T> {
     }
 }
 
+impl<E: EthSpec> NaiveAggregationPool<AggregatedAttestationMap<E>> {
+    /// Summarizes the aggregated attestations at `slot`, by committee index.
+    pub fn summarize_slot(&self, slot: Slot) -> BTreeMap<u64, CommitteeAggregationSummary> {
+        let mut summaries = BTreeMap::<u64, CommitteeAggregationSummary>::new();
+        if let Some(map) = self.maps.get(&slot) {
+            for attestation in map.map.values() {
+                let summary = summaries.entry(attestation.data.index).or_default();
+                summary.data_roots += 1;
+                summary.max_participation = std::cmp::max(
+                    summary.max_participation,
+                    attestation.aggregation_bits.num_set_bits(),
+                );
+            }
+        }
+        summaries
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -768,6 +801,58 @@ mod tests {
         };
     }
 
+    #[test]
+    fn summarize_slot_by_committee() {
+        let mut pool: NaiveAggregationPool<AggregatedAttestationMap<E>> =
+            NaiveAggregationPool::default();
+        let genesis_validators_root = Hash256::random();
+        let mut insert = |template: &Attestation<E>, committee_positions: &[usize]| {
+            for &i in committee_positions {
+                let mut a = template.clone();
+                sign_attestation(&mut a, i, genesis_validators_root);
+                pool.insert(&a).expect("should insert attestation");
+            }
+        };
+
+        // Two distinct votes from committee 0 and one from committee 1.
+        let slot = Slot::new(1);
+        let mut a = get_attestation(slot);
+        a.data.index = 0;
+        let mut b = a.clone();
+        mutate_attestation_block_root(&mut b, Hash256::random());
+        let mut c = a.clone();
+        c.data.index = 1;
+        insert(&a, &[0, 1, 2]);
+        insert(&b, &[3]);
+        insert(&c, &[0, 1]);
+
+        // Attestations from other slots are not included.
+        let mut d = get_attestation(slot + 1);
+        d.data.index = 0;
+        insert(&d, &[0]);
+
+        assert_eq!(
+            pool.summarize_slot(slot),
+            BTreeMap::from([
+                (
+                    0,
+                    CommitteeAggregationSummary {
+                        data_roots: 2,
+                        max_participation: 3,
+                    }
+                ),
+                (
+                    1,
+                    CommitteeAggregationSummary {
+                        data_roots: 1,
+                        max_participation: 2,
+                    }
+                ),
+            ])
+        );
+        assert!(pool.summarize_slot(slot + 2).is_empty());
+    }
+
     test_suite! {
         attestation_tests,
         get_attestation,
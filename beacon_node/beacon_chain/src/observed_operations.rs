@@ -1,6 +1,6 @@
 use derivative::Derivative;
 use smallvec::SmallVec;
-use state_processing::{SigVerifiedOp, VerifyOperation};
+use state_processing::{batch_validate, SigVerifiedOp, VerifyOperation};
 use std::collections::HashSet;
 use std::marker::PhantomData;
 use types::{
@@ -91,10 +91,7 @@ impl<T: ObservableOperation<E>, E: EthSpec> ObservedOperations<T, E> {
         //
         // At least one index in the intersection of the attesting indices of each attestation has
         // not yet been seen in any prior attester_slashing.
-        if new_validator_indices
-            .iter()
-            .all(|index| observed_validator_indices.contains(index))
-        {
+        if Self::all_observed(observed_validator_indices, &new_validator_indices) {
             return Ok(ObservationOutcome::AlreadyKnown);
         }
 
@@ -107,4 +104,49 @@ impl<T: ObservableOperation<E>, E: EthSpec> ObservedOperations<T, E> {
 
         Ok(ObservationOutcome::New(verified_op))
     }
+
+    /// Verify and observe each of `ops` in turn, verifying the signatures of the batch at once.
+    ///
+    /// The outcome for each operation is identical to that of calling `verify_and_observe` for
+    /// each operation in order.
+    pub fn batch_verify_and_observe(
+        &mut self,
+        ops: Vec<T>,
+        head_state: &BeaconState<E>,
+        spec: &ChainSpec,
+    ) -> Vec<Result<ObservationOutcome<T>, T::Error>> {
+        let mut outcomes = Vec::with_capacity(ops.len());
+        let mut pending = vec![];
+        let mut to_verify = vec![];
+        for op in ops {
+            let new_validator_indices = op.observed_validators();
+            if !Self::all_observed(&self.observed_validator_indices, &new_validator_indices) {
+                pending.push((outcomes.len(), new_validator_indices));
+                to_verify.push(op);
+            }
+            // Operations pending verification have their outcome replaced below.
+            outcomes.push(Ok(ObservationOutcome::AlreadyKnown));
+        }
+
+        let results = batch_validate(to_verify, head_state, spec);
+        for ((position, new_validator_indices), result) in pending.into_iter().zip(results) {
+            // An earlier operation in the batch may have covered the indices of this one.
+            if Self::all_observed(&self.observed_validator_indices, &new_validator_indices) {
+                continue;
+            }
+            outcomes[position] = result.map(|verified_op| {
+                self.observed_validator_indices
+                    .extend(new_validator_indices);
+                ObservationOutcome::New(verified_op)
+            });
+        }
+
+        outcomes
+    }
+
+    fn all_observed(observed_validator_indices: &HashSet<u64>, validator_indices: &[u64]) -> bool {
+        validator_indices
+            .iter()
+            .all(|index| observed_validator_indices.contains(index))
+    }
 }
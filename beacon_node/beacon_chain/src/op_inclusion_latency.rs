@@ -0,0 +1,210 @@
+//! Measures the latency of operations, from when they're first verified to when they're included in
+//! an imported block, by type of operation.
+//!
+//! Each operation is keyed by a root which is shared by the verified operation and its inclusion in
+//! a block. This is the root of the operation for exits and slashings, the root of the attestation
+//! data for attestations (since aggregates, rather than the verified attestations themselves, are
+//! included) and the beacon block root of sync contributions (since they're included as part of
+//! a sync aggregate over the parent of the including block).
+//!
+//! Operations which aren't included within `OP_INCLUSION_LATENCY_HORIZON_SLOTS` are dropped, and
+//! at most `MAX_PENDING_OPERATIONS` are tracked at once.
+use crate::observed_operations::ObservationOutcome;
+use crate::{metrics, BeaconChain, BeaconChainTypes};
+use parking_lot::Mutex;
+use slot_clock::SlotClock;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tree_hash::TreeHash;
+use types::{BeaconBlockRef, EthSpec, ExecPayload, Hash256, Slot};
+
+/// Operations which aren't included within this many slots of being verified are dropped.
+pub const OP_INCLUSION_LATENCY_HORIZON_SLOTS: u64 = 64;
+
+/// The maximum number of operations awaiting inclusion which are tracked. Further operations are
+/// ignored until some are included or dropped.
+pub const MAX_PENDING_OPERATIONS: usize = 16_384;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OperationKind {
+    Attestation,
+    VoluntaryExit,
+    ProposerSlashing,
+    AttesterSlashing,
+    SyncContribution,
+}
+
+impl OperationKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OperationKind::Attestation => "attestation",
+            OperationKind::VoluntaryExit => "voluntary_exit",
+            OperationKind::ProposerSlashing => "proposer_slashing",
+            OperationKind::AttesterSlashing => "attester_slashing",
+            OperationKind::SyncContribution => "sync_contribution",
+        }
+    }
+}
+
+struct PendingOperation {
+    /// The slot in which the operation was first verified.
+    slot: Slot,
+    first_verified: Instant,
+}
+
+/// The operations which have been verified but not yet included in an imported block.
+#[derive(Default)]
+pub struct PendingOperations {
+    operations: Mutex<HashMap<(OperationKind, Hash256), PendingOperation>>,
+}
+
+impl PendingOperations {
+    /// Record that the operation with `root` was verified in `slot`, unless it's already tracked or
+    /// `MAX_PENDING_OPERATIONS` are tracked.
+    pub fn observe(&self, kind: OperationKind, root: Hash256, slot: Slot) {
+        let mut operations = self.operations.lock();
+        if operations.len() >= MAX_PENDING_OPERATIONS {
+            return;
+        }
+        operations
+            .entry((kind, root))
+            .or_insert_with(|| PendingOperation {
+                slot,
+                first_verified: Instant::now(),
+            });
+    }
+
+    /// Stop tracking the operation with `root`, returning the time since it was first verified.
+    pub fn take(&self, kind: OperationKind, root: Hash256) -> Option<Duration> {
+        self.operations
+            .lock()
+            .remove(&(kind, root))
+            .map(|operation| operation.first_verified.elapsed())
+    }
+
+    /// Drop the operations verified more than `OP_INCLUSION_LATENCY_HORIZON_SLOTS` before
+    /// `current_slot`.
+    pub fn prune(&self, current_slot: Slot) {
+        self.operations.lock().retain(|_, operation| {
+            operation.slot + OP_INCLUSION_LATENCY_HORIZON_SLOTS >= current_slot
+        });
+    }
+
+    pub fn len(&self) -> usize {
+        self.operations.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.operations.lock().is_empty()
+    }
+}
+
+impl<T: BeaconChainTypes> BeaconChain<T> {
+    /// Record that the operation with `root` has been verified, if it's the first time.
+    pub(crate) fn observe_verified_operation(&self, kind: OperationKind, root: Hash256) {
+        if let Some(slot) = self.slot_clock.now() {
+            self.pending_operations.observe(kind, root, slot);
+        }
+    }
+
+    /// Record that the exit or slashing in `outcome` has been verified, if it's new.
+    pub(crate) fn observe_verified_new_operation<Op: TreeHash>(
+        &self,
+        kind: OperationKind,
+        outcome: &ObservationOutcome<Op>,
+    ) {
+        if let ObservationOutcome::New(op) = outcome {
+            self.observe_verified_operation(kind, op.as_inner().tree_hash_root());
+        }
+    }
+
+    /// Record the inclusion latency of each tracked operation which is included in `block`.
+    pub(crate) fn observe_included_operations<Payload: ExecPayload<T::EthSpec>>(
+        &self,
+        block: BeaconBlockRef<T::EthSpec, Payload>,
+    ) {
+        if self.pending_operations.is_empty() {
+            return;
+        }
+
+        let body = block.body();
+        let included = body
+            .attestations()
+            .iter()
+            .map(|attestation| {
+                (
+                    OperationKind::Attestation,
+                    attestation.data.tree_hash_root(),
+                )
+            })
+            .chain(
+                body.voluntary_exits()
+                    .iter()
+                    .map(|exit| (OperationKind::VoluntaryExit, exit.tree_hash_root())),
+            )
+            .chain(
+                body.proposer_slashings()
+                    .iter()
+                    .map(|slashing| (OperationKind::ProposerSlashing, slashing.tree_hash_root())),
+            )
+            .chain(
+                body.attester_slashings()
+                    .iter()
+                    .map(|slashing| (OperationKind::AttesterSlashing, slashing.tree_hash_root())),
+            )
+            .chain(
+                body.sync_aggregate()
+                    .ok()
+                    .filter(|sync_aggregate| sync_aggregate.num_set_bits() > 0)
+                    .map(|_| (OperationKind::SyncContribution, block.parent_root())),
+            );
+
+        for (kind, root) in included {
+            if let Some(latency) = self.pending_operations.take(kind, root) {
+                metrics::observe_timer_vec(
+                    &metrics::OP_INCLUSION_LATENCY,
+                    &[kind.as_str()],
+                    latency,
+                );
+            }
+        }
+    }
+
+    /// Drop the tracked operations which are no longer expected to be included.
+    pub(crate) fn prune_pending_operations(&self, current_slot: Slot) {
+        self.pending_operations.prune(current_slot);
+        metrics::set_gauge(
+            &metrics::OP_INCLUSION_LATENCY_PENDING,
+            self.pending_operations.len() as i64,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pending_operations_are_bounded_and_pruned() {
+        let pending = PendingOperations::default();
+        let root = |i: u64| Hash256::from_low_u64_be(i);
+
+        for i in 0..MAX_PENDING_OPERATIONS as u64 + 1 {
+            pending.observe(OperationKind::Attestation, root(i), Slot::new(i % 2));
+        }
+        assert_eq!(pending.len(), MAX_PENDING_OPERATIONS);
+
+        // Operations are keyed by kind as well as root.
+        assert!(pending
+            .take(OperationKind::VoluntaryExit, root(0))
+            .is_none());
+        assert!(pending.take(OperationKind::Attestation, root(0)).is_some());
+        assert!(pending.take(OperationKind::Attestation, root(0)).is_none());
+
+        // Only the operations verified in slot 1 are within the horizon.
+        pending.prune(Slot::new(1 + OP_INCLUSION_LATENCY_HORIZON_SLOTS));
+        assert_eq!(pending.len(), MAX_PENDING_OPERATIONS / 2);
+        pending.prune(Slot::new(2 + OP_INCLUSION_LATENCY_HORIZON_SLOTS));
+        assert!(pending.is_empty());
+    }
+}
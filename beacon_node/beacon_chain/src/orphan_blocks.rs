@@ -0,0 +1,205 @@
+//! Tracks blocks which were rejected because their parent is unknown.
+//!
+//! Such blocks are handed back to the network layer, which looks up the parent. Whilst that
+//! happens, the chain would otherwise have no record of the block, so a stalled import is only
+//! visible in the sync logs. This tracker retains a summary of each orphan until it (or its parent)
+//! is imported, its slot is finalized, or it is evicted to make room for newer orphans.
+use crate::validator_monitor::timestamp_now;
+use crate::{BeaconChain, BeaconChainTypes};
+use lru::LruCache;
+use parking_lot::Mutex;
+use serde_derive::{Deserialize, Serialize};
+use std::time::Duration;
+use types::{EthSpec, Hash256, Slot};
+
+/// The maximum number of orphan blocks to track.
+pub const ORPHAN_BLOCK_TRACKER_CAPACITY: usize = 64;
+
+const METRICS_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// A block whose parent was unknown when it was received.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OrphanBlock {
+    pub block_root: Hash256,
+    pub parent_root: Hash256,
+    pub slot: Slot,
+    /// When the block was first rejected, in milliseconds since the UNIX epoch.
+    pub first_seen_ms: u64,
+}
+
+pub struct OrphanBlockTracker {
+    orphans: Mutex<LruCache<Hash256, OrphanBlock>>,
+}
+
+impl Default for OrphanBlockTracker {
+    fn default() -> Self {
+        Self::new(ORPHAN_BLOCK_TRACKER_CAPACITY)
+    }
+}
+
+impl OrphanBlockTracker {
+    /// Create a tracker which holds at most `capacity` orphans.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            orphans: Mutex::new(LruCache::new(capacity)),
+        }
+    }
+
+    /// Record that the block with `block_root` was rejected as its parent is unknown.
+    ///
+    /// If the block is already tracked, the time it was first seen is retained. The least recently
+    /// seen orphan is evicted if the tracker is full.
+    pub fn insert(&self, block_root: Hash256, parent_root: Hash256, slot: Slot, now: Duration) {
+        let mut orphans = self.orphans.lock();
+        if orphans.get(&block_root).is_none() {
+            orphans.put(
+                block_root,
+                OrphanBlock {
+                    block_root,
+                    parent_root,
+                    slot,
+                    first_seen_ms: now.as_millis() as u64,
+                },
+            );
+        }
+    }
+
+    /// Stop tracking the block with `block_root` and its children, since it has been imported.
+    pub fn block_imported(&self, block_root: Hash256) {
+        let mut orphans = self.orphans.lock();
+        orphans.pop(&block_root);
+
+        let children = orphans
+            .iter()
+            .filter(|(_, orphan)| orphan.parent_root == block_root)
+            .map(|(child_root, _)| *child_root)
+            .collect::<Vec<_>>();
+        for child_root in &children {
+            orphans.pop(child_root);
+        }
+    }
+
+    /// Stop tracking orphans at or before `finalized_slot`, which can never be imported.
+    pub fn prune(&self, finalized_slot: Slot) {
+        let mut orphans = self.orphans.lock();
+        let stale = orphans
+            .iter()
+            .filter(|(_, orphan)| orphan.slot <= finalized_slot)
+            .map(|(block_root, _)| *block_root)
+            .collect::<Vec<_>>();
+        for block_root in &stale {
+            orphans.pop(block_root);
+        }
+    }
+
+    /// Returns the tracked orphans, ordered by slot.
+    pub fn orphans(&self) -> Vec<OrphanBlock> {
+        let mut orphans = self
+            .orphans
+            .lock()
+            .iter()
+            .map(|(_, orphan)| orphan.clone())
+            .collect::<Vec<_>>();
+        orphans.sort_by_key(|orphan| (orphan.slot, orphan.block_root));
+        orphans
+    }
+
+    pub fn len(&self) -> usize {
+        self.orphans.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn metrics(&self) -> Option<usize> {
+        Some(self.orphans.try_lock_for(METRICS_TIMEOUT)?.len())
+    }
+}
+
+impl<T: BeaconChainTypes> BeaconChain<T> {
+    /// Returns the blocks which are awaiting the import of their unknown parent, ordered by slot.
+    pub fn orphan_blocks(&self) -> Vec<OrphanBlock> {
+        self.orphan_block_tracker.orphans()
+    }
+
+    /// Record that `block_root` was rejected as its parent, `parent_root`, is unknown.
+    pub(crate) fn register_orphan_block(
+        &self,
+        block_root: Hash256,
+        parent_root: Hash256,
+        slot: Slot,
+    ) {
+        self.orphan_block_tracker
+            .insert(block_root, parent_root, slot, timestamp_now());
+    }
+
+    pub fn prune_orphan_blocks(&self) {
+        let finalized_slot = self
+            .canonical_head
+            .cached_head()
+            .finalized_checkpoint()
+            .epoch
+            .start_slot(T::EthSpec::slots_per_epoch());
+        self.orphan_block_tracker.prune(finalized_slot);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn root(i: u64) -> Hash256 {
+        Hash256::from_low_u64_be(i)
+    }
+
+    #[test]
+    fn orphans_are_removed_when_they_or_their_parent_are_imported() {
+        let tracker = OrphanBlockTracker::new(4);
+        let now = Duration::from_secs(1);
+
+        // Two children of the same unknown parent, and a grandchild.
+        tracker.insert(root(2), root(1), Slot::new(2), now);
+        tracker.insert(root(3), root(1), Slot::new(3), now);
+        tracker.insert(root(4), root(2), Slot::new(4), now);
+        assert_eq!(tracker.len(), 3);
+
+        // A repeat retains the first-seen time.
+        tracker.insert(root(2), root(1), Slot::new(2), Duration::from_secs(2));
+        assert_eq!(tracker.orphans()[0].first_seen_ms, 1_000);
+
+        tracker.block_imported(root(1));
+        assert_eq!(
+            tracker
+                .orphans()
+                .iter()
+                .map(|orphan| orphan.block_root)
+                .collect::<Vec<_>>(),
+            vec![root(4)]
+        );
+
+        tracker.block_imported(root(4));
+        assert!(tracker.is_empty());
+    }
+
+    #[test]
+    fn orphans_are_bounded_and_pruned() {
+        let tracker = OrphanBlockTracker::new(2);
+        let now = Duration::from_secs(1);
+
+        tracker.insert(root(1), root(0), Slot::new(1), now);
+        tracker.insert(root(2), root(0), Slot::new(2), now);
+        tracker.insert(root(3), root(0), Slot::new(3), now);
+        let slots = |tracker: &OrphanBlockTracker| {
+            tracker
+                .orphans()
+                .iter()
+                .map(|orphan| orphan.slot.as_u64())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(slots(&tracker), vec![2, 3]);
+
+        tracker.prune(Slot::new(2));
+        assert_eq!(slots(&tracker), vec![3]);
+    }
+}
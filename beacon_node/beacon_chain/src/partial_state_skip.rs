@@ -0,0 +1,162 @@
+//! Retains the progress of a forward state skip in `BeaconChain::state_at_slot` which fails part
+//! way, so that a retry resumes from the furthest slot reached rather than repeating the whole
+//! skip.
+//!
+//! Only the progress of the most recent failed skip is retained. It's keyed by the root of the
+//! state from which the skip started and by whether state roots were computed, so it's discarded
+//! once a skip starts from another state, or once the head changes.
+//!
+//! Epoch processing and fork upgrades may fail after mutating the state, so a skip which fails
+//! whilst running them retains no progress. Cloning the state before each of them would make every
+//! skip across an epoch boundary pay for a rare failure, and since they're deterministic, a retry
+//! from the state before them would only fail again. Otherwise, slot processing fails before
+//! mutating the state (as does a skip which times out), so the state itself is retained without
+//! being cloned.
+use crate::beacon_chain::StateSkipConfig;
+use crate::{BeaconChain, BeaconChainError as Error, BeaconChainTypes};
+use slog::warn;
+use state_processing::per_slot_processing;
+use std::time::{Duration, Instant};
+use types::{BeaconState, EthSpec, Hash256, Slot};
+
+/// A state which was advanced part of the way to the slot requested of a failed skip.
+pub struct PartialStateSkip<E: EthSpec> {
+    /// The root of the state from which the skip started.
+    base_state_root: Hash256,
+    /// Whether the zero hash was used in place of the state root of each skipped slot.
+    without_state_roots: bool,
+    state: BeaconState<E>,
+}
+
+impl<T: BeaconChainTypes> BeaconChain<T> {
+    /// Advance `state`, which has the root `base_state_root`, through skip slots to `slot`.
+    ///
+    /// If a previous skip from the same state failed before reaching `slot`, this skip resumes
+    /// from where it stopped. If this skip fails, its progress is retained in the same way, unless
+    /// it fails during epoch processing or a fork upgrade.
+    pub fn skip_state_forward(
+        &self,
+        mut state: BeaconState<T::EthSpec>,
+        base_state_root: Hash256,
+        slot: Slot,
+        config: StateSkipConfig,
+    ) -> Result<BeaconState<T::EthSpec>, Error> {
+        let start_slot = state.slot();
+        let task_start = Instant::now();
+        let max_task_runtime = Duration::from_secs(self.spec.seconds_per_slot);
+
+        let skip_state_root = match config {
+            StateSkipConfig::WithStateRoots => None,
+            StateSkipConfig::WithoutStateRoots => Some(Hash256::zero()),
+        };
+        let without_state_roots = skip_state_root.is_some();
+
+        if let Some(partial_state) =
+            self.take_partial_state_skip(base_state_root, without_state_roots, slot)
+        {
+            state = partial_state;
+        }
+
+        while state.slot() < slot {
+            // Do not allow and forward state skip that takes longer than the maximum task duration.
+            //
+            // This is a protection against nodes doing too much work when they're not synced
+            // to a chain.
+            if task_start + max_task_runtime < Instant::now() {
+                self.retain_partial_state_skip(base_state_root, without_state_roots, state);
+                return Err(Error::StateSkipTooLarge {
+                    start_slot,
+                    requested_slot: slot,
+                    max_task_runtime,
+                });
+            }
+
+            let next_slot = state.slot() + 1;
+
+            // Note: supplying some `state_root` when it is known would be a cheap and easy
+            // optimization.
+            if let Err(e) = per_slot_processing(&mut state, skip_state_root, &self.spec) {
+                warn!(
+                    self.log,
+                    "Unable to advance state to slot";
+                    "error" => ?e,
+                    "failed_slot" => next_slot,
+                    "start_slot" => start_slot,
+                    "requested_slot" => slot
+                );
+                // Epoch processing and fork upgrades run upon the transition to the first slot of an
+                // epoch, and may have left the state part way through them.
+                if next_slot % T::EthSpec::slots_per_epoch() != 0 {
+                    self.retain_partial_state_skip(base_state_root, without_state_roots, state);
+                }
+                return Err(Error::StateAdvanceFailed {
+                    requested_slot: slot,
+                    failed_slot: next_slot,
+                    error: e,
+                });
+            }
+        }
+
+        Ok(state)
+    }
+
+    /// Take the progress of a failed skip from the state with `base_state_root` which may be
+    /// resumed to reach `slot`.
+    ///
+    /// Progress beyond `slot` is left in place for a later request.
+    fn take_partial_state_skip(
+        &self,
+        base_state_root: Hash256,
+        without_state_roots: bool,
+        slot: Slot,
+    ) -> Option<BeaconState<T::EthSpec>> {
+        let mut partial_state_skip = self.partial_state_skip.lock();
+        let partial = partial_state_skip.take()?;
+        if partial.base_state_root != base_state_root
+            || partial.without_state_roots != without_state_roots
+        {
+            None
+        } else if partial.state.slot() > slot {
+            *partial_state_skip = Some(partial);
+            None
+        } else {
+            Some(partial.state)
+        }
+    }
+
+    /// Returns the slot reached by the most recent failed skip, if its progress is retained.
+    pub fn partial_state_skip_slot(&self) -> Option<Slot> {
+        self.partial_state_skip
+            .lock()
+            .as_ref()
+            .map(|partial| partial.state.slot())
+    }
+
+    /// Drop the progress of a failed skip, unless it started from the state with
+    /// `head_state_root`.
+    ///
+    /// Skips start from the head state, so progress from any other state is no longer needed once
+    /// the head changes.
+    pub(crate) fn prune_partial_state_skip(&self, head_state_root: Hash256) {
+        let mut partial_state_skip = self.partial_state_skip.lock();
+        if partial_state_skip
+            .as_ref()
+            .map_or(false, |partial| partial.base_state_root != head_state_root)
+        {
+            *partial_state_skip = None;
+        }
+    }
+
+    fn retain_partial_state_skip(
+        &self,
+        base_state_root: Hash256,
+        without_state_roots: bool,
+        state: BeaconState<T::EthSpec>,
+    ) {
+        *self.partial_state_skip.lock() = Some(PartialStateSkip {
+            base_state_root,
+            without_state_roots,
+            state,
+        });
+    }
+}
@@ -0,0 +1,114 @@
+//! Deletes the execution payloads of finalized blocks.
+//!
+//! Blocks are stored blinded, with their payloads stored separately. The database returns blocks
+//! prior to the split slot in blinded form regardless, and `BeaconChain::get_block` reconstructs
+//! their payloads from the execution layer, so the payloads of those blocks can be deleted without
+//! changing what is served. Payloads of blocks at or after the split slot are never deleted.
+use crate::{metrics, BeaconChain, BeaconChainError, BeaconChainTypes};
+use slog::{debug, info, Logger};
+use std::cmp;
+use std::mem;
+use store::{DBColumn, HotColdDB, ItemStore, StoreOp};
+use types::{EthSpec, Hash256, Slot};
+
+/// The number of payloads deleted in each database write.
+const PAYLOAD_PRUNING_BATCH_SIZE: usize = 1024;
+
+/// Delete the execution payloads of the blocks with the given `block_roots`, which may be in
+/// either order and may repeat the roots of skipped slots.
+///
+/// Only the payloads of blocks prior to the split slot are deleted. Returns the number of payloads
+/// deleted.
+pub fn prune_execution_payloads<E, Hot, Cold, Err>(
+    store: &HotColdDB<E, Hot, Cold>,
+    block_roots: impl Iterator<Item = Result<(Hash256, Slot), Err>>,
+    log: &Logger,
+) -> Result<usize, Err>
+where
+    E: EthSpec,
+    Hot: ItemStore<E>,
+    Cold: ItemStore<E>,
+    Err: From<store::Error>,
+{
+    let split_slot = store.get_split_slot();
+    let bellatrix_fork_slot = match store.get_chain_spec().bellatrix_fork_epoch {
+        Some(epoch) => epoch.start_slot(E::slots_per_epoch()),
+        None => return Ok(0),
+    };
+
+    let mut pruned = 0;
+    let mut batch = vec![];
+    let mut prev_block_root = None;
+    for result in block_roots {
+        let (block_root, slot) = result?;
+        // The roots of skipped slots are those of earlier blocks, so the block is prior to the
+        // split slot if `slot` is.
+        if slot < bellatrix_fork_slot || slot >= split_slot || prev_block_root == Some(block_root) {
+            continue;
+        }
+        prev_block_root = Some(block_root);
+
+        if store
+            .hot_db
+            .key_exists(DBColumn::ExecPayload.into(), block_root.as_bytes())?
+        {
+            batch.push(StoreOp::DeleteExecutionPayload(block_root));
+        }
+
+        if batch.len() >= PAYLOAD_PRUNING_BATCH_SIZE {
+            pruned += batch.len();
+            store.do_atomically(mem::take(&mut batch))?;
+            info!(
+                log,
+                "Pruning execution payloads";
+                "pruned" => pruned,
+                "slot" => slot,
+            );
+        }
+    }
+    pruned += batch.len();
+    store.do_atomically(batch)?;
+
+    metrics::inc_counter_by(&metrics::PRUNED_EXECUTION_PAYLOADS, pruned as u64);
+    debug!(
+        log,
+        "Pruned execution payloads";
+        "pruned" => pruned,
+        "split_slot" => split_slot,
+    );
+    Ok(pruned)
+}
+
+impl<T: BeaconChainTypes> BeaconChain<T> {
+    /// Delete the execution payloads of the canonical blocks prior to `before_slot`, or prior to
+    /// the split slot if that is earlier, returning the number of payloads deleted.
+    ///
+    /// This allows the payloads of blocks finalized before `ChainConfig::prune_payloads` was
+    /// enabled to be pruned.
+    pub fn prune_payloads_manually(&self, before_slot: Slot) -> Result<usize, BeaconChainError> {
+        let bellatrix_fork_slot = match self.spec.bellatrix_fork_epoch {
+            Some(epoch) => epoch.start_slot(T::EthSpec::slots_per_epoch()),
+            None => return Ok(0),
+        };
+        let start_slot = cmp::max(bellatrix_fork_slot, self.store.get_oldest_block_slot());
+        let end_slot = cmp::min(before_slot, self.store.get_split_slot());
+        if end_slot <= start_slot {
+            return Ok(0);
+        }
+
+        info!(
+            self.log,
+            "Pruning execution payloads";
+            "start_slot" => start_slot,
+            "before_slot" => end_slot,
+        );
+        let block_roots = self.forwards_iter_block_roots_until(start_slot, end_slot - 1)?;
+        let pruned = prune_execution_payloads(&self.store, block_roots, &self.log)?;
+        info!(
+            self.log,
+            "Execution payload pruning complete";
+            "pruned" => pruned,
+        );
+        Ok(pruned)
+    }
+}
@@ -0,0 +1,162 @@
+//! Reconstructs the execution payloads of blinded blocks from the execution layer.
+//!
+//! A reconstructed payload must match the payload header of the stored block. Execution clients
+//! have been known to return an inconsistent payload transiently, so the payload is requested a
+//! second time before failing. Each inconsistent payload is recorded, whether or not the retry
+//! succeeds, so that faults of the execution layer are visible without trawling the logs.
+use crate::{metrics, BeaconChain, BeaconChainError as Error, BeaconChainTypes};
+use parking_lot::Mutex;
+use serde_derive::{Deserialize, Serialize};
+use slog::{debug, warn};
+use std::collections::VecDeque;
+use tree_hash::TreeHash;
+use types::{
+    ExecutionBlockHash, ExecutionPayload, ExecutionPayloadHeader, Hash256,
+    SignedBlindedBeaconBlock, Slot,
+};
+
+/// The maximum number of failures to retain.
+pub const PAYLOAD_RECONSTRUCTION_FAILURES_CAPACITY: usize = 32;
+
+/// A payload from the execution layer which did not match the header of its block.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PayloadReconstructionFailure {
+    pub slot: Slot,
+    pub block_root: Hash256,
+    pub exec_block_hash: ExecutionBlockHash,
+    /// Whether a consistent payload was returned when the execution layer was re-queried.
+    pub recovered: bool,
+}
+
+#[derive(Default)]
+pub struct PayloadReconstructionFailures {
+    failures: Mutex<VecDeque<PayloadReconstructionFailure>>,
+}
+
+impl PayloadReconstructionFailures {
+    /// Record `failure`, evicting the oldest failure if full.
+    pub fn insert(&self, failure: PayloadReconstructionFailure) {
+        let mut failures = self.failures.lock();
+        if failures.len() >= PAYLOAD_RECONSTRUCTION_FAILURES_CAPACITY {
+            failures.pop_front();
+        }
+        failures.push_back(failure);
+    }
+
+    /// Returns the retained failures, oldest first.
+    pub fn to_vec(&self) -> Vec<PayloadReconstructionFailure> {
+        self.failures.lock().iter().cloned().collect()
+    }
+}
+
+impl<T: BeaconChainTypes> BeaconChain<T> {
+    /// Returns the most recent payloads from the execution layer which did not match the headers
+    /// of their blocks, oldest first.
+    pub fn payload_reconstruction_failures(&self) -> Vec<PayloadReconstructionFailure> {
+        self.payload_reconstruction_failures.to_vec()
+    }
+
+    /// Load the execution payload of `blinded_block` from the execution layer, checking that it
+    /// matches the block's payload header.
+    pub(crate) async fn reconstruct_payload(
+        &self,
+        block_root: Hash256,
+        blinded_block: &SignedBlindedBeaconBlock<T::EthSpec>,
+    ) -> Result<ExecutionPayload<T::EthSpec>, Error> {
+        let execution_payload_header = &blinded_block
+            .message()
+            .execution_payload()
+            .map_err(|_| Error::BlockVariantLacksExecutionPayload(block_root))?
+            .execution_payload_header;
+        let exec_block_hash = execution_payload_header.block_hash;
+
+        let execution_payload = self.get_payload_by_block_hash(exec_block_hash).await?;
+        let header_from_payload = ExecutionPayloadHeader::from(&execution_payload);
+        if header_from_payload == *execution_payload_header {
+            return Ok(execution_payload);
+        }
+
+        metrics::inc_counter(&metrics::PAYLOAD_RECONSTRUCTION_FAILURES);
+        warn!(
+            self.log,
+            "Inconsistent payload from execution layer";
+            "info" => "re-querying the execution layer",
+            "exec_block_hash" => ?exec_block_hash,
+            "block_root" => ?block_root,
+            "slot" => blinded_block.slot(),
+        );
+
+        let execution_payload = self.get_payload_by_block_hash(exec_block_hash).await?;
+        let header_from_payload = ExecutionPayloadHeader::from(&execution_payload);
+        let recovered = header_from_payload == *execution_payload_header;
+        self.payload_reconstruction_failures
+            .insert(PayloadReconstructionFailure {
+                slot: blinded_block.slot(),
+                block_root,
+                exec_block_hash,
+                recovered,
+            });
+        if recovered {
+            return Ok(execution_payload);
+        }
+
+        if self.config.log_inconsistent_payload_transactions {
+            for txn in &execution_payload.transactions {
+                debug!(
+                    self.log,
+                    "Reconstructed txn";
+                    "bytes" => format!("0x{}", hex::encode(&**txn)),
+                );
+            }
+        }
+
+        Err(Error::InconsistentPayloadReconstructed {
+            slot: blinded_block.slot(),
+            exec_block_hash,
+            canonical_payload_root: execution_payload_header.tree_hash_root(),
+            reconstructed_payload_root: header_from_payload.tree_hash_root(),
+            canonical_transactions_root: execution_payload_header.transactions_root,
+            reconstructed_transactions_root: header_from_payload.transactions_root,
+        })
+    }
+
+    async fn get_payload_by_block_hash(
+        &self,
+        exec_block_hash: ExecutionBlockHash,
+    ) -> Result<ExecutionPayload<T::EthSpec>, Error> {
+        self.execution_layer
+            .as_ref()
+            .ok_or(Error::ExecutionLayerMissing)?
+            .get_payload_by_block_hash(exec_block_hash)
+            .await
+            .map_err(|e| Error::ExecutionLayerErrorPayloadReconstruction(exec_block_hash, e))?
+            .ok_or(Error::BlockHashMissingFromExecutionLayer(exec_block_hash))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn failure(slot: u64) -> PayloadReconstructionFailure {
+        PayloadReconstructionFailure {
+            slot: Slot::new(slot),
+            block_root: Hash256::from_low_u64_be(slot),
+            exec_block_hash: ExecutionBlockHash::zero(),
+            recovered: false,
+        }
+    }
+
+    #[test]
+    fn oldest_failures_are_evicted() {
+        let failures = PayloadReconstructionFailures::default();
+        let count = PAYLOAD_RECONSTRUCTION_FAILURES_CAPACITY as u64 + 2;
+        for slot in 0..count {
+            failures.insert(failure(slot));
+        }
+        assert_eq!(
+            failures.to_vec(),
+            (2..count).map(failure).collect::<Vec<_>>()
+        );
+    }
+}
@@ -1,12 +1,12 @@
-use crate::{BeaconChain, BeaconChainError, BeaconChainTypes};
+use crate::chain_config::DEFAULT_PRE_FINALIZATION_BLOCK_CACHE_SIZE;
+use crate::{metrics, BeaconChain, BeaconChainError, BeaconChainTypes};
 use itertools::process_results;
 use lru::LruCache;
 use parking_lot::Mutex;
 use slog::debug;
 use std::time::Duration;
-use types::Hash256;
+use types::{EthSpec, Hash256, Slot};
 
-const BLOCK_ROOT_CACHE_LIMIT: usize = 512;
 const LOOKUP_LIMIT: usize = 8;
 const METRICS_TIMEOUT: Duration = Duration::from_millis(100);
 
@@ -14,24 +14,49 @@ const METRICS_TIMEOUT: Duration = Duration::from_millis(100);
 ///
 /// It stores a collection of block roots that are pre-finalization and therefore not known to fork
 /// choice in `verify_head_block_is_known` during attestation processing.
-#[derive(Default)]
 pub struct PreFinalizationBlockCache {
     cache: Mutex<Cache>,
 }
 
+/// Statistics about the use of the `PreFinalizationBlockCache` since it was created.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct PreFinalizationCacheStats {
+    /// Number of block roots which were answered from the cache, avoiding a database lookup.
+    pub hits: u64,
+    /// Number of block roots which had to be looked up in memory or the database.
+    pub misses: u64,
+    /// Number of block roots added to the cache.
+    pub inserts: u64,
+    /// Number of block roots and lookups removed from the cache after finalization.
+    pub pruned: u64,
+    /// Number of pre-finalization block roots currently cached.
+    pub block_roots: usize,
+    /// Number of block roots currently subject to single block lookups.
+    pub in_progress_lookups: usize,
+}
+
 struct Cache {
-    /// Set of block roots that are known to be pre-finalization.
-    block_roots: LruCache<Hash256, ()>,
-    /// Set of block roots that are the subject of single block lookups.
-    in_progress_lookups: LruCache<Hash256, ()>,
+    /// Block roots that are known to be pre-finalization, with the slots of their blocks.
+    block_roots: LruCache<Hash256, Slot>,
+    /// Block roots that are the subject of single block lookups, with the slot at which each
+    /// lookup started.
+    in_progress_lookups: LruCache<Hash256, Slot>,
+    /// The finalized slot when the cache was last pruned.
+    finalized_slot: Slot,
+    stats: PreFinalizationCacheStats,
 }
 
-impl Default for Cache {
+impl Cache {
+    fn insert(&mut self, block_root: Hash256, block_slot: Slot) {
+        self.block_roots.put(block_root, block_slot);
+        self.stats.inserts += 1;
+        metrics::inc_counter(&metrics::PRE_FINALIZATION_BLOCK_CACHE_INSERTS);
+    }
+}
+
+impl Default for PreFinalizationBlockCache {
     fn default() -> Self {
-        Cache {
-            block_roots: LruCache::new(BLOCK_ROOT_CACHE_LIMIT),
-            in_progress_lookups: LruCache::new(LOOKUP_LIMIT),
-        }
+        Self::new(DEFAULT_PRE_FINALIZATION_BLOCK_CACHE_SIZE)
     }
 }
 
@@ -48,36 +73,47 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
 
         // Check the cache to see if we already know this pre-finalization block root.
         if cache.block_roots.contains(&block_root) {
+            cache.stats.hits += 1;
+            metrics::inc_counter(&metrics::PRE_FINALIZATION_BLOCK_CACHE_HITS);
             return Ok(true);
         }
 
         // Avoid repeating the disk lookup for blocks that are already subject to a network lookup.
         // Sync will take care of de-duplicating the single block lookups.
         if cache.in_progress_lookups.contains(&block_root) {
+            cache.stats.hits += 1;
+            metrics::inc_counter(&metrics::PRE_FINALIZATION_BLOCK_CACHE_HITS);
             return Ok(false);
         }
 
+        cache.stats.misses += 1;
+        metrics::inc_counter(&metrics::PRE_FINALIZATION_BLOCK_CACHE_MISSES);
+
         // 1. Check memory for a recent pre-finalization block.
-        let is_recent_finalized_block = self.with_head(|head| {
+        let recent_finalized_block_slot = self.with_head(|head| {
             process_results(
                 head.beacon_state.rev_iter_block_roots(&self.spec),
-                |mut iter| iter.any(|(_, root)| root == block_root),
+                |mut iter| {
+                    iter.find(|(_, root)| *root == block_root)
+                        .map(|(slot, _)| slot)
+                },
             )
             .map_err(BeaconChainError::BeaconStateError)
         })?;
-        if is_recent_finalized_block {
-            cache.block_roots.put(block_root, ());
+        if let Some(block_slot) = recent_finalized_block_slot {
+            cache.insert(block_root, block_slot);
             return Ok(true);
         }
 
         // 2. Check on disk.
-        if self.store.get_blinded_block(&block_root)?.is_some() {
-            cache.block_roots.put(block_root, ());
+        if let Some(block) = self.store.get_blinded_block(&block_root)? {
+            cache.insert(block_root, block.slot());
             return Ok(true);
         }
 
         // 3. Check the network with a single block lookup.
-        cache.in_progress_lookups.put(block_root, ());
+        let current_slot = self.slot().unwrap_or_else(|_| self.spec.genesis_slot);
+        cache.in_progress_lookups.put(block_root, current_slot);
         if cache.in_progress_lookups.len() == LOOKUP_LIMIT {
             // NOTE: we expect this to occur sometimes if a lot of blocks that we look up fail to be
             // imported for reasons other than being pre-finalization. The cache will eventually
@@ -93,15 +129,44 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         Ok(false)
     }
 
-    pub fn pre_finalization_block_rejected(&self, block_root: Hash256) {
+    pub fn pre_finalization_block_rejected(&self, block_root: Hash256, block_slot: Slot) {
         // Future requests can know that this block is invalid without having to look it up again.
         let mut cache = self.pre_finalization_block_cache.cache.lock();
         cache.in_progress_lookups.pop(&block_root);
-        cache.block_roots.put(block_root, ());
+        cache.insert(block_root, block_slot);
+    }
+
+    /// Prune the pre-finalization block cache if the finalized checkpoint has advanced since it
+    /// was last pruned.
+    pub fn prune_pre_finalization_block_cache(&self) {
+        let finalized_slot = self
+            .canonical_head
+            .cached_head()
+            .finalized_checkpoint()
+            .epoch
+            .start_slot(T::EthSpec::slots_per_epoch());
+        self.pre_finalization_block_cache.prune(finalized_slot);
+    }
+
+    /// Returns statistics about the use of the pre-finalization block cache.
+    pub fn pre_finalization_cache_stats(&self) -> PreFinalizationCacheStats {
+        self.pre_finalization_block_cache.stats()
     }
 }
 
 impl PreFinalizationBlockCache {
+    /// Create a cache which holds at most `capacity` pre-finalization block roots.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            cache: Mutex::new(Cache {
+                block_roots: LruCache::new(capacity),
+                in_progress_lookups: LruCache::new(LOOKUP_LIMIT),
+                finalized_slot: Slot::new(0),
+                stats: PreFinalizationCacheStats::default(),
+            }),
+        }
+    }
+
     pub fn block_processed(&self, block_root: Hash256) {
         // Future requests will find this block in fork choice, so no need to cache it in the
         // ongoing lookup cache any longer.
@@ -112,8 +177,60 @@ impl PreFinalizationBlockCache {
         self.cache.lock().block_roots.contains(&block_root)
     }
 
+    /// Returns the slot of the block with `block_root` if it is known to be pre-finalization.
+    pub fn block_slot(&self, block_root: Hash256) -> Option<Slot> {
+        self.cache.lock().block_roots.peek(&block_root).copied()
+    }
+
     pub fn metrics(&self) -> Option<(usize, usize)> {
         let cache = self.cache.try_lock_for(METRICS_TIMEOUT)?;
         Some((cache.block_roots.len(), cache.in_progress_lookups.len()))
     }
+
+    pub fn stats(&self) -> PreFinalizationCacheStats {
+        let cache = self.cache.lock();
+        PreFinalizationCacheStats {
+            block_roots: cache.block_roots.len(),
+            in_progress_lookups: cache.in_progress_lookups.len(),
+            ..cache.stats
+        }
+    }
+
+    /// Prune the cache once the finalized checkpoint has advanced to `finalized_slot`.
+    ///
+    /// Block roots from before the *previous* finalized slot are removed, since attestations to
+    /// them can only come from nodes which are more than one finalization behind. Lookups which
+    /// started before `finalized_slot` are also removed: their blocks either failed to import
+    /// or are now from before finalization, so `block_processed` will never be called for them.
+    pub fn prune(&self, finalized_slot: Slot) {
+        let mut cache = self.cache.lock();
+        if finalized_slot <= cache.finalized_slot {
+            return;
+        }
+        let previous_finalized_slot = std::mem::replace(&mut cache.finalized_slot, finalized_slot);
+
+        let stale_block_roots = cache
+            .block_roots
+            .iter()
+            .filter(|(_, block_slot)| **block_slot < previous_finalized_slot)
+            .map(|(block_root, _)| *block_root)
+            .collect::<Vec<_>>();
+        let stale_lookups = cache
+            .in_progress_lookups
+            .iter()
+            .filter(|(_, lookup_slot)| **lookup_slot < finalized_slot)
+            .map(|(block_root, _)| *block_root)
+            .collect::<Vec<_>>();
+
+        for block_root in &stale_block_roots {
+            cache.block_roots.pop(block_root);
+        }
+        for block_root in &stale_lookups {
+            cache.in_progress_lookups.pop(block_root);
+        }
+
+        let pruned = (stale_block_roots.len() + stale_lookups.len()) as u64;
+        cache.stats.pruned += pruned;
+        metrics::inc_counter_by(&metrics::PRE_FINALIZATION_BLOCK_CACHE_PRUNED, pruned);
+    }
 }
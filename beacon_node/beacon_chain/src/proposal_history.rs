@@ -0,0 +1,580 @@
+//! Keeps a record of the blocks produced by this node and the source of their execution payloads.
+//!
+//! This allows users running with a builder to audit which of their proposals actually used a
+//! builder payload, and which fell back to the local execution engine.
+//!
+//! Records are created during block production (i.e., before the block is signed or published),
+//! so a record will exist even if the block is never published or is later orphaned.
+use crate::builder_bid_history::BuilderBidRecord;
+use crate::{metrics, BeaconChain, BeaconChainError, BeaconChainTypes, ChainConfig};
+use execution_layer::{ChainHealth, FailedCondition, PayloadSource};
+use serde_derive::{Deserialize, Serialize};
+use slog::warn;
+use ssz::{Decode, Encode};
+use ssz_derive::{Decode, Encode};
+use std::collections::VecDeque;
+use store::{DBColumn, Error as StoreError, StoreItem};
+use types::{Epoch, EthSpec, Hash256, Slot, Uint256};
+
+/// The maximum number of proposals that are retained.
+pub const PROPOSAL_HISTORY_CAPACITY: usize = 64;
+
+/// A proposal which has not been imported this many slots after its slot is considered missed.
+pub const MISSED_PROPOSAL_SLOT_TOLERANCE: u64 = 2;
+
+/// Missed proposals which used a builder payload are only considered when deciding whether to use
+/// the builder if they occurred within this many epochs.
+pub const BUILDER_FAULT_LOOKBACK_EPOCHS: u64 = 32;
+
+/// 32-byte key for accessing the `ProposalHistory`. All zero because it has its own column.
+pub const PROPOSAL_HISTORY_DB_KEY: Hash256 = Hash256::zero();
+
+/// The source of the execution payload of a proposal.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProposalPayloadSource {
+    Builder,
+    Local,
+}
+
+/// What is known to have happened to a proposal after it was produced.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProposalOutcome {
+    /// The block was produced, but has not (yet) been imported by this node.
+    Produced,
+    /// The block was imported by this node. Since blocks published via the HTTP API are imported
+    /// immediately, this indicates that the block was published.
+    Published,
+    /// No block produced for this slot was imported within `MISSED_PROPOSAL_SLOT_TOLERANCE` slots.
+    Missed,
+    /// The block was signed, however the builder failed to reveal its payload.
+    Withheld,
+}
+
+/// A record of a single block produced by this node.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProposalRecord {
+    pub slot: Slot,
+    pub block_root: Hash256,
+    #[serde(with = "eth2_serde_utils::quoted_u64")]
+    pub proposer_index: u64,
+    /// `true` if a blinded block was produced.
+    pub blinded: bool,
+    pub payload_source: ProposalPayloadSource,
+    /// The (redacted) URL of the builder which provided the payload.
+    pub relay: Option<String>,
+    /// The value of the bid that was accepted from the builder, in wei.
+    pub builder_bid_value: Option<Uint256>,
+    /// The value of the locally built payload, in wei.
+    ///
+    /// `engine_getPayloadV1` does not report the value of a payload, so this is only known when
+    /// the execution engine provides it.
+    pub local_value: Option<Uint256>,
+    pub outcome: ProposalOutcome,
+}
+
+impl ProposalRecord {
+    /// Create a new record for a block which has just been produced.
+    pub fn new(
+        slot: Slot,
+        block_root: Hash256,
+        proposer_index: u64,
+        blinded: bool,
+        source: PayloadSource,
+    ) -> Self {
+        let (payload_source, relay, builder_bid_value) = match source {
+            PayloadSource::Builder { relay, bid } => {
+                (ProposalPayloadSource::Builder, Some(relay), Some(bid.value))
+            }
+            PayloadSource::Local => (ProposalPayloadSource::Local, None, None),
+        };
+
+        Self {
+            slot,
+            block_root,
+            proposer_index,
+            blinded,
+            payload_source,
+            relay,
+            builder_bid_value,
+            local_value: None,
+            outcome: ProposalOutcome::Produced,
+        }
+    }
+}
+
+/// A fixed-size ring buffer of the most recent `ProposalRecord`s.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ProposalHistory {
+    records: VecDeque<ProposalRecord>,
+}
+
+impl ProposalHistory {
+    /// Add `record` to the history, evicting the oldest record if the history is full.
+    pub fn insert(&mut self, record: ProposalRecord) {
+        if self.records.len() >= PROPOSAL_HISTORY_CAPACITY {
+            self.records.pop_front();
+        }
+        self.records.push_back(record);
+    }
+
+    /// Mark the proposal with `block_root` as published. Returns `false` if there is no such
+    /// proposal in the history.
+    pub fn mark_published(&mut self, block_root: Hash256) -> bool {
+        let mut found = false;
+        for record in self
+            .records
+            .iter_mut()
+            .filter(|record| record.block_root == block_root)
+        {
+            record.outcome = ProposalOutcome::Published;
+            found = true;
+        }
+        found
+    }
+
+    /// Mark the proposal with `block_root` as having had its payload withheld by the builder.
+    pub fn mark_withheld(&mut self, block_root: Hash256) {
+        for record in self
+            .records
+            .iter_mut()
+            .filter(|record| record.block_root == block_root)
+        {
+            record.outcome = ProposalOutcome::Withheld;
+        }
+    }
+
+    /// Mark any proposals at slots prior to `current_slot - MISSED_PROPOSAL_SLOT_TOLERANCE` as
+    /// missed, if no block was published for that slot.
+    ///
+    /// Returns the number of slots that were newly marked as missed.
+    pub fn mark_missed(&mut self, current_slot: Slot) -> usize {
+        let mut missed_slots = vec![];
+        for record in &self.records {
+            if record.outcome == ProposalOutcome::Produced
+                && record.slot + MISSED_PROPOSAL_SLOT_TOLERANCE < current_slot
+                && !self.records.iter().any(|other| {
+                    other.slot == record.slot && other.outcome == ProposalOutcome::Published
+                })
+                && !missed_slots.contains(&record.slot)
+            {
+                missed_slots.push(record.slot);
+            }
+        }
+
+        for record in self
+            .records
+            .iter_mut()
+            .filter(|record| missed_slots.contains(&record.slot))
+        {
+            record.outcome = ProposalOutcome::Missed;
+        }
+
+        missed_slots.len()
+    }
+
+    /// Returns the number of slots at or after `start_slot` for which a proposal using a builder
+    /// payload was missed or had its payload withheld.
+    pub fn builder_faults_since(&self, start_slot: Slot) -> usize {
+        let mut fault_slots = self
+            .records
+            .iter()
+            .filter(|record| {
+                record.slot >= start_slot
+                    && record.payload_source == ProposalPayloadSource::Builder
+                    && matches!(
+                        record.outcome,
+                        ProposalOutcome::Missed | ProposalOutcome::Withheld
+                    )
+            })
+            .map(|record| record.slot)
+            .collect::<Vec<_>>();
+        fault_slots.sort_unstable();
+        fault_slots.dedup();
+        fault_slots.len()
+    }
+
+    /// Returns all records, oldest first.
+    pub fn iter(&self) -> impl Iterator<Item = &ProposalRecord> {
+        self.records.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+}
+
+impl<T: BeaconChainTypes> BeaconChain<T> {
+    /// Returns the most recent blocks produced by this node, oldest first.
+    pub fn recent_proposals(&self) -> Vec<ProposalRecord> {
+        self.proposal_history.read().iter().cloned().collect()
+    }
+
+    /// Record that a block has been produced with an execution payload from `source`.
+    pub(crate) fn record_proposal(
+        &self,
+        slot: Slot,
+        block_root: Hash256,
+        proposer_index: u64,
+        blinded: bool,
+        source: PayloadSource,
+    ) {
+        if let PayloadSource::Builder { relay, bid } = &source {
+            self.record_builder_bid(BuilderBidRecord {
+                slot,
+                block_root,
+                relay: relay.clone(),
+                bid: bid.clone(),
+            });
+        }
+
+        let record = ProposalRecord::new(slot, block_root, proposer_index, blinded, source);
+        let label = match record.payload_source {
+            ProposalPayloadSource::Builder => "builder",
+            ProposalPayloadSource::Local => "local",
+        };
+        metrics::inc_counter_vec(&metrics::PROPOSAL_PAYLOAD_SOURCE_TOTAL, &[label]);
+        self.proposal_history.write().insert(record);
+    }
+
+    /// Update the outcome of any proposals which have been missed as of `current_slot`.
+    pub(crate) fn update_proposal_outcomes(&self, current_slot: Slot) {
+        let missed = self.proposal_history.write().mark_missed(current_slot);
+        if missed > 0 {
+            warn!(
+                self.log,
+                "Produced block was not published";
+                "msg" => "check the validator client logs for errors during block proposal",
+                "missed_slots" => missed,
+                "current_slot" => current_slot,
+            );
+            metrics::inc_counter_by(&metrics::PROPOSALS_MISSED_TOTAL, missed as u64);
+        }
+    }
+
+    /// Determine whether the chain is healthy enough to use the builder for a proposal at `slot`.
+    pub fn builder_chain_health(&self, slot: Slot, finalized_epoch: Epoch) -> ChainHealth {
+        let slots_per_epoch = T::EthSpec::slots_per_epoch();
+        let lookback_start = slot.saturating_sub(BUILDER_FAULT_LOOKBACK_EPOCHS * slots_per_epoch);
+        let builder_faults = self
+            .proposal_history
+            .read()
+            .builder_faults_since(lookback_start);
+        let epochs_since_finality = slot
+            .epoch(slots_per_epoch)
+            .saturating_sub(finalized_epoch)
+            .as_u64();
+
+        builder_chain_health(&self.config, builder_faults, epochs_since_finality)
+    }
+
+    /// Persists the proposal history to disk.
+    pub fn persist_proposal_history(&self) -> Result<(), BeaconChainError> {
+        if self.config.read_only {
+            return Ok(());
+        }
+
+        let persisted = PersistedProposalHistory::from(&*self.proposal_history.read());
+        self.store
+            .put_item(&PROPOSAL_HISTORY_DB_KEY, &persisted)
+            .map_err(Into::into)
+    }
+}
+
+/// Returns `ChainHealth::Unhealthy` if either of the builder fallback conditions in `config` have
+/// been met.
+fn builder_chain_health(
+    config: &ChainConfig,
+    builder_faults: usize,
+    epochs_since_finality: u64,
+) -> ChainHealth {
+    if builder_faults > config.builder_fallback_skips {
+        ChainHealth::Unhealthy(FailedCondition::Skips)
+    } else if epochs_since_finality > config.builder_fallback_epochs_since_finality {
+        ChainHealth::Unhealthy(FailedCondition::EpochsSinceFinality)
+    } else {
+        ChainHealth::Healthy
+    }
+}
+
+/// SSZ-encodable form of a `ProposalRecord`.
+#[derive(Encode, Decode)]
+struct SszProposalRecord {
+    slot: Slot,
+    block_root: Hash256,
+    proposer_index: u64,
+    blinded: bool,
+    payload_source: u8,
+    relay: Vec<u8>,
+    builder_bid_value: Vec<Uint256>,
+    local_value: Vec<Uint256>,
+    outcome: u8,
+}
+
+impl From<&ProposalRecord> for SszProposalRecord {
+    fn from(record: &ProposalRecord) -> Self {
+        Self {
+            slot: record.slot,
+            block_root: record.block_root,
+            proposer_index: record.proposer_index,
+            blinded: record.blinded,
+            payload_source: match record.payload_source {
+                ProposalPayloadSource::Builder => 0,
+                ProposalPayloadSource::Local => 1,
+            },
+            relay: record
+                .relay
+                .as_ref()
+                .map(|relay| relay.as_bytes().to_vec())
+                .unwrap_or_default(),
+            builder_bid_value: record.builder_bid_value.into_iter().collect(),
+            local_value: record.local_value.into_iter().collect(),
+            outcome: match record.outcome {
+                ProposalOutcome::Produced => 0,
+                ProposalOutcome::Published => 1,
+                ProposalOutcome::Missed => 2,
+                ProposalOutcome::Withheld => 3,
+            },
+        }
+    }
+}
+
+impl TryFrom<SszProposalRecord> for ProposalRecord {
+    type Error = String;
+
+    fn try_from(record: SszProposalRecord) -> Result<Self, String> {
+        let payload_source = match record.payload_source {
+            0 => ProposalPayloadSource::Builder,
+            1 => ProposalPayloadSource::Local,
+            other => return Err(format!("invalid payload source: {}", other)),
+        };
+        let relay = if record.relay.is_empty() {
+            None
+        } else {
+            Some(String::from_utf8(record.relay).map_err(|e| format!("invalid relay: {}", e))?)
+        };
+        let outcome = match record.outcome {
+            0 => ProposalOutcome::Produced,
+            1 => ProposalOutcome::Published,
+            2 => ProposalOutcome::Missed,
+            3 => ProposalOutcome::Withheld,
+            other => return Err(format!("invalid outcome: {}", other)),
+        };
+
+        Ok(Self {
+            slot: record.slot,
+            block_root: record.block_root,
+            proposer_index: record.proposer_index,
+            blinded: record.blinded,
+            payload_source,
+            relay,
+            builder_bid_value: record.builder_bid_value.first().copied(),
+            local_value: record.local_value.first().copied(),
+            outcome,
+        })
+    }
+}
+
+/// Wrapper around the `ProposalHistory` for persistence to disk.
+#[derive(Encode, Decode)]
+pub struct PersistedProposalHistory {
+    records: Vec<SszProposalRecord>,
+}
+
+impl From<&ProposalHistory> for PersistedProposalHistory {
+    fn from(history: &ProposalHistory) -> Self {
+        Self {
+            records: history.iter().map(SszProposalRecord::from).collect(),
+        }
+    }
+}
+
+impl TryFrom<PersistedProposalHistory> for ProposalHistory {
+    type Error = String;
+
+    fn try_from(persisted: PersistedProposalHistory) -> Result<Self, String> {
+        let mut history = ProposalHistory::default();
+        for record in persisted.records {
+            history.insert(record.try_into()?);
+        }
+        Ok(history)
+    }
+}
+
+impl StoreItem for PersistedProposalHistory {
+    fn db_column() -> DBColumn {
+        DBColumn::ProposalHistory
+    }
+
+    fn as_store_bytes(&self) -> Vec<u8> {
+        self.as_ssz_bytes()
+    }
+
+    fn from_store_bytes(bytes: &[u8]) -> Result<Self, StoreError> {
+        Self::from_ssz_bytes(bytes).map_err(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::builder_bid::BuilderBidEvidence;
+    use types::{ExecutionBlockHash, PublicKeyBytes, Signature};
+
+    fn record(slot: u64, root: u64, source: PayloadSource) -> ProposalRecord {
+        ProposalRecord::new(
+            Slot::new(slot),
+            Hash256::from_low_u64_be(root),
+            0,
+            false,
+            source,
+        )
+    }
+
+    #[test]
+    fn ring_buffer_evicts_oldest() {
+        let mut history = ProposalHistory::default();
+        for i in 0..PROPOSAL_HISTORY_CAPACITY as u64 + 2 {
+            history.insert(record(i, i, PayloadSource::Local));
+        }
+        assert_eq!(history.len(), PROPOSAL_HISTORY_CAPACITY);
+        assert_eq!(history.iter().next().unwrap().slot, Slot::new(2));
+    }
+
+    #[test]
+    fn outcomes() {
+        let mut history = ProposalHistory::default();
+        history.insert(record(1, 1, PayloadSource::Local));
+        // Two blocks produced for slot 2, only one of which was published.
+        history.insert(record(2, 2, PayloadSource::Local));
+        history.insert(record(2, 3, PayloadSource::Local));
+        history.insert(record(3, 4, PayloadSource::Local));
+
+        assert!(history.mark_published(Hash256::from_low_u64_be(3)));
+        assert!(!history.mark_published(Hash256::from_low_u64_be(42)));
+
+        assert_eq!(history.mark_missed(Slot::new(3)), 0);
+        assert_eq!(history.mark_missed(Slot::new(6)), 2);
+        // Slots should only be counted as missed once.
+        assert_eq!(history.mark_missed(Slot::new(7)), 0);
+
+        let outcomes = history
+            .iter()
+            .map(|record| record.outcome)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            outcomes,
+            vec![
+                ProposalOutcome::Missed,
+                ProposalOutcome::Produced,
+                ProposalOutcome::Published,
+                ProposalOutcome::Missed,
+            ]
+        );
+    }
+
+    fn builder_source() -> PayloadSource {
+        PayloadSource::Builder {
+            relay: "http://relay.example.com/".into(),
+            bid: BuilderBidEvidence {
+                header_root: Hash256::zero(),
+                block_hash: ExecutionBlockHash::zero(),
+                value: Uint256::from(1_000_000_000_u64),
+                pubkey: PublicKeyBytes::empty(),
+                signature: Signature::empty(),
+                time_to_first_byte_ms: 0,
+                deserialization_ms: 0,
+            },
+        }
+    }
+
+    #[test]
+    fn builder_faults() {
+        let mut history = ProposalHistory::default();
+        // A blinded block and a full block produced at each slot.
+        for slot in 1..=4 {
+            history.insert(record(slot, slot * 2, builder_source()));
+            history.insert(record(slot, slot * 2 + 1, PayloadSource::Local));
+        }
+        history.mark_published(Hash256::from_low_u64_be(4));
+        history.mark_withheld(Hash256::from_low_u64_be(6));
+        history.mark_missed(Slot::new(10));
+
+        // Slot 2 was published, slot 3 was withheld and slots 1 and 4 were missed.
+        assert_eq!(history.builder_faults_since(Slot::new(0)), 3);
+        assert_eq!(history.builder_faults_since(Slot::new(2)), 2);
+        assert_eq!(history.builder_faults_since(Slot::new(5)), 0);
+
+        // Missed local proposals are not attributed to the builder.
+        history.insert(record(11, 42, PayloadSource::Local));
+        history.mark_missed(Slot::new(20));
+        assert_eq!(history.builder_faults_since(Slot::new(0)), 3);
+    }
+
+    #[test]
+    fn chain_health() {
+        let config = ChainConfig {
+            builder_fallback_skips: 2,
+            builder_fallback_epochs_since_finality: 3,
+            ..ChainConfig::default()
+        };
+
+        assert_eq!(builder_chain_health(&config, 0, 2), ChainHealth::Healthy);
+        assert_eq!(builder_chain_health(&config, 2, 3), ChainHealth::Healthy);
+        assert_eq!(
+            builder_chain_health(&config, 3, 2),
+            ChainHealth::Unhealthy(FailedCondition::Skips)
+        );
+        assert_eq!(
+            builder_chain_health(&config, 0, 4),
+            ChainHealth::Unhealthy(FailedCondition::EpochsSinceFinality)
+        );
+    }
+
+    #[test]
+    fn chain_health_zero_skips() {
+        let config = ChainConfig {
+            builder_fallback_skips: 0,
+            ..ChainConfig::default()
+        };
+
+        assert_eq!(builder_chain_health(&config, 0, 0), ChainHealth::Healthy);
+        assert_eq!(
+            builder_chain_health(&config, 1, 0),
+            ChainHealth::Unhealthy(FailedCondition::Skips)
+        );
+    }
+
+    #[test]
+    fn ssz_round_trip() {
+        let mut history = ProposalHistory::default();
+        history.insert(record(1, 1, PayloadSource::Local));
+        history.insert(record(2, 2, builder_source()));
+        history.mark_published(Hash256::from_low_u64_be(2));
+
+        let bytes = PersistedProposalHistory::from(&history).as_store_bytes();
+        let decoded: ProposalHistory = PersistedProposalHistory::from_store_bytes(&bytes)
+            .unwrap()
+            .try_into()
+            .unwrap();
+        assert_eq!(decoded, history);
+    }
+
+    #[test]
+    fn json_quotes_proposer_index() {
+        let mut record = record(1, 1, PayloadSource::Local);
+        record.proposer_index = 12;
+
+        let json = serde_json::to_value(&record).unwrap();
+        assert_eq!(json["proposer_index"], "12");
+        assert_eq!(
+            serde_json::from_value::<ProposalRecord>(json).unwrap(),
+            record
+        );
+    }
+}
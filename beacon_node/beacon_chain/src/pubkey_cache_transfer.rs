@@ -0,0 +1,196 @@
+//! Exports and imports the validator pubkey cache, so that a node may be bootstrapped from the cache
+//! of a healthy node on the same network rather than decompressing every public key itself.
+//!
+//! The export is an SSZ-encoded `PubkeyCacheHeader` followed by the public key of each validator, in
+//! order of validator index and in uncompressed form. Uncompressed keys are deserialized without
+//! the expensive decompression and subgroup checks, so an export must only be imported from a
+//! trusted node. The header guards against importing the cache of another network or a corrupted
+//! export, and a random sample of the imported keys is checked against the head state before any
+//! are written to the cache.
+use crate::beacon_chain::VALIDATOR_PUBKEY_CACHE_LOCK_TIMEOUT;
+use crate::{BeaconChain, BeaconChainError as Error, BeaconChainTypes};
+use bls::PUBLIC_KEY_UNCOMPRESSED_BYTES_LEN;
+use rand::seq::index::sample;
+use ssz::{Decode, Encode};
+use ssz_derive::{Decode, Encode};
+use std::io::{Read, Write};
+use types::{Hash256, PublicKey, PublicKeyBytes};
+
+/// The number of imported keys which are checked against the head state.
+pub const PUBKEY_CACHE_IMPORT_SAMPLE_SIZE: usize = 128;
+
+#[derive(Debug)]
+pub enum PubkeyCacheTransferError {
+    Io(std::io::Error),
+    InvalidHeader(ssz::DecodeError),
+    GenesisValidatorsRootMismatch {
+        expected: Hash256,
+        found: Hash256,
+    },
+    ValidatorCountMismatch {
+        expected: u64,
+        found: u64,
+    },
+    ChecksumMismatch {
+        expected: Hash256,
+        found: Hash256,
+    },
+    InvalidPubkey {
+        validator_index: usize,
+        error: bls::Error,
+    },
+    /// An imported key differs from the key of the validator in the head state.
+    HeadStateMismatch {
+        validator_index: usize,
+    },
+    /// An imported key differs from the key which is already in the cache.
+    CacheMismatch {
+        validator_index: usize,
+    },
+}
+
+impl From<std::io::Error> for PubkeyCacheTransferError {
+    fn from(e: std::io::Error) -> Self {
+        PubkeyCacheTransferError::Io(e)
+    }
+}
+
+/// Precedes the keys in an export of the validator pubkey cache.
+#[derive(Debug, Clone, Copy, PartialEq, Encode, Decode)]
+pub struct PubkeyCacheHeader {
+    /// The genesis validators root of the network of the exporting node.
+    pub genesis_validators_root: Hash256,
+    /// The number of keys which follow the header.
+    pub validator_count: u64,
+    /// The hash of the keys which follow the header.
+    pub checksum: Hash256,
+}
+
+impl<T: BeaconChainTypes> BeaconChain<T> {
+    /// Write every key in the validator pubkey cache to `writer`, returning the header which was
+    /// written before them.
+    pub fn export_pubkey_cache<W: Write>(&self, mut writer: W) -> Result<PubkeyCacheHeader, Error> {
+        // Serialize the keys under the lock, but write them without it.
+        let key_bytes = {
+            let pubkey_cache = self
+                .validator_pubkey_cache
+                .try_read_for(VALIDATOR_PUBKEY_CACHE_LOCK_TIMEOUT)
+                .ok_or(Error::ValidatorPubkeyCacheLockTimeout)?;
+
+            let mut key_bytes =
+                Vec::with_capacity(pubkey_cache.len() * PUBLIC_KEY_UNCOMPRESSED_BYTES_LEN);
+            for pubkey in (0..pubkey_cache.len()).filter_map(|i| pubkey_cache.get(i)) {
+                key_bytes.extend_from_slice(&pubkey.serialize_uncompressed());
+            }
+            key_bytes
+        };
+
+        let header = PubkeyCacheHeader {
+            genesis_validators_root: self.genesis_validators_root,
+            validator_count: (key_bytes.len() / PUBLIC_KEY_UNCOMPRESSED_BYTES_LEN) as u64,
+            checksum: Hash256::from_slice(&eth2_hashing::hash(&key_bytes)),
+        };
+
+        writer
+            .write_all(&header.as_ssz_bytes())
+            .and_then(|()| writer.write_all(&key_bytes))
+            .map_err(PubkeyCacheTransferError::from)?;
+
+        Ok(header)
+    }
+
+    /// Read an export of the validator pubkey cache from `reader` and add any keys which are
+    /// missing from the cache, returning the number of keys which were added.
+    ///
+    /// The import is refused if the export belongs to another network or fails its checksum, or
+    /// if any of the sampled keys differ from those in the head state. Keys which are already in
+    /// the cache must also match the export. Keys of validators which are not yet in the head state
+    /// are ignored.
+    pub fn import_pubkey_cache<R: Read>(&self, mut reader: R) -> Result<usize, Error> {
+        let mut header_bytes = vec![0; <PubkeyCacheHeader as Decode>::ssz_fixed_len()];
+        reader
+            .read_exact(&mut header_bytes)
+            .map_err(PubkeyCacheTransferError::from)?;
+        let header = PubkeyCacheHeader::from_ssz_bytes(&header_bytes)
+            .map_err(PubkeyCacheTransferError::InvalidHeader)?;
+
+        if header.genesis_validators_root != self.genesis_validators_root {
+            return Err(PubkeyCacheTransferError::GenesisValidatorsRootMismatch {
+                expected: self.genesis_validators_root,
+                found: header.genesis_validators_root,
+            }
+            .into());
+        }
+
+        let mut key_bytes = vec![];
+        reader
+            .read_to_end(&mut key_bytes)
+            .map_err(PubkeyCacheTransferError::from)?;
+
+        let validator_count = (key_bytes.len() / PUBLIC_KEY_UNCOMPRESSED_BYTES_LEN) as u64;
+        if key_bytes.len() % PUBLIC_KEY_UNCOMPRESSED_BYTES_LEN != 0
+            || validator_count != header.validator_count
+        {
+            return Err(PubkeyCacheTransferError::ValidatorCountMismatch {
+                expected: header.validator_count,
+                found: validator_count,
+            }
+            .into());
+        }
+
+        let checksum = Hash256::from_slice(&eth2_hashing::hash(&key_bytes));
+        if checksum != header.checksum {
+            return Err(PubkeyCacheTransferError::ChecksumMismatch {
+                expected: header.checksum,
+                found: checksum,
+            }
+            .into());
+        }
+
+        // Keys beyond the validators of the head state can't be checked, so they're left for the
+        // node to decompress itself as it imports blocks.
+        let head = self.head_snapshot();
+        let validators = head.beacon_state.validators();
+        let pubkeys = key_bytes
+            .chunks_exact(PUBLIC_KEY_UNCOMPRESSED_BYTES_LEN)
+            .take(validators.len())
+            .enumerate()
+            .map(|(validator_index, bytes)| {
+                let pubkey = PublicKey::deserialize_uncompressed(bytes).map_err(|error| {
+                    PubkeyCacheTransferError::InvalidPubkey {
+                        validator_index,
+                        error,
+                    }
+                })?;
+                Ok((pubkey.compress(), pubkey))
+            })
+            .collect::<Result<Vec<(PublicKeyBytes, PublicKey)>, PubkeyCacheTransferError>>()?;
+
+        // Check a sample of the keys against the head state before any are written to the cache,
+        // so that a bad export never reaches the database.
+        let sample_size = std::cmp::min(PUBKEY_CACHE_IMPORT_SAMPLE_SIZE, pubkeys.len());
+        for validator_index in sample(&mut rand::thread_rng(), pubkeys.len(), sample_size).iter() {
+            if validators[validator_index].pubkey != pubkeys[validator_index].0 {
+                return Err(PubkeyCacheTransferError::HeadStateMismatch { validator_index }.into());
+            }
+        }
+        drop(head);
+
+        let mut pubkey_cache = self
+            .validator_pubkey_cache
+            .try_write_for(VALIDATOR_PUBKEY_CACHE_LOCK_TIMEOUT)
+            .ok_or(Error::ValidatorPubkeyCacheLockTimeout)?;
+
+        let existing = pubkey_cache.len();
+        if let Some(validator_index) = (0..std::cmp::min(existing, pubkeys.len()))
+            .find(|&i| pubkey_cache.get_pubkey_bytes(i) != Some(&pubkeys[i].0))
+        {
+            return Err(PubkeyCacheTransferError::CacheMismatch { validator_index }.into());
+        }
+
+        let imported = pubkeys.len().saturating_sub(existing);
+        pubkey_cache.import_decompressed(pubkeys.into_iter().skip(existing))?;
+
+        Ok(imported)
+    }
+}
@@ -0,0 +1,85 @@
+//! Exposes the randao mixes and shuffling seeds of the head state, for external tooling which
+//! computes future duties without downloading the whole state.
+//!
+//! The shuffling decision roots are included, since the values only change when those roots do.
+//! A consumer may cache the values for as long as the decision roots are unchanged.
+use crate::{BeaconChain, BeaconChainError as Error, BeaconChainTypes};
+use serde_derive::{Deserialize, Serialize};
+use types::{Domain, Epoch, Hash256, RelativeEpoch};
+
+/// The number of epochs, up to and including the current epoch, for which mixes are included.
+pub const RANDAO_MIXES_SNAPSHOT_EPOCHS: u64 = 8;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EpochRandaoMix {
+    pub epoch: Epoch,
+    pub mix: Hash256,
+}
+
+/// The randao mixes and shuffling seeds of the head state.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RandaoMixesSnapshot {
+    /// The current epoch of the head state, which may be prior to the wall-clock epoch.
+    pub epoch: Epoch,
+    /// The mixes of recent epochs, oldest first. The mix of `epoch` is still being accumulated.
+    pub mixes: Vec<EpochRandaoMix>,
+    pub current_proposer_seed: Hash256,
+    pub next_proposer_seed: Hash256,
+    pub current_attester_seed: Hash256,
+    pub next_attester_seed: Hash256,
+    /// The root which decided the proposer shuffling of `epoch`.
+    pub proposer_shuffling_decision_root: Hash256,
+    /// The root which decided the attester shuffling of `epoch`.
+    pub current_attester_shuffling_decision_root: Hash256,
+    /// The root which decided the attester shuffling of `epoch + 1`.
+    pub next_attester_shuffling_decision_root: Hash256,
+}
+
+impl<T: BeaconChainTypes> BeaconChain<T> {
+    /// Returns the randao mixes and shuffling seeds of the head state, without cloning it.
+    pub fn randao_mixes_snapshot(&self) -> Result<RandaoMixesSnapshot, Error> {
+        self.with_head(|head| {
+            let state = &head.beacon_state;
+            let head_root = head.beacon_block_root;
+            let epoch = state.current_epoch();
+            let next_epoch = epoch + 1;
+
+            let first_epoch = std::cmp::max(
+                state.min_randao_epoch(),
+                (epoch + 1).saturating_sub(RANDAO_MIXES_SNAPSHOT_EPOCHS),
+            );
+            let mixes = (first_epoch.as_u64()..=epoch.as_u64())
+                .map(Epoch::new)
+                .map(|epoch| {
+                    Ok(EpochRandaoMix {
+                        epoch,
+                        mix: *state.get_randao_mix(epoch)?,
+                    })
+                })
+                .collect::<Result<Vec<_>, Error>>()?;
+
+            Ok(RandaoMixesSnapshot {
+                epoch,
+                mixes,
+                current_proposer_seed: state.get_seed(epoch, Domain::BeaconProposer, &self.spec)?,
+                next_proposer_seed: state.get_seed(
+                    next_epoch,
+                    Domain::BeaconProposer,
+                    &self.spec,
+                )?,
+                current_attester_seed: state.get_seed(epoch, Domain::BeaconAttester, &self.spec)?,
+                next_attester_seed: state.get_seed(
+                    next_epoch,
+                    Domain::BeaconAttester,
+                    &self.spec,
+                )?,
+                proposer_shuffling_decision_root: state
+                    .proposer_shuffling_decision_root(head_root)?,
+                current_attester_shuffling_decision_root: state
+                    .attester_shuffling_decision_root(head_root, RelativeEpoch::Current)?,
+                next_attester_shuffling_decision_root: state
+                    .attester_shuffling_decision_root(head_root, RelativeEpoch::Next)?,
+            })
+        })
+    }
+}
@@ -1,11 +1,12 @@
 //! Utilities for managing database schema changes.
+mod migration_schema_v10;
 mod migration_schema_v6;
 mod migration_schema_v7;
 mod migration_schema_v8;
 mod migration_schema_v9;
 mod types;
 
-use crate::beacon_chain::{BeaconChainTypes, FORK_CHOICE_DB_KEY};
+use crate::beacon_chain::{BeaconChainTypes, ETH1_CACHE_DB_KEY, FORK_CHOICE_DB_KEY};
 use crate::persisted_fork_choice::{PersistedForkChoiceV1, PersistedForkChoiceV7};
 use crate::types::ChainSpec;
 use slog::{warn, Logger};
@@ -130,6 +131,23 @@ pub fn migrate_schema<T: BeaconChainTypes>(
             migration_schema_v9::downgrade_from_v9::<T>(db.clone(), log)?;
             db.store_schema_version(to)
         }
+        // Upgrade from v9 to v10 to allow the deposit cache to be initialised from a snapshot.
+        (SchemaVersion(9), SchemaVersion(10)) => {
+            let mut ops = vec![];
+            if let Some(eth1) = migration_schema_v10::update_eth1_cache(&db)? {
+                ops.push(eth1.as_kv_store_op(ETH1_CACHE_DB_KEY));
+            }
+            db.store_schema_version_atomically(to, ops)
+        }
+        // Downgrade from v10 to v9, which is only possible if the deposit cache wasn't initialised
+        // from a snapshot.
+        (SchemaVersion(10), SchemaVersion(9)) => {
+            let mut ops = vec![];
+            if let Some(eth1) = migration_schema_v10::downgrade_eth1_cache(&db)? {
+                ops.push(eth1.as_kv_store_op(ETH1_CACHE_DB_KEY));
+            }
+            db.store_schema_version_atomically(to, ops)
+        }
         // Anything else is an error.
         (_, _) => Err(HotColdDBError::UnsupportedSchemaVersion {
             target_version: to,
@@ -0,0 +1,46 @@
+use crate::beacon_chain::{BeaconChainTypes, ETH1_CACHE_DB_KEY};
+use crate::eth1_chain::SszEth1;
+use eth1::{SszEth1CacheV1, SszEth1CacheV10};
+use ssz::{Decode, Encode};
+use store::{Error, HotColdDB};
+
+/// Convert the persisted deposit cache, if any, to the format which supports snapshots.
+pub fn update_eth1_cache<T: BeaconChainTypes>(
+    db: &HotColdDB<T::EthSpec, T::HotStore, T::ColdStore>,
+) -> Result<Option<SszEth1>, Error> {
+    convert_eth1_cache::<T>(db, |bytes| {
+        let cache_v1 = SszEth1CacheV1::from_ssz_bytes(bytes)?;
+        Ok(SszEth1CacheV10::from(cache_v1).as_ssz_bytes())
+    })
+}
+
+/// Convert the persisted deposit cache, if any, to the format used prior to v10.
+///
+/// Fails if the deposit cache was initialised from a snapshot.
+pub fn downgrade_eth1_cache<T: BeaconChainTypes>(
+    db: &HotColdDB<T::EthSpec, T::HotStore, T::ColdStore>,
+) -> Result<Option<SszEth1>, Error> {
+    convert_eth1_cache::<T>(db, |bytes| {
+        let cache_v10 = SszEth1CacheV10::from_ssz_bytes(bytes)?;
+        let cache_v1 = SszEth1CacheV1::try_from(cache_v10).map_err(Error::SchemaMigrationError)?;
+        Ok(cache_v1.as_ssz_bytes())
+    })
+}
+
+fn convert_eth1_cache<T: BeaconChainTypes>(
+    db: &HotColdDB<T::EthSpec, T::HotStore, T::ColdStore>,
+    convert: impl FnOnce(&[u8]) -> Result<Vec<u8>, Error>,
+) -> Result<Option<SszEth1>, Error> {
+    let mut eth1 = match db.get_item::<SszEth1>(&ETH1_CACHE_DB_KEY)? {
+        Some(eth1) => eth1,
+        None => return Ok(None),
+    };
+
+    // The dummy backend persists no bytes.
+    if eth1.backend_bytes.is_empty() {
+        return Ok(None);
+    }
+
+    eth1.backend_bytes = convert(&eth1.backend_bytes)?;
+    Ok(Some(eth1))
+}
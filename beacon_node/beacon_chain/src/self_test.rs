@@ -0,0 +1,218 @@
+//! Checks the components the node depends upon at startup, producing a readiness report.
+//!
+//! Each check runs concurrently with its own timeout. A failed check is either `Fatal`, meaning the
+//! node can't operate correctly (e.g. the execution engine is unreachable), or `Degraded`, meaning
+//! the node can operate with reduced functionality (e.g. blocks can't be built by the builder).
+//! The readiness of the node is determined by the most severe failed check.
+//!
+//! The report is logged and retained so that it can be served by the HTTP API.
+use crate::{BeaconChain, BeaconChainTypes};
+use eth2::types::{
+    SelfTestCheckResult, SelfTestReadiness, SelfTestReport, SelfTestSeverity, SelfTestStatus,
+};
+use futures::future::{join_all, BoxFuture};
+use slog::{crit, info, warn};
+use slot_clock::SlotClock;
+use std::fs;
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use types::EthSpec;
+
+/// The default time allowed for each check to complete.
+pub const SELF_TEST_CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The name of the file written to test the data directory.
+const DISK_WRITE_TEST_FILENAME: &str = ".self_test";
+
+/// The number of slots the head may be ahead of the wall-clock before the clock is suspect.
+const SLOT_CLOCK_TOLERANCE_SLOTS: u64 = 1;
+
+/// A single check to be run by the self-test.
+pub struct SelfTestCheck {
+    name: &'static str,
+    severity: SelfTestSeverity,
+    timeout: Duration,
+    check: BoxFuture<'static, SelfTestStatus>,
+}
+
+impl SelfTestCheck {
+    pub fn new<F>(name: &'static str, severity: SelfTestSeverity, check: F) -> Self
+    where
+        F: Future<Output = SelfTestStatus> + Send + 'static,
+    {
+        Self {
+            name,
+            severity,
+            timeout: SELF_TEST_CHECK_TIMEOUT,
+            check: Box::pin(check),
+        }
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Run the check, failing it if it doesn't complete within the timeout.
+    pub async fn run(self) -> SelfTestCheckResult {
+        let start = Instant::now();
+        let status = tokio::time::timeout(self.timeout, self.check)
+            .await
+            .unwrap_or_else(|_| {
+                SelfTestStatus::Failed(format!("timed out after {}ms", self.timeout.as_millis()))
+            });
+        SelfTestCheckResult {
+            name: self.name.to_string(),
+            severity: self.severity,
+            status,
+            duration_ms: start.elapsed().as_millis() as u64,
+        }
+    }
+}
+
+/// Run all `checks` concurrently, returning the report in the order of `checks`.
+pub async fn run_self_test_checks(checks: Vec<SelfTestCheck>) -> SelfTestReport {
+    SelfTestReport::new(join_all(checks.into_iter().map(SelfTestCheck::run)).await)
+}
+
+/// Converts the result of a check into a status.
+fn status<E: std::fmt::Debug>(result: Result<(), E>) -> SelfTestStatus {
+    match result {
+        Ok(()) => SelfTestStatus::Passed,
+        Err(e) => SelfTestStatus::Failed(format!("{:?}", e)),
+    }
+}
+
+/// Checks that a file can be written to, read from and deleted from `dir`.
+pub fn disk_write_check(dir: PathBuf) -> SelfTestCheck {
+    SelfTestCheck::new("disk_write", SelfTestSeverity::Fatal, async move {
+        let path = dir.join(DISK_WRITE_TEST_FILENAME);
+        let contents = b"lighthouse self-test";
+        // Use a blocking task to avoid blocking the core executor whilst accessing the disk.
+        let task_path = path.clone();
+        let result = tokio::task::spawn_blocking(move || {
+            fs::write(&task_path, contents)
+                .and_then(|()| fs::read(&task_path))
+                .and_then(|read| {
+                    fs::remove_file(&task_path)?;
+                    Ok(read)
+                })
+        })
+        .await;
+        match result {
+            Ok(Ok(read)) if read == contents => SelfTestStatus::Passed,
+            Ok(Ok(_)) => SelfTestStatus::Failed(format!("{:?} was not read back correctly", path)),
+            Ok(Err(e)) => SelfTestStatus::Failed(format!("unable to write {:?}: {}", path, e)),
+            Err(e) => SelfTestStatus::Failed(format!("disk write task failed: {}", e)),
+        }
+    })
+}
+
+impl<T: BeaconChainTypes> BeaconChain<T> {
+    /// Returns the checks of the components held by the `BeaconChain`.
+    ///
+    /// The slot clock is only compared to the head; no time server is queried.
+    pub fn self_test_checks(self: &Arc<Self>) -> Vec<SelfTestCheck> {
+        let chain = self.clone();
+        let execution_engine =
+            SelfTestCheck::new("execution_engine", SelfTestSeverity::Fatal, async move {
+                match chain.execution_layer.as_ref() {
+                    Some(el) => status(el.exchange_transition_configuration(&chain.spec).await),
+                    None => SelfTestStatus::Skipped("no execution endpoint configured".into()),
+                }
+            });
+
+        let chain = self.clone();
+        let builder = SelfTestCheck::new("builder", SelfTestSeverity::Degraded, async move {
+            let builders = match chain.execution_layer.as_ref() {
+                Some(el) if el.has_builder() => el.builders(),
+                _ => return SelfTestStatus::Skipped("no builder configured".into()),
+            };
+            for builder in builders.iter() {
+                if let Err(e) = builder.get_builder_status::<T::EthSpec>().await {
+                    return SelfTestStatus::Failed(format!("{}: {:?}", builder.server(), e));
+                }
+            }
+            SelfTestStatus::Passed
+        });
+
+        let chain = self.clone();
+        let slot_clock = SelfTestCheck::new("slot_clock", SelfTestSeverity::Degraded, async move {
+            let current_slot = match chain.slot_clock.now() {
+                Some(slot) => slot,
+                None => return SelfTestStatus::Skipped("prior to genesis".into()),
+            };
+            let head_slot = chain.canonical_head.cached_head().head_slot();
+            if head_slot > current_slot + SLOT_CLOCK_TOLERANCE_SLOTS {
+                SelfTestStatus::Failed(format!(
+                    "the head slot {} is ahead of the wall-clock slot {}, the system clock may \
+                    be behind",
+                    head_slot, current_slot
+                ))
+            } else {
+                SelfTestStatus::Passed
+            }
+        });
+
+        vec![execution_engine, builder, slot_clock]
+    }
+
+    /// Run the self-test of the components held by the `BeaconChain` and the `extra_checks`,
+    /// logging and retaining the report.
+    pub async fn run_self_test(
+        self: &Arc<Self>,
+        extra_checks: Vec<SelfTestCheck>,
+    ) -> SelfTestReport {
+        let mut checks = self.self_test_checks();
+        checks.extend(extra_checks);
+        let report = run_self_test_checks(checks).await;
+
+        for check in &report.checks {
+            match &check.status {
+                SelfTestStatus::Passed => info!(
+                    self.log,
+                    "Self-test check passed";
+                    "check" => &check.name,
+                    "duration_ms" => check.duration_ms,
+                ),
+                SelfTestStatus::Skipped(reason) => info!(
+                    self.log,
+                    "Self-test check skipped";
+                    "check" => &check.name,
+                    "reason" => reason,
+                ),
+                SelfTestStatus::Failed(error) => warn!(
+                    self.log,
+                    "Self-test check failed";
+                    "check" => &check.name,
+                    "severity" => ?check.severity,
+                    "error" => error,
+                    "duration_ms" => check.duration_ms,
+                ),
+            }
+        }
+        match report.readiness {
+            SelfTestReadiness::Ready => info!(self.log, "Self-test complete, node is ready"),
+            SelfTestReadiness::Degraded => warn!(
+                self.log,
+                "Self-test complete, node is degraded";
+                "msg" => "the node will run with reduced functionality",
+            ),
+            SelfTestReadiness::NotReady => crit!(
+                self.log,
+                "Self-test complete, node is not ready";
+                "msg" => "the node cannot operate correctly until the failed checks pass",
+            ),
+        }
+
+        *self.self_test_report.lock() = Some(report.clone());
+        report
+    }
+
+    /// Returns the report of the most recent self-test, if any.
+    pub fn self_test_report(&self) -> Option<SelfTestReport> {
+        self.self_test_report.lock().clone()
+    }
+}
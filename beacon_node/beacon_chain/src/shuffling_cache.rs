@@ -45,6 +45,39 @@ impl ShufflingCache {
             self.cache.put(key, committee_cache.clone());
         }
     }
+
+    /// Returns an estimate of the memory used by the cached committees, in bytes.
+    pub fn memory_usage(&self) -> usize {
+        self.cache
+            .iter()
+            .map(|(_, committee_cache)| entry_memory_usage(committee_cache))
+            .sum()
+    }
+
+    /// Evict the least recently used committees until at most `max_bytes` are used.
+    pub fn shrink_to(&mut self, max_bytes: usize) {
+        let mut memory_usage = self.memory_usage();
+        while memory_usage > max_bytes {
+            match self.cache.pop_lru() {
+                Some((_, committee_cache)) => {
+                    memory_usage -= entry_memory_usage(&committee_cache);
+                }
+                None => break,
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.cache.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cache.is_empty()
+    }
+}
+
+fn entry_memory_usage(committee_cache: &CommitteeCache) -> usize {
+    std::mem::size_of::<AttestationShufflingId>() + committee_cache.memory_usage()
 }
 
 impl Default for ShufflingCache {
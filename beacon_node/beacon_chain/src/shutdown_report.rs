@@ -0,0 +1,184 @@
+//! Records why the beacon chain requested that the node shut down, so that the next startup (or an
+//! operator investigating the failure) can see the cause without trawling the logs.
+//!
+//! The report is written to `last_shutdown.json` in the data directory before the shutdown signal
+//! is sent. Each cause is also mapped to a distinct process exit code.
+use crate::{BeaconChain, BeaconChainTypes, INVALID_JUSTIFIED_PAYLOAD_SHUTDOWN_REASON};
+use futures::channel::mpsc::TrySendError;
+use serde_derive::{Deserialize, Serialize};
+use slog::{crit, warn, Logger};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::Ordering;
+use std::time::{SystemTime, UNIX_EPOCH};
+use task_executor::ShutdownReason;
+use types::{ExecutionBlockHash, Hash256, Slot};
+
+/// The name of the shutdown report file within the data directory.
+pub const SHUTDOWN_REPORT_FILENAME: &str = "last_shutdown.json";
+
+/// The message used when shutting down due to an invalid finalized payload.
+pub const INVALID_FINALIZED_PAYLOAD_SHUTDOWN_REASON: &str =
+    "Finalized block has an invalid execution payload.";
+
+/// The message used when shutting down due to a block which conflicts with the weak subjectivity
+/// checkpoint.
+pub const WEAK_SUBJECTIVITY_CONFLICT_SHUTDOWN_REASON: &str =
+    "Weak subjectivity checkpoint verification failed. Provided block root is not a checkpoint.";
+
+/// The conditions in which the beacon chain shuts down the node.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShutdownCause {
+    /// An imported block conflicts with the weak subjectivity checkpoint.
+    WeakSubjectivityConflict,
+    /// The execution payload of the justified block is invalid.
+    InvalidJustifiedPayload,
+    /// The execution payload of the finalized block is invalid.
+    InvalidFinalizedPayload,
+}
+
+impl ShutdownCause {
+    pub fn message(&self) -> &'static str {
+        match self {
+            ShutdownCause::WeakSubjectivityConflict => WEAK_SUBJECTIVITY_CONFLICT_SHUTDOWN_REASON,
+            ShutdownCause::InvalidJustifiedPayload => INVALID_JUSTIFIED_PAYLOAD_SHUTDOWN_REASON,
+            ShutdownCause::InvalidFinalizedPayload => INVALID_FINALIZED_PAYLOAD_SHUTDOWN_REASON,
+        }
+    }
+
+    /// The process exit code, which is distinct from the generic failure code of 1.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            ShutdownCause::WeakSubjectivityConflict => 3,
+            ShutdownCause::InvalidJustifiedPayload => 4,
+            ShutdownCause::InvalidFinalizedPayload => 5,
+        }
+    }
+
+    pub fn shutdown_reason(&self) -> ShutdownReason {
+        ShutdownReason::FailureWithExitCode(self.message(), self.exit_code())
+    }
+}
+
+/// The contents of the shutdown report file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ShutdownReport {
+    pub cause: ShutdownCause,
+    pub message: String,
+    pub exit_code: i32,
+    /// Seconds since the UNIX epoch at which the shutdown was requested.
+    pub timestamp: u64,
+    pub client_version: String,
+    /// The root of the block which caused the shutdown.
+    pub block_root: Hash256,
+    /// The execution block hash of the payload of `block_root`, if any.
+    pub execution_block_hash: Option<ExecutionBlockHash>,
+    pub head_block_root: Hash256,
+    pub head_slot: Slot,
+}
+
+impl ShutdownReport {
+    pub fn new(
+        cause: ShutdownCause,
+        block_root: Hash256,
+        execution_block_hash: Option<ExecutionBlockHash>,
+        head_block_root: Hash256,
+        head_slot: Slot,
+    ) -> Self {
+        Self {
+            cause,
+            message: cause.message().to_string(),
+            exit_code: cause.exit_code(),
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |duration| duration.as_secs()),
+            client_version: lighthouse_version::VERSION.to_string(),
+            block_root,
+            execution_block_hash,
+            head_block_root,
+            head_slot,
+        }
+    }
+
+    /// Read the report file at `path`, returning `None` if it does not exist.
+    pub fn load(path: &Path) -> Result<Option<Self>, String> {
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let contents =
+            fs::read(path).map_err(|e| format!("Unable to read {}: {}", path.display(), e))?;
+        serde_json::from_slice(&contents)
+            .map(Some)
+            .map_err(|e| format!("Unable to parse {}: {}", path.display(), e))
+    }
+
+    /// Write the report file to `path`.
+    pub fn write(&self, path: &Path) -> Result<(), String> {
+        let contents = serde_json::to_vec_pretty(self)
+            .map_err(|e| format!("Unable to serialize shutdown report: {}", e))?;
+
+        // Write to a temporary file first so that an interrupted write can't corrupt the file.
+        let temp_path = path.with_extension("json.tmp");
+        fs::write(&temp_path, contents)
+            .and_then(|()| fs::rename(&temp_path, path))
+            .map_err(|e| format!("Unable to write {}: {}", path.display(), e))
+    }
+
+    /// Move the report at `path` aside once it has been logged, so that it is only reported on the
+    /// first startup after the shutdown. Returns the path of the archived report.
+    pub fn archive(path: &Path) -> Result<PathBuf, String> {
+        let archived_path = path.with_extension("json.previous");
+        fs::rename(path, &archived_path)
+            .map(|()| archived_path)
+            .map_err(|e| format!("Unable to archive {}: {}", path.display(), e))
+    }
+
+    /// Log the report left by a previous run of the node.
+    pub fn log_previous(&self, log: &Logger) {
+        warn!(
+            log,
+            "Previous run was shut down by the beacon chain";
+            "msg" => "see last_shutdown.json.previous in the datadir for more information",
+            "cause" => ?self.cause,
+            "message" => &self.message,
+            "timestamp" => self.timestamp,
+            "client_version" => &self.client_version,
+            "block_root" => ?self.block_root,
+            "execution_block_hash" => ?self.execution_block_hash,
+            "head_block_root" => ?self.head_block_root,
+            "head_slot" => self.head_slot,
+        );
+    }
+}
+
+impl<T: BeaconChainTypes> BeaconChain<T> {
+    /// Request that the node shuts down, first writing `report` to the data directory (if known).
+    ///
+    /// Only the first report of this run is written, since any later shut down requests are likely
+    /// to be consequences of the first.
+    ///
+    /// ## Notes
+    ///
+    /// This function takes no locks, so it may be called whilst holding the `canonical_head`.
+    pub(crate) fn shut_down_with_report(
+        &self,
+        report: ShutdownReport,
+    ) -> Result<(), TrySendError<ShutdownReason>> {
+        if let Some(path) = &self.shutdown_report_path {
+            if !self.shutdown_report_written.swap(true, Ordering::SeqCst) {
+                if let Err(e) = report.write(path) {
+                    crit!(
+                        self.log,
+                        "Unable to write shutdown report";
+                        "error" => e,
+                    );
+                }
+            }
+        }
+
+        self.shutdown_sender()
+            .try_send(report.cause.shutdown_reason())
+    }
+}
@@ -1,5 +1,7 @@
+use crate::cache_memory::state_memory_usage;
 use crate::BeaconSnapshot;
 use itertools::process_results;
+use ssz::Encode;
 use std::cmp;
 use std::sync::Arc;
 use std::time::Duration;
@@ -71,6 +73,13 @@ impl<T: EthSpec> CacheItem<T> {
         }
     }
 
+    /// Returns an estimate of the memory used by `self`, in bytes.
+    pub fn memory_usage(&self) -> usize {
+        self.beacon_block.ssz_bytes_len()
+            + state_memory_usage(&self.beacon_state)
+            + self.pre_state.as_ref().map_or(0, state_memory_usage)
+    }
+
     pub fn clone_as_pre_state(&self) -> PreProcessingSnapshot<T> {
         // Do not include the beacon state root if the state has been advanced.
         let beacon_state_root =
@@ -350,6 +359,40 @@ impl<T: EthSpec> SnapshotCache<T> {
         })
     }
 
+    /// Returns an estimate of the memory used by the cached snapshots, in bytes.
+    pub fn memory_usage(&self) -> usize {
+        self.snapshots.iter().map(CacheItem::memory_usage).sum()
+    }
+
+    /// Returns an estimate of the memory used by the snapshot of the head, which is never evicted.
+    pub fn head_memory_usage(&self) -> usize {
+        self.snapshots
+            .iter()
+            .filter(|snapshot| snapshot.beacon_block_root == self.head_block_root)
+            .map(CacheItem::memory_usage)
+            .sum()
+    }
+
+    /// Evict the snapshots with the lowest slots until at most `max_bytes` are used, or only the
+    /// snapshot of the head remains.
+    pub fn shrink_to(&mut self, max_bytes: usize) {
+        let mut memory_usage = self.memory_usage();
+        while memory_usage > max_bytes {
+            let evict_at = self
+                .snapshots
+                .iter()
+                .enumerate()
+                .filter(|(_i, snapshot)| snapshot.beacon_block_root != self.head_block_root)
+                .min_by_key(|(_i, snapshot)| snapshot.beacon_state.slot())
+                .map(|(i, _snapshot)| i);
+
+            match evict_at {
+                Some(i) => memory_usage -= self.snapshots.remove(i).memory_usage(),
+                None => break,
+            }
+        }
+    }
+
     /// Inform the cache that the head of the beacon chain has changed.
     ///
     /// The snapshot that matches this `head_block_root` will never be ejected from the cache
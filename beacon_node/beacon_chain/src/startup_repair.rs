@@ -0,0 +1,219 @@
+//! Repairs the persisted state of the beacon chain when resuming, for use after a crash has left it
+//! inconsistent with the blocks in the database.
+use crate::fork_revert::{fork_choice_from_checkpoint, replay_blocks_into_fork_choice};
+use crate::head_tracker::HeadTracker;
+use crate::BeaconForkChoiceStore;
+use fork_choice::ForkChoice;
+use parking_lot::RwLock;
+use slog::{info, warn, Logger};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use store::{DBColumn, HotColdDB, HotStateSummary, ItemStore, KeyValueStore, StoreItem};
+use types::{
+    BlindedPayload, ChainSpec, Checkpoint, Epoch, EthSpec, Hash256, SignedBeaconBlock, Slot,
+};
+
+/// Re-derive fork choice from the finalized checkpoint recorded in the database, by replaying
+/// every stored chain of blocks which descends from it.
+///
+/// The chains replayed are those ending at the heads in `head_tracker`, along with those ending at
+/// any other leaf block in the database, which covers blocks imported after the head tracker was
+/// last persisted. Each block is replayed once, on top of the state of its parent, so blocks shared
+/// by several chains aren't replayed again. Heads which can't be replayed are dropped.
+///
+/// Returns fork choice along with a head tracker containing only the heads which were replayed.
+#[allow(clippy::type_complexity)]
+pub fn rebuild_fork_choice<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>>(
+    store: Arc<HotColdDB<E, Hot, Cold>>,
+    genesis_block_root: Hash256,
+    head_tracker: &HeadTracker,
+    spec: &ChainSpec,
+    log: &Logger,
+) -> Result<
+    (
+        ForkChoice<BeaconForkChoiceStore<E, Hot, Cold>, E>,
+        HeadTracker,
+    ),
+    String,
+> {
+    // The pruning checkpoint is the finalized checkpoint at the last database migration.
+    let finalized_checkpoint = store
+        .load_pruning_checkpoint()
+        .map_err(|e| format!("Unable to load pruning checkpoint: {:?}", e))?
+        .filter(|checkpoint| !checkpoint.root.is_zero())
+        .unwrap_or(Checkpoint {
+            epoch: Epoch::new(0),
+            root: genesis_block_root,
+        });
+    let finalized_slot = finalized_checkpoint.epoch.start_slot(E::slots_per_epoch());
+
+    info!(
+        log,
+        "Rebuilding fork choice from the database";
+        "finalized_epoch" => finalized_checkpoint.epoch,
+        "finalized_root" => ?finalized_checkpoint.root,
+    );
+
+    let (mut fork_choice, finalized_state) =
+        fork_choice_from_checkpoint(finalized_checkpoint, store.clone(), None, spec)?;
+
+    let mut heads = head_tracker
+        .heads()
+        .into_iter()
+        .collect::<HashMap<Hash256, Slot>>();
+    for (block_root, slot) in stored_leaf_blocks(&store, finalized_slot, log)? {
+        if !heads.contains_key(&block_root) {
+            info!(
+                log,
+                "Recovered head missing from head tracker";
+                "block_root" => ?block_root,
+                "slot" => slot,
+            );
+            heads.insert(block_root, slot);
+        }
+    }
+    let mut heads = heads
+        .into_iter()
+        .filter(|(_, slot)| *slot > finalized_slot)
+        .collect::<Vec<_>>();
+    heads.sort_by_key(|(block_root, slot)| (*slot, *block_root));
+
+    let mut replayed_heads = HashMap::new();
+    for (head_root, head_slot) in heads {
+        let result = load_unknown_blocks(&store, &fork_choice, head_root, finalized_slot).and_then(
+            |(parent_root, blocks)| {
+                // Replay the blocks on top of the state of the latest block known to fork choice.
+                let state = if parent_root == finalized_checkpoint.root {
+                    finalized_state.clone()
+                } else {
+                    let parent = fork_choice
+                        .get_block(&parent_root)
+                        .ok_or_else(|| format!("Missing parent block {:?}", parent_root))?;
+                    store
+                        .get_state(&parent.state_root, Some(parent.slot))
+                        .map_err(|e| format!("Error loading parent state: {:?}", e))?
+                        .ok_or_else(|| format!("Missing parent state {:?}", parent.state_root))?
+                };
+
+                let num_blocks = blocks.len();
+                replay_blocks_into_fork_choice(&mut fork_choice, state, blocks, spec)
+                    .map(|()| num_blocks)
+            },
+        );
+
+        match result {
+            Ok(num_blocks) => {
+                info!(
+                    log,
+                    "Replayed chain into fork choice";
+                    "head_root" => ?head_root,
+                    "head_slot" => head_slot,
+                    "blocks" => num_blocks,
+                );
+                replayed_heads.insert(head_root, head_slot);
+            }
+            Err(e) => warn!(
+                log,
+                "Dropping head which can't be replayed";
+                "head_root" => ?head_root,
+                "head_slot" => head_slot,
+                "error" => e,
+            ),
+        }
+    }
+
+    // Without any descendants, the finalized block is the only head.
+    if replayed_heads.is_empty() {
+        replayed_heads.insert(
+            finalized_checkpoint.root,
+            finalized_state.latest_block_header().slot,
+        );
+    }
+
+    Ok((fork_choice, HeadTracker(RwLock::new(replayed_heads))))
+}
+
+/// Load the blocks from `head_root` back to the latest ancestor which is already known to
+/// `fork_choice`.
+///
+/// Returns the root of that ancestor, along with the blocks after it in ascending slot order.
+#[allow(clippy::type_complexity)]
+fn load_unknown_blocks<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>>(
+    store: &HotColdDB<E, Hot, Cold>,
+    fork_choice: &ForkChoice<BeaconForkChoiceStore<E, Hot, Cold>, E>,
+    head_root: Hash256,
+    finalized_slot: Slot,
+) -> Result<(Hash256, Vec<SignedBeaconBlock<E, BlindedPayload<E>>>), String> {
+    let mut blocks = vec![];
+    let mut block_root = head_root;
+    while !fork_choice.contains_block(&block_root) {
+        let block = store
+            .get_blinded_block(&block_root)
+            .map_err(|e| format!("Error loading block to replay: {:?}", e))?
+            .ok_or_else(|| format!("Missing block {:?}", block_root))?;
+        if block.slot() <= finalized_slot {
+            return Err(format!(
+                "Block {:?} does not descend from the finalized block",
+                block_root
+            ));
+        }
+        block_root = block.parent_root();
+        blocks.push(block);
+    }
+    blocks.reverse();
+
+    Ok((block_root, blocks))
+}
+
+/// Returns the root and slot of each block in the database after `finalized_slot` which is not
+/// the parent of another stored block.
+///
+/// The blocks are found via the summaries of the hot states, which are only kept for states after
+/// the last database migration, so the finalized blocks aren't read.
+fn stored_leaf_blocks<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>>(
+    store: &HotColdDB<E, Hot, Cold>,
+    finalized_slot: Slot,
+    log: &Logger,
+) -> Result<Vec<(Hash256, Slot)>, String> {
+    let mut block_roots = HashSet::new();
+    for result in store.hot_db.iter_column(DBColumn::BeaconStateSummary) {
+        let (state_root, bytes) =
+            result.map_err(|e| format!("Unable to iterate state summaries: {:?}", e))?;
+        match HotStateSummary::from_store_bytes(&bytes) {
+            Ok(summary) if summary.slot() > finalized_slot => {
+                block_roots.insert(summary.latest_block_root());
+            }
+            Ok(_) => (),
+            Err(e) => warn!(
+                log,
+                "Ignoring state summary which can't be decoded";
+                "state_root" => ?state_root,
+                "error" => ?e,
+            ),
+        }
+    }
+
+    let mut blocks = HashMap::new();
+    let mut parents = HashSet::new();
+    for block_root in block_roots {
+        match store.get_blinded_block(&block_root) {
+            Ok(Some(block)) if block.slot() > finalized_slot => {
+                blocks.insert(block_root, block.slot());
+                parents.insert(block.parent_root());
+            }
+            // The summary of a state whose block import didn't complete may outlive the block.
+            Ok(_) => (),
+            Err(e) => warn!(
+                log,
+                "Ignoring block which can't be loaded";
+                "block_root" => ?block_root,
+                "error" => ?e,
+            ),
+        }
+    }
+
+    Ok(blocks
+        .into_iter()
+        .filter(|(block_root, _)| !parents.contains(block_root))
+        .collect())
+}
@@ -0,0 +1,79 @@
+//! Locks which serialize advancing the state of a head block.
+//!
+//! When the state advance timer hasn't yet advanced the head state, each block producer would
+//! otherwise advance its own copy of it. Instead, block producers (and the timer) hold the lock for
+//! the head block whilst advancing its state and returning the result to the snapshot cache, so
+//! that concurrent callers wait for and reuse the first caller's result.
+//!
+//! States are only advanced on blocking threads, so the locks are blocking mutexes.
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::sync::Arc;
+use types::Hash256;
+
+/// Each lock guards the number of times the state of its block has been advanced.
+pub type StateAdvanceLock = Arc<Mutex<usize>>;
+
+/// A map from block root to the lock for advancing the state of that block.
+#[derive(Default)]
+pub struct StateAdvanceLocks {
+    locks: Mutex<HashMap<Hash256, StateAdvanceLock>>,
+}
+
+impl StateAdvanceLocks {
+    /// Return the lock for advancing the state of the block with `block_root`.
+    pub fn lock_for(&self, block_root: Hash256) -> StateAdvanceLock {
+        self.locks.lock().entry(block_root).or_default().clone()
+    }
+
+    /// Return the number of times the state of the block with `block_root` has been advanced
+    /// whilst holding its lock, waiting for any advance in progress.
+    pub fn advances(&self, block_root: Hash256) -> usize {
+        let lock = self.locks.lock().get(&block_root).cloned();
+        lock.map_or(0, |lock| *lock.lock())
+    }
+
+    /// Drop the locks of every block other than `head_block_root`.
+    ///
+    /// Callers already holding a dropped lock are unaffected, however any later caller for that
+    /// block is given a new lock.
+    pub fn prune(&self, head_block_root: Hash256) {
+        self.locks
+            .lock()
+            .retain(|block_root, _| *block_root == head_block_root);
+    }
+
+    /// Return the number of blocks with a lock.
+    pub fn len(&self) -> usize {
+        self.locks.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.locks.lock().is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prune_retains_head() {
+        let locks = StateAdvanceLocks::default();
+        let (a, b) = (Hash256::repeat_byte(1), Hash256::repeat_byte(2));
+
+        *locks.lock_for(a).lock() += 1;
+        let held = locks.lock_for(b);
+        *held.lock() += 1;
+        assert_eq!(locks.len(), 2);
+        assert_eq!(locks.advances(a), 1);
+
+        locks.prune(a);
+        assert_eq!(locks.len(), 1);
+        assert_eq!(locks.advances(a), 1);
+        assert_eq!(locks.advances(b), 0);
+
+        // A pruned lock is no longer shared with later callers.
+        assert!(!Arc::ptr_eq(&held, &locks.lock_for(b)));
+    }
+}
@@ -13,6 +13,7 @@
 //! 1. We are required to store an additional `BeaconState` for the head block. This consumes
 //!    memory.
 //! 2. There's a possibility that the head block is never built upon, causing wasted CPU cycles.
+use crate::failure_log_limiter::FailureKind;
 use crate::validator_monitor::HISTORIC_EPOCHS as VALIDATOR_MONITOR_HISTORIC_EPOCHS;
 use crate::{
     beacon_chain::{ATTESTATION_CACHE_LOCK_TIMEOUT, BLOCK_PROCESSING_CACHE_LOCK_TIMEOUT},
@@ -215,19 +216,34 @@ async fn state_advance_timer<T: BeaconChainTypes>(
         let next_slot = current_slot + 1;
         executor.spawn(
             async move {
-                // Don't run fork choice during sync.
-                if beacon_chain.best_slot() + MAX_FORK_CHOICE_DISTANCE < current_slot {
+                // Don't run fork choice during sync, or on a read-only chain.
+                if beacon_chain.best_slot() + MAX_FORK_CHOICE_DISTANCE < current_slot
+                    || beacon_chain.config.read_only
+                {
                     return;
                 }
 
-                if let Err(e) = beacon_chain.recompute_head_at_slot(next_slot).await {
-                    warn!(
-                        log,
-                        "Error updating fork choice for next slot";
-                        "error" => ?e,
-                        "slot" => next_slot,
-                    );
-                }
+                let summary = match beacon_chain.fork_choice_at_slot(next_slot).await {
+                    Ok(summary) => {
+                        beacon_chain.record_failure_cleared(FailureKind::ForkChoice);
+                        Some(summary)
+                    }
+                    Err(e) => {
+                        if beacon_chain.should_log_failure(
+                            FailureKind::ForkChoice,
+                            "Error updating fork choice for next slot",
+                            &e,
+                        ) {
+                            warn!(
+                                log,
+                                "Error updating fork choice for next slot";
+                                "error" => ?e,
+                                "slot" => next_slot,
+                            );
+                        }
+                        None
+                    }
+                };
 
                 // Use a blocking task to avoid blocking the core executor whilst waiting for locks
                 // in `ForkChoiceSignalTx`.
@@ -235,7 +251,7 @@ async fn state_advance_timer<T: BeaconChainTypes>(
                     move || {
                         // Signal block proposal for the next slot (if it happens to be waiting).
                         if let Some(tx) = &beacon_chain.fork_choice_signal_tx {
-                            if let Err(e) = tx.notify_fork_choice_complete(next_slot) {
+                            if let Err(e) = tx.notify_fork_choice_complete(next_slot, summary) {
                                 warn!(
                                     log,
                                     "Error signalling fork choice waiter";
@@ -282,6 +298,10 @@ fn advance_head<T: BeaconChainTypes>(
 
     let head_root = beacon_chain.head_beacon_block_root();
 
+    // Hold the lock for advancing the head state so that block production doesn't also advance it.
+    let advance_lock = beacon_chain.state_advance_locks.lock_for(head_root);
+    let mut advances = advance_lock.lock();
+
     let (head_slot, head_state_root, mut state) = match beacon_chain
         .snapshot_cache
         .try_write_for(BLOCK_PROCESSING_CACHE_LOCK_TIMEOUT)
@@ -349,6 +369,7 @@ fn advance_head<T: BeaconChainTypes>(
             }
         }
     }
+    *advances += 1;
 
     debug!(
         log,
@@ -424,6 +445,7 @@ fn advance_head<T: BeaconChainTypes>(
         .ok_or(BeaconChainError::SnapshotCacheLockTimeout)?
         .update_pre_state(head_root, state)
         .ok_or(Error::HeadMissingFromSnapshotCache(head_root))?;
+    drop(advances);
 
     // If we have moved into the next slot whilst processing the state then this function is going
     // to become ineffective and likely become a hindrance as we're stealing the tree hash cache
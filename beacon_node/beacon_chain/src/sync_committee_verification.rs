@@ -28,10 +28,8 @@
 
 use crate::observed_attesters::SlotSubcommitteeIndex;
 use crate::{
-    beacon_chain::{MAXIMUM_GOSSIP_CLOCK_DISPARITY, VALIDATOR_PUBKEY_CACHE_LOCK_TIMEOUT},
-    metrics,
-    observed_aggregates::ObserveOutcome,
-    BeaconChain, BeaconChainError, BeaconChainTypes,
+    beacon_chain::VALIDATOR_PUBKEY_CACHE_LOCK_TIMEOUT, metrics,
+    observed_aggregates::ObserveOutcome, BeaconChain, BeaconChainError, BeaconChainTypes,
 };
 use bls::{verify_signature_sets, PublicKeyBytes};
 use derivative::Derivative;
@@ -45,6 +43,7 @@ use state_processing::signature_sets::{
 };
 use std::borrow::Cow;
 use std::collections::HashMap;
+use std::time::Duration;
 use strum::AsRefStr;
 use tree_hash::TreeHash;
 use types::consts::altair::SYNC_COMMITTEE_SUBNET_COUNT;
@@ -272,8 +271,12 @@ impl<T: BeaconChainTypes> VerifiedSyncContribution<T> {
         let contribution = &signed_aggregate.message.contribution;
         let subcommittee_index = contribution.subcommittee_index as usize;
 
-        // Ensure sync committee contribution is within the MAXIMUM_GOSSIP_CLOCK_DISPARITY allowance.
-        verify_propagation_slot_range(&chain.slot_clock, contribution)?;
+        // Ensure sync committee contribution is within the gossip clock disparity allowance.
+        verify_propagation_slot_range(
+            &chain.slot_clock,
+            contribution,
+            chain.gossip_clock_disparity(),
+        )?;
 
         // Validate subcommittee index.
         if contribution.subcommittee_index >= SYNC_COMMITTEE_SUBNET_COUNT {
@@ -425,10 +428,14 @@ impl VerifiedSyncCommitteeMessage {
         chain: &BeaconChain<T>,
     ) -> Result<Self, Error> {
         // Ensure sync committee message is for the current slot (within a
-        // MAXIMUM_GOSSIP_CLOCK_DISPARITY allowance).
+        // gossip clock disparity allowance).
         //
         // We do not queue future sync committee messages for later processing.
-        verify_propagation_slot_range(&chain.slot_clock, &sync_message)?;
+        verify_propagation_slot_range(
+            &chain.slot_clock,
+            &sync_message,
+            chain.gossip_clock_disparity(),
+        )?;
 
         // Ensure the `subnet_id` is valid for the given validator.
         let pubkey = chain
@@ -515,15 +522,16 @@ impl VerifiedSyncCommitteeMessage {
 /// Verify that the `sync_contribution` is within the acceptable gossip propagation range, with reference
 /// to the current slot of the `chain`.
 ///
-/// Accounts for `MAXIMUM_GOSSIP_CLOCK_DISPARITY`.
+/// Accounts for a `clock_disparity` allowance, usually `BeaconChain::gossip_clock_disparity`.
 pub fn verify_propagation_slot_range<S: SlotClock, U: SlotData>(
     slot_clock: &S,
     sync_contribution: &U,
+    clock_disparity: Duration,
 ) -> Result<(), Error> {
     let message_slot = sync_contribution.get_slot();
 
     let latest_permissible_slot = slot_clock
-        .now_with_future_tolerance(MAXIMUM_GOSSIP_CLOCK_DISPARITY)
+        .now_with_future_tolerance(clock_disparity)
         .ok_or(BeaconChainError::UnableToReadSlot)?;
     if message_slot > latest_permissible_slot {
         return Err(Error::FutureSlot {
@@ -533,7 +541,7 @@ pub fn verify_propagation_slot_range<S: SlotClock, U: SlotData>(
     }
 
     let earliest_permissible_slot = slot_clock
-        .now_with_past_tolerance(MAXIMUM_GOSSIP_CLOCK_DISPARITY)
+        .now_with_past_tolerance(clock_disparity)
         .ok_or(BeaconChainError::UnableToReadSlot)?;
 
     if message_slot < earliest_permissible_slot {
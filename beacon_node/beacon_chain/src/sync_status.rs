@@ -0,0 +1,68 @@
+//! Provides a consistent view of how far the chain is from the wall-clock, for the syncing API and
+//! reporting.
+//!
+//! Each of the values could be read separately, but the head may change between the reads (e.g.,
+//! reporting the distance from one head alongside the optimistic status of another). The head is
+//! read only once here and every value is derived from it.
+use crate::{BeaconChain, BeaconChainError as Error, BeaconChainTypes};
+use serde_derive::{Deserialize, Serialize};
+use slot_clock::SlotClock;
+use types::{Epoch, Hash256, Slot};
+
+/// A snapshot of the sync status of the chain, derived from a single read of the head.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SyncStatus {
+    pub head_block_root: Hash256,
+    pub head_slot: Slot,
+    pub finalized_epoch: Epoch,
+    /// The wall-clock slot, or the genesis slot prior to genesis.
+    pub current_slot: Slot,
+    /// The number of slots by which the head lags the wall-clock.
+    pub sync_distance: Slot,
+    /// Whether the blocks prior to the anchor, if any, have all been backfilled.
+    pub backfill_complete: bool,
+    /// Whether the execution payload of the head block has yet to be verified.
+    pub is_optimistic: bool,
+}
+
+impl<T: BeaconChainTypes> BeaconChain<T> {
+    /// Returns the sync status of the chain.
+    ///
+    /// Returns an error if the head can't be read within `BLOCK_PROCESSING_CACHE_LOCK_TIMEOUT`, or
+    /// if the head has been pruned from fork choice by a concurrent update.
+    pub fn sync_status(&self) -> Result<SyncStatus, Error> {
+        let head = self
+            .canonical_head
+            .try_cached_head()
+            .ok_or(Error::CanonicalHeadLockTimeout)?;
+        let head_block_root = head.head_block_root();
+        let head_slot = head.head_slot();
+
+        let current_slot = self
+            .slot_clock
+            .now_or_genesis()
+            .ok_or(Error::UnableToReadSlot)?;
+
+        let is_optimistic = self
+            .canonical_head
+            .fork_choice_read_lock()
+            .get_block_execution_status(&head_block_root)
+            .ok_or(Error::HeadMissingFromForkChoice(head_block_root))?
+            .is_optimistic();
+
+        let backfill_complete = self
+            .store
+            .get_anchor_info()
+            .map_or(true, |anchor| anchor.block_backfill_complete());
+
+        Ok(SyncStatus {
+            head_block_root,
+            head_slot,
+            finalized_epoch: head.finalized_checkpoint().epoch,
+            current_slot,
+            sync_distance: current_slot.saturating_sub(head_slot),
+            backfill_complete,
+            is_optimistic,
+        })
+    }
+}
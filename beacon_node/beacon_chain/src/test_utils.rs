@@ -1,6 +1,9 @@
 pub use crate::persisted_beacon_chain::PersistedBeaconChain;
 pub use crate::{
-    beacon_chain::{BEACON_CHAIN_DB_KEY, ETH1_CACHE_DB_KEY, FORK_CHOICE_DB_KEY, OP_POOL_DB_KEY},
+    beacon_chain::{
+        BEACON_CHAIN_DB_KEY, BLOCK_PROCESSING_CACHE_LOCK_TIMEOUT, ETH1_CACHE_DB_KEY,
+        FORK_CHOICE_DB_KEY, OP_POOL_DB_KEY,
+    },
     migrate::MigratorConfig,
     BeaconChainError, ProduceBlockVerification,
 };
@@ -28,7 +31,7 @@ use rand::Rng;
 use rand::SeedableRng;
 use rayon::prelude::*;
 use sensitive_url::SensitiveUrl;
-use slog::Logger;
+use slog::{o, Drain, Level, Logger, Never, OwnedKVList, Record};
 use slot_clock::TestingSlotClock;
 use state_processing::per_block_processing::compute_timestamp_at_slot;
 use state_processing::{
@@ -37,6 +40,7 @@ use state_processing::{
 };
 use std::borrow::Cow;
 use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
 use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Duration;
@@ -70,6 +74,47 @@ pub type BoxedMutator<E, Hot, Cold> = Box<
     ) -> BeaconChainBuilder<BaseHarnessType<E, Hot, Cold>>,
 >;
 
+/// A log drain which retains the level and message of every record, allowing tests to assert upon
+/// the logs of a harness.
+#[derive(Clone, Default)]
+pub struct CapturedLogs(Arc<std::sync::Mutex<Vec<(Level, String)>>>);
+
+impl CapturedLogs {
+    /// Returns a logger which writes to `self`.
+    pub fn logger(&self) -> Logger {
+        Logger::root(self.clone().fuse(), o!())
+    }
+
+    /// Returns the messages logged at `level`, oldest first.
+    pub fn messages(&self, level: Level) -> Vec<String> {
+        self.0
+            .lock()
+            .expect("captured logs lock poisoned")
+            .iter()
+            .filter(|(record_level, _)| *record_level == level)
+            .map(|(_, message)| message.clone())
+            .collect()
+    }
+
+    /// Discards the captured records.
+    pub fn clear(&self) {
+        self.0.lock().expect("captured logs lock poisoned").clear();
+    }
+}
+
+impl Drain for CapturedLogs {
+    type Ok = ();
+    type Err = Never;
+
+    fn log(&self, record: &Record, _: &OwnedKVList) -> Result<(), Never> {
+        self.0
+            .lock()
+            .expect("captured logs lock poisoned")
+            .push((record.level(), record.msg().to_string()));
+        Ok(())
+    }
+}
+
 pub type AddBlocksResult<E> = (
     HashMap<Slot, SignedBeaconBlockHash>,
     HashMap<Slot, BeaconStateHash>,
@@ -327,6 +372,15 @@ where
         self
     }
 
+    /// Use `log` for the beacon chain and its store rather than the logger of the test runtime.
+    ///
+    /// This must be called before the store is created.
+    pub fn logger(mut self, log: Logger) -> Self {
+        assert!(self.store.is_none(), "store already created");
+        self.log = log;
+        self
+    }
+
     pub fn execution_layer(mut self, urls: &[&str]) -> Self {
         assert!(
             self.execution_layer.is_none(),
@@ -372,6 +426,41 @@ where
         self
     }
 
+    /// As per `Self::mock_execution_layer`, but the execution layer is also connected to a mock
+    /// builder.
+    pub fn mock_execution_layer_with_builder(mut self) -> Self {
+        let spec = self.spec.clone().expect("cannot build without spec");
+        let mock = MockExecutionLayer::new_with_mock_builder(
+            self.runtime.task_executor.clone(),
+            spec.terminal_total_difficulty,
+            DEFAULT_TERMINAL_BLOCK,
+            spec.terminal_block_hash,
+            spec.terminal_block_hash_activation_epoch,
+            Some(JwtKey::from_slice(&DEFAULT_JWT_SECRET).unwrap()),
+        );
+        self.execution_layer = Some(mock.el.clone());
+        self.mock_execution_layer = Some(mock);
+        self
+    }
+
+    /// As per `Self::mock_execution_layer`, but the execution layer reads per-validator fee
+    /// recipients from the file at `path`.
+    pub fn mock_execution_layer_with_fee_recipient_file(mut self, path: PathBuf) -> Self {
+        let spec = self.spec.clone().expect("cannot build without spec");
+        let mock = MockExecutionLayer::new_with_fee_recipient_file(
+            self.runtime.task_executor.clone(),
+            spec.terminal_total_difficulty,
+            DEFAULT_TERMINAL_BLOCK,
+            spec.terminal_block_hash,
+            spec.terminal_block_hash_activation_epoch,
+            Some(JwtKey::from_slice(&DEFAULT_JWT_SECRET).unwrap()),
+            path,
+        );
+        self.execution_layer = Some(mock.el.clone());
+        self.mock_execution_layer = Some(mock);
+        self
+    }
+
     /// Instruct the mock execution engine to always return a "valid" response to any payload it is
     /// asked to execute.
     pub fn mock_execution_layer_all_payloads_valid(self) -> Self {
@@ -1345,8 +1434,9 @@ where
         // Building proofs
         let mut proofs = vec![];
         for i in 0..leaves.len() {
-            let (_, mut proof) =
-                tree.generate_proof(i, self.spec.deposit_contract_tree_depth as usize);
+            let (_, mut proof) = tree
+                .generate_proof(i, self.spec.deposit_contract_tree_depth as usize)
+                .expect("should generate proof");
             proof.push(Hash256::from_slice(&int_to_bytes32(leaves.len() as u64)));
             proofs.push(proof);
         }
@@ -1406,6 +1496,7 @@ where
             .chain
             .batch_verify_unaggregated_attestations_for_gossip(unaggregated.into_iter())
             .unwrap()
+            .0
         {
             let verified = result.unwrap();
             self.chain.add_to_naive_aggregation_pool(&verified).unwrap();
@@ -1415,6 +1506,7 @@ where
             .chain
             .batch_verify_aggregated_attestations_for_gossip(aggregated.into_iter())
             .unwrap()
+            .0
         {
             let verified = result.unwrap();
             self.chain
@@ -1424,6 +1516,23 @@ where
         }
     }
 
+    /// Remove the head from the snapshot cache, as if the state advance timer hadn't prepared its
+    /// state for block production.
+    pub fn evict_head_from_snapshot_cache(&self) {
+        let head = self.chain.head_snapshot();
+        self.chain
+            .snapshot_cache
+            .try_write_for(BLOCK_PROCESSING_CACHE_LOCK_TIMEOUT)
+            .unwrap()
+            .get_state_for_block_processing(
+                head.beacon_block_root,
+                head.beacon_block.slot(),
+                None,
+                &self.spec,
+            )
+            .expect("head should be in the snapshot cache");
+    }
+
     pub fn set_current_slot(&self, slot: Slot) {
         let current_slot = self.chain.slot().unwrap();
         let current_epoch = current_slot.epoch(E::slots_per_epoch());
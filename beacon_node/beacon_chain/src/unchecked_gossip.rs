@@ -0,0 +1,42 @@
+//! Imports blocks without the gossip checks, for fuzzers and fork-testing tools which inject blocks
+//! that equivocate or otherwise violate the gossip rules in order to simulate chain splits.
+//!
+//! Only the gossip-specific checks are skipped: the observation of proposers, which rejects a
+//! second block from a proposer in a slot, and the timeliness of the block relative to the
+//! wall-clock. The block is still subject to full signature verification and the state transition.
+//!
+//! This is never used by the node itself. It is only compiled with the `unchecked_gossip_blocks`
+//! feature, and is refused unless `ChainConfig::allow_unchecked_gossip_blocks` is set, which logs
+//! loudly at startup.
+use crate::{BeaconChain, BeaconChainError, BeaconChainTypes, BlockError};
+use slog::warn;
+use std::sync::Arc;
+use types::{Hash256, SignedBeaconBlock};
+
+impl<T: BeaconChainTypes> BeaconChain<T> {
+    /// Verify and import `block` without the gossip checks on its proposer and timeliness.
+    ///
+    /// Returns `Err(UncheckedGossipBlocksDisabled)` unless
+    /// `ChainConfig::allow_unchecked_gossip_blocks` is set.
+    pub async fn process_block_unchecked_gossip(
+        self: &Arc<Self>,
+        block: Arc<SignedBeaconBlock<T::EthSpec>>,
+    ) -> Result<Hash256, BlockError<T::EthSpec>> {
+        if !self.config.allow_unchecked_gossip_blocks {
+            return Err(BlockError::BeaconChainError(
+                BeaconChainError::UncheckedGossipBlocksDisabled,
+            ));
+        }
+
+        warn!(
+            self.log,
+            "Importing block without gossip checks";
+            "slot" => block.slot(),
+            "proposer_index" => block.message().proposer_index(),
+        );
+
+        // Unlike `verify_block_for_gossip`, importing a block directly doesn't consult or update
+        // the observed proposers, so equivocating blocks are imported.
+        self.process_block(block).await
+    }
+}
@@ -16,7 +16,7 @@ use std::marker::PhantomData;
 use std::str::Utf8Error;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use types::{
-    AttesterSlashing, BeaconBlockRef, BeaconState, ChainSpec, Epoch, EthSpec, Hash256,
+    AttesterSlashing, BeaconBlockRef, BeaconState, BitVector, ChainSpec, Epoch, EthSpec, Hash256,
     IndexedAttestation, ProposerSlashing, PublicKeyBytes, SignedAggregateAndProof,
     SignedContributionAndProof, Slot, SyncCommitteeMessage, VoluntaryExit,
 };
@@ -32,6 +32,32 @@ pub enum Error {
     InvalidUtf8(Utf8Error),
 }
 
+/// The inclusion of a validator's sync committee messages in blocks during an epoch.
+///
+/// A validator may hold several positions in a sync committee, each of which is counted.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct SyncCommitteePerformance {
+    /// The number of times the validator's sync signature was included in a sync aggregate.
+    pub included: usize,
+    /// The number of times the validator's sync signature was absent from a sync aggregate.
+    pub missed: usize,
+}
+
+impl SyncCommitteePerformance {
+    /// The number of sync signatures expected in the blocks of the epoch.
+    ///
+    /// Skipped slots are not counted, since there is no sync aggregate to be included in.
+    pub fn expected(&self) -> usize {
+        self.included + self.missed
+    }
+
+    /// The ratio of included to expected sync signatures, or `None` if none were expected.
+    pub fn hit_rate(&self) -> Option<f64> {
+        let expected = self.expected();
+        (expected > 0).then(|| self.included as f64 / expected as f64)
+    }
+}
+
 /// Contains data pertaining to one validator for one epoch.
 #[derive(Default)]
 struct EpochSummary {
@@ -72,6 +98,8 @@ struct EpochSummary {
     sync_committee_message_min_delay: Option<Duration>,
     /// The number of times a validator's sync signature was included in the sync aggregate.
     sync_signature_block_inclusions: usize,
+    /// The number of times a validator's sync signature was absent from the sync aggregate.
+    sync_signature_block_misses: usize,
     /// The number of times a validator's sync signature was aggregated into a sync contribution.
     sync_signature_contribution_inclusions: usize,
 
@@ -149,6 +177,17 @@ impl EpochSummary {
         self.sync_signature_block_inclusions += 1;
     }
 
+    pub fn register_sync_signature_block_miss(&mut self) {
+        self.sync_signature_block_misses += 1;
+    }
+
+    pub fn sync_committee_performance(&self) -> SyncCommitteePerformance {
+        SyncCommitteePerformance {
+            included: self.sync_signature_block_inclusions,
+            missed: self.sync_signature_block_misses,
+        }
+    }
+
     pub fn register_exit(&mut self) {
         self.exits += 1;
     }
@@ -205,6 +244,14 @@ impl MonitoredValidator {
         })
     }
 
+    /// Returns the sync committee performance for the given epoch, if any is recorded.
+    fn sync_committee_performance(&self, epoch: Epoch) -> Option<SyncCommitteePerformance> {
+        self.summaries
+            .read()
+            .get(&epoch)
+            .map(EpochSummary::sync_committee_performance)
+    }
+
     /// Maps `func` across the `self.summaries`.
     ///
     /// ## Warning
@@ -272,8 +319,9 @@ impl<T: EthSpec> ValidatorMonitor<T> {
         s
     }
 
-    /// Add some validators to `self` for additional monitoring.
-    fn add_validator_pubkey(&mut self, pubkey: PublicKeyBytes) {
+    /// Add a validator to `self` for additional monitoring. Does nothing if the validator is
+    /// already monitored.
+    pub fn add_validator_pubkey(&mut self, pubkey: PublicKeyBytes) {
         let index_opt = self
             .indices
             .iter()
@@ -404,6 +452,12 @@ impl<T: EthSpec> ValidatorMonitor<T> {
         // - One to account for the state advancing an epoch whilst generating the validator
         //     statuses.
         let prev_epoch = epoch - 2;
+
+        // Index the sync committee once, rather than scanning it for each monitored validator.
+        let sync_committee_pubkeys = summary
+            .sync_committee()
+            .map(|sync_committee| sync_committee.pubkeys.iter().collect::<HashSet<_>>());
+
         for (pubkey, monitored_validator) in self.validators.iter() {
             if let Some(i) = monitored_validator.index {
                 let i = i as usize;
@@ -540,25 +594,45 @@ impl<T: EthSpec> ValidatorMonitor<T> {
                 // Note: Unlike attestations, sync committee signatures must be included in the
                 // immediate next slot. Hence, num included sync aggregates for `state.epoch - 1`
                 // is available right after state transition to state.epoch.
+                //
+                // The summary is reported whenever signatures were expected, rather than only
+                // when the validator is in `summary.sync_committee()`. At the end of a sync
+                // committee period that is already the committee of the next period.
                 let current_epoch = epoch - 1;
-                if let Some(sync_committee) = summary.sync_committee() {
-                    if sync_committee.contains(pubkey) {
+                if let Some(performance) = monitored_validator
+                    .sync_committee_performance(current_epoch)
+                    .filter(|performance| performance.expected() > 0)
+                {
+                    let hit_rate = performance.hit_rate().unwrap_or_default();
+                    if performance.missed == 0 {
+                        info!(
+                            self.log,
+                            "Current epoch sync signatures";
+                            "included" => performance.included,
+                            "expected" => performance.expected(),
+                            "hit_rate" => hit_rate,
+                            "epoch" => current_epoch,
+                            "validator" => id,
+                        );
+                    } else {
+                        warn!(
+                            self.log,
+                            "Current epoch sync signatures";
+                            "included" => performance.included,
+                            "expected" => performance.expected(),
+                            "hit_rate" => hit_rate,
+                            "epoch" => current_epoch,
+                            "validator" => id,
+                        );
+                    }
+                }
+                if let Some(sync_committee_pubkeys) = &sync_committee_pubkeys {
+                    if sync_committee_pubkeys.contains(pubkey) {
                         metrics::set_int_gauge(
                             &metrics::VALIDATOR_MONITOR_VALIDATOR_IN_CURRENT_SYNC_COMMITTEE,
                             &[id],
                             1,
                         );
-                        let epoch_summary = monitored_validator.summaries.read();
-                        if let Some(summary) = epoch_summary.get(&current_epoch) {
-                            info!(
-                                self.log,
-                                "Current epoch sync signatures";
-                                "included" => summary.sync_signature_block_inclusions,
-                                "expected" => T::slots_per_epoch(),
-                                "epoch" => current_epoch,
-                                "validator" => id,
-                            );
-                        }
                     } else {
                         metrics::set_int_gauge(
                             &metrics::VALIDATOR_MONITOR_VALIDATOR_IN_CURRENT_SYNC_COMMITTEE,
@@ -638,6 +712,19 @@ impl<T: EthSpec> ValidatorMonitor<T> {
             .and_then(|pubkey| self.validators.get(pubkey))
     }
 
+    /// Returns the sync committee performance of a monitored validator in `epoch`.
+    ///
+    /// Returns `None` if the validator isn't monitored or nothing was recorded for `epoch`, which
+    /// may have been pruned.
+    pub fn get_sync_committee_performance(
+        &self,
+        validator_index: u64,
+        epoch: Epoch,
+    ) -> Option<SyncCommitteePerformance> {
+        self.get_validator(validator_index)
+            .and_then(|validator| validator.sync_committee_performance(epoch))
+    }
+
     /// Returns the number of validators monitored by `self`.
     pub fn num_validators(&self) -> usize {
         self.validators.len()
@@ -1153,35 +1240,65 @@ impl<T: EthSpec> ValidatorMonitor<T> {
     }
 
     /// Register that the `sync_aggregate` was included in a *valid* `BeaconBlock`.
+    ///
+    /// The `sync_committee_pubkeys` are those of the sync committee of the block's slot, and
+    /// `sync_committee_bits` are the bits of the aggregate. Monitored validators in the committee
+    /// whose bit isn't set are registered as having missed the block. The performance is recorded
+    /// against the epoch of the block, so the signatures of the last slot of a sync committee
+    /// period are attributed to the committee of the next period.
     pub fn register_sync_aggregate_in_block(
         &self,
         slot: Slot,
         beacon_block_root: Hash256,
-        participant_pubkeys: Vec<&PublicKeyBytes>,
+        sync_committee_pubkeys: &[PublicKeyBytes],
+        sync_committee_bits: &BitVector<T::SyncCommitteeSize>,
     ) {
         let epoch = slot.epoch(T::slots_per_epoch());
 
-        for validator_pubkey in participant_pubkeys {
+        for (validator_pubkey, included) in sync_committee_pubkeys
+            .iter()
+            .zip(sync_committee_bits.iter())
+        {
             if let Some(validator) = self.validators.get(validator_pubkey) {
                 let id = &validator.id;
 
-                metrics::inc_counter_vec(
-                    &metrics::VALIDATOR_MONITOR_SYNC_COMMITTEE_MESSAGE_IN_BLOCK_TOTAL,
-                    &["block", id],
-                );
+                if included {
+                    metrics::inc_counter_vec(
+                        &metrics::VALIDATOR_MONITOR_SYNC_COMMITTEE_MESSAGE_IN_BLOCK_TOTAL,
+                        &["block", id],
+                    );
 
-                info!(
-                    self.log,
-                    "Sync signature included in block";
-                    "head" => %beacon_block_root,
-                    "epoch" => %epoch,
-                    "slot" => %slot,
-                    "validator" => %id,
-                );
+                    info!(
+                        self.log,
+                        "Sync signature included in block";
+                        "head" => %beacon_block_root,
+                        "epoch" => %epoch,
+                        "slot" => %slot,
+                        "validator" => %id,
+                    );
 
-                validator.with_epoch_summary(epoch, |summary| {
-                    summary.register_sync_signature_block_inclusions();
-                });
+                    validator.with_epoch_summary(epoch, |summary| {
+                        summary.register_sync_signature_block_inclusions();
+                    });
+                } else {
+                    metrics::inc_counter_vec(
+                        &metrics::VALIDATOR_MONITOR_SYNC_COMMITTEE_MESSAGE_NOT_IN_BLOCK_TOTAL,
+                        &[id],
+                    );
+
+                    debug!(
+                        self.log,
+                        "Sync signature missing from block";
+                        "head" => %beacon_block_root,
+                        "epoch" => %epoch,
+                        "slot" => %slot,
+                        "validator" => %id,
+                    );
+
+                    validator.with_epoch_summary(epoch, |summary| {
+                        summary.register_sync_signature_block_miss();
+                    });
+                }
             }
         }
     }
@@ -1406,6 +1523,18 @@ impl<T: EthSpec> ValidatorMonitor<T> {
                         &[id],
                         summary.sync_signature_block_inclusions as i64,
                     );
+                    metrics::set_gauge_vec(
+                        &metrics::VALIDATOR_MONITOR_PREV_EPOCH_SYNC_SIGNATURE_BLOCK_MISSES,
+                        &[id],
+                        summary.sync_signature_block_misses as i64,
+                    );
+                    if let Some(hit_rate) = summary.sync_committee_performance().hit_rate() {
+                        metrics::set_float_gauge_vec(
+                            &metrics::VALIDATOR_MONITOR_PREV_EPOCH_SYNC_SIGNATURE_BLOCK_HIT_RATE,
+                            &[id],
+                            hit_rate,
+                        );
+                    }
 
                     /*
                      * Sync contributions
@@ -0,0 +1,239 @@
+//! Provides a file-backed list of validators for the `ValidatorMonitor`.
+//!
+//! The file contains one 0x-prefixed validator pubkey per line. Anything following a `#` is
+//! treated as a comment and blank lines are ignored. For compatibility with the original format
+//! of `--validator-monitor-file`, multiple pubkeys on the same line may be separated by commas.
+//!
+//! The file is re-read whenever its modification time or length changes, allowing operators to
+//! add validators without restarting the node. Validators are never removed from the monitor when
+//! they're removed from the file.
+use crate::validator_monitor::ValidatorMonitor;
+use crate::{BeaconChain, BeaconChainTypes};
+use parking_lot::RwLock;
+use slog::{debug, info, warn, Logger};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use task_executor::TaskExecutor;
+use tokio::time::sleep;
+use types::{EthSpec, PublicKeyBytes};
+
+/// The interval at which the validator monitor file is checked for modifications.
+pub const VALIDATOR_MONITOR_FILE_RELOAD_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The attributes of the file which are used to detect modifications.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct FileStamp {
+    modified: Option<SystemTime>,
+    len: u64,
+}
+
+impl FileStamp {
+    fn read(path: &Path) -> Result<Self, String> {
+        let metadata = fs::metadata(path)
+            .map_err(|e| format!("Unable to read metadata of {:?}: {:?}", path, e))?;
+        Ok(Self {
+            modified: metadata.modified().ok(),
+            len: metadata.len(),
+        })
+    }
+}
+
+/// A file containing the pubkeys of validators to be monitored.
+pub struct ValidatorMonitorFile {
+    path: PathBuf,
+    /// The stamp of the file when it was last read, if ever.
+    stamp: Option<FileStamp>,
+}
+
+impl ValidatorMonitorFile {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path, stamp: None }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Read all the valid pubkeys from the file, logging a warning for each malformed line.
+    ///
+    /// Returns an error if the file cannot be read at all.
+    pub fn read_pubkeys(&mut self, log: &Logger) -> Result<Vec<PublicKeyBytes>, String> {
+        let stamp = FileStamp::read(&self.path)?;
+        let contents = fs::read_to_string(&self.path)
+            .map_err(|e| format!("Unable to read {:?}: {:?}", self.path, e))?;
+        self.stamp = Some(stamp);
+
+        let (pubkeys, errors) = parse_pubkeys(&contents);
+        for (line, error) in errors {
+            warn!(
+                log,
+                "Skipping malformed validator monitor pubkey";
+                "error" => error,
+                "line" => line,
+                "path" => ?self.path,
+            );
+        }
+
+        Ok(pubkeys)
+    }
+
+    /// Re-read the file if it has been modified since it was last read, registering any new
+    /// pubkeys with the `validator_monitor`.
+    ///
+    /// Returns the number of validators which were newly added to the monitor.
+    pub fn reload<E: EthSpec>(
+        &mut self,
+        validator_monitor: &RwLock<ValidatorMonitor<E>>,
+        log: &Logger,
+    ) -> Result<usize, String> {
+        if self.stamp == Some(FileStamp::read(&self.path)?) {
+            return Ok(0);
+        }
+
+        let pubkeys = self.read_pubkeys(log)?;
+
+        let mut validator_monitor = validator_monitor.write();
+        let num_before = validator_monitor.num_validators();
+        for pubkey in pubkeys {
+            validator_monitor.add_validator_pubkey(pubkey);
+        }
+        let num_added = validator_monitor.num_validators() - num_before;
+
+        debug!(
+            log,
+            "Reloaded validator monitor file";
+            "new_validators" => num_added,
+            "total_validators" => validator_monitor.num_validators(),
+            "path" => ?self.path,
+        );
+
+        Ok(num_added)
+    }
+}
+
+/// Parse the `contents` of a validator monitor file.
+///
+/// Returns the valid pubkeys along with a `(line_number, error)` pair for each malformed entry.
+/// Line numbers start at 1.
+pub fn parse_pubkeys(contents: &str) -> (Vec<PublicKeyBytes>, Vec<(usize, String)>) {
+    let mut pubkeys = vec![];
+    let mut errors = vec![];
+
+    for (i, line) in contents.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("");
+        for entry in line.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            match PublicKeyBytes::from_str(entry) {
+                Ok(pubkey) => pubkeys.push(pubkey),
+                Err(e) => errors.push((i + 1, e)),
+            }
+        }
+    }
+
+    (pubkeys, errors)
+}
+
+/// Spawns a routine which reloads the validator monitor `file` every
+/// `VALIDATOR_MONITOR_FILE_RELOAD_INTERVAL`.
+pub fn spawn_validator_monitor_file_reloader<T: BeaconChainTypes>(
+    executor: TaskExecutor,
+    chain: Arc<BeaconChain<T>>,
+    mut file: ValidatorMonitorFile,
+    log: Logger,
+) {
+    info!(
+        log,
+        "Watching validator monitor file";
+        "reload_interval_secs" => VALIDATOR_MONITOR_FILE_RELOAD_INTERVAL.as_secs(),
+        "path" => ?file.path(),
+    );
+
+    executor.spawn(
+        async move {
+            loop {
+                sleep(VALIDATOR_MONITOR_FILE_RELOAD_INTERVAL).await;
+
+                if let Err(e) = file.reload(&chain.validator_monitor, &log) {
+                    warn!(
+                        log,
+                        "Unable to reload validator monitor file";
+                        "error" => e,
+                    );
+                }
+            }
+        },
+        "validator_monitor_file",
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use types::{Keypair, MainnetEthSpec};
+
+    fn null_logger() -> Logger {
+        Logger::root(slog::Discard, slog::o!())
+    }
+
+    fn pubkey_line() -> String {
+        format!("{}\n", Keypair::random().pk.compress())
+    }
+
+    #[test]
+    fn parse_reports_malformed_lines() {
+        let contents = format!(
+            "# monitored validators\n{}\n0xdeadbeef\n{} # with comment\n",
+            pubkey_line().trim_end(),
+            pubkey_line().trim_end()
+        );
+        let (pubkeys, errors) = parse_pubkeys(&contents);
+        assert_eq!(pubkeys.len(), 2);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, 3);
+    }
+
+    #[test]
+    fn parse_comma_separated() {
+        let contents = format!(
+            "{},{}\n",
+            pubkey_line().trim_end(),
+            pubkey_line().trim_end()
+        );
+        let (pubkeys, errors) = parse_pubkeys(&contents);
+        assert_eq!(pubkeys.len(), 2);
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn reload_picks_up_new_pubkeys() {
+        let log = null_logger();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("validators.txt");
+        fs::write(&path, format!("{}{}", pubkey_line(), pubkey_line())).unwrap();
+
+        let mut file = ValidatorMonitorFile::new(path.clone());
+        let pubkeys = file.read_pubkeys(&log).unwrap();
+        let validator_monitor = RwLock::new(ValidatorMonitor::<MainnetEthSpec>::new(
+            pubkeys,
+            false,
+            log.clone(),
+        ));
+        assert_eq!(validator_monitor.read().num_validators(), 2);
+
+        // An unmodified file is not re-read.
+        assert_eq!(file.reload(&validator_monitor, &log), Ok(0));
+
+        fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(pubkey_line().as_bytes())
+            .unwrap();
+
+        assert_eq!(file.reload(&validator_monitor, &log), Ok(1));
+        assert_eq!(validator_monitor.read().num_validators(), 3);
+    }
+}
@@ -3,7 +3,7 @@ use crate::{BeaconChainTypes, BeaconStore};
 use ssz::{Decode, Encode};
 use std::collections::HashMap;
 use std::convert::TryInto;
-use store::{DBColumn, Error as StoreError, StoreItem};
+use store::{DBColumn, Error as StoreError, KeyValueStore, StoreItem};
 use types::{BeaconState, Hash256, PublicKey, PublicKeyBytes};
 
 /// Provides a mapping of `validator_index -> validator_publickey`.
@@ -45,6 +45,24 @@ impl<T: BeaconChainTypes> ValidatorPubkeyCache<T> {
         Ok(cache)
     }
 
+    /// Create a new public key cache using the keys in `state.validators`, replacing any cache
+    /// which was persisted to `store`.
+    pub fn rebuild(
+        state: &BeaconState<T::EthSpec>,
+        store: BeaconStore<T>,
+    ) -> Result<Self, BeaconChainError> {
+        // Delete every persisted key first, so that none remain beyond those in `state`.
+        for validator_index in 0.. {
+            let key = DatabasePubkey::key_for_index(validator_index);
+            if !store.item_exists::<DatabasePubkey>(&key)? {
+                break;
+            }
+            store.hot_db.delete::<DatabasePubkey>(&key)?;
+        }
+
+        Self::new(state, store)
+    }
+
     /// Load the pubkey cache from the given on-disk database.
     pub fn load_from_store(store: BeaconStore<T>) -> Result<Self, BeaconChainError> {
         let mut pubkeys = vec![];
@@ -101,37 +119,65 @@ impl<T: BeaconChainTypes> ValidatorPubkeyCache<T> {
         self.indices.reserve(validator_keys.len());
 
         for pubkey in validator_keys {
-            let i = self.pubkeys.len();
+            let decompressed = (&pubkey)
+                .try_into()
+                .map_err(BeaconChainError::InvalidValidatorPubkeyBytes)?;
+            self.push(pubkey, decompressed)?;
+        }
 
-            if self.indices.contains_key(&pubkey) {
-                return Err(BeaconChainError::DuplicateValidatorPublicKey);
-            }
+        Ok(())
+    }
+
+    /// Adds zero or more validators to `self`, whose public keys have already been decompressed.
+    ///
+    /// The caller is responsible for ensuring that each decompressed key matches its bytes.
+    pub fn import_decompressed<I>(&mut self, validator_keys: I) -> Result<(), BeaconChainError>
+    where
+        I: Iterator<Item = (PublicKeyBytes, PublicKey)> + ExactSizeIterator,
+    {
+        self.pubkey_bytes.reserve(validator_keys.len());
+        self.pubkeys.reserve(validator_keys.len());
+        self.indices.reserve(validator_keys.len());
 
-            // The item is written to disk _before_ it is written into
-            // the local struct.
-            //
-            // This means that a pubkey cache read from disk will always be equivalent to or
-            // _later than_ the cache that was running in the previous instance of Lighthouse.
-            //
-            // The motivation behind this ordering is that we do not want to have states that
-            // reference a pubkey that is not in our cache. However, it's fine to have pubkeys
-            // that are never referenced in a state.
-            self.store
-                .put_item(&DatabasePubkey::key_for_index(i), &DatabasePubkey(pubkey))?;
-
-            self.pubkeys.push(
-                (&pubkey)
-                    .try_into()
-                    .map_err(BeaconChainError::InvalidValidatorPubkeyBytes)?,
-            );
-            self.pubkey_bytes.push(pubkey);
-
-            self.indices.insert(pubkey, i);
+        for (pubkey, decompressed) in validator_keys {
+            self.push(pubkey, decompressed)?;
         }
 
         Ok(())
     }
 
+    /// Adds a single validator to `self`, with the next index.
+    fn push(
+        &mut self,
+        pubkey: PublicKeyBytes,
+        decompressed: PublicKey,
+    ) -> Result<(), BeaconChainError> {
+        let i = self.pubkeys.len();
+
+        if self.indices.contains_key(&pubkey) {
+            return Err(BeaconChainError::DuplicateValidatorPublicKey);
+        }
+
+        // The item is written to disk _before_ it is written into
+        // the local struct.
+        //
+        // This means that a pubkey cache read from disk will always be equivalent to or
+        // _later than_ the cache that was running in the previous instance of Lighthouse.
+        //
+        // The motivation behind this ordering is that we do not want to have states that
+        // reference a pubkey that is not in our cache. However, it's fine to have pubkeys
+        // that are never referenced in a state.
+        self.store
+            .put_item(&DatabasePubkey::key_for_index(i), &DatabasePubkey(pubkey))?;
+
+        self.pubkeys.push(decompressed);
+        self.pubkey_bytes.push(pubkey);
+
+        self.indices.insert(pubkey, i);
+
+        Ok(())
+    }
+
     /// Get the public key for a validator with index `i`.
     pub fn get(&self, i: usize) -> Option<&PublicKey> {
         self.pubkeys.get(i)
@@ -0,0 +1,510 @@
+//! Provides a chain-level entry point for the `SignedValidatorRegistrationData` messages that
+//! validator clients send for use with the builder API.
+//!
+//! Registrations are verified against the validator pubkey cache, the preferences they contain
+//! (fee recipient, gas limit) are retained so they can be used during proposer preparation and,
+//! finally, the registrations are forwarded to the connected builder (if any).
+//!
+//! The registrations are persisted to disk, so that the fee recipients of registered validators are
+//! used for the first proposals after a restart, rather than only once the validator clients have
+//! re-registered.
+use crate::beacon_chain::VALIDATOR_PUBKEY_CACHE_LOCK_TIMEOUT;
+use crate::{metrics, BeaconChain, BeaconChainError, BeaconChainTypes};
+use slog::debug;
+use slot_clock::SlotClock;
+use ssz::{Decode, Encode};
+use ssz_derive::{Decode, Encode};
+use std::collections::HashMap;
+use std::sync::Arc;
+use store::{DBColumn, Error as StoreError, StoreItem};
+use types::{
+    Address, Epoch, EthSpec, Hash256, ProposerPreparationData, PublicKeyBytes, Signature,
+    SignedRoot, SignedValidatorRegistrationData, ValidatorRegistrationData,
+};
+
+/// A registration which hasn't been received for this many epochs is stale. Stale registrations
+/// are pruned before the registrations are persisted, and ignored when they're loaded.
+///
+/// Validator clients re-send their registrations every epoch, so a stale registration most likely
+/// belongs to a validator which is no longer connected to this node.
+pub const VALIDATOR_REGISTRATION_RETENTION_EPOCHS: u64 = 256;
+
+/// The maximum number of registrations retained for pubkeys which aren't known to the pubkey cache.
+///
+/// Registrations for unknown validators are accepted so that validators with pending deposits are
+/// registered with the builder, however any party can sign them. Capping them bounds the memory
+/// (and disk) used by the `ValidatorRegistrations`.
+pub const MAX_UNKNOWN_VALIDATOR_REGISTRATIONS: usize = 1_024;
+
+/// 32-byte key for accessing the `ValidatorRegistrations`. All zero because it has its own column.
+pub const VALIDATOR_REGISTRATIONS_DB_KEY: Hash256 = Hash256::zero();
+
+/// The preferences expressed by a validator in its most recent valid registration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidatorPreferences {
+    pub fee_recipient: Address,
+    pub gas_limit: u64,
+    pub timestamp: u64,
+    /// The index of the validator, if it was known to the pubkey cache at the time of
+    /// registration.
+    pub validator_index: Option<u64>,
+    /// The epoch in which the registration was most recently received.
+    pub last_registered_epoch: Epoch,
+}
+
+/// The result of processing a single `SignedValidatorRegistrationData`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValidatorRegistrationOutcome {
+    /// The registration is valid and belongs to a known validator.
+    Registered { validator_index: u64 },
+    /// The registration is valid, however the pubkey is not known to the pubkey cache. This is
+    /// expected for validators with pending deposits, so the registration is still retained and
+    /// forwarded to the builder.
+    UnknownValidator,
+    /// A registration with a later timestamp has already been received for this pubkey. The
+    /// registration was ignored.
+    Outdated { known_timestamp: u64 },
+    /// The pubkey in the registration is not a valid BLS public key.
+    InvalidPubkey,
+    /// The signature on the registration is invalid.
+    InvalidSignature,
+    /// The pubkey is not known to the pubkey cache and `MAX_UNKNOWN_VALIDATOR_REGISTRATIONS` are
+    /// already retained. The registration was ignored.
+    TooManyUnknownValidators,
+}
+
+impl ValidatorRegistrationOutcome {
+    /// Returns `true` if the registration was retained and should be forwarded to the builder.
+    pub fn is_accepted(&self) -> bool {
+        matches!(
+            self,
+            ValidatorRegistrationOutcome::Registered { .. }
+                | ValidatorRegistrationOutcome::UnknownValidator
+        )
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            ValidatorRegistrationOutcome::Registered { .. } => "registered",
+            ValidatorRegistrationOutcome::UnknownValidator => "unknown_validator",
+            ValidatorRegistrationOutcome::Outdated { .. } => "outdated",
+            ValidatorRegistrationOutcome::InvalidPubkey => "invalid_pubkey",
+            ValidatorRegistrationOutcome::InvalidSignature => "invalid_signature",
+            ValidatorRegistrationOutcome::TooManyUnknownValidators => "too_many_unknown_validators",
+        }
+    }
+
+    /// Returns the reason the registration was rejected, if it was invalid or could not be
+    /// retained.
+    ///
+    /// Outdated registrations are not considered failures, since they are superseded by a
+    /// registration which has already been accepted.
+    pub fn failure_reason(&self) -> Option<&'static str> {
+        match self {
+            ValidatorRegistrationOutcome::Registered { .. }
+            | ValidatorRegistrationOutcome::UnknownValidator
+            | ValidatorRegistrationOutcome::Outdated { .. } => None,
+            ValidatorRegistrationOutcome::InvalidPubkey => Some("invalid pubkey"),
+            ValidatorRegistrationOutcome::InvalidSignature => Some("invalid signature"),
+            ValidatorRegistrationOutcome::TooManyUnknownValidators => {
+                Some("too many registrations for unknown validators")
+            }
+        }
+    }
+}
+
+/// Stores the latest valid registration preferences for each pubkey.
+///
+/// Unlike the proposer preparation data in the `ExecutionLayer`, entries in this map only expire
+/// after `VALIDATOR_REGISTRATION_RETENTION_EPOCHS`, unless replaced by a newer registration for the
+/// same pubkey.
+#[derive(Default)]
+pub struct ValidatorRegistrations {
+    preferences: HashMap<PublicKeyBytes, ValidatorPreferences>,
+    /// The registrations from which the `preferences` were taken, which are forwarded to builders
+    /// connected at runtime.
+    registrations: HashMap<PublicKeyBytes, SignedValidatorRegistrationData>,
+    /// The number of `preferences` without a `validator_index`.
+    num_unknown_validators: usize,
+}
+
+impl ValidatorRegistrations {
+    /// Returns the preferences registered for `pubkey`, if any.
+    pub fn get(&self, pubkey: &PublicKeyBytes) -> Option<&ValidatorPreferences> {
+        self.preferences.get(pubkey)
+    }
+
+    /// Returns the number of registrations retained for validators without a known index.
+    pub fn num_unknown_validators(&self) -> usize {
+        self.num_unknown_validators
+    }
+
+    /// Store the `preferences` from `registration`, replacing any existing entry for its pubkey.
+    pub fn insert(
+        &mut self,
+        registration: SignedValidatorRegistrationData,
+        preferences: ValidatorPreferences,
+    ) {
+        let pubkey = registration.message.pubkey;
+        if preferences.validator_index.is_none() {
+            self.num_unknown_validators += 1;
+        }
+        if let Some(replaced) = self.preferences.insert(pubkey, preferences) {
+            if replaced.validator_index.is_none() {
+                self.num_unknown_validators -= 1;
+            }
+        }
+        self.registrations.insert(pubkey, registration);
+    }
+
+    /// Returns the latest registration of each pubkey.
+    pub fn signed_registrations(&self) -> Vec<SignedValidatorRegistrationData> {
+        self.registrations.values().cloned().collect()
+    }
+
+    /// Returns the proposer preparation data of each registered validator with a known index.
+    pub fn proposer_preparation_data(&self) -> Vec<ProposerPreparationData> {
+        self.preferences
+            .values()
+            .filter_map(|preferences| {
+                Some(ProposerPreparationData {
+                    validator_index: preferences.validator_index?,
+                    fee_recipient: preferences.fee_recipient,
+                })
+            })
+            .collect()
+    }
+
+    /// Remove the registrations which are stale as of `current_epoch`.
+    pub fn prune(&mut self, current_epoch: Epoch) {
+        let registrations = &mut self.registrations;
+        let num_unknown_validators = &mut self.num_unknown_validators;
+        self.preferences.retain(|pubkey, preferences| {
+            let retain = !is_stale(preferences.last_registered_epoch, current_epoch);
+            if !retain {
+                registrations.remove(pubkey);
+                if preferences.validator_index.is_none() {
+                    *num_unknown_validators -= 1;
+                }
+            }
+            retain
+        });
+    }
+
+    /// Restore the registrations from disk, ignoring those which are stale as of `current_epoch`.
+    ///
+    /// The index of each validator is looked up with `validator_index`, since it may have become
+    /// known since the registration was received.
+    pub fn from_persisted(
+        persisted: PersistedValidatorRegistrations,
+        current_epoch: Epoch,
+        validator_index: impl Fn(&PublicKeyBytes) -> Option<usize>,
+    ) -> Self {
+        let mut registrations = Self::default();
+        for entry in persisted.registrations {
+            if is_stale(entry.last_registered_epoch, current_epoch) {
+                continue;
+            }
+
+            let message = entry.message;
+            let validator_index = validator_index(&message.pubkey).map(|i| i as u64);
+            if validator_index.is_none()
+                && registrations.num_unknown_validators >= MAX_UNKNOWN_VALIDATOR_REGISTRATIONS
+            {
+                continue;
+            }
+
+            let preferences = ValidatorPreferences {
+                fee_recipient: message.fee_recipient,
+                gas_limit: message.gas_limit,
+                timestamp: message.timestamp,
+                validator_index,
+                last_registered_epoch: entry.last_registered_epoch,
+            };
+            registrations.insert(
+                SignedValidatorRegistrationData {
+                    message,
+                    signature: entry.signature,
+                },
+                preferences,
+            );
+        }
+        registrations
+    }
+
+    pub fn len(&self) -> usize {
+        self.preferences.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.preferences.is_empty()
+    }
+}
+
+impl<T: BeaconChainTypes> BeaconChain<T> {
+    /// Process a batch of validator registrations received from validator clients.
+    ///
+    /// Each registration is verified against the pubkey cache and, if valid, its preferences are
+    /// stored for use by `Self::prepare_beacon_proposer`. All accepted registrations are then
+    /// forwarded to the builder, if one is configured.
+    ///
+    /// Returns one `ValidatorRegistrationOutcome` per registration, in the order they were
+    /// supplied. An error is only returned if the registrations could not be processed at all or
+    /// if forwarding them to the builder failed. In the latter case the registrations have still
+    /// been stored locally.
+    pub async fn process_validator_registrations(
+        self: &Arc<Self>,
+        registrations: Vec<SignedValidatorRegistrationData>,
+    ) -> Result<Vec<ValidatorRegistrationOutcome>, BeaconChainError> {
+        let execution_layer = self
+            .execution_layer
+            .clone()
+            .ok_or(BeaconChainError::ExecutionLayerMissing)?;
+        // Registrations may be sent prior to genesis, treat them as being received at genesis.
+        let current_epoch = self
+            .slot_clock
+            .now_or_genesis()
+            .ok_or(BeaconChainError::UnableToReadSlot)?
+            .epoch(T::EthSpec::slots_per_epoch());
+
+        // Signature verification is CPU-intensive, so avoid doing it on the core executor.
+        let chain = self.clone();
+        let (outcomes, registrations) = self
+            .spawn_blocking_handle(
+                move || {
+                    let outcomes = chain
+                        .verify_and_store_validator_registrations(&registrations, current_epoch)?;
+                    Ok::<_, BeaconChainError>((outcomes, registrations))
+                },
+                "process_validator_registrations",
+            )
+            .await??;
+
+        // Inform the execution layer of the new fee recipients so that they're used immediately,
+        // rather than waiting for the next round of proposer preparation.
+        let preparation_data = registrations
+            .iter()
+            .zip(outcomes.iter())
+            .filter_map(|(registration, outcome)| match outcome {
+                ValidatorRegistrationOutcome::Registered { validator_index } => {
+                    Some(ProposerPreparationData {
+                        validator_index: *validator_index,
+                        fee_recipient: registration.message.fee_recipient,
+                    })
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        execution_layer
+            .update_proposer_preparation(current_epoch, &preparation_data)
+            .await;
+
+        let accepted = registrations
+            .into_iter()
+            .zip(outcomes.iter())
+            .filter(|(_, outcome)| outcome.is_accepted())
+            .map(|(registration, _)| registration)
+            .collect::<Vec<_>>();
+
+        debug!(
+            self.log,
+            "Processed validator registrations";
+            "total" => outcomes.len(),
+            "accepted" => accepted.len(),
+            "known_validators" => preparation_data.len(),
+        );
+
+        if !accepted.is_empty() {
+            execution_layer
+                .register_validators(&accepted)
+                .await
+                .map_err(BeaconChainError::ValidatorRegistrationForwardingFailed)?;
+        }
+
+        Ok(outcomes)
+    }
+
+    /// Returns the fee recipient from the latest registration of the validator with
+    /// `validator_index`, if any.
+    pub fn registered_fee_recipient(
+        &self,
+        validator_index: u64,
+    ) -> Result<Option<Address>, BeaconChainError> {
+        let pubkey = match self.validator_pubkey_bytes(validator_index as usize)? {
+            Some(pubkey) => pubkey,
+            None => return Ok(None),
+        };
+        Ok(self
+            .validator_registrations
+            .read()
+            .get(&pubkey)
+            .map(|preferences| preferences.fee_recipient))
+    }
+
+    /// Persists the validator registrations to disk, after pruning those which are stale.
+    pub fn persist_validator_registrations(&self) -> Result<(), BeaconChainError> {
+        if self.config.read_only {
+            return Ok(());
+        }
+
+        let current_epoch = self
+            .slot_clock
+            .now_or_genesis()
+            .ok_or(BeaconChainError::UnableToReadSlot)?
+            .epoch(T::EthSpec::slots_per_epoch());
+        let mut registrations = self.validator_registrations.write();
+        registrations.prune(current_epoch);
+        let persisted = PersistedValidatorRegistrations::from(&*registrations);
+        drop(registrations);
+
+        self.store
+            .put_item(&VALIDATOR_REGISTRATIONS_DB_KEY, &persisted)
+            .map_err(Into::into)
+    }
+
+    /// Verify each of the `registrations`, storing the preferences of those which are valid.
+    ///
+    /// This function performs signature verification and should not be run on the core executor.
+    fn verify_and_store_validator_registrations(
+        &self,
+        registrations: &[SignedValidatorRegistrationData],
+        current_epoch: Epoch,
+    ) -> Result<Vec<ValidatorRegistrationOutcome>, BeaconChainError> {
+        let domain = self.spec.get_builder_domain();
+        let pubkey_cache = self
+            .validator_pubkey_cache
+            .try_read_for(VALIDATOR_PUBKEY_CACHE_LOCK_TIMEOUT)
+            .ok_or(BeaconChainError::ValidatorPubkeyCacheLockTimeout)?;
+
+        let mut outcomes = Vec::with_capacity(registrations.len());
+        for registration in registrations {
+            let message = &registration.message;
+            let validator_index = pubkey_cache.get_index(&message.pubkey);
+
+            // Use the decompressed key from the cache where possible, it's much faster.
+            let decompressed;
+            let pubkey = match validator_index.and_then(|i| pubkey_cache.get(i)) {
+                Some(pubkey) => pubkey,
+                None => match message.pubkey.decompress() {
+                    Ok(pubkey) => {
+                        decompressed = pubkey;
+                        &decompressed
+                    }
+                    Err(_) => {
+                        outcomes.push(ValidatorRegistrationOutcome::InvalidPubkey);
+                        continue;
+                    }
+                },
+            };
+
+            if !registration
+                .signature
+                .verify(pubkey, message.signing_root(domain))
+            {
+                outcomes.push(ValidatorRegistrationOutcome::InvalidSignature);
+                continue;
+            }
+
+            let mut stored = self.validator_registrations.write();
+            match stored.get(&message.pubkey) {
+                Some(known) if known.timestamp > message.timestamp => {
+                    outcomes.push(ValidatorRegistrationOutcome::Outdated {
+                        known_timestamp: known.timestamp,
+                    });
+                    continue;
+                }
+                // Replacing an existing registration doesn't grow the map.
+                Some(_) => (),
+                None if validator_index.is_none()
+                    && stored.num_unknown_validators() >= MAX_UNKNOWN_VALIDATOR_REGISTRATIONS =>
+                {
+                    outcomes.push(ValidatorRegistrationOutcome::TooManyUnknownValidators);
+                    continue;
+                }
+                None => (),
+            }
+
+            let validator_index = validator_index.map(|i| i as u64);
+            stored.insert(
+                registration.clone(),
+                ValidatorPreferences {
+                    fee_recipient: message.fee_recipient,
+                    gas_limit: message.gas_limit,
+                    timestamp: message.timestamp,
+                    validator_index,
+                    last_registered_epoch: current_epoch,
+                },
+            );
+
+            if let Some(validator_index) = validator_index {
+                outcomes.push(ValidatorRegistrationOutcome::Registered { validator_index });
+            } else {
+                debug!(
+                    self.log,
+                    "Registration for unknown validator";
+                    "msg" => "this is expected for validators with pending deposits",
+                    "pubkey" => ?message.pubkey,
+                );
+                outcomes.push(ValidatorRegistrationOutcome::UnknownValidator);
+            }
+        }
+
+        for outcome in &outcomes {
+            metrics::inc_counter_vec(&metrics::VALIDATOR_REGISTRATIONS, &[outcome.as_str()]);
+        }
+
+        Ok(outcomes)
+    }
+}
+
+/// Returns `true` if a registration last received in `last_registered_epoch` is stale.
+fn is_stale(last_registered_epoch: Epoch, current_epoch: Epoch) -> bool {
+    last_registered_epoch + VALIDATOR_REGISTRATION_RETENTION_EPOCHS < current_epoch
+}
+
+/// SSZ-encodable form of a registration and the epoch in which it was last received.
+#[derive(Encode, Decode)]
+struct SszValidatorRegistration {
+    message: ValidatorRegistrationData,
+    signature: Signature,
+    last_registered_epoch: Epoch,
+}
+
+/// Wrapper around the `ValidatorRegistrations` for persistence to disk.
+#[derive(Encode, Decode)]
+pub struct PersistedValidatorRegistrations {
+    registrations: Vec<SszValidatorRegistration>,
+}
+
+impl From<&ValidatorRegistrations> for PersistedValidatorRegistrations {
+    fn from(registrations: &ValidatorRegistrations) -> Self {
+        Self {
+            registrations: registrations
+                .registrations
+                .iter()
+                .filter_map(|(pubkey, registration)| {
+                    let preferences = registrations.preferences.get(pubkey)?;
+                    Some(SszValidatorRegistration {
+                        message: registration.message.clone(),
+                        signature: registration.signature.clone(),
+                        last_registered_epoch: preferences.last_registered_epoch,
+                    })
+                })
+                .collect(),
+        }
+    }
+}
+
+impl StoreItem for PersistedValidatorRegistrations {
+    fn db_column() -> DBColumn {
+        DBColumn::ValidatorRegistrations
+    }
+
+    fn as_store_bytes(&self) -> Vec<u8> {
+        self.as_ssz_bytes()
+    }
+
+    fn from_store_bytes(bytes: &[u8]) -> Result<Self, StoreError> {
+        Self::from_ssz_bytes(bytes).map_err(Into::into)
+    }
+}
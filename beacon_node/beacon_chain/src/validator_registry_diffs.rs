@@ -0,0 +1,190 @@
+//! Provides a feed of changes to the lifecycle of validators.
+//!
+//! At each epoch transition of the head, the validator registry of the first state of the new epoch
+//! is compared to that of the first state of the previous epoch, recording the validators which
+//! became eligible for activation, were scheduled for activation, initiated an exit, became
+//! withdrawable or were slashed.
+//!
+//! The first state of an epoch includes the block at the first slot of the epoch, if any, so a
+//! change made by that block is attributed to the epoch of the block rather than the epoch after.
+use crate::{BeaconChain, BeaconChainError, BeaconChainTypes};
+use eth2::types::SseValidatorRegistryUpdate;
+use std::collections::VecDeque;
+use types::{BeaconState, ChainSpec, Epoch, EthSpec, Hash256, Validator};
+
+/// The number of epochs of diffs retained by `ValidatorRegistryDiffs`.
+pub const VALIDATOR_REGISTRY_DIFFS_CAPACITY: usize = 8;
+
+/// Compute the changes to the validator registry from `prev_state` to `state`, the first states of
+/// consecutive epochs.
+///
+/// The registries are compared in place, without cloning either of them.
+pub fn compute_validator_registry_diff<E: EthSpec>(
+    prev_state: &BeaconState<E>,
+    state: &BeaconState<E>,
+    state_root: Hash256,
+    spec: &ChainSpec,
+) -> SseValidatorRegistryUpdate {
+    let mut diff = SseValidatorRegistryUpdate {
+        epoch: state.current_epoch(),
+        state: state_root,
+        activation_eligible: vec![],
+        activations_queued: vec![],
+        exits_initiated: vec![],
+        withdrawable: vec![],
+        slashed: vec![],
+    };
+
+    let (prev_epoch, epoch) = (prev_state.current_epoch(), state.current_epoch());
+    let far_future = spec.far_future_epoch;
+    for (index, validator) in state.validators().iter().enumerate() {
+        let prev = prev_state.validators().get(index);
+        let newly_set = |field: fn(&Validator) -> Epoch| {
+            field(validator) != far_future && prev.map_or(true, |v| field(v) == far_future)
+        };
+        let index = index as u64;
+
+        if newly_set(|v| v.activation_eligibility_epoch) {
+            diff.activation_eligible.push(index);
+        }
+        if newly_set(|v| v.activation_epoch) {
+            diff.activations_queued.push(index);
+        }
+        if newly_set(|v| v.exit_epoch) {
+            diff.exits_initiated.push(index);
+        }
+        if validator.is_withdrawable_at(epoch)
+            && !prev.map_or(false, |v| v.is_withdrawable_at(prev_epoch))
+        {
+            diff.withdrawable.push(index);
+        }
+        if validator.slashed && !prev.map_or(false, |v| v.slashed) {
+            diff.slashed.push(index);
+        }
+    }
+
+    diff
+}
+
+/// The diffs of the most recent epochs, ordered by epoch.
+pub struct ValidatorRegistryDiffs {
+    diffs: VecDeque<SseValidatorRegistryUpdate>,
+    capacity: usize,
+}
+
+impl Default for ValidatorRegistryDiffs {
+    fn default() -> Self {
+        Self::new(VALIDATOR_REGISTRY_DIFFS_CAPACITY)
+    }
+}
+
+impl ValidatorRegistryDiffs {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            diffs: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Record `diff`, replacing any diff for the same or a later epoch, since those were computed
+    /// on a chain which is no longer canonical.
+    pub fn insert(&mut self, diff: SseValidatorRegistryUpdate) {
+        while self
+            .diffs
+            .back()
+            .map_or(false, |last| last.epoch >= diff.epoch)
+        {
+            self.diffs.pop_back();
+        }
+        if self.diffs.len() >= self.capacity {
+            self.diffs.pop_front();
+        }
+        if self.capacity > 0 {
+            self.diffs.push_back(diff);
+        }
+    }
+
+    pub fn get(&self, epoch: Epoch) -> Option<&SseValidatorRegistryUpdate> {
+        self.diffs.iter().find(|diff| diff.epoch == epoch)
+    }
+}
+
+impl<T: BeaconChainTypes> BeaconChain<T> {
+    /// Returns the changes to the validator registry during the epoch prior to `epoch`, if `epoch`
+    /// is one of the most recent epochs of the canonical chain.
+    pub fn validator_registry_diff(&self, epoch: Epoch) -> Option<SseValidatorRegistryUpdate> {
+        self.validator_registry_diffs.lock().get(epoch).cloned()
+    }
+
+    /// Compute and record the diff for the epoch of the new head `head_state`, returning it.
+    ///
+    /// The first state of the previous epoch is loaded from the database, as is the first state of
+    /// the head's epoch unless it is the head state.
+    pub(crate) fn update_validator_registry_diff(
+        &self,
+        head_state: &BeaconState<T::EthSpec>,
+        head_state_root: Hash256,
+    ) -> Result<Option<SseValidatorRegistryUpdate>, BeaconChainError> {
+        let slots_per_epoch = T::EthSpec::slots_per_epoch();
+        let epoch = head_state.current_epoch();
+        if epoch == 0 {
+            return Ok(None);
+        }
+
+        let load_first_state = |epoch: Epoch| -> Result<_, BeaconChainError> {
+            let slot = epoch.start_slot(slots_per_epoch);
+            let state_root = *head_state.get_state_root(slot)?;
+            let state = self
+                .store
+                .get_state(&state_root, Some(slot))?
+                .ok_or(BeaconChainError::MissingBeaconState(state_root))?;
+            Ok((state, state_root))
+        };
+
+        let (prev_state, _) = load_first_state(epoch - 1)?;
+        let diff = if head_state.slot() == epoch.start_slot(slots_per_epoch) {
+            compute_validator_registry_diff(&prev_state, head_state, head_state_root, &self.spec)
+        } else {
+            let (state, state_root) = load_first_state(epoch)?;
+            compute_validator_registry_diff(&prev_state, &state, state_root, &self.spec)
+        };
+
+        self.validator_registry_diffs.lock().insert(diff.clone());
+        Ok(Some(diff))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diff(epoch: u64) -> SseValidatorRegistryUpdate {
+        SseValidatorRegistryUpdate {
+            epoch: Epoch::new(epoch),
+            state: Hash256::from_low_u64_be(epoch),
+            activation_eligible: vec![],
+            activations_queued: vec![],
+            exits_initiated: vec![],
+            withdrawable: vec![],
+            slashed: vec![],
+        }
+    }
+
+    #[test]
+    fn insert_evicts_oldest_and_replaces_reorged() {
+        let mut diffs = ValidatorRegistryDiffs::new(3);
+        for epoch in 1..=4 {
+            diffs.insert(diff(epoch));
+        }
+        assert!(diffs.get(Epoch::new(1)).is_none());
+        assert_eq!(diffs.get(Epoch::new(4)), Some(&diff(4)));
+
+        // A diff computed after a re-org replaces those for the same and later epochs.
+        let mut reorged = diff(3);
+        reorged.slashed.push(1);
+        diffs.insert(reorged.clone());
+        assert_eq!(diffs.get(Epoch::new(3)), Some(&reorged));
+        assert!(diffs.get(Epoch::new(4)).is_none());
+        assert_eq!(diffs.get(Epoch::new(2)), Some(&diff(2)));
+    }
+}
@@ -0,0 +1,143 @@
+//! Tracks the pressure on the batch verification of gossip attestations, so that the beacon
+//! processor can shed load when verification is falling behind.
+//!
+//! Pressure is measured by the number of batches being verified concurrently, and by a moving
+//! average of the time taken to verify each batch. The pressure is high if either exceeds its
+//! threshold in the `ChainConfig`.
+use crate::{metrics, BeaconChain, BeaconChainTypes};
+use parking_lot::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+/// The weight of the latest batch in the moving average of the verification latency.
+pub const VERIFICATION_LATENCY_EWMA_WEIGHT: f64 = 0.2;
+
+/// The pressure on attestation verification at a point in time.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct VerificationPressure {
+    /// The number of batches currently being verified.
+    pub in_flight_batches: usize,
+    /// The exponentially weighted moving average of the time taken to verify a batch.
+    pub latency: Duration,
+    /// Whether either of the above exceeds its threshold.
+    pub is_high: bool,
+}
+
+#[derive(Default)]
+pub struct VerificationPressureTracker {
+    in_flight_batches: AtomicUsize,
+    /// The moving average of the batch verification latency in seconds, or `None` if no batch
+    /// has been verified.
+    latency_secs: Mutex<Option<f64>>,
+}
+
+impl VerificationPressureTracker {
+    /// Record the start of the verification of a batch. The verification ends when the returned
+    /// guard is dropped.
+    pub fn start_batch(&self) -> BatchVerificationGuard<'_> {
+        let in_flight_batches = self.in_flight_batches.fetch_add(1, Ordering::Relaxed) + 1;
+        metrics::set_gauge(
+            &metrics::ATTESTATION_VERIFICATION_IN_FLIGHT_BATCHES,
+            in_flight_batches as i64,
+        );
+        BatchVerificationGuard {
+            tracker: self,
+            started: Instant::now(),
+        }
+    }
+
+    fn finish_batch(&self, latency: Duration) {
+        let in_flight_batches = self
+            .in_flight_batches
+            .fetch_sub(1, Ordering::Relaxed)
+            .saturating_sub(1);
+        metrics::set_gauge(
+            &metrics::ATTESTATION_VERIFICATION_IN_FLIGHT_BATCHES,
+            in_flight_batches as i64,
+        );
+        self.observe_latency(latency);
+    }
+
+    fn observe_latency(&self, latency: Duration) {
+        let latency = latency.as_secs_f64();
+        let mut latency_secs = self.latency_secs.lock();
+        let average = latency_secs.map_or(latency, |average| {
+            average + VERIFICATION_LATENCY_EWMA_WEIGHT * (latency - average)
+        });
+        *latency_secs = Some(average);
+        metrics::set_float_gauge(&metrics::ATTESTATION_VERIFICATION_LATENCY_EWMA, average);
+    }
+
+    pub fn in_flight_batches(&self) -> usize {
+        self.in_flight_batches.load(Ordering::Relaxed)
+    }
+
+    /// The moving average of the batch verification latency, or zero if no batch has been
+    /// verified.
+    pub fn latency(&self) -> Duration {
+        self.latency_secs
+            .lock()
+            .map_or(Duration::ZERO, Duration::from_secs_f64)
+    }
+}
+
+/// Marks the verification of a batch as in flight until dropped.
+pub struct BatchVerificationGuard<'a> {
+    tracker: &'a VerificationPressureTracker,
+    started: Instant,
+}
+
+impl Drop for BatchVerificationGuard<'_> {
+    fn drop(&mut self) {
+        self.tracker.finish_batch(self.started.elapsed());
+    }
+}
+
+impl<T: BeaconChainTypes> BeaconChain<T> {
+    /// Returns the current pressure on the batch verification of gossip attestations.
+    pub fn verification_pressure(&self) -> VerificationPressure {
+        let in_flight_batches = self.verification_pressure.in_flight_batches();
+        let latency = self.verification_pressure.latency();
+        let is_high = in_flight_batches > self.config.verification_pressure_max_batches
+            || latency > Duration::from_millis(self.config.verification_pressure_latency_ms);
+        VerificationPressure {
+            in_flight_batches,
+            latency,
+            is_high,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn in_flight_batches() {
+        let tracker = VerificationPressureTracker::default();
+        let first = tracker.start_batch();
+        let second = tracker.start_batch();
+        assert_eq!(tracker.in_flight_batches(), 2);
+
+        drop(first);
+        assert_eq!(tracker.in_flight_batches(), 1);
+        drop(second);
+        assert_eq!(tracker.in_flight_batches(), 0);
+    }
+
+    #[test]
+    fn latency_moving_average() {
+        let tracker = VerificationPressureTracker::default();
+        assert_eq!(tracker.latency(), Duration::ZERO);
+
+        // The first batch sets the average.
+        tracker.observe_latency(Duration::from_millis(100));
+        assert_eq!(tracker.latency(), Duration::from_millis(100));
+
+        // Later batches move the average towards their latency.
+        tracker.observe_latency(Duration::from_millis(600));
+        assert_eq!(tracker.latency(), Duration::from_millis(200));
+        tracker.observe_latency(Duration::from_millis(0));
+        assert_eq!(tracker.latency(), Duration::from_millis(160));
+    }
+}
@@ -1,11 +1,13 @@
 #![cfg(not(debug_assertions))]
 
 use beacon_chain::test_utils::{AttestationStrategy, BeaconChainHarness, BlockStrategy};
-use beacon_chain::{StateSkipConfig, WhenSlotSkipped};
+use beacon_chain::{BeaconChainError, ChainConfig, StateSkipConfig, WhenSlotSkipped};
 use lazy_static::lazy_static;
 use std::sync::Arc;
 use tree_hash::TreeHash;
-use types::{AggregateSignature, EthSpec, Keypair, MainnetEthSpec, RelativeEpoch, Slot};
+use types::{
+    AggregateSignature, EthSpec, Keypair, MainnetEthSpec, MinimalEthSpec, RelativeEpoch, Slot,
+};
 
 pub const VALIDATOR_COUNT: usize = 16;
 
@@ -218,3 +220,85 @@ async fn early_attester_cache_old_request() {
         .unwrap();
     assert_eq!(attested_block.slot(), attest_slot);
 }
+
+/// Ensures that attestations to slots beyond the historical roots of the head state are only
+/// produced when `ChainConfig::allow_ancient_attestation_production` is set.
+#[tokio::test]
+async fn ancient_attestation_production() {
+    let slots_per_historical_root = MinimalEthSpec::slots_per_historical_root() as u64;
+    let num_blocks_produced = slots_per_historical_root + MinimalEthSpec::slots_per_epoch() * 2;
+
+    for allow_ancient_attestation_production in [false, true] {
+        let harness = BeaconChainHarness::builder(MinimalEthSpec)
+            .default_spec()
+            .chain_config(ChainConfig {
+                allow_ancient_attestation_production,
+                ..ChainConfig::default()
+            })
+            .keypairs(KEYPAIRS[..].to_vec())
+            .fresh_ephemeral_store()
+            .mock_execution_layer()
+            .build();
+        harness.advance_slot();
+
+        // Build the chain without attestations, so that it doesn't finalize and the request slot
+        // is not pre-finalization.
+        harness
+            .extend_chain(
+                num_blocks_produced as usize,
+                BlockStrategy::OnCanonicalHead,
+                AttestationStrategy::SomeValidators(vec![]),
+            )
+            .await;
+
+        let chain = &harness.chain;
+        let head_slot = chain.head_snapshot().beacon_state.slot();
+        let lowest_permissible_slot = head_slot - slots_per_historical_root;
+        let request_slot = lowest_permissible_slot - 1;
+
+        let result = chain.produce_unaggregated_attestation(request_slot, 0);
+        if !allow_ancient_attestation_production {
+            assert!(matches!(
+                result,
+                Err(BeaconChainError::AttestingToAncientSlot {
+                    lowest_permissible_slot: lowest,
+                    request_slot: slot,
+                }) if lowest == lowest_permissible_slot && slot == request_slot
+            ));
+            continue;
+        }
+
+        let attestation = result.expect("should produce ancient attestation");
+        let mut state = chain
+            .state_at_slot(request_slot, StateSkipConfig::WithStateRoots)
+            .unwrap();
+        state
+            .build_committee_cache(RelativeEpoch::Current, &chain.spec)
+            .unwrap();
+        let committee_len = state
+            .get_beacon_committee(request_slot, 0)
+            .unwrap()
+            .committee
+            .len();
+        let request_epoch = request_slot.epoch(MinimalEthSpec::slots_per_epoch());
+        let block_root_at = |slot| {
+            chain
+                .block_root_at_slot(slot, WhenSlotSkipped::Prev)
+                .unwrap()
+                .unwrap()
+        };
+
+        let data = &attestation.data;
+        assert_eq!(data.slot, request_slot);
+        assert_eq!(data.index, 0);
+        assert_eq!(data.beacon_block_root, block_root_at(request_slot));
+        assert_eq!(data.source, state.current_justified_checkpoint());
+        assert_eq!(data.target.epoch, request_epoch);
+        assert_eq!(
+            data.target.root,
+            block_root_at(request_epoch.start_slot(MinimalEthSpec::slots_per_epoch()))
+        );
+        assert_eq!(attestation.aggregation_bits.len(), committee_len);
+        assert_eq!(attestation.aggregation_bits.num_set_bits(), 0);
+    }
+}
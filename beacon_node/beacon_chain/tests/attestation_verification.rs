@@ -1,22 +1,26 @@
 #![cfg(not(debug_assertions))]
 
 use beacon_chain::{
-    attestation_verification::Error as AttnError,
+    attestation_verification::{BatchVerificationSummary, Error as AttnError, VerifiedAttestation},
     test_utils::{
         test_spec, AttestationStrategy, BeaconChainHarness, BlockStrategy, EphemeralHarnessType,
     },
-    BeaconChain, BeaconChainError, BeaconChainTypes, WhenSlotSkipped,
+    BeaconChain, BeaconChainError, BeaconChainTypes, ChainConfig, WhenSlotSkipped,
 };
 use int_to_bytes::int_to_bytes32;
 use lazy_static::lazy_static;
+use proto_array::Block as ProtoBlock;
 use state_processing::{
-    per_block_processing::errors::AttestationValidationError, per_slot_processing,
+    common::get_indexed_attestation, per_block_processing::errors::AttestationValidationError,
+    per_slot_processing, state_advance::complete_state_advance,
 };
+use std::sync::Arc;
 use tree_hash::TreeHash;
 use types::{
-    test_utils::generate_deterministic_keypair, AggregateSignature, Attestation, BeaconStateError,
-    BitList, Epoch, EthSpec, Hash256, Keypair, MainnetEthSpec, SecretKey, SelectionProof,
-    SignedAggregateAndProof, Slot, SubnetId, Unsigned,
+    test_utils::generate_deterministic_keypair, AggregateSignature, Attestation,
+    AttestationShufflingId, BeaconState, BeaconStateError, BitList, Epoch, EthSpec, Hash256,
+    Keypair, MainnetEthSpec, RelativeEpoch, SecretKey, SelectionProof, SignedAggregateAndProof,
+    SignedBeaconBlockHash, Slot, SubnetId, Unsigned,
 };
 
 pub type E = MainnetEthSpec;
@@ -334,7 +338,7 @@ impl GossipTester {
         /*
          * Batch verification
          */
-        let mut results = self
+        let (mut results, _) = self
             .harness
             .chain
             .batch_verify_aggregated_attestations_for_gossip(
@@ -377,7 +381,7 @@ impl GossipTester {
         /*
          * Batch verification
          */
-        let mut results = self
+        let (mut results, _) = self
             .harness
             .chain
             .batch_verify_unaggregated_attestations_for_gossip(
@@ -1189,3 +1193,532 @@ async fn verify_attestation_for_gossip_doppelganger_detection() {
         .validator_has_been_observed(epoch, index)
         .expect("should check if gossip aggregator was observed"));
 }
+
+#[tokio::test]
+async fn unaggregated_attestation_without_subnet() {
+    let harness = get_harness(VALIDATOR_COUNT);
+
+    harness
+        .extend_chain(
+            MainnetEthSpec::slots_per_epoch() as usize * 3 - 1,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::AllValidators,
+        )
+        .await;
+
+    // Advance into a slot where there have not been blocks or attestations produced.
+    harness.advance_slot();
+
+    let (valid_attestation, _, _, _, subnet_id) =
+        get_valid_unaggregated_attestation(&harness.chain);
+
+    let verified = harness
+        .chain
+        .verify_unaggregated_attestation_for_gossip(&valid_attestation, None)
+        .expect("should verify attestation without a subnet");
+
+    assert_eq!(verified.subnet_id(), subnet_id);
+
+    let committee_len = harness
+        .chain
+        .head_snapshot()
+        .beacon_state
+        .get_beacon_committee(valid_attestation.data.slot, valid_attestation.data.index)
+        .expect("should get committee")
+        .committee
+        .len();
+    assert_eq!(verified.committee_len(), committee_len);
+}
+
+/// Verifies attestations from two forks whose shufflings for the same slot are decided by different
+/// blocks, changing the head part-way through the slot.
+///
+/// If `clear_committee_memo`, the committee memo is cleared before each attestation is verified so
+/// that all committees are read from the shuffling cache.
+async fn verify_attestations_on_forks_with_head_change(
+    clear_committee_memo: bool,
+) -> Vec<Result<u64, String>> {
+    let harness = get_harness(VALIDATOR_COUNT);
+    let spec = &harness.chain.spec;
+    let slots_per_epoch = E::slots_per_epoch();
+    let attestation_slot = Slot::new(2 * slots_per_epoch);
+    let all_validators = (0..VALIDATOR_COUNT).collect::<Vec<_>>();
+
+    // The forks diverge in the first epoch, so the shuffling of `attestation_slot` is decided by a
+    // different block on each fork. Fork A has the majority of validators so it becomes the head.
+    let genesis_state = harness.get_current_state();
+    let (validators_a, validators_b): (Vec<usize>, Vec<usize>) =
+        (0..VALIDATOR_COUNT).partition(|i| i % 4 != 0);
+    let mut forks = harness
+        .add_blocks_on_multiple_chains(vec![
+            (
+                genesis_state.clone(),
+                (1..attestation_slot.as_u64()).map(Slot::new).collect(),
+                validators_a,
+            ),
+            (
+                genesis_state,
+                (2..attestation_slot.as_u64()).map(Slot::new).collect(),
+                validators_b,
+            ),
+        ])
+        .await;
+    let (_, _, head_b, state_b) = forks.pop().unwrap();
+    let (_, _, head_a, state_a) = forks.pop().unwrap();
+
+    let attestations_for = |state: &BeaconState<E>, head: SignedBeaconBlockHash| {
+        let mut state = state.clone();
+        let state_root = state.update_tree_hash_cache().unwrap();
+        complete_state_advance(&mut state, Some(state_root), attestation_slot, spec).unwrap();
+        state.build_all_committee_caches(spec).unwrap();
+        let state_root = state.update_tree_hash_cache().unwrap();
+        harness
+            .make_unaggregated_attestations(
+                &all_validators,
+                &state,
+                state_root,
+                head,
+                attestation_slot,
+            )
+            .remove(0)
+    };
+    let attestations_a = attestations_for(&state_a, head_a);
+    let attestations_b = attestations_for(&state_b, head_b);
+    let half = attestations_a.len() / 2;
+
+    harness.set_current_slot(attestation_slot);
+    assert_eq!(harness.head_block_root(), head_a.into());
+
+    let verify = |attestations: &[(Attestation<E>, SubnetId)]| {
+        attestations
+            .iter()
+            .map(|(attestation, subnet_id)| {
+                if clear_committee_memo {
+                    harness.chain.committee_memo.clear();
+                }
+                harness
+                    .chain
+                    .verify_unaggregated_attestation_for_gossip(attestation, Some(*subnet_id))
+                    .map(|verified| verified.indexed_attestation().attesting_indices[0])
+                    .map_err(|e| format!("{:?}", e))
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let mut results = verify(&attestations_a[..half]);
+    results.extend(verify(&attestations_b));
+
+    // Import a block on fork A in the attestation slot, changing the head mid-slot.
+    let (head_a, _, state_a) = harness
+        .add_block_at_slot(attestation_slot, state_a)
+        .await
+        .unwrap();
+    assert_eq!(harness.head_block_root(), head_a.into());
+    let attestations_a = attestations_for(&state_a, head_a);
+    results.extend(verify(&attestations_a[half..]));
+    results.extend(verify(&attestations_b));
+
+    assert!(
+        results[..half].iter().all(Result::is_ok),
+        "attestations on the head should be accepted"
+    );
+    results
+}
+
+#[tokio::test]
+async fn committee_memo_decisions_match_shuffling_cache() {
+    let without_memo = verify_attestations_on_forks_with_head_change(true).await;
+    let with_memo = verify_attestations_on_forks_with_head_change(false).await;
+    assert_eq!(with_memo, without_memo);
+}
+
+/// Ensure that attestations to a block which is in the early attester cache, but not yet in fork
+/// choice, are deferred rather than rejected.
+#[tokio::test]
+async fn attestation_to_importing_block_is_deferred() {
+    let harness = get_harness(VALIDATOR_COUNT);
+    let chain = &harness.chain;
+
+    // Extend the chain to the last slot of an epoch, so that the next block is the target of the
+    // attestations to it.
+    harness
+        .extend_chain(
+            MainnetEthSpec::slots_per_epoch() as usize * 3 - 1,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::AllValidators,
+        )
+        .await;
+    harness.advance_slot();
+
+    let block_slot = chain.slot().unwrap();
+    assert_eq!(block_slot.as_u64() % E::slots_per_epoch(), 0);
+    let head = chain.head_snapshot();
+    let (block, state) = harness
+        .make_block(head.beacon_state.clone(), block_slot)
+        .await;
+    let block_root = block.canonical_root();
+
+    // Add the block to the early attester cache, without importing it to fork choice.
+    let head_proto_block = chain
+        .canonical_head
+        .fork_choice_read_lock()
+        .get_block(&head.beacon_block_root)
+        .unwrap();
+    let proto_block = ProtoBlock {
+        slot: block_slot,
+        root: block_root,
+        parent_root: Some(head.beacon_block_root),
+        state_root: block.state_root(),
+        target_root: block_root,
+        current_epoch_shuffling_id: AttestationShufflingId::new(
+            block_root,
+            &state,
+            RelativeEpoch::Current,
+        )
+        .unwrap(),
+        next_epoch_shuffling_id: AttestationShufflingId::new(
+            block_root,
+            &state,
+            RelativeEpoch::Next,
+        )
+        .unwrap(),
+        justified_checkpoint: state.current_justified_checkpoint(),
+        finalized_checkpoint: state.finalized_checkpoint(),
+        execution_status: head_proto_block.execution_status,
+    };
+    chain
+        .early_attester_cache
+        .add_head_block(
+            block_root,
+            Arc::new(block),
+            proto_block,
+            &state,
+            &chain.spec,
+        )
+        .unwrap();
+
+    // The attestation is produced from the early attester cache.
+    let mut attestation = chain
+        .produce_unaggregated_attestation(block_slot, 0)
+        .unwrap();
+    assert_eq!(attestation.data.beacon_block_root, block_root);
+    assert_eq!(attestation.data.target.root, block_root);
+    attestation.aggregation_bits.set(0, true).unwrap();
+
+    assert!(matches!(
+        chain.verify_unaggregated_attestation_for_gossip(&attestation, None),
+        Err(AttnError::DeferredUnknownHead { beacon_block_root }) if beacon_block_root == block_root
+    ));
+
+    let aggregate = SignedAggregateAndProof::from_aggregate(
+        0,
+        attestation.clone(),
+        None,
+        &KEYPAIRS[0].sk,
+        &state.fork(),
+        chain.genesis_validators_root,
+        &chain.spec,
+    );
+    assert!(matches!(
+        chain.verify_aggregated_attestation_for_gossip(&aggregate),
+        Err(AttnError::DeferredUnknownHead { beacon_block_root }) if beacon_block_root == block_root
+    ));
+
+    // Once the block is no longer in the early attester cache, the attestation is for an unknown
+    // block.
+    chain.early_attester_cache.clear();
+    assert!(matches!(
+        chain.verify_unaggregated_attestation_for_gossip(&attestation, None),
+        Err(AttnError::UnknownHeadBlock { beacon_block_root }) if beacon_block_root == block_root
+    ));
+}
+
+/// Aggregates verified on gossip have their indexed attestations cached for block import.
+#[tokio::test]
+async fn block_import_uses_indexed_attestation_cache() {
+    let harness = get_harness(VALIDATOR_COUNT);
+
+    // The blocks imported here include attestations which were never seen on gossip.
+    harness
+        .extend_chain(
+            2,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::AllValidators,
+        )
+        .await;
+    let cold_stats = harness.chain.indexed_attestation_cache_stats();
+    assert_eq!(cold_stats.hits, 0);
+    assert!(cold_stats.misses > 0);
+
+    harness.advance_slot();
+
+    let (valid_attestation, _, _, _, _) = get_valid_unaggregated_attestation(&harness.chain);
+    let (valid_aggregate, _, _) =
+        get_valid_aggregated_attestation(&harness.chain, valid_attestation);
+    let attestation = valid_aggregate.message.aggregate.clone();
+
+    let verified_aggregate = harness
+        .chain
+        .verify_aggregated_attestation_for_gossip(&valid_aggregate)
+        .expect("should verify aggregate attestation");
+    let expected = verified_aggregate.indexed_attestation().clone();
+    harness
+        .chain
+        .add_to_block_inclusion_pool(&verified_aggregate)
+        .expect("should add aggregate to pool");
+    assert_eq!(
+        harness.chain.indexed_attestation_cache.get(&attestation),
+        Some(expected.clone())
+    );
+
+    let slot = harness.chain.slot().unwrap() + 1;
+    let (_, block, state) = harness
+        .add_block_at_slot(slot, harness.get_current_state())
+        .await
+        .unwrap();
+    assert!(block
+        .message()
+        .body()
+        .attestations()
+        .iter()
+        .any(|included| *included == attestation));
+
+    // The aggregate was served from the cache, and matches its indexed form computed from the
+    // block's state.
+    let stats = harness.chain.indexed_attestation_cache_stats();
+    assert_eq!(stats.hits, 2);
+    let committee = state
+        .get_beacon_committee(attestation.data.slot, attestation.data.index)
+        .unwrap();
+    assert_eq!(
+        get_indexed_attestation(committee.committee, &attestation).unwrap(),
+        expected
+    );
+    assert_eq!(
+        harness
+            .chain
+            .indexed_attestation_for_block(&state, &attestation)
+            .unwrap(),
+        expected
+    );
+    assert_eq!(harness.chain.indexed_attestation_cache_stats().hits, 3);
+}
+
+/// The total of the counter named `name` across all tests, or 0 if it hasn't been registered.
+fn counter_value(name: &str) -> u64 {
+    lighthouse_metrics::gather()
+        .iter()
+        .find(|family| family.get_name() == name)
+        .map_or(0, |family| {
+            family
+                .get_metric()
+                .iter()
+                .map(|metric| metric.get_counter().get_value() as u64)
+                .sum()
+        })
+}
+
+#[tokio::test]
+async fn batch_with_invalid_signature_falls_back() {
+    let harness = get_harness(VALIDATOR_COUNT);
+    harness
+        .extend_chain(
+            1,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::AllValidators,
+        )
+        .await;
+    harness.advance_slot();
+
+    let head = harness.chain.head_snapshot();
+    let mut attestations = harness
+        .get_unaggregated_attestations(
+            &AttestationStrategy::AllValidators,
+            &head.beacon_state,
+            head.beacon_state_root(),
+            head.beacon_block_root,
+            harness.chain.slot().unwrap(),
+        )
+        .into_iter()
+        .flatten()
+        .take(4)
+        .collect::<Vec<_>>();
+    assert_eq!(attestations.len(), 4);
+
+    // Give the first attestation the signature of another validator.
+    attestations[0].0.signature = attestations[1].0.signature.clone();
+
+    let fallbacks = "beacon_attestation_processing_batch_unagg_fallbacks_total";
+    let salvaged = "beacon_attestation_processing_batch_unagg_salvaged_total";
+    let fallbacks_before = counter_value(fallbacks);
+    let salvaged_before = counter_value(salvaged);
+
+    let (results, summary) = harness
+        .chain
+        .batch_verify_unaggregated_attestations_for_gossip(
+            attestations
+                .iter()
+                .map(|(attestation, subnet_id)| (attestation, Some(*subnet_id))),
+        )
+        .unwrap();
+    assert!(matches!(results[0], Err(AttnError::InvalidSignature)));
+    assert!(results[1..].iter().all(Result::is_ok));
+    assert_eq!(
+        summary,
+        BatchVerificationSummary {
+            batch_size: 4,
+            signature_sets: 4,
+            fallback: true,
+            salvaged: 3,
+        }
+    );
+
+    // Other tests may also fall back, so the counters are only known to have increased.
+    assert!(counter_value(fallbacks) > fallbacks_before);
+    assert!(counter_value(salvaged) >= salvaged_before + 3);
+}
+
+#[tokio::test]
+async fn verification_pressure_rises_and_falls() {
+    let harness = BeaconChainHarness::builder(MainnetEthSpec)
+        .default_spec()
+        .chain_config(ChainConfig {
+            verification_pressure_max_batches: 2,
+            verification_pressure_latency_ms: 60_000,
+            ..ChainConfig::default()
+        })
+        .keypairs(KEYPAIRS[0..VALIDATOR_COUNT].to_vec())
+        .fresh_ephemeral_store()
+        .mock_execution_layer()
+        .build();
+    harness.advance_slot();
+    harness
+        .extend_chain(
+            1,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::AllValidators,
+        )
+        .await;
+    harness.advance_slot();
+
+    let chain = &harness.chain;
+    assert!(!chain.verification_pressure().is_high);
+
+    // Simulate a storm of batches being verified concurrently.
+    let batches = (0..3)
+        .map(|_| chain.verification_pressure.start_batch())
+        .collect::<Vec<_>>();
+    let pressure = chain.verification_pressure();
+    assert_eq!(pressure.in_flight_batches, 3);
+    assert!(pressure.is_high);
+
+    drop(batches);
+    let pressure = chain.verification_pressure();
+    assert_eq!(pressure.in_flight_batches, 0);
+    assert!(!pressure.is_high);
+
+    // A real batch is counted whilst in flight and contributes to the latency.
+    let head = chain.head_snapshot();
+    let attestations = harness
+        .get_unaggregated_attestations(
+            &AttestationStrategy::AllValidators,
+            &head.beacon_state,
+            head.beacon_state_root(),
+            head.beacon_block_root,
+            chain.slot().unwrap(),
+        )
+        .into_iter()
+        .flatten()
+        .take(4)
+        .collect::<Vec<_>>();
+    let (results, _) = chain
+        .batch_verify_unaggregated_attestations_for_gossip(
+            attestations
+                .iter()
+                .map(|(attestation, subnet_id)| (attestation, Some(*subnet_id))),
+        )
+        .unwrap();
+    assert!(results.iter().all(Result::is_ok));
+
+    let pressure = chain.verification_pressure();
+    assert_eq!(pressure.in_flight_batches, 0);
+    assert!(pressure.latency > std::time::Duration::ZERO);
+    assert!(!pressure.is_high);
+}
+
+#[tokio::test]
+async fn aggregate_from_non_aggregator_is_accepted_for_inclusion_only() {
+    let harness = get_harness(VALIDATOR_COUNT);
+    harness
+        .extend_chain(
+            2,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::AllValidators,
+        )
+        .await;
+    harness.advance_slot();
+    let chain = &harness.chain;
+
+    let (valid_attestation, _, _, _, _) = get_valid_unaggregated_attestation(chain);
+    let (valid_aggregate, _, _) = get_valid_aggregated_attestation(chain, valid_attestation);
+    let attestation = valid_aggregate.message.aggregate.clone();
+
+    let (non_aggregator_index, non_aggregator_sk) = get_non_aggregator(chain, &attestation);
+    let sign_aggregate = |sk: &SecretKey| {
+        SignedAggregateAndProof::from_aggregate(
+            non_aggregator_index as u64,
+            attestation.clone(),
+            None,
+            sk,
+            &chain.canonical_head.cached_head().head_fork(),
+            chain.genesis_validators_root,
+            &chain.spec,
+        )
+    };
+    let aggregate = sign_aggregate(&non_aggregator_sk);
+    let is_invalid_selection_proof = |result: Result<_, AttnError>| {
+        matches!(
+            result,
+            Err(AttnError::InvalidSelectionProof { aggregator_index })
+            if aggregator_index == non_aggregator_index as u64
+        )
+    };
+    assert!(is_invalid_selection_proof(
+        chain
+            .verify_aggregated_attestation_for_gossip(&aggregate)
+            .map(|_| ())
+    ));
+
+    // The signatures are still checked.
+    let wrongly_signed =
+        sign_aggregate(&generate_deterministic_keypair(non_aggregator_index + 1).sk);
+    assert!(matches!(
+        chain.verify_aggregated_attestation_for_inclusion(&wrongly_signed),
+        Err(AttnError::InvalidSignature)
+    ));
+
+    let verified = chain
+        .verify_aggregated_attestation_for_inclusion(&aggregate)
+        .expect("should accept aggregate for inclusion");
+    assert_eq!(verified.attestation(), &attestation);
+
+    // The gossip path still rejects the aggregate.
+    assert!(is_invalid_selection_proof(
+        chain
+            .verify_aggregated_attestation_for_gossip(&aggregate)
+            .map(|_| ())
+    ));
+
+    // The aggregate was added to the op pool and is packed into the next block.
+    let slot = chain.slot().unwrap() + 1;
+    let (_, block, _) = harness
+        .add_block_at_slot(slot, harness.get_current_state())
+        .await
+        .unwrap();
+    assert!(block
+        .message()
+        .body()
+        .attestations()
+        .iter()
+        .any(|included| *included == attestation));
+}
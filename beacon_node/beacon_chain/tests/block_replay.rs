@@ -0,0 +1,103 @@
+//! Tests for the replay of stored blocks on their parent states.
+
+#![cfg(not(debug_assertions))]
+
+use beacon_chain::test_utils::{
+    AttestationStrategy, BeaconChainHarness, BlockStrategy, EphemeralHarnessType,
+};
+use lazy_static::lazy_static;
+use state_processing::BlockSignatureStrategy;
+use types::*;
+
+pub const VALIDATOR_COUNT: usize = 24;
+
+lazy_static! {
+    /// A cached set of keys.
+    static ref KEYPAIRS: Vec<Keypair> =
+        types::test_utils::generate_deterministic_keypairs(VALIDATOR_COUNT);
+}
+
+type E = MinimalEthSpec;
+
+/// Build a chain whose head is the first block of epoch 2, so that the parent state of the head is
+/// an epoch boundary state, which is stored in full.
+async fn get_harness() -> BeaconChainHarness<EphemeralHarnessType<E>> {
+    let harness = BeaconChainHarness::builder(MinimalEthSpec)
+        .default_spec()
+        .keypairs(KEYPAIRS.to_vec())
+        .fresh_ephemeral_store()
+        .build();
+    harness.advance_slot();
+    harness
+        .extend_chain(
+            E::slots_per_epoch() as usize * 2 + 1,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::AllValidators,
+        )
+        .await;
+    harness
+}
+
+#[tokio::test]
+async fn replay_of_valid_block_matches_stored_state_root() {
+    let harness = get_harness().await;
+    let head = harness.chain.head_snapshot();
+
+    let report = harness
+        .chain
+        .replay_block(
+            head.beacon_block_root,
+            BlockSignatureStrategy::VerifyIndividual,
+        )
+        .unwrap();
+
+    assert_eq!(report.error, None);
+    assert_eq!(report.block_root, head.beacon_block_root);
+    assert_eq!(report.slot, head.beacon_block.slot());
+    assert_eq!(report.parent_root, head.beacon_block.parent_root());
+    assert_eq!(report.stored_state_root, head.beacon_state_root());
+    assert_eq!(report.computed_state_root, Some(head.beacon_state_root()));
+    assert!(report.state_roots_match);
+}
+
+#[tokio::test]
+async fn replay_on_corrupted_parent_state_reports_mismatch() {
+    let harness = get_harness().await;
+    let head = harness.chain.head_snapshot();
+
+    let parent = harness
+        .chain
+        .get_blinded_block(&head.beacon_block.parent_root())
+        .unwrap()
+        .unwrap();
+    assert_eq!(parent.slot() % E::slots_per_epoch(), 0);
+
+    // Overwrite the stored parent state with one in which a balance differs.
+    let parent_state_root = parent.state_root();
+    let mut parent_state = harness
+        .chain
+        .get_state(&parent_state_root, Some(parent.slot()))
+        .unwrap()
+        .unwrap();
+    parent_state.balances_mut()[0] += 1;
+    harness
+        .chain
+        .store
+        .put_state(&parent_state_root, &parent_state)
+        .unwrap();
+
+    let report = harness
+        .chain
+        .replay_block(
+            head.beacon_block_root,
+            BlockSignatureStrategy::VerifyIndividual,
+        )
+        .unwrap();
+
+    // The block is still valid on the corrupted state, but results in a different post-state.
+    assert_eq!(report.error, None);
+    assert_eq!(report.stored_state_root, head.beacon_state_root());
+    assert!(report.computed_state_root.is_some());
+    assert_ne!(report.computed_state_root, Some(head.beacon_state_root()));
+    assert!(!report.state_roots_match);
+}
@@ -4,6 +4,7 @@ use beacon_chain::test_utils::{
     AttestationStrategy, BeaconChainHarness, BlockStrategy, EphemeralHarnessType,
 };
 use beacon_chain::{BeaconSnapshot, BlockError, ChainSegmentResult};
+use eth2::types::EventKind;
 use lazy_static::lazy_static;
 use logging::test_logger;
 use slasher::{Config as SlasherConfig, Slasher};
@@ -14,7 +15,9 @@ use state_processing::{
 };
 use std::marker::PhantomData;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tempfile::tempdir;
+use tokio::sync::oneshot;
 use types::{test_utils::generate_deterministic_keypair, *};
 
 type E = MainnetEthSpec;
@@ -1301,3 +1304,226 @@ async fn add_altair_block_to_base_chain() {
         }
     ));
 }
+
+/// Returns a harness with a short chain in a slot without a block, along with the block for that
+/// slot.
+async fn get_harness_and_next_block() -> (
+    BeaconChainHarness<EphemeralHarnessType<E>>,
+    SignedBeaconBlock<E>,
+) {
+    let harness = get_harness(VALIDATOR_COUNT);
+    harness
+        .extend_chain(
+            2,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::AllValidators,
+        )
+        .await;
+    harness.advance_slot();
+
+    let slot = harness.get_current_slot();
+    let (block, _) = harness.make_block(harness.get_current_state(), slot).await;
+    (harness, block)
+}
+
+#[tokio::test]
+async fn publish_then_import_publishes_before_db_write() {
+    let (harness, block) = get_harness_and_next_block().await;
+    let block = Arc::new(block);
+    let block_root = block.canonical_root();
+
+    // Hold the database's write transaction lock to simulate a slow database write.
+    let db_write_delay = Duration::from_millis(500);
+    let txn_lock = harness.chain.store.hot_db.begin_rw_transaction();
+
+    let (published_tx, published_rx) = oneshot::channel();
+    let chain = harness.chain.clone();
+    let produced = Instant::now();
+    let import = tokio::spawn(async move {
+        chain
+            .publish_then_import(block, move |block| {
+                published_tx
+                    .send((Instant::now(), block.canonical_root()))
+                    .unwrap();
+            })
+            .await
+    });
+
+    let (published_at, published_root) = published_rx.await.unwrap();
+    let publish_delay = published_at.duration_since(produced);
+    assert_eq!(published_root, block_root);
+
+    tokio::time::sleep(db_write_delay).await;
+    assert!(
+        !harness.chain.store.block_exists(&block_root).unwrap(),
+        "the block should not be written whilst the database is blocked"
+    );
+    drop(txn_lock);
+
+    assert_eq!(import.await.unwrap().unwrap(), block_root);
+    assert!(harness.chain.store.block_exists(&block_root).unwrap());
+    assert!(
+        publish_delay < db_write_delay,
+        "the block should be published without waiting for the database write, took {:?}",
+        publish_delay
+    );
+}
+
+#[tokio::test]
+async fn publish_then_import_invalid_block() {
+    let (harness, block) = get_harness_and_next_block().await;
+    let mut events = harness
+        .chain
+        .event_handler
+        .as_ref()
+        .unwrap()
+        .subscribe_invalid_published_block();
+
+    // A block with an invalid state root passes gossip verification, but fails import.
+    let (mut block, _) = block.deconstruct();
+    *block.state_root_mut() = Hash256::repeat_byte(0xff);
+    let proposer_index = block.proposer_index();
+    let block = Arc::new(block.sign(
+        &generate_deterministic_keypair(proposer_index as usize).sk,
+        &harness.chain.canonical_head.cached_head().head_fork(),
+        harness.chain.genesis_validators_root,
+        &harness.chain.spec,
+    ));
+    let block_root = block.canonical_root();
+
+    let mut published = false;
+    let result = harness
+        .chain
+        .publish_then_import(block.clone(), |_| published = true)
+        .await;
+    assert!(
+        published,
+        "a block passing gossip verification should be published"
+    );
+    assert!(matches!(result, Err(BlockError::StateRootMismatch { .. })));
+
+    match events.try_recv() {
+        Ok(EventKind::InvalidPublishedBlock(event)) => {
+            assert_eq!(event.block, block_root);
+            assert_eq!(event.slot, block.slot());
+            assert_eq!(event.proposer_index, proposer_index);
+        }
+        other => panic!("expected an invalid published block event, got {:?}", other),
+    }
+
+    // A block failing gossip verification is not published.
+    let mut published = false;
+    let result = harness
+        .chain
+        .publish_then_import(block, |_| published = true)
+        .await;
+    assert!(
+        !published,
+        "a block failing gossip verification should not be published"
+    );
+    assert!(matches!(result, Err(BlockError::RepeatProposal { .. })));
+    assert!(events.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn orphan_blocks_are_tracked_until_parent_imported() {
+    let harness = get_harness(VALIDATOR_COUNT);
+    harness.advance_slot();
+    let parent_slot = harness.get_current_slot();
+    let (parent, parent_state) = harness
+        .make_block(harness.get_current_state(), parent_slot)
+        .await;
+    let parent_root = parent.canonical_root();
+
+    let child_slot = parent_slot + 1;
+    let (child, _) = harness.make_block(parent_state, child_slot).await;
+    let child = Arc::new(child);
+    let child_root = child.canonical_root();
+    harness.set_current_slot(child_slot);
+
+    // The child is rejected by both gossip verification and import, but tracked only once.
+    assert!(matches!(
+        harness.chain.verify_block_for_gossip(child.clone()).await,
+        Err(BlockError::ParentUnknown(_))
+    ));
+    assert!(matches!(
+        harness.chain.process_block(child.clone()).await,
+        Err(BlockError::ParentUnknown(_))
+    ));
+    let orphans = harness.chain.orphan_blocks();
+    assert_eq!(orphans.len(), 1);
+    assert_eq!(orphans[0].block_root, child_root);
+    assert_eq!(orphans[0].parent_root, parent_root);
+    assert_eq!(orphans[0].slot, child_slot);
+
+    // Importing the parent stops tracking the child, which can now be imported.
+    harness.chain.process_block(Arc::new(parent)).await.unwrap();
+    assert!(harness.chain.orphan_blocks().is_empty());
+    assert_eq!(
+        harness.chain.process_block(child).await.unwrap(),
+        child_root
+    );
+}
+
+#[cfg(feature = "unchecked_gossip_blocks")]
+#[tokio::test]
+async fn equivocating_blocks_imported_without_gossip_checks() {
+    let harness = BeaconChainHarness::builder(MainnetEthSpec)
+        .default_spec()
+        .keypairs(KEYPAIRS[0..VALIDATOR_COUNT].to_vec())
+        .fresh_ephemeral_store()
+        .chain_config(beacon_chain::ChainConfig {
+            allow_unchecked_gossip_blocks: true,
+            ..beacon_chain::ChainConfig::default()
+        })
+        .mock_execution_layer()
+        .build();
+    harness.advance_slot();
+
+    let state = harness.get_current_state();
+    let (block1, _) = harness.make_block(state.clone(), Slot::new(1)).await;
+    let (block2, _) = harness.make_block(state, Slot::new(1)).await;
+    let (block1, block2) = (Arc::new(block1), Arc::new(block2));
+    assert_ne!(block1.canonical_root(), block2.canonical_root());
+
+    let verified_block = harness
+        .chain
+        .verify_block_for_gossip(block1.clone())
+        .await
+        .unwrap();
+    let block1_root = harness.chain.process_block(verified_block).await.unwrap();
+
+    // The second block is rejected by gossip verification, but may be imported without it.
+    assert!(matches!(
+        harness.chain.verify_block_for_gossip(block2.clone()).await,
+        Err(BlockError::RepeatProposal { .. })
+    ));
+    let block2_root = harness
+        .chain
+        .process_block_unchecked_gossip(block2)
+        .await
+        .unwrap();
+
+    let fork_choice = harness.chain.canonical_head.fork_choice_read_lock();
+    assert!(fork_choice.contains_block(&block1_root));
+    assert!(fork_choice.contains_block(&block2_root));
+}
+
+#[cfg(feature = "unchecked_gossip_blocks")]
+#[tokio::test]
+async fn unchecked_gossip_blocks_refused_by_default() {
+    let harness = get_harness(VALIDATOR_COUNT);
+    let (block, _) = harness
+        .make_block(harness.get_current_state(), Slot::new(1))
+        .await;
+
+    assert!(matches!(
+        harness
+            .chain
+            .process_block_unchecked_gossip(Arc::new(block))
+            .await,
+        Err(BlockError::BeaconChainError(
+            beacon_chain::BeaconChainError::UncheckedGossipBlocksDisabled
+        ))
+    ));
+}
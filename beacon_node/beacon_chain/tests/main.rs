@@ -1,9 +1,12 @@
 mod attestation_production;
 mod attestation_verification;
+mod block_replay;
 mod block_verification;
 mod merge;
 mod op_verification;
 mod payload_invalidation;
+mod rewards;
 mod store_tests;
 mod sync_committee_verification;
 mod tests;
+mod validator_monitor;
@@ -207,3 +207,118 @@ async fn base_altair_merge_with_terminal_block_after_fork() {
 
     verify_execution_payload_chain(execution_payloads.as_slice());
 }
+
+#[tokio::test]
+async fn execution_payload_summaries() {
+    let bellatrix_fork_epoch = Epoch::new(1);
+    let merge_fork_slot = bellatrix_fork_epoch.start_slot(E::slots_per_epoch());
+
+    let mut spec = E::default_spec();
+    spec.altair_fork_epoch = Some(Epoch::new(0));
+    spec.bellatrix_fork_epoch = Some(bellatrix_fork_epoch);
+
+    let harness = BeaconChainHarness::builder(E::default())
+        .spec(spec)
+        .deterministic_keypairs(VALIDATOR_COUNT)
+        .fresh_ephemeral_store()
+        .mock_execution_layer()
+        .build();
+
+    // Blocks after the fork but prior to the merge have no summary.
+    let pre_merge_root = harness.extend_to_slot(merge_fork_slot).await;
+    assert_eq!(
+        harness
+            .chain
+            .execution_payload_summary(pre_merge_root)
+            .unwrap(),
+        None
+    );
+
+    harness
+        .execution_block_generator()
+        .move_to_terminal_block()
+        .unwrap();
+    let timestamp = harness.get_timestamp_at_slot() + harness.spec.seconds_per_slot;
+    harness
+        .execution_block_generator()
+        .modify_last_block(|block| {
+            if let Block::PoW(terminal_block) = block {
+                terminal_block.timestamp = timestamp;
+            }
+        });
+    harness.extend_slots(1).await;
+
+    // Skip a slot before each of the blocks with payloads.
+    let mut blocks = vec![];
+    for _ in 0..4 {
+        harness.advance_slot();
+        harness.advance_slot();
+        harness.extend_slots(1).await;
+        blocks.push(harness.chain.head_snapshot().beacon_block.clone());
+    }
+    let head_slot = harness.chain.head_snapshot().beacon_block.slot();
+
+    // The transition block is the first block with a payload.
+    let transition_block = &blocks[0];
+    assert_eq!(
+        harness
+            .execution_block_generator()
+            .block_by_hash(
+                transition_block
+                    .message()
+                    .body()
+                    .execution_payload()
+                    .unwrap()
+                    .parent_hash()
+            )
+            .map(|block| matches!(block, Block::PoW(_))),
+        Some(true)
+    );
+
+    let summaries = harness
+        .chain
+        .execution_payload_summaries(Slot::new(0), head_slot)
+        .unwrap();
+    assert_eq!(summaries.len(), blocks.len());
+    for (summary, block) in summaries.iter().zip(&blocks) {
+        let payload = &block
+            .message()
+            .body()
+            .execution_payload()
+            .unwrap()
+            .execution_payload;
+        assert_eq!(summary.slot, block.slot());
+        assert_eq!(summary.block_root, block.canonical_root());
+        assert_eq!(summary.block_hash, payload.block_hash);
+        assert_eq!(summary.block_number, payload.block_number);
+        assert_eq!(summary.gas_used, payload.gas_used);
+        assert_eq!(summary.gas_limit, payload.gas_limit);
+        assert_eq!(summary.base_fee_per_gas, payload.base_fee_per_gas);
+        assert_eq!(summary.timestamp, payload.timestamp);
+        assert_eq!(summary.transaction_count, Some(payload.transactions.len()));
+        assert_eq!(
+            harness
+                .chain
+                .execution_payload_summary(block.canonical_root())
+                .unwrap()
+                .as_ref(),
+            Some(summary)
+        );
+    }
+
+    // A range starting at a skipped slot omits the prior block.
+    let summaries = harness
+        .chain
+        .execution_payload_summaries(blocks[0].slot() + 1, head_slot)
+        .unwrap();
+    assert_eq!(
+        summaries
+            .iter()
+            .map(|summary| summary.slot)
+            .collect::<Vec<_>>(),
+        blocks[1..]
+            .iter()
+            .map(|block| block.slot())
+            .collect::<Vec<_>>()
+    );
+}
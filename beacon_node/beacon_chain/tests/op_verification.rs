@@ -232,3 +232,174 @@ fn attester_slashing() {
         ObservationOutcome::AlreadyKnown
     ));
 }
+
+/// Summarise an outcome as `Some(true)` if new, `Some(false)` if already known and `None` if
+/// invalid, so that batch and sequential outcomes can be compared.
+fn summarise<T, U>(outcome: Result<ObservationOutcome<T>, U>) -> Option<bool> {
+    match outcome {
+        Ok(ObservationOutcome::New(_)) => Some(true),
+        Ok(ObservationOutcome::AlreadyKnown) => Some(false),
+        Err(_) => None,
+    }
+}
+
+#[tokio::test]
+async fn batch_voluntary_exits() {
+    let db_path = tempdir().unwrap();
+    let store = get_store(&db_path);
+    let harness = get_harness(store.clone(), VALIDATOR_COUNT);
+    let sequential_db_path = tempdir().unwrap();
+    let sequential_harness = get_harness(get_store(&sequential_db_path), VALIDATOR_COUNT);
+    let spec = &harness.chain.spec.clone();
+
+    for harness in [&harness, &sequential_harness] {
+        harness
+            .extend_chain(
+                (E::slots_per_epoch() * (spec.shard_committee_period + 1)) as usize,
+                BlockStrategy::OnCanonicalHead,
+                AttestationStrategy::AllValidators,
+            )
+            .await;
+    }
+
+    let epoch = Epoch::new(spec.shard_committee_period);
+    let exit1 = harness.make_voluntary_exit(VALIDATOR_COUNT as u64 - 1, epoch);
+    let exit2 = harness.make_voluntary_exit(VALIDATOR_COUNT as u64 - 2, epoch);
+    let exit3 = harness.make_voluntary_exit(VALIDATOR_COUNT as u64 - 3, epoch);
+    // An exit for validator 4 carrying the signature of validator 3.
+    let mut invalid_exit = harness.make_voluntary_exit(VALIDATOR_COUNT as u64 - 4, epoch);
+    invalid_exit.signature = exit3.signature.clone();
+
+    let exits = vec![
+        exit1.clone(),
+        invalid_exit.clone(),
+        exit1,
+        exit2,
+        invalid_exit,
+        exit3,
+    ];
+
+    let sequential = exits
+        .iter()
+        .map(|exit| {
+            summarise(
+                sequential_harness
+                    .chain
+                    .verify_voluntary_exit_for_gossip(exit.clone()),
+            )
+        })
+        .collect::<Vec<_>>();
+    let batch = harness
+        .chain
+        .batch_verify_voluntary_exits_for_gossip(exits)
+        .unwrap()
+        .into_iter()
+        .map(summarise)
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        batch,
+        vec![Some(true), None, Some(false), Some(true), None, Some(true)]
+    );
+    assert_eq!(batch, sequential);
+}
+
+#[test]
+fn batch_proposer_slashings() {
+    let db_path = tempdir().unwrap();
+    let harness = get_harness(get_store(&db_path), VALIDATOR_COUNT);
+    let sequential_db_path = tempdir().unwrap();
+    let sequential_harness = get_harness(get_store(&sequential_db_path), VALIDATOR_COUNT);
+
+    let slashing1 = harness.make_proposer_slashing(VALIDATOR_COUNT as u64 - 1);
+    let slashing2 = harness.make_proposer_slashing(VALIDATOR_COUNT as u64 - 2);
+    // A slashing for validator 3 carrying a header signed by validator 2.
+    let mut invalid_slashing = harness.make_proposer_slashing(VALIDATOR_COUNT as u64 - 3);
+    invalid_slashing.signed_header_1.signature = slashing2.signed_header_1.signature.clone();
+
+    let slashings = vec![
+        slashing1.clone(),
+        invalid_slashing,
+        slashing1.clone(),
+        ProposerSlashing {
+            signed_header_1: slashing1.signed_header_2,
+            signed_header_2: slashing1.signed_header_1,
+        },
+        slashing2,
+    ];
+
+    let sequential = slashings
+        .iter()
+        .map(|slashing| {
+            summarise(
+                sequential_harness
+                    .chain
+                    .verify_proposer_slashing_for_gossip(slashing.clone()),
+            )
+        })
+        .collect::<Vec<_>>();
+    let batch = harness
+        .chain
+        .batch_verify_proposer_slashings_for_gossip(slashings)
+        .unwrap()
+        .into_iter()
+        .map(summarise)
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        batch,
+        vec![Some(true), None, Some(false), Some(false), Some(true)]
+    );
+    assert_eq!(batch, sequential);
+}
+
+#[test]
+fn batch_attester_slashings() {
+    let db_path = tempdir().unwrap();
+    let harness = get_harness(get_store(&db_path), VALIDATOR_COUNT);
+    let sequential_db_path = tempdir().unwrap();
+    let sequential_harness = get_harness(get_store(&sequential_db_path), VALIDATOR_COUNT);
+
+    let first_third = (0..VALIDATOR_COUNT as u64 / 3).collect::<Vec<_>>();
+    let first_half = (0..VALIDATOR_COUNT as u64 / 2).collect::<Vec<_>>();
+    let second_half = (VALIDATOR_COUNT as u64 / 2..VALIDATOR_COUNT as u64).collect::<Vec<_>>();
+
+    let slashing1 = harness.make_attester_slashing(first_half);
+    let slashing2 = harness.make_attester_slashing(first_third);
+    let slashing3 = harness.make_attester_slashing(second_half);
+    // The second half slashing carrying the signatures of the first half slashing.
+    let mut invalid_slashing = slashing3.clone();
+    invalid_slashing.attestation_1.signature = slashing1.attestation_1.signature.clone();
+
+    let slashings = vec![
+        slashing1.clone(),
+        slashing2,
+        invalid_slashing,
+        slashing1,
+        slashing3,
+    ];
+
+    let sequential = slashings
+        .iter()
+        .map(|slashing| {
+            summarise(
+                sequential_harness
+                    .chain
+                    .verify_attester_slashing_for_gossip(slashing.clone()),
+            )
+        })
+        .collect::<Vec<_>>();
+    let batch = harness
+        .chain
+        .batch_verify_attester_slashings_for_gossip(slashings)
+        .unwrap()
+        .into_iter()
+        .map(summarise)
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        batch,
+        vec![Some(true), Some(false), None, Some(false), Some(true)]
+    );
+    assert_eq!(batch, sequential);
+}
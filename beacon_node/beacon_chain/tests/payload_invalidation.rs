@@ -1,20 +1,29 @@
 #![cfg(not(debug_assertions))]
 
 use beacon_chain::{
+    proposal_history::{ProposalOutcome, ProposalPayloadSource, MISSED_PROPOSAL_SLOT_TOLERANCE},
+    shutdown_report::{ShutdownCause, ShutdownReport, SHUTDOWN_REPORT_FILENAME},
     test_utils::{BeaconChainHarness, EphemeralHarnessType},
-    BeaconChainError, BlockError, ExecutionPayloadError, StateSkipConfig, WhenSlotSkipped,
-    INVALID_JUSTIFIED_PAYLOAD_SHUTDOWN_REASON,
+    validator_registrations::{ValidatorRegistrationOutcome, MAX_UNKNOWN_VALIDATOR_REGISTRATIONS},
+    BeaconChainError, BeaconForkChoiceStore, BlockError, ExecutionPayloadError,
+    ProduceBlockVerification, StateSkipConfig, WhenSlotSkipped,
 };
 use execution_layer::{
+    fee_recipient_file::to_checksum,
     json_structures::{JsonForkChoiceStateV1, JsonPayloadAttributesV1},
-    ExecutionLayer, ForkChoiceState, PayloadAttributes,
+    test_utils::MockBuilder,
+    BuilderConfig, ExecutionLayer, ForkChoiceState, PayloadAttributes,
 };
-use fork_choice::{Error as ForkChoiceError, InvalidationOperation, PayloadVerificationStatus};
-use proto_array::{Error as ProtoArrayError, ExecutionStatus};
+use fork_choice::{
+    Error as ForkChoiceError, ForkChoice, InvalidationOperation, PayloadVerificationStatus,
+};
+use proto_array::{Error as ProtoArrayError, ExecutionStatus, ProtoArrayForkChoice};
+use sensitive_url::SensitiveUrl;
 use slot_clock::SlotClock;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
-use task_executor::ShutdownReason;
+use tempfile::tempdir;
 use tree_hash::TreeHash;
 use types::*;
 
@@ -40,16 +49,50 @@ struct InvalidPayloadRig {
 
 impl InvalidPayloadRig {
     fn new() -> Self {
+        Self::new_inner(false, None, None)
+    }
+
+    /// As per `Self::new`, but the execution layer is also connected to a mock builder.
+    fn new_with_mock_builder() -> Self {
+        Self::new_inner(true, None, None)
+    }
+
+    /// As per `Self::new`, but the execution layer uses the suggested fee recipient file at
+    /// `path`.
+    fn new_with_fee_recipient_file(path: PathBuf) -> Self {
+        Self::new_inner(false, Some(path), None)
+    }
+
+    /// As per `Self::new`, but the beacon chain writes shutdown reports to `data_dir`.
+    fn new_with_data_dir(data_dir: PathBuf) -> Self {
+        Self::new_inner(false, None, Some(data_dir))
+    }
+
+    fn new_inner(
+        mock_builder: bool,
+        fee_recipient_file: Option<PathBuf>,
+        data_dir: Option<PathBuf>,
+    ) -> Self {
         let mut spec = E::default_spec();
         spec.altair_fork_epoch = Some(Epoch::new(0));
         spec.bellatrix_fork_epoch = Some(Epoch::new(0));
 
-        let harness = BeaconChainHarness::builder(MainnetEthSpec)
+        let builder = BeaconChainHarness::builder(MainnetEthSpec)
             .spec(spec)
-            .deterministic_keypairs(VALIDATOR_COUNT)
-            .mock_execution_layer()
-            .fresh_ephemeral_store()
-            .build();
+            .deterministic_keypairs(VALIDATOR_COUNT);
+        let builder = if mock_builder {
+            builder.mock_execution_layer_with_builder()
+        } else if let Some(path) = fee_recipient_file {
+            builder.mock_execution_layer_with_fee_recipient_file(path)
+        } else {
+            builder.mock_execution_layer()
+        };
+        let builder = if let Some(data_dir) = data_dir {
+            builder.initial_mutator(Box::new(move |builder| builder.data_dir(data_dir)))
+        } else {
+            builder
+        };
+        let harness = builder.fresh_ephemeral_store().build();
 
         // Move to slot 1.
         harness.advance_slot();
@@ -479,10 +522,100 @@ async fn justified_checkpoint_becomes_invalid() {
     // The beacon chain should have triggered a shutdown.
     assert_eq!(
         rig.harness.shutdown_reasons(),
-        vec![ShutdownReason::Failure(
-            INVALID_JUSTIFIED_PAYLOAD_SHUTDOWN_REASON
-        )]
+        vec![ShutdownCause::InvalidJustifiedPayload.shutdown_reason()]
+    );
+}
+
+/// Ensure the client shuts down and records a report when the finalized payload is invalid.
+#[tokio::test]
+async fn finalized_payload_becomes_invalid() {
+    let data_dir = tempdir().unwrap();
+    let mut rig =
+        InvalidPayloadRig::new_with_data_dir(data_dir.path().into()).enable_attestations();
+    rig.move_to_terminal_block();
+    rig.import_block(Payload::Valid).await; // Import a valid transition block.
+    rig.build_blocks(E::slots_per_epoch() * 4 - 1, Payload::Syncing)
+        .await;
+
+    let finalized_checkpoint = rig.harness.finalized_checkpoint();
+    assert_eq!(finalized_checkpoint.epoch, 2);
+    let finalized_hash = rig.block_hash(finalized_checkpoint.root);
+    let head_block_root = rig.harness.head_block_root();
+    let head_slot = rig.harness.head_slot();
+
+    // Fork choice refuses to invalidate the finalized block without also invalidating the
+    // justified block, so mark the finalized payload as invalid directly.
+    {
+        let chain = &rig.harness.chain;
+        let mut fork_choice = chain.canonical_head.fork_choice_write_lock();
+        let mut persisted = fork_choice.to_persisted();
+        let mut proto_array =
+            ProtoArrayForkChoice::from_bytes(&persisted.proto_array_bytes).unwrap();
+        let core_proto_array = proto_array.core_proto_array_mut();
+        let finalized_index = *core_proto_array
+            .indices
+            .get(&finalized_checkpoint.root)
+            .unwrap();
+        core_proto_array.nodes[finalized_index].execution_status =
+            ExecutionStatus::Invalid(finalized_hash);
+        persisted.proto_array_bytes = proto_array.as_bytes();
+
+        let fc_store = BeaconForkChoiceStore::from_persisted(
+            fork_choice.fc_store().to_persisted(),
+            chain.store.clone(),
+        )
+        .unwrap();
+        *fork_choice = ForkChoice::from_persisted(persisted, fc_store, &chain.spec).unwrap();
+    }
+
+    // No report should be written until the beacon chain notices the invalid payload.
+    let report_path = data_dir.path().join(SHUTDOWN_REPORT_FILENAME);
+    assert_eq!(ShutdownReport::load(&report_path).unwrap(), None);
+
+    assert!(matches!(
+        rig.harness.chain.recompute_head_at_current_slot().await,
+        Err(BeaconChainError::InvalidFinalizedPayload { .. })
+    ));
+
+    // The beacon chain should have triggered a shutdown with a distinct exit code.
+    let shutdown_reason = ShutdownCause::InvalidFinalizedPayload.shutdown_reason();
+    assert_eq!(rig.harness.shutdown_reasons(), vec![shutdown_reason]);
+    assert_eq!(shutdown_reason.exit_code(), 5);
+
+    let report = ShutdownReport::load(&report_path).unwrap().unwrap();
+    assert_eq!(report.cause, ShutdownCause::InvalidFinalizedPayload);
+    assert_eq!(report.message, shutdown_reason.message());
+    assert_eq!(report.exit_code, shutdown_reason.exit_code());
+    assert_eq!(report.block_root, finalized_checkpoint.root);
+    assert_eq!(report.execution_block_hash, Some(finalized_hash));
+    assert_eq!(report.head_block_root, head_block_root);
+    assert_eq!(report.head_slot, head_slot);
+    assert!(report.timestamp > 0);
+
+    // A second shutdown request shouldn't overwrite the report of the first.
+    let _ = rig.harness.chain.recompute_head_at_current_slot().await;
+    assert_eq!(ShutdownReport::load(&report_path).unwrap(), Some(report));
+}
+
+/// Ensure a report left by a previous run is archived once logged, so it isn't logged again.
+#[tokio::test]
+async fn previous_shutdown_report_is_archived_on_startup() {
+    let data_dir = tempdir().unwrap();
+    let report_path = data_dir.path().join(SHUTDOWN_REPORT_FILENAME);
+    let report = ShutdownReport::new(
+        ShutdownCause::InvalidFinalizedPayload,
+        Hash256::repeat_byte(1),
+        Some(ExecutionBlockHash::repeat_byte(2)),
+        Hash256::repeat_byte(3),
+        Slot::new(4),
     );
+    report.write(&report_path).unwrap();
+
+    let _rig = InvalidPayloadRig::new_with_data_dir(data_dir.path().into());
+
+    assert_eq!(ShutdownReport::load(&report_path).unwrap(), None);
+    let archived_path = report_path.with_extension("json.previous");
+    assert_eq!(ShutdownReport::load(&archived_path).unwrap(), Some(report));
 }
 
 /// Ensure that a `latest_valid_hash` for a pre-finality block only reverts a single block.
@@ -948,6 +1081,755 @@ async fn payload_preparation() {
     assert_eq!(rig.previous_payload_attributes(), payload_attributes);
 }
 
+/// Ensure that the fee recipient from a validator registration is used during proposer
+/// preparation, without any other preparation data being supplied to the EL.
+#[tokio::test]
+async fn payload_preparation_from_validator_registration() {
+    let mut rig = InvalidPayloadRig::new();
+    rig.move_to_terminal_block();
+    rig.import_block(Payload::Valid).await;
+
+    let chain = &rig.harness.chain;
+    let head = chain.head_snapshot();
+    let current_slot = chain.slot().unwrap();
+    let next_slot = current_slot + 1;
+    let proposer = head
+        .beacon_state
+        .get_beacon_proposer_index(next_slot, &chain.spec)
+        .unwrap();
+
+    let fee_recipient = Address::repeat_byte(99);
+    let sign_registration = |keypair: &Keypair| {
+        let message = ValidatorRegistrationData {
+            fee_recipient,
+            gas_limit: 30_000_000,
+            timestamp: 1,
+            pubkey: keypair.pk.compress(),
+        };
+        let signature = keypair
+            .sk
+            .sign(message.signing_root(chain.spec.get_builder_domain()));
+        SignedValidatorRegistrationData { message, signature }
+    };
+
+    // Register the proposer along with a validator that is not (yet) known to the chain.
+    let unknown_keypair = Keypair::random();
+    let outcomes = chain
+        .process_validator_registrations(vec![
+            sign_registration(&rig.harness.validator_keypairs[proposer]),
+            sign_registration(&unknown_keypair),
+        ])
+        .await
+        .unwrap();
+    assert_eq!(
+        outcomes,
+        vec![
+            ValidatorRegistrationOutcome::Registered {
+                validator_index: proposer as u64
+            },
+            ValidatorRegistrationOutcome::UnknownValidator,
+        ]
+    );
+
+    chain.prepare_beacon_proposer(current_slot).await.unwrap();
+
+    let payload_attributes = PayloadAttributes {
+        timestamp: chain.slot_clock.start_of(next_slot).unwrap().as_secs(),
+        prev_randao: *head
+            .beacon_state
+            .get_randao_mix(head.beacon_state.current_epoch())
+            .unwrap(),
+        suggested_fee_recipient: fee_recipient,
+    };
+    assert_eq!(rig.previous_payload_attributes(), payload_attributes);
+}
+
+/// Ensure that the fee recipient from the suggested fee recipient file is used during proposer
+/// preparation, without any preparation data being supplied to the EL.
+#[tokio::test]
+async fn payload_preparation_from_fee_recipient_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("fee_recipients.txt");
+    let keypairs = types::test_utils::generate_deterministic_keypairs(VALIDATOR_COUNT);
+    let default_fee_recipient = Address::repeat_byte(98);
+    let file_fee_recipient = |validator_index: usize| Address::repeat_byte(validator_index as u8);
+    let contents = keypairs
+        .iter()
+        .enumerate()
+        .map(|(i, keypair)| {
+            format!(
+                "{}: {}\n",
+                keypair.pk.compress(),
+                to_checksum(&file_fee_recipient(i))
+            )
+        })
+        .collect::<String>();
+    std::fs::write(
+        &path,
+        format!(
+            "default: {}\n{}",
+            to_checksum(&default_fee_recipient),
+            contents
+        ),
+    )
+    .unwrap();
+
+    let mut rig = InvalidPayloadRig::new_with_fee_recipient_file(path);
+    rig.move_to_terminal_block();
+    rig.import_block(Payload::Valid).await;
+
+    let chain = &rig.harness.chain;
+    let head = chain.head_snapshot();
+    let current_slot = chain.slot().unwrap();
+    let next_slot = current_slot + 1;
+    let proposer = head
+        .beacon_state
+        .get_beacon_proposer_index(next_slot, &chain.spec)
+        .unwrap();
+
+    chain.prepare_beacon_proposer(current_slot).await.unwrap();
+
+    let payload_attributes = PayloadAttributes {
+        timestamp: chain.slot_clock.start_of(next_slot).unwrap().as_secs(),
+        prev_randao: *head
+            .beacon_state
+            .get_randao_mix(head.beacon_state.current_epoch())
+            .unwrap(),
+        suggested_fee_recipient: file_fee_recipient(proposer),
+    };
+    assert_eq!(rig.previous_payload_attributes(), payload_attributes);
+
+    // Preparation data supplied by the VC takes precedence over the file.
+    let vc_fee_recipient = Address::repeat_byte(99);
+    rig.execution_layer()
+        .update_proposer_preparation(
+            next_slot.epoch(E::slots_per_epoch()),
+            &[ProposerPreparationData {
+                validator_index: proposer as u64,
+                fee_recipient: vc_fee_recipient,
+            }],
+        )
+        .await;
+    assert_eq!(
+        rig.execution_layer()
+            .get_suggested_fee_recipient(proposer as u64, Some(keypairs[proposer].pk.compress()))
+            .await,
+        vc_fee_recipient
+    );
+
+    // Validators which aren't listed use the default entry, rather than the global address.
+    assert_eq!(
+        rig.execution_layer()
+            .get_suggested_fee_recipient(u64::MAX, Some(Keypair::random().pk.compress()))
+            .await,
+        default_fee_recipient
+    );
+}
+
+/// A registration with an invalid signature must not be stored.
+#[tokio::test]
+async fn validator_registration_invalid_signature() {
+    let rig = InvalidPayloadRig::new();
+    let chain = &rig.harness.chain;
+
+    let keypair = &rig.harness.validator_keypairs[0];
+    let message = ValidatorRegistrationData {
+        fee_recipient: Address::repeat_byte(99),
+        gas_limit: 30_000_000,
+        timestamp: 1,
+        pubkey: keypair.pk.compress(),
+    };
+    // Sign with the wrong domain.
+    let signature = keypair
+        .sk
+        .sign(message.signing_root(chain.spec.get_deposit_domain()));
+
+    let outcomes = chain
+        .process_validator_registrations(vec![SignedValidatorRegistrationData {
+            message,
+            signature,
+        }])
+        .await
+        .unwrap();
+    assert_eq!(
+        outcomes,
+        vec![ValidatorRegistrationOutcome::InvalidSignature]
+    );
+    assert_eq!(chain.registered_fee_recipient(0).unwrap(), None);
+}
+
+/// Registrations for unknown validators are retained, up to a limit.
+#[tokio::test]
+async fn validator_registration_unknown_validator_limit() {
+    let rig = InvalidPayloadRig::new();
+    let chain = &rig.harness.chain;
+
+    let sign_registration = |keypair: &Keypair| {
+        let message = ValidatorRegistrationData {
+            fee_recipient: Address::repeat_byte(99),
+            gas_limit: 30_000_000,
+            timestamp: 1,
+            pubkey: keypair.pk.compress(),
+        };
+        let signature = keypair
+            .sk
+            .sign(message.signing_root(chain.spec.get_builder_domain()));
+        SignedValidatorRegistrationData { message, signature }
+    };
+
+    let unknown_keypairs = (0..MAX_UNKNOWN_VALIDATOR_REGISTRATIONS + 1)
+        .map(|_| Keypair::random())
+        .collect::<Vec<_>>();
+    let outcomes = chain
+        .process_validator_registrations(unknown_keypairs.iter().map(sign_registration).collect())
+        .await
+        .unwrap();
+    let (last, retained) = outcomes.split_last().unwrap();
+    assert!(retained
+        .iter()
+        .all(|outcome| *outcome == ValidatorRegistrationOutcome::UnknownValidator));
+    assert_eq!(
+        *last,
+        ValidatorRegistrationOutcome::TooManyUnknownValidators
+    );
+    assert_eq!(
+        chain
+            .validator_registrations
+            .read()
+            .num_unknown_validators(),
+        MAX_UNKNOWN_VALIDATOR_REGISTRATIONS
+    );
+
+    // Retained unknown validators and known validators may still update their registrations.
+    let outcomes = chain
+        .process_validator_registrations(vec![
+            sign_registration(&unknown_keypairs[0]),
+            sign_registration(&rig.harness.validator_keypairs[0]),
+        ])
+        .await
+        .unwrap();
+    assert_eq!(
+        outcomes,
+        vec![
+            ValidatorRegistrationOutcome::UnknownValidator,
+            ValidatorRegistrationOutcome::Registered { validator_index: 0 },
+        ]
+    );
+    assert_eq!(
+        chain
+            .validator_registrations
+            .read()
+            .num_unknown_validators(),
+        MAX_UNKNOWN_VALIDATOR_REGISTRATIONS
+    );
+}
+
+/// Ensure that the payload source is recorded for both full and blinded blocks produced by this
+/// node, even if they are never published.
+#[tokio::test]
+async fn proposal_history_records_payload_source() {
+    let mut rig = InvalidPayloadRig::new();
+    rig.move_to_terminal_block();
+    rig.import_block(Payload::Valid).await;
+
+    let chain = rig.harness.chain.clone();
+    // Blocks produced by the rig are also recorded, ignore them.
+    let existing = chain.recent_proposals().len();
+
+    let head = chain.head_snapshot();
+    let slot = head.beacon_block.slot() + 1;
+
+    let (full_block, _) = rig
+        .harness
+        .make_block(head.beacon_state.clone(), slot)
+        .await;
+    let (blinded_block, _) = chain
+        .produce_block_on_state::<BlindedPayload<E>>(
+            head.beacon_state.clone(),
+            None,
+            slot,
+            Signature::empty(),
+            None,
+            ProduceBlockVerification::NoVerification,
+        )
+        .await
+        .unwrap();
+
+    let proposals = chain.recent_proposals();
+    assert_eq!(proposals.len(), existing + 2);
+
+    let full = &proposals[existing];
+    assert_eq!(full.slot, slot);
+    assert_eq!(full.block_root, full_block.canonical_root());
+    assert!(!full.blinded);
+    assert_eq!(full.payload_source, ProposalPayloadSource::Local);
+    assert_eq!(full.outcome, ProposalOutcome::Produced);
+
+    // There is no builder configured, so the blinded block must use a local payload.
+    let blinded = &proposals[existing + 1];
+    assert_eq!(blinded.slot, slot);
+    assert_eq!(blinded.block_root, blinded_block.canonical_root());
+    assert!(blinded.blinded);
+    assert_eq!(blinded.payload_source, ProposalPayloadSource::Local);
+    assert_eq!(blinded.relay, None);
+    assert_eq!(blinded.builder_bid_value, None);
+
+    // Publish only the full block.
+    rig.harness
+        .process_block(slot, full_block.clone())
+        .await
+        .unwrap();
+    rig.harness.set_current_slot(slot + 4);
+    chain.per_slot_task().await;
+
+    let proposals = chain.recent_proposals();
+    assert_eq!(proposals[existing].outcome, ProposalOutcome::Published);
+    // The unpublished block is retained and is not considered missed, since another block was
+    // published at the same slot.
+    assert_eq!(proposals[existing + 1].outcome, ProposalOutcome::Produced);
+}
+
+/// Ensure that a builder withholding the payload of a signed blinded block is accounted for and
+/// that local payloads are used for subsequent proposals.
+#[tokio::test]
+async fn builder_withholds_payload() {
+    let num_blocks = E::slots_per_epoch() * 4;
+
+    let mut rig = InvalidPayloadRig::new_with_mock_builder().enable_attestations();
+    rig.move_to_terminal_block();
+    rig.import_block(Payload::Valid).await;
+    rig.build_blocks(num_blocks - 1, Payload::Valid).await;
+    assert!(rig.harness.finalized_checkpoint().epoch > 0);
+
+    let chain = rig.harness.chain.clone();
+    let execution_layer = rig.execution_layer();
+    let mock_builder = rig
+        .harness
+        .mock_execution_layer
+        .as_ref()
+        .unwrap()
+        .builder
+        .as_ref()
+        .unwrap();
+
+    let head = chain.head_snapshot();
+    let slot = head.beacon_block.slot() + 1;
+    rig.harness.set_current_slot(slot);
+
+    // Produce a full block so that the mock execution engine has a payload for the builder to
+    // offer.
+    rig.harness
+        .make_block(head.beacon_state.clone(), slot)
+        .await;
+
+    let produce_blinded_block = || async {
+        chain
+            .produce_block_on_state::<BlindedPayload<E>>(
+                head.beacon_state.clone(),
+                None,
+                slot,
+                Signature::empty(),
+                None,
+                ProduceBlockVerification::NoVerification,
+            )
+            .await
+            .unwrap()
+            .0
+    };
+
+    let blinded_block = produce_blinded_block().await;
+    let block_root = blinded_block.canonical_root();
+    assert_eq!(mock_builder.get_header_requests(), 1);
+
+    let record = chain
+        .recent_proposals()
+        .into_iter()
+        .find(|record| record.block_root == block_root)
+        .unwrap();
+    assert!(record.blinded);
+    assert_eq!(record.payload_source, ProposalPayloadSource::Builder);
+    assert_eq!(
+        record.builder_bid_value,
+        Some(Uint256::from(
+            execution_layer::test_utils::DEFAULT_BUILDER_BID_VALUE
+        ))
+    );
+
+    mock_builder.fail_blinded_blocks(true);
+    let result = chain
+        .unblind_block(SignedBeaconBlock::from_block(
+            blinded_block,
+            Signature::empty(),
+        ))
+        .await;
+    assert!(matches!(
+        result,
+        Err(BeaconChainError::BuilderFailedToRevealPayload { .. })
+    ));
+    assert_eq!(mock_builder.blinded_blocks_requests(), 1);
+
+    let record = chain
+        .recent_proposals()
+        .into_iter()
+        .find(|record| record.block_root == block_root)
+        .unwrap();
+    assert_eq!(record.outcome, ProposalOutcome::Withheld);
+    assert!(execution_layer.builder_circuit_breaker_tripped(slot).await);
+    assert!(
+        !execution_layer
+            .builder_circuit_breaker_tripped(
+                slot + execution_layer::BUILDER_CIRCUIT_BREAKER_EPOCHS * E::slots_per_epoch()
+            )
+            .await
+    );
+
+    // The builder should not be used whilst the circuit breaker is tripped.
+    let blinded_block = produce_blinded_block().await;
+    assert_eq!(mock_builder.get_header_requests(), 1);
+    let record = chain
+        .recent_proposals()
+        .into_iter()
+        .find(|record| record.block_root == blinded_block.canonical_root())
+        .unwrap();
+    assert_eq!(record.payload_source, ProposalPayloadSource::Local);
+}
+
+/// Ensure that the signed bid of the builder is retained when its payload header is used, such
+/// that it can be verified against the builder's pubkey, along with the timings of its response.
+#[tokio::test]
+async fn builder_bid_is_retained() {
+    let num_blocks = E::slots_per_epoch() * 4;
+
+    let mut rig = InvalidPayloadRig::new_with_mock_builder().enable_attestations();
+    rig.move_to_terminal_block();
+    rig.import_block(Payload::Valid).await;
+    rig.build_blocks(num_blocks - 1, Payload::Valid).await;
+    assert!(rig.harness.finalized_checkpoint().epoch > 0);
+
+    let chain = rig.harness.chain.clone();
+    let mock_builder = rig
+        .harness
+        .mock_execution_layer
+        .as_ref()
+        .unwrap()
+        .builder
+        .as_ref()
+        .unwrap();
+
+    // Delay the builder's response so that the time to its first byte is measurable.
+    let get_header_delay = Duration::from_millis(50);
+    mock_builder.delay_get_header(get_header_delay);
+
+    let head = chain.head_snapshot();
+    let slot = head.beacon_block.slot() + 1;
+    rig.harness.set_current_slot(slot);
+
+    // Produce a full block so that the mock execution engine has a payload for the builder to
+    // offer.
+    rig.harness
+        .make_block(head.beacon_state.clone(), slot)
+        .await;
+    assert!(chain.builder_bids_at_slot(slot).is_empty());
+
+    let (blinded_block, _) = chain
+        .produce_block_on_state::<BlindedPayload<E>>(
+            head.beacon_state.clone(),
+            None,
+            slot,
+            Signature::empty(),
+            None,
+            ProduceBlockVerification::NoVerification,
+        )
+        .await
+        .unwrap();
+    let header_root = blinded_block
+        .body()
+        .execution_payload()
+        .unwrap()
+        .execution_payload_header
+        .tree_hash_root();
+
+    let bids = chain.builder_bids_at_slot(slot);
+    assert_eq!(bids.len(), 1);
+    let record = &bids[0];
+    assert_eq!(record.block_root, blinded_block.canonical_root());
+    assert_eq!(
+        record.relay,
+        SensitiveUrl::parse(&mock_builder.url()).unwrap().redacted
+    );
+    assert_eq!(record.bid.header_root, header_root);
+    assert_eq!(record.bid.pubkey, mock_builder.pubkey());
+    assert_eq!(
+        record.bid.value,
+        Uint256::from(execution_layer::test_utils::DEFAULT_BUILDER_BID_VALUE)
+    );
+    assert!(record.bid.is_signed_by(&mock_builder.pubkey(), &chain.spec));
+    assert!(record.bid.time_to_first_byte_ms >= get_header_delay.as_millis() as u64);
+    assert!(record.bid.deserialization_ms < get_header_delay.as_millis() as u64);
+    assert_eq!(chain.builder_bids(), bids);
+
+    // The signature doesn't verify for any other bid or builder.
+    let mut tampered = record.bid.clone();
+    tampered.value = tampered.value + Uint256::from(1_u64);
+    assert!(!tampered.is_signed_by(&mock_builder.pubkey(), &chain.spec));
+    let mut tampered = record.bid.clone();
+    tampered.header_root = Hash256::zero();
+    assert!(!tampered.is_signed_by(&mock_builder.pubkey(), &chain.spec));
+    let other_pubkey = Keypair::random().pk.compress();
+    assert!(!record.bid.is_signed_by(&other_pubkey, &chain.spec));
+}
+
+/// Ensure that a bid which isn't signed by the pubkey configured for its builder is ignored in
+/// favour of a local payload.
+#[tokio::test]
+async fn builder_bid_not_signed_by_configured_pubkey() {
+    let num_blocks = E::slots_per_epoch() * 4;
+
+    let mut rig = InvalidPayloadRig::new_with_mock_builder().enable_attestations();
+    rig.move_to_terminal_block();
+    rig.import_block(Payload::Valid).await;
+    rig.build_blocks(num_blocks - 1, Payload::Valid).await;
+    assert!(rig.harness.finalized_checkpoint().epoch > 0);
+
+    let chain = rig.harness.chain.clone();
+    let mock_execution_layer = rig.harness.mock_execution_layer.as_ref().unwrap();
+    let first_builder = mock_execution_layer.builder.as_ref().unwrap();
+
+    // Connect a builder which signs its bids with a key other than the one configured for it.
+    let second_builder = MockBuilder::new(
+        &tokio::runtime::Handle::current(),
+        mock_execution_layer.server.ctx.clone(),
+        &chain.spec,
+    );
+    let second_url = SensitiveUrl::parse(&second_builder.url()).unwrap();
+    chain
+        .add_builder(BuilderConfig {
+            pubkey: Some(Keypair::random().pk.compress()),
+            ..BuilderConfig::new(second_url)
+        })
+        .await
+        .unwrap();
+
+    let head = chain.head_snapshot();
+    let slot = head.beacon_block.slot() + 1;
+    rig.harness.set_current_slot(slot);
+
+    // Produce a full block so that the mock execution engine has a payload for the builders to
+    // offer.
+    rig.harness
+        .make_block(head.beacon_state.clone(), slot)
+        .await;
+
+    // Only the second builder offers a bid, which is ignored.
+    first_builder.fail_get_header(true);
+    let (block, _) = chain
+        .produce_block_on_state::<BlindedPayload<E>>(
+            head.beacon_state.clone(),
+            None,
+            slot,
+            Signature::empty(),
+            None,
+            ProduceBlockVerification::NoVerification,
+        )
+        .await
+        .unwrap();
+    assert_eq!(second_builder.get_header_requests(), 1);
+    assert!(chain.builder_bids_at_slot(slot).is_empty());
+
+    let block_root = block.canonical_root();
+    let record = chain
+        .recent_proposals()
+        .into_iter()
+        .find(|record| record.block_root == block_root)
+        .unwrap();
+    assert_eq!(record.payload_source, ProposalPayloadSource::Local);
+}
+
+/// Ensure that a builder connected at runtime is sent the stored registrations and queried for the
+/// next proposal, and that removing it doesn't prevent its payload from being revealed.
+#[tokio::test]
+async fn builder_added_at_runtime() {
+    let num_blocks = E::slots_per_epoch() * 4;
+
+    let mut rig = InvalidPayloadRig::new_with_mock_builder().enable_attestations();
+    rig.move_to_terminal_block();
+    rig.import_block(Payload::Valid).await;
+    rig.build_blocks(num_blocks - 1, Payload::Valid).await;
+    assert!(rig.harness.finalized_checkpoint().epoch > 0);
+
+    let chain = rig.harness.chain.clone();
+    let mock_execution_layer = rig.harness.mock_execution_layer.as_ref().unwrap();
+    let first_builder = mock_execution_layer.builder.as_ref().unwrap();
+
+    // Register a validator whilst only the first builder is connected.
+    let keypair = &rig.harness.validator_keypairs[0];
+    let message = ValidatorRegistrationData {
+        fee_recipient: Address::repeat_byte(99),
+        gas_limit: 30_000_000,
+        timestamp: 1,
+        pubkey: keypair.pk.compress(),
+    };
+    let signature = keypair
+        .sk
+        .sign(message.signing_root(chain.spec.get_builder_domain()));
+    chain
+        .process_validator_registrations(vec![SignedValidatorRegistrationData {
+            message,
+            signature,
+        }])
+        .await
+        .unwrap();
+    assert_eq!(first_builder.registrations(), 1);
+
+    // The new builder is sent the stored registration.
+    let second_builder = MockBuilder::new(
+        &tokio::runtime::Handle::current(),
+        mock_execution_layer.server.ctx.clone(),
+        &chain.spec,
+    );
+    let second_url = SensitiveUrl::parse(&second_builder.url()).unwrap();
+    chain
+        .add_builder(BuilderConfig::new(second_url.clone()))
+        .await
+        .unwrap();
+    assert_eq!(second_builder.registrations(), 1);
+    assert_eq!(chain.list_builders().len(), 2);
+    assert!(matches!(
+        chain
+            .add_builder(BuilderConfig::new(second_url.clone()))
+            .await,
+        Err(BeaconChainError::AddBuilderFailed(_))
+    ));
+
+    let head = chain.head_snapshot();
+    let slot = head.beacon_block.slot() + 1;
+    rig.harness.set_current_slot(slot);
+
+    // Produce a full block so that the mock execution engine has a payload for the builders to
+    // offer.
+    rig.harness
+        .make_block(head.beacon_state.clone(), slot)
+        .await;
+
+    // Both builders are queried. Only the second offers a bid.
+    first_builder.fail_get_header(true);
+    let (blinded_block, _) = chain
+        .produce_block_on_state::<BlindedPayload<E>>(
+            head.beacon_state.clone(),
+            None,
+            slot,
+            Signature::empty(),
+            None,
+            ProduceBlockVerification::NoVerification,
+        )
+        .await
+        .unwrap();
+    assert_eq!(first_builder.get_header_requests(), 1);
+    assert_eq!(second_builder.get_header_requests(), 1);
+
+    let block_root = blinded_block.canonical_root();
+    let record = chain
+        .recent_proposals()
+        .into_iter()
+        .find(|record| record.block_root == block_root)
+        .unwrap();
+    assert_eq!(record.payload_source, ProposalPayloadSource::Builder);
+    assert_eq!(record.relay, Some(second_url.redacted.clone()));
+
+    // The block is sent to the builder which offered its payload, even once it is removed.
+    assert!(chain.remove_builder(&second_url).unwrap());
+    assert!(!chain.remove_builder(&second_url).unwrap());
+    assert_eq!(chain.list_builders().len(), 1);
+    chain
+        .unblind_block(SignedBeaconBlock::from_block(
+            blinded_block,
+            Signature::empty(),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(second_builder.blinded_blocks_requests(), 1);
+    assert_eq!(first_builder.blinded_blocks_requests(), 0);
+}
+
+/// Ensure that the builder is not used after too many missed proposals which used a builder
+/// payload.
+#[tokio::test]
+async fn builder_fallback_after_missed_proposals() {
+    let num_blocks = E::slots_per_epoch() * 4;
+
+    let mut rig = InvalidPayloadRig::new_with_mock_builder().enable_attestations();
+    rig.move_to_terminal_block();
+    rig.import_block(Payload::Valid).await;
+    rig.build_blocks(num_blocks - 1, Payload::Valid).await;
+    assert!(rig.harness.finalized_checkpoint().epoch > 0);
+
+    let chain = rig.harness.chain.clone();
+    let mock_builder = rig
+        .harness
+        .mock_execution_layer
+        .as_ref()
+        .unwrap()
+        .builder
+        .as_ref()
+        .unwrap();
+    // The builder is only skipped once more than `builder_fallback_skips` proposals are missed.
+    let faults = chain.config.builder_fallback_skips as u64 + 1;
+
+    let head = chain.head_snapshot();
+    let produce_blinded_block = |slot| {
+        let chain = chain.clone();
+        let state = head.beacon_state.clone();
+        async move {
+            chain
+                .produce_block_on_state::<BlindedPayload<E>>(
+                    state,
+                    None,
+                    slot,
+                    Signature::empty(),
+                    None,
+                    ProduceBlockVerification::NoVerification,
+                )
+                .await
+                .unwrap()
+                .0
+        }
+    };
+
+    // Produce a blinded block using the builder at each of the next `faults` slots, but never
+    // publish them.
+    for i in 1..=faults {
+        let slot = head.beacon_block.slot() + i;
+        rig.harness.set_current_slot(slot);
+        // Produce a full block so that the mock execution engine has a payload for the builder to
+        // offer.
+        rig.harness
+            .make_block(head.beacon_state.clone(), slot)
+            .await;
+        produce_blinded_block(slot).await;
+        assert_eq!(mock_builder.get_header_requests() as u64, i);
+        chain.per_slot_task().await;
+    }
+
+    let slot = head.beacon_block.slot() + faults + MISSED_PROPOSAL_SLOT_TOLERANCE + 1;
+    rig.harness.set_current_slot(slot);
+    chain.per_slot_task().await;
+    assert!(chain
+        .recent_proposals()
+        .iter()
+        .filter(|record| record.payload_source == ProposalPayloadSource::Builder)
+        .all(|record| record.outcome == ProposalOutcome::Missed));
+
+    // The builder should be skipped entirely.
+    let blinded_block = produce_blinded_block(slot).await;
+    assert_eq!(mock_builder.get_header_requests() as u64, faults);
+    let record = chain
+        .recent_proposals()
+        .into_iter()
+        .find(|record| record.block_root == blinded_block.canonical_root())
+        .unwrap();
+    assert_eq!(record.payload_source, ProposalPayloadSource::Local);
+}
+
 #[tokio::test]
 async fn invalid_parent() {
     let mut rig = InvalidPayloadRig::new();
@@ -978,7 +1860,7 @@ async fn invalid_parent() {
     // Ensure the block built atop an invalid payload is invalid for gossip.
     assert!(matches!(
         rig.harness.chain.clone().verify_block_for_gossip(block.clone()).await,
-        Err(BlockError::ParentExecutionPayloadInvalid { parent_root: invalid_root })
+        Err(BlockError::ParentExecutionInvalid { parent_root: invalid_root })
         if invalid_root == parent_root
     ));
 
@@ -1011,6 +1893,63 @@ async fn invalid_parent() {
     ));
 }
 
+#[tokio::test]
+async fn invalid_branch_children_rejected_before_verification() {
+    let mut rig = InvalidPayloadRig::new();
+    rig.move_to_terminal_block();
+    rig.import_block(Payload::Valid).await; // Import a valid transition block.
+
+    // Import two syncing blocks atop the transition block.
+    let invalid_root = rig.import_block(Payload::Syncing).await;
+    let descendant_root = rig.import_block(Payload::Syncing).await;
+    let descendant_block = rig.harness.get_block(descendant_root.into()).unwrap();
+    let descendant_state = rig
+        .harness
+        .get_hot_state(descendant_block.state_root().into())
+        .unwrap();
+
+    // Produce a block atop the descendant with an invalid signature, but don't import it.
+    let slot = descendant_block.slot() + 1;
+    rig.harness.set_current_slot(slot);
+    let (block, _) = rig.harness.make_block(descendant_state, slot).await;
+    let (block, _) = block.deconstruct();
+    let block = Arc::new(SignedBeaconBlock::from_block(block, Signature::empty()));
+
+    // Invalidating the first block also invalidates its descendant.
+    rig.invalidate_manually(invalid_root).await;
+    assert!(rig.execution_status(descendant_root).is_invalid());
+    let cache = &rig.harness.chain.invalid_block_cache;
+    assert!(cache.contains(&invalid_root));
+    assert!(cache.contains(&descendant_root));
+
+    // The block is rejected before its signature is verified.
+    assert!(matches!(
+        rig.harness.chain.clone().verify_block_for_gossip(block).await,
+        Err(BlockError::ParentExecutionInvalid { parent_root })
+        if parent_root == descendant_root
+    ));
+}
+
+/// Ensure that a head whose only child has an invalid payload isn't reported as diverging from the
+/// head tracker, which tracks the child rather than the head.
+#[tokio::test]
+async fn head_with_invalid_child_is_consistent() {
+    let mut rig = InvalidPayloadRig::new();
+    rig.move_to_terminal_block();
+    let valid_root = rig.import_block(Payload::Valid).await; // Import a valid transition block.
+    let invalid_root = rig.import_block(Payload::Syncing).await;
+
+    rig.invalidate_manually(invalid_root).await;
+    rig.recompute_head().await;
+    assert_eq!(rig.harness.head_block_root(), valid_root);
+    let heads = rig.harness.chain.heads();
+    assert_eq!(heads.len(), 1);
+    assert_eq!(heads[0].0, invalid_root);
+
+    let consistency = rig.harness.chain.check_head_consistency();
+    assert!(consistency.is_consistent(), "{:?}", consistency);
+}
+
 /// Tests to ensure that we will still send a proposer preparation
 #[tokio::test]
 async fn payload_preparation_before_transition_block() {
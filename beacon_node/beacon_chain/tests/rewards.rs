@@ -0,0 +1,199 @@
+//! Tests for the computation of block rewards and attestation performance.
+
+#![cfg(not(debug_assertions))]
+
+use beacon_chain::observed_operations::ObservationOutcome;
+use beacon_chain::test_utils::{
+    AttestationStrategy, BeaconChainHarness, BlockStrategy, EphemeralHarnessType,
+    RelativeSyncCommittee,
+};
+use beacon_chain::BeaconChainError;
+use lazy_static::lazy_static;
+use state_processing::{
+    per_block_processing::altair::sync_committee::compute_sync_aggregate_rewards,
+    state_advance::complete_state_advance,
+};
+use types::*;
+
+pub const VALIDATOR_COUNT: usize = 24;
+
+lazy_static! {
+    /// A cached set of keys.
+    static ref KEYPAIRS: Vec<Keypair> =
+        types::test_utils::generate_deterministic_keypairs(VALIDATOR_COUNT);
+}
+
+type E = MinimalEthSpec;
+
+fn get_harness() -> BeaconChainHarness<EphemeralHarnessType<E>> {
+    let mut spec = E::default_spec();
+    spec.altair_fork_epoch = Some(Epoch::new(0));
+    let harness = BeaconChainHarness::builder(MinimalEthSpec)
+        .spec(spec)
+        .keypairs(KEYPAIRS.to_vec())
+        .fresh_ephemeral_store()
+        .mock_execution_layer()
+        .build();
+    harness.advance_slot();
+    harness
+}
+
+#[tokio::test]
+async fn block_reward_matches_proposer_balance_change() {
+    let harness = get_harness();
+    let spec = harness.chain.spec.clone();
+
+    harness
+        .extend_chain(
+            E::slots_per_epoch() as usize * 2,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::AllValidators,
+        )
+        .await;
+
+    // Add sync committee contributions for the head to the op pool, so that the next block has a
+    // sync aggregate.
+    let head = harness.chain.head_snapshot();
+    let head_state = head.beacon_state.clone();
+    for contribution in harness
+        .make_sync_contributions(
+            &head_state,
+            head.beacon_block_root,
+            head.beacon_block.slot(),
+            RelativeSyncCommittee::Current,
+        )
+        .into_iter()
+        .filter_map(|(_, contribution)| contribution)
+    {
+        harness
+            .chain
+            .op_pool
+            .insert_sync_contribution(contribution.message.contribution)
+            .unwrap();
+    }
+
+    harness.advance_slot();
+    let slot = harness.chain.slot().unwrap();
+    let mut pre_state = head_state.clone();
+    complete_state_advance(&mut pre_state, None, slot, &spec).unwrap();
+    pre_state.build_all_caches(&spec).unwrap();
+    let proposer_index = pre_state.get_beacon_proposer_index(slot, &spec).unwrap();
+
+    // Slash some validators other than the proposer. One validator is slashed by both slashings,
+    // but is only slashed (and rewarded) once.
+    let slashed = (0..VALIDATOR_COUNT as u64)
+        .filter(|index| *index != proposer_index as u64)
+        .take(3)
+        .collect::<Vec<_>>();
+    match harness
+        .chain
+        .verify_proposer_slashing_for_gossip(harness.make_proposer_slashing(slashed[0]))
+        .unwrap()
+    {
+        ObservationOutcome::New(slashing) => harness.chain.import_proposer_slashing(slashing),
+        ObservationOutcome::AlreadyKnown => panic!("proposer slashing should be new"),
+    }
+    match harness
+        .chain
+        .verify_attester_slashing_for_gossip(harness.make_attester_slashing(slashed.clone()))
+        .unwrap()
+    {
+        ObservationOutcome::New(slashing) => harness.chain.import_attester_slashing(slashing),
+        ObservationOutcome::AlreadyKnown => panic!("attester slashing should be new"),
+    }
+
+    let (block_root, block, post_state) =
+        harness.add_block_at_slot(slot, head_state).await.unwrap();
+    let body = block.message().body();
+    assert!(!body.attestations().is_empty());
+    assert_eq!(body.proposer_slashings().len(), 1);
+    assert_eq!(body.attester_slashings().len(), 1);
+    let sync_aggregate = body.sync_aggregate().unwrap();
+    assert!(sync_aggregate.num_set_bits() > 0);
+
+    let reward = harness
+        .chain
+        .compute_block_reward_for_root(block_root.into(), false)
+        .unwrap();
+    assert_eq!(reward.meta.proposer_index, proposer_index as u64);
+    assert!(reward.attestation_rewards.total > 0);
+    assert!(reward.sync_committee_rewards > 0);
+    let whistleblower_reward = spec.max_effective_balance / spec.whistleblower_reward_quotient;
+    assert_eq!(reward.proposer_slashing_reward, whistleblower_reward);
+    assert_eq!(reward.attester_slashing_reward, 2 * whistleblower_reward);
+
+    // The proposer's balance also changes if it is a member of the sync committee, which isn't
+    // part of the block reward.
+    let (participant_reward, _) = compute_sync_aggregate_rewards(&pre_state, &spec).unwrap();
+    let proposer_pubkey = &pre_state.validators()[proposer_index].pubkey;
+    let mut participation_delta = 0i64;
+    for (position, pubkey) in pre_state
+        .current_sync_committee()
+        .unwrap()
+        .pubkeys
+        .iter()
+        .enumerate()
+    {
+        if pubkey == proposer_pubkey {
+            if sync_aggregate.sync_committee_bits.get(position).unwrap() {
+                participation_delta += participant_reward as i64;
+            } else {
+                participation_delta -= participant_reward as i64;
+            }
+        }
+    }
+
+    let balance_delta =
+        post_state.balances()[proposer_index] as i64 - pre_state.balances()[proposer_index] as i64;
+    assert_eq!(balance_delta, reward.total as i64 + participation_delta);
+}
+
+#[tokio::test]
+async fn attestation_performance_of_idle_validator() {
+    let harness = get_harness();
+    let idle_validator = VALIDATOR_COUNT - 1;
+
+    harness
+        .extend_chain(
+            E::slots_per_epoch() as usize * 4,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::SomeValidators((0..idle_validator).collect()),
+        )
+        .await;
+
+    let epoch = Epoch::new(2);
+    let performance = harness.chain.attestation_performance(epoch, None).unwrap();
+    assert_eq!(performance.len(), VALIDATOR_COUNT);
+
+    for validator in &performance {
+        assert!(validator.active);
+        assert!(validator.ideal_reward > 0);
+        if validator.validator_index == idle_validator as u64 {
+            assert!(!validator.source);
+            assert!(!validator.target);
+            assert!(!validator.head);
+            assert!(validator.reward < 0);
+        } else {
+            assert!(validator.source && validator.target && validator.head);
+            assert_eq!(validator.reward, validator.ideal_reward as i64);
+        }
+    }
+
+    // Filtering returns the same report for just the requested validators.
+    let filter = [0, idle_validator as u64];
+    let filtered = harness
+        .chain
+        .attestation_performance(epoch, Some(&filter))
+        .unwrap();
+    assert_eq!(
+        filtered,
+        vec![performance[0].clone(), performance[idle_validator].clone()]
+    );
+
+    // The rewards for the current epoch haven't been applied yet.
+    let current_epoch = harness.chain.epoch().unwrap();
+    assert!(matches!(
+        harness.chain.attestation_performance(current_epoch, None),
+        Err(BeaconChainError::AttestationPerformanceEpochTooRecent(_))
+    ));
+}
@@ -2,27 +2,40 @@
 
 use beacon_chain::attestation_verification::Error as AttnError;
 use beacon_chain::builder::BeaconChainBuilder;
+use beacon_chain::chain_config::BackfillRateLimit;
+use beacon_chain::eth1_chain::{CachingEth1Backend, Eth1Chain};
+use beacon_chain::payload_reconstruction::PayloadReconstructionFailure;
+use beacon_chain::pubkey_cache_transfer::{PubkeyCacheHeader, PubkeyCacheTransferError};
 use beacon_chain::test_utils::{
     test_spec, AttestationStrategy, BeaconChainHarness, BlockStrategy, DiskHarnessType,
+    EphemeralHarnessType, DEFAULT_ETH1_BLOCK_HASH,
 };
 use beacon_chain::{
-    historical_blocks::HistoricalBlockError, migrate::MigratorConfig, BeaconChain,
-    BeaconChainError, BeaconChainTypes, BeaconSnapshot, ChainConfig, ServerSentEventHandler,
-    WhenSlotSkipped,
+    historical_blocks::HistoricalBlockError,
+    migrate::{BackgroundMigrator, MigratorConfig},
+    BeaconChain, BeaconChainError, BeaconChainTypes, BeaconSnapshot, BlockError, ChainConfig,
+    ChainSegmentResult, HeadTrackerPersistence, PersistHeadAndForkChoiceResult,
+    ServerSentEventHandler, WhenSlotSkipped,
 };
+use bls::get_withdrawal_credentials;
+use eth1::{Config as Eth1Config, Eth1Block};
 use lazy_static::lazy_static;
 use logging::test_logger;
 use maplit::hashset;
 use rand::Rng;
-use state_processing::BlockReplayer;
+use ssz::Encode;
+use state_processing::{common::DepositDataTree, BlockReplayer};
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::convert::TryInto;
 use std::sync::Arc;
 use std::time::Duration;
 use store::{
+    config::StoreConfigError,
     iter::{BlockRootsIterator, StateRootsIterator},
-    HotColdDB, LevelDB, StoreConfig,
+    metadata::{RestorePointMigration, RESTORE_POINT_MIGRATION_KEY},
+    DBColumn, DatabaseBlock, Error as StoreError, HotColdDB, HotStateDiff, ItemStore,
+    KeyValueStore, LevelDB, StoreConfig,
 };
 use tempfile::{tempdir, TempDir};
 use tree_hash::TreeHash;
@@ -58,6 +71,49 @@ fn get_store_with_spec(
         .expect("disk store should initialize")
 }
 
+fn get_store_with_slots_per_restore_point<T: EthSpec>(
+    db_path: &TempDir,
+    slots_per_restore_point: u64,
+    force_restore_point_migration: bool,
+) -> Result<Arc<HotColdDB<T, LevelDB<T>, LevelDB<T>>>, StoreError> {
+    let hot_path = db_path.path().join("hot_db");
+    let cold_path = db_path.path().join("cold_db");
+    let config = StoreConfig {
+        slots_per_restore_point,
+        slots_per_restore_point_set_explicitly: true,
+        force_restore_point_migration,
+        ..StoreConfig::default()
+    };
+    let log = test_logger();
+
+    HotColdDB::open(
+        &hot_path,
+        &cold_path,
+        |_, _, _| Ok(()),
+        config,
+        T::default_spec(),
+        log,
+    )
+}
+
+fn get_store_with_config(
+    db_path: &TempDir,
+    config: StoreConfig,
+) -> Result<Arc<HotColdDB<E, LevelDB<E>, LevelDB<E>>>, StoreError> {
+    let hot_path = db_path.path().join("hot_db");
+    let cold_path = db_path.path().join("cold_db");
+    let log = test_logger();
+
+    HotColdDB::open(
+        &hot_path,
+        &cold_path,
+        |_, _, _| Ok(()),
+        config,
+        test_spec::<E>(),
+        log,
+    )
+}
+
 fn get_harness(
     store: Arc<HotColdDB<E, LevelDB<E>, LevelDB<E>>>,
     validator_count: usize,
@@ -280,6 +336,234 @@ async fn split_slot_restore() {
     assert_eq!(store.get_split_slot(), split_slot);
 }
 
+#[test]
+fn slots_per_restore_point_mismatch_refused() {
+    type T = MainnetEthSpec;
+    let db_path = tempdir().unwrap();
+
+    drop(get_store_with_slots_per_restore_point::<T>(&db_path, 2048, false).unwrap());
+
+    let result = get_store_with_slots_per_restore_point::<T>(&db_path, 8192, false);
+    assert!(matches!(
+        result,
+        Err(StoreError::ConfigError(
+            StoreConfigError::MismatchedSlotsPerRestorePoint {
+                config: 8192,
+                on_disk: 2048,
+            }
+        ))
+    ));
+
+    // The database is untouched.
+    let store = get_store_with_slots_per_restore_point::<T>(&db_path, 2048, false).unwrap();
+    assert_eq!(store.get_config().slots_per_restore_point, 2048);
+}
+
+/// Build a chain in a database with 16 slots per restore point, returning the split slot and the
+/// state roots of the states in the freezer.
+async fn build_chain_for_restore_point_migration(
+    db_path: &TempDir,
+) -> (Slot, Vec<(Slot, Hash256)>) {
+    let num_blocks = 8 * E::slots_per_epoch();
+    let store = get_store_with_slots_per_restore_point::<E>(db_path, 16, false).unwrap();
+    let harness = get_harness(store.clone(), LOW_VALIDATOR_COUNT);
+
+    harness
+        .extend_chain(
+            num_blocks as usize,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::AllValidators,
+        )
+        .await;
+
+    let split_slot = store.get_split_slot();
+    let cold_state_roots = (0..split_slot.as_u64())
+        .map(Slot::new)
+        .map(|slot| {
+            (
+                slot,
+                harness.chain.state_root_at_slot(slot).unwrap().unwrap(),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    // There should be several restore points with both the old and new spacings.
+    assert!(split_slot >= Slot::new(40), "split slot: {}", split_slot);
+    (split_slot, cold_state_roots)
+}
+
+fn check_cold_states(
+    store: &HotColdDB<E, LevelDB<E>, LevelDB<E>>,
+    cold_state_roots: &[(Slot, Hash256)],
+) {
+    for (slot, state_root) in cold_state_roots {
+        let state = store.get_state(state_root, Some(*slot)).unwrap().unwrap();
+        assert_eq!(state.slot(), *slot);
+        assert_eq!(state.canonical_root(), *state_root);
+    }
+}
+
+fn run_restore_point_migration(store: &Arc<HotColdDB<E, LevelDB<E>, LevelDB<E>>>) {
+    BackgroundMigrator::new(
+        store.clone(),
+        MigratorConfig::default().blocking(),
+        Hash256::zero(),
+        test_logger(),
+    );
+}
+
+#[tokio::test]
+async fn slots_per_restore_point_migration() {
+    let db_path = tempdir().unwrap();
+    let (split_slot, cold_state_roots) = build_chain_for_restore_point_migration(&db_path).await;
+
+    // Migrate to denser restore points, then to sparser ones.
+    let mut old_slots_per_restore_point = 16;
+    for slots_per_restore_point in [8, 32] {
+        assert!(matches!(
+            get_store_with_slots_per_restore_point::<E>(&db_path, slots_per_restore_point, false),
+            Err(StoreError::ConfigError(_))
+        ));
+
+        // The migration is left to the background migrator, and the freezer remains readable
+        // with the old spacing until then.
+        let store =
+            get_store_with_slots_per_restore_point::<E>(&db_path, slots_per_restore_point, true)
+                .unwrap();
+        assert!(store.restore_point_migration_pending());
+        assert_eq!(
+            store.get_slots_per_restore_point(),
+            old_slots_per_restore_point
+        );
+        assert_eq!(store.get_split_slot(), split_slot);
+        check_cold_states(&store, &cold_state_roots);
+
+        run_restore_point_migration(&store);
+        assert!(!store.restore_point_migration_pending());
+        assert_eq!(store.get_slots_per_restore_point(), slots_per_restore_point);
+        check_cold_states(&store, &cold_state_roots);
+        drop(store);
+
+        // The new spacing is recorded on disk, so the database opens without the flag.
+        let store =
+            get_store_with_slots_per_restore_point::<E>(&db_path, slots_per_restore_point, false)
+                .unwrap();
+        assert_eq!(
+            store.get_config().slots_per_restore_point,
+            slots_per_restore_point
+        );
+        assert_eq!(store.get_slots_per_restore_point(), slots_per_restore_point);
+        old_slots_per_restore_point = slots_per_restore_point;
+    }
+}
+
+#[tokio::test]
+async fn slots_per_restore_point_migration_interrupted() {
+    let db_path = tempdir().unwrap();
+    let (_, cold_state_roots) = build_chain_for_restore_point_migration(&db_path).await;
+    let slot_8_state_root = cold_state_roots[8].1;
+
+    // Interrupt a migration after the new restore points are written.
+    let interrupt = |slots_per_restore_point| {
+        let store =
+            get_store_with_slots_per_restore_point::<E>(&db_path, slots_per_restore_point, true)
+                .unwrap();
+        assert!(!store.restore_point_migration_step().unwrap());
+        assert!(store
+            .hot_db
+            .get::<RestorePointMigration>(&RESTORE_POINT_MIGRATION_KEY)
+            .unwrap()
+            .is_some());
+        store
+    };
+    drop(interrupt(8));
+
+    // Opening without the flag rolls the migration back.
+    let store = get_store_with_slots_per_restore_point::<E>(&db_path, 16, false).unwrap();
+    assert!(!store.restore_point_migration_pending());
+    assert!(store
+        .hot_db
+        .get::<RestorePointMigration>(&RESTORE_POINT_MIGRATION_KEY)
+        .unwrap()
+        .is_none());
+    assert!(!store
+        .cold_db
+        .key_exists(DBColumn::BeaconState.into(), slot_8_state_root.as_bytes())
+        .unwrap());
+    check_cold_states(&store, &cold_state_roots);
+    drop(store);
+
+    // Opening with the flag resumes the migration.
+    drop(interrupt(8));
+    let store = get_store_with_slots_per_restore_point::<E>(&db_path, 8, true).unwrap();
+    assert!(store.restore_point_migration_pending());
+    assert!(store
+        .cold_db
+        .key_exists(DBColumn::BeaconState.into(), slot_8_state_root.as_bytes())
+        .unwrap());
+    run_restore_point_migration(&store);
+    assert_eq!(store.get_slots_per_restore_point(), 8);
+    check_cold_states(&store, &cold_state_roots);
+    drop(store);
+
+    // A migration interrupted whilst replacing the index is completed on open, even without the
+    // flag.
+    let store = interrupt(32);
+    let mut migration = store
+        .hot_db
+        .get::<RestorePointMigration>(&RESTORE_POINT_MIGRATION_KEY)
+        .unwrap()
+        .unwrap();
+    migration.replacing_index = true;
+    store
+        .hot_db
+        .put(&RESTORE_POINT_MIGRATION_KEY, &migration)
+        .unwrap();
+    drop(store);
+
+    let store = get_store_with_slots_per_restore_point::<E>(&db_path, 32, false).unwrap();
+    assert!(!store.restore_point_migration_pending());
+    assert_eq!(store.get_slots_per_restore_point(), 32);
+    assert!(!store
+        .cold_db
+        .key_exists(DBColumn::BeaconState.into(), slot_8_state_root.as_bytes())
+        .unwrap());
+    check_cold_states(&store, &cold_state_roots);
+}
+
+#[tokio::test]
+async fn slots_per_restore_point_migration_interrupted_without_force() {
+    let db_path = tempdir().unwrap();
+    let (_, cold_state_roots) = build_chain_for_restore_point_migration(&db_path).await;
+
+    // Interrupt a migration after the new restore points are written.
+    let store = get_store_with_slots_per_restore_point::<E>(&db_path, 8, true).unwrap();
+    assert!(!store.restore_point_migration_step().unwrap());
+    drop(store);
+
+    // Reopening with the new spacing but without the flag doesn't continue the migration.
+    assert!(matches!(
+        get_store_with_slots_per_restore_point::<E>(&db_path, 8, false),
+        Err(StoreError::ConfigError(
+            StoreConfigError::MismatchedSlotsPerRestorePoint {
+                config: 8,
+                on_disk: 16,
+            }
+        ))
+    ));
+
+    // The migration was rolled back, so the database is still usable with its original spacing.
+    let store = get_store_with_slots_per_restore_point::<E>(&db_path, 16, false).unwrap();
+    assert!(!store.restore_point_migration_pending());
+    assert_eq!(store.get_slots_per_restore_point(), 16);
+    assert!(store
+        .hot_db
+        .get::<RestorePointMigration>(&RESTORE_POINT_MIGRATION_KEY)
+        .unwrap()
+        .is_none());
+    check_cold_states(&store, &cold_state_roots);
+}
+
 // Check attestation processing and `load_epoch_boundary_state` in the presence of a split DB.
 // This is a bit of a monster test in that it tests lots of different things, but until they're
 // tested elsewhere, this is as good a place as any.
@@ -438,6 +722,80 @@ async fn forwards_iter_block_and_state_roots_until() {
     test_range(Slot::new(0), head_state.slot());
 }
 
+#[tokio::test]
+async fn epoch_boundary_state_roots_match_individual_lookups() {
+    let db_path = tempdir().unwrap();
+    let store = get_store(&db_path);
+    let harness = get_harness(store.clone(), LOW_VALIDATOR_COUNT);
+    let slots_per_epoch = E::slots_per_epoch();
+
+    harness
+        .extend_chain(
+            (slots_per_epoch * 6 - 1) as usize,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::AllValidators,
+        )
+        .await;
+    // Skip the first slot of an epoch.
+    harness.advance_slot();
+    harness
+        .extend_chain(
+            (slots_per_epoch * 11) as usize,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::AllValidators,
+        )
+        .await;
+
+    let chain = &harness.chain;
+    let head_epoch = chain
+        .head_snapshot()
+        .beacon_block
+        .slot()
+        .epoch(slots_per_epoch);
+    // Some of the roots are only in the freezer, and some are beyond the head state's roots.
+    assert!(store.get_split_slot() > slots_per_epoch);
+    assert!(head_epoch.start_slot(slots_per_epoch) > E::slots_per_historical_root() as u64);
+
+    let expected = |epochs: std::ops::Range<u64>| {
+        epochs
+            .map(Epoch::new)
+            .map(|epoch| {
+                let slot = epoch.start_slot(slots_per_epoch);
+                (epoch, chain.state_root_at_slot(slot).unwrap().unwrap())
+            })
+            .collect::<Vec<_>>()
+    };
+
+    for (start, end) in [
+        (0, head_epoch.as_u64() + 1),
+        (2, 9),
+        (6, 7),
+        (head_epoch.as_u64() - 2, head_epoch.as_u64() + 1),
+    ] {
+        assert_eq!(
+            chain
+                .epoch_boundary_state_roots(Epoch::new(start)..Epoch::new(end))
+                .unwrap(),
+            expected(start..end),
+            "epochs {}..{}",
+            start,
+            end
+        );
+    }
+
+    // Epochs which start after the head are omitted.
+    assert_eq!(
+        chain
+            .epoch_boundary_state_roots(head_epoch..head_epoch + 3)
+            .unwrap(),
+        expected(head_epoch.as_u64()..head_epoch.as_u64() + 1)
+    );
+    assert!(chain
+        .epoch_boundary_state_roots(Epoch::new(3)..Epoch::new(3))
+        .unwrap()
+        .is_empty());
+}
+
 #[tokio::test]
 async fn block_replay_with_inaccurate_state_roots() {
     let num_blocks_produced = E::slots_per_epoch() * 3 + 31;
@@ -1020,7 +1378,12 @@ async fn prunes_abandoned_fork_between_two_finalized_checkpoints() {
     let (mut state, state_root) = rig.get_current_state_and_root();
 
     let canonical_chain_slots: Vec<Slot> = (1..=rig.epoch_start_slot(1)).map(Slot::new).collect();
-    let (canonical_chain_blocks_pre_finalization, _, _, new_state) = rig
+    let (
+        canonical_chain_blocks_pre_finalization,
+        canonical_chain_states_pre_finalization,
+        _,
+        new_state,
+    ) = rig
         .add_attested_blocks_at_slots(
             state,
             state_root,
@@ -1066,6 +1429,10 @@ async fn prunes_abandoned_fork_between_two_finalized_checkpoints() {
 
     assert!(rig.chain.knows_head(&stray_head));
 
+    let pruned_blocks = counter_value("beacon_pruned_abandoned_blocks_total");
+    let pruned_states = counter_value("beacon_pruned_abandoned_states_total");
+    let pruned_bytes = counter_value("beacon_pruned_abandoned_bytes_total");
+
     // Trigger finalization
     let finalization_slots: Vec<Slot> = ((canonical_chain_slot + 1)
         ..=(canonical_chain_slot + slots_per_epoch * 5))
@@ -1110,6 +1477,48 @@ async fn prunes_abandoned_fork_between_two_finalized_checkpoints() {
     }
 
     assert!(!rig.chain.knows_head(&stray_head));
+
+    // The canonical chain is untouched.
+    for &block_hash in canonical_chain_blocks_pre_finalization.values() {
+        assert!(
+            rig.block_exists(block_hash),
+            "canonical block {} should be present",
+            block_hash
+        );
+    }
+    for (&slot, &state_hash) in &canonical_chain_states_pre_finalization {
+        assert!(
+            rig.hot_state_exists(state_hash) || rig.cold_state_exists(state_hash),
+            "canonical state {} at slot {} should be present",
+            state_hash,
+            slot
+        );
+    }
+
+    // Other tests may prune concurrently, so the counters increase by at least this fork.
+    assert!(
+        counter_value("beacon_pruned_abandoned_blocks_total") - pruned_blocks
+            >= stray_blocks.len() as u64
+    );
+    assert!(
+        counter_value("beacon_pruned_abandoned_states_total") - pruned_states
+            >= stray_states.len() as u64
+    );
+    assert!(counter_value("beacon_pruned_abandoned_bytes_total") > pruned_bytes);
+}
+
+/// The total of the counter named `name` across all tests, or 0 if it hasn't been registered.
+pub(crate) fn counter_value(name: &str) -> u64 {
+    lighthouse_metrics::gather()
+        .iter()
+        .find(|family| family.get_name() == name)
+        .map_or(0, |family| {
+            family
+                .get_metric()
+                .iter()
+                .map(|metric| metric.get_counter().get_value() as u64)
+                .sum()
+        })
 }
 
 #[tokio::test]
@@ -2147,6 +2556,31 @@ async fn weak_subjectivity_sync() {
         ))
     ));
 
+    // Iterating backwards from the head should end at the anchor block, without error.
+    let head_block_root = beacon_chain.head_snapshot().beacon_block_root;
+    let mut rev_iter = beacon_chain
+        .rev_iter_available_block_roots_from(head_block_root)
+        .unwrap();
+    let rev_block_roots = rev_iter.by_ref().collect::<Result<Vec<_>, _>>().unwrap();
+    assert!(rev_iter.is_truncated());
+    assert_eq!(
+        rev_block_roots.last(),
+        Some(&(wss_block.canonical_root(), wss_slot))
+    );
+    for (block_root, _) in &rev_block_roots {
+        assert!(beacon_chain
+            .get_blinded_block(block_root)
+            .unwrap()
+            .is_some());
+    }
+    assert_eq!(
+        beacon_chain
+            .rev_iter_block_roots_from(head_block_root)
+            .unwrap()
+            .count(),
+        rev_block_roots.len()
+    );
+
     // Simulate processing of a `StatusMessage` with an older finalized epoch by calling
     // `block_root_at_slot` with an old slot for which we don't know the block root. It should
     // return `None` rather than erroring.
@@ -2221,59 +2655,395 @@ async fn weak_subjectivity_sync() {
     assert_eq!(store.get_anchor_slot(), None);
 }
 
-#[tokio::test]
-async fn finalizes_after_resuming_from_db() {
-    let validator_count = 16;
-    let num_blocks_produced = MinimalEthSpec::slots_per_epoch() * 8;
-    let first_half = num_blocks_produced / 2;
+/// A chain started from the finalized checkpoint of another chain, without any history.
+struct CheckpointSyncedChain {
+    chain: Arc<BeaconChain<DiskHarnessType<E>>>,
+    /// The blocks prior to the checkpoint, excluding genesis, in slot-ascending order.
+    historical_blocks: Vec<Arc<SignedBlindedBeaconBlock<E>>>,
+    _datadir: TempDir,
+}
 
-    let db_path = tempdir().unwrap();
-    let store = get_store(&db_path);
+/// Start a new chain from the finalized checkpoint of `harness`.
+fn checkpoint_sync_from(harness: &TestHarness, chain_config: ChainConfig) -> CheckpointSyncedChain {
+    let full_store = &harness.chain.store;
+    let genesis_state = full_store
+        .get_state(&harness.chain.genesis_state_root, Some(Slot::new(0)))
+        .unwrap()
+        .unwrap();
+    let wss_checkpoint = harness.finalized_checkpoint();
+    let wss_block = full_store
+        .get_full_block(&wss_checkpoint.root)
+        .unwrap()
+        .unwrap();
+    let wss_state = full_store
+        .get_state(&wss_block.state_root(), None)
+        .unwrap()
+        .unwrap();
+    let wss_slot = wss_block.slot();
 
-    let harness = BeaconChainHarness::builder(MinimalEthSpec)
-        .default_spec()
-        .keypairs(KEYPAIRS[0..validator_count].to_vec())
-        .fresh_disk_store(store.clone())
-        .mock_execution_layer()
-        .build();
+    let (shutdown_tx, _shutdown_rx) = futures::channel::mpsc::channel(1);
+    let log = test_logger();
+    let datadir = tempdir().unwrap();
+    let store = get_store(&datadir);
+    let seconds_per_slot = test_spec::<E>().seconds_per_slot;
 
-    harness.advance_slot();
+    let chain = Arc::new(
+        BeaconChainBuilder::new(MinimalEthSpec)
+            .store(store)
+            .custom_spec(test_spec::<E>())
+            .task_executor(harness.chain.task_executor.clone())
+            .weak_subjectivity_state(wss_state, wss_block, genesis_state)
+            .unwrap()
+            .logger(log.clone())
+            .store_migrator_config(MigratorConfig::default().blocking())
+            .dummy_eth1_backend()
+            .expect("should build dummy backend")
+            .testing_slot_clock(Duration::from_secs(seconds_per_slot))
+            .expect("should configure testing slot clock")
+            .shutdown_sender(shutdown_tx)
+            .chain_config(chain_config)
+            .event_handler(Some(ServerSentEventHandler::new_with_capacity(
+                log.clone(),
+                1,
+            )))
+            .monitor_validators(true, vec![], log)
+            .build()
+            .expect("should build"),
+    );
+
+    let historical_blocks = harness.chain.chain_dump().unwrap()[..wss_slot.as_usize()]
+        .iter()
+        .filter(|s| s.beacon_block.slot() != 0)
+        .map(|s| s.beacon_block.clone())
+        .collect();
+
+    CheckpointSyncedChain {
+        chain,
+        historical_blocks,
+        _datadir: datadir,
+    }
+}
 
+/// The head of a chain started from an anchor state, without the history prior to it, is usable
+/// and still provides the proposer shuffling decision root and randao mix.
+#[tokio::test]
+async fn checkpoint_synced_head_tolerates_missing_history() {
+    let temp1 = tempdir().unwrap();
+    let full_store = get_store(&temp1);
+    let harness = get_harness(full_store, LOW_VALIDATOR_COUNT);
     harness
         .extend_chain(
-            first_half as usize,
+            (E::slots_per_epoch() * 6) as usize,
             BlockStrategy::OnCanonicalHead,
             AttestationStrategy::AllValidators,
         )
         .await;
 
-    assert!(
-        harness
-            .chain
-            .head_snapshot()
-            .beacon_state
-            .finalized_checkpoint()
-            .epoch
-            > 0,
-        "the chain should have already finalized"
-    );
+    let CheckpointSyncedChain {
+        chain: beacon_chain,
+        _datadir,
+        ..
+    } = checkpoint_sync_from(&harness, ChainConfig::default());
 
-    let latest_slot = harness.chain.slot().expect("should have a slot");
+    // The head is the anchor, and the blocks prior to it are missing.
+    let cached_head = beacon_chain.canonical_head.cached_head();
+    let anchor = &cached_head.snapshot;
+    assert_eq!(
+        beacon_chain.store.get_oldest_block_slot(),
+        anchor.beacon_block.slot()
+    );
 
-    harness
-        .chain
-        .persist_head_and_fork_choice()
-        .expect("should persist the head and fork choice");
-    harness
-        .chain
-        .persist_op_pool()
-        .expect("should persist the op pool");
-    harness
-        .chain
-        .persist_eth1_cache()
-        .expect("should persist the eth1 cache");
+    assert_eq!(
+        cached_head.head_proposer_shuffling_decision_root(),
+        Some(
+            anchor
+                .beacon_state
+                .proposer_shuffling_decision_root(anchor.beacon_block_root)
+                .unwrap()
+        )
+    );
+    assert_eq!(
+        cached_head.head_random(),
+        Some(
+            *anchor
+                .beacon_state
+                .get_randao_mix(anchor.beacon_state.current_epoch())
+                .unwrap()
+        )
+    );
+    assert_eq!(cached_head.head_block_root(), anchor.beacon_block_root);
+    assert_eq!(
+        cached_head.finalized_checkpoint().root,
+        anchor.beacon_block_root
+    );
+}
 
-    let original_chain = harness.chain;
+#[tokio::test]
+async fn backfill_is_rate_limited() {
+    let num_initial_blocks = E::slots_per_epoch() * 6;
+
+    let temp1 = tempdir().unwrap();
+    let full_store = get_store(&temp1);
+    let harness = get_harness(full_store, LOW_VALIDATOR_COUNT);
+
+    harness
+        .extend_chain(
+            num_initial_blocks as usize,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::AllValidators,
+        )
+        .await;
+
+    // Don't budget for any batches, leaving only the guaranteed batch every 3 slots.
+    let chain_config = ChainConfig {
+        backfill_rate_limit: Some(BackfillRateLimit {
+            batches_per_slot: 0,
+            max_slots_between_batches: 3,
+        }),
+        ..ChainConfig::default()
+    };
+    let CheckpointSyncedChain {
+        chain: beacon_chain,
+        historical_blocks,
+        _datadir,
+    } = checkpoint_sync_from(&harness, chain_config);
+
+    // Split the historical blocks into batches of one epoch, which are imported newest first.
+    let mut batches = historical_blocks
+        .chunks(E::slots_per_epoch() as usize)
+        .map(<[_]>::to_vec)
+        .collect::<Vec<_>>();
+    let num_batches = batches.len();
+    assert!(num_batches > 1);
+
+    // Attempt to import every remaining batch in each slot, as an unrestricted backfill would.
+    let rate_limited = counter_value("beacon_backfill_rate_limited_total");
+    let mut imported_per_slot = vec![];
+    let mut slot = beacon_chain.slot().unwrap();
+    while !batches.is_empty() {
+        beacon_chain.slot_clock.set_slot(slot.as_u64());
+        let mut imported = 0;
+        while !batches.is_empty() && beacon_chain.try_acquire_backfill_batch() {
+            beacon_chain
+                .import_historical_block_batch(batches.pop().unwrap())
+                .unwrap();
+            imported += 1;
+        }
+        imported_per_slot.push(imported);
+        slot += 1;
+    }
+    let expected = (0..imported_per_slot.len())
+        .map(|i| if i % 3 == 0 { 1 } else { 0 })
+        .collect::<Vec<_>>();
+    assert_eq!(imported_per_slot, expected);
+    assert_eq!(imported_per_slot.len(), 3 * (num_batches - 1) + 1);
+    assert_eq!(beacon_chain.store.get_oldest_block_slot(), 0);
+
+    // Whilst paused, no batches are allowed, not even after the maximum interval.
+    beacon_chain.pause_backfill();
+    assert!(beacon_chain.is_backfill_paused());
+    for _ in 0..4 {
+        beacon_chain.slot_clock.set_slot(slot.as_u64());
+        assert!(!beacon_chain.try_acquire_backfill_batch());
+        slot += 1;
+    }
+
+    // Once resumed, a batch is allowed straight away.
+    beacon_chain.resume_backfill();
+    assert!(!beacon_chain.is_backfill_paused());
+    beacon_chain.slot_clock.set_slot(slot.as_u64());
+    assert!(beacon_chain.try_acquire_backfill_batch());
+    assert!(!beacon_chain.try_acquire_backfill_batch());
+
+    // Every refused batch is counted: one per slot until the last batch is imported, one per
+    // paused slot and one after resuming.
+    assert_eq!(
+        counter_value("beacon_backfill_rate_limited_total") - rate_limited,
+        (imported_per_slot.len() - 1 + 4 + 1) as u64
+    );
+}
+
+#[tokio::test]
+async fn backfill_rejects_blocks_not_in_anchor_block_roots() {
+    let slots_per_historical_root = E::slots_per_historical_root() as u64;
+    let num_initial_blocks = E::slots_per_epoch() * 11;
+
+    let temp1 = tempdir().unwrap();
+    let full_store = get_store(&temp1);
+    let harness = get_harness(full_store, LOW_VALIDATOR_COUNT);
+
+    harness
+        .extend_chain(
+            num_initial_blocks as usize,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::AllValidators,
+        )
+        .await;
+
+    let CheckpointSyncedChain {
+        chain: beacon_chain,
+        historical_blocks,
+        _datadir,
+    } = checkpoint_sync_from(&harness, ChainConfig::default());
+    let wss_slot = beacon_chain.store.get_oldest_block_slot();
+
+    // The anchor's `block_roots` cover the blocks after `window_start`, which is within the
+    // history, and wrap around a `historical_roots` boundary.
+    let window_start = wss_slot - slots_per_historical_root;
+    let period_boundary =
+        Slot::new(wss_slot.as_u64() / slots_per_historical_root * slots_per_historical_root);
+    assert!(window_start > 1);
+    assert!(period_boundary > window_start);
+
+    // Every slot has a block, so the block at `slot` is at index `slot - 1`.
+    let corrupt = |blocks: &[Arc<SignedBlindedBeaconBlock<E>>], slot: Slot| {
+        let mut blocks = blocks.to_vec();
+        let index = slot.as_usize() - 1;
+        let mut block = (*blocks[index]).clone();
+        *block.message_mut().state_root_mut() = Hash256::repeat_byte(0xff);
+        blocks[index] = Arc::new(block);
+        blocks
+    };
+
+    // A batch straddling the `historical_roots` boundary with a corrupt block after the boundary
+    // is rejected at that block's slot.
+    let batch_start = period_boundary.as_usize() - 4;
+    let batch = &historical_blocks[batch_start - 1..];
+    let corrupt_slot = period_boundary + 1;
+    let corrupt_batch = corrupt(&historical_blocks, corrupt_slot);
+    match beacon_chain.import_historical_block_batch(corrupt_batch[batch_start - 1..].to_vec()) {
+        Err(BeaconChainError::HistoricalBlockError(
+            HistoricalBlockError::AnchorBlockRootMismatch {
+                slot,
+                block_root,
+                expected_block_root,
+            },
+        )) => {
+            assert_eq!(slot, corrupt_slot);
+            assert_eq!(
+                block_root,
+                corrupt_batch[corrupt_slot.as_usize() - 1].canonical_root()
+            );
+            assert_eq!(
+                expected_block_root,
+                historical_blocks[corrupt_slot.as_usize() - 1].canonical_root()
+            );
+        }
+        other => panic!("expected an anchor block root mismatch, got {:?}", other),
+    }
+    assert_eq!(beacon_chain.store.get_oldest_block_slot(), wss_slot);
+
+    // The same is true of a corrupt block before the boundary.
+    let corrupt_slot = period_boundary - 2;
+    let corrupt_batch = corrupt(&historical_blocks, corrupt_slot);
+    assert!(matches!(
+        beacon_chain.import_historical_block_batch(corrupt_batch[batch_start - 1..].to_vec()),
+        Err(BeaconChainError::HistoricalBlockError(
+            HistoricalBlockError::AnchorBlockRootMismatch { slot, .. }
+        )) if slot == corrupt_slot
+    ));
+
+    beacon_chain
+        .import_historical_block_batch(batch.to_vec())
+        .unwrap();
+    assert_eq!(
+        beacon_chain.store.get_oldest_block_slot(),
+        Slot::new(batch_start as u64)
+    );
+    assert!(beacon_chain.anchor_block_roots.read().is_some());
+
+    // The block at the start of the window is the oldest to be checked against the anchor.
+    let corrupt_batch = corrupt(&historical_blocks, window_start);
+    assert!(matches!(
+        beacon_chain.import_historical_block_batch(corrupt_batch[..batch_start - 1].to_vec()),
+        Err(BeaconChainError::HistoricalBlockError(
+            HistoricalBlockError::AnchorBlockRootMismatch { slot, .. }
+        )) if slot == window_start
+    ));
+
+    // Blocks prior to the window are only checked against their successors, so a corrupt block
+    // in a batch straddling the start of the window is reported as a mismatched parent.
+    let corrupt_slot = window_start - 1;
+    let corrupt_batch = corrupt(&historical_blocks, corrupt_slot);
+    assert!(matches!(
+        beacon_chain.import_historical_block_batch(corrupt_batch[..batch_start - 1].to_vec()),
+        Err(BeaconChainError::HistoricalBlockError(
+            HistoricalBlockError::MismatchedBlockRoot { block_root, .. }
+        )) if block_root == corrupt_batch[corrupt_slot.as_usize() - 1].canonical_root()
+    ));
+    assert_eq!(
+        beacon_chain.store.get_oldest_block_slot(),
+        Slot::new(batch_start as u64)
+    );
+
+    // Once backfill reaches the start of the window, the anchor's block roots are freed.
+    let batch = &historical_blocks[window_start.as_usize() - 1..batch_start - 1];
+    beacon_chain
+        .import_historical_block_batch(batch.to_vec())
+        .unwrap();
+    assert_eq!(beacon_chain.store.get_oldest_block_slot(), window_start);
+    assert!(beacon_chain.anchor_block_roots.read().is_none());
+
+    let batch = &historical_blocks[..window_start.as_usize() - 1];
+    beacon_chain
+        .import_historical_block_batch(batch.to_vec())
+        .unwrap();
+    assert_eq!(beacon_chain.store.get_oldest_block_slot(), 0);
+}
+
+#[tokio::test]
+async fn finalizes_after_resuming_from_db() {
+    let validator_count = 16;
+    let num_blocks_produced = MinimalEthSpec::slots_per_epoch() * 8;
+    let first_half = num_blocks_produced / 2;
+
+    let db_path = tempdir().unwrap();
+    let store = get_store(&db_path);
+
+    let harness = BeaconChainHarness::builder(MinimalEthSpec)
+        .default_spec()
+        .keypairs(KEYPAIRS[0..validator_count].to_vec())
+        .fresh_disk_store(store.clone())
+        .mock_execution_layer()
+        .build();
+
+    harness.advance_slot();
+
+    harness
+        .extend_chain(
+            first_half as usize,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::AllValidators,
+        )
+        .await;
+
+    assert!(
+        harness
+            .chain
+            .head_snapshot()
+            .beacon_state
+            .finalized_checkpoint()
+            .epoch
+            > 0,
+        "the chain should have already finalized"
+    );
+
+    let latest_slot = harness.chain.slot().expect("should have a slot");
+
+    harness
+        .chain
+        .persist_head_and_fork_choice()
+        .expect("should persist the head and fork choice");
+    harness
+        .chain
+        .persist_op_pool()
+        .expect("should persist the op pool");
+    harness
+        .chain
+        .persist_eth1_cache()
+        .expect("should persist the eth1 cache");
+
+    let original_chain = harness.chain;
 
     let resumed_harness = BeaconChainHarness::builder(MinimalEthSpec)
         .default_spec()
@@ -2324,88 +3094,361 @@ async fn finalizes_after_resuming_from_db() {
 }
 
 #[tokio::test]
-async fn revert_minority_fork_on_resume() {
+async fn read_only_freezer_skips_migration() {
     let validator_count = 16;
-    let slots_per_epoch = MinimalEthSpec::slots_per_epoch();
-
-    let fork_epoch = Epoch::new(4);
-    let fork_slot = fork_epoch.start_slot(slots_per_epoch);
-    let initial_blocks = slots_per_epoch * fork_epoch.as_u64() - 1;
-    let post_fork_blocks = slots_per_epoch * 3;
-
-    let mut spec1 = MinimalEthSpec::default_spec();
-    spec1.altair_fork_epoch = None;
-    let mut spec2 = MinimalEthSpec::default_spec();
-    spec2.altair_fork_epoch = Some(fork_epoch);
+    let num_blocks_produced = MinimalEthSpec::slots_per_epoch() * 8;
+    let first_half = num_blocks_produced / 2;
+    let db_path = tempdir().unwrap();
+    let read_only_config = StoreConfig {
+        freezer_readonly: true,
+        ..StoreConfig::default()
+    };
 
-    let seconds_per_slot = spec1.seconds_per_slot;
+    // A read-only freezer must already exist.
+    assert!(get_store_with_config(&db_path, read_only_config.clone()).is_err());
 
-    let all_validators = (0..validator_count).collect::<Vec<usize>>();
+    let (latest_slot, split_slot, cold_state_roots) = {
+        let store = get_store(&db_path);
+        let harness = get_harness(store.clone(), validator_count);
+        harness
+            .extend_chain(
+                first_half as usize,
+                BlockStrategy::OnCanonicalHead,
+                AttestationStrategy::AllValidators,
+            )
+            .await;
+        harness.chain.persist_head_and_fork_choice().unwrap();
+        harness.chain.persist_op_pool().unwrap();
+        harness.chain.persist_eth1_cache().unwrap();
 
-    // Chain with no fork epoch configured.
-    let db_path1 = tempdir().unwrap();
-    let store1 = get_store_with_spec(&db_path1, spec1.clone());
-    let harness1 = BeaconChainHarness::builder(MinimalEthSpec)
-        .spec(spec1)
-        .keypairs(KEYPAIRS[0..validator_count].to_vec())
-        .fresh_disk_store(store1)
-        .mock_execution_layer()
-        .build();
+        let split_slot = store.get_split_slot();
+        assert!(split_slot > 0, "the freezer should contain some states");
+        let cold_state_roots = (0..split_slot.as_u64())
+            .map(Slot::new)
+            .map(|slot| {
+                (
+                    slot,
+                    harness.chain.state_root_at_slot(slot).unwrap().unwrap(),
+                )
+            })
+            .collect::<Vec<_>>();
+        (harness.chain.slot().unwrap(), split_slot, cold_state_roots)
+    };
 
-    // Chain with fork epoch configured.
-    let db_path2 = tempdir().unwrap();
-    let store2 = get_store_with_spec(&db_path2, spec2.clone());
-    let harness2 = BeaconChainHarness::builder(MinimalEthSpec)
-        .spec(spec2.clone())
+    let store = get_store_with_config(&db_path, read_only_config).unwrap();
+    let resumed_harness = BeaconChainHarness::builder(MinimalEthSpec)
+        .default_spec()
         .keypairs(KEYPAIRS[0..validator_count].to_vec())
-        .fresh_disk_store(store2)
+        .resumed_disk_store(store.clone())
         .mock_execution_layer()
         .build();
+    resumed_harness
+        .chain
+        .slot_clock
+        .set_slot(latest_slot.as_u64() + 1);
 
-    // Apply the same blocks to both chains initially.
-    let mut state = harness1.get_current_state();
-    let mut block_root = harness1.chain.genesis_block_root;
-    for slot in (1..=initial_blocks).map(Slot::new) {
-        let state_root = state.update_tree_hash_cache().unwrap();
-
-        let attestations = harness1.make_attestations(
-            &all_validators,
-            &state,
-            state_root,
-            block_root.into(),
-            slot,
-        );
-        harness1.set_current_slot(slot);
-        harness2.set_current_slot(slot);
-        harness1.process_attestations(attestations.clone());
-        harness2.process_attestations(attestations);
-
-        let (block, new_state) = harness1.make_block(state, slot).await;
+    let finalized_epoch_before = resumed_harness
+        .chain
+        .head_snapshot()
+        .beacon_state
+        .finalized_checkpoint()
+        .epoch;
+    resumed_harness
+        .extend_chain(
+            (num_blocks_produced - first_half) as usize,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::AllValidators,
+        )
+        .await;
 
-        harness1.process_block(slot, block.clone()).await.unwrap();
-        harness2.process_block(slot, block.clone()).await.unwrap();
+    // Blocks are imported and the chain finalizes as usual.
+    let state = &resumed_harness.chain.head_snapshot().beacon_state;
+    assert_eq!(state.slot(), num_blocks_produced);
+    assert!(state.finalized_checkpoint().epoch > finalized_epoch_before);
 
-        state = new_state;
-        block_root = block.canonical_root();
+    // Nothing was migrated to the freezer, which can still be read.
+    assert_eq!(store.get_split_slot(), split_slot);
+    for (slot, state_root) in &cold_state_roots {
+        let state = store.get_state(state_root, Some(*slot)).unwrap().unwrap();
+        assert_eq!(state.canonical_root(), *state_root);
     }
+    assert!(matches!(
+        store.cold_db.put_bytes("test", b"key", b"value"),
+        Err(StoreError::ReadOnlyDatabase)
+    ));
+}
 
-    assert_eq!(harness1.head_slot(), fork_slot - 1);
-    assert_eq!(harness2.head_slot(), fork_slot - 1);
+#[tokio::test]
+async fn repair_on_startup_rebuilds_fork_choice() {
+    let validator_count = 16;
+    let slots_per_epoch = MinimalEthSpec::slots_per_epoch();
+    let db_path = tempdir().unwrap();
+    let store = get_store(&db_path);
+    let spec = test_spec::<E>();
 
-    // Fork the two chains.
-    let mut state1 = state.clone();
-    let mut state2 = state.clone();
+    let (head_block_root, finalized_checkpoint, latest_slot) = {
+        let harness = get_harness(store.clone(), validator_count);
+        harness
+            .extend_chain(
+                (slots_per_epoch * 4 + 2) as usize,
+                BlockStrategy::OnCanonicalHead,
+                AttestationStrategy::AllValidators,
+            )
+            .await;
+        let cached_head = harness.chain.canonical_head.cached_head();
+        assert!(cached_head.finalized_checkpoint().epoch > 0);
+        (
+            cached_head.head_block_root(),
+            cached_head.finalized_checkpoint(),
+            harness.chain.slot().unwrap(),
+        )
+    };
 
-    let mut majority_blocks = vec![];
+    // Corrupt the persisted fork choice and op pool, and leave a stale key in the pubkey cache.
+    store
+        .hot_db
+        .put_bytes(
+            DBColumn::ForkChoice.into(),
+            Hash256::zero().as_bytes(),
+            b"corrupt",
+        )
+        .unwrap();
+    store
+        .hot_db
+        .put_bytes(
+            DBColumn::OpPool.into(),
+            Hash256::zero().as_bytes(),
+            b"corrupt",
+        )
+        .unwrap();
+    let stale_pubkey_key = Hash256::from_low_u64_be(validator_count as u64);
+    store
+        .hot_db
+        .put_bytes(
+            DBColumn::PubkeyCache.into(),
+            stale_pubkey_key.as_bytes(),
+            &KEYPAIRS[validator_count].pk.compress().as_ssz_bytes(),
+        )
+        .unwrap();
+    assert!(BeaconChain::<DiskHarnessType<E>>::load_fork_choice(store.clone(), &spec).is_err());
 
-    for i in 0..post_fork_blocks {
-        let slot = fork_slot + i;
+    let resumed_harness = BeaconChainHarness::builder(MinimalEthSpec)
+        .default_spec()
+        .keypairs(KEYPAIRS[0..validator_count].to_vec())
+        .resumed_disk_store(store.clone())
+        .chain_config(ChainConfig {
+            repair_on_startup: true,
+            ..ChainConfig::default()
+        })
+        .mock_execution_layer()
+        .build();
 
-        // Attestations on majority chain.
-        let state_root = state.update_tree_hash_cache().unwrap();
+    // The head is consistent with the blocks in the database.
+    let cached_head = resumed_harness.chain.canonical_head.cached_head();
+    assert_eq!(cached_head.head_block_root(), head_block_root);
+    assert_eq!(cached_head.finalized_checkpoint(), finalized_checkpoint);
+    assert!(resumed_harness.chain.knows_head(&head_block_root.into()));
 
-        let attestations = harness2.make_attestations(
-            &all_validators,
+    // The repaired fork choice was persisted, and the stale pubkey removed.
+    assert!(
+        BeaconChain::<DiskHarnessType<E>>::load_fork_choice(store.clone(), &spec)
+            .unwrap()
+            .is_some()
+    );
+    assert!(!store
+        .hot_db
+        .key_exists(DBColumn::PubkeyCache.into(), stale_pubkey_key.as_bytes())
+        .unwrap());
+
+    // The chain continues to import blocks and finalize.
+    resumed_harness
+        .chain
+        .slot_clock
+        .set_slot(latest_slot.as_u64() + 1);
+    resumed_harness
+        .extend_chain(
+            (slots_per_epoch * 2) as usize,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::AllValidators,
+        )
+        .await;
+    assert!(
+        resumed_harness
+            .chain
+            .canonical_head
+            .cached_head()
+            .finalized_checkpoint()
+            .epoch
+            > finalized_checkpoint.epoch
+    );
+}
+
+#[tokio::test]
+async fn repair_on_startup_replays_forks() {
+    let validator_count = 16;
+    let slots_per_epoch = MinimalEthSpec::slots_per_epoch();
+    let db_path = tempdir().unwrap();
+    let store = get_store(&db_path);
+    let spec = test_spec::<E>();
+
+    let (fork_heads, finalized_checkpoint) = {
+        let harness = get_harness(store.clone(), validator_count);
+        harness
+            .extend_chain(
+                (slots_per_epoch * 4 + 2) as usize,
+                BlockStrategy::OnCanonicalHead,
+                AttestationStrategy::AllValidators,
+            )
+            .await;
+
+        // Build two forks on top of the head, which share the rest of the chain.
+        let (state, state_root) = harness.get_current_state_and_root();
+        let slot = harness.get_current_slot();
+        let (_, _, head_a, _) = harness
+            .add_attested_blocks_at_slots(
+                state.clone(),
+                state_root,
+                &[slot + 1, slot + 2],
+                &(0..validator_count / 2).collect::<Vec<_>>(),
+            )
+            .await;
+        let (_, _, head_b, _) = harness
+            .add_attested_blocks_at_slots(
+                state,
+                state_root,
+                &[slot + 2],
+                &(validator_count / 2..validator_count).collect::<Vec<_>>(),
+            )
+            .await;
+        assert_ne!(head_a, head_b);
+
+        let finalized_checkpoint = harness
+            .chain
+            .canonical_head
+            .cached_head()
+            .finalized_checkpoint();
+        assert!(finalized_checkpoint.epoch > 0);
+        ([head_a, head_b], finalized_checkpoint)
+    };
+
+    // Corrupt the persisted fork choice.
+    store
+        .hot_db
+        .put_bytes(
+            DBColumn::ForkChoice.into(),
+            Hash256::zero().as_bytes(),
+            b"corrupt",
+        )
+        .unwrap();
+    assert!(BeaconChain::<DiskHarnessType<E>>::load_fork_choice(store.clone(), &spec).is_err());
+
+    let resumed_harness = BeaconChainHarness::builder(MinimalEthSpec)
+        .default_spec()
+        .keypairs(KEYPAIRS[0..validator_count].to_vec())
+        .resumed_disk_store(store.clone())
+        .chain_config(ChainConfig {
+            repair_on_startup: true,
+            ..ChainConfig::default()
+        })
+        .mock_execution_layer()
+        .build();
+
+    // Both forks are replayed into fork choice.
+    let cached_head = resumed_harness.chain.canonical_head.cached_head();
+    assert_eq!(cached_head.finalized_checkpoint(), finalized_checkpoint);
+    for head in &fork_heads {
+        assert!(resumed_harness.chain.knows_head(head));
+        assert!(resumed_harness
+            .chain
+            .canonical_head
+            .fork_choice_read_lock()
+            .contains_block(&(*head).into()));
+    }
+}
+
+#[tokio::test]
+async fn revert_minority_fork_on_resume() {
+    let validator_count = 16;
+    let slots_per_epoch = MinimalEthSpec::slots_per_epoch();
+
+    let fork_epoch = Epoch::new(4);
+    let fork_slot = fork_epoch.start_slot(slots_per_epoch);
+    let initial_blocks = slots_per_epoch * fork_epoch.as_u64() - 1;
+    let post_fork_blocks = slots_per_epoch * 3;
+
+    let mut spec1 = MinimalEthSpec::default_spec();
+    spec1.altair_fork_epoch = None;
+    let mut spec2 = MinimalEthSpec::default_spec();
+    spec2.altair_fork_epoch = Some(fork_epoch);
+
+    let seconds_per_slot = spec1.seconds_per_slot;
+
+    let all_validators = (0..validator_count).collect::<Vec<usize>>();
+
+    // Chain with no fork epoch configured.
+    let db_path1 = tempdir().unwrap();
+    let store1 = get_store_with_spec(&db_path1, spec1.clone());
+    let harness1 = BeaconChainHarness::builder(MinimalEthSpec)
+        .spec(spec1)
+        .keypairs(KEYPAIRS[0..validator_count].to_vec())
+        .fresh_disk_store(store1)
+        .mock_execution_layer()
+        .build();
+
+    // Chain with fork epoch configured.
+    let db_path2 = tempdir().unwrap();
+    let store2 = get_store_with_spec(&db_path2, spec2.clone());
+    let harness2 = BeaconChainHarness::builder(MinimalEthSpec)
+        .spec(spec2.clone())
+        .keypairs(KEYPAIRS[0..validator_count].to_vec())
+        .fresh_disk_store(store2)
+        .mock_execution_layer()
+        .build();
+
+    // Apply the same blocks to both chains initially.
+    let mut state = harness1.get_current_state();
+    let mut block_root = harness1.chain.genesis_block_root;
+    for slot in (1..=initial_blocks).map(Slot::new) {
+        let state_root = state.update_tree_hash_cache().unwrap();
+
+        let attestations = harness1.make_attestations(
+            &all_validators,
+            &state,
+            state_root,
+            block_root.into(),
+            slot,
+        );
+        harness1.set_current_slot(slot);
+        harness2.set_current_slot(slot);
+        harness1.process_attestations(attestations.clone());
+        harness2.process_attestations(attestations);
+
+        let (block, new_state) = harness1.make_block(state, slot).await;
+
+        harness1.process_block(slot, block.clone()).await.unwrap();
+        harness2.process_block(slot, block.clone()).await.unwrap();
+
+        state = new_state;
+        block_root = block.canonical_root();
+    }
+
+    assert_eq!(harness1.head_slot(), fork_slot - 1);
+    assert_eq!(harness2.head_slot(), fork_slot - 1);
+
+    // Fork the two chains.
+    let mut state1 = state.clone();
+    let mut state2 = state.clone();
+
+    let mut majority_blocks = vec![];
+
+    for i in 0..post_fork_blocks {
+        let slot = fork_slot + i;
+
+        // Attestations on majority chain.
+        let state_root = state.update_tree_hash_cache().unwrap();
+
+        let attestations = harness2.make_attestations(
+            &all_validators,
             &state2,
             state_root,
             block_root.into(),
@@ -2589,100 +3632,422 @@ fn check_split_slot(harness: &TestHarness, store: Arc<HotColdDB<E, LevelDB<E>, L
     assert_ne!(split_slot, 0);
 }
 
-/// Check that all the states in a chain dump have the correct tree hash.
-fn check_chain_dump(harness: &TestHarness, expected_len: u64) {
-    let chain_dump = harness.chain.chain_dump().unwrap();
+/// Build a chain which has merged at genesis, and finalize several epochs of it.
+async fn get_finalized_merged_harness(
+    prune_payloads: bool,
+) -> BeaconChainHarness<EphemeralHarnessType<E>> {
+    let mut spec = test_spec::<E>();
+    spec.altair_fork_epoch = Some(Epoch::new(0));
+    spec.bellatrix_fork_epoch = Some(Epoch::new(0));
+    let harness = BeaconChainHarness::builder(MinimalEthSpec)
+        .spec(spec)
+        .keypairs(KEYPAIRS[0..LOW_VALIDATOR_COUNT].to_vec())
+        .fresh_ephemeral_store()
+        .mock_execution_layer()
+        .chain_config(ChainConfig {
+            prune_payloads,
+            ..ChainConfig::default()
+        })
+        .build();
+    harness
+        .execution_block_generator()
+        .move_to_terminal_block()
+        .unwrap();
+    harness.advance_slot();
 
-    assert_eq!(chain_dump.len() as u64, expected_len);
+    harness
+        .extend_chain(
+            E::slots_per_epoch() as usize * 5,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::AllValidators,
+        )
+        .await;
+    assert!(harness.chain.store.get_split_slot() > E::slots_per_epoch());
+    harness
+}
 
-    for checkpoint in &chain_dump {
-        // Check that the tree hash of the stored state is as expected
-        assert_eq!(
-            checkpoint.beacon_state_root(),
-            checkpoint.beacon_state.tree_hash_root(),
-            "tree hash of stored state is incorrect"
-        );
+/// Check that the payloads of the canonical blocks prior to `pruned_before` have been pruned, and
+/// that those blocks are still served with payloads reconstructed by the execution layer.
+async fn check_payloads_pruned_before(
+    harness: &BeaconChainHarness<EphemeralHarnessType<E>>,
+    pruned_before: Slot,
+) {
+    let store = &harness.chain.store;
+    let head_slot = harness.chain.head_snapshot().beacon_block.slot();
+    for slot in 1..=head_slot.as_u64() {
+        let block_root = harness
+            .chain
+            .block_root_at_slot(Slot::new(slot), WhenSlotSkipped::None)
+            .unwrap()
+            .unwrap();
 
-        // Check that looking up the state root with no slot hint succeeds.
-        // This tests the state root -> slot mapping.
-        assert_eq!(
-            harness
-                .chain
-                .store
-                .get_state(&checkpoint.beacon_state_root(), None)
-                .expect("no error")
-                .expect("state exists")
-                .slot(),
-            checkpoint.beacon_state.slot()
+        if slot < pruned_before {
+            assert!(
+                store.get_execution_payload(&block_root).is_err(),
+                "payload at slot {} should have been pruned",
+                slot
+            );
+            assert!(matches!(
+                store.try_get_full_block(&block_root).unwrap(),
+                Some(DatabaseBlock::Blinded(_))
+            ));
+        } else {
+            assert!(
+                store.get_execution_payload(&block_root).is_ok(),
+                "payload at slot {} should not have been pruned",
+                slot
+            );
+        }
+
+        let block = harness.chain.get_block(&block_root).await.unwrap().unwrap();
+        assert_eq!(block.canonical_root(), block_root);
+        assert_ne!(
+            block
+                .message()
+                .body()
+                .execution_payload()
+                .unwrap()
+                .block_hash(),
+            ExecutionBlockHash::zero()
         );
     }
+}
 
-    // Check the forwards block roots iterator against the chain dump
-    let chain_dump_block_roots = chain_dump
-        .iter()
-        .map(|checkpoint| (checkpoint.beacon_block_root, checkpoint.beacon_block.slot()))
-        .collect::<Vec<_>>();
-
-    let mut forward_block_roots = harness
-        .chain
-        .forwards_iter_block_roots(Slot::new(0))
-        .expect("should get iter")
-        .map(Result::unwrap)
-        .collect::<Vec<_>>();
-
-    // Drop the block roots for skipped slots.
-    forward_block_roots.dedup_by_key(|(block_root, _)| *block_root);
+#[tokio::test]
+async fn finalized_payloads_are_pruned() {
+    let harness = get_finalized_merged_harness(true).await;
+    let split_slot = harness.chain.store.get_split_slot();
 
-    for i in 0..std::cmp::max(chain_dump_block_roots.len(), forward_block_roots.len()) {
-        assert_eq!(
-            chain_dump_block_roots[i],
-            forward_block_roots[i],
-            "split slot is {}",
-            harness.chain.store.get_split_slot()
-        );
-    }
+    check_payloads_pruned_before(&harness, split_slot).await;
 }
 
-/// Check that every state from the canonical chain is in the database, and that the
-/// reverse state and block root iterators reach genesis.
-fn check_iterators(harness: &TestHarness) {
-    let mut max_slot = None;
-    for (state_root, slot) in harness
-        .chain
-        .forwards_iter_state_roots(Slot::new(0))
-        .expect("should get iter")
-        .map(Result::unwrap)
-    {
-        assert!(
-            harness
-                .chain
-                .store
-                .get_state(&state_root, Some(slot))
-                .unwrap()
-                .is_some(),
-            "state {:?} from canonical chain should be in DB",
-            state_root
-        );
-        max_slot = Some(slot);
-    }
-    // Assert that we reached the head.
-    assert_eq!(max_slot, Some(harness.head_slot()));
-    // Assert that the block root iterator reaches the head.
+#[tokio::test]
+async fn payloads_are_pruned_manually() {
+    let harness = get_finalized_merged_harness(false).await;
+    let split_slot = harness.chain.store.get_split_slot();
+    check_payloads_pruned_before(&harness, Slot::new(1)).await;
+
+    let pruned_before = split_slot - E::slots_per_epoch();
     assert_eq!(
         harness
             .chain
-            .forwards_iter_block_roots(Slot::new(0))
-            .expect("should get iter")
-            .last()
-            .map(Result::unwrap)
-            .map(|(_, slot)| slot),
-        Some(harness.head_slot())
+            .prune_payloads_manually(pruned_before)
+            .unwrap(),
+        // Including the payload of the genesis block.
+        pruned_before.as_usize()
+    );
+    check_payloads_pruned_before(&harness, pruned_before).await;
+
+    // Payloads from the split slot onwards are never pruned.
+    let head_slot = harness.chain.head_snapshot().beacon_block.slot();
+    assert_eq!(
+        harness.chain.prune_payloads_manually(head_slot).unwrap(),
+        (split_slot - pruned_before).as_usize()
     );
+    check_payloads_pruned_before(&harness, split_slot).await;
 }
 
-fn get_finalized_epoch_boundary_blocks(
-    dump: &[BeaconSnapshot<MinimalEthSpec, BlindedPayload<MinimalEthSpec>>],
-) -> HashSet<SignedBeaconBlockHash> {
+#[tokio::test]
+async fn inconsistent_reconstructed_payloads_are_requeried() {
+    let harness = get_finalized_merged_harness(true).await;
+    let server = &harness.mock_execution_layer.as_ref().unwrap().server;
+
+    let block_root = harness
+        .chain
+        .block_root_at_slot(Slot::new(1), WhenSlotSkipped::None)
+        .unwrap()
+        .unwrap();
+    let exec_block_hash = harness
+        .chain
+        .get_blinded_block(&block_root)
+        .unwrap()
+        .unwrap()
+        .message()
+        .execution_payload()
+        .unwrap()
+        .execution_payload_header
+        .block_hash;
+    let failure = |recovered| PayloadReconstructionFailure {
+        slot: Slot::new(1),
+        block_root,
+        exec_block_hash,
+        recovered,
+    };
+
+    // The execution layer returns an inconsistent payload once, then the right one.
+    server.return_inconsistent_payloads(exec_block_hash, 1);
+    let block = harness.chain.get_block(&block_root).await.unwrap().unwrap();
+    assert_eq!(block.canonical_root(), block_root);
+    assert_eq!(
+        harness.chain.payload_reconstruction_failures(),
+        vec![failure(true)]
+    );
+
+    // The block is not served if the re-queried payload is also inconsistent.
+    server.return_inconsistent_payloads(exec_block_hash, 2);
+    assert!(matches!(
+        harness.chain.get_block(&block_root).await,
+        Err(BeaconChainError::InconsistentPayloadReconstructed { .. })
+    ));
+    assert_eq!(
+        harness.chain.payload_reconstruction_failures(),
+        vec![failure(true), failure(false)]
+    );
+}
+
+fn get_store_with_hot_state_diffs(
+    db_path: &TempDir,
+    epochs_per_full_hot_state: Option<u64>,
+) -> Arc<HotColdDB<E, LevelDB<E>, LevelDB<E>>> {
+    let config = StoreConfig {
+        epochs_per_full_hot_state,
+        ..StoreConfig::default()
+    };
+    get_store_with_config(db_path, config).expect("disk store should initialize")
+}
+
+/// The total size of the values in `column` of the hot database.
+fn hot_column_size(store: &HotColdDB<E, LevelDB<E>, LevelDB<E>>, column: DBColumn) -> usize {
+    store
+        .hot_db
+        .iter_column(column)
+        .map(|result| result.unwrap().1.len())
+        .sum()
+}
+
+#[tokio::test]
+async fn hot_state_diffs_reconstruct_states() {
+    let db_path = tempdir().unwrap();
+    let store = get_store_with_hot_state_diffs(&db_path, Some(4));
+    let harness = get_harness(store.clone(), LOW_VALIDATOR_COUNT);
+
+    // Without attestations nothing is finalized, so every state remains in the hot database.
+    let num_blocks_produced = E::slots_per_epoch() * 6;
+    harness
+        .extend_chain(
+            num_blocks_produced as usize,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::SomeValidators(vec![]),
+        )
+        .await;
+    assert_eq!(store.get_split_slot(), 0);
+
+    for snapshot in harness.chain.chain_dump().unwrap() {
+        let state_root = snapshot.beacon_state_root();
+        let slot = snapshot.beacon_state.slot();
+
+        if slot % E::slots_per_epoch() == 0 {
+            let epoch = slot.epoch(E::slots_per_epoch());
+            let stored_in_full = store
+                .hot_db
+                .key_exists(DBColumn::BeaconState.into(), state_root.as_bytes())
+                .unwrap();
+            let stored_as_diff = store
+                .hot_db
+                .key_exists(DBColumn::BeaconStateDiff.into(), state_root.as_bytes())
+                .unwrap();
+            assert_eq!(stored_in_full, epoch % 4 == 0, "epoch {}", epoch);
+            assert_eq!(stored_as_diff, epoch % 4 != 0, "epoch {}", epoch);
+        }
+
+        // States on epoch boundaries are reconstructed from diffs, and the states in between by
+        // replaying blocks on them.
+        let state = store.get_state(&state_root, Some(slot)).unwrap().unwrap();
+        assert_eq!(state.slot(), slot);
+        assert_eq!(state.canonical_root(), state_root);
+    }
+
+    check_chain_dump(&harness, num_blocks_produced + 1);
+    check_iterators(&harness);
+}
+
+#[tokio::test]
+async fn hot_state_diffs_survive_finalization() {
+    let db_path = tempdir().unwrap();
+    let store = get_store_with_hot_state_diffs(&db_path, Some(4));
+    let harness = get_harness(store.clone(), LOW_VALIDATOR_COUNT);
+
+    let num_blocks_produced = E::slots_per_epoch() * 11;
+    harness
+        .extend_chain(
+            num_blocks_produced as usize,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::AllValidators,
+        )
+        .await;
+
+    check_finalization(&harness, num_blocks_produced);
+    check_split_slot(&harness, store.clone());
+    check_chain_dump(&harness, num_blocks_produced + 1);
+    check_iterators(&harness);
+
+    // The split state is stored as a diff against the full state at epoch 8, which is kept in the
+    // hot database despite being finalized. The full states of earlier epochs are deleted.
+    let split = store.get_split_info();
+    assert_eq!(split.slot, E::slots_per_epoch() * 9);
+    let split_diff = store
+        .get_item::<HotStateDiff>(&split.state_root)
+        .unwrap()
+        .unwrap();
+    let head_state = harness.get_current_state();
+    assert_eq!(
+        split_diff.base_state_root,
+        *head_state
+            .get_state_root(Slot::new(E::slots_per_epoch() * 8))
+            .unwrap()
+    );
+    assert_eq!(hot_column_size(&store, DBColumn::BeaconState), {
+        let base_bytes = store
+            .hot_db
+            .get_bytes(
+                DBColumn::BeaconState.into(),
+                split_diff.base_state_root.as_bytes(),
+            )
+            .unwrap()
+            .unwrap();
+        base_bytes.len()
+    });
+
+    // Every unfinalized state can still be loaded.
+    for slot in split.slot.as_u64()..=num_blocks_produced {
+        let slot = Slot::new(slot);
+        let state_root = *head_state
+            .get_state_root(slot)
+            .unwrap_or(&head_state.canonical_root());
+        let state = store.get_state(&state_root, Some(slot)).unwrap().unwrap();
+        assert_eq!(state.canonical_root(), state_root);
+    }
+}
+
+#[tokio::test]
+async fn hot_state_diffs_reduce_bytes_written() {
+    let num_blocks_produced = E::slots_per_epoch() * 12;
+    let mut bytes_written = vec![];
+    for epochs_per_full_hot_state in [None, Some(4)] {
+        let db_path = tempdir().unwrap();
+        let store = get_store_with_hot_state_diffs(&db_path, epochs_per_full_hot_state);
+        let harness = get_harness(store.clone(), LOW_VALIDATOR_COUNT);
+
+        // Without attestations nothing is finalized, so every state written remains in the hot
+        // database.
+        harness
+            .extend_chain(
+                num_blocks_produced as usize,
+                BlockStrategy::OnCanonicalHead,
+                AttestationStrategy::SomeValidators(vec![]),
+            )
+            .await;
+        assert_eq!(store.get_split_slot(), 0);
+
+        bytes_written.push(
+            hot_column_size(&store, DBColumn::BeaconState)
+                + hot_column_size(&store, DBColumn::BeaconStateDiff),
+        );
+    }
+
+    let (full_bytes, diff_bytes) = (bytes_written[0], bytes_written[1]);
+    assert!(
+        diff_bytes * 3 < full_bytes * 2,
+        "{} bytes written with diffs, {} bytes written without",
+        diff_bytes,
+        full_bytes
+    );
+}
+
+/// Check that all the states in a chain dump have the correct tree hash.
+fn check_chain_dump(harness: &TestHarness, expected_len: u64) {
+    let chain_dump = harness.chain.chain_dump().unwrap();
+
+    assert_eq!(chain_dump.len() as u64, expected_len);
+
+    for checkpoint in &chain_dump {
+        // Check that the tree hash of the stored state is as expected
+        assert_eq!(
+            checkpoint.beacon_state_root(),
+            checkpoint.beacon_state.tree_hash_root(),
+            "tree hash of stored state is incorrect"
+        );
+
+        // Check that looking up the state root with no slot hint succeeds.
+        // This tests the state root -> slot mapping.
+        assert_eq!(
+            harness
+                .chain
+                .store
+                .get_state(&checkpoint.beacon_state_root(), None)
+                .expect("no error")
+                .expect("state exists")
+                .slot(),
+            checkpoint.beacon_state.slot()
+        );
+    }
+
+    // Check the forwards block roots iterator against the chain dump
+    let chain_dump_block_roots = chain_dump
+        .iter()
+        .map(|checkpoint| (checkpoint.beacon_block_root, checkpoint.beacon_block.slot()))
+        .collect::<Vec<_>>();
+
+    let mut forward_block_roots = harness
+        .chain
+        .forwards_iter_block_roots(Slot::new(0))
+        .expect("should get iter")
+        .map(Result::unwrap)
+        .collect::<Vec<_>>();
+
+    // Drop the block roots for skipped slots.
+    forward_block_roots.dedup_by_key(|(block_root, _)| *block_root);
+
+    for i in 0..std::cmp::max(chain_dump_block_roots.len(), forward_block_roots.len()) {
+        assert_eq!(
+            chain_dump_block_roots[i],
+            forward_block_roots[i],
+            "split slot is {}",
+            harness.chain.store.get_split_slot()
+        );
+    }
+}
+
+/// Check that every state from the canonical chain is in the database, and that the
+/// reverse state and block root iterators reach genesis.
+fn check_iterators(harness: &TestHarness) {
+    let mut max_slot = None;
+    for (state_root, slot) in harness
+        .chain
+        .forwards_iter_state_roots(Slot::new(0))
+        .expect("should get iter")
+        .map(Result::unwrap)
+    {
+        assert!(
+            harness
+                .chain
+                .store
+                .get_state(&state_root, Some(slot))
+                .unwrap()
+                .is_some(),
+            "state {:?} from canonical chain should be in DB",
+            state_root
+        );
+        max_slot = Some(slot);
+    }
+    // Assert that we reached the head.
+    assert_eq!(max_slot, Some(harness.head_slot()));
+    // Assert that the block root iterator reaches the head.
+    assert_eq!(
+        harness
+            .chain
+            .forwards_iter_block_roots(Slot::new(0))
+            .expect("should get iter")
+            .last()
+            .map(Result::unwrap)
+            .map(|(_, slot)| slot),
+        Some(harness.head_slot())
+    );
+}
+
+fn get_finalized_epoch_boundary_blocks(
+    dump: &[BeaconSnapshot<MinimalEthSpec, BlindedPayload<MinimalEthSpec>>],
+) -> HashSet<SignedBeaconBlockHash> {
     dump.iter()
         .cloned()
         .map(|checkpoint| checkpoint.beacon_state.finalized_checkpoint().root.into())
@@ -2697,3 +4062,750 @@ fn get_blocks(
         .map(|checkpoint| checkpoint.beacon_block_root.into())
         .collect()
 }
+
+#[tokio::test]
+async fn purge_persisted_eth1_cache() {
+    let db_path = tempdir().unwrap();
+    let store = get_store(&db_path);
+
+    {
+        let harness = get_harness(store.clone(), LOW_VALIDATOR_COUNT);
+        harness
+            .extend_chain(
+                E::slots_per_epoch() as usize,
+                BlockStrategy::OnCanonicalHead,
+                AttestationStrategy::AllValidators,
+            )
+            .await;
+        harness.chain.persist_eth1_cache().unwrap();
+    }
+
+    let builder: BeaconChainBuilder<DiskHarnessType<E>> =
+        BeaconChainBuilder::new(MinimalEthSpec).store(store.clone());
+    assert!(builder.get_persisted_eth1_backend().unwrap().is_some());
+
+    builder.purge_persisted_eth1_backend().unwrap();
+    assert!(builder.get_persisted_eth1_backend().unwrap().is_none());
+
+    // The resumed chain starts with empty caches.
+    let harness = BeaconChainHarness::builder(MinimalEthSpec)
+        .default_spec()
+        .keypairs(KEYPAIRS[0..LOW_VALIDATOR_COUNT].to_vec())
+        .resumed_disk_store(store)
+        .mock_execution_layer()
+        .build();
+    let status = harness.chain.eth1_cache_status().unwrap();
+    assert_eq!(status.latest_cached_block_number, None);
+    assert_eq!(status.deposit_count, 0);
+}
+
+/// Returns a snapshot of the deposit tree of the genesis state of a harness with `validator_count`
+/// validators, taken at eth1 block `block_number`.
+fn genesis_deposit_snapshot(
+    validator_count: usize,
+    block_number: u64,
+    spec: &ChainSpec,
+) -> DepositTreeSnapshot {
+    let leaves = KEYPAIRS[0..validator_count]
+        .iter()
+        .map(|keypair| {
+            let mut data = DepositData {
+                pubkey: keypair.pk.clone().into(),
+                withdrawal_credentials: Hash256::from_slice(&get_withdrawal_credentials(
+                    &keypair.pk,
+                    spec.bls_withdrawal_prefix_byte,
+                )),
+                amount: spec.max_effective_balance,
+                signature: SignatureBytes::empty(),
+            };
+            data.signature = data.create_signature(&keypair.sk, spec);
+            data.tree_hash_root()
+        })
+        .collect::<Vec<_>>();
+    let tree = DepositDataTree::create(&leaves, validator_count, DEPOSIT_TREE_DEPTH);
+
+    DepositTreeSnapshot {
+        finalized: tree.get_finalized_hashes(validator_count).unwrap(),
+        deposit_root: tree.root(),
+        deposit_count: validator_count as u64,
+        execution_block_hash: Hash256::from_slice(DEFAULT_ETH1_BLOCK_HASH),
+        execution_block_height: block_number,
+    }
+}
+
+#[tokio::test]
+async fn eth1_cache_from_deposit_snapshot() {
+    let db_path = tempdir().unwrap();
+    let store = get_store(&db_path);
+    let snapshot_block_number = 1_000;
+    let snapshot = genesis_deposit_snapshot(
+        LOW_VALIDATOR_COUNT,
+        snapshot_block_number,
+        &test_spec::<E>(),
+    );
+
+    let backend = CachingEth1Backend::from_deposit_snapshot(
+        Eth1Config::default(),
+        test_logger(),
+        test_spec::<E>(),
+        snapshot.clone(),
+    )
+    .unwrap();
+    let harness = BeaconChainHarness::builder(MinimalEthSpec)
+        .default_spec()
+        .keypairs(KEYPAIRS[0..LOW_VALIDATOR_COUNT].to_vec())
+        .fresh_disk_store(store.clone())
+        .mock_execution_layer()
+        .initial_mutator(Box::new(move |builder| builder.eth1_backend(Some(backend))))
+        .build();
+    harness.advance_slot();
+
+    let genesis_eth1_data = harness
+        .chain
+        .head_snapshot()
+        .beacon_state
+        .eth1_data()
+        .clone();
+    assert_eq!(snapshot.deposit_root, genesis_eth1_data.deposit_root);
+    assert_eq!(snapshot.deposit_count, genesis_eth1_data.deposit_count);
+
+    // The deposits of the snapshot are known without any deposit logs.
+    let status = harness.chain.eth1_cache_status().unwrap();
+    assert_eq!(status.deposit_count, snapshot.deposit_count);
+
+    harness
+        .extend_chain(
+            E::slots_per_epoch() as usize,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::AllValidators,
+        )
+        .await;
+    let head = harness.chain.head_snapshot();
+    assert_eq!(head.beacon_block.slot(), E::slots_per_epoch());
+    assert_eq!(
+        head.beacon_block.message().body().eth1_data(),
+        &genesis_eth1_data
+    );
+
+    // The snapshot is persisted along with the deposit cache.
+    harness.chain.persist_eth1_cache().unwrap();
+    drop(harness);
+
+    let builder: BeaconChainBuilder<DiskHarnessType<E>> =
+        BeaconChainBuilder::new(MinimalEthSpec).store(store);
+    let persisted = builder.get_persisted_eth1_backend().unwrap().unwrap();
+    let backend = Eth1Chain::<CachingEth1Backend<E>, E>::from_ssz_container(
+        &persisted,
+        Eth1Config::default(),
+        &test_logger(),
+        test_spec::<E>(),
+    )
+    .unwrap()
+    .into_backend();
+    let deposits = backend.core.deposits().read();
+    assert_eq!(deposits.cache.snapshot(), Some(&snapshot));
+    assert_eq!(deposits.cache.len(), LOW_VALIDATOR_COUNT);
+    assert_eq!(deposits.last_processed_block, Some(snapshot_block_number));
+}
+
+#[tokio::test]
+async fn eth1_vote_falls_back_when_cache_is_stale() {
+    let db_path = tempdir().unwrap();
+    let store = get_store(&db_path);
+    let spec = test_spec::<E>();
+    let snapshot_block_number = 1_000;
+    let snapshot = genesis_deposit_snapshot(LOW_VALIDATOR_COUNT, snapshot_block_number, &spec);
+
+    let backend = CachingEth1Backend::from_deposit_snapshot(
+        Eth1Config::default(),
+        test_logger(),
+        spec.clone(),
+        snapshot.clone(),
+    )
+    .unwrap();
+    let eth1_service = backend.core.clone();
+    let harness = BeaconChainHarness::builder(MinimalEthSpec)
+        .default_spec()
+        .keypairs(KEYPAIRS[0..LOW_VALIDATOR_COUNT].to_vec())
+        .fresh_disk_store(store)
+        .mock_execution_layer()
+        .initial_mutator(Box::new(move |builder| builder.eth1_backend(Some(backend))))
+        .build();
+    harness.advance_slot();
+
+    // A candidate eth1 block for the first voting period, without any new deposits.
+    let genesis_state = harness.chain.head_snapshot().beacon_state.clone();
+    let eth1_block = Eth1Block {
+        hash: Hash256::repeat_byte(42),
+        timestamp: genesis_state.genesis_time()
+            - spec.eth1_follow_distance * spec.seconds_per_eth1_block,
+        number: snapshot_block_number + 1,
+        deposit_root: Some(snapshot.deposit_root),
+        deposit_count: Some(snapshot.deposit_count),
+    };
+    let vote = eth1_block.clone().eth1_data().unwrap();
+    assert_ne!(&vote, genesis_state.eth1_data());
+    eth1_service
+        .blocks()
+        .write()
+        .insert_root_or_child(eth1_block)
+        .unwrap();
+
+    let block_votes = |harness: &TestHarness| {
+        let head = harness.chain.head_snapshot();
+        head.beacon_block.message().body().eth1_data().clone()
+    };
+
+    for _ in 0..E::slots_per_epoch() {
+        harness
+            .extend_chain(
+                1,
+                BlockStrategy::OnCanonicalHead,
+                AttestationStrategy::AllValidators,
+            )
+            .await;
+        assert_eq!(block_votes(&harness), vote);
+    }
+
+    // The eth1 node goes away and the block cache is lost.
+    eth1_service.clear_block_cache();
+
+    // Blocks keep voting with the majority of the voting period and don't include any deposits.
+    for _ in 0..E::slots_per_epoch() {
+        harness
+            .extend_chain(
+                1,
+                BlockStrategy::OnCanonicalHead,
+                AttestationStrategy::AllValidators,
+            )
+            .await;
+        assert_eq!(block_votes(&harness), vote);
+        let head = harness.chain.head_snapshot();
+        assert!(head.beacon_block.message().body().deposits().is_empty());
+    }
+    assert_eq!(
+        harness.chain.head_snapshot().beacon_block.slot(),
+        2 * E::slots_per_epoch()
+    );
+}
+
+#[tokio::test]
+async fn persist_head_and_fork_choice_with_truncated_head_tracker() {
+    let validator_count = LOW_VALIDATOR_COUNT;
+    let db_path = tempdir().unwrap();
+    let store = get_store(&db_path);
+    let spec = test_spec::<E>();
+    let harness = BeaconChainHarness::builder(MinimalEthSpec)
+        .default_spec()
+        .keypairs(KEYPAIRS[0..validator_count].to_vec())
+        .fresh_disk_store(store.clone())
+        .chain_config(ChainConfig {
+            max_persisted_heads: 1,
+            ..ChainConfig::default()
+        })
+        .mock_execution_layer()
+        .build();
+    harness.advance_slot();
+    harness
+        .extend_chain(
+            E::slots_per_epoch() as usize,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::AllValidators,
+        )
+        .await;
+
+    // Two competing children of the head, so there are more heads than can be persisted.
+    let parent_state = harness.get_current_state();
+    let slot = harness.get_current_slot() + 1;
+    let (older_head, _, _) = harness
+        .add_block_at_slot(slot, parent_state.clone())
+        .await
+        .unwrap();
+    let (newer_head, _, _) = harness
+        .add_block_at_slot(slot + 1, parent_state)
+        .await
+        .unwrap();
+    assert_eq!(harness.chain.heads().len(), 2);
+
+    let result = harness.chain.persist_head_and_fork_choice().unwrap();
+    assert_eq!(
+        result,
+        PersistHeadAndForkChoiceResult {
+            fork_choice_persisted: true,
+            head_tracker: HeadTrackerPersistence::Truncated { dropped: 1 },
+        }
+    );
+
+    // The in-memory head tracker is unchanged.
+    assert_eq!(harness.chain.heads().len(), 2);
+
+    // Fork choice was persisted in full, including the dropped head.
+    let fork_choice = BeaconChain::<DiskHarnessType<E>>::load_fork_choice(store.clone(), &spec)
+        .unwrap()
+        .expect("fork choice should be persisted");
+    assert!(fork_choice.contains_block(&older_head.into()));
+    assert!(fork_choice.contains_block(&newer_head.into()));
+    drop(harness);
+
+    // The truncated head tracker is restored on resumption.
+    let resumed_harness = BeaconChainHarness::builder(MinimalEthSpec)
+        .default_spec()
+        .keypairs(KEYPAIRS[0..validator_count].to_vec())
+        .resumed_disk_store(store)
+        .mock_execution_layer()
+        .build();
+    assert_eq!(
+        resumed_harness.chain.heads(),
+        vec![(Hash256::from(newer_head), slot + 1)]
+    );
+}
+
+/// Returns the contents of the hot database, so that it can be checked for writes.
+fn hot_db_contents(
+    store: &HotColdDB<E, LevelDB<E>, LevelDB<E>>,
+) -> Vec<(DBColumn, Vec<(Hash256, Vec<u8>)>)> {
+    [
+        DBColumn::BeaconMeta,
+        DBColumn::BeaconBlock,
+        DBColumn::BeaconState,
+        DBColumn::BeaconStateDiff,
+        DBColumn::BeaconStateSummary,
+        DBColumn::BeaconStateTemporary,
+        DBColumn::ExecPayload,
+        DBColumn::BeaconChain,
+        DBColumn::OpPool,
+        DBColumn::Eth1Cache,
+        DBColumn::ForkChoice,
+        DBColumn::PubkeyCache,
+        DBColumn::ProposalHistory,
+        DBColumn::BuilderBidHistory,
+        DBColumn::ValidatorRegistrations,
+    ]
+    .into_iter()
+    .map(|column| {
+        let contents = store
+            .hot_db
+            .iter_column(column)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        (column, contents)
+    })
+    .collect()
+}
+
+#[tokio::test]
+async fn read_only_mode() {
+    let validator_count = LOW_VALIDATOR_COUNT;
+    let db_path = tempdir().unwrap();
+    let store = get_store(&db_path);
+    let harness = get_harness(store.clone(), validator_count);
+    harness
+        .extend_chain(
+            E::slots_per_epoch() as usize * 2,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::AllValidators,
+        )
+        .await;
+
+    // A block on top of the head, which is not imported.
+    let slot = harness.get_current_slot() + 1;
+    harness.set_current_slot(slot);
+    let (block, _) = harness.make_block(harness.get_current_state(), slot).await;
+    let block = Arc::new(block);
+
+    let head = harness.chain.head_snapshot();
+    let head_block_root = head.beacon_block_root;
+    let head_slot = head.beacon_block.slot();
+    drop(head);
+    drop(harness);
+    drop(store);
+
+    // Reopen the database through the same path as the node, which must not write to it.
+    let store = get_store_with_config(
+        &db_path,
+        StoreConfig {
+            read_only: true,
+            ..StoreConfig::default()
+        },
+    )
+    .unwrap();
+    let db_contents = hot_db_contents(&store);
+
+    let read_only_harness = BeaconChainHarness::builder(MinimalEthSpec)
+        .default_spec()
+        .keypairs(KEYPAIRS[0..validator_count].to_vec())
+        .chain_config(ChainConfig {
+            read_only: true,
+            ..ChainConfig::default()
+        })
+        .resumed_disk_store(store.clone())
+        .mock_execution_layer()
+        .build();
+    let chain = &read_only_harness.chain;
+    chain.slot_clock.set_slot(slot.as_u64());
+
+    // Queries are served from the existing database.
+    assert_eq!(chain.head_snapshot().beacon_block_root, head_block_root);
+    assert!(chain.get_blinded_block(&head_block_root).unwrap().is_some());
+    assert_eq!(
+        chain
+            .block_root_at_slot(head_slot, WhenSlotSkipped::None)
+            .unwrap(),
+        Some(head_block_root)
+    );
+    assert_eq!(
+        chain.finalized_state().unwrap().slot(),
+        chain
+            .canonical_head
+            .cached_head()
+            .finalized_checkpoint()
+            .epoch
+            .start_slot(E::slots_per_epoch())
+    );
+
+    // Imports are refused.
+    assert!(matches!(
+        chain.process_block(block.clone()).await,
+        Err(BlockError::BeaconChainError(BeaconChainError::ReadOnlyMode))
+    ));
+    assert!(matches!(
+        chain.process_chain_segment(vec![block]).await,
+        ChainSegmentResult::Failed {
+            imported_blocks: 0,
+            error: BlockError::BeaconChainError(BeaconChainError::ReadOnlyMode),
+        }
+    ));
+    assert_eq!(chain.head_snapshot().beacon_block_root, head_block_root);
+
+    // Fork choice doesn't run, so the chain can't finalize and migrate the database.
+    assert!(matches!(
+        chain.fork_choice_at_slot(slot).await,
+        Err(BeaconChainError::ReadOnlyMode)
+    ));
+    chain.recompute_head_at_current_slot().await.unwrap();
+    chain.per_slot_task().await;
+    assert_eq!(chain.head_snapshot().beacon_block_root, head_block_root);
+
+    // Persistence is a no-op.
+    assert_eq!(
+        chain.persist_head_and_fork_choice().unwrap(),
+        PersistHeadAndForkChoiceResult {
+            fork_choice_persisted: false,
+            head_tracker: HeadTrackerPersistence::ReadOnly,
+        }
+    );
+    chain.persist_op_pool().unwrap();
+    chain.persist_eth1_cache().unwrap();
+    chain.persist_proposal_history().unwrap();
+    chain.persist_builder_bid_history().unwrap();
+    chain.persist_validator_registrations().unwrap();
+    drop(read_only_harness);
+
+    assert!(hot_db_contents(&store) == db_contents);
+}
+
+/// Check that a head tracker persisted prior to the head is detected as divergent on restart, and
+/// healed if configured.
+#[tokio::test]
+async fn stale_head_tracker_detected_and_healed() {
+    let db_path = tempdir().unwrap();
+    let store = get_store(&db_path);
+    let harness = get_harness(store.clone(), LOW_VALIDATOR_COUNT);
+    harness
+        .extend_chain(
+            E::slots_per_epoch() as usize,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::AllValidators,
+        )
+        .await;
+
+    // Keep a copy of the head tracker as persisted prior to the head, as if a crash had occurred
+    // before the latest head tracker was persisted.
+    harness.chain.persist_head_and_fork_choice().unwrap();
+    let stale_head_block_root = harness.head_block_root();
+    let beacon_chain_key = Hash256::zero();
+    let stale_persisted_chain = store
+        .hot_db
+        .get_bytes(DBColumn::BeaconChain.into(), beacon_chain_key.as_bytes())
+        .unwrap()
+        .unwrap();
+    harness.extend_slots(2).await;
+    let head_block_root = harness.head_block_root();
+    let current_slot = harness.get_current_slot();
+    drop(harness);
+
+    let resume = |heal_head_divergence| {
+        store
+            .hot_db
+            .put_bytes(
+                DBColumn::BeaconChain.into(),
+                beacon_chain_key.as_bytes(),
+                &stale_persisted_chain,
+            )
+            .unwrap();
+        let harness = BeaconChainHarness::builder(MinimalEthSpec)
+            .default_spec()
+            .keypairs(KEYPAIRS[0..LOW_VALIDATOR_COUNT].to_vec())
+            .resumed_disk_store(store.clone())
+            .chain_config(ChainConfig {
+                heal_head_divergence,
+                ..ChainConfig::default()
+            })
+            .mock_execution_layer()
+            .build();
+        harness.chain.slot_clock.set_slot(current_slot.as_u64());
+        harness
+    };
+
+    // The divergence is detected, but not healed by default.
+    let resumed_harness = resume(false);
+    let chain = &resumed_harness.chain;
+    let consistency = chain.check_head_consistency();
+    assert_eq!(consistency.snapshot_head_root, head_block_root);
+    assert_eq!(consistency.fork_choice_head_root, head_block_root);
+    assert_eq!(consistency.untracked_heads, vec![head_block_root]);
+    assert_eq!(consistency.stale_heads, vec![stale_head_block_root]);
+    assert!(!consistency.is_consistent());
+
+    // It may be healed explicitly.
+    assert!(chain.heal_head_divergence().await.unwrap().is_consistent());
+    let heads = chain.heads();
+    assert_eq!(heads.len(), 1);
+    assert_eq!(heads[0].0, head_block_root);
+    drop(resumed_harness);
+
+    // The divergence is healed on startup if configured.
+    let healed_harness = resume(true);
+    let consistency = healed_harness.chain.check_head_consistency();
+    assert_eq!(consistency.snapshot_head_root, head_block_root);
+    assert!(consistency.is_consistent());
+    let heads = healed_harness.chain.heads();
+    assert_eq!(heads.len(), 1);
+    assert_eq!(heads[0].0, head_block_root);
+}
+
+/// Check that the fee recipient of a registered validator is used for proposer preparation after
+/// a restart, without the registration being re-sent.
+#[tokio::test]
+async fn validator_registrations_persist_across_restarts() {
+    let mut spec = test_spec::<E>();
+    spec.altair_fork_epoch = Some(Epoch::new(0));
+    spec.bellatrix_fork_epoch = Some(Epoch::new(0));
+    let db_path = tempdir().unwrap();
+    let store = get_store_with_spec(&db_path, spec.clone());
+    let harness = BeaconChainHarness::builder(MinimalEthSpec)
+        .spec(spec.clone())
+        .keypairs(KEYPAIRS[0..LOW_VALIDATOR_COUNT].to_vec())
+        .fresh_disk_store(store.clone())
+        .mock_execution_layer()
+        .build();
+    harness
+        .execution_block_generator()
+        .move_to_terminal_block()
+        .unwrap();
+    harness.advance_slot();
+    harness
+        .extend_chain(
+            1,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::AllValidators,
+        )
+        .await;
+
+    let chain = &harness.chain;
+    let current_slot = chain.slot().unwrap();
+    let proposer = chain
+        .head_snapshot()
+        .beacon_state
+        .get_beacon_proposer_index(current_slot + 1, &chain.spec)
+        .unwrap();
+
+    let fee_recipient = Address::repeat_byte(42);
+    let keypair = &KEYPAIRS[proposer];
+    let message = ValidatorRegistrationData {
+        fee_recipient,
+        gas_limit: 30_000_000,
+        timestamp: 1,
+        pubkey: keypair.pk.compress(),
+    };
+    let signature = keypair
+        .sk
+        .sign(message.signing_root(chain.spec.get_builder_domain()));
+    chain
+        .process_validator_registrations(vec![SignedValidatorRegistrationData {
+            message,
+            signature,
+        }])
+        .await
+        .unwrap();
+
+    // The registrations are persisted when the chain is dropped.
+    drop(harness);
+
+    let resumed_harness = BeaconChainHarness::builder(MinimalEthSpec)
+        .spec(spec)
+        .keypairs(KEYPAIRS[0..LOW_VALIDATOR_COUNT].to_vec())
+        .resumed_disk_store(store)
+        .mock_execution_layer()
+        .build();
+    let chain = &resumed_harness.chain;
+    chain.slot_clock.set_slot(current_slot.as_u64());
+
+    assert_eq!(
+        chain.registered_fee_recipient(proposer as u64).unwrap(),
+        Some(fee_recipient)
+    );
+    chain.prepare_beacon_proposer(current_slot).await.unwrap();
+    let execution_layer = chain.execution_layer.as_ref().unwrap();
+    assert_eq!(
+        execution_layer
+            .get_suggested_fee_recipient(proposer as u64, None)
+            .await,
+        fee_recipient
+    );
+}
+
+/// Returns a harness resumed from a database whose validator pubkey cache has been deleted, as a
+/// node which has yet to decompress any keys would be.
+fn get_harness_without_pubkey_cache(db_path: &TempDir, validator_count: usize) -> TestHarness {
+    let store = get_store(db_path);
+    drop(get_harness(store.clone(), validator_count));
+
+    for validator_index in 0..validator_count {
+        store
+            .hot_db
+            .key_delete(
+                DBColumn::PubkeyCache.into(),
+                Hash256::from_low_u64_be(validator_index as u64).as_bytes(),
+            )
+            .unwrap();
+    }
+
+    let harness = BeaconChainHarness::builder(MinimalEthSpec)
+        .default_spec()
+        .keypairs(KEYPAIRS[0..validator_count].to_vec())
+        .resumed_disk_store(store)
+        .mock_execution_layer()
+        .build();
+    assert_eq!(harness.chain.validator_pubkey(0).unwrap(), None);
+    harness
+}
+
+#[tokio::test]
+async fn exported_pubkey_cache_bootstraps_sibling() {
+    let validator_count = 8;
+    let exporter_path = tempdir().unwrap();
+    let exporter = get_harness(get_store(&exporter_path), validator_count);
+    let mut export = vec![];
+    let header = exporter.chain.export_pubkey_cache(&mut export).unwrap();
+    assert_eq!(header.validator_count, validator_count as u64);
+
+    let sibling_path = tempdir().unwrap();
+    let sibling = get_harness_without_pubkey_cache(&sibling_path, validator_count);
+
+    assert_eq!(
+        sibling.chain.import_pubkey_cache(&export[..]).unwrap(),
+        validator_count
+    );
+    for (i, keypair) in sibling.validator_keypairs.iter().enumerate() {
+        assert_eq!(
+            sibling.chain.validator_pubkey(i).unwrap(),
+            Some(keypair.pk.clone())
+        );
+        assert_eq!(
+            sibling
+                .chain
+                .validator_index(&keypair.pk.compress())
+                .unwrap(),
+            Some(i)
+        );
+    }
+
+    // Importing again adds nothing.
+    assert_eq!(sibling.chain.import_pubkey_cache(&export[..]).unwrap(), 0);
+}
+
+#[tokio::test]
+async fn pubkey_cache_import_ignores_keys_beyond_head_state() {
+    let validator_count = 8;
+    let extra_keys = 4;
+    let exporter_path = tempdir().unwrap();
+    let exporter = get_harness(get_store(&exporter_path), validator_count);
+    let mut export = vec![];
+    let header = exporter.chain.export_pubkey_cache(&mut export).unwrap();
+
+    // Append the keys of validators which aren't in the head state, with a valid header.
+    let mut key_bytes = export.split_off(<PubkeyCacheHeader as ssz::Decode>::ssz_fixed_len());
+    for keypair in &KEYPAIRS[validator_count..validator_count + extra_keys] {
+        key_bytes.extend_from_slice(&keypair.pk.serialize_uncompressed());
+    }
+    let header = PubkeyCacheHeader {
+        validator_count: (validator_count + extra_keys) as u64,
+        checksum: Hash256::from_slice(&eth2_hashing::hash(&key_bytes)),
+        ..header
+    };
+    let mut export = header.as_ssz_bytes();
+    export.extend_from_slice(&key_bytes);
+
+    let sibling_path = tempdir().unwrap();
+    let sibling = get_harness_without_pubkey_cache(&sibling_path, validator_count);
+
+    assert_eq!(
+        sibling.chain.import_pubkey_cache(&export[..]).unwrap(),
+        validator_count
+    );
+    for (i, keypair) in KEYPAIRS[0..validator_count + extra_keys].iter().enumerate() {
+        let expected = (i < validator_count).then(|| keypair.pk.clone());
+        assert_eq!(sibling.chain.validator_pubkey(i).unwrap(), expected);
+        assert_eq!(
+            sibling
+                .chain
+                .store
+                .hot_db
+                .key_exists(
+                    DBColumn::PubkeyCache.into(),
+                    Hash256::from_low_u64_be(i as u64).as_bytes()
+                )
+                .unwrap(),
+            i < validator_count
+        );
+    }
+}
+
+#[tokio::test]
+async fn invalid_pubkey_cache_exports_are_refused() {
+    let exporter_path = tempdir().unwrap();
+    let exporter = get_harness(get_store(&exporter_path), 8);
+    let mut export = vec![];
+    exporter.chain.export_pubkey_cache(&mut export).unwrap();
+
+    // A different validator set has a different genesis validators root.
+    let other_network_path = tempdir().unwrap();
+    let other_network = get_harness(get_store(&other_network_path), 16);
+    assert!(matches!(
+        other_network.chain.import_pubkey_cache(&export[..]),
+        Err(BeaconChainError::PubkeyCacheTransferError(
+            PubkeyCacheTransferError::GenesisValidatorsRootMismatch { .. }
+        ))
+    ));
+
+    let sibling_path = tempdir().unwrap();
+    let sibling = get_harness(get_store(&sibling_path), 8);
+    let mut truncated = export.clone();
+    truncated.pop();
+    assert!(matches!(
+        sibling.chain.import_pubkey_cache(&truncated[..]),
+        Err(BeaconChainError::PubkeyCacheTransferError(
+            PubkeyCacheTransferError::ValidatorCountMismatch { .. }
+        ))
+    ));
+
+    let mut corrupted = export;
+    *corrupted.last_mut().unwrap() ^= 1;
+    assert!(matches!(
+        sibling.chain.import_pubkey_cache(&corrupted[..]),
+        Err(BeaconChainError::PubkeyCacheTransferError(
+            PubkeyCacheTransferError::ChecksumMismatch { .. }
+        ))
+    ));
+}
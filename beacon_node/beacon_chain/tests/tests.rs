@@ -1,20 +1,56 @@
 #![cfg(not(debug_assertions))]
 
+use crate::store_tests::counter_value;
 use beacon_chain::{
     attestation_verification::Error as AttnError,
+    block_status::BlockStatusReport,
+    chain_config::{ShutdownTarget, HEAD_SYNCED_TOLERANCE_SLOTS},
+    checkpoint_state_cache::CheckpointStateCacheStats,
+    clock_monitor::{
+        ClockJump, ClockStatus, CLOCK_JUMP_GOSSIP_CLOCK_DISPARITY, CLOCK_JUMP_RECOVERY_SLOTS,
+    },
+    deposit_status::DepositStatus,
+    eth1_chain::CachingEth1Backend,
+    failure_log_limiter::FailureKind,
+    fork_choice_signal::SlotWaitResult,
+    graffiti::GraffitiMode,
+    observed_operations::ObservationOutcome,
+    randao_snapshot::RANDAO_MIXES_SNAPSHOT_EPOCHS,
+    self_test::{disk_write_check, SelfTestCheck},
+    slot_clock::SlotClock,
     test_utils::{
-        AttestationStrategy, BeaconChainHarness, BlockStrategy, EphemeralHarnessType,
-        OP_POOL_DB_KEY,
+        test_spec, AttestationStrategy, BeaconChainHarness, BlockStrategy, CapturedLogs,
+        EphemeralHarnessType, HarnessAttestations, HARNESS_GENESIS_TIME, OP_POOL_DB_KEY,
     },
-    BeaconChain, StateSkipConfig, WhenSlotSkipped,
+    BeaconChain, BeaconChainError, BeaconForkChoiceStore, BlockProductionError, ChainConfig,
+    HeadChange, PreFinalizationCacheStats, ProduceBlockVerification, StateSkipConfig,
+    WhenSlotSkipped, BLOCK_PROCESSING_CACHE_LOCK_TIMEOUT, MAXIMUM_GOSSIP_CLOCK_DISPARITY,
+};
+use bls::get_withdrawal_credentials;
+use eth1::{Config as Eth1Config, DepositLog, Eth1Block};
+use eth2::types::{
+    EventKind, FinalityDelayLevel, SelfTestReadiness, SelfTestSeverity, SelfTestStatus, ValidatorId,
 };
+use fork_choice::ForkChoice;
 use lazy_static::lazy_static;
 use operation_pool::PersistedOperationPool;
+use proto_array::ProtoArrayForkChoice;
+use slog::Level;
+use ssz::Encode;
 use state_processing::{
-    per_slot_processing, per_slot_processing::Error as SlotProcessingError, EpochProcessingError,
+    per_slot_processing, per_slot_processing::Error as SlotProcessingError,
+    state_advance::complete_state_advance, EpochProcessingError,
 };
+use std::collections::BTreeMap;
+use std::mem::discriminant;
+use std::sync::Arc;
+use std::time::Duration;
+use task_executor::ShutdownReason;
 use types::{
-    BeaconState, BeaconStateError, EthSpec, Hash256, Keypair, MinimalEthSpec, RelativeEpoch, Slot,
+    test_utils::generate_deterministic_keypair, AttestationShufflingId, BeaconState,
+    BeaconStateError, Checkpoint, DepositData, Domain, Epoch, EthSpec, FullPayload, Graffiti,
+    Hash256, Keypair, MainnetEthSpec, MinimalEthSpec, RelativeEpoch, Signature, SignatureBytes,
+    Slot, Unsigned, VariableList, DEPOSIT_TREE_DEPTH, GRAFFITI_BYTES_LEN,
 };
 
 // Should ideally be divisible by 3.
@@ -226,6 +262,391 @@ async fn find_reorgs() {
     );
 }
 
+#[tokio::test]
+async fn canonical_chain_updates_on_reorg() {
+    let harness = get_harness(VALIDATOR_COUNT);
+
+    harness
+        .extend_chain(
+            MinimalEthSpec::slots_per_epoch() as usize,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::AllValidators,
+        )
+        .await;
+
+    let mut events = harness
+        .chain
+        .event_handler
+        .as_ref()
+        .unwrap()
+        .subscribe_canonical_chain_updates();
+
+    let fork_point = harness.chain.head_snapshot();
+    let fork_state = fork_point.beacon_state.clone();
+    let fork_slot = fork_state.slot();
+
+    // Two blocks on top of the fork point, without any attestations.
+    let (b1, _, state) = harness
+        .add_block_at_slot(fork_slot + 1, fork_state.clone())
+        .await
+        .unwrap();
+    let (b2, _, _) = harness
+        .add_block_at_slot(fork_slot + 2, state)
+        .await
+        .unwrap();
+    let (b1, b2) = (Hash256::from(b1), Hash256::from(b2));
+    assert_eq!(harness.head_block_root(), b2);
+
+    let update = harness.chain.canonical_updates_since(0).pop().unwrap();
+    assert_eq!(update.old_head_block, b1);
+    assert_eq!(update.new_head_block, b2);
+    assert_eq!(update.common_ancestor, b1);
+    assert_eq!(update.canonical_blocks, vec![b2]);
+    assert!(update.non_canonical_blocks.is_empty());
+    let next_sequence_number = update.sequence_number + 1;
+
+    // Two competing blocks on top of the fork point. The head isn't updated after the first, the
+    // second receives the proposer boost and becomes the head.
+    harness.set_current_slot(fork_slot + 3);
+    let (c1, state) = harness.make_block(fork_state, fork_slot + 3).await;
+    let c1_root = c1.canonical_root();
+    harness.chain.process_block(Arc::new(c1)).await.unwrap();
+    assert_eq!(harness.head_block_root(), b2);
+
+    let (c2, _) = harness.make_block(state, fork_slot + 4).await;
+    let c2_root: Hash256 = harness
+        .process_block(fork_slot + 4, c2)
+        .await
+        .unwrap()
+        .into();
+    assert_eq!(harness.head_block_root(), c2_root);
+
+    let updates = harness.chain.canonical_updates_since(next_sequence_number);
+    assert_eq!(updates.len(), 1);
+    let update = &updates[0];
+    assert_eq!(update.sequence_number, next_sequence_number);
+    assert_eq!(update.old_head_block, b2);
+    assert_eq!(update.new_head_block, c2_root);
+    assert_eq!(update.common_ancestor, fork_point.beacon_block_root);
+    assert_eq!(update.canonical_blocks, vec![c1_root, c2_root]);
+    assert_eq!(update.non_canonical_blocks, vec![b1, b2]);
+
+    // The same updates are published as server-sent events.
+    let mut received = vec![];
+    while let Ok(EventKind::CanonicalChainUpdate(update)) = events.try_recv() {
+        received.push(update);
+    }
+    assert_eq!(received.len(), 3);
+    assert_eq!(received.last(), Some(update));
+}
+
+#[tokio::test]
+async fn head_changes_are_broadcast() {
+    let harness = get_harness(VALIDATOR_COUNT);
+
+    harness
+        .extend_chain(
+            MinimalEthSpec::slots_per_epoch() as usize,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::AllValidators,
+        )
+        .await;
+
+    let mut head_changes = harness.chain.subscribe_to_head_changes();
+    let finalized_checkpoint = harness
+        .chain
+        .canonical_head
+        .cached_head()
+        .finalized_checkpoint();
+
+    let fork_point = harness.chain.head_snapshot();
+    let fork_state = fork_point.beacon_state.clone();
+    let fork_slot = fork_state.slot();
+
+    // A single block on top of the head is published once, and is not a re-org.
+    let (b1, _, _) = harness
+        .add_block_at_slot(fork_slot + 1, fork_state.clone())
+        .await
+        .unwrap();
+    let b1 = Hash256::from(b1);
+    assert_eq!(
+        head_changes.try_recv().unwrap(),
+        HeadChange {
+            old_root: fork_point.beacon_block_root,
+            new_root: b1,
+            slot: fork_slot + 1,
+            is_reorg: false,
+            finalized_checkpoint,
+        }
+    );
+    assert!(head_changes.try_recv().is_err());
+
+    // A competing block which receives the proposer boost re-orgs out `b1`.
+    let (c1, _) = harness.make_block(fork_state, fork_slot + 2).await;
+    let c1_root: Hash256 = harness
+        .process_block(fork_slot + 2, c1)
+        .await
+        .unwrap()
+        .into();
+    assert_eq!(harness.head_block_root(), c1_root);
+    assert_eq!(
+        head_changes.try_recv().unwrap(),
+        HeadChange {
+            old_root: b1,
+            new_root: c1_root,
+            slot: fork_slot + 2,
+            is_reorg: true,
+            finalized_checkpoint,
+        }
+    );
+    assert!(head_changes.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn latest_head_and_finalized_events_are_replayed() {
+    let harness = get_harness(VALIDATOR_COUNT);
+    let event_handler = harness.chain.event_handler.as_ref().unwrap();
+
+    // The events are registered at startup, before the head or finalized checkpoint change.
+    match event_handler.subscribe_head_with_replay() {
+        (Some(EventKind::Head(head)), _) => {
+            assert_eq!(head.block, harness.chain.genesis_block_root)
+        }
+        (other, _) => panic!("expected a head event, got {:?}", other),
+    }
+    match event_handler.subscribe_finalized_with_replay() {
+        (Some(EventKind::FinalizedCheckpoint(checkpoint)), _) => {
+            assert_eq!(checkpoint.epoch, Epoch::new(0))
+        }
+        (other, _) => panic!("expected a finalized checkpoint event, got {:?}", other),
+    }
+
+    // No subscribers are registered whilst the chain finalizes.
+    harness
+        .extend_chain(
+            MinimalEthSpec::slots_per_epoch() as usize * 5,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::AllValidators,
+        )
+        .await;
+    let cached_head = harness.chain.canonical_head.cached_head();
+    assert!(cached_head.finalized_checkpoint().epoch > 0);
+
+    let (replayed_head, mut heads) = event_handler.subscribe_head_with_replay();
+    match replayed_head {
+        Some(EventKind::Head(head)) => {
+            assert_eq!(head.block, cached_head.head_block_root());
+            assert_eq!(head.slot, cached_head.head_slot());
+        }
+        other => panic!("expected a head event, got {:?}", other),
+    }
+    let (replayed_finalized, mut finalized) = event_handler.subscribe_finalized_with_replay();
+    match replayed_finalized {
+        Some(EventKind::FinalizedCheckpoint(checkpoint)) => {
+            assert_eq!(checkpoint.epoch, cached_head.finalized_checkpoint().epoch);
+            assert_eq!(checkpoint.block, cached_head.finalized_checkpoint().root);
+        }
+        other => panic!("expected a finalized checkpoint event, got {:?}", other),
+    }
+    // The replayed events are not also received.
+    assert!(heads.try_recv().is_err());
+    assert!(finalized.try_recv().is_err());
+}
+
+/// The number of validators which vote in the aggregates of `attestations`.
+fn aggregate_votes(attestations: &HarnessAttestations<MinimalEthSpec>) -> u64 {
+    attestations
+        .iter()
+        .filter_map(|(_, aggregate)| aggregate.as_ref())
+        .map(|aggregate| aggregate.message.aggregate.aggregation_bits.num_set_bits() as u64)
+        .sum()
+}
+
+#[tokio::test]
+async fn head_weights_are_reported() {
+    let harness = get_harness(VALIDATOR_COUNT);
+    let balance = harness.spec.max_effective_balance;
+    let mut events = harness
+        .chain
+        .event_handler
+        .as_ref()
+        .unwrap()
+        .subscribe_head();
+    let mut last_head_event = || {
+        let mut last = None;
+        while let Ok(EventKind::Head(head)) = events.try_recv() {
+            last = Some(head);
+        }
+        last.unwrap()
+    };
+
+    // Two competing blocks on top of genesis, without any attestations. The second receives the
+    // proposer boost and becomes the head.
+    let (genesis_state, _) = harness.get_current_state_and_root();
+    let (a, a_block, a_state) = harness
+        .add_block_at_slot(Slot::new(1), genesis_state.clone())
+        .await
+        .unwrap();
+    let (b, b_block, b_state) = harness
+        .add_block_at_slot(Slot::new(2), genesis_state)
+        .await
+        .unwrap();
+    assert_eq!(harness.head_block_root(), Hash256::from(b));
+
+    let head = last_head_event();
+    assert_eq!(head.block, Hash256::from(b));
+    assert!(head.proposer_boost.unwrap() > 0);
+    assert_eq!(head.head_weight, head.proposer_boost);
+    assert_eq!(head.competitor_weight, Some(0));
+
+    // The committee of the first block votes for it, whilst only one member of the committee of
+    // the second block votes for that.
+    let a_attestations = harness.make_attestations(
+        &harness.get_all_validators(),
+        &a_state,
+        a_block.state_root(),
+        a,
+        Slot::new(1),
+    );
+    let b_voter = b_state
+        .get_beacon_committee(Slot::new(2), 0)
+        .unwrap()
+        .committee[0];
+    let b_attestations =
+        harness.make_attestations(&[b_voter], &b_state, b_block.state_root(), b, Slot::new(2));
+    let (a_votes, b_votes) = (
+        aggregate_votes(&a_attestations),
+        aggregate_votes(&b_attestations),
+    );
+    assert!(a_votes > b_votes);
+    assert_eq!(b_votes, 1);
+    harness.process_attestations(a_attestations);
+    harness.process_attestations(b_attestations);
+
+    // Once the proposer boost expires, the first block becomes the head again.
+    harness.advance_slot();
+    harness
+        .chain
+        .recompute_head_at_current_slot()
+        .await
+        .unwrap();
+    assert_eq!(harness.head_block_root(), Hash256::from(a));
+
+    let head = last_head_event();
+    assert_eq!(head.block, Hash256::from(a));
+    assert_eq!(head.head_weight, Some(a_votes * balance));
+    assert_eq!(head.competitor_weight, Some(b_votes * balance));
+    assert_eq!(head.proposer_boost, Some(0));
+}
+
+#[tokio::test]
+async fn attestation_inclusion_delays_are_summarized() {
+    let harness = get_harness(VALIDATOR_COUNT);
+    let (state, state_root) = harness.get_current_state_and_root();
+
+    // The attestations to the blocks before the skipped slots are included late.
+    let slots = [1, 2, 4, 7].map(Slot::new);
+    let (block_roots, _, _, _) = harness
+        .add_attested_blocks_at_slots(state, state_root, &slots, &harness.get_all_validators())
+        .await;
+
+    let mut expected_delays = BTreeMap::new();
+    for block_root in block_roots.values() {
+        let block = harness
+            .chain
+            .get_blinded_block(&(*block_root).into())
+            .unwrap()
+            .unwrap();
+        for attestation in block.message().body().attestations() {
+            *expected_delays
+                .entry((block.slot() - attestation.data.slot).as_u64())
+                .or_insert(0) += 1;
+        }
+    }
+    for delay in 1..=3 {
+        assert!(expected_delays.contains_key(&delay));
+    }
+
+    let summary = harness
+        .chain
+        .inclusion_delay_summary(Epoch::new(0))
+        .expect("should summarize epoch 0");
+    assert_eq!(summary.delays, expected_delays);
+    let attestations: u64 = expected_delays.values().sum();
+    let total_delay: u64 = expected_delays
+        .iter()
+        .map(|(delay, count)| delay * count)
+        .sum();
+    assert_eq!(summary.attestations(), attestations);
+    assert_eq!(
+        summary.mean(),
+        Some(total_delay as f64 / attestations as f64)
+    );
+    assert_eq!(harness.chain.inclusion_delay_summary(Epoch::new(1)), None);
+}
+
+#[tokio::test]
+async fn fork_choice_runs_are_summarized_once_per_slot() {
+    let harness = get_harness(VALIDATOR_COUNT);
+    harness
+        .extend_chain(
+            2,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::SomeValidators(vec![]),
+        )
+        .await;
+    let chain = &harness.chain;
+
+    // Attestations to the head in the current slot are queued by fork choice.
+    let head = chain.head_snapshot();
+    let slot = head.beacon_block.slot();
+    let attestations = harness.make_attestations(
+        &harness.get_all_validators(),
+        &head.beacon_state,
+        head.beacon_state_root(),
+        head.beacon_block_root.into(),
+        slot,
+    );
+    harness.process_attestations(attestations);
+    let queued = chain
+        .canonical_head
+        .fork_choice_read_lock()
+        .queued_attestations()
+        .len();
+    assert!(queued > 0);
+
+    // Fork choice is brought up to date with the next slot before its run, as it would be by an
+    // imported block. The queued attestations are still counted by the run.
+    harness.advance_slot();
+    chain.recompute_head_at_slot(slot + 1).await.unwrap();
+    assert!(chain
+        .canonical_head
+        .fork_choice_read_lock()
+        .queued_attestations()
+        .is_empty());
+    let summary = chain.fork_choice_at_slot(slot + 1).await.unwrap();
+    assert_eq!(summary.slot, slot + 1);
+    assert_eq!(summary.queued_attestations, queued);
+    assert_eq!(summary.applied, queued);
+    assert_eq!(summary.dropped_invalid, 0);
+
+    // A further run for the same slot has nothing to apply and is merged into the same summary.
+    let merged_summary = chain.fork_choice_at_slot(slot + 1).await.unwrap();
+    assert_eq!(merged_summary.queued_attestations, queued);
+    assert_eq!(merged_summary.applied, queued);
+    assert!(merged_summary.duration >= summary.duration);
+    assert_eq!(chain.fork_choice_run_summaries(), vec![merged_summary]);
+
+    // The run for the next slot is summarized separately.
+    harness.advance_slot();
+    let next_summary = chain.fork_choice_at_slot(slot + 2).await.unwrap();
+    assert_eq!(next_summary.queued_attestations, 0);
+    assert_eq!(
+        chain.fork_choice_run_summaries(),
+        vec![merged_summary, next_summary]
+    );
+}
+
 #[tokio::test]
 async fn chooses_fork() {
     let harness = get_harness(VALIDATOR_COUNT);
@@ -276,6 +697,256 @@ async fn chooses_fork() {
     );
 }
 
+#[tokio::test]
+async fn detailed_heads_of_orphaned_fork() {
+    let harness = get_harness(VALIDATOR_COUNT);
+
+    let two_thirds = (VALIDATOR_COUNT / 3) * 2;
+    let honest_validators: Vec<usize> = (0..two_thirds).collect();
+    let faulty_validators: Vec<usize> = (two_thirds..VALIDATOR_COUNT).collect();
+
+    harness
+        .extend_chain(
+            2,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::AllValidators,
+        )
+        .await;
+    let (honest_head, faulty_head) = harness
+        .generate_two_forks_by_skipping_a_block(&honest_validators, &faulty_validators, 2, 3)
+        .await;
+
+    let detailed_head = |root| {
+        harness
+            .chain
+            .detailed_heads()
+            .unwrap()
+            .into_iter()
+            .find(|head| head.root == root)
+    };
+
+    // Both forks descend from the genesis checkpoint.
+    let orphan = detailed_head(faulty_head).expect("orphan should be a head");
+    assert!(!orphan.canonical);
+    assert!(orphan.descends_from_justified);
+    assert!(orphan.viable);
+    let head = detailed_head(honest_head).expect("canonical head should be a head");
+    assert!(head.canonical);
+    assert_eq!(head.distance, 0);
+    assert_eq!(orphan.distance, orphan.slot.as_u64() - head.slot.as_u64());
+
+    // Extend the canonical chain until the justified checkpoint is after the fork.
+    while harness
+        .chain
+        .canonical_head
+        .cached_head()
+        .justified_checkpoint()
+        .epoch
+        == 0
+    {
+        harness
+            .extend_chain(
+                1,
+                BlockStrategy::OnCanonicalHead,
+                AttestationStrategy::AllValidators,
+            )
+            .await;
+    }
+    assert_eq!(
+        harness
+            .chain
+            .canonical_head
+            .cached_head()
+            .finalized_checkpoint()
+            .epoch,
+        0,
+        "the orphan should not have been pruned by finalization"
+    );
+
+    let canonical_root = harness.chain.head_snapshot().beacon_block_root;
+    let canonical_slot = harness.chain.head_snapshot().beacon_block.slot();
+    let orphan = detailed_head(faulty_head).expect("orphan should still be a head");
+    assert!(!orphan.canonical);
+    assert!(!orphan.descends_from_justified);
+    assert!(!orphan.viable);
+    assert_eq!(
+        orphan.distance,
+        canonical_slot.as_u64() - orphan.slot.as_u64()
+    );
+
+    let head = detailed_head(canonical_root).expect("canonical head should be a head");
+    assert!(head.canonical);
+    assert!(head.descends_from_justified);
+    assert!(head.viable);
+    assert_eq!(head.distance, 0);
+}
+
+#[tokio::test]
+async fn block_status_of_canonical_orphaned_finalized_and_unknown_blocks() {
+    let harness = get_harness(VALIDATOR_COUNT);
+
+    let two_thirds = (VALIDATOR_COUNT / 3) * 2;
+    let honest_validators: Vec<usize> = (0..two_thirds).collect();
+    let faulty_validators: Vec<usize> = (two_thirds..VALIDATOR_COUNT).collect();
+
+    harness
+        .extend_chain(
+            2,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::AllValidators,
+        )
+        .await;
+    let (honest_head, faulty_head) = harness
+        .generate_two_forks_by_skipping_a_block(&honest_validators, &faulty_validators, 2, 3)
+        .await;
+    assert_eq!(harness.head_block_root(), honest_head);
+
+    match harness.chain.block_status(honest_head).unwrap() {
+        BlockStatusReport::Known {
+            slot,
+            canonical,
+            finalized,
+            execution_status,
+        } => {
+            assert_eq!(slot, harness.chain.head_snapshot().beacon_block.slot());
+            assert!(canonical);
+            assert!(!finalized);
+            assert!(execution_status.is_some());
+        }
+        other => panic!("head should be known, got {:?}", other),
+    }
+    match harness.chain.block_status(faulty_head).unwrap() {
+        BlockStatusReport::Known {
+            canonical,
+            finalized,
+            ..
+        } => {
+            assert!(!canonical);
+            assert!(!finalized);
+        }
+        other => panic!("orphan should be known, got {:?}", other),
+    }
+
+    harness
+        .extend_chain(
+            MinimalEthSpec::slots_per_epoch() as usize * 5,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::AllValidators,
+        )
+        .await;
+    let finalized_epoch = harness.finalized_checkpoint().epoch;
+    assert!(finalized_epoch > 0, "the chain should have finalized");
+
+    let finalized_slot = Slot::new(1);
+    let finalized_root = harness
+        .chain
+        .block_root_at_slot(finalized_slot, WhenSlotSkipped::None)
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        harness.chain.block_status(finalized_root).unwrap(),
+        BlockStatusReport::Known {
+            slot: finalized_slot,
+            canonical: true,
+            finalized: true,
+            execution_status: harness
+                .chain
+                .canonical_head
+                .fork_choice_read_lock()
+                .proto_array()
+                .get_block_execution_status(&finalized_root),
+        }
+    );
+    // The honest head is now finalized too.
+    assert!(matches!(
+        harness.chain.block_status(honest_head).unwrap(),
+        BlockStatusReport::Known {
+            canonical: true,
+            finalized: true,
+            ..
+        }
+    ));
+
+    assert_eq!(
+        harness
+            .chain
+            .block_status(Hash256::repeat_byte(0x42))
+            .unwrap(),
+        BlockStatusReport::Unknown
+    );
+}
+
+#[tokio::test]
+async fn block_status_of_blocks_pruned_from_fork_choice() {
+    let harness = get_harness(VALIDATOR_COUNT);
+
+    harness
+        .extend_chain(
+            MinimalEthSpec::slots_per_epoch() as usize * 5,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::AllValidators,
+        )
+        .await;
+    let finalized_checkpoint = harness.finalized_checkpoint();
+    assert!(
+        finalized_checkpoint.epoch > 0,
+        "the chain should have finalized"
+    );
+
+    // Prune the blocks from before finalization from fork choice, so they're only in the database.
+    {
+        let chain = &harness.chain;
+        let mut fork_choice = chain.canonical_head.fork_choice_write_lock();
+        let mut persisted = fork_choice.to_persisted();
+        let mut proto_array =
+            ProtoArrayForkChoice::from_bytes(&persisted.proto_array_bytes).unwrap();
+        proto_array.set_prune_threshold(0);
+        proto_array.maybe_prune(finalized_checkpoint.root).unwrap();
+        persisted.proto_array_bytes = proto_array.as_bytes();
+
+        let fc_store = BeaconForkChoiceStore::from_persisted(
+            fork_choice.fc_store().to_persisted(),
+            chain.store.clone(),
+        )
+        .unwrap();
+        *fork_choice = ForkChoice::from_persisted(persisted, fc_store, &chain.spec).unwrap();
+    }
+
+    let finalized_slot = Slot::new(1);
+    let finalized_root = harness
+        .chain
+        .block_root_at_slot(finalized_slot, WhenSlotSkipped::None)
+        .unwrap()
+        .unwrap();
+    assert!(!harness
+        .chain
+        .canonical_head
+        .fork_choice_read_lock()
+        .contains_block(&finalized_root));
+    assert_eq!(
+        harness.chain.block_status(finalized_root).unwrap(),
+        BlockStatusReport::Known {
+            slot: finalized_slot,
+            canonical: true,
+            finalized: true,
+            execution_status: None,
+        }
+    );
+
+    // A block rejected for conflicting with finalization is only known to the pre-finalization
+    // cache.
+    let rejected_root = Hash256::repeat_byte(0x42);
+    harness
+        .chain
+        .pre_finalization_block_rejected(rejected_root, finalized_slot);
+    assert_eq!(
+        harness.chain.block_status(rejected_root).unwrap(),
+        BlockStatusReport::ConflictsWithFinalization {
+            slot: finalized_slot
+        }
+    );
+}
+
 #[tokio::test]
 async fn finalizes_with_full_participation() {
     let num_blocks_produced = MinimalEthSpec::slots_per_epoch() * 5;
@@ -444,426 +1115,2391 @@ async fn does_not_finalize_without_attestation() {
     );
 }
 
-#[tokio::test]
-async fn roundtrip_operation_pool() {
-    let num_blocks_produced = MinimalEthSpec::slots_per_epoch() * 5;
+fn get_harness_with_shutdown_target(
+    target: ShutdownTarget,
+) -> BeaconChainHarness<EphemeralHarnessType<MinimalEthSpec>> {
+    let harness = BeaconChainHarness::builder(MinimalEthSpec)
+        .default_spec()
+        .chain_config(ChainConfig {
+            shutdown_after: Some(target),
+            ..ChainConfig::default()
+        })
+        .keypairs(KEYPAIRS[0..VALIDATOR_COUNT].to_vec())
+        .fresh_ephemeral_store()
+        .mock_execution_layer()
+        .build();
 
-    let harness = get_harness(VALIDATOR_COUNT);
+    harness.advance_slot();
+
+    harness
+}
+
+#[tokio::test]
+async fn shutdown_after_target_slot() {
+    let target_slot = Slot::new(4);
+    let harness = get_harness_with_shutdown_target(ShutdownTarget::Slot(target_slot));
 
-    // Add some attestations
     harness
         .extend_chain(
-            num_blocks_produced as usize,
+            target_slot.as_usize() - 1,
             BlockStrategy::OnCanonicalHead,
             AttestationStrategy::AllValidators,
         )
         .await;
-    assert!(harness.chain.op_pool.num_attestations() > 0);
+    assert!(harness.shutdown_reasons().is_empty());
 
-    // TODO: could add some other operations
+    // Reaching the target slot triggers the shut down.
     harness
-        .chain
-        .persist_op_pool()
-        .expect("should persist op pool");
+        .extend_chain(
+            1,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::AllValidators,
+        )
+        .await;
+    assert_eq!(
+        harness.chain.head_snapshot().beacon_block.slot(),
+        target_slot
+    );
+    assert_eq!(
+        harness.shutdown_reasons(),
+        vec![ShutdownReason::Success("Shutdown target reached")]
+    );
 
-    let restored_op_pool = harness
+    // The head and op pool were persisted before the shut down was requested.
+    assert!(harness
         .chain
         .store
         .get_item::<PersistedOperationPool<MinimalEthSpec>>(&OP_POOL_DB_KEY)
-        .expect("should read db")
-        .expect("should find op pool")
-        .into_operation_pool()
-        .unwrap();
+        .unwrap()
+        .is_some());
 
-    assert_eq!(harness.chain.op_pool, restored_op_pool);
+    // The shut down is only requested once, even as the chain progresses.
+    harness
+        .extend_chain(
+            2,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::AllValidators,
+        )
+        .await;
+    harness.chain.per_slot_task().await;
+    assert!(harness.shutdown_reasons().is_empty());
 }
 
 #[tokio::test]
-async fn unaggregated_attestations_added_to_fork_choice_some_none() {
-    let num_blocks_produced = MinimalEthSpec::slots_per_epoch() / 2;
+async fn shutdown_after_head_synced() {
+    let harness = get_harness_with_shutdown_target(ShutdownTarget::HeadSynced);
 
-    let harness = get_harness(VALIDATOR_COUNT);
+    // Skip well beyond the tolerance so that the head is behind the wall-clock slot.
+    for _ in 0..=HEAD_SYNCED_TOLERANCE_SLOTS {
+        harness.advance_slot();
+    }
+    harness.chain.per_slot_task().await;
+    assert!(harness.shutdown_reasons().is_empty());
 
     harness
         .extend_chain(
-            num_blocks_produced as usize,
+            1,
             BlockStrategy::OnCanonicalHead,
             AttestationStrategy::AllValidators,
         )
         .await;
+    assert_eq!(
+        harness.shutdown_reasons(),
+        vec![ShutdownReason::Success("Shutdown target reached")]
+    );
+}
 
-    let head = harness.chain.head_snapshot();
-    let state = &head.beacon_state;
-    let mut fork_choice = harness.chain.canonical_head.fork_choice_write_lock();
-
-    // Move forward a slot so all queued attestations can be processed.
-    harness.advance_slot();
-    fork_choice
-        .update_time(harness.chain.slot().unwrap())
-        .unwrap();
-
-    let validator_slots: Vec<(usize, Slot)> = (0..VALIDATOR_COUNT)
-        .into_iter()
-        .map(|validator_index| {
-            let slot = state
-                .get_attestation_duties(validator_index, RelativeEpoch::Current)
-                .expect("should get attester duties")
-                .unwrap()
-                .slot;
-
-            (validator_index, slot)
-        })
-        .collect();
-
-    for (validator, slot) in validator_slots.clone() {
-        let latest_message = fork_choice.latest_message(validator);
-
-        if slot <= num_blocks_produced && slot != 0 {
-            assert_eq!(
-                latest_message.unwrap().1,
-                slot.epoch(MinimalEthSpec::slots_per_epoch()),
-                "Latest message epoch for {} should be equal to epoch {}.",
-                validator,
-                slot
-            )
-        } else {
-            assert!(
-                latest_message.is_none(),
-                "Latest message slot should be None."
-            )
-        }
-    }
+/// The value of the gauge named `name`, which may be set by any test.
+fn gauge_value(name: &str) -> Option<i64> {
+    lighthouse_metrics::gather()
+        .iter()
+        .find(|family| family.get_name() == name)
+        .and_then(|family| family.get_metric().first())
+        .map(|metric| metric.get_gauge().get_value() as i64)
 }
 
 #[tokio::test]
-async fn attestations_with_increasing_slots() {
-    let num_blocks_produced = MinimalEthSpec::slots_per_epoch() * 5;
-
-    let harness = get_harness(VALIDATOR_COUNT);
+async fn finality_delay_watchdog_escalates() {
+    let chain_config = ChainConfig {
+        finality_delay_warn_epochs: 3,
+        finality_delay_crit_epochs: 5,
+        finality_delay_pause_backfill: true,
+        ..ChainConfig::default()
+    };
+    let logs = CapturedLogs::default();
+    let harness = BeaconChainHarness::builder(MinimalEthSpec)
+        .default_spec()
+        .chain_config(chain_config)
+        .keypairs(KEYPAIRS[..].to_vec())
+        .logger(logs.logger())
+        .fresh_ephemeral_store()
+        .mock_execution_layer()
+        .build();
+    harness.advance_slot();
+    let slots_per_epoch = MinimalEthSpec::slots_per_epoch() as usize;
+    let logged = |level: Level, message: &str| {
+        logs.messages(level)
+            .iter()
+            .filter(|logged| *logged == message)
+            .count()
+    };
 
-    let mut attestations = vec![];
+    let mut events = harness
+        .chain
+        .event_handler
+        .as_ref()
+        .unwrap()
+        .subscribe_finality_delay();
 
-    for _ in 0..num_blocks_produced {
+    // Without attestations the chain doesn't finalize.
+    let mut levels = vec![];
+    for epoch in 1..=6 {
+        logs.clear();
         harness
             .extend_chain(
-                2,
+                slots_per_epoch,
                 BlockStrategy::OnCanonicalHead,
-                // Don't produce & include any attestations (we'll collect them later).
                 AttestationStrategy::SomeValidators(vec![]),
             )
             .await;
+        harness.chain.per_slot_task().await;
 
-        let head = harness.chain.head_snapshot();
-        let head_state_root = head.beacon_state_root();
-
-        attestations.extend(harness.get_unaggregated_attestations(
-            &AttestationStrategy::AllValidators,
-            &head.beacon_state,
-            head_state_root,
-            head.beacon_block_root,
-            head.beacon_block.slot(),
-        ));
+        let delay = harness.chain.finality_delay().unwrap();
+        assert_eq!(delay.epoch, epoch);
+        assert_eq!(delay.epochs_since_finality, epoch);
+        levels.push(delay.level);
+        assert_eq!(
+            harness.chain.backfill_rate_limiter.is_paused(),
+            delay.level == FinalityDelayLevel::Critical
+        );
 
-        harness.advance_slot();
+        // The delay is logged at the level it has escalated to, and backfill is paused once.
+        assert_eq!(
+            logged(Level::Warning, "Finality is delayed"),
+            (delay.level == FinalityDelayLevel::Warning) as usize
+        );
+        assert_eq!(
+            logged(Level::Critical, "Chain is not finalizing"),
+            (delay.level == FinalityDelayLevel::Critical) as usize
+        );
+        assert_eq!(
+            logged(
+                Level::Warning,
+                "Paused backfill sync whilst finality is delayed"
+            ),
+            (epoch == 5) as usize
+        );
     }
+    assert_eq!(
+        levels,
+        vec![
+            FinalityDelayLevel::Healthy,
+            FinalityDelayLevel::Healthy,
+            FinalityDelayLevel::Warning,
+            FinalityDelayLevel::Warning,
+            FinalityDelayLevel::Critical,
+            FinalityDelayLevel::Critical,
+        ]
+    );
 
-    for (attestation, subnet_id) in attestations.into_iter().flatten() {
-        let res = harness
-            .chain
-            .verify_unaggregated_attestation_for_gossip(&attestation, Some(subnet_id));
-
-        let current_slot = harness.chain.slot().expect("should get slot");
-        let expected_attestation_slot = attestation.data.slot;
-        let expected_earliest_permissible_slot =
-            current_slot - MinimalEthSpec::slots_per_epoch() - 1;
+    // Other tests may set the gauge too, so set it again immediately before reading it.
+    harness
+        .chain
+        .check_finality_delay(harness.get_current_slot(), false);
+    assert_eq!(gauge_value("beacon_finality_delay_epochs"), Some(6));
 
-        if expected_attestation_slot < expected_earliest_permissible_slot {
-            assert!(matches!(
-                res.err().unwrap(),
-                AttnError::PastSlot {
-                    attestation_slot,
-                    earliest_permissible_slot,
-                }
-                if attestation_slot == expected_attestation_slot && earliest_permissible_slot == expected_earliest_permissible_slot
-            ))
-        } else {
-            res.expect("should process attestation");
-        }
+    // Once the chain finalizes again, backfill resumes.
+    harness
+        .extend_chain(
+            slots_per_epoch * 4,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::AllValidators,
+        )
+        .await;
+    logs.clear();
+    harness.chain.per_slot_task().await;
+    let delay = harness.chain.finality_delay().unwrap();
+    assert_eq!(delay.level, FinalityDelayLevel::Healthy);
+    assert!(delay.finalized_epoch > 0);
+    assert!(!harness.chain.backfill_rate_limiter.is_paused());
+    assert_eq!(logged(Level::Info, "Finality restored"), 1);
+    assert_eq!(logged(Level::Info, "Resumed backfill sync"), 1);
+    levels.push(delay.level);
+
+    let mut event_levels = vec![];
+    while let Ok(EventKind::FinalityDelay(delay)) = events.try_recv() {
+        event_levels.push(delay.level);
     }
+    assert_eq!(event_levels, levels);
 }
 
 #[tokio::test]
-async fn unaggregated_attestations_added_to_fork_choice_all_updated() {
-    let num_blocks_produced = MinimalEthSpec::slots_per_epoch() * 2 - 1;
-
+async fn pre_finalization_cache_is_pruned_after_finalization() {
     let harness = get_harness(VALIDATOR_COUNT);
+    let slots_per_epoch = MinimalEthSpec::slots_per_epoch();
+    let finalized_slot = |harness: &BeaconChainHarness<EphemeralHarnessType<MinimalEthSpec>>| {
+        harness
+            .finalized_checkpoint()
+            .epoch
+            .start_slot(slots_per_epoch)
+    };
 
     harness
         .extend_chain(
-            num_blocks_produced as usize,
+            slots_per_epoch as usize * 4,
             BlockStrategy::OnCanonicalHead,
             AttestationStrategy::AllValidators,
         )
         .await;
+    harness.chain.per_slot_task().await;
+    let first_finalized_slot = finalized_slot(&harness);
+    assert!(first_finalized_slot > 1);
 
-    let head = harness.chain.head_snapshot();
-    let state = &head.beacon_state;
-    let mut fork_choice = harness.chain.canonical_head.fork_choice_write_lock();
-
-    // Move forward a slot so all queued attestations can be processed.
-    harness.advance_slot();
-    fork_choice
-        .update_time(harness.chain.slot().unwrap())
-        .unwrap();
-
-    let validators: Vec<usize> = (0..VALIDATOR_COUNT).collect();
-    let slots: Vec<Slot> = validators
-        .iter()
-        .map(|&v| {
-            state
-                .get_attestation_duties(v, RelativeEpoch::Current)
-                .expect("should get attester duties")
+    // Fill the cache with the blocks from before finalization, and look them all up twice.
+    let pre_finalization_roots = (1..first_finalized_slot.as_u64())
+        .map(|slot| {
+            harness
+                .chain
+                .block_root_at_slot(Slot::new(slot), WhenSlotSkipped::None)
+                .unwrap()
                 .unwrap()
-                .slot
         })
-        .collect();
-    let validator_slots: Vec<(&usize, Slot)> = validators.iter().zip(slots).collect();
+        .collect::<Vec<_>>();
+    for _ in 0..2 {
+        for block_root in &pre_finalization_roots {
+            assert!(harness
+                .chain
+                .is_pre_finalization_block(*block_root)
+                .unwrap());
+        }
+    }
 
-    for (validator, slot) in validator_slots {
-        let latest_message = fork_choice.latest_message(*validator);
+    // A block which is looked up from the network but never imported.
+    let unknown_root = Hash256::repeat_byte(42);
+    let lookup_slot = harness.get_current_slot();
+    assert!(!harness
+        .chain
+        .is_pre_finalization_block(unknown_root)
+        .unwrap());
 
-        assert_eq!(
-            latest_message.unwrap().1,
-            slot.epoch(MinimalEthSpec::slots_per_epoch()),
-            "Latest message slot should be equal to attester duty."
-        );
+    let num_roots = pre_finalization_roots.len();
+    assert_eq!(
+        harness.chain.pre_finalization_cache_stats(),
+        PreFinalizationCacheStats {
+            hits: num_roots as u64,
+            misses: num_roots as u64 + 1,
+            inserts: num_roots as u64,
+            pruned: 0,
+            block_roots: num_roots,
+            in_progress_lookups: 1,
+        }
+    );
 
-        if slot != num_blocks_produced {
-            let block_root = state
-                .get_block_root(slot)
-                .expect("Should get block root at slot");
+    // Nothing is pruned until finalization advances.
+    harness.chain.per_slot_task().await;
+    assert_eq!(harness.chain.pre_finalization_cache_stats().pruned, 0);
 
-            assert_eq!(
-                latest_message.unwrap().0,
-                *block_root,
-                "Latest message block root should be equal to block at slot."
-            );
-        }
-    }
-}
+    harness
+        .extend_chain(
+            slots_per_epoch as usize * 3,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::AllValidators,
+        )
+        .await;
+    harness.chain.per_slot_task().await;
+    assert!(finalized_slot(&harness) > lookup_slot);
 
-async fn run_skip_slot_test(skip_slots: u64) {
-    let num_validators = 8;
-    let harness_a = get_harness(num_validators);
-    let harness_b = get_harness(num_validators);
+    let stats = harness.chain.pre_finalization_cache_stats();
+    assert_eq!(stats.pruned, num_roots as u64 + 1);
+    assert_eq!(stats.block_roots, 0);
+    assert_eq!(stats.in_progress_lookups, 0);
+}
 
-    for _ in 0..skip_slots {
-        harness_a.advance_slot();
-        harness_b.advance_slot();
-    }
+#[tokio::test]
+async fn clock_jumps_are_detected_and_pools_pruned() {
+    let harness = get_harness(VALIDATOR_COUNT);
 
-    harness_a
+    harness
         .extend_chain(
-            1,
+            MinimalEthSpec::slots_per_epoch() as usize,
             BlockStrategy::OnCanonicalHead,
-            // No attestation required for test.
-            AttestationStrategy::SomeValidators(vec![]),
+            AttestationStrategy::AllValidators,
         )
         .await;
+    harness.chain.per_slot_task().await;
+    let slot = harness.get_current_slot();
+    assert_eq!(
+        harness.chain.clock_status(),
+        ClockStatus {
+            last_observed_slot: Some(slot),
+            last_jump: None,
+            recovering: false,
+        }
+    );
+    assert_eq!(
+        harness.chain.gossip_clock_disparity(),
+        MAXIMUM_GOSSIP_CLOCK_DISPARITY
+    );
 
+    // Add an attestation for the current slot to the naive aggregation pool.
+    let head = harness.chain.head_snapshot();
+    let (attestation, _) = harness
+        .get_unaggregated_attestations(
+            &AttestationStrategy::AllValidators,
+            &head.beacon_state,
+            head.beacon_state_root(),
+            head.beacon_block_root,
+            slot,
+        )
+        .into_iter()
+        .flatten()
+        .next()
+        .unwrap();
+    harness
+        .chain
+        .naive_aggregation_pool
+        .write()
+        .insert(&attestation)
+        .unwrap();
+
+    // After the clock moves backwards, the attestation is from the future and is removed.
+    let earlier_slot = slot - 3;
+    harness.chain.slot_clock.set_slot(earlier_slot.as_u64());
+    harness.chain.per_slot_task().await;
     assert_eq!(
-        harness_a.chain.head_snapshot().beacon_block.slot(),
-        Slot::new(skip_slots + 1)
+        harness.chain.clock_status(),
+        ClockStatus {
+            last_observed_slot: Some(earlier_slot),
+            last_jump: Some(ClockJump::Backwards {
+                from: slot,
+                to: earlier_slot,
+            }),
+            recovering: true,
+        }
     );
     assert_eq!(
-        harness_b.chain.head_snapshot().beacon_block.slot(),
-        Slot::new(0)
+        harness.chain.gossip_clock_disparity(),
+        CLOCK_JUMP_GOSSIP_CLOCK_DISPARITY
     );
+    assert_eq!(harness.chain.naive_aggregation_pool.read().num_items(), 0);
 
+    let later_slot = slot + 10;
+    harness.chain.slot_clock.set_slot(later_slot.as_u64());
+    harness.chain.per_slot_task().await;
     assert_eq!(
-        harness_b
-            .chain
-            .process_block(harness_a.chain.head_snapshot().beacon_block.clone())
-            .await
-            .unwrap(),
-        harness_a.chain.head_snapshot().beacon_block_root
+        harness.chain.clock_status().last_jump,
+        Some(ClockJump::Forwards {
+            from: earlier_slot,
+            to: later_slot,
+        })
     );
 
-    harness_b
-        .chain
-        .recompute_head_at_current_slot()
-        .await
-        .expect("should run fork choice");
-
+    // The tolerance returns to normal once the clock has been steady for a while.
+    for _ in 0..CLOCK_JUMP_RECOVERY_SLOTS {
+        harness.advance_slot();
+        harness.chain.per_slot_task().await;
+    }
+    assert!(!harness.chain.clock_status().recovering);
     assert_eq!(
-        harness_b.chain.head_snapshot().beacon_block.slot(),
-        Slot::new(skip_slots + 1)
+        harness.chain.gossip_clock_disparity(),
+        MAXIMUM_GOSSIP_CLOCK_DISPARITY
     );
 }
 
 #[tokio::test]
-async fn produces_and_processes_with_genesis_skip_slots() {
-    for i in 0..MinimalEthSpec::slots_per_epoch() * 4 {
-        run_skip_slot_test(i).await
+async fn produces_block_within_max_network_size() {
+    let max_network_size = 4_000;
+    let harness = BeaconChainHarness::builder(MinimalEthSpec)
+        .default_spec()
+        .chain_config(ChainConfig {
+            max_network_size,
+            ..ChainConfig::default()
+        })
+        .keypairs(KEYPAIRS[0..VALIDATOR_COUNT].to_vec())
+        .fresh_ephemeral_store()
+        .mock_execution_layer()
+        .build();
+    harness.advance_slot();
+
+    harness
+        .extend_chain(
+            2,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::AllValidators,
+        )
+        .await;
+
+    // Fill the op pool with more proposer slashings than fit within the maximum network size.
+    let max_proposer_slashings = <MinimalEthSpec as EthSpec>::MaxProposerSlashings::to_u64();
+    for validator_index in 0..max_proposer_slashings {
+        match harness
+            .chain
+            .verify_proposer_slashing_for_gossip(harness.make_proposer_slashing(validator_index))
+            .unwrap()
+        {
+            ObservationOutcome::New(slashing) => harness.chain.import_proposer_slashing(slashing),
+            ObservationOutcome::AlreadyKnown => panic!("proposer slashing should be new"),
+        }
     }
+    let all_slashings_size =
+        max_proposer_slashings as usize * harness.make_proposer_slashing(0).ssz_bytes_len();
+    assert!(all_slashings_size > max_network_size);
+
+    harness.advance_slot();
+    let slot = harness.get_current_slot();
+    let (block, _) = harness.make_block(harness.get_current_state(), slot).await;
+    assert!(block.ssz_bytes_len() <= max_network_size);
+    let num_slashings = block.message().body().proposer_slashings().len() as u64;
+    assert!(num_slashings > 0);
+    assert!(num_slashings < max_proposer_slashings);
+
+    // The block is valid.
+    harness.process_block(slot, block).await.unwrap();
 }
 
 #[tokio::test]
-async fn block_roots_skip_slot_behaviour() {
+async fn block_production_rejects_randao_reveal_for_wrong_epoch() {
     let harness = get_harness(VALIDATOR_COUNT);
+    let spec = harness.chain.spec.clone();
+    let slots_per_epoch = MinimalEthSpec::slots_per_epoch();
 
-    // Test should be longer than the block roots to ensure a DB lookup is triggered.
-    let chain_length = harness
-        .chain
-        .head_snapshot()
-        .beacon_state
-        .block_roots()
-        .len() as u64
-        * 3;
-
-    let skipped_slots = [1, 6, 7, 10, chain_length];
-
-    // Build a chain with some skip slots.
-    for i in 1..=chain_length {
-        if i > 1 {
-            harness.advance_slot();
-        }
+    harness
+        .extend_chain(
+            slots_per_epoch as usize + 1,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::AllValidators,
+        )
+        .await;
+    let head_slot = harness.head_slot();
+
+    // Produce a block in the epoch of the head, and one in the next epoch.
+    let next_epoch_start_slot = (head_slot.epoch(slots_per_epoch) + 1).start_slot(slots_per_epoch);
+    for slot in [head_slot + 1, next_epoch_start_slot] {
+        harness.set_current_slot(slot);
+        let mut state = harness.get_current_state();
+        complete_state_advance(&mut state, None, slot, &spec).unwrap();
+        state.build_all_caches(&spec).unwrap();
+        let proposer_index = state.get_beacon_proposer_index(slot, &spec).unwrap();
+
+        let wrong_epoch_reveal =
+            harness.sign_randao_reveal(&state, proposer_index, slot - slots_per_epoch);
+        let result = harness
+            .chain
+            .produce_block_with_verification::<FullPayload<MinimalEthSpec>>(
+                wrong_epoch_reveal.clone(),
+                slot,
+                None,
+                ProduceBlockVerification::VerifyRandao,
+            )
+            .await;
+        assert!(
+            matches!(
+                result,
+                Err(BlockProductionError::InvalidRandaoReveal { expected_epoch })
+                    if expected_epoch == slot.epoch(slots_per_epoch)
+            ),
+            "{:?}",
+            result.map(|_| ())
+        );
 
-        let slot = harness.chain.slot().unwrap().as_u64();
+        let reveal = harness.sign_randao_reveal(&state, proposer_index, slot);
+        harness
+            .chain
+            .produce_block_with_verification::<FullPayload<MinimalEthSpec>>(
+                reveal,
+                slot,
+                None,
+                ProduceBlockVerification::VerifyRandao,
+            )
+            .await
+            .unwrap();
 
-        if !skipped_slots.contains(&slot) {
-            harness
-                .extend_chain(
-                    1,
-                    BlockStrategy::OnCanonicalHead,
-                    AttestationStrategy::AllValidators,
-                )
-                .await;
-        }
+        // The reveal isn't checked without verification.
+        harness
+            .chain
+            .produce_block_with_verification::<FullPayload<MinimalEthSpec>>(
+                wrong_epoch_reveal,
+                slot,
+                None,
+                ProduceBlockVerification::NoVerification,
+            )
+            .await
+            .unwrap();
     }
+}
 
-    let mut prev_unskipped_root = None;
-
-    for target_slot in 0..=chain_length {
-        if skipped_slots.contains(&target_slot) {
-            /*
-             * A skip slot
-             */
-            assert!(
-                harness
-                    .chain
-                    .block_root_at_slot(target_slot.into(), WhenSlotSkipped::None)
-                    .unwrap()
-                    .is_none(),
-                "WhenSlotSkipped::None should return None on a skip slot"
-            );
-
-            let skipped_root = harness
-                .chain
-                .block_root_at_slot(target_slot.into(), WhenSlotSkipped::Prev)
-                .unwrap()
-                .expect("WhenSlotSkipped::Prev should always return Some");
+#[tokio::test]
+async fn randao_mixes_snapshot_matches_head_state() {
+    let harness = get_harness(VALIDATOR_COUNT);
+    let slots_per_epoch = MinimalEthSpec::slots_per_epoch();
+    harness
+        .extend_chain(
+            (3 * slots_per_epoch) as usize,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::AllValidators,
+        )
+        .await;
 
-            assert_eq!(
-                skipped_root,
-                prev_unskipped_root.expect("test is badly formed"),
-                "WhenSlotSkipped::Prev should accurately return the prior skipped block"
-            );
+    let chain = &harness.chain;
+    let snapshot = chain.randao_mixes_snapshot().unwrap();
+    let head = chain.head_snapshot();
+    let state = &head.beacon_state;
+    let epoch = state.current_epoch();
+    let spec = &chain.spec;
 
-            let expected_block = harness
-                .chain
-                .get_blinded_block(&skipped_root)
-                .unwrap()
-                .unwrap();
+    assert_eq!(snapshot.epoch, epoch);
+    assert_eq!(
+        snapshot.mixes.len() as u64,
+        std::cmp::min(epoch.as_u64() + 1, RANDAO_MIXES_SNAPSHOT_EPOCHS)
+    );
+    assert_eq!(snapshot.mixes.last().unwrap().epoch, epoch);
+    for mix in &snapshot.mixes {
+        assert_eq!(mix.mix, *state.get_randao_mix(mix.epoch).unwrap());
+    }
+    assert_eq!(
+        snapshot.current_proposer_seed,
+        state.get_seed(epoch, Domain::BeaconProposer, spec).unwrap()
+    );
+    assert_eq!(
+        snapshot.next_proposer_seed,
+        state
+            .get_seed(epoch + 1, Domain::BeaconProposer, spec)
+            .unwrap()
+    );
+    assert_eq!(
+        snapshot.current_attester_seed,
+        state.get_seed(epoch, Domain::BeaconAttester, spec).unwrap()
+    );
+    assert_eq!(
+        snapshot.next_attester_seed,
+        state
+            .get_seed(epoch + 1, Domain::BeaconAttester, spec)
+            .unwrap()
+    );
+    assert_eq!(
+        snapshot.proposer_shuffling_decision_root,
+        state
+            .proposer_shuffling_decision_root(head.beacon_block_root)
+            .unwrap()
+    );
+    assert_eq!(
+        snapshot.next_attester_shuffling_decision_root,
+        state
+            .attester_shuffling_decision_root(head.beacon_block_root, RelativeEpoch::Next)
+            .unwrap()
+    );
 
-            assert_eq!(
-                harness
-                    .chain
-                    .block_at_slot(target_slot.into(), WhenSlotSkipped::Prev)
-                    .unwrap()
-                    .unwrap(),
-                expected_block,
-            );
+    // Once the head moves into the next epoch, its values for the current epoch are those the
+    // previous snapshot had for the next epoch.
+    harness
+        .extend_chain(
+            slots_per_epoch as usize,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::AllValidators,
+        )
+        .await;
+    let next_snapshot = chain.randao_mixes_snapshot().unwrap();
+    assert_eq!(next_snapshot.epoch, epoch + 1);
+    assert_eq!(
+        next_snapshot.current_attester_seed,
+        snapshot.next_attester_seed
+    );
+    assert_eq!(
+        next_snapshot.current_attester_shuffling_decision_root,
+        snapshot.next_attester_shuffling_decision_root
+    );
+    assert_ne!(
+        next_snapshot.next_attester_shuffling_decision_root,
+        snapshot.next_attester_shuffling_decision_root
+    );
+}
 
-            assert!(
-                harness
-                    .chain
-                    .block_at_slot(target_slot.into(), WhenSlotSkipped::None)
-                    .unwrap()
-                    .is_none(),
-                "WhenSlotSkipped::None should return None on a skip slot"
-            );
-        } else {
-            /*
-             * Not a skip slot
-             */
-            let skips_none = harness
-                .chain
-                .block_root_at_slot(target_slot.into(), WhenSlotSkipped::None)
-                .unwrap()
-                .expect("WhenSlotSkipped::None should return Some for non-skipped block");
-            let skips_prev = harness
-                .chain
-                .block_root_at_slot(target_slot.into(), WhenSlotSkipped::Prev)
-                .unwrap()
-                .expect("WhenSlotSkipped::Prev should always return Some");
-            assert_eq!(
-                skips_none, skips_prev,
-                "WhenSlotSkipped::None and WhenSlotSkipped::Prev should be equal on non-skipped slot"
-            );
+#[tokio::test]
+async fn validator_registry_diff_reports_exit() {
+    // Allow validators to exit immediately.
+    let mut spec = test_spec::<MinimalEthSpec>();
+    spec.shard_committee_period = 0;
+    let harness = BeaconChainHarness::builder(MinimalEthSpec)
+        .spec(spec)
+        .keypairs(KEYPAIRS[..].to_vec())
+        .fresh_ephemeral_store()
+        .mock_execution_layer()
+        .build();
+    harness.advance_slot();
+    let slots_per_epoch = MinimalEthSpec::slots_per_epoch();
 
-            let expected_block = harness
-                .chain
-                .get_blinded_block(&skips_prev)
-                .unwrap()
-                .unwrap();
+    let mut events = harness
+        .chain
+        .event_handler
+        .as_ref()
+        .unwrap()
+        .subscribe_validator_registry_updates();
 
-            assert_eq!(
-                harness
-                    .chain
-                    .block_at_slot(target_slot.into(), WhenSlotSkipped::Prev)
-                    .unwrap()
-                    .unwrap(),
-                expected_block
-            );
+    // Exit a validator in a block in the middle of epoch 1.
+    harness
+        .extend_chain(
+            slots_per_epoch as usize + 2,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::AllValidators,
+        )
+        .await;
+    let validator_index = VALIDATOR_COUNT as u64 - 1;
+    let exit = harness.make_voluntary_exit(validator_index, Epoch::new(0));
+    match harness
+        .chain
+        .verify_voluntary_exit_for_gossip(exit)
+        .unwrap()
+    {
+        ObservationOutcome::New(exit) => harness.chain.import_voluntary_exit(exit),
+        ObservationOutcome::AlreadyKnown => panic!("exit should be new"),
+    }
+    harness
+        .extend_chain(
+            slots_per_epoch as usize,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::AllValidators,
+        )
+        .await;
+    assert_eq!(harness.head_slot().epoch(slots_per_epoch), 2);
+    let head = harness.chain.head_snapshot();
+    let validator = head
+        .beacon_state
+        .get_validator(validator_index as usize)
+        .unwrap();
+    assert_ne!(validator.exit_epoch, harness.chain.spec.far_future_epoch);
 
-            assert_eq!(
-                harness
-                    .chain
-                    .block_at_slot(target_slot.into(), WhenSlotSkipped::None)
-                    .unwrap()
-                    .unwrap(),
-                expected_block
-            );
+    let epoch_1_diff = harness
+        .chain
+        .validator_registry_diff(Epoch::new(1))
+        .unwrap();
+    assert!(epoch_1_diff.exits_initiated.is_empty());
 
-            prev_unskipped_root = Some(skips_prev);
-        }
+    let epoch_2_diff = harness
+        .chain
+        .validator_registry_diff(Epoch::new(2))
+        .unwrap();
+    assert_eq!(epoch_2_diff.exits_initiated, vec![validator_index]);
+    assert!(epoch_2_diff.activation_eligible.is_empty());
+    assert!(epoch_2_diff.activations_queued.is_empty());
+    assert!(epoch_2_diff.withdrawable.is_empty());
+    assert!(epoch_2_diff.slashed.is_empty());
+
+    let mut updates = vec![];
+    while let Ok(EventKind::ValidatorRegistryUpdate(update)) = events.try_recv() {
+        updates.push(update);
     }
+    assert_eq!(updates, vec![epoch_1_diff, epoch_2_diff]);
+}
 
-    /*
-     * A future, non-existent slot.
-     */
+#[tokio::test]
+async fn concurrent_block_production_advances_head_state_once() {
+    let harness = get_harness(VALIDATOR_COUNT);
+    harness
+        .extend_chain(
+            MinimalEthSpec::slots_per_epoch() as usize,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::AllValidators,
+        )
+        .await;
+    let head_root = harness.head_block_root();
+    let slot = harness.head_slot() + 1;
+    harness.set_current_slot(slot);
 
+    // The state advance timer isn't running, so the head state is yet to be advanced.
+    let produce = || {
+        harness
+            .chain
+            .produce_block_with_verification::<FullPayload<MinimalEthSpec>>(
+                Signature::empty(),
+                slot,
+                None,
+                ProduceBlockVerification::NoVerification,
+            )
+    };
+    let (first, second) = futures::join!(produce(), produce());
+    first.unwrap();
+    second.unwrap();
+    assert_eq!(harness.chain.state_advance_locks.advances(head_root), 1);
+
+    // Later producers reuse the advanced state too.
+    produce().await.unwrap();
+    assert_eq!(harness.chain.state_advance_locks.advances(head_root), 1);
+
+    // The lock is dropped once the head changes.
+    harness.extend_slots(1).await;
+    assert_eq!(harness.chain.state_advance_locks.advances(head_root), 0);
+}
+
+#[tokio::test]
+async fn block_production_advances_head_state_inline_on_cache_miss() {
+    let harness = get_harness(VALIDATOR_COUNT);
+    harness
+        .extend_chain(
+            MinimalEthSpec::slots_per_epoch() as usize,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::AllValidators,
+        )
+        .await;
+    let head_root = harness.head_block_root();
+    let slot = harness.head_slot() + 2;
+    harness.set_current_slot(slot);
+    harness.evict_head_from_snapshot_cache();
+
+    let fallbacks = || counter_value("beacon_block_production_state_advance_fallbacks_total");
+    let fallbacks_before = fallbacks();
+    let (block, state) = harness
+        .chain
+        .produce_block_with_verification::<FullPayload<MinimalEthSpec>>(
+            Signature::empty(),
+            slot,
+            None,
+            ProduceBlockVerification::NoVerification,
+        )
+        .await
+        .unwrap();
+    assert_eq!(block.slot(), slot);
+    assert_eq!(block.parent_root(), head_root);
+    assert_eq!(state.slot(), slot);
+    assert!(fallbacks() > fallbacks_before);
+}
+
+#[tokio::test]
+async fn block_production_reports_attestation_packing() {
+    let harness = get_harness(VALIDATOR_COUNT);
+    harness
+        .extend_chain(
+            MinimalEthSpec::slots_per_epoch() as usize * 2,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::AllValidators,
+        )
+        .await;
+
+    // Only the attestations to the head block are yet to be included in a block, one aggregate for
+    // each committee at the slot of the head.
+    let head = harness.chain.head_snapshot();
+    let mut state = head.beacon_state.clone();
+    state
+        .build_committee_cache(RelativeEpoch::Current, &harness.chain.spec)
+        .unwrap();
+    let committees = state
+        .get_beacon_committees_at_slot(head.beacon_block.slot())
+        .unwrap();
+    let num_attesters = committees
+        .iter()
+        .map(|committee| committee.committee.len())
+        .sum::<usize>();
+
+    harness.advance_slot();
+    let slot = harness.chain.slot().unwrap();
+    let (block, _) = harness.make_block(state, slot).await;
+    assert_eq!(
+        block.message().body().attestations().len(),
+        committees.len()
+    );
+
+    let info = harness.chain.last_block_production_info().unwrap();
+    assert_eq!(info.slot, slot);
+    let packing = info.attestation_packing;
+    assert_eq!(packing.distinct_attestation_data, committees.len());
+    assert_eq!(packing.new_attesting_indices, num_attesters);
+    assert_eq!(packing.incompatible_shuffling, 0);
+    assert!(packing.packed_reward > 0);
+    assert_eq!(packing.reward_left_on_table, 0);
+}
+
+#[tokio::test]
+async fn roundtrip_operation_pool() {
+    let num_blocks_produced = MinimalEthSpec::slots_per_epoch() * 5;
+
+    let harness = get_harness(VALIDATOR_COUNT);
+
+    // Add some attestations
+    harness
+        .extend_chain(
+            num_blocks_produced as usize,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::AllValidators,
+        )
+        .await;
+    assert!(harness.chain.op_pool.num_attestations() > 0);
+
+    // TODO: could add some other operations
+    harness
+        .chain
+        .persist_op_pool()
+        .expect("should persist op pool");
+
+    let restored_op_pool = harness
+        .chain
+        .store
+        .get_item::<PersistedOperationPool<MinimalEthSpec>>(&OP_POOL_DB_KEY)
+        .expect("should read db")
+        .expect("should find op pool")
+        .into_operation_pool()
+        .unwrap();
+
+    assert_eq!(harness.chain.op_pool, restored_op_pool);
+}
+
+#[tokio::test]
+async fn unaggregated_attestations_added_to_fork_choice_some_none() {
+    let num_blocks_produced = MinimalEthSpec::slots_per_epoch() / 2;
+
+    let harness = get_harness(VALIDATOR_COUNT);
+
+    harness
+        .extend_chain(
+            num_blocks_produced as usize,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::AllValidators,
+        )
+        .await;
+
+    let head = harness.chain.head_snapshot();
+    let state = &head.beacon_state;
+    let mut fork_choice = harness.chain.canonical_head.fork_choice_write_lock();
+
+    // Move forward a slot so all queued attestations can be processed.
+    harness.advance_slot();
+    fork_choice
+        .update_time(harness.chain.slot().unwrap())
+        .unwrap();
+
+    let validator_slots: Vec<(usize, Slot)> = (0..VALIDATOR_COUNT)
+        .into_iter()
+        .map(|validator_index| {
+            let slot = state
+                .get_attestation_duties(validator_index, RelativeEpoch::Current)
+                .expect("should get attester duties")
+                .unwrap()
+                .slot;
+
+            (validator_index, slot)
+        })
+        .collect();
+
+    for (validator, slot) in validator_slots.clone() {
+        let latest_message = fork_choice.latest_message(validator);
+
+        if slot <= num_blocks_produced && slot != 0 {
+            assert_eq!(
+                latest_message.unwrap().1,
+                slot.epoch(MinimalEthSpec::slots_per_epoch()),
+                "Latest message epoch for {} should be equal to epoch {}.",
+                validator,
+                slot
+            )
+        } else {
+            assert!(
+                latest_message.is_none(),
+                "Latest message slot should be None."
+            )
+        }
+    }
+}
+
+#[tokio::test]
+async fn attestations_with_increasing_slots() {
+    let num_blocks_produced = MinimalEthSpec::slots_per_epoch() * 5;
+
+    let harness = get_harness(VALIDATOR_COUNT);
+
+    let mut attestations = vec![];
+
+    for _ in 0..num_blocks_produced {
+        harness
+            .extend_chain(
+                2,
+                BlockStrategy::OnCanonicalHead,
+                // Don't produce & include any attestations (we'll collect them later).
+                AttestationStrategy::SomeValidators(vec![]),
+            )
+            .await;
+
+        let head = harness.chain.head_snapshot();
+        let head_state_root = head.beacon_state_root();
+
+        attestations.extend(harness.get_unaggregated_attestations(
+            &AttestationStrategy::AllValidators,
+            &head.beacon_state,
+            head_state_root,
+            head.beacon_block_root,
+            head.beacon_block.slot(),
+        ));
+
+        harness.advance_slot();
+    }
+
+    for (attestation, subnet_id) in attestations.into_iter().flatten() {
+        let res = harness
+            .chain
+            .verify_unaggregated_attestation_for_gossip(&attestation, Some(subnet_id));
+
+        let current_slot = harness.chain.slot().expect("should get slot");
+        let expected_attestation_slot = attestation.data.slot;
+        let expected_earliest_permissible_slot =
+            current_slot - MinimalEthSpec::slots_per_epoch() - 1;
+
+        if expected_attestation_slot < expected_earliest_permissible_slot {
+            assert!(matches!(
+                res.err().unwrap(),
+                AttnError::PastSlot {
+                    attestation_slot,
+                    earliest_permissible_slot,
+                }
+                if attestation_slot == expected_attestation_slot && earliest_permissible_slot == expected_earliest_permissible_slot
+            ))
+        } else {
+            res.expect("should process attestation");
+        }
+    }
+}
+
+#[tokio::test]
+async fn unaggregated_attestations_added_to_fork_choice_all_updated() {
+    let num_blocks_produced = MinimalEthSpec::slots_per_epoch() * 2 - 1;
+
+    let harness = get_harness(VALIDATOR_COUNT);
+
+    harness
+        .extend_chain(
+            num_blocks_produced as usize,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::AllValidators,
+        )
+        .await;
+
+    let head = harness.chain.head_snapshot();
+    let state = &head.beacon_state;
+    let mut fork_choice = harness.chain.canonical_head.fork_choice_write_lock();
+
+    // Move forward a slot so all queued attestations can be processed.
+    harness.advance_slot();
+    fork_choice
+        .update_time(harness.chain.slot().unwrap())
+        .unwrap();
+
+    let validators: Vec<usize> = (0..VALIDATOR_COUNT).collect();
+    let slots: Vec<Slot> = validators
+        .iter()
+        .map(|&v| {
+            state
+                .get_attestation_duties(v, RelativeEpoch::Current)
+                .expect("should get attester duties")
+                .unwrap()
+                .slot
+        })
+        .collect();
+    let validator_slots: Vec<(&usize, Slot)> = validators.iter().zip(slots).collect();
+
+    for (validator, slot) in validator_slots {
+        let latest_message = fork_choice.latest_message(*validator);
+
+        assert_eq!(
+            latest_message.unwrap().1,
+            slot.epoch(MinimalEthSpec::slots_per_epoch()),
+            "Latest message slot should be equal to attester duty."
+        );
+
+        if slot != num_blocks_produced {
+            let block_root = state
+                .get_block_root(slot)
+                .expect("Should get block root at slot");
+
+            assert_eq!(
+                latest_message.unwrap().0,
+                *block_root,
+                "Latest message block root should be equal to block at slot."
+            );
+        }
+    }
+}
+
+async fn run_skip_slot_test(skip_slots: u64) {
+    let num_validators = 8;
+    let harness_a = get_harness(num_validators);
+    let harness_b = get_harness(num_validators);
+
+    for _ in 0..skip_slots {
+        harness_a.advance_slot();
+        harness_b.advance_slot();
+    }
+
+    harness_a
+        .extend_chain(
+            1,
+            BlockStrategy::OnCanonicalHead,
+            // No attestation required for test.
+            AttestationStrategy::SomeValidators(vec![]),
+        )
+        .await;
+
+    assert_eq!(
+        harness_a.chain.head_snapshot().beacon_block.slot(),
+        Slot::new(skip_slots + 1)
+    );
+    assert_eq!(
+        harness_b.chain.head_snapshot().beacon_block.slot(),
+        Slot::new(0)
+    );
+
+    assert_eq!(
+        harness_b
+            .chain
+            .process_block(harness_a.chain.head_snapshot().beacon_block.clone())
+            .await
+            .unwrap(),
+        harness_a.chain.head_snapshot().beacon_block_root
+    );
+
+    harness_b
+        .chain
+        .recompute_head_at_current_slot()
+        .await
+        .expect("should run fork choice");
+
+    assert_eq!(
+        harness_b.chain.head_snapshot().beacon_block.slot(),
+        Slot::new(skip_slots + 1)
+    );
+}
+
+#[tokio::test]
+async fn produces_and_processes_with_genesis_skip_slots() {
+    for i in 0..MinimalEthSpec::slots_per_epoch() * 4 {
+        run_skip_slot_test(i).await
+    }
+}
+
+#[tokio::test]
+async fn block_roots_skip_slot_behaviour() {
+    let harness = get_harness(VALIDATOR_COUNT);
+
+    // Test should be longer than the block roots to ensure a DB lookup is triggered.
+    let chain_length = harness
+        .chain
+        .head_snapshot()
+        .beacon_state
+        .block_roots()
+        .len() as u64
+        * 3;
+
+    let skipped_slots = [1, 6, 7, 10, chain_length];
+
+    // Build a chain with some skip slots.
+    for i in 1..=chain_length {
+        if i > 1 {
+            harness.advance_slot();
+        }
+
+        let slot = harness.chain.slot().unwrap().as_u64();
+
+        if !skipped_slots.contains(&slot) {
+            harness
+                .extend_chain(
+                    1,
+                    BlockStrategy::OnCanonicalHead,
+                    AttestationStrategy::AllValidators,
+                )
+                .await;
+        }
+    }
+
+    let mut prev_unskipped_root = None;
+
+    for target_slot in 0..=chain_length {
+        if skipped_slots.contains(&target_slot) {
+            /*
+             * A skip slot
+             */
+            assert!(
+                harness
+                    .chain
+                    .block_root_at_slot(target_slot.into(), WhenSlotSkipped::None)
+                    .unwrap()
+                    .is_none(),
+                "WhenSlotSkipped::None should return None on a skip slot"
+            );
+
+            let skipped_root = harness
+                .chain
+                .block_root_at_slot(target_slot.into(), WhenSlotSkipped::Prev)
+                .unwrap()
+                .expect("WhenSlotSkipped::Prev should always return Some");
+
+            assert_eq!(
+                skipped_root,
+                prev_unskipped_root.expect("test is badly formed"),
+                "WhenSlotSkipped::Prev should accurately return the prior skipped block"
+            );
+
+            let expected_block = harness
+                .chain
+                .get_blinded_block(&skipped_root)
+                .unwrap()
+                .unwrap();
+
+            assert_eq!(
+                harness
+                    .chain
+                    .block_at_slot(target_slot.into(), WhenSlotSkipped::Prev)
+                    .unwrap()
+                    .unwrap(),
+                expected_block,
+            );
+
+            assert!(
+                harness
+                    .chain
+                    .block_at_slot(target_slot.into(), WhenSlotSkipped::None)
+                    .unwrap()
+                    .is_none(),
+                "WhenSlotSkipped::None should return None on a skip slot"
+            );
+        } else {
+            /*
+             * Not a skip slot
+             */
+            let skips_none = harness
+                .chain
+                .block_root_at_slot(target_slot.into(), WhenSlotSkipped::None)
+                .unwrap()
+                .expect("WhenSlotSkipped::None should return Some for non-skipped block");
+            let skips_prev = harness
+                .chain
+                .block_root_at_slot(target_slot.into(), WhenSlotSkipped::Prev)
+                .unwrap()
+                .expect("WhenSlotSkipped::Prev should always return Some");
+            assert_eq!(
+                skips_none, skips_prev,
+                "WhenSlotSkipped::None and WhenSlotSkipped::Prev should be equal on non-skipped slot"
+            );
+
+            let expected_block = harness
+                .chain
+                .get_blinded_block(&skips_prev)
+                .unwrap()
+                .unwrap();
+
+            assert_eq!(
+                harness
+                    .chain
+                    .block_at_slot(target_slot.into(), WhenSlotSkipped::Prev)
+                    .unwrap()
+                    .unwrap(),
+                expected_block
+            );
+
+            assert_eq!(
+                harness
+                    .chain
+                    .block_at_slot(target_slot.into(), WhenSlotSkipped::None)
+                    .unwrap()
+                    .unwrap(),
+                expected_block
+            );
+
+            prev_unskipped_root = Some(skips_prev);
+        }
+    }
+
+    /*
+     * A future, non-existent slot.
+     */
+
+    let future_slot = harness.chain.slot().unwrap() + 1;
+    assert_eq!(
+        harness.chain.head_snapshot().beacon_block.slot(),
+        future_slot - 2,
+        "test precondition"
+    );
+    assert!(
+        harness
+            .chain
+            .block_root_at_slot(future_slot, WhenSlotSkipped::None)
+            .unwrap()
+            .is_none(),
+        "WhenSlotSkipped::None should return None on a future slot"
+    );
+    assert!(
+        harness
+            .chain
+            .block_root_at_slot(future_slot, WhenSlotSkipped::Prev)
+            .unwrap()
+            .is_none(),
+        "WhenSlotSkipped::Prev should return None on a future slot"
+    );
+}
+
+#[tokio::test]
+async fn fork_choice_balances_summary() {
+    let harness = get_harness(VALIDATOR_COUNT);
+
+    harness
+        .extend_chain(
+            MinimalEthSpec::slots_per_epoch() as usize * 5,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::AllValidators,
+        )
+        .await;
+
+    let summary = harness.chain.fork_choice_balances_summary();
+    let justified_checkpoint = summary.justified_checkpoint;
+    assert!(justified_checkpoint.epoch > 0, "test precondition");
+
+    let justified_block = harness
+        .chain
+        .get_blinded_block(&justified_checkpoint.root)
+        .unwrap()
+        .unwrap();
+    let justified_state = harness
+        .chain
+        .get_state(&justified_block.state_root(), Some(justified_block.slot()))
+        .unwrap()
+        .unwrap();
+    let active_validators = justified_state
+        .validators()
+        .iter()
+        .filter(|validator| validator.is_active_at(justified_state.current_epoch()))
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        summary.total_active_balance,
+        active_validators
+            .iter()
+            .map(|validator| validator.effective_balance)
+            .sum::<u64>()
+    );
+    assert_eq!(summary.non_zero_validator_count, active_validators.len());
+    assert_eq!(summary.validator_count, VALIDATOR_COUNT);
+
+    // The dumped balances should match the summary.
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("balances.json");
+    assert_eq!(
+        harness.chain.dump_fork_choice_balances(&path).unwrap(),
+        summary
+    );
+    let balances: Vec<u64> =
+        serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(balances.iter().sum::<u64>(), summary.total_active_balance);
+    assert_eq!(
+        beacon_chain::fork_choice_balances::balances_checksum(&balances),
+        summary.checksum
+    );
+}
+
+#[tokio::test]
+async fn block_root_at_slot_with_skip_info() {
+    let harness = get_harness(VALIDATOR_COUNT);
+
+    // Test should be longer than the block roots to ensure a DB lookup is triggered.
+    let chain_length = harness
+        .chain
+        .head_snapshot()
+        .beacon_state
+        .block_roots()
+        .len() as u64
+        * 3;
+    // A run of skipped slots, followed by skipped slots after the head.
+    let skipped_run = 20..30;
+    let head_slot = chain_length - 3;
+
+    let mut expected = vec![(harness.chain.genesis_block_root, false)];
+    for slot in 1..=chain_length {
+        if slot > 1 {
+            harness.advance_slot();
+        }
+        assert_eq!(harness.chain.slot().unwrap(), Slot::new(slot));
+
+        if skipped_run.contains(&slot) || slot > head_slot {
+            let prev_root = expected.last().unwrap().0;
+            expected.push((prev_root, true));
+        } else {
+            let block_root = harness
+                .extend_chain(
+                    1,
+                    BlockStrategy::OnCanonicalHead,
+                    AttestationStrategy::AllValidators,
+                )
+                .await;
+            expected.push((block_root, false));
+        }
+    }
+
+    // The head is older than the latest requested slots.
+    assert_eq!(harness.chain.head_snapshot().beacon_block.slot(), head_slot);
+
+    for (slot, expected) in expected.into_iter().enumerate() {
+        let slot = Slot::new(slot as u64);
+        assert_eq!(
+            harness
+                .chain
+                .block_root_at_slot_with_skip_info(slot)
+                .unwrap(),
+            Some(expected),
+            "slot {}",
+            slot
+        );
+        assert_eq!(
+            harness
+                .chain
+                .block_root_at_slot(slot, WhenSlotSkipped::Prev)
+                .unwrap(),
+            Some(expected.0)
+        );
+        assert_eq!(
+            harness
+                .chain
+                .block_root_at_slot(slot, WhenSlotSkipped::None)
+                .unwrap(),
+            (!expected.1).then(|| expected.0)
+        );
+    }
+
+    // A future slot.
     let future_slot = harness.chain.slot().unwrap() + 1;
     assert_eq!(
-        harness.chain.head_snapshot().beacon_block.slot(),
-        future_slot - 2,
-        "test precondition"
+        harness
+            .chain
+            .block_root_at_slot_with_skip_info(future_slot)
+            .unwrap(),
+        None
+    );
+}
+
+#[tokio::test]
+async fn self_test_reports_failed_checks() {
+    let harness = get_harness(VALIDATOR_COUNT);
+    let chain = &harness.chain;
+    assert_eq!(chain.self_test_report(), None);
+
+    let data_dir = tempfile::tempdir().unwrap();
+    let passing_disk = disk_write_check(data_dir.path().to_path_buf());
+    let failing_disk = disk_write_check(data_dir.path().join("missing"));
+    let hanging = SelfTestCheck::new("hanging", SelfTestSeverity::Degraded, async {
+        futures::future::pending::<()>().await;
+        SelfTestStatus::Passed
+    })
+    .timeout(Duration::from_millis(10));
+
+    let report = chain
+        .run_self_test(vec![passing_disk, failing_disk, hanging])
+        .await;
+    let statuses = report
+        .checks
+        .iter()
+        .map(|check| (check.name.as_str(), check.severity, &check.status))
+        .collect::<Vec<_>>();
+
+    assert_eq!(statuses[0].0, "execution_engine");
+    assert_eq!(statuses[0].2, &SelfTestStatus::Passed);
+    assert_eq!(statuses[1].0, "builder");
+    assert!(matches!(statuses[1].2, SelfTestStatus::Skipped(_)));
+    assert_eq!(statuses[2].0, "slot_clock");
+    assert_eq!(statuses[2].2, &SelfTestStatus::Passed);
+    assert_eq!(statuses[3].0, "disk_write");
+    assert_eq!(statuses[3].2, &SelfTestStatus::Passed);
+    assert_eq!(statuses[4].0, "disk_write");
+    assert_eq!(statuses[4].1, SelfTestSeverity::Fatal);
+    assert!(matches!(statuses[4].2, SelfTestStatus::Failed(_)));
+    assert_eq!(statuses[5].0, "hanging");
+    assert!(matches!(statuses[5].2, SelfTestStatus::Failed(_)));
+
+    // The failed fatal check determines the readiness.
+    assert_eq!(report.readiness, SelfTestReadiness::NotReady);
+    assert_eq!(chain.self_test_report(), Some(report));
+}
+
+#[tokio::test]
+async fn wait_for_slot_with_fork_choice_signal() {
+    let harness = get_harness(VALIDATOR_COUNT);
+    let chain = harness.chain.clone();
+    assert!(chain.fork_choice_signal_rx.is_some());
+    let current_slot = chain.slot().unwrap();
+    let next_slot = current_slot + 1;
+    let timeout = Duration::from_secs(5);
+    let short_timeout = Duration::from_millis(10);
+
+    // The next slot doesn't start within the timeout.
+    assert_eq!(
+        chain.wait_for_slot(next_slot, short_timeout).await,
+        SlotWaitResult::TimeOut
+    );
+
+    // The slot has started, but fork choice hasn't been run for it.
+    assert_eq!(
+        chain.wait_for_slot(current_slot, short_timeout).await,
+        SlotWaitResult::TimeOut
+    );
+
+    // Fork choice completes whilst waiting.
+    let signal_chain = chain.clone();
+    let signal = tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        signal_chain
+            .fork_choice_signal_tx
+            .as_ref()
+            .unwrap()
+            .notify_fork_choice_complete(current_slot, None)
+            .unwrap();
+    });
+    assert_eq!(
+        chain.wait_for_slot(current_slot, timeout).await,
+        SlotWaitResult::Success(current_slot, None)
+    );
+    signal.await.unwrap();
+
+    // Both have already happened.
+    assert_eq!(
+        chain.wait_for_slot(current_slot, Duration::ZERO).await,
+        SlotWaitResult::Success(current_slot, None)
+    );
+
+    // Fork choice is run for the next slot before it starts, so the start of the slot is awaited.
+    let next_slot_start = chain.slot_clock.start_of(next_slot).unwrap();
+    chain
+        .slot_clock
+        .set_current_time(next_slot_start - Duration::from_millis(20));
+    chain
+        .fork_choice_signal_tx
+        .as_ref()
+        .unwrap()
+        .notify_fork_choice_complete(next_slot, None)
+        .unwrap();
+    assert_eq!(
+        chain.wait_for_slot(next_slot, timeout).await,
+        SlotWaitResult::Success(next_slot, None)
+    );
+
+    // Fork choice is updated to an earlier slot.
+    let later_slot = next_slot + 1;
+    chain.slot_clock.set_slot(later_slot.as_u64());
+    let signal_chain = chain.clone();
+    let signal = tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        signal_chain
+            .fork_choice_signal_tx
+            .as_ref()
+            .unwrap()
+            .notify_fork_choice_complete(next_slot, None)
+            .unwrap();
+    });
+    assert_eq!(
+        chain.wait_for_slot(later_slot, timeout).await,
+        SlotWaitResult::Behind(next_slot)
+    );
+    signal.await.unwrap();
+}
+
+#[tokio::test]
+async fn wait_for_slot_without_fork_choice_signal() {
+    let harness = BeaconChainHarness::builder(MinimalEthSpec)
+        .default_spec()
+        .chain_config(ChainConfig {
+            fork_choice_before_proposal_timeout_ms: 0,
+            ..ChainConfig::default()
+        })
+        .keypairs(KEYPAIRS[0..VALIDATOR_COUNT].to_vec())
+        .fresh_ephemeral_store()
+        .mock_execution_layer()
+        .build();
+    harness.advance_slot();
+    let chain = &harness.chain;
+    assert!(chain.fork_choice_signal_rx.is_none());
+    let current_slot = chain.slot().unwrap();
+    let next_slot = current_slot + 1;
+    let timeout = Duration::from_secs(5);
+
+    // Only the clock is awaited.
+    assert_eq!(
+        chain.wait_for_slot(current_slot, Duration::ZERO).await,
+        SlotWaitResult::SlotStarted
+    );
+    assert_eq!(
+        chain
+            .wait_for_slot(next_slot, Duration::from_millis(10))
+            .await,
+        SlotWaitResult::TimeOut
+    );
+
+    let next_slot_start = chain.slot_clock.start_of(next_slot).unwrap();
+    chain
+        .slot_clock
+        .set_current_time(next_slot_start - Duration::from_millis(20));
+    assert_eq!(
+        chain.wait_for_slot(next_slot, timeout).await,
+        SlotWaitResult::SlotStarted
+    );
+}
+
+async fn get_finalized_harness(
+    chain_config: ChainConfig,
+) -> BeaconChainHarness<EphemeralHarnessType<MinimalEthSpec>> {
+    let harness = BeaconChainHarness::builder(MinimalEthSpec)
+        .default_spec()
+        .chain_config(chain_config)
+        .keypairs(KEYPAIRS[0..VALIDATOR_COUNT].to_vec())
+        .fresh_ephemeral_store()
+        .mock_execution_layer()
+        .build();
+    harness.advance_slot();
+
+    harness
+        .extend_chain(
+            MinimalEthSpec::slots_per_epoch() as usize * 5,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::AllValidators,
+        )
+        .await;
+    assert!(
+        harness.finalized_checkpoint().epoch > 0,
+        "the chain should have finalized"
+    );
+
+    harness
+}
+
+/// Returns the root of the state at the start of the epoch of `checkpoint`.
+fn checkpoint_state_root(
+    harness: &BeaconChainHarness<EphemeralHarnessType<MinimalEthSpec>>,
+    checkpoint: Checkpoint,
+) -> Hash256 {
+    harness
+        .chain
+        .state_root_at_slot(
+            checkpoint
+                .epoch
+                .start_slot(MinimalEthSpec::slots_per_epoch()),
+        )
+        .unwrap()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn checkpoint_states_are_cached() {
+    let harness = get_finalized_harness(ChainConfig::default()).await;
+    let chain = &harness.chain;
+    let cached_head = chain.canonical_head.cached_head();
+
+    // The finalized state was cached upon finalization, so it is served without a store read.
+    let stats = chain.checkpoint_state_cache_stats();
+    let finalized_state = chain.finalized_state().unwrap();
+    let new_stats = chain.checkpoint_state_cache_stats();
+    assert_eq!(new_stats.store_loads, stats.store_loads);
+    assert_eq!(new_stats.hits, stats.hits + 1);
+    assert_eq!(
+        finalized_state.canonical_root(),
+        checkpoint_state_root(&harness, cached_head.finalized_checkpoint())
+    );
+    assert!(Arc::ptr_eq(
+        &finalized_state,
+        &chain.finalized_state().unwrap()
+    ));
+
+    // The justified state may have changed since finalization, in which case it is loaded once.
+    let justified_state = chain.justified_state().unwrap();
+    let stats = chain.checkpoint_state_cache_stats();
+    assert!(Arc::ptr_eq(
+        &justified_state,
+        &chain.justified_state().unwrap()
+    ));
+    let new_stats = chain.checkpoint_state_cache_stats();
+    assert_eq!(new_stats.store_loads, stats.store_loads);
+    assert_eq!(new_stats.hits, stats.hits + 1);
+    assert_eq!(
+        justified_state.canonical_root(),
+        checkpoint_state_root(&harness, cached_head.justified_checkpoint())
+    );
+}
+
+#[tokio::test]
+async fn checkpoint_states_are_loaded_when_cache_disabled() {
+    let harness = get_finalized_harness(ChainConfig {
+        cache_checkpoint_states: false,
+        ..ChainConfig::default()
+    })
+    .await;
+    let chain = &harness.chain;
+    assert_eq!(
+        chain.checkpoint_state_cache_stats(),
+        CheckpointStateCacheStats::default()
+    );
+
+    let finalized_state = chain.finalized_state().unwrap();
+    let justified_state = chain.justified_state().unwrap();
+    assert!(!Arc::ptr_eq(
+        &finalized_state,
+        &chain.finalized_state().unwrap()
+    ));
+    assert_eq!(
+        chain.checkpoint_state_cache_stats(),
+        CheckpointStateCacheStats {
+            hits: 0,
+            store_loads: 3,
+        }
+    );
+
+    let cached_head = chain.canonical_head.cached_head();
+    assert_eq!(
+        finalized_state.canonical_root(),
+        checkpoint_state_root(&harness, cached_head.finalized_checkpoint())
+    );
+    assert_eq!(
+        justified_state.canonical_root(),
+        checkpoint_state_root(&harness, cached_head.justified_checkpoint())
+    );
+}
+
+/// Produce a block with the node's graffiti set to `mode`, returning the block's graffiti.
+async fn produce_block_graffiti(
+    mode: GraffitiMode,
+    validator_graffiti: Option<Graffiti>,
+) -> Graffiti {
+    let harness = BeaconChainHarness::builder(MinimalEthSpec)
+        .default_spec()
+        .keypairs(KEYPAIRS[0..VALIDATOR_COUNT].to_vec())
+        .fresh_ephemeral_store()
+        .mock_execution_layer()
+        .initial_mutator(Box::new(move |builder| builder.graffiti(mode)))
+        .build();
+    harness.advance_slot();
+
+    let (block, _) = harness
+        .chain
+        .produce_block_with_verification::<FullPayload<MinimalEthSpec>>(
+            Signature::empty(),
+            harness.get_current_slot(),
+            validator_graffiti,
+            ProduceBlockVerification::NoVerification,
+        )
+        .await
+        .unwrap();
+    *block.body().graffiti()
+}
+
+#[tokio::test]
+async fn block_graffiti_follows_graffiti_mode() {
+    let custom = Graffiti::from([b'x'; GRAFFITI_BYTES_LEN]);
+    assert_eq!(
+        produce_block_graffiti(GraffitiMode::Custom(custom), None).await,
+        custom
+    );
+
+    let version = produce_block_graffiti(GraffitiMode::ClientVersion, None).await;
+    assert!(version.as_utf8_lossy().starts_with("Lighthouse/v"));
+    assert_eq!(version, GraffitiMode::ClientVersion.resolve());
+
+    assert_eq!(
+        produce_block_graffiti(GraffitiMode::Empty, None).await,
+        Graffiti::default()
+    );
+
+    let random = produce_block_graffiti(GraffitiMode::Random, None).await;
+    assert!(random.0.iter().all(|byte| (0x20..=0x7e).contains(byte)));
+    assert_ne!(
+        random,
+        produce_block_graffiti(GraffitiMode::Random, None).await
+    );
+}
+
+#[tokio::test]
+async fn validator_graffiti_overrides_graffiti_mode() {
+    let validator_graffiti = Graffiti::from([b'v'; GRAFFITI_BYTES_LEN]);
+    for mode in [
+        GraffitiMode::Custom(Graffiti::from([b'x'; GRAFFITI_BYTES_LEN])),
+        GraffitiMode::ClientVersion,
+        GraffitiMode::Empty,
+        GraffitiMode::Random,
+    ] {
+        assert_eq!(
+            produce_block_graffiti(mode, Some(validator_graffiti)).await,
+            validator_graffiti
+        );
+    }
+}
+
+fn histogram_sample_count(name: &str, label_value: &str) -> u64 {
+    lighthouse_metrics::gather()
+        .iter()
+        .filter(|family| family.get_name() == name)
+        .flat_map(|family| family.get_metric())
+        .find(|metric| {
+            metric
+                .get_label()
+                .iter()
+                .any(|label| label.get_value() == label_value)
+        })
+        .map_or(0, |metric| metric.get_histogram().get_sample_count())
+}
+
+#[tokio::test]
+async fn verified_exit_inclusion_latency_is_recorded() {
+    // Allow validators to exit immediately.
+    let mut spec = test_spec::<MinimalEthSpec>();
+    spec.shard_committee_period = 0;
+    let harness = BeaconChainHarness::builder(MinimalEthSpec)
+        .spec(spec)
+        .keypairs(KEYPAIRS[..].to_vec())
+        .fresh_ephemeral_store()
+        .mock_execution_layer()
+        .build();
+    harness.advance_slot();
+    harness
+        .extend_chain(
+            2,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::AllValidators,
+        )
+        .await;
+
+    let metric = "beacon_op_pool_inclusion_latency_seconds";
+    let samples_before = histogram_sample_count(metric, "voluntary_exit");
+
+    let exit = harness.make_voluntary_exit(VALIDATOR_COUNT as u64 - 1, Epoch::new(0));
+    match harness
+        .chain
+        .verify_voluntary_exit_for_gossip(exit)
+        .unwrap()
+    {
+        ObservationOutcome::New(exit) => harness.chain.import_voluntary_exit(exit),
+        ObservationOutcome::AlreadyKnown => panic!("exit should be new"),
+    }
+    harness
+        .extend_chain(
+            1,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::AllValidators,
+        )
+        .await;
+
+    let head = harness.chain.head_snapshot();
+    assert_eq!(
+        head.beacon_block.message().body().voluntary_exits().len(),
+        1
     );
-    assert!(
+    // Other tests in this binary may also record samples, so only check for an increase.
+    assert!(histogram_sample_count(metric, "voluntary_exit") > samples_before);
+}
+
+#[tokio::test]
+async fn sync_status_tracks_distance_from_wall_clock() {
+    let harness = get_harness(VALIDATOR_COUNT);
+    harness
+        .extend_chain(
+            4,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::AllValidators,
+        )
+        .await;
+
+    let status = harness.chain.sync_status().unwrap();
+    assert_eq!(status.head_block_root, harness.head_block_root());
+    assert_eq!(status.head_slot, harness.head_slot());
+    assert_eq!(status.current_slot, harness.chain.slot().unwrap());
+    assert_eq!(status.sync_distance, status.current_slot - status.head_slot);
+    assert!(status.backfill_complete);
+    assert!(!status.is_optimistic);
+
+    // Let the clock run ahead of the head.
+    let head_slot = harness.head_slot();
+    harness.set_current_slot(head_slot + 5);
+    let status = harness.chain.sync_status().unwrap();
+    assert_eq!(status.head_slot, head_slot);
+    assert_eq!(status.current_slot, head_slot + 5);
+    assert_eq!(status.sync_distance, Slot::new(5));
+
+    // Catch up with the clock, skipping the intermediate slots.
+    harness
+        .extend_chain(
+            1,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::AllValidators,
+        )
+        .await;
+    let status = harness.chain.sync_status().unwrap();
+    assert_eq!(status.head_slot, head_slot + 5);
+    assert_eq!(status.current_slot, head_slot + 5);
+    assert_eq!(status.sync_distance, Slot::new(0));
+}
+
+#[tokio::test]
+async fn deposit_status_tracks_deposit_through_activation() {
+    let spec = test_spec::<MinimalEthSpec>();
+    let new_keypair = generate_deterministic_keypair(VALIDATOR_COUNT);
+    let new_index = VALIDATOR_COUNT as u64;
+
+    // An eth1 cache holding the deposits of the genesis validators, followed by the deposit of a new
+    // validator.
+    let backend = CachingEth1Backend::<MinimalEthSpec>::new(
+        Eth1Config::default(),
+        logging::test_logger(),
+        spec.clone(),
+    );
+    let deposit_root = {
+        let mut deposits = backend.core.deposits().write();
+        for (index, keypair) in KEYPAIRS.iter().chain([&new_keypair]).enumerate() {
+            let mut deposit_data = DepositData {
+                pubkey: keypair.pk.compress(),
+                withdrawal_credentials: Hash256::from_slice(&get_withdrawal_credentials(
+                    &keypair.pk,
+                    spec.bls_withdrawal_prefix_byte,
+                )),
+                amount: spec.max_effective_balance,
+                signature: SignatureBytes::empty(),
+            };
+            deposit_data.signature = deposit_data.create_signature(&keypair.sk, &spec);
+            deposits
+                .cache
+                .insert_log(DepositLog {
+                    deposit_data,
+                    block_number: 0,
+                    index: index as u64,
+                    signature_is_valid: true,
+                })
+                .unwrap();
+        }
+        deposits
+            .cache
+            .get_deposits(new_index, new_index + 1, new_index + 1, DEPOSIT_TREE_DEPTH)
+            .unwrap()
+            .0
+    };
+
+    // An eth1 block which is a candidate for the first voting period, so that block producers vote
+    // to include the new deposit.
+    backend
+        .core
+        .blocks()
+        .write()
+        .insert_root_or_child(Eth1Block {
+            hash: Hash256::repeat_byte(1),
+            timestamp: HARNESS_GENESIS_TIME
+                - spec.seconds_per_eth1_block * spec.eth1_follow_distance * 3 / 2,
+            number: 0,
+            deposit_root: Some(deposit_root),
+            deposit_count: Some(new_index + 1),
+        })
+        .unwrap();
+
+    let chain_backend = backend.clone();
+    let mut harness = BeaconChainHarness::builder(MinimalEthSpec)
+        .spec(spec)
+        .keypairs(KEYPAIRS.to_vec())
+        .fresh_ephemeral_store()
+        .mock_execution_layer()
+        .initial_mutator(Box::new(move |builder| {
+            builder.eth1_backend(Some(chain_backend))
+        }))
+        .build();
+    // Sign with the new validator once it's active.
+    harness.validator_keypairs.push(new_keypair.clone());
+    harness.advance_slot();
+
+    // Record the last status and summary reported at each stage, and the first report from the
+    // activation queue.
+    let validator_id = ValidatorId::PublicKey(new_keypair.pk.compress());
+    let mut stages = vec![];
+    let mut queued = None;
+    for _ in 0..16 * MinimalEthSpec::slots_per_epoch() {
+        let status = harness.chain.deposit_status(&validator_id).unwrap();
+        let summary = harness.chain.pending_deposit_queue_summary().unwrap();
+        match stages.last_mut() {
+            Some((last, last_summary)) if discriminant(last) == discriminant(&status) => {
+                *last = status;
+                *last_summary = summary;
+            }
+            _ => stages.push((status, summary)),
+        }
+        if matches!(status, DepositStatus::PendingActivation { .. }) && queued.is_none() {
+            queued = Some((status, summary));
+        }
+        if matches!(status, DepositStatus::Active { .. }) {
+            break;
+        }
+
         harness
+            .extend_chain(
+                1,
+                BlockStrategy::OnCanonicalHead,
+                AttestationStrategy::AllValidators,
+            )
+            .await;
+    }
+
+    assert_eq!(stages.len(), 4, "{:?}", stages);
+    let activation_epoch =
+        harness.get_current_state().validators()[VALIDATOR_COUNT].activation_epoch;
+
+    let (status, summary) = stages[0];
+    assert_eq!(
+        status,
+        DepositStatus::InEth1Cache {
+            deposit_index: new_index
+        }
+    );
+    assert_eq!(summary.eth1_cached_deposits, 1);
+    assert_eq!(summary.awaiting_inclusion, 0);
+
+    let (status, summary) = stages[1];
+    assert_eq!(
+        status,
+        DepositStatus::Included {
+            validator_index: new_index,
+            deposit_index: Some(new_index),
+        }
+    );
+    assert_eq!(summary.eth1_cached_deposits, 0);
+    assert_eq!(summary.awaiting_eligibility, 1);
+
+    // The estimate is made from within the activation queue.
+    let (status, summary) = queued.unwrap();
+    let estimated_activation_epoch = match status {
+        DepositStatus::PendingActivation {
+            queue_position,
+            estimated_activation_epoch,
+            ..
+        } => {
+            assert_eq!(queue_position, 0);
+            estimated_activation_epoch
+        }
+        other => panic!("expected pending activation, got {:?}", other),
+    };
+    assert_eq!(summary.activation_queue_length, 1);
+    assert_eq!(
+        summary.estimated_queue_clear_epoch,
+        Some(estimated_activation_epoch)
+    );
+
+    // The activation epoch is exact once the validator has left the queue.
+    let (status, summary) = stages[2];
+    assert_eq!(
+        status,
+        DepositStatus::PendingActivation {
+            validator_index: new_index,
+            deposit_index: Some(new_index),
+            queue_position: 0,
+            estimated_activation_epoch: activation_epoch,
+        }
+    );
+    assert_eq!(summary.activation_queue_length, 0);
+    assert_eq!(summary.awaiting_activation_epoch, 1);
+
+    let (status, summary) = stages[3];
+    assert_eq!(
+        status,
+        DepositStatus::Active {
+            validator_index: new_index,
+            deposit_index: Some(new_index),
+            activation_epoch,
+        }
+    );
+    assert_eq!(summary.awaiting_eligibility, 0);
+    assert_eq!(summary.activation_queue_length, 0);
+    assert_eq!(summary.awaiting_activation_epoch, 0);
+    assert_eq!(summary.estimated_queue_clear_epoch, None);
+}
+
+#[tokio::test]
+async fn exit_queue_summary_counts_exiting_validators() {
+    // Allow validators to exit immediately.
+    let mut spec = test_spec::<MinimalEthSpec>();
+    spec.shard_committee_period = 0;
+    let harness = BeaconChainHarness::builder(MinimalEthSpec)
+        .spec(spec)
+        .keypairs(KEYPAIRS[..].to_vec())
+        .fresh_ephemeral_store()
+        .mock_execution_layer()
+        .build();
+    harness.advance_slot();
+    harness
+        .extend_chain(
+            2,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::AllValidators,
+        )
+        .await;
+
+    let before = harness.chain.exit_queue_summary().unwrap();
+    assert_eq!(before.epoch, Epoch::new(0));
+    assert_eq!(before.decision_root, harness.chain.genesis_block_root);
+    assert_eq!(before.exiting_validators, 0);
+    assert_eq!(before.max_exit_epoch, None);
+    assert_eq!(
+        before.withdrawal_credential_prefixes.get("0x00"),
+        Some(&(VALIDATOR_COUNT as u64))
+    );
+
+    // More exits than the churn limit, so that they spill into a second epoch.
+    let churn_limit = before.churn_limit;
+    let exiting = (0..churn_limit + 2).collect::<Vec<u64>>();
+    for &validator_index in &exiting {
+        let exit = harness.make_voluntary_exit(validator_index, Epoch::new(0));
+        match harness
             .chain
-            .block_root_at_slot(future_slot, WhenSlotSkipped::None)
+            .verify_voluntary_exit_for_gossip(exit)
             .unwrap()
-            .is_none(),
-        "WhenSlotSkipped::None should return None on a future slot"
+        {
+            ObservationOutcome::New(exit) => harness.chain.import_voluntary_exit(exit),
+            ObservationOutcome::AlreadyKnown => panic!("exit should be new"),
+        }
+    }
+    harness
+        .extend_chain(
+            1,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::AllValidators,
+        )
+        .await;
+    assert_eq!(
+        harness
+            .chain
+            .head_snapshot()
+            .beacon_block
+            .message()
+            .body()
+            .voluntary_exits()
+            .len(),
+        exiting.len()
     );
-    assert!(
+
+    // The summary is only recomputed once the epoch changes.
+    assert_eq!(harness.chain.exit_queue_summary().unwrap(), before);
+
+    harness
+        .extend_chain(
+            MinimalEthSpec::slots_per_epoch() as usize,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::AllValidators,
+        )
+        .await;
+
+    let head = harness.chain.head_snapshot();
+    let max_exit_epoch = exiting
+        .iter()
+        .map(|&i| head.beacon_state.validators()[i as usize].exit_epoch)
+        .max()
+        .unwrap();
+    drop(head);
+
+    let after = harness.chain.exit_queue_summary().unwrap();
+    assert_eq!(after.epoch, Epoch::new(1));
+    assert_eq!(
+        after.decision_root,
         harness
             .chain
-            .block_root_at_slot(future_slot, WhenSlotSkipped::Prev)
+            .block_root_at_slot(
+                Epoch::new(1).start_slot(MinimalEthSpec::slots_per_epoch()) - 1,
+                WhenSlotSkipped::Prev
+            )
             .unwrap()
-            .is_none(),
-        "WhenSlotSkipped::Prev should return None on a future slot"
+            .unwrap()
+    );
+    assert_eq!(after.exiting_validators, exiting.len() as u64);
+    assert_eq!(after.max_exit_epoch, Some(max_exit_epoch));
+    assert_eq!(after.exit_queue_epoch, max_exit_epoch);
+    assert_eq!(after.exit_queue_churn, 2);
+    assert_eq!(after.churn_limit, churn_limit);
+    assert_eq!(
+        after.withdrawal_credential_prefixes.get("0x00"),
+        Some(&(VALIDATOR_COUNT as u64))
     );
 }
+
+#[tokio::test]
+async fn failed_state_skip_resumes_from_partial_progress() {
+    // Upgrade to Altair at the third epoch, so that a state of the wrong fork can be supplied.
+    let altair_fork_epoch = Epoch::new(2);
+    let mut spec = MinimalEthSpec::default_spec();
+    spec.altair_fork_epoch = Some(altair_fork_epoch);
+    spec.bellatrix_fork_epoch = None;
+    let harness = BeaconChainHarness::builder(MinimalEthSpec)
+        .spec(spec)
+        .keypairs(KEYPAIRS[0..8].to_vec())
+        .fresh_ephemeral_store()
+        .mock_execution_layer()
+        .build();
+    harness.advance_slot();
+    harness
+        .extend_chain(
+            2,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::AllValidators,
+        )
+        .await;
+
+    let chain = &harness.chain;
+    let head = chain.head_snapshot();
+    let base_state_root = head.beacon_state_root();
+    let requested_slot = (altair_fork_epoch + 1).start_slot(MinimalEthSpec::slots_per_epoch());
+    let skip = |state| {
+        chain.skip_state_forward(
+            state,
+            base_state_root,
+            requested_slot,
+            StateSkipConfig::WithStateRoots,
+        )
+    };
+
+    // A phase 0 state after the fork fails slot processing without being modified.
+    let fork_slot = altair_fork_epoch.start_slot(MinimalEthSpec::slots_per_epoch()) + 1;
+    let wrong_fork_state = || {
+        let mut state = head.beacon_state.clone();
+        *state.slot_mut() = fork_slot;
+        state
+    };
+    let assert_fails_after_fork_slot = |result: Result<_, BeaconChainError>| match result {
+        Err(BeaconChainError::StateAdvanceFailed {
+            requested_slot: slot,
+            failed_slot,
+            error: SlotProcessingError::InconsistentStateFork(_),
+        }) => {
+            assert_eq!(slot, requested_slot);
+            assert_eq!(failed_slot, fork_slot + 1);
+        }
+        other => panic!(
+            "expected a failed state advance, got {:?}",
+            other.map(|_| ())
+        ),
+    };
+
+    assert_fails_after_fork_slot(skip(wrong_fork_state()));
+    assert_eq!(chain.partial_state_skip_slot(), Some(fork_slot));
+
+    // A retry from the same state resumes from the state which was retained, so it fails in the
+    // same slot even though the supplied state is intact.
+    assert_fails_after_fork_slot(skip(head.beacon_state.clone()));
+    assert_eq!(chain.partial_state_skip_slot(), Some(fork_slot));
+
+    // A skip from another state ignores the progress of the failed skip.
+    let state = chain
+        .skip_state_forward(
+            head.beacon_state.clone(),
+            Hash256::repeat_byte(1),
+            requested_slot,
+            StateSkipConfig::WithStateRoots,
+        )
+        .unwrap();
+    assert_eq!(state.slot(), requested_slot);
+    assert_eq!(chain.partial_state_skip_slot(), None);
+
+    // The progress of a failed skip from the head state is dropped once the head changes.
+    assert_fails_after_fork_slot(skip(wrong_fork_state()));
+    assert_eq!(chain.partial_state_skip_slot(), Some(fork_slot));
+    drop(head);
+    harness
+        .extend_chain(
+            1,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::AllValidators,
+        )
+        .await;
+    assert_eq!(chain.partial_state_skip_slot(), None);
+}
+
+#[tokio::test]
+async fn state_skip_failing_in_epoch_processing_retains_no_progress() {
+    let harness = get_harness(8);
+    harness
+        .extend_chain(
+            2,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::AllValidators,
+        )
+        .await;
+
+    let chain = &harness.chain;
+    let head = chain.head_snapshot();
+    let requested_slot = head.beacon_block.slot() + 2 * MinimalEthSpec::slots_per_epoch();
+    let first_epoch_slot = (head
+        .beacon_block
+        .slot()
+        .epoch(MinimalEthSpec::slots_per_epoch())
+        + 1)
+    .start_slot(MinimalEthSpec::slots_per_epoch());
+    let skip = |state| {
+        chain.skip_state_forward(
+            state,
+            head.beacon_state_root(),
+            requested_slot,
+            StateSkipConfig::WithStateRoots,
+        )
+    };
+
+    // Drop a balance, so that epoch processing fails.
+    let mut corrupt_state = head.beacon_state.clone();
+    let balances = corrupt_state.balances().to_vec();
+    *corrupt_state.balances_mut() =
+        VariableList::new(balances[1..].to_vec()).expect("should fit in list");
+
+    match skip(corrupt_state) {
+        Err(BeaconChainError::StateAdvanceFailed {
+            requested_slot: slot,
+            failed_slot,
+            error: SlotProcessingError::EpochProcessingError(_),
+        }) => {
+            assert_eq!(slot, requested_slot);
+            assert_eq!(failed_slot, first_epoch_slot);
+        }
+        other => panic!(
+            "expected a failed state advance, got {:?}",
+            other.map(|_| ())
+        ),
+    }
+
+    // The state may have been modified by epoch processing, so it isn't retained and a retry
+    // starts afresh from the supplied state.
+    assert_eq!(chain.partial_state_skip_slot(), None);
+    let state = skip(head.beacon_state.clone()).unwrap();
+    assert_eq!(state.slot(), requested_slot);
+}
+
+#[tokio::test]
+async fn caches_are_shrunk_to_budget() {
+    const BUDGET_MB: u64 = 4;
+
+    let harness = BeaconChainHarness::builder(MainnetEthSpec)
+        .default_spec()
+        .chain_config(ChainConfig {
+            cache_memory_budget_mb: Some(BUDGET_MB),
+            ..ChainConfig::default()
+        })
+        .deterministic_keypairs(8)
+        .fresh_ephemeral_store()
+        .build();
+    harness.advance_slot();
+    harness
+        .extend_chain(
+            3,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::AllValidators,
+        )
+        .await;
+    let chain = &harness.chain;
+
+    // Fill the checkpoint state cache, which holds a state per checkpoint.
+    chain.finalized_state().unwrap();
+    chain.justified_state().unwrap();
+
+    let budget = BUDGET_MB as usize * 1_048_576;
+    let before = chain.cache_memory_usage().unwrap();
+    assert!(before.total() > budget);
+    assert!(before.pinned() < budget);
+    assert!(before.checkpoint_state_cache > 0);
+    assert!(before.snapshot_cache_head > 0);
+
+    let after = chain.enforce_cache_memory_budget().unwrap();
+    assert!(after.total() <= budget);
+
+    // The pinned entries remain.
+    assert_eq!(after.snapshot_cache_head, before.snapshot_cache_head);
+    assert_eq!(after.early_attester_cache, before.early_attester_cache);
+    assert_eq!(after.head, before.head);
+}
+
+#[tokio::test]
+async fn caches_are_prewarmed_after_skipped_epochs() {
+    type E = MainnetEthSpec;
+
+    let logs = CapturedLogs::default();
+    let harness = BeaconChainHarness::builder(E::default())
+        .default_spec()
+        .deterministic_keypairs(8)
+        .logger(logs.logger())
+        .fresh_ephemeral_store()
+        .build();
+    harness.advance_slot();
+    harness
+        .extend_chain(
+            2,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::AllValidators,
+        )
+        .await;
+    let chain = &harness.chain;
+
+    // Skip two epochs.
+    let head = chain.head_snapshot();
+    let current_slot = head.beacon_block.slot() + 2 * E::slots_per_epoch() + 1;
+    harness.set_current_slot(current_slot);
+    let current_epoch = current_slot.epoch(E::slots_per_epoch());
+
+    assert!(chain.prewarm_caches(current_slot).unwrap());
+    // The caches are only pre-warmed once per head and epoch.
+    assert!(!chain.prewarm_caches(current_slot).unwrap());
+
+    // The first attestation after the gap is served by the attester cache, without loading a
+    // state.
+    logs.clear();
+    let attestation = chain
+        .produce_unaggregated_attestation(current_slot, 0)
+        .unwrap();
+    assert_eq!(attestation.data.beacon_block_root, head.beacon_block_root);
+    assert_eq!(attestation.data.target.epoch, current_epoch);
+    assert!(!logs
+        .messages(Level::Debug)
+        .iter()
+        .any(|message| message == "Attester cache miss"));
+
+    // The proposer and shuffling caches are keyed by the head, which decides the shufflings of
+    // the skipped epochs.
+    assert!(chain
+        .beacon_proposer_cache
+        .lock()
+        .get_epoch::<E>(head.beacon_block_root, current_epoch)
+        .is_some());
+    for epoch in [current_epoch, current_epoch + 1] {
+        let shuffling_id = AttestationShufflingId::from_components(epoch, head.beacon_block_root);
+        assert!(chain
+            .shuffling_cache
+            .try_read_for(BLOCK_PROCESSING_CACHE_LOCK_TIMEOUT)
+            .unwrap()
+            .contains(&shuffling_id));
+    }
+}
+
+#[tokio::test]
+async fn repeated_failures_are_logged_once() {
+    let logs = CapturedLogs::default();
+    let harness = BeaconChainHarness::builder(MinimalEthSpec)
+        .default_spec()
+        .keypairs(KEYPAIRS[..].to_vec())
+        .logger(logs.logger())
+        .fresh_ephemeral_store()
+        .build();
+    let chain = &harness.chain;
+    let logged = |level: Level, message: &str| {
+        logs.messages(level)
+            .iter()
+            .filter(|logged| *logged == message)
+            .count()
+    };
+
+    let kind = FailureKind::ExecutionForkchoiceUpdate;
+    let error = BeaconChainError::CanonicalHeadLockTimeout;
+    let msg = "Failed to update execution head";
+
+    // Only the first failure within the window is logged at full severity, repeats are logged at
+    // debug level.
+    assert!(chain.should_log_failure(kind, msg, &error));
+    assert_eq!(logged(Level::Debug, "Repeated failure"), 0);
+    for _ in 0..3 {
+        assert!(!chain.should_log_failure(kind, msg, &error));
+    }
+    assert_eq!(logged(Level::Debug, "Repeated failure"), 3);
+
+    // Other kinds of failure are limited independently.
+    assert!(chain.should_log_failure(FailureKind::PrepareProposer, msg, &error));
+    assert_eq!(logged(Level::Debug, "Repeated failure"), 3);
+
+    // A summary is logged once the failure clears, and only once.
+    chain.record_failure_cleared(kind);
+    assert_eq!(logged(Level::Info, "Failure cleared"), 1);
+    chain.record_failure_cleared(kind);
+    chain.record_failure_cleared(FailureKind::ForkChoice);
+    assert_eq!(logged(Level::Info, "Failure cleared"), 1);
+
+    // A failure after the condition has cleared is logged again.
+    assert!(chain.should_log_failure(kind, msg, &error));
+    assert_eq!(logged(Level::Debug, "Repeated failure"), 3);
+}
@@ -0,0 +1,139 @@
+//! Tests for the validator monitor.
+
+#![cfg(not(debug_assertions))]
+
+use beacon_chain::test_utils::{BeaconChainHarness, EphemeralHarnessType, RelativeSyncCommittee};
+use beacon_chain::validator_monitor::SyncCommitteePerformance;
+use lazy_static::lazy_static;
+use types::*;
+
+pub const VALIDATOR_COUNT: usize = 24;
+
+lazy_static! {
+    /// A cached set of keys.
+    static ref KEYPAIRS: Vec<Keypair> =
+        types::test_utils::generate_deterministic_keypairs(VALIDATOR_COUNT);
+}
+
+type E = MinimalEthSpec;
+
+fn get_harness() -> BeaconChainHarness<EphemeralHarnessType<E>> {
+    let mut spec = E::default_spec();
+    spec.altair_fork_epoch = Some(Epoch::new(0));
+    let harness = BeaconChainHarness::builder(MinimalEthSpec)
+        .spec(spec)
+        .keypairs(KEYPAIRS.to_vec())
+        .fresh_ephemeral_store()
+        .mock_execution_layer()
+        .build();
+    harness.advance_slot();
+    harness
+}
+
+/// Add the sync contributions of all members of the current sync committee for the head to the
+/// op pool, so that the next block includes them.
+fn add_sync_contributions_for_head(harness: &BeaconChainHarness<EphemeralHarnessType<E>>) {
+    let head = harness.chain.head_snapshot();
+    for contribution in harness
+        .make_sync_contributions(
+            &head.beacon_state,
+            head.beacon_block_root,
+            head.beacon_block.slot(),
+            RelativeSyncCommittee::Current,
+        )
+        .into_iter()
+        .filter_map(|(_, contribution)| contribution)
+    {
+        harness
+            .chain
+            .op_pool
+            .insert_sync_contribution(contribution.message.contribution)
+            .unwrap();
+    }
+}
+
+#[tokio::test]
+async fn sync_committee_performance_of_monitored_validator() {
+    let harness = get_harness();
+    let mut state = harness.get_current_state();
+
+    let sync_committee = state.current_sync_committee().unwrap().clone();
+    let monitored_pubkey = sync_committee.pubkeys[0];
+    let positions = sync_committee
+        .pubkeys
+        .iter()
+        .filter(|pubkey| **pubkey == monitored_pubkey)
+        .count();
+    let validator_index = state
+        .get_validator_index(&monitored_pubkey)
+        .unwrap()
+        .unwrap() as u64;
+    harness
+        .chain
+        .validator_monitor
+        .write()
+        .add_validator_pubkey(monitored_pubkey);
+
+    // Produce a block at each remaining slot of the first epoch. Only the blocks at even slots
+    // include the sync committee's signatures.
+    let mut included_blocks = 0;
+    let mut missed_blocks = 0;
+    for slot in 1..E::slots_per_epoch() {
+        if slot % 2 == 0 {
+            add_sync_contributions_for_head(&harness);
+            included_blocks += 1;
+        } else {
+            missed_blocks += 1;
+        }
+
+        let head_state = harness.chain.head_snapshot().beacon_state.clone();
+        let (_, block, _) = harness
+            .add_block_at_slot(Slot::new(slot), head_state)
+            .await
+            .unwrap();
+        let sync_aggregate = block.message().body().sync_aggregate().unwrap();
+        if slot % 2 == 0 {
+            assert_eq!(
+                sync_aggregate.num_set_bits(),
+                E::sync_committee_size(),
+                "all sync signatures should be included"
+            );
+        } else {
+            assert_eq!(sync_aggregate.num_set_bits(), 0, "no sync signatures");
+        }
+        harness.advance_slot();
+    }
+
+    let validator_monitor = harness.chain.validator_monitor.read();
+    let performance = validator_monitor
+        .get_sync_committee_performance(validator_index, Epoch::new(0))
+        .unwrap();
+    assert_eq!(
+        performance,
+        SyncCommitteePerformance {
+            included: included_blocks * positions,
+            missed: missed_blocks * positions,
+        }
+    );
+    assert_eq!(
+        performance.expected(),
+        (E::slots_per_epoch() as usize - 1) * positions
+    );
+    assert_eq!(
+        performance.hit_rate(),
+        Some(included_blocks as f64 / (included_blocks + missed_blocks) as f64)
+    );
+
+    // Nothing is recorded for unmonitored validators or for later epochs.
+    let unmonitored_index = (0..VALIDATOR_COUNT as u64)
+        .find(|index| *index != validator_index)
+        .unwrap();
+    assert_eq!(
+        validator_monitor.get_sync_committee_performance(unmonitored_index, Epoch::new(0)),
+        None
+    );
+    assert_eq!(
+        validator_monitor.get_sync_committee_performance(validator_index, Epoch::new(1)),
+        None
+    );
+}
@@ -1,29 +1,157 @@
-use eth2::ok_or_error;
 use eth2::types::builder_bid::SignedBuilderBid;
 use eth2::types::{
-    BlindedPayload, EthSpec, ExecPayload, ExecutionBlockHash, ExecutionPayload,
+    BlindedPayload, EthSpec, ExecPayload, ExecutionBlockHash, ExecutionPayload, ForkName,
     ForkVersionedResponse, PublicKeyBytes, SignedBeaconBlock, SignedValidatorRegistrationData,
-    Slot,
+    Slot, Uint256,
 };
 pub use eth2::Error;
-use reqwest::{IntoUrl, Response};
+use eth2::{ok_or_error, CONSENSUS_VERSION_HEADER};
+use reqwest::header::{HeaderValue, ACCEPT, CONTENT_TYPE};
+use reqwest::{IntoUrl, RequestBuilder, Response};
 use sensitive_url::SensitiveUrl;
 use serde::de::DeserializeOwned;
-use serde::Serialize;
-use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use ssz::Decode;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
 
 pub const DEFAULT_GET_HEADER_TIMEOUT_MILLIS: u64 = 500;
+pub const DEFAULT_TIMEOUT_MILLIS: u64 = 15_000;
 
-#[derive(Clone)]
+/// The longest permitted timeout for `GET /eth/v1/builder/header`.
+///
+/// The header is requested whilst producing a block, so a slow builder must not be able to delay
+/// the proposal for a significant portion of the slot.
+pub const MAX_GET_HEADER_TIMEOUT: Duration = Duration::from_secs(4);
+
+/// The longest permitted timeout for any other builder request.
+pub const MAX_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Requests SSZ responses, whilst still accepting JSON from builders which don't support SSZ.
+const PREFER_SSZ_ACCEPT_HEADER: &str = "application/octet-stream;q=1.0,application/json;q=0.9";
+
+const SSZ_CONTENT_TYPE: &str = "application/octet-stream";
+
+/// The time taken by each phase of a request to a builder.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ResponseTimings {
+    /// The time from sending the request until the response headers were received.
+    pub time_to_first_byte: Duration,
+    /// The time taken to read and deserialize the response body.
+    pub deserialization: Duration,
+}
+
+/// A response from a builder, along with the time taken by each phase of the request.
+#[derive(Debug)]
+pub struct TimedResponse<T> {
+    pub data: T,
+    pub timings: ResponseTimings,
+}
+
+/// The timeouts for each of the builder API endpoints.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Timeouts {
-    get_header: Duration,
+    #[serde(with = "millis")]
+    pub get_header: Duration,
+    #[serde(with = "millis")]
+    pub post_validators: Duration,
+    #[serde(with = "millis")]
+    pub post_blinded_blocks: Duration,
+    #[serde(with = "millis")]
+    pub get_builder_status: Duration,
 }
 
 impl Default for Timeouts {
     fn default() -> Self {
         Self {
             get_header: Duration::from_millis(DEFAULT_GET_HEADER_TIMEOUT_MILLIS),
+            post_validators: Duration::from_millis(DEFAULT_TIMEOUT_MILLIS),
+            post_blinded_blocks: Duration::from_millis(DEFAULT_TIMEOUT_MILLIS),
+            get_builder_status: Duration::from_millis(DEFAULT_TIMEOUT_MILLIS),
+        }
+    }
+}
+
+impl Timeouts {
+    /// Returns an error if any timeout is zero or longer than permitted.
+    pub fn validate(&self) -> Result<(), String> {
+        let check = |name: &str, timeout: Duration, max: Duration| {
+            if timeout.is_zero() {
+                Err(format!("The builder {} timeout must be non-zero", name))
+            } else if timeout > max {
+                Err(format!(
+                    "The builder {} timeout of {}ms exceeds the maximum of {}ms",
+                    name,
+                    timeout.as_millis(),
+                    max.as_millis()
+                ))
+            } else {
+                Ok(())
+            }
+        };
+
+        check("get_header", self.get_header, MAX_GET_HEADER_TIMEOUT)?;
+        check("post_validators", self.post_validators, MAX_TIMEOUT)?;
+        check("post_blinded_blocks", self.post_blinded_blocks, MAX_TIMEOUT)?;
+        check("get_builder_status", self.get_builder_status, MAX_TIMEOUT)
+    }
+}
+
+/// The configuration of a connection to a builder (or relay).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Config {
+    /// The URL of the builder.
+    pub url: SensitiveUrl,
+    #[serde(default)]
+    pub timeouts: Timeouts,
+    /// Request SSZ encoded responses from the builder, falling back to JSON.
+    #[serde(default)]
+    pub prefer_ssz: bool,
+    /// Bids with a value (in wei) below this amount are ignored in favour of a local payload.
+    #[serde(default, with = "eth2_serde_utils::quoted_u256")]
+    pub min_bid: Uint256,
+    /// Overrides the `User-Agent` header sent to the builder.
+    #[serde(default)]
+    pub user_agent: Option<String>,
+    /// The pubkey with which the builder signs its bids. Bids which aren't signed by this key are
+    /// ignored.
+    ///
+    /// If unset, the builder's bids are trusted without verification.
+    #[serde(default)]
+    pub pubkey: Option<PublicKeyBytes>,
+}
+
+impl Config {
+    pub fn new(url: SensitiveUrl) -> Self {
+        Self {
+            url,
+            timeouts: Timeouts::default(),
+            prefer_ssz: false,
+            min_bid: Uint256::zero(),
+            user_agent: None,
+            pubkey: None,
+        }
+    }
+
+    /// Returns an error if the configuration can't be used to connect to a builder.
+    pub fn validate(&self) -> Result<(), String> {
+        match self.url.full.scheme() {
+            "http" | "https" => (),
+            scheme => {
+                return Err(format!(
+                    "Unsupported builder URL scheme {}, expected http or https",
+                    scheme
+                ))
+            }
+        }
+
+        if let Some(user_agent) = &self.user_agent {
+            HeaderValue::from_str(user_agent)
+                .map_err(|e| format!("Invalid builder user agent {}: {}", user_agent, e))?;
         }
+
+        self.timeouts.validate()
     }
 }
 
@@ -32,31 +160,73 @@ pub struct BuilderHttpClient {
     client: reqwest::Client,
     server: SensitiveUrl,
     timeouts: Timeouts,
+    prefer_ssz: bool,
+    min_bid: Uint256,
+    pubkey: Option<PublicKeyBytes>,
 }
 
 impl BuilderHttpClient {
     pub fn new(server: SensitiveUrl) -> Result<Self, Error> {
-        Ok(Self {
-            client: reqwest::Client::new(),
-            server,
-            timeouts: Timeouts::default(),
-        })
+        Self::new_with_timeouts(server, Timeouts::default())
     }
 
     pub fn new_with_timeouts(server: SensitiveUrl, timeouts: Timeouts) -> Result<Self, Error> {
-        Ok(Self {
-            client: reqwest::Client::new(),
-            server,
+        Self::from_config(&Config {
             timeouts,
+            ..Config::new(server)
         })
     }
 
-    async fn get<T: DeserializeOwned, U: IntoUrl>(&self, url: U) -> Result<T, Error> {
-        self.get_response_with_timeout(url, None)
-            .await?
-            .json()
-            .await
-            .map_err(Error::Reqwest)
+    /// Instantiate a client from `config`, which should have been checked with
+    /// `Config::validate`.
+    pub fn from_config(config: &Config) -> Result<Self, Error> {
+        let mut builder = reqwest::Client::builder();
+        if let Some(user_agent) = &config.user_agent {
+            builder = builder.user_agent(user_agent);
+        }
+
+        Ok(Self {
+            client: builder.build()?,
+            server: config.url.clone(),
+            timeouts: config.timeouts.clone(),
+            prefer_ssz: config.prefer_ssz,
+            min_bid: config.min_bid,
+            pubkey: config.pubkey,
+        })
+    }
+
+    /// Returns the URL of the builder this client connects to.
+    pub fn server(&self) -> &SensitiveUrl {
+        &self.server
+    }
+
+    /// Returns the timeouts applied to each request.
+    pub fn timeouts(&self) -> &Timeouts {
+        &self.timeouts
+    }
+
+    /// Returns the minimum value of a bid which should be used in place of a local payload.
+    pub fn min_bid(&self) -> Uint256 {
+        self.min_bid
+    }
+
+    /// Returns the pubkey with which the builder's bids must be signed, if any.
+    pub fn pubkey(&self) -> Option<&PublicKeyBytes> {
+        self.pubkey.as_ref()
+    }
+
+    /// Returns `true` if responses are requested as SSZ in preference to JSON.
+    pub fn prefer_ssz(&self) -> bool {
+        self.prefer_ssz
+    }
+
+    /// Set the `Accept` header according to the SSZ preference.
+    fn accept(&self, builder: RequestBuilder) -> RequestBuilder {
+        if self.prefer_ssz {
+            builder.header(ACCEPT, PREFER_SSZ_ACCEPT_HEADER)
+        } else {
+            builder
+        }
     }
 
     async fn get_with_timeout<T: DeserializeOwned, U: IntoUrl>(
@@ -64,25 +234,27 @@ impl BuilderHttpClient {
         url: U,
         timeout: Duration,
     ) -> Result<T, Error> {
-        self.get_response_with_timeout(url, Some(timeout))
-            .await?
-            .json()
-            .await
-            .map_err(Error::Reqwest)
+        let (response, _) = self.get_response_with_timeout(url, Some(timeout)).await?;
+        response.json().await.map_err(Error::Reqwest)
     }
 
-    /// Perform a HTTP GET request, returning the `Response` for further processing.
+    /// Perform a HTTP GET request, returning the `Response` for further processing along with the
+    /// time taken to receive its headers.
+    ///
+    /// The timeout applies to the whole request, including reading the body of the response.
     async fn get_response_with_timeout<U: IntoUrl>(
         &self,
         url: U,
         timeout: Option<Duration>,
-    ) -> Result<Response, Error> {
-        let mut builder = self.client.get(url);
+    ) -> Result<(Response, Duration), Error> {
+        let mut builder = self.accept(self.client.get(url));
         if let Some(timeout) = timeout {
             builder = builder.timeout(timeout);
         }
+        let start = Instant::now();
         let response = builder.send().await.map_err(Error::Reqwest)?;
-        ok_or_error(response).await
+        let time_to_first_byte = start.elapsed();
+        Ok((ok_or_error(response).await?, time_to_first_byte))
     }
 
     /// Generic POST function supporting arbitrary responses and timeouts.
@@ -104,10 +276,11 @@ impl BuilderHttpClient {
         &self,
         url: U,
         body: &T,
+        timeout: Duration,
     ) -> Result<Response, Error> {
         let response = self
-            .client
-            .post(url)
+            .accept(self.client.post(url))
+            .timeout(timeout)
             .json(body)
             .send()
             .await
@@ -115,6 +288,33 @@ impl BuilderHttpClient {
         ok_or_error(response).await
     }
 
+    /// Decode a fork-versioned response, using SSZ if the builder responded with it.
+    async fn fork_versioned_response<T: DeserializeOwned>(
+        response: Response,
+        from_ssz_bytes: impl FnOnce(&[u8]) -> Result<T, ssz::DecodeError>,
+    ) -> Result<ForkVersionedResponse<T>, Error> {
+        let is_ssz = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|content_type| content_type.to_str().ok())
+            .map_or(false, |content_type| {
+                content_type.starts_with(SSZ_CONTENT_TYPE)
+            });
+
+        if is_ssz {
+            let version = response
+                .headers()
+                .get(CONSENSUS_VERSION_HEADER)
+                .and_then(|version| version.to_str().ok())
+                .and_then(|version| ForkName::from_str(version).ok());
+            let bytes = response.bytes().await?;
+            let data = from_ssz_bytes(&bytes).map_err(Error::InvalidSsz)?;
+            Ok(ForkVersionedResponse { version, data })
+        } else {
+            response.json().await.map_err(Error::Reqwest)
+        }
+    }
+
     /// `POST /eth/v1/builder/validators`
     pub async fn post_builder_validators(
         &self,
@@ -129,7 +329,8 @@ impl BuilderHttpClient {
             .push("builder")
             .push("validators");
 
-        self.post_generic(path, &validator, None).await?;
+        self.post_generic(path, &validator, Some(self.timeouts.post_validators))
+            .await?;
         Ok(())
     }
 
@@ -147,20 +348,22 @@ impl BuilderHttpClient {
             .push("builder")
             .push("blinded_blocks");
 
-        Ok(self
-            .post_with_raw_response(path, &blinded_block)
-            .await?
-            .json()
-            .await?)
+        let response = self
+            .post_with_raw_response(path, &blinded_block, self.timeouts.post_blinded_blocks)
+            .await?;
+        Self::fork_versioned_response(response, ExecutionPayload::from_ssz_bytes).await
     }
 
     /// `GET /eth/v1/builder/header`
+    ///
+    /// The timings distinguish a builder which is slow to respond from a bid which is slow to
+    /// deserialize.
     pub async fn get_builder_header<E: EthSpec, Payload: ExecPayload<E>>(
         &self,
         slot: Slot,
         parent_hash: ExecutionBlockHash,
         pubkey: &PublicKeyBytes,
-    ) -> Result<ForkVersionedResponse<SignedBuilderBid<E, Payload>>, Error> {
+    ) -> Result<TimedResponse<ForkVersionedResponse<SignedBuilderBid<E, Payload>>>, Error> {
         let mut path = self.server.full.clone();
 
         path.path_segments_mut()
@@ -173,7 +376,20 @@ impl BuilderHttpClient {
             .push(format!("{parent_hash:?}").as_str())
             .push(pubkey.as_hex_string().as_str());
 
-        self.get_with_timeout(path, self.timeouts.get_header).await
+        let (response, time_to_first_byte) = self
+            .get_response_with_timeout(path, Some(self.timeouts.get_header))
+            .await?;
+        let start = Instant::now();
+        let data =
+            Self::fork_versioned_response(response, SignedBuilderBid::from_ssz_bytes).await?;
+
+        Ok(TimedResponse {
+            data,
+            timings: ResponseTimings {
+                time_to_first_byte,
+                deserialization: start.elapsed(),
+            },
+        })
     }
 
     /// `GET /eth/v1/builder/status`
@@ -187,6 +403,189 @@ impl BuilderHttpClient {
             .push("builder")
             .push("status");
 
-        self.get(path).await
+        self.get_with_timeout(path, self.timeouts.get_builder_status)
+            .await
+    }
+}
+
+/// Serialize a `Duration` as a number of milliseconds.
+mod millis {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::time::Duration;
+
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(duration.as_millis() as u64)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        u64::deserialize(deserializer).map(Duration::from_millis)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use eth2::types::builder_bid::BuilderBid;
+    use eth2::types::{MainnetEthSpec, Signature};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    type E = MainnetEthSpec;
+
+    fn config() -> Config {
+        Config::new(SensitiveUrl::parse("http://localhost:18550").unwrap())
+    }
+
+    #[test]
+    fn validate() {
+        assert_eq!(config().validate(), Ok(()));
+
+        let mut config = config();
+        config.timeouts.get_header = Duration::ZERO;
+        assert!(config.validate().is_err());
+
+        config.timeouts.get_header = MAX_GET_HEADER_TIMEOUT + Duration::from_millis(1);
+        assert!(config.validate().is_err());
+
+        config.timeouts.get_header = MAX_GET_HEADER_TIMEOUT;
+        config.timeouts.post_blinded_blocks = MAX_TIMEOUT * 2;
+        assert!(config.validate().is_err());
+
+        let mut config = self::config();
+        config.user_agent = Some("invalid\nagent".to_string());
+        assert!(config.validate().is_err());
+
+        let mut config = self::config();
+        config.url = SensitiveUrl::parse("ws://localhost:18550").unwrap();
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn timeouts_serde_defaults() {
+        let timeouts: Timeouts = serde_json::from_str(r#"{"get_header": 750}"#).unwrap();
+        assert_eq!(
+            timeouts,
+            Timeouts {
+                get_header: Duration::from_millis(750),
+                ..Timeouts::default()
+            }
+        );
+        assert_eq!(
+            serde_json::from_str::<Timeouts>(&serde_json::to_string(&timeouts).unwrap()).unwrap(),
+            timeouts
+        );
+    }
+
+    #[tokio::test]
+    async fn get_header_times_out() {
+        // Accept connections but never respond to them.
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let mut sockets = vec![];
+            while let Ok((socket, _)) = listener.accept().await {
+                sockets.push(socket);
+            }
+        });
+
+        let mut config =
+            Config::new(SensitiveUrl::parse(&format!("http://127.0.0.1:{}", port)).unwrap());
+        config.timeouts.get_header = Duration::from_millis(50);
+        let client = BuilderHttpClient::from_config(&config).unwrap();
+        assert_eq!(client.timeouts().get_header, Duration::from_millis(50));
+
+        let result = tokio::time::timeout(
+            Duration::from_secs(5),
+            client.get_builder_header::<MainnetEthSpec, BlindedPayload<MainnetEthSpec>>(
+                Slot::new(1),
+                ExecutionBlockHash::zero(),
+                &PublicKeyBytes::empty(),
+            ),
+        )
+        .await
+        .expect("request should time out before the test deadline");
+
+        match result {
+            Err(Error::Reqwest(e)) => assert!(e.is_timeout(), "{:?}", e),
+            other => panic!("expected a timeout, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    /// Serve a single bid over HTTP, delaying the response headers by `header_delay` and the body
+    /// by a further `body_delay`. Returns a client for the server.
+    async fn serve_bid(header_delay: Duration, body_delay: Duration) -> BuilderHttpClient {
+        let bid = SignedBuilderBid::<E, BlindedPayload<E>> {
+            message: BuilderBid::new(
+                BlindedPayload::default(),
+                Uint256::from(1),
+                PublicKeyBytes::empty(),
+            ),
+            signature: Signature::empty(),
+        };
+        let body = serde_json::to_vec(&ForkVersionedResponse {
+            version: Some(ForkName::Merge),
+            data: bid,
+        })
+        .unwrap();
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+
+            // Read the whole request, which has no body.
+            let mut request = vec![];
+            while !request.ends_with(b"\r\n\r\n") {
+                let mut buf = [0; 1024];
+                let n = socket.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+
+            tokio::time::sleep(header_delay).await;
+            let headers = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n",
+                body.len()
+            );
+            socket.write_all(headers.as_bytes()).await.unwrap();
+            socket.flush().await.unwrap();
+
+            tokio::time::sleep(body_delay).await;
+            socket.write_all(&body).await.unwrap();
+            socket.flush().await.unwrap();
+        });
+
+        let config =
+            Config::new(SensitiveUrl::parse(&format!("http://127.0.0.1:{}", port)).unwrap());
+        BuilderHttpClient::from_config(&config).unwrap()
+    }
+
+    async fn get_header_timings(client: &BuilderHttpClient) -> ResponseTimings {
+        client
+            .get_builder_header::<E, BlindedPayload<E>>(
+                Slot::new(1),
+                ExecutionBlockHash::zero(),
+                &PublicKeyBytes::empty(),
+            )
+            .await
+            .expect("should get header")
+            .timings
+    }
+
+    #[tokio::test]
+    async fn get_header_timings_attribute_delayed_headers() {
+        let delay = Duration::from_millis(200);
+        let client = serve_bid(delay, Duration::ZERO).await;
+        let timings = get_header_timings(&client).await;
+        assert!(timings.time_to_first_byte >= delay, "{:?}", timings);
+        assert!(timings.deserialization < delay, "{:?}", timings);
+    }
+
+    #[tokio::test]
+    async fn get_header_timings_attribute_delayed_body() {
+        let delay = Duration::from_millis(200);
+        let client = serve_bid(Duration::ZERO, delay).await;
+        let timings = get_header_timings(&client).await;
+        assert!(timings.time_to_first_byte < delay, "{:?}", timings);
+        assert!(timings.deserialization >= delay, "{:?}", timings);
     }
 }
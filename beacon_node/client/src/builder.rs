@@ -1,27 +1,38 @@
-use crate::config::{ClientGenesis, Config as ClientConfig};
+use crate::chain_health::BeaconChainHealth;
+use crate::checkpoint_sync::{
+    fetch_checkpoint, fetch_deposit_snapshot, load_deposit_snapshot_file,
+};
+use crate::config::{ClientGenesis, Config as ClientConfig, LevelOverrides};
+use crate::datadir_version::DataDirVersionFile;
+use crate::disk_space::{Config as DiskSpaceConfig, DiskSpaceMonitor, SystemDiskSpace};
 use crate::notifier::spawn_notifier;
+use crate::remote_genesis::{fetch_remote_genesis_state, REMOTE_GENESIS_CACHE_FILENAME};
 use crate::Client;
 use beacon_chain::proposer_prep_service::start_proposer_prep_service;
 use beacon_chain::schema_change::migrate_schema;
+use beacon_chain::self_test::{disk_write_check, SelfTestCheck};
 use beacon_chain::{
     builder::{BeaconChainBuilder, Witness},
     eth1_chain::{CachingEth1Backend, Eth1Chain},
     slot_clock::{SlotClock, SystemTimeSlotClock},
     state_advance_timer::spawn_state_advance_timer,
-    store::{HotColdDB, ItemStore, LevelDB, StoreConfig},
+    store::{
+        config::StoreConfigError, hot_cold_store::HotColdDBError, metadata::CURRENT_SCHEMA_VERSION,
+        Error as StoreError, HotColdDB, ItemStore, LevelDB, StoreConfig,
+    },
+    validator_monitor_file::{spawn_validator_monitor_file_reloader, ValidatorMonitorFile},
     BeaconChain, BeaconChainTypes, Eth1ChainBackend, ServerSentEventHandler,
 };
+use builder_client::BuilderHttpClient;
 use environment::RuntimeContext;
 use eth1::{Config as Eth1Config, Service as Eth1Service};
-use eth2::{
-    types::{BlockId, StateId},
-    BeaconNodeHttpClient, Error as ApiError, Timeouts,
-};
+use eth2::types::{SelfTestSeverity, SelfTestStatus};
 use execution_layer::ExecutionLayer;
 use genesis::{interop_genesis_state, Eth1GenesisService, DEFAULT_ETH1_BLOCK_HASH};
 use lighthouse_network::{prometheus_client::registry::Registry, NetworkGlobals};
 use monitoring_api::{MonitoringHttpClient, ProcessType};
 use network::{NetworkConfig, NetworkMessage, NetworkService};
+use sensitive_url::SensitiveUrl;
 use slasher::Slasher;
 use slasher_service::SlasherService;
 use slog::{debug, info, warn, Logger};
@@ -32,16 +43,13 @@ use std::time::Duration;
 use timer::spawn_timer;
 use tokio::sync::{mpsc::UnboundedSender, oneshot};
 use types::{
-    test_utils::generate_deterministic_keypairs, BeaconState, ChainSpec, EthSpec,
-    ExecutionBlockHash, Hash256, SignedBeaconBlock,
+    test_utils::generate_deterministic_keypairs, BeaconState, ChainSpec, DepositTreeSnapshot,
+    EthSpec, ExecutionBlockHash, Hash256, SignedBeaconBlock,
 };
 
 /// Interval between polling the eth1 node for genesis information.
 pub const ETH1_GENESIS_UPDATE_INTERVAL_MILLIS: u64 = 7_000;
 
-/// Timeout for checkpoint sync HTTP requests.
-pub const CHECKPOINT_SYNC_HTTP_TIMEOUT: Duration = Duration::from_secs(60);
-
 /// Builds a `Client` instance.
 ///
 /// ## Notes
@@ -64,6 +72,9 @@ pub struct ClientBuilder<T: BeaconChainTypes> {
     beacon_chain_builder: Option<BeaconChainBuilder<T>>,
     beacon_chain: Option<Arc<BeaconChain<T>>>,
     eth1_service: Option<Eth1Service>,
+    /// A snapshot of the deposit tree from which the eth1 deposit cache is initialised if there is
+    /// no persisted cache.
+    eth1_deposit_snapshot: Option<DepositTreeSnapshot>,
     network_globals: Option<Arc<NetworkGlobals<T::EthSpec>>>,
     network_send: Option<UnboundedSender<NetworkMessage<T::EthSpec>>>,
     gossipsub_registry: Option<Registry>,
@@ -71,7 +82,13 @@ pub struct ClientBuilder<T: BeaconChainTypes> {
     freezer_db_path: Option<PathBuf>,
     http_api_config: http_api::Config,
     http_metrics_config: http_metrics::Config,
+    effective_config: Option<ClientConfig>,
+    validator_monitor_file: Option<ValidatorMonitorFile>,
+    datadir_version_file: Option<DataDirVersionFile>,
+    disk_space_config: Option<DiskSpaceConfig>,
+    disk_space_monitor: Option<DiskSpaceMonitor<SystemDiskSpace>>,
     slasher: Option<Arc<Slasher<T::EthSpec>>>,
+    monitoring_config: Option<monitoring_api::Config>,
     eth_spec_instance: T::EthSpec,
 }
 
@@ -96,6 +113,7 @@ where
             beacon_chain_builder: None,
             beacon_chain: None,
             eth1_service: None,
+            eth1_deposit_snapshot: None,
             network_globals: None,
             network_send: None,
             gossipsub_registry: None,
@@ -103,7 +121,13 @@ where
             freezer_db_path: None,
             http_api_config: <_>::default(),
             http_metrics_config: <_>::default(),
+            effective_config: None,
+            validator_monitor_file: None,
+            datadir_version_file: None,
+            disk_space_config: None,
+            disk_space_monitor: None,
             slasher: None,
+            monitoring_config: None,
             eth_spec_instance,
         }
     }
@@ -114,12 +138,36 @@ where
         self
     }
 
+    /// Applies the per-module log level `overrides` to the logger of the runtime context, replacing
+    /// any overrides supplied when the logger was built.
+    pub fn log_level_overrides(self, overrides: LevelOverrides) -> Result<Self, String> {
+        let context = self
+            .runtime_context
+            .as_ref()
+            .ok_or("log_level_overrides requires a runtime_context")?;
+        if overrides != context.log_level_overrides.get() {
+            info!(
+                context.log(),
+                "Applying log level overrides";
+                "overrides" => %overrides,
+            );
+            context.log_level_overrides.set(overrides);
+        }
+        Ok(self)
+    }
+
     /// Specifies the `ChainSpec`.
     pub fn chain_spec(mut self, spec: ChainSpec) -> Self {
         self.chain_spec = Some(spec);
         self
     }
 
+    /// Specifies the free disk space thresholds for the databases opened by `disk_store`.
+    pub fn disk_space_config(mut self, config: DiskSpaceConfig) -> Self {
+        self.disk_space_config = Some(config);
+        self
+    }
+
     pub fn slasher(mut self, slasher: Arc<Slasher<TEthSpec>>) -> Self {
         self.slasher = Some(slasher);
         self
@@ -138,6 +186,7 @@ where
         let eth_spec_instance = self.eth_spec_instance.clone();
         let chain_config = config.chain.clone();
         let graffiti = config.graffiti;
+        let data_dir = config.get_data_dir();
 
         let store = store.ok_or("beacon_chain_start_method requires a store")?;
         let runtime_context =
@@ -150,35 +199,60 @@ where
             None
         };
 
+        let builder = config
+            .builder
+            .as_ref()
+            .map(|builder_config| {
+                builder_config.validate()?;
+                BuilderHttpClient::from_config(builder_config)
+                    .map_err(|e| format!("unable to create builder client: {:?}", e))
+            })
+            .transpose()?;
+
         let execution_layer = if let Some(config) = config.execution_layer {
             let context = runtime_context.service_context("exec".into());
-            let execution_layer = ExecutionLayer::from_config(
+            let execution_layer = ExecutionLayer::from_config_with_builder(
                 config,
+                builder,
                 context.executor.clone(),
                 context.log().clone(),
             )
             .map_err(|e| format!("unable to start execution layer endpoints: {:?}", e))?;
             Some(execution_layer)
+        } else if builder.is_some() {
+            return Err("a builder requires an execution endpoint".to_string());
         } else {
             None
         };
 
+        let validator_monitor_log = runtime_context
+            .service_context("val_mon".to_string())
+            .log()
+            .clone();
+        let mut validator_monitor_pubkeys = config.validator_monitor_pubkeys.clone();
+        if let Some(path) = config.validator_monitor_pubkeys_file.clone() {
+            let mut file = ValidatorMonitorFile::new(path);
+            let pubkeys = file
+                .read_pubkeys(&validator_monitor_log)
+                .map_err(|e| format!("Unable to read validator monitor file: {}", e))?;
+            validator_monitor_pubkeys.extend(pubkeys);
+            self.validator_monitor_file = Some(file);
+        }
+
         let builder = BeaconChainBuilder::new(eth_spec_instance)
             .logger(context.log().clone())
             .store(store)
             .task_executor(context.executor.clone())
             .custom_spec(spec.clone())
             .chain_config(chain_config)
+            .data_dir(data_dir)
             .graffiti(graffiti)
             .event_handler(event_handler)
             .execution_layer(execution_layer)
             .monitor_validators(
                 config.validator_monitor_auto,
-                config.validator_monitor_pubkeys.clone(),
-                runtime_context
-                    .service_context("val_mon".to_string())
-                    .log()
-                    .clone(),
+                validator_monitor_pubkeys,
+                validator_monitor_log,
             );
 
         let builder = if let Some(slasher) = self.slasher.clone() {
@@ -187,6 +261,15 @@ where
             builder
         };
 
+        if config.purge_eth1_cache_on_startup {
+            info!(context.log(), "Purging persisted eth1 cache");
+            builder.purge_persisted_eth1_backend()?;
+        }
+
+        if let Some(path) = &config.eth1_deposit_snapshot_file {
+            self.eth1_deposit_snapshot = Some(load_deposit_snapshot_file(path)?);
+        }
+
         let chain_exists = builder.store_contains_beacon_chain().unwrap_or(false);
 
         // If the client is expect to resume but there's no beacon chain in the database,
@@ -244,6 +327,28 @@ where
 
                 builder.genesis_state(genesis_state).map(|v| (v, None))?
             }
+            ClientGenesis::RemoteSsz { url, checksum } => {
+                info!(
+                    context.log(),
+                    "Starting from remote genesis state";
+                    "remote_url" => %url,
+                );
+
+                let cache_path = config
+                    .create_data_dir()?
+                    .join(REMOTE_GENESIS_CACHE_FILENAME);
+                let genesis_state = fetch_remote_genesis_state(
+                    &url,
+                    checksum,
+                    Duration::from_secs(config.genesis_state_url_timeout),
+                    &cache_path,
+                    &spec,
+                    context.log(),
+                )
+                .await?;
+
+                builder.genesis_state(genesis_state).map(|v| (v, None))?
+            }
             ClientGenesis::WeakSubjSszBytes {
                 anchor_state_bytes,
                 anchor_block_bytes,
@@ -266,100 +371,34 @@ where
                 genesis_state_bytes,
                 url,
             } => {
+                let snapshot_url = url.clone();
                 info!(
                     context.log(),
                     "Starting checkpoint sync";
                     "remote_url" => %url,
                 );
 
-                let remote =
-                    BeaconNodeHttpClient::new(url, Timeouts::set_all(CHECKPOINT_SYNC_HTTP_TIMEOUT));
-                let slots_per_epoch = TEthSpec::slots_per_epoch();
-
-                debug!(context.log(), "Downloading finalized block");
-
-                // Find a suitable finalized block on an epoch boundary.
-                let mut block = remote
-                    .get_beacon_blocks_ssz::<TEthSpec>(BlockId::Finalized, &spec)
-                    .await
-                    .map_err(|e| match e {
-                        ApiError::InvalidSsz(e) => format!(
-                            "Unable to parse SSZ: {:?}. Ensure the checkpoint-sync-url refers to a \
-                            node for the correct network",
-                            e
-                        ),
-                        e => format!("Error fetching finalized block from remote: {:?}", e),
-                    })?
-                    .ok_or("Finalized block missing from remote, it returned 404")?;
-
-                debug!(context.log(), "Downloaded finalized block");
-
-                let mut block_slot = block.slot();
-
-                while block.slot() % slots_per_epoch != 0 {
-                    block_slot = (block_slot / slots_per_epoch - 1) * slots_per_epoch;
-
-                    debug!(
-                        context.log(),
-                        "Searching for aligned checkpoint block";
-                        "block_slot" => block_slot,
-                    );
-
-                    debug!(
-                        context.log(),
-                        "Searching for aligned checkpoint block";
-                        "block_slot" => block_slot
-                    );
-
-                    if let Some(found_block) = remote
-                        .get_beacon_blocks_ssz::<TEthSpec>(BlockId::Slot(block_slot), &spec)
-                        .await
-                        .map_err(|e| {
-                            format!("Error fetching block at slot {}: {:?}", block_slot, e)
-                        })?
-                    {
-                        block = found_block;
-                    }
-                }
-
-                debug!(
-                    context.log(),
-                    "Downloaded aligned finalized block";
-                    "block_root" => ?block.canonical_root(),
-                    "block_slot" => block.slot(),
-                );
-
-                let state_root = block.state_root();
-                debug!(
-                    context.log(),
-                    "Downloading finalized state";
-                    "state_root" => ?state_root
-                );
-                let state = remote
-                    .get_debug_beacon_states_ssz::<TEthSpec>(StateId::Root(state_root), &spec)
-                    .await
-                    .map_err(|e| {
-                        format!(
-                            "Error loading checkpoint state from remote {:?}: {:?}",
-                            state_root, e
-                        )
-                    })?
-                    .ok_or_else(|| {
-                        format!("Checkpoint state missing from remote: {:?}", state_root)
-                    })?;
-
-                debug!(context.log(), "Downloaded finalized state");
-
                 let genesis_state = BeaconState::from_ssz_bytes(&genesis_state_bytes, &spec)
                     .map_err(|e| format!("Unable to parse genesis state SSZ: {:?}", e))?;
 
-                info!(
+                let (state, block) = fetch_checkpoint(
+                    url,
+                    Duration::from_secs(config.checkpoint_sync_url_timeout),
+                    &genesis_state,
+                    &spec,
                     context.log(),
-                    "Loaded checkpoint block and state";
-                    "slot" => block.slot(),
-                    "block_root" => ?block.canonical_root(),
-                    "state_root" => ?state_root,
-                );
+                )
+                .await?;
+
+                if self.eth1_deposit_snapshot.is_none() {
+                    self.eth1_deposit_snapshot = fetch_deposit_snapshot(
+                        snapshot_url,
+                        Duration::from_secs(config.checkpoint_sync_url_timeout),
+                        &state,
+                        context.log(),
+                    )
+                    .await;
+                }
 
                 builder
                     .weak_subjectivity_state(state, block, genesis_state)
@@ -399,6 +438,7 @@ where
                         network_tx: None,
                         network_globals: None,
                         eth1_service: Some(genesis_service.eth1_service.clone()),
+                        effective_config: self.effective_config_json(),
                         log: context.log().clone(),
                     });
 
@@ -522,6 +562,20 @@ where
         self
     }
 
+    /// Provides the effective config of the client (see `ClientConfig::effective`), which is served
+    /// via the HTTP API.
+    pub fn effective_config(mut self, config: ClientConfig) -> Self {
+        self.effective_config = Some(config);
+        self
+    }
+
+    /// Returns the effective config serialized as JSON, for use by the HTTP API.
+    fn effective_config_json(&self) -> Option<serde_json::Value> {
+        self.effective_config
+            .as_ref()
+            .and_then(|config| serde_json::to_value(config).ok())
+    }
+
     /// Provides configuration for the HTTP server that serves Prometheus metrics.
     pub fn http_metrics_config(mut self, config: http_metrics::Config) -> Self {
         self.http_metrics_config = config;
@@ -550,17 +604,62 @@ where
 
     /// Start the explorer client which periodically sends beacon
     /// and system metrics to the configured endpoint.
-    pub fn monitoring_client(self, config: &monitoring_api::Config) -> Result<Self, String> {
+    ///
+    /// The client is started when the `Client` is built, so that it can report the health of the
+    /// beacon chain.
+    pub fn monitoring_client(mut self, config: &monitoring_api::Config) -> Result<Self, String> {
+        // Check the endpoint now, rather than failing later during `build`.
+        SensitiveUrl::parse(&config.monitoring_endpoint)
+            .map_err(|e| format!("Invalid monitoring endpoint: {:?}", e))?;
+        self.monitoring_config = Some(config.clone());
+        Ok(self)
+    }
+
+    /// Start the monitoring client, reporting the health of the beacon chain if there is one.
+    fn start_monitoring_client(&self, config: &monitoring_api::Config) -> Result<(), String> {
         let context = self
             .runtime_context
             .as_ref()
             .ok_or("monitoring_client requires a runtime_context")?
             .service_context("monitoring_client".into());
-        let monitoring_client = MonitoringHttpClient::new(config, context.log().clone())?;
+        let mut monitoring_client = MonitoringHttpClient::new(config, context.log().clone())?;
+        if let Some(beacon_chain) = self.beacon_chain.clone() {
+            monitoring_client =
+                monitoring_client.with_chain_health(Arc::new(BeaconChainHealth::new(beacon_chain)));
+        }
         monitoring_client.auto_update(
             context.executor,
             vec![ProcessType::BeaconNode, ProcessType::System],
         );
+        Ok(())
+    }
+
+    /// Checks the components the node depends upon, including the eth1 endpoints and the data
+    /// directory at `data_dir`, logging a readiness report.
+    ///
+    /// The node continues to start regardless of the outcome, the report is served by the HTTP
+    /// API.
+    pub async fn self_test(self, data_dir: &Path) -> Result<Self, String> {
+        let beacon_chain = self
+            .beacon_chain
+            .clone()
+            .ok_or("self_test requires a beacon chain")?;
+
+        let eth1_service = self.eth1_service.clone();
+        let eth1 = SelfTestCheck::new("eth1", SelfTestSeverity::Degraded, async move {
+            match eth1_service {
+                Some(service) => match service.check_endpoints().await {
+                    Ok(()) => SelfTestStatus::Passed,
+                    Err(e) => SelfTestStatus::Failed(e),
+                },
+                None => SelfTestStatus::Skipped("no eth1 endpoint configured".into()),
+            }
+        });
+
+        beacon_chain
+            .run_self_test(vec![eth1, disk_write_check(data_dir.to_path_buf())])
+            .await;
+
         Ok(self)
     }
 
@@ -618,6 +717,7 @@ where
                 network_tx: self.network_send.clone(),
                 network_globals: self.network_globals.clone(),
                 eth1_service: self.eth1_service.clone(),
+                effective_config: self.effective_config_json(),
                 log: log.clone(),
             });
 
@@ -672,6 +772,10 @@ where
             self.start_slasher_service()?;
         }
 
+        if let Some(monitoring_config) = &self.monitoring_config {
+            self.start_monitoring_client(monitoring_config)?;
+        }
+
         if let Some(beacon_chain) = self.beacon_chain.as_ref() {
             let state_advance_context = runtime_context.service_context("state_advance".into());
             let state_advance_log = state_advance_context.log().clone();
@@ -722,12 +826,30 @@ where
                         beacon_chain.slot_clock.clone(),
                     );
 
+                    // Spawn a routine that reloads the suggested fee recipient file, if any.
+                    execution_layer.spawn_fee_recipient_file_reload_routine();
+
                     // Spawns a routine that polls the `exchange_transition_configuration` endpoint.
                     execution_layer.spawn_transition_configuration_poll(beacon_chain.spec.clone());
                 }
             }
 
             start_proposer_prep_service(runtime_context.executor.clone(), beacon_chain.clone());
+
+            if let Some(validator_monitor_file) = self.validator_monitor_file.take() {
+                let validator_monitor_context = runtime_context.service_context("val_mon".into());
+                let validator_monitor_log = validator_monitor_context.log().clone();
+                spawn_validator_monitor_file_reloader(
+                    validator_monitor_context.executor,
+                    beacon_chain.clone(),
+                    validator_monitor_file,
+                    validator_monitor_log,
+                );
+            }
+        }
+
+        if let Some(disk_space_monitor) = self.disk_space_monitor.take() {
+            disk_space_monitor.spawn(runtime_context.executor.clone());
         }
 
         Ok(Client {
@@ -735,6 +857,7 @@ where
             network_globals: self.network_globals,
             http_api_listen_addr,
             http_metrics_listen_addr,
+            effective_config: self.effective_config,
         })
     }
 }
@@ -768,6 +891,19 @@ where
             .build()
             .map_err(|e| format!("Failed to build beacon chain: {}", e))?;
 
+        // A read-only node must not modify its datadir, so the version file is only checked.
+        if let Some(datadir_version_file) = self
+            .datadir_version_file
+            .take()
+            .filter(|_| !chain.config.read_only)
+        {
+            datadir_version_file.update(
+                &chain.spec,
+                chain.genesis_validators_root,
+                context.log(),
+            )?;
+        }
+
         self.beacon_chain = Some(Arc::new(chain));
         self.beacon_chain_builder = None;
 
@@ -805,6 +941,20 @@ where
         self.db_path = Some(hot_path.into());
         self.freezer_db_path = Some(cold_path.into());
 
+        if let Some(disk_space_config) = self.disk_space_config.take() {
+            let disk_space_monitor = DiskSpaceMonitor::new(
+                disk_space_config,
+                vec![("hot", hot_path.into()), ("freezer", cold_path.into())],
+                SystemDiskSpace,
+                log.clone(),
+            );
+            disk_space_monitor.check_at_startup()?;
+            self.disk_space_monitor = Some(disk_space_monitor);
+        }
+
+        // Check the datadir belongs to this network before the database is opened or migrated.
+        self.datadir_version_file = Some(DataDirVersionFile::open(datadir, &spec, &log)?);
+
         let inner_spec = spec.clone();
         let schema_upgrade = |db, from, to| {
             migrate_schema::<Witness<TSlotClock, TEth1Backend, _, _, _>>(
@@ -825,7 +975,54 @@ where
             spec,
             context.log().clone(),
         )
-        .map_err(|e| format!("Unable to open database: {:?}", e))?;
+        .map_err(|e| match e {
+            StoreError::ConfigError(StoreConfigError::MismatchedSlotsPerRestorePoint {
+                config,
+                on_disk,
+            }) => format!(
+                "The database was created with --slots-per-restore-point {} but {} is \
+                 configured. Use --slots-per-restore-point {} or add \
+                 --force-restore-point-migration to migrate the database",
+                on_disk, config, on_disk
+            ),
+            StoreError::HotColdDBError(HotColdDBError::ReadOnlySchemaMigration {
+                on_disk_version,
+            }) => format!(
+                "The database can't be opened read-only because its schema version is {:?} \
+                 rather than {:?}. Start the node without --read-only to migrate it first",
+                on_disk_version.map(|version| version.as_u64()),
+                CURRENT_SCHEMA_VERSION.as_u64()
+            ),
+            StoreError::ReadOnlyDatabaseOverlay { path, message } => format!(
+                "Unable to open the read-only database at {}: {}. Read-only databases are opened \
+                 through a temporary directory, which may be set with TMPDIR",
+                path.display(),
+                message
+            ),
+            e => format!("Unable to open database: {:?}", e),
+        })?;
+
+        // The database may adjust its config to suit the existing data, so report what it uses.
+        let store_config = store.get_config();
+        info!(
+            context.log(),
+            "Opened database";
+            "slots_per_restore_point" => store.get_slots_per_restore_point(),
+            "block_cache_size" => store_config.block_cache_size,
+        );
+        if store.restore_point_migration_pending() {
+            info!(
+                context.log(),
+                "Restore points will be migrated in the background";
+                "slots_per_restore_point" => store_config.slots_per_restore_point,
+            );
+        }
+        if let Some(effective_config) = &mut self.effective_config {
+            effective_config.store = store_config.clone();
+            // Report the spacing of the restore points on disk until they have been migrated.
+            effective_config.store.slots_per_restore_point = store.get_slots_per_restore_point();
+        }
+
         self.store = Some(store);
         Ok(self)
     }
@@ -858,6 +1055,29 @@ where
             .clone()
             .ok_or("caching_eth1_backend requires a chain spec")?;
 
+        let snapshot = self.eth1_deposit_snapshot.take();
+        let new_backend = |config: Eth1Config| match snapshot {
+            Some(snapshot) => {
+                info!(
+                    context.log(),
+                    "Initialising deposit cache from snapshot";
+                    "deposit_count" => snapshot.deposit_count,
+                    "eth1_block_number" => snapshot.execution_block_height,
+                );
+                CachingEth1Backend::from_deposit_snapshot(
+                    config,
+                    context.log().clone(),
+                    spec.clone(),
+                    snapshot,
+                )
+            }
+            None => Ok(CachingEth1Backend::new(
+                config,
+                context.log().clone(),
+                spec.clone(),
+            )),
+        };
+
         let backend = if let Some(eth1_service_from_genesis) = self.eth1_service {
             eth1_service_from_genesis.update_config(config)?;
 
@@ -873,7 +1093,7 @@ where
 
             CachingEth1Backend::from_service(eth1_service_from_genesis)
         } else if config.purge_cache {
-            CachingEth1Backend::new(config, context.log().clone(), spec)
+            new_backend(config)?
         } else {
             beacon_chain_builder
                 .get_persisted_eth1_backend()?
@@ -886,15 +1106,27 @@ where
                     )
                     .map(|chain| chain.into_backend())
                 })
-                .unwrap_or_else(|| {
-                    Ok(CachingEth1Backend::new(
-                        config,
-                        context.log().clone(),
-                        spec.clone(),
-                    ))
-                })?
+                .unwrap_or_else(|| new_backend(config))?
         };
 
+        // Refuse to start with an endpoint on the wrong network, rather than failing to find
+        // deposits some time later.
+        if let Err(e) = backend.core.verify_network().await {
+            if backend.core.config().ignore_network_mismatch {
+                warn!(
+                    context.log(),
+                    "Ignoring eth1 network mismatch";
+                    "error" => e,
+                );
+            } else {
+                return Err(format!(
+                    "{}. Check the eth1 endpoints, or use --eth1-ignore-network-mismatch to \
+                     start regardless",
+                    e
+                ));
+            }
+        }
+
         self.eth1_service = Some(backend.core.clone());
 
         // Starts the service that connects to an eth1 node and periodically updates caches.
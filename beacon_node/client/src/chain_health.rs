@@ -0,0 +1,239 @@
+//! Provides the health of the `BeaconChain` to the monitoring API.
+use async_trait::async_trait;
+use beacon_chain::{BeaconChain, BeaconChainTypes, BLOCK_PROCESSING_CACHE_LOCK_TIMEOUT};
+use monitoring_api::{BlockDelayHealth, ChainHealthSource, ExecutionHealth, HeadHealth};
+use slot_clock::SlotClock;
+use std::sync::Arc;
+
+/// Reads the health of a `BeaconChain` for monitoring API reports.
+pub struct BeaconChainHealth<T: BeaconChainTypes> {
+    chain: Arc<BeaconChain<T>>,
+}
+
+impl<T: BeaconChainTypes> BeaconChainHealth<T> {
+    pub fn new(chain: Arc<BeaconChain<T>>) -> Self {
+        Self { chain }
+    }
+}
+
+#[async_trait]
+impl<T: BeaconChainTypes> ChainHealthSource for BeaconChainHealth<T> {
+    fn head_health(&self) -> HeadHealth {
+        let sync_status = match self.chain.sync_status() {
+            Ok(sync_status) => sync_status,
+            Err(_) => return HeadHealth::default(),
+        };
+
+        HeadHealth {
+            chain_head_slot: Some(sync_status.head_slot.as_u64()),
+            chain_finalized_epoch: Some(sync_status.finalized_epoch.as_u64()),
+            chain_sync_distance: Some(sync_status.sync_distance.as_u64()),
+        }
+    }
+
+    async fn execution_health(&self) -> ExecutionHealth {
+        let sync_execution_synced = match &self.chain.execution_layer {
+            Some(execution_layer) => Some(execution_layer.is_synced().await),
+            None => None,
+        };
+        ExecutionHealth {
+            sync_execution_synced,
+        }
+    }
+
+    fn block_delay_health(&self) -> BlockDelayHealth {
+        let block_times_cache = match self
+            .chain
+            .block_times_cache
+            .try_read_for(BLOCK_PROCESSING_CACHE_LOCK_TIMEOUT)
+        {
+            Some(block_times_cache) => block_times_cache,
+            None => return BlockDelayHealth::default(),
+        };
+
+        // Only consider blocks delivered by a peer, blocks we published ourselves aren't delayed
+        // by the network.
+        let mut delays = block_times_cache
+            .cache
+            .iter()
+            .filter(|(_, value)| value.peer_info.id.is_some())
+            .filter_map(|(block_root, value)| {
+                let slot_start = self.chain.slot_clock.start_of(value.slot)?;
+                block_times_cache
+                    .get_block_delays(*block_root, slot_start)
+                    .observed
+            })
+            .collect::<Vec<_>>();
+        drop(block_times_cache);
+
+        delays.sort_unstable();
+        BlockDelayHealth {
+            block_observed_delay_p95_ms: percentile(&delays, 95)
+                .map(|delay| delay.as_millis() as u64),
+        }
+    }
+}
+
+/// Returns the `p`th percentile of the sorted `values`, using the nearest-rank method.
+fn percentile<T: Copy>(values: &[T], p: usize) -> Option<T> {
+    let rank = (values.len() * p + 99) / 100;
+    values.get(rank.checked_sub(1)?).copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use beacon_chain::test_utils::{BeaconChainHarness, EphemeralHarnessType};
+    use beacon_chain::BlockDeliveryTransport;
+    use monitoring_api::{MonitoringHttpClient, ProcessType};
+    use std::time::Duration;
+    use types::MinimalEthSpec;
+
+    type E = MinimalEthSpec;
+
+    fn get_harness() -> BeaconChainHarness<EphemeralHarnessType<E>> {
+        let harness = BeaconChainHarness::builder(MinimalEthSpec)
+            .default_spec()
+            .deterministic_keypairs(8)
+            .fresh_ephemeral_store()
+            .mock_execution_layer()
+            .build();
+        harness.advance_slot();
+        harness
+    }
+
+    /// Renders a beacon report for `chain` as JSON.
+    async fn render_report<T: BeaconChainTypes>(
+        chain: Arc<BeaconChain<T>>,
+        config: monitoring_api::Config,
+    ) -> serde_json::Value {
+        let dir = tempfile::tempdir().unwrap();
+        let config = monitoring_api::Config {
+            monitoring_endpoint: "http://localhost:8080".to_string(),
+            db_path: Some(dir.path().join("chain_db")),
+            freezer_db_path: Some(dir.path().join("freezer_db")),
+            ..config
+        };
+        let client = MonitoringHttpClient::new(&config, environment::null_logger().unwrap())
+            .unwrap()
+            .with_chain_health(Arc::new(BeaconChainHealth::new(chain)));
+        let report = client.get_metrics(&ProcessType::BeaconNode).await.unwrap();
+        serde_json::to_value(&report).unwrap()
+    }
+
+    /// Record `block_root` as delivered by a peer `delay` into its slot.
+    fn observe_block<T: BeaconChainTypes>(
+        chain: &BeaconChain<T>,
+        block_root: types::Hash256,
+        delay: Duration,
+    ) {
+        let slot = chain
+            .store
+            .get_blinded_block(&block_root)
+            .unwrap()
+            .unwrap()
+            .slot();
+        let slot_start = chain.slot_clock.start_of(slot).unwrap();
+        chain.block_times_cache.write().set_time_observed(
+            block_root,
+            slot,
+            slot_start + delay,
+            BlockDeliveryTransport::Gossip,
+            Some("peer".to_string()),
+            Some("Lighthouse".to_string()),
+        );
+    }
+
+    #[tokio::test]
+    async fn report_includes_chain_health() {
+        let harness = get_harness();
+        harness.extend_slots(8).await;
+        harness.advance_slot();
+        harness.advance_slot();
+
+        let head = harness.chain.head_snapshot();
+        observe_block(
+            &harness.chain,
+            head.beacon_block_root,
+            Duration::from_millis(1_500),
+        );
+        observe_block(
+            &harness.chain,
+            head.beacon_block.parent_root(),
+            Duration::from_millis(300),
+        );
+
+        let report = render_report(harness.chain.clone(), <_>::default()).await;
+        let head_slot = head.beacon_block.slot();
+        assert_eq!(report["chain_head_slot"], head_slot.as_u64());
+        assert_eq!(
+            report["chain_finalized_epoch"],
+            harness
+                .chain
+                .canonical_head
+                .cached_head()
+                .finalized_checkpoint()
+                .epoch
+                .as_u64()
+        );
+        assert_eq!(
+            report["chain_sync_distance"],
+            (harness.chain.slot().unwrap() - head_slot).as_u64()
+        );
+        // The chain advanced two slots without any blocks.
+        assert!(report["chain_sync_distance"].as_u64().unwrap() >= 2);
+        assert!(report["sync_execution_synced"].is_boolean());
+        assert_eq!(report["block_observed_delay_p95_ms"], 1_500);
+    }
+
+    #[tokio::test]
+    async fn report_omits_disabled_groups() {
+        let harness = get_harness();
+        harness.extend_slots(2).await;
+
+        let config = monitoring_api::Config {
+            report_head: false,
+            report_execution_layer: false,
+            ..<_>::default()
+        };
+        let report = render_report(harness.chain.clone(), config).await;
+        assert!(report.get("chain_head_slot").is_none());
+        assert!(report.get("sync_execution_synced").is_none());
+        // No blocks were received from peers.
+        assert!(report["block_observed_delay_p95_ms"].is_null());
+        // The remainder of the report is unaffected.
+        assert_eq!(report["client_name"], "lighthouse");
+    }
+
+    // Holding the lock whilst rendering the report is the point of this test.
+    #[allow(clippy::await_holding_lock)]
+    #[tokio::test]
+    async fn report_degrades_to_nulls_when_locked() {
+        let harness = get_harness();
+        harness.extend_slots(2).await;
+        observe_block(
+            &harness.chain,
+            harness.chain.head_beacon_block_root(),
+            Duration::from_millis(500),
+        );
+
+        let block_times_cache = harness.chain.block_times_cache.clone();
+        let _write_lock = block_times_cache.write();
+        let report = render_report(harness.chain.clone(), <_>::default()).await;
+        assert!(report["block_observed_delay_p95_ms"].is_null());
+        assert_eq!(
+            report["chain_head_slot"],
+            harness.chain.head_snapshot().beacon_block.slot().as_u64()
+        );
+    }
+
+    #[test]
+    fn nearest_rank_percentile() {
+        assert_eq!(percentile::<u64>(&[], 95), None);
+        assert_eq!(percentile(&[7], 95), Some(7));
+        let values = (1..=100).collect::<Vec<u64>>();
+        assert_eq!(percentile(&values, 95), Some(95));
+        assert_eq!(percentile(&values, 100), Some(100));
+        assert_eq!(percentile(&[1, 2, 3], 50), Some(2));
+    }
+}
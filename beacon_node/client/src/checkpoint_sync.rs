@@ -0,0 +1,651 @@
+//! Downloads a finalized checkpoint block and state from a remote beacon node, for use with
+//! `ClientGenesis::CheckpointSyncUrl`.
+//!
+//! Requests which fail due to network errors or server errors are retried with an exponential
+//! backoff. The downloaded block and state are verified against each other and against the
+//! genesis state before they're returned.
+//!
+//! A snapshot of the deposit tree may also be downloaded, so that the eth1 deposit cache can be
+//! initialised without the deposit contract logs prior to the checkpoint.
+use eth2::{
+    types::{BlockId, GenericResponse, StateId},
+    BeaconNodeHttpClient, Error as ApiError, Timeouts,
+};
+use sensitive_url::SensitiveUrl;
+use serde_derive::Deserialize;
+use slog::{debug, info, warn, Logger};
+use std::fs::File;
+use std::future::Future;
+use std::path::Path;
+use std::time::Duration;
+use tokio::time::sleep;
+use types::{BeaconState, ChainSpec, DepositTreeSnapshot, EthSpec, SignedBeaconBlock};
+
+/// The maximum number of times each request to the remote beacon node is attempted.
+pub const CHECKPOINT_SYNC_MAX_ATTEMPTS: usize = 5;
+
+/// The delay before the first retry of a failed request. It is doubled for each subsequent retry.
+pub const CHECKPOINT_SYNC_INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Download an epoch-aligned finalized block and its state from the beacon node at `url`.
+///
+/// Each request will time out after `timeout`.
+pub async fn fetch_checkpoint<E: EthSpec>(
+    url: SensitiveUrl,
+    timeout: Duration,
+    genesis_state: &BeaconState<E>,
+    spec: &ChainSpec,
+    log: &Logger,
+) -> Result<(BeaconState<E>, SignedBeaconBlock<E>), String> {
+    fetch_checkpoint_with_backoff(
+        url,
+        timeout,
+        CHECKPOINT_SYNC_INITIAL_BACKOFF,
+        genesis_state,
+        spec,
+        log,
+    )
+    .await
+}
+
+async fn fetch_checkpoint_with_backoff<E: EthSpec>(
+    url: SensitiveUrl,
+    timeout: Duration,
+    initial_backoff: Duration,
+    genesis_state: &BeaconState<E>,
+    spec: &ChainSpec,
+    log: &Logger,
+) -> Result<(BeaconState<E>, SignedBeaconBlock<E>), String> {
+    let remote = BeaconNodeHttpClient::new(url, Timeouts::set_all(timeout));
+    let slots_per_epoch = E::slots_per_epoch();
+
+    debug!(log, "Downloading finalized block");
+
+    // Find a suitable finalized block on an epoch boundary.
+    let mut block = with_retries("finalized block", initial_backoff, log, || {
+        remote.get_beacon_blocks_ssz::<E>(BlockId::Finalized, spec)
+    })
+    .await?
+    .ok_or("Finalized block missing from remote, it returned 404")?;
+
+    debug!(log, "Downloaded finalized block");
+
+    let mut block_slot = block.slot();
+
+    while block.slot() % slots_per_epoch != 0 {
+        block_slot = (block_slot / slots_per_epoch - 1) * slots_per_epoch;
+
+        debug!(
+            log,
+            "Searching for aligned checkpoint block";
+            "block_slot" => block_slot,
+        );
+
+        if let Some(found_block) = with_retries("aligned block", initial_backoff, log, || {
+            remote.get_beacon_blocks_ssz::<E>(BlockId::Slot(block_slot), spec)
+        })
+        .await?
+        {
+            block = found_block;
+        }
+    }
+
+    debug!(
+        log,
+        "Downloaded aligned finalized block";
+        "block_root" => ?block.canonical_root(),
+        "block_slot" => block.slot(),
+    );
+
+    let state_root = block.state_root();
+    debug!(
+        log,
+        "Downloading finalized state";
+        "state_root" => ?state_root
+    );
+    let mut state = with_retries("finalized state", initial_backoff, log, || {
+        remote.get_debug_beacon_states_ssz::<E>(StateId::Root(state_root), spec)
+    })
+    .await?
+    .ok_or_else(|| format!("Checkpoint state missing from remote: {:?}", state_root))?;
+
+    debug!(log, "Downloaded finalized state");
+
+    // Computing the state root also primes the tree hash cache, so the root won't need to be
+    // recomputed from scratch when the state is imported.
+    let computed_state_root = state
+        .update_tree_hash_cache()
+        .map_err(|e| format!("Error computing checkpoint state root: {:?}", e))?;
+    if computed_state_root != state_root {
+        return Err(format!(
+            "Checkpoint state does not match block, expected state root {:?} but got {:?}",
+            state_root, computed_state_root
+        ));
+    }
+
+    if state.genesis_validators_root() != genesis_state.genesis_validators_root() {
+        return Err(format!(
+            "Checkpoint state appears to be from the wrong network. Genesis validators root \
+             is {:?} but should be {:?}",
+            state.genesis_validators_root(),
+            genesis_state.genesis_validators_root()
+        ));
+    }
+
+    info!(
+        log,
+        "Loaded checkpoint block and state";
+        "msg" => "verify that the block root matches a second trusted source",
+        "epoch" => block.slot().epoch(slots_per_epoch),
+        "slot" => block.slot(),
+        "block_root" => ?block.canonical_root(),
+        "state_root" => ?state_root,
+    );
+
+    Ok((state, block))
+}
+
+/// Download a snapshot of the deposit tree at the `eth1_data` of the checkpoint `state` from the
+/// beacon node at `url`, so that the deposit cache doesn't need to be built from the deposit
+/// contract logs since genesis.
+///
+/// Returns `None` if the remote doesn't serve deposit snapshots or the snapshot doesn't match
+/// `state`, in which case the deposit cache should be built from the logs.
+pub async fn fetch_deposit_snapshot<E: EthSpec>(
+    url: SensitiveUrl,
+    timeout: Duration,
+    state: &BeaconState<E>,
+    log: &Logger,
+) -> Option<DepositTreeSnapshot> {
+    fetch_deposit_snapshot_with_backoff(url, timeout, CHECKPOINT_SYNC_INITIAL_BACKOFF, state, log)
+        .await
+}
+
+async fn fetch_deposit_snapshot_with_backoff<E: EthSpec>(
+    url: SensitiveUrl,
+    timeout: Duration,
+    initial_backoff: Duration,
+    state: &BeaconState<E>,
+    log: &Logger,
+) -> Option<DepositTreeSnapshot> {
+    let remote = BeaconNodeHttpClient::new(url, Timeouts::set_all(timeout));
+
+    debug!(log, "Downloading deposit snapshot");
+
+    let snapshot = match with_retries("deposit snapshot", initial_backoff, log, || {
+        remote.get_deposit_snapshot()
+    })
+    .await
+    {
+        Ok(Some(response)) => response.data,
+        Ok(None) => {
+            warn!(
+                log,
+                "Remote has no deposit snapshot";
+                "msg" => "the deposit cache will be built from the deposit contract logs"
+            );
+            return None;
+        }
+        Err(e) => {
+            warn!(
+                log,
+                "Unable to download deposit snapshot";
+                "error" => e,
+                "msg" => "the deposit cache will be built from the deposit contract logs"
+            );
+            return None;
+        }
+    };
+
+    let eth1_data = state.eth1_data();
+    if !snapshot.is_valid()
+        || snapshot.deposit_root != eth1_data.deposit_root
+        || snapshot.deposit_count != eth1_data.deposit_count
+    {
+        warn!(
+            log,
+            "Ignoring deposit snapshot which doesn't match checkpoint state";
+            "snapshot_deposit_root" => ?snapshot.deposit_root,
+            "snapshot_deposit_count" => snapshot.deposit_count,
+            "state_deposit_root" => ?eth1_data.deposit_root,
+            "state_deposit_count" => eth1_data.deposit_count,
+        );
+        return None;
+    }
+
+    // Deposits in the snapshot can't be served to block production, so the state must have
+    // processed all of them already.
+    if state.eth1_deposit_index() < snapshot.deposit_count {
+        warn!(
+            log,
+            "Ignoring deposit snapshot with deposits unprocessed by checkpoint state";
+            "snapshot_deposit_count" => snapshot.deposit_count,
+            "state_deposit_index" => state.eth1_deposit_index(),
+        );
+        return None;
+    }
+
+    info!(
+        log,
+        "Loaded deposit snapshot";
+        "deposit_count" => snapshot.deposit_count,
+        "eth1_block_number" => snapshot.execution_block_height,
+    );
+
+    Some(snapshot)
+}
+
+/// Read a deposit snapshot from the JSON file at `path`. The snapshot may be wrapped in a `data`
+/// object, as it is served by the beacon node API.
+pub fn load_deposit_snapshot_file(path: &Path) -> Result<DepositTreeSnapshot, String> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum SnapshotFile {
+        Response(GenericResponse<DepositTreeSnapshot>),
+        Snapshot(DepositTreeSnapshot),
+    }
+
+    let file = File::open(path)
+        .map_err(|e| format!("Unable to open deposit snapshot {}: {}", path.display(), e))?;
+    let snapshot = match serde_json::from_reader(file)
+        .map_err(|e| format!("Unable to parse deposit snapshot {}: {}", path.display(), e))?
+    {
+        SnapshotFile::Response(response) => response.data,
+        SnapshotFile::Snapshot(snapshot) => snapshot,
+    };
+
+    if !snapshot.is_valid() {
+        return Err(format!(
+            "Deposit snapshot {} is invalid, its deposit root doesn't match its finalized hashes",
+            path.display()
+        ));
+    }
+    Ok(snapshot)
+}
+
+/// Returns `true` if a request which failed with `e` may succeed if it is repeated.
+pub(crate) fn is_retryable(e: &ApiError) -> bool {
+    match e {
+        // Connection failures and timeouts don't have a status.
+        ApiError::Reqwest(_) => e.status().map_or(true, |status| status.is_server_error()),
+        ApiError::ServerMessage(_)
+        | ApiError::ServerIndexedMessage(_)
+        | ApiError::StatusCode(_) => e.status().map_or(false, |status| status.is_server_error()),
+        _ => false,
+    }
+}
+
+/// Perform `request` until it succeeds, fails with an error that isn't retryable or has been
+/// attempted `CHECKPOINT_SYNC_MAX_ATTEMPTS` times.
+pub(crate) async fn with_retries<T, F, R>(
+    description: &str,
+    initial_backoff: Duration,
+    log: &Logger,
+    request: F,
+) -> Result<T, String>
+where
+    F: Fn() -> R,
+    R: Future<Output = Result<T, ApiError>>,
+{
+    let mut backoff = initial_backoff;
+    let mut attempt = 1;
+
+    loop {
+        match request().await {
+            Ok(response) => return Ok(response),
+            Err(ApiError::InvalidSsz(e)) => {
+                return Err(format!(
+                    "Unable to parse {} SSZ: {:?}. Ensure the checkpoint-sync-url refers to a \
+                     node for the correct network",
+                    description, e
+                ))
+            }
+            Err(e) if is_retryable(&e) && attempt < CHECKPOINT_SYNC_MAX_ATTEMPTS => {
+                warn!(
+                    log,
+                    "Request to remote failed";
+                    "error" => ?e,
+                    "request" => description,
+                    "attempt" => attempt,
+                    "retry_in_ms" => backoff.as_millis(),
+                );
+                sleep(backoff).await;
+                backoff *= 2;
+                attempt += 1;
+            }
+            Err(e) => {
+                return Err(format!(
+                    "Error fetching {} from remote after {} attempt(s): {:?}",
+                    description, attempt, e
+                ))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use genesis::{interop_genesis_state, DEFAULT_ETH1_BLOCK_HASH};
+    use merkle_proof::MerkleTree;
+    use ssz::Encode;
+    use std::net::SocketAddr;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use types::{
+        test_utils::generate_deterministic_keypairs, BeaconBlock, Hash256, MinimalEthSpec,
+        Signature, DEPOSIT_TREE_DEPTH,
+    };
+    use warp::{http::StatusCode, Filter, Reply};
+
+    type E = MinimalEthSpec;
+
+    const TEST_BACKOFF: Duration = Duration::from_millis(10);
+    const TEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+    fn genesis_state(spec: &ChainSpec) -> BeaconState<E> {
+        let keypairs = generate_deterministic_keypairs(8);
+        interop_genesis_state(
+            &keypairs,
+            0,
+            Hash256::from_slice(DEFAULT_ETH1_BLOCK_HASH),
+            None,
+            spec,
+        )
+        .unwrap()
+    }
+
+    /// Serves `block` and `state`, responding with a server error to the first `failures` requests
+    /// for each.
+    fn serve(
+        block: SignedBeaconBlock<E>,
+        state: BeaconState<E>,
+        failures: usize,
+    ) -> (SensitiveUrl, Arc<AtomicUsize>) {
+        let requests = Arc::new(AtomicUsize::new(0));
+
+        let respond = |bytes: Vec<u8>, failures: usize, requests: Arc<AtomicUsize>| {
+            let count = Arc::new(AtomicUsize::new(0));
+            warp::any().map(move || -> warp::reply::Response {
+                requests.fetch_add(1, Ordering::Relaxed);
+                if count.fetch_add(1, Ordering::Relaxed) < failures {
+                    warp::reply::with_status("unavailable", StatusCode::INTERNAL_SERVER_ERROR)
+                        .into_response()
+                } else {
+                    bytes.clone().into_response()
+                }
+            })
+        };
+
+        let blocks = warp::path!("eth" / "v2" / "beacon" / "blocks" / String)
+            .and(respond(block.as_ssz_bytes(), failures, requests.clone()))
+            .map(|_block_id: String, response| response);
+        let states = warp::path!("eth" / "v2" / "debug" / "beacon" / "states" / String)
+            .and(respond(state.as_ssz_bytes(), failures, requests.clone()))
+            .map(|_state_id: String, response| response);
+
+        let (addr, server): (SocketAddr, _) =
+            warp::serve(blocks.or(states)).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+
+        let url = SensitiveUrl::parse(&format!("http://{}", addr)).unwrap();
+        (url, requests)
+    }
+
+    fn signed_block(state_root: Hash256, spec: &ChainSpec) -> SignedBeaconBlock<E> {
+        let mut block = BeaconBlock::empty(spec);
+        *block.state_root_mut() = state_root;
+        SignedBeaconBlock::from_block(block, Signature::empty())
+    }
+
+    #[tokio::test]
+    async fn retries_failed_requests() {
+        let log = environment::null_logger().unwrap();
+        let spec = E::default_spec();
+        let mut genesis_state = genesis_state(&spec);
+        let state_root = genesis_state.update_tree_hash_cache().unwrap();
+        let block = signed_block(state_root, &spec);
+
+        let (url, requests) = serve(block.clone(), genesis_state.clone(), 2);
+        let (state, checkpoint_block) = fetch_checkpoint_with_backoff(
+            url,
+            TEST_TIMEOUT,
+            TEST_BACKOFF,
+            &genesis_state,
+            &spec,
+            &log,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(checkpoint_block, block);
+        assert_eq!(state.slot(), block.slot());
+        // Two failures and one success for each of the block and the state.
+        assert_eq!(requests.load(Ordering::Relaxed), 6);
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_max_attempts() {
+        let log = environment::null_logger().unwrap();
+        let spec = E::default_spec();
+        let mut genesis_state = genesis_state(&spec);
+        let state_root = genesis_state.update_tree_hash_cache().unwrap();
+        let block = signed_block(state_root, &spec);
+
+        let (url, requests) = serve(block, genesis_state.clone(), usize::MAX);
+        let result = fetch_checkpoint_with_backoff(
+            url,
+            TEST_TIMEOUT,
+            TEST_BACKOFF,
+            &genesis_state,
+            &spec,
+            &log,
+        )
+        .await;
+
+        assert!(result.is_err());
+        assert_eq!(
+            requests.load(Ordering::Relaxed),
+            CHECKPOINT_SYNC_MAX_ATTEMPTS
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_mismatched_state_root() {
+        let log = environment::null_logger().unwrap();
+        let spec = E::default_spec();
+        let genesis_state = genesis_state(&spec);
+        let block = signed_block(Hash256::repeat_byte(42), &spec);
+
+        let (url, _) = serve(block, genesis_state.clone(), 0);
+        let error = fetch_checkpoint_with_backoff(
+            url,
+            TEST_TIMEOUT,
+            TEST_BACKOFF,
+            &genesis_state,
+            &spec,
+            &log,
+        )
+        .await
+        .unwrap_err();
+
+        assert!(
+            error.starts_with("Checkpoint state does not match block"),
+            "{}",
+            error
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_mismatched_genesis_validators_root() {
+        let log = environment::null_logger().unwrap();
+        let spec = E::default_spec();
+        let genesis_state = genesis_state(&spec);
+
+        // A state from a network with a different validator set at genesis.
+        let mut other_state = interop_genesis_state::<E>(
+            &generate_deterministic_keypairs(4),
+            0,
+            Hash256::from_slice(DEFAULT_ETH1_BLOCK_HASH),
+            None,
+            &spec,
+        )
+        .unwrap();
+        assert_ne!(
+            other_state.genesis_validators_root(),
+            genesis_state.genesis_validators_root()
+        );
+        let state_root = other_state.update_tree_hash_cache().unwrap();
+        let block = signed_block(state_root, &spec);
+
+        let (url, _) = serve(block, other_state, 0);
+        let error = fetch_checkpoint_with_backoff(
+            url,
+            TEST_TIMEOUT,
+            TEST_BACKOFF,
+            &genesis_state,
+            &spec,
+            &log,
+        )
+        .await
+        .unwrap_err();
+
+        assert!(
+            error.starts_with("Checkpoint state appears to be from the wrong network"),
+            "{}",
+            error
+        );
+    }
+
+    #[tokio::test]
+    async fn deposit_snapshot_must_match_state() {
+        let log = environment::null_logger().unwrap();
+        let spec = E::default_spec();
+        let mut state = genesis_state(&spec);
+
+        // A remote which doesn't serve deposit snapshots.
+        let (url, _) = serve(signed_block(Hash256::zero(), &spec), state.clone(), 0);
+        assert_eq!(
+            fetch_deposit_snapshot_with_backoff(url, TEST_TIMEOUT, TEST_BACKOFF, &state, &log)
+                .await,
+            None
+        );
+
+        let mut snapshot = DepositTreeSnapshot {
+            finalized: vec![],
+            deposit_root: Hash256::zero(),
+            deposit_count: 0,
+            execution_block_hash: Hash256::repeat_byte(1),
+            execution_block_height: 1,
+        };
+        snapshot.deposit_root = snapshot.calculate_root().unwrap();
+
+        let response = GenericResponse::from(snapshot.clone());
+        let route = warp::path!("eth" / "v1" / "beacon" / "deposit_snapshot")
+            .map(move || warp::reply::json(&response));
+        let (addr, server): (SocketAddr, _) =
+            warp::serve(route).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+        let url = SensitiveUrl::parse(&format!("http://{}", addr)).unwrap();
+
+        // The snapshot doesn't match the deposits of the state.
+        assert_eq!(
+            fetch_deposit_snapshot_with_backoff(
+                url.clone(),
+                TEST_TIMEOUT,
+                TEST_BACKOFF,
+                &state,
+                &log
+            )
+            .await,
+            None
+        );
+
+        state.eth1_data_mut().deposit_root = snapshot.deposit_root;
+        state.eth1_data_mut().deposit_count = snapshot.deposit_count;
+        assert_eq!(
+            fetch_deposit_snapshot_with_backoff(url, TEST_TIMEOUT, TEST_BACKOFF, &state, &log)
+                .await,
+            Some(snapshot)
+        );
+    }
+
+    #[tokio::test]
+    async fn deposit_snapshot_must_be_processed_by_state() {
+        let log = environment::null_logger().unwrap();
+        let spec = E::default_spec();
+        let mut state = genesis_state(&spec);
+
+        let deposit_count = 5;
+        let leaves = (0..deposit_count)
+            .map(Hash256::from_low_u64_be)
+            .collect::<Vec<_>>();
+        let tree = MerkleTree::create(&leaves, DEPOSIT_TREE_DEPTH);
+        let mut snapshot = DepositTreeSnapshot {
+            finalized: tree
+                .get_finalized_hashes(deposit_count as usize, DEPOSIT_TREE_DEPTH)
+                .unwrap(),
+            deposit_root: Hash256::zero(),
+            deposit_count,
+            execution_block_hash: Hash256::repeat_byte(1),
+            execution_block_height: 1,
+        };
+        snapshot.deposit_root = snapshot.calculate_root().unwrap();
+        state.eth1_data_mut().deposit_root = snapshot.deposit_root;
+        state.eth1_data_mut().deposit_count = snapshot.deposit_count;
+
+        let response = GenericResponse::from(snapshot.clone());
+        let route = warp::path!("eth" / "v1" / "beacon" / "deposit_snapshot")
+            .map(move || warp::reply::json(&response));
+        let (addr, server): (SocketAddr, _) =
+            warp::serve(route).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+        let url = SensitiveUrl::parse(&format!("http://{}", addr)).unwrap();
+
+        // The last deposit hasn't been processed by the state.
+        *state.eth1_deposit_index_mut() = deposit_count - 1;
+        assert_eq!(
+            fetch_deposit_snapshot_with_backoff(
+                url.clone(),
+                TEST_TIMEOUT,
+                TEST_BACKOFF,
+                &state,
+                &log
+            )
+            .await,
+            None
+        );
+
+        *state.eth1_deposit_index_mut() = deposit_count;
+        assert_eq!(
+            fetch_deposit_snapshot_with_backoff(url, TEST_TIMEOUT, TEST_BACKOFF, &state, &log)
+                .await,
+            Some(snapshot)
+        );
+    }
+
+    #[test]
+    fn deposit_snapshot_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("snapshot.json");
+        let mut snapshot = DepositTreeSnapshot {
+            finalized: vec![],
+            deposit_root: Hash256::zero(),
+            deposit_count: 0,
+            execution_block_hash: Hash256::repeat_byte(1),
+            execution_block_height: 1,
+        };
+
+        std::fs::write(&path, serde_json::to_vec(&snapshot).unwrap()).unwrap();
+        let error = load_deposit_snapshot_file(&path).unwrap_err();
+        assert!(error.contains("is invalid"), "{}", error);
+
+        snapshot.deposit_root = snapshot.calculate_root().unwrap();
+        std::fs::write(&path, serde_json::to_vec(&snapshot).unwrap()).unwrap();
+        assert_eq!(load_deposit_snapshot_file(&path), Ok(snapshot.clone()));
+
+        let response = GenericResponse::from(snapshot.clone());
+        std::fs::write(&path, serde_json::to_vec(&response).unwrap()).unwrap();
+        assert_eq!(load_deposit_snapshot_file(&path), Ok(snapshot));
+    }
+}
@@ -0,0 +1,476 @@
+//! Checks for combinations of settings which would otherwise fail late with obscure errors or
+//! silently misbehave.
+//!
+//! All problems are reported at once so that they can be fixed before restarting the node.
+use crate::config::{ClientGenesis, Config};
+use slog::{error, warn, Logger};
+use std::fmt;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+use types::{ChainSpec, Epoch, EthSpec};
+
+/// A problem with the configuration of the node.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigError {
+    /// Checkpoint sync requires a real eth1 backend to keep following deposits from the anchor.
+    CheckpointSyncWithDummyEth1,
+    /// The weak subjectivity checkpoint can't have been reached yet.
+    FutureWeakSubjectivityCheckpoint {
+        checkpoint_epoch: Epoch,
+        latest_possible_epoch: Epoch,
+    },
+    /// Blocks produced without a fee recipient from the validator client will burn their fees.
+    FeeRecipientUnset,
+    /// Pruning one database would corrupt the other.
+    FreezerDbInsideHotDb {
+        hot_db_path: PathBuf,
+        freezer_db_path: PathBuf,
+    },
+    /// Historic states can't be reconstructed into a freezer which can't be written to.
+    ReconstructionWithReadOnlyFreezer,
+    /// A read-only chain would still write to its database, or a writable chain would fail to write
+    /// to its read-only database.
+    ReadOnlyMismatch,
+    /// The eth1 block cache can't hold the blocks needed to vote in a single voting period.
+    Eth1BlockCacheTooSmall {
+        block_cache_truncation: usize,
+        minimum: u64,
+    },
+    /// The eth1 cache would never import the blocks within the follow distance.
+    Eth1CacheFollowDistanceTooLarge {
+        cache_follow_distance: u64,
+        follow_distance: u64,
+    },
+}
+
+impl ConfigError {
+    /// Returns `true` if the node must not start with this problem.
+    pub fn is_fatal(&self) -> bool {
+        match self {
+            ConfigError::CheckpointSyncWithDummyEth1
+            | ConfigError::FutureWeakSubjectivityCheckpoint { .. }
+            | ConfigError::FreezerDbInsideHotDb { .. }
+            | ConfigError::ReconstructionWithReadOnlyFreezer
+            | ConfigError::ReadOnlyMismatch
+            | ConfigError::Eth1BlockCacheTooSmall { .. }
+            | ConfigError::Eth1CacheFollowDistanceTooLarge { .. } => true,
+            ConfigError::FeeRecipientUnset => false,
+        }
+    }
+
+    /// Returns a suggestion for fixing the problem.
+    pub fn suggested_fix(&self) -> &'static str {
+        match self {
+            ConfigError::CheckpointSyncWithDummyEth1 => {
+                "remove --dummy-eth1 or start without --checkpoint-sync-url"
+            }
+            ConfigError::FutureWeakSubjectivityCheckpoint { .. } => {
+                "check the epoch passed to --wss-checkpoint, it should be a recently finalized \
+                 checkpoint"
+            }
+            ConfigError::FeeRecipientUnset => {
+                "set --suggested-fee-recipient or ensure every validator client supplies a fee \
+                 recipient"
+            }
+            ConfigError::FreezerDbInsideHotDb { .. } => {
+                "set --freezer-dir to a directory outside of the hot database"
+            }
+            ConfigError::ReconstructionWithReadOnlyFreezer => {
+                "remove --reconstruct-historic-states or --freezer-readonly"
+            }
+            ConfigError::ReadOnlyMismatch => {
+                "use --read-only, or set both chain.read_only and store.read_only in the config \
+                 file"
+            }
+            ConfigError::Eth1BlockCacheTooSmall { .. } => {
+                "increase --eth1-block-cache-truncation or remove it to use the default"
+            }
+            ConfigError::Eth1CacheFollowDistanceTooLarge { .. } => {
+                "decrease --eth1-cache-follow-distance or remove it to use the default"
+            }
+        }
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::CheckpointSyncWithDummyEth1 => {
+                write!(f, "checkpoint sync can't be used with a dummy eth1 backend")?
+            }
+            ConfigError::FutureWeakSubjectivityCheckpoint {
+                checkpoint_epoch,
+                latest_possible_epoch,
+            } => write!(
+                f,
+                "the weak subjectivity checkpoint at epoch {} is in the future, the current epoch \
+                 is at most {}",
+                checkpoint_epoch, latest_possible_epoch
+            )?,
+            ConfigError::FeeRecipientUnset => write!(
+                f,
+                "no suggested fee recipient is set on a network which has scheduled the merge"
+            )?,
+            ConfigError::FreezerDbInsideHotDb {
+                hot_db_path,
+                freezer_db_path,
+            } => write!(
+                f,
+                "the freezer database {:?} is inside the hot database {:?}",
+                freezer_db_path, hot_db_path
+            )?,
+            ConfigError::ReconstructionWithReadOnlyFreezer => write!(
+                f,
+                "historic states can't be reconstructed in a read-only freezer database"
+            )?,
+            ConfigError::ReadOnlyMismatch => write!(
+                f,
+                "only one of the beacon chain and its database is read-only"
+            )?,
+            ConfigError::Eth1BlockCacheTooSmall {
+                block_cache_truncation,
+                minimum,
+            } => write!(
+                f,
+                "the eth1 block cache of {} blocks is smaller than an eth1 voting period of {} \
+                 blocks",
+                block_cache_truncation, minimum
+            )?,
+            ConfigError::Eth1CacheFollowDistanceTooLarge {
+                cache_follow_distance,
+                follow_distance,
+            } => write!(
+                f,
+                "the eth1 cache follow distance of {} blocks is greater than the follow distance \
+                 of {} blocks",
+                cache_follow_distance, follow_distance
+            )?,
+        }
+        write!(f, " ({})", self.suggested_fix())
+    }
+}
+
+impl Config {
+    /// Check the configuration for problems, returning all of them if there are any.
+    ///
+    /// Problems for which `ConfigError::is_fatal` is `false` are only warnings. Checks which depend
+    /// on the length of an epoch are made by `validate_for_preset`.
+    pub fn validate(&self, spec: &ChainSpec) -> Result<(), Vec<ConfigError>> {
+        let mut problems = vec![];
+
+        if self.dummy_eth1_backend
+            && matches!(self.genesis, ClientGenesis::CheckpointSyncUrl { .. })
+        {
+            problems.push(ConfigError::CheckpointSyncWithDummyEth1);
+        }
+
+        if let Some(el_config) = &self.execution_layer {
+            if spec.bellatrix_fork_epoch.is_some()
+                && el_config.suggested_fee_recipient.is_none()
+                && el_config.suggested_fee_recipient_file.is_none()
+            {
+                problems.push(ConfigError::FeeRecipientUnset);
+            }
+        }
+
+        let hot_db_path = self.get_db_path();
+        let freezer_db_path = self.get_freezer_db_path();
+        if freezer_db_path.starts_with(&hot_db_path) {
+            problems.push(ConfigError::FreezerDbInsideHotDb {
+                hot_db_path,
+                freezer_db_path,
+            });
+        }
+
+        if self.store.freezer_is_read_only() && self.chain.reconstruct_historic_states {
+            problems.push(ConfigError::ReconstructionWithReadOnlyFreezer);
+        }
+
+        if self.chain.read_only != self.store.read_only {
+            problems.push(ConfigError::ReadOnlyMismatch);
+        }
+
+        let cache_follow_distance = self.eth1.cache_follow_distance();
+        if cache_follow_distance > self.eth1.follow_distance {
+            problems.push(ConfigError::Eth1CacheFollowDistanceTooLarge {
+                cache_follow_distance,
+                follow_distance: self.eth1.follow_distance,
+            });
+        }
+
+        into_result(problems)
+    }
+
+    /// Check the parts of the configuration which depend on the length of an epoch in the `E`
+    /// preset, returning all of the problems if there are any.
+    pub fn validate_for_preset<E: EthSpec>(
+        &self,
+        spec: &ChainSpec,
+    ) -> Result<(), Vec<ConfigError>> {
+        let mut problems = vec![];
+
+        if let Some(checkpoint) = self.chain.weak_subjectivity_checkpoint {
+            let latest_possible_epoch = latest_possible_epoch::<E>(spec);
+            if checkpoint.epoch > latest_possible_epoch {
+                problems.push(ConfigError::FutureWeakSubjectivityCheckpoint {
+                    checkpoint_epoch: checkpoint.epoch,
+                    latest_possible_epoch,
+                });
+            }
+        }
+
+        if let Some(block_cache_truncation) = self.eth1.block_cache_truncation {
+            let minimum = eth1::Config::eth1_blocks_per_voting_period::<E>(spec);
+            if (block_cache_truncation as u64) < minimum {
+                problems.push(ConfigError::Eth1BlockCacheTooSmall {
+                    block_cache_truncation,
+                    minimum,
+                });
+            }
+        }
+
+        into_result(problems)
+    }
+
+    /// Validate the configuration at startup, logging every problem.
+    ///
+    /// Returns an error describing all of the fatal problems, if any.
+    pub fn validate_at_startup<E: EthSpec>(
+        &self,
+        spec: &ChainSpec,
+        log: &Logger,
+    ) -> Result<(), String> {
+        let problems = self
+            .validate(spec)
+            .err()
+            .into_iter()
+            .chain(self.validate_for_preset::<E>(spec).err())
+            .flatten();
+
+        let mut fatal = vec![];
+        for problem in problems {
+            if problem.is_fatal() {
+                error!(log, "Invalid configuration"; "error" => %problem);
+                fatal.push(problem.to_string());
+            } else {
+                warn!(log, "Questionable configuration"; "warning" => %problem);
+            }
+        }
+
+        if fatal.is_empty() {
+            Ok(())
+        } else {
+            Err(format!("Invalid configuration: {}", fatal.join("; ")))
+        }
+    }
+}
+
+fn into_result(problems: Vec<ConfigError>) -> Result<(), Vec<ConfigError>> {
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(problems)
+    }
+}
+
+/// Returns the latest epoch the chain could have reached, assuming genesis occurred at the
+/// earliest possible time.
+fn latest_possible_epoch<E: EthSpec>(spec: &ChainSpec) -> Epoch {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+    let seconds_per_epoch = spec.seconds_per_slot * E::slots_per_epoch();
+    Epoch::new(now.saturating_sub(spec.min_genesis_time) / seconds_per_epoch)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::{Checkpoint, Hash256, MainnetEthSpec};
+
+    type E = MainnetEthSpec;
+
+    fn spec() -> ChainSpec {
+        let mut spec = E::default_spec();
+        spec.bellatrix_fork_epoch = Some(Epoch::new(144_896));
+        spec
+    }
+
+    fn config() -> Config {
+        let dir = tempfile::tempdir().unwrap();
+        Config {
+            data_dir: dir.path().join("beacon"),
+            ..Config::default()
+        }
+    }
+
+    #[test]
+    fn default_config_is_valid() {
+        assert_eq!(config().validate(&spec()), Ok(()));
+        assert_eq!(config().validate_for_preset::<E>(&spec()), Ok(()));
+    }
+
+    #[test]
+    fn checkpoint_sync_with_dummy_eth1() {
+        let mut config = config();
+        config.dummy_eth1_backend = true;
+        assert_eq!(config.validate(&spec()), Ok(()));
+
+        config.genesis = ClientGenesis::CheckpointSyncUrl {
+            genesis_state_bytes: vec![],
+            url: sensitive_url::SensitiveUrl::parse("http://localhost:5052").unwrap(),
+        };
+        assert_eq!(
+            config.validate(&spec()),
+            Err(vec![ConfigError::CheckpointSyncWithDummyEth1])
+        );
+    }
+
+    #[test]
+    fn future_weak_subjectivity_checkpoint() {
+        let mut config = config();
+        config.chain.weak_subjectivity_checkpoint = Some(Checkpoint {
+            epoch: Epoch::new(1024),
+            root: Hash256::repeat_byte(1),
+        });
+        assert_eq!(config.validate_for_preset::<E>(&spec()), Ok(()));
+
+        config.chain.weak_subjectivity_checkpoint = Some(Checkpoint {
+            epoch: Epoch::new(u64::MAX / 2),
+            root: Hash256::repeat_byte(1),
+        });
+        let problems = config.validate_for_preset::<E>(&spec()).unwrap_err();
+        assert!(matches!(
+            problems.as_slice(),
+            [ConfigError::FutureWeakSubjectivityCheckpoint { .. }]
+        ));
+        assert!(problems[0].is_fatal());
+    }
+
+    #[test]
+    fn fee_recipient_unset() {
+        let mut config = config();
+        config.execution_layer = Some(<_>::default());
+        assert_eq!(
+            config.validate(&spec()),
+            Err(vec![ConfigError::FeeRecipientUnset])
+        );
+        assert!(!ConfigError::FeeRecipientUnset.is_fatal());
+
+        // Only a problem once the merge is scheduled.
+        assert_eq!(config.validate(&E::default_spec()), Ok(()));
+
+        config.execution_layer = Some(execution_layer::Config {
+            suggested_fee_recipient: Some(<_>::default()),
+            ..<_>::default()
+        });
+        assert_eq!(config.validate(&spec()), Ok(()));
+    }
+
+    #[test]
+    fn freezer_db_inside_hot_db() {
+        let mut config = config();
+        config.freezer_db_path = Some(config.get_db_path().join("freezer"));
+        let problems = config.validate(&spec()).unwrap_err();
+        assert!(matches!(
+            problems.as_slice(),
+            [ConfigError::FreezerDbInsideHotDb { .. }]
+        ));
+
+        config.freezer_db_path = Some(config.get_data_dir().join("freezer"));
+        assert_eq!(config.validate(&spec()), Ok(()));
+    }
+
+    #[test]
+    fn reconstruction_with_read_only_freezer() {
+        let mut config = config();
+        config.store.freezer_readonly = true;
+        assert_eq!(config.validate(&spec()), Ok(()));
+
+        config.chain.reconstruct_historic_states = true;
+        assert_eq!(
+            config.validate(&spec()),
+            Err(vec![ConfigError::ReconstructionWithReadOnlyFreezer])
+        );
+    }
+
+    #[test]
+    fn read_only_mismatch() {
+        let mut config = config();
+        config.chain.read_only = true;
+        assert_eq!(
+            config.validate(&spec()),
+            Err(vec![ConfigError::ReadOnlyMismatch])
+        );
+
+        config.store.read_only = true;
+        assert_eq!(config.validate(&spec()), Ok(()));
+
+        config.chain.read_only = false;
+        assert_eq!(
+            config.validate(&spec()),
+            Err(vec![ConfigError::ReadOnlyMismatch])
+        );
+    }
+
+    #[test]
+    fn eth1_block_cache_too_small() {
+        let mut config = config();
+        let minimum = eth1::Config::eth1_blocks_per_voting_period::<E>(&spec());
+        config.eth1.block_cache_truncation = Some(minimum as usize);
+        assert_eq!(config.validate_for_preset::<E>(&spec()), Ok(()));
+
+        // An unbounded cache is never too small.
+        config.eth1.block_cache_truncation = None;
+        assert_eq!(config.validate_for_preset::<E>(&spec()), Ok(()));
+
+        config.eth1.block_cache_truncation = Some(minimum as usize - 1);
+        assert_eq!(
+            config.validate_for_preset::<E>(&spec()),
+            Err(vec![ConfigError::Eth1BlockCacheTooSmall {
+                block_cache_truncation: minimum as usize - 1,
+                minimum,
+            }])
+        );
+    }
+
+    #[test]
+    fn eth1_cache_follow_distance_too_large() {
+        let mut config = config();
+        config.eth1.cache_follow_distance = Some(config.eth1.follow_distance);
+        assert_eq!(config.validate(&spec()), Ok(()));
+
+        config.eth1.cache_follow_distance = Some(config.eth1.follow_distance + 1);
+        let problems = config.validate(&spec()).unwrap_err();
+        assert!(matches!(
+            problems.as_slice(),
+            [ConfigError::Eth1CacheFollowDistanceTooLarge { .. }]
+        ));
+        assert!(problems[0].is_fatal());
+    }
+
+    #[test]
+    fn startup_reports_all_fatal_problems() {
+        let log = environment::null_logger().unwrap();
+        let mut config = config();
+        config.execution_layer = Some(<_>::default());
+        assert!(config.validate_at_startup::<E>(&spec(), &log).is_ok());
+
+        config.dummy_eth1_backend = true;
+        config.genesis = ClientGenesis::CheckpointSyncUrl {
+            genesis_state_bytes: vec![],
+            url: sensitive_url::SensitiveUrl::parse("http://localhost:5052").unwrap(),
+        };
+        config.freezer_db_path = Some(config.get_db_path().join("freezer"));
+
+        let error = config.validate_at_startup::<E>(&spec(), &log).unwrap_err();
+        assert!(
+            error.contains(&ConfigError::CheckpointSyncWithDummyEth1.to_string()),
+            "{}",
+            error
+        );
+        assert!(error.contains("is inside the hot database"), "{}", error);
+        // Warnings are not included in the error.
+        assert!(!error.contains("fee recipient"), "{}", error);
+    }
+}
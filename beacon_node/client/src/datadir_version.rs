@@ -0,0 +1,276 @@
+//! Records the version of Lighthouse, database schema and network which last used a data
+//! directory, so that incompatible uses of the directory can be detected at startup.
+//!
+//! The network is checked before the database is opened, whilst the genesis validators root is
+//! only known once the beacon chain has been built.
+use serde_derive::{Deserialize, Serialize};
+use slog::{crit, debug, info, Logger};
+use std::fs;
+use std::path::{Path, PathBuf};
+use store::metadata::CURRENT_SCHEMA_VERSION;
+use types::{ChainSpec, Hash256};
+
+/// The name of the version file within the data directory.
+pub const DATADIR_VERSION_FILENAME: &str = "version.json";
+
+/// The contents of the version file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DataDirVersion {
+    pub client_version: String,
+    pub schema_version: u64,
+    pub network: Option<String>,
+    pub genesis_validators_root: Option<Hash256>,
+}
+
+impl DataDirVersion {
+    /// Returns the version of the running node.
+    pub fn current(spec: &ChainSpec, genesis_validators_root: Option<Hash256>) -> Self {
+        Self {
+            client_version: lighthouse_version::VERSION.to_string(),
+            schema_version: CURRENT_SCHEMA_VERSION.as_u64(),
+            network: spec.config_name.clone(),
+            genesis_validators_root,
+        }
+    }
+
+    /// Read the version file at `path`, returning `None` if it does not exist.
+    pub fn load(path: &Path) -> Result<Option<Self>, String> {
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let contents =
+            fs::read(path).map_err(|e| format!("Unable to read {}: {}", path.display(), e))?;
+        serde_json::from_slice(&contents)
+            .map(Some)
+            .map_err(|e| format!("Unable to parse {}: {}", path.display(), e))
+    }
+
+    /// Write the version file to `path`.
+    pub fn write(&self, path: &Path) -> Result<(), String> {
+        let contents = serde_json::to_vec_pretty(self)
+            .map_err(|e| format!("Unable to serialize datadir version: {}", e))?;
+
+        // Write to a temporary file first so that an interrupted write can't corrupt the file.
+        let temp_path = path.with_extension("json.tmp");
+        fs::write(&temp_path, contents)
+            .and_then(|()| fs::rename(&temp_path, path))
+            .map_err(|e| format!("Unable to write {}: {}", path.display(), e))
+    }
+
+    /// Check whether a data directory which was last used by `previous` may be used by `self`.
+    ///
+    /// Returns an error if the directory belongs to a different network. Downgrades are permitted,
+    /// but logged since they may leave the database in an unusable state.
+    pub fn check_compatibility(&self, previous: &Self, log: &Logger) -> Result<(), String> {
+        if let (Some(network), Some(previous_network)) = (&self.network, &previous.network) {
+            if network != previous_network {
+                return Err(format!(
+                    "The datadir was created for the {} network but the node is configured for \
+                     {}. Use a different --datadir or change the --network",
+                    previous_network, network
+                ));
+            }
+        }
+
+        if let (Some(root), Some(previous_root)) = (
+            self.genesis_validators_root,
+            previous.genesis_validators_root,
+        ) {
+            if root != previous_root {
+                return Err(format!(
+                    "The datadir was created for a network with genesis validators root {:?} but \
+                     the node is configured for a network with root {:?}. Use a different \
+                     --datadir or change the --network",
+                    previous_root, root
+                ));
+            }
+        }
+
+        if previous.schema_version > self.schema_version {
+            crit!(
+                log,
+                "Database schema downgrade";
+                "msg" => "the database may be unusable, downgrade it with `lighthouse db migrate` \
+                          or upgrade Lighthouse",
+                "previous_schema_version" => previous.schema_version,
+                "schema_version" => self.schema_version,
+            );
+        }
+
+        match (
+            parse_version(&previous.client_version),
+            parse_version(&self.client_version),
+        ) {
+            (Some(previous_version), Some(version)) if previous_version > version => crit!(
+                log,
+                "Lighthouse downgrade detected";
+                "msg" => "the datadir was last used by a newer version of Lighthouse, which \
+                          may have made incompatible changes",
+                "previous_version" => &previous.client_version,
+                "version" => &self.client_version,
+            ),
+            _ => (),
+        }
+
+        Ok(())
+    }
+}
+
+/// The version file of a data directory which is in use by the node.
+pub struct DataDirVersionFile {
+    path: PathBuf,
+    previous: Option<DataDirVersion>,
+}
+
+impl DataDirVersionFile {
+    /// Read the version file in `datadir` and verify it is compatible with the running node.
+    ///
+    /// This should be called before the database is opened.
+    pub fn open(datadir: &Path, spec: &ChainSpec, log: &Logger) -> Result<Self, String> {
+        let path = datadir.join(DATADIR_VERSION_FILENAME);
+        let previous = DataDirVersion::load(&path)?;
+
+        if let Some(previous) = &previous {
+            debug!(
+                log,
+                "Checking datadir version";
+                "previous_version" => &previous.client_version,
+                "previous_schema_version" => previous.schema_version,
+                "previous_network" => ?previous.network,
+            );
+            DataDirVersion::current(spec, None).check_compatibility(previous, log)?;
+        }
+
+        Ok(Self { path, previous })
+    }
+
+    /// Verify the genesis validators root of the beacon chain and record the running node's version
+    /// in the version file.
+    pub fn update(
+        self,
+        spec: &ChainSpec,
+        genesis_validators_root: Hash256,
+        log: &Logger,
+    ) -> Result<(), String> {
+        let current = DataDirVersion::current(spec, Some(genesis_validators_root));
+
+        if let Some(previous) = &self.previous {
+            current.check_compatibility(previous, log)?;
+            if *previous == current {
+                return Ok(());
+            }
+        } else {
+            info!(
+                log,
+                "Writing datadir version file";
+                "path" => ?self.path,
+            );
+        }
+
+        current.write(&self.path)
+    }
+}
+
+/// Parse the `(major, minor, patch)` version from a version string like `Lighthouse/v2.4.0-abc123`.
+fn parse_version(version: &str) -> Option<(u64, u64, u64)> {
+    let version = version.split_once("/v")?.1;
+    let mut parts = version
+        .split(|c: char| !c.is_ascii_digit())
+        .map(|part| part.parse::<u64>().ok());
+    Some((parts.next()??, parts.next()??, parts.next()??))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::{EthSpec, MainnetEthSpec};
+
+    fn null_logger() -> Logger {
+        Logger::root(slog::Discard, slog::o!())
+    }
+
+    #[test]
+    fn parse_versions() {
+        assert_eq!(parse_version("Lighthouse/v2.4.0-67da032+"), Some((2, 4, 0)));
+        assert_eq!(parse_version("Lighthouse/v10.11.12"), Some((10, 11, 12)));
+        assert_eq!(parse_version("Lighthouse/unknown"), None);
+    }
+
+    #[test]
+    fn first_start_writes_version() {
+        let log = null_logger();
+        let datadir = tempfile::tempdir().unwrap();
+        let spec = MainnetEthSpec::default_spec();
+        let root = Hash256::repeat_byte(1);
+
+        DataDirVersionFile::open(datadir.path(), &spec, &log)
+            .unwrap()
+            .update(&spec, root, &log)
+            .unwrap();
+
+        let version = DataDirVersion::load(&datadir.path().join(DATADIR_VERSION_FILENAME))
+            .unwrap()
+            .unwrap();
+        assert_eq!(version, DataDirVersion::current(&spec, Some(root)));
+
+        // A subsequent start with the same network succeeds.
+        DataDirVersionFile::open(datadir.path(), &spec, &log)
+            .unwrap()
+            .update(&spec, root, &log)
+            .unwrap();
+    }
+
+    #[test]
+    fn network_mismatch() {
+        let log = null_logger();
+        let datadir = tempfile::tempdir().unwrap();
+        let mainnet = MainnetEthSpec::default_spec();
+        let gnosis = ChainSpec::gnosis();
+        let root = Hash256::repeat_byte(1);
+
+        DataDirVersionFile::open(datadir.path(), &mainnet, &log)
+            .unwrap()
+            .update(&mainnet, root, &log)
+            .unwrap();
+
+        let error = DataDirVersionFile::open(datadir.path(), &gnosis, &log)
+            .err()
+            .unwrap();
+        assert!(
+            error.starts_with("The datadir was created for the mainnet network"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn genesis_validators_root_mismatch() {
+        let log = null_logger();
+        let datadir = tempfile::tempdir().unwrap();
+        let spec = MainnetEthSpec::default_spec();
+
+        DataDirVersionFile::open(datadir.path(), &spec, &log)
+            .unwrap()
+            .update(&spec, Hash256::repeat_byte(1), &log)
+            .unwrap();
+
+        // The network name matches, so the mismatch is only detected once the root is known.
+        let file = DataDirVersionFile::open(datadir.path(), &spec, &log).unwrap();
+        assert!(file.update(&spec, Hash256::repeat_byte(2), &log).is_err());
+    }
+
+    #[test]
+    fn downgrade_is_permitted() {
+        let log = null_logger();
+        let spec = MainnetEthSpec::default_spec();
+        let current = DataDirVersion::current(&spec, None);
+        let newer = DataDirVersion {
+            client_version: "Lighthouse/v99.0.0".to_string(),
+            schema_version: current.schema_version + 1,
+            ..current.clone()
+        };
+
+        assert_eq!(current.check_compatibility(&newer, &log), Ok(()));
+    }
+}
@@ -0,0 +1,370 @@
+//! Checks that the filesystems containing the hot and freezer databases have enough free space.
+//!
+//! Running out of disk space whilst writing to the database can corrupt it, so the node refuses to
+//! start when space is critically low and keeps watching the free space whilst it runs.
+use crate::metrics;
+use futures::channel::mpsc::Sender;
+use serde_derive::{Deserialize, Serialize};
+use slog::{crit, debug, warn, Logger};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use task_executor::{ShutdownReason, TaskExecutor};
+use tokio::time::sleep;
+
+/// The interval at which the free space is checked whilst the node is running.
+pub const DISK_SPACE_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The reason given when shutting down due to low disk space.
+pub const LOW_DISK_SPACE_SHUTDOWN_REASON: &str = "Free disk space is below the minimum";
+
+const BYTES_PER_MB: u64 = 1024 * 1024;
+
+/// Free disk space thresholds for the database filesystems.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Config {
+    /// Log a warning if the free space falls below this many megabytes.
+    pub warn_threshold_mb: u64,
+    /// Refuse to start if the free space is below this many megabytes.
+    pub minimum_mb: u64,
+    /// Start even if the free space is below `minimum_mb`.
+    pub ignore_minimum_at_startup: bool,
+    /// Shut down if the free space falls below `minimum_mb` whilst running.
+    pub shutdown_below_minimum: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            warn_threshold_mb: 10 * 1024,
+            minimum_mb: 1024,
+            ignore_minimum_at_startup: false,
+            shutdown_below_minimum: false,
+        }
+    }
+}
+
+/// Provides the free space of the filesystem containing a path.
+///
+/// Abstracted so that the checks can be tested without filling a real disk.
+pub trait DiskSpace: Send + Sync + 'static {
+    /// Returns the number of bytes available to this process on the filesystem containing `path`.
+    fn available_space(&self, path: &Path) -> io::Result<u64>;
+}
+
+/// Queries the operating system (e.g., via `statvfs`) for the free space.
+pub struct SystemDiskSpace;
+
+impl DiskSpace for SystemDiskSpace {
+    fn available_space(&self, path: &Path) -> io::Result<u64> {
+        fs2::available_space(path)
+    }
+}
+
+/// The free space of a filesystem relative to the thresholds in `Config`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SpaceStatus {
+    Sufficient,
+    BelowWarnThreshold,
+    BelowMinimum,
+}
+
+impl Config {
+    /// Returns the status of a filesystem with `available` bytes free.
+    pub fn status(&self, available: u64) -> SpaceStatus {
+        if available < self.minimum_mb.saturating_mul(BYTES_PER_MB) {
+            SpaceStatus::BelowMinimum
+        } else if available < self.warn_threshold_mb.saturating_mul(BYTES_PER_MB) {
+            SpaceStatus::BelowWarnThreshold
+        } else {
+            SpaceStatus::Sufficient
+        }
+    }
+}
+
+/// Checks the free space of the filesystems containing each of the databases.
+pub struct DiskSpaceMonitor<D> {
+    config: Config,
+    /// The name of each database (used in logs and metrics) and its path.
+    databases: Vec<(&'static str, PathBuf)>,
+    disk_space: D,
+    log: Logger,
+}
+
+impl<D: DiskSpace> DiskSpaceMonitor<D> {
+    pub fn new(
+        config: Config,
+        databases: Vec<(&'static str, PathBuf)>,
+        disk_space: D,
+        log: Logger,
+    ) -> Self {
+        Self {
+            config,
+            databases,
+            disk_space,
+            log,
+        }
+    }
+
+    /// Check the free space before the databases are opened.
+    ///
+    /// Returns an error if any database has less than the minimum free space, unless
+    /// `Config::ignore_minimum_at_startup` is set.
+    pub fn check_at_startup(&self) -> Result<(), String> {
+        for (name, path, available) in self.available_space() {
+            match self.config.status(available) {
+                SpaceStatus::Sufficient => (),
+                SpaceStatus::BelowWarnThreshold => warn!(
+                    self.log,
+                    "Low disk space";
+                    "msg" => "the node will refuse to start if the free space falls below the minimum",
+                    "available_mb" => available / BYTES_PER_MB,
+                    "minimum_mb" => self.config.minimum_mb,
+                    "database" => name,
+                    "path" => ?path,
+                ),
+                SpaceStatus::BelowMinimum if self.config.ignore_minimum_at_startup => crit!(
+                    self.log,
+                    "Insufficient disk space";
+                    "msg" => "starting anyway as the minimum is ignored, the database may be corrupted",
+                    "available_mb" => available / BYTES_PER_MB,
+                    "minimum_mb" => self.config.minimum_mb,
+                    "database" => name,
+                    "path" => ?path,
+                ),
+                SpaceStatus::BelowMinimum => {
+                    return Err(format!(
+                        "Insufficient disk space for the {} database at {:?}: {} MB available but \
+                         at least {} MB is required. Free up space, lower --disk-space-minimum or \
+                         use --ignore-disk-space-minimum",
+                        name,
+                        path,
+                        available / BYTES_PER_MB,
+                        self.config.minimum_mb
+                    ))
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check the free space whilst the node is running, updating the metrics and logging if it is
+    /// low.
+    ///
+    /// Returns `true` if any database has less than the minimum free space.
+    pub fn check(&self) -> bool {
+        let mut below_minimum = false;
+
+        for (name, path, available) in self.available_space() {
+            metrics::set_gauge_vec(&metrics::DISK_SPACE_AVAILABLE, &[name], available as i64);
+
+            match self.config.status(available) {
+                SpaceStatus::Sufficient => debug!(
+                    self.log,
+                    "Disk space sufficient";
+                    "available_mb" => available / BYTES_PER_MB,
+                    "database" => name,
+                ),
+                SpaceStatus::BelowWarnThreshold => warn!(
+                    self.log,
+                    "Low disk space";
+                    "msg" => "free up disk space to avoid corrupting the database",
+                    "available_mb" => available / BYTES_PER_MB,
+                    "minimum_mb" => self.config.minimum_mb,
+                    "database" => name,
+                    "path" => ?path,
+                ),
+                SpaceStatus::BelowMinimum => {
+                    crit!(
+                        self.log,
+                        "Insufficient disk space";
+                        "msg" => "free up disk space immediately, the database may be corrupted",
+                        "available_mb" => available / BYTES_PER_MB,
+                        "minimum_mb" => self.config.minimum_mb,
+                        "database" => name,
+                        "path" => ?path,
+                    );
+                    below_minimum = true;
+                }
+            }
+        }
+
+        below_minimum
+    }
+
+    /// Check the free space, triggering a shut down via `shutdown_sender` if it is below the
+    /// minimum and `Config::shutdown_below_minimum` is set.
+    ///
+    /// Returns `true` if a shut down was triggered.
+    fn check_and_shutdown(&self, shutdown_sender: &mut Sender<ShutdownReason>) -> bool {
+        if !self.check() || !self.config.shutdown_below_minimum {
+            return false;
+        }
+
+        if let Err(e) =
+            shutdown_sender.try_send(ShutdownReason::Failure(LOW_DISK_SPACE_SHUTDOWN_REASON))
+        {
+            crit!(
+                self.log,
+                "Unable to trigger client shut down";
+                "msg" => "shut down may already be under way",
+                "error" => ?e
+            );
+        }
+        true
+    }
+
+    /// Spawns a routine which checks the free space every `DISK_SPACE_CHECK_INTERVAL`.
+    pub fn spawn(self, executor: TaskExecutor) {
+        let mut shutdown_sender = executor.shutdown_sender();
+
+        executor.spawn(
+            async move {
+                loop {
+                    if self.check_and_shutdown(&mut shutdown_sender) {
+                        break;
+                    }
+                    sleep(DISK_SPACE_CHECK_INTERVAL).await;
+                }
+            },
+            "disk_space_monitor",
+        );
+    }
+
+    /// Returns the free space for each database, logging and skipping any which can't be read.
+    fn available_space(&self) -> Vec<(&'static str, &Path, u64)> {
+        self.databases
+            .iter()
+            .filter_map(|(name, path)| match self.disk_space.available_space(path) {
+                Ok(available) => Some((*name, path.as_path(), available)),
+                Err(e) => {
+                    warn!(
+                        self.log,
+                        "Unable to determine free disk space";
+                        "error" => ?e,
+                        "database" => name,
+                        "path" => ?path,
+                    );
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Arc;
+
+    /// Reports the same free space for every path.
+    #[derive(Clone, Default)]
+    struct MockDiskSpace(Arc<AtomicU64>);
+
+    impl MockDiskSpace {
+        fn set_mb(&self, mb: u64) {
+            self.0.store(mb * BYTES_PER_MB, Ordering::Relaxed);
+        }
+    }
+
+    impl DiskSpace for MockDiskSpace {
+        fn available_space(&self, _: &Path) -> io::Result<u64> {
+            Ok(self.0.load(Ordering::Relaxed))
+        }
+    }
+
+    fn new_monitor(config: Config) -> (DiskSpaceMonitor<MockDiskSpace>, MockDiskSpace) {
+        let disk_space = MockDiskSpace::default();
+        let monitor = DiskSpaceMonitor::new(
+            config,
+            vec![
+                ("hot", PathBuf::from("chain_db")),
+                ("freezer", PathBuf::from("freezer_db")),
+            ],
+            disk_space.clone(),
+            Logger::root(slog::Discard, slog::o!()),
+        );
+        (monitor, disk_space)
+    }
+
+    fn config() -> Config {
+        Config {
+            warn_threshold_mb: 100,
+            minimum_mb: 10,
+            ..Config::default()
+        }
+    }
+
+    #[test]
+    fn status() {
+        let config = config();
+        assert_eq!(config.status(100 * BYTES_PER_MB), SpaceStatus::Sufficient);
+        assert_eq!(
+            config.status(100 * BYTES_PER_MB - 1),
+            SpaceStatus::BelowWarnThreshold
+        );
+        assert_eq!(
+            config.status(10 * BYTES_PER_MB),
+            SpaceStatus::BelowWarnThreshold
+        );
+        assert_eq!(config.status(0), SpaceStatus::BelowMinimum);
+    }
+
+    #[test]
+    fn startup_check() {
+        let (monitor, disk_space) = new_monitor(config());
+
+        disk_space.set_mb(1000);
+        assert!(monitor.check_at_startup().is_ok());
+
+        // Only a warning is logged when below the warning threshold.
+        disk_space.set_mb(50);
+        assert!(monitor.check_at_startup().is_ok());
+
+        disk_space.set_mb(5);
+        assert!(monitor
+            .check_at_startup()
+            .unwrap_err()
+            .starts_with("Insufficient disk space for the hot database"));
+    }
+
+    #[test]
+    fn startup_check_ignore_minimum() {
+        let (monitor, disk_space) = new_monitor(Config {
+            ignore_minimum_at_startup: true,
+            ..config()
+        });
+
+        disk_space.set_mb(5);
+        assert!(monitor.check_at_startup().is_ok());
+    }
+
+    #[test]
+    fn runtime_shutdown() {
+        let (mut shutdown_sender, mut shutdown_receiver) = futures::channel::mpsc::channel(1);
+
+        // Without `shutdown_below_minimum` the node keeps running.
+        let (monitor, disk_space) = new_monitor(config());
+        disk_space.set_mb(5);
+        assert!(monitor.check());
+        assert!(!monitor.check_and_shutdown(&mut shutdown_sender));
+        assert!(shutdown_receiver.try_next().is_err());
+
+        let (monitor, disk_space) = new_monitor(Config {
+            shutdown_below_minimum: true,
+            ..config()
+        });
+        disk_space.set_mb(50);
+        assert!(!monitor.check_and_shutdown(&mut shutdown_sender));
+        assert!(shutdown_receiver.try_next().is_err());
+
+        disk_space.set_mb(5);
+        assert!(monitor.check_and_shutdown(&mut shutdown_sender));
+        assert_eq!(
+            shutdown_receiver.try_next().unwrap(),
+            Some(ShutdownReason::Failure(LOW_DISK_SPACE_SHUTDOWN_REASON))
+        );
+    }
+}
@@ -0,0 +1,155 @@
+//! Resolves the network selected by `Config::eth2_network` into the values used by the rest of the
+//! client: the chain spec, the source of the genesis state and the deposit contract details.
+use crate::config::{ClientGenesis, Config};
+use eth2_network_config::Eth2NetworkConfig;
+use std::cmp::max;
+use types::{Address, ChainSpec, EthSpec};
+
+/// The configuration of a network, resolved from a `NetworkSelection`.
+#[derive(Debug, Clone)]
+pub struct ResolvedNetwork {
+    pub network_config: Eth2NetworkConfig,
+    pub spec: ChainSpec,
+    /// Starts from the network's genesis state if it is known, otherwise waits for genesis via
+    /// the deposit contract.
+    pub genesis: ClientGenesis,
+    pub deposit_contract_address: Address,
+    pub deposit_contract_deploy_block: u64,
+    pub deposit_chain_id: u64,
+}
+
+impl ResolvedNetwork {
+    pub fn from_network_config<E: EthSpec>(
+        network_config: Eth2NetworkConfig,
+    ) -> Result<Self, String> {
+        let spec = network_config.chain_spec::<E>()?;
+        let genesis = match &network_config.genesis_state_bytes {
+            Some(genesis_state_bytes) => ClientGenesis::SszBytes {
+                genesis_state_bytes: genesis_state_bytes.clone(),
+            },
+            None => ClientGenesis::DepositContract,
+        };
+
+        Ok(Self {
+            deposit_contract_address: spec.deposit_contract_address,
+            deposit_contract_deploy_block: network_config.deposit_contract_deploy_block,
+            deposit_chain_id: spec.deposit_chain_id,
+            genesis,
+            spec,
+            network_config,
+        })
+    }
+
+    /// Returns the genesis state bytes of the network, if known.
+    pub fn genesis_state_bytes(&self) -> Option<&[u8]> {
+        self.network_config.genesis_state_bytes.as_deref()
+    }
+}
+
+impl Config {
+    /// Load the network selected by `self.eth2_network`.
+    pub fn resolve_network<E: EthSpec>(&self) -> Result<ResolvedNetwork, String> {
+        let network_config = self.eth2_network.load()?;
+        ResolvedNetwork::from_network_config::<E>(network_config)
+    }
+
+    /// Configure the eth1 service, boot nodes and genesis from `network`.
+    pub fn apply_network<E: EthSpec>(&mut self, network: &ResolvedNetwork) {
+        let spec = &network.spec;
+
+        self.eth1.deposit_contract_address = format!("{:?}", network.deposit_contract_address);
+        self.eth1.deposit_contract_deploy_block = network.deposit_contract_deploy_block;
+        self.eth1.lowest_cached_block_number = network.deposit_contract_deploy_block;
+        self.eth1.follow_distance = spec.eth1_follow_distance;
+        self.eth1.node_far_behind_seconds =
+            max(5, spec.eth1_follow_distance / 2) * spec.seconds_per_eth1_block;
+        self.eth1.chain_id = network.deposit_chain_id.into();
+        self.eth1.set_block_cache_truncation::<E>(spec);
+
+        // Only append network config bootnodes if discovery is not disabled
+        if !self.network.disable_discovery {
+            if let Some(boot_nodes) = &network.network_config.boot_enr {
+                self.network.boot_nodes_enr.extend_from_slice(boot_nodes)
+            }
+        }
+
+        self.genesis = network.genesis.clone();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use eth2_network_config::{NetworkSelection, BASE_CONFIG_FILE, GENESIS_STATE_FILE};
+    use std::fs;
+    use types::{Config as SpecConfig, MainnetEthSpec};
+
+    type E = MainnetEthSpec;
+
+    /// Writes a custom network, based on mainnet, to a new directory.
+    fn write_custom_network(dir: &std::path::Path) -> ChainSpec {
+        let mainnet = Eth2NetworkConfig::constant("mainnet").unwrap().unwrap();
+        let mut spec = mainnet.chain_spec::<E>().unwrap();
+        spec.config_name = Some("custom".to_string());
+        spec.deposit_chain_id = 1337;
+        spec.seconds_per_slot = 6;
+
+        Eth2NetworkConfig {
+            deposit_contract_deploy_block: 42,
+            config: SpecConfig::from_chain_spec::<E>(&spec),
+            ..mainnet
+        }
+        .write_to_file(dir.to_path_buf(), false)
+        .unwrap();
+
+        spec
+    }
+
+    #[test]
+    fn custom_directory() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let network_dir = temp_dir.path().join("custom");
+        let expected_spec = write_custom_network(&network_dir);
+
+        let mut config = Config {
+            eth2_network: NetworkSelection::Directory(network_dir.clone()),
+            ..Config::default()
+        };
+        let network = config.resolve_network::<E>().unwrap();
+        assert_eq!(network.spec, expected_spec);
+        assert_eq!(network.spec.config_name.as_deref(), Some("custom"));
+        assert_eq!(network.spec.seconds_per_slot, 6);
+        assert!(matches!(network.genesis, ClientGenesis::SszBytes { .. }));
+        assert!(network.genesis_state_bytes().is_some());
+
+        config.apply_network::<E>(&network);
+        assert_eq!(config.eth1.chain_id, 1337u64.into());
+        assert_eq!(config.eth1.deposit_contract_deploy_block, 42);
+        assert_eq!(
+            config.eth1.deposit_contract_address,
+            format!("{:?}", expected_spec.deposit_contract_address)
+        );
+        assert!(!config.network.boot_nodes_enr.is_empty());
+        assert!(matches!(config.genesis, ClientGenesis::SszBytes { .. }));
+
+        // Without a genesis state, genesis is awaited via the deposit contract.
+        fs::remove_file(network_dir.join(GENESIS_STATE_FILE)).unwrap();
+        let network = config.resolve_network::<E>().unwrap();
+        assert!(matches!(network.genesis, ClientGenesis::DepositContract));
+
+        fs::remove_file(network_dir.join(BASE_CONFIG_FILE)).unwrap();
+        let error = config.resolve_network::<E>().unwrap_err();
+        assert!(error.contains("Missing config.yaml"), "{}", error);
+    }
+
+    #[test]
+    fn named_network() {
+        let config = Config {
+            eth2_network: NetworkSelection::Named("prater".to_string()),
+            ..Config::default()
+        };
+        let network = config.resolve_network::<E>().unwrap();
+        assert_eq!(network.spec.config_name.as_deref(), Some("prater"));
+        assert_eq!(network.deposit_chain_id, 5);
+    }
+}
@@ -1,20 +1,28 @@
 extern crate slog;
 
+mod chain_health;
+mod checkpoint_sync;
 pub mod config;
+pub mod config_validation;
+mod datadir_version;
+pub mod disk_space;
+pub mod eth2_network;
 mod metrics;
 mod notifier;
+mod remote_genesis;
 
 pub mod builder;
 pub mod error;
 
 use beacon_chain::BeaconChain;
+use config::Config;
 use lighthouse_network::{Enr, Multiaddr, NetworkGlobals};
 use std::net::SocketAddr;
 use std::sync::Arc;
 
 pub use beacon_chain::{BeaconChainTypes, Eth1ChainBackend};
 pub use builder::ClientBuilder;
-pub use config::{ClientGenesis, Config as ClientConfig};
+pub use config::{BuilderConfig, ClientGenesis, Config as ClientConfig, NetworkSelection};
 pub use eth2_config::Eth2Config;
 
 /// The core "beacon node" client.
@@ -27,6 +35,8 @@ pub struct Client<T: BeaconChainTypes> {
     http_api_listen_addr: Option<SocketAddr>,
     /// Listen address for the HTTP server which serves Prometheus metrics.
     http_metrics_listen_addr: Option<SocketAddr>,
+    /// The effective config of the client, if it was provided.
+    effective_config: Option<Config>,
 }
 
 impl<T: BeaconChainTypes> Client<T> {
@@ -45,6 +55,11 @@ impl<T: BeaconChainTypes> Client<T> {
         self.http_metrics_listen_addr
     }
 
+    /// Returns the effective config of the client (see `Config::effective`), if it was provided.
+    pub fn effective_config(&self) -> Option<&Config> {
+        self.effective_config.as_ref()
+    }
+
     /// Returns the port of the client's libp2p stack, if it was started.
     pub fn libp2p_listen_port(&self) -> Option<u16> {
         self.network_globals.as_ref().map(|n| n.listen_port_tcp())
@@ -16,4 +16,10 @@ lazy_static! {
         "notifier_head_slot",
         "The head slot sourced from the beacon chain notifier"
     );
+
+    pub static ref DISK_SPACE_AVAILABLE: Result<IntGaugeVec> = try_create_int_gauge_vec(
+        "disk_space_available_bytes",
+        "The free space of the filesystem containing each database",
+        &["database"]
+    );
 }
@@ -0,0 +1,307 @@
+//! Downloads the genesis state from a URL, for use with `ClientGenesis::RemoteSsz`.
+//!
+//! The downloaded state is cached in the data directory so that it's only downloaded once, even if
+//! the database is later purged.
+use crate::checkpoint_sync::with_retries;
+use eth2::{types::Accept, BeaconNodeHttpClient, Timeouts};
+use eth2_hashing::hash;
+use sensitive_url::SensitiveUrl;
+use slog::{debug, info, warn, Logger};
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+use types::{BeaconState, ChainSpec, EthSpec, Hash256};
+
+/// The name of the file in the data directory in which a downloaded genesis state is cached.
+pub const REMOTE_GENESIS_CACHE_FILENAME: &str = "remote_genesis.ssz";
+
+/// The delay before the first retry of a failed download. It is doubled for each subsequent retry.
+const REMOTE_GENESIS_INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Load the genesis state from `url`, or from the cache at `cache_path` if it has been downloaded
+/// previously.
+///
+/// If `checksum` is supplied then the state must have either a SHA-256 hash or a tree hash root
+/// that matches it.
+pub async fn fetch_remote_genesis_state<E: EthSpec>(
+    url: &SensitiveUrl,
+    checksum: Option<Hash256>,
+    timeout: Duration,
+    cache_path: &Path,
+    spec: &ChainSpec,
+    log: &Logger,
+) -> Result<BeaconState<E>, String> {
+    fetch_remote_genesis_state_with_backoff(
+        url,
+        checksum,
+        timeout,
+        REMOTE_GENESIS_INITIAL_BACKOFF,
+        cache_path,
+        spec,
+        log,
+    )
+    .await
+}
+
+async fn fetch_remote_genesis_state_with_backoff<E: EthSpec>(
+    url: &SensitiveUrl,
+    checksum: Option<Hash256>,
+    timeout: Duration,
+    initial_backoff: Duration,
+    cache_path: &Path,
+    spec: &ChainSpec,
+    log: &Logger,
+) -> Result<BeaconState<E>, String> {
+    if cache_path.exists() {
+        let cached = fs::read(cache_path)
+            .map_err(|e| {
+                format!(
+                    "Unable to read cached genesis state {:?}: {}",
+                    cache_path, e
+                )
+            })
+            .and_then(|bytes| verify_genesis_state(&bytes, checksum, url, spec));
+        match cached {
+            Ok(state) => {
+                info!(
+                    log,
+                    "Loaded cached genesis state";
+                    "path" => ?cache_path,
+                );
+                return Ok(state);
+            }
+            Err(e) => warn!(
+                log,
+                "Ignoring invalid cached genesis state";
+                "error" => e,
+                "path" => ?cache_path,
+            ),
+        }
+    }
+
+    info!(
+        log,
+        "Downloading genesis state";
+        "url" => %url,
+    );
+
+    let remote = BeaconNodeHttpClient::new(url.clone(), Timeouts::set_all(timeout));
+    let bytes = with_retries("genesis state", initial_backoff, log, || {
+        remote.get_bytes_opt_accept_header(url.full.clone(), Accept::Ssz)
+    })
+    .await?
+    .ok_or_else(|| {
+        format!(
+            "Genesis state not found at {} (HTTP 404), check the genesis state URL",
+            url
+        )
+    })?;
+
+    let state = verify_genesis_state(&bytes, checksum, url, spec)?;
+
+    // Write to a temporary file first so that an interrupted write can't leave a truncated cache.
+    let temp_path = cache_path.with_extension("ssz.tmp");
+    fs::write(&temp_path, &bytes)
+        .and_then(|()| fs::rename(&temp_path, cache_path))
+        .map_err(|e| format!("Unable to cache genesis state at {:?}: {}", cache_path, e))?;
+
+    debug!(
+        log,
+        "Cached genesis state";
+        "path" => ?cache_path,
+    );
+
+    Ok(state)
+}
+
+/// Decode the genesis state `bytes`, verifying them against `checksum` if it is supplied.
+fn verify_genesis_state<E: EthSpec>(
+    bytes: &[u8],
+    checksum: Option<Hash256>,
+    url: &SensitiveUrl,
+    spec: &ChainSpec,
+) -> Result<BeaconState<E>, String> {
+    let state = BeaconState::from_ssz_bytes(bytes, spec).map_err(|e| {
+        format!(
+            "Genesis state from {} is truncated or invalid ({} bytes): {:?}. Ensure the URL \
+             refers to an SSZ genesis state for the correct network",
+            url,
+            bytes.len(),
+            e
+        )
+    })?;
+
+    if let Some(checksum) = checksum {
+        let sha256 = Hash256::from_slice(&hash(bytes));
+        if sha256 != checksum {
+            let state_root = state.canonical_root();
+            if state_root != checksum {
+                return Err(format!(
+                    "Genesis state from {} does not match the expected checksum {:?}. Its SHA-256 \
+                     hash is {:?} and its state root is {:?}. The file may be corrupt or for a \
+                     different network",
+                    url, checksum, sha256, state_root
+                ));
+            }
+        }
+    }
+
+    Ok(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use genesis::{interop_genesis_state, DEFAULT_ETH1_BLOCK_HASH};
+    use ssz::Encode;
+    use std::net::SocketAddr;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use types::{test_utils::generate_deterministic_keypairs, MinimalEthSpec};
+    use warp::{http::StatusCode, Filter, Reply};
+
+    type E = MinimalEthSpec;
+
+    const TEST_BACKOFF: Duration = Duration::from_millis(10);
+    const TEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+    struct Rig {
+        state: BeaconState<E>,
+        bytes: Vec<u8>,
+        url: SensitiveUrl,
+        requests: Arc<AtomicUsize>,
+        spec: ChainSpec,
+        log: Logger,
+        dir: tempfile::TempDir,
+    }
+
+    impl Rig {
+        /// Serve a genesis state at `/genesis.ssz`. Any other path returns a 404.
+        fn new() -> Self {
+            let spec = E::default_spec();
+            let keypairs = generate_deterministic_keypairs(8);
+            let state = interop_genesis_state::<E>(
+                &keypairs,
+                0,
+                Hash256::from_slice(DEFAULT_ETH1_BLOCK_HASH),
+                None,
+                &spec,
+            )
+            .unwrap();
+            let bytes = state.as_ssz_bytes();
+
+            let requests = Arc::new(AtomicUsize::new(0));
+            let inner_requests = requests.clone();
+            let inner_bytes = bytes.clone();
+            let genesis = warp::path!("genesis.ssz").map(move || {
+                inner_requests.fetch_add(1, Ordering::Relaxed);
+                inner_bytes.clone().into_response()
+            });
+            let not_found =
+                warp::any().map(|| warp::reply::with_status("not found", StatusCode::NOT_FOUND));
+            let (addr, server): (SocketAddr, _) =
+                warp::serve(genesis.or(not_found)).bind_ephemeral(([127, 0, 0, 1], 0));
+            tokio::spawn(server);
+
+            let url = SensitiveUrl::parse(&format!("http://{}/genesis.ssz", addr)).unwrap();
+
+            Self {
+                state,
+                bytes,
+                url,
+                requests,
+                spec,
+                log: environment::null_logger().unwrap(),
+                dir: tempfile::tempdir().unwrap(),
+            }
+        }
+
+        async fn fetch(
+            &self,
+            url: &SensitiveUrl,
+            checksum: Option<Hash256>,
+        ) -> Result<BeaconState<E>, String> {
+            fetch_remote_genesis_state_with_backoff(
+                url,
+                checksum,
+                TEST_TIMEOUT,
+                TEST_BACKOFF,
+                &self.cache_path(),
+                &self.spec,
+                &self.log,
+            )
+            .await
+        }
+
+        fn cache_path(&self) -> std::path::PathBuf {
+            self.dir.path().join(REMOTE_GENESIS_CACHE_FILENAME)
+        }
+
+        fn requests(&self) -> usize {
+            self.requests.load(Ordering::Relaxed)
+        }
+    }
+
+    #[tokio::test]
+    async fn download_and_reuse_cache() {
+        let rig = Rig::new();
+        let checksum = Hash256::from_slice(&hash(&rig.bytes));
+
+        let state = rig.fetch(&rig.url, Some(checksum)).await.unwrap();
+        assert_eq!(state, rig.state);
+        assert_eq!(rig.requests(), 1);
+        assert_eq!(fs::read(rig.cache_path()).unwrap(), rig.bytes);
+
+        // The second start should use the cache, rather than downloading again.
+        let state = rig.fetch(&rig.url, Some(checksum)).await.unwrap();
+        assert_eq!(state, rig.state);
+        assert_eq!(rig.requests(), 1);
+    }
+
+    #[tokio::test]
+    async fn accept_state_root_checksum() {
+        let rig = Rig::new();
+        let checksum = rig.state.canonical_root();
+
+        let state = rig.fetch(&rig.url, Some(checksum)).await.unwrap();
+        assert_eq!(state, rig.state);
+    }
+
+    #[tokio::test]
+    async fn reject_checksum_mismatch() {
+        let rig = Rig::new();
+
+        let error = rig
+            .fetch(&rig.url, Some(Hash256::repeat_byte(42)))
+            .await
+            .unwrap_err();
+        assert!(
+            error.contains("does not match the expected checksum"),
+            "{}",
+            error
+        );
+        assert!(!rig.cache_path().exists());
+    }
+
+    #[tokio::test]
+    async fn reject_invalid_cache() {
+        let rig = Rig::new();
+        let checksum = Hash256::from_slice(&hash(&rig.bytes));
+
+        // A truncated cache file should be ignored and replaced.
+        fs::write(rig.cache_path(), &rig.bytes[..rig.bytes.len() / 2]).unwrap();
+        let state = rig.fetch(&rig.url, Some(checksum)).await.unwrap();
+        assert_eq!(state, rig.state);
+        assert_eq!(rig.requests(), 1);
+        assert_eq!(fs::read(rig.cache_path()).unwrap(), rig.bytes);
+    }
+
+    #[tokio::test]
+    async fn not_found() {
+        let rig = Rig::new();
+        let url = SensitiveUrl::parse(&format!("{}/missing", rig.url.full)).unwrap();
+
+        let error = rig.fetch(&url, None).await.unwrap_err();
+        assert!(error.contains("HTTP 404"), "{}", error);
+    }
+}
@@ -1,9 +1,15 @@
 use execution_layer::http::deposit_log::DepositLog;
+use ssz::four_byte_option_impl;
 use ssz_derive::{Decode, Encode};
 use state_processing::common::DepositDataTree;
 use std::cmp::Ordering;
+use superstruct::superstruct;
 use tree_hash::TreeHash;
-use types::{Deposit, Hash256, DEPOSIT_TREE_DEPTH};
+use types::{Deposit, DepositTreeSnapshot, Hash256, DEPOSIT_TREE_DEPTH};
+
+// Define "legacy" implementations of `Option<DepositTreeSnapshot>` which use four bytes for
+// encoding the union selector.
+four_byte_option_impl!(four_byte_option_deposit_tree_snapshot, DepositTreeSnapshot);
 
 #[derive(Debug, PartialEq)]
 pub enum Error {
@@ -25,18 +31,64 @@ pub enum Error {
     ///
     /// E.g., you cannot request deposit 10 when the deposit count is 9.
     DepositCountInvalid { deposit_count: u64, range_end: u64 },
+    /// The requested deposits were included in the snapshot the cache was initialised from, so
+    /// only the hashes of their subtrees are known.
+    DepositsBeforeSnapshot {
+        requested: u64,
+        snapshot_deposit_count: u64,
+    },
+    /// The root of the deposit tree snapshot doesn't match its finalized hashes.
+    InvalidSnapshot,
     /// Error with the merkle tree for deposits.
     DepositTree(merkle_proof::MerkleTreeError),
     /// An unexpected condition was encountered.
     Internal(String),
 }
 
-#[derive(Encode, Decode, Clone)]
+// If adding a new version you should update this type alias and fix the breakages.
+pub type SszDepositCache = SszDepositCacheV10;
+
+#[superstruct(
+    variants(V1, V10),
+    variant_attributes(derive(Encode, Decode, Clone)),
+    no_enum
+)]
 pub struct SszDepositCache {
-    logs: Vec<DepositLog>,
-    leaves: Vec<Hash256>,
-    deposit_contract_deploy_block: u64,
-    deposit_roots: Vec<Hash256>,
+    pub logs: Vec<DepositLog>,
+    pub leaves: Vec<Hash256>,
+    pub deposit_contract_deploy_block: u64,
+    pub deposit_roots: Vec<Hash256>,
+    #[superstruct(only(V10))]
+    #[ssz(with = "four_byte_option_deposit_tree_snapshot")]
+    pub snapshot: Option<DepositTreeSnapshot>,
+}
+
+impl From<SszDepositCacheV1> for SszDepositCacheV10 {
+    fn from(cache: SszDepositCacheV1) -> Self {
+        Self {
+            logs: cache.logs,
+            leaves: cache.leaves,
+            deposit_contract_deploy_block: cache.deposit_contract_deploy_block,
+            deposit_roots: cache.deposit_roots,
+            snapshot: None,
+        }
+    }
+}
+
+impl TryFrom<SszDepositCacheV10> for SszDepositCacheV1 {
+    type Error = String;
+
+    fn try_from(cache: SszDepositCacheV10) -> Result<Self, String> {
+        if cache.snapshot.is_some() {
+            return Err("Deposit caches initialised from a snapshot can't be downgraded".into());
+        }
+        Ok(Self {
+            logs: cache.logs,
+            leaves: cache.leaves,
+            deposit_contract_deploy_block: cache.deposit_contract_deploy_block,
+            deposit_roots: cache.deposit_roots,
+        })
+    }
 }
 
 impl SszDepositCache {
@@ -46,12 +98,25 @@ impl SszDepositCache {
             leaves: cache.leaves.clone(),
             deposit_contract_deploy_block: cache.deposit_contract_deploy_block,
             deposit_roots: cache.deposit_roots.clone(),
+            snapshot: cache.snapshot.clone(),
         }
     }
 
     pub fn to_deposit_cache(&self) -> Result<DepositCache, String> {
-        let deposit_tree =
-            DepositDataTree::create(&self.leaves, self.leaves.len(), DEPOSIT_TREE_DEPTH);
+        let mut deposit_tree = match &self.snapshot {
+            Some(snapshot) => DepositDataTree::from_snapshot(
+                &snapshot.finalized,
+                snapshot.deposit_count as usize,
+                DEPOSIT_TREE_DEPTH,
+            )
+            .map_err(|e| format!("Invalid SszDepositCache: invalid snapshot: {:?}", e))?,
+            None => DepositDataTree::create(&[], 0, DEPOSIT_TREE_DEPTH),
+        };
+        for leaf in &self.leaves {
+            deposit_tree
+                .push_leaf(*leaf)
+                .map_err(|e| format!("Invalid SszDepositCache: {:?}", e))?;
+        }
         // Check for invalid SszDepositCache conditions
         if self.leaves.len() != self.logs.len() {
             return Err("Invalid SszDepositCache: logs and leaves should have equal length".into());
@@ -67,6 +132,7 @@ impl SszDepositCache {
             logs: self.logs.clone(),
             leaves: self.leaves.clone(),
             deposit_contract_deploy_block: self.deposit_contract_deploy_block,
+            snapshot: self.snapshot.clone(),
             deposit_tree,
             deposit_roots: self.deposit_roots.clone(),
         })
@@ -76,15 +142,21 @@ impl SszDepositCache {
 /// Mirrors the merkle tree of deposits in the eth1 deposit contract.
 ///
 /// Provides `Deposit` objects with merkle proofs included.
+///
+/// The cache may be initialised from a `DepositTreeSnapshot`, in which case only the deposits
+/// after the snapshot are stored and proofs can only be generated for those deposits.
 pub struct DepositCache {
+    /// The logs of the deposits after the snapshot, if any.
     logs: Vec<DepositLog>,
     leaves: Vec<Hash256>,
     deposit_contract_deploy_block: u64,
+    /// The snapshot the cache was initialised from.
+    snapshot: Option<DepositTreeSnapshot>,
     /// An incremental merkle tree which represents the current state of the
     /// deposit contract tree.
     deposit_tree: DepositDataTree,
     /// Vector of deposit roots. `deposit_roots[i]` denotes `deposit_root` at
-    /// `deposit_index` `i`.
+    /// `deposit_index` `i`, counting from the snapshot's `deposit_count`.
     deposit_roots: Vec<Hash256>,
 }
 
@@ -96,6 +168,7 @@ impl Default for DepositCache {
             logs: Vec::new(),
             leaves: Vec::new(),
             deposit_contract_deploy_block: 1,
+            snapshot: None,
             deposit_tree,
             deposit_roots,
         }
@@ -118,27 +191,71 @@ impl DepositCache {
         }
     }
 
-    /// Returns the number of deposits available in the cache.
+    /// Create a new `DepositCache` which contains the deposits in `snapshot`, to which the logs
+    /// after `snapshot.execution_block_height` can be added.
+    pub fn from_snapshot(
+        deposit_contract_deploy_block: u64,
+        snapshot: DepositTreeSnapshot,
+    ) -> Result<Self, Error> {
+        if !snapshot.is_valid() {
+            return Err(Error::InvalidSnapshot);
+        }
+        let deposit_tree = DepositDataTree::from_snapshot(
+            &snapshot.finalized,
+            snapshot.deposit_count as usize,
+            DEPOSIT_TREE_DEPTH,
+        )
+        .map_err(Error::DepositTree)?;
+
+        Ok(DepositCache {
+            logs: Vec::new(),
+            leaves: Vec::new(),
+            deposit_contract_deploy_block,
+            deposit_roots: vec![deposit_tree.root()],
+            deposit_tree,
+            snapshot: Some(snapshot),
+        })
+    }
+
+    /// Returns the snapshot the cache was initialised from, if any.
+    pub fn snapshot(&self) -> Option<&DepositTreeSnapshot> {
+        self.snapshot.as_ref()
+    }
+
+    /// Returns the number of deposits contained in the snapshot the cache was initialised from.
+    fn snapshot_deposit_count(&self) -> u64 {
+        self.snapshot
+            .as_ref()
+            .map_or(0, |snapshot| snapshot.deposit_count)
+    }
+
+    /// Returns the number of deposits available in the cache, including those in the snapshot.
     pub fn len(&self) -> usize {
-        self.logs.len()
+        self.snapshot_deposit_count() as usize + self.logs.len()
     }
 
     /// True if the cache does not store any blocks.
     pub fn is_empty(&self) -> bool {
-        self.logs.is_empty()
+        self.len() == 0
     }
 
     /// Returns the block number for the most recent deposit in the cache.
+    ///
+    /// This is the block of the snapshot if there have been no deposits since.
     pub fn latest_block_number(&self) -> Option<u64> {
-        self.logs.last().map(|log| log.block_number)
+        self.logs.last().map(|log| log.block_number).or_else(|| {
+            self.snapshot
+                .as_ref()
+                .map(|snapshot| snapshot.execution_block_height)
+        })
     }
 
-    /// Returns an iterator over all the logs in `self`.
+    /// Returns an iterator over all the logs in `self`, excluding those in the snapshot.
     pub fn iter(&self) -> impl Iterator<Item = &DepositLog> {
         self.logs.iter()
     }
 
-    /// Returns the i'th deposit log.
+    /// Returns the i'th deposit log after the snapshot.
     pub fn get(&self, i: usize) -> Option<&DepositLog> {
         self.logs.get(i)
     }
@@ -146,14 +263,15 @@ impl DepositCache {
     /// Adds `log` to self.
     ///
     /// This function enforces that `logs` are imported one-by-one with no gaps between
-    /// `log.index`, starting at `log.index == 0`.
+    /// `log.index`, starting at `log.index == 0` or the deposit count of the snapshot.
     ///
     /// ## Errors
     ///
     /// - If a log with index `log.index - 1` is not already present in `self` (ignored when empty).
     /// - If a log with `log.index` is already known, but the given `log` is distinct to it.
     pub fn insert_log(&mut self, log: DepositLog) -> Result<DepositCacheInsertOutcome, Error> {
-        match log.index.cmp(&(self.logs.len() as u64)) {
+        let snapshot_deposit_count = self.snapshot_deposit_count();
+        match log.index.cmp(&(self.len() as u64)) {
             Ordering::Equal => {
                 let deposit = log.deposit_data.tree_hash_root();
                 self.leaves.push(deposit);
@@ -164,8 +282,12 @@ impl DepositCache {
                 self.deposit_roots.push(self.deposit_tree.root());
                 Ok(DepositCacheInsertOutcome::Inserted)
             }
+            // The logs in the snapshot are unknown, so they can't be checked.
+            Ordering::Less if log.index < snapshot_deposit_count => {
+                Ok(DepositCacheInsertOutcome::Duplicate)
+            }
             Ordering::Less => {
-                if self.logs[log.index as usize] == log {
+                if self.logs[(log.index - snapshot_deposit_count) as usize] == log {
                     Ok(DepositCacheInsertOutcome::Duplicate)
                 } else {
                     Err(Error::DuplicateDistinctLog(log.index))
@@ -173,11 +295,50 @@ impl DepositCache {
             }
             Ordering::Greater => Err(Error::NonConsecutive {
                 log_index: log.index,
-                expected: self.logs.len(),
+                expected: self.len(),
             }),
         }
     }
 
+    /// Returns a new `DepositDataTree` containing the first `deposit_count` deposits.
+    fn deposit_tree_at(
+        &self,
+        deposit_count: u64,
+        tree_depth: usize,
+    ) -> Result<DepositDataTree, Error> {
+        let snapshot_deposit_count = self.snapshot_deposit_count();
+        let leaves = deposit_count
+            .checked_sub(snapshot_deposit_count)
+            .and_then(|count| self.leaves.get(0..count as usize))
+            .ok_or_else(|| Error::Internal("Unable to get known leaves".into()))?;
+
+        // Note: there is likely a more optimal solution than recreating the `DepositDataTree`
+        // each time this function is called.
+        //
+        // Perhaps a base merkle tree could be maintained that contains all deposits up to the
+        // last finalized eth1 deposit count. Then, that tree could be cloned and extended for
+        // each of these calls.
+        match &self.snapshot {
+            Some(snapshot) => {
+                let mut tree = DepositDataTree::from_snapshot(
+                    &snapshot.finalized,
+                    snapshot_deposit_count as usize,
+                    tree_depth,
+                )
+                .map_err(Error::DepositTree)?;
+                for leaf in leaves {
+                    tree.push_leaf(*leaf).map_err(Error::DepositTree)?;
+                }
+                Ok(tree)
+            }
+            None => Ok(DepositDataTree::create(
+                leaves,
+                deposit_count as usize,
+                tree_depth,
+            )),
+        }
+    }
+
     /// Returns a list of `Deposit` objects, within the given deposit index `range`.
     ///
     /// The `deposit_count` is used to generate the proofs for the `Deposits`. For example, if we
@@ -189,6 +350,7 @@ impl DepositCache {
     ///
     /// - If `deposit_count` is larger than `end`.
     /// - There are not sufficient deposits in the tree to generate the proof.
+    /// - The range or `deposit_count` starts before the end of the snapshot.
     pub fn get_deposits(
         &self,
         start: u64,
@@ -196,65 +358,104 @@ impl DepositCache {
         deposit_count: u64,
         tree_depth: usize,
     ) -> Result<(Hash256, Vec<Deposit>), Error> {
+        let snapshot_deposit_count = self.snapshot_deposit_count();
         if deposit_count < end {
             // It's invalid to ask for more deposits than should exist.
             Err(Error::DepositCountInvalid {
                 deposit_count,
                 range_end: end,
             })
-        } else if end > self.logs.len() as u64 {
+        } else if end > self.len() as u64 {
             // The range of requested deposits exceeds the deposits stored locally.
             Err(Error::InsufficientDeposits {
                 requested: end,
-                known_deposits: self.logs.len(),
+                known_deposits: self.len(),
             })
-        } else if deposit_count > self.leaves.len() as u64 {
+        } else if deposit_count > self.len() as u64 {
             // There are not `deposit_count` known deposit roots, so we can't build the merkle tree
             // to prove into.
             Err(Error::InsufficientDeposits {
                 requested: deposit_count,
-                known_deposits: self.logs.len(),
+                known_deposits: self.len(),
+            })
+        } else if (start < end && start < snapshot_deposit_count)
+            || deposit_count < snapshot_deposit_count
+        {
+            // Only the hashes of the subtrees of the deposits in the snapshot are known.
+            Err(Error::DepositsBeforeSnapshot {
+                requested: std::cmp::min(start, deposit_count),
+                snapshot_deposit_count,
             })
         } else {
-            let leaves = self
-                .leaves
-                .get(0..deposit_count as usize)
-                .ok_or_else(|| Error::Internal("Unable to get known leaves".into()))?;
-
-            // Note: there is likely a more optimal solution than recreating the `DepositDataTree`
-            // each time this function is called.
-            //
-            // Perhaps a base merkle tree could be maintained that contains all deposits up to the
-            // last finalized eth1 deposit count. Then, that tree could be cloned and extended for
-            // each of these calls.
-
-            let tree = DepositDataTree::create(leaves, deposit_count as usize, tree_depth);
+            let tree = self.deposit_tree_at(deposit_count, tree_depth)?;
 
             let deposits = self
                 .logs
-                .get(start as usize..end as usize)
+                .get(
+                    start.saturating_sub(snapshot_deposit_count) as usize
+                        ..end.saturating_sub(snapshot_deposit_count) as usize,
+                )
                 .ok_or_else(|| Error::Internal("Unable to get known log".into()))?
                 .iter()
                 .map(|deposit_log| {
-                    let (_leaf, proof) = tree.generate_proof(deposit_log.index as usize);
+                    let (_leaf, proof) = tree
+                        .generate_proof(deposit_log.index as usize)
+                        .map_err(Error::DepositTree)?;
 
-                    Deposit {
+                    Ok(Deposit {
                         proof: proof.into(),
                         data: deposit_log.deposit_data.clone(),
-                    }
+                    })
                 })
-                .collect();
+                .collect::<Result<_, _>>()?;
 
             Ok((tree.root(), deposits))
         }
     }
 
+    /// Returns a snapshot of the first `deposit_count` deposits, which were all included in the
+    /// eth1 block with the given hash and height.
+    pub fn get_snapshot(
+        &self,
+        deposit_count: u64,
+        execution_block_hash: Hash256,
+        execution_block_height: u64,
+    ) -> Result<DepositTreeSnapshot, Error> {
+        let snapshot_deposit_count = self.snapshot_deposit_count();
+        if deposit_count > self.len() as u64 {
+            return Err(Error::InsufficientDeposits {
+                requested: deposit_count,
+                known_deposits: self.len(),
+            });
+        } else if deposit_count < snapshot_deposit_count {
+            return Err(Error::DepositsBeforeSnapshot {
+                requested: deposit_count,
+                snapshot_deposit_count,
+            });
+        }
+
+        let tree = self.deposit_tree_at(deposit_count, DEPOSIT_TREE_DEPTH)?;
+        Ok(DepositTreeSnapshot {
+            finalized: tree
+                .get_finalized_hashes(deposit_count as usize)
+                .map_err(Error::DepositTree)?,
+            deposit_root: tree.root(),
+            deposit_count,
+            execution_block_hash,
+            execution_block_height,
+        })
+    }
+
     /// Returns the number of deposits with valid signatures that have been observed up to and
     /// including the block at `block_number`.
     ///
-    /// Returns `None` if the `block_number` is zero or prior to contract deployment.
+    /// Returns `None` if the `block_number` is zero or prior to contract deployment, or if the
+    /// cache was initialised from a snapshot, since the signatures of its deposits are unknown.
     pub fn get_valid_signature_count(&self, block_number: u64) -> Option<usize> {
-        if block_number == 0 || block_number < self.deposit_contract_deploy_block {
+        if block_number == 0
+            || block_number < self.deposit_contract_deploy_block
+            || self.snapshot.is_some()
+        {
             None
         } else {
             Some(
@@ -270,16 +471,23 @@ impl DepositCache {
     /// Returns the number of deposits that have been observed up to and
     /// including the block at `block_number`.
     ///
-    /// Returns `None` if the `block_number` is zero or prior to contract deployment.
+    /// Returns `None` if the `block_number` is zero or prior to contract deployment, or if it is
+    /// prior to the block of the snapshot the cache was initialised from.
     pub fn get_deposit_count_from_cache(&self, block_number: u64) -> Option<u64> {
-        if block_number == 0 || block_number < self.deposit_contract_deploy_block {
+        let before_snapshot = self.snapshot.as_ref().map_or(false, |snapshot| {
+            block_number < snapshot.execution_block_height
+        });
+        if block_number == 0 || block_number < self.deposit_contract_deploy_block || before_snapshot
+        {
             None
         } else {
             Some(
-                self.logs
-                    .iter()
-                    .take_while(|deposit| deposit.block_number <= block_number)
-                    .count() as u64,
+                self.snapshot_deposit_count()
+                    + self
+                        .logs
+                        .iter()
+                        .take_while(|deposit| deposit.block_number <= block_number)
+                        .count() as u64,
             )
         }
     }
@@ -289,7 +497,9 @@ impl DepositCache {
     /// Fetches the `deposit_count` on or just before the queried `block_number`
     /// and queries the `deposit_roots` map to get the corresponding `deposit_root`.
     pub fn get_deposit_root_from_cache(&self, block_number: u64) -> Option<Hash256> {
-        let index = self.get_deposit_count_from_cache(block_number)?;
+        let index = self
+            .get_deposit_count_from_cache(block_number)?
+            .checked_sub(self.snapshot_deposit_count())?;
         Some(*self.deposit_roots.get(index as usize)?)
     }
 }
@@ -298,6 +508,7 @@ impl DepositCache {
 pub mod tests {
     use super::*;
     use execution_layer::http::deposit_log::Log;
+    use ssz::{Decode, Encode};
     use types::{EthSpec, MainnetEthSpec};
 
     pub const TREE_DEPTH: usize = 32;
@@ -478,4 +689,140 @@ pub mod tests {
         // Range higher than count.
         assert!(tree.get_deposits(0, 4, 2, TREE_DEPTH).is_err());
     }
+
+    /// Returns a cache containing `n` deposits, one per block.
+    fn full_cache(n: u64) -> DepositCache {
+        let mut tree = DepositCache::default();
+        for i in 0..n {
+            let mut log = example_log();
+            log.index = i;
+            log.block_number = i + 1;
+            log.deposit_data.withdrawal_credentials = Hash256::from_low_u64_be(i);
+            tree.insert_log(log).expect("should add consecutive logs");
+        }
+        tree
+    }
+
+    #[test]
+    fn from_snapshot() {
+        let n = 64;
+        let snapshot_count = 37;
+        let full = full_cache(n);
+
+        let snapshot = full
+            .get_snapshot(snapshot_count, Hash256::repeat_byte(42), snapshot_count)
+            .expect("should get snapshot");
+        assert!(snapshot.is_valid());
+        assert_eq!(
+            Some(snapshot.deposit_root),
+            full.get_deposit_root_from_cache(snapshot_count)
+        );
+
+        let mut tree =
+            DepositCache::from_snapshot(1, snapshot.clone()).expect("should load snapshot");
+        assert_eq!(tree.len(), snapshot_count as usize);
+        assert_eq!(tree.latest_block_number(), Some(snapshot_count));
+
+        // Logs included in the snapshot are ignored.
+        assert_eq!(
+            tree.insert_log(full.get(0).unwrap().clone()),
+            Ok(DepositCacheInsertOutcome::Duplicate)
+        );
+        for log in full.iter().skip(snapshot_count as usize) {
+            tree.insert_log(log.clone())
+                .expect("should add consecutive logs");
+        }
+        assert_eq!(tree.len(), n as usize);
+
+        assert_eq!(
+            tree.get_deposits(40, 44, n, TREE_DEPTH),
+            full.get_deposits(40, 44, n, TREE_DEPTH),
+            "should generate the same proofs as the full cache"
+        );
+        assert_eq!(
+            tree.get_deposits(snapshot_count, 50, 50, TREE_DEPTH),
+            full.get_deposits(snapshot_count, 50, 50, TREE_DEPTH),
+        );
+        assert_eq!(
+            tree.get_deposits(0, 4, n, TREE_DEPTH),
+            Err(Error::DepositsBeforeSnapshot {
+                requested: 0,
+                snapshot_deposit_count: snapshot_count,
+            })
+        );
+        assert!(tree.get_deposits(36, 36, 36, TREE_DEPTH).is_err());
+
+        for block_number in 0..n + 2 {
+            if block_number < snapshot_count {
+                assert_eq!(tree.get_deposit_count_from_cache(block_number), None);
+                assert_eq!(tree.get_deposit_root_from_cache(block_number), None);
+            } else {
+                assert_eq!(
+                    tree.get_deposit_count_from_cache(block_number),
+                    full.get_deposit_count_from_cache(block_number)
+                );
+                assert_eq!(
+                    tree.get_deposit_root_from_cache(block_number),
+                    full.get_deposit_root_from_cache(block_number)
+                );
+            }
+        }
+        assert_eq!(tree.get_valid_signature_count(n), None);
+
+        // A later snapshot can be served from the cache.
+        assert_eq!(
+            tree.get_snapshot(50, Hash256::zero(), 50),
+            full.get_snapshot(50, Hash256::zero(), 50)
+        );
+        assert!(tree
+            .get_snapshot(snapshot_count - 1, Hash256::zero(), 1)
+            .is_err());
+
+        // The cache can be recovered from its SSZ representation.
+        let bytes = SszDepositCache::from_deposit_cache(&tree).as_ssz_bytes();
+        let recovered = SszDepositCache::from_ssz_bytes(&bytes)
+            .unwrap()
+            .to_deposit_cache()
+            .expect("should recover cache");
+        assert_eq!(recovered.snapshot(), Some(&snapshot));
+        assert_eq!(recovered.len(), n as usize);
+        assert_eq!(
+            recovered.get_deposits(40, 44, n, TREE_DEPTH),
+            full.get_deposits(40, 44, n, TREE_DEPTH),
+        );
+    }
+
+    #[test]
+    fn invalid_snapshot() {
+        let mut snapshot = full_cache(16)
+            .get_snapshot(16, Hash256::zero(), 16)
+            .unwrap();
+        snapshot.deposit_root = Hash256::repeat_byte(1);
+        assert!(matches!(
+            DepositCache::from_snapshot(1, snapshot),
+            Err(Error::InvalidSnapshot)
+        ));
+    }
+
+    #[test]
+    fn ssz_v1_upgrade() {
+        let tree = full_cache(8);
+        let ssz_cache = SszDepositCache::from_deposit_cache(&tree);
+        let v1 = SszDepositCacheV1::try_from(ssz_cache).expect("should downgrade");
+        let upgraded = SszDepositCacheV10::from(
+            SszDepositCacheV1::from_ssz_bytes(&v1.as_ssz_bytes()).unwrap(),
+        );
+        assert_eq!(upgraded.snapshot, None);
+        assert_eq!(
+            upgraded
+                .to_deposit_cache()
+                .unwrap()
+                .get_deposits(0, 8, 8, TREE_DEPTH),
+            tree.get_deposits(0, 8, 8, TREE_DEPTH)
+        );
+
+        let snapshot = tree.get_snapshot(8, Hash256::zero(), 8).unwrap();
+        let tree = DepositCache::from_snapshot(1, snapshot).unwrap();
+        assert!(SszDepositCacheV1::try_from(SszDepositCache::from_deposit_cache(&tree)).is_err());
+    }
 }
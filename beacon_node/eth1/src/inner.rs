@@ -1,7 +1,7 @@
 use crate::Config;
 use crate::{
     block_cache::{BlockCache, Eth1Block},
-    deposit_cache::{DepositCache, SszDepositCache},
+    deposit_cache::{DepositCache, SszDepositCache, SszDepositCacheV1, SszDepositCacheV10},
     service::EndpointsCache,
 };
 use parking_lot::RwLock;
@@ -9,7 +9,8 @@ use ssz::four_byte_option_impl;
 use ssz::{Decode, Encode};
 use ssz_derive::{Decode, Encode};
 use std::sync::Arc;
-use types::ChainSpec;
+use superstruct::superstruct;
+use types::{ChainSpec, DepositTreeSnapshot};
 
 // Define "legacy" implementations of `Option<u64>` which use four bytes for encoding the union
 // selector.
@@ -29,6 +30,21 @@ impl DepositUpdater {
             last_processed_block: None,
         }
     }
+
+    /// Create a new `DepositUpdater` containing the deposits in `snapshot`. Only the logs after
+    /// the block of the snapshot will be downloaded.
+    pub fn from_snapshot(
+        deposit_contract_deploy_block: u64,
+        snapshot: DepositTreeSnapshot,
+    ) -> Result<Self, String> {
+        let last_processed_block = Some(snapshot.execution_block_height);
+        let cache = DepositCache::from_snapshot(deposit_contract_deploy_block, snapshot)
+            .map_err(|e| format!("Invalid deposit tree snapshot: {:?}", e))?;
+        Ok(DepositUpdater {
+            cache,
+            last_processed_block,
+        })
+    }
 }
 
 #[derive(Default)]
@@ -70,12 +86,44 @@ impl Inner {
     }
 }
 
-#[derive(Encode, Decode, Clone)]
+// If adding a new version you should update this type alias and fix the breakages.
+pub type SszEth1Cache = SszEth1CacheV10;
+
+#[superstruct(
+    variants(V1, V10),
+    variant_attributes(derive(Encode, Decode, Clone)),
+    no_enum
+)]
 pub struct SszEth1Cache {
-    block_cache: BlockCache,
-    deposit_cache: SszDepositCache,
+    pub block_cache: BlockCache,
+    #[superstruct(only(V1))]
+    pub deposit_cache: SszDepositCacheV1,
+    #[superstruct(only(V10))]
+    pub deposit_cache: SszDepositCacheV10,
     #[ssz(with = "four_byte_option_u64")]
-    last_processed_block: Option<u64>,
+    pub last_processed_block: Option<u64>,
+}
+
+impl From<SszEth1CacheV1> for SszEth1CacheV10 {
+    fn from(cache: SszEth1CacheV1) -> Self {
+        Self {
+            block_cache: cache.block_cache,
+            deposit_cache: cache.deposit_cache.into(),
+            last_processed_block: cache.last_processed_block,
+        }
+    }
+}
+
+impl TryFrom<SszEth1CacheV10> for SszEth1CacheV1 {
+    type Error = String;
+
+    fn try_from(cache: SszEth1CacheV10) -> Result<Self, String> {
+        Ok(Self {
+            block_cache: cache.block_cache,
+            deposit_cache: cache.deposit_cache.try_into()?,
+            last_processed_block: cache.last_processed_block,
+        })
+    }
 }
 
 impl SszEth1Cache {
@@ -10,8 +10,8 @@ mod service;
 pub use block_cache::{BlockCache, Eth1Block};
 pub use deposit_cache::DepositCache;
 pub use execution_layer::http::deposit_log::DepositLog;
-pub use inner::SszEth1Cache;
+pub use inner::{SszEth1Cache, SszEth1CacheV1, SszEth1CacheV10};
 pub use service::{
-    BlockCacheUpdateOutcome, Config, DepositCacheUpdateOutcome, Error, Eth1Endpoint, Service,
-    DEFAULT_CHAIN_ID,
+    BlockCacheUpdateOutcome, Config, DepositCacheUpdateOutcome, Error, Eth1Endpoint,
+    NetworkMismatch, Service, DEFAULT_CHAIN_ID,
 };
@@ -23,7 +23,7 @@ use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 use tokio::sync::RwLock as TRwLock;
 use tokio::time::{interval_at, Duration, Instant};
-use types::{ChainSpec, EthSpec, Unsigned};
+use types::{ChainSpec, DepositTreeSnapshot, Eth1Data, EthSpec, Unsigned};
 
 /// Indicates the default eth1 chain id we use for the deposit contract.
 pub const DEFAULT_CHAIN_ID: Eth1Id = Eth1Id::Goerli;
@@ -39,6 +39,10 @@ const GET_BLOCK_TIMEOUT_MILLIS: u64 = STANDARD_TIMEOUT_MILLIS;
 /// Timeout when doing an eth_getLogs to read the deposit contract logs.
 const GET_DEPOSIT_LOG_TIMEOUT_MILLIS: u64 = 60_000;
 
+/// The interval at which the endpoints are checked to be on the network of the deposit contract,
+/// in case an endpoint (e.g., behind a load balancer) switches networks.
+const NETWORK_VERIFICATION_INTERVAL: Duration = Duration::from_secs(600);
+
 const WARNING_MSG: &str = "BLOCK PROPOSALS WILL FAIL WITHOUT VALID, SYNCED ETH1 CONNECTION";
 
 /// Number of blocks to download if the node detects it is lagging behind due to an inaccurate
@@ -223,6 +227,64 @@ async fn endpoint_state(
     }
 }
 
+/// The reason that an endpoint is considered to be on a different network to the configured
+/// deposit contract.
+#[derive(Debug, PartialEq, Clone)]
+pub enum NetworkMismatch {
+    WrongChainId {
+        expected: Eth1Id,
+        received: Eth1Id,
+    },
+    /// There is no contract at the deposit contract address, although the endpoint is past the
+    /// block at which it was deployed.
+    NoDepositContract {
+        address: String,
+        deploy_block: u64,
+        block_number: u64,
+    },
+}
+
+/// Checks that `endpoint` is on the network with `config_chain_id` on which a deposit contract
+/// was deployed at `deposit_contract_address` in `deposit_contract_deploy_block`.
+///
+/// Returns `Ok(None)` if the endpoint is on the expected network, or is not synced far enough to
+/// tell.
+async fn network_mismatch(
+    endpoint: &HttpJsonRpc,
+    config_chain_id: &Eth1Id,
+    deposit_contract_address: &str,
+    deposit_contract_deploy_block: u64,
+) -> Result<Option<NetworkMismatch>, String> {
+    let timeout = Duration::from_millis(STANDARD_TIMEOUT_MILLIS);
+
+    let chain_id = endpoint.get_chain_id(timeout).await?;
+    // Eth1 nodes return chain_id = 0 if the node is not synced.
+    if chain_id == Eth1Id::Custom(0) {
+        return Ok(None);
+    }
+    if &chain_id != config_chain_id {
+        return Ok(Some(NetworkMismatch::WrongChainId {
+            expected: config_chain_id.clone(),
+            received: chain_id,
+        }));
+    }
+
+    let block_number = endpoint.get_block_number(timeout).await?;
+    if block_number < deposit_contract_deploy_block {
+        return Ok(None);
+    }
+    let code = endpoint.get_code(deposit_contract_address, timeout).await?;
+    if code.is_empty() {
+        return Ok(Some(NetworkMismatch::NoDepositContract {
+            address: deposit_contract_address.to_string(),
+            deploy_block: deposit_contract_deploy_block,
+            block_number,
+        }));
+    }
+
+    Ok(None)
+}
+
 /// Enum for the two internal (maybe different) cached heads for cached deposits and for the block
 /// cache.
 pub enum HeadType {
@@ -428,18 +490,16 @@ pub struct Config {
     pub max_blocks_per_update: Option<usize>,
     /// If set to true, the eth1 caches are wiped clean when the eth1 service starts.
     pub purge_cache: bool,
+    /// If set to true, the node starts even if an endpoint is on a different network to the
+    /// deposit contract.
+    pub ignore_network_mismatch: bool,
 }
 
 impl Config {
     /// Sets the block cache to a length that is suitable for the given `EthSpec` and `ChainSpec`.
     pub fn set_block_cache_truncation<E: EthSpec>(&mut self, spec: &ChainSpec) {
-        // Compute the number of eth1 blocks in an eth1 voting period.
-        let seconds_per_voting_period =
-            E::SlotsPerEth1VotingPeriod::to_u64() * spec.seconds_per_slot;
-        let eth1_blocks_per_voting_period = seconds_per_voting_period / spec.seconds_per_eth1_block;
-
         // Ensure we can store two full windows of voting blocks.
-        let voting_windows = eth1_blocks_per_voting_period * 2;
+        let voting_windows = Self::eth1_blocks_per_voting_period::<E>(spec) * 2;
 
         // Extend the cache to account for the cache follow distance.
         let extra_follow_distance_blocks = self
@@ -456,6 +516,13 @@ impl Config {
         self.block_cache_truncation = Some(cache_size as usize);
     }
 
+    /// The number of eth1 blocks in an eth1 voting period.
+    pub fn eth1_blocks_per_voting_period<E: EthSpec>(spec: &ChainSpec) -> u64 {
+        let seconds_per_voting_period =
+            E::SlotsPerEth1VotingPeriod::to_u64() * spec.seconds_per_slot;
+        seconds_per_voting_period / spec.seconds_per_eth1_block
+    }
+
     /// The distance at which the cache should follow the head.
     ///
     /// Defaults to 3/4 of `follow_distance` unless set manually.
@@ -483,6 +550,7 @@ impl Default for Config {
             max_log_requests_per_update: Some(5_000),
             max_blocks_per_update: Some(8_192),
             purge_cache: false,
+            ignore_network_mismatch: false,
         }
     }
 }
@@ -517,6 +585,30 @@ impl Service {
         }
     }
 
+    /// Creates a new service with the deposit cache initialised from `snapshot`, so that only the
+    /// deposit logs after the block of the snapshot are downloaded. Does not attempt to connect to
+    /// the eth1 node.
+    pub fn from_deposit_snapshot(
+        config: Config,
+        log: Logger,
+        spec: ChainSpec,
+        snapshot: DepositTreeSnapshot,
+    ) -> Result<Self, String> {
+        let deposit_cache =
+            DepositUpdater::from_snapshot(config.deposit_contract_deploy_block, snapshot)?;
+        Ok(Self {
+            inner: Arc::new(Inner {
+                block_cache: <_>::default(),
+                deposit_cache: RwLock::new(deposit_cache),
+                endpoints_cache: RwLock::new(None),
+                remote_head_block: RwLock::new(None),
+                config: RwLock::new(config),
+                spec,
+            }),
+            log,
+        })
+    }
+
     /// Returns the follow distance that has been shortened to accommodate for differences in the
     /// spacing between blocks.
     ///
@@ -645,6 +737,27 @@ impl Service {
             .get_valid_signature_count(block_number)
     }
 
+    /// Returns a snapshot of the deposit tree at `eth1_data`.
+    ///
+    /// Returns `None` if the block of `eth1_data` isn't in the block cache, or if the deposits
+    /// in the cache don't match `eth1_data`.
+    pub fn get_deposit_snapshot(&self, eth1_data: &Eth1Data) -> Option<DepositTreeSnapshot> {
+        let block_number = self
+            .blocks()
+            .read()
+            .iter()
+            .rev()
+            .find(|block| block.hash == eth1_data.block_hash)?
+            .number;
+        let snapshot = self
+            .deposits()
+            .read()
+            .cache
+            .get_snapshot(eth1_data.deposit_count, eth1_data.block_hash, block_number)
+            .ok()?;
+        (snapshot.deposit_root == eth1_data.deposit_root).then(|| snapshot)
+    }
+
     /// Read the service's configuration.
     pub fn config(&self) -> RwLockReadGuard<Config> {
         self.inner.config.read()
@@ -724,6 +837,69 @@ impl Service {
         }
     }
 
+    /// Check that at least one endpoint is reachable, synced and on the configured chain id,
+    /// returning an error describing the state of each endpoint if none are.
+    pub async fn check_endpoints(&self) -> Result<(), String> {
+        let endpoints = self.get_endpoints()?;
+        endpoints.reset_errorred_endpoints().await;
+        endpoints
+            .first_success(|_| async { Ok::<_, SingleEndpointError>(()) })
+            .await
+            .map(|_| ())
+            .map_err(|e| format!("{:?}", e))
+    }
+
+    /// Check that each endpoint is on the network of the deposit contract, i.e. that it has the
+    /// configured chain id and that the deposit contract exists at the configured address.
+    ///
+    /// Returns an error describing each endpoint which is on a different network. Endpoints
+    /// which are unreachable or not yet synced cannot be checked, and are ignored. The endpoints
+    /// on a different network have their chain id re-checked before they are next used.
+    pub async fn verify_network(&self) -> Result<(), String> {
+        let endpoints = self.get_endpoints()?;
+        let (chain_id, deposit_contract_address, deposit_contract_deploy_block) = {
+            let config = self.config();
+            (
+                config.chain_id.clone(),
+                config.deposit_contract_address.clone(),
+                config.deposit_contract_deploy_block,
+            )
+        };
+
+        let mut mismatches = vec![];
+        for endpoint in &endpoints.fallback.servers {
+            match network_mismatch(
+                &endpoint.client,
+                &chain_id,
+                &deposit_contract_address,
+                deposit_contract_deploy_block,
+            )
+            .await
+            {
+                Ok(None) => {}
+                Ok(Some(mismatch)) => {
+                    reset_endpoint_state(endpoint).await;
+                    mismatches.push(format!("{}: {:?}", endpoint.client, mismatch));
+                }
+                Err(e) => debug!(
+                    self.log,
+                    "Unable to verify network of eth1 endpoint";
+                    "endpoint" => %endpoint.client,
+                    "error" => e,
+                ),
+            }
+        }
+
+        if mismatches.is_empty() {
+            Ok(())
+        } else {
+            Err(format!(
+                "Eth1 endpoints are on a different network to the deposit contract: {}",
+                mismatches.join(", ")
+            ))
+        }
+    }
+
     /// Update the deposit and block cache, returning an error if either fail.
     ///
     /// ## Returns
@@ -854,6 +1030,9 @@ impl Service {
     /// A looping future that updates the cache, then waits `config.auto_update_interval` before
     /// updating it again.
     ///
+    /// Also spawns a looping future which verifies the network of the endpoints every
+    /// `NETWORK_VERIFICATION_INTERVAL`.
+    ///
     /// ## Returns
     ///
     /// - Ok(_) if the update was successful (the cache may or may not have been modified).
@@ -866,6 +1045,7 @@ impl Service {
         let mut interval = interval_at(Instant::now(), update_interval);
 
         let num_fallbacks = self.config().endpoints.len() - 1;
+        let service = self.clone();
         let update_future = async move {
             loop {
                 interval.tick().await;
@@ -883,6 +1063,25 @@ impl Service {
             metrics::set_gauge(&metrics::ETH1_FALLBACK_CONNECTED, 0);
         }
         handle.spawn(update_future, "eth1");
+
+        let verify_future = async move {
+            let mut interval = interval_at(
+                Instant::now() + NETWORK_VERIFICATION_INTERVAL,
+                NETWORK_VERIFICATION_INTERVAL,
+            );
+            loop {
+                interval.tick().await;
+                if let Err(e) = service.verify_network().await {
+                    crit!(
+                        service.log,
+                        "Eth1 endpoint switched network";
+                        "error" => e,
+                        "warning" => WARNING_MSG,
+                    );
+                }
+            }
+        };
+        handle.spawn(verify_future, "eth1_network_verification");
     }
 
     async fn do_update(&self, update_interval: Duration) -> Result<(), ()> {
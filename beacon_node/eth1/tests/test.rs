@@ -1080,3 +1080,69 @@ mod fallbacks {
         .await;
     }
 }
+
+/// Tests for the verification of the network of the endpoints.
+mod network_verification {
+    use super::*;
+
+    fn service_for(eth1: &GanacheEth1Instance, deposit_contract_address: String) -> Service {
+        Service::new(
+            Config {
+                endpoints: Eth1Endpoint::NoAuth(vec![SensitiveUrl::parse(
+                    eth1.endpoint().as_str(),
+                )
+                .unwrap()]),
+                deposit_contract_address,
+                deposit_contract_deploy_block: 0,
+                ..Config::default()
+            },
+            null_logger(),
+            MainnetEthSpec::default_spec(),
+        )
+    }
+
+    #[tokio::test]
+    async fn correct_network_is_accepted() {
+        async {
+            let eth1 = new_ganache_instance()
+                .await
+                .expect("should start eth1 environment");
+            let service = service_for(&eth1, eth1.deposit_contract.address());
+
+            assert_eq!(service.verify_network().await, Ok(()));
+        }
+        .await;
+    }
+
+    #[tokio::test]
+    async fn wrong_chain_id_is_rejected() {
+        async {
+            let correct_chain_id: u64 = DEFAULT_CHAIN_ID.into();
+            let eth1 = GanacheEth1Instance::new(correct_chain_id + 1)
+                .await
+                .expect("should start eth1 environment");
+            let service = service_for(&eth1, eth1.deposit_contract.address());
+
+            let error = service.verify_network().await.unwrap_err();
+            assert!(error.contains("WrongChainId"), "{}", error);
+        }
+        .await;
+    }
+
+    #[tokio::test]
+    async fn missing_deposit_contract_is_rejected() {
+        async {
+            let eth1 = new_ganache_instance()
+                .await
+                .expect("should start eth1 environment");
+            let service = service_for(
+                &eth1,
+                "0x0000000000000000000000000000000000000001".to_string(),
+            );
+
+            let error = service.verify_network().await.unwrap_err();
+            assert!(error.contains("NoDepositContract"), "{}", error);
+        }
+        .await;
+    }
+}
@@ -317,6 +317,16 @@ pub mod deposit_methods {
                 .map_err(|e| format!("Failed to get block number: {}", e))
         }
 
+        /// Returns the code of the contract at `address` as of the latest block, which is empty if
+        /// there is no contract at `address`.
+        pub async fn get_code(&self, address: &str, timeout: Duration) -> Result<Vec<u8>, String> {
+            let response: String = self
+                .rpc_request("eth_getCode", json!([address, "latest"]), timeout)
+                .await
+                .map_err(|e| format!("eth_getCode call failed {:?}", e))?;
+            hex_to_bytes(response.as_str())
+        }
+
         /// Gets a block hash by block number.
         pub async fn get_block(
             &self,
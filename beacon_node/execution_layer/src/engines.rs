@@ -3,11 +3,15 @@
 use crate::engine_api::{
     Error as EngineApiError, ForkchoiceUpdatedResponse, PayloadAttributes, PayloadId,
 };
-use crate::HttpJsonRpc;
+use crate::{metrics, HttpJsonRpc};
+use eth2::lighthouse::ExecutionEngineHealth;
+use futures::future::join_all;
 use lru::LruCache;
-use slog::{debug, error, info, Logger};
+use slog::{debug, error, info, warn, Logger};
 use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::time::Instant;
 use task_executor::TaskExecutor;
 use tokio::sync::{Mutex, RwLock};
 use types::{Address, ExecutionBlockHash, Hash256};
@@ -26,6 +30,15 @@ enum EngineState {
     AuthFailed,
 }
 
+/// The health of an engine, as observed by the requests made to it.
+#[derive(Copy, Clone, Default, Debug)]
+struct EngineHealth {
+    /// The time at which the most recent request succeeded.
+    last_success: Option<Instant>,
+    /// The number of requests which have failed since the most recent successful request.
+    consecutive_failures: u64,
+}
+
 #[derive(Copy, Clone, PartialEq, Debug)]
 pub struct ForkChoiceState {
     pub head_block_hash: ExecutionBlockHash,
@@ -54,6 +67,7 @@ pub struct Engine {
     pub api: HttpJsonRpc,
     payload_id_cache: Mutex<LruCache<PayloadIdCacheKey, PayloadId>>,
     state: RwLock<EngineState>,
+    health: RwLock<EngineHealth>,
     pub latest_forkchoice_state: RwLock<Option<ForkChoiceState>>,
     pub executor: TaskExecutor,
     pub log: Logger,
@@ -66,6 +80,7 @@ impl Engine {
             api,
             payload_id_cache: Mutex::new(LruCache::new(PAYLOAD_ID_LRU_CACHE_SIZE)),
             state: RwLock::new(EngineState::Offline),
+            health: <_>::default(),
             latest_forkchoice_state: Default::default(),
             executor,
             log: log.clone(),
@@ -127,7 +142,9 @@ impl Engine {
         *self.latest_forkchoice_state.write().await = Some(state);
     }
 
-    async fn send_latest_forkchoice_state(&self) {
+    /// Send the latest forkchoice state to the engine, returning an error if the engine failed to
+    /// receive it.
+    async fn send_latest_forkchoice_state(&self) -> Result<(), EngineApiError> {
         let latest_forkchoice_state = self.get_latest_forkchoice_state().await;
 
         if let Some(forkchoice_state) = latest_forkchoice_state {
@@ -137,7 +154,7 @@ impl Engine {
                     "No need to call forkchoiceUpdated";
                     "msg" => "head does not have execution enabled",
                 );
-                return;
+                return Ok(());
             }
 
             info!(
@@ -154,6 +171,7 @@ impl Engine {
                     "Failed to issue latest head to engine";
                     "error" => ?e,
                 );
+                return Err(e);
             }
         } else {
             debug!(
@@ -161,6 +179,29 @@ impl Engine {
                 "No head, not sending to engine";
             );
         }
+
+        Ok(())
+    }
+
+    /// Send the latest forkchoice state to the engine without payload attributes.
+    ///
+    /// This is used to keep standby engines following the head, so it only logs at debug.
+    async fn forkchoice_updated_to_latest(&self) -> Result<(), EngineApiError> {
+        let forkchoice_state = match self.get_latest_forkchoice_state().await {
+            Some(state) if state.head_block_hash != ExecutionBlockHash::zero() => state,
+            _ => return Ok(()),
+        };
+
+        debug!(
+            self.log,
+            "Issuing forkchoiceUpdated to standby engine";
+            "forkchoice_state" => ?forkchoice_state,
+        );
+
+        self.api
+            .forkchoice_updated_v1(forkchoice_state, None)
+            .await
+            .map(|_| ())
     }
 
     /// Returns `true` if the engine has a "synced" status.
@@ -168,6 +209,21 @@ impl Engine {
         *self.state.read().await == EngineState::Synced
     }
 
+    /// Returns `true` if the most recent request to the engine succeeded.
+    pub async fn is_healthy(&self) -> bool {
+        self.health.read().await.consecutive_failures == 0
+    }
+
+    async fn record_success(&self) {
+        let mut health = self.health.write().await;
+        health.last_success = Some(Instant::now());
+        health.consecutive_failures = 0;
+    }
+
+    async fn record_failure(&self) {
+        self.health.write().await.consecutive_failures += 1;
+    }
+
     /// Run the `EngineApi::upcheck` function if the node's last known state is not synced. This
     /// might be used to recover the node if offline.
     pub async fn upcheck(&self) {
@@ -175,19 +231,24 @@ impl Engine {
             Ok(()) => {
                 let mut state = self.state.write().await;
 
-                if *state != EngineState::Synced {
+                // An engine which has had failed requests may have missed forkchoice updates.
+                if *state != EngineState::Synced || !self.is_healthy().await {
                     info!(
                         self.log,
                         "Execution engine online";
                     );
 
                     // Send the node our latest forkchoice_state.
-                    self.send_latest_forkchoice_state().await;
+                    match self.send_latest_forkchoice_state().await {
+                        Ok(()) => self.record_success().await,
+                        Err(_) => self.record_failure().await,
+                    }
                 } else {
                     debug!(
                         self.log,
                         "Execution engine online";
                     );
+                    self.record_success().await;
                 }
 
                 *state = EngineState::Synced;
@@ -205,6 +266,7 @@ impl Engine {
                     "error" => ?err,
                 );
 
+                self.record_failure().await;
                 let mut state = self.state.write().await;
                 *state = EngineState::AuthFailed;
                 *state
@@ -216,6 +278,7 @@ impl Engine {
                     "error" => ?e,
                 );
 
+                self.record_failure().await;
                 let mut state = self.state.write().await;
                 *state = EngineState::Offline;
                 *state
@@ -242,6 +305,8 @@ impl Engine {
     {
         match func(self).await {
             Ok(result) => {
+                self.record_success().await;
+
                 // Take a clone *without* holding the read-lock since the `upcheck` function will
                 // take a write-lock.
                 let state: EngineState = *self.state.read().await;
@@ -266,6 +331,7 @@ impl Engine {
                     "Execution engine call failed";
                     "error" => ?error,
                 );
+                self.record_failure().await;
 
                 // The node just returned an error, run an upcheck so we can update the endpoint
                 // state.
@@ -283,6 +349,197 @@ impl Engine {
     }
 }
 
+/// A set of execution engines, in order of priority.
+///
+/// Requests are sent to the highest priority engine which is healthy (i.e., its most recent
+/// request succeeded), falling back to the other engines if it fails. Once a higher priority
+/// engine recovers, requests are routed back to it.
+pub struct Engines {
+    engines: Vec<Arc<Engine>>,
+    /// The index of the engine to which requests are currently routed.
+    active: AtomicUsize,
+    log: Logger,
+}
+
+impl Engines {
+    /// Creates a new set of engines, where the first engine is the primary.
+    pub fn new(engines: Vec<Engine>, log: Logger) -> Self {
+        metrics::set_gauge(&metrics::EXECUTION_LAYER_ACTIVE_ENDPOINT, 0);
+        Self {
+            engines: engines.into_iter().map(Arc::new).collect(),
+            active: AtomicUsize::new(0),
+            log,
+        }
+    }
+
+    /// Returns the engine to which requests are currently routed.
+    pub fn active(&self) -> &Arc<Engine> {
+        &self.engines[self.active_index()]
+    }
+
+    /// Returns the index of the engine to which requests are currently routed, where `0` is the
+    /// primary.
+    pub fn active_index(&self) -> usize {
+        self.active.load(Ordering::Relaxed)
+    }
+
+    pub async fn set_latest_forkchoice_state(&self, state: ForkChoiceState) {
+        for engine in &self.engines {
+            engine.set_latest_forkchoice_state(state).await;
+        }
+    }
+
+    /// Send the latest forkchoice state to every synced engine other than the one at `active`, so
+    /// that the standby engines follow the head and are ready to take over if `active` fails.
+    ///
+    /// The updates are sent from spawned tasks to avoid delaying the update of the active engine.
+    /// Each task sends the latest state at the time it runs, so out-of-order tasks cannot move an
+    /// engine's head backwards.
+    pub fn update_standby_engines(&self, active: usize) {
+        for (index, engine) in self.engines.iter().enumerate() {
+            if index == active {
+                continue;
+            }
+
+            let inner_engine = engine.clone();
+            engine.executor.spawn(
+                async move {
+                    if inner_engine.is_synced().await {
+                        // Failures are logged and trigger an upcheck within `Engine::request`.
+                        let _ = inner_engine
+                            .request(|engine| engine.forkchoice_updated_to_latest())
+                            .await;
+                    }
+                },
+                "update_standby_engine",
+            );
+        }
+    }
+
+    /// Returns `true` if any engine has a "synced" status.
+    pub async fn is_synced(&self) -> bool {
+        for engine in &self.engines {
+            if engine.is_synced().await {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Run `Engine::upcheck` on all engines, routing requests back to a higher priority engine if
+    /// it has recovered.
+    pub async fn upcheck(&self) {
+        join_all(self.engines.iter().map(|engine| engine.upcheck())).await;
+
+        let (healthy, _) = self.partition_by_health().await;
+        if let Some(&index) = healthy.first() {
+            self.set_active(index);
+        }
+        self.update_failure_metrics().await;
+    }
+
+    /// Run `func` on the highest priority healthy engine, falling back to the other engines in
+    /// order of priority if it fails. Unhealthy engines are only tried once all healthy engines
+    /// have failed.
+    ///
+    /// Returns the error from the first engine which was tried if all engines fail.
+    pub async fn request<'a, F, G, H>(&'a self, func: F) -> Result<H, EngineError>
+    where
+        F: Fn(&'a Engine) -> G,
+        G: Future<Output = Result<H, EngineApiError>>,
+    {
+        let (healthy, unhealthy) = self.partition_by_health().await;
+
+        let mut first_error = None;
+        for index in healthy.into_iter().chain(unhealthy) {
+            match self.engines[index].request(&func).await {
+                Ok(result) => {
+                    self.set_active(index);
+                    self.update_failure_metrics().await;
+                    return Ok(result);
+                }
+                Err(error) => {
+                    first_error.get_or_insert(error);
+                }
+            }
+        }
+
+        self.update_failure_metrics().await;
+        Err(first_error.unwrap_or(EngineError::Offline))
+    }
+
+    /// Returns the health of each engine, in order of priority.
+    pub async fn health(&self) -> Vec<ExecutionEngineHealth> {
+        let active = self.active_index();
+        let mut engines = Vec::with_capacity(self.engines.len());
+
+        for (index, engine) in self.engines.iter().enumerate() {
+            let health = *engine.health.read().await;
+            engines.push(ExecutionEngineHealth {
+                index,
+                endpoint: engine.api.url.to_string(),
+                active: index == active,
+                synced: engine.is_synced().await,
+                consecutive_failures: health.consecutive_failures,
+                seconds_since_last_success: health
+                    .last_success
+                    .map(|instant| instant.elapsed().as_secs()),
+            });
+        }
+
+        engines
+    }
+
+    /// Returns the indices of the healthy and unhealthy engines, each in order of priority.
+    async fn partition_by_health(&self) -> (Vec<usize>, Vec<usize>) {
+        let mut healthy = vec![];
+        let mut unhealthy = vec![];
+        for (index, engine) in self.engines.iter().enumerate() {
+            if engine.is_healthy().await {
+                healthy.push(index);
+            } else {
+                unhealthy.push(index);
+            }
+        }
+        (healthy, unhealthy)
+    }
+
+    fn set_active(&self, index: usize) {
+        let previous = self.active.swap(index, Ordering::Relaxed);
+        if previous == index {
+            return;
+        }
+
+        metrics::set_gauge(&metrics::EXECUTION_LAYER_ACTIVE_ENDPOINT, index as i64);
+        if index == 0 {
+            info!(
+                self.log,
+                "Execution requests routed to primary endpoint";
+                "endpoint" => %self.engines[index].api.url,
+            );
+        } else {
+            warn!(
+                self.log,
+                "Execution requests routed to fallback endpoint";
+                "msg" => "higher priority endpoints are failing",
+                "endpoint" => %self.engines[index].api.url,
+                "previous_endpoint" => %self.engines[previous].api.url,
+            );
+        }
+    }
+
+    async fn update_failure_metrics(&self) {
+        for (index, engine) in self.engines.iter().enumerate() {
+            let failures = engine.health.read().await.consecutive_failures;
+            metrics::set_gauge_vec(
+                &metrics::EXECUTION_LAYER_ENDPOINT_CONSECUTIVE_FAILURES,
+                &[&index.to_string()],
+                failures as i64,
+            );
+        }
+    }
+}
+
 impl PayloadIdCacheKey {
     fn new(state: &ForkChoiceState, attributes: &PayloadAttributes) -> Self {
         Self {
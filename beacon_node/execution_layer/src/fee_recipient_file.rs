@@ -0,0 +1,265 @@
+//! Provides a file of per-validator suggested fee recipients.
+//!
+//! Each line of the file maps a validator pubkey to a fee recipient address, e.g.:
+//!
+//! ```text
+//! # Used for any validator not listed below.
+//! default: 0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed
+//! 0x933ad9491b62059dd065b560d256d8957a8c402cc6e8d8ee7290ae11e8f7329267a8811c397529dac52ae1342ba58c95: 0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359
+//! ```
+//!
+//! Addresses must be EIP-55 checksummed and each pubkey may only appear once. Anything following
+//! a `#` is treated as a comment.
+use parking_lot::{Mutex, RwLock};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Duration, SystemTime};
+use types::{Address, PublicKeyBytes};
+
+/// The interval at which the file is checked for modifications.
+pub const FEE_RECIPIENT_FILE_RELOAD_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The key used to specify the fee recipient of validators which are not listed in the file.
+const DEFAULT_KEY: &str = "default";
+
+/// The contents of a fee recipient file.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct FeeRecipients {
+    /// The fee recipient for validators without an entry in `validators`.
+    pub default: Option<Address>,
+    pub validators: HashMap<PublicKeyBytes, Address>,
+}
+
+impl FeeRecipients {
+    /// Parse the `contents` of a fee recipient file.
+    ///
+    /// Returns an error describing the first invalid line, if any.
+    pub fn parse(contents: &str) -> Result<Self, String> {
+        let mut fee_recipients = Self::default();
+
+        for (i, line) in contents.lines().enumerate() {
+            let line_number = i + 1;
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let (key, value) = line
+                .split_once(':')
+                .ok_or_else(|| format!("line {}: expected `pubkey: address`", line_number))?;
+            let address = parse_checksummed_address(value.trim())
+                .map_err(|e| format!("line {}: invalid address: {}", line_number, e))?;
+
+            let key = key.trim();
+            if key == DEFAULT_KEY {
+                if fee_recipients.default.replace(address).is_some() {
+                    return Err(format!("line {}: duplicate default entry", line_number));
+                }
+            } else {
+                let pubkey = PublicKeyBytes::from_str(key)
+                    .map_err(|e| format!("line {}: invalid pubkey: {}", line_number, e))?;
+                if fee_recipients.validators.insert(pubkey, address).is_some() {
+                    return Err(format!(
+                        "line {}: duplicate entry for {}",
+                        line_number, pubkey
+                    ));
+                }
+            }
+        }
+
+        Ok(fee_recipients)
+    }
+}
+
+/// The attributes of the file which are used to detect modifications.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct FileStamp {
+    modified: Option<SystemTime>,
+    len: u64,
+}
+
+impl FileStamp {
+    fn read(path: &Path) -> Result<Self, String> {
+        let metadata = fs::metadata(path)
+            .map_err(|e| format!("unable to read metadata of {:?}: {:?}", path, e))?;
+        Ok(Self {
+            modified: metadata.modified().ok(),
+            len: metadata.len(),
+        })
+    }
+}
+
+/// A fee recipient file which can be reloaded whilst the node is running.
+pub struct FeeRecipientFile {
+    path: PathBuf,
+    stamp: Mutex<Option<FileStamp>>,
+    fee_recipients: RwLock<FeeRecipients>,
+}
+
+impl FeeRecipientFile {
+    /// Load and validate the file at `path`.
+    pub fn load(path: PathBuf) -> Result<Self, String> {
+        let file = Self {
+            path,
+            stamp: <_>::default(),
+            fee_recipients: <_>::default(),
+        };
+        file.reload()?;
+        Ok(file)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Re-read the file if it has been modified since it was last read.
+    ///
+    /// Returns `Ok(true)` if the file was re-read. If the file is invalid then an error is returned
+    /// and the previously loaded values are retained.
+    pub fn reload(&self) -> Result<bool, String> {
+        let mut stamp = self.stamp.lock();
+        let new_stamp = FileStamp::read(&self.path)?;
+        if *stamp == Some(new_stamp) {
+            return Ok(false);
+        }
+
+        let contents = fs::read_to_string(&self.path)
+            .map_err(|e| format!("unable to read {:?}: {:?}", self.path, e))?;
+        let fee_recipients = FeeRecipients::parse(&contents)?;
+
+        *self.fee_recipients.write() = fee_recipients;
+        *stamp = Some(new_stamp);
+        Ok(true)
+    }
+
+    /// Returns the fee recipient listed for `pubkey`, ignoring the default entry.
+    pub fn get(&self, pubkey: &PublicKeyBytes) -> Option<Address> {
+        self.fee_recipients.read().validators.get(pubkey).copied()
+    }
+
+    /// Returns the default fee recipient, if any.
+    pub fn default_fee_recipient(&self) -> Option<Address> {
+        self.fee_recipients.read().default
+    }
+
+    /// Returns the number of validators with an entry in the file.
+    pub fn num_validators(&self) -> usize {
+        self.fee_recipients.read().validators.len()
+    }
+}
+
+/// Returns the EIP-55 checksummed representation of `address`.
+pub fn to_checksum(address: &Address) -> String {
+    // `ethers_core` uses a different version of `ethereum_types`, so convert via the raw bytes.
+    ethers_core::utils::to_checksum(&address.to_fixed_bytes().into(), None)
+}
+
+/// Parse a 0x-prefixed address, ensuring it has a valid EIP-55 checksum.
+pub fn parse_checksummed_address(s: &str) -> Result<Address, String> {
+    let stripped = s
+        .strip_prefix("0x")
+        .ok_or_else(|| "must start with 0x".to_string())?;
+    let bytes = hex::decode(stripped).map_err(|e| format!("{:?}", e))?;
+    if bytes.len() != Address::len_bytes() {
+        return Err(format!(
+            "expected {} bytes, got {}",
+            Address::len_bytes(),
+            bytes.len()
+        ));
+    }
+
+    let address = Address::from_slice(&bytes);
+    let checksummed = to_checksum(&address);
+    if checksummed != s {
+        return Err(format!("invalid checksum, expected {}", checksummed));
+    }
+
+    Ok(address)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const ADDRESS_1: &str = "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed";
+    const ADDRESS_2: &str = "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359";
+    const PUBKEY: &str = "0x933ad9491b62059dd065b560d256d8957a8c402cc6e8d8ee7290ae11e8f7329267a8811c397529dac52ae1342ba58c95";
+
+    #[test]
+    fn checksum() {
+        for s in [ADDRESS_1, ADDRESS_2] {
+            let address = parse_checksummed_address(s).unwrap();
+            assert_eq!(to_checksum(&address), s);
+        }
+        assert!(parse_checksummed_address(&ADDRESS_1.to_lowercase()).is_err());
+        assert!(parse_checksummed_address("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeA").is_err());
+    }
+
+    #[test]
+    fn parse() {
+        let contents = format!(
+            "# comment\ndefault: {}\n\n{}: {} # another comment\n",
+            ADDRESS_1, PUBKEY, ADDRESS_2
+        );
+        let fee_recipients = FeeRecipients::parse(&contents).unwrap();
+        assert_eq!(
+            fee_recipients.default,
+            Some(Address::from_str(ADDRESS_1).unwrap())
+        );
+        assert_eq!(
+            fee_recipients.validators[&PublicKeyBytes::from_str(PUBKEY).unwrap()],
+            Address::from_str(ADDRESS_2).unwrap()
+        );
+    }
+
+    #[test]
+    fn parse_rejects_duplicates() {
+        let contents = format!("{}: {}\n{}: {}\n", PUBKEY, ADDRESS_1, PUBKEY, ADDRESS_2);
+        assert_eq!(
+            FeeRecipients::parse(&contents),
+            Err(format!("line 2: duplicate entry for {}", PUBKEY))
+        );
+
+        let contents = format!("default: {}\ndefault: {}\n", ADDRESS_1, ADDRESS_2);
+        assert_eq!(
+            FeeRecipients::parse(&contents),
+            Err("line 2: duplicate default entry".to_string())
+        );
+    }
+
+    #[test]
+    fn reload_retains_previous_values_on_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("fee_recipients.txt");
+        fs::write(&path, format!("{}: {}\n", PUBKEY, ADDRESS_1)).unwrap();
+
+        let file = FeeRecipientFile::load(path.clone()).unwrap();
+        let pubkey = PublicKeyBytes::from_str(PUBKEY).unwrap();
+        assert_eq!(
+            file.get(&pubkey),
+            Some(Address::from_str(ADDRESS_1).unwrap())
+        );
+        assert_eq!(file.reload(), Ok(false));
+
+        // Append comments so the file length changes, regardless of the mtime resolution.
+        fs::write(
+            &path,
+            format!("{}: {} # invalid\n", PUBKEY, ADDRESS_1.to_lowercase()),
+        )
+        .unwrap();
+        assert!(file.reload().is_err());
+        assert_eq!(
+            file.get(&pubkey),
+            Some(Address::from_str(ADDRESS_1).unwrap())
+        );
+
+        fs::write(&path, format!("{}: {} # updated\n", PUBKEY, ADDRESS_2)).unwrap();
+        assert_eq!(file.reload(), Ok(true));
+        assert_eq!(
+            file.get(&pubkey),
+            Some(Address::from_str(ADDRESS_2).unwrap())
+        );
+    }
+}
@@ -5,24 +5,29 @@
 //! deposit-contract functionality that the `beacon_node/eth1` crate already provides.
 
 use auth::{strip_prefix, Auth, JwtKey};
-use builder_client::BuilderHttpClient;
+pub use builder_client::{
+    BuilderHttpClient, Config as BuilderConfig, ResponseTimings as BuilderResponseTimings,
+    Timeouts as BuilderTimeouts,
+};
 use engine_api::Error as ApiError;
 pub use engine_api::*;
 pub use engine_api::{http, http::deposit_methods, http::HttpJsonRpc};
 pub use engines::ForkChoiceState;
-use engines::{Engine, EngineError};
+use engines::{Engine, EngineError, Engines};
+use eth2::lighthouse::ExecutionEngineHealth;
+use fee_recipient_file::{FeeRecipientFile, FEE_RECIPIENT_FILE_RELOAD_INTERVAL};
 use fork_choice::ForkchoiceUpdateParameters;
 use lru::LruCache;
 use payload_status::process_payload_status;
 pub use payload_status::PayloadStatus;
 use sensitive_url::SensitiveUrl;
 use serde::{Deserialize, Serialize};
-use slog::{crit, debug, error, info, trace, warn, Logger};
+use slog::{crit, debug, error, info, o, trace, warn, Logger};
 use slot_clock::SlotClock;
 use std::collections::HashMap;
 use std::future::Future;
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use task_executor::TaskExecutor;
@@ -30,13 +35,16 @@ use tokio::{
     sync::{Mutex, MutexGuard, RwLock},
     time::sleep,
 };
+use types::builder_bid::BuilderBidEvidence;
 use types::{
     BlindedPayload, BlockType, ChainSpec, Epoch, ExecPayload, ExecutionBlockHash,
-    ProposerPreparationData, PublicKeyBytes, SignedBeaconBlock, Slot,
+    ProposerPreparationData, PublicKeyBytes, SignedBeaconBlock, SignedValidatorRegistrationData,
+    Slot,
 };
 
 mod engine_api;
 mod engines;
+pub mod fee_recipient_file;
 mod metrics;
 mod payload_status;
 pub mod test_utils;
@@ -51,6 +59,10 @@ pub const DEFAULT_JWT_FILE: &str = "jwt.hex";
 /// in an LRU cache to avoid redundant lookups. This is the size of that cache.
 const EXECUTION_BLOCKS_LRU_CACHE_SIZE: usize = 128;
 
+/// The number of payload headers for which the builder that offered them is remembered, so that
+/// the signed blinded block is sent to the same builder.
+const BID_BUILDERS_LRU_CACHE_SIZE: usize = 16;
+
 /// A fee recipient address for use during block production. Only used as a very last resort if
 /// there is no address provided by the user.
 ///
@@ -63,6 +75,14 @@ const DEFAULT_SUGGESTED_FEE_RECIPIENT: [u8; 20] =
 
 const CONFIG_POLL_INTERVAL: Duration = Duration::from_secs(60);
 
+/// The number of epochs for which the builder will not be used for block production after it has
+/// failed to reveal the payload of a signed blinded block.
+pub const BUILDER_CIRCUIT_BREAKER_EPOCHS: u64 = 2;
+
+/// A warning is logged when deserializing a JSON bid from a builder takes longer than this, since
+/// requesting SSZ would be faster.
+pub const BUILDER_DESERIALIZATION_WARN_THRESHOLD: Duration = Duration::from_millis(100);
+
 #[derive(Debug)]
 pub enum Error {
     NoEngine,
@@ -75,6 +95,8 @@ pub enum Error {
     FeeRecipientUnspecified,
     MissingLatestValidHash,
     InvalidJWTSecret(String),
+    InvalidFeeRecipientFile(String),
+    BuilderAlreadyAdded(String),
 }
 
 impl From<ApiError> for Error {
@@ -83,6 +105,62 @@ impl From<ApiError> for Error {
     }
 }
 
+/// Indicates where the execution payload for a block proposal was obtained.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PayloadSource {
+    /// A payload header was obtained from the builder at `relay`, which signed `bid`.
+    Builder {
+        relay: String,
+        bid: BuilderBidEvidence,
+    },
+    /// The payload was built by the local execution engine.
+    Local,
+}
+
+/// Parameters used to decide whether or not the builder should be used for a block proposal.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BuilderParams {
+    pub pubkey: Option<PublicKeyBytes>,
+    pub slot: Slot,
+    pub chain_health: ChainHealth,
+    /// The domain over which builders sign their bids.
+    pub builder_domain: Hash256,
+}
+
+/// Indicates whether the chain is healthy enough for the builder to be used.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChainHealth {
+    Healthy,
+    Unhealthy(FailedCondition),
+}
+
+/// The reason the chain was deemed unhealthy.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FailedCondition {
+    /// Too many recent proposals which used a builder payload were missed.
+    Skips,
+    /// The chain has not finalized for too many epochs.
+    EpochsSinceFinality,
+}
+
+/// A builder connected to the `ExecutionLayer`, as returned by `ExecutionLayer::list_builders`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BuilderInfo {
+    /// The redacted URL of the builder.
+    pub url: String,
+    pub timeouts: BuilderTimeouts,
+    #[serde(with = "eth2_serde_utils::quoted_u256")]
+    pub min_bid: Uint256,
+    /// The pubkey with which the builder's bids must be signed, if any.
+    pub pubkey: Option<PublicKeyBytes>,
+}
+
+/// A payload returned by `ExecutionLayer::get_payload`, along with its source.
+pub struct ProducedPayload<Payload> {
+    pub payload: Payload,
+    pub source: PayloadSource,
+}
+
 #[derive(Clone, PartialEq)]
 pub struct ProposerPreparationDataEntry {
     update_epoch: Epoch,
@@ -102,13 +180,20 @@ pub struct Proposer {
 }
 
 struct Inner<E: EthSpec> {
-    engine: Arc<Engine>,
-    builder: Option<BuilderHttpClient>,
+    engines: Engines,
+    /// The connected builders. Readers take a snapshot of the list, which is replaced (rather than
+    /// modified) when a builder is added or removed.
+    builders: parking_lot::RwLock<Arc<Vec<BuilderHttpClient>>>,
+    /// The builder which offered each recent payload header, by block hash.
+    bid_builders: parking_lot::Mutex<LruCache<ExecutionBlockHash, BuilderHttpClient>>,
     execution_engine_forkchoice_lock: Mutex<()>,
     suggested_fee_recipient: Option<Address>,
+    fee_recipient_file: Option<FeeRecipientFile>,
     proposer_preparation_data: Mutex<HashMap<u64, ProposerPreparationDataEntry>>,
     execution_blocks: Mutex<LruCache<ExecutionBlockHash, ExecutionBlock>>,
     proposers: RwLock<HashMap<ProposerKey, Proposer>>,
+    /// The slot of the most recent block for which the builder failed to reveal the payload.
+    builder_fault_slot: Mutex<Option<Slot>>,
     executor: TaskExecutor,
     phantom: std::marker::PhantomData<E>,
     log: Logger,
@@ -116,15 +201,22 @@ struct Inner<E: EthSpec> {
 
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct Config {
-    /// Endpoint urls for EL nodes that are running the engine api.
+    /// Endpoint urls for EL nodes that are running the engine api, in order of priority.
+    ///
+    /// Requests are sent to the first endpoint, falling back to the others if it fails.
     pub execution_endpoints: Vec<SensitiveUrl>,
     /// Endpoint urls for services providing the builder api.
     pub builder_url: Option<SensitiveUrl>,
-    /// JWT secrets for the above endpoints running the engine api.
+    /// JWT secrets for the above endpoints running the engine api, where the secret at each index
+    /// is used for the endpoint at the same index. Endpoints without a secret use the default
+    /// secret file in `default_datadir`.
     pub secret_files: Vec<PathBuf>,
     /// The default fee recipient to use on the beacon node if none if provided from
     /// the validator client during block preparation.
     pub suggested_fee_recipient: Option<Address>,
+    /// A file of per-validator fee recipients, which take precedence over
+    /// `suggested_fee_recipient` but not over values provided by the validator client.
+    pub suggested_fee_recipient_file: Option<PathBuf>,
     /// An optional id for the beacon node that will be passed to the EL in the JWT token claim.
     pub jwt_id: Option<String>,
     /// An optional client version for the beacon node that will be passed to the EL in the JWT token claim.
@@ -133,84 +225,97 @@ pub struct Config {
     pub default_datadir: PathBuf,
 }
 
-/// Provides access to one execution engine and provides a neat interface for consumption by the
-/// `BeaconChain`.
+/// Provides access to one or more execution engines and provides a neat interface for consumption
+/// by the `BeaconChain`.
 #[derive(Clone)]
 pub struct ExecutionLayer<T: EthSpec> {
     inner: Arc<Inner<T>>,
 }
 
 impl<T: EthSpec> ExecutionLayer<T> {
-    /// Instantiate `Self` with an Execution engine specified in `Config`, using JSON-RPC via HTTP.
+    /// Instantiate `Self` with the Execution engines specified in `Config`, using JSON-RPC via HTTP.
     pub fn from_config(config: Config, executor: TaskExecutor, log: Logger) -> Result<Self, Error> {
+        Self::from_config_with_builder(config, None, executor, log)
+    }
+
+    /// Instantiate `Self` as per `from_config`, using `builder` to connect to the builder in
+    /// preference to `Config::builder_url`.
+    ///
+    /// Further builders may be connected with `Self::add_builder`.
+    pub fn from_config_with_builder(
+        config: Config,
+        builder: Option<BuilderHttpClient>,
+        executor: TaskExecutor,
+        log: Logger,
+    ) -> Result<Self, Error> {
         let Config {
             execution_endpoints: urls,
             builder_url,
             secret_files,
             suggested_fee_recipient,
+            suggested_fee_recipient_file,
             jwt_id,
             jwt_version,
             default_datadir,
         } = config;
 
-        if urls.len() > 1 {
-            warn!(log, "Only the first execution engine url will be used");
+        if urls.is_empty() {
+            return Err(Error::NoEngine);
         }
-        let execution_url = urls.into_iter().next().ok_or(Error::NoEngine)?;
 
-        // Use the default jwt secret path if not provided via cli.
-        let secret_file = secret_files
+        let engines = urls
             .into_iter()
-            .next()
-            .unwrap_or_else(|| default_datadir.join(DEFAULT_JWT_FILE));
-
-        let jwt_key = if secret_file.exists() {
-            // Read secret from file if it already exists
-            std::fs::read_to_string(&secret_file)
-                .map_err(|e| format!("Failed to read JWT secret file. Error: {:?}", e))
-                .and_then(|ref s| {
-                    let secret = JwtKey::from_slice(
-                        &hex::decode(strip_prefix(s.trim_end()))
-                            .map_err(|e| format!("Invalid hex string: {:?}", e))?,
-                    )?;
-                    Ok(secret)
-                })
-                .map_err(Error::InvalidJWTSecret)
-        } else {
-            // Create a new file and write a randomly generated secret to it if file does not exist
-            std::fs::File::options()
-                .write(true)
-                .create_new(true)
-                .open(&secret_file)
-                .map_err(|e| format!("Failed to open JWT secret file. Error: {:?}", e))
-                .and_then(|mut f| {
-                    let secret = auth::JwtKey::random();
-                    f.write_all(secret.hex_string().as_bytes())
-                        .map_err(|e| format!("Failed to write to JWT secret file: {:?}", e))?;
-                    Ok(secret)
-                })
-                .map_err(Error::InvalidJWTSecret)
-        }?;
-
-        let engine: Engine = {
-            let auth = Auth::new(jwt_key, jwt_id, jwt_version);
-            debug!(log, "Loaded execution endpoint"; "endpoint" => %execution_url, "jwt_path" => ?secret_file.as_path());
-            let api = HttpJsonRpc::new_with_auth(execution_url, auth).map_err(Error::ApiError)?;
-            Engine::new(api, executor.clone(), &log)
+            .enumerate()
+            .map(|(index, execution_url)| {
+                // Use the default jwt secret path if not provided via cli.
+                let secret_file = secret_files
+                    .get(index)
+                    .cloned()
+                    .unwrap_or_else(|| default_datadir.join(DEFAULT_JWT_FILE));
+                let jwt_key = load_or_create_jwt_key(&secret_file)?;
+
+                let auth = Auth::new(jwt_key, jwt_id.clone(), jwt_version.clone());
+                debug!(log, "Loaded execution endpoint"; "endpoint" => %execution_url, "jwt_path" => ?secret_file.as_path());
+                let engine_log = log.new(o!("endpoint" => execution_url.to_string()));
+                let api = HttpJsonRpc::new_with_auth(execution_url, auth).map_err(Error::ApiError)?;
+                Ok(Engine::new(api, executor.clone(), &engine_log))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        let builder = match builder {
+            Some(builder) => Some(builder),
+            None => builder_url
+                .map(|url| BuilderHttpClient::new(url).map_err(Error::Builder))
+                .transpose()?,
         };
 
-        let builder = builder_url
-            .map(|url| BuilderHttpClient::new(url).map_err(Error::Builder))
+        let fee_recipient_file = suggested_fee_recipient_file
+            .map(|path| {
+                FeeRecipientFile::load(path.clone())
+                    .map_err(|e| Error::InvalidFeeRecipientFile(format!("{:?}: {}", path, e)))
+            })
             .transpose()?;
+        if let Some(file) = &fee_recipient_file {
+            info!(
+                log,
+                "Loaded suggested fee recipient file";
+                "validators" => file.num_validators(),
+                "default" => ?file.default_fee_recipient(),
+                "path" => ?file.path(),
+            );
+        }
 
         let inner = Inner {
-            engine: Arc::new(engine),
-            builder,
+            engines: Engines::new(engines, log.clone()),
+            builders: parking_lot::RwLock::new(Arc::new(builder.into_iter().collect())),
+            bid_builders: parking_lot::Mutex::new(LruCache::new(BID_BUILDERS_LRU_CACHE_SIZE)),
             execution_engine_forkchoice_lock: <_>::default(),
             suggested_fee_recipient,
+            fee_recipient_file,
             proposer_preparation_data: Mutex::new(HashMap::new()),
             proposers: RwLock::new(HashMap::new()),
             execution_blocks: Mutex::new(LruCache::new(EXECUTION_BLOCKS_LRU_CACHE_SIZE)),
+            builder_fault_slot: <_>::default(),
             executor,
             phantom: std::marker::PhantomData,
             log,
@@ -223,12 +328,80 @@ impl<T: EthSpec> ExecutionLayer<T> {
 }
 
 impl<T: EthSpec> ExecutionLayer<T> {
-    fn engine(&self) -> &Arc<Engine> {
-        &self.inner.engine
+    fn engines(&self) -> &Engines {
+        &self.inner.engines
     }
 
-    pub fn builder(&self) -> &Option<BuilderHttpClient> {
-        &self.inner.builder
+    /// Returns a snapshot of the connected builders.
+    pub fn builders(&self) -> Arc<Vec<BuilderHttpClient>> {
+        self.inner.builders.read().clone()
+    }
+
+    /// Returns `true` if at least one builder is connected.
+    pub fn has_builder(&self) -> bool {
+        !self.inner.builders.read().is_empty()
+    }
+
+    /// Returns the connected builders, with their URLs redacted.
+    pub fn list_builders(&self) -> Vec<BuilderInfo> {
+        self.builders()
+            .iter()
+            .map(|builder| BuilderInfo {
+                url: builder.server().redacted.clone(),
+                timeouts: builder.timeouts().clone(),
+                min_bid: builder.min_bid(),
+                pubkey: builder.pubkey().copied(),
+            })
+            .collect()
+    }
+
+    /// Connect to an additional builder, which is queried from the next block proposal.
+    ///
+    /// Returns an error if a builder with the same URL is already connected.
+    pub fn add_builder(&self, builder: BuilderHttpClient) -> Result<(), Error> {
+        let mut builders = self.inner.builders.write();
+        if builders
+            .iter()
+            .any(|existing| existing.server().full == builder.server().full)
+        {
+            return Err(Error::BuilderAlreadyAdded(
+                builder.server().redacted.clone(),
+            ));
+        }
+
+        info!(
+            self.log(),
+            "Connected to builder";
+            "url" => %builder.server(),
+        );
+        let mut updated = builders.as_ref().clone();
+        updated.push(builder);
+        *builders = Arc::new(updated);
+        Ok(())
+    }
+
+    /// Disconnect from the builder at `url`, returning `false` if it was not connected.
+    ///
+    /// A payload offered by the builder can still be revealed, since the builder which offered
+    /// each recent payload header is retained separately.
+    pub fn remove_builder(&self, url: &SensitiveUrl) -> bool {
+        let mut builders = self.inner.builders.write();
+        let updated = builders
+            .iter()
+            .filter(|builder| builder.server().full != url.full)
+            .cloned()
+            .collect::<Vec<_>>();
+        if updated.len() == builders.len() {
+            return false;
+        }
+
+        info!(
+            self.log(),
+            "Disconnected from builder";
+            "url" => %url,
+        );
+        *builders = Arc::new(updated);
+        true
     }
 
     pub fn executor(&self) -> &TaskExecutor {
@@ -238,7 +411,8 @@ impl<T: EthSpec> ExecutionLayer<T> {
     /// Get the current difficulty of the PoW chain.
     pub async fn get_current_difficulty(&self) -> Result<Uint256, ApiError> {
         let block = self
-            .engine()
+            .engines()
+            .active()
             .api
             .get_block_by_number(BlockByNumberQuery::Tag(LATEST_TAG))
             .await?
@@ -301,7 +475,7 @@ impl<T: EthSpec> ExecutionLayer<T> {
 
     /// Performs a single execution of the watchdog routine.
     pub async fn watchdog_task(&self) {
-        self.engine().upcheck().await;
+        self.engines().upcheck().await;
     }
 
     /// Spawns a routine which cleans the cached proposer data periodically.
@@ -343,6 +517,44 @@ impl<T: EthSpec> ExecutionLayer<T> {
         self.spawn(preparation_cleaner, "exec_preparation_cleanup");
     }
 
+    /// Spawns a routine that reloads the suggested fee recipient file whenever it is modified.
+    ///
+    /// The routine will not be started if there is no file configured.
+    pub fn spawn_fee_recipient_file_reload_routine(&self) {
+        if self.inner.fee_recipient_file.is_none() {
+            return;
+        }
+
+        let routine = |el: ExecutionLayer<T>| async move {
+            loop {
+                sleep(FEE_RECIPIENT_FILE_RELOAD_INTERVAL).await;
+
+                let file = match el.inner.fee_recipient_file.as_ref() {
+                    Some(file) => file,
+                    None => return,
+                };
+                match file.reload() {
+                    Ok(true) => info!(
+                        el.log(),
+                        "Reloaded suggested fee recipient file";
+                        "validators" => file.num_validators(),
+                        "default" => ?file.default_fee_recipient(),
+                    ),
+                    Ok(false) => (),
+                    Err(e) => warn!(
+                        el.log(),
+                        "Invalid suggested fee recipient file";
+                        "msg" => "the previously loaded values will continue to be used",
+                        "error" => e,
+                        "path" => ?file.path(),
+                    ),
+                }
+            }
+        };
+
+        self.spawn(routine, "exec_fee_recipient_file_reload");
+    }
+
     /// Spawns a routine that polls the `exchange_transition_configuration` endpoint.
     pub fn spawn_transition_configuration_poll(&self, spec: ChainSpec) {
         let routine = |el: ExecutionLayer<T>| async move {
@@ -361,9 +573,14 @@ impl<T: EthSpec> ExecutionLayer<T> {
         self.spawn(routine, "exec_config_poll");
     }
 
-    /// Returns `true` if the execution engine is synced and reachable.
+    /// Returns `true` if any execution engine is synced and reachable.
     pub async fn is_synced(&self) -> bool {
-        self.engine().is_synced().await
+        self.engines().is_synced().await
+    }
+
+    /// Returns the health of each execution engine, in order of priority.
+    pub async fn execution_engine_health(&self) -> Vec<ExecutionEngineHealth> {
+        self.engines().health().await
     }
 
     /// Execution nodes return a "SYNCED" response when they do not have any peers.
@@ -376,7 +593,8 @@ impl<T: EthSpec> ExecutionLayer<T> {
         let synced = self.is_synced().await;
         if synced {
             if let Ok(Some(block)) = self
-                .engine()
+                .engines()
+                .active()
                 .api
                 .get_block_by_number(BlockByNumberQuery::Tag(LATEST_TAG))
                 .await
@@ -411,6 +629,42 @@ impl<T: EthSpec> ExecutionLayer<T> {
         }
     }
 
+    /// Forwards validator registrations to each connected builder.
+    ///
+    /// This is a no-op if no builder is configured. All builders are contacted even if one fails,
+    /// in which case the first error is returned.
+    pub async fn register_validators(
+        &self,
+        registrations: &[SignedValidatorRegistrationData],
+    ) -> Result<(), Error> {
+        let builders = self.builders();
+        let results = futures::future::join_all(
+            builders
+                .iter()
+                .map(|builder| self.register_validators_with_builder(builder, registrations)),
+        )
+        .await;
+        results.into_iter().collect()
+    }
+
+    /// Forwards validator registrations to `builder`, which need not be connected.
+    pub async fn register_validators_with_builder(
+        &self,
+        builder: &BuilderHttpClient,
+        registrations: &[SignedValidatorRegistrationData],
+    ) -> Result<(), Error> {
+        debug!(
+            self.log(),
+            "Forwarding validator registrations to builder";
+            "count" => registrations.len(),
+            "url" => %builder.server(),
+        );
+        builder
+            .post_builder_validators(registrations)
+            .await
+            .map_err(Error::Builder)
+    }
+
     /// Removes expired entries from proposer_preparation_data and proposers caches
     async fn clean_proposer_caches(&self, current_epoch: Epoch) -> Result<(), Error> {
         let mut proposer_preparation_data = self.proposer_preparation_data().await;
@@ -445,8 +699,43 @@ impl<T: EthSpec> ExecutionLayer<T> {
             .contains_key(&proposer_index)
     }
 
-    /// Returns the fee-recipient address that should be used to build a block
-    pub async fn get_suggested_fee_recipient(&self, proposer_index: u64) -> Address {
+    /// Returns `true` if the suggested fee recipient file contains an entry for `pubkey`.
+    ///
+    /// The default entry of the file is ignored.
+    pub fn has_fee_recipient_file_entry(&self, pubkey: &PublicKeyBytes) -> bool {
+        self.inner
+            .fee_recipient_file
+            .as_ref()
+            .map_or(false, |file| file.get(pubkey).is_some())
+    }
+
+    /// Returns `true` if the suggested fee recipient file contains an entry for any validator.
+    pub fn has_any_fee_recipient_file_entries(&self) -> bool {
+        self.inner
+            .fee_recipient_file
+            .as_ref()
+            .map_or(false, |file| file.num_validators() > 0)
+    }
+
+    /// Returns the fee-recipient address that should be used to build a block.
+    ///
+    /// In order of precedence, the address is taken from:
+    ///
+    /// 1. The proposer preparation data supplied by the validator client.
+    /// 2. The entry for `pubkey` in the suggested fee recipient file.
+    /// 3. The default entry of the suggested fee recipient file.
+    /// 4. The `--suggested-fee-recipient` flag.
+    pub async fn get_suggested_fee_recipient(
+        &self,
+        proposer_index: u64,
+        pubkey: Option<PublicKeyBytes>,
+    ) -> Address {
+        let file_fee_recipient = self.inner.fee_recipient_file.as_ref().and_then(|file| {
+            pubkey
+                .and_then(|pubkey| file.get(&pubkey))
+                .or_else(|| file.default_fee_recipient())
+        });
+
         if let Some(preparation_data_entry) =
             self.proposer_preparation_data().await.get(&proposer_index)
         {
@@ -469,6 +758,10 @@ impl<T: EthSpec> ExecutionLayer<T> {
             }
             // The values provided via the API have first priority.
             preparation_data_entry.preparation_data.fee_recipient
+        } else if let Some(address) = file_fee_recipient {
+            // If there has been no fee recipient provided via the API, use the value from the
+            // suggested fee recipient file.
+            address
         } else if let Some(address) = self.inner.suggested_fee_recipient {
             // If there has been no fee recipient provided via the API, but the BN has been provided
             // with a global default address, use that.
@@ -504,11 +797,12 @@ impl<T: EthSpec> ExecutionLayer<T> {
         timestamp: u64,
         prev_randao: Hash256,
         proposer_index: u64,
-        pubkey: Option<PublicKeyBytes>,
-        slot: Slot,
+        builder_params: BuilderParams,
         forkchoice_update_params: ForkchoiceUpdateParameters,
-    ) -> Result<Payload, Error> {
-        let suggested_fee_recipient = self.get_suggested_fee_recipient(proposer_index).await;
+    ) -> Result<ProducedPayload<Payload>, Error> {
+        let suggested_fee_recipient = self
+            .get_suggested_fee_recipient(proposer_index, builder_params.pubkey)
+            .await;
 
         match Payload::block_type() {
             BlockType::Blinded => {
@@ -521,8 +815,7 @@ impl<T: EthSpec> ExecutionLayer<T> {
                     timestamp,
                     prev_randao,
                     suggested_fee_recipient,
-                    pubkey,
-                    slot,
+                    builder_params,
                     forkchoice_update_params,
                 )
                 .await
@@ -540,6 +833,10 @@ impl<T: EthSpec> ExecutionLayer<T> {
                     forkchoice_update_params,
                 )
                 .await
+                .map(|payload| ProducedPayload {
+                    payload,
+                    source: PayloadSource::Local,
+                })
             }
         }
     }
@@ -551,16 +848,33 @@ impl<T: EthSpec> ExecutionLayer<T> {
         timestamp: u64,
         prev_randao: Hash256,
         suggested_fee_recipient: Address,
-        pubkey_opt: Option<PublicKeyBytes>,
-        slot: Slot,
+        builder_params: BuilderParams,
         forkchoice_update_params: ForkchoiceUpdateParameters,
-    ) -> Result<Payload, Error> {
+    ) -> Result<ProducedPayload<Payload>, Error> {
+        let slot = builder_params.slot;
         //FIXME(sean) fallback logic included in PR #3134
 
         // Don't attempt to outsource payload construction until after the merge transition has been
         // finalized. We want to be conservative with payload construction until then.
-        if let (Some(builder), Some(pubkey)) = (self.builder(), pubkey_opt) {
-            if forkchoice_update_params
+        let builders = self.builders();
+        if let (false, Some(pubkey)) = (builders.is_empty(), builder_params.pubkey) {
+            if self.builder_circuit_breaker_tripped(slot).await {
+                warn!(
+                    self.log(),
+                    "Not using builder for block production";
+                    "msg" => "the builder recently failed to reveal a payload",
+                    "slot" => ?slot,
+                );
+            } else if let ChainHealth::Unhealthy(condition) = builder_params.chain_health {
+                info!(
+                    self.log(),
+                    "Chain is unhealthy, using local payload";
+                    "info" => "this helps protect the network. the --builder-fallback flags \
+                               can adjust the expected health conditions.",
+                    "failed_condition" => ?condition,
+                    "slot" => ?slot,
+                );
+            } else if forkchoice_update_params
                 .finalized_hash
                 .map_or(false, |finalized_block_hash| {
                     finalized_block_hash != ExecutionBlockHash::zero()
@@ -568,16 +882,102 @@ impl<T: EthSpec> ExecutionLayer<T> {
             {
                 info!(
                     self.log(),
-                    "Requesting blinded header from connected builder";
+                    "Requesting blinded header from connected builders";
                     "slot" => ?slot,
                     "pubkey" => ?pubkey,
                     "parent_hash" => ?parent_hash,
+                    "builders" => builders.len(),
                 );
-                return builder
-                    .get_builder_header::<T, Payload>(slot, parent_hash, &pubkey)
-                    .await
-                    .map(|d| d.data.message.header)
-                    .map_err(Error::Builder);
+                let responses = futures::future::join_all(builders.iter().map(|builder| {
+                    builder.get_builder_header::<T, Payload>(slot, parent_hash, &pubkey)
+                }))
+                .await;
+
+                // Take the most valuable bid which meets the minimum of the builder offering it.
+                let mut first_error = None;
+                let mut best_bid = None;
+                let mut bids_received = 0;
+                for (builder, response) in builders.iter().zip(responses) {
+                    let (bid, timings) = match response {
+                        Ok(response) => {
+                            bids_received += 1;
+                            self.observe_builder_timings(builder, response.timings, slot);
+                            (response.data.data, response.timings)
+                        }
+                        Err(e) => {
+                            warn!(
+                                self.log(),
+                                "Failed to obtain header from builder";
+                                "error" => ?e,
+                                "url" => %builder.server(),
+                                "slot" => ?slot,
+                            );
+                            first_error.get_or_insert(e);
+                            continue;
+                        }
+                    };
+
+                    if let Some(builder_pubkey) = builder.pubkey() {
+                        if !bid.is_signed_by(builder_pubkey, builder_params.builder_domain) {
+                            warn!(
+                                self.log(),
+                                "Ignoring builder bid with invalid signature";
+                                "bid_pubkey" => ?bid.message.pubkey,
+                                "builder_pubkey" => ?builder_pubkey,
+                                "url" => %builder.server(),
+                                "slot" => ?slot,
+                            );
+                            metrics::inc_counter(
+                                &metrics::EXECUTION_LAYER_BUILDER_BID_INVALID_SIGNATURE,
+                            );
+                            continue;
+                        }
+                    }
+
+                    if bid.message.value < builder.min_bid() {
+                        info!(
+                            self.log(),
+                            "Builder bid below minimum";
+                            "bid_value" => %bid.message.value,
+                            "min_bid" => %builder.min_bid(),
+                            "url" => %builder.server(),
+                            "slot" => ?slot,
+                        );
+                    } else if best_bid
+                        .as_ref()
+                        .map_or(true, |(_, best, _)| bid.message.value > best.message.value)
+                    {
+                        best_bid = Some((builder, bid, timings));
+                    }
+                }
+
+                match (best_bid, first_error) {
+                    (Some((builder, bid, timings)), _) => {
+                        self.inner
+                            .bid_builders
+                            .lock()
+                            .put(bid.message.header.block_hash(), builder.clone());
+                        return Ok(ProducedPayload {
+                            source: PayloadSource::Builder {
+                                relay: builder.server().redacted.clone(),
+                                bid: BuilderBidEvidence {
+                                    time_to_first_byte_ms: timings.time_to_first_byte.as_millis()
+                                        as u64,
+                                    deserialization_ms: timings.deserialization.as_millis() as u64,
+                                    ..BuilderBidEvidence::from(&bid)
+                                },
+                            },
+                            payload: bid.message.header,
+                        });
+                    }
+                    // Every builder failed to respond with a bid.
+                    (None, Some(e)) if bids_received == 0 => return Err(Error::Builder(e)),
+                    (None, _) => info!(
+                        self.log(),
+                        "No acceptable builder bid, using local payload";
+                        "slot" => ?slot,
+                    ),
+                }
             }
         }
         self.get_full_payload::<Payload>(
@@ -588,6 +988,41 @@ impl<T: EthSpec> ExecutionLayer<T> {
             forkchoice_update_params,
         )
         .await
+        .map(|payload| ProducedPayload {
+            payload,
+            source: PayloadSource::Local,
+        })
+    }
+
+    /// Record the timings of a header request to `builder`, warning if the bid was slow to
+    /// deserialize from JSON.
+    fn observe_builder_timings(
+        &self,
+        builder: &BuilderHttpClient,
+        timings: BuilderResponseTimings,
+        slot: Slot,
+    ) {
+        metrics::observe_duration(
+            &metrics::EXECUTION_LAYER_BUILDER_GET_HEADER_TIME_TO_FIRST_BYTE,
+            timings.time_to_first_byte,
+        );
+        metrics::observe_duration(
+            &metrics::EXECUTION_LAYER_BUILDER_GET_HEADER_DESERIALIZATION,
+            timings.deserialization,
+        );
+
+        if !builder.prefer_ssz() && timings.deserialization > BUILDER_DESERIALIZATION_WARN_THRESHOLD
+        {
+            warn!(
+                self.log(),
+                "Slow deserialization of builder bid";
+                "msg" => "consider setting prefer_ssz in the builder config",
+                "deserialization_ms" => timings.deserialization.as_millis(),
+                "time_to_first_byte_ms" => timings.time_to_first_byte.as_millis(),
+                "url" => %builder.server(),
+                "slot" => ?slot,
+            );
+        }
     }
 
     /// Get a full payload without caching its result in the execution layer's payload cache.
@@ -627,7 +1062,7 @@ impl<T: EthSpec> ExecutionLayer<T> {
             "timestamp" => timestamp,
             "parent_hash" => ?parent_hash,
         );
-        self.engine()
+        self.engines()
             .request(|engine| async move {
                 let payload_id = if let Some(id) = engine
                     .get_payload_id(parent_hash, timestamp, prev_randao, suggested_fee_recipient)
@@ -735,7 +1170,7 @@ impl<T: EthSpec> ExecutionLayer<T> {
         );
 
         let result = self
-            .engine()
+            .engines()
             .request(|engine| engine.api.new_payload_v1(execution_payload.clone()))
             .await;
 
@@ -865,12 +1300,12 @@ impl<T: EthSpec> ExecutionLayer<T> {
             finalized_block_hash,
         };
 
-        self.engine()
+        self.engines()
             .set_latest_forkchoice_state(forkchoice_state)
             .await;
 
         let result = self
-            .engine()
+            .engines()
             .request(|engine| async move {
                 engine
                     .notify_forkchoice_updated(forkchoice_state, payload_attributes, self.log())
@@ -878,6 +1313,13 @@ impl<T: EthSpec> ExecutionLayer<T> {
             })
             .await;
 
+        // Keep the other engines following the head so that they are ready if the active engine
+        // fails.
+        if result.is_ok() {
+            self.engines()
+                .update_standby_engines(self.engines().active_index());
+        }
+
         process_payload_status(
             head_block_hash,
             result.map(|response| response.payload_status),
@@ -895,7 +1337,7 @@ impl<T: EthSpec> ExecutionLayer<T> {
         };
 
         let result = self
-            .engine()
+            .engines()
             .request(|engine| engine.api.exchange_transition_configuration_v1(local))
             .await;
 
@@ -952,7 +1394,7 @@ impl<T: EthSpec> ExecutionLayer<T> {
         );
 
         let hash_opt = self
-            .engine()
+            .engines()
             .request(|engine| async move {
                 let terminal_block_hash = spec.terminal_block_hash;
                 if terminal_block_hash != ExecutionBlockHash::zero() {
@@ -1079,7 +1521,7 @@ impl<T: EthSpec> ExecutionLayer<T> {
             &[metrics::IS_VALID_TERMINAL_POW_BLOCK_HASH],
         );
 
-        self.engine()
+        self.engines()
             .request(|engine| async move {
                 if let Some(pow_block) = self.get_pow_block(engine, block_hash).await? {
                     if let Some(pow_parent) =
@@ -1146,7 +1588,7 @@ impl<T: EthSpec> ExecutionLayer<T> {
         &self,
         hash: ExecutionBlockHash,
     ) -> Result<Option<ExecutionPayload<T>>, Error> {
-        self.engine()
+        self.engines()
             .request(|engine| async move {
                 self.get_payload_by_block_hash_from_engine(engine, hash)
                     .await
@@ -1201,31 +1643,72 @@ impl<T: EthSpec> ExecutionLayer<T> {
         }))
     }
 
+    /// Prevent the builder from being used for block production for
+    /// `BUILDER_CIRCUIT_BREAKER_EPOCHS`, starting from `slot`.
+    ///
+    /// This should be called when the builder fails to reveal the payload for a signed blinded
+    /// block at `slot`.
+    pub async fn trip_builder_circuit_breaker(&self, slot: Slot) {
+        let mut fault_slot = self.inner.builder_fault_slot.lock().await;
+        if fault_slot.map_or(true, |fault_slot| fault_slot < slot) {
+            *fault_slot = Some(slot);
+        }
+    }
+
+    /// Returns `true` if the builder should not be used to produce a block at `slot`.
+    pub async fn builder_circuit_breaker_tripped(&self, slot: Slot) -> bool {
+        self.inner
+            .builder_fault_slot
+            .lock()
+            .await
+            .map_or(false, |fault_slot| {
+                slot < fault_slot + BUILDER_CIRCUIT_BREAKER_EPOCHS * T::slots_per_epoch()
+            })
+    }
+
     pub async fn propose_blinded_beacon_block(
         &self,
         block: &SignedBeaconBlock<T, BlindedPayload<T>>,
     ) -> Result<ExecutionPayload<T>, Error> {
+        // Send the block to the builder which offered its payload, even if it has since been
+        // removed. Otherwise, use the first connected builder.
+        let bid_builder = block
+            .message()
+            .body()
+            .execution_payload()
+            .ok()
+            .and_then(|payload| {
+                self.inner
+                    .bid_builders
+                    .lock()
+                    .get(&payload.block_hash())
+                    .cloned()
+            });
+        let builder = match bid_builder.or_else(|| self.builders().first().cloned()) {
+            Some(builder) => builder,
+            None => return Err(Error::NoPayloadBuilder),
+        };
+
         debug!(
             self.log(),
             "Sending block to builder";
             "root" => ?block.canonical_root(),
+            "url" => %builder.server(),
         );
-        if let Some(builder) = self.builder() {
-            builder
-                .post_builder_blinded_blocks(block)
-                .await
-                .map_err(Error::Builder)
-                .map(|d| d.data)
-        } else {
-            Err(Error::NoPayloadBuilder)
-        }
+        builder
+            .post_builder_blinded_blocks(block)
+            .await
+            .map_err(Error::Builder)
+            .map(|d| d.data)
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::test_utils::MockExecutionLayer as GenericMockExecutionLayer;
+    use crate::test_utils::{
+        MockExecutionLayer as GenericMockExecutionLayer, MockServer, DEFAULT_JWT_SECRET,
+    };
     use task_executor::test_utils::TestRuntime;
     use types::MainnetEthSpec;
 
@@ -1250,7 +1733,7 @@ mod test {
         MockExecutionLayer::default_params(runtime.task_executor.clone())
             .move_to_block_prior_to_terminal_block()
             .with_terminal_block(|spec, el, _| async move {
-                el.engine().upcheck().await;
+                el.engines().upcheck().await;
                 assert_eq!(
                     el.get_terminal_pow_block_hash(&spec, timestamp_now())
                         .await
@@ -1277,7 +1760,7 @@ mod test {
         MockExecutionLayer::default_params(runtime.task_executor.clone())
             .move_to_block_prior_to_terminal_block()
             .with_terminal_block(|spec, el, _| async move {
-                el.engine().upcheck().await;
+                el.engines().upcheck().await;
                 assert_eq!(
                     el.get_terminal_pow_block_hash(&spec, timestamp_now())
                         .await
@@ -1305,7 +1788,7 @@ mod test {
         MockExecutionLayer::default_params(runtime.task_executor.clone())
             .move_to_terminal_block()
             .with_terminal_block(|spec, el, terminal_block| async move {
-                el.engine().upcheck().await;
+                el.engines().upcheck().await;
                 assert_eq!(
                     el.is_valid_terminal_pow_block_hash(terminal_block.unwrap().block_hash, &spec)
                         .await
@@ -1322,7 +1805,7 @@ mod test {
         MockExecutionLayer::default_params(runtime.task_executor.clone())
             .move_to_terminal_block()
             .with_terminal_block(|spec, el, terminal_block| async move {
-                el.engine().upcheck().await;
+                el.engines().upcheck().await;
                 let invalid_terminal_block = terminal_block.unwrap().parent_hash;
 
                 assert_eq!(
@@ -1341,7 +1824,7 @@ mod test {
         MockExecutionLayer::default_params(runtime.task_executor.clone())
             .move_to_terminal_block()
             .with_terminal_block(|spec, el, _| async move {
-                el.engine().upcheck().await;
+                el.engines().upcheck().await;
                 let missing_terminal_block = ExecutionBlockHash::repeat_byte(42);
 
                 assert_eq!(
@@ -1353,6 +1836,146 @@ mod test {
             })
             .await;
     }
+
+    #[tokio::test]
+    async fn fails_over_and_back_between_execution_endpoints() {
+        let runtime = TestRuntime::default();
+        let executor = runtime.task_executor.clone();
+        let servers = (0..2)
+            .map(|_| MockServer::<MainnetEthSpec>::unit_testing())
+            .collect::<Vec<_>>();
+
+        // Give each endpoint its own secret file.
+        let dir = tempfile::tempdir().unwrap();
+        let secret_files = (0..servers.len())
+            .map(|i| {
+                let path = dir.path().join(format!("jwt-{}.hex", i));
+                std::fs::write(&path, hex::encode(DEFAULT_JWT_SECRET)).unwrap();
+                path
+            })
+            .collect();
+        let config = Config {
+            execution_endpoints: servers
+                .iter()
+                .map(|server| SensitiveUrl::parse(&server.url()).unwrap())
+                .collect(),
+            secret_files,
+            ..Config::default()
+        };
+        let el = ExecutionLayer::<MainnetEthSpec>::from_config(
+            config,
+            executor.clone(),
+            executor.log().clone(),
+        )
+        .unwrap();
+        el.watchdog_task().await;
+
+        let forkchoice_updated = |byte: u8| {
+            let el = el.clone();
+            async move {
+                let head = ExecutionBlockHash::repeat_byte(byte);
+                el.notify_forkchoice_updated(
+                    head,
+                    ExecutionBlockHash::zero(),
+                    ExecutionBlockHash::zero(),
+                    Slot::new(0),
+                    Hash256::repeat_byte(byte),
+                )
+                .await
+                .unwrap();
+                head
+            }
+        };
+        let previous_head = |server: &MockServer<MainnetEthSpec>| {
+            server
+                .previous_forkchoice_state()
+                .map(|state| state.head_block_hash)
+        };
+        // Standby engines are updated from spawned tasks, so wait for them to complete.
+        async fn wait_for_head(server: &MockServer<MainnetEthSpec>, head: ExecutionBlockHash) {
+            for _ in 0..100 {
+                let previous = server.previous_forkchoice_state();
+                if previous.map(|state| state.head_block_hash) == Some(head) {
+                    return;
+                }
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+            panic!("standby engine was not sent head {:?}", head);
+        }
+
+        // Requests are routed to the primary whilst it is healthy and the standby follows the head.
+        let head = forkchoice_updated(1).await;
+        assert_eq!(previous_head(&servers[0]), Some(head));
+        assert_eq!(el.engines().active_index(), 0);
+        wait_for_head(&servers[1], head).await;
+
+        // The update is sent to the fallback when the primary fails.
+        servers[0].set_offline(true);
+        let head = forkchoice_updated(2).await;
+        assert_eq!(previous_head(&servers[1]), Some(head));
+        assert_eq!(el.engines().active_index(), 1);
+
+        let head = forkchoice_updated(3).await;
+        assert_eq!(
+            previous_head(&servers[0]),
+            Some(ExecutionBlockHash::repeat_byte(1))
+        );
+        assert_eq!(previous_head(&servers[1]), Some(head));
+
+        let health = el.execution_engine_health().await;
+        assert!(!health[0].active);
+        assert!(health[0].consecutive_failures > 0);
+        assert!(health[1].active);
+        assert_eq!(health[1].consecutive_failures, 0);
+
+        // Once the primary recovers it is sent the updates it missed and requests are routed back
+        // to it.
+        servers[0].set_offline(false);
+        el.watchdog_task().await;
+        assert_eq!(previous_head(&servers[0]), Some(head));
+        assert_eq!(el.engines().active_index(), 0);
+
+        let head = forkchoice_updated(4).await;
+        assert_eq!(previous_head(&servers[0]), Some(head));
+        wait_for_head(&servers[1], head).await;
+
+        let health = el.execution_engine_health().await;
+        assert!(health[0].active);
+        assert_eq!(health[0].consecutive_failures, 0);
+        assert!(health[0].seconds_since_last_success.is_some());
+    }
+}
+
+/// Read the JWT secret from `secret_file`, or generate a new secret and write it to `secret_file`
+/// if the file does not exist.
+fn load_or_create_jwt_key(secret_file: &Path) -> Result<JwtKey, Error> {
+    if secret_file.exists() {
+        // Read secret from file if it already exists
+        std::fs::read_to_string(secret_file)
+            .map_err(|e| format!("Failed to read JWT secret file. Error: {:?}", e))
+            .and_then(|ref s| {
+                let secret = JwtKey::from_slice(
+                    &hex::decode(strip_prefix(s.trim_end()))
+                        .map_err(|e| format!("Invalid hex string: {:?}", e))?,
+                )?;
+                Ok(secret)
+            })
+            .map_err(Error::InvalidJWTSecret)
+    } else {
+        // Create a new file and write a randomly generated secret to it if file does not exist
+        std::fs::File::options()
+            .write(true)
+            .create_new(true)
+            .open(secret_file)
+            .map_err(|e| format!("Failed to open JWT secret file. Error: {:?}", e))
+            .and_then(|mut f| {
+                let secret = auth::JwtKey::random();
+                f.write_all(secret.hex_string().as_bytes())
+                    .map_err(|e| format!("Failed to write to JWT secret file: {:?}", e))?;
+                Ok(secret)
+            })
+            .map_err(Error::InvalidJWTSecret)
+    }
 }
 
 fn noop<T: EthSpec>(_: &ExecutionLayer<T>, _: &ExecutionPayload<T>) -> Option<ExecutionPayload<T>> {
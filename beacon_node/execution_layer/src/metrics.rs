@@ -36,4 +36,25 @@ lazy_static::lazy_static! {
         "execution_layer_get_payload_by_block_hash_time",
         "Time to reconstruct a payload from the EE using eth_getBlockByHash"
     );
+    pub static ref EXECUTION_LAYER_ACTIVE_ENDPOINT: Result<IntGauge> = try_create_int_gauge(
+        "execution_layer_active_endpoint",
+        "The index of the execution endpoint to which requests are routed, where 0 is the primary",
+    );
+    pub static ref EXECUTION_LAYER_ENDPOINT_CONSECUTIVE_FAILURES: Result<IntGaugeVec> = try_create_int_gauge_vec(
+        "execution_layer_endpoint_consecutive_failures",
+        "The number of requests to each execution endpoint which have failed since its last success",
+        &["endpoint"]
+    );
+    pub static ref EXECUTION_LAYER_BUILDER_GET_HEADER_TIME_TO_FIRST_BYTE: Result<Histogram> = try_create_histogram(
+        "execution_layer_builder_get_header_time_to_first_byte_seconds",
+        "Time from requesting a header from a builder until the response headers are received",
+    );
+    pub static ref EXECUTION_LAYER_BUILDER_GET_HEADER_DESERIALIZATION: Result<Histogram> = try_create_histogram(
+        "execution_layer_builder_get_header_deserialization_seconds",
+        "Time to read and deserialize the body of a header response from a builder",
+    );
+    pub static ref EXECUTION_LAYER_BUILDER_BID_INVALID_SIGNATURE: Result<IntCounter> = try_create_int_counter(
+        "execution_layer_builder_bid_invalid_signature_total",
+        "Count of builder bids ignored because they weren't signed by the configured builder pubkey",
+    );
 }
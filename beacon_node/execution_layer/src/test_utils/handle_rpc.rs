@@ -1,6 +1,7 @@
 use super::Context;
 use crate::engine_api::{http::*, *};
 use crate::json_structures::*;
+use crate::ForkChoiceState;
 use serde::de::DeserializeOwned;
 use serde_json::Value as JsonValue;
 use std::sync::Arc;
@@ -53,12 +54,19 @@ pub async fn handle_rpc<T: EthSpec>(
                 .and_then(JsonValue::as_bool)
                 .ok_or_else(|| "missing/invalid params[1] value".to_string())?;
             if full_tx {
-                Ok(serde_json::to_value(
-                    ctx.execution_block_generator
-                        .read()
-                        .execution_block_with_txs_by_hash(hash),
-                )
-                .unwrap())
+                let mut block = ctx
+                    .execution_block_generator
+                    .read()
+                    .execution_block_with_txs_by_hash(hash);
+                if let Some(count) = ctx.inconsistent_payloads.lock().get_mut(&hash) {
+                    if *count > 0 {
+                        *count -= 1;
+                        if let Some(block) = block.as_mut() {
+                            block.gas_used += 1;
+                        }
+                    }
+                }
+                Ok(serde_json::to_value(block).unwrap())
             } else {
                 Ok(serde_json::to_value(
                     ctx.execution_block_generator
@@ -109,16 +117,18 @@ pub async fn handle_rpc<T: EthSpec>(
             Ok(serde_json::to_value(JsonExecutionPayloadV1::from(response)).unwrap())
         }
         ENGINE_FORKCHOICE_UPDATED_V1 => {
-            let forkchoice_state: JsonForkChoiceStateV1 = get_param(params, 0)?;
+            let forkchoice_state: ForkChoiceState =
+                get_param::<JsonForkChoiceStateV1>(params, 0)?.into();
             let payload_attributes: Option<JsonPayloadAttributesV1> = get_param(params, 1)?;
 
             let head_block_hash = forkchoice_state.head_block_hash;
+            *ctx.previous_forkchoice_state.lock() = Some(forkchoice_state);
 
             let mut response = ctx
                 .execution_block_generator
                 .write()
                 .forkchoice_updated_v1(
-                    forkchoice_state.into(),
+                    forkchoice_state,
                     payload_attributes.map(|json| json.into()),
                 )?;
 
@@ -0,0 +1,327 @@
+//! Provides a mock implementation of the builder API (i.e., a relay) for use in testing.
+//!
+//! The mock builder does not build its own payloads. Instead, it offers the most recent payload
+//! that the mock execution engine has built upon the requested parent.
+use crate::test_utils::Context;
+use environment::null_logger;
+use parking_lot::Mutex;
+use serde_json::json;
+use slog::info;
+use std::collections::HashMap;
+use std::future::Future;
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::{runtime, sync::oneshot};
+use types::builder_bid::{BuilderBid, SignedBuilderBid};
+use types::{
+    BlindedPayload, ChainSpec, EthSpec, ExecutionBlockHash, ExecutionPayload,
+    ExecutionPayloadHeader, ForkName, Hash256, Keypair, PublicKeyBytes, SignedBeaconBlock,
+    SignedRoot, Slot, Uint256,
+};
+use warp::{http::StatusCode, Filter, Reply};
+
+/// The value of each bid from the mock builder, in wei.
+pub const DEFAULT_BUILDER_BID_VALUE: u64 = 1_000_000_000;
+
+/// Shared state for the mock builder HTTP server.
+pub struct MockBuilderContext<T: EthSpec> {
+    /// The context of the mock execution engine, from which payloads are obtained.
+    el_ctx: Arc<Context<T>>,
+    /// The key with which bids are signed.
+    keypair: Keypair,
+    builder_domain: Hash256,
+    /// Payloads that have been offered in a bid, by block hash.
+    payloads: Mutex<HashMap<ExecutionBlockHash, ExecutionPayload<T>>>,
+    fail_validators: AtomicBool,
+    fail_get_header: AtomicBool,
+    fail_blinded_blocks: AtomicBool,
+    /// The time to wait before responding to each `get_header` request.
+    get_header_delay: Mutex<Duration>,
+    get_header_requests: AtomicUsize,
+    blinded_blocks_requests: AtomicUsize,
+    /// The total number of validator registrations received.
+    registrations: AtomicUsize,
+}
+
+impl<T: EthSpec> MockBuilderContext<T> {
+    /// Returns the most recent payload built by the mock execution engine upon `parent_hash`.
+    fn payload_for_parent(&self, parent_hash: ExecutionBlockHash) -> Option<ExecutionPayload<T>> {
+        self.el_ctx
+            .execution_block_generator
+            .read()
+            .payload_ids
+            .values()
+            .filter(|payload| payload.parent_hash == parent_hash)
+            .max_by_key(|payload| payload.timestamp)
+            .cloned()
+    }
+}
+
+pub struct MockBuilder<T: EthSpec> {
+    _shutdown_tx: oneshot::Sender<()>,
+    listen_socket_addr: SocketAddr,
+    pub ctx: Arc<MockBuilderContext<T>>,
+}
+
+impl<T: EthSpec> MockBuilder<T> {
+    pub fn new(handle: &runtime::Handle, el_ctx: Arc<Context<T>>, spec: &ChainSpec) -> Self {
+        let ctx = Arc::new(MockBuilderContext {
+            el_ctx,
+            keypair: Keypair::random(),
+            builder_domain: spec.get_builder_domain(),
+            payloads: <_>::default(),
+            fail_validators: AtomicBool::new(false),
+            fail_get_header: AtomicBool::new(false),
+            fail_blinded_blocks: AtomicBool::new(false),
+            get_header_delay: Mutex::new(Duration::ZERO),
+            get_header_requests: AtomicUsize::new(0),
+            blinded_blocks_requests: AtomicUsize::new(0),
+            registrations: AtomicUsize::new(0),
+        });
+
+        let (shutdown_tx, shutdown_rx) = oneshot::channel();
+        let shutdown_future = async {
+            // Ignore the result from the channel, shut down regardless.
+            let _ = shutdown_rx.await;
+        };
+
+        // See `MockServer::new_with_config` for why `block_on` is sometimes required.
+        let serve = || serve(ctx.clone(), shutdown_future).unwrap();
+        let (listen_socket_addr, server_future) = if runtime::Handle::try_current().is_err() {
+            handle.block_on(async { serve() })
+        } else {
+            serve()
+        };
+
+        handle.spawn(server_future);
+
+        Self {
+            _shutdown_tx: shutdown_tx,
+            listen_socket_addr,
+            ctx,
+        }
+    }
+
+    pub fn url(&self) -> String {
+        format!(
+            "http://{}:{}",
+            self.listen_socket_addr.ip(),
+            self.listen_socket_addr.port()
+        )
+    }
+
+    /// The pubkey with which the builder signs its bids.
+    pub fn pubkey(&self) -> PublicKeyBytes {
+        self.ctx.keypair.pk.compress()
+    }
+
+    /// Respond to all `validators` requests with an error.
+    pub fn fail_validators(&self, fail: bool) {
+        self.ctx.fail_validators.store(fail, Ordering::Relaxed);
+    }
+
+    /// Respond to all `get_header` requests with an error.
+    pub fn fail_get_header(&self, fail: bool) {
+        self.ctx.fail_get_header.store(fail, Ordering::Relaxed);
+    }
+
+    /// Respond to all `blinded_blocks` requests with an error, i.e., withhold all payloads.
+    pub fn fail_blinded_blocks(&self, fail: bool) {
+        self.ctx.fail_blinded_blocks.store(fail, Ordering::Relaxed);
+    }
+
+    /// Wait for `delay` before responding to each `get_header` request.
+    pub fn delay_get_header(&self, delay: Duration) {
+        *self.ctx.get_header_delay.lock() = delay;
+    }
+
+    /// The number of `get_header` requests received.
+    pub fn get_header_requests(&self) -> usize {
+        self.ctx.get_header_requests.load(Ordering::Relaxed)
+    }
+
+    /// The number of `blinded_blocks` requests received.
+    pub fn blinded_blocks_requests(&self) -> usize {
+        self.ctx.blinded_blocks_requests.load(Ordering::Relaxed)
+    }
+
+    /// The total number of validator registrations received by `validators` requests.
+    pub fn registrations(&self) -> usize {
+        self.ctx.registrations.load(Ordering::Relaxed)
+    }
+}
+
+fn error_response(code: StatusCode, message: String) -> warp::reply::Response {
+    let json = warp::reply::json(&json!({
+        "code": code.as_u16(),
+        "message": message,
+    }));
+    warp::reply::with_status(json, code).into_response()
+}
+
+/// Creates a server that will serve builder API requests using information from `ctx`.
+///
+/// The server will shut down gracefully when the `shutdown` future resolves.
+fn serve<T: EthSpec>(
+    ctx: Arc<MockBuilderContext<T>>,
+    shutdown: impl Future<Output = ()> + Send + Sync + 'static,
+) -> Result<(SocketAddr, impl Future<Output = ()>), warp::Error> {
+    let log = null_logger().unwrap();
+
+    let inner_ctx = ctx.clone();
+    let ctx_filter = warp::any().map(move || inner_ctx.clone());
+
+    let prefix = warp::path("eth")
+        .and(warp::path("v1"))
+        .and(warp::path("builder"));
+
+    // GET /eth/v1/builder/status
+    let status = prefix
+        .clone()
+        .and(warp::path("status"))
+        .and(warp::path::end())
+        .and(warp::get())
+        .map(warp::reply);
+
+    // POST /eth/v1/builder/validators
+    let validators = prefix
+        .clone()
+        .and(warp::path("validators"))
+        .and(warp::path::end())
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(ctx_filter.clone())
+        .map(
+            |registrations: Vec<serde_json::Value>, ctx: Arc<MockBuilderContext<T>>| {
+                if ctx.fail_validators.load(Ordering::Relaxed) {
+                    return error_response(
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        "validators failure requested".to_string(),
+                    );
+                }
+
+                ctx.registrations
+                    .fetch_add(registrations.len(), Ordering::Relaxed);
+                warp::reply().into_response()
+            },
+        );
+
+    // GET /eth/v1/builder/header/{slot}/{parent_hash}/{pubkey}
+    let header = prefix
+        .clone()
+        .and(warp::path("header"))
+        .and(warp::path::param::<Slot>())
+        .and(warp::path::param::<ExecutionBlockHash>())
+        .and(warp::path::param::<String>())
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(ctx_filter.clone())
+        .then(
+            |_slot: Slot,
+             parent_hash: ExecutionBlockHash,
+             _pubkey: String,
+             ctx: Arc<MockBuilderContext<T>>| async move {
+                ctx.get_header_requests.fetch_add(1, Ordering::Relaxed);
+
+                let delay = *ctx.get_header_delay.lock();
+                if !delay.is_zero() {
+                    tokio::time::sleep(delay).await;
+                }
+
+                if ctx.fail_get_header.load(Ordering::Relaxed) {
+                    return error_response(
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        "get_header failure requested".to_string(),
+                    );
+                }
+
+                let payload = match ctx.payload_for_parent(parent_hash) {
+                    Some(payload) => payload,
+                    None => {
+                        return error_response(
+                            StatusCode::NO_CONTENT,
+                            format!("no payload for parent {:?}", parent_hash),
+                        )
+                    }
+                };
+                let message = BuilderBid::<T, BlindedPayload<T>>::new(
+                    ExecutionPayloadHeader::from(&payload).into(),
+                    Uint256::from(DEFAULT_BUILDER_BID_VALUE),
+                    ctx.keypair.pk.compress(),
+                );
+                let signature = ctx
+                    .keypair
+                    .sk
+                    .sign(message.signing_root(ctx.builder_domain));
+                ctx.payloads.lock().insert(payload.block_hash, payload);
+
+                warp::reply::json(&json!({
+                    "version": ForkName::Merge,
+                    "data": SignedBuilderBid { message, signature },
+                }))
+                .into_response()
+            },
+        );
+
+    // POST /eth/v1/builder/blinded_blocks
+    let blinded_blocks = prefix
+        .and(warp::path("blinded_blocks"))
+        .and(warp::path::end())
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(ctx_filter)
+        .map(
+            |block: SignedBeaconBlock<T, BlindedPayload<T>>, ctx: Arc<MockBuilderContext<T>>| {
+                ctx.blinded_blocks_requests.fetch_add(1, Ordering::Relaxed);
+
+                if ctx.fail_blinded_blocks.load(Ordering::Relaxed) {
+                    return error_response(
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        "blinded_blocks failure requested".to_string(),
+                    );
+                }
+
+                let block_hash = match block.message().body().execution_payload() {
+                    Ok(payload) => payload.execution_payload_header.block_hash,
+                    Err(e) => {
+                        return error_response(StatusCode::BAD_REQUEST, format!("{:?}", e));
+                    }
+                };
+
+                match ctx.payloads.lock().get(&block_hash) {
+                    Some(payload) => warp::reply::json(&json!({
+                        "version": ForkName::Merge,
+                        "data": payload,
+                    }))
+                    .into_response(),
+                    None => error_response(
+                        StatusCode::BAD_REQUEST,
+                        format!("unknown payload {:?}", block_hash),
+                    ),
+                }
+            },
+        );
+
+    let routes = status
+        .or(validators)
+        .or(header)
+        .or(blinded_blocks)
+        .map(|reply| warp::reply::with_header(reply, "Server", "lighthouse-mock-builder"));
+
+    let (listening_socket, server) = warp::serve(routes).try_bind_with_graceful_shutdown(
+        SocketAddrV4::new(Ipv4Addr::new(127, 0, 0, 1), 0),
+        async {
+            shutdown.await;
+        },
+    )?;
+
+    info!(
+        log,
+        "Mock builder HTTP server started";
+        "listen_address" => listening_socket.to_string(),
+    );
+
+    Ok((listening_socket, server))
+}
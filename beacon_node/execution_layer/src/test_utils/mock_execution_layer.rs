@@ -1,6 +1,7 @@
 use crate::{
     test_utils::{
-        MockServer, DEFAULT_JWT_SECRET, DEFAULT_TERMINAL_BLOCK, DEFAULT_TERMINAL_DIFFICULTY,
+        MockBuilder, MockServer, DEFAULT_JWT_SECRET, DEFAULT_TERMINAL_BLOCK,
+        DEFAULT_TERMINAL_DIFFICULTY,
     },
     Config, *,
 };
@@ -11,6 +12,7 @@ use types::{Address, ChainSpec, Epoch, EthSpec, FullPayload, Hash256, Uint256};
 
 pub struct MockExecutionLayer<T: EthSpec> {
     pub server: MockServer<T>,
+    pub builder: Option<MockBuilder<T>>,
     pub el: ExecutionLayer<T>,
     pub executor: TaskExecutor,
     pub spec: ChainSpec,
@@ -37,6 +39,80 @@ impl<T: EthSpec> MockExecutionLayer<T> {
         terminal_block_hash_activation_epoch: Epoch,
         jwt_key: Option<JwtKey>,
         builder_url: Option<SensitiveUrl>,
+    ) -> Self {
+        Self::new_inner(
+            executor,
+            terminal_total_difficulty,
+            terminal_block,
+            terminal_block_hash,
+            terminal_block_hash_activation_epoch,
+            jwt_key,
+            builder_url,
+            false,
+            None,
+        )
+    }
+
+    /// As per `Self::new`, but the execution layer is connected to a `MockBuilder` which serves
+    /// payloads from the mock execution engine.
+    pub fn new_with_mock_builder(
+        executor: TaskExecutor,
+        terminal_total_difficulty: Uint256,
+        terminal_block: u64,
+        terminal_block_hash: ExecutionBlockHash,
+        terminal_block_hash_activation_epoch: Epoch,
+        jwt_key: Option<JwtKey>,
+    ) -> Self {
+        Self::new_inner(
+            executor,
+            terminal_total_difficulty,
+            terminal_block,
+            terminal_block_hash,
+            terminal_block_hash_activation_epoch,
+            jwt_key,
+            None,
+            true,
+            None,
+        )
+    }
+
+    /// As per `Self::new`, but the execution layer reads per-validator fee recipients from the
+    /// file at `suggested_fee_recipient_file`.
+    pub fn new_with_fee_recipient_file(
+        executor: TaskExecutor,
+        terminal_total_difficulty: Uint256,
+        terminal_block: u64,
+        terminal_block_hash: ExecutionBlockHash,
+        terminal_block_hash_activation_epoch: Epoch,
+        jwt_key: Option<JwtKey>,
+        suggested_fee_recipient_file: PathBuf,
+    ) -> Self {
+        Self::new_inner(
+            executor,
+            terminal_total_difficulty,
+            terminal_block,
+            terminal_block_hash,
+            terminal_block_hash_activation_epoch,
+            jwt_key,
+            None,
+            false,
+            Some(suggested_fee_recipient_file),
+        )
+    }
+
+    /// Creates a new instance. If `mock_builder` is `true` then a `MockBuilder` is started and used
+    /// in place of `builder_url`.
+    #[allow(clippy::too_many_arguments)]
+    fn new_inner(
+        executor: TaskExecutor,
+        terminal_total_difficulty: Uint256,
+        terminal_block: u64,
+        terminal_block_hash: ExecutionBlockHash,
+        terminal_block_hash_activation_epoch: Epoch,
+        jwt_key: Option<JwtKey>,
+        builder_url: Option<SensitiveUrl>,
+        mock_builder: bool,
+        suggested_fee_recipient_file: Option<PathBuf>,
     ) -> Self {
         let handle = executor.handle().unwrap();
 
@@ -57,6 +133,12 @@ impl<T: EthSpec> MockExecutionLayer<T> {
         let url = SensitiveUrl::parse(&server.url()).unwrap();
         let file = NamedTempFile::new().unwrap();
 
+        let builder = mock_builder.then(|| MockBuilder::new(&handle, server.ctx.clone(), &spec));
+        let builder_url = builder
+            .as_ref()
+            .map(|builder| SensitiveUrl::parse(&builder.url()).unwrap())
+            .or(builder_url);
+
         let path = file.path().into();
         std::fs::write(&path, hex::encode(DEFAULT_JWT_SECRET)).unwrap();
 
@@ -65,13 +147,28 @@ impl<T: EthSpec> MockExecutionLayer<T> {
             builder_url,
             secret_files: vec![path],
             suggested_fee_recipient: Some(Address::repeat_byte(42)),
+            suggested_fee_recipient_file,
             ..Default::default()
         };
-        let el =
-            ExecutionLayer::from_config(config, executor.clone(), executor.log().clone()).unwrap();
+        // Only accept bids signed by the mock builder.
+        let builder_client = builder.as_ref().map(|builder| {
+            BuilderHttpClient::from_config(&BuilderConfig {
+                pubkey: Some(builder.pubkey()),
+                ..BuilderConfig::new(SensitiveUrl::parse(&builder.url()).unwrap())
+            })
+            .unwrap()
+        });
+        let el = ExecutionLayer::from_config_with_builder(
+            config,
+            builder_client,
+            executor.clone(),
+            executor.log().clone(),
+        )
+        .unwrap();
 
         Self {
             server,
+            builder,
             el,
             executor,
             spec,
@@ -124,6 +221,12 @@ impl<T: EthSpec> MockExecutionLayer<T> {
             .unwrap();
 
         let validator_index = 0;
+        let builder_params = BuilderParams {
+            pubkey: None,
+            slot,
+            chain_health: ChainHealth::Healthy,
+            builder_domain: self.spec.get_builder_domain(),
+        };
         let payload = self
             .el
             .get_payload::<FullPayload<T>>(
@@ -131,12 +234,12 @@ impl<T: EthSpec> MockExecutionLayer<T> {
                 timestamp,
                 prev_randao,
                 validator_index,
-                None,
-                slot,
+                builder_params,
                 forkchoice_update_params,
             )
             .await
             .unwrap()
+            .payload
             .execution_payload;
         let block_hash = payload.block_hash;
         assert_eq!(payload.parent_hash, parent_hash);
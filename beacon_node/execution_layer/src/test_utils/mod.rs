@@ -4,6 +4,7 @@ use crate::engine_api::auth::JwtKey;
 use crate::engine_api::{
     auth::Auth, http::JSONRPC_VERSION, PayloadStatusV1, PayloadStatusV1Status,
 };
+use crate::ForkChoiceState;
 use bytes::Bytes;
 use environment::null_logger;
 use execution_block_generator::PoWBlock;
@@ -12,6 +13,7 @@ use parking_lot::{Mutex, RwLock, RwLockWriteGuard};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use slog::{info, Logger};
+use std::collections::HashMap;
 use std::convert::Infallible;
 use std::future::Future;
 use std::marker::PhantomData;
@@ -22,6 +24,7 @@ use types::{EthSpec, ExecutionBlockHash, Uint256};
 use warp::{http::StatusCode, Filter, Rejection};
 
 pub use execution_block_generator::{generate_pow_block, Block, ExecutionBlockGenerator};
+pub use mock_builder::{MockBuilder, DEFAULT_BUILDER_BID_VALUE};
 pub use mock_execution_layer::MockExecutionLayer;
 
 pub const DEFAULT_TERMINAL_DIFFICULTY: u64 = 6400;
@@ -30,6 +33,7 @@ pub const DEFAULT_JWT_SECRET: [u8; 32] = [42; 32];
 
 mod execution_block_generator;
 mod handle_rpc;
+mod mock_builder;
 mod mock_execution_layer;
 
 /// Configuration for the MockExecutionLayer.
@@ -94,6 +98,9 @@ impl<T: EthSpec> MockServer<T> {
             preloaded_responses,
             static_new_payload_response: <_>::default(),
             static_forkchoice_updated_response: <_>::default(),
+            previous_forkchoice_state: <_>::default(),
+            offline: <_>::default(),
+            inconsistent_payloads: <_>::default(),
             _phantom: PhantomData,
         });
 
@@ -178,6 +185,25 @@ impl<T: EthSpec> MockServer<T> {
         *self.ctx.static_forkchoice_updated_response.lock() = Some(status);
     }
 
+    /// Returns the forkchoice state of the most recent `engine_forkchoiceUpdatedV1` request.
+    pub fn previous_forkchoice_state(&self) -> Option<ForkChoiceState> {
+        *self.ctx.previous_forkchoice_state.lock()
+    }
+
+    /// Whilst `offline` is `true` the server responds to all requests with an HTTP error.
+    pub fn set_offline(&self, offline: bool) {
+        *self.ctx.offline.lock() = offline;
+    }
+
+    /// Respond to the next `count` requests for the block with `block_hash` and its transactions
+    /// with a block whose payload differs from that of the actual block.
+    pub fn return_inconsistent_payloads(&self, block_hash: ExecutionBlockHash, count: usize) {
+        self.ctx
+            .inconsistent_payloads
+            .lock()
+            .insert(block_hash, count);
+    }
+
     fn valid_status() -> PayloadStatusV1 {
         PayloadStatusV1 {
             status: PayloadStatusV1Status::Valid,
@@ -404,6 +430,9 @@ pub struct Context<T: EthSpec> {
     pub previous_request: Arc<Mutex<Option<serde_json::Value>>>,
     pub static_new_payload_response: Arc<Mutex<Option<StaticNewPayloadResponse>>>,
     pub static_forkchoice_updated_response: Arc<Mutex<Option<PayloadStatusV1>>>,
+    pub previous_forkchoice_state: Arc<Mutex<Option<ForkChoiceState>>>,
+    pub offline: Arc<Mutex<bool>>,
+    pub inconsistent_payloads: Arc<Mutex<HashMap<ExecutionBlockHash, usize>>>,
     pub _phantom: PhantomData<T>,
 }
 
@@ -517,6 +546,12 @@ pub fn serve<T: EthSpec>(
         .and(warp::body::json())
         .and(ctx_filter.clone())
         .and_then(|body: serde_json::Value, ctx: Arc<Context<T>>| async move {
+            if *ctx.offline.lock() {
+                return Ok(warp::http::Response::builder()
+                    .status(StatusCode::SERVICE_UNAVAILABLE)
+                    .body("offline".to_string()));
+            }
+
             let id = body
                 .get("id")
                 .and_then(serde_json::Value::as_u64)
@@ -11,6 +11,7 @@ mod block_id;
 mod block_packing_efficiency;
 mod block_rewards;
 mod database;
+mod listener;
 mod metrics;
 mod proposer_duties;
 mod state_id;
@@ -23,18 +24,21 @@ use beacon_chain::{
     observed_operations::ObservationOutcome,
     validator_monitor::{get_block_delay_ms, timestamp_now},
     AttestationError as AttnError, BeaconChain, BeaconChainError, BeaconChainTypes,
-    ProduceBlockVerification, WhenSlotSkipped,
+    BlockDeliveryTransport, BlockError, ProduceBlockVerification, WhenSlotSkipped,
 };
 use block_id::BlockId;
 use eth2::types::{self as api_types, EndpointVersion, ValidatorId};
+use futures::FutureExt;
 use lighthouse_network::{types::SyncState, EnrExt, NetworkGlobals, PeerId, PubsubMessage};
 use lighthouse_version::version_with_platform;
+pub use listener::{Listener, DEFAULT_UNIX_SOCKET_PERMISSIONS};
 use network::NetworkMessage;
 use serde::{Deserialize, Serialize};
 use slog::{crit, debug, error, info, warn, Logger};
 use slot_clock::SlotClock;
 use ssz::Encode;
 use state_id::StateId;
+use state_processing::BlockSignatureStrategy;
 use std::borrow::Cow;
 use std::convert::TryInto;
 use std::future::Future;
@@ -45,12 +49,11 @@ use std::sync::Arc;
 use tokio::sync::mpsc::UnboundedSender;
 use tokio_stream::{wrappers::BroadcastStream, StreamExt};
 use types::{
-    Attestation, AttesterSlashing, BeaconBlockBodyMerge, BeaconBlockMerge, BeaconStateError,
-    BlindedPayload, CommitteeCache, ConfigAndPreset, Epoch, EthSpec, ForkName, FullPayload,
-    ProposerPreparationData, ProposerSlashing, RelativeEpoch, Signature, SignedAggregateAndProof,
-    SignedBeaconBlock, SignedBeaconBlockMerge, SignedBlindedBeaconBlock,
-    SignedContributionAndProof, SignedValidatorRegistrationData, SignedVoluntaryExit, Slot,
-    SyncCommitteeMessage, SyncContributionData,
+    Attestation, AttesterSlashing, BeaconStateError, BlindedPayload, CommitteeCache,
+    ConfigAndPreset, Epoch, EthSpec, ForkName, FullPayload, Hash256, ProposerPreparationData,
+    ProposerSlashing, RelativeEpoch, Signature, SignedAggregateAndProof, SignedBeaconBlock,
+    SignedBlindedBeaconBlock, SignedContributionAndProof, SignedValidatorRegistrationData,
+    SignedVoluntaryExit, Slot, SyncCommitteeMessage, SyncContributionData,
 };
 use version::{
     add_consensus_version_header, fork_versioned_response, inconsistent_fork_rejection,
@@ -93,6 +96,8 @@ pub struct Context<T: BeaconChainTypes> {
     pub network_tx: Option<UnboundedSender<NetworkMessage<T::EthSpec>>>,
     pub network_globals: Option<Arc<NetworkGlobals<T::EthSpec>>>,
     pub eth1_service: Option<eth1::Service>,
+    /// The effective configuration of the beacon node, with any credentials redacted.
+    pub effective_config: Option<serde_json::Value>,
     pub log: Logger,
 }
 
@@ -106,6 +111,8 @@ pub struct Config {
     pub serve_legacy_spec: bool,
     pub tls_config: Option<TlsConfig>,
     pub allow_sync_stalled: bool,
+    /// Additional listeners to serve the API on, besides `listen_addr:listen_port`.
+    pub listeners: Vec<Listener>,
 }
 
 impl Default for Config {
@@ -118,6 +125,7 @@ impl Default for Config {
             serve_legacy_spec: true,
             tls_config: None,
             allow_sync_stalled: false,
+            listeners: vec![],
         }
     }
 }
@@ -244,10 +252,11 @@ pub fn prometheus_metrics() -> warp::filters::log::Log<impl Fn(warp::filters::lo
 ///
 /// ## Returns
 ///
-/// This function will bind the server to the provided address and then return a tuple of:
+/// This function will bind the server to the provided address and each of `config.listeners`,
+/// and then return a tuple of:
 ///
-/// - `SocketAddr`: the address that the HTTP server will listen on.
-/// - `Future`: the actual server future that will need to be awaited.
+/// - `SocketAddr`: the address that the HTTP server will listen on at `listen_addr`.
+/// - `Future`: the actual server future, serving every listener, that will need to be awaited.
 ///
 /// ## Errors
 ///
@@ -264,7 +273,7 @@ pub fn serve<T: BeaconChainTypes>(
     // Configure CORS.
     let cors_builder = {
         let builder = warp::cors()
-            .allow_methods(vec!["GET", "POST"])
+            .allow_methods(vec!["GET", "POST", "DELETE"])
             .allow_headers(vec!["Content-Type"]);
 
         warp_utils::cors::set_builder_origins(
@@ -369,23 +378,18 @@ pub fn serve<T: BeaconChainTypes>(
                       chain: Arc<BeaconChain<T>>| async move {
                     match *network_globals.sync_state.read() {
                         SyncState::SyncingFinalized { .. } => {
-                            let head_slot = chain.canonical_head.cached_head().head_slot();
-
-                            let current_slot =
-                                chain.slot_clock.now_or_genesis().ok_or_else(|| {
-                                    warp_utils::reject::custom_server_error(
-                                        "unable to read slot clock".to_string(),
-                                    )
-                                })?;
+                            let sync_status = chain
+                                .sync_status()
+                                .map_err(warp_utils::reject::beacon_chain_error)?;
 
                             let tolerance = SYNC_TOLERANCE_EPOCHS * T::EthSpec::slots_per_epoch();
 
-                            if head_slot + tolerance >= current_slot {
+                            if sync_status.sync_distance <= tolerance {
                                 Ok(())
                             } else {
                                 Err(warp_utils::reject::not_synced(format!(
                                     "head slot is {}, current slot is {}",
-                                    head_slot, current_slot
+                                    sync_status.head_slot, sync_status.current_slot
                                 )))
                             }
                         }
@@ -443,6 +447,40 @@ pub fn serve<T: BeaconChainTypes>(
         }))
         .and(chain_filter.clone());
 
+    // GET beacon/deposit_snapshot
+    let get_beacon_deposit_snapshot = eth1_v1
+        .and(warp::path("beacon"))
+        .and(warp::path("deposit_snapshot"))
+        .and(warp::path::end())
+        .and(chain_filter.clone())
+        .and(eth1_service_filter.clone())
+        .and_then(|chain: Arc<BeaconChain<T>>, eth1_service: eth1::Service| {
+            blocking_json_task(move || {
+                // Only deposits which have all been processed by the finalized state are part of
+                // the snapshot, so that its tree never needs to be modified.
+                let eth1_data = StateId::finalized().map_state(&chain, |state| {
+                    let eth1_data = state.eth1_data();
+                    if state.eth1_deposit_index() >= eth1_data.deposit_count {
+                        Ok(eth1_data.clone())
+                    } else {
+                        Err(warp_utils::reject::custom_not_found(
+                            "the deposits of the finalized eth1 data haven't been processed"
+                                .to_string(),
+                        ))
+                    }
+                })?;
+                eth1_service
+                    .get_deposit_snapshot(&eth1_data)
+                    .map(api_types::GenericResponse::from)
+                    .ok_or_else(|| {
+                        warp_utils::reject::custom_not_found(
+                            "no deposit snapshot is available for the finalized eth1 data"
+                                .to_string(),
+                        )
+                    })
+            })
+        });
+
     // GET beacon/states/{state_id}/root
     let get_beacon_state_root = beacon_states_path
         .clone()
@@ -932,15 +970,21 @@ pub fn serve<T: BeaconChainTypes>(
              log: Logger| async move {
                 let seen_timestamp = timestamp_now();
 
-                // Send the block, regardless of whether or not it is valid. The API
-                // specification is very clear that this is the desired behaviour.
-                publish_pubsub_message(&network_tx, PubsubMessage::BeaconBlock(block.clone()))?;
-
                 // Determine the delay after the start of the slot, register it with metrics.
                 let delay = get_block_delay_ms(seen_timestamp, block.message(), &chain.slot_clock);
                 metrics::observe_duration(&metrics::HTTP_API_BLOCK_BROADCAST_DELAY_TIMES, delay);
 
-                match chain.process_block(block.clone()).await {
+                // Write the time the block was observed into the delay cache.
+                chain.block_times_cache.write().set_time_observed(
+                    block.canonical_root(),
+                    block.slot(),
+                    seen_timestamp,
+                    BlockDeliveryTransport::Api,
+                    None,
+                    None,
+                );
+
+                match publish_then_import_block(&chain, &network_tx, block.clone()).await? {
                     Ok(root) => {
                         info!(
                             log,
@@ -1022,69 +1066,63 @@ pub fn serve<T: BeaconChainTypes>(
         .and(network_tx_filter.clone())
         .and(log_filter.clone())
         .and_then(
-            |block: Arc<SignedBeaconBlock<T::EthSpec, BlindedPayload<_>>>,
+            |block: SignedBeaconBlock<T::EthSpec, BlindedPayload<_>>,
              chain: Arc<BeaconChain<T>>,
              network_tx: UnboundedSender<NetworkMessage<T::EthSpec>>,
-             _log: Logger| async move {
-                if let Some(el) = chain.execution_layer.as_ref() {
-                    //FIXME(sean): we may not always receive the payload in this response because it
-                    // should be the relay's job to propogate the block. However, since this block is
-                    // already signed and sent this might be ok (so long as the relay validates
-                    // the block before revealing the payload).
+             log: Logger| async move {
+                let seen_timestamp = timestamp_now();
 
+                if chain.execution_layer.is_some() {
                     //FIXME(sean) additionally, this endpoint should serve blocks prior to Bellatrix, and should
                     // be able to support the normal block proposal flow, because at some point full block endpoints
                     // will be deprecated from the beacon API. This will entail creating full blocks in
                     // `validator/blinded_blocks`, caching their payloads, and transforming them into blinded
                     // blocks. We will access the payload of those blocks here. This flow should happen if the
                     // execution layer has no payload builders or if we have not yet finalized post-merge transition.
-                    let payload = el.propose_blinded_beacon_block(&block).await.map_err(|e| {
-                        warp_utils::reject::custom_server_error(format!("proposal failed: {:?}", e))
-                    })?;
-                    let new_block = SignedBeaconBlock::Merge(SignedBeaconBlockMerge {
-                        message: BeaconBlockMerge {
-                            slot: block.message().slot(),
-                            proposer_index: block.message().proposer_index(),
-                            parent_root: block.message().parent_root(),
-                            state_root: block.message().state_root(),
-                            body: BeaconBlockBodyMerge {
-                                randao_reveal: block.message().body().randao_reveal().clone(),
-                                eth1_data: block.message().body().eth1_data().clone(),
-                                graffiti: *block.message().body().graffiti(),
-                                proposer_slashings: block
-                                    .message()
-                                    .body()
-                                    .proposer_slashings()
-                                    .clone(),
-                                attester_slashings: block
-                                    .message()
-                                    .body()
-                                    .attester_slashings()
-                                    .clone(),
-                                attestations: block.message().body().attestations().clone(),
-                                deposits: block.message().body().deposits().clone(),
-                                voluntary_exits: block.message().body().voluntary_exits().clone(),
-                                sync_aggregate: block
-                                    .message()
-                                    .body()
-                                    .sync_aggregate()
-                                    .unwrap()
-                                    .clone(),
-                                execution_payload: payload.into(),
-                            },
-                        },
-                        signature: block.signature().clone(),
-                    });
+                    let withheld_block = chain
+                        .config
+                        .broadcast_withheld_blinded_blocks
+                        .then(|| block.clone());
+                    let new_block = match chain.unblind_block(block).await {
+                        Ok(new_block) => new_block,
+                        Err(e) => {
+                            if let (
+                                Some(withheld_block),
+                                BeaconChainError::BuilderFailedToRevealPayload { .. }
+                                | BeaconChainError::BuilderRevealedInconsistentPayload { .. },
+                            ) = (withheld_block, &e)
+                            {
+                                // Peers subscribed to the blinded block topic may still be able
+                                // to obtain the payload from the relay.
+                                info!(
+                                    log,
+                                    "Broadcasting withheld blinded block";
+                                    "slot" => withheld_block.slot(),
+                                    "block_root" => ?withheld_block.canonical_root(),
+                                );
+                                publish_pubsub_message(
+                                    &network_tx,
+                                    PubsubMessage::BlindedBeaconBlock(Arc::new(withheld_block)),
+                                )?;
+                            }
+                            return Err(warp_utils::reject::custom_server_error(format!(
+                                "proposal failed: {:?}",
+                                e
+                            )));
+                        }
+                    };
                     let new_block = Arc::new(new_block);
 
-                    // Send the block, regardless of whether or not it is valid. The API
-                    // specification is very clear that this is the desired behaviour.
-                    publish_pubsub_message(
-                        &network_tx,
-                        PubsubMessage::BeaconBlock(new_block.clone()),
-                    )?;
+                    chain.block_times_cache.write().set_time_observed(
+                        new_block.canonical_root(),
+                        new_block.slot(),
+                        seen_timestamp,
+                        BlockDeliveryTransport::Api,
+                        None,
+                        None,
+                    );
 
-                    match chain.process_block(new_block).await {
+                    match publish_then_import_block(&chain, &network_tx, new_block).await? {
                         Ok(_) => {
                             // Update the head since it's likely this block will become the new
                             // head.
@@ -1706,18 +1744,15 @@ pub fn serve<T: BeaconChainTypes>(
         .and_then(
             |network_globals: Arc<NetworkGlobals<T::EthSpec>>, chain: Arc<BeaconChain<T>>| {
                 blocking_json_task(move || {
-                    let head_slot = chain.canonical_head.cached_head().head_slot();
-                    let current_slot = chain.slot_clock.now_or_genesis().ok_or_else(|| {
-                        warp_utils::reject::custom_server_error("Unable to read slot clock".into())
-                    })?;
-
-                    // Taking advantage of saturating subtraction on slot.
-                    let sync_distance = current_slot - head_slot;
+                    let sync_status = chain
+                        .sync_status()
+                        .map_err(warp_utils::reject::beacon_chain_error)?;
 
                     let syncing_data = api_types::SyncingData {
                         is_syncing: network_globals.sync_state.read().is_syncing(),
-                        head_slot,
-                        sync_distance,
+                        is_optimistic: Some(sync_status.is_optimistic),
+                        head_slot: sync_status.head_slot,
+                        sync_distance: sync_status.sync_distance,
                     };
 
                     Ok(api_types::GenericResponse::from(syncing_data))
@@ -2418,17 +2453,11 @@ pub fn serve<T: BeaconChainTypes>(
             |chain: Arc<BeaconChain<T>>,
              log: Logger,
              register_val_data: Vec<SignedValidatorRegistrationData>| async move {
-                let execution_layer = chain
-                    .execution_layer
-                    .as_ref()
-                    .ok_or(BeaconChainError::ExecutionLayerMissing)
-                    .map_err(warp_utils::reject::beacon_chain_error)?;
                 let current_slot = chain
                     .slot_clock
                     .now_or_genesis()
                     .ok_or(BeaconChainError::UnableToReadSlot)
                     .map_err(warp_utils::reject::beacon_chain_error)?;
-                let current_epoch = current_slot.epoch(T::EthSpec::slots_per_epoch());
 
                 debug!(
                     log,
@@ -2436,29 +2465,12 @@ pub fn serve<T: BeaconChainTypes>(
                     "count" => register_val_data.len(),
                 );
 
-                let preparation_data = register_val_data
-                    .iter()
-                    .filter_map(|register_data| {
-                        chain
-                            .validator_index(&register_data.message.pubkey)
-                            .ok()
-                            .flatten()
-                            .map(|validator_index| ProposerPreparationData {
-                                validator_index: validator_index as u64,
-                                fee_recipient: register_data.message.fee_recipient,
-                            })
-                    })
-                    .collect::<Vec<_>>();
-
-                debug!(
-                    log,
-                    "Resolved validator request pubkeys";
-                    "count" => preparation_data.len()
-                );
-
-                // Update the prepare beacon proposer cache based on this request.
-                execution_layer
-                    .update_proposer_preparation(current_epoch, &preparation_data)
+                // Verify and store the registrations, forwarding them to the builder.
+                //
+                // The result is only checked after proposer preparation, since the registrations
+                // have been stored even if forwarding them to the builder failed.
+                let result = chain
+                    .process_validator_registrations(register_val_data)
                     .await;
 
                 // Call prepare beacon proposer blocking with the latest update in order to make
@@ -2474,7 +2486,30 @@ pub fn serve<T: BeaconChainTypes>(
                         ))
                     })?;
 
-                //TODO(sean): In the MEV-boost PR, add a call here to send the update request to the builder
+                let outcomes = result.map_err(warp_utils::reject::beacon_chain_error)?;
+
+                debug!(
+                    log,
+                    "Processed register validator request";
+                    "count" => outcomes.len(),
+                    "accepted" => outcomes.iter().filter(|outcome| outcome.is_accepted()).count(),
+                );
+
+                let failures = outcomes
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(index, outcome)| {
+                        outcome
+                            .failure_reason()
+                            .map(|reason| api_types::Failure::new(index, reason.to_string()))
+                    })
+                    .collect::<Vec<_>>();
+                if !failures.is_empty() {
+                    return Err(warp_utils::reject::indexed_bad_request(
+                        "error processing validator registrations".to_string(),
+                        failures,
+                    ));
+                }
 
                 Ok::<_, warp::Rejection>(warp::reply::json(&()))
             },
@@ -2566,6 +2601,43 @@ pub fn serve<T: BeaconChainTypes>(
             })
         });
 
+    // GET lighthouse/health/execution_engines
+    let get_lighthouse_health_execution_engines = warp::path("lighthouse")
+        .and(warp::path("health"))
+        .and(warp::path("execution_engines"))
+        .and(warp::path::end())
+        .and(chain_filter.clone())
+        .and_then(|chain: Arc<BeaconChain<T>>| async move {
+            let execution_layer = chain
+                .execution_layer
+                .as_ref()
+                .ok_or(BeaconChainError::ExecutionLayerMissing)
+                .map_err(warp_utils::reject::beacon_chain_error)?;
+
+            Ok::<_, warp::reject::Rejection>(warp::reply::json(&api_types::GenericResponse::from(
+                execution_layer.execution_engine_health().await,
+            )))
+        });
+
+    // GET lighthouse/health/self_test
+    let get_lighthouse_health_self_test = warp::path("lighthouse")
+        .and(warp::path("health"))
+        .and(warp::path("self_test"))
+        .and(warp::path::end())
+        .and(chain_filter.clone())
+        .and_then(|chain: Arc<BeaconChain<T>>| {
+            blocking_json_task(move || {
+                chain
+                    .self_test_report()
+                    .map(api_types::GenericResponse::from)
+                    .ok_or_else(|| {
+                        warp_utils::reject::custom_not_found(
+                            "self-test has not been run, see --self-test".to_string(),
+                        )
+                    })
+            })
+        });
+
     // GET lighthouse/syncing
     let get_lighthouse_syncing = warp::path("lighthouse")
         .and(warp::path("syncing"))
@@ -2595,6 +2667,24 @@ pub fn serve<T: BeaconChainTypes>(
             })
         });
 
+    // GET lighthouse/config
+    let inner_ctx = ctx.clone();
+    let get_lighthouse_config = warp::path("lighthouse")
+        .and(warp::path("config"))
+        .and(warp::path::end())
+        .and_then(move || {
+            let effective_config = inner_ctx.effective_config.clone();
+            blocking_json_task(move || {
+                effective_config
+                    .map(api_types::GenericResponse::from)
+                    .ok_or_else(|| {
+                        warp_utils::reject::custom_not_found(
+                            "The effective config is not available.".to_string(),
+                        )
+                    })
+            })
+        });
+
     // GET lighthouse/peers
     let get_lighthouse_peers = warp::path("lighthouse")
         .and(warp::path("peers"))
@@ -2651,6 +2741,20 @@ pub fn serve<T: BeaconChainTypes>(
             })
         });
 
+    // GET lighthouse/fork_choice/runs
+    let get_lighthouse_fork_choice_runs = warp::path("lighthouse")
+        .and(warp::path("fork_choice"))
+        .and(warp::path("runs"))
+        .and(warp::path::end())
+        .and(chain_filter.clone())
+        .and_then(|chain: Arc<BeaconChain<T>>| {
+            blocking_json_task(move || {
+                Ok(api_types::GenericResponse::from(
+                    chain.fork_choice_run_summaries(),
+                ))
+            })
+        });
+
     // GET lighthouse/validator_inclusion/{epoch}/{validator_id}
     let get_lighthouse_validator_inclusion_global = warp::path("lighthouse")
         .and(warp::path("validator_inclusion"))
@@ -2711,6 +2815,25 @@ pub fn serve<T: BeaconChainTypes>(
             })
         });
 
+    // GET lighthouse/eth1/cache_status
+    let get_lighthouse_eth1_cache_status = warp::path("lighthouse")
+        .and(warp::path("eth1"))
+        .and(warp::path("cache_status"))
+        .and(warp::path::end())
+        .and(chain_filter.clone())
+        .and_then(|chain: Arc<BeaconChain<T>>| {
+            blocking_json_task(move || {
+                chain
+                    .eth1_cache_status()
+                    .ok_or_else(|| {
+                        warp_utils::reject::custom_not_found(
+                            "Eth1 sync is disabled. See the --eth1 CLI flag.".to_string(),
+                        )
+                    })
+                    .map(api_types::GenericResponse::from)
+            })
+        });
+
     // GET lighthouse/eth1/block_cache
     let get_lighthouse_eth1_block_cache = warp::path("lighthouse")
         .and(warp::path("eth1"))
@@ -2887,6 +3010,125 @@ pub fn serve<T: BeaconChainTypes>(
             })
         });
 
+    // GET lighthouse/analysis/proposals
+    let get_lighthouse_proposals = warp::path("lighthouse")
+        .and(warp::path("analysis"))
+        .and(warp::path("proposals"))
+        .and(warp::path::end())
+        .and(chain_filter.clone())
+        .and_then(|chain: Arc<BeaconChain<T>>| {
+            blocking_json_task(move || {
+                Ok(api_types::GenericResponse::from(chain.recent_proposals()))
+            })
+        });
+
+    // GET lighthouse/analysis/exit_queue
+    let get_lighthouse_exit_queue = warp::path("lighthouse")
+        .and(warp::path("analysis"))
+        .and(warp::path("exit_queue"))
+        .and(warp::path::end())
+        .and(chain_filter.clone())
+        .and_then(|chain: Arc<BeaconChain<T>>| {
+            blocking_json_task(move || {
+                chain
+                    .exit_queue_summary()
+                    .map(api_types::GenericResponse::from)
+                    .map_err(warp_utils::reject::beacon_chain_error)
+            })
+        });
+
+    // GET lighthouse/builders
+    let get_lighthouse_builders = warp::path("lighthouse")
+        .and(warp::path("builders"))
+        .and(warp::path::end())
+        .and(chain_filter.clone())
+        .and_then(|chain: Arc<BeaconChain<T>>| {
+            blocking_json_task(move || Ok(api_types::GenericResponse::from(chain.list_builders())))
+        });
+
+    // POST lighthouse/builders
+    //
+    // Not authenticated, like the rest of the API, so anyone able to reach the API can choose the
+    // builders used for block production.
+    let post_lighthouse_builders = warp::path("lighthouse")
+        .and(warp::path("builders"))
+        .and(warp::path::end())
+        .and(warp::body::json())
+        .and(chain_filter.clone())
+        .and_then(
+            |config: execution_layer::BuilderConfig, chain: Arc<BeaconChain<T>>| async move {
+                chain.add_builder(config).await.map_err(|e| match e {
+                    BeaconChainError::InvalidBuilderConfig(_)
+                    | BeaconChainError::AddBuilderFailed(_) => {
+                        warp_utils::reject::custom_bad_request(format!("{:?}", e))
+                    }
+                    e => warp_utils::reject::beacon_chain_error(e),
+                })?;
+                Ok::<_, warp::Rejection>(warp::reply::json(&api_types::GenericResponse::from(
+                    chain.list_builders(),
+                )))
+            },
+        );
+
+    // DELETE lighthouse/builders
+    //
+    // Not authenticated, see `POST lighthouse/builders`.
+    let delete_lighthouse_builders = warp::path("lighthouse")
+        .and(warp::path("builders"))
+        .and(warp::path::end())
+        .and(warp::body::json())
+        .and(chain_filter.clone())
+        .and_then(
+            |request: eth2::lighthouse::RemoveBuilderRequest, chain: Arc<BeaconChain<T>>| {
+                blocking_json_task(move || {
+                    let removed = chain
+                        .remove_builder(&request.url)
+                        .map_err(warp_utils::reject::beacon_chain_error)?;
+                    if !removed {
+                        return Err(warp_utils::reject::custom_not_found(format!(
+                            "no builder connected at {}",
+                            request.url
+                        )));
+                    }
+                    Ok(api_types::GenericResponse::from(chain.list_builders()))
+                })
+            },
+        );
+
+    // GET lighthouse/orphan_blocks
+    let get_lighthouse_orphan_blocks = warp::path("lighthouse")
+        .and(warp::path("orphan_blocks"))
+        .and(warp::path::end())
+        .and(chain_filter.clone())
+        .and_then(|chain: Arc<BeaconChain<T>>| {
+            blocking_json_task(move || Ok(api_types::GenericResponse::from(chain.orphan_blocks())))
+        });
+
+    // GET lighthouse/analysis/replay/{block_id}
+    let get_lighthouse_replay = warp::path("lighthouse")
+        .and(warp::path("analysis"))
+        .and(warp::path("replay"))
+        .and(block_id_or_err)
+        .and(warp::path::end())
+        .and(chain_filter.clone())
+        .and_then(|block_id: BlockId, chain: Arc<BeaconChain<T>>| {
+            blocking_json_task(move || {
+                let block_root = block_id.root(&chain)?;
+                chain
+                    .replay_block(block_root, BlockSignatureStrategy::VerifyIndividual)
+                    .map(api_types::GenericResponse::from)
+                    .map_err(|e| match e {
+                        BeaconChainError::MissingBeaconBlock(root) => {
+                            warp_utils::reject::custom_not_found(format!(
+                                "missing block {:?}",
+                                root
+                            ))
+                        }
+                        e => warp_utils::reject::beacon_chain_error(e),
+                    })
+            })
+        });
+
     // GET lighthouse/merge_readiness
     let get_lighthouse_merge_readiness = warp::path("lighthouse")
         .and(warp::path("merge_readiness"))
@@ -2914,48 +3156,68 @@ pub fn serve<T: BeaconChainTypes>(
 
                     if let Some(event_handler) = chain.event_handler.as_ref() {
                         for topic in topics.topics {
-                            let receiver = match topic {
-                                api_types::EventTopic::Head => event_handler.subscribe_head(),
-                                api_types::EventTopic::Block => event_handler.subscribe_block(),
+                            let (replay, receiver) = match topic {
+                                api_types::EventTopic::Head => {
+                                    event_handler.subscribe_head_with_replay()
+                                }
+                                api_types::EventTopic::Block => {
+                                    (None, event_handler.subscribe_block())
+                                }
                                 api_types::EventTopic::Attestation => {
-                                    event_handler.subscribe_attestation()
+                                    (None, event_handler.subscribe_attestation())
                                 }
                                 api_types::EventTopic::VoluntaryExit => {
-                                    event_handler.subscribe_exit()
+                                    (None, event_handler.subscribe_exit())
                                 }
                                 api_types::EventTopic::FinalizedCheckpoint => {
-                                    event_handler.subscribe_finalized()
+                                    event_handler.subscribe_finalized_with_replay()
                                 }
                                 api_types::EventTopic::ChainReorg => {
-                                    event_handler.subscribe_reorgs()
+                                    (None, event_handler.subscribe_reorgs())
+                                }
+                                api_types::EventTopic::CanonicalChainUpdate => {
+                                    (None, event_handler.subscribe_canonical_chain_updates())
                                 }
                                 api_types::EventTopic::ContributionAndProof => {
-                                    event_handler.subscribe_contributions()
+                                    (None, event_handler.subscribe_contributions())
                                 }
                                 api_types::EventTopic::LateHead => {
-                                    event_handler.subscribe_late_head()
+                                    (None, event_handler.subscribe_late_head())
+                                }
+                                api_types::EventTopic::ValidatorRegistryUpdate => {
+                                    (None, event_handler.subscribe_validator_registry_updates())
+                                }
+                                api_types::EventTopic::FinalityDelay => {
+                                    (None, event_handler.subscribe_finality_delay())
+                                }
+                                api_types::EventTopic::InvalidPublishedBlock => {
+                                    (None, event_handler.subscribe_invalid_published_block())
                                 }
                                 api_types::EventTopic::BlockReward => {
-                                    event_handler.subscribe_block_reward()
+                                    (None, event_handler.subscribe_block_reward())
                                 }
                             };
 
-                            receivers.push(BroadcastStream::new(receiver).map(|msg| {
-                                match msg {
-                                    Ok(data) => Event::default()
-                                        .event(data.topic_name())
-                                        .json_data(data)
-                                        .map_err(|e| {
-                                            warp_utils::reject::server_sent_event_error(format!(
-                                                "{:?}",
-                                                e
-                                            ))
-                                        }),
-                                    Err(e) => Err(warp_utils::reject::server_sent_event_error(
-                                        format!("{:?}", e),
-                                    )),
-                                }
-                            }));
+                            // Replay the most recent event of the topic, if any, so that the
+                            // subscriber needn't wait for the next one to learn the current value.
+                            let replay = futures::stream::iter(replay.map(Ok));
+                            receivers.push(replay.chain(BroadcastStream::new(receiver)).map(
+                                |msg| {
+                                    match msg {
+                                        Ok(data) => Event::default()
+                                            .event(data.topic_name())
+                                            .json_data(data)
+                                            .map_err(|e| {
+                                                warp_utils::reject::server_sent_event_error(
+                                                    format!("{:?}", e),
+                                                )
+                                            }),
+                                        Err(e) => Err(warp_utils::reject::server_sent_event_error(
+                                            format!("{:?}", e),
+                                        )),
+                                    }
+                                },
+                            ));
                         }
                     } else {
                         return Err(warp_utils::reject::custom_server_error(
@@ -2975,6 +3237,7 @@ pub fn serve<T: BeaconChainTypes>(
         .and(
             get_beacon_genesis
                 .boxed()
+                .or(get_beacon_deposit_snapshot.boxed())
                 .or(get_beacon_state_root.boxed())
                 .or(get_beacon_state_fork.boxed())
                 .or(get_beacon_state_finality_checkpoints.boxed())
@@ -3011,14 +3274,19 @@ pub fn serve<T: BeaconChainTypes>(
                 .or(get_validator_aggregate_attestation.boxed())
                 .or(get_validator_sync_committee_contribution.boxed())
                 .or(get_lighthouse_health.boxed())
+                .or(get_lighthouse_health_execution_engines.boxed())
+                .or(get_lighthouse_health_self_test.boxed())
                 .or(get_lighthouse_syncing.boxed())
                 .or(get_lighthouse_nat.boxed())
+                .or(get_lighthouse_config.boxed())
                 .or(get_lighthouse_peers.boxed())
                 .or(get_lighthouse_peers_connected.boxed())
                 .or(get_lighthouse_proto_array.boxed())
+                .or(get_lighthouse_fork_choice_runs.boxed())
                 .or(get_lighthouse_validator_inclusion_global.boxed())
                 .or(get_lighthouse_validator_inclusion.boxed())
                 .or(get_lighthouse_eth1_syncing.boxed())
+                .or(get_lighthouse_eth1_cache_status.boxed())
                 .or(get_lighthouse_eth1_block_cache.boxed())
                 .or(get_lighthouse_eth1_deposit_cache.boxed())
                 .or(get_lighthouse_beacon_states_ssz.boxed())
@@ -3027,6 +3295,11 @@ pub fn serve<T: BeaconChainTypes>(
                 .or(get_lighthouse_block_rewards.boxed())
                 .or(get_lighthouse_attestation_performance.boxed())
                 .or(get_lighthouse_block_packing_efficiency.boxed())
+                .or(get_lighthouse_proposals.boxed())
+                .or(get_lighthouse_exit_queue.boxed())
+                .or(get_lighthouse_orphan_blocks.boxed())
+                .or(get_lighthouse_builders.boxed())
+                .or(get_lighthouse_replay.boxed())
                 .or(get_lighthouse_merge_readiness.boxed())
                 .or(get_events.boxed()),
         )
@@ -3050,8 +3323,10 @@ pub fn serve<T: BeaconChainTypes>(
                 .or(post_lighthouse_liveness.boxed())
                 .or(post_lighthouse_database_reconstruct.boxed())
                 .or(post_lighthouse_database_historical_blocks.boxed())
-                .or(post_lighthouse_block_rewards.boxed()),
+                .or(post_lighthouse_block_rewards.boxed())
+                .or(post_lighthouse_builders.boxed()),
         ))
+        .or(warp::delete().and(delete_lighthouse_builders.boxed()))
         .recover(warp_utils::reject::handle_rejection)
         .with(slog_logging(log.clone()))
         .with(prometheus_metrics())
@@ -3059,37 +3334,125 @@ pub fn serve<T: BeaconChainTypes>(
         .map(|reply| warp::reply::with_header(reply, "Server", &version_with_platform()))
         .with(cors_builder.build());
 
-    let http_socket: SocketAddr = SocketAddr::new(config.listen_addr, config.listen_port);
-    let http_server: HttpServer = match config.tls_config {
-        Some(tls_config) => {
-            let (socket, server) = warp::serve(routes)
-                .tls()
-                .cert_path(tls_config.cert)
-                .key_path(tls_config.key)
-                .try_bind_with_graceful_shutdown(http_socket, async {
-                    shutdown.await;
-                })?;
+    // Every listener shuts down with the same signal.
+    let shutdown = Box::pin(shutdown).shared();
+
+    let bind_tcp = |http_socket: SocketAddr| -> Result<HttpServer, Error> {
+        let http_server: HttpServer = match &config.tls_config {
+            Some(tls_config) => {
+                let (socket, server) = warp::serve(routes.clone())
+                    .tls()
+                    .cert_path(&tls_config.cert)
+                    .key_path(&tls_config.key)
+                    .try_bind_with_graceful_shutdown(http_socket, shutdown.clone())?;
+                (socket, Box::pin(server))
+            }
+            None => {
+                let (socket, server) = warp::serve(routes.clone())
+                    .try_bind_with_graceful_shutdown(http_socket, shutdown.clone())?;
+                (socket, Box::pin(server))
+            }
+        };
+        Ok(http_server)
+    };
 
-            info!(log, "HTTP API is being served over TLS";);
+    let http_socket: SocketAddr = SocketAddr::new(config.listen_addr, config.listen_port);
+    let (listen_address, server) = bind_tcp(http_socket)?;
 
-            (socket, Box::pin(server))
-        }
-        None => {
-            let (socket, server) =
-                warp::serve(routes).try_bind_with_graceful_shutdown(http_socket, async {
-                    shutdown.await;
-                })?;
-            (socket, Box::pin(server))
-        }
-    };
+    if config.tls_config.is_some() {
+        info!(log, "HTTP API is being served over TLS";);
+    }
 
     info!(
         log,
         "HTTP API started";
-        "listen_address" => %http_server.0,
+        "listen_address" => %listen_address,
     );
 
-    Ok(http_server)
+    let mut servers = vec![server];
+    for listener_config in &config.listeners {
+        match listener_config {
+            Listener::Tcp(http_socket) => {
+                let (socket, server) = bind_tcp(*http_socket)?;
+                info!(
+                    log,
+                    "HTTP API listening";
+                    "listen_address" => %socket,
+                );
+                servers.push(server);
+            }
+            #[cfg(unix)]
+            Listener::Unix { path, permissions } => {
+                let unix_listener =
+                    listener::bind_unix_socket(path, *permissions).map_err(Error::Other)?;
+                let server = warp::serve(routes.clone()).serve_incoming_with_graceful_shutdown(
+                    tokio_stream::wrappers::UnixListenerStream::new(unix_listener),
+                    shutdown.clone(),
+                );
+                info!(
+                    log,
+                    "HTTP API listening";
+                    "unix_socket" => %path.display(),
+                    "permissions" => format!("{:o}", permissions),
+                );
+
+                let path = path.clone();
+                let log = log.clone();
+                servers.push(Box::pin(async move {
+                    server.await;
+                    if let Err(e) = listener::remove_unix_socket(&path) {
+                        warn!(
+                            log,
+                            "Failed to clean up HTTP API socket";
+                            "error" => e,
+                        );
+                    }
+                }));
+            }
+            #[cfg(not(unix))]
+            Listener::Unix { path, .. } => {
+                return Err(Error::Other(format!(
+                    "Unable to serve the HTTP API on {}, Unix sockets are not supported on this \
+                     platform",
+                    path.display()
+                )));
+            }
+        }
+    }
+
+    let server = futures::future::join_all(servers).map(|_| ());
+    Ok((listen_address, Box::pin(server)))
+}
+
+/// Publish `block` on the libp2p pubsub network as soon as it passes gossip verification, and
+/// then import it.
+///
+/// A block which fails gossip verification is published anyway, since the API specification is
+/// very clear that blocks should be sent regardless of whether or not they are valid. Such a block
+/// may still be importable (e.g., a repeat proposal), so it is imported without gossip
+/// verification.
+async fn publish_then_import_block<T: BeaconChainTypes>(
+    chain: &Arc<BeaconChain<T>>,
+    network_tx: &UnboundedSender<NetworkMessage<T::EthSpec>>,
+    block: Arc<SignedBeaconBlock<T::EthSpec>>,
+) -> Result<Result<Hash256, BlockError<T::EthSpec>>, warp::Rejection> {
+    let mut published = None;
+    let result = chain
+        .publish_then_import(block.clone(), |block| {
+            published = Some(publish_pubsub_message(
+                network_tx,
+                PubsubMessage::BeaconBlock(block),
+            ))
+        })
+        .await;
+
+    if let Some(published) = published {
+        published?;
+        Ok(result)
+    } else {
+        publish_pubsub_message(network_tx, PubsubMessage::BeaconBlock(block.clone()))?;
+        Ok(chain.process_block(block).await)
+    }
 }
 
 /// Publish a message to the libp2p pubsub network.
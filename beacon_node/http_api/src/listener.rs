@@ -0,0 +1,125 @@
+//! Listeners on which the HTTP API is served in addition to `Config::listen_addr`.
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+
+/// The default permissions of a Unix domain socket, allowing access by its owner and group.
+pub const DEFAULT_UNIX_SOCKET_PERMISSIONS: u32 = 0o660;
+
+/// An additional address on which to serve the HTTP API.
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Listener {
+    /// A TCP socket address. TLS applies to this listener if it is configured.
+    Tcp(SocketAddr),
+    /// A Unix domain socket, which is never served over TLS.
+    Unix {
+        path: PathBuf,
+        /// The permission bits of the socket file, e.g. `0o660`.
+        #[serde(default = "default_unix_socket_permissions")]
+        permissions: u32,
+    },
+}
+
+fn default_unix_socket_permissions() -> u32 {
+    DEFAULT_UNIX_SOCKET_PERMISSIONS
+}
+
+#[cfg(unix)]
+pub use unix::{bind_unix_socket, remove_unix_socket};
+
+#[cfg(unix)]
+mod unix {
+    use std::fs;
+    use std::io::ErrorKind;
+    use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+    use std::path::Path;
+    use tokio::net::UnixListener;
+
+    /// Bind a Unix domain socket at `path` with the given `permissions`.
+    ///
+    /// A socket left at `path` by a previous run that didn't shut down cleanly is removed, but
+    /// a socket which is still in use or any other kind of file is never replaced.
+    pub fn bind_unix_socket(path: &Path, permissions: u32) -> Result<UnixListener, String> {
+        match fs::symlink_metadata(path) {
+            Ok(metadata) if metadata.file_type().is_socket() => {
+                if std::os::unix::net::UnixStream::connect(path).is_ok() {
+                    return Err(format!("Unix socket {} is already in use", path.display()));
+                }
+                fs::remove_file(path).map_err(|e| {
+                    format!("Unable to remove stale socket {}: {}", path.display(), e)
+                })?;
+            }
+            Ok(_) => {
+                return Err(format!(
+                    "Unable to bind Unix socket, {} exists and is not a socket",
+                    path.display()
+                ))
+            }
+            Err(e) if e.kind() == ErrorKind::NotFound => (),
+            Err(e) => return Err(format!("Unable to read {}: {}", path.display(), e)),
+        }
+
+        let listener = UnixListener::bind(path)
+            .map_err(|e| format!("Unable to bind Unix socket {}: {}", path.display(), e))?;
+        fs::set_permissions(path, fs::Permissions::from_mode(permissions)).map_err(|e| {
+            format!(
+                "Unable to set permissions of Unix socket {}: {}",
+                path.display(),
+                e
+            )
+        })?;
+
+        Ok(listener)
+    }
+
+    /// Remove the socket at `path` once the server listening on it has shut down.
+    pub fn remove_unix_socket(path: &Path) -> Result<(), String> {
+        match fs::remove_file(path) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(format!("Unable to remove socket {}: {}", path.display(), e)),
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn stale_socket_is_replaced() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("http.sock");
+
+        // A socket whose listener has gone away, as after a crash.
+        drop(std::os::unix::net::UnixListener::bind(&path).unwrap());
+        assert!(path.exists());
+
+        let listener = bind_unix_socket(&path, 0o600).unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions();
+        assert_eq!(
+            std::os::unix::fs::PermissionsExt::mode(&mode) & 0o777,
+            0o600
+        );
+
+        // The socket is now in use.
+        let error = bind_unix_socket(&path, 0o600).unwrap_err();
+        assert!(error.contains("already in use"), "{}", error);
+
+        drop(listener);
+        remove_unix_socket(&path).unwrap();
+        assert!(!path.exists());
+    }
+
+    #[tokio::test]
+    async fn other_files_are_not_replaced() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("http.sock");
+        std::fs::write(&path, "not a socket").unwrap();
+
+        let error = bind_unix_socket(&path, 0o600).unwrap_err();
+        assert!(error.contains("is not a socket"), "{}", error);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "not a socket");
+    }
+}
@@ -1,4 +1,4 @@
-use beacon_chain::{BeaconChain, BeaconChainTypes};
+use beacon_chain::{checkpoint_state_cache::CheckpointKind, BeaconChain, BeaconChainTypes};
 use eth2::types::StateId as CoreStateId;
 use std::str::FromStr;
 use types::{BeaconState, EthSpec, Fork, Hash256, Slot};
@@ -12,6 +12,10 @@ impl StateId {
         Self(CoreStateId::Slot(slot))
     }
 
+    pub fn finalized() -> Self {
+        Self(CoreStateId::Finalized)
+    }
+
     /// Return the state root identified by `self`.
     pub fn root<T: BeaconChainTypes>(
         &self,
@@ -52,11 +56,28 @@ impl StateId {
         self.map_state(chain, |state| Ok(state.fork()))
     }
 
+    /// Return the checkpoint identified by `self`, if any.
+    fn checkpoint_kind(&self) -> Option<CheckpointKind> {
+        match &self.0 {
+            CoreStateId::Finalized => Some(CheckpointKind::Finalized),
+            CoreStateId::Justified => Some(CheckpointKind::Justified),
+            _ => None,
+        }
+    }
+
     /// Return the `BeaconState` identified by `self`.
     pub fn state<T: BeaconChainTypes>(
         &self,
         chain: &BeaconChain<T>,
     ) -> Result<BeaconState<T::EthSpec>, warp::Rejection> {
+        // The states of the finalized and justified checkpoints may be cached.
+        if let Some(kind) = self.checkpoint_kind() {
+            return chain
+                .checkpoint_state(kind)
+                .map(|state| state.as_ref().clone())
+                .map_err(warp_utils::reject::beacon_chain_error);
+        }
+
         let (state_root, slot_opt) = match &self.0 {
             CoreStateId::Head => return Ok(chain.head_beacon_state_cloned()),
             CoreStateId::Slot(slot) => (self.root(chain)?, Some(*slot)),
@@ -79,7 +100,7 @@ impl StateId {
     /// Map a function across the `BeaconState` identified by `self`.
     ///
     /// This function will avoid instantiating/copying a new state when `self` points to the head
-    /// of the chain.
+    /// of the chain, or to a cached finalized or justified checkpoint state.
     pub fn map_state<T: BeaconChainTypes, F, U>(
         &self,
         chain: &BeaconChain<T>,
@@ -88,6 +109,13 @@ impl StateId {
     where
         F: Fn(&BeaconState<T::EthSpec>) -> Result<U, warp::Rejection>,
     {
+        if let Some(kind) = self.checkpoint_kind() {
+            let state = chain
+                .checkpoint_state(kind)
+                .map_err(warp_utils::reject::beacon_chain_error)?;
+            return func(&state);
+        }
+
         match &self.0 {
             CoreStateId::Head => chain
                 .with_head(|snapshot| Ok(func(&snapshot.beacon_state)))
@@ -6,14 +6,13 @@ use beacon_chain::sync_committee_verification::{
 };
 use beacon_chain::{
     validator_monitor::timestamp_now, BeaconChain, BeaconChainError, BeaconChainTypes,
-    StateSkipConfig, MAXIMUM_GOSSIP_CLOCK_DISPARITY,
+    MAXIMUM_GOSSIP_CLOCK_DISPARITY,
 };
 use eth2::types::{self as api_types};
 use lighthouse_network::PubsubMessage;
 use network::NetworkMessage;
 use slog::{error, warn, Logger};
 use slot_clock::SlotClock;
-use std::cmp::max;
 use std::collections::HashMap;
 use tokio::sync::mpsc::UnboundedSender;
 use types::{
@@ -90,19 +89,9 @@ fn duties_from_state_load<T: BeaconChainTypes>(
         // Empty response if the epoch is pre-Altair.
         Ok(vec![])
     } else if sync_committee_period <= max_sync_committee_period {
-        // Load the state at the start of the *previous* sync committee period.
-        // This is sufficient for historical duties, and efficient in the case where the head
-        // is lagging the current epoch and we need duties for the next period (because we only
-        // have to transition the head to start of the current period).
-        //
-        // We also need to ensure that the load slot is after the Altair fork.
-        let load_slot = max(
-            chain.spec.epochs_per_sync_committee_period * sync_committee_period.saturating_sub(1),
-            altair_fork_epoch,
-        )
-        .start_slot(T::EthSpec::slots_per_epoch());
-
-        let state = chain.state_at_slot(load_slot, StateSkipConfig::WithoutStateRoots)?;
+        // Load the state at the start of the *previous* sync committee period, or the Altair fork
+        // if it is later.
+        let state = chain.state_for_sync_committee_period(sync_committee_period)?;
 
         state
             .get_sync_committee_duties(request_epoch, request_indices, &chain.spec)
@@ -1,7 +1,9 @@
 use crate::state_id::StateId;
 use beacon_chain::{BeaconChain, BeaconChainTypes};
 use eth2::{
-    lighthouse::{GlobalValidatorInclusionData, ValidatorInclusionData},
+    lighthouse::{
+        GlobalValidatorInclusionData, SyncCommitteeInclusionData, ValidatorInclusionData,
+    },
     types::ValidatorId,
 };
 use state_processing::per_epoch_processing::{
@@ -95,6 +97,17 @@ pub fn validator_inclusion_data<T: BeaconChainTypes>(
 
     let summary = get_epoch_processing_summary(&mut state, &chain.spec)?;
 
+    let sync_committee_inclusion = chain
+        .validator_monitor
+        .read()
+        .get_sync_committee_performance(validator_index as u64, epoch)
+        // Validators outside of the sync committee aren't expected to sign.
+        .filter(|performance| performance.expected() > 0)
+        .map(|performance| SyncCommitteeInclusionData {
+            included: performance.included as u64,
+            expected: performance.expected() as u64,
+        });
+
     Ok(Some(ValidatorInclusionData {
         is_slashed: validator.slashed,
         is_withdrawable_in_current_epoch: validator.is_withdrawable_at(epoch),
@@ -112,5 +125,6 @@ pub fn validator_inclusion_data<T: BeaconChainTypes>(
         is_previous_epoch_head_attester: summary
             .is_previous_epoch_head_attester(validator_index)
             .map_err(convert_cache_error)?,
+        sync_committee_inclusion,
     }))
 }
@@ -1,5 +1,5 @@
 use beacon_chain::{
-    test_utils::{BeaconChainHarness, EphemeralHarnessType},
+    test_utils::{BeaconChainHarness, Builder as HarnessBuilder, EphemeralHarnessType},
     BeaconChain, BeaconChainTypes,
 };
 use eth2::{BeaconNodeHttpClient, Timeouts};
@@ -26,6 +26,11 @@ pub const UDP_PORT: u16 = 42;
 pub const SEQ_NUMBER: u64 = 0;
 pub const EXTERNAL_ADDR: &str = "/ip4/0.0.0.0/tcp/9000";
 
+/// A function which modifies the `BeaconChainHarness` builder of an `InteractiveTester`.
+pub type HarnessBuilderMutator<E> = Box<
+    dyn FnOnce(HarnessBuilder<EphemeralHarnessType<E>>) -> HarnessBuilder<EphemeralHarnessType<E>>,
+>;
+
 /// HTTP API tester that allows interaction with the underlying beacon chain harness.
 pub struct InteractiveTester<E: EthSpec> {
     pub harness: BeaconChainHarness<EphemeralHarnessType<E>>,
@@ -48,11 +53,23 @@ pub struct ApiServer<E: EthSpec, SFut: Future<Output = ()>> {
 
 impl<E: EthSpec> InteractiveTester<E> {
     pub async fn new(spec: Option<ChainSpec>, validator_count: usize) -> Self {
-        let harness = BeaconChainHarness::builder(E::default())
+        Self::new_with_mutator(spec, validator_count, None).await
+    }
+
+    /// As per `Self::new`, but the harness builder is modified by `mutator` before the harness is
+    /// built.
+    pub async fn new_with_mutator(
+        spec: Option<ChainSpec>,
+        validator_count: usize,
+        mutator: Option<HarnessBuilderMutator<E>>,
+    ) -> Self {
+        let mut harness_builder = BeaconChainHarness::builder(E::default())
             .spec_or_default(spec)
-            .deterministic_keypairs(validator_count)
-            .fresh_ephemeral_store()
-            .build();
+            .deterministic_keypairs(validator_count);
+        if let Some(mutator) = mutator {
+            harness_builder = mutator(harness_builder);
+        }
+        let harness = harness_builder.fresh_ephemeral_store().build();
 
         let ApiServer {
             server,
@@ -86,6 +103,24 @@ impl<E: EthSpec> InteractiveTester<E> {
 pub async fn create_api_server<T: BeaconChainTypes>(
     chain: Arc<BeaconChain<T>>,
     log: Logger,
+) -> ApiServer<T::EthSpec, impl Future<Output = ()>> {
+    let config = Config {
+        enabled: true,
+        listen_addr: IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)),
+        listen_port: 0,
+        allow_origin: None,
+        serve_legacy_spec: true,
+        tls_config: None,
+        allow_sync_stalled: false,
+        listeners: vec![],
+    };
+    create_api_server_with_config(chain, log, config).await
+}
+
+pub async fn create_api_server_with_config<T: BeaconChainTypes>(
+    chain: Arc<BeaconChain<T>>,
+    log: Logger,
+    config: Config,
 ) -> ApiServer<T::EthSpec, impl Future<Output = ()>> {
     let (network_tx, network_rx) = mpsc::unbounded_channel();
 
@@ -126,19 +161,12 @@ pub async fn create_api_server<T: BeaconChainTypes>(
     let eth1_service = eth1::Service::new(eth1::Config::default(), log.clone(), chain.spec.clone());
 
     let context = Arc::new(Context {
-        config: Config {
-            enabled: true,
-            listen_addr: IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)),
-            listen_port: 0,
-            allow_origin: None,
-            serve_legacy_spec: true,
-            tls_config: None,
-            allow_sync_stalled: false,
-        },
+        config,
         chain: Some(chain.clone()),
         network_tx: Some(network_tx),
         network_globals: Some(network_globals),
         eth1_service: Some(eth1_service),
+        effective_config: None,
         log,
     });
     let ctx = context.clone();
@@ -99,6 +99,90 @@ async fn sync_committee_duties_across_fork() {
     );
 }
 
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn sync_committee_duties_after_skipped_fork_epochs() {
+    let validator_count = E::sync_committee_size();
+    let fork_epoch = Epoch::new(8);
+    let spec = altair_spec(fork_epoch);
+    let tester = InteractiveTester::<E>::new(Some(spec.clone()), validator_count).await;
+    let harness = &tester.harness;
+    let client = &tester.client;
+
+    let all_validators = harness.get_all_validators();
+    let all_validators_u64 = all_validators.iter().map(|x| *x as u64).collect::<Vec<_>>();
+    let fork_period = fork_epoch.sync_committee_period(&spec).unwrap();
+
+    // The last block prior to the fork is followed by two entirely skipped epochs.
+    let fork_slot = fork_epoch.start_slot(E::slots_per_epoch());
+    let (genesis_state, genesis_state_root) = harness.get_current_state_and_root();
+    let (_, pre_fork_state) = harness
+        .add_attested_block_at_slot(
+            fork_slot - 1,
+            genesis_state,
+            genesis_state_root,
+            &all_validators,
+        )
+        .await
+        .unwrap();
+    let resume_slot = (fork_epoch + 2).start_slot(E::slots_per_epoch());
+    harness.set_current_slot(resume_slot);
+
+    // Whilst the head is prior to the fork, the committee is found by advancing the head.
+    let expected_committee = harness
+        .chain
+        .state_for_sync_committee_period(fork_period)
+        .unwrap()
+        .current_sync_committee()
+        .unwrap()
+        .clone();
+    assert_eq!(
+        *harness.chain.sync_committee_at_epoch(fork_epoch).unwrap(),
+        expected_committee
+    );
+    let sync_duties = client
+        .post_validator_duties_sync(fork_epoch, &all_validators_u64)
+        .await
+        .unwrap()
+        .data;
+    assert_eq!(sync_duties.len(), E::sync_committee_size());
+
+    // Once a block is applied after the skipped epochs, the state at the skipped fork slot is
+    // loaded from the database.
+    let pre_fork_state_root = pre_fork_state.canonical_root();
+    harness
+        .add_attested_block_at_slot(
+            resume_slot,
+            pre_fork_state,
+            pre_fork_state_root,
+            &all_validators,
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        harness.chain.head_snapshot().beacon_block.slot(),
+        resume_slot
+    );
+
+    let state = harness
+        .chain
+        .state_for_sync_committee_period(fork_period)
+        .unwrap();
+    assert_eq!(state.slot(), fork_slot);
+    assert_eq!(*state.current_sync_committee().unwrap(), expected_committee);
+    assert_eq!(
+        *harness.chain.sync_committee_at_epoch(fork_epoch).unwrap(),
+        expected_committee
+    );
+    assert_eq!(
+        client
+            .post_validator_duties_sync(fork_epoch, &all_validators_u64)
+            .await
+            .unwrap()
+            .data,
+        sync_duties
+    );
+}
+
 #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
 async fn attestations_across_fork_with_skip_slots() {
     let validator_count = E::sync_committee_size();
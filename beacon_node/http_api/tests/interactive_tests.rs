@@ -1,9 +1,23 @@
 //! Generic tests that make use of the (newer) `InteractiveApiTester`
 use crate::common::*;
-use beacon_chain::test_utils::{AttestationStrategy, BlockStrategy};
+use beacon_chain::{
+    test_utils::{AttestationStrategy, BlockStrategy},
+    ChainConfig, ProduceBlockVerification,
+};
 use eth2::types::DepositContractData;
+use eth2::{BeaconNodeHttpClient, Timeouts};
+use http_api::{Config, Listener};
+use lighthouse_network::PubsubMessage;
+use network::NetworkMessage;
+use sensitive_url::SensitiveUrl;
+use std::net::{IpAddr, Ipv4Addr};
+use std::time::Duration;
 use tree_hash::TreeHash;
-use types::{EthSpec, FullPayload, MainnetEthSpec, Slot};
+use types::{
+    Address, BlindedPayload, Epoch, EthSpec, FullPayload, MainnetEthSpec, Signature,
+    SignedBeaconBlock, SignedRoot, SignedValidatorRegistrationData, Slot,
+    ValidatorRegistrationData,
+};
 
 type E = MainnetEthSpec;
 
@@ -130,3 +144,216 @@ pub async fn fork_choice_before_proposal() {
     // D's parent is B.
     assert_eq!(block_d.parent_root(), block_root_b.into());
 }
+
+// Test that a signed blinded block is broadcast on the blinded block topic when the builder
+// withholds its payload and `broadcast_withheld_blinded_blocks` is enabled.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+pub async fn broadcast_withheld_blinded_block() {
+    let validator_count = 32;
+    let mut spec = E::default_spec();
+    spec.altair_fork_epoch = Some(Epoch::new(0));
+    spec.bellatrix_fork_epoch = Some(Epoch::new(0));
+
+    let chain_config = ChainConfig {
+        broadcast_withheld_blinded_blocks: true,
+        ..ChainConfig::default()
+    };
+    let mut tester = InteractiveTester::<E>::new_with_mutator(
+        Some(spec),
+        validator_count,
+        Some(Box::new(move |builder| {
+            builder
+                .mock_execution_layer_with_builder()
+                .chain_config(chain_config)
+        })),
+    )
+    .await;
+    let harness = &tester.harness;
+    let mock_execution_layer = harness.mock_execution_layer.as_ref().unwrap();
+    let mock_builder = mock_execution_layer.builder.as_ref().unwrap();
+    mock_execution_layer
+        .server
+        .execution_block_generator()
+        .move_to_terminal_block()
+        .unwrap();
+
+    // Finalize the chain so that the builder is considered healthy.
+    harness.advance_slot();
+    harness
+        .extend_chain(
+            E::slots_per_epoch() as usize * 4,
+            BlockStrategy::OnCanonicalHead,
+            AttestationStrategy::AllValidators,
+        )
+        .await;
+    assert!(harness.finalized_checkpoint().epoch > 0);
+
+    let head = harness.chain.head_snapshot();
+    let slot = head.beacon_block.slot() + 1;
+    harness.set_current_slot(slot);
+
+    // Produce a full block so that the mock execution engine has a payload for the builder to
+    // offer.
+    harness.make_block(head.beacon_state.clone(), slot).await;
+
+    let blinded_block = harness
+        .chain
+        .produce_block_on_state::<BlindedPayload<E>>(
+            head.beacon_state.clone(),
+            None,
+            slot,
+            Signature::empty(),
+            None,
+            ProduceBlockVerification::NoVerification,
+        )
+        .await
+        .unwrap()
+        .0;
+    assert_eq!(mock_builder.get_header_requests(), 1);
+    let signed_blinded_block = SignedBeaconBlock::from_block(blinded_block, Signature::empty());
+
+    mock_builder.fail_blinded_blocks(true);
+    tester
+        .client
+        .post_beacon_blinded_blocks(&signed_blinded_block)
+        .await
+        .unwrap_err();
+    assert_eq!(mock_builder.blinded_blocks_requests(), 1);
+
+    // The signed blinded block is published, rather than a full block.
+    match tester.network_rx.recv().await {
+        Some(NetworkMessage::Publish { messages }) => match messages.as_slice() {
+            [PubsubMessage::BlindedBeaconBlock(block)] => {
+                assert_eq!(**block, signed_blinded_block)
+            }
+            other => panic!("unexpected messages: {:?}", other),
+        },
+        other => panic!("unexpected network message: {:?}", other),
+    }
+}
+
+// Test that proposers are prepared using the new registrations even if forwarding them to the
+// builder fails.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+pub async fn register_validator_prepares_proposer_when_builder_fails() {
+    let validator_count = 32;
+    let mut spec = E::default_spec();
+    spec.altair_fork_epoch = Some(Epoch::new(0));
+    spec.bellatrix_fork_epoch = Some(Epoch::new(0));
+
+    let tester = InteractiveTester::<E>::new_with_mutator(
+        Some(spec.clone()),
+        validator_count,
+        Some(Box::new(|builder| {
+            builder.mock_execution_layer_with_builder()
+        })),
+    )
+    .await;
+    let harness = &tester.harness;
+    let mock_execution_layer = harness.mock_execution_layer.as_ref().unwrap();
+    let mock_builder = mock_execution_layer.builder.as_ref().unwrap();
+
+    let domain = spec.get_builder_domain();
+    let registrations = harness
+        .validator_keypairs
+        .iter()
+        .enumerate()
+        .map(|(val_index, keypair)| {
+            let message = ValidatorRegistrationData {
+                fee_recipient: Address::from_low_u64_be(val_index as u64 + 1),
+                gas_limit: 0,
+                timestamp: 0,
+                pubkey: keypair.pk.compress(),
+            };
+            let signature = keypair.sk.sign(message.signing_root(domain));
+            SignedValidatorRegistrationData { message, signature }
+        })
+        .collect::<Vec<_>>();
+
+    mock_builder.fail_validators(true);
+    tester
+        .client
+        .post_validator_register_validator(&registrations)
+        .await
+        .unwrap_err();
+    assert_eq!(mock_builder.registrations(), 0);
+
+    // The proposer of the next slot has been prepared regardless.
+    let head_root = harness.chain.head_beacon_block_root();
+    let next_slot = harness.chain.slot().unwrap() + 1;
+    assert!(mock_execution_layer
+        .el
+        .payload_attributes(next_slot, head_root)
+        .await
+        .is_some());
+}
+
+// Test that the API is served on both its TCP port and an additional Unix socket.
+#[cfg(unix)]
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn tcp_and_unix_socket_listeners() {
+    use std::io::{Read, Write};
+    use std::os::unix::net::UnixStream;
+
+    let harness = beacon_chain::test_utils::BeaconChainHarness::builder(E::default())
+        .default_spec()
+        .deterministic_keypairs(8)
+        .fresh_ephemeral_store()
+        .build();
+
+    let socket_dir = tempfile::tempdir().unwrap();
+    let socket_path = socket_dir.path().join("http.sock");
+    let config = Config {
+        enabled: true,
+        listen_addr: IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)),
+        listen_port: 0,
+        listeners: vec![Listener::Unix {
+            path: socket_path.clone(),
+            permissions: 0o600,
+        }],
+        ..Config::default()
+    };
+
+    let ApiServer {
+        server,
+        listening_socket,
+        shutdown_tx,
+        ..
+    } = create_api_server_with_config(harness.chain.clone(), harness.logger().clone(), config)
+        .await;
+    let server = tokio::spawn(server);
+
+    // TCP.
+    let client = BeaconNodeHttpClient::new(
+        SensitiveUrl::parse(&format!("http://{}", listening_socket)).unwrap(),
+        Timeouts::set_all(Duration::from_secs(1)),
+    );
+    let version = client.get_node_version().await.unwrap().data.version;
+    assert_eq!(version, lighthouse_version::version_with_platform());
+
+    // Unix socket.
+    let request_path = socket_path.clone();
+    let response = tokio::task::spawn_blocking(move || {
+        let mut stream = UnixStream::connect(request_path).unwrap();
+        stream
+            .write_all(
+                b"GET /eth/v1/node/version HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+            )
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    })
+    .await
+    .unwrap();
+    assert!(response.starts_with("HTTP/1.1 200 OK"), "{}", response);
+    assert!(response.contains(&version), "{}", response);
+
+    // Shutting down stops every listener and removes the socket.
+    shutdown_tx.send(()).unwrap();
+    tokio::time::timeout(Duration::from_secs(5), server)
+        .await
+        .expect("server should shut down")
+        .unwrap();
+    assert!(!socket_path.exists());
+}
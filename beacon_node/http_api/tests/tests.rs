@@ -5,12 +5,14 @@ use beacon_chain::{
     BeaconChain, StateSkipConfig, WhenSlotSkipped, MAXIMUM_GOSSIP_CLOCK_DISPARITY,
 };
 use environment::null_logger;
+use eth2::lighthouse::RemoveBuilderRequest;
 use eth2::{
     mixin::{RequestAccept, ResponseForkName, ResponseOptional},
     reqwest::RequestBuilder,
     types::*,
     BeaconNodeHttpClient, Error, StatusCode, Timeouts,
 };
+use execution_layer::{BuilderConfig, BuilderInfo};
 use futures::stream::{Stream, StreamExt};
 use futures::FutureExt;
 use lighthouse_network::{Enr, EnrExt, PeerId};
@@ -26,7 +28,7 @@ use tree_hash::TreeHash;
 use types::application_domain::ApplicationDomain;
 use types::{
     AggregateSignature, BeaconState, BitList, Domain, EthSpec, Hash256, Keypair, MainnetEthSpec,
-    RelativeEpoch, SelectionProof, SignedRoot, Slot,
+    RelativeEpoch, SelectionProof, SignedRoot, Slot, Uint256,
 };
 
 type E = MainnetEthSpec;
@@ -433,6 +435,14 @@ impl ApiTester {
         self
     }
 
+    pub async fn test_beacon_deposit_snapshot(self) -> Self {
+        // The eth1 service has no blocks, so no snapshot can be made.
+        let result = self.client.get_deposit_snapshot().await.unwrap();
+        assert_eq!(result, None);
+
+        self
+    }
+
     pub async fn test_beacon_states_root(self) -> Self {
         for state_id in self.interesting_state_ids() {
             let result = self
@@ -980,6 +990,38 @@ impl ApiTester {
         self
     }
 
+    pub async fn test_post_beacon_blocks_delivery_attribution(mut self) -> Self {
+        let next_block = self.next_block.clone();
+        let block_root = next_block.canonical_root();
+
+        self.client.post_beacon_blocks(&next_block).await.unwrap();
+        assert!(self.network_rx.recv().await.is_some());
+
+        let timings = self.chain.block_timings(block_root).unwrap();
+        let observed = timings.timestamps.observed.unwrap();
+        assert!(timings.timestamps.imported.is_some());
+        assert_eq!(
+            timings.peer_info.transport,
+            Some(BlockDeliveryTransport::Api)
+        );
+        assert_eq!(timings.peer_info.duplicate_deliveries, 0);
+
+        // Publishing the block again is rejected, but still counted as a duplicate delivery.
+        assert!(self.client.post_beacon_blocks(&next_block).await.is_err());
+        assert!(self.network_rx.recv().await.is_some());
+
+        let timings = self.chain.block_timings(block_root).unwrap();
+        assert_eq!(timings.timestamps.observed, Some(observed));
+        assert_eq!(
+            timings.peer_info.transport,
+            Some(BlockDeliveryTransport::Api)
+        );
+        assert_eq!(timings.peer_info.duplicate_deliveries, 1);
+        assert_eq!(timings.peer_info.distinct_peers(), 0);
+
+        self
+    }
+
     pub async fn test_beacon_blocks(self) -> Self {
         for block_id in self.interesting_block_ids() {
             let expected = self.get_block(block_id).await;
@@ -1350,6 +1392,7 @@ impl ApiTester {
 
         let expected = SyncingData {
             is_syncing: false,
+            is_optimistic: Some(false),
             head_slot,
             sync_distance,
         };
@@ -2307,7 +2350,7 @@ impl ApiTester {
                 .execution_layer
                 .as_ref()
                 .unwrap()
-                .get_suggested_fee_recipient(val_index as u64)
+                .get_suggested_fee_recipient(val_index as u64, None)
                 .await;
             assert_eq!(actual, fee_recipient);
         }
@@ -2315,6 +2358,79 @@ impl ApiTester {
         self
     }
 
+    pub async fn test_post_validator_register_validator_invalid_signatures(self) -> Self {
+        let fork = self.chain.head_snapshot().beacon_state.fork();
+        let builder_domain = self.chain.spec.get_domain(
+            Epoch::new(0),
+            Domain::ApplicationMask(ApplicationDomain::Builder),
+            &fork,
+            Hash256::zero(),
+        );
+
+        // Sign every other registration with the wrong domain.
+        let registrations = self
+            .validator_keypairs()
+            .iter()
+            .enumerate()
+            .map(|(val_index, keypair)| {
+                let data = ValidatorRegistrationData {
+                    fee_recipient: Address::from_low_u64_be(val_index as u64 + 1),
+                    gas_limit: 0,
+                    timestamp: 0,
+                    pubkey: keypair.pk.compress(),
+                };
+                let domain = if val_index % 2 == 0 {
+                    builder_domain
+                } else {
+                    self.chain.spec.get_deposit_domain()
+                };
+                let signature = keypair.sk.sign(data.signing_root(domain));
+                SignedValidatorRegistrationData {
+                    message: data,
+                    signature,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let err = self
+            .client
+            .post_validator_register_validator(&registrations)
+            .await
+            .unwrap_err();
+
+        match err {
+            Error::ServerIndexedMessage(IndexedErrorMessage {
+                code,
+                message: _,
+                failures,
+            }) => {
+                assert_eq!(code, 400);
+                assert_eq!(
+                    failures
+                        .iter()
+                        .map(|failure| failure.index)
+                        .collect::<Vec<_>>(),
+                    (0..registrations.len() as u64)
+                        .filter(|index| index % 2 == 1)
+                        .collect::<Vec<_>>()
+                );
+            }
+            _ => panic!("query did not fail correctly"),
+        }
+
+        // The valid registrations are still stored.
+        for val_index in 0..registrations.len() as u64 {
+            let expected =
+                Some(Address::from_low_u64_be(val_index + 1)).filter(|_| val_index % 2 == 0);
+            assert_eq!(
+                self.chain.registered_fee_recipient(val_index).unwrap(),
+                expected
+            );
+        }
+
+        self
+    }
+
     #[cfg(target_os = "linux")]
     pub async fn test_get_lighthouse_health(self) -> Self {
         self.client.get_lighthouse_health().await.unwrap();
@@ -2329,6 +2445,21 @@ impl ApiTester {
         self
     }
 
+    pub async fn test_get_lighthouse_health_execution_engines(self) -> Self {
+        let engines = self
+            .client
+            .get_lighthouse_health_execution_engines()
+            .await
+            .unwrap()
+            .data;
+
+        assert_eq!(engines.len(), 1);
+        assert_eq!(engines[0].index, 0);
+        assert!(engines[0].active);
+
+        self
+    }
+
     pub async fn test_get_lighthouse_syncing(self) -> Self {
         self.client.get_lighthouse_syncing().await.unwrap();
 
@@ -2367,6 +2498,22 @@ impl ApiTester {
         self
     }
 
+    pub async fn test_get_lighthouse_eth1_cache_status(self) -> Self {
+        let status = self
+            .client
+            .get_lighthouse_eth1_cache_status()
+            .await
+            .unwrap()
+            .data;
+
+        assert_eq!(status, self.chain.eth1_cache_status().unwrap());
+        assert_eq!(status.latest_cached_block_number, None);
+        assert_eq!(status.deposit_count, 0);
+        assert_eq!(status.blocks_behind_follow_head, None);
+
+        self
+    }
+
     pub async fn test_get_lighthouse_eth1_block_cache(self) -> Self {
         let blocks = self.client.get_lighthouse_eth1_block_cache().await.unwrap();
 
@@ -2425,6 +2572,27 @@ impl ApiTester {
         self
     }
 
+    pub async fn test_get_lighthouse_analysis_exit_queue(self) -> Self {
+        let summary = self
+            .client
+            .get_lighthouse_analysis_exit_queue()
+            .await
+            .unwrap()
+            .data;
+
+        let head = self.chain.head_snapshot();
+        assert_eq!(summary, self.chain.exit_queue_summary().unwrap());
+        assert_eq!(summary.epoch, head.beacon_state.current_epoch());
+        assert_eq!(summary.head_block_root, head.beacon_block_root);
+        assert_eq!(summary.exiting_validators, 0);
+        assert_eq!(
+            summary.withdrawal_credential_prefixes.values().sum::<u64>(),
+            head.beacon_state.validators().len() as u64
+        );
+
+        self
+    }
+
     pub async fn test_post_lighthouse_database_reconstruct(self) -> Self {
         let response = self
             .client
@@ -2499,6 +2667,76 @@ impl ApiTester {
         self
     }
 
+    pub async fn test_post_and_delete_lighthouse_builders(self) -> Self {
+        // There are no client methods for these endpoints, since the builder types are defined
+        // downstream of `eth2`. Neither endpoint requires authentication.
+        let url = format!("{}lighthouse/builders", self.client.as_ref());
+        let http_client = eth2::reqwest::Client::new();
+        let builder_url = SensitiveUrl::parse("http://127.0.0.1:18550").unwrap();
+        assert!(self.chain.list_builders().is_empty());
+
+        let config = BuilderConfig {
+            min_bid: Uint256::from(1_u64),
+            ..BuilderConfig::new(builder_url.clone())
+        };
+        let response = http_client.post(&url).json(&config).send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let builders = response
+            .json::<GenericResponse<Vec<BuilderInfo>>>()
+            .await
+            .unwrap()
+            .data;
+        assert_eq!(builders, self.chain.list_builders());
+        assert_eq!(builders.len(), 1);
+        assert_eq!(builders[0].url, builder_url.redacted);
+        assert_eq!(builders[0].min_bid, Uint256::from(1_u64));
+
+        // A builder which is already connected is rejected.
+        let response = http_client.post(&url).json(&config).send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        // A builder with an unsupported URL scheme is rejected.
+        let invalid_config =
+            BuilderConfig::new(SensitiveUrl::parse("ws://127.0.0.1:18551").unwrap());
+        let response = http_client
+            .post(&url)
+            .json(&invalid_config)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(self.chain.list_builders().len(), 1);
+
+        let request = RemoveBuilderRequest {
+            url: builder_url.clone(),
+        };
+        let response = http_client
+            .delete(&url)
+            .json(&request)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let builders = response
+            .json::<GenericResponse<Vec<BuilderInfo>>>()
+            .await
+            .unwrap()
+            .data;
+        assert!(builders.is_empty());
+        assert!(self.chain.list_builders().is_empty());
+
+        // A builder which isn't connected can't be removed.
+        let response = http_client
+            .delete(&url)
+            .json(&request)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        self
+    }
+
     pub async fn test_get_events(self) -> Self {
         // Subscribe to all events
         let topics = vec![
@@ -2514,6 +2752,32 @@ impl ApiTester {
             .await
             .unwrap();
 
+        // The latest head and finalized checkpoint are replayed upon subscription.
+        let head = self.chain.canonical_head.cached_head();
+        let replayed_events =
+            poll_events(&mut events_future, 2, Duration::from_millis(10000)).await;
+        assert_eq!(replayed_events.len(), 2);
+        let head_state = &head.snapshot.beacon_state;
+        let current_duty_dependent_root = head_state
+            .proposer_shuffling_decision_root(head.head_block_root())
+            .unwrap();
+        let previous_duty_dependent_root = head_state
+            .attester_shuffling_decision_root(head.head_block_root(), RelativeEpoch::Current)
+            .unwrap();
+        assert!(replayed_events.iter().any(|event| matches!(
+            event,
+            EventKind::Head(replayed)
+                if replayed.block == head.head_block_root()
+                    && replayed.current_duty_dependent_root == current_duty_dependent_root
+                    && replayed.previous_duty_dependent_root == previous_duty_dependent_root
+        )));
+        assert!(replayed_events.iter().any(|event| matches!(
+            event,
+            EventKind::FinalizedCheckpoint(replayed)
+                if replayed.epoch == head.finalized_checkpoint().epoch
+                    && replayed.block == head.finalized_checkpoint().root
+        )));
+
         let expected_attestation_len = self.attestations.len();
 
         self.client
@@ -2575,6 +2839,9 @@ impl ApiTester {
                 .unwrap()
                 .unwrap(),
             epoch_transition: true,
+            head_weight: None,
+            competitor_weight: None,
+            proposer_boost: None,
         });
 
         let finalized_block_root = self
@@ -2600,7 +2867,9 @@ impl ApiTester {
             .await
             .unwrap();
 
-        let block_events = poll_events(&mut events_future, 3, Duration::from_millis(10000)).await;
+        let block_events = without_head_weights(
+            poll_events(&mut events_future, 3, Duration::from_millis(10000)).await,
+        );
         assert_eq!(
             block_events.as_slice(),
             &[expected_block, expected_head, expected_finalized]
@@ -2681,6 +2950,14 @@ impl ApiTester {
             .await
             .unwrap();
 
+        // The genesis head is replayed upon subscription.
+        let replayed_events =
+            poll_events(&mut events_future, 1, Duration::from_millis(10000)).await;
+        assert!(matches!(
+            replayed_events.as_slice(),
+            [EventKind::Head(replayed)] if replayed.block == self.chain.genesis_block_root
+        ));
+
         let block_root = self.next_block.canonical_root();
         let next_slot = self.next_block.slot();
 
@@ -2696,6 +2973,9 @@ impl ApiTester {
             current_duty_dependent_root: self.chain.genesis_block_root,
             previous_duty_dependent_root: self.chain.genesis_block_root,
             epoch_transition: false,
+            head_weight: None,
+            competitor_weight: None,
+            proposer_boost: None,
         });
 
         self.client
@@ -2703,7 +2983,9 @@ impl ApiTester {
             .await
             .unwrap();
 
-        let block_events = poll_events(&mut events_future, 2, Duration::from_millis(10000)).await;
+        let block_events = without_head_weights(
+            poll_events(&mut events_future, 2, Duration::from_millis(10000)).await,
+        );
         assert_eq!(block_events.as_slice(), &[expected_block, expected_head]);
 
         self
@@ -2734,6 +3016,25 @@ async fn poll_events<S: Stream<Item = Result<EventKind<T>, eth2::Error>> + Unpin
     }
 }
 
+/// Clears the fork choice weights of head events, which depend on the timing of attestations,
+/// after checking that they are populated.
+fn without_head_weights<T: EthSpec>(events: Vec<EventKind<T>>) -> Vec<EventKind<T>> {
+    events
+        .into_iter()
+        .map(|event| match event {
+            EventKind::Head(mut head) => {
+                assert!(head.head_weight.is_some());
+                assert!(head.proposer_boost.is_some());
+                head.head_weight = None;
+                head.competitor_weight = None;
+                head.proposer_boost = None;
+                EventKind::Head(head)
+            }
+            event => event,
+        })
+        .collect()
+}
+
 #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
 async fn get_events() {
     ApiTester::new().await.test_get_events().await;
@@ -2763,6 +3064,8 @@ async fn beacon_get() {
         .await
         .test_beacon_genesis()
         .await
+        .test_beacon_deposit_snapshot()
+        .await
         .test_beacon_states_root()
         .await
         .test_beacon_states_fork()
@@ -2812,6 +3115,14 @@ async fn post_beacon_blocks_invalid() {
         .await;
 }
 
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn post_beacon_blocks_delivery_attribution() {
+    ApiTester::new()
+        .await
+        .test_post_beacon_blocks_delivery_attribution()
+        .await;
+}
+
 #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
 async fn beacon_pools_post_attestations_valid() {
     ApiTester::new()
@@ -3074,12 +3385,22 @@ async fn post_validator_register_validator() {
         .await;
 }
 
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn post_validator_register_validator_invalid_signatures() {
+    ApiTester::new()
+        .await
+        .test_post_validator_register_validator_invalid_signatures()
+        .await;
+}
+
 #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
 async fn lighthouse_endpoints() {
     ApiTester::new()
         .await
         .test_get_lighthouse_health()
         .await
+        .test_get_lighthouse_health_execution_engines()
+        .await
         .test_get_lighthouse_syncing()
         .await
         .test_get_lighthouse_proto_array()
@@ -3090,6 +3411,8 @@ async fn lighthouse_endpoints() {
         .await
         .test_get_lighthouse_eth1_syncing()
         .await
+        .test_get_lighthouse_eth1_cache_status()
+        .await
         .test_get_lighthouse_eth1_block_cache()
         .await
         .test_get_lighthouse_eth1_deposit_cache()
@@ -3100,8 +3423,12 @@ async fn lighthouse_endpoints() {
         .await
         .test_get_lighthouse_database_info()
         .await
+        .test_get_lighthouse_analysis_exit_queue()
+        .await
         .test_post_lighthouse_database_reconstruct()
         .await
         .test_post_lighthouse_liveness()
+        .await
+        .test_post_and_delete_lighthouse_builders()
         .await;
 }
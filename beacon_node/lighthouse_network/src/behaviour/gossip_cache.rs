@@ -150,7 +150,7 @@ impl GossipCache {
     // Insert a message to be sent later.
     pub fn insert(&mut self, topic: GossipTopic, data: Vec<u8>) {
         let expire_timeout = match topic.kind() {
-            GossipKind::BeaconBlock => self.beacon_block,
+            GossipKind::BeaconBlock | GossipKind::BlindedBeaconBlock => self.beacon_block,
             GossipKind::BeaconAggregateAndProof => self.aggregates,
             GossipKind::Attestation(_) => self.attestation,
             GossipKind::VoluntaryExit => self.voluntary_exit,
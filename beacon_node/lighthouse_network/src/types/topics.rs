@@ -11,6 +11,7 @@ use crate::Subnet;
 pub const TOPIC_PREFIX: &str = "eth2";
 pub const SSZ_SNAPPY_ENCODING_POSTFIX: &str = "ssz_snappy";
 pub const BEACON_BLOCK_TOPIC: &str = "beacon_block";
+pub const BLINDED_BEACON_BLOCK_TOPIC: &str = "blinded_beacon_block";
 pub const BEACON_AGGREGATE_AND_PROOF_TOPIC: &str = "beacon_aggregate_and_proof";
 pub const BEACON_ATTESTATION_PREFIX: &str = "beacon_attestation_";
 pub const VOLUNTARY_EXIT_TOPIC: &str = "voluntary_exit";
@@ -47,6 +48,10 @@ pub struct GossipTopic {
 pub enum GossipKind {
     /// Topic for publishing beacon blocks.
     BeaconBlock,
+    /// Topic for publishing signed blinded beacon blocks whose payload was withheld by the builder.
+    ///
+    /// This is not a core topic, it is only published to peers that subscribe to it.
+    BlindedBeaconBlock,
     /// Topic for publishing aggregate attestations and proofs.
     BeaconAggregateAndProof,
     /// Topic for publishing raw attestations on a particular subnet.
@@ -131,6 +136,7 @@ impl GossipTopic {
             };
             let kind = match topic_parts[3] {
                 BEACON_BLOCK_TOPIC => GossipKind::BeaconBlock,
+                BLINDED_BEACON_BLOCK_TOPIC => GossipKind::BlindedBeaconBlock,
                 BEACON_AGGREGATE_AND_PROOF_TOPIC => GossipKind::BeaconAggregateAndProof,
                 SIGNED_CONTRIBUTION_AND_PROOF_TOPIC => GossipKind::SignedContributionAndProof,
                 VOLUNTARY_EXIT_TOPIC => GossipKind::VoluntaryExit,
@@ -178,6 +184,7 @@ impl From<GossipTopic> for String {
 
         let kind = match topic.kind {
             GossipKind::BeaconBlock => BEACON_BLOCK_TOPIC.into(),
+            GossipKind::BlindedBeaconBlock => BLINDED_BEACON_BLOCK_TOPIC.into(),
             GossipKind::BeaconAggregateAndProof => BEACON_AGGREGATE_AND_PROOF_TOPIC.into(),
             GossipKind::VoluntaryExit => VOLUNTARY_EXIT_TOPIC.into(),
             GossipKind::ProposerSlashing => PROPOSER_SLASHING_TOPIC.into(),
@@ -206,6 +213,7 @@ impl std::fmt::Display for GossipTopic {
 
         let kind = match self.kind {
             GossipKind::BeaconBlock => BEACON_BLOCK_TOPIC.into(),
+            GossipKind::BlindedBeaconBlock => BLINDED_BEACON_BLOCK_TOPIC.into(),
             GossipKind::BeaconAggregateAndProof => BEACON_AGGREGATE_AND_PROOF_TOPIC.into(),
             GossipKind::VoluntaryExit => VOLUNTARY_EXIT_TOPIC.into(),
             GossipKind::ProposerSlashing => PROPOSER_SLASHING_TOPIC.into(),
@@ -280,6 +288,7 @@ mod tests {
         for encoding in [GossipEncoding::SSZSnappy].iter() {
             for kind in [
                 BeaconBlock,
+                BlindedBeaconBlock,
                 BeaconAggregateAndProof,
                 SignedContributionAndProof,
                 Attestation(SubnetId::new(42)),
@@ -373,6 +382,7 @@ mod tests {
     #[test]
     fn test_as_str_ref() {
         assert_eq!("beacon_block", BeaconBlock.as_ref());
+        assert_eq!("blinded_beacon_block", BlindedBeaconBlock.as_ref());
         assert_eq!(
             "beacon_aggregate_and_proof",
             BeaconAggregateAndProof.as_ref()
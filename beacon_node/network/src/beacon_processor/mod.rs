@@ -41,6 +41,7 @@
 use crate::sync::manager::BlockProcessType;
 use crate::{metrics, service::NetworkMessage, sync::SyncMessage};
 use beacon_chain::parking_lot::Mutex;
+use beacon_chain::verification_pressure::VerificationPressure;
 use beacon_chain::{BeaconChain, BeaconChainTypes, GossipVerifiedBlock};
 use derivative::Derivative;
 use futures::stream::{Stream, StreamExt};
@@ -78,6 +79,7 @@ mod worker;
 use crate::beacon_processor::work_reprocessing_queue::QueuedGossipBlock;
 pub use worker::{
     ChainSegmentProcessId, FailureMode, GossipAggregatePackage, GossipAttestationPackage,
+    GossipOperationPackage,
 };
 
 /// The maximum size of the channel for work events to the `BeaconProcessor`.
@@ -106,6 +108,10 @@ const MAX_UNAGGREGATED_ATTESTATION_REPROCESS_QUEUE_LEN: usize = 8_192;
 /// start dropping them.
 const MAX_AGGREGATED_ATTESTATION_QUEUE_LEN: usize = 4_096;
 
+/// Whilst attestation verification is under pressure, aggregates from a peer are dropped rather
+/// than queued if this many aggregates from the same peer are already queued.
+const MAX_QUEUED_AGGREGATES_PER_PEER_UNDER_PRESSURE: usize = 64;
+
 /// The maximum number of queued `SignedAggregateAndProof` objects that will be stored before we
 /// start dropping them.
 const MAX_AGGREGATED_ATTESTATION_REPROCESS_QUEUE_LEN: usize = 1_024;
@@ -178,6 +184,10 @@ const WORKER_TASK_NAME: &str = "beacon_processor_worker";
 const MAX_GOSSIP_ATTESTATION_BATCH_SIZE: usize = 64;
 const MAX_GOSSIP_AGGREGATE_BATCH_SIZE: usize = 64;
 
+/// The maximum number of queued exits or slashings of a single kind that will be verified in one
+/// batch.
+const MAX_GOSSIP_OPERATION_BATCH_SIZE: usize = 64;
+
 /// Unique IDs used for metrics and testing.
 pub const WORKER_FREED: &str = "worker_freed";
 pub const NOTHING_TO_DO: &str = "nothing_to_do";
@@ -188,8 +198,11 @@ pub const GOSSIP_AGGREGATE_BATCH: &str = "gossip_aggregate_batch";
 pub const GOSSIP_BLOCK: &str = "gossip_block";
 pub const DELAYED_IMPORT_BLOCK: &str = "delayed_import_block";
 pub const GOSSIP_VOLUNTARY_EXIT: &str = "gossip_voluntary_exit";
+pub const GOSSIP_VOLUNTARY_EXIT_BATCH: &str = "gossip_voluntary_exit_batch";
 pub const GOSSIP_PROPOSER_SLASHING: &str = "gossip_proposer_slashing";
+pub const GOSSIP_PROPOSER_SLASHING_BATCH: &str = "gossip_proposer_slashing_batch";
 pub const GOSSIP_ATTESTER_SLASHING: &str = "gossip_attester_slashing";
+pub const GOSSIP_ATTESTER_SLASHING_BATCH: &str = "gossip_attester_slashing_batch";
 pub const GOSSIP_SYNC_SIGNATURE: &str = "gossip_sync_signature";
 pub const GOSSIP_SYNC_CONTRIBUTION: &str = "gossip_sync_contribution";
 pub const RPC_BLOCK: &str = "rpc_block";
@@ -282,6 +295,35 @@ impl<T> LifoQueue<T> {
     pub fn len(&self) -> usize {
         self.queue.len()
     }
+
+    /// Iterate over the items in the queue, from the front to the back.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.queue.iter()
+    }
+}
+
+/// Returns `true` if an aggregate from `peer_id` should be dropped rather than queued.
+///
+/// Aggregates are only shed whilst attestation verification is under pressure, and only from peers
+/// which already have `MAX_QUEUED_AGGREGATES_PER_PEER_UNDER_PRESSURE` aggregates queued, so that a
+/// single peer can't crowd out the aggregates of others.
+fn should_shed_aggregate<T: BeaconChainTypes>(
+    pressure: VerificationPressure,
+    aggregate_queue: &LifoQueue<Work<T>>,
+    peer_id: &PeerId,
+) -> bool {
+    pressure.is_high
+        && aggregate_queue
+            .iter()
+            .filter(|work| match work {
+                Work::GossipAggregate {
+                    peer_id: queued_peer_id,
+                    ..
+                } => queued_peer_id == peer_id,
+                _ => false,
+            })
+            .count()
+            >= MAX_QUEUED_AGGREGATES_PER_PEER_UNDER_PRESSURE
 }
 
 /// A handle that sends a message on the provided channel to a receiver when it gets dropped.
@@ -683,16 +725,25 @@ pub enum Work<T: BeaconChainTypes> {
         peer_id: PeerId,
         voluntary_exit: Box<SignedVoluntaryExit>,
     },
+    GossipVoluntaryExitBatch {
+        packages: Vec<GossipOperationPackage<SignedVoluntaryExit>>,
+    },
     GossipProposerSlashing {
         message_id: MessageId,
         peer_id: PeerId,
         proposer_slashing: Box<ProposerSlashing>,
     },
+    GossipProposerSlashingBatch {
+        packages: Vec<GossipOperationPackage<ProposerSlashing>>,
+    },
     GossipAttesterSlashing {
         message_id: MessageId,
         peer_id: PeerId,
         attester_slashing: Box<AttesterSlashing<T::EthSpec>>,
     },
+    GossipAttesterSlashingBatch {
+        packages: Vec<GossipOperationPackage<AttesterSlashing<T::EthSpec>>>,
+    },
     GossipSyncSignature {
         message_id: MessageId,
         peer_id: PeerId,
@@ -743,8 +794,11 @@ impl<T: BeaconChainTypes> Work<T> {
             Work::GossipBlock { .. } => GOSSIP_BLOCK,
             Work::DelayedImportBlock { .. } => DELAYED_IMPORT_BLOCK,
             Work::GossipVoluntaryExit { .. } => GOSSIP_VOLUNTARY_EXIT,
+            Work::GossipVoluntaryExitBatch { .. } => GOSSIP_VOLUNTARY_EXIT_BATCH,
             Work::GossipProposerSlashing { .. } => GOSSIP_PROPOSER_SLASHING,
+            Work::GossipProposerSlashingBatch { .. } => GOSSIP_PROPOSER_SLASHING_BATCH,
             Work::GossipAttesterSlashing { .. } => GOSSIP_ATTESTER_SLASHING,
+            Work::GossipAttesterSlashingBatch { .. } => GOSSIP_ATTESTER_SLASHING_BATCH,
             Work::GossipSyncSignature { .. } => GOSSIP_SYNC_SIGNATURE,
             Work::GossipSyncContribution { .. } => GOSSIP_SYNC_CONTRIBUTION,
             Work::RpcBlock { .. } => RPC_BLOCK,
@@ -758,6 +812,28 @@ impl<T: BeaconChainTypes> Work<T> {
     }
 }
 
+/// Pops the next item of work from a queue of gossip exits or slashings of a single kind.
+///
+/// A lone operation is returned as-is, whilst two or more (up to
+/// `MAX_GOSSIP_OPERATION_BATCH_SIZE`) are unpacked with `unpack` and combined into a batch with
+/// `into_batch`.
+fn pop_gossip_operations<T: BeaconChainTypes, O>(
+    queue: &mut FifoQueue<Work<T>>,
+    unpack: fn(Work<T>) -> Option<GossipOperationPackage<O>>,
+    into_batch: fn(Vec<GossipOperationPackage<O>>) -> Work<T>,
+) -> Option<Work<T>> {
+    if queue.len() < 2 {
+        return queue.pop();
+    }
+
+    let batch_size = cmp::min(queue.len(), MAX_GOSSIP_OPERATION_BATCH_SIZE);
+    let packages = (0..batch_size)
+        .filter_map(|_| queue.pop())
+        .filter_map(unpack)
+        .collect();
+    Some(into_batch(packages))
+}
+
 /// Unifies all the messages processed by the `BeaconProcessor`.
 enum InboundEvent<T: BeaconChainTypes> {
     /// A worker has completed a task and is free.
@@ -864,6 +940,7 @@ impl<T: BeaconChainTypes> BeaconProcessor<T> {
         // earlier ones, so we consider them more valuable.
         let mut aggregate_queue = LifoQueue::new(MAX_AGGREGATED_ATTESTATION_QUEUE_LEN);
         let mut aggregate_debounce = TimeLatch::default();
+        let mut aggregate_shed_debounce = TimeLatch::default();
         let mut attestation_queue = LifoQueue::new(MAX_UNAGGREGATED_ATTESTATION_QUEUE_LEN);
         let mut attestation_debounce = TimeLatch::default();
         let mut unknown_block_aggregate_queue =
@@ -1123,12 +1200,60 @@ impl<T: BeaconChainTypes> BeaconProcessor<T> {
                         //
                         // Check attester slashings before proposer slashings since they have the
                         // potential to slash multiple validators at once.
-                        } else if let Some(item) = gossip_attester_slashing_queue.pop() {
+                        //
+                        // Two or more operations of a kind are verified as a batch, so the
+                        // verification state is computed once for the whole batch.
+                        } else if let Some(item) = pop_gossip_operations(
+                            &mut gossip_attester_slashing_queue,
+                            |work| match work {
+                                Work::GossipAttesterSlashing {
+                                    message_id,
+                                    peer_id,
+                                    attester_slashing,
+                                } => Some(GossipOperationPackage::new(
+                                    message_id,
+                                    peer_id,
+                                    attester_slashing,
+                                )),
+                                _ => None,
+                            },
+                            |packages| Work::GossipAttesterSlashingBatch { packages },
+                        ) {
                             self.spawn_worker(item, toolbox);
-                        } else if let Some(item) = gossip_proposer_slashing_queue.pop() {
+                        } else if let Some(item) = pop_gossip_operations(
+                            &mut gossip_proposer_slashing_queue,
+                            |work| match work {
+                                Work::GossipProposerSlashing {
+                                    message_id,
+                                    peer_id,
+                                    proposer_slashing,
+                                } => Some(GossipOperationPackage::new(
+                                    message_id,
+                                    peer_id,
+                                    proposer_slashing,
+                                )),
+                                _ => None,
+                            },
+                            |packages| Work::GossipProposerSlashingBatch { packages },
+                        ) {
                             self.spawn_worker(item, toolbox);
                         // Check exits last since our validators don't get rewards from them.
-                        } else if let Some(item) = gossip_voluntary_exit_queue.pop() {
+                        } else if let Some(item) = pop_gossip_operations(
+                            &mut gossip_voluntary_exit_queue,
+                            |work| match work {
+                                Work::GossipVoluntaryExit {
+                                    message_id,
+                                    peer_id,
+                                    voluntary_exit,
+                                } => Some(GossipOperationPackage::new(
+                                    message_id,
+                                    peer_id,
+                                    voluntary_exit,
+                                )),
+                                _ => None,
+                            },
+                            |packages| Work::GossipVoluntaryExitBatch { packages },
+                        ) {
                             self.spawn_worker(item, toolbox);
                         // Handle backfill sync chain segments.
                         } else if let Some(item) = backfill_chain_segment.pop() {
@@ -1190,6 +1315,27 @@ impl<T: BeaconChainTypes> BeaconProcessor<T> {
                                     "Unsupported inbound event";
                                     "type" => "GossipAttestationBatch"
                             ),
+                            Work::GossipAggregate { ref peer_id, .. }
+                                if self.beacon_chain.upgrade().map_or(false, |chain| {
+                                    should_shed_aggregate(
+                                        chain.verification_pressure(),
+                                        &aggregate_queue,
+                                        peer_id,
+                                    )
+                                }) =>
+                            {
+                                metrics::inc_counter(
+                                    &metrics::BEACON_PROCESSOR_AGGREGATED_ATTESTATION_SHED_TOTAL,
+                                );
+                                if aggregate_shed_debounce.elapsed() {
+                                    warn!(
+                                        self.log,
+                                        "Shedding aggregates from over-represented peer";
+                                        "msg" => "attestation verification is under pressure",
+                                        "peer_id" => %peer_id,
+                                    );
+                                }
+                            }
                             Work::GossipAggregate { .. } => aggregate_queue.push(work),
                             // Aggregate batches are formed internally within the `BeaconProcessor`,
                             // they are not sent from external services.
@@ -1213,6 +1359,14 @@ impl<T: BeaconChainTypes> BeaconProcessor<T> {
                             Work::GossipAttesterSlashing { .. } => {
                                 gossip_attester_slashing_queue.push(work, work_id, &self.log)
                             }
+                            // Like aggregate batches, operation batches are formed internally.
+                            Work::GossipVoluntaryExitBatch { .. }
+                            | Work::GossipProposerSlashingBatch { .. }
+                            | Work::GossipAttesterSlashingBatch { .. } => crit!(
+                                    self.log,
+                                    "Unsupported inbound event";
+                                    "type" => work_id
+                            ),
                             Work::GossipSyncSignature { .. } => sync_message_queue.push(work),
                             Work::GossipSyncContribution { .. } => {
                                 sync_contribution_queue.push(work)
@@ -1476,6 +1630,8 @@ impl<T: BeaconChainTypes> BeaconProcessor<T> {
             } => task_spawner.spawn_blocking(move || {
                 worker.process_gossip_voluntary_exit(message_id, peer_id, *voluntary_exit)
             }),
+            Work::GossipVoluntaryExitBatch { packages } => task_spawner
+                .spawn_blocking(move || worker.process_gossip_voluntary_exit_batch(packages)),
             /*
              * Proposer slashings received on gossip.
              */
@@ -1486,6 +1642,8 @@ impl<T: BeaconChainTypes> BeaconProcessor<T> {
             } => task_spawner.spawn_blocking(move || {
                 worker.process_gossip_proposer_slashing(message_id, peer_id, *proposer_slashing)
             }),
+            Work::GossipProposerSlashingBatch { packages } => task_spawner
+                .spawn_blocking(move || worker.process_gossip_proposer_slashing_batch(packages)),
             /*
              * Attester slashings received on gossip.
              */
@@ -1496,6 +1654,8 @@ impl<T: BeaconChainTypes> BeaconProcessor<T> {
             } => task_spawner.spawn_blocking(move || {
                 worker.process_gossip_attester_slashing(message_id, peer_id, *attester_slashing)
             }),
+            Work::GossipAttesterSlashingBatch { packages } => task_spawner
+                .spawn_blocking(move || worker.process_gossip_attester_slashing_batch(packages)),
             /*
              * Sync committee message verification.
              */
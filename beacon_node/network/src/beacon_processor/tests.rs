@@ -9,13 +9,14 @@ use crate::{service::NetworkMessage, sync::SyncMessage};
 use beacon_chain::test_utils::{
     AttestationStrategy, BeaconChainHarness, BlockStrategy, EphemeralHarnessType,
 };
-use beacon_chain::{BeaconChain, MAXIMUM_GOSSIP_CLOCK_DISPARITY};
+use beacon_chain::{BeaconChain, BlockDeliveryTransport, MAXIMUM_GOSSIP_CLOCK_DISPARITY};
 use lighthouse_network::{
     discv5::enr::{CombinedKey, EnrBuilder},
     rpc::methods::{MetaData, MetaDataV2},
     types::{EnrAttestationBitfield, EnrSyncCommitteeBitfield},
     MessageId, NetworkGlobals, PeerId,
 };
+use slog::Logger;
 use slot_clock::SlotClock;
 use std::cmp;
 use std::iter::Iterator;
@@ -57,6 +58,7 @@ struct TestRig {
     _network_rx: mpsc::UnboundedReceiver<NetworkMessage<E>>,
     _sync_rx: mpsc::UnboundedReceiver<SyncMessage<E>>,
     duplicate_cache: DuplicateCache,
+    log: Logger,
     _harness: BeaconChainHarness<T>,
 }
 
@@ -216,6 +218,7 @@ impl TestRig {
             _network_rx,
             _sync_rx,
             duplicate_cache,
+            log,
             _harness: harness,
         }
     }
@@ -255,7 +258,10 @@ impl TestRig {
         let event = WorkEvent::rpc_beacon_block(
             self.next_block.clone(),
             std::time::Duration::default(),
-            BlockProcessType::SingleBlock { id: 1 },
+            BlockProcessType::SingleBlock {
+                id: 1,
+                peer_id: PeerId::random(),
+            },
         );
         self.beacon_processor_tx.try_send(event).unwrap();
     }
@@ -557,6 +563,35 @@ async fn import_gossip_block_at_current_slot() {
     );
 }
 
+/// Deliveries of a block after the first are counted as duplicates, including those which are
+/// ignored by gossip verification.
+#[tokio::test]
+async fn duplicate_block_deliveries_are_recorded() {
+    let mut rig = TestRig::new(SMALL_CHAIN).await;
+    let block_root = rig.next_block.canonical_root();
+
+    rig.enqueue_gossip_block();
+    rig.assert_event_journal(&[GOSSIP_BLOCK, WORKER_FREED, NOTHING_TO_DO])
+        .await;
+    assert_eq!(rig.head_root(), block_root);
+
+    // The block is delivered again by other peers, over gossip and RPC.
+    rig.enqueue_gossip_block();
+    rig.assert_event_journal(&[GOSSIP_BLOCK, WORKER_FREED, NOTHING_TO_DO])
+        .await;
+    rig.enqueue_single_lookup_rpc_block();
+    rig.assert_event_journal(&[RPC_BLOCK, WORKER_FREED, NOTHING_TO_DO])
+        .await;
+
+    let timings = rig.chain.block_timings(block_root).unwrap();
+    assert_eq!(
+        timings.peer_info.transport,
+        Some(BlockDeliveryTransport::Gossip)
+    );
+    assert_eq!(timings.peer_info.duplicate_deliveries, 2);
+    assert_eq!(timings.peer_info.distinct_peers(), 3);
+}
+
 /// Ensure a valid attestation can be imported.
 #[tokio::test]
 async fn import_gossip_attestation() {
@@ -871,4 +906,118 @@ async fn test_rpc_block_reprocessing() {
     // head should update to next block now since the duplicate
     // cache handle was dropped.
     assert_eq!(next_block_root, rig.head_root());
+
+    // The requeued block isn't counted as a second delivery.
+    let timings = rig.chain.block_timings(next_block_root).unwrap();
+    assert_eq!(
+        timings.peer_info.transport,
+        Some(BlockDeliveryTransport::Rpc)
+    );
+    assert_eq!(timings.peer_info.duplicate_deliveries, 0);
+}
+
+/// Aggregates are only shed whilst attestation verification is under pressure, and only from peers
+/// which already have many aggregates queued.
+#[tokio::test]
+async fn sheds_aggregates_from_over_represented_peers_under_pressure() {
+    let rig = TestRig::new(SMALL_CHAIN).await;
+    let aggregate = rig.next_block_aggregate_attestations.first().unwrap();
+    let busy_peer = junk_peer_id();
+    let other_peer = junk_peer_id();
+
+    let mut aggregate_queue = LifoQueue::new(MAX_AGGREGATED_ATTESTATION_QUEUE_LEN);
+    for _ in 0..MAX_QUEUED_AGGREGATES_PER_PEER_UNDER_PRESSURE {
+        aggregate_queue.push(Work::<T>::GossipAggregate {
+            message_id: junk_message_id(),
+            peer_id: busy_peer,
+            aggregate: Box::new(aggregate.clone()),
+            seen_timestamp: Duration::from_secs(0),
+        });
+    }
+
+    let pressure = rig.chain.verification_pressure();
+    assert!(!pressure.is_high);
+    assert!(!should_shed_aggregate(
+        pressure,
+        &aggregate_queue,
+        &busy_peer
+    ));
+
+    // Verify more batches concurrently than the threshold allows.
+    let _batches = (0..=rig.chain.config.verification_pressure_max_batches)
+        .map(|_| rig.chain.verification_pressure.start_batch())
+        .collect::<Vec<_>>();
+    let pressure = rig.chain.verification_pressure();
+    assert!(pressure.is_high);
+    assert!(should_shed_aggregate(
+        pressure,
+        &aggregate_queue,
+        &busy_peer
+    ));
+    assert!(!should_shed_aggregate(
+        pressure,
+        &aggregate_queue,
+        &other_peer
+    ));
+}
+
+/// Two or more queued exits are verified as a batch, with each exit handled as it would be alone.
+#[tokio::test]
+async fn batches_queued_voluntary_exits() {
+    let rig = TestRig::new(SMALL_CHAIN).await;
+    let exit_work = || Work::<T>::GossipVoluntaryExit {
+        message_id: junk_message_id(),
+        peer_id: junk_peer_id(),
+        voluntary_exit: Box::new(rig.voluntary_exit.clone()),
+    };
+    let unpack = |work: Work<T>| match work {
+        Work::GossipVoluntaryExit {
+            message_id,
+            peer_id,
+            voluntary_exit,
+        } => Some(GossipOperationPackage::new(
+            message_id,
+            peer_id,
+            voluntary_exit,
+        )),
+        _ => None,
+    };
+    let into_batch = |packages| Work::GossipVoluntaryExitBatch { packages };
+
+    // A lone exit is processed individually.
+    let mut queue = FifoQueue::new(MAX_GOSSIP_EXIT_QUEUE_LEN);
+    queue.push(exit_work(), GOSSIP_VOLUNTARY_EXIT, &rig.log);
+    let work = pop_gossip_operations(&mut queue, unpack, into_batch).unwrap();
+    assert_eq!(work.str_id(), GOSSIP_VOLUNTARY_EXIT);
+    assert!(pop_gossip_operations(&mut queue, unpack, into_batch).is_none());
+
+    // The exit and a duplicate of it are combined into one batch.
+    queue.push(exit_work(), GOSSIP_VOLUNTARY_EXIT, &rig.log);
+    queue.push(exit_work(), GOSSIP_VOLUNTARY_EXIT, &rig.log);
+    let packages = match pop_gossip_operations(&mut queue, unpack, into_batch) {
+        Some(Work::GossipVoluntaryExitBatch { packages }) => packages,
+        other => panic!(
+            "expected a batch, got {:?}",
+            other.map(|work| work.str_id())
+        ),
+    };
+    assert_eq!(packages.len(), 2);
+    assert_eq!(queue.len(), 0);
+
+    // Only the first exit of the batch is imported, the duplicate is ignored.
+    let initial_voluntary_exits = rig.chain.op_pool.num_voluntary_exits();
+    let (network_tx, _network_rx) = mpsc::unbounded_channel();
+    let (sync_tx, _sync_rx) = mpsc::unbounded_channel();
+    Worker {
+        chain: rig.chain.clone(),
+        network_tx,
+        sync_tx,
+        log: rig.log.clone(),
+    }
+    .process_gossip_voluntary_exit_batch(packages);
+    assert_eq!(
+        rig.chain.op_pool.num_voluntary_exits(),
+        initial_voluntary_exits + 1,
+        "op pool should have one more exit"
+    );
 }
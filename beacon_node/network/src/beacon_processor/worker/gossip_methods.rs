@@ -6,7 +6,8 @@ use beacon_chain::{
     observed_operations::ObservationOutcome,
     sync_committee_verification::{self, Error as SyncCommitteeError},
     validator_monitor::get_block_delay_ms,
-    BeaconChainError, BeaconChainTypes, BlockError, ForkChoiceError, GossipVerifiedBlock,
+    BeaconChainError, BeaconChainTypes, BlockDeliveryTransport, BlockError, ForkChoiceError,
+    GossipVerifiedBlock,
 };
 use lighthouse_network::{Client, MessageAcceptance, MessageId, PeerAction, PeerId, ReportSource};
 use slog::{crit, debug, error, info, trace, warn};
@@ -180,6 +181,24 @@ impl<E: EthSpec> GossipAggregatePackage<E> {
     }
 }
 
+/// Items required to verify a batch of gossip exits or slashings.
+#[derive(Debug)]
+pub struct GossipOperationPackage<O> {
+    message_id: MessageId,
+    peer_id: PeerId,
+    operation: Box<O>,
+}
+
+impl<O> GossipOperationPackage<O> {
+    pub fn new(message_id: MessageId, peer_id: PeerId, operation: Box<O>) -> Self {
+        Self {
+            message_id,
+            peer_id,
+            operation,
+        }
+    }
+}
+
 impl<T: BeaconChainTypes> Worker<T> {
     /* Auxiliary functions */
 
@@ -267,7 +286,17 @@ impl<T: BeaconChainTypes> Worker<T> {
             .chain
             .batch_verify_unaggregated_attestations_for_gossip(attestations_and_subnets)
         {
-            Ok(results) => results,
+            Ok((results, summary)) => {
+                if summary.fallback {
+                    debug!(
+                        self.log,
+                        "Batch unagg. attn signatures verified individually";
+                        "batch_size" => summary.batch_size,
+                        "salvaged" => summary.salvaged,
+                    );
+                }
+                results
+            }
             Err(e) => {
                 error!(
                     self.log,
@@ -478,7 +507,17 @@ impl<T: BeaconChainTypes> Worker<T> {
             .chain
             .batch_verify_aggregated_attestations_for_gossip(aggregates)
         {
-            Ok(results) => results,
+            Ok((results, summary)) => {
+                if summary.fallback {
+                    debug!(
+                        self.log,
+                        "Batch agg. attn signatures verified individually";
+                        "batch_size" => summary.batch_size,
+                        "salvaged" => summary.salvaged,
+                    );
+                }
+                results
+            }
             Err(e) => {
                 error!(
                     self.log,
@@ -704,6 +743,7 @@ impl<T: BeaconChainTypes> Worker<T> {
             block.canonical_root(),
             block.slot(),
             seen_duration,
+            BlockDeliveryTransport::Gossip,
             Some(peer_id.to_string()),
             Some(peer_client.to_string()),
         );
@@ -798,6 +838,7 @@ impl<T: BeaconChainTypes> Worker<T> {
             | Err(e @ BlockError::ExecutionPayloadError(_))
             // TODO(merge): reconsider peer scoring for this event.
             | Err(e @ BlockError::ParentExecutionPayloadInvalid { .. })
+            | Err(e @ BlockError::ParentExecutionInvalid { .. })
             | Err(e @ BlockError::GenesisBlock) => {
                 warn!(self.log, "Could not verify block for gossip, rejecting the block";
                             "error" => %e);
@@ -984,8 +1025,60 @@ impl<T: BeaconChainTypes> Worker<T> {
         voluntary_exit: SignedVoluntaryExit,
     ) {
         let validator_index = voluntary_exit.message.validator_index;
+        let result = self.chain.verify_voluntary_exit_for_gossip(voluntary_exit);
+        self.process_voluntary_exit_outcome(message_id, peer_id, validator_index, result);
+    }
+
+    /// Process a batch of voluntary exits received from the gossip network.
+    ///
+    /// Each exit is handled as it would be by `Self::process_gossip_voluntary_exit`, but the batch
+    /// is verified with a single call to the `BeaconChain`.
+    pub fn process_gossip_voluntary_exit_batch(
+        self,
+        packages: Vec<GossipOperationPackage<SignedVoluntaryExit>>,
+    ) {
+        let (senders, exits): (Vec<_>, Vec<_>) = packages
+            .into_iter()
+            .map(|package| {
+                let validator_index = package.operation.message.validator_index;
+                (
+                    (package.message_id, package.peer_id, validator_index),
+                    *package.operation,
+                )
+            })
+            .unzip();
+
+        match self.chain.batch_verify_voluntary_exits_for_gossip(exits) {
+            Ok(results) => {
+                for ((message_id, peer_id, validator_index), result) in
+                    senders.into_iter().zip(results)
+                {
+                    self.process_voluntary_exit_outcome(
+                        message_id,
+                        peer_id,
+                        validator_index,
+                        result,
+                    );
+                }
+            }
+            Err(e) => self.ignore_gossip_operation_batch(
+                senders
+                    .into_iter()
+                    .map(|(message_id, peer_id, _)| (message_id, peer_id)),
+                "exits",
+                e,
+            ),
+        }
+    }
 
-        let exit = match self.chain.verify_voluntary_exit_for_gossip(voluntary_exit) {
+    fn process_voluntary_exit_outcome(
+        &self,
+        message_id: MessageId,
+        peer_id: PeerId,
+        validator_index: u64,
+        result: Result<ObservationOutcome<SignedVoluntaryExit>, BeaconChainError>,
+    ) {
+        let exit = match result {
             Ok(ObservationOutcome::New(exit)) => exit,
             Ok(ObservationOutcome::AlreadyKnown) => {
                 self.propagate_validation_result(message_id, peer_id, MessageAcceptance::Ignore);
@@ -1042,11 +1135,64 @@ impl<T: BeaconChainTypes> Worker<T> {
         proposer_slashing: ProposerSlashing,
     ) {
         let validator_index = proposer_slashing.signed_header_1.message.proposer_index;
+        let result = self
+            .chain
+            .verify_proposer_slashing_for_gossip(proposer_slashing);
+        self.process_proposer_slashing_outcome(message_id, peer_id, validator_index, result);
+    }
 
-        let slashing = match self
+    /// Process a batch of proposer slashings received from the gossip network.
+    ///
+    /// See `Self::process_gossip_voluntary_exit_batch`.
+    pub fn process_gossip_proposer_slashing_batch(
+        self,
+        packages: Vec<GossipOperationPackage<ProposerSlashing>>,
+    ) {
+        let (senders, slashings): (Vec<_>, Vec<_>) = packages
+            .into_iter()
+            .map(|package| {
+                let validator_index = package.operation.signed_header_1.message.proposer_index;
+                (
+                    (package.message_id, package.peer_id, validator_index),
+                    *package.operation,
+                )
+            })
+            .unzip();
+
+        match self
             .chain
-            .verify_proposer_slashing_for_gossip(proposer_slashing)
+            .batch_verify_proposer_slashings_for_gossip(slashings)
         {
+            Ok(results) => {
+                for ((message_id, peer_id, validator_index), result) in
+                    senders.into_iter().zip(results)
+                {
+                    self.process_proposer_slashing_outcome(
+                        message_id,
+                        peer_id,
+                        validator_index,
+                        result,
+                    );
+                }
+            }
+            Err(e) => self.ignore_gossip_operation_batch(
+                senders
+                    .into_iter()
+                    .map(|(message_id, peer_id, _)| (message_id, peer_id)),
+                "proposer slashings",
+                e,
+            ),
+        }
+    }
+
+    fn process_proposer_slashing_outcome(
+        &self,
+        message_id: MessageId,
+        peer_id: PeerId,
+        validator_index: u64,
+        result: Result<ObservationOutcome<ProposerSlashing>, BeaconChainError>,
+    ) {
+        let slashing = match result {
             Ok(ObservationOutcome::New(slashing)) => slashing,
             Ok(ObservationOutcome::AlreadyKnown) => {
                 debug!(
@@ -1103,10 +1249,44 @@ impl<T: BeaconChainTypes> Worker<T> {
         peer_id: PeerId,
         attester_slashing: AttesterSlashing<T::EthSpec>,
     ) {
-        let slashing = match self
+        let result = self
+            .chain
+            .verify_attester_slashing_for_gossip(attester_slashing);
+        self.process_attester_slashing_outcome(message_id, peer_id, result);
+    }
+
+    /// Process a batch of attester slashings received from the gossip network.
+    ///
+    /// See `Self::process_gossip_voluntary_exit_batch`.
+    pub fn process_gossip_attester_slashing_batch(
+        self,
+        packages: Vec<GossipOperationPackage<AttesterSlashing<T::EthSpec>>>,
+    ) {
+        let (senders, slashings): (Vec<_>, Vec<_>) = packages
+            .into_iter()
+            .map(|package| ((package.message_id, package.peer_id), *package.operation))
+            .unzip();
+
+        match self
             .chain
-            .verify_attester_slashing_for_gossip(attester_slashing)
+            .batch_verify_attester_slashings_for_gossip(slashings)
         {
+            Ok(results) => {
+                for ((message_id, peer_id), result) in senders.into_iter().zip(results) {
+                    self.process_attester_slashing_outcome(message_id, peer_id, result);
+                }
+            }
+            Err(e) => self.ignore_gossip_operation_batch(senders, "attester slashings", e),
+        }
+    }
+
+    fn process_attester_slashing_outcome(
+        &self,
+        message_id: MessageId,
+        peer_id: PeerId,
+        result: Result<ObservationOutcome<AttesterSlashing<T::EthSpec>>, BeaconChainError>,
+    ) {
+        let slashing = match result {
             Ok(ObservationOutcome::New(slashing)) => slashing,
             Ok(ObservationOutcome::AlreadyKnown) => {
                 debug!(
@@ -1151,6 +1331,30 @@ impl<T: BeaconChainTypes> Worker<T> {
         metrics::inc_counter(&metrics::BEACON_PROCESSOR_ATTESTER_SLASHING_IMPORTED_TOTAL);
     }
 
+    /// Ignore each message of a batch of exits or slashings which couldn't be verified.
+    ///
+    /// The batch fails as a whole due to a fault in the beacon chain, so the peers aren't
+    /// penalized.
+    fn ignore_gossip_operation_batch(
+        &self,
+        senders: impl IntoIterator<Item = (MessageId, PeerId)>,
+        kind: &'static str,
+        error: BeaconChainError,
+    ) {
+        let mut count = 0;
+        for (message_id, peer_id) in senders {
+            self.propagate_validation_result(message_id, peer_id, MessageAcceptance::Ignore);
+            count += 1;
+        }
+        debug!(
+            self.log,
+            "Dropping batch of gossip operations";
+            "kind" => kind,
+            "count" => count,
+            "error" => ?error
+        );
+    }
+
     /// Process the sync committee signature received from the gossip network and:
     ///
     /// - If it passes gossip propagation criteria, tell the network thread to forward it.
@@ -1324,6 +1528,7 @@ impl<T: BeaconChainTypes> Worker<T> {
                     attestation_verification::verify_propagation_slot_range(
                         seen_clock,
                         failed_att.attestation(),
+                        self.chain.gossip_clock_disparity(),
                     );
 
                 // Only penalize the peer if it would have been invalid at the moment we received
@@ -1487,25 +1692,31 @@ impl<T: BeaconChainTypes> Worker<T> {
                     "attn_val_index_too_high",
                 );
             }
-            AttnError::UnknownHeadBlock { beacon_block_root } => {
+            AttnError::UnknownHeadBlock { beacon_block_root }
+            | AttnError::DeferredUnknownHead { beacon_block_root } => {
                 trace!(
                     self.log,
                     "Attestation for unknown block";
                     "peer_id" => %peer_id,
-                    "block" => ?beacon_block_root
+                    "block" => ?beacon_block_root,
+                    "importing" => matches!(error, AttnError::DeferredUnknownHead { .. }),
                 );
                 if let Some(sender) = reprocess_tx {
-                    // We don't know the block, get the sync manager to handle the block lookup, and
-                    // send the attestation to be scheduled for re-processing.
-                    self.sync_tx
-                        .send(SyncMessage::UnknownBlockHash(peer_id, *beacon_block_root))
-                        .unwrap_or_else(|_| {
-                            warn!(
-                                self.log,
-                                "Failed to send to sync service";
-                                "msg" => "UnknownBlockHash"
-                            )
-                        });
+                    // If we don't know the block, get the sync manager to handle the block lookup.
+                    // A deferred attestation's block is already being imported, so there's no need.
+                    //
+                    // In either case, send the attestation to be scheduled for re-processing.
+                    if matches!(error, AttnError::UnknownHeadBlock { .. }) {
+                        self.sync_tx
+                            .send(SyncMessage::UnknownBlockHash(peer_id, *beacon_block_root))
+                            .unwrap_or_else(|_| {
+                                warn!(
+                                    self.log,
+                                    "Failed to send to sync service";
+                                    "msg" => "UnknownBlockHash"
+                                )
+                            });
+                    }
                     let msg = match failed_att {
                         FailedAtt::Aggregate {
                             attestation,
@@ -1842,6 +2053,7 @@ impl<T: BeaconChainTypes> Worker<T> {
                         sync_committee_verification::verify_propagation_slot_range(
                             seen_clock,
                             &sync_committee_message_slot,
+                            self.chain.gossip_clock_disparity(),
                         );
                     hindsight_verification.is_err()
                 };
@@ -2135,6 +2347,7 @@ impl<T: BeaconChainTypes> Worker<T> {
         let is_timely = attestation_verification::verify_propagation_slot_range(
             &self.chain.slot_clock,
             attestation,
+            self.chain.gossip_clock_disparity(),
         )
         .is_ok();
 
@@ -9,7 +9,9 @@ mod gossip_methods;
 mod rpc_methods;
 mod sync_methods;
 
-pub use gossip_methods::{GossipAggregatePackage, GossipAttestationPackage};
+pub use gossip_methods::{
+    GossipAggregatePackage, GossipAttestationPackage, GossipOperationPackage,
+};
 pub use sync_methods::{ChainSegmentProcessId, FailureMode};
 
 pub(crate) const FUTURE_SLOT_TOLERANCE: u64 = 1;
@@ -9,7 +9,8 @@ use crate::sync::manager::{BlockProcessType, SyncMessage};
 use crate::sync::{BatchProcessResult, ChainId};
 use beacon_chain::ExecutionPayloadError;
 use beacon_chain::{
-    BeaconChainError, BeaconChainTypes, BlockError, ChainSegmentResult, HistoricalBlockError,
+    BeaconChainError, BeaconChainTypes, BlockDeliveryTransport, BlockError, ChainSegmentResult,
+    HistoricalBlockError,
 };
 use lighthouse_network::PeerAction;
 use slog::{debug, error, info, warn};
@@ -56,6 +57,19 @@ impl<T: BeaconChainTypes> Worker<T> {
         duplicate_cache: DuplicateCache,
         should_process: bool,
     ) {
+        // Write the time the block was observed into the delay cache, before any checks which may
+        // ignore it so that duplicate deliveries are counted.
+        if let BlockProcessType::SingleBlock { peer_id, .. } = &process_type {
+            self.chain.block_times_cache.write().set_time_observed(
+                block.canonical_root(),
+                block.slot(),
+                seen_timestamp,
+                BlockDeliveryTransport::Rpc,
+                Some(peer_id.to_string()),
+                None,
+            );
+        }
+
         if !should_process {
             // Sync handles these results
             self.send_sync_message(SyncMessage::BlockProcessed {
@@ -103,14 +117,6 @@ impl<T: BeaconChainTypes> Worker<T> {
                 error!(self.log, "Failed to inform block import"; "source" => "rpc", "block_root" => %hash)
             };
             if matches!(process_type, BlockProcessType::SingleBlock { .. }) {
-                self.chain.block_times_cache.write().set_time_observed(
-                    hash,
-                    slot,
-                    seen_timestamp,
-                    None,
-                    None,
-                );
-
                 self.recompute_head("process_rpc_block").await;
             }
         }
@@ -299,6 +305,27 @@ impl<T: BeaconChainTypes> Worker<T> {
                                 mode: FailureMode::ConsensusLayer,
                             }
                         }
+                        HistoricalBlockError::AnchorBlockRootMismatch {
+                            slot,
+                            block_root,
+                            expected_block_root,
+                        } => {
+                            debug!(
+                                self.log,
+                                "Backfill batch processing error";
+                                "error" => "anchor_block_root_mismatch",
+                                "slot" => slot,
+                                "block_root" => ?block_root,
+                                "expected_root" => ?expected_block_root
+                            );
+
+                            ChainSegmentFailed {
+                                message: String::from("anchor_block_root_mismatch"),
+                                // The peer is faulty if they send blocks that aren't canonical.
+                                peer_action: Some(PeerAction::LowToleranceError),
+                                mode: FailureMode::ConsensusLayer,
+                            }
+                        }
                         HistoricalBlockError::InvalidSignature
                         | HistoricalBlockError::SignatureSet(_) => {
                             warn!(
@@ -211,6 +211,10 @@ lazy_static! {
         "beacon_processor_aggregated_attestation_requeued_total",
         "Total number of aggregated attestations that referenced an unknown block and were re-queued."
     );
+    pub static ref BEACON_PROCESSOR_AGGREGATED_ATTESTATION_SHED_TOTAL: Result<IntCounter> = try_create_int_counter(
+        "beacon_processor_aggregated_attestation_shed_total",
+        "Total number of aggregated attestations dropped from over-represented peers whilst attestation verification was under pressure."
+    );
     // Sync committee messages.
     pub static ref BEACON_PROCESSOR_SYNC_MESSAGE_QUEUE_TOTAL: Result<IntGauge> = try_create_int_gauge(
         "beacon_processor_sync_message_queue_total",
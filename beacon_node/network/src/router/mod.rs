@@ -229,6 +229,15 @@ impl<T: BeaconChainTypes> Router<T> {
                     block,
                 );
             }
+            PubsubMessage::BlindedBeaconBlock(block) => {
+                debug!(
+                    self.log,
+                    "Received a blinded block";
+                    "peer_id" => %peer_id,
+                    "slot" => block.slot(),
+                );
+                self.processor.on_blinded_block_gossip(id, peer_id);
+            }
             PubsubMessage::VoluntaryExit(exit) => {
                 debug!(self.log, "Received a voluntary exit"; "peer_id" => %peer_id);
                 self.processor.on_voluntary_exit_gossip(id, peer_id, exit);
@@ -8,7 +8,7 @@ use crate::sync::SyncMessage;
 use beacon_chain::{BeaconChain, BeaconChainTypes};
 use lighthouse_network::rpc::*;
 use lighthouse_network::{
-    Client, MessageId, NetworkGlobals, PeerId, PeerRequestId, Request, Response,
+    Client, MessageAcceptance, MessageId, NetworkGlobals, PeerId, PeerRequestId, Request, Response,
 };
 use slog::{debug, error, o, trace, warn};
 use std::cmp;
@@ -287,6 +287,16 @@ impl<T: BeaconChainTypes> Processor<T> {
         ))
     }
 
+    /// Blinded blocks cannot be imported without their payload, so they are never propagated.
+    pub fn on_blinded_block_gossip(&mut self, message_id: MessageId, peer_id: PeerId) {
+        self.network
+            .inform_network(NetworkMessage::ValidationResult {
+                propagation_source: peer_id,
+                message_id,
+                validation_result: MessageAcceptance::Ignore,
+            })
+    }
+
     pub fn on_voluntary_exit_gossip(
         &mut self,
         message_id: MessageId,
@@ -148,8 +148,6 @@ pub struct NetworkService<T: BeaconChainTypes> {
     next_unsubscribe: Pin<Box<OptionFuture<Sleep>>>,
     /// Subscribe to all the subnets once synced.
     subscribe_all_subnets: bool,
-    /// Shutdown beacon node after sync is complete.
-    shutdown_after_sync: bool,
     /// Whether metrics are enabled or not.
     metrics_enabled: bool,
     /// A timer for updating various network metrics.
@@ -280,7 +278,6 @@ impl<T: BeaconChainTypes> NetworkService<T> {
             next_fork_subscriptions,
             next_unsubscribe,
             subscribe_all_subnets: config.subscribe_all_subnets,
-            shutdown_after_sync: config.shutdown_after_sync,
             metrics_enabled: config.metrics_enabled,
             metrics_update,
             gossipsub_parameter_update,
@@ -356,7 +353,7 @@ impl<T: BeaconChainTypes> NetworkService<T> {
                     _ = self.gossipsub_parameter_update.tick() => self.update_gossipsub_parameters(),
 
                     // handle a message sent to the network
-                    Some(msg) = self.network_recv.recv() => self.on_network_msg(msg, &mut shutdown_sender).await,
+                    Some(msg) = self.network_recv.recv() => self.on_network_msg(msg).await,
 
                     // process any attestation service events
                     Some(msg) = self.attestation_service.next() => self.on_attestation_service_msg(msg),
@@ -500,11 +497,7 @@ impl<T: BeaconChainTypes> NetworkService<T> {
     }
 
     /// Handle a message sent to the network service.
-    async fn on_network_msg(
-        &mut self,
-        msg: NetworkMessage<T::EthSpec>,
-        shutdown_sender: &mut Sender<ShutdownReason>,
-    ) {
+    async fn on_network_msg(&mut self, msg: NetworkMessage<T::EthSpec>) {
         match msg {
             NetworkMessage::SendRequest {
                 peer_id,
@@ -623,22 +616,6 @@ impl<T: BeaconChainTypes> NetworkService<T> {
                 }
             }
             NetworkMessage::SubscribeCoreTopics => {
-                if self.shutdown_after_sync {
-                    if let Err(e) = shutdown_sender
-                        .send(ShutdownReason::Success(
-                            "Beacon node completed sync. \
-                             Shutting down as --shutdown-after-sync flag is enabled",
-                        ))
-                        .await
-                    {
-                        warn!(
-                            self.log,
-                            "failed to send a shutdown signal";
-                            "error" => %e
-                        )
-                    }
-                    return;
-                }
                 let mut subscribed_topics: Vec<GossipTopic> = vec![];
                 for topic_kind in lighthouse_network::types::CORE_TOPICS.iter() {
                     for fork_digest in self.required_gossip_fork_digests() {
@@ -877,25 +854,33 @@ impl<T: BeaconChainTypes> Drop for NetworkService<T> {
     fn drop(&mut self) {
         // network thread is terminating
         let enrs = self.libp2p.swarm.behaviour_mut().enr_entries();
-        debug!(
-            self.log,
-            "Persisting DHT to store";
-            "Number of peers" => enrs.len(),
-        );
-        if let Err(e) = clear_dht::<T::EthSpec, T::HotStore, T::ColdStore>(self.store.clone()) {
-            error!(self.log, "Failed to clear old DHT entries"; "error" => ?e);
-        }
-        // Still try to update new entries
-        match persist_dht::<T::EthSpec, T::HotStore, T::ColdStore>(self.store.clone(), enrs) {
-            Err(e) => error!(
+        if self.beacon_chain.config.read_only {
+            debug!(
                 self.log,
-                "Failed to persist DHT on drop";
-                "error" => ?e
-            ),
-            Ok(_) => info!(
+                "Not persisting DHT to read-only store";
+                "Number of peers" => enrs.len(),
+            );
+        } else {
+            debug!(
                 self.log,
-                "Saved DHT state";
-            ),
+                "Persisting DHT to store";
+                "Number of peers" => enrs.len(),
+            );
+            if let Err(e) = clear_dht::<T::EthSpec, T::HotStore, T::ColdStore>(self.store.clone()) {
+                error!(self.log, "Failed to clear old DHT entries"; "error" => ?e);
+            }
+            // Still try to update new entries
+            match persist_dht::<T::EthSpec, T::HotStore, T::ColdStore>(self.store.clone(), enrs) {
+                Err(e) => error!(
+                    self.log,
+                    "Failed to persist DHT on drop";
+                    "error" => ?e
+                ),
+                Ok(_) => info!(
+                    self.log,
+                    "Saved DHT state";
+                ),
+            }
         }
 
         // attempt to remove port mappings
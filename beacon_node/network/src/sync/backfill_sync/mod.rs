@@ -135,6 +135,10 @@ pub struct BackFillSync<T: BeaconChainTypes> {
     /// This signifies that we are able to attempt to restart a failed chain.
     restart_failed_sync: bool,
 
+    /// Whether the next ready batch was held back by the beacon chain's backfill rate limit and
+    /// should be retried once more capacity is available.
+    rate_limited: bool,
+
     /// Reference to the beacon chain to obtain initial starting points for the backfill sync.
     beacon_chain: Arc<BeaconChain<T>>,
 
@@ -190,6 +194,7 @@ impl<T: BeaconChainTypes> BackFillSync<T> {
             validated_batches: 0,
             participating_peers: HashSet::new(),
             restart_failed_sync: false,
+            rate_limited: false,
             beacon_chain,
             beacon_processor_send,
             log,
@@ -509,6 +514,14 @@ impl<T: BeaconChainTypes> BackFillSync<T> {
             return Ok(ProcessResult::Successful);
         }
 
+        // Leave resources for forward sync and validator duties if backfill is rate limited or
+        // paused. The sync manager retries the batch once there is capacity again.
+        if !self.beacon_chain.try_acquire_backfill_batch() {
+            self.rate_limited = true;
+            return Ok(ProcessResult::Successful);
+        }
+        self.rate_limited = false;
+
         let batch = match self.batches.get_mut(&batch_id) {
             Some(batch) => batch,
             None => {
@@ -562,6 +575,24 @@ impl<T: BeaconChainTypes> BackFillSync<T> {
         }
     }
 
+    /// Returns `true` if a batch is ready for processing but was held back by the backfill rate
+    /// limit.
+    pub fn is_rate_limited(&self) -> bool {
+        self.rate_limited
+    }
+
+    /// Attempts to process a batch that was previously held back by the backfill rate limit.
+    ///
+    /// If an error is returned the BackFill sync has failed.
+    #[must_use = "A failure here indicates the backfill sync has failed and the global sync state should be updated"]
+    pub fn retry_rate_limited_batch(
+        &mut self,
+        network: &mut SyncNetworkContext<T::EthSpec>,
+    ) -> Result<ProcessResult, BackFillError> {
+        self.rate_limited = false;
+        self.process_completed_batches(network)
+    }
+
     /// The block processor has completed processing a batch. This function handles the result
     /// of the batch processor.
     /// If an error is returned the BackFill sync has failed.
@@ -171,7 +171,7 @@ impl<T: BeaconChainTypes> BlockLookups<T> {
                     .send_block_for_processing(
                         block,
                         seen_timestamp,
-                        BlockProcessType::SingleBlock { id },
+                        BlockProcessType::SingleBlock { id, peer_id },
                     )
                     .is_err()
                 {
@@ -124,7 +124,7 @@ pub enum SyncMessage<T: EthSpec> {
 /// The type of processing specified for a received block.
 #[derive(Debug, Clone)]
 pub enum BlockProcessType {
-    SingleBlock { id: Id },
+    SingleBlock { id: Id, peer_id: PeerId },
     ParentLookup { chain_hash: Hash256 },
 }
 
@@ -468,100 +468,118 @@ impl<T: BeaconChainTypes> SyncManager<T> {
     async fn main(&mut self) {
         // process any inbound messages
         loop {
-            if let Some(sync_message) = self.input_channel.recv().await {
-                match sync_message {
-                    SyncMessage::AddPeer(peer_id, info) => {
-                        self.add_peer(peer_id, info);
-                    }
-                    SyncMessage::RpcBlock {
-                        request_id,
-                        peer_id,
-                        beacon_block,
-                        seen_timestamp,
-                    } => {
-                        self.rpc_block_received(request_id, peer_id, beacon_block, seen_timestamp);
-                    }
-                    SyncMessage::UnknownBlock(peer_id, block) => {
-                        // If we are not synced or within SLOT_IMPORT_TOLERANCE of the block, ignore
-                        if !self.network_globals.sync_state.read().is_synced() {
-                            let head_slot = self.chain.canonical_head.cached_head().head_slot();
-                            let unknown_block_slot = block.slot();
-
-                            // if the block is far in the future, ignore it. If its within the slot tolerance of
-                            // our current head, regardless of the syncing state, fetch it.
-                            if (head_slot >= unknown_block_slot
-                                && head_slot.sub(unknown_block_slot).as_usize()
-                                    > SLOT_IMPORT_TOLERANCE)
-                                || (head_slot < unknown_block_slot
-                                    && unknown_block_slot.sub(head_slot).as_usize()
-                                        > SLOT_IMPORT_TOLERANCE)
-                            {
-                                continue;
-                            }
-                        }
-                        if self.network_globals.peers.read().is_connected(&peer_id) {
-                            self.block_lookups
-                                .search_parent(block, peer_id, &mut self.network);
-                        }
-                    }
-                    SyncMessage::UnknownBlockHash(peer_id, block_hash) => {
-                        // If we are not synced, ignore this block.
-                        if self.network_globals.sync_state.read().is_synced()
-                            && self.network_globals.peers.read().is_connected(&peer_id)
-                        {
-                            self.block_lookups
-                                .search_block(block_hash, peer_id, &mut self.network);
+            tokio::select! {
+                Some(sync_message) = self.input_channel.recv() => {
+                    self.handle_message(sync_message);
+                }
+                // Retry a backfill batch that was held back by the rate limit once there is
+                // capacity again.
+                _ = self.chain.wait_for_backfill_capacity(), if self.backfill_sync.is_rate_limited() => {
+                    match self.backfill_sync.retry_rate_limited_batch(&mut self.network) {
+                        Ok(ProcessResult::Successful) => {}
+                        Ok(ProcessResult::SyncCompleted) => self.update_sync_state(),
+                        Err(error) => {
+                            error!(self.log, "Backfill sync failed"; "error" => ?error);
+                            // Update the global status
+                            self.update_sync_state();
                         }
                     }
-                    SyncMessage::Disconnect(peer_id) => {
-                        self.peer_disconnect(&peer_id);
+                }
+                else => break,
+            }
+        }
+    }
+
+    fn handle_message(&mut self, sync_message: SyncMessage<T::EthSpec>) {
+        match sync_message {
+            SyncMessage::AddPeer(peer_id, info) => {
+                self.add_peer(peer_id, info);
+            }
+            SyncMessage::RpcBlock {
+                request_id,
+                peer_id,
+                beacon_block,
+                seen_timestamp,
+            } => {
+                self.rpc_block_received(request_id, peer_id, beacon_block, seen_timestamp);
+            }
+            SyncMessage::UnknownBlock(peer_id, block) => {
+                // If we are not synced or within SLOT_IMPORT_TOLERANCE of the block, ignore
+                if !self.network_globals.sync_state.read().is_synced() {
+                    let head_slot = self.chain.canonical_head.cached_head().head_slot();
+                    let unknown_block_slot = block.slot();
+
+                    // if the block is far in the future, ignore it. If its within the slot tolerance of
+                    // our current head, regardless of the syncing state, fetch it.
+                    if (head_slot >= unknown_block_slot
+                        && head_slot.sub(unknown_block_slot).as_usize() > SLOT_IMPORT_TOLERANCE)
+                        || (head_slot < unknown_block_slot
+                            && unknown_block_slot.sub(head_slot).as_usize() > SLOT_IMPORT_TOLERANCE)
+                    {
+                        return;
                     }
-                    SyncMessage::RpcError {
-                        peer_id,
-                        request_id,
-                    } => self.inject_error(peer_id, request_id),
-                    SyncMessage::BlockProcessed {
-                        process_type,
+                }
+                if self.network_globals.peers.read().is_connected(&peer_id) {
+                    self.block_lookups
+                        .search_parent(block, peer_id, &mut self.network);
+                }
+            }
+            SyncMessage::UnknownBlockHash(peer_id, block_hash) => {
+                // If we are not synced, ignore this block.
+                if self.network_globals.sync_state.read().is_synced()
+                    && self.network_globals.peers.read().is_connected(&peer_id)
+                {
+                    self.block_lookups
+                        .search_block(block_hash, peer_id, &mut self.network);
+                }
+            }
+            SyncMessage::Disconnect(peer_id) => {
+                self.peer_disconnect(&peer_id);
+            }
+            SyncMessage::RpcError {
+                peer_id,
+                request_id,
+            } => self.inject_error(peer_id, request_id),
+            SyncMessage::BlockProcessed {
+                process_type,
+                result,
+            } => match process_type {
+                BlockProcessType::SingleBlock { id, .. } => self
+                    .block_lookups
+                    .single_block_processed(id, result, &mut self.network),
+                BlockProcessType::ParentLookup { chain_hash } => self
+                    .block_lookups
+                    .parent_block_processed(chain_hash, result, &mut self.network),
+            },
+            SyncMessage::BatchProcessed { sync_type, result } => match sync_type {
+                ChainSegmentProcessId::RangeBatchId(chain_id, epoch) => {
+                    self.range_sync.handle_block_process_result(
+                        &mut self.network,
+                        chain_id,
+                        epoch,
                         result,
-                    } => match process_type {
-                        BlockProcessType::SingleBlock { id } => self
-                            .block_lookups
-                            .single_block_processed(id, result, &mut self.network),
-                        BlockProcessType::ParentLookup { chain_hash } => self
-                            .block_lookups
-                            .parent_block_processed(chain_hash, result, &mut self.network),
-                    },
-                    SyncMessage::BatchProcessed { sync_type, result } => match sync_type {
-                        ChainSegmentProcessId::RangeBatchId(chain_id, epoch) => {
-                            self.range_sync.handle_block_process_result(
-                                &mut self.network,
-                                chain_id,
-                                epoch,
-                                result,
-                            );
+                    );
+                    self.update_sync_state();
+                }
+                ChainSegmentProcessId::BackSyncBatchId(epoch) => {
+                    match self.backfill_sync.on_batch_process_result(
+                        &mut self.network,
+                        epoch,
+                        &result,
+                    ) {
+                        Ok(ProcessResult::Successful) => {}
+                        Ok(ProcessResult::SyncCompleted) => self.update_sync_state(),
+                        Err(error) => {
+                            error!(self.log, "Backfill sync failed"; "error" => ?error);
+                            // Update the global status
                             self.update_sync_state();
                         }
-                        ChainSegmentProcessId::BackSyncBatchId(epoch) => {
-                            match self.backfill_sync.on_batch_process_result(
-                                &mut self.network,
-                                epoch,
-                                &result,
-                            ) {
-                                Ok(ProcessResult::Successful) => {}
-                                Ok(ProcessResult::SyncCompleted) => self.update_sync_state(),
-                                Err(error) => {
-                                    error!(self.log, "Backfill sync failed"; "error" => ?error);
-                                    // Update the global status
-                                    self.update_sync_state();
-                                }
-                            }
-                        }
-                        ChainSegmentProcessId::ParentLookup(chain_hash) => self
-                            .block_lookups
-                            .parent_chain_processed(chain_hash, result, &mut self.network),
-                    },
+                    }
                 }
-            }
+                ChainSegmentProcessId::ParentLookup(chain_hash) => self
+                    .block_lookups
+                    .parent_chain_processed(chain_hash, result, &mut self.network),
+            },
         }
     }
 
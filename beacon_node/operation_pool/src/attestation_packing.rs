@@ -0,0 +1,358 @@
+//! Improves upon the greedy maximum cover of attestations by local search.
+//!
+//! The greedy maximum cover repeatedly chooses the aggregate which covers the most new validators,
+//! which is suboptimal when aggregates overlap heavily. For example, with a limit of two and the
+//! aggregates `{1, 2, 3, 4}`, `{1, 2, 5}` and `{3, 4, 6}`, it chooses the first aggregate and then
+//! one of the others, covering five validators rather than six.
+//!
+//! Starting from the greedy solution, the swap of a chosen aggregate for an unchosen one which
+//! increases the reward of the covered validators the most is made repeatedly, until no swap
+//! increases the reward or the budget is exhausted. Whilst below the limit, unchosen aggregates
+//! may also be added without a swap.
+//!
+//! Aggregates with different `AttestationData` never cover the same validators, so the candidates
+//! are decomposed by `AttestationData` and the effect of a swap is computed from the coverage of
+//! the data of the aggregates involved alone.
+use crate::{metrics, AttMaxCover};
+use std::collections::HashMap;
+use std::ptr;
+use std::time::{Duration, Instant};
+use types::{AttestationData, EthSpec};
+
+/// Limits the effort spent improving upon the greedy packing of attestations.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PackingBudget {
+    /// The maximum number of swaps to make.
+    pub max_iterations: usize,
+    /// The maximum time to spend searching for swaps.
+    pub max_time: Duration,
+}
+
+impl PackingBudget {
+    /// A budget which leaves the greedy packing as it is.
+    pub fn greedy() -> Self {
+        Self {
+            max_iterations: 0,
+            max_time: Duration::from_secs(0),
+        }
+    }
+
+    /// Returns `true` if the budget is too small for any swaps to be made.
+    pub fn is_greedy(&self) -> bool {
+        self.max_iterations == 0 || self.max_time == Duration::from_secs(0)
+    }
+}
+
+/// Improve upon the `greedy` selection from `candidates` of at most `limit` aggregates, within
+/// `budget`.
+///
+/// The `greedy` covers must refer to attestations from `candidates`. The covers returned are from
+/// `candidates`, with their complete covering sets.
+pub fn improve_packing<'a, T: EthSpec>(
+    candidates: &[AttMaxCover<'a, T>],
+    greedy: &[AttMaxCover<'a, T>],
+    limit: usize,
+    budget: PackingBudget,
+) -> Vec<AttMaxCover<'a, T>> {
+    let mut packing = Packing::new(candidates);
+    for cover in greedy {
+        if let Some(index) = candidates
+            .iter()
+            .position(|candidate| ptr::eq(candidate.att, cover.att))
+        {
+            packing.select(index);
+        }
+    }
+
+    let start = Instant::now();
+    for _ in 0..budget.max_iterations {
+        if start.elapsed() >= budget.max_time {
+            break;
+        }
+        match packing.best_move(limit) {
+            Some(Move::Add(added)) => packing.select(added),
+            Some(Move::Swap { removed, added }) => {
+                packing.deselect(removed);
+                packing.select(added);
+            }
+            None => break,
+        }
+        metrics::inc_counter(&metrics::ATTESTATION_LOCAL_SEARCH_SWAPS);
+    }
+
+    packing
+        .selected
+        .iter()
+        .zip(candidates)
+        .filter(|(selected, _)| **selected)
+        .map(|(_, cover)| cover.clone())
+        .collect()
+}
+
+/// A change to the selected aggregates which increases the reward.
+#[derive(Debug, PartialEq)]
+enum Move {
+    Add(usize),
+    Swap { removed: usize, added: usize },
+}
+
+struct Packing<'b, 'a, T: EthSpec> {
+    candidates: &'b [AttMaxCover<'a, T>],
+    /// The index into `coverage` of the `AttestationData` of each candidate.
+    group: Vec<usize>,
+    selected: Vec<bool>,
+    /// For each `AttestationData`, the number of selected aggregates covering each validator.
+    coverage: Vec<HashMap<u64, usize>>,
+}
+
+impl<'b, 'a, T: EthSpec> Packing<'b, 'a, T> {
+    fn new(candidates: &'b [AttMaxCover<'a, T>]) -> Self {
+        let mut groups = HashMap::<&AttestationData, usize>::new();
+        let group = candidates
+            .iter()
+            .map(|cover| {
+                let num_groups = groups.len();
+                *groups.entry(&cover.att.data).or_insert(num_groups)
+            })
+            .collect();
+        Self {
+            candidates,
+            group,
+            selected: vec![false; candidates.len()],
+            coverage: vec![HashMap::new(); groups.len()],
+        }
+    }
+
+    fn select(&mut self, index: usize) {
+        let coverage = &mut self.coverage[self.group[index]];
+        for validator_index in self.candidates[index].fresh_validators_rewards.keys() {
+            *coverage.entry(*validator_index).or_insert(0) += 1;
+        }
+        self.selected[index] = true;
+    }
+
+    fn deselect(&mut self, index: usize) {
+        let coverage = &mut self.coverage[self.group[index]];
+        for validator_index in self.candidates[index].fresh_validators_rewards.keys() {
+            if let Some(count) = coverage.get_mut(validator_index) {
+                *count -= 1;
+                if *count == 0 {
+                    coverage.remove(validator_index);
+                }
+            }
+        }
+        self.selected[index] = false;
+    }
+
+    /// The reward for the validators covered by the candidate at `index` which aren't yet covered.
+    fn gain(&self, index: usize) -> u64 {
+        let coverage = &self.coverage[self.group[index]];
+        self.candidates[index]
+            .fresh_validators_rewards
+            .iter()
+            .filter(|(validator_index, _)| !coverage.contains_key(validator_index))
+            .map(|(_, reward)| reward)
+            .sum()
+    }
+
+    /// The reward for the validators covered only by the selected candidate at `index`.
+    fn loss(&self, index: usize) -> u64 {
+        let coverage = &self.coverage[self.group[index]];
+        self.candidates[index]
+            .fresh_validators_rewards
+            .iter()
+            .filter(|(validator_index, _)| coverage.get(validator_index) == Some(&1))
+            .map(|(_, reward)| reward)
+            .sum()
+    }
+
+    /// The gain of the candidate at `added` once the candidate at `removed`, which has the same
+    /// `AttestationData`, is deselected.
+    fn gain_after_removal(&self, added: usize, removed: usize) -> u64 {
+        let coverage = &self.coverage[self.group[added]];
+        let removed_validators = &self.candidates[removed].fresh_validators_rewards;
+        self.candidates[added]
+            .fresh_validators_rewards
+            .iter()
+            .filter(|(validator_index, _)| match coverage.get(validator_index) {
+                None => true,
+                Some(1) => removed_validators.contains_key(validator_index),
+                Some(_) => false,
+            })
+            .map(|(_, reward)| reward)
+            .sum()
+    }
+
+    /// Returns the move which increases the reward the most, if any.
+    fn best_move(&self, limit: usize) -> Option<Move> {
+        let selected = (0..self.candidates.len())
+            .filter(|&index| self.selected[index])
+            .map(|index| (index, self.loss(index)))
+            .collect::<Vec<_>>();
+
+        let mut best = None;
+        let mut best_increase = 0;
+        for added in (0..self.candidates.len()).filter(|&index| !self.selected[index]) {
+            // A swap can increase the reward by at most the gain of the added candidate.
+            let gain = self.gain(added);
+            if gain <= best_increase {
+                continue;
+            }
+
+            if selected.len() < limit {
+                best = Some(Move::Add(added));
+                best_increase = gain;
+                continue;
+            }
+
+            for &(removed, loss) in &selected {
+                let gain = if self.group[removed] == self.group[added] {
+                    self.gain_after_removal(added, removed)
+                } else {
+                    gain
+                };
+                if gain > loss && gain - loss > best_increase {
+                    best = Some(Move::Swap { removed, added });
+                    best_increase = gain - loss;
+                }
+            }
+        }
+        best
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::max_cover::{maximum_cover, merge_solutions};
+    use types::{AggregateSignature, Attestation, BitList, Hash256, MainnetEthSpec};
+
+    type E = MainnetEthSpec;
+
+    fn attestation(data_root: u64) -> Attestation<E> {
+        Attestation {
+            aggregation_bits: BitList::with_capacity(8).unwrap(),
+            data: AttestationData {
+                beacon_block_root: Hash256::from_low_u64_be(data_root),
+                ..AttestationData::default()
+            },
+            signature: AggregateSignature::empty(),
+        }
+    }
+
+    fn cover<'a>(att: &'a Attestation<E>, validators: &[(u64, u64)]) -> AttMaxCover<'a, E> {
+        AttMaxCover {
+            att,
+            fresh_validators_rewards: validators.iter().copied().collect(),
+        }
+    }
+
+    fn greedy<'a>(candidates: &[AttMaxCover<'a, E>], limit: usize) -> Vec<AttMaxCover<'a, E>> {
+        merge_solutions(
+            maximum_cover(candidates.to_vec(), limit, "test"),
+            vec![],
+            limit,
+        )
+    }
+
+    fn budget(max_iterations: usize) -> PackingBudget {
+        PackingBudget {
+            max_iterations,
+            max_time: Duration::from_secs(60),
+        }
+    }
+
+    fn total_reward(covers: &[AttMaxCover<E>]) -> u64 {
+        let mut rewards = HashMap::new();
+        for cover in covers {
+            for (validator_index, reward) in &cover.fresh_validators_rewards {
+                rewards.insert((cover.att.data.clone(), *validator_index), *reward);
+            }
+        }
+        rewards.values().sum()
+    }
+
+    fn is_packed(covers: &[AttMaxCover<E>], att: &Attestation<E>) -> bool {
+        covers.iter().any(|cover| ptr::eq(cover.att, att))
+    }
+
+    #[test]
+    fn overlapping_aggregates() {
+        let atts = (0..3).map(|_| attestation(0)).collect::<Vec<_>>();
+        let candidates = vec![
+            cover(&atts[0], &[(1, 1), (2, 1), (3, 1), (4, 1)]),
+            cover(&atts[1], &[(1, 1), (2, 1), (5, 1)]),
+            cover(&atts[2], &[(3, 1), (4, 1), (6, 1)]),
+        ];
+        let limit = 2;
+
+        // Greedy chooses the largest aggregate, after which neither of the others covers more
+        // than one new validator.
+        let greedy = greedy(&candidates, limit);
+        assert!(is_packed(&greedy, &atts[0]));
+        assert_eq!(total_reward(&greedy), 5);
+
+        // A single swap finds the optimal packing.
+        let improved = improve_packing(&candidates, &greedy, limit, budget(1));
+        assert_eq!(improved.len(), 2);
+        assert!(is_packed(&improved, &atts[1]));
+        assert!(is_packed(&improved, &atts[2]));
+        assert_eq!(total_reward(&improved), 6);
+
+        // Without a budget the greedy packing is kept.
+        let unimproved = improve_packing(&candidates, &greedy, limit, PackingBudget::greedy());
+        assert_eq!(total_reward(&unimproved), 5);
+    }
+
+    #[test]
+    fn rewards_are_weighted() {
+        // Greedy chooses the aggregate covering the most validators, rather than the aggregate
+        // with the greater reward.
+        let atts = vec![attestation(0), attestation(1)];
+        let candidates = vec![
+            cover(&atts[0], &[(1, 1), (2, 1), (3, 1)]),
+            cover(&atts[1], &[(11, 10), (12, 10)]),
+        ];
+        let limit = 1;
+
+        let greedy = greedy(&candidates, limit);
+        assert!(is_packed(&greedy, &atts[0]));
+
+        let improved = improve_packing(&candidates, &greedy, limit, budget(8));
+        assert_eq!(improved.len(), 1);
+        assert!(is_packed(&improved, &atts[1]));
+        assert_eq!(total_reward(&improved), 20);
+    }
+
+    #[test]
+    fn aggregates_added_below_limit() {
+        let atts = vec![attestation(0), attestation(1)];
+        let candidates = vec![
+            cover(&atts[0], &[(1, 1), (2, 1)]),
+            cover(&atts[1], &[(11, 1)]),
+        ];
+
+        let improved = improve_packing(&candidates, &[], 2, budget(8));
+        assert_eq!(improved.len(), 2);
+        assert_eq!(total_reward(&improved), 3);
+    }
+
+    #[test]
+    fn optimal_packing_is_unchanged() {
+        let atts = vec![attestation(0), attestation(0), attestation(1)];
+        let candidates = vec![
+            cover(&atts[0], &[(1, 1), (2, 1)]),
+            cover(&atts[1], &[(1, 1), (2, 1), (3, 1)]),
+            cover(&atts[2], &[(11, 1)]),
+        ];
+        let limit = 2;
+
+        let greedy = greedy(&candidates, limit);
+        assert_eq!(total_reward(&greedy), 4);
+
+        let improved = improve_packing(&candidates, &greedy, limit, budget(8));
+        assert_eq!(improved.len(), 2);
+        assert!(is_packed(&improved, &atts[1]));
+        assert!(is_packed(&improved, &atts[2]));
+    }
+}
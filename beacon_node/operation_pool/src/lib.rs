@@ -1,5 +1,6 @@
 mod attestation;
 mod attestation_id;
+mod attestation_packing;
 mod attester_slashing;
 mod max_cover;
 mod metrics;
@@ -7,6 +8,7 @@ mod persistence;
 mod sync_aggregate_id;
 
 pub use attestation::AttMaxCover;
+pub use attestation_packing::PackingBudget;
 pub use max_cover::MaxCover;
 pub use persistence::{PersistedOperationPool, PersistedOperationPoolAltair};
 
@@ -65,6 +67,35 @@ pub struct AttestationStats {
     pub max_aggregates_per_data: usize,
 }
 
+/// The quality of a set of attestations chosen for inclusion in a block.
+///
+/// Rewards are the proposer rewards for including the attestations, in Gwei.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct AttestationPackingStats {
+    /// Number of validators whose votes are included for the first time by the attestations.
+    pub new_attesting_indices: usize,
+    /// Reward for the attestations chosen.
+    pub packed_reward: u64,
+    /// Reward for all of the valid attestations in the pool, were there no limit on the number of
+    /// attestations in a block.
+    pub available_reward: u64,
+}
+
+/// The reward for each validator covered by any of `covers`, keyed by target epoch and validator
+/// index.
+fn covered_rewards<T: EthSpec>(covers: &[AttMaxCover<T>]) -> HashMap<(Epoch, u64), u64> {
+    let mut rewards = HashMap::new();
+    for cover in covers {
+        for (&validator_index, &reward) in cover.covering_set() {
+            let covered = rewards
+                .entry((cover.att.data.target.epoch, validator_index))
+                .or_insert(0);
+            *covered = std::cmp::max(*covered, reward);
+        }
+    }
+    rewards
+}
+
 impl From<SyncAggregateError> for OpPoolError {
     fn from(e: SyncAggregateError) -> Self {
         OpPoolError::SyncAggregateError(e)
@@ -280,6 +311,27 @@ impl<T: EthSpec> OperationPool<T> {
         curr_epoch_validity_filter: impl FnMut(&&Attestation<T>) -> bool + Send,
         spec: &ChainSpec,
     ) -> Result<Vec<Attestation<T>>, OpPoolError> {
+        self.get_attestations_with_stats(
+            state,
+            prev_epoch_validity_filter,
+            curr_epoch_validity_filter,
+            PackingBudget::greedy(),
+            spec,
+        )
+        .map(|(attestations, _)| attestations)
+    }
+
+    /// As for `get_attestations`, also returning statistics about the quality of the packing.
+    ///
+    /// From Altair, the greedy maximum cover is improved upon by local search within `budget`.
+    pub fn get_attestations_with_stats(
+        &self,
+        state: &BeaconState<T>,
+        prev_epoch_validity_filter: impl FnMut(&&Attestation<T>) -> bool + Send,
+        curr_epoch_validity_filter: impl FnMut(&&Attestation<T>) -> bool + Send,
+        budget: PackingBudget,
+        spec: &ChainSpec,
+    ) -> Result<(Vec<Attestation<T>>, AttestationPackingStats), OpPoolError> {
         // Attestations for the current fork, which may be from the current or previous epoch.
         let prev_epoch = state.previous_epoch();
         let current_epoch = state.current_epoch();
@@ -324,34 +376,72 @@ impl<T: EthSpec> OperationPool<T> {
             T::MaxAttestations::to_usize()
         };
 
-        let (prev_cover, curr_cover) = rayon::join(
+        // The valid attestations are retained alongside each greedy solution, as candidates for
+        // the local search.
+        let ((prev_candidates, prev_cover), (curr_candidates, curr_cover)) = rayon::join(
             move || {
                 let _timer = metrics::start_timer(&metrics::ATTESTATION_PREV_EPOCH_PACKING_TIME);
                 // If we're in the genesis epoch, just use the current epoch attestations.
                 if prev_epoch == current_epoch {
-                    vec![]
+                    (vec![], vec![])
                 } else {
-                    maximum_cover(prev_epoch_att, prev_epoch_limit, "prev_epoch_attestations")
+                    let candidates = prev_epoch_att.collect::<Vec<_>>();
+                    let cover = maximum_cover(
+                        candidates.clone(),
+                        prev_epoch_limit,
+                        "prev_epoch_attestations",
+                    );
+                    (candidates, cover)
                 }
             },
             move || {
                 let _timer = metrics::start_timer(&metrics::ATTESTATION_CURR_EPOCH_PACKING_TIME);
-                maximum_cover(
-                    curr_epoch_att,
+                let candidates = curr_epoch_att.collect::<Vec<_>>();
+                let cover = maximum_cover(
+                    candidates.clone(),
                     T::MaxAttestations::to_usize(),
                     "curr_epoch_attestations",
-                )
+                );
+                (candidates, cover)
             },
         );
 
         metrics::set_gauge(&metrics::NUM_PREV_EPOCH_ATTESTATIONS, num_prev_valid);
         metrics::set_gauge(&metrics::NUM_CURR_EPOCH_ATTESTATIONS, num_curr_valid);
 
-        Ok(max_cover::merge_solutions(
-            curr_cover,
-            prev_cover,
-            T::MaxAttestations::to_usize(),
-        ))
+        let greedy_solution =
+            max_cover::merge_solutions(curr_cover, prev_cover, T::MaxAttestations::to_usize());
+        let candidates = curr_candidates
+            .into_iter()
+            .chain(prev_candidates)
+            .collect::<Vec<_>>();
+
+        // Prior to Altair the number of previous epoch attestations is limited separately, which
+        // the local search doesn't account for.
+        let solution = if budget.is_greedy() || matches!(state, BeaconState::Base(_)) {
+            greedy_solution
+        } else {
+            let _timer = metrics::start_timer(&metrics::ATTESTATION_LOCAL_SEARCH_TIME);
+            attestation_packing::improve_packing(
+                &candidates,
+                &greedy_solution,
+                T::MaxAttestations::to_usize(),
+                budget,
+            )
+        };
+
+        let packed_rewards = covered_rewards(&solution);
+        let stats = AttestationPackingStats {
+            new_attesting_indices: packed_rewards.len(),
+            packed_reward: packed_rewards.values().sum(),
+            available_reward: covered_rewards(&candidates).values().sum(),
+        };
+        let attestations = solution
+            .into_iter()
+            .map(|cover| cover.object().clone())
+            .collect();
+
+        Ok((attestations, stats))
     }
 
     /// Remove attestations which are too old to be included in a block.
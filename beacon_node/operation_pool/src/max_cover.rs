@@ -96,7 +96,7 @@ where
 }
 
 /// Perform a greedy merge of two max cover solutions, preferring higher-score values.
-pub fn merge_solutions<I1, I2, T>(cover1: I1, cover2: I2, limit: usize) -> Vec<T::Object>
+pub fn merge_solutions<I1, I2, T>(cover1: I1, cover2: I2, limit: usize) -> Vec<T>
 where
     I1: IntoIterator<Item = T>,
     I2: IntoIterator<Item = T>,
@@ -106,7 +106,6 @@ where
         .into_iter()
         .merge_by(cover2, |item1, item2| item1.score() >= item2.score())
         .take(limit)
-        .map(|item| item.object().clone())
         .collect()
 }
 
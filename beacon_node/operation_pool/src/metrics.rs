@@ -11,6 +11,14 @@ lazy_static! {
         "op_pool_attestation_curr_epoch_packing_time",
         "Time to pack current epoch attestations"
     );
+    pub static ref ATTESTATION_LOCAL_SEARCH_TIME: Result<Histogram> = try_create_histogram(
+        "op_pool_attestation_local_search_time",
+        "Time to improve upon the greedy packing of attestations"
+    );
+    pub static ref ATTESTATION_LOCAL_SEARCH_SWAPS: Result<IntCounter> = try_create_int_counter(
+        "op_pool_attestation_local_search_swaps_total",
+        "Number of swaps made to improve upon the greedy packing of attestations"
+    );
     pub static ref NUM_PREV_EPOCH_ATTESTATIONS: Result<IntGauge> = try_create_int_gauge(
         "op_pool_prev_epoch_attestations",
         "Number of valid attestations considered for packing from the previous epoch"
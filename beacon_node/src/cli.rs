@@ -1,4 +1,11 @@
 use clap::{App, Arg};
+use client::config::DEFAULT_CHECKPOINT_SYNC_URL_TIMEOUT;
+use lazy_static::lazy_static;
+
+lazy_static! {
+    static ref DEFAULT_CHECKPOINT_SYNC_URL_TIMEOUT_STR: String =
+        DEFAULT_CHECKPOINT_SYNC_URL_TIMEOUT.to_string();
+}
 
 pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
     App::new("beacon_node")
@@ -9,6 +16,20 @@ pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
         .about("The primary component which connects to the Ethereum 2.0 P2P network and \
                 downloads, verifies and stores blocks. Provides a HTTP API for querying \
                 the beacon chain and publishing messages to the network.")
+        /*
+         * Configuration file.
+         */
+        .arg(
+            Arg::with_name("config-file")
+                .long("config-file")
+                .value_name("PATH")
+                .help("Path to a TOML, YAML or JSON file containing the beacon node configuration. \
+                       Values which are not present in the file take their default values and \
+                       any flags supplied on the command line take precedence over the file. The \
+                       data directory and genesis configuration may only be set on the command \
+                       line.")
+                .takes_value(true)
+        )
         /*
          * Configuration directory locations.
          */
@@ -27,6 +48,49 @@ pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
                 .help("Data directory for the freezer database.")
                 .takes_value(true)
         )
+        .arg(
+            Arg::with_name("freezer-readonly")
+                .long("freezer-readonly")
+                .help("Open an existing freezer database without write access, e.g. a snapshot \
+                       shared between nodes. Finalized states are kept in the hot database rather \
+                       than being migrated to the freezer, so the hot database will keep growing. \
+                       The freezer must contain all historic blocks.")
+                .conflicts_with_all(&["force-restore-point-migration", "reconstruct-historic-states"])
+        )
+        .arg(
+            Arg::with_name("disk-space-warn-threshold")
+                .long("disk-space-warn-threshold")
+                .value_name("MEGABYTES")
+                .help("Log a warning if the free space of the filesystem containing the hot or \
+                       freezer database falls below this many megabytes.")
+                .takes_value(true)
+                .default_value("10240")
+        )
+        .arg(
+            Arg::with_name("disk-space-minimum")
+                .long("disk-space-minimum")
+                .value_name("MEGABYTES")
+                .help("Refuse to start if the free space of the filesystem containing the hot or \
+                       freezer database is below this many megabytes. If the free space falls \
+                       below this value whilst running a critical log is emitted.")
+                .takes_value(true)
+                .default_value("1024")
+        )
+        .arg(
+            Arg::with_name("ignore-disk-space-minimum")
+                .long("ignore-disk-space-minimum")
+                .help("Start even if the free disk space is below --disk-space-minimum. Running \
+                       out of disk space may corrupt the database.")
+                .takes_value(false)
+        )
+        .arg(
+            Arg::with_name("shutdown-on-low-disk-space")
+                .long("shutdown-on-low-disk-space")
+                .help("Shut down the node if the free disk space falls below \
+                       --disk-space-minimum whilst running, to protect the database from \
+                       corruption.")
+                .takes_value(false)
+        )
         /*
          * Network parameters.
          */
@@ -54,10 +118,29 @@ pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
         .arg(
             Arg::with_name("shutdown-after-sync")
                 .long("shutdown-after-sync")
-                .help("Shutdown beacon node as soon as sync is completed. Backfill sync will \
-                       not be performed before shutdown.")
+                .help("Shutdown beacon node as soon as the head is synced to the current slot, \
+                       after saving the chain to disk. Backfill sync will not be performed before \
+                       shutdown.")
                 .takes_value(false),
         )
+        .arg(
+            Arg::with_name("shutdown-after-slot")
+                .long("shutdown-after-slot")
+                .value_name("SLOT")
+                .help("Shutdown beacon node once the head reaches this slot, after saving the \
+                       chain to disk.")
+                .conflicts_with_all(&["shutdown-after-sync", "shutdown-after-finalized-epoch"])
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("shutdown-after-finalized-epoch")
+                .long("shutdown-after-finalized-epoch")
+                .value_name("EPOCH")
+                .help("Shutdown beacon node once the chain finalizes this epoch, after saving the \
+                       chain to disk.")
+                .conflicts_with("shutdown-after-sync")
+                .takes_value(true),
+        )
         .arg(
             Arg::with_name("zero-ports")
                 .long("zero-ports")
@@ -365,7 +448,16 @@ pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
             Arg::with_name("eth1-purge-cache")
                 .long("eth1-purge-cache")
                 .value_name("PURGE-CACHE")
-                .help("Purges the eth1 block and deposit caches")
+                .help("Purges the eth1 block and deposit caches, including the copy persisted \
+                       in the database")
+                .takes_value(false)
+        )
+        .arg(
+            Arg::with_name("eth1-ignore-network-mismatch")
+                .long("eth1-ignore-network-mismatch")
+                .help("Start even if an eth1 endpoint reports a different chain id to the \
+                       deposit contract, or has no deposit contract at the configured address. \
+                       Deposits will not be found on such an endpoint.")
                 .takes_value(false)
         )
         .arg(
@@ -387,15 +479,53 @@ pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
                        can make the node vulnerable to re-orgs.")
                 .takes_value(true)
         )
+        .arg(
+            Arg::with_name("eth1-deposit-log-start-block")
+                .long("eth1-deposit-log-start-block")
+                .value_name("BLOCK_NUMBER")
+                .help("Specifies the first Eth1 block to search for deposit logs and to cache. \
+                       Defaults to the block in which the deposit contract of the network was \
+                       deployed. Deposits made before this block will be missed.")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("eth1-block-cache-truncation")
+                .long("eth1-block-cache-truncation")
+                .value_name("BLOCKS")
+                .help("Specifies the number of Eth1 blocks to retain in the block cache. Defaults \
+                       to a length which covers two Eth1 voting periods.")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("eth1-deposit-snapshot-file")
+                .long("eth1-deposit-snapshot-file")
+                .value_name("FILE")
+                .help("A JSON file containing a snapshot of the deposit tree, as served at \
+                       /eth/v1/beacon/deposit_snapshot. If there is no persisted eth1 cache, \
+                       the deposit cache is initialised from the snapshot and only the deposit \
+                       logs after it are downloaded. When checkpoint syncing, the snapshot is \
+                       otherwise fetched from the checkpoint sync URL if it is available.")
+                .takes_value(true)
+        )
         .arg(
             Arg::with_name("slots-per-restore-point")
                 .long("slots-per-restore-point")
                 .value_name("SLOT_COUNT")
                 .help("Specifies how often a freezer DB restore point should be stored. \
-                       Cannot be changed after initialization. \
+                       Cannot be changed after initialization without \
+                       --force-restore-point-migration. \
                        [default: 8192 (mainnet) or 64 (minimal)]")
                 .takes_value(true)
         )
+        .arg(
+            Arg::with_name("force-restore-point-migration")
+                .long("force-restore-point-migration")
+                .help("If the freezer DB was created with a different --slots-per-restore-point, \
+                       re-space its restore points in the background rather than refusing to \
+                       start. This may take a long time and requires the freezer DB to be complete \
+                       between restore points.")
+                .requires("slots-per-restore-point")
+        )
         .arg(
             Arg::with_name("block-cache-size")
                 .long("block-cache-size")
@@ -403,6 +533,15 @@ pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
                 .help("Specifies how many blocks the database should cache in memory [default: 5]")
                 .takes_value(true)
         )
+        .arg(
+            Arg::with_name("epochs-per-full-hot-state")
+                .long("epochs-per-full-hot-state")
+                .value_name("EPOCHS")
+                .help("Store unfinalized epoch boundary states in full only once every EPOCHS \
+                       epochs, storing the states in between as diffs. This reduces the amount \
+                       written to disk whilst syncing, at the cost of slower state loads.")
+                .takes_value(true)
+        )
         /*
          * Execution Layer Integration
          */
@@ -421,7 +560,11 @@ pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
                 .help("Server endpoint for an execution layer jwt authenticated HTTP \
                        JSON-RPC connection. Uses the same endpoint to populate the \
                        deposit cache. Also enables the --merge flag.\
-                       If not provided, uses the default value of http://127.0.0.1:8551")
+                       If not provided, uses the default value of http://127.0.0.1:8551. \
+                       Multiple comma-separated endpoints may be provided in order of \
+                       priority, in which case requests fail over to the next endpoint \
+                       whilst the first is failing. The deposit cache always uses the first \
+                       endpoint.")
                 .takes_value(true)
                 .requires("execution-jwt")
         )
@@ -431,7 +574,9 @@ pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
                 .value_name("EXECUTION-JWT")
                 .alias("jwt-secrets")
                 .help("File path which contains the hex-encoded JWT secret for the \
-                       execution endpoint provided in the --execution-endpoint flag.")
+                       execution endpoint provided in the --execution-endpoint flag. If \
+                       multiple endpoints are provided, either a single path shared by all \
+                       endpoints or one comma-separated path per endpoint may be provided.")
                 .takes_value(true)
         )
         .arg(
@@ -465,15 +610,65 @@ pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
                 .requires("execution-endpoint")
                 .takes_value(true)
         )
+        .arg(
+            Arg::with_name("suggested-fee-recipient-file")
+                .long("suggested-fee-recipient-file")
+                .value_name("PATH")
+                .help("Path to a file containing one `pubkey: address` pair per line, specifying \
+                       the fee recipient for individual validators. A `default: address` line \
+                       may be used for validators which are not listed. These values take \
+                       precedence over --suggested-fee-recipient, but not over fee recipients \
+                       supplied by the validator client. Addresses must be checksummed. The \
+                       file is reloaded when modified.")
+                .requires("execution-endpoint")
+                .takes_value(true)
+        )
         .arg(
             Arg::with_name("builder")
                 .long("builder")
                 .alias("payload-builder")
                 .alias("payload-builders")
-                .help("The URL of a service compatible with the MEV-boost API.")
+                .help("The URL of a service compatible with the MEV-boost API. Overrides the \
+                       URL in the [builder] section of the --config-file, whilst retaining its \
+                       timeouts and other settings.")
                 .requires("execution-endpoint")
                 .takes_value(true)
         )
+        .arg(
+            Arg::with_name("builder-fallback-skips")
+                .long("builder-fallback-skips")
+                .help("If this node has missed more than this many recent proposals which used \
+                       a payload from the builder, local payloads will be used until the missed \
+                       proposals are sufficiently old.")
+                .default_value("3")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("builder-fallback-epochs-since-finality")
+                .long("builder-fallback-epochs-since-finality")
+                .help("If the chain has not finalized for more than this many epochs, local \
+                       payloads will be used instead of payloads from the builder.")
+                .default_value("3")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("builder-bid-retention-epochs")
+                .long("builder-bid-retention-epochs")
+                .value_name("EPOCHS")
+                .help("The number of epochs for which the signed bids of builders whose payloads \
+                       were used for block production are retained, as evidence of what each \
+                       builder committed to.")
+                .default_value("256")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("broadcast-withheld-blinded-blocks")
+                .long("broadcast-withheld-blinded-blocks")
+                .help("If the builder fails to reveal the payload of a signed blinded block, \
+                       publish the signed blinded block to peers subscribed to the blinded block \
+                       gossip topic, which may still be able to obtain the payload from the relay.")
+                .takes_value(false)
+        )
 
         /*
          * Database purging and compaction.
@@ -505,11 +700,25 @@ pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
                 .long("graffiti")
                 .help(
                     "Specify your custom graffiti to be included in blocks. \
-                    Defaults to the current version and commit, truncated to fit in 32 bytes. "
+                    Defaults to the graffiti chosen by --graffiti-mode. "
                 )
                 .value_name("GRAFFITI")
                 .takes_value(true)
         )
+        .arg(
+            Arg::with_name("graffiti-mode")
+                .long("graffiti-mode")
+                .help(
+                    "Choose the graffiti included in blocks for which the validator supplies none. \
+                    \"client-version\" uses the current version and commit, \"empty\" leaves the \
+                    graffiti zeroed and \"random\" fills it with random printable characters for \
+                    each block. Defaults to \"client-version\"."
+                )
+                .value_name("MODE")
+                .possible_values(&["client-version", "empty", "random"])
+                .conflicts_with("graffiti")
+                .takes_value(true)
+        )
         .arg(
             Arg::with_name("max-skip-slots")
                 .long("max-skip-slots")
@@ -660,12 +869,70 @@ pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
                 .takes_value(true)
                 .conflicts_with("checkpoint-state")
         )
+        .arg(
+            Arg::with_name("checkpoint-sync-url-timeout")
+                .long("checkpoint-sync-url-timeout")
+                .help("Set the timeout for each checkpoint sync request to the remote beacon node, \
+                       in seconds. Failed requests are retried several times with an \
+                       exponential backoff.")
+                .value_name("SECONDS")
+                .takes_value(true)
+                .default_value(&DEFAULT_CHECKPOINT_SYNC_URL_TIMEOUT_STR)
+        )
+        .arg(
+            Arg::with_name("genesis-state-url")
+                .long("genesis-state-url")
+                .help("Download the genesis state from this URL, for networks which do not \
+                       include a genesis state. The state is cached in the data directory so it \
+                       is only downloaded once.")
+                .value_name("URL")
+                .takes_value(true)
+                .conflicts_with_all(&["checkpoint-state", "checkpoint-sync-url"])
+        )
+        .arg(
+            Arg::with_name("genesis-state-url-checksum")
+                .long("genesis-state-url-checksum")
+                .help("The expected SHA-256 hash or state root of the state downloaded from \
+                       --genesis-state-url, as a 0x-prefixed hex string. The node will refuse \
+                       to start if the state does not match.")
+                .value_name("HASH")
+                .takes_value(true)
+                .requires("genesis-state-url")
+        )
+        .arg(
+            Arg::with_name("genesis-state-url-timeout")
+                .long("genesis-state-url-timeout")
+                .help("Set the timeout for each attempt to download the state from \
+                       --genesis-state-url, in seconds.")
+                .value_name("SECONDS")
+                .takes_value(true)
+                .default_value("180")
+        )
         .arg(
             Arg::with_name("reconstruct-historic-states")
                 .long("reconstruct-historic-states")
                 .help("After a checkpoint sync, reconstruct historic states in the database.")
                 .takes_value(false)
         )
+        .arg(
+            Arg::with_name("repair-on-startup")
+                .long("repair-on-startup")
+                .help("Repair the database after a crash has left it inconsistent, instead of \
+                       purging it. Fork choice is re-derived from the finalized checkpoint and the \
+                       stored blocks, the validator pubkey cache is rebuilt and an unreadable \
+                       operation pool is cleared.")
+                .takes_value(false)
+        )
+        .arg(
+            Arg::with_name("read-only")
+                .long("read-only")
+                .help("Serve the existing database without importing blocks or attestations, \
+                       running fork choice or writing to the database. Intended for maintenance \
+                       and forensic analysis via the HTTP API. The database must already exist \
+                       and use the current schema version.")
+                .takes_value(false)
+                .conflicts_with("repair-on-startup")
+        )
         .arg(
             Arg::with_name("validator-monitor-auto")
                 .long("validator-monitor-auto")
@@ -686,8 +953,10 @@ pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
         .arg(
             Arg::with_name("validator-monitor-file")
                 .long("validator-monitor-file")
-                .help("As per --validator-monitor-pubkeys, but the comma-separated list is \
-                    contained within a file at the given path.")
+                .help("As per --validator-monitor-pubkeys, but the pubkeys are contained within \
+                    a file at the given path, one per line. Lines may contain comments starting \
+                    with #. The file is periodically re-read and any new pubkeys are monitored \
+                    without a restart.")
                 .value_name("PATH")
                 .takes_value(true)
         )
@@ -708,4 +977,141 @@ pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
                 .default_value("250")
                 .takes_value(true)
         )
+        .arg(
+            Arg::with_name("prune-payloads")
+                .long("prune-payloads")
+                .help("Delete the execution payloads of finalized blocks from the database. This \
+                       saves space, but payloads must then be reconstructed by the execution \
+                       client when the blocks are requested, e.g. by syncing peers.")
+                .default_value("true")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("finality-delay-warn-epochs")
+                .long("finality-delay-warn-epochs")
+                .value_name("EPOCHS")
+                .help("Log a warning each epoch whilst the chain has not finalized for at least \
+                       this many epochs.")
+                .default_value("5")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("finality-delay-crit-epochs")
+                .long("finality-delay-crit-epochs")
+                .value_name("EPOCHS")
+                .help("Log a critical message each epoch whilst the chain has not finalized for \
+                       at least this many epochs.")
+                .default_value("20")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("finality-delay-pause-backfill")
+                .long("finality-delay-pause-backfill")
+                .help("Pause backfill sync whilst the chain has not finalized for at least \
+                       --finality-delay-crit-epochs, conserving resources for the head of the \
+                       chain.")
+                .takes_value(false)
+        )
+        .arg(
+            Arg::with_name("failure-log-window")
+                .long("failure-log-window")
+                .value_name("SECONDS")
+                .help("Failures which repeat each slot, such as those of fork choice or the \
+                       execution engine, are logged at full severity at most once within this \
+                       many seconds. Repeats are logged at debug level.")
+                .default_value("600")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("verification-pressure-latency")
+                .long("verification-pressure-latency")
+                .value_name("MILLISECONDS")
+                .help("Attestation verification is considered to be under pressure whilst the \
+                       moving average of the time taken to verify a batch of gossip \
+                       attestations exceeds this many milliseconds.")
+                .default_value("250")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("verification-pressure-batches")
+                .long("verification-pressure-batches")
+                .value_name("COUNT")
+                .help("Attestation verification is considered to be under pressure whilst more \
+                       than this many batches of gossip attestations are being verified \
+                       concurrently.")
+                .default_value("8")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("cache-memory-budget")
+                .long("cache-memory-budget")
+                .value_name("MEGABYTES")
+                .help("The maximum estimated memory of the beacon chain's caches, such as the \
+                       snapshot and shuffling caches. Entries are evicted from each cache in \
+                       proportion to its usage when the total exceeds this budget. The head \
+                       state is exempt. No limit is applied by default.")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("prewarm-max-epochs")
+                .long("prewarm-max-epochs")
+                .value_name("EPOCHS")
+                .help("Whilst the head lags the current slot by more than an epoch, the head state \
+                       is advanced in the background to pre-warm the committee caches for the \
+                       current epoch. This is skipped if the state must be advanced more than \
+                       this many epochs. Set to 0 to disable.")
+                .default_value("4")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("heal-head-divergence")
+                .long("heal-head-divergence")
+                .help("The canonical head, fork choice and the head tracker are checked for \
+                       agreement upon the head at startup and each slot, with any divergence \
+                       logged. If this flag is set, divergence is also healed by re-running fork \
+                       choice and registering the head with the head tracker.")
+                .takes_value(false)
+        )
+        .arg(
+            Arg::with_name("log-inconsistent-payload-transactions")
+                .long("log-inconsistent-payload-transactions")
+                .help("When a payload reconstructed by the execution engine does not match the \
+                       header of its block, log each of its transactions. The transactions may \
+                       be very large, so this is intended for debugging only.")
+                .takes_value(false)
+        )
+        .arg(
+            Arg::with_name("allow-ancient-attestation-production")
+                .long("allow-ancient-attestation-production")
+                .help("Produce attestations to slots too old for the block roots of the head \
+                       state by loading a historical state, instead of refusing them. Loading \
+                       the state may be slow, so this is intended for lagging validator clients \
+                       and testing.")
+                .takes_value(false)
+        )
+        .arg(
+            Arg::with_name("pre-finalization-block-cache-size")
+                .long("pre-finalization-block-cache-size")
+                .value_name("COUNT")
+                .help("The maximum number of block roots from before finalization to cache, so \
+                       that attestations to them can be rejected without reading the database.")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("checkpoint-state-cache")
+                .long("checkpoint-state-cache")
+                .help("Keep the states of the finalized and justified checkpoints in memory, so \
+                       that they can be served without reading the database. This costs the \
+                       memory of two states.")
+                .default_value("true")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("self-test")
+                .long("self-test")
+                .help("Before joining the network, check the execution engine, builder, eth1 \
+                       endpoints, data directory and system clock, logging a readiness report. \
+                       The report is also served at /lighthouse/health/self_test.")
+                .takes_value(false)
+        )
 }
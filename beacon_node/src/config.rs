@@ -1,6 +1,9 @@
+use beacon_chain::chain_config::ShutdownTarget;
+use beacon_chain::GraffitiMode;
 use clap::ArgMatches;
 use clap_utils::flags::DISABLE_MALLOC_TUNING_FLAG;
-use client::{ClientConfig, ClientGenesis};
+use client::eth2_network::ResolvedNetwork;
+use client::{BuilderConfig, ClientConfig, ClientGenesis};
 use directory::{DEFAULT_BEACON_NODE_DIR, DEFAULT_NETWORK_DIR, DEFAULT_ROOT_DIR};
 use environment::RuntimeContext;
 use genesis::Eth1Endpoint;
@@ -8,17 +11,20 @@ use http_api::TlsConfig;
 use lighthouse_network::{multiaddr::Protocol, Enr, Multiaddr, NetworkConfig, PeerIdSerialized};
 use sensitive_url::SensitiveUrl;
 use slog::{info, warn, Logger};
-use std::cmp;
-use std::cmp::max;
 use std::fmt::Debug;
 use std::fmt::Write;
 use std::fs;
 use std::net::{IpAddr, Ipv4Addr, ToSocketAddrs};
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
-use types::{Checkpoint, Epoch, EthSpec, Hash256, PublicKeyBytes, GRAFFITI_BYTES_LEN};
+use types::{
+    Checkpoint, Epoch, EthSpec, Graffiti, Hash256, PublicKeyBytes, Slot, GRAFFITI_BYTES_LEN,
+};
 use unused_port::{unused_tcp_port, unused_udp_port};
 
+/// The flag used to supply a config file to the beacon node.
+pub const CONFIG_FILE_FLAG: &str = "config-file";
+
 /// Gets the fully-initialized global client.
 ///
 /// The top-level `clap` arguments should be provided as `cli_args`.
@@ -33,11 +39,41 @@ pub fn get_config<E: EthSpec>(
     let spec = &context.eth2_config.spec;
     let log = context.log();
 
-    let mut client_config = ClientConfig {
-        data_dir: get_data_dir(cli_args),
-        ..Default::default()
+    // Values from the config file (if any) are overridden by any values supplied via the CLI.
+    let mut client_config = if let Some(path) = cli_args.value_of(CONFIG_FILE_FLAG) {
+        ClientConfig::from_file(Path::new(path), log)?
+    } else {
+        ClientConfig::default()
     };
 
+    // The network named in the config file takes precedence, the CLI may only select the same
+    // network. The environment has already been built for this network.
+    let config_network = match cli_args.value_of(CONFIG_FILE_FLAG) {
+        Some(path) => ClientConfig::network_from_file(Path::new(path))?,
+        None => None,
+    };
+    client_config.eth2_network = clap_utils::select_network(cli_args, config_network)?;
+    client_config.data_dir = get_data_dir_with_network_dir(
+        cli_args,
+        &directory::network_dir_name(&client_config.eth2_network),
+    );
+
+    if let Some(log_format) = cli_parse_optional(cli_args, "log-format")? {
+        client_config.log_format = Some(log_format);
+    }
+
+    if let Some(max_size) = cli_parse_optional(cli_args, "logfile-max-size")? {
+        client_config.logfile_max_size = max_size;
+    }
+
+    if let Some(max_number) = cli_parse_optional(cli_args, "logfile-max-number")? {
+        client_config.logfile_max_number = max_number;
+    }
+
+    if let Some(overrides) = cli_parse_optional(cli_args, "log-level-overrides")? {
+        client_config.log_level_overrides = overrides;
+    }
+
     // If necessary, remove any existing database and configuration
     if client_config.data_dir.exists() && cli_args.is_present("purge-db") {
         // Remove the chain_db.
@@ -94,13 +130,13 @@ pub fn get_config<E: EthSpec>(
         client_config.http_api.enabled = true;
     }
 
-    if let Some(address) = cli_args.value_of("http-address") {
+    if let Some(address) = cli_value_of(cli_args, "http-address") {
         client_config.http_api.listen_addr = address
             .parse::<IpAddr>()
             .map_err(|_| "http-address is not a valid IP address.")?;
     }
 
-    if let Some(port) = cli_args.value_of("http-port") {
+    if let Some(port) = cli_value_of(cli_args, "http-port") {
         client_config.http_api.listen_port = port
             .parse::<u16>()
             .map_err(|_| "http-port is not a valid u16.")?;
@@ -146,13 +182,13 @@ pub fn get_config<E: EthSpec>(
         client_config.http_metrics.enabled = true;
     }
 
-    if let Some(address) = cli_args.value_of("metrics-address") {
+    if let Some(address) = cli_value_of(cli_args, "metrics-address") {
         client_config.http_metrics.listen_addr = address
             .parse::<IpAddr>()
             .map_err(|_| "metrics-address is not a valid IP address.")?;
     }
 
-    if let Some(port) = cli_args.value_of("metrics-port") {
+    if let Some(port) = cli_value_of(cli_args, "metrics-port") {
         client_config.http_metrics.listen_port = port
             .parse::<u16>()
             .map_err(|_| "metrics-port is not a valid u16.")?;
@@ -172,9 +208,8 @@ pub fn get_config<E: EthSpec>(
      */
     if let Some(monitoring_endpoint) = cli_args.value_of("monitoring-endpoint") {
         client_config.monitoring_api = Some(monitoring_api::Config {
-            db_path: None,
-            freezer_db_path: None,
             monitoring_endpoint: monitoring_endpoint.to_string(),
+            ..client_config.monitoring_api.take().unwrap_or_default()
         });
     }
 
@@ -232,7 +267,7 @@ pub fn get_config<E: EthSpec>(
         client_config.eth1.endpoints = Eth1Endpoint::NoAuth(endpoints);
     }
 
-    if let Some(val) = cli_args.value_of("eth1-blocks-per-log-query") {
+    if let Some(val) = cli_value_of(cli_args, "eth1-blocks-per-log-query") {
         client_config.eth1.blocks_per_log_query = val
             .parse()
             .map_err(|_| "eth1-blocks-per-log-query is not a valid integer".to_string())?;
@@ -240,6 +275,11 @@ pub fn get_config<E: EthSpec>(
 
     if cli_args.is_present("eth1-purge-cache") {
         client_config.eth1.purge_cache = true;
+        client_config.purge_eth1_cache_on_startup = true;
+    }
+
+    if cli_args.is_present("eth1-ignore-network-mismatch") {
+        client_config.eth1.ignore_network_mismatch = true;
     }
 
     if let Some(follow_distance) =
@@ -276,29 +316,58 @@ pub fn get_config<E: EthSpec>(
         // stakers. The merge is already complicated enough.
         client_config.sync_eth1_chain = true;
 
-        // Parse a single execution endpoint, logging warnings if multiple endpoints are supplied.
-        let execution_endpoint =
-            parse_only_one_value(endpoints, SensitiveUrl::parse, "--execution-endpoint", log)?;
+        // Parse the execution endpoints, in order of priority.
+        let execution_endpoints = endpoints
+            .split(',')
+            .map(SensitiveUrl::parse)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("--execution-endpoint contains an invalid value {:?}", e))?;
 
-        // Parse a single JWT secret, logging warnings if multiple are supplied.
+        // Parse one JWT secret per endpoint, or a single secret which is shared by all endpoints.
         //
         // JWTs are required if `--execution-endpoint` is supplied.
         let secret_files: String = clap_utils::parse_required(cli_args, "execution-jwt")?;
-        let secret_file =
-            parse_only_one_value(&secret_files, PathBuf::from_str, "--execution-jwt", log)?;
+        let mut secret_files = secret_files
+            .split(',')
+            .map(PathBuf::from_str)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("--execution-jwt contains an invalid value {:?}", e))?;
+        if secret_files.len() == 1 {
+            secret_files = vec![secret_files[0].clone(); execution_endpoints.len()];
+        } else if secret_files.len() != execution_endpoints.len() {
+            return Err(format!(
+                "--execution-jwt must contain either one secret or one secret per endpoint, \
+                 {} secrets were provided for {} endpoints",
+                secret_files.len(),
+                execution_endpoints.len()
+            ));
+        }
+
+        // The deposit contract is followed using the primary endpoint.
+        let execution_endpoint = execution_endpoints[0].clone();
+        let secret_file = secret_files[0].clone();
 
         // Parse and set the payload builder, if any.
         if let Some(endpoint) = cli_args.value_of("builder") {
             let payload_builder =
                 parse_only_one_value(endpoint, SensitiveUrl::parse, "--builder", log)?;
-            el_config.builder_url = Some(payload_builder);
+            // Retain any other builder settings from the config file.
+            client_config.builder = Some(match client_config.builder.take() {
+                Some(builder_config) => BuilderConfig {
+                    url: payload_builder,
+                    ..builder_config
+                },
+                None => BuilderConfig::new(payload_builder),
+            });
         }
 
         // Set config values from parse values.
-        el_config.secret_files = vec![secret_file.clone()];
-        el_config.execution_endpoints = vec![execution_endpoint.clone()];
+        el_config.secret_files = secret_files;
+        el_config.execution_endpoints = execution_endpoints;
         el_config.suggested_fee_recipient =
             clap_utils::parse_optional(cli_args, "suggested-fee-recipient")?;
+        el_config.suggested_fee_recipient_file =
+            clap_utils::parse_optional(cli_args, "suggested-fee-recipient-file")?;
         el_config.jwt_id = clap_utils::parse_optional(cli_args, "execution-jwt-id")?;
         el_config.jwt_version = clap_utils::parse_optional(cli_args, "execution-jwt-version")?;
         el_config.default_datadir = client_config.data_dir.clone();
@@ -328,9 +397,35 @@ pub fn get_config<E: EthSpec>(
         client_config.freezer_db_path = Some(PathBuf::from(freezer_dir));
     }
 
+    if let Some(warn_threshold_mb) = cli_parse_optional(cli_args, "disk-space-warn-threshold")? {
+        client_config.disk_space.warn_threshold_mb = warn_threshold_mb;
+    }
+
+    if let Some(minimum_mb) = cli_parse_optional(cli_args, "disk-space-minimum")? {
+        client_config.disk_space.minimum_mb = minimum_mb;
+    }
+
+    if cli_args.is_present("ignore-disk-space-minimum") {
+        client_config.disk_space.ignore_minimum_at_startup = true;
+    }
+
+    if cli_args.is_present("shutdown-on-low-disk-space") {
+        client_config.disk_space.shutdown_below_minimum = true;
+    }
+
     let (sprp, sprp_explicit) = get_slots_per_restore_point::<E>(cli_args)?;
-    client_config.store.slots_per_restore_point = sprp;
-    client_config.store.slots_per_restore_point_set_explicitly = sprp_explicit;
+    if sprp_explicit || !cli_args.is_present(CONFIG_FILE_FLAG) {
+        client_config.store.slots_per_restore_point = sprp;
+        client_config.store.slots_per_restore_point_set_explicitly = sprp_explicit;
+    }
+
+    if cli_args.is_present("force-restore-point-migration") {
+        client_config.store.force_restore_point_migration = true;
+    }
+
+    if cli_args.is_present("freezer-readonly") {
+        client_config.store.freezer_readonly = true;
+    }
 
     if let Some(block_cache_size) = cli_args.value_of("block-cache-size") {
         client_config.store.block_cache_size = block_cache_size
@@ -338,8 +433,16 @@ pub fn get_config<E: EthSpec>(
             .map_err(|_| "block-cache-size is not a valid integer".to_string())?;
     }
 
-    client_config.store.compact_on_init = cli_args.is_present("compact-db");
-    if let Some(compact_on_prune) = cli_args.value_of("auto-compact-db") {
+    if let Some(epochs_per_full_hot_state) =
+        cli_parse_optional(cli_args, "epochs-per-full-hot-state")?
+    {
+        client_config.store.epochs_per_full_hot_state = Some(epochs_per_full_hot_state);
+    }
+
+    if cli_args.is_present("compact-db") {
+        client_config.store.compact_on_init = true;
+    }
+    if let Some(compact_on_prune) = cli_value_of(cli_args, "auto-compact-db") {
         client_config.store.compact_on_prune = compact_on_prune
             .parse()
             .map_err(|_| "auto-compact-db takes a boolean".to_string())?;
@@ -371,19 +474,24 @@ pub fn get_config<E: EthSpec>(
      */
     let eth2_network_config = context
         .eth2_network_config
-        .as_ref()
+        .clone()
         .ok_or("Context is missing eth2 network config")?;
+    let network = ResolvedNetwork::from_network_config::<E>(eth2_network_config)?;
+    client_config.apply_network::<E>(&network);
 
-    client_config.eth1.deposit_contract_address = format!("{:?}", spec.deposit_contract_address);
-    client_config.eth1.deposit_contract_deploy_block =
-        eth2_network_config.deposit_contract_deploy_block;
-    client_config.eth1.lowest_cached_block_number =
-        client_config.eth1.deposit_contract_deploy_block;
-    client_config.eth1.follow_distance = spec.eth1_follow_distance;
-    client_config.eth1.node_far_behind_seconds =
-        max(5, spec.eth1_follow_distance / 2) * spec.seconds_per_eth1_block;
-    client_config.eth1.chain_id = spec.deposit_chain_id.into();
-    client_config.eth1.set_block_cache_truncation::<E>(spec);
+    // The network sets defaults for these values, so they must be overridden afterwards.
+    if let Some(start_block) = clap_utils::parse_optional(cli_args, "eth1-deposit-log-start-block")?
+    {
+        client_config.eth1.deposit_contract_deploy_block = start_block;
+        client_config.eth1.lowest_cached_block_number = start_block;
+    }
+
+    if let Some(truncation) = clap_utils::parse_optional(cli_args, "eth1-block-cache-truncation")? {
+        client_config.eth1.block_cache_truncation = Some(truncation);
+    }
+
+    client_config.eth1_deposit_snapshot_file =
+        clap_utils::parse_optional(cli_args, "eth1-deposit-snapshot-file")?;
 
     info!(
         log,
@@ -392,19 +500,25 @@ pub fn get_config<E: EthSpec>(
         "address" => &client_config.eth1.deposit_contract_address
     );
 
-    // Only append network config bootnodes if discovery is not disabled
-    if !client_config.network.disable_discovery {
-        if let Some(boot_nodes) = &eth2_network_config.boot_enr {
-            client_config
-                .network
-                .boot_nodes_enr
-                .extend_from_slice(boot_nodes)
-        }
+    if let Some(timeout) = cli_parse_optional(cli_args, "checkpoint-sync-url-timeout")? {
+        client_config.checkpoint_sync_url_timeout = timeout;
+    }
+
+    if let Some(timeout) = cli_parse_optional(cli_args, "genesis-state-url-timeout")? {
+        client_config.genesis_state_url_timeout = timeout;
     }
 
     client_config.genesis = if let Some(genesis_state_bytes) =
-        eth2_network_config.genesis_state_bytes.clone()
+        network.genesis_state_bytes().map(<[u8]>::to_vec)
     {
+        if cli_args.is_present("genesis-state-url") {
+            return Err(
+                "The --genesis-state-url flag cannot be used as this network already has a \
+                 genesis state"
+                    .to_string(),
+            );
+        }
+
         // Set up weak subjectivity sync, or start from the hardcoded genesis state.
         if let (Some(initial_state_path), Some(initial_block_path)) = (
             cli_args.value_of("checkpoint-state"),
@@ -453,14 +567,32 @@ pub fn get_config<E: EthSpec>(
                     .to_string(),
             );
         }
-        ClientGenesis::DepositContract
+
+        if let Some(url) = cli_args.value_of("genesis-state-url") {
+            let url = SensitiveUrl::parse(url)
+                .map_err(|e| format!("Invalid genesis state URL: {:?}", e))?;
+            let checksum = clap_utils::parse_optional(cli_args, "genesis-state-url-checksum")?;
+
+            ClientGenesis::RemoteSsz { url, checksum }
+        } else {
+            ClientGenesis::DepositContract
+        }
     };
 
     if cli_args.is_present("reconstruct-historic-states") {
         client_config.chain.reconstruct_historic_states = true;
     }
 
-    let raw_graffiti = if let Some(graffiti) = cli_args.value_of("graffiti") {
+    if cli_args.is_present("repair-on-startup") {
+        client_config.chain.repair_on_startup = true;
+    }
+
+    if cli_args.is_present("read-only") {
+        client_config.chain.read_only = true;
+        client_config.store.read_only = true;
+    }
+
+    if let Some(graffiti) = cli_args.value_of("graffiti") {
         if graffiti.len() > GRAFFITI_BYTES_LEN {
             return Err(format!(
                 "Your graffiti is too long! {} bytes maximum!",
@@ -468,16 +600,17 @@ pub fn get_config<E: EthSpec>(
             ));
         }
 
-        graffiti.as_bytes()
+        let mut custom_graffiti = Graffiti::default();
+        custom_graffiti.0[..graffiti.len()].copy_from_slice(graffiti.as_bytes());
+        client_config.graffiti = GraffitiMode::Custom(custom_graffiti);
+    } else if let Some(graffiti_mode) = cli_args.value_of("graffiti-mode") {
+        client_config.graffiti = graffiti_mode.parse()?;
     } else if cli_args.is_present("private") {
-        b""
-    } else {
-        lighthouse_version::VERSION.as_bytes()
-    };
-
-    let trimmed_graffiti_len = cmp::min(raw_graffiti.len(), GRAFFITI_BYTES_LEN);
-    client_config.graffiti.0[..trimmed_graffiti_len]
-        .copy_from_slice(&raw_graffiti[..trimmed_graffiti_len]);
+        client_config.graffiti = GraffitiMode::Empty;
+    } else if !cli_args.is_present(CONFIG_FILE_FLAG) {
+        // Without a config file to retain the graffiti from, default to the client version.
+        client_config.graffiti = GraffitiMode::ClientVersion;
+    }
 
     if let Some(wss_checkpoint) = cli_args.value_of("wss-checkpoint") {
         let mut split = wss_checkpoint.split(':');
@@ -602,34 +735,107 @@ pub fn get_config<E: EthSpec>(
             .extend_from_slice(&pubkeys);
     }
 
-    if let Some(path) = cli_args.value_of("validator-monitor-file") {
-        let string = fs::read(path)
-            .map_err(|e| format!("Unable to read --validator-monitor-file: {}", e))
-            .and_then(|bytes| {
-                String::from_utf8(bytes)
-                    .map_err(|e| format!("--validator-monitor-file is not utf8: {}", e))
-            })?;
-        let pubkeys = string
-            .trim_end() // Remove trailing white space
-            .split(',')
-            .map(PublicKeyBytes::from_str)
-            .collect::<Result<Vec<_>, _>>()
-            .map_err(|e| format!("Invalid --validator-monitor-file contents: {:?}", e))?;
-        client_config
-            .validator_monitor_pubkeys
-            .extend_from_slice(&pubkeys);
+    if let Some(path) = clap_utils::parse_optional(cli_args, "validator-monitor-file")? {
+        client_config.validator_monitor_pubkeys_file = Some(path);
     }
 
     if cli_args.is_present("disable-lock-timeouts") {
         client_config.chain.enable_lock_timeouts = false;
     }
 
-    if let Some(timeout) =
-        clap_utils::parse_optional(cli_args, "fork-choice-before-proposal-timeout")?
-    {
+    if let Some(timeout) = cli_parse_optional(cli_args, "fork-choice-before-proposal-timeout")? {
         client_config.chain.fork_choice_before_proposal_timeout_ms = timeout;
     }
 
+    if let Some(prune_payloads) = cli_parse_optional(cli_args, "prune-payloads")? {
+        client_config.chain.prune_payloads = prune_payloads;
+    }
+
+    if let Some(skips) = cli_parse_optional(cli_args, "builder-fallback-skips")? {
+        client_config.chain.builder_fallback_skips = skips;
+    }
+
+    if let Some(epochs) = cli_parse_optional(cli_args, "builder-fallback-epochs-since-finality")? {
+        client_config.chain.builder_fallback_epochs_since_finality = epochs;
+    }
+
+    if let Some(epochs) = cli_parse_optional(cli_args, "builder-bid-retention-epochs")? {
+        client_config.chain.builder_bid_retention_epochs = epochs;
+    }
+
+    if cli_args.is_present("broadcast-withheld-blinded-blocks") {
+        client_config.chain.broadcast_withheld_blinded_blocks = true;
+    }
+
+    if let Some(epochs) = cli_parse_optional(cli_args, "finality-delay-warn-epochs")? {
+        client_config.chain.finality_delay_warn_epochs = epochs;
+    }
+
+    if let Some(epochs) = cli_parse_optional(cli_args, "finality-delay-crit-epochs")? {
+        client_config.chain.finality_delay_crit_epochs = epochs;
+    }
+
+    if cli_args.is_present("finality-delay-pause-backfill") {
+        client_config.chain.finality_delay_pause_backfill = true;
+    }
+
+    if let Some(secs) = cli_parse_optional(cli_args, "failure-log-window")? {
+        client_config.chain.failure_log_window_secs = secs;
+    }
+
+    if let Some(millis) = cli_parse_optional(cli_args, "verification-pressure-latency")? {
+        client_config.chain.verification_pressure_latency_ms = millis;
+    }
+
+    if let Some(count) = cli_parse_optional(cli_args, "verification-pressure-batches")? {
+        client_config.chain.verification_pressure_max_batches = count;
+    }
+
+    if let Some(mb) = cli_parse_optional(cli_args, "cache-memory-budget")? {
+        client_config.chain.cache_memory_budget_mb = Some(mb);
+    }
+
+    if let Some(epochs) = cli_parse_optional(cli_args, "prewarm-max-epochs")? {
+        client_config.chain.prewarm_max_epochs = epochs;
+    }
+
+    if cli_args.is_present("heal-head-divergence") {
+        client_config.chain.heal_head_divergence = true;
+    }
+
+    if cli_args.is_present("log-inconsistent-payload-transactions") {
+        client_config.chain.log_inconsistent_payload_transactions = true;
+    }
+
+    if cli_args.is_present("allow-ancient-attestation-production") {
+        client_config.chain.allow_ancient_attestation_production = true;
+    }
+
+    if let Some(size) = cli_parse_optional(cli_args, "pre-finalization-block-cache-size")? {
+        client_config.chain.pre_finalization_block_cache_size = size;
+    }
+
+    if let Some(enabled) = cli_parse_optional(cli_args, "checkpoint-state-cache")? {
+        client_config.chain.cache_checkpoint_states = enabled;
+    }
+
+    if cli_args.is_present("self-test") {
+        client_config.self_test = true;
+    }
+
+    if cli_args.is_present("shutdown-after-sync") {
+        client_config.chain.shutdown_after = Some(ShutdownTarget::HeadSynced);
+    }
+
+    if let Some(slot) = clap_utils::parse_optional(cli_args, "shutdown-after-slot")? {
+        client_config.chain.shutdown_after = Some(ShutdownTarget::Slot(Slot::new(slot)));
+    }
+
+    if let Some(epoch) = clap_utils::parse_optional(cli_args, "shutdown-after-finalized-epoch")? {
+        client_config.chain.shutdown_after =
+            Some(ShutdownTarget::FinalizedEpoch(Epoch::new(epoch)));
+    }
+
     Ok(client_config)
 }
 
@@ -656,24 +862,20 @@ pub fn set_network_config(
         config.import_all_attestations = true;
     }
 
-    if cli_args.is_present("shutdown-after-sync") {
-        config.shutdown_after_sync = true;
-    }
-
-    if let Some(listen_address_str) = cli_args.value_of("listen-address") {
+    if let Some(listen_address_str) = cli_value_of(cli_args, "listen-address") {
         let listen_address = listen_address_str
             .parse()
             .map_err(|_| format!("Invalid listen address: {:?}", listen_address_str))?;
         config.listen_address = listen_address;
     }
 
-    if let Some(target_peers_str) = cli_args.value_of("target-peers") {
+    if let Some(target_peers_str) = cli_value_of(cli_args, "target-peers") {
         config.target_peers = target_peers_str
             .parse::<usize>()
             .map_err(|_| format!("Invalid number of target peers: {}", target_peers_str))?;
     }
 
-    if let Some(port_str) = cli_args.value_of("port") {
+    if let Some(port_str) = cli_value_of(cli_args, "port") {
         let port = port_str
             .parse::<u16>()
             .map_err(|_| format!("Invalid port: {}", port_str))?;
@@ -688,7 +890,7 @@ pub fn set_network_config(
         config.discovery_port = port;
     }
 
-    if let Some(value) = cli_args.value_of("network-load") {
+    if let Some(value) = cli_value_of(cli_args, "network-load") {
         let network_load = value
             .parse::<u8>()
             .map_err(|_| format!("Invalid integer: {}", value))?;
@@ -846,6 +1048,12 @@ pub fn set_network_config(
 
 /// Gets the datadir which should be used.
 pub fn get_data_dir(cli_args: &ArgMatches) -> PathBuf {
+    get_data_dir_with_network_dir(cli_args, &directory::get_network_dir(cli_args))
+}
+
+/// Gets the datadir which should be used, where `network_dir` is the directory name of the
+/// selected network.
+fn get_data_dir_with_network_dir(cli_args: &ArgMatches, network_dir: &str) -> PathBuf {
     // Read the `--datadir` flag.
     //
     // If it's not present, try and find the home directory (`~`) and push the default data
@@ -857,13 +1065,38 @@ pub fn get_data_dir(cli_args: &ArgMatches) -> PathBuf {
         .or_else(|| {
             dirs::home_dir().map(|home| {
                 home.join(DEFAULT_ROOT_DIR)
-                    .join(directory::get_network_dir(cli_args))
+                    .join(network_dir)
                     .join(DEFAULT_BEACON_NODE_DIR)
             })
         })
         .unwrap_or_else(|| PathBuf::from("."))
 }
 
+/// Returns the value of the argument `name`.
+///
+/// If a config file has been supplied, values which `clap` has populated from the default value of
+/// the argument are ignored so that they don't override the values in the file.
+fn cli_value_of<'a>(cli_args: &'a ArgMatches, name: &str) -> Option<&'a str> {
+    if cli_args.occurrences_of(name) == 0 && cli_args.is_present(CONFIG_FILE_FLAG) {
+        None
+    } else {
+        cli_args.value_of(name)
+    }
+}
+
+/// As per `clap_utils::parse_optional`, but with the default value behaviour of `cli_value_of`.
+fn cli_parse_optional<T>(cli_args: &ArgMatches, name: &str) -> Result<Option<T>, String>
+where
+    T: FromStr,
+    <T as FromStr>::Err: std::fmt::Display,
+{
+    if cli_args.occurrences_of(name) == 0 && cli_args.is_present(CONFIG_FILE_FLAG) {
+        Ok(None)
+    } else {
+        clap_utils::parse_optional(cli_args, name)
+    }
+}
+
 /// Get the `slots_per_restore_point` value to use for the database.
 ///
 /// Return `(sprp, set_explicitly)` where `set_explicitly` is `true` if the user provided the value.
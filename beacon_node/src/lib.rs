@@ -12,12 +12,15 @@ use beacon_chain::{
 };
 use clap::ArgMatches;
 pub use cli::cli_app;
-pub use client::{Client, ClientBuilder, ClientConfig, ClientGenesis};
-pub use config::{get_config, get_data_dir, get_slots_per_restore_point, set_network_config};
+use client::config::DataDirLocation;
+pub use client::{Client, ClientBuilder, ClientConfig, ClientGenesis, NetworkSelection};
+pub use config::{
+    get_config, get_data_dir, get_slots_per_restore_point, set_network_config, CONFIG_FILE_FLAG,
+};
 use environment::RuntimeContext;
 pub use eth2_config::Eth2Config;
 use slasher::Slasher;
-use slog::{info, warn};
+use slog::{debug, info, warn};
 use std::ops::{Deref, DerefMut};
 use std::sync::Arc;
 use types::EthSpec;
@@ -61,18 +64,48 @@ impl<E: EthSpec> ProductionBeaconNode<E> {
         let client_genesis = client_config.genesis.clone();
         let store_config = client_config.store.clone();
         let log = context.log().clone();
+        client_config.validate_at_startup::<E>(&spec, &log)?;
         let datadir = client_config.create_data_dir()?;
         let db_path = client_config.create_db_path()?;
         let freezer_db_path = client_config.create_freezer_db_path()?;
         let executor = context.executor.clone();
 
-        if let Some(legacy_dir) = client_config.get_existing_legacy_data_dir() {
-            warn!(
+        match client_config.get_data_dir_and_location() {
+            (location, DataDirLocation::Legacy) => warn!(
                 log,
                 "Legacy datadir location";
-                "msg" => "this occurs when using relative paths for a datadir location",
-                "location" => ?legacy_dir,
-            )
+                "msg" => DataDirLocation::Legacy.reason(),
+                "location" => ?location,
+            ),
+            (location, DataDirLocation::Modern) => info!(
+                log,
+                "Using datadir";
+                "reason" => DataDirLocation::Modern.reason(),
+                "location" => ?location,
+            ),
+        }
+
+        let effective_config = client_config.effective()?;
+        let effective_config_path = client_config.effective_config_path();
+        if client_config.chain.read_only {
+            debug!(
+                log,
+                "Not writing effective config in read-only mode";
+                "path" => ?effective_config_path,
+            );
+        } else if let Err(e) = client_config.write_effective(&effective_config_path) {
+            warn!(
+                log,
+                "Unable to write effective config";
+                "error" => e,
+                "path" => ?effective_config_path,
+            );
+        } else {
+            debug!(
+                log,
+                "Wrote effective config";
+                "path" => ?effective_config_path,
+            );
         }
 
         if !client_config.chain.enable_lock_timeouts {
@@ -82,8 +115,11 @@ impl<E: EthSpec> ProductionBeaconNode<E> {
 
         let builder = ClientBuilder::new(context.eth_spec_instance.clone())
             .runtime_context(context)
+            .log_level_overrides(client_config.log_level_overrides.clone())?
             .chain_spec(spec)
             .http_api_config(client_config.http_api.clone())
+            .effective_config(effective_config)
+            .disk_space_config(client_config.disk_space.clone())
             .disk_store(
                 &datadir,
                 &db_path,
@@ -145,8 +181,14 @@ impl<E: EthSpec> ProductionBeaconNode<E> {
         let discv5_executor = Discv5Executor(executor);
         client_config.network.discv5_config.executor = Some(Box::new(discv5_executor));
 
+        let builder = builder.build_beacon_chain()?;
+        let builder = if client_config.self_test {
+            builder.self_test(&datadir).await?
+        } else {
+            builder
+        };
+
         builder
-            .build_beacon_chain()?
             .network(&client_config.network)
             .await?
             .notifier()?
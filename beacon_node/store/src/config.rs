@@ -15,12 +15,25 @@ pub struct StoreConfig {
     pub slots_per_restore_point: u64,
     /// Flag indicating whether the `slots_per_restore_point` was set explicitly by the user.
     pub slots_per_restore_point_set_explicitly: bool,
+    /// Whether to re-space the restore points of an existing freezer database to match
+    /// `slots_per_restore_point`, rather than refusing to open it.
+    pub force_restore_point_migration: bool,
+    /// Whether to open the freezer database without write access, e.g. because it is a snapshot
+    /// shared between nodes. Finalized states are kept in the hot database instead of being
+    /// migrated to the freezer.
+    pub freezer_readonly: bool,
+    /// Whether to open both databases without write access. The database must already exist and
+    /// use the current schema version, and nothing is written to it, not even its config.
+    pub read_only: bool,
     /// Maximum number of blocks to store in the in-memory block cache.
     pub block_cache_size: usize,
     /// Whether to compact the database on initialization.
     pub compact_on_init: bool,
     /// Whether to compact the database during database pruning.
     pub compact_on_prune: bool,
+    /// If set, hot epoch boundary states are only stored in full once every this many epochs, and
+    /// the states in between are stored as diffs against the preceding full state.
+    pub epochs_per_full_hot_state: Option<u64>,
 }
 
 /// Variant of `StoreConfig` that gets written to disk. Contains immutable configuration params.
@@ -40,9 +53,13 @@ impl Default for StoreConfig {
             // Safe default for tests, shouldn't ever be read by a CLI node.
             slots_per_restore_point: MinimalEthSpec::slots_per_historical_root() as u64,
             slots_per_restore_point_set_explicitly: false,
+            force_restore_point_migration: false,
+            freezer_readonly: false,
+            read_only: false,
             block_cache_size: DEFAULT_BLOCK_CACHE_SIZE,
             compact_on_init: false,
             compact_on_prune: true,
+            epochs_per_full_hot_state: None,
         }
     }
 }
@@ -54,6 +71,11 @@ impl StoreConfig {
         }
     }
 
+    /// Whether the freezer database is opened without write access.
+    pub fn freezer_is_read_only(&self) -> bool {
+        self.freezer_readonly || self.read_only
+    }
+
     pub fn check_compatibility(
         &self,
         on_disk_config: &OnDiskStoreConfig,
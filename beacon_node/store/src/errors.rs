@@ -3,6 +3,7 @@ use crate::config::StoreConfigError;
 use crate::hot_cold_store::HotColdDBError;
 use ssz::DecodeError;
 use state_processing::BlockReplayError;
+use std::path::PathBuf;
 use types::{BeaconStateError, Hash256, Slot};
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -45,6 +46,13 @@ pub enum Error {
     ResyncRequiredForExecutionPayloadSeparation,
     SlotClockUnavailableForMigration,
     V9MigrationFailure(Hash256),
+    /// A write was attempted to a database which was opened read-only.
+    ReadOnlyDatabase,
+    /// A read-only database can't be opened because its overlay directory couldn't be created.
+    ReadOnlyDatabaseOverlay {
+        path: PathBuf,
+        message: String,
+    },
 }
 
 pub trait HandleUnavailable<T> {
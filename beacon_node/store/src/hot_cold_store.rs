@@ -6,7 +6,9 @@ use crate::config::{
     PREV_DEFAULT_SLOTS_PER_RESTORE_POINT,
 };
 use crate::forwards_iter::{HybridForwardsBlockRootsIterator, HybridForwardsStateRootsIterator};
-use crate::impls::beacon_state::{get_full_state, store_full_state};
+use crate::impls::beacon_state::{
+    decode_full_state, full_state_bytes, get_full_state, store_full_state,
+};
 use crate::iter::{ParentRootBlockIterator, StateRootsIterator};
 use crate::leveldb_store::BytesKey;
 use crate::leveldb_store::LevelDB;
@@ -18,7 +20,7 @@ use crate::metadata::{
 };
 use crate::metrics;
 use crate::{
-    get_key_for_col, DBColumn, DatabaseBlock, Error, ItemStore, KeyValueStoreOp,
+    get_key_for_col, DBColumn, DatabaseBlock, Error, HotStateDiff, ItemStore, KeyValueStoreOp,
     PartialBeaconState, StoreItem, StoreOp,
 };
 use leveldb::iterator::LevelDBIterator;
@@ -52,6 +54,11 @@ pub struct HotColdDB<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>> {
     pub(crate) split: RwLock<Split>,
     /// The starting slots for the range of blocks & states stored in the database.
     anchor_info: RwLock<Option<AnchorInfo>>,
+    /// The spacing of the restore points currently in the freezer.
+    ///
+    /// This differs from the configured `slots_per_restore_point` while a restore point migration
+    /// is pending.
+    pub(crate) restore_point_spacing: RwLock<u64>,
     pub(crate) config: StoreConfig,
     /// Cold database containing compact historical data.
     pub cold_db: Cold,
@@ -88,10 +95,26 @@ pub enum HotColdDBError {
     MissingColdStateSummary(Hash256),
     MissingHotStateSummary(Hash256),
     MissingEpochBoundaryState(Hash256),
+    MissingHotStateDiffBase {
+        state_root: Hash256,
+        base_state_root: Hash256,
+    },
+    /// A hot state diff is inconsistent with the state it is based on.
+    InvalidHotStateDiff(Hash256),
     MissingSplitState(Hash256, Slot),
     MissingExecutionPayload(Hash256),
     MissingFullBlockExecutionPayloadPruned(Hash256, Slot),
     MissingAnchorInfo,
+    /// The restore points of a read-only freezer can't be re-spaced.
+    FreezerReadOnlyRestorePointMigration,
+    /// A read-only database can only be opened if it already uses the current schema version.
+    ReadOnlySchemaMigration {
+        on_disk_version: Option<SchemaVersion>,
+    },
+    /// Blocks prior to `oldest_block_slot` would need to be backfilled into the read-only freezer.
+    FreezerReadOnlyBackfillRequired {
+        oldest_block_slot: Slot,
+    },
     HotStateSummaryError(BeaconStateError),
     RestorePointDecodeError(ssz::DecodeError),
     BlockReplayBeaconError(BeaconStateError),
@@ -125,6 +148,7 @@ impl<E: EthSpec> HotColdDB<E, MemoryStore<E>, MemoryStore<E>> {
         let db = HotColdDB {
             split: RwLock::new(Split::default()),
             anchor_info: RwLock::new(None),
+            restore_point_spacing: RwLock::new(config.slots_per_restore_point),
             cold_db: MemoryStore::open(),
             hot_db: MemoryStore::open(),
             block_cache: Mutex::new(LruCache::new(config.block_cache_size)),
@@ -155,11 +179,23 @@ impl<E: EthSpec> HotColdDB<E, LevelDB<E>, LevelDB<E>> {
     ) -> Result<Arc<Self>, Error> {
         Self::verify_slots_per_restore_point(config.slots_per_restore_point)?;
 
+        let cold_db = if config.freezer_is_read_only() {
+            LevelDB::open_read_only(cold_path)?
+        } else {
+            LevelDB::open(cold_path)?
+        };
+        let hot_db = if config.read_only {
+            LevelDB::open_read_only(hot_path)?
+        } else {
+            LevelDB::open(hot_path)?
+        };
+
         let mut db = HotColdDB {
             split: RwLock::new(Split::default()),
             anchor_info: RwLock::new(None),
-            cold_db: LevelDB::open(cold_path)?,
-            hot_db: LevelDB::open(hot_path)?,
+            restore_point_spacing: RwLock::new(config.slots_per_restore_point),
+            cold_db,
+            hot_db,
             block_cache: Mutex::new(LruCache::new(config.block_cache_size)),
             config,
             spec,
@@ -171,7 +207,16 @@ impl<E: EthSpec> HotColdDB<E, LevelDB<E>, LevelDB<E>> {
         // uses the new default. Don't error on a failed read because the config itself may need
         // migrating.
         if let Ok(Some(disk_config)) = db.load_config() {
-            if !db.config.slots_per_restore_point_set_explicitly
+            if db.config.force_restore_point_migration
+                && disk_config.slots_per_restore_point != db.config.slots_per_restore_point
+            {
+                if db.config.freezer_is_read_only() {
+                    return Err(HotColdDBError::FreezerReadOnlyRestorePointMigration.into());
+                }
+                // Read the freezer using the on-disk spacing until the background migrator has
+                // re-spaced it.
+                *db.restore_point_spacing.get_mut() = disk_config.slots_per_restore_point;
+            } else if !db.config.slots_per_restore_point_set_explicitly
                 && disk_config.slots_per_restore_point == PREV_DEFAULT_SLOTS_PER_RESTORE_POINT
                 && db.config.slots_per_restore_point == DEFAULT_SLOTS_PER_RESTORE_POINT
             {
@@ -184,6 +229,7 @@ impl<E: EthSpec> HotColdDB<E, LevelDB<E>, LevelDB<E>> {
 
                 // Mutate the in-memory config so that it's compatible.
                 db.config.slots_per_restore_point = PREV_DEFAULT_SLOTS_PER_RESTORE_POINT;
+                *db.restore_point_spacing.get_mut() = PREV_DEFAULT_SLOTS_PER_RESTORE_POINT;
             }
         }
 
@@ -202,10 +248,35 @@ impl<E: EthSpec> HotColdDB<E, LevelDB<E>, LevelDB<E>> {
             );
         }
 
+        // Resume or roll back a restore point migration which was interrupted.
+        db.recover_restore_point_migration()?;
+
+        // A read-only freezer can't receive backfilled blocks, so it must hold the full history.
+        if db.config.freezer_readonly {
+            if let Some(anchor) = db.get_anchor_info() {
+                if anchor.oldest_block_slot > db.spec.genesis_slot {
+                    return Err(HotColdDBError::FreezerReadOnlyBackfillRequired {
+                        oldest_block_slot: anchor.oldest_block_slot,
+                    }
+                    .into());
+                }
+            }
+            info!(
+                db.log,
+                "Opened freezer database read-only";
+                "split_slot" => db.get_split_slot(),
+            );
+        }
+
         // Ensure that the schema version of the on-disk database matches the software.
         // If the version is mismatched, an automatic migration will be attempted.
         let db = Arc::new(db);
-        if let Some(schema_version) = db.load_schema_version()? {
+        if db.config.read_only {
+            let on_disk_version = db.load_schema_version()?;
+            if on_disk_version != Some(CURRENT_SCHEMA_VERSION) {
+                return Err(HotColdDBError::ReadOnlySchemaMigration { on_disk_version }.into());
+            }
+        } else if let Some(schema_version) = db.load_schema_version()? {
             debug!(
                 db.log,
                 "Attempting schema migration";
@@ -217,10 +288,24 @@ impl<E: EthSpec> HotColdDB<E, LevelDB<E>, LevelDB<E>> {
             db.store_schema_version(CURRENT_SCHEMA_VERSION)?;
         }
 
-        // Ensure that any on-disk config is compatible with the supplied config.
+        // Ensure that any on-disk config is compatible with the supplied config. A pending restore
+        // point migration is run by the background migrator, after any schema migration.
         if let Some(disk_config) = db.load_config()? {
-            db.config.check_compatibility(&disk_config)?;
+            if !db.restore_point_migration_pending() {
+                db.config.check_compatibility(&disk_config)?;
+            }
+        }
+
+        // Nothing else may be written to a read-only database.
+        if db.config.read_only {
+            info!(
+                db.log,
+                "Opened database read-only";
+                "split_slot" => db.get_split_slot(),
+            );
+            return Ok(db);
         }
+
         db.store_config()?;
 
         // Run a garbage collection pass.
@@ -552,10 +637,12 @@ impl<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>> HotColdDB<E, Hot, Cold>
         self.hot_db
             .key_delete(DBColumn::BeaconStateSummary.into(), state_root.as_bytes())?;
 
-        // Delete the full state if it lies on an epoch boundary.
+        // Delete the full state or its diff if it lies on an epoch boundary.
         if slot % E::slots_per_epoch() == 0 {
             self.hot_db
                 .key_delete(DBColumn::BeaconState.into(), state_root.as_bytes())?;
+            self.hot_db
+                .key_delete(DBColumn::BeaconStateDiff.into(), state_root.as_bytes())?;
         }
 
         Ok(())
@@ -708,6 +795,11 @@ impl<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>> HotColdDB<E, Hot, Cold>
                         let state_key =
                             get_key_for_col(DBColumn::BeaconState.into(), state_root.as_bytes());
                         key_value_batch.push(KeyValueStoreOp::DeleteKey(state_key));
+                        let diff_key = get_key_for_col(
+                            DBColumn::BeaconStateDiff.into(),
+                            state_root.as_bytes(),
+                        );
+                        key_value_batch.push(KeyValueStoreOp::DeleteKey(diff_key));
                     }
                 }
 
@@ -758,23 +850,44 @@ impl<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>> HotColdDB<E, Hot, Cold>
 
     /// Store a post-finalization state efficiently in the hot database.
     ///
-    /// On an epoch boundary, store a full state. On an intermediate slot, store
-    /// just a backpointer to the nearest epoch boundary.
+    /// On an epoch boundary, store a full state, or a diff against an earlier full state if
+    /// `epochs_per_full_hot_state` is set. On an intermediate slot, store just a backpointer to the
+    /// nearest epoch boundary.
     pub fn store_hot_state(
         &self,
         state_root: &Hash256,
         state: &BeaconState<E>,
         ops: &mut Vec<KeyValueStoreOp>,
     ) -> Result<(), Error> {
-        // On the epoch boundary, store the full state.
+        // On the epoch boundary, store the full state or its diff.
         if state.slot() % E::slots_per_epoch() == 0 {
-            trace!(
-                self.log,
-                "Storing full state on epoch boundary";
-                "slot" => state.slot().as_u64(),
-                "state_root" => format!("{:?}", state_root)
-            );
-            store_full_state(state_root, state, ops)?;
+            if let Some((base_state_root, base_bytes)) = self.hot_state_diff_base(state)? {
+                trace!(
+                    self.log,
+                    "Storing state diff on epoch boundary";
+                    "slot" => state.slot().as_u64(),
+                    "state_root" => format!("{:?}", state_root),
+                    "base_state_root" => format!("{:?}", base_state_root),
+                );
+                let diff =
+                    HotStateDiff::compute(base_state_root, &base_bytes, &full_state_bytes(state));
+                let bytes = diff.as_store_bytes();
+                metrics::inc_counter_by(
+                    &metrics::BEACON_STATE_DIFF_WRITE_BYTES,
+                    bytes.len() as u64,
+                );
+                metrics::inc_counter(&metrics::BEACON_STATE_DIFF_WRITE_COUNT);
+                let key = get_key_for_col(DBColumn::BeaconStateDiff.into(), state_root.as_bytes());
+                ops.push(KeyValueStoreOp::PutKeyValue(key, bytes));
+            } else {
+                trace!(
+                    self.log,
+                    "Storing full state on epoch boundary";
+                    "slot" => state.slot().as_u64(),
+                    "state_root" => format!("{:?}", state_root)
+                );
+                store_full_state(state_root, state, ops)?;
+            }
         }
 
         // Store a summary of the state.
@@ -787,6 +900,66 @@ impl<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>> HotColdDB<E, Hot, Cold>
         Ok(())
     }
 
+    /// Returns the slot of the latest epoch boundary at or before `slot` on which hot states are
+    /// stored in full, or `None` if every epoch boundary state is stored in full.
+    fn hot_state_full_slot(&self, slot: Slot) -> Option<Slot> {
+        let epochs_per_full_state = self
+            .config
+            .epochs_per_full_hot_state
+            .filter(|epochs| *epochs > 1)?;
+        let epoch = slot.epoch(E::slots_per_epoch());
+        Some((epoch - epoch % epochs_per_full_state).start_slot(E::slots_per_epoch()))
+    }
+
+    /// Returns the root and stored bytes of the full state which the epoch boundary `state` should
+    /// be stored as a diff against, or `None` if it should be stored in full.
+    fn hot_state_diff_base(
+        &self,
+        state: &BeaconState<E>,
+    ) -> Result<Option<(Hash256, Vec<u8>)>, Error> {
+        let base_slot = match self.hot_state_full_slot(state.slot()) {
+            Some(base_slot) if base_slot < state.slot() => base_slot,
+            _ => return Ok(None),
+        };
+        // The base state is an ancestor of `state`, so its root is known unless it is too old, in
+        // which case the state is stored in full.
+        let base_state_root = match state.get_state_root(base_slot) {
+            Ok(base_state_root) => *base_state_root,
+            Err(_) => return Ok(None),
+        };
+        Ok(self
+            .hot_db
+            .get_bytes(DBColumn::BeaconState.into(), base_state_root.as_bytes())?
+            .map(|base_bytes| (base_state_root, base_bytes)))
+    }
+
+    /// Load an epoch boundary state from the hot database, applying its diff to its base state if
+    /// it isn't stored in full.
+    pub fn load_hot_full_state(
+        &self,
+        state_root: &Hash256,
+    ) -> Result<Option<BeaconState<E>>, Error> {
+        if let Some(state) = get_full_state(&self.hot_db, state_root, &self.spec)? {
+            return Ok(Some(state));
+        }
+
+        let diff = match self.hot_db.get::<HotStateDiff>(state_root)? {
+            Some(diff) => diff,
+            None => return Ok(None),
+        };
+        let base_bytes = self
+            .hot_db
+            .get_bytes(
+                DBColumn::BeaconState.into(),
+                diff.base_state_root.as_bytes(),
+            )?
+            .ok_or(HotColdDBError::MissingHotStateDiffBase {
+                state_root: *state_root,
+                base_state_root: diff.base_state_root,
+            })?;
+        decode_full_state(&diff.apply(&base_bytes)?, &self.spec).map(Some)
+    }
+
     /// Load a post-finalization state from the hot database.
     ///
     /// Will replay blocks from the nearest epoch boundary.
@@ -809,10 +982,11 @@ impl<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>> HotColdDB<E, Hot, Cold>
             epoch_boundary_state_root,
         }) = self.load_hot_state_summary(state_root)?
         {
-            let boundary_state =
-                get_full_state(&self.hot_db, &epoch_boundary_state_root, &self.spec)?.ok_or(
-                    HotColdDBError::MissingEpochBoundaryState(epoch_boundary_state_root),
-                )?;
+            let boundary_state = self
+                .load_hot_full_state(&epoch_boundary_state_root)?
+                .ok_or(HotColdDBError::MissingEpochBoundaryState(
+                    epoch_boundary_state_root,
+                ))?;
 
             // Optimization to avoid even *thinking* about replaying blocks if we're already
             // on an epoch boundary.
@@ -847,7 +1021,8 @@ impl<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>> HotColdDB<E, Hot, Cold>
     ) -> Result<(), Error> {
         ops.push(ColdStateSummary { slot: state.slot() }.as_kv_store_op(*state_root));
 
-        if state.slot() % self.config.slots_per_restore_point != 0 {
+        let slots_per_restore_point = self.get_slots_per_restore_point();
+        if state.slot() % slots_per_restore_point != 0 {
             return Ok(());
         }
 
@@ -858,6 +1033,22 @@ impl<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>> HotColdDB<E, Hot, Cold>
             "state_root" => format!("{:?}", state_root)
         );
 
+        self.store_restore_point_state(state_root, state, ops)?;
+
+        // Index the state as a restore point.
+        let restore_point_index = state.slot().as_u64() / slots_per_restore_point;
+        self.store_restore_point_hash(restore_point_index, *state_root, ops);
+
+        Ok(())
+    }
+
+    /// Store the full `state` in the freezer database, without indexing it as a restore point.
+    pub(crate) fn store_restore_point_state(
+        &self,
+        state_root: &Hash256,
+        state: &BeaconState<E>,
+        ops: &mut Vec<KeyValueStoreOp>,
+    ) -> Result<(), Error> {
         // 1. Convert to PartialBeaconState and store that in the DB.
         let partial_state = PartialBeaconState::from_state_forgetful(state);
         let op = partial_state.as_kv_store_op(*state_root);
@@ -870,10 +1061,6 @@ impl<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>> HotColdDB<E, Hot, Cold>
         store_updated_vector(HistoricalRoots, db, state, &self.spec, ops)?;
         store_updated_vector(RandaoMixes, db, state, &self.spec, ops)?;
 
-        // 3. Store restore point.
-        let restore_point_index = state.slot().as_u64() / self.config.slots_per_restore_point;
-        self.store_restore_point_hash(restore_point_index, *state_root, ops);
-
         Ok(())
     }
 
@@ -896,12 +1083,16 @@ impl<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>> HotColdDB<E, Hot, Cold>
         // See the comments in `get_historic_state_limits` for more information.
         let (lower_limit, upper_limit) = self.get_historic_state_limits();
 
+        // Acquire the read lock, so that the restore points can't be re-spaced while this is
+        // happening.
+        let slots_per_restore_point = self.restore_point_spacing.read_recursive();
+
         if slot <= lower_limit || slot >= upper_limit {
-            if slot % self.config.slots_per_restore_point == 0 {
-                let restore_point_idx = slot.as_u64() / self.config.slots_per_restore_point;
+            if slot % *slots_per_restore_point == 0 {
+                let restore_point_idx = slot.as_u64() / *slots_per_restore_point;
                 self.load_restore_point_by_index(restore_point_idx)
             } else {
-                self.load_cold_intermediate_state(slot)
+                self.load_cold_intermediate_state(slot, *slots_per_restore_point)
             }
             .map(Some)
         } else {
@@ -937,16 +1128,21 @@ impl<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>> HotColdDB<E, Hot, Cold>
     }
 
     /// Load a frozen state that lies between restore points.
-    fn load_cold_intermediate_state(&self, slot: Slot) -> Result<BeaconState<E>, Error> {
+    fn load_cold_intermediate_state(
+        &self,
+        slot: Slot,
+        slots_per_restore_point: u64,
+    ) -> Result<BeaconState<E>, Error> {
         // 1. Load the restore points either side of the intermediate state.
-        let low_restore_point_idx = slot.as_u64() / self.config.slots_per_restore_point;
+        let low_restore_point_idx = slot.as_u64() / slots_per_restore_point;
         let high_restore_point_idx = low_restore_point_idx + 1;
 
         // Acquire the read lock, so that the split can't change while this is happening.
         let split = self.split.read_recursive();
 
         let low_restore_point = self.load_restore_point_by_index(low_restore_point_idx)?;
-        let high_restore_point = self.get_restore_point(high_restore_point_idx, &split)?;
+        let high_restore_point =
+            self.get_restore_point(high_restore_point_idx, slots_per_restore_point, &split)?;
 
         // 2. Load the blocks from the high restore point back to the low restore point.
         let blocks = self.load_blocks_to_replay(
@@ -978,9 +1174,10 @@ impl<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>> HotColdDB<E, Hot, Cold>
     pub(crate) fn get_restore_point(
         &self,
         restore_point_idx: u64,
+        slots_per_restore_point: u64,
         split: &Split,
     ) -> Result<BeaconState<E>, Error> {
-        if restore_point_idx * self.config.slots_per_restore_point >= split.slot.as_u64() {
+        if restore_point_idx * slots_per_restore_point >= split.slot.as_u64() {
             self.get_state(&split.state_root, Some(split.slot))?
                 .ok_or(HotColdDBError::MissingSplitState(
                     split.state_root,
@@ -1105,8 +1302,8 @@ impl<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>> HotColdDB<E, Hot, Cold>
 
     /// Fetch the slot of the most recently stored restore point.
     pub fn get_latest_restore_point_slot(&self) -> Slot {
-        (self.get_split_slot() - 1) / self.config.slots_per_restore_point
-            * self.config.slots_per_restore_point
+        let slots_per_restore_point = self.get_slots_per_restore_point();
+        (self.get_split_slot() - 1) / slots_per_restore_point * slots_per_restore_point
     }
 
     /// Load the database schema version from disk.
@@ -1137,7 +1334,15 @@ impl<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>> HotColdDB<E, Hot, Cold>
     /// Initialise the anchor info for checkpoint sync starting from `block`.
     pub fn init_anchor_info(&self, block: BeaconBlockRef<'_, E>) -> Result<KeyValueStoreOp, Error> {
         let anchor_slot = block.slot();
-        let slots_per_restore_point = self.config.slots_per_restore_point;
+        let slots_per_restore_point = self.get_slots_per_restore_point();
+
+        // Blocks prior to the anchor can't be backfilled into a read-only freezer.
+        if self.config.freezer_readonly && anchor_slot > self.spec.genesis_slot {
+            return Err(HotColdDBError::FreezerReadOnlyBackfillRequired {
+                oldest_block_slot: anchor_slot,
+            }
+            .into());
+        }
 
         // Set the `state_upper_limit` to the slot of the *next* restore point.
         // See `get_state_upper_limit` for rationale.
@@ -1270,14 +1475,25 @@ impl<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>> HotColdDB<E, Hot, Cold>
         &self.config
     }
 
+    /// Return the spacing of the restore points currently in the freezer.
+    pub fn get_slots_per_restore_point(&self) -> u64 {
+        *self.restore_point_spacing.read_recursive()
+    }
+
     /// Load previously-stored config from disk.
     fn load_config(&self) -> Result<Option<OnDiskStoreConfig>, Error> {
         self.hot_db.get(&CONFIG_KEY)
     }
 
     /// Write the config to disk.
+    ///
+    /// The spacing of the restore points currently in the freezer is recorded, which differs from
+    /// the configured spacing while a restore point migration is pending.
     fn store_config(&self) -> Result<(), Error> {
-        self.hot_db.put(&CONFIG_KEY, &self.config.as_disk_config())
+        let disk_config = OnDiskStoreConfig {
+            slots_per_restore_point: self.get_slots_per_restore_point(),
+        };
+        self.hot_db.put(&CONFIG_KEY, &disk_config)
     }
 
     /// Load the split point from disk.
@@ -1291,7 +1507,10 @@ impl<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>> HotColdDB<E, Hot, Cold>
     }
 
     /// Load the state root of a restore point.
-    fn load_restore_point_hash(&self, restore_point_index: u64) -> Result<Hash256, Error> {
+    pub(crate) fn load_restore_point_hash(
+        &self,
+        restore_point_index: u64,
+    ) -> Result<Hash256, Error> {
         let key = Self::restore_point_key(restore_point_index);
         self.cold_db
             .get(&key)?
@@ -1300,7 +1519,7 @@ impl<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>> HotColdDB<E, Hot, Cold>
     }
 
     /// Store the state root of a restore point.
-    fn store_restore_point_hash(
+    pub(crate) fn store_restore_point_hash(
         &self,
         restore_point_index: u64,
         state_root: Hash256,
@@ -1312,7 +1531,7 @@ impl<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>> HotColdDB<E, Hot, Cold>
     }
 
     /// Convert a `restore_point_index` into a database key.
-    fn restore_point_key(restore_point_index: u64) -> Hash256 {
+    pub(crate) fn restore_point_key(restore_point_index: u64) -> Hash256 {
         Hash256::from_low_u64_be(restore_point_index)
     }
 
@@ -1456,6 +1675,19 @@ pub fn migrate_database<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>>(
 
     let mut hot_db_ops: Vec<StoreOp<E>> = Vec::new();
 
+    // States from the new split onwards may be stored as diffs against the full state which the
+    // diff of the new split state is based on, so that state is kept in the hot database until a
+    // later migration replaces it.
+    let diff_base_state_root = |state_root: &Hash256| -> Result<Option<Hash256>, Error> {
+        Ok(store
+            .hot_db
+            .get::<HotStateDiff>(state_root)?
+            .map(|diff| diff.base_state_root))
+    };
+    let current_split_state_root = store.split.read_recursive().state_root;
+    let previous_diff_base_state_root = diff_base_state_root(&current_split_state_root)?;
+    let new_diff_base_state_root = diff_base_state_root(&frozen_head_root)?;
+
     // 1. Copy all of the states between the head and the split slot, from the hot DB
     // to the cold DB.
     let state_root_iter = StateRootsIterator::new(&store, frozen_head);
@@ -1470,8 +1702,9 @@ pub fn migrate_database<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>>(
 
         let mut cold_db_ops: Vec<KeyValueStoreOp> = Vec::new();
 
-        if slot % store.config.slots_per_restore_point == 0 {
-            let state: BeaconState<E> = get_full_state(&store.hot_db, &state_root, &store.spec)?
+        if slot % store.get_slots_per_restore_point() == 0 {
+            let state: BeaconState<E> = store
+                .load_hot_full_state(&state_root)?
                 .ok_or(HotColdDBError::MissingStateToFreeze(state_root))?;
 
             store.store_cold_state(&state_root, &state, &mut cold_db_ops)?;
@@ -1488,7 +1721,17 @@ pub fn migrate_database<E: EthSpec, Hot: ItemStore<E>, Cold: ItemStore<E>>(
         store.cold_db.do_atomically(cold_db_ops)?;
 
         // Delete the old summary, and the full state if we lie on an epoch boundary.
-        hot_db_ops.push(StoreOp::DeleteState(state_root, Some(slot)));
+        if Some(state_root) != new_diff_base_state_root {
+            hot_db_ops.push(StoreOp::DeleteState(state_root, Some(slot)));
+        }
+    }
+
+    // The diff base kept by the previous migration lies before the old split, so it isn't
+    // deleted above.
+    if let Some(state_root) = previous_diff_base_state_root {
+        if Some(state_root) != new_diff_base_state_root {
+            hot_db_ops.push(StoreOp::DeleteState(state_root, None));
+        }
     }
 
     // Warning: Critical section.  We have to take care not to put any of the two databases in an
@@ -1622,6 +1865,15 @@ impl HotStateSummary {
             epoch_boundary_state_root,
         })
     }
+
+    pub fn slot(&self) -> Slot {
+        self.slot
+    }
+
+    /// The root of the latest block applied to the state.
+    pub fn latest_block_root(&self) -> Hash256 {
+        self.latest_block_root
+    }
 }
 
 /// Struct for summarising a state in the freezer database.
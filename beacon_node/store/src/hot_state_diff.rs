@@ -0,0 +1,210 @@
+//! Diffs between the stored bytes of hot states.
+//!
+//! Most of a state is unchanged from one epoch to the next, the validator registry in particular,
+//! so rather than storing every epoch boundary state in full, the hot database may store a state as
+//! the runs of bytes in which its encoding differs from that of an earlier full state on the same
+//! chain. Bytes are compared in fixed-size chunks, so that a handful of changed bytes doesn't split
+//! a run.
+use crate::hot_cold_store::HotColdDBError;
+use crate::{DBColumn, Error, StoreItem};
+use ssz::{Decode, Encode};
+use ssz_derive::{Decode, Encode};
+use std::cmp;
+use types::Hash256;
+
+/// The number of bytes compared at a time when computing a diff.
+const CHUNK_SIZE: usize = 32;
+
+/// The difference between the stored bytes of a state and those of its base state.
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
+pub struct HotStateDiff {
+    /// The root of the full state which the diff applies to.
+    pub base_state_root: Hash256,
+    /// The length of the stored bytes of the state.
+    len: u64,
+    /// The offset of each run of bytes which differs from the base state.
+    run_offsets: Vec<u64>,
+    /// The length of each run of bytes which differs from the base state.
+    run_lengths: Vec<u64>,
+    /// The bytes of every run, concatenated.
+    run_bytes: Vec<u8>,
+}
+
+impl HotStateDiff {
+    /// Compute the diff which turns `base`, the stored bytes of the state with `base_state_root`,
+    /// into `target`.
+    pub fn compute(base_state_root: Hash256, base: &[u8], target: &[u8]) -> Self {
+        let mut diff = Self {
+            base_state_root,
+            len: target.len() as u64,
+            run_offsets: vec![],
+            run_lengths: vec![],
+            run_bytes: vec![],
+        };
+
+        let mut run_start = None;
+        for offset in (0..target.len()).step_by(CHUNK_SIZE) {
+            let end = cmp::min(offset + CHUNK_SIZE, target.len());
+            let changed = base.get(offset..end) != Some(&target[offset..end]);
+            match run_start {
+                None if changed => run_start = Some(offset),
+                Some(start) if !changed => {
+                    diff.push_run(start, &target[start..offset]);
+                    run_start = None;
+                }
+                _ => (),
+            }
+        }
+        if let Some(start) = run_start {
+            diff.push_run(start, &target[start..]);
+        }
+
+        diff
+    }
+
+    fn push_run(&mut self, offset: usize, bytes: &[u8]) {
+        self.run_offsets.push(offset as u64);
+        self.run_lengths.push(bytes.len() as u64);
+        self.run_bytes.extend_from_slice(bytes);
+    }
+
+    /// Apply the diff to `base`, the stored bytes of the state with `self.base_state_root`.
+    pub fn apply(&self, base: &[u8]) -> Result<Vec<u8>, Error> {
+        let invalid = || HotColdDBError::InvalidHotStateDiff(self.base_state_root);
+
+        let mut target = base.to_vec();
+        target.resize(self.len as usize, 0);
+
+        if self.run_offsets.len() != self.run_lengths.len() {
+            return Err(invalid().into());
+        }
+        let mut run_bytes = self.run_bytes.as_slice();
+        for (&offset, &len) in self.run_offsets.iter().zip(&self.run_lengths) {
+            let (offset, len) = (offset as usize, len as usize);
+            if run_bytes.len() < len {
+                return Err(invalid().into());
+            }
+            let (bytes, rest) = run_bytes.split_at(len);
+            target
+                .get_mut(offset..offset.saturating_add(len))
+                .ok_or_else(invalid)?
+                .copy_from_slice(bytes);
+            run_bytes = rest;
+        }
+        if !run_bytes.is_empty() {
+            return Err(invalid().into());
+        }
+
+        Ok(target)
+    }
+}
+
+impl StoreItem for HotStateDiff {
+    fn db_column() -> DBColumn {
+        DBColumn::BeaconStateDiff
+    }
+
+    fn as_store_bytes(&self) -> Vec<u8> {
+        self.as_ssz_bytes()
+    }
+
+    fn from_store_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Ok(Self::from_ssz_bytes(bytes)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BeaconStateStorageContainer;
+    use types::test_utils::{SeedableRng, TestRandom, XorShiftRng};
+    use types::{BeaconState, Eth1Data, EthSpec, MinimalEthSpec, Validator};
+
+    type E = MinimalEthSpec;
+
+    fn round_trip(base: &[u8], target: &[u8]) -> HotStateDiff {
+        let diff = HotStateDiff::compute(Hash256::zero(), base, target);
+        let decoded = HotStateDiff::from_store_bytes(&diff.as_store_bytes()).unwrap();
+        assert_eq!(decoded, diff);
+        assert_eq!(decoded.apply(base).unwrap(), target);
+        diff
+    }
+
+    #[test]
+    fn identical_bytes() {
+        let bytes = (0..100).collect::<Vec<u8>>();
+        let diff = round_trip(&bytes, &bytes);
+        assert!(diff.run_bytes.is_empty());
+    }
+
+    #[test]
+    fn changed_bytes() {
+        let base = vec![0; 200];
+        let mut target = base.clone();
+        target[0] = 1;
+        target[100] = 1;
+        target[199] = 1;
+        let diff = round_trip(&base, &target);
+        assert_eq!(diff.run_offsets, vec![0, 96, 192]);
+        assert_eq!(diff.run_bytes.len(), 32 + 32 + 8);
+
+        // Adjacent changed chunks form a single run.
+        target[40] = 1;
+        let diff = round_trip(&base, &target);
+        assert_eq!(diff.run_offsets, vec![0, 96, 192]);
+        assert_eq!(diff.run_lengths[0], 64);
+    }
+
+    #[test]
+    fn changed_length() {
+        let base = vec![0; 100];
+        round_trip(&base, &[0; 150]);
+        round_trip(&base, &[0; 50]);
+        round_trip(&base, &[]);
+        round_trip(&[], &base);
+        round_trip(&[1; 33], &base);
+    }
+
+    #[test]
+    fn corrupt_diff() {
+        let base = vec![0; 100];
+        let mut diff = HotStateDiff::compute(Hash256::zero(), &base, &[1; 100]);
+        diff.run_bytes.pop();
+        assert!(diff.apply(&base).is_err());
+
+        let mut diff = HotStateDiff::compute(Hash256::zero(), &base, &[1; 100]);
+        diff.run_offsets[0] = 90;
+        assert!(diff.apply(&base).is_err());
+    }
+
+    #[test]
+    fn state_round_trip() {
+        let spec = E::default_spec();
+        let mut rng = XorShiftRng::from_seed([42; 16]);
+        let mut state = BeaconState::<E>::new(0, Eth1Data::default(), &spec);
+        for _ in 0..64 {
+            let validator = Validator::random_for_test(&mut rng);
+            state.validators_mut().push(validator).unwrap();
+            state
+                .balances_mut()
+                .push(spec.max_effective_balance)
+                .unwrap();
+        }
+        let base = BeaconStateStorageContainer::new(&state).as_ssz_bytes();
+
+        *state.slot_mut() += E::slots_per_epoch();
+        *state.balances_mut().get_mut(3).unwrap() += 1;
+        state.validators_mut().get_mut(5).unwrap().slashed = true;
+        let target = BeaconStateStorageContainer::new(&state).as_ssz_bytes();
+
+        let diff = round_trip(&base, &target);
+        assert!(diff.run_bytes.len() < target.len() / 4);
+
+        let decoded: BeaconState<E> =
+            BeaconStateStorageContainer::from_ssz_bytes(&diff.apply(&base).unwrap(), &spec)
+                .unwrap()
+                .try_into()
+                .unwrap();
+        assert_eq!(decoded, state);
+    }
+}
@@ -9,10 +9,7 @@ pub fn store_full_state<E: EthSpec>(
     state: &BeaconState<E>,
     ops: &mut Vec<KeyValueStoreOp>,
 ) -> Result<(), Error> {
-    let bytes = {
-        let _overhead_timer = metrics::start_timer(&metrics::BEACON_STATE_WRITE_OVERHEAD_TIMES);
-        StorageContainer::new(state).as_ssz_bytes()
-    };
+    let bytes = full_state_bytes(state);
     metrics::inc_counter_by(&metrics::BEACON_STATE_WRITE_BYTES, bytes.len() as u64);
     metrics::inc_counter(&metrics::BEACON_STATE_WRITE_COUNT);
     let key = get_key_for_col(DBColumn::BeaconState.into(), state_root.as_bytes());
@@ -20,6 +17,12 @@ pub fn store_full_state<E: EthSpec>(
     Ok(())
 }
 
+/// Encode `state` as it is stored in full.
+pub fn full_state_bytes<E: EthSpec>(state: &BeaconState<E>) -> Vec<u8> {
+    let _overhead_timer = metrics::start_timer(&metrics::BEACON_STATE_WRITE_OVERHEAD_TIMES);
+    StorageContainer::new(state).as_ssz_bytes()
+}
+
 pub fn get_full_state<KV: KeyValueStore<E>, E: EthSpec>(
     db: &KV,
     state_root: &Hash256,
@@ -29,20 +32,29 @@ pub fn get_full_state<KV: KeyValueStore<E>, E: EthSpec>(
 
     match db.get_bytes(DBColumn::BeaconState.into(), state_root.as_bytes())? {
         Some(bytes) => {
-            let overhead_timer = metrics::start_timer(&metrics::BEACON_STATE_READ_OVERHEAD_TIMES);
-            let container = StorageContainer::from_ssz_bytes(&bytes, spec)?;
-
-            metrics::stop_timer(overhead_timer);
+            let state = decode_full_state(&bytes, spec)?;
             metrics::stop_timer(total_timer);
-            metrics::inc_counter(&metrics::BEACON_STATE_READ_COUNT);
-            metrics::inc_counter_by(&metrics::BEACON_STATE_READ_BYTES, bytes.len() as u64);
-
-            Ok(Some(container.try_into()?))
+            Ok(Some(state))
         }
         None => Ok(None),
     }
 }
 
+/// Decode a state from the bytes it is stored as in full.
+pub fn decode_full_state<E: EthSpec>(
+    bytes: &[u8],
+    spec: &ChainSpec,
+) -> Result<BeaconState<E>, Error> {
+    let overhead_timer = metrics::start_timer(&metrics::BEACON_STATE_READ_OVERHEAD_TIMES);
+    let container = StorageContainer::from_ssz_bytes(bytes, spec)?;
+
+    metrics::stop_timer(overhead_timer);
+    metrics::inc_counter(&metrics::BEACON_STATE_READ_COUNT);
+    metrics::inc_counter_by(&metrics::BEACON_STATE_READ_BYTES, bytes.len() as u64);
+
+    container.try_into()
+}
+
 /// A container for storing `BeaconState` components.
 // TODO: would be more space efficient with the caches stored separately and referenced by hash
 #[derive(Encode)]
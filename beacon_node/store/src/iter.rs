@@ -346,6 +346,69 @@ impl<'a, T: EthSpec, Hot: ItemStore<T>, Cold: ItemStore<T>> Iterator
     }
 }
 
+/// Ends a reverse iterator over `(block_root, slot)` pairs at the oldest block stored, rather than
+/// continuing into the slots prior to a checkpoint sync anchor for which blocks are yet to be
+/// backfilled.
+pub struct AvailableBlockRootsIterator<I> {
+    inner: I,
+    oldest_block_slot: Slot,
+    last_slot: Option<Slot>,
+    done: bool,
+    truncated: bool,
+}
+
+impl<I> AvailableBlockRootsIterator<I> {
+    pub fn new(inner: I, oldest_block_slot: Slot) -> Self {
+        Self {
+            inner,
+            oldest_block_slot,
+            last_slot: None,
+            done: false,
+            truncated: false,
+        }
+    }
+
+    /// Returns `true` if the iteration ended before genesis because older blocks or states aren't
+    /// stored.
+    ///
+    /// Only meaningful once the iterator has returned `None`.
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+}
+
+impl<I, E> Iterator for AvailableBlockRootsIterator<I>
+where
+    I: Iterator<Item = Result<(Hash256, Slot), E>>,
+{
+    type Item = Result<(Hash256, Slot), E>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        match self.inner.next() {
+            Some(Ok((_, slot))) if slot < self.oldest_block_slot => {
+                self.done = true;
+                self.truncated = true;
+                None
+            }
+            Some(Ok((block_root, slot))) => {
+                self.last_slot = Some(slot);
+                Some(Ok((block_root, slot)))
+            }
+            Some(Err(e)) => Some(Err(e)),
+            None => {
+                // The inner iterator ends early when the states it needs are unavailable.
+                self.done = true;
+                self.truncated = self.last_slot.map_or(false, |slot| slot > 0);
+                None
+            }
+        }
+    }
+}
+
 /// Fetch the next state to use whilst backtracking in `*RootsIterator`.
 ///
 /// Return `Err(HistoryUnavailable)` in the case where no more backtrack states are available
@@ -504,4 +567,34 @@ mod test {
             );
         }
     }
+
+    fn rev_roots(from: u64, to: u64) -> impl Iterator<Item = Result<(Hash256, Slot), Error>> {
+        (to..=from)
+            .rev()
+            .map(|slot| Ok((Hash256::from_low_u64_be(slot), Slot::new(slot))))
+    }
+
+    #[test]
+    fn available_block_roots_end_at_oldest_block() {
+        let mut iter = AvailableBlockRootsIterator::new(rev_roots(10, 0), Slot::new(4));
+        let slots = iter
+            .by_ref()
+            .map(|result| result.unwrap().1.as_u64())
+            .collect::<Vec<_>>();
+        assert_eq!(slots, (4..=10).rev().collect::<Vec<_>>());
+        assert!(iter.is_truncated());
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn available_block_roots_reach_genesis() {
+        let mut iter = AvailableBlockRootsIterator::new(rev_roots(10, 0), Slot::new(0));
+        assert_eq!(iter.by_ref().count(), 11);
+        assert!(!iter.is_truncated());
+
+        // The inner iterator ending early due to unavailable states also truncates the iteration.
+        let mut iter = AvailableBlockRootsIterator::new(rev_roots(10, 6), Slot::new(0));
+        assert_eq!(iter.by_ref().count(), 5);
+        assert!(iter.is_truncated());
+    }
 }
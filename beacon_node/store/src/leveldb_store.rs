@@ -10,23 +10,57 @@ use leveldb::error::Error as LevelDBError;
 use leveldb::iterator::{Iterable, KeyIterator, LevelDBIterator};
 use leveldb::options::{Options, ReadOptions, WriteOptions};
 use parking_lot::{Mutex, MutexGuard};
+use std::fs;
 use std::marker::PhantomData;
+#[cfg(unix)]
+use std::os::unix::fs::symlink;
+#[cfg(windows)]
+use std::os::windows::fs::symlink_file as symlink;
 use std::path::Path;
+use tempfile::TempDir;
+
+/// The prefix of the temporary directory through which a database is opened read-only.
+const OVERLAY_DIR_PREFIX: &str = "lighthouse_read_only_db";
 
 /// A wrapped leveldb database.
 pub struct LevelDB<E: EthSpec> {
     db: Database<BytesKey>,
     /// A mutex to synchronise sensitive read-write transactions.
     transaction_mutex: Mutex<()>,
+    /// Whether all writes to the database should be refused.
+    read_only: bool,
+    /// The directory through which a read-only database was opened, which is removed when dropped.
+    ///
+    /// Declared after `db` so that the database is closed before the directory is removed.
+    _overlay_dir: Option<TempDir>,
     _phantom: PhantomData<E>,
 }
 
 impl<E: EthSpec> LevelDB<E> {
     /// Open a database at `path`, creating a new database if one does not already exist.
     pub fn open(path: &Path) -> Result<Self, Error> {
+        Self::open_with_access(path, false)
+    }
+
+    /// Open an existing database at `path`, refusing all writes to it.
+    ///
+    /// LevelDB writes its lock and log files to the directory of a database whilst opening it, and
+    /// may compact its tables whilst it's open. So that `path` is never modified, and so that it
+    /// may be on read-only storage, the database is opened through an overlay in a temporary
+    /// directory (see `std::env::temp_dir`): its table files are symlinked from the overlay, and
+    /// the small files which LevelDB rewrites are copied. Any tables written by LevelDB whilst the
+    /// database is open are written to the overlay, which is removed when the database is dropped.
+    pub fn open_read_only(path: &Path) -> Result<Self, Error> {
+        let overlay_dir = Self::create_overlay(path)?;
+        let mut db = Self::open_with_access(overlay_dir.path(), true)?;
+        db._overlay_dir = Some(overlay_dir);
+        Ok(db)
+    }
+
+    fn open_with_access(path: &Path, read_only: bool) -> Result<Self, Error> {
         let mut options = Options::new();
 
-        options.create_if_missing = true;
+        options.create_if_missing = !read_only;
 
         let db = Database::open(path, options)?;
         let transaction_mutex = Mutex::new(());
@@ -34,10 +68,69 @@ impl<E: EthSpec> LevelDB<E> {
         Ok(Self {
             db,
             transaction_mutex,
+            read_only,
+            _overlay_dir: None,
             _phantom: PhantomData,
         })
     }
 
+    /// Create a temporary directory through which the database at `path` may be opened without
+    /// modifying it.
+    ///
+    /// Table files are immutable once written, so they're symlinked. Every other file (the
+    /// manifest, log and `CURRENT` files) is copied, since LevelDB may replay, rewrite or delete
+    /// them. The lock file and LevelDB's own info logs are omitted.
+    fn create_overlay(path: &Path) -> Result<TempDir, Error> {
+        let overlay_error = |message: String| Error::ReadOnlyDatabaseOverlay {
+            path: path.to_path_buf(),
+            message,
+        };
+
+        let entries = fs::read_dir(path)
+            .map_err(|e| overlay_error(format!("unable to read the directory: {}", e)))?;
+        let overlay_dir = tempfile::Builder::new()
+            .prefix(OVERLAY_DIR_PREFIX)
+            .tempdir()
+            .map_err(|e| overlay_error(format!("unable to create an overlay directory: {}", e)))?;
+
+        for entry in entries {
+            let entry = entry.map_err(|e| overlay_error(format!("unable to read entry: {}", e)))?;
+            let file_name = entry.file_name();
+            let source = entry.path();
+            let target = overlay_dir.path().join(&file_name);
+
+            let is_table = matches!(
+                source.extension().and_then(|extension| extension.to_str()),
+                Some("ldb" | "sst")
+            );
+            let is_skipped = matches!(file_name.to_str(), Some("LOCK" | "LOG" | "LOG.old"));
+            let result = if is_table {
+                symlink(&source, &target)
+            } else if is_skipped {
+                continue;
+            } else {
+                fs::copy(&source, &target).map(|_| ())
+            };
+            result.map_err(|e| {
+                overlay_error(format!(
+                    "unable to add {} to the overlay: {}",
+                    source.display(),
+                    e
+                ))
+            })?;
+        }
+
+        Ok(overlay_dir)
+    }
+
+    fn check_writable(&self) -> Result<(), Error> {
+        if self.read_only {
+            Err(Error::ReadOnlyDatabase)
+        } else {
+            Ok(())
+        }
+    }
+
     fn read_options(&self) -> ReadOptions<BytesKey> {
         ReadOptions::new()
     }
@@ -59,6 +152,7 @@ impl<E: EthSpec> LevelDB<E> {
         val: &[u8],
         opts: WriteOptions,
     ) -> Result<(), Error> {
+        self.check_writable()?;
         let column_key = get_key_for_col(col, key);
 
         metrics::inc_counter(&metrics::DISK_DB_WRITE_COUNT);
@@ -125,6 +219,7 @@ impl<E: EthSpec> KeyValueStore<E> for LevelDB<E> {
 
     /// Removes `key` from `column`.
     fn key_delete(&self, col: &str, key: &[u8]) -> Result<(), Error> {
+        self.check_writable()?;
         let column_key = get_key_for_col(col, key);
 
         metrics::inc_counter(&metrics::DISK_DB_DELETE_COUNT);
@@ -135,6 +230,7 @@ impl<E: EthSpec> KeyValueStore<E> for LevelDB<E> {
     }
 
     fn do_atomically(&self, ops_batch: Vec<KeyValueStoreOp>) -> Result<(), Error> {
+        self.check_writable()?;
         let mut leveldb_batch = Writebatch::new();
         for op in ops_batch {
             match op {
@@ -157,6 +253,7 @@ impl<E: EthSpec> KeyValueStore<E> for LevelDB<E> {
 
     /// Compact all values in the states and states flag columns.
     fn compact(&self) -> Result<(), Error> {
+        self.check_writable()?;
         let endpoints = |column: DBColumn| {
             (
                 BytesKey::from_vec(get_key_for_col(column.as_str(), Hash256::zero().as_bytes())),
@@ -170,6 +267,7 @@ impl<E: EthSpec> KeyValueStore<E> for LevelDB<E> {
         for (start_key, end_key) in vec![
             endpoints(DBColumn::BeaconStateTemporary),
             endpoints(DBColumn::BeaconState),
+            endpoints(DBColumn::BeaconStateDiff),
         ] {
             self.db.compact(&start_key, &end_key);
         }
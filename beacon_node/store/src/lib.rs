@@ -18,6 +18,7 @@ pub mod errors;
 mod forwards_iter;
 mod garbage_collection;
 pub mod hot_cold_store;
+mod hot_state_diff;
 mod impls;
 mod leveldb_store;
 mod memory_store;
@@ -25,12 +26,14 @@ pub mod metadata;
 pub mod metrics;
 mod partial_beacon_state;
 pub mod reconstruct;
+mod restore_point_migration;
 
 pub mod iter;
 
 pub use self::chunk_writer::ChunkWriter;
 pub use self::config::StoreConfig;
 pub use self::hot_cold_store::{HotColdDB, HotStateSummary, Split};
+pub use self::hot_state_diff::HotStateDiff;
 pub use self::leveldb_store::LevelDB;
 pub use self::memory_store::MemoryStore;
 pub use self::partial_beacon_state::PartialBeaconState;
@@ -174,6 +177,9 @@ pub enum DBColumn {
     /// For full `BeaconState`s in the hot database (finalized or fork-boundary states).
     #[strum(serialize = "ste")]
     BeaconState,
+    /// For hot epoch boundary states stored as diffs against an earlier full state.
+    #[strum(serialize = "bsd")]
+    BeaconStateDiff,
     /// For the mapping from state roots to their slots or summaries.
     #[strum(serialize = "bss")]
     BeaconStateSummary,
@@ -198,6 +204,9 @@ pub enum DBColumn {
     /// For the table mapping restore point numbers to state roots.
     #[strum(serialize = "brp")]
     BeaconRestorePoint,
+    /// For the restore point index being built by a restore point migration.
+    #[strum(serialize = "bpm")]
+    BeaconRestorePointMigration,
     #[strum(serialize = "bbr")]
     BeaconBlockRoots,
     #[strum(serialize = "bsr")]
@@ -208,6 +217,15 @@ pub enum DBColumn {
     BeaconRandaoMixes,
     #[strum(serialize = "dht")]
     DhtEnrs,
+    /// For the record of blocks recently produced by this node.
+    #[strum(serialize = "prh")]
+    ProposalHistory,
+    /// For the signed bids of builders whose payloads were used for block production.
+    #[strum(serialize = "bbh")]
+    BuilderBidHistory,
+    /// For the validator registrations received via the builder API.
+    #[strum(serialize = "vrg")]
+    ValidatorRegistrations,
 }
 
 /// A block from the database, which might have an execution payload or not.
@@ -324,4 +342,73 @@ mod tests {
 
         assert!(!store.exists::<StorableThing>(&key).unwrap());
     }
+
+    /// Returns the name and contents of each file in the directory at `path`.
+    fn directory_contents(path: &std::path::Path) -> Vec<(std::ffi::OsString, Vec<u8>)> {
+        let mut contents = std::fs::read_dir(path)
+            .unwrap()
+            .map(|entry| {
+                let entry = entry.unwrap();
+                (entry.file_name(), std::fs::read(entry.path()).unwrap())
+            })
+            .collect::<Vec<_>>();
+        contents.sort();
+        contents
+    }
+
+    #[test]
+    fn read_only_directory() {
+        let dir = tempdir().unwrap();
+        let path = dir.path();
+        let key = Hash256::random();
+        let item = StorableThing { a: 1, b: 42 };
+        LevelDB::<MinimalEthSpec>::open(path)
+            .unwrap()
+            .put(&key, &item)
+            .unwrap();
+
+        let set_readonly = |readonly| {
+            let mut permissions = std::fs::metadata(path).unwrap().permissions();
+            permissions.set_readonly(readonly);
+            std::fs::set_permissions(path, permissions).unwrap();
+        };
+
+        // The database can be opened on read-only storage, without modifying it.
+        set_readonly(true);
+        let contents = directory_contents(path);
+        let store = LevelDB::<MinimalEthSpec>::open_read_only(path).unwrap();
+        assert_eq!(
+            store.get::<StorableThing>(&key).unwrap(),
+            Some(StorableThing { a: 1, b: 42 })
+        );
+        assert!(matches!(
+            store.delete::<StorableThing>(&key),
+            Err(Error::ReadOnlyDatabase)
+        ));
+        drop(store);
+        let contents_after = directory_contents(path);
+        set_readonly(false);
+        assert_eq!(contents_after, contents);
+
+        // It may also be opened whilst it's open for writing elsewhere.
+        let writable_store = LevelDB::<MinimalEthSpec>::open(path).unwrap();
+        let store = LevelDB::<MinimalEthSpec>::open_read_only(path).unwrap();
+        assert_eq!(
+            store.get::<StorableThing>(&key).unwrap(),
+            Some(StorableThing { a: 1, b: 42 })
+        );
+        drop(writable_store);
+    }
+
+    #[test]
+    fn read_only_missing_database() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("missing");
+
+        assert!(matches!(
+            LevelDB::<MinimalEthSpec>::open_read_only(&path),
+            Err(Error::ReadOnlyDatabaseOverlay { .. })
+        ));
+        assert!(!path.exists());
+    }
 }
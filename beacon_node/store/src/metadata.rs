@@ -4,7 +4,7 @@ use ssz::{Decode, Encode};
 use ssz_derive::{Decode, Encode};
 use types::{Checkpoint, Hash256, Slot};
 
-pub const CURRENT_SCHEMA_VERSION: SchemaVersion = SchemaVersion(9);
+pub const CURRENT_SCHEMA_VERSION: SchemaVersion = SchemaVersion(10);
 
 // All the keys that get stored under the `BeaconMeta` column.
 //
@@ -15,6 +15,7 @@ pub const SPLIT_KEY: Hash256 = Hash256::repeat_byte(2);
 pub const PRUNING_CHECKPOINT_KEY: Hash256 = Hash256::repeat_byte(3);
 pub const COMPACTION_TIMESTAMP_KEY: Hash256 = Hash256::repeat_byte(4);
 pub const ANCHOR_INFO_KEY: Hash256 = Hash256::repeat_byte(5);
+pub const RESTORE_POINT_MIGRATION_KEY: Hash256 = Hash256::repeat_byte(6);
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct SchemaVersion(pub u64);
@@ -117,3 +118,30 @@ impl StoreItem for AnchorInfo {
         Ok(Self::from_ssz_bytes(bytes)?)
     }
 }
+
+/// Marker for a restore point migration which is in progress.
+#[derive(Debug, PartialEq, Eq, Clone, Encode, Decode)]
+pub struct RestorePointMigration {
+    /// The spacing of the restore points in the restore point index.
+    pub old_slots_per_restore_point: u64,
+    /// The spacing of the restore points being written.
+    pub new_slots_per_restore_point: u64,
+    /// The slot from which new restore points are yet to be written (>=).
+    pub progress_slot: Slot,
+    /// Whether the restore point index may have been replaced by the new restore points.
+    pub replacing_index: bool,
+}
+
+impl StoreItem for RestorePointMigration {
+    fn db_column() -> DBColumn {
+        DBColumn::BeaconMeta
+    }
+
+    fn as_store_bytes(&self) -> Vec<u8> {
+        self.as_ssz_bytes()
+    }
+
+    fn from_store_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Ok(Self::from_ssz_bytes(bytes)?)
+    }
+}
@@ -90,6 +90,14 @@ lazy_static! {
         "store_beacon_state_write_bytes_total",
         "Total number of beacon state bytes written to the DB"
     );
+    pub static ref BEACON_STATE_DIFF_WRITE_COUNT: Result<IntCounter> = try_create_int_counter(
+        "store_beacon_state_diff_write_total",
+        "Total number of beacon states written to the DB as diffs"
+    );
+    pub static ref BEACON_STATE_DIFF_WRITE_BYTES: Result<IntCounter> = try_create_int_counter(
+        "store_beacon_state_diff_write_bytes_total",
+        "Total number of beacon state diff bytes written to the DB"
+    );
     /*
      * Beacon Block
      */
@@ -0,0 +1,344 @@
+//! Re-spacing of the restore points in the freezer database.
+//!
+//! The migration is run in steps by the background migrator, whilst the freezer continues to be
+//! read using the old spacing:
+//!
+//! 1. A `RestorePointMigration` marker is written to the hot database before the freezer is
+//!    modified.
+//! 2. The state of each new restore point is written to the freezer, and its state root is staged
+//!    in the `BeaconRestorePointMigration` column. The progress is recorded in the marker after
+//!    each step.
+//! 3. The marker is flagged as `replacing_index`, then the restore point index is replaced by the
+//!    staged index in a single batch. Finally, the new spacing is recorded and the marker deleted.
+//!
+//! When the database is opened with a marker present, the migration is resumed if the same
+//! migration is still requested, completed if the index may already have been replaced, and rolled
+//! back otherwise.
+use crate::hot_cold_store::{HotColdDB, HotColdDBError};
+use crate::metadata::{AnchorInfo, RestorePointMigration, CONFIG_KEY, RESTORE_POINT_MIGRATION_KEY};
+use crate::{
+    config::OnDiskStoreConfig, get_key_for_col, DBColumn, Error, ItemStore, KeyValueStore,
+    KeyValueStoreOp, StoreItem,
+};
+use slog::info;
+use ssz::{Decode, Encode};
+use types::{EthSpec, Hash256, Slot};
+
+/// The number of new restore points written by each step of the migration.
+const RESTORE_POINT_MIGRATION_STEP_SIZE: usize = 64;
+
+impl<E, Hot, Cold> HotColdDB<E, Hot, Cold>
+where
+    E: EthSpec,
+    Hot: KeyValueStore<E> + ItemStore<E>,
+    Cold: KeyValueStore<E> + ItemStore<E>,
+{
+    /// Return `true` if the restore points in the freezer are yet to be re-spaced to the
+    /// configured `slots_per_restore_point`.
+    ///
+    /// A migration is only ever run if `force_restore_point_migration` is set.
+    pub fn restore_point_migration_pending(&self) -> bool {
+        self.config.force_restore_point_migration
+            && self.get_slots_per_restore_point() != self.config.slots_per_restore_point
+    }
+
+    /// Run the next step of the pending restore point migration, returning `true` once the
+    /// migration is complete.
+    ///
+    /// Each step writes at most `RESTORE_POINT_MIGRATION_STEP_SIZE` restore point states, so that
+    /// other work can be interleaved with the migration. The freezer must not be written to
+    /// concurrently, i.e. this should only be called by the background migrator.
+    pub fn restore_point_migration_step(&self) -> Result<bool, Error> {
+        if !self.restore_point_migration_pending() {
+            return Ok(true);
+        }
+        let old_slots_per_restore_point = self.get_slots_per_restore_point();
+        let new_slots_per_restore_point = self.config.slots_per_restore_point;
+
+        // Record the migration before touching the freezer, so that it can be resumed or rolled
+        // back if it is interrupted.
+        let mut migration = match self.load_restore_point_migration()? {
+            Some(migration) => migration,
+            None => {
+                let migration = RestorePointMigration {
+                    old_slots_per_restore_point,
+                    new_slots_per_restore_point,
+                    progress_slot: Slot::new(0),
+                    replacing_index: false,
+                };
+                self.hot_db.put(&RESTORE_POINT_MIGRATION_KEY, &migration)?;
+
+                info!(
+                    self.log,
+                    "Migrating freezer restore points";
+                    "from_slots_per_restore_point" => old_slots_per_restore_point,
+                    "to_slots_per_restore_point" => new_slots_per_restore_point,
+                );
+                migration
+            }
+        };
+
+        // 1. Write the state of each new restore point which isn't already a restore point, and
+        // stage its state root for the new index.
+        let split_slot = self.get_split_slot();
+        let new_restore_point_slots = self
+            .restore_point_slots(new_slots_per_restore_point, split_slot)
+            .skip_while(|slot| *slot < migration.progress_slot)
+            .take(RESTORE_POINT_MIGRATION_STEP_SIZE)
+            .collect::<Vec<_>>();
+
+        if let Some(last_slot) = new_restore_point_slots.last() {
+            for slot in &new_restore_point_slots {
+                let mut ops = vec![];
+                let state_root = if *slot % old_slots_per_restore_point == 0 {
+                    self.load_restore_point_hash(slot.as_u64() / old_slots_per_restore_point)?
+                } else {
+                    let mut state = self
+                        .load_cold_state_by_slot(*slot)?
+                        .ok_or(Error::HistoryUnavailable)?;
+                    let state_root = state.update_tree_hash_cache()?;
+                    self.store_restore_point_state(&state_root, &state, &mut ops)?;
+                    state_root
+                };
+                ops.push(KeyValueStoreOp::PutKeyValue(
+                    staged_restore_point_key(slot.as_u64() / new_slots_per_restore_point),
+                    state_root.as_ssz_bytes(),
+                ));
+                self.cold_db.do_atomically(ops)?;
+            }
+
+            migration.progress_slot = *last_slot + 1;
+            self.hot_db.put(&RESTORE_POINT_MIGRATION_KEY, &migration)?;
+
+            info!(
+                self.log,
+                "Restore point migration in progress";
+                "migrated_to_slot" => last_slot,
+                "split_slot" => split_slot,
+            );
+            return Ok(false);
+        }
+
+        // 2. Replace the restore point index once all of the new restore points are staged.
+        migration.replacing_index = true;
+        self.hot_db.put(&RESTORE_POINT_MIGRATION_KEY, &migration)?;
+        self.replace_restore_point_index(&migration)?;
+
+        Ok(true)
+    }
+
+    /// Resume, complete or roll back a restore point migration which was interrupted when the
+    /// database was last open.
+    ///
+    /// The migration is resumed if `force_restore_point_migration` is set and the configured
+    /// `slots_per_restore_point` is the target of the interrupted migration. If the restore point
+    /// index was being replaced then the migration is completed, since the old index may be gone.
+    /// Otherwise the new restore points are deleted, leaving the freezer as it was before the
+    /// migration.
+    pub(crate) fn recover_restore_point_migration(&mut self) -> Result<(), Error> {
+        let migration = match self.load_restore_point_migration()? {
+            Some(migration) => migration,
+            None => return Ok(()),
+        };
+
+        if self.config.freezer_is_read_only() {
+            return Err(HotColdDBError::FreezerReadOnlyRestorePointMigration.into());
+        }
+
+        *self.restore_point_spacing.get_mut() = migration.old_slots_per_restore_point;
+
+        if migration.replacing_index {
+            info!(
+                self.log,
+                "Completing interrupted restore point migration";
+                "slots_per_restore_point" => migration.new_slots_per_restore_point,
+            );
+            self.replace_restore_point_index(&migration)
+        } else if self.config.force_restore_point_migration
+            && self.config.slots_per_restore_point == migration.new_slots_per_restore_point
+        {
+            info!(
+                self.log,
+                "Resuming interrupted restore point migration";
+                "slots_per_restore_point" => migration.new_slots_per_restore_point,
+                "migrated_to_slot" => migration.progress_slot,
+            );
+            Ok(())
+        } else {
+            info!(
+                self.log,
+                "Rolling back interrupted restore point migration";
+                "slots_per_restore_point" => migration.old_slots_per_restore_point,
+                "msg" => "use --force-restore-point-migration to resume the migration",
+            );
+            self.roll_back_restore_point_migration(&migration)
+        }
+    }
+
+    /// Replace the restore point index with the staged index, deleting the states of the old
+    /// restore points which are no longer needed, then record the new spacing.
+    ///
+    /// The summaries of the deleted states are kept, so they can still be loaded as intermediate
+    /// states. This function may be re-run if it is interrupted.
+    fn replace_restore_point_index(&self, migration: &RestorePointMigration) -> Result<(), Error> {
+        let old_slots_per_restore_point = migration.old_slots_per_restore_point;
+        let new_slots_per_restore_point = migration.new_slots_per_restore_point;
+
+        // Block readers of restore points until the index and spacing are consistent again.
+        let mut restore_point_spacing = self.restore_point_spacing.write();
+
+        // The staged index is deleted in the same batch which replaces the index, so if nothing is
+        // staged the index has already been replaced.
+        let staged = self
+            .restore_point_slots(new_slots_per_restore_point, self.get_split_slot())
+            .filter_map(|slot| {
+                let index = slot.as_u64() / new_slots_per_restore_point;
+                self.load_staged_restore_point_hash(index)
+                    .transpose()
+                    .map(|state_root| state_root.map(|state_root| (index, state_root)))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        if !staged.is_empty() {
+            let mut ops = vec![];
+            for slot in self.restore_point_slots(old_slots_per_restore_point, self.get_split_slot())
+            {
+                let index = slot.as_u64() / old_slots_per_restore_point;
+                if slot % new_slots_per_restore_point != 0 {
+                    let state_root = self.load_restore_point_hash(index)?;
+                    ops.push(KeyValueStoreOp::DeleteKey(get_key_for_col(
+                        DBColumn::BeaconState.into(),
+                        state_root.as_bytes(),
+                    )));
+                }
+                ops.push(KeyValueStoreOp::DeleteKey(get_key_for_col(
+                    DBColumn::BeaconRestorePoint.into(),
+                    Self::restore_point_key(index).as_bytes(),
+                )));
+            }
+            for (index, state_root) in staged {
+                self.store_restore_point_hash(index, state_root, &mut ops);
+                ops.push(KeyValueStoreOp::DeleteKey(staged_restore_point_key(index)));
+            }
+            self.cold_db.do_atomically(ops)?;
+        }
+
+        // Align the limits of the available states to the new restore points, so that they only
+        // include states which can be loaded, and record the new spacing.
+        let anchor = self.get_anchor_info();
+        let new_anchor = anchor.clone().map(|anchor| AnchorInfo {
+            state_upper_limit: align_up(anchor.state_upper_limit, new_slots_per_restore_point),
+            state_lower_limit: align_down(anchor.state_lower_limit, new_slots_per_restore_point),
+            ..anchor
+        });
+        let disk_config = OnDiskStoreConfig {
+            slots_per_restore_point: new_slots_per_restore_point,
+        };
+        self.hot_db.do_atomically(vec![
+            self.compare_and_set_anchor_info(anchor, new_anchor)?,
+            disk_config.as_kv_store_op(CONFIG_KEY),
+            delete_migration_op(),
+        ])?;
+        *restore_point_spacing = new_slots_per_restore_point;
+
+        info!(
+            self.log,
+            "Restore point migration complete";
+            "slots_per_restore_point" => new_slots_per_restore_point,
+        );
+
+        Ok(())
+    }
+
+    /// Delete the new restore point states and the staged index, then the migration marker.
+    fn roll_back_restore_point_migration(
+        &self,
+        migration: &RestorePointMigration,
+    ) -> Result<(), Error> {
+        let new_slots_per_restore_point = migration.new_slots_per_restore_point;
+
+        // Restore points may have been written beyond the recorded progress before the migration
+        // was interrupted.
+        let mut ops = vec![];
+        for slot in self.restore_point_slots(new_slots_per_restore_point, self.get_split_slot()) {
+            let index = slot.as_u64() / new_slots_per_restore_point;
+            let state_root = match self.load_staged_restore_point_hash(index)? {
+                Some(state_root) => state_root,
+                None => continue,
+            };
+            if slot % migration.old_slots_per_restore_point != 0 {
+                ops.push(KeyValueStoreOp::DeleteKey(get_key_for_col(
+                    DBColumn::BeaconState.into(),
+                    state_root.as_bytes(),
+                )));
+            }
+            ops.push(KeyValueStoreOp::DeleteKey(staged_restore_point_key(index)));
+        }
+        self.cold_db.do_atomically(ops)?;
+        self.hot_db.do_atomically(vec![delete_migration_op()])
+    }
+
+    /// The slots of the restore points below `end_slot` for the given spacing, excluding any gap
+    /// left by checkpoint sync.
+    fn restore_point_slots(
+        &self,
+        slots_per_restore_point: u64,
+        end_slot: Slot,
+    ) -> impl Iterator<Item = Slot> {
+        let (lower_limit, upper_limit) = self.get_historic_state_limits();
+        (0..)
+            .map(move |index| Slot::new(index * slots_per_restore_point))
+            .take_while(move |slot| *slot < end_slot)
+            .filter(move |slot| *slot <= lower_limit || *slot >= upper_limit)
+    }
+
+    /// Load the staged state root of the new restore point with `restore_point_index`.
+    fn load_staged_restore_point_hash(
+        &self,
+        restore_point_index: u64,
+    ) -> Result<Option<Hash256>, Error> {
+        self.cold_db
+            .get_bytes(
+                DBColumn::BeaconRestorePointMigration.into(),
+                Hash256::from_low_u64_be(restore_point_index).as_bytes(),
+            )?
+            .map(|bytes| Hash256::from_ssz_bytes(&bytes).map_err(Into::into))
+            .transpose()
+    }
+
+    /// Load the marker of an in-progress restore point migration.
+    fn load_restore_point_migration(&self) -> Result<Option<RestorePointMigration>, Error> {
+        self.hot_db.get(&RESTORE_POINT_MIGRATION_KEY)
+    }
+}
+
+/// The key of the staged state root of the new restore point with `restore_point_index`.
+fn staged_restore_point_key(restore_point_index: u64) -> Vec<u8> {
+    get_key_for_col(
+        DBColumn::BeaconRestorePointMigration.into(),
+        Hash256::from_low_u64_be(restore_point_index).as_bytes(),
+    )
+}
+
+fn delete_migration_op() -> KeyValueStoreOp {
+    KeyValueStoreOp::DeleteKey(get_key_for_col(
+        RestorePointMigration::db_column().into(),
+        RESTORE_POINT_MIGRATION_KEY.as_bytes(),
+    ))
+}
+
+/// Round `slot` down to a multiple of `slots_per_restore_point`.
+fn align_down(slot: Slot, slots_per_restore_point: u64) -> Slot {
+    slot - slot % slots_per_restore_point
+}
+
+/// Round `slot` up to a multiple of `slots_per_restore_point`, leaving it unchanged if that would
+/// overflow.
+fn align_up(slot: Slot, slots_per_restore_point: u64) -> Slot {
+    if slot % slots_per_restore_point == 0 {
+        return slot;
+    }
+    align_down(slot, slots_per_restore_point)
+        .as_u64()
+        .checked_add(slots_per_restore_point)
+        .map_or(slot, Slot::new)
+}
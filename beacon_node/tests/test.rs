@@ -5,8 +5,9 @@ use beacon_chain::StateSkipConfig;
 use node_test_rig::{
     environment::{Environment, EnvironmentBuilder},
     eth2::types::StateId,
-    testing_client_config, LocalBeaconNode,
+    testing_client_config, ClientGenesis, LocalBeaconNode,
 };
+use sensitive_url::SensitiveUrl;
 use types::{EthSpec, MinimalEthSpec, Slot};
 
 fn env_builder() -> EnvironmentBuilder<MinimalEthSpec> {
@@ -62,3 +63,35 @@ fn http_server_genesis_state() {
 
     env.fire_signal();
 }
+
+#[test]
+fn startup_fails_with_invalid_config() {
+    let mut env = env_builder()
+        .null_logger()
+        .expect("should build env logger")
+        .multi_threaded_tokio_runtime()
+        .expect("should start tokio runtime")
+        .build()
+        .expect("environment should build");
+
+    // Checkpoint sync can't be used with the dummy eth1 backend of the testing config.
+    let mut client_config = testing_client_config();
+    client_config.genesis = ClientGenesis::CheckpointSyncUrl {
+        genesis_state_bytes: vec![],
+        url: SensitiveUrl::parse("http://localhost:5052").unwrap(),
+    };
+
+    let context = env.core_context();
+    let error = env
+        .runtime()
+        .block_on(LocalBeaconNode::production(context, client_config))
+        .err()
+        .expect("node should fail to start");
+    assert!(
+        error.starts_with("Invalid configuration: checkpoint sync can't be used"),
+        "{}",
+        error
+    );
+
+    env.fire_signal();
+}
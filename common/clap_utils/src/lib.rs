@@ -1,7 +1,7 @@
 //! A helper library for parsing values from `clap::ArgMatches`.
 
 use clap::ArgMatches;
-use eth2_network_config::{Eth2NetworkConfig, DEFAULT_HARDCODED_NETWORK};
+use eth2_network_config::{Eth2NetworkConfig, NetworkSelection, DEFAULT_HARDCODED_NETWORK};
 use ethereum_types::U256 as Uint256;
 use ssz::Decode;
 use std::path::PathBuf;
@@ -18,13 +18,24 @@ pub const BAD_TESTNET_DIR_MESSAGE: &str = "The hard-coded testnet directory was
 /// Try to parse the eth2 network config from the `network`, `testnet-dir` flags in that order.
 /// Returns the default hardcoded testnet if neither flags are set.
 pub fn get_eth2_network_config(cli_args: &ArgMatches) -> Result<Eth2NetworkConfig, String> {
-    let optional_network_config = if cli_args.is_present("network") {
-        parse_hardcoded_network(cli_args, "network")?
-    } else if cli_args.is_present("testnet-dir") {
-        parse_testnet_dir(cli_args, "testnet-dir")?
-    } else {
+    get_eth2_network_config_with_config_network(cli_args, None)
+}
+
+/// As per `get_eth2_network_config`, except that the network is selected by `config_network` (e.g.,
+/// the network named in a config file) if it is set.
+///
+/// Returns an error if the `network` or `testnet-dir` flags select a different network to
+/// `config_network`.
+pub fn get_eth2_network_config_with_config_network(
+    cli_args: &ArgMatches,
+    config_network: Option<NetworkSelection>,
+) -> Result<Eth2NetworkConfig, String> {
+    let optional_network_config = match config_network {
+        Some(config_network) => Some(select_network(cli_args, Some(config_network))?.load()?),
+        None if cli_args.is_present("network") => parse_hardcoded_network(cli_args, "network")?,
+        None if cli_args.is_present("testnet-dir") => parse_testnet_dir(cli_args, "testnet-dir")?,
         // if neither is present, assume the default network
-        Eth2NetworkConfig::constant(DEFAULT_HARDCODED_NETWORK)?
+        None => Eth2NetworkConfig::constant(DEFAULT_HARDCODED_NETWORK)?,
     };
 
     let mut eth2_network_config =
@@ -62,6 +73,38 @@ pub fn get_eth2_network_config(cli_args: &ArgMatches) -> Result<Eth2NetworkConfi
     Ok(eth2_network_config)
 }
 
+/// Returns the network selected by the `network` or `testnet-dir` flags, if either is set.
+pub fn parse_network_selection(cli_args: &ArgMatches) -> Result<Option<NetworkSelection>, String> {
+    if let Some(name) = parse_optional(cli_args, "network")? {
+        Ok(Some(NetworkSelection::Named(name)))
+    } else {
+        Ok(parse_optional(cli_args, "testnet-dir")?.map(NetworkSelection::Directory))
+    }
+}
+
+/// Selects `config_network` if it is set, otherwise the network selected by the `network` or
+/// `testnet-dir` flags, otherwise the default network.
+///
+/// Returns an error if the flags select a different network to `config_network`.
+pub fn select_network(
+    cli_args: &ArgMatches,
+    config_network: Option<NetworkSelection>,
+) -> Result<NetworkSelection, String> {
+    match (config_network, parse_network_selection(cli_args)?) {
+        (Some(config_network), Some(cli_network))
+            if !config_network.is_same_network(&cli_network) =>
+        {
+            Err(format!(
+                "The config file selects the {} network, which conflicts with the {} network \
+                 selected via the CLI",
+                config_network, cli_network
+            ))
+        }
+        (Some(network), _) | (None, Some(network)) => Ok(network),
+        (None, None) => Ok(NetworkSelection::default()),
+    }
+}
+
 /// Attempts to load the testnet dir at the path if `name` is in `matches`, returning an error if
 /// the path cannot be found or the testnet dir is invalid.
 pub fn parse_testnet_dir(
@@ -1,4 +1,5 @@
 use clap::ArgMatches;
+use eth2_network_config::NetworkSelection;
 pub use eth2_network_config::DEFAULT_HARDCODED_NETWORK;
 use std::fs::{self, create_dir_all};
 use std::path::{Path, PathBuf};
@@ -29,6 +30,14 @@ pub fn get_network_dir(matches: &ArgMatches) -> String {
     }
 }
 
+/// Gets the network directory name for `network`.
+pub fn network_dir_name(network: &NetworkSelection) -> String {
+    match network {
+        NetworkSelection::Named(network_name) => network_name.clone(),
+        NetworkSelection::Directory(_) => CUSTOM_TESTNET_DIR.to_string(),
+    }
+}
+
 /// Checks if a directory exists in the given path and creates a directory if it does not exist.
 pub fn ensure_dir_exists<P: AsRef<Path>>(path: P) -> Result<(), String> {
     let path = path.as_ref();
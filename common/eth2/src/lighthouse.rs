@@ -2,18 +2,21 @@
 
 mod attestation_performance;
 mod block_packing_efficiency;
+mod block_replay;
 mod block_rewards;
 
 use crate::{
     ok_or_error,
-    types::{BeaconState, ChainSpec, Epoch, EthSpec, GenericResponse, ValidatorId},
+    types::{BeaconState, ChainSpec, Epoch, EthSpec, GenericResponse, SelfTestReport, ValidatorId},
     BeaconNodeHttpClient, DepositData, Error, Eth1Data, Hash256, StateId, StatusCode,
 };
 use proto_array::core::ProtoArray;
 use reqwest::IntoUrl;
+use sensitive_url::SensitiveUrl;
 use serde::{Deserialize, Serialize};
 use ssz::four_byte_option_impl;
 use ssz_derive::{Decode, Encode};
+use std::collections::BTreeMap;
 use store::{AnchorInfo, Split, StoreConfig};
 
 pub use attestation_performance::{
@@ -22,6 +25,7 @@ pub use attestation_performance::{
 pub use block_packing_efficiency::{
     BlockPackingEfficiency, BlockPackingEfficiencyQuery, ProposerInfo, UniqueAttestation,
 };
+pub use block_replay::{ReplayReport, ReplayTimings};
 pub use block_rewards::{AttestationRewards, BlockReward, BlockRewardMeta, BlockRewardsQuery};
 pub use lighthouse_network::{types::SyncState, PeerInfo};
 
@@ -82,6 +86,21 @@ pub struct ValidatorInclusionData {
     /// True if the validator's beacon block root attestation in the _previous_ epoch at the
     /// attestation's slot (`attestation_data.slot`) matches the block root known to the state.
     pub is_previous_epoch_head_attester: bool,
+    /// The inclusion of the validator's sync committee messages in the blocks of the epoch.
+    ///
+    /// Only present if the validator is monitored by the validator monitor, the epoch is recent
+    /// enough to be retained by it and the validator was in the sync committee during the epoch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sync_committee_inclusion: Option<SyncCommitteeInclusionData>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SyncCommitteeInclusionData {
+    /// The number of times the validator's sync signature was included in a sync aggregate.
+    pub included: u64,
+    /// The number of sync signatures expected from the validator, i.e. one for each of its
+    /// positions in the sync committee, for each block.
+    pub expected: u64,
 }
 
 #[cfg(target_os = "linux")]
@@ -285,6 +304,26 @@ impl Health {
     }
 }
 
+/// The health of an execution endpoint, as observed by the beacon node.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ExecutionEngineHealth {
+    /// The priority of the endpoint, where `0` is the primary.
+    pub index: usize,
+    /// The endpoint URL, with any credentials redacted.
+    pub endpoint: String,
+    /// `true` if execution requests are currently routed to this endpoint.
+    pub active: bool,
+    pub synced: bool,
+    pub consecutive_failures: u64,
+    pub seconds_since_last_success: Option<u64>,
+}
+
+/// A request to disconnect from the builder at `url`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RemoveBuilderRequest {
+    pub url: SensitiveUrl,
+}
+
 /// Indicates how up-to-date the Eth1 caches are.
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Eth1SyncStatusData {
@@ -297,6 +336,18 @@ pub struct Eth1SyncStatusData {
     pub lighthouse_is_cached_and_ready: bool,
 }
 
+/// The contents of the Eth1 block and deposit caches.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Eth1CacheStatusData {
+    pub latest_cached_block_number: Option<u64>,
+    pub latest_cached_block_timestamp: Option<u64>,
+    pub deposit_count: u64,
+    /// The block which the cache follows, `cache_follow_distance` blocks behind the Eth1 head.
+    pub follow_head_block_number: Option<u64>,
+    /// The number of blocks between the latest cached block and the follow head.
+    pub blocks_behind_follow_head: Option<u64>,
+}
+
 /// A fully parsed eth1 deposit contract log.
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize, Encode, Decode)]
 pub struct DepositLog {
@@ -331,6 +382,31 @@ impl Eth1Block {
     }
 }
 
+/// A summary of the exit queue and withdrawal credentials of the head state.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExitQueueSummary {
+    /// The current epoch of the head state.
+    pub epoch: Epoch,
+    /// The root of the head block when the summary was computed.
+    pub head_block_root: Hash256,
+    /// The proposer shuffling decision root of the head state for `epoch`.
+    pub decision_root: Hash256,
+    /// The number of validators which have initiated an exit but not yet exited.
+    pub exiting_validators: u64,
+    /// The furthest exit epoch of the exiting validators, if any.
+    pub max_exit_epoch: Option<Epoch>,
+    /// The exit epoch which would be assigned to a validator initiating an exit now, unless the
+    /// epoch is already at the churn limit.
+    pub exit_queue_epoch: Epoch,
+    /// The number of validators already scheduled to exit in `exit_queue_epoch`.
+    pub exit_queue_churn: u64,
+    /// The maximum number of validators which may exit in any epoch.
+    pub churn_limit: u64,
+    /// The number of validators with each withdrawal credential prefix, keyed by the prefix in
+    /// hex (e.g., "0x00" for BLS credentials and "0x01" for execution addresses).
+    pub withdrawal_credential_prefixes: BTreeMap<String, u64>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct DatabaseInfo {
     pub schema_version: u64,
@@ -373,6 +449,36 @@ impl BeaconNodeHttpClient {
         self.get(path).await
     }
 
+    /// `GET lighthouse/health/execution_engines`
+    pub async fn get_lighthouse_health_execution_engines(
+        &self,
+    ) -> Result<GenericResponse<Vec<ExecutionEngineHealth>>, Error> {
+        let mut path = self.server.full.clone();
+
+        path.path_segments_mut()
+            .map_err(|()| Error::InvalidUrl(self.server.clone()))?
+            .push("lighthouse")
+            .push("health")
+            .push("execution_engines");
+
+        self.get(path).await
+    }
+
+    /// `GET lighthouse/health/self_test`
+    pub async fn get_lighthouse_health_self_test(
+        &self,
+    ) -> Result<GenericResponse<SelfTestReport>, Error> {
+        let mut path = self.server.full.clone();
+
+        path.path_segments_mut()
+            .map_err(|()| Error::InvalidUrl(self.server.clone()))?
+            .push("lighthouse")
+            .push("health")
+            .push("self_test");
+
+        self.get(path).await
+    }
+
     /// `GET lighthouse/syncing`
     pub async fn get_lighthouse_syncing(&self) -> Result<GenericResponse<SyncState>, Error> {
         let mut path = self.server.full.clone();
@@ -455,6 +561,21 @@ impl BeaconNodeHttpClient {
         self.get(path).await
     }
 
+    /// `GET lighthouse/eth1/cache_status`
+    pub async fn get_lighthouse_eth1_cache_status(
+        &self,
+    ) -> Result<GenericResponse<Eth1CacheStatusData>, Error> {
+        let mut path = self.server.full.clone();
+
+        path.path_segments_mut()
+            .map_err(|()| Error::InvalidUrl(self.server.clone()))?
+            .push("lighthouse")
+            .push("eth1")
+            .push("cache_status");
+
+        self.get(path).await
+    }
+
     /// `GET lighthouse/eth1/block_cache`
     pub async fn get_lighthouse_eth1_block_cache(
         &self,
@@ -544,4 +665,19 @@ impl BeaconNodeHttpClient {
 
         self.post_with_response(path, &()).await
     }
+
+    /// `GET lighthouse/analysis/exit_queue`
+    pub async fn get_lighthouse_analysis_exit_queue(
+        &self,
+    ) -> Result<GenericResponse<ExitQueueSummary>, Error> {
+        let mut path = self.server.full.clone();
+
+        path.path_segments_mut()
+            .map_err(|()| Error::InvalidUrl(self.server.clone()))?
+            .push("lighthouse")
+            .push("analysis")
+            .push("exit_queue");
+
+        self.get(path).await
+    }
 }
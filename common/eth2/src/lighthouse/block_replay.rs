@@ -0,0 +1,35 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use types::{Hash256, Slot};
+
+/// The result of re-executing the state transition of a stored block on its parent state.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct ReplayReport {
+    pub block_root: Hash256,
+    pub slot: Slot,
+    pub parent_root: Hash256,
+    /// The root of the post-state computed by the replay, if it completed.
+    pub computed_state_root: Option<Hash256>,
+    /// The root of the post-state stored for the block, as committed to by the block.
+    pub stored_state_root: Hash256,
+    /// Whether the computed state root is equal to the stored state root.
+    pub state_roots_match: bool,
+    pub timings: ReplayTimings,
+    /// The error which stopped the replay, if any.
+    pub error: Option<String>,
+}
+
+/// The time taken by each phase of a replay.
+///
+/// Phases which weren't reached are zero.
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
+pub struct ReplayTimings {
+    /// Loading the block and its parent state from the database.
+    pub load: Duration,
+    /// Advancing the parent state through any skipped slots to the slot of the block.
+    pub slot_processing: Duration,
+    /// Processing the block.
+    pub block_processing: Duration,
+    /// Computing the root of the post-state.
+    pub state_root: Duration,
+}
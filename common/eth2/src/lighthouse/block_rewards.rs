@@ -5,9 +5,6 @@ use types::{AttestationData, Hash256, Slot};
 /// Details about the rewards paid to a block proposer for proposing a block.
 ///
 /// All rewards in GWei.
-///
-/// Presently this only counts attestation rewards, but in future should be expanded
-/// to include information on slashings and sync committee aggregates too.
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct BlockReward {
     /// Sum of all reward components.
@@ -20,6 +17,10 @@ pub struct BlockReward {
     pub attestation_rewards: AttestationRewards,
     /// Sum of rewards due to sync committee signatures.
     pub sync_committee_rewards: u64,
+    /// Sum of rewards due to proposer slashings.
+    pub proposer_slashing_reward: u64,
+    /// Sum of rewards due to attester slashings.
+    pub attester_slashing_reward: u64,
 }
 
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
@@ -40,7 +41,8 @@ pub struct AttestationRewards {
     pub curr_epoch_total: u64,
     /// Vec of attestation rewards for each attestation included.
     ///
-    /// Each element of the vec is a map from validator index to reward.
+    /// Each element of the vec is a map from validator index to reward. From Altair the rewards
+    /// of each validator are rounded down individually, so they may sum to less than the total.
     pub per_attestation_rewards: Vec<HashMap<u64, u64>>,
     /// The attestations themselves (optional).
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
@@ -522,6 +522,8 @@ pub struct VersionData {
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct SyncingData {
     pub is_syncing: bool,
+    /// Absent from the responses of nodes which don't support optimistic sync.
+    pub is_optimistic: Option<bool>,
     pub head_slot: Slot,
     pub sync_distance: Slot,
 }
@@ -811,6 +813,15 @@ pub struct SseHead {
     pub current_duty_dependent_root: Hash256,
     pub previous_duty_dependent_root: Hash256,
     pub epoch_transition: bool,
+    /// The weight (in Gwei) of the head block in fork choice, including any proposer boost.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub head_weight: Option<u64>,
+    /// The greatest weight of the other children of the head's parent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub competitor_weight: Option<u64>,
+    /// The proposer boost included in `head_weight`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proposer_boost: Option<u64>,
 }
 
 #[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
@@ -825,6 +836,145 @@ pub struct SseChainReorg {
     pub epoch: Epoch,
 }
 
+/// A change to the canonical chain, describing which blocks were added to or removed from it.
+///
+/// If the blocks between the common ancestor and the heads are no longer known (e.g. after a very
+/// deep re-org), only the common ancestor is reported and both lists of blocks are empty.
+#[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
+pub struct SseCanonicalChainUpdate {
+    /// Increments by one for each update, allowing consumers to detect missed updates.
+    #[serde(with = "eth2_serde_utils::quoted_u64")]
+    pub sequence_number: u64,
+    pub slot: Slot,
+    pub old_head_block: Hash256,
+    pub new_head_block: Hash256,
+    pub common_ancestor: Hash256,
+    /// The blocks which became canonical, from the oldest up to and including the new head.
+    pub canonical_blocks: Vec<Hash256>,
+    /// The blocks which are no longer canonical, from the oldest up to and including the old head.
+    pub non_canonical_blocks: Vec<Hash256>,
+}
+
+/// The changes to the validator registry between the first states of `epoch - 1` and `epoch`.
+#[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
+pub struct SseValidatorRegistryUpdate {
+    pub epoch: Epoch,
+    /// The root of the first state of `epoch`.
+    pub state: Hash256,
+    /// Validators which became eligible to join the activation queue.
+    #[serde(with = "eth2_serde_utils::quoted_u64_vec")]
+    pub activation_eligible: Vec<u64>,
+    /// Validators which were dequeued and scheduled for activation.
+    #[serde(with = "eth2_serde_utils::quoted_u64_vec")]
+    pub activations_queued: Vec<u64>,
+    #[serde(with = "eth2_serde_utils::quoted_u64_vec")]
+    pub exits_initiated: Vec<u64>,
+    #[serde(with = "eth2_serde_utils::quoted_u64_vec")]
+    pub withdrawable: Vec<u64>,
+    #[serde(with = "eth2_serde_utils::quoted_u64_vec")]
+    pub slashed: Vec<u64>,
+}
+
+/// A locally published block which failed to import.
+#[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
+pub struct SseInvalidPublishedBlock {
+    pub slot: Slot,
+    pub block: Hash256,
+    #[serde(with = "eth2_serde_utils::quoted_u64")]
+    pub proposer_index: u64,
+    pub error: String,
+}
+
+/// The severity of a delay in finality.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FinalityDelayLevel {
+    Healthy,
+    Warning,
+    Critical,
+}
+
+#[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
+pub struct SseFinalityDelay {
+    /// The wall-clock epoch.
+    pub epoch: Epoch,
+    /// The finalized epoch of the head.
+    pub finalized_epoch: Epoch,
+    #[serde(with = "eth2_serde_utils::quoted_u64")]
+    pub epochs_since_finality: u64,
+    pub level: FinalityDelayLevel,
+}
+
+/// The consequence of a failed self-test check for the readiness of the node.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SelfTestSeverity {
+    /// The node can operate, with reduced functionality.
+    Degraded,
+    /// The node can't operate correctly.
+    Fatal,
+}
+
+/// The outcome of a single self-test check.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase", tag = "status", content = "reason")]
+pub enum SelfTestStatus {
+    Passed,
+    /// The check doesn't apply to the configuration of the node.
+    Skipped(String),
+    Failed(String),
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SelfTestCheckResult {
+    pub name: String,
+    pub severity: SelfTestSeverity,
+    #[serde(flatten)]
+    pub status: SelfTestStatus,
+    pub duration_ms: u64,
+}
+
+/// The readiness of the node, as determined by the most severe failed self-test check.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SelfTestReadiness {
+    Ready,
+    Degraded,
+    NotReady,
+}
+
+/// The results of the self-test run at startup.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SelfTestReport {
+    pub readiness: SelfTestReadiness,
+    pub checks: Vec<SelfTestCheckResult>,
+}
+
+impl SelfTestReport {
+    pub fn new(checks: Vec<SelfTestCheckResult>) -> Self {
+        let worst_failure = checks
+            .iter()
+            .filter(|check| matches!(check.status, SelfTestStatus::Failed(_)))
+            .map(|check| check.severity)
+            .max();
+        let readiness = match worst_failure {
+            None => SelfTestReadiness::Ready,
+            Some(SelfTestSeverity::Degraded) => SelfTestReadiness::Degraded,
+            Some(SelfTestSeverity::Fatal) => SelfTestReadiness::NotReady,
+        };
+        Self { readiness, checks }
+    }
+}
+
+/// The means by which a block was delivered to the beacon node.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BlockDeliveryTransport {
+    Gossip,
+    Rpc,
+    Api,
+}
+
 #[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
 pub struct SseLateHead {
     pub slot: Slot,
@@ -832,6 +982,15 @@ pub struct SseLateHead {
     pub proposer_index: u64,
     pub peer_id: Option<String>,
     pub peer_client: Option<String>,
+    /// The transport over which the block was first delivered.
+    #[serde(default)]
+    pub transport: Option<BlockDeliveryTransport>,
+    /// The number of times the block was delivered after the first.
+    #[serde(default)]
+    pub duplicate_deliveries: u64,
+    /// The number of distinct peers which delivered the block.
+    #[serde(default)]
+    pub distinct_peers: u64,
     pub proposer_graffiti: String,
     pub block_delay: Duration,
     pub observed_delay: Option<Duration>,
@@ -848,8 +1007,12 @@ pub enum EventKind<T: EthSpec> {
     Head(SseHead),
     VoluntaryExit(SignedVoluntaryExit),
     ChainReorg(SseChainReorg),
+    CanonicalChainUpdate(SseCanonicalChainUpdate),
     ContributionAndProof(Box<SignedContributionAndProof<T>>),
     LateHead(SseLateHead),
+    ValidatorRegistryUpdate(SseValidatorRegistryUpdate),
+    FinalityDelay(SseFinalityDelay),
+    InvalidPublishedBlock(SseInvalidPublishedBlock),
     #[cfg(feature = "lighthouse")]
     BlockReward(BlockReward),
 }
@@ -863,8 +1026,12 @@ impl<T: EthSpec> EventKind<T> {
             EventKind::VoluntaryExit(_) => "voluntary_exit",
             EventKind::FinalizedCheckpoint(_) => "finalized_checkpoint",
             EventKind::ChainReorg(_) => "chain_reorg",
+            EventKind::CanonicalChainUpdate(_) => "canonical_chain_update",
             EventKind::ContributionAndProof(_) => "contribution_and_proof",
             EventKind::LateHead(_) => "late_head",
+            EventKind::ValidatorRegistryUpdate(_) => "validator_registry_update",
+            EventKind::FinalityDelay(_) => "finality_delay",
+            EventKind::InvalidPublishedBlock(_) => "invalid_published_block",
             #[cfg(feature = "lighthouse")]
             EventKind::BlockReward(_) => "block_reward",
         }
@@ -898,6 +1065,11 @@ impl<T: EthSpec> EventKind<T> {
             "chain_reorg" => Ok(EventKind::ChainReorg(serde_json::from_str(data).map_err(
                 |e| ServerError::InvalidServerSentEvent(format!("Chain Reorg: {:?}", e)),
             )?)),
+            "canonical_chain_update" => Ok(EventKind::CanonicalChainUpdate(
+                serde_json::from_str(data).map_err(|e| {
+                    ServerError::InvalidServerSentEvent(format!("Canonical Chain Update: {:?}", e))
+                })?,
+            )),
             "finalized_checkpoint" => Ok(EventKind::FinalizedCheckpoint(
                 serde_json::from_str(data).map_err(|e| {
                     ServerError::InvalidServerSentEvent(format!("Finalized Checkpoint: {:?}", e))
@@ -914,6 +1086,24 @@ impl<T: EthSpec> EventKind<T> {
                     ServerError::InvalidServerSentEvent(format!("Voluntary Exit: {:?}", e))
                 })?,
             )),
+            "validator_registry_update" => Ok(EventKind::ValidatorRegistryUpdate(
+                serde_json::from_str(data).map_err(|e| {
+                    ServerError::InvalidServerSentEvent(format!(
+                        "Validator Registry Update: {:?}",
+                        e
+                    ))
+                })?,
+            )),
+            "finality_delay" => Ok(EventKind::FinalityDelay(
+                serde_json::from_str(data).map_err(|e| {
+                    ServerError::InvalidServerSentEvent(format!("Finality Delay: {:?}", e))
+                })?,
+            )),
+            "invalid_published_block" => Ok(EventKind::InvalidPublishedBlock(
+                serde_json::from_str(data).map_err(|e| {
+                    ServerError::InvalidServerSentEvent(format!("Invalid Published Block: {:?}", e))
+                })?,
+            )),
             "contribution_and_proof" => Ok(EventKind::ContributionAndProof(Box::new(
                 serde_json::from_str(data).map_err(|e| {
                     ServerError::InvalidServerSentEvent(format!("Contribution and Proof: {:?}", e))
@@ -946,8 +1136,12 @@ pub enum EventTopic {
     VoluntaryExit,
     FinalizedCheckpoint,
     ChainReorg,
+    CanonicalChainUpdate,
     ContributionAndProof,
     LateHead,
+    ValidatorRegistryUpdate,
+    FinalityDelay,
+    InvalidPublishedBlock,
     #[cfg(feature = "lighthouse")]
     BlockReward,
 }
@@ -963,8 +1157,12 @@ impl FromStr for EventTopic {
             "voluntary_exit" => Ok(EventTopic::VoluntaryExit),
             "finalized_checkpoint" => Ok(EventTopic::FinalizedCheckpoint),
             "chain_reorg" => Ok(EventTopic::ChainReorg),
+            "canonical_chain_update" => Ok(EventTopic::CanonicalChainUpdate),
             "contribution_and_proof" => Ok(EventTopic::ContributionAndProof),
             "late_head" => Ok(EventTopic::LateHead),
+            "validator_registry_update" => Ok(EventTopic::ValidatorRegistryUpdate),
+            "finality_delay" => Ok(EventTopic::FinalityDelay),
+            "invalid_published_block" => Ok(EventTopic::InvalidPublishedBlock),
             #[cfg(feature = "lighthouse")]
             "block_reward" => Ok(EventTopic::BlockReward),
             _ => Err("event topic cannot be parsed.".to_string()),
@@ -981,8 +1179,12 @@ impl fmt::Display for EventTopic {
             EventTopic::VoluntaryExit => write!(f, "voluntary_exit"),
             EventTopic::FinalizedCheckpoint => write!(f, "finalized_checkpoint"),
             EventTopic::ChainReorg => write!(f, "chain_reorg"),
+            EventTopic::CanonicalChainUpdate => write!(f, "canonical_chain_update"),
             EventTopic::ContributionAndProof => write!(f, "contribution_and_proof"),
             EventTopic::LateHead => write!(f, "late_head"),
+            EventTopic::ValidatorRegistryUpdate => write!(f, "validator_registry_update"),
+            EventTopic::FinalityDelay => write!(f, "finality_delay"),
+            EventTopic::InvalidPublishedBlock => write!(f, "invalid_published_block"),
             #[cfg(feature = "lighthouse")]
             EventTopic::BlockReward => write!(f, "block_reward"),
         }
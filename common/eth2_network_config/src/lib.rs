@@ -13,6 +13,8 @@
 
 use enr::{CombinedKey, Enr};
 use eth2_config::{instantiate_hardcoded_nets, HardcodedNet};
+use serde_derive::{Deserialize, Serialize};
+use std::fmt;
 use std::fs::{create_dir_all, File};
 use std::io::{Read, Write};
 use std::path::PathBuf;
@@ -32,6 +34,63 @@ instantiate_hardcoded_nets!(eth2_config);
 
 pub const DEFAULT_HARDCODED_NETWORK: &str = "mainnet";
 
+/// Selects the network a node connects to.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NetworkSelection {
+    /// One of the networks built into the binary (e.g., `mainnet` or `prater`).
+    Named(String),
+    /// A directory containing a `config.yaml`, `deploy_block.txt` and optionally a `genesis.ssz`
+    /// and `boot_enr.yaml`.
+    Directory(PathBuf),
+}
+
+impl Default for NetworkSelection {
+    fn default() -> Self {
+        NetworkSelection::Named(DEFAULT_HARDCODED_NETWORK.to_string())
+    }
+}
+
+impl NetworkSelection {
+    /// Load the configuration of the selected network.
+    pub fn load(&self) -> Result<Eth2NetworkConfig, String> {
+        match self {
+            NetworkSelection::Named(name) => Eth2NetworkConfig::constant(name)?.ok_or_else(|| {
+                format!(
+                    "Unknown network {}, expected one of: {}",
+                    name,
+                    HARDCODED_NET_NAMES.join(", ")
+                )
+            }),
+            NetworkSelection::Directory(dir) => Eth2NetworkConfig::load(dir.clone())
+                .map_err(|e| format!("Unable to open testnet dir at {:?}: {}", dir, e)),
+        }
+    }
+
+    /// Returns `true` if `self` and `other` select the same network, treating two paths which
+    /// resolve to the same directory as equal.
+    pub fn is_same_network(&self, other: &Self) -> bool {
+        match (self, other) {
+            (NetworkSelection::Directory(a), NetworkSelection::Directory(b)) => {
+                match (a.canonicalize(), b.canonicalize()) {
+                    (Ok(a), Ok(b)) => a == b,
+                    _ => a == b,
+                }
+            }
+            _ => self == other,
+        }
+    }
+}
+
+impl fmt::Display for NetworkSelection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NetworkSelection::Named(name) => write!(f, "{}", name),
+            NetworkSelection::Directory(dir) => write!(f, "custom ({})", dir.display()),
+        }
+    }
+}
+
 /// Specifies an Eth2 network.
 ///
 /// See the crate-level documentation for more details.
@@ -171,6 +230,20 @@ impl Eth2NetworkConfig {
     }
 
     pub fn load(base_dir: PathBuf) -> Result<Self, String> {
+        if !base_dir.is_dir() {
+            return Err(format!("{:?} is not a directory", base_dir));
+        }
+
+        // Check the required files up-front so that the user knows exactly what is missing.
+        for (file, contents) in [
+            (BASE_CONFIG_FILE, "the chain spec"),
+            (DEPLOY_BLOCK_FILE, "the deposit contract deploy block"),
+        ] {
+            if !base_dir.join(file).exists() {
+                return Err(format!("Missing {}, which must contain {}", file, contents));
+            }
+        }
+
         macro_rules! load_from_file {
             ($file: ident) => {
                 File::open(base_dir.join($file))
@@ -305,6 +378,67 @@ mod tests {
         do_test::<E>(None, None, config);
     }
 
+    #[test]
+    fn missing_files() {
+        let temp_dir = TempBuilder::new()
+            .prefix("eth2_testnet_test")
+            .tempdir()
+            .expect("should create temp dir");
+        let base_dir = temp_dir.path().join("my_testnet");
+
+        let error = Eth2NetworkConfig::load(base_dir.clone()).unwrap_err();
+        assert!(error.ends_with("is not a directory"), "{}", error);
+
+        Eth2NetworkConfig {
+            deposit_contract_deploy_block: 42,
+            boot_enr: None,
+            genesis_state_bytes: None,
+            config: Config::from_chain_spec::<E>(&E::default_spec()),
+        }
+        .write_to_file(base_dir.clone(), false)
+        .expect("should write to file");
+
+        std::fs::remove_file(base_dir.join(DEPLOY_BLOCK_FILE)).unwrap();
+        let error = Eth2NetworkConfig::load(base_dir.clone()).unwrap_err();
+        assert!(error.starts_with("Missing deploy_block.txt"), "{}", error);
+
+        std::fs::remove_file(base_dir.join(BASE_CONFIG_FILE)).unwrap();
+        let error = NetworkSelection::Directory(base_dir).load().unwrap_err();
+        assert!(error.contains("Missing config.yaml"), "{}", error);
+    }
+
+    #[test]
+    fn same_network() {
+        let temp_dir = TempBuilder::new()
+            .prefix("eth2_testnet_test")
+            .tempdir()
+            .expect("should create temp dir");
+        let dir = temp_dir.path().to_path_buf();
+        let named = |name: &str| NetworkSelection::Named(name.to_string());
+
+        assert!(named("prater").is_same_network(&named("prater")));
+        assert!(!named("prater").is_same_network(&named("mainnet")));
+        assert!(!named("prater").is_same_network(&NetworkSelection::Directory(dir.clone())));
+        assert!(NetworkSelection::Directory(dir.join("."))
+            .is_same_network(&NetworkSelection::Directory(dir.clone())));
+        assert!(!NetworkSelection::Directory(dir.join("a"))
+            .is_same_network(&NetworkSelection::Directory(dir)));
+    }
+
+    #[test]
+    fn network_selection() {
+        assert_eq!(
+            NetworkSelection::default().load().unwrap(),
+            Eth2NetworkConfig::constant(DEFAULT_HARDCODED_NETWORK)
+                .unwrap()
+                .unwrap()
+        );
+        assert!(NetworkSelection::Named("meow".to_string())
+            .load()
+            .unwrap_err()
+            .starts_with("Unknown network meow"));
+    }
+
     fn do_test<E: EthSpec>(
         boot_enr: Option<Vec<Enr<CombinedKey>>>,
         genesis_state: Option<BeaconState<E>>,
@@ -105,6 +105,19 @@ pub fn try_create_histogram(name: &str, help: &str) -> Result<Histogram> {
     Ok(histogram)
 }
 
+/// Attempts to create a `Histogram` with the given `buckets`, returning `Err` if the registry does
+/// not accept the counter (potentially due to naming conflict).
+pub fn try_create_histogram_with_buckets(
+    name: &str,
+    help: &str,
+    buckets: Vec<f64>,
+) -> Result<Histogram> {
+    let opts = HistogramOpts::new(name, help).buckets(buckets);
+    let histogram = Histogram::with_opts(opts)?;
+    prometheus::register(Box::new(histogram.clone()))?;
+    Ok(histogram)
+}
+
 /// Attempts to create a `HistogramVec`, returning `Err` if the registry does not accept the counter
 /// (potentially due to naming conflict).
 pub fn try_create_histogram_vec(
@@ -118,6 +131,20 @@ pub fn try_create_histogram_vec(
     Ok(histogram_vec)
 }
 
+/// Attempts to create a `HistogramVec` with the given `buckets`, returning `Err` if the registry
+/// does not accept the counter (potentially due to naming conflict).
+pub fn try_create_histogram_vec_with_buckets(
+    name: &str,
+    help: &str,
+    label_names: &[&str],
+    buckets: Vec<f64>,
+) -> Result<HistogramVec> {
+    let opts = HistogramOpts::new(name, help).buckets(buckets);
+    let histogram_vec = HistogramVec::new(opts, label_names)?;
+    prometheus::register(Box::new(histogram_vec.clone()))?;
+    Ok(histogram_vec)
+}
+
 /// Attempts to create a `IntGaugeVec`, returning `Err` if the registry does not accept the gauge
 /// (potentially due to naming conflict).
 pub fn try_create_int_gauge_vec(
@@ -0,0 +1,352 @@
+//! Allows the log level to be overridden for specific modules, e.g. to silence noisy networking
+//! logs whilst retaining debug logs from the rest of the node.
+//!
+//! Overrides are specified as comma-separated `target=level` pairs, where `target` is a module path
+//! (e.g. `network::beacon_processor=warn,beacon_chain=trace`). A target applies to the module and
+//! all of its descendants, with the longest matching target taking precedence.
+//!
+//! The overrides applied by a `LevelOverrideFilter` are shared via a `LevelOverridesHandle`, so
+//! that they may be replaced after the logger is built (e.g. once the client config is loaded).
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+use slog::{Drain, Level, OwnedKVList, Record};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+
+/// Per-module log level overrides.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LevelOverrides {
+    overrides: Vec<(String, Level)>,
+}
+
+impl LevelOverrides {
+    pub fn is_empty(&self) -> bool {
+        self.overrides.is_empty()
+    }
+
+    /// Returns the level of the most specific override which applies to `module`, if any.
+    pub fn level_for(&self, module: &str) -> Option<Level> {
+        self.overrides
+            .iter()
+            .filter(|(target, _)| {
+                module
+                    .strip_prefix(target.as_str())
+                    .map_or(false, |rest| rest.is_empty() || rest.starts_with("::"))
+            })
+            .max_by_key(|(target, _)| target.len())
+            .map(|(_, level)| *level)
+    }
+
+    /// Returns the most verbose level of any override.
+    fn most_verbose(&self) -> Option<Level> {
+        self.overrides
+            .iter()
+            .map(|(_, level)| *level)
+            .max_by_key(|level| level.as_usize())
+    }
+}
+
+impl FromStr for LevelOverrides {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let overrides = s
+            .split(',')
+            .map(str::trim)
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                let (target, level) = pair.split_once('=').ok_or_else(|| {
+                    format!("Invalid level override {}, expected target=level", pair)
+                })?;
+                let target = target.trim();
+                if target.is_empty() {
+                    return Err(format!("Invalid level override {}, empty target", pair));
+                }
+                Ok((target.to_string(), parse_level(level.trim())?))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self { overrides })
+    }
+}
+
+impl Serialize for LevelOverrides {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for LevelOverrides {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(D::Error::custom)
+    }
+}
+
+impl fmt::Display for LevelOverrides {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let pairs = self
+            .overrides
+            .iter()
+            .map(|(target, level)| format!("{}={}", target, level_name(*level)))
+            .collect::<Vec<_>>();
+        write!(f, "{}", pairs.join(","))
+    }
+}
+
+/// Parse a level using the same names as the `--debug-level` flag.
+pub fn parse_level(s: &str) -> Result<Level, String> {
+    match s {
+        "info" => Ok(Level::Info),
+        "debug" => Ok(Level::Debug),
+        "trace" => Ok(Level::Trace),
+        "warn" => Ok(Level::Warning),
+        "error" => Ok(Level::Error),
+        "crit" => Ok(Level::Critical),
+        unknown => Err(format!("Unknown debug-level: {}", unknown)),
+    }
+}
+
+fn level_name(level: Level) -> &'static str {
+    match level {
+        Level::Info => "info",
+        Level::Debug => "debug",
+        Level::Trace => "trace",
+        Level::Warning => "warn",
+        Level::Error => "error",
+        Level::Critical => "crit",
+    }
+}
+
+/// A shared, replaceable set of `LevelOverrides`.
+#[derive(Debug, Clone, Default)]
+pub struct LevelOverridesHandle(Arc<RwLock<LevelOverrides>>);
+
+impl LevelOverridesHandle {
+    pub fn new(overrides: LevelOverrides) -> Self {
+        Self(Arc::new(RwLock::new(overrides)))
+    }
+
+    /// Returns a copy of the current overrides.
+    pub fn get(&self) -> LevelOverrides {
+        self.read(Clone::clone)
+    }
+
+    /// Replace the overrides of all filters sharing this handle.
+    pub fn set(&self, overrides: LevelOverrides) {
+        // A panic whilst holding the lock can't leave the overrides in an invalid state.
+        *self.0.write().unwrap_or_else(|e| e.into_inner()) = overrides;
+    }
+
+    fn read<T>(&self, f: impl FnOnce(&LevelOverrides) -> T) -> T {
+        f(&self.0.read().unwrap_or_else(|e| e.into_inner()))
+    }
+}
+
+/// A drain which filters records by level, applying `LevelOverrides` for specific modules.
+pub struct LevelOverrideFilter<D> {
+    drain: D,
+    level: Level,
+    overrides: LevelOverridesHandle,
+}
+
+impl<D: Drain> LevelOverrideFilter<D> {
+    /// Pass records at `level` or above to `drain`, unless overridden by `overrides`.
+    pub fn new(drain: D, level: Level, overrides: LevelOverridesHandle) -> Self {
+        Self {
+            drain,
+            level,
+            overrides,
+        }
+    }
+}
+
+impl<D: Drain> Drain for LevelOverrideFilter<D> {
+    type Ok = Option<D::Ok>;
+    type Err = D::Err;
+
+    fn log(&self, record: &Record, values: &OwnedKVList) -> Result<Self::Ok, Self::Err> {
+        let level = self
+            .overrides
+            .read(|overrides| overrides.level_for(record.module()))
+            .unwrap_or(self.level);
+
+        if record.level().is_at_least(level) {
+            self.drain.log(record, values).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn is_enabled(&self, level: Level) -> bool {
+        let most_verbose = match self.overrides.read(LevelOverrides::most_verbose) {
+            Some(override_level) if self.level.is_at_least(override_level) => override_level,
+            _ => self.level,
+        };
+        level.is_at_least(most_verbose) && self.drain.is_enabled(level)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use slog::{debug, info, o, warn, Logger};
+    use std::io::{self, Write};
+    use std::sync::{Arc, Mutex};
+
+    /// A writer which collects everything written to it.
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    mod noisy {
+        use super::*;
+
+        pub fn log(log: &Logger) {
+            debug!(log, "Noisy debug");
+            warn!(log, "Noisy warning");
+        }
+    }
+
+    fn json_logger(level: Level, overrides: &str) -> (Logger, Buffer) {
+        let buffer = Buffer::default();
+        let drain = slog_json::Json::default(buffer.clone());
+        let drain = LevelOverrideFilter::new(
+            drain,
+            level,
+            LevelOverridesHandle::new(overrides.parse().unwrap()),
+        );
+        let log = Logger::root(Mutex::new(drain).fuse(), o!("service" => "test"));
+        (log, buffer)
+    }
+
+    fn messages(buffer: &Buffer) -> Vec<String> {
+        String::from_utf8(buffer.0.lock().unwrap().clone())
+            .unwrap()
+            .lines()
+            .map(|line| {
+                let value: serde_json::Value = serde_json::from_str(line).unwrap();
+                value["msg"].as_str().unwrap().to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parse() {
+        let overrides: LevelOverrides = "network=warn, beacon_chain::fork_choice=trace"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            overrides.to_string(),
+            "network=warn,beacon_chain::fork_choice=trace"
+        );
+        assert!("".parse::<LevelOverrides>().unwrap().is_empty());
+        assert!("network".parse::<LevelOverrides>().is_err());
+        assert!("network=loud".parse::<LevelOverrides>().is_err());
+        assert!("=info".parse::<LevelOverrides>().is_err());
+    }
+
+    #[test]
+    fn most_specific_target() {
+        let overrides: LevelOverrides = "network=warn,network::sync=debug".parse().unwrap();
+        assert_eq!(overrides.level_for("network"), Some(Level::Warning));
+        assert_eq!(overrides.level_for("network::router"), Some(Level::Warning));
+        assert_eq!(
+            overrides.level_for("network::sync::manager"),
+            Some(Level::Debug)
+        );
+        assert_eq!(overrides.level_for("network_utils"), None);
+        assert_eq!(overrides.level_for("beacon_chain"), None);
+    }
+
+    #[test]
+    fn module_override_filters() {
+        let noisy_module = module_path!().to_string() + "::noisy";
+        let (log, buffer) = json_logger(Level::Debug, &format!("{}=warn", noisy_module));
+
+        noisy::log(&log);
+        debug!(log, "Chain debug");
+        info!(log, "Chain info");
+
+        assert_eq!(
+            messages(&buffer),
+            vec!["Noisy warning", "Chain debug", "Chain info"]
+        );
+    }
+
+    #[test]
+    fn module_override_more_verbose() {
+        let noisy_module = module_path!().to_string() + "::noisy";
+        let (log, buffer) = json_logger(Level::Info, &format!("{}=debug", noisy_module));
+
+        noisy::log(&log);
+        debug!(log, "Chain debug");
+
+        assert_eq!(messages(&buffer), vec!["Noisy debug", "Noisy warning"]);
+    }
+
+    #[test]
+    fn is_enabled_with_more_verbose_override() {
+        let filter = |overrides: &str| {
+            LevelOverrideFilter::new(
+                slog_json::Json::default(Buffer::default()),
+                Level::Info,
+                LevelOverridesHandle::new(overrides.parse().unwrap()),
+            )
+        };
+
+        // A more verbose override must enable records at the override level.
+        let filter_debug = filter("network=debug");
+        assert!(filter_debug.is_enabled(Level::Debug));
+        assert!(!filter_debug.is_enabled(Level::Trace));
+
+        // A less verbose override doesn't disable records at the global level.
+        let filter_warn = filter("network=warn");
+        assert!(filter_warn.is_enabled(Level::Info));
+        assert!(!filter_warn.is_enabled(Level::Debug));
+    }
+
+    #[test]
+    fn replace_overrides() {
+        let noisy_module = module_path!().to_string() + "::noisy";
+        let buffer = Buffer::default();
+        let handle = LevelOverridesHandle::default();
+        let drain = LevelOverrideFilter::new(
+            slog_json::Json::default(buffer.clone()),
+            Level::Debug,
+            handle.clone(),
+        );
+        let log = Logger::root(Mutex::new(drain).fuse(), o!());
+
+        noisy::log(&log);
+        handle.set(format!("{}=warn", noisy_module).parse().unwrap());
+        noisy::log(&log);
+
+        assert_eq!(
+            messages(&buffer),
+            vec!["Noisy debug", "Noisy warning", "Noisy warning"]
+        );
+    }
+
+    #[test]
+    fn serde_round_trip() {
+        let overrides: LevelOverrides = "network=warn,beacon_chain=trace".parse().unwrap();
+        let json = serde_json::to_string(&overrides).unwrap();
+        assert_eq!(json, "\"network=warn,beacon_chain=trace\"");
+        assert_eq!(
+            serde_json::from_str::<LevelOverrides>(&json).unwrap(),
+            overrides
+        );
+        assert!(serde_json::from_str::<LevelOverrides>("\"network\"").is_err());
+    }
+}
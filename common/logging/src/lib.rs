@@ -9,6 +9,10 @@ use slog_term::Decorator;
 use std::io::{Result, Write};
 use std::time::{Duration, Instant};
 
+mod level_overrides;
+
+pub use level_overrides::{parse_level, LevelOverrideFilter, LevelOverrides, LevelOverridesHandle};
+
 pub const MAX_MESSAGE_WIDTH: usize = 40;
 
 /// The minimum interval between log messages indicating that a queue is full.
@@ -0,0 +1,106 @@
+use super::types::{BlockDelayHealth, ChainHealth, ExecutionHealth, HeadHealth};
+use super::Config;
+use async_trait::async_trait;
+
+/// Provides the health of the beacon chain to include in beacon process reports.
+///
+/// Implementations should not block for long on locks; a value which can't be read in time
+/// should be reported as `None` rather than delaying the report.
+#[async_trait]
+pub trait ChainHealthSource: Send + Sync {
+    /// Returns the head slot, finalized epoch and sync distance of the chain.
+    fn head_health(&self) -> HeadHealth;
+
+    /// Returns the health of the execution layer.
+    async fn execution_health(&self) -> ExecutionHealth;
+
+    /// Returns statistics about the delays to blocks received from peers.
+    fn block_delay_health(&self) -> BlockDelayHealth;
+}
+
+/// Gathers each group of chain health which is enabled in `config`.
+pub async fn gather_chain_health(source: &dyn ChainHealthSource, config: &Config) -> ChainHealth {
+    let head = config.report_head.then(|| source.head_health());
+    let execution = if config.report_execution_layer {
+        Some(source.execution_health().await)
+    } else {
+        None
+    };
+    let block_delays = config
+        .report_block_delays
+        .then(|| source.block_delay_health());
+
+    ChainHealth {
+        head,
+        execution,
+        block_delays,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    struct StaticSource;
+
+    #[async_trait]
+    impl ChainHealthSource for StaticSource {
+        fn head_health(&self) -> HeadHealth {
+            HeadHealth {
+                chain_head_slot: Some(64),
+                chain_finalized_epoch: Some(1),
+                // Unavailable.
+                chain_sync_distance: None,
+            }
+        }
+
+        async fn execution_health(&self) -> ExecutionHealth {
+            ExecutionHealth {
+                sync_execution_synced: Some(true),
+            }
+        }
+
+        fn block_delay_health(&self) -> BlockDelayHealth {
+            BlockDelayHealth {
+                block_observed_delay_p95_ms: Some(1200),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn groups_can_be_disabled() {
+        let config = Config::default();
+        let health = gather_chain_health(&StaticSource, &config).await;
+        assert_eq!(
+            serde_json::to_value(&health).unwrap(),
+            json!({
+                "chain_head_slot": 64,
+                "chain_finalized_epoch": 1,
+                "chain_sync_distance": null,
+                "sync_execution_synced": true,
+                "block_observed_delay_p95_ms": 1200,
+            })
+        );
+
+        let config = Config {
+            report_head: false,
+            report_block_delays: false,
+            ..Config::default()
+        };
+        let health = gather_chain_health(&StaticSource, &config).await;
+        assert_eq!(
+            serde_json::to_value(&health).unwrap(),
+            json!({ "sync_execution_synced": true })
+        );
+    }
+
+    #[test]
+    fn groups_enabled_by_default() {
+        let config: Config =
+            serde_json::from_value(json!({ "monitoring_endpoint": "http://localhost" })).unwrap();
+        assert!(config.report_head);
+        assert!(config.report_execution_layer);
+        assert!(config.report_block_delays);
+    }
+}
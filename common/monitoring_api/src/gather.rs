@@ -1,4 +1,4 @@
-use super::types::{BeaconProcessMetrics, ValidatorProcessMetrics};
+use super::types::{BeaconProcessMetrics, ChainHealth, ValidatorProcessMetrics};
 use lazy_static::lazy_static;
 use lighthouse_metrics::{MetricFamily, MetricType};
 use serde_json::json;
@@ -173,6 +173,7 @@ pub fn gather_beacon_metrics(
     Ok(BeaconProcessMetrics {
         beacon: beacon_metrics,
         common: process,
+        chain_health: ChainHealth::default(),
     })
 }
 
@@ -1,7 +1,9 @@
+mod chain_health;
 mod gather;
 mod types;
-use std::{path::PathBuf, time::Duration};
+use std::{path::PathBuf, sync::Arc, time::Duration};
 
+use chain_health::gather_chain_health;
 use eth2::lighthouse::SystemHealth;
 use gather::{gather_beacon_metrics, gather_validator_metrics};
 use reqwest::{IntoUrl, Response};
@@ -13,7 +15,8 @@ use task_executor::TaskExecutor;
 use tokio::time::{interval_at, Instant};
 use types::*;
 
-pub use types::ProcessType;
+pub use chain_health::ChainHealthSource;
+pub use types::{BlockDelayHealth, ChainHealth, ExecutionHealth, HeadHealth, ProcessType};
 
 /// Duration after which we collect and send metrics to remote endpoint.
 pub const UPDATE_DURATION: u64 = 60;
@@ -45,7 +48,7 @@ impl std::fmt::Display for Error {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
     /// Endpoint
     pub monitoring_endpoint: String,
@@ -55,6 +58,32 @@ pub struct Config {
     /// Path for the cold database required for fetching beacon db size metrics.
     /// Note: not relevant for validator and system metrics.
     pub freezer_db_path: Option<PathBuf>,
+    /// Include the head slot, finalized epoch and sync distance in beacon reports.
+    #[serde(default = "default_true")]
+    pub report_head: bool,
+    /// Include the sync status of the execution layer in beacon reports.
+    #[serde(default = "default_true")]
+    pub report_execution_layer: bool,
+    /// Include the delays to blocks received from peers in beacon reports.
+    #[serde(default = "default_true")]
+    pub report_block_delays: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            monitoring_endpoint: String::new(),
+            db_path: None,
+            freezer_db_path: None,
+            report_head: true,
+            report_execution_layer: true,
+            report_block_delays: true,
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
 }
 
 #[derive(Clone)]
@@ -65,6 +94,9 @@ pub struct MonitoringHttpClient {
     /// Path to the freezer database.
     freezer_db_path: Option<PathBuf>,
     monitoring_endpoint: SensitiveUrl,
+    /// Provides the health of the beacon chain for beacon reports, if available.
+    chain_health: Option<Arc<dyn ChainHealthSource>>,
+    config: Config,
     log: slog::Logger,
 }
 
@@ -76,10 +108,18 @@ impl MonitoringHttpClient {
             freezer_db_path: config.freezer_db_path.clone(),
             monitoring_endpoint: SensitiveUrl::parse(&config.monitoring_endpoint)
                 .map_err(|e| format!("Invalid monitoring endpoint: {:?}", e))?,
+            chain_health: None,
+            config: config.clone(),
             log,
         })
     }
 
+    /// Include the health of the beacon chain provided by `source` in beacon reports.
+    pub fn with_chain_health(mut self, source: Arc<dyn ChainHealthSource>) -> Self {
+        self.chain_health = Some(source);
+        self
+    }
+
     /// Perform a HTTP POST request.
     async fn post<T: Serialize, U: IntoUrl>(&self, url: U, body: &T) -> Result<(), Error> {
         let response = self
@@ -123,7 +163,7 @@ impl MonitoringHttpClient {
     }
 
     /// Gets beacon metrics and updates the metrics struct
-    pub fn get_beacon_metrics(&self) -> Result<MonitoringMetrics, Error> {
+    pub async fn get_beacon_metrics(&self) -> Result<MonitoringMetrics, Error> {
         let db_path = self.db_path.as_ref().ok_or_else(|| {
             Error::BeaconMetricsFailed("Beacon metrics require db path".to_string())
         })?;
@@ -131,8 +171,11 @@ impl MonitoringHttpClient {
         let freezer_db_path = self.freezer_db_path.as_ref().ok_or_else(|| {
             Error::BeaconMetricsFailed("Beacon metrics require freezer db path".to_string())
         })?;
-        let metrics =
+        let mut metrics =
             gather_beacon_metrics(db_path, freezer_db_path).map_err(Error::BeaconMetricsFailed)?;
+        if let Some(source) = &self.chain_health {
+            metrics.chain_health = gather_chain_health(source.as_ref(), &self.config).await;
+        }
         Ok(MonitoringMetrics {
             metadata: Metadata::new(ProcessType::BeaconNode),
             process_metrics: Process::Beacon(metrics),
@@ -163,7 +206,7 @@ impl MonitoringHttpClient {
         process_type: &ProcessType,
     ) -> Result<MonitoringMetrics, Error> {
         match process_type {
-            ProcessType::BeaconNode => self.get_beacon_metrics(),
+            ProcessType::BeaconNode => self.get_beacon_metrics().await,
             ProcessType::System => self.get_system_metrics(),
             ProcessType::Validator => self.get_validator_metrics(),
         }
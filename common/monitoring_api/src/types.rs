@@ -152,6 +152,45 @@ pub struct BeaconProcessMetrics {
     pub common: ProcessMetrics,
     #[serde(flatten)]
     pub beacon: serde_json::Value,
+    #[serde(flatten)]
+    pub chain_health: ChainHealth,
+}
+
+/// Health of the beacon chain, reported alongside the beacon process metrics.
+///
+/// A group is omitted from the report if it is disabled in the `Config`, whilst a value is `null`
+/// if it could not be determined when the report was gathered.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChainHealth {
+    #[serde(flatten)]
+    pub head: Option<HeadHealth>,
+    #[serde(flatten)]
+    pub execution: Option<ExecutionHealth>,
+    #[serde(flatten)]
+    pub block_delays: Option<BlockDelayHealth>,
+}
+
+/// The head and finality of the beacon chain.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HeadHealth {
+    pub chain_head_slot: Option<u64>,
+    pub chain_finalized_epoch: Option<u64>,
+    /// The number of slots between the head and the current slot.
+    pub chain_sync_distance: Option<u64>,
+}
+
+/// The health of the execution layer.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExecutionHealth {
+    /// `null` if no execution layer is configured.
+    pub sync_execution_synced: Option<bool>,
+}
+
+/// Delays to blocks received from peers.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlockDelayHealth {
+    /// The 95th percentile delay between the start of a slot and a peer delivering its block.
+    pub block_observed_delay_p95_ms: Option<u64>,
 }
 
 /// All validator process metrics
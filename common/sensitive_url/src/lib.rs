@@ -82,6 +82,17 @@ impl SensitiveUrl {
             redacted: redacted.to_string(),
         })
     }
+
+    /// Returns a copy of `self` where the full URL is replaced with the redacted URL.
+    ///
+    /// This is useful when serializing, since the `Serialize` implementation uses the full URL.
+    pub fn to_redacted(&self) -> Result<Self, SensitiveError> {
+        let full = Url::parse(&self.redacted).map_err(SensitiveError::ParseError)?;
+        Ok(Self {
+            full,
+            redacted: self.redacted.clone(),
+        })
+    }
 }
 
 #[cfg(test)]
@@ -94,6 +105,10 @@ mod tests {
         let surl = SensitiveUrl::parse(full).unwrap();
         assert_eq!(surl.to_string(), "https://example.com/");
         assert_eq!(surl.full.to_string(), full);
+
+        let redacted = surl.to_redacted().unwrap();
+        assert_eq!(redacted.full.to_string(), "https://example.com/");
+        assert_eq!(redacted.to_string(), "https://example.com/");
     }
     #[test]
     fn redact_localhost_url() {
@@ -16,6 +16,9 @@ pub enum ShutdownReason {
     Success(&'static str),
     /// The node shut down due to an error condition.
     Failure(&'static str),
+    /// The node shut down due to an error condition which is identified by a distinct process
+    /// exit code.
+    FailureWithExitCode(&'static str, i32),
 }
 
 impl ShutdownReason {
@@ -23,6 +26,16 @@ impl ShutdownReason {
         match self {
             ShutdownReason::Success(msg) => msg,
             ShutdownReason::Failure(msg) => msg,
+            ShutdownReason::FailureWithExitCode(msg, _) => msg,
+        }
+    }
+
+    /// The code with which the process should exit.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            ShutdownReason::Success(_) => 0,
+            ShutdownReason::Failure(_) => 1,
+            ShutdownReason::FailureWithExitCode(_, exit_code) => *exit_code,
         }
     }
 }
@@ -228,6 +228,18 @@ impl<E: EthSpec> From<&IndexedAttestation<E>> for QueuedAttestation {
     }
 }
 
+/// Counts of the queued attestations which have been dequeued as the slot clock advanced.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct AttestationQueueStats {
+    /// The number of queued attestations which became eligible and were dequeued.
+    pub queued: usize,
+    /// The number of dequeued attestations which were applied.
+    pub applied: usize,
+    /// The number of dequeued attestations whose block is no longer known to fork choice as a
+    /// descendant of the finalized checkpoint, such that their votes carry no weight.
+    pub dropped_invalid: usize,
+}
+
 /// Returns all values in `self.queued_attestations` that have a slot that is earlier than the
 /// current slot. Also removes those values from `self.queued_attestations`.
 fn dequeue_attestations(
@@ -287,6 +299,8 @@ pub struct ForkChoice<T, E> {
     proto_array: ProtoArrayForkChoice,
     /// Attestations that arrived at the current slot and must be queued for later processing.
     queued_attestations: Vec<QueuedAttestation>,
+    /// Counts of the queued attestations processed since they were last taken.
+    attestation_queue_stats: AttestationQueueStats,
     /// Stores a cache of the values required to be sent to the execution layer.
     forkchoice_update_parameters: ForkchoiceUpdateParameters,
     /// The most recent result of running `Self::get_head`.
@@ -370,6 +384,7 @@ where
             fc_store,
             proto_array,
             queued_attestations: vec![],
+            attestation_queue_stats: AttestationQueueStats::default(),
             // This will be updated during the next call to `Self::get_head`.
             forkchoice_update_parameters: ForkchoiceUpdateParameters {
                 head_hash: None,
@@ -995,11 +1010,26 @@ where
                     attestation.target_epoch,
                 )?;
             }
+
+            // The attestation was validated when it was queued, however its block may since have
+            // been pruned or have ceased to descend from the finalized checkpoint.
+            self.attestation_queue_stats.queued += 1;
+            if self.contains_block(&attestation.block_root) {
+                self.attestation_queue_stats.applied += 1;
+            } else {
+                self.attestation_queue_stats.dropped_invalid += 1;
+            }
         }
 
         Ok(())
     }
 
+    /// Returns the counts of the queued attestations processed since this function was last
+    /// called, resetting them.
+    pub fn take_attestation_queue_stats(&mut self) -> AttestationQueueStats {
+        std::mem::take(&mut self.attestation_queue_stats)
+    }
+
     /// Returns `true` if the block is known **and** a descendant of the finalized root.
     pub fn contains_block(&self, block_root: &Hash256) -> bool {
         self.proto_array.contains_block(block_root) && self.is_descendant_of_finalized(*block_root)
@@ -1215,6 +1245,7 @@ where
             fc_store,
             proto_array,
             queued_attestations: persisted.queued_attestations,
+            attestation_queue_stats: AttestationQueueStats::default(),
             // Will be updated in the following call to `Self::get_head`.
             forkchoice_update_parameters: ForkchoiceUpdateParameters {
                 head_hash: None,
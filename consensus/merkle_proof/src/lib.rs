@@ -27,6 +27,11 @@ pub enum MerkleTree {
     ///
     /// It represents a Merkle tree of 2^depth zero leaves.
     Zero(usize),
+    /// Full subtree of which only the hash is known.
+    ///
+    /// It represents a subtree whose leaves have been discarded, e.g. the deposits which were
+    /// finalized before a deposit tree snapshot was taken.
+    Finalized(H256),
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -41,6 +46,8 @@ pub enum MerkleTreeError {
     DepthTooSmall,
     // Overflow occurred
     ArithError,
+    // A proof was requested for a leaf within a finalized subtree
+    ProofEncounteredFinalizedNode,
 }
 
 impl MerkleTree {
@@ -78,6 +85,100 @@ impl MerkleTree {
         }
     }
 
+    /// Create a new Merkle tree from the hashes of the finalized subtrees which contain the first
+    /// `deposit_count` leaves, in left-to-right order, as in an EIP-4881 deposit tree snapshot.
+    ///
+    /// The remaining leaves are zero, and can be appended with `push_leaf`.
+    pub fn from_finalized_snapshot(
+        finalized_branch: &[H256],
+        deposit_count: usize,
+        depth: usize,
+    ) -> Result<Self, MerkleTreeError> {
+        use MerkleTree::*;
+
+        if finalized_branch.is_empty() {
+            return if deposit_count == 0 {
+                Ok(Zero(depth))
+            } else {
+                Err(MerkleTreeError::Invalid)
+            };
+        }
+        if deposit_count == 1usize.checked_shl(depth as u32).unwrap_or(0) {
+            return if finalized_branch.len() == 1 {
+                Ok(Finalized(finalized_branch[0]))
+            } else {
+                Err(MerkleTreeError::Invalid)
+            };
+        }
+        if depth == 0 {
+            return Err(MerkleTreeError::DepthTooSmall);
+        }
+
+        let subtree_capacity = 2usize.pow(depth as u32 - 1);
+        let (left_subtree, right_subtree) = if deposit_count <= subtree_capacity {
+            (
+                MerkleTree::from_finalized_snapshot(finalized_branch, deposit_count, depth - 1)?,
+                Zero(depth - 1),
+            )
+        } else {
+            (
+                Finalized(finalized_branch[0]),
+                MerkleTree::from_finalized_snapshot(
+                    &finalized_branch[1..],
+                    deposit_count - subtree_capacity,
+                    depth - 1,
+                )?,
+            )
+        };
+        let hash = H256::from_slice(&hash32_concat(
+            left_subtree.hash().as_bytes(),
+            right_subtree.hash().as_bytes(),
+        ));
+
+        Ok(Node(hash, Box::new(left_subtree), Box::new(right_subtree)))
+    }
+
+    /// Return the hashes of the full subtrees which contain the first `deposit_count` leaves, in
+    /// left-to-right order. This is the inverse of `from_finalized_snapshot`.
+    pub fn get_finalized_hashes(
+        &self,
+        deposit_count: usize,
+        depth: usize,
+    ) -> Result<Vec<H256>, MerkleTreeError> {
+        let mut hashes = vec![];
+        self.append_finalized_hashes(deposit_count, depth, &mut hashes)?;
+        Ok(hashes)
+    }
+
+    fn append_finalized_hashes(
+        &self,
+        deposit_count: usize,
+        depth: usize,
+        hashes: &mut Vec<H256>,
+    ) -> Result<(), MerkleTreeError> {
+        if deposit_count == 0 {
+            return Ok(());
+        }
+        if deposit_count == 1usize.checked_shl(depth as u32).unwrap_or(0) {
+            hashes.push(self.hash());
+            return Ok(());
+        }
+
+        let subtree_capacity = 2usize.pow(depth as u32 - 1);
+        let (left, right) = match self {
+            MerkleTree::Node(_, left, right) => (left, right),
+            MerkleTree::Finalized(_) => return Err(MerkleTreeError::ProofEncounteredFinalizedNode),
+            // The tree doesn't contain `deposit_count` leaves.
+            MerkleTree::Leaf(_) | MerkleTree::Zero(_) => return Err(MerkleTreeError::Invalid),
+        };
+        if deposit_count <= subtree_capacity {
+            left.append_finalized_hashes(deposit_count, depth - 1, hashes)
+        } else {
+            hashes.push(left.hash());
+            right.append_finalized_hashes(deposit_count - subtree_capacity, depth - 1, hashes)
+        }
+    }
+
     /// Push an element in the MerkleTree.
     /// MerkleTree and depth must be correct, as the algorithm expects valid data.
     pub fn push_leaf(&mut self, elem: H256, depth: usize) -> Result<(), MerkleTreeError> {
@@ -89,6 +190,7 @@ impl MerkleTree {
 
         match self {
             Leaf(_) => return Err(MerkleTreeError::LeafReached),
+            Finalized(_) => return Err(MerkleTreeError::MerkleTreeFull),
             Zero(_) => {
                 *self = MerkleTree::create(&[elem], depth);
             }
@@ -97,9 +199,11 @@ impl MerkleTree {
                 let right: &mut MerkleTree = &mut *right;
                 match (&*left, &*right) {
                     // Tree is full
-                    (Leaf(_), Leaf(_)) => return Err(MerkleTreeError::MerkleTreeFull),
+                    (Leaf(_), Leaf(_)) | (Finalized(_), Leaf(_)) | (Finalized(_), Finalized(_)) => {
+                        return Err(MerkleTreeError::MerkleTreeFull)
+                    }
                     // There is a right node so insert in right node
-                    (Node(_, _, _), Node(_, _, _)) => {
+                    (Node(_, _, _), Node(_, _, _)) | (Finalized(_), Node(_, _, _)) => {
                         if let Err(e) = right.push_leaf(elem, depth - 1) {
                             return Err(e);
                         }
@@ -108,8 +212,9 @@ impl MerkleTree {
                     (Zero(_), Zero(_)) => {
                         *left = MerkleTree::create(&[elem], depth - 1);
                     }
-                    // Leaf on left branch and zero on right branch, insert on right side
-                    (Leaf(_), Zero(_)) => {
+                    // Leaf or finalized subtree on left branch and zero on right branch, insert
+                    // on right side
+                    (Leaf(_), Zero(_)) | (Finalized(_), Zero(_)) => {
                         *right = MerkleTree::create(&[elem], depth - 1);
                     }
                     // Try inserting on the left node -> if it fails because it is full, insert in right side.
@@ -141,6 +246,7 @@ impl MerkleTree {
         match *self {
             MerkleTree::Leaf(h) => h,
             MerkleTree::Node(h, _, _) => h,
+            MerkleTree::Finalized(h) => h,
             MerkleTree::Zero(depth) => H256::from_slice(&ZERO_HASHES[depth]),
         }
     }
@@ -148,7 +254,7 @@ impl MerkleTree {
     /// Get a reference to the left and right subtrees if they exist.
     pub fn left_and_right_branches(&self) -> Option<(&Self, &Self)> {
         match *self {
-            MerkleTree::Leaf(_) | MerkleTree::Zero(0) => None,
+            MerkleTree::Leaf(_) | MerkleTree::Finalized(_) | MerkleTree::Zero(0) => None,
             MerkleTree::Node(_, ref l, ref r) => Some((l, r)),
             MerkleTree::Zero(depth) => Some((&ZERO_NODES[depth - 1], &ZERO_NODES[depth - 1])),
         }
@@ -163,12 +269,21 @@ impl MerkleTree {
     ///
     /// The Merkle proof is in "bottom-up" order, starting with a leaf node
     /// and moving up the tree. Its length will be exactly equal to `depth`.
-    pub fn generate_proof(&self, index: usize, depth: usize) -> (H256, Vec<H256>) {
+    ///
+    /// Proofs can't be generated for leaves within a finalized subtree.
+    pub fn generate_proof(
+        &self,
+        index: usize,
+        depth: usize,
+    ) -> Result<(H256, Vec<H256>), MerkleTreeError> {
         let mut proof = vec![];
         let mut current_node = self;
         let mut current_depth = depth;
         while current_depth > 0 {
             let ith_bit = (index >> (current_depth - 1)) & 0x01;
+            if let MerkleTree::Finalized(_) = current_node {
+                return Err(MerkleTreeError::ProofEncounteredFinalizedNode);
+            }
             // Note: unwrap is safe because leaves are only ever constructed at depth == 0.
             let (left, right) = current_node.left_and_right_branches().unwrap();
 
@@ -189,7 +304,7 @@ impl MerkleTree {
         // Put proof in bottom-up order.
         proof.reverse();
 
-        (current_node.hash(), proof)
+        Ok((current_node.hash(), proof))
     }
 }
 
@@ -257,7 +372,7 @@ mod tests {
         let merkle_root = merkle_tree.hash();
 
         let proofs_ok = (0..leaves.len()).all(|i| {
-            let (leaf, branch) = merkle_tree.generate_proof(i, depth);
+            let (leaf, branch) = merkle_tree.generate_proof(i, depth).unwrap();
             leaf == leaves[i] && verify_merkle_proof(leaf, &branch, depth, i, merkle_root)
         });
 
@@ -276,13 +391,79 @@ mod tests {
 
         let proofs_ok = leaves_iter.enumerate().all(|(i, leaf)| {
             assert_eq!(merkle_tree.push_leaf(leaf, depth), Ok(()));
-            let (stored_leaf, branch) = merkle_tree.generate_proof(i, depth);
+            let (stored_leaf, branch) = merkle_tree.generate_proof(i, depth).unwrap();
             stored_leaf == leaf && verify_merkle_proof(leaf, &branch, depth, i, merkle_tree.hash())
         });
 
         TestResult::from_bool(proofs_ok)
     }
 
+    /// Check that a tree built from the finalized hashes of another tree has the same root, and
+    /// that leaves pushed after the finalized ones can still be proven.
+    #[quickcheck]
+    fn quickcheck_finalized_snapshot(
+        int_leaves: Vec<u64>,
+        finalized_count: usize,
+        depth: usize,
+    ) -> TestResult {
+        if depth == 0
+            || depth > MAX_TREE_DEPTH
+            || int_leaves.len() > 2usize.pow(depth as u32)
+            || finalized_count > int_leaves.len()
+        {
+            return TestResult::discard();
+        }
+
+        let leaves: Vec<_> = int_leaves.into_iter().map(H256::from_low_u64_be).collect();
+        let full_tree = MerkleTree::create(&leaves, depth);
+
+        let finalized = full_tree
+            .get_finalized_hashes(finalized_count, depth)
+            .unwrap();
+        assert_eq!(finalized.len(), finalized_count.count_ones() as usize);
+        let mut tree =
+            MerkleTree::from_finalized_snapshot(&finalized, finalized_count, depth).unwrap();
+        assert_eq!(
+            tree.get_finalized_hashes(finalized_count, depth).unwrap(),
+            finalized
+        );
+
+        for leaf in &leaves[finalized_count..] {
+            tree.push_leaf(*leaf, depth).unwrap();
+        }
+        let root = tree.hash();
+
+        let proofs_ok = (0..leaves.len()).all(|i| match tree.generate_proof(i, depth) {
+            Ok((leaf, branch)) => {
+                i >= finalized_count
+                    && leaf == leaves[i]
+                    && verify_merkle_proof(leaf, &branch, depth, i, root)
+            }
+            Err(e) => i < finalized_count && e == MerkleTreeError::ProofEncounteredFinalizedNode,
+        });
+
+        TestResult::from_bool(root == full_tree.hash() && proofs_ok)
+    }
+
+    #[test]
+    fn finalized_snapshot_must_match_deposit_count() {
+        let hash = H256::from([0xAA; 32]);
+        // One finalized subtree per set bit of the deposit count.
+        assert!(MerkleTree::from_finalized_snapshot(&[hash], 3, 2).is_err());
+        assert!(MerkleTree::from_finalized_snapshot(&[], 1, 2).is_err());
+        assert!(MerkleTree::from_finalized_snapshot(&[hash, hash], 3, 2).is_ok());
+        assert_eq!(
+            MerkleTree::from_finalized_snapshot(&[hash], 4, 2),
+            Ok(MerkleTree::Finalized(hash))
+        );
+
+        let mut full = MerkleTree::from_finalized_snapshot(&[hash], 4, 2).unwrap();
+        assert_eq!(
+            full.push_leaf(hash, 2),
+            Err(MerkleTreeError::MerkleTreeFull)
+        );
+    }
+
     #[test]
     fn sparse_zero_correct() {
         let depth = 2;
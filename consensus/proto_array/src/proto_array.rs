@@ -114,6 +114,18 @@ impl Default for ProposerBoost {
     }
 }
 
+/// The weights which support a head block, as of the last `ProtoArray::apply_score_changes`.
+#[derive(PartialEq, Debug, Default, Copy, Clone)]
+pub struct HeadWeights {
+    /// The weight of the head block, including any proposer boost.
+    pub head_weight: u64,
+    /// The greatest weight of the other viable children of the head's parent, or `None` if there
+    /// are no such children.
+    pub competitor_weight: Option<u64>,
+    /// The proposer boost included in `head_weight`.
+    pub proposer_boost: u64,
+}
+
 #[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
 pub struct ProtoArray {
     /// Do not attempt to prune the tree unless it has at least this many nodes. Small prunes
@@ -862,6 +874,26 @@ impl ProtoArray {
         }
     }
 
+    /// Returns the nodes which have no children.
+    pub fn leaves(&self) -> Vec<&ProtoNode> {
+        let parents = self
+            .nodes
+            .iter()
+            .filter_map(|node| node.parent)
+            .collect::<HashSet<_>>();
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !parents.contains(i))
+            .map(|(_, node)| node)
+            .collect()
+    }
+
+    /// Returns `true` if `node` is viable for the head, as per `filter_block_tree`.
+    pub fn is_viable_for_head(&self, node: &ProtoNode) -> bool {
+        self.node_is_viable_for_head(node)
+    }
+
     /// Return a reverse iterator over the nodes which comprise the chain ending at `block_root`.
     pub fn iter_nodes<'a>(&'a self, block_root: &Hash256) -> Iter<'a> {
         let next_node_index = self.indices.get(block_root).copied();
@@ -901,6 +933,65 @@ impl ProtoArray {
             .unwrap_or(false)
     }
 
+    /// Returns the root of the most recent block which is an ancestor of both `a_root` and
+    /// `b_root`. Returns `None` if either input root is unknown or if they don't share an ancestor
+    /// which is still in the array.
+    ///
+    /// ## Notes
+    ///
+    /// Returns `a_root` if it is an ancestor of `b_root` (or they are equal), and vice versa.
+    pub fn find_common_ancestor(&self, a_root: Hash256, b_root: Hash256) -> Option<Hash256> {
+        let mut a_index = *self.indices.get(&a_root)?;
+        let mut b_index = *self.indices.get(&b_root)?;
+
+        // A node is always inserted after its parent, so the node with the higher index can't be
+        // an ancestor of the other and can be replaced by its parent.
+        while a_index != b_index {
+            if a_index > b_index {
+                a_index = self.nodes.get(a_index)?.parent?;
+            } else {
+                b_index = self.nodes.get(b_index)?.parent?;
+            }
+        }
+
+        self.nodes.get(a_index).map(|node| node.root)
+    }
+
+    /// Returns the weights supporting the block with `head_root` as of the last call to
+    /// `Self::apply_score_changes`. Returns `None` if `head_root` is unknown.
+    pub fn head_weights(&self, head_root: &Hash256) -> Option<HeadWeights> {
+        let head = self
+            .indices
+            .get(head_root)
+            .and_then(|i| self.nodes.get(*i))?;
+
+        // A node is always inserted after its parent, so its siblings all follow the parent.
+        let competitor_weight = head.parent.and_then(|parent_index| {
+            self.nodes
+                .iter()
+                .skip(parent_index + 1)
+                .filter(|node| node.parent == Some(parent_index) && node.root != head.root)
+                .filter(|node| self.node_leads_to_viable_head(node).unwrap_or(false))
+                .map(|node| node.weight)
+                .max()
+        });
+
+        // The boost is applied to the boosted block and all of its ancestors.
+        let boost = &self.previous_proposer_boost;
+        let proposer_boost =
+            if boost.root != Hash256::zero() && self.is_descendant(head.root, boost.root) {
+                boost.score
+            } else {
+                0
+            };
+
+        Some(HeadWeights {
+            head_weight: head.weight,
+            competitor_weight,
+            proposer_boost,
+        })
+    }
+
     /// Returns the first *beacon block root* which contains an execution payload with the given
     /// `block_hash`, if any.
     pub fn execution_block_hash_to_beacon_block_root(
@@ -1,5 +1,7 @@
 use crate::error::Error;
-use crate::proto_array::{InvalidationOperation, Iter, ProposerBoost, ProtoArray, ProtoNode};
+use crate::proto_array::{
+    HeadWeights, InvalidationOperation, Iter, ProposerBoost, ProtoArray, ProtoNode,
+};
 use crate::ssz_container::SszContainer;
 use serde_derive::{Deserialize, Serialize};
 use ssz::{Decode, Encode};
@@ -368,6 +370,16 @@ impl ProtoArrayForkChoice {
             .is_descendant(ancestor_root, descendant_root)
     }
 
+    /// See `ProtoArray` documentation.
+    pub fn head_weights(&self, head_root: &Hash256) -> Option<HeadWeights> {
+        self.proto_array.head_weights(head_root)
+    }
+
+    /// See `ProtoArray` documentation.
+    pub fn find_common_ancestor(&self, a_root: Hash256, b_root: Hash256) -> Option<Hash256> {
+        self.proto_array.find_common_ancestor(a_root, b_root)
+    }
+
     pub fn latest_message(&self, validator_index: usize) -> Option<(Hash256, Epoch)> {
         if validator_index < self.votes.0.len() {
             let vote = &self.votes.0[validator_index];
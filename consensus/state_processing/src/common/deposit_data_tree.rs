@@ -21,6 +21,29 @@ impl DepositDataTree {
         }
     }
 
+    /// Create a new Merkle tree from the finalized hashes of a deposit tree snapshot, to which the
+    /// deposits after the first `deposit_count` can be pushed.
+    pub fn from_snapshot(
+        finalized: &[Hash256],
+        deposit_count: usize,
+        depth: usize,
+    ) -> Result<Self, MerkleTreeError> {
+        Ok(Self {
+            tree: MerkleTree::from_finalized_snapshot(finalized, deposit_count, depth)?,
+            mix_in_length: deposit_count,
+            depth,
+        })
+    }
+
+    /// Returns the hashes of the full subtrees which contain the first `deposit_count` leaves, as
+    /// stored in a deposit tree snapshot.
+    pub fn get_finalized_hashes(
+        &self,
+        deposit_count: usize,
+    ) -> Result<Vec<Hash256>, MerkleTreeError> {
+        self.tree.get_finalized_hashes(deposit_count, self.depth)
+    }
+
     /// Returns 32 bytes representing the "mix in length" for the merkle root of this tree.
     fn length_bytes(&self) -> Vec<u8> {
         int_to_bytes32(self.mix_in_length as u64)
@@ -38,10 +61,10 @@ impl DepositDataTree {
     ///
     /// The Merkle proof is in "bottom-up" order, starting with a leaf node
     /// and moving up the tree. Its length will be exactly equal to `depth + 1`.
-    pub fn generate_proof(&self, index: usize) -> (Hash256, Vec<Hash256>) {
-        let (root, mut proof) = self.tree.generate_proof(index, self.depth);
+    pub fn generate_proof(&self, index: usize) -> Result<(Hash256, Vec<Hash256>), MerkleTreeError> {
+        let (root, mut proof) = self.tree.generate_proof(index, self.depth)?;
         proof.push(Hash256::from_slice(&self.length_bytes()));
-        (root, proof)
+        Ok((root, proof))
     }
 
     /// Add a deposit to the merkle tree.
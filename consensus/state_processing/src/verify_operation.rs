@@ -2,9 +2,14 @@ use crate::per_block_processing::{
     errors::{
         AttesterSlashingValidationError, ExitValidationError, ProposerSlashingValidationError,
     },
+    signature_sets::{
+        attester_slashing_signature_sets, exit_signature_set, get_pubkey_from_state,
+        proposer_slashing_signature_set,
+    },
     verify_attester_slashing, verify_exit, verify_proposer_slashing,
 };
 use crate::VerifySignatures;
+use bls::{verify_signature_sets, SignatureSet};
 use types::{
     AttesterSlashing, BeaconState, ChainSpec, EthSpec, ProposerSlashing, SignedVoluntaryExit,
 };
@@ -35,6 +40,51 @@ pub trait VerifyOperation<E: EthSpec>: Sized {
         state: &BeaconState<E>,
         spec: &ChainSpec,
     ) -> Result<SigVerifiedOp<Self>, Self::Error>;
+
+    /// Verify everything but the signatures of the operation, returning the signature sets which
+    /// must also be valid for the operation to be valid.
+    fn verify_sans_signatures<'a>(
+        &'a self,
+        state: &'a BeaconState<E>,
+        spec: &'a ChainSpec,
+    ) -> Result<Vec<SignatureSet<'a>>, Self::Error>;
+}
+
+/// Verify each of `ops` against `state`, checking the signatures of the whole batch at once.
+///
+/// If any operation is invalid, or the batch of signatures is invalid, the affected operations are
+/// verified individually with `validate`, so the result for each operation is identical to that of
+/// `validate`.
+pub fn batch_validate<E: EthSpec, T: VerifyOperation<E>>(
+    ops: Vec<T>,
+    state: &BeaconState<E>,
+    spec: &ChainSpec,
+) -> Vec<Result<SigVerifiedOp<T>, T::Error>> {
+    let mut signature_sets = Vec::with_capacity(ops.len());
+    let valid_sans_signatures = ops
+        .iter()
+        .map(|op| match op.verify_sans_signatures(state, spec) {
+            Ok(sets) => {
+                signature_sets.extend(sets);
+                true
+            }
+            Err(_) => false,
+        })
+        .collect::<Vec<_>>();
+    let signatures_valid =
+        !signature_sets.is_empty() && verify_signature_sets(signature_sets.iter());
+    drop(signature_sets);
+
+    ops.into_iter()
+        .zip(valid_sans_signatures)
+        .map(|(op, valid_sans_signatures)| {
+            if valid_sans_signatures && signatures_valid {
+                Ok(SigVerifiedOp(op))
+            } else {
+                op.validate(state, spec)
+            }
+        })
+        .collect()
 }
 
 impl<E: EthSpec> VerifyOperation<E> for SignedVoluntaryExit {
@@ -48,6 +98,20 @@ impl<E: EthSpec> VerifyOperation<E> for SignedVoluntaryExit {
         verify_exit(state, &self, VerifySignatures::True, spec)?;
         Ok(SigVerifiedOp(self))
     }
+
+    fn verify_sans_signatures<'a>(
+        &'a self,
+        state: &'a BeaconState<E>,
+        spec: &'a ChainSpec,
+    ) -> Result<Vec<SignatureSet<'a>>, Self::Error> {
+        verify_exit(state, self, VerifySignatures::False, spec)?;
+        Ok(vec![exit_signature_set(
+            state,
+            |i| get_pubkey_from_state(state, i),
+            self,
+            spec,
+        )?])
+    }
 }
 
 impl<E: EthSpec> VerifyOperation<E> for AttesterSlashing<E> {
@@ -61,6 +125,21 @@ impl<E: EthSpec> VerifyOperation<E> for AttesterSlashing<E> {
         verify_attester_slashing(state, &self, VerifySignatures::True, spec)?;
         Ok(SigVerifiedOp(self))
     }
+
+    fn verify_sans_signatures<'a>(
+        &'a self,
+        state: &'a BeaconState<E>,
+        spec: &'a ChainSpec,
+    ) -> Result<Vec<SignatureSet<'a>>, Self::Error> {
+        verify_attester_slashing(state, self, VerifySignatures::False, spec)?;
+        let (set_1, set_2) = attester_slashing_signature_sets(
+            state,
+            |i| get_pubkey_from_state(state, i),
+            self,
+            spec,
+        )?;
+        Ok(vec![set_1, set_2])
+    }
 }
 
 impl<E: EthSpec> VerifyOperation<E> for ProposerSlashing {
@@ -74,4 +153,19 @@ impl<E: EthSpec> VerifyOperation<E> for ProposerSlashing {
         verify_proposer_slashing(&self, state, VerifySignatures::True, spec)?;
         Ok(SigVerifiedOp(self))
     }
+
+    fn verify_sans_signatures<'a>(
+        &'a self,
+        state: &'a BeaconState<E>,
+        spec: &'a ChainSpec,
+    ) -> Result<Vec<SignatureSet<'a>>, Self::Error> {
+        verify_proposer_slashing(self, state, VerifySignatures::False, spec)?;
+        let (set_1, set_2) = proposer_slashing_signature_set(
+            state,
+            |i| get_pubkey_from_state(state, i),
+            self,
+            spec,
+        )?;
+        Ok(vec![set_1, set_2])
+    }
 }
@@ -278,6 +278,13 @@ impl CommitteeCache {
             .0
             .map(|p| p.get() - 1)
     }
+
+    /// Returns an estimate of the memory used by `self`, in bytes.
+    pub fn memory_usage(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + self.shuffling.capacity() * std::mem::size_of::<usize>()
+            + self.shuffling_positions.capacity() * std::mem::size_of::<NonZeroUsizeOption>()
+    }
 }
 
 /// Computes the position of the given `committee_index` with respect to all committees in the
@@ -1,10 +1,16 @@
-use crate::{EthSpec, ExecPayload, ExecutionPayloadHeader, Uint256};
+use crate::{
+    ChainSpec, EthSpec, ExecPayload, ExecutionBlockHash, ExecutionPayloadHeader, Hash256,
+    SignedRoot, SigningData, Uint256,
+};
 use bls::blst_implementations::PublicKeyBytes;
 use bls::Signature;
 use serde::{Deserialize as De, Deserializer, Serialize as Ser, Serializer};
 use serde_derive::{Deserialize, Serialize};
 use serde_with::{serde_as, DeserializeAs, SerializeAs};
+use ssz::{Decode, DecodeError};
+use ssz_derive::{Decode, Encode};
 use std::marker::PhantomData;
+use tree_hash::{TreeHash, TreeHashType};
 
 #[serde_as]
 #[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
@@ -19,6 +25,51 @@ pub struct BuilderBid<E: EthSpec, Payload: ExecPayload<E>> {
     _phantom_data: PhantomData<E>,
 }
 
+impl<E: EthSpec, Payload: ExecPayload<E>> BuilderBid<E, Payload> {
+    pub fn new(header: Payload, value: Uint256, pubkey: PublicKeyBytes) -> Self {
+        Self {
+            header,
+            value,
+            pubkey,
+            _phantom_data: PhantomData,
+        }
+    }
+}
+
+/// The bid is merkleized with its payload as an `ExecutionPayloadHeader`, regardless of `Payload`.
+impl<E: EthSpec, Payload: ExecPayload<E>> TreeHash for BuilderBid<E, Payload> {
+    fn tree_hash_type() -> TreeHashType {
+        TreeHashType::Container
+    }
+
+    fn tree_hash_packed_encoding(&self) -> Vec<u8> {
+        unreachable!("Container should never be packed.")
+    }
+
+    fn tree_hash_packing_factor() -> usize {
+        unreachable!("Container should never be packed.")
+    }
+
+    fn tree_hash_root(&self) -> Hash256 {
+        builder_bid_root(
+            self.header.to_execution_payload_header().tree_hash_root(),
+            self.value,
+            &self.pubkey,
+        )
+    }
+}
+
+impl<E: EthSpec, Payload: ExecPayload<E>> SignedRoot for BuilderBid<E, Payload> {}
+
+/// Returns the root of a `BuilderBid` with the given fields.
+fn builder_bid_root(header_root: Hash256, value: Uint256, pubkey: &PublicKeyBytes) -> Hash256 {
+    let mut leaves = Vec::with_capacity(3 * 32);
+    leaves.extend_from_slice(header_root.as_bytes());
+    leaves.extend_from_slice(value.tree_hash_root().as_bytes());
+    leaves.extend_from_slice(pubkey.tree_hash_root().as_bytes());
+    tree_hash::merkle_root(&leaves, 0)
+}
+
 /// Validator registration, for use in interacting with servers implementing the builder API.
 #[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
 #[serde(bound = "E: EthSpec, Payload: ExecPayload<E>")]
@@ -27,6 +78,125 @@ pub struct SignedBuilderBid<E: EthSpec, Payload: ExecPayload<E>> {
     pub signature: Signature,
 }
 
+impl<E: EthSpec, Payload: ExecPayload<E>> SignedBuilderBid<E, Payload> {
+    /// Decode a bid from the SSZ encoding used by the builder API, where the payload is
+    /// represented by its header.
+    pub fn from_ssz_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let SszSignedBuilderBid::<E> { message, signature } =
+            SszSignedBuilderBid::from_ssz_bytes(bytes)?;
+        let header = Payload::try_from(message.header).map_err(|_| {
+            DecodeError::BytesInvalid("unable to convert payload header to payload".into())
+        })?;
+
+        Ok(Self {
+            message: BuilderBid {
+                header,
+                value: message.value,
+                pubkey: message.pubkey,
+                _phantom_data: PhantomData,
+            },
+            signature,
+        })
+    }
+
+    /// Returns `true` if the bid was made by the builder with `pubkey`, i.e., it contains `pubkey`
+    /// and is signed by it over the builder `domain`.
+    pub fn is_signed_by(&self, pubkey: &PublicKeyBytes, domain: Hash256) -> bool {
+        if self.message.pubkey != *pubkey {
+            return false;
+        }
+        let pubkey = match pubkey.decompress() {
+            Ok(pubkey) => pubkey,
+            Err(_) => return false,
+        };
+        self.signature
+            .verify(&pubkey, self.message.signing_root(domain))
+    }
+}
+
+/// The parts of a `SignedBuilderBid` which prove what a builder committed to, i.e., enough to
+/// recompute the signed root of the bid and verify the builder's signature over it.
+#[derive(PartialEq, Debug, Serialize, Deserialize, Clone, Encode, Decode)]
+pub struct BuilderBidEvidence {
+    /// The root of the `ExecutionPayloadHeader` offered by the builder.
+    pub header_root: Hash256,
+    /// The block hash of the offered payload. This is contained in the header, so it is not
+    /// required to verify the bid.
+    pub block_hash: ExecutionBlockHash,
+    #[serde(with = "eth2_serde_utils::quoted_u256")]
+    pub value: Uint256,
+    pub pubkey: PublicKeyBytes,
+    pub signature: Signature,
+    /// The time from requesting the bid until the builder's response headers were received, in
+    /// milliseconds. Not covered by the signature.
+    #[serde(with = "eth2_serde_utils::quoted_u64")]
+    pub time_to_first_byte_ms: u64,
+    /// The time taken to read and deserialize the builder's response body, in milliseconds. Not
+    /// covered by the signature.
+    #[serde(with = "eth2_serde_utils::quoted_u64")]
+    pub deserialization_ms: u64,
+}
+
+impl BuilderBidEvidence {
+    /// Returns the root of the `BuilderBid` that was signed.
+    pub fn bid_root(&self) -> Hash256 {
+        builder_bid_root(self.header_root, self.value, &self.pubkey)
+    }
+
+    /// Returns `true` if the bid was made by the builder with `pubkey`, i.e., `self.pubkey` is
+    /// `pubkey` and `self.signature` is a valid signature of the bid by it.
+    pub fn is_signed_by(&self, pubkey: &PublicKeyBytes, spec: &ChainSpec) -> bool {
+        if self.pubkey != *pubkey {
+            return false;
+        }
+        let pubkey = match pubkey.decompress() {
+            Ok(pubkey) => pubkey,
+            Err(_) => return false,
+        };
+        let signing_root = SigningData {
+            object_root: self.bid_root(),
+            domain: spec.get_builder_domain(),
+        }
+        .tree_hash_root();
+        self.signature.verify(&pubkey, signing_root)
+    }
+}
+
+impl<E: EthSpec, Payload: ExecPayload<E>> From<&SignedBuilderBid<E, Payload>>
+    for BuilderBidEvidence
+{
+    fn from(bid: &SignedBuilderBid<E, Payload>) -> Self {
+        Self {
+            header_root: bid
+                .message
+                .header
+                .to_execution_payload_header()
+                .tree_hash_root(),
+            block_hash: bid.message.header.block_hash(),
+            value: bid.message.value,
+            pubkey: bid.message.pubkey,
+            signature: bid.signature.clone(),
+            time_to_first_byte_ms: 0,
+            deserialization_ms: 0,
+        }
+    }
+}
+
+/// The SSZ representation of a `BuilderBid`.
+#[derive(Decode)]
+struct SszBuilderBid<E: EthSpec> {
+    header: ExecutionPayloadHeader<E>,
+    value: Uint256,
+    pubkey: PublicKeyBytes,
+}
+
+/// The SSZ representation of a `SignedBuilderBid`.
+#[derive(Decode)]
+struct SszSignedBuilderBid<E: EthSpec> {
+    message: SszBuilderBid<E>,
+    signature: Signature,
+}
+
 struct BlindedPayloadAsHeader<E>(PhantomData<E>);
 
 impl<E: EthSpec, Payload: ExecPayload<E>> SerializeAs<Payload> for BlindedPayloadAsHeader<E> {
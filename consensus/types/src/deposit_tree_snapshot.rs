@@ -0,0 +1,80 @@
+use crate::test_utils::TestRandom;
+use crate::{Hash256, DEPOSIT_TREE_DEPTH};
+use eth2_hashing::hash32_concat;
+use int_to_bytes::int_to_bytes32;
+use merkle_proof::MerkleTree;
+use serde_derive::{Deserialize, Serialize};
+use ssz_derive::{Decode, Encode};
+use test_random_derive::TestRandom;
+
+/// A snapshot of the deposit contract tree, as of the eth1 block at `execution_block_height`.
+///
+/// Only the hashes of the full subtrees which contain the first `deposit_count` deposits are
+/// retained, which is enough to extend the tree with later deposits (EIP-4881).
+#[cfg_attr(feature = "arbitrary-fuzz", derive(arbitrary::Arbitrary))]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, Encode, Decode, TestRandom)]
+pub struct DepositTreeSnapshot {
+    pub finalized: Vec<Hash256>,
+    pub deposit_root: Hash256,
+    #[serde(with = "eth2_serde_utils::quoted_u64")]
+    pub deposit_count: u64,
+    pub execution_block_hash: Hash256,
+    #[serde(with = "eth2_serde_utils::quoted_u64")]
+    pub execution_block_height: u64,
+}
+
+impl DepositTreeSnapshot {
+    /// Computes the root of the deposit tree described by `self.finalized`, with the deposit count
+    /// mixed in.
+    ///
+    /// Returns `None` if `self.finalized` doesn't match `self.deposit_count`.
+    pub fn calculate_root(&self) -> Option<Hash256> {
+        let tree = MerkleTree::from_finalized_snapshot(
+            &self.finalized,
+            self.deposit_count as usize,
+            DEPOSIT_TREE_DEPTH,
+        )
+        .ok()?;
+        Some(Hash256::from_slice(&hash32_concat(
+            tree.hash().as_bytes(),
+            &int_to_bytes32(self.deposit_count),
+        )))
+    }
+
+    /// Returns `true` if `self.deposit_root` is the root of the deposit tree described by
+    /// `self.finalized`.
+    pub fn is_valid(&self) -> bool {
+        self.calculate_root() == Some(self.deposit_root)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    ssz_tests!(DepositTreeSnapshot);
+
+    #[test]
+    fn validity() {
+        let leaves = (0..5).map(Hash256::from_low_u64_be).collect::<Vec<_>>();
+        let tree = MerkleTree::create(&leaves, DEPOSIT_TREE_DEPTH);
+        let mut snapshot = DepositTreeSnapshot {
+            finalized: tree.get_finalized_hashes(5, DEPOSIT_TREE_DEPTH).unwrap(),
+            deposit_root: Hash256::from_slice(&hash32_concat(
+                tree.hash().as_bytes(),
+                &int_to_bytes32(5),
+            )),
+            deposit_count: 5,
+            execution_block_hash: Hash256::repeat_byte(1),
+            execution_block_height: 42,
+        };
+        assert!(snapshot.is_valid());
+
+        snapshot.deposit_count = 6;
+        assert!(!snapshot.is_valid());
+
+        snapshot.deposit_count = 5;
+        snapshot.deposit_root = Hash256::zero();
+        assert!(!snapshot.is_valid());
+    }
+}
@@ -11,12 +11,22 @@ use tree_hash::TreeHash;
 /// The byte-length of a BLS public key when serialized in compressed form.
 pub const PUBLIC_KEY_BYTES_LEN: usize = 48;
 
+/// The byte-length of a BLS public key when serialized in uncompressed form.
+pub const PUBLIC_KEY_UNCOMPRESSED_BYTES_LEN: usize = 96;
+
 /// Represents the public key at infinity.
 pub const INFINITY_PUBLIC_KEY: [u8; PUBLIC_KEY_BYTES_LEN] = [
     0xc0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
     0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
 ];
 
+/// Represents the public key at infinity, in uncompressed form.
+pub const INFINITY_PUBLIC_KEY_UNCOMPRESSED: [u8; PUBLIC_KEY_UNCOMPRESSED_BYTES_LEN] = {
+    let mut bytes = [0; PUBLIC_KEY_UNCOMPRESSED_BYTES_LEN];
+    bytes[0] = 0x40;
+    bytes
+};
+
 /// Implemented on some struct from a BLS library so it may be used as the `point` in a
 /// `GenericPublicKey`.
 pub trait TPublicKey: Sized + Clone {
@@ -25,6 +35,13 @@ pub trait TPublicKey: Sized + Clone {
 
     /// Deserialize `self` from compressed bytes.
     fn deserialize(bytes: &[u8]) -> Result<Self, Error>;
+
+    /// Serialize `self` as uncompressed bytes.
+    fn serialize_uncompressed(&self) -> [u8; PUBLIC_KEY_UNCOMPRESSED_BYTES_LEN];
+
+    /// Deserialize `self` from uncompressed bytes, without checking that the point is in the
+    /// correct subgroup.
+    fn deserialize_uncompressed(bytes: &[u8]) -> Result<Self, Error>;
 }
 
 /// A BLS public key that is generic across some BLS point (`Pub`).
@@ -75,6 +92,26 @@ where
             })
         }
     }
+
+    /// Serialize `self` as uncompressed bytes.
+    pub fn serialize_uncompressed(&self) -> [u8; PUBLIC_KEY_UNCOMPRESSED_BYTES_LEN] {
+        self.point.serialize_uncompressed()
+    }
+
+    /// Deserialize `self` from uncompressed bytes.
+    ///
+    /// This avoids the cost of decompression, but it doesn't check that the point is in the
+    /// correct subgroup, so it must only be used with bytes from a trusted source (e.g., bytes
+    /// produced by `Self::serialize_uncompressed` on a key which was deserialized normally).
+    pub fn deserialize_uncompressed(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes == &INFINITY_PUBLIC_KEY_UNCOMPRESSED[..] {
+            Err(Error::InvalidInfinityPublicKey)
+        } else {
+            Ok(Self {
+                point: Pub::deserialize_uncompressed(bytes)?,
+            })
+        }
+    }
 }
 
 impl<Pub: TPublicKey> Eq for GenericPublicKey<Pub> {}
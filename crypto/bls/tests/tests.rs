@@ -1,4 +1,4 @@
-use bls::{Hash256, INFINITY_SIGNATURE, SECRET_KEY_BYTES_LEN};
+use bls::{Hash256, INFINITY_PUBLIC_KEY_UNCOMPRESSED, INFINITY_SIGNATURE, SECRET_KEY_BYTES_LEN};
 use ssz::{Decode, Encode};
 use std::borrow::Cow;
 use std::fmt::Debug;
@@ -37,6 +37,17 @@ macro_rules! test_suite {
             assert!(AggregateSignature::infinity().is_infinity());
         }
 
+        #[test]
+        fn uncompressed_pubkey_round_trip() {
+            let pubkey = secret_from_u64(42).public_key();
+            let bytes = pubkey.serialize_uncompressed();
+            assert_eq!(PublicKey::deserialize_uncompressed(&bytes).unwrap(), pubkey);
+            assert!(PublicKey::deserialize_uncompressed(&bytes[1..]).is_err());
+            assert!(
+                PublicKey::deserialize_uncompressed(&INFINITY_PUBLIC_KEY_UNCOMPRESSED).is_err()
+            );
+        }
+
         #[test]
         fn ssz_round_trip_multiple_types() {
             let mut agg_sig = AggregateSignature::infinity();
@@ -661,6 +661,7 @@ fn run<T: EthSpec>(
             max_log_size: 0,
             max_log_number: 0,
             compression: false,
+            level_overrides: <_>::default(),
         })
         .map_err(|e| format!("should start logger: {:?}", e))?
         .build()
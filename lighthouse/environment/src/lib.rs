@@ -12,8 +12,13 @@ use eth2_network_config::Eth2NetworkConfig;
 use futures::channel::mpsc::{channel, Receiver, Sender};
 use futures::{future, StreamExt};
 
-use slog::{error, info, o, warn, Drain, Duplicate, Level, Logger};
-use sloggers::{file::FileLoggerBuilder, types::Format, types::Severity, Build};
+use logging::{parse_level, LevelOverrideFilter, LevelOverrides, LevelOverridesHandle};
+use slog::{error, info, o, warn, Drain, Duplicate, Logger};
+use sloggers::{
+    file::FileLoggerBuilder,
+    types::{Format, OverflowStrategy, Severity},
+    Build,
+};
 use std::fs::create_dir_all;
 use std::path::PathBuf;
 use std::sync::Arc;
@@ -34,6 +39,10 @@ use {futures::channel::oneshot, std::cell::RefCell};
 pub use task_executor::test_utils::null_logger;
 
 const LOG_CHANNEL_SIZE: usize = 2048;
+/// The default size in MB at which the log file is rotated.
+pub const DEFAULT_LOGFILE_MAX_SIZE: u64 = 200;
+/// The default number of log files kept.
+pub const DEFAULT_LOGFILE_MAX_NUMBER: usize = 5;
 /// The maximum time in seconds the client will wait for all internal tasks to shutdown.
 const MAXIMUM_SHUTDOWN_TIME: u64 = 15;
 
@@ -50,12 +59,15 @@ pub struct LoggerConfig<'a> {
     pub max_log_size: u64,
     pub max_log_number: usize,
     pub compression: bool,
+    /// Per-module overrides of `debug_level` and `logfile_debug_level`.
+    pub level_overrides: LevelOverrides,
 }
 
 /// Builds an `Environment`.
 pub struct EnvironmentBuilder<E: EthSpec> {
     runtime: Option<Arc<Runtime>>,
     log: Option<Logger>,
+    log_level_overrides: LevelOverridesHandle,
     eth_spec_instance: E,
     eth2_config: Eth2Config,
     eth2_network_config: Option<Eth2NetworkConfig>,
@@ -67,6 +79,7 @@ impl EnvironmentBuilder<MinimalEthSpec> {
         Self {
             runtime: None,
             log: None,
+            log_level_overrides: <_>::default(),
             eth_spec_instance: MinimalEthSpec,
             eth2_config: Eth2Config::minimal(),
             eth2_network_config: None,
@@ -80,6 +93,7 @@ impl EnvironmentBuilder<MainnetEthSpec> {
         Self {
             runtime: None,
             log: None,
+            log_level_overrides: <_>::default(),
             eth_spec_instance: MainnetEthSpec,
             eth2_config: Eth2Config::mainnet(),
             eth2_network_config: None,
@@ -93,6 +107,7 @@ impl EnvironmentBuilder<GnosisEthSpec> {
         Self {
             runtime: None,
             log: None,
+            log_level_overrides: <_>::default(),
             eth_spec_instance: GnosisEthSpec,
             eth2_config: Eth2Config::gnosis(),
             eth2_network_config: None,
@@ -148,15 +163,14 @@ impl<E: EthSpec> EnvironmentBuilder<E> {
                 .build()
         };
 
-        let stdout_drain = match config.debug_level {
-            "info" => stdout_drain.filter_level(Level::Info),
-            "debug" => stdout_drain.filter_level(Level::Debug),
-            "trace" => stdout_drain.filter_level(Level::Trace),
-            "warn" => stdout_drain.filter_level(Level::Warning),
-            "error" => stdout_drain.filter_level(Level::Error),
-            "crit" => stdout_drain.filter_level(Level::Critical),
-            unknown => return Err(format!("Unknown debug-level: {}", unknown)),
-        };
+        // The overrides are shared by the stdout and file drains, and may be replaced by services
+        // via the `RuntimeContext`.
+        self.log_level_overrides = LevelOverridesHandle::new(config.level_overrides);
+        let stdout_drain = LevelOverrideFilter::new(
+            stdout_drain,
+            parse_level(config.debug_level)?,
+            self.log_level_overrides.clone(),
+        );
 
         let stdout_logger = Logger::root(stdout_drain.fuse(), o!());
 
@@ -200,19 +214,20 @@ impl<E: EthSpec> EnvironmentBuilder<E> {
             }
         }
 
-        let logfile_level = match config.logfile_debug_level {
-            "info" => Severity::Info,
-            "debug" => Severity::Debug,
-            "trace" => Severity::Trace,
-            "warn" => Severity::Warning,
-            "error" => Severity::Error,
-            "crit" => Severity::Critical,
-            unknown => return Err(format!("Unknown loglevel-debug-level: {}", unknown)),
-        };
+        let logfile_level = parse_level(config.logfile_debug_level).map_err(|_| {
+            format!(
+                "Unknown loglevel-debug-level: {}",
+                config.logfile_debug_level
+            )
+        })?;
 
         let file_logger = FileLoggerBuilder::new(&path)
-            .level(logfile_level)
+            // Records are filtered by the `LevelOverrideFilter` below.
+            .level(Severity::Trace)
             .channel_size(LOG_CHANNEL_SIZE)
+            // Drop records when the channel is full rather than blocking, which could stall the
+            // async runtime whilst the file is slow to write. The number dropped is logged.
+            .overflow_strategy(OverflowStrategy::DropAndReport)
             .format(match config.log_format {
                 Some("JSON") => Format::Json,
                 _ => Format::default(),
@@ -224,7 +239,10 @@ impl<E: EthSpec> EnvironmentBuilder<E> {
             .build()
             .map_err(|e| format!("Unable to build file logger: {}", e))?;
 
-        let log = Logger::root(Duplicate::new(stdout_logger, file_logger).fuse(), o!());
+        let file_drain =
+            LevelOverrideFilter::new(file_logger, logfile_level, self.log_level_overrides.clone());
+
+        let log = Logger::root(Duplicate::new(stdout_logger, file_drain).fuse(), o!());
 
         info!(
             log,
@@ -274,6 +292,7 @@ impl<E: EthSpec> EnvironmentBuilder<E> {
             signal: Some(signal),
             exit,
             log: self.log.ok_or("Cannot build environment without log")?,
+            log_level_overrides: self.log_level_overrides,
             eth_spec_instance: self.eth_spec_instance,
             eth2_config: self.eth2_config,
             eth2_network_config: self.eth2_network_config.map(Arc::new),
@@ -288,6 +307,8 @@ impl<E: EthSpec> EnvironmentBuilder<E> {
 #[derive(Clone)]
 pub struct RuntimeContext<E: EthSpec> {
     pub executor: TaskExecutor,
+    /// The per-module log level overrides applied to the logger of the `executor`.
+    pub log_level_overrides: LevelOverridesHandle,
     pub eth_spec_instance: E,
     pub eth2_config: Eth2Config,
     pub eth2_network_config: Option<Arc<Eth2NetworkConfig>>,
@@ -300,6 +321,7 @@ impl<E: EthSpec> RuntimeContext<E> {
     pub fn service_context(&self, service_name: String) -> Self {
         Self {
             executor: self.executor.clone_with_name(service_name),
+            log_level_overrides: self.log_level_overrides.clone(),
             eth_spec_instance: self.eth_spec_instance.clone(),
             eth2_config: self.eth2_config.clone(),
             eth2_network_config: self.eth2_network_config.clone(),
@@ -328,6 +350,7 @@ pub struct Environment<E: EthSpec> {
     signal: Option<exit_future::Signal>,
     exit: exit_future::Exit,
     log: Logger,
+    log_level_overrides: LevelOverridesHandle,
     eth_spec_instance: E,
     pub eth2_config: Eth2Config,
     pub eth2_network_config: Option<Arc<Eth2NetworkConfig>>,
@@ -351,6 +374,7 @@ impl<E: EthSpec> Environment<E> {
                 self.log.clone(),
                 self.signal_tx.clone(),
             ),
+            log_level_overrides: self.log_level_overrides.clone(),
             eth_spec_instance: self.eth_spec_instance.clone(),
             eth2_config: self.eth2_config.clone(),
             eth2_network_config: self.eth2_network_config.clone(),
@@ -366,6 +390,7 @@ impl<E: EthSpec> Environment<E> {
                 self.log.new(o!("service" => service_name)),
                 self.signal_tx.clone(),
             ),
+            log_level_overrides: self.log_level_overrides.clone(),
             eth_spec_instance: self.eth_spec_instance.clone(),
             eth2_config: self.eth2_config.clone(),
             eth2_network_config: self.eth2_network_config.clone(),
@@ -49,3 +49,73 @@ mod setup_eth2_config {
         }
     }
 }
+
+mod initialize_logger {
+    use super::*;
+    use environment::LoggerConfig;
+    use slog::debug;
+    use std::fs;
+    use std::thread;
+
+    // Fewer lines than the log file channel holds, so that none are dropped due to overflow.
+    const THREADS: usize = 4;
+    const LINES_PER_THREAD: usize = 250;
+
+    #[test]
+    fn rotation_does_not_lose_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("beacon.log");
+
+        let mut environment = EnvironmentBuilder::mainnet()
+            .multi_threaded_tokio_runtime()
+            .expect("should set runtime")
+            .initialize_logger(LoggerConfig {
+                path: Some(path),
+                debug_level: "crit",
+                logfile_debug_level: "debug",
+                log_format: Some("JSON"),
+                // Small enough to rotate many times, with enough files that none are removed.
+                max_log_size: 16 * 1_024,
+                max_log_number: 1_000,
+                compression: false,
+                level_overrides: <_>::default(),
+            })
+            .expect("should set logger")
+            .build()
+            .expect("should build environment");
+        let log = environment.core_context().log().clone();
+
+        let threads = (0..THREADS)
+            .map(|thread| {
+                let log = log.clone();
+                thread::spawn(move || {
+                    for line in 0..LINES_PER_THREAD {
+                        debug!(log, "Test line"; "thread" => thread, "line" => line);
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        // Dropping every logger flushes the file logger.
+        drop(log);
+        drop(environment);
+
+        let mut files = 0;
+        let mut lines = 0;
+        for entry in fs::read_dir(dir.path()).unwrap() {
+            files += 1;
+            for line in fs::read_to_string(entry.unwrap().path()).unwrap().lines() {
+                let record: serde_json::Value = serde_json::from_str(line).unwrap();
+                if record["msg"] == "Test line" {
+                    lines += 1;
+                }
+            }
+        }
+
+        assert!(files > 1, "log file should have been rotated");
+        assert_eq!(lines, THREADS * LINES_PER_THREAD);
+    }
+}
@@ -2,23 +2,34 @@
 
 mod metrics;
 
-use beacon_node::ProductionBeaconNode;
+use beacon_node::{ClientConfig, NetworkSelection, ProductionBeaconNode, CONFIG_FILE_FLAG};
 use clap::{App, Arg, ArgMatches};
-use clap_utils::{flags::DISABLE_MALLOC_TUNING_FLAG, get_eth2_network_config};
-use directory::{parse_path_or_default, DEFAULT_BEACON_NODE_DIR, DEFAULT_VALIDATOR_DIR};
+use clap_utils::{flags::DISABLE_MALLOC_TUNING_FLAG, get_eth2_network_config_with_config_network};
+use directory::{
+    network_dir_name, parse_path_or_default, DEFAULT_BEACON_NODE_DIR, DEFAULT_ROOT_DIR,
+    DEFAULT_VALIDATOR_DIR,
+};
 use env_logger::{Builder, Env};
-use environment::{EnvironmentBuilder, LoggerConfig};
+use environment::{
+    EnvironmentBuilder, LoggerConfig, DEFAULT_LOGFILE_MAX_NUMBER, DEFAULT_LOGFILE_MAX_SIZE,
+};
 use eth2_hashing::have_sha_extensions;
-use eth2_network_config::{Eth2NetworkConfig, DEFAULT_HARDCODED_NETWORK, HARDCODED_NET_NAMES};
+use eth2_network_config::{Eth2NetworkConfig, HARDCODED_NET_NAMES};
+use lazy_static::lazy_static;
 use lighthouse_version::VERSION;
 use malloc_utils::configure_memory_allocator;
-use slog::{crit, info, warn};
-use std::path::PathBuf;
+use slog::{crit, info, o, warn, Logger};
+use std::path::{Path, PathBuf};
 use std::process::exit;
 use task_executor::ShutdownReason;
 use types::{EthSpec, EthSpecId};
 use validator_client::ProductionValidatorClient;
 
+lazy_static! {
+    static ref DEFAULT_LOGFILE_MAX_SIZE_STR: String = DEFAULT_LOGFILE_MAX_SIZE.to_string();
+    static ref DEFAULT_LOGFILE_MAX_NUMBER_STR: String = DEFAULT_LOGFILE_MAX_NUMBER.to_string();
+}
+
 fn bls_library_name() -> &'static str {
     if cfg!(feature = "portable") {
         "blst-portable"
@@ -107,7 +118,7 @@ fn main() {
                     "The maximum size (in MB) each log file can grow to before rotating. If set \
                     to 0, background file logging is disabled.")
                 .takes_value(true)
-                .default_value("200")
+                .default_value(&DEFAULT_LOGFILE_MAX_SIZE_STR)
                 .global(true),
         )
         .arg(
@@ -118,7 +129,7 @@ fn main() {
                     "The maximum number of log files that will be stored. If set to 0, \
                     background file logging is disabled.")
                 .takes_value(true)
-                .default_value("5")
+                .default_value(&DEFAULT_LOGFILE_MAX_NUMBER_STR)
                 .global(true),
         )
         .arg(
@@ -138,6 +149,18 @@ fn main() {
                 .takes_value(true)
                 .global(true),
         )
+        .arg(
+            Arg::with_name("log-level-overrides")
+                .long("log-level-overrides")
+                .value_name("TARGETS")
+                .help(
+                    "Override the verbosity level of both the terminal and log file for specific \
+                    modules, as a comma-separated list of target=level pairs. A target applies to \
+                    the module and all of its descendants, e.g. \
+                    `network=warn,beacon_chain::fork_choice=trace`.")
+                .takes_value(true)
+                .global(true),
+        )
         .arg(
             Arg::with_name("debug-level")
                 .long("debug-level")
@@ -300,7 +323,9 @@ fn main() {
         Builder::from_env(Env::default()).init();
     }
 
-    let result = get_eth2_network_config(&matches).and_then(|eth2_network_config| {
+    let result = config_file_network(&matches).and_then(|config_network| {
+        let eth2_network_config =
+            get_eth2_network_config_with_config_network(&matches, config_network.clone())?;
         let eth_spec_id = eth2_network_config.eth_spec_id()?;
 
         // boot node subcommand circumvents the environment
@@ -319,15 +344,30 @@ fn main() {
                 debug_info,
             );
 
-            return Ok(());
+            return Ok(None);
         }
 
         match eth_spec_id {
-            EthSpecId::Mainnet => run(EnvironmentBuilder::mainnet(), &matches, eth2_network_config),
+            EthSpecId::Mainnet => run(
+                EnvironmentBuilder::mainnet(),
+                &matches,
+                eth2_network_config,
+                config_network,
+            ),
             #[cfg(feature = "gnosis")]
-            EthSpecId::Gnosis => run(EnvironmentBuilder::gnosis(), &matches, eth2_network_config),
+            EthSpecId::Gnosis => run(
+                EnvironmentBuilder::gnosis(),
+                &matches,
+                eth2_network_config,
+                config_network,
+            ),
             #[cfg(feature = "spec-minimal")]
-            EthSpecId::Minimal => run(EnvironmentBuilder::minimal(), &matches, eth2_network_config),
+            EthSpecId::Minimal => run(
+                EnvironmentBuilder::minimal(),
+                &matches,
+                eth2_network_config,
+                config_network,
+            ),
             #[cfg(not(all(feature = "spec-minimal", feature = "gnosis")))]
             other => {
                 eprintln!(
@@ -345,7 +385,11 @@ fn main() {
 
     // Return the appropriate error code.
     match result {
-        Ok(()) => exit(0),
+        Ok(None) | Ok(Some(ShutdownReason::Success(_))) => exit(0),
+        Ok(Some(shutdown_reason)) => {
+            eprintln!("{}", shutdown_reason.message());
+            exit(shutdown_reason.exit_code())
+        }
         Err(e) => {
             eprintln!("{}", e);
             drop(e);
@@ -354,11 +398,42 @@ fn main() {
     }
 }
 
+/// Returns the network selected by the beacon node's config file, if any.
+///
+/// The network determines the `EthSpec` of the environment, so it is read before the rest of the
+/// config file.
+fn config_file_network(matches: &ArgMatches) -> Result<Option<NetworkSelection>, String> {
+    match matches
+        .subcommand_matches("beacon_node")
+        .and_then(|bn_matches| bn_matches.value_of(CONFIG_FILE_FLAG))
+    {
+        Some(path) => ClientConfig::network_from_file(Path::new(path)),
+        None => Ok(None),
+    }
+}
+
+/// Returns the beacon node's config file, if any.
+///
+/// Unknown keys are reported once the beacon node loads the file again, so they're ignored here.
+fn beacon_node_config_file(matches: &ArgMatches) -> Result<Option<ClientConfig>, String> {
+    match matches
+        .subcommand_matches("beacon_node")
+        .and_then(|bn_matches| bn_matches.value_of(CONFIG_FILE_FLAG))
+    {
+        Some(path) => {
+            ClientConfig::from_file(Path::new(path), &Logger::root(slog::Discard, o!())).map(Some)
+        }
+        None => Ok(None),
+    }
+}
+
+/// Returns the reason the node was shut down, or `None` if a subcommand ran to completion.
 fn run<E: EthSpec>(
     environment_builder: EnvironmentBuilder<E>,
     matches: &ArgMatches,
     eth2_network_config: Eth2NetworkConfig,
-) -> Result<(), String> {
+    config_network: Option<NetworkSelection>,
+) -> Result<Option<ShutdownReason>, String> {
     if std::mem::size_of::<usize>() != 8 {
         return Err(format!(
             "{}-bit architecture is not supported (64-bit only).",
@@ -366,40 +441,72 @@ fn run<E: EthSpec>(
         ));
     }
 
+    let network = clap_utils::select_network(matches, config_network)?;
+
     let debug_level = matches
         .value_of("debug-level")
         .ok_or("Expected --debug-level flag")?;
 
-    let log_format = matches.value_of("log-format");
+    // The logger is built before the beacon node's config file is loaded, so read the logging
+    // options from the file now. As for the other options in the file, only flags supplied
+    // explicitly take precedence over them.
+    let config_file = beacon_node_config_file(matches)?;
+    let from_config_file = |name: &str| {
+        config_file
+            .as_ref()
+            .filter(|_| matches.occurrences_of(name) == 0)
+    };
+
+    let log_format = match from_config_file("log-format") {
+        Some(config) => config.log_format.as_deref(),
+        None => matches.value_of("log-format"),
+    };
 
     let logfile_debug_level = matches
         .value_of("logfile-debug-level")
         .ok_or("Expected --logfile-debug-level flag")?;
 
-    let logfile_max_size: u64 = matches
-        .value_of("logfile-max-size")
-        .ok_or("Expected --logfile-max-size flag")?
-        .parse()
-        .map_err(|e| format!("Failed to parse `logfile-max-size`: {:?}", e))?;
+    let logfile_max_size: u64 = match from_config_file("logfile-max-size") {
+        Some(config) => config.logfile_max_size,
+        None => matches
+            .value_of("logfile-max-size")
+            .ok_or("Expected --logfile-max-size flag")?
+            .parse()
+            .map_err(|e| format!("Failed to parse `logfile-max-size`: {:?}", e))?,
+    };
 
-    let logfile_max_number: usize = matches
-        .value_of("logfile-max-number")
-        .ok_or("Expected --logfile-max-number flag")?
-        .parse()
-        .map_err(|e| format!("Failed to parse `logfile-max-number`: {:?}", e))?;
+    let logfile_max_number: usize = match from_config_file("logfile-max-number") {
+        Some(config) => config.logfile_max_number,
+        None => matches
+            .value_of("logfile-max-number")
+            .ok_or("Expected --logfile-max-number flag")?
+            .parse()
+            .map_err(|e| format!("Failed to parse `logfile-max-number`: {:?}", e))?,
+    };
 
     let logfile_compress = matches.is_present("logfile-compress");
 
+    let level_overrides =
+        clap_utils::parse_optional(matches, "log-level-overrides")?.unwrap_or_default();
+
     // Construct the path to the log file.
     let mut log_path: Option<PathBuf> = clap_utils::parse_optional(matches, "logfile")?;
     if log_path.is_none() {
         log_path = match matches.subcommand() {
+            // The beacon node's config file may select the network, so the default datadir is
+            // determined by the selected network rather than the flags alone.
             ("beacon_node", _) => Some(
-                parse_path_or_default(matches, "datadir")?
-                    .join(DEFAULT_BEACON_NODE_DIR)
-                    .join("logs")
-                    .join("beacon")
-                    .with_extension("log"),
+                clap_utils::parse_path_with_default_in_home_dir(
+                    matches,
+                    "datadir",
+                    PathBuf::new()
+                        .join(DEFAULT_ROOT_DIR)
+                        .join(network_dir_name(&network)),
+                )?
+                .join(DEFAULT_BEACON_NODE_DIR)
+                .join("logs")
+                .join("beacon")
+                .with_extension("log"),
             ),
             ("validator_client", Some(vc_matches)) => {
                 let base_path = if vc_matches.is_present("validators-dir") {
@@ -427,6 +534,7 @@ fn run<E: EthSpec>(
         max_log_size: logfile_max_size * 1_024 * 1_024,
         max_log_number: logfile_max_number,
         compression: logfile_compress,
+        level_overrides,
     };
 
     let builder = environment_builder.initialize_logger(logger_config)?;
@@ -469,15 +577,7 @@ fn run<E: EthSpec>(
     // Creating a command which can run both might be useful future works.
 
     // Print an indication of which network is currently in use.
-    let optional_testnet = clap_utils::parse_optional::<String>(matches, "network")?;
-    let optional_testnet_dir = clap_utils::parse_optional::<PathBuf>(matches, "testnet-dir")?;
-
-    let network_name = match (optional_testnet, optional_testnet_dir) {
-        (Some(testnet), None) => testnet,
-        (None, Some(testnet_dir)) => format!("custom ({})", testnet_dir.display()),
-        (None, None) => DEFAULT_HARDCODED_NETWORK.to_string(),
-        (Some(_), Some(_)) => panic!("CLI prevents both --network and --testnet-dir"),
-    };
+    let network_name = network.to_string();
 
     if let Some(sub_matches) = matches.subcommand_matches("account_manager") {
         eprintln!("Running account manager for {} network", network_name);
@@ -485,7 +585,7 @@ fn run<E: EthSpec>(
         account_manager::run(sub_matches, environment)?;
 
         // Exit as soon as account manager returns control.
-        return Ok(());
+        return Ok(None);
     }
 
     if let Some(sub_matches) = matches.subcommand_matches(database_manager::CMD) {
@@ -494,7 +594,7 @@ fn run<E: EthSpec>(
         database_manager::run(sub_matches, environment)?;
 
         // Exit as soon as database manager returns control.
-        return Ok(());
+        return Ok(None);
     }
 
     info!(log, "Lighthouse started"; "version" => VERSION);
@@ -578,8 +678,5 @@ fn run<E: EthSpec>(
     // Shutdown the environment once all tasks have completed.
     environment.shutdown_on_idle();
 
-    match shutdown_reason {
-        ShutdownReason::Success(_) => Ok(()),
-        ShutdownReason::Failure(msg) => Err(msg.to_string()),
-    }
+    Ok(Some(shutdown_reason))
 }
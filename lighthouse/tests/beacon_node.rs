@@ -1,4 +1,9 @@
+use beacon_node::beacon_chain::chain_config::{
+    ShutdownTarget, DEFAULT_PRE_FINALIZATION_BLOCK_CACHE_SIZE,
+};
+use beacon_node::beacon_chain::GraffitiMode;
 use beacon_node::ClientConfig as Config;
+use beacon_node::NetworkSelection;
 
 use crate::exec::{CommandLineTestExec, CompletedTest};
 use eth1::Eth1Endpoint;
@@ -10,8 +15,9 @@ use std::path::PathBuf;
 use std::process::Command;
 use std::str::FromStr;
 use std::string::ToString;
+use std::time::Duration;
 use tempfile::TempDir;
-use types::{Address, Checkpoint, Epoch, ExecutionBlockHash, Hash256, MainnetEthSpec};
+use types::{Address, Checkpoint, Epoch, ExecutionBlockHash, Hash256, MainnetEthSpec, Slot};
 use unused_port::{unused_tcp_port, unused_udp_port};
 
 const DEFAULT_ETH1_ENDPOINT: &str = "http://localhost:8545/";
@@ -90,6 +96,43 @@ fn wss_checkpoint_flag() {
         .with_config(|config| assert_eq!(config.chain.weak_subjectivity_checkpoint, state));
 }
 #[test]
+fn checkpoint_sync_url_timeout_flag() {
+    CommandLineTest::new()
+        .flag("checkpoint-sync-url-timeout", Some("300"))
+        .run_with_zero_port()
+        .with_config(|config| assert_eq!(config.checkpoint_sync_url_timeout, 300));
+}
+#[test]
+fn checkpoint_sync_url_timeout_default() {
+    CommandLineTest::new()
+        .run_with_zero_port()
+        .with_config(|config| assert_eq!(config.checkpoint_sync_url_timeout, 60));
+}
+#[test]
+fn genesis_state_url_timeout_flag() {
+    CommandLineTest::new()
+        .flag("genesis-state-url-timeout", Some("30"))
+        .run_with_zero_port()
+        .with_config(|config| assert_eq!(config.genesis_state_url_timeout, 30));
+}
+#[test]
+fn genesis_state_url_timeout_default() {
+    CommandLineTest::new()
+        .run_with_zero_port()
+        .with_config(|config| assert_eq!(config.genesis_state_url_timeout, 180));
+}
+#[test]
+#[should_panic]
+fn genesis_state_url_with_known_genesis_state() {
+    // Mainnet includes a genesis state, so a remote one must not be used.
+    CommandLineTest::new()
+        .flag(
+            "genesis-state-url",
+            Some("http://localhost:8000/genesis.ssz"),
+        )
+        .run_with_zero_port();
+}
+#[test]
 fn max_skip_slots_flag() {
     CommandLineTest::new()
         .flag("max-skip-slots", Some("10"))
@@ -132,6 +175,21 @@ fn fork_choice_before_proposal_timeout_zero() {
         .with_config(|config| assert_eq!(config.chain.fork_choice_before_proposal_timeout_ms, 0));
 }
 
+#[test]
+fn prune_payloads_default() {
+    CommandLineTest::new()
+        .run_with_zero_port()
+        .with_config(|config| assert!(config.chain.prune_payloads));
+}
+
+#[test]
+fn prune_payloads_false() {
+    CommandLineTest::new()
+        .flag("prune-payloads", Some("false"))
+        .run_with_zero_port()
+        .with_config(|config| assert!(!config.chain.prune_payloads));
+}
+
 #[test]
 fn freezer_dir_flag() {
     let dir = TempDir::new().expect("Unable to create temporary directory");
@@ -141,6 +199,230 @@ fn freezer_dir_flag() {
         .with_config(|config| assert_eq!(config.freezer_db_path, Some(dir.path().to_path_buf())));
 }
 
+#[test]
+fn disk_space_default() {
+    CommandLineTest::new()
+        .run_with_zero_port()
+        .with_config(|config| {
+            assert_eq!(config.disk_space.warn_threshold_mb, 10240);
+            assert_eq!(config.disk_space.minimum_mb, 1024);
+            assert!(!config.disk_space.ignore_minimum_at_startup);
+            assert!(!config.disk_space.shutdown_below_minimum);
+        });
+}
+
+#[test]
+fn disk_space_flags() {
+    CommandLineTest::new()
+        .flag("disk-space-warn-threshold", Some("2048"))
+        .flag("disk-space-minimum", Some("512"))
+        .flag("ignore-disk-space-minimum", None)
+        .flag("shutdown-on-low-disk-space", None)
+        .run_with_zero_port()
+        .with_config(|config| {
+            assert_eq!(config.disk_space.warn_threshold_mb, 2048);
+            assert_eq!(config.disk_space.minimum_mb, 512);
+            assert!(config.disk_space.ignore_minimum_at_startup);
+            assert!(config.disk_space.shutdown_below_minimum);
+        });
+}
+
+fn write_config_file(dir: &TempDir, contents: &str) -> PathBuf {
+    let path = dir.path().join("config.toml");
+    let mut file = File::create(&path).expect("Unable to create config file");
+    file.write_all(contents.as_bytes())
+        .expect("Unable to write to config file");
+    path
+}
+
+#[test]
+fn config_file_flag() {
+    let dir = TempDir::new().expect("Unable to create temporary directory");
+    let file_port = unused_tcp_port().expect("Unable to find unused port.");
+    let cli_port = unused_tcp_port().expect("Unable to find unused port.");
+    let path = write_config_file(
+        &dir,
+        &format!(
+            r#"
+            db_name = "my_chain_db"
+
+            [chain]
+            reconstruct_historic_states = true
+            builder_fallback_skips = 5
+
+            [http_api]
+            enabled = true
+            listen_port = {}
+            allow_sync_stalled = true
+            "#,
+            file_port
+        ),
+    );
+    CommandLineTest::new()
+        .flag("config-file", path.as_os_str().to_str())
+        .flag("http-port", Some(cli_port.to_string().as_str()))
+        .flag("builder-fallback-skips", Some("7"))
+        .run()
+        .with_config(|config| {
+            assert_eq!(config.db_name, "my_chain_db");
+            assert!(config.chain.reconstruct_historic_states);
+            assert!(config.http_api.enabled);
+            assert!(config.http_api.allow_sync_stalled);
+            // Values from the CLI take precedence over the file.
+            assert_eq!(config.http_api.listen_port, cli_port);
+            assert_eq!(config.chain.builder_fallback_skips, 7);
+        });
+}
+
+#[test]
+fn log_level_overrides_flag() {
+    CommandLineTest::new()
+        .flag(
+            "log-level-overrides",
+            Some("network=warn,beacon_chain=trace"),
+        )
+        .run()
+        .with_config(|config| {
+            assert_eq!(
+                config.log_level_overrides.to_string(),
+                "network=warn,beacon_chain=trace"
+            )
+        });
+}
+
+#[test]
+fn logfile_options_default() {
+    CommandLineTest::new().run().with_config(|config| {
+        assert_eq!(config.log_format, None);
+        assert_eq!(config.logfile_max_size, 200);
+        assert_eq!(config.logfile_max_number, 5);
+    });
+}
+
+#[test]
+fn logfile_options_flags() {
+    CommandLineTest::new()
+        .flag("log-format", Some("JSON"))
+        .flag("logfile-max-size", Some("10"))
+        .flag("logfile-max-number", Some("2"))
+        .run()
+        .with_config(|config| {
+            assert_eq!(config.log_format.as_deref(), Some("JSON"));
+            assert_eq!(config.logfile_max_size, 10);
+            assert_eq!(config.logfile_max_number, 2);
+        });
+}
+
+#[test]
+fn logfile_options_config_file() {
+    let dir = TempDir::new().expect("Unable to create temporary directory");
+    let path = write_config_file(
+        &dir,
+        "log_format = \"JSON\"\nlogfile_max_size = 10\nlogfile_max_number = 2",
+    );
+    CommandLineTest::new()
+        .flag("config-file", path.as_os_str().to_str())
+        .run()
+        .with_config(|config| {
+            assert_eq!(config.log_format.as_deref(), Some("JSON"));
+            assert_eq!(config.logfile_max_size, 10);
+            assert_eq!(config.logfile_max_number, 2);
+        });
+}
+
+#[test]
+fn log_level_overrides_config_file() {
+    let dir = TempDir::new().expect("Unable to create temporary directory");
+    let path = write_config_file(&dir, r#"log_level_overrides = "network=debug""#);
+    CommandLineTest::new()
+        .flag("config-file", path.as_os_str().to_str())
+        .run()
+        .with_config(|config| assert_eq!(config.log_level_overrides.to_string(), "network=debug"));
+}
+
+#[test]
+fn config_file_not_overridden_by_cli_defaults() {
+    let dir = TempDir::new().expect("Unable to create temporary directory");
+    let file_port = unused_tcp_port().expect("Unable to find unused port.");
+    let path = write_config_file(
+        &dir,
+        &format!(
+            r#"
+            graffiti = "0x6e6963652d677261666669746900000000000000000000000000000000000000"
+
+            [chain]
+            fork_choice_before_proposal_timeout_ms = 100
+
+            [http_api]
+            listen_port = {}
+            "#,
+            file_port
+        ),
+    );
+    CommandLineTest::new()
+        .flag("config-file", path.as_os_str().to_str())
+        .run()
+        .with_config(|config| {
+            assert_eq!(config.chain.fork_choice_before_proposal_timeout_ms, 100);
+            assert_eq!(config.http_api.listen_port, file_port);
+            assert_eq!(
+                config.graffiti.to_string(),
+                "0x6e6963652d677261666669746900000000000000000000000000000000000000"
+            );
+        });
+}
+
+#[test]
+fn config_file_network_matches_cli() {
+    let dir = TempDir::new().expect("Unable to create temporary directory");
+    let path = write_config_file(&dir, "eth2_network = { named = \"prater\" }\n");
+    CommandLineTest::new()
+        .flag("config-file", path.as_os_str().to_str())
+        .flag("network", Some("prater"))
+        .run_with_zero_port()
+        .with_config(|config| {
+            assert_eq!(
+                config.eth2_network,
+                NetworkSelection::Named("prater".to_string())
+            );
+            assert_eq!(config.eth1.deposit_contract_deploy_block, 4367322);
+        });
+}
+
+#[test]
+fn config_file_network_not_selected_on_cli() {
+    let dir = TempDir::new().expect("Unable to create temporary directory");
+    let path = write_config_file(&dir, "eth2_network = { named = \"prater\" }\n");
+    CommandLineTest::new()
+        .flag("config-file", path.as_os_str().to_str())
+        .run_with_zero_port()
+        .with_config(|config| {
+            assert_eq!(
+                config.eth2_network,
+                NetworkSelection::Named("prater".to_string())
+            );
+            assert_eq!(config.eth1.deposit_contract_deploy_block, 4367322);
+        });
+}
+
+#[test]
+#[should_panic]
+fn config_file_network_conflicts_with_cli() {
+    let dir = TempDir::new().expect("Unable to create temporary directory");
+    let path = write_config_file(&dir, "eth2_network = { named = \"prater\" }\n");
+    CommandLineTest::new()
+        .flag("config-file", path.as_os_str().to_str())
+        .flag("network", Some("mainnet"))
+        .run_with_zero_port();
+}
+
+#[test]
+fn default_network() {
+    CommandLineTest::new()
+        .run_with_zero_port()
+        .with_config(|config| assert_eq!(config.eth2_network, NetworkSelection::default()));
+}
+
 #[test]
 fn graffiti_flag() {
     CommandLineTest::new()
@@ -154,6 +436,29 @@ fn graffiti_flag() {
         });
 }
 
+#[test]
+fn graffiti_mode_default() {
+    CommandLineTest::new()
+        .run_with_zero_port()
+        .with_config(|config| assert_eq!(config.graffiti, GraffitiMode::ClientVersion));
+}
+
+#[test]
+fn graffiti_mode_flag() {
+    CommandLineTest::new()
+        .flag("graffiti-mode", Some("random"))
+        .run_with_zero_port()
+        .with_config(|config| assert_eq!(config.graffiti, GraffitiMode::Random));
+}
+
+#[test]
+fn graffiti_mode_private_flag() {
+    CommandLineTest::new()
+        .flag("private", None)
+        .run_with_zero_port()
+        .with_config(|config| assert_eq!(config.graffiti, GraffitiMode::Empty));
+}
+
 #[test]
 fn trusted_peers_flag() {
     let peers = vec![PeerId::random(), PeerId::random()];
@@ -230,7 +535,52 @@ fn eth1_purge_cache_flag() {
     CommandLineTest::new()
         .flag("eth1-purge-cache", None)
         .run_with_zero_port()
-        .with_config(|config| assert!(config.eth1.purge_cache));
+        .with_config(|config| {
+            assert!(config.eth1.purge_cache);
+            assert!(config.purge_eth1_cache_on_startup);
+        });
+}
+#[test]
+fn eth1_ignore_network_mismatch_flag() {
+    CommandLineTest::new()
+        .flag("eth1-ignore-network-mismatch", None)
+        .run_with_zero_port()
+        .with_config(|config| assert!(config.eth1.ignore_network_mismatch));
+}
+#[test]
+fn eth1_ignore_network_mismatch_default() {
+    CommandLineTest::new()
+        .run_with_zero_port()
+        .with_config(|config| assert!(!config.eth1.ignore_network_mismatch));
+}
+#[test]
+fn eth1_deposit_log_start_block_flag() {
+    CommandLineTest::new()
+        .flag("eth1-deposit-log-start-block", Some("1000"))
+        .run_with_zero_port()
+        .with_config(|config| {
+            assert_eq!(config.eth1.deposit_contract_deploy_block, 1000);
+            assert_eq!(config.eth1.lowest_cached_block_number, 1000);
+        });
+}
+#[test]
+fn eth1_block_cache_truncation_flag() {
+    CommandLineTest::new()
+        .flag("eth1-block-cache-truncation", Some("4096"))
+        .run_with_zero_port()
+        .with_config(|config| assert_eq!(config.eth1.block_cache_truncation, Some(4096)));
+}
+#[test]
+fn eth1_deposit_snapshot_file_flag() {
+    CommandLineTest::new()
+        .flag("eth1-deposit-snapshot-file", Some("snapshot.json"))
+        .run_with_zero_port()
+        .with_config(|config| {
+            assert_eq!(
+                config.eth1_deposit_snapshot_file,
+                Some(PathBuf::from("snapshot.json"))
+            )
+        });
 }
 #[test]
 fn eth1_cache_follow_distance_default() {
@@ -256,8 +606,6 @@ fn eth1_cache_follow_distance_manual() {
 fn run_merge_execution_endpoints_flag_test(flag: &str) {
     use sensitive_url::SensitiveUrl;
     let urls = vec!["http://sigp.io/no-way:1337", "http://infura.not_real:4242"];
-    // we don't support redundancy for execution-endpoints
-    // only the first provided endpoint is parsed.
 
     let mut endpoint_arg = urls[0].to_string();
     for url in urls.iter().skip(1) {
@@ -285,18 +633,67 @@ fn run_merge_execution_endpoints_flag_test(flag: &str) {
         .flag(flag, Some(&endpoint_arg))
         .flag("execution-jwt", Some(&jwts_arg))
         .run_with_zero_port()
+        .with_config(|config| {
+            let el_config = config.execution_layer.as_ref().unwrap();
+            assert_eq!(
+                el_config.execution_endpoints,
+                urls.iter()
+                    .map(|url| SensitiveUrl::parse(url).unwrap())
+                    .collect::<Vec<_>>()
+            );
+            // Each endpoint has its own secret file.
+            assert_eq!(el_config.secret_files, jwts);
+            // The deposit contract is followed using the first endpoint.
+            assert_eq!(
+                config.eth1.endpoints,
+                Eth1Endpoint::Auth {
+                    endpoint: SensitiveUrl::parse(urls[0]).unwrap(),
+                    jwt_path: jwts[0].clone(),
+                    jwt_id: None,
+                    jwt_version: None,
+                }
+            );
+        });
+}
+#[test]
+fn merge_execution_endpoints_shared_jwt_flag() {
+    use sensitive_url::SensitiveUrl;
+    let dir = TempDir::new().expect("Unable to create temporary directory");
+    let jwt = dir.path().join("jwt-file");
+    CommandLineTest::new()
+        .flag(
+            "execution-endpoints",
+            Some("http://localhost:8551/,http://localhost:8552/"),
+        )
+        .flag("execution-jwt", jwt.as_os_str().to_str())
+        .run_with_zero_port()
         .with_config(|config| {
             let config = config.execution_layer.as_ref().unwrap();
-            assert_eq!(config.execution_endpoints.len(), 1);
             assert_eq!(
-                config.execution_endpoints[0],
-                SensitiveUrl::parse(&urls[0]).unwrap()
+                config.execution_endpoints[1],
+                SensitiveUrl::parse("http://localhost:8552/").unwrap()
             );
-            // Only the first secret file should be used.
-            assert_eq!(config.secret_files, vec![jwts[0].clone()]);
+            assert_eq!(config.secret_files, vec![jwt.clone(), jwt.clone()]);
         });
 }
 #[test]
+#[should_panic]
+fn merge_execution_endpoints_mismatched_jwts_flag() {
+    let dir = TempDir::new().expect("Unable to create temporary directory");
+    let jwts = format!(
+        "{},{}",
+        dir.path().join("jwt-0").display(),
+        dir.path().join("jwt-1").display()
+    );
+    CommandLineTest::new()
+        .flag(
+            "execution-endpoints",
+            Some("http://localhost:8551/,http://localhost:8552/,http://localhost:8553/"),
+        )
+        .flag("execution-jwt", Some(&jwts))
+        .run_with_zero_port();
+}
+#[test]
 fn merge_execution_endpoints_flag() {
     run_merge_execution_endpoints_flag_test("execution-endpoints")
 }
@@ -391,6 +788,23 @@ fn merge_fee_recipient_flag() {
             );
         });
 }
+#[test]
+fn merge_fee_recipient_file_flag() {
+    let dir = TempDir::new().expect("Unable to create temporary directory");
+    let path = dir.path().join("fee-recipients.txt");
+    CommandLineTest::new()
+        .flag("execution-endpoint", Some("http://meow.cats"))
+        .flag(
+            "execution-jwt",
+            dir.path().join("jwt-file").as_os_str().to_str(),
+        )
+        .flag("suggested-fee-recipient-file", path.as_os_str().to_str())
+        .run_with_zero_port()
+        .with_config(|config| {
+            let config = config.execution_layer.as_ref().unwrap();
+            assert_eq!(config.suggested_fee_recipient_file, Some(path.clone()));
+        });
+}
 fn run_payload_builder_flag_test(flag: &str, builders: &str) {
     use sensitive_url::SensitiveUrl;
 
@@ -408,10 +822,44 @@ fn run_payload_builder_flag_test(flag: &str, builders: &str) {
         .flag(flag, Some(builders))
         .run_with_zero_port()
         .with_config(|config| {
-            let config = config.execution_layer.as_ref().unwrap();
             // Only first provided endpoint is parsed as we don't support
             // redundancy.
-            assert_eq!(config.builder_url, all_builders.get(0).cloned());
+            assert_eq!(
+                config.builder.as_ref().map(|builder| builder.url.clone()),
+                all_builders.get(0).cloned()
+            );
+        });
+}
+
+#[test]
+fn payload_builder_flag_with_config_file() {
+    let dir = TempDir::new().expect("Unable to create temporary directory");
+    let path = write_config_file(
+        &dir,
+        r#"
+        [builder]
+        url = "http://woof.dogs"
+        prefer_ssz = true
+
+        [builder.timeouts]
+        get_header = 1000
+        "#,
+    );
+    CommandLineTest::new()
+        .flag("config-file", path.as_os_str().to_str())
+        .flag("execution-endpoint", Some("http://meow.cats"))
+        .flag(
+            "execution-jwt",
+            dir.path().join("jwt-file").as_os_str().to_str(),
+        )
+        .flag("builder", Some("http://meow.cats"))
+        .run_with_zero_port()
+        .with_config(|config| {
+            let builder = config.builder.as_ref().unwrap();
+            // The URL from the CLI takes precedence, whilst the other settings are retained.
+            assert_eq!(builder.url.full.as_str(), "http://meow.cats/");
+            assert!(builder.prefer_ssz);
+            assert_eq!(builder.timeouts.get_header, Duration::from_millis(1000));
         });
 }
 
@@ -423,6 +871,254 @@ fn payload_builder_flags() {
     run_payload_builder_flag_test("payload-builders", "http://meow.cats,http://woof.dogs");
 }
 
+#[test]
+fn builder_fallback_flags_default() {
+    CommandLineTest::new()
+        .run_with_zero_port()
+        .with_config(|config| {
+            assert_eq!(
+                config.chain.builder_fallback_skips,
+                beacon_node::beacon_chain::chain_config::DEFAULT_BUILDER_FALLBACK_SKIPS
+            );
+            assert_eq!(
+                config.chain.builder_fallback_epochs_since_finality,
+                beacon_node::beacon_chain::chain_config::DEFAULT_BUILDER_FALLBACK_EPOCHS_SINCE_FINALITY
+            );
+        });
+}
+
+#[test]
+fn builder_fallback_flags() {
+    CommandLineTest::new()
+        .flag("builder-fallback-skips", Some("7"))
+        .flag("builder-fallback-epochs-since-finality", Some("11"))
+        .run_with_zero_port()
+        .with_config(|config| {
+            assert_eq!(config.chain.builder_fallback_skips, 7);
+            assert_eq!(config.chain.builder_fallback_epochs_since_finality, 11);
+        });
+}
+
+#[test]
+fn broadcast_withheld_blinded_blocks_flag() {
+    CommandLineTest::new()
+        .run_with_zero_port()
+        .with_config(|config| assert!(!config.chain.broadcast_withheld_blinded_blocks));
+    CommandLineTest::new()
+        .flag("broadcast-withheld-blinded-blocks", None)
+        .run_with_zero_port()
+        .with_config(|config| assert!(config.chain.broadcast_withheld_blinded_blocks));
+}
+
+#[test]
+fn builder_bid_retention_epochs_flag() {
+    CommandLineTest::new()
+        .run_with_zero_port()
+        .with_config(|config| {
+            assert_eq!(
+                config.chain.builder_bid_retention_epochs,
+                beacon_node::beacon_chain::chain_config::DEFAULT_BUILDER_BID_RETENTION_EPOCHS
+            )
+        });
+    CommandLineTest::new()
+        .flag("builder-bid-retention-epochs", Some("32"))
+        .run_with_zero_port()
+        .with_config(|config| assert_eq!(config.chain.builder_bid_retention_epochs, 32));
+}
+
+#[test]
+fn finality_delay_flags_default() {
+    CommandLineTest::new()
+        .run_with_zero_port()
+        .with_config(|config| {
+            assert_eq!(
+                config.chain.finality_delay_warn_epochs,
+                beacon_node::beacon_chain::chain_config::DEFAULT_FINALITY_DELAY_WARN_EPOCHS
+            );
+            assert_eq!(
+                config.chain.finality_delay_crit_epochs,
+                beacon_node::beacon_chain::chain_config::DEFAULT_FINALITY_DELAY_CRIT_EPOCHS
+            );
+            assert!(!config.chain.finality_delay_pause_backfill);
+        });
+}
+
+#[test]
+fn finality_delay_flags() {
+    CommandLineTest::new()
+        .flag("finality-delay-warn-epochs", Some("3"))
+        .flag("finality-delay-crit-epochs", Some("8"))
+        .flag("finality-delay-pause-backfill", None)
+        .run_with_zero_port()
+        .with_config(|config| {
+            assert_eq!(config.chain.finality_delay_warn_epochs, 3);
+            assert_eq!(config.chain.finality_delay_crit_epochs, 8);
+            assert!(config.chain.finality_delay_pause_backfill);
+        });
+}
+
+#[test]
+fn failure_log_window_default() {
+    CommandLineTest::new()
+        .run_with_zero_port()
+        .with_config(|config| {
+            assert_eq!(
+                config.chain.failure_log_window_secs,
+                beacon_node::beacon_chain::chain_config::DEFAULT_FAILURE_LOG_WINDOW_SECS
+            )
+        });
+}
+
+#[test]
+fn failure_log_window_flag() {
+    CommandLineTest::new()
+        .flag("failure-log-window", Some("60"))
+        .run_with_zero_port()
+        .with_config(|config| assert_eq!(config.chain.failure_log_window_secs, 60));
+}
+
+#[test]
+fn verification_pressure_default() {
+    CommandLineTest::new()
+        .run_with_zero_port()
+        .with_config(|config| {
+            assert_eq!(
+                config.chain.verification_pressure_latency_ms,
+                beacon_node::beacon_chain::chain_config::DEFAULT_VERIFICATION_PRESSURE_LATENCY_MS
+            );
+            assert_eq!(
+                config.chain.verification_pressure_max_batches,
+                beacon_node::beacon_chain::chain_config::DEFAULT_VERIFICATION_PRESSURE_MAX_BATCHES
+            );
+        });
+}
+
+#[test]
+fn verification_pressure_flags() {
+    CommandLineTest::new()
+        .flag("verification-pressure-latency", Some("100"))
+        .flag("verification-pressure-batches", Some("4"))
+        .run_with_zero_port()
+        .with_config(|config| {
+            assert_eq!(config.chain.verification_pressure_latency_ms, 100);
+            assert_eq!(config.chain.verification_pressure_max_batches, 4);
+        });
+}
+
+#[test]
+fn cache_memory_budget_default() {
+    CommandLineTest::new()
+        .run_with_zero_port()
+        .with_config(|config| assert_eq!(config.chain.cache_memory_budget_mb, None));
+}
+
+#[test]
+fn cache_memory_budget_flag() {
+    CommandLineTest::new()
+        .flag("cache-memory-budget", Some("512"))
+        .run_with_zero_port()
+        .with_config(|config| assert_eq!(config.chain.cache_memory_budget_mb, Some(512)));
+}
+
+#[test]
+fn prewarm_max_epochs_default() {
+    CommandLineTest::new()
+        .run_with_zero_port()
+        .with_config(|config| {
+            assert_eq!(
+                config.chain.prewarm_max_epochs,
+                beacon_node::beacon_chain::chain_config::DEFAULT_PREWARM_MAX_EPOCHS
+            )
+        });
+}
+
+#[test]
+fn prewarm_max_epochs_flag() {
+    CommandLineTest::new()
+        .flag("prewarm-max-epochs", Some("0"))
+        .run_with_zero_port()
+        .with_config(|config| assert_eq!(config.chain.prewarm_max_epochs, 0));
+}
+
+#[test]
+fn heal_head_divergence_flag() {
+    CommandLineTest::new()
+        .run_with_zero_port()
+        .with_config(|config| assert!(!config.chain.heal_head_divergence));
+    CommandLineTest::new()
+        .flag("heal-head-divergence", None)
+        .run_with_zero_port()
+        .with_config(|config| assert!(config.chain.heal_head_divergence));
+}
+
+#[test]
+fn log_inconsistent_payload_transactions_flag() {
+    CommandLineTest::new()
+        .run_with_zero_port()
+        .with_config(|config| assert!(!config.chain.log_inconsistent_payload_transactions));
+    CommandLineTest::new()
+        .flag("log-inconsistent-payload-transactions", None)
+        .run_with_zero_port()
+        .with_config(|config| assert!(config.chain.log_inconsistent_payload_transactions));
+}
+
+#[test]
+fn allow_ancient_attestation_production_flag() {
+    CommandLineTest::new()
+        .run_with_zero_port()
+        .with_config(|config| assert!(!config.chain.allow_ancient_attestation_production));
+    CommandLineTest::new()
+        .flag("allow-ancient-attestation-production", None)
+        .run_with_zero_port()
+        .with_config(|config| assert!(config.chain.allow_ancient_attestation_production));
+}
+
+#[test]
+fn pre_finalization_block_cache_size_default() {
+    CommandLineTest::new()
+        .run_with_zero_port()
+        .with_config(|config| {
+            assert_eq!(
+                config.chain.pre_finalization_block_cache_size,
+                DEFAULT_PRE_FINALIZATION_BLOCK_CACHE_SIZE
+            )
+        });
+}
+
+#[test]
+fn pre_finalization_block_cache_size_flag() {
+    CommandLineTest::new()
+        .flag("pre-finalization-block-cache-size", Some("2048"))
+        .run_with_zero_port()
+        .with_config(|config| assert_eq!(config.chain.pre_finalization_block_cache_size, 2048));
+}
+
+#[test]
+fn checkpoint_state_cache_default() {
+    CommandLineTest::new()
+        .run_with_zero_port()
+        .with_config(|config| assert!(config.chain.cache_checkpoint_states));
+}
+
+#[test]
+fn checkpoint_state_cache_false() {
+    CommandLineTest::new()
+        .flag("checkpoint-state-cache", Some("false"))
+        .run_with_zero_port()
+        .with_config(|config| assert!(!config.chain.cache_checkpoint_states));
+}
+
+#[test]
+fn self_test_flag() {
+    CommandLineTest::new()
+        .run_with_zero_port()
+        .with_config(|config| assert!(!config.self_test));
+    CommandLineTest::new()
+        .flag("self-test", None)
+        .run_with_zero_port()
+        .with_config(|config| assert!(config.self_test));
+}
+
 fn run_jwt_optional_flags_test(jwt_flag: &str, jwt_id_flag: &str, jwt_version_flag: &str) {
     use sensitive_url::SensitiveUrl;
 
@@ -550,17 +1246,54 @@ fn network_import_all_attestations_flag() {
         .with_config(|config| assert!(config.network.import_all_attestations));
 }
 #[test]
-fn network_shutdown_after_sync_flag() {
+fn shutdown_after_sync_flag() {
     CommandLineTest::new()
         .flag("shutdown-after-sync", None)
         .run_with_zero_port()
-        .with_config(|config| assert!(config.network.shutdown_after_sync));
+        .with_config(|config| {
+            assert_eq!(
+                config.chain.shutdown_after,
+                Some(ShutdownTarget::HeadSynced)
+            )
+        });
+}
+#[test]
+fn shutdown_after_sync_disabled_flag() {
+    CommandLineTest::new()
+        .run_with_zero_port()
+        .with_config(|config| assert_eq!(config.chain.shutdown_after, None));
 }
 #[test]
-fn network_shutdown_after_sync_disabled_flag() {
+fn shutdown_after_slot_flag() {
     CommandLineTest::new()
+        .flag("shutdown-after-slot", Some("4096"))
         .run_with_zero_port()
-        .with_config(|config| assert!(!config.network.shutdown_after_sync));
+        .with_config(|config| {
+            assert_eq!(
+                config.chain.shutdown_after,
+                Some(ShutdownTarget::Slot(Slot::new(4096)))
+            )
+        });
+}
+#[test]
+fn shutdown_after_finalized_epoch_flag() {
+    CommandLineTest::new()
+        .flag("shutdown-after-finalized-epoch", Some("128"))
+        .run_with_zero_port()
+        .with_config(|config| {
+            assert_eq!(
+                config.chain.shutdown_after,
+                Some(ShutdownTarget::FinalizedEpoch(Epoch::new(128)))
+            )
+        });
+}
+#[test]
+#[should_panic]
+fn shutdown_after_slot_and_sync_flags() {
+    CommandLineTest::new()
+        .flag("shutdown-after-sync", None)
+        .flag("shutdown-after-slot", Some("4096"))
+        .run_with_zero_port();
 }
 #[test]
 fn network_listen_address_flag() {
@@ -960,8 +1693,12 @@ fn validator_monitor_file_flag() {
         .flag("validator-monitor-file", dir.path().join("pubkeys.txt").as_os_str().to_str())
         .run_with_zero_port()
         .with_config(|config| {
-            assert_eq!(config.validator_monitor_pubkeys[0].to_string(), "0xdeadbeefdeadbeefdeadbeefdeadbeefdeadbeefdeadbeefdeadbeefdeadbeefdeadbeefdeadbeefdeadbeefdeadbeef");
-            assert_eq!(config.validator_monitor_pubkeys[1].to_string(), "0xbeefdeadbeefdeaddeadbeefdeadbeefdeadbeefdeadbeefdeadbeefdeadbeefdeadbeefdeadbeefdeadbeefdeadbeef");
+            // The file is read by the client at startup, so that it can be periodically reloaded.
+            assert!(config.validator_monitor_pubkeys.is_empty());
+            assert_eq!(
+                config.validator_monitor_pubkeys_file,
+                Some(dir.path().join("pubkeys.txt"))
+            );
         });
 }
 
@@ -1007,6 +1744,43 @@ fn slots_per_restore_point_update_prev_default() {
         })
 }
 
+#[test]
+fn force_restore_point_migration_flag() {
+    CommandLineTest::new()
+        .flag("slots-per-restore-point", Some("64"))
+        .run_with_zero_port()
+        .with_config(|config| assert!(!config.store.force_restore_point_migration));
+    CommandLineTest::new()
+        .flag("slots-per-restore-point", Some("64"))
+        .flag("force-restore-point-migration", None)
+        .run_with_zero_port()
+        .with_config(|config| assert!(config.store.force_restore_point_migration));
+}
+#[test]
+#[should_panic]
+fn force_restore_point_migration_requires_slots_per_restore_point() {
+    CommandLineTest::new()
+        .flag("force-restore-point-migration", None)
+        .run_with_zero_port();
+}
+#[test]
+fn freezer_readonly_flag() {
+    CommandLineTest::new()
+        .run_with_zero_port()
+        .with_config(|config| assert!(!config.store.freezer_readonly));
+    CommandLineTest::new()
+        .flag("freezer-readonly", None)
+        .run_with_zero_port()
+        .with_config(|config| assert!(config.store.freezer_readonly));
+}
+#[test]
+#[should_panic]
+fn freezer_readonly_conflicts_with_reconstruct_historic_states() {
+    CommandLineTest::new()
+        .flag("freezer-readonly", None)
+        .flag("reconstruct-historic-states", None)
+        .run_with_zero_port();
+}
 #[test]
 fn block_cache_size_flag() {
     CommandLineTest::new()
@@ -1015,6 +1789,19 @@ fn block_cache_size_flag() {
         .with_config(|config| assert_eq!(config.store.block_cache_size, 4_usize));
 }
 #[test]
+fn epochs_per_full_hot_state_default() {
+    CommandLineTest::new()
+        .run_with_zero_port()
+        .with_config(|config| assert_eq!(config.store.epochs_per_full_hot_state, None));
+}
+#[test]
+fn epochs_per_full_hot_state_flag() {
+    CommandLineTest::new()
+        .flag("epochs-per-full-hot-state", Some("4"))
+        .run_with_zero_port()
+        .with_config(|config| assert_eq!(config.store.epochs_per_full_hot_state, Some(4)));
+}
+#[test]
 fn auto_compact_db_flag() {
     CommandLineTest::new()
         .flag("auto-compact-db", Some("false"))
@@ -1030,6 +1817,32 @@ fn compact_db_flag() {
         .with_config(|config| assert!(config.store.compact_on_init));
 }
 #[test]
+fn repair_on_startup_flag() {
+    CommandLineTest::new()
+        .run_with_zero_port()
+        .with_config(|config| assert!(!config.chain.repair_on_startup));
+    CommandLineTest::new()
+        .flag("repair-on-startup", None)
+        .run_with_zero_port()
+        .with_config(|config| assert!(config.chain.repair_on_startup));
+}
+#[test]
+fn read_only_flag() {
+    CommandLineTest::new()
+        .run_with_zero_port()
+        .with_config(|config| {
+            assert!(!config.chain.read_only);
+            assert!(!config.store.read_only);
+        });
+    CommandLineTest::new()
+        .flag("read-only", None)
+        .run_with_zero_port()
+        .with_config(|config| {
+            assert!(config.chain.read_only);
+            assert!(config.store.read_only);
+        });
+}
+#[test]
 fn reconstruct_historic_states_flag() {
     CommandLineTest::new()
         .flag("reconstruct-historic-states", None)
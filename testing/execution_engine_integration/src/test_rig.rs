@@ -3,7 +3,9 @@ use crate::execution_engine::{
 };
 use crate::transactions::transactions;
 use ethers_providers::Middleware;
-use execution_layer::{ExecutionLayer, PayloadAttributes, PayloadStatus};
+use execution_layer::{
+    BuilderParams, ChainHealth, ExecutionLayer, PayloadAttributes, PayloadStatus,
+};
 use fork_choice::ForkchoiceUpdateParameters;
 use reqwest::{header::CONTENT_TYPE, Client};
 use sensitive_url::SensitiveUrl;
@@ -305,6 +307,11 @@ impl<E: GenericExecutionEngine> TestRig<E> {
         // in CI.
         sleep(Duration::from_secs(3)).await;
 
+        let builder_params = BuilderParams {
+            pubkey: None,
+            slot: Slot::new(0),
+            chain_health: ChainHealth::Healthy,
+        };
         let valid_payload = self
             .ee_a
             .execution_layer
@@ -313,12 +320,12 @@ impl<E: GenericExecutionEngine> TestRig<E> {
                 timestamp,
                 prev_randao,
                 proposer_index,
-                None,
-                Slot::new(0),
+                builder_params.clone(),
                 forkchoice_update_params,
             )
             .await
             .unwrap()
+            .payload
             .execution_payload;
 
         assert_eq!(valid_payload.transactions.len(), txs.len());
@@ -421,12 +428,12 @@ impl<E: GenericExecutionEngine> TestRig<E> {
                 timestamp,
                 prev_randao,
                 proposer_index,
-                None,
-                Slot::new(0),
+                builder_params,
                 forkchoice_update_params,
             )
             .await
             .unwrap()
+            .payload
             .execution_payload;
 
         /*
@@ -53,6 +53,7 @@ pub fn run_no_eth1_sim(matches: &ArgMatches) -> Result<(), String> {
             max_log_size: 0,
             max_log_number: 0,
             compression: false,
+            level_overrides: <_>::default(),
         })?
         .multi_threaded_tokio_runtime()?
         .build()?;
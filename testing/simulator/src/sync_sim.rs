@@ -54,6 +54,7 @@ fn syncing_sim(
             max_log_size: 0,
             max_log_number: 0,
             compression: false,
+            level_overrides: <_>::default(),
         })?
         .multi_threaded_tokio_runtime()?
         .build()?;
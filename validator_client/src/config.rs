@@ -286,9 +286,8 @@ impl Config {
          */
         if let Some(monitoring_endpoint) = cli_args.value_of("monitoring-endpoint") {
             config.monitoring_api = Some(monitoring_api::Config {
-                db_path: None,
-                freezer_db_path: None,
                 monitoring_endpoint: monitoring_endpoint.to_string(),
+                ..monitoring_api::Config::default()
             });
         }
 